@@ -52,6 +52,10 @@ pub enum Health {
 #[derive(Clone, Debug)]
 pub struct Healthz;
 
+/// Message received by readiness probe handlers.
+#[derive(Clone, Debug)]
+pub struct Readyz;
+
 impl<A: Actor> fmt::Debug for ActorHandle<A> {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         formatter
@@ -55,7 +55,7 @@ pub(crate) mod tests;
 mod universe;
 
 pub use actor::{Actor, ActorExitStatus, DeferableReplyHandler, Handler};
-pub use actor_handle::{ActorHandle, Health, Healthz, Supervisable};
+pub use actor_handle::{ActorHandle, Health, Healthz, Readyz, Supervisable};
 pub use command::Command;
 pub use observation::{Observation, ObservationType};
 use quickwit_common::KillSwitch;
@@ -0,0 +1,560 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Import of documents from Elasticsearch or OpenSearch clusters.
+//!
+//! Documents are read with the scroll API, which both Elasticsearch and OpenSearch support, and
+//! sorted by a user-provided field. Sorting lets an interrupted import resume from a checkpoint:
+//! a new scroll starts from the sort value of the last imported document.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+/// Duration a scroll is kept alive between two pages.
+pub const SCROLL_KEEP_ALIVE: Duration = Duration::from_secs(5 * 60);
+
+/// Minimal client of the search APIs of an Elasticsearch or OpenSearch cluster.
+pub struct EsClient {
+    http_client: reqwest::Client,
+    endpoint: Url,
+    credentials_opt: Option<(String, String)>,
+}
+
+/// A page of documents returned by the scroll API.
+#[derive(Debug, Default)]
+pub struct ScrollPage {
+    pub scroll_id_opt: Option<String>,
+    pub total_hits_opt: Option<u64>,
+    pub hits: Vec<EsHit>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EsHit {
+    pub id: String,
+    pub source: JsonValue,
+    pub sort_value_opt: Option<JsonValue>,
+}
+
+impl EsClient {
+    pub fn new(endpoint: Url, credentials_opt: Option<(String, String)>) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            endpoint,
+            credentials_opt,
+        }
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body_opt: Option<JsonValue>,
+    ) -> anyhow::Result<JsonValue> {
+        let url = self
+            .endpoint
+            .join(path)
+            .with_context(|| format!("Failed to build URL for path `{path}`."))?;
+        let mut request_builder = self.http_client.request(method, url.clone());
+
+        if let Some(body) = &body_opt {
+            request_builder = request_builder.json(body);
+        }
+        if let Some((username, password)) = &self.credentials_opt {
+            request_builder = request_builder.basic_auth(username, Some(password));
+        }
+        let response = request_builder
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to `{url}`."))?;
+        let status = response.status();
+        let response_json: JsonValue = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse response from `{url}`."))?;
+        if !status.is_success() {
+            bail!("Request to `{url}` failed with status {status}: {response_json}");
+        }
+        Ok(response_json)
+    }
+
+    /// Returns the properties of the mapping of `es_index`.
+    pub async fn get_mapping_properties(
+        &self,
+        es_index: &str,
+    ) -> anyhow::Result<JsonMap<String, JsonValue>> {
+        let response_json = self
+            .send(reqwest::Method::GET, &format!("{es_index}/_mapping"), None)
+            .await?;
+        parse_mapping_properties(response_json)
+    }
+
+    /// Opens a scroll over the documents of `es_index` matching `query`, sorted by `sort_field`.
+    pub async fn start_scroll(
+        &self,
+        es_index: &str,
+        query: JsonValue,
+        sort_field: &str,
+        batch_size: usize,
+        keep_alive: Duration,
+    ) -> anyhow::Result<ScrollPage> {
+        let body = json!({
+            "size": batch_size,
+            "query": query,
+            "sort": [{ sort_field: "asc" }],
+        });
+        let path = format!("{es_index}/_search?scroll={}s", keep_alive.as_secs());
+        let response_json = self.send(reqwest::Method::POST, &path, Some(body)).await?;
+        parse_scroll_page(response_json)
+    }
+
+    /// Fetches the next page of a scroll.
+    pub async fn next_scroll_page(
+        &self,
+        scroll_id: &str,
+        keep_alive: Duration,
+    ) -> anyhow::Result<ScrollPage> {
+        let body = json!({
+            "scroll": format!("{}s", keep_alive.as_secs()),
+            "scroll_id": scroll_id,
+        });
+        let response_json = self
+            .send(reqwest::Method::POST, "_search/scroll", Some(body))
+            .await?;
+        parse_scroll_page(response_json)
+    }
+
+    /// Releases the resources held by a scroll.
+    pub async fn clear_scroll(&self, scroll_id: &str) -> anyhow::Result<()> {
+        let body = json!({ "scroll_id": scroll_id });
+        self.send(reqwest::Method::DELETE, "_search/scroll", Some(body))
+            .await?;
+        Ok(())
+    }
+}
+
+fn parse_mapping_properties(
+    response_json: JsonValue,
+) -> anyhow::Result<JsonMap<String, JsonValue>> {
+    // The response is keyed by the concrete index name, which differs from the requested one when
+    // an alias is used.
+    let JsonValue::Object(indexes) = response_json else {
+        bail!("Mapping response should be a JSON object.");
+    };
+    let Some((_, index_mapping)) = indexes.into_iter().next() else {
+        bail!("Mapping response does not contain any index.");
+    };
+    let mut mappings = index_mapping
+        .get("mappings")
+        .cloned()
+        .context("Mapping response does not contain any mappings.")?;
+
+    // Elasticsearch 6 nests the properties under the document type.
+    if mappings.get("properties").is_none() {
+        if let Some(doc_type_mappings) = mappings
+            .as_object()
+            .and_then(|doc_types| doc_types.values().next())
+            .cloned()
+        {
+            mappings = doc_type_mappings;
+        }
+    }
+    match mappings.get("properties") {
+        Some(JsonValue::Object(properties)) => Ok(properties.clone()),
+        _ => Ok(JsonMap::new()),
+    }
+}
+
+fn parse_scroll_page(response_json: JsonValue) -> anyhow::Result<ScrollPage> {
+    let scroll_id_opt = response_json
+        .get("_scroll_id")
+        .and_then(JsonValue::as_str)
+        .map(ToString::to_string);
+    // Elasticsearch 7+ and OpenSearch return an object, Elasticsearch 6 returns a number.
+    let total_hits_opt = match response_json.pointer("/hits/total") {
+        Some(JsonValue::Object(total)) => total.get("value").and_then(JsonValue::as_u64),
+        Some(total) => total.as_u64(),
+        None => None,
+    };
+    let Some(JsonValue::Array(hits_json)) = response_json.pointer("/hits/hits") else {
+        bail!("Search response does not contain any hits.");
+    };
+    let mut hits = Vec::with_capacity(hits_json.len());
+
+    for hit_json in hits_json {
+        let id = hit_json
+            .get("_id")
+            .and_then(JsonValue::as_str)
+            .context("Hit does not have an `_id`.")?
+            .to_string();
+        let source = hit_json
+            .get("_source")
+            .cloned()
+            .with_context(|| format!("Hit `{id}` does not have a `_source`."))?;
+        let sort_value_opt = hit_json
+            .get("sort")
+            .and_then(|sort_values| sort_values.get(0))
+            .cloned();
+        hits.push(EsHit {
+            id,
+            source,
+            sort_value_opt,
+        });
+    }
+    Ok(ScrollPage {
+        scroll_id_opt,
+        total_hits_opt,
+        hits,
+    })
+}
+
+/// Tracks the progress of an import so that it can be resumed.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportCheckpoint {
+    pub num_imported_docs: u64,
+    /// Sort value of the last imported document.
+    pub last_sort_value: Option<JsonValue>,
+    /// IDs of the imported documents whose sort value is `last_sort_value`. Resuming starts from
+    /// `last_sort_value` included, and these documents are skipped.
+    pub last_sort_value_doc_ids: Vec<String>,
+}
+
+impl ImportCheckpoint {
+    /// Loads the checkpoint stored at `path`, or returns an empty checkpoint if there is none.
+    pub async fn load(path: &Path) -> anyhow::Result<Self> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("Failed to parse checkpoint `{}`.", path.display())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error)
+                .with_context(|| format!("Failed to read checkpoint `{}`.", path.display())),
+        }
+    }
+
+    /// Stores the checkpoint at `path`. The file is replaced atomically.
+    pub async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let temp_path = path.with_extension("tmp");
+        tokio::fs::write(&temp_path, serde_json::to_vec(self)?).await?;
+        tokio::fs::rename(&temp_path, path)
+            .await
+            .with_context(|| format!("Failed to write checkpoint `{}`.", path.display()))?;
+        Ok(())
+    }
+
+    /// Returns the query selecting the documents that remain to import.
+    pub fn resume_query(&self, sort_field: &str, is_date_field: bool) -> JsonValue {
+        let Some(last_sort_value) = &self.last_sort_value else {
+            return json!({ "match_all": {} });
+        };
+        let mut range = json!({ "gte": last_sort_value });
+
+        if is_date_field {
+            // Sort values of date fields are expressed in milliseconds.
+            range["format"] = json!("epoch_millis");
+        }
+        json!({ "range": { sort_field: range } })
+    }
+
+    /// Returns whether the document was imported before the checkpoint was recorded.
+    pub fn is_imported(&self, hit: &EsHit) -> bool {
+        self.last_sort_value.is_some()
+            && hit.sort_value_opt == self.last_sort_value
+            && self.last_sort_value_doc_ids.contains(&hit.id)
+    }
+
+    /// Records the import of a document. Documents must be recorded in sort order.
+    pub fn record(&mut self, hit: &EsHit) {
+        if hit.sort_value_opt != self.last_sort_value {
+            self.last_sort_value = hit.sort_value_opt.clone();
+            self.last_sort_value_doc_ids.clear();
+        }
+        self.last_sort_value_doc_ids.push(hit.id.clone());
+        self.num_imported_docs += 1;
+    }
+}
+
+/// Translates the properties of an Elasticsearch mapping into Quickwit field mappings.
+///
+/// Returns the field mappings and the paths of the fields that could not be translated. Those
+/// are still imported, as the generated doc mapping is dynamic.
+pub fn translate_es_properties(
+    properties: &JsonMap<String, JsonValue>,
+) -> (Vec<JsonValue>, Vec<String>) {
+    let mut field_mappings = Vec::new();
+    let mut untranslated_fields = Vec::new();
+
+    for (field_name, field_mapping) in properties {
+        match translate_es_field_mapping(field_name, field_mapping) {
+            Ok(field_mapping) => field_mappings.push(field_mapping),
+            Err(untranslated_subfields) => untranslated_fields.extend(untranslated_subfields),
+        }
+    }
+    (field_mappings, untranslated_fields)
+}
+
+fn translate_es_field_mapping(
+    field_name: &str,
+    es_field_mapping: &JsonValue,
+) -> Result<JsonValue, Vec<String>> {
+    let es_type = es_field_mapping
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .unwrap_or("object");
+    let mut field_mapping = match es_type {
+        "text" | "match_only_text" => json!({ "type": "text", "tokenizer": "default" }),
+        "keyword" | "constant_keyword" | "wildcard" => {
+            json!({ "type": "text", "tokenizer": "raw", "fast": true })
+        }
+        "long" | "integer" | "short" | "byte" => json!({ "type": "i64", "fast": true }),
+        "unsigned_long" => json!({ "type": "u64", "fast": true }),
+        "double" | "float" | "half_float" | "scaled_float" => {
+            json!({ "type": "f64", "fast": true })
+        }
+        "boolean" => json!({ "type": "bool", "fast": true }),
+        "date" | "date_nanos" => json!({
+            "type": "datetime",
+            "input_formats": ["rfc3339", "iso8601", "unix_timestamp"],
+            "fast": true,
+        }),
+        "ip" => json!({ "type": "ip", "fast": true }),
+        "binary" => json!({ "type": "bytes" }),
+        "flattened" => json!({ "type": "json" }),
+        "object" => {
+            let Some(JsonValue::Object(properties)) = es_field_mapping.get("properties") else {
+                return Ok(json!({ "name": field_name, "type": "json" }));
+            };
+            let (field_mappings, untranslated_fields) = translate_es_properties(properties);
+            if !untranslated_fields.is_empty() {
+                return Err(untranslated_fields
+                    .into_iter()
+                    .map(|subfield| format!("{field_name}.{subfield}"))
+                    .collect());
+            }
+            json!({ "type": "object", "field_mappings": field_mappings })
+        }
+        _ => return Err(vec![field_name.to_string()]),
+    };
+    field_mapping["name"] = json!(field_name);
+    Ok(field_mapping)
+}
+
+/// Returns the Elasticsearch type of the field at `field_path`, if it exists.
+pub fn es_field_type<'a>(
+    properties: &'a JsonMap<String, JsonValue>,
+    field_path: &str,
+) -> Option<&'a str> {
+    let (field_name, subfield_path_opt) = match field_path.split_once('.') {
+        Some((field_name, subfield_path)) => (field_name, Some(subfield_path)),
+        None => (field_path, None),
+    };
+    let field_mapping = properties.get(field_name)?;
+
+    if let Some(subfield_path) = subfield_path_opt {
+        let JsonValue::Object(subproperties) = field_mapping.get("properties")? else {
+            return None;
+        };
+        return es_field_type(subproperties, subfield_path);
+    }
+    field_mapping.get("type").and_then(JsonValue::as_str)
+}
+
+/// Builds the config of a Quickwit index holding the documents of an Elasticsearch index.
+///
+/// Returns the index config and the paths of the fields whose mapping could not be translated.
+pub fn build_index_config(
+    index_id: &str,
+    properties: &JsonMap<String, JsonValue>,
+    sort_field: &str,
+) -> (JsonValue, Vec<String>) {
+    let (field_mappings, untranslated_fields) = translate_es_properties(properties);
+    let mut doc_mapping = json!({
+        "mode": "dynamic",
+        "field_mappings": field_mappings,
+    });
+    // The timestamp field must be a top-level datetime field.
+    if let Some("date" | "date_nanos") = properties
+        .get(sort_field)
+        .and_then(|field_mapping| field_mapping.get("type"))
+        .and_then(JsonValue::as_str)
+    {
+        doc_mapping["timestamp_field"] = json!(sort_field);
+    }
+    let index_config = json!({
+        "version": "0.6",
+        "index_id": index_id,
+        "doc_mapping": doc_mapping,
+    });
+    (index_config, untranslated_fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_properties() -> JsonMap<String, JsonValue> {
+        let mapping_response = json!({
+            "logs-000001": {
+                "mappings": {
+                    "properties": {
+                        "@timestamp": { "type": "date" },
+                        "message": { "type": "text" },
+                        "level": { "type": "keyword" },
+                        "status": { "type": "long" },
+                        "client": {
+                            "properties": {
+                                "ip": { "type": "ip" },
+                                "geo": { "type": "geo_point" },
+                            }
+                        },
+                        "spans": { "type": "nested", "properties": {} },
+                    }
+                }
+            }
+        });
+        parse_mapping_properties(mapping_response).unwrap()
+    }
+
+    #[test]
+    fn test_parse_mapping_properties_es6() {
+        let mapping_response = json!({
+            "logs": {
+                "mappings": {
+                    "_doc": {
+                        "properties": {
+                            "message": { "type": "text" },
+                        }
+                    }
+                }
+            }
+        });
+        let properties = parse_mapping_properties(mapping_response).unwrap();
+        assert_eq!(properties.len(), 1);
+        assert!(properties.contains_key("message"));
+    }
+
+    #[test]
+    fn test_build_index_config() {
+        let properties = test_properties();
+        let (index_config, mut untranslated_fields) =
+            build_index_config("logs", &properties, "@timestamp");
+        untranslated_fields.sort();
+        assert_eq!(untranslated_fields, ["client.geo", "spans"]);
+
+        assert_eq!(index_config["index_id"], "logs");
+        assert_eq!(index_config["doc_mapping"]["mode"], "dynamic");
+        assert_eq!(index_config["doc_mapping"]["timestamp_field"], "@timestamp");
+
+        let field_mappings = index_config["doc_mapping"]["field_mappings"]
+            .as_array()
+            .unwrap();
+        let field_mapping = |field_name: &str| {
+            field_mappings
+                .iter()
+                .find(|field_mapping| field_mapping["name"] == field_name)
+                .cloned()
+        };
+        assert_eq!(field_mapping("@timestamp").unwrap()["type"], "datetime");
+        assert_eq!(field_mapping("message").unwrap()["type"], "text");
+        assert_eq!(field_mapping("level").unwrap()["tokenizer"], "raw");
+        assert_eq!(field_mapping("status").unwrap()["type"], "i64");
+        // Objects with untranslated subfields are left to the dynamic mode.
+        assert!(field_mapping("client").is_none());
+        assert!(field_mapping("spans").is_none());
+    }
+
+    #[test]
+    fn test_es_field_type() {
+        let properties = test_properties();
+        assert_eq!(es_field_type(&properties, "@timestamp"), Some("date"));
+        assert_eq!(es_field_type(&properties, "client.ip"), Some("ip"));
+        assert_eq!(es_field_type(&properties, "client.port"), None);
+        assert_eq!(es_field_type(&properties, "missing"), None);
+    }
+
+    #[test]
+    fn test_parse_scroll_page() {
+        let response_json = json!({
+            "_scroll_id": "scroll-id",
+            "hits": {
+                "total": { "value": 2, "relation": "eq" },
+                "hits": [
+                    { "_id": "doc-1", "_source": { "message": "foo" }, "sort": [1000] },
+                    { "_id": "doc-2", "_source": { "message": "bar" }, "sort": [2000] },
+                ]
+            }
+        });
+        let scroll_page = parse_scroll_page(response_json).unwrap();
+        assert_eq!(scroll_page.scroll_id_opt.as_deref(), Some("scroll-id"));
+        assert_eq!(scroll_page.total_hits_opt, Some(2));
+        assert_eq!(scroll_page.hits.len(), 2);
+        assert_eq!(scroll_page.hits[1].id, "doc-2");
+        assert_eq!(scroll_page.hits[1].source, json!({ "message": "bar" }));
+        assert_eq!(scroll_page.hits[1].sort_value_opt, Some(json!(2000)));
+
+        let es6_response_json = json!({ "hits": { "total": 3, "hits": [] } });
+        let scroll_page = parse_scroll_page(es6_response_json).unwrap();
+        assert_eq!(scroll_page.total_hits_opt, Some(3));
+        assert!(scroll_page.hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_checkpoint() {
+        let make_hit = |id: &str, sort_value: u64| EsHit {
+            id: id.to_string(),
+            source: json!({}),
+            sort_value_opt: Some(json!(sort_value)),
+        };
+        let mut checkpoint = ImportCheckpoint::default();
+        assert_eq!(
+            checkpoint.resume_query("@timestamp", true),
+            json!({ "match_all": {} })
+        );
+        assert!(!checkpoint.is_imported(&make_hit("doc-1", 1000)));
+
+        checkpoint.record(&make_hit("doc-1", 1000));
+        checkpoint.record(&make_hit("doc-2", 2000));
+        checkpoint.record(&make_hit("doc-3", 2000));
+        assert_eq!(checkpoint.num_imported_docs, 3);
+        assert_eq!(checkpoint.last_sort_value_doc_ids, ["doc-2", "doc-3"]);
+
+        assert_eq!(
+            checkpoint.resume_query("@timestamp", true),
+            json!({ "range": { "@timestamp": { "gte": 2000, "format": "epoch_millis" } } })
+        );
+        assert!(checkpoint.is_imported(&make_hit("doc-3", 2000)));
+        assert!(!checkpoint.is_imported(&make_hit("doc-4", 2000)));
+        assert!(!checkpoint.is_imported(&make_hit("doc-5", 3000)));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let checkpoint_path = temp_dir.path().join("checkpoint.json");
+        assert_eq!(
+            ImportCheckpoint::load(&checkpoint_path).await.unwrap(),
+            ImportCheckpoint::default()
+        );
+        checkpoint.save(&checkpoint_path).await.unwrap();
+        assert_eq!(
+            ImportCheckpoint::load(&checkpoint_path).await.unwrap(),
+            checkpoint
+        );
+    }
+}
@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Display;
 use std::io::{stdout, Stdout, Write};
 use std::path::PathBuf;
@@ -35,8 +35,12 @@ use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
 use quickwit_actors::{ActorHandle, ObservationType};
 use quickwit_common::uri::Uri;
-use quickwit_common::GREEN_COLOR;
-use quickwit_config::{ConfigFormat, IndexConfig};
+use quickwit_common::{GREEN_COLOR, RED_COLOR};
+use quickwit_config::{
+    has_env_overlays, parse_with_env_overlays, ConfigFormat, IndexConfig, LabelFilter,
+    INDEX_CONFIG_ENV_OVERLAY_PREFIX,
+};
+use quickwit_doc_mapper::MappingChangeImpact;
 use quickwit_indexing::models::IndexingStatistics;
 use quickwit_indexing::IndexingPipeline;
 use quickwit_metastore::{IndexMetadata, Split, SplitState};
@@ -44,8 +48,11 @@ use quickwit_proto::SortOrder;
 use quickwit_rest_client::models::IngestSource;
 use quickwit_rest_client::rest_client::{CommitType, IngestEvent};
 use quickwit_search::SearchResponseRest;
-use quickwit_serve::{ListSplitsQueryParams, SearchRequestQueryString, SortByField};
+use quickwit_serve::{
+    LabelsQueryParams, ListSplitsQueryParams, SearchRequestQueryString, SortByField,
+};
 use quickwit_storage::{load_file, StorageResolver};
+use serde_json::Value as JsonValue;
 use tabled::object::{Columns, Segment};
 use tabled::{Alignment, Concat, Format, Modify, Panel, Rotate, Style, Table, Tabled};
 use thousands::Separable;
@@ -70,6 +77,20 @@ pub fn build_index_command() -> Command {
                         .required(false),
                 ])
             )
+        .subcommand(
+            Command::new("check-mapping")
+                .display_order(1)
+                .about("Checks whether the doc mapping of an index config file is compatible with the doc mapping of an existing index.")
+                .long_about("Compares the doc mapping of an existing index with the doc mapping of an index config file, reports breaking and non-breaking changes, and tells whether a reindex is needed. Exits with an error if at least one change is breaking.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"index-config" <INDEX_CONFIG> "Location of the new index config file.")
+                        .display_order(2)
+                        .required(true),
+                ])
+            )
         .subcommand(
             Command::new("clear")
                 .display_order(2)
@@ -111,6 +132,11 @@ pub fn build_index_command() -> Command {
                 .alias("ls")
                 .display_order(5)
                 .about("List indexes.")
+                .args(&[
+                    arg!(--labels <LABEL_FILTERS> "Selects the indexes whose labels match all the filters of this comma-separated list of `key` or `key:value` filters.")
+                        .required(false)
+                        .value_delimiter(','),
+                ])
             )
         .subcommand(
             Command::new("ingest")
@@ -179,6 +205,13 @@ pub fn build_index_command() -> Command {
         .arg_required_else_help(true)
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct CheckMappingArgs {
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub index_config_uri: Uri,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct ClearIndexArgs {
     pub client_args: ClientArgs,
@@ -235,10 +268,12 @@ pub struct DeleteIndexArgs {
 #[derive(Debug, Eq, PartialEq)]
 pub struct ListIndexesArgs {
     pub client_args: ClientArgs,
+    pub labels: Option<Vec<LabelFilter>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum IndexCliCommand {
+    CheckMapping(CheckMappingArgs),
     Clear(ClearIndexArgs),
     Create(CreateIndexArgs),
     Delete(DeleteIndexArgs),
@@ -261,6 +296,7 @@ impl IndexCliCommand {
             .remove_subcommand()
             .context("Failed to parse index subcommand.")?;
         match subcommand.as_str() {
+            "check-mapping" => Self::parse_check_mapping_args(submatches),
             "clear" => Self::parse_clear_args(submatches),
             "create" => Self::parse_create_args(submatches),
             "delete" => Self::parse_delete_args(submatches),
@@ -272,6 +308,22 @@ impl IndexCliCommand {
         }
     }
 
+    fn parse_check_mapping_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let index_config_uri = matches
+            .remove_one::<String>("index-config")
+            .map(|uri| Uri::from_str(&uri))
+            .expect("`index-config` should be a required arg.")?;
+        Ok(Self::CheckMapping(CheckMappingArgs {
+            client_args,
+            index_id,
+            index_config_uri,
+        }))
+    }
+
     fn parse_clear_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
         let client_args = ClientArgs::parse(&mut matches)?;
         let index_id = matches
@@ -315,7 +367,11 @@ impl IndexCliCommand {
 
     fn parse_list_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
         let client_args = ClientArgs::parse(&mut matches)?;
-        Ok(Self::List(ListIndexesArgs { client_args }))
+        let labels = parse_label_filters(&mut matches)?;
+        Ok(Self::List(ListIndexesArgs {
+            client_args,
+            labels,
+        }))
     }
 
     fn parse_ingest_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
@@ -420,6 +476,7 @@ impl IndexCliCommand {
 
     pub async fn execute(self) -> anyhow::Result<()> {
         match self {
+            Self::CheckMapping(args) => check_mapping_cli(args).await,
             Self::Clear(args) => clear_index_cli(args).await,
             Self::Create(args) => create_index_cli(args).await,
             Self::Delete(args) => delete_index_cli(args).await,
@@ -431,6 +488,73 @@ impl IndexCliCommand {
     }
 }
 
+#[derive(Tabled)]
+struct MappingChangeRow {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Change")]
+    description: String,
+    #[tabled(rename = "Impact")]
+    impact: MappingChangeImpact,
+}
+
+/// Loads an index config file and applies the `QW_INDEX__` environment variable overlays, if any.
+/// The overlaid config is sent to the server as JSON.
+async fn load_index_config_file(index_config_uri: &Uri) -> anyhow::Result<(Bytes, ConfigFormat)> {
+    let storage_resolver = StorageResolver::unconfigured();
+    let file_content = load_file(&storage_resolver, index_config_uri).await?;
+    let config_format = ConfigFormat::sniff_from_uri(index_config_uri)?;
+    let env_vars = std::env::vars().collect::<HashMap<_, _>>();
+
+    if !has_env_overlays(INDEX_CONFIG_ENV_OVERLAY_PREFIX, &env_vars) {
+        return Ok((Bytes::from(file_content.to_vec()), config_format));
+    }
+    let index_config_value: JsonValue = parse_with_env_overlays(
+        config_format,
+        &file_content,
+        INDEX_CONFIG_ENV_OVERLAY_PREFIX,
+        &env_vars,
+    )?;
+    let bytes = serde_json::to_vec(&index_config_value)?;
+    Ok((Bytes::from(bytes), ConfigFormat::Json))
+}
+
+pub async fn check_mapping_cli(args: CheckMappingArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "check-mapping");
+    println!("❯ Checking doc mapping compatibility...");
+    let (bytes, config_format) = load_index_config_file(&args.index_config_uri).await?;
+    let qw_client = args.client_args.client();
+    let report = qw_client
+        .indexes()
+        .check_mapping(&args.index_id, bytes, config_format)
+        .await?;
+    if report.changes.is_empty() {
+        println!("{} Doc mappings are identical.", "✔".color(GREEN_COLOR));
+        return Ok(());
+    }
+    let rows = report.changes.iter().map(|change| MappingChangeRow {
+        path: change.path.clone(),
+        description: change.description.clone(),
+        impact: change.impact,
+    });
+    let table = make_table("Doc Mapping Changes", rows, false);
+    println!("\n{table}\n");
+
+    if report.requires_reindex() {
+        println!(
+            "{} Existing splits are not consistent with the new doc mapping. The index must be \
+             reindexed.",
+            "✘".color(RED_COLOR)
+        );
+    }
+    report.ensure_compatible()?;
+    println!(
+        "{} Doc mapping changes are backward compatible.",
+        "✔".color(GREEN_COLOR)
+    );
+    Ok(())
+}
+
 pub async fn clear_index_cli(args: ClearIndexArgs) -> anyhow::Result<()> {
     debug!(args=?args, "clear-index");
     if !args.assume_yes {
@@ -452,9 +576,7 @@ pub async fn clear_index_cli(args: ClearIndexArgs) -> anyhow::Result<()> {
 pub async fn create_index_cli(args: CreateIndexArgs) -> anyhow::Result<()> {
     debug!(args=?args, "create-index");
     println!("❯ Creating index...");
-    let storage_resolver = StorageResolver::unconfigured();
-    let file_content = load_file(&storage_resolver, &args.index_config_uri).await?;
-    let config_format = ConfigFormat::sniff_from_uri(&args.index_config_uri)?;
+    let (bytes, config_format) = load_index_config_file(&args.index_config_uri).await?;
     let qw_client = args.client_args.client();
     // TODO: nice to have: check first if the index exists by send a GET request, if we get a 404,
     // the index does not exist. If it exists, we can display the prompt.
@@ -467,7 +589,6 @@ pub async fn create_index_cli(args: CreateIndexArgs) -> anyhow::Result<()> {
             return Ok(());
         }
     }
-    let bytes = Bytes::from(file_content.to_vec());
     qw_client
         .indexes()
         .create(bytes, config_format, args.overwrite)
@@ -479,7 +600,10 @@ pub async fn create_index_cli(args: CreateIndexArgs) -> anyhow::Result<()> {
 pub async fn list_index_cli(args: ListIndexesArgs) -> anyhow::Result<()> {
     debug!(args=?args, "list-index");
     let qw_client = args.client_args.client();
-    let indexes_metadatas = qw_client.indexes().list().await?;
+    let labels_query_params = LabelsQueryParams {
+        labels: args.labels,
+    };
+    let indexes_metadatas = qw_client.indexes().list(labels_query_params).await?;
     let index_table = make_list_indexes_table(
         indexes_metadatas
             .into_iter()
@@ -32,7 +32,9 @@ use quickwit_common::run_checklist;
 use quickwit_common::runtimes::RuntimesConfig;
 use quickwit_common::uri::Uri;
 use quickwit_config::service::QuickwitService;
-use quickwit_config::{ConfigFormat, QuickwitConfig, SourceConfig, DEFAULT_QW_CONFIG_PATH};
+use quickwit_config::{
+    ConfigFormat, LabelFilter, QuickwitConfig, SourceConfig, DEFAULT_QW_CONFIG_PATH,
+};
 use quickwit_indexing::check_source_connectivity;
 use quickwit_metastore::{Metastore, MetastoreResolver};
 use quickwit_rest_client::models::Timeout;
@@ -45,10 +47,13 @@ use tabled::{Alignment, Header, Modify, Style, Table, Tabled};
 use tracing::info;
 
 pub mod cli;
+pub mod es_import;
 pub mod index;
 #[cfg(feature = "jemalloc")]
 pub mod jemalloc;
 pub mod metrics;
+pub mod parquet_archive;
+pub mod parquet_ingest;
 pub mod service;
 pub mod source;
 pub mod split;
@@ -228,6 +233,18 @@ pub fn parse_duration_or_none(duration_with_unit_str: &str) -> anyhow::Result<Ti
     }
 }
 
+/// Parses the comma-separated list of label filters passed with the `--labels` arg, if any.
+fn parse_label_filters(matches: &mut ArgMatches) -> anyhow::Result<Option<Vec<LabelFilter>>> {
+    matches
+        .remove_many::<String>("labels")
+        .map(|values| {
+            values
+                .map(|label_filter_str| label_filter_str.parse::<LabelFilter>())
+                .collect::<anyhow::Result<Vec<_>>>()
+        })
+        .transpose()
+}
+
 pub fn start_actor_runtimes(
     runtimes_config: RuntimesConfig,
     services: &HashSet<QuickwitService>,
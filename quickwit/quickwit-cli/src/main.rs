@@ -174,20 +174,44 @@ mod tests {
     use byte_unit::Byte;
     use quickwit_cli::cli::{build_cli, CliCommand};
     use quickwit_cli::index::{
-        ClearIndexArgs, CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs, IndexCliCommand,
-        IngestDocsArgs, SearchIndexArgs,
+        CheckMappingArgs, ClearIndexArgs, CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs,
+        IndexCliCommand, IngestDocsArgs, SearchIndexArgs,
     };
     use quickwit_cli::split::{DescribeSplitArgs, SplitCliCommand};
     use quickwit_cli::tool::{
-        ExtractSplitArgs, GarbageCollectIndexArgs, LocalIngestDocsArgs, MergeArgs, ToolCliCommand,
+        BenchIngestArgs, BenchIngestTarget, BenchSearchArgs, ExportParquetArgs, ExtractSplitArgs,
+        GarbageCollectIndexArgs, LocalIngestDocsArgs, MergeArgs, RestoreParquetArgs,
+        ToolCliCommand,
     };
     use quickwit_cli::ClientArgs;
     use quickwit_common::uri::Uri;
-    use quickwit_config::SourceInputFormat;
+    use quickwit_config::{CsvColumnType, SourceInputFormat, DEFAULT_QW_CONFIG_PATH};
     use quickwit_rest_client::models::Timeout;
     use quickwit_rest_client::rest_client::CommitType;
     use reqwest::Url;
 
+    #[test]
+    fn test_parse_check_mapping_args() {
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from([
+                "index",
+                "check-mapping",
+                "--index",
+                "wikipedia",
+                "--index-config",
+                "/index-conf.yaml",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_cmd = CliCommand::Index(IndexCliCommand::CheckMapping(CheckMappingArgs {
+            client_args: ClientArgs::default(),
+            index_id: "wikipedia".to_string(),
+            index_config_uri: Uri::from_str("file:///index-conf.yaml").unwrap(),
+        }));
+        assert_eq!(command, expected_cmd);
+    }
+
     #[test]
     fn test_parse_clear_args() {
         let app = build_cli().no_binary_name(true);
@@ -451,6 +475,7 @@ mod tests {
                     overwrite,
                     vrl_script: Some(vrl_script),
                     clear_cache,
+                    ..
                 })) if &index_id == "wikipedia"
                        && config_uri == Uri::from_str("file:///config.yaml").unwrap()
                        && vrl_script == ".message = downcase(string!(.message))"
@@ -460,6 +485,57 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_local_ingest_csv_args() {
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from([
+                "tool",
+                "local-ingest",
+                "--index",
+                "users",
+                "--config",
+                "/config.yaml",
+                "--input-format",
+                "csv",
+                "--csv-delimiter",
+                "\\t",
+                "--csv-no-header",
+                "--csv-columns",
+                "name, age",
+                "--csv-schema",
+                "age:u64",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let CliCommand::Tool(ToolCliCommand::LocalIngest(args)) = command else {
+            panic!("Command should be a local ingest command.");
+        };
+        assert_eq!(args.input_format, SourceInputFormat::Csv);
+        assert_eq!(args.csv_params.delimiter, '\t');
+        assert_eq!(args.csv_params.quote, '"');
+        assert!(!args.csv_params.has_header);
+        assert_eq!(args.csv_params.columns, ["name", "age"]);
+        assert_eq!(args.csv_params.column_types["age"], CsvColumnType::U64);
+        assert!(args.csv_params.infer_types);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from([
+                "tool",
+                "local-ingest",
+                "--index",
+                "users",
+                "--config",
+                "/config.yaml",
+                "--input-format",
+                "csv",
+                "--csv-no-header",
+            ])
+            .unwrap();
+        CliCommand::parse_cli_args(matches).unwrap_err();
+    }
+
     #[test]
     fn test_parse_search_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
@@ -704,4 +780,205 @@ mod tests {
         ));
         Ok(())
     }
+
+    #[test]
+    fn test_parse_bench_search_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "bench-search",
+            "--index",
+            "wikipedia",
+            "--query-log",
+            "queries.ndjson",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command = CliCommand::Tool(ToolCliCommand::BenchSearch(BenchSearchArgs {
+            client_args: ClientArgs::default(),
+            index_id: "wikipedia".to_string(),
+            query_log_path: PathBuf::from("queries.ndjson"),
+            concurrency: 4,
+            num_iterations: 1,
+            duration_opt: None,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "bench-search",
+            "--index",
+            "wikipedia",
+            "--query-log",
+            "queries.ndjson",
+            "--concurrency",
+            "16",
+            "--duration",
+            "10m",
+            "--endpoint",
+            "http://127.0.0.1:1234",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command = CliCommand::Tool(ToolCliCommand::BenchSearch(BenchSearchArgs {
+            client_args: ClientArgs {
+                cluster_endpoint: Url::from_str("http://127.0.0.1:1234")?,
+                ..Default::default()
+            },
+            index_id: "wikipedia".to_string(),
+            query_log_path: PathBuf::from("queries.ndjson"),
+            concurrency: 16,
+            num_iterations: 1,
+            duration_opt: Some(Duration::from_secs(10 * 60)),
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "bench-search",
+            "--index",
+            "wikipedia",
+            "--query-log",
+            "queries.ndjson",
+            "--concurrency",
+            "0",
+        ])?;
+        assert!(CliCommand::parse_cli_args(matches).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_bench_ingest_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(["tool", "bench-ingest", "--index", "wikipedia"])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command = CliCommand::Tool(ToolCliCommand::BenchIngest(BenchIngestArgs {
+            config_uri: Uri::from_str(DEFAULT_QW_CONFIG_PATH)?,
+            client_args: ClientArgs::default(),
+            index_id: "wikipedia".to_string(),
+            num_docs: 100_000,
+            num_fields: 8,
+            cardinality: 1_000,
+            timestamp_skew: Duration::from_secs(60 * 60),
+            target: BenchIngestTarget::Api,
+            seed_opt: None,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "bench-ingest",
+            "--index",
+            "wikipedia",
+            "--num-docs",
+            "1000",
+            "--num-fields",
+            "2",
+            "--cardinality",
+            "10",
+            "--timestamp-skew",
+            "1d",
+            "--target",
+            "local",
+            "--seed",
+            "42",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command = CliCommand::Tool(ToolCliCommand::BenchIngest(BenchIngestArgs {
+            config_uri: Uri::from_str("file:///config.yaml")?,
+            client_args: ClientArgs::default(),
+            index_id: "wikipedia".to_string(),
+            num_docs: 1_000,
+            num_fields: 2,
+            cardinality: 10,
+            timestamp_skew: Duration::from_secs(24 * 60 * 60),
+            target: BenchIngestTarget::Local,
+            seed_opt: Some(42),
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        assert!(app
+            .try_get_matches_from([
+                "tool",
+                "bench-ingest",
+                "--index",
+                "wikipedia",
+                "--target",
+                "kafka",
+            ])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_export_parquet_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "export-parquet",
+            "--index",
+            "hdfs-logs",
+            "--output-path",
+            "s3://my-bucket/archives/hdfs-logs",
+            "--start-timestamp",
+            "1684000000",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command = CliCommand::Tool(ToolCliCommand::ExportParquet(ExportParquetArgs {
+            config_uri: Uri::from_str("file:///config.yaml")?,
+            index_id: "hdfs-logs".to_string(),
+            output_uri: Uri::from_str("s3://my-bucket/archives/hdfs-logs")?,
+            start_timestamp_opt: Some(1_684_000_000),
+            end_timestamp_opt: None,
+            max_rows_per_file: 1_000_000,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "export-parquet",
+            "--index",
+            "hdfs-logs",
+            "--output-path",
+            "s3://my-bucket/archives/hdfs-logs",
+            "--start-timestamp",
+            "1684000000",
+            "--end-timestamp",
+            "1684000000",
+        ])?;
+        assert!(CliCommand::parse_cli_args(matches).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_restore_parquet_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "tool",
+            "restore-parquet",
+            "--index",
+            "hdfs-logs-restored",
+            "--input-path",
+            "s3://my-bucket/archives/hdfs-logs",
+            "--create-index",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_command =
+            CliCommand::Tool(ToolCliCommand::RestoreParquet(RestoreParquetArgs {
+                client_args: ClientArgs::default(),
+                index_id: "hdfs-logs-restored".to_string(),
+                input_uri: Uri::from_str("s3://my-bucket/archives/hdfs-logs")?,
+                create_index: true,
+                batch_size: 8_192,
+            }));
+        assert_eq!(command, expected_command);
+        Ok(())
+    }
 }
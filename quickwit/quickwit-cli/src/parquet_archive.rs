@@ -0,0 +1,358 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Archives of index snapshots made of Parquet files.
+//!
+//! An archive is a directory holding one Parquet file per split and chunk of documents, and a
+//! manifest recording the config of the archived index and the list of files. When the index
+//! has a timestamp field, files are partitioned by day, Hive-style (`date=2023-05-13/`), so that
+//! lake query engines can prune them.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::ArrayRef;
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use quickwit_config::IndexConfig;
+use quickwit_doc_mapper::FieldMappingEntry;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+/// Name of the manifest file at the root of an archive.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Number of rows converted into a record batch at once.
+const RECORD_BATCH_SIZE: usize = 8_192;
+
+/// Describes the content of an archive.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub index_id: String,
+    pub index_uid: String,
+    /// Config of the archived index, used to recreate the index on restore.
+    pub index_config: IndexConfig,
+    /// Unix timestamp of the export, in seconds.
+    pub exported_at: i64,
+    /// Lower bound (inclusive) of the exported time range, in seconds.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_timestamp: Option<i64>,
+    /// Upper bound (exclusive) of the exported time range, in seconds.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_timestamp: Option<i64>,
+    pub files: Vec<ArchiveFile>,
+}
+
+impl ArchiveManifest {
+    pub fn num_docs(&self) -> u64 {
+        self.files.iter().map(|file| file.num_docs).sum()
+    }
+}
+
+/// A Parquet file of an archive.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    /// Path of the file, relative to the root of the archive.
+    pub path: String,
+    /// ID of the split the documents of the file were read from.
+    pub split_id: String,
+    pub num_docs: u64,
+}
+
+/// Returns the config of the index to create on restore: the archived config under the new
+/// index ID. The index URI is dropped so that the index gets the default URI of the cluster, and
+/// the retention policy and document TTL are dropped so that the restored documents are not
+/// deleted right away.
+pub fn restore_index_config(
+    archived_index_config: &IndexConfig,
+    index_id: &str,
+) -> anyhow::Result<JsonValue> {
+    let mut index_config_json = serde_json::to_value(archived_index_config)?;
+    let index_config_obj = index_config_json
+        .as_object_mut()
+        .context("Index config should serialize to a JSON object.")?;
+    index_config_obj.insert("index_id".to_string(), JsonValue::from(index_id));
+    for key in ["index_uri", "retention", "document_ttl"] {
+        index_config_obj.remove(key);
+    }
+    Ok(index_config_json)
+}
+
+/// Returns the field mappings with the output format of the datetime fields set to RFC 3339, so
+/// that exported timestamps are unambiguous.
+pub fn with_rfc3339_datetime_output(
+    field_mappings: &[FieldMappingEntry],
+) -> anyhow::Result<Vec<FieldMappingEntry>> {
+    let mut field_mappings_json = serde_json::to_value(field_mappings)?;
+    set_rfc3339_datetime_output(&mut field_mappings_json);
+    let field_mappings = serde_json::from_value(field_mappings_json)?;
+    Ok(field_mappings)
+}
+
+fn set_rfc3339_datetime_output(field_mappings_json: &mut JsonValue) {
+    let Some(field_mappings) = field_mappings_json.as_array_mut() else {
+        return;
+    };
+    for field_mapping in field_mappings
+        .iter_mut()
+        .filter_map(JsonValue::as_object_mut)
+    {
+        match field_mapping.get("type").and_then(JsonValue::as_str) {
+            Some("datetime" | "array<datetime>") => {
+                field_mapping.insert("output_format".to_string(), JsonValue::from("rfc3339"));
+            }
+            Some("object" | "array<object>") => {
+                if let Some(field_mappings_json) = field_mapping.get_mut("field_mappings") {
+                    set_rfc3339_datetime_output(field_mappings_json);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Groups documents by the directory of the archive they belong to: the day of their timestamp
+/// if a timestamp field is given, the root of the archive otherwise. Timestamps are expected to
+/// be RFC 3339 strings.
+pub fn partition_docs(
+    docs: Vec<JsonMap<String, JsonValue>>,
+    timestamp_field_opt: Option<&str>,
+) -> BTreeMap<String, Vec<JsonMap<String, JsonValue>>> {
+    let mut partitions: BTreeMap<String, Vec<JsonMap<String, JsonValue>>> = BTreeMap::new();
+
+    for doc in docs {
+        let partition = match timestamp_field_opt {
+            Some(timestamp_field) => {
+                let date_opt = lookup_value(&doc, timestamp_field)
+                    .and_then(JsonValue::as_str)
+                    .and_then(|timestamp| timestamp.get(..10));
+                match date_opt {
+                    Some(date) => format!("date={date}/"),
+                    None => "date=unknown/".to_string(),
+                }
+            }
+            None => String::new(),
+        };
+        partitions.entry(partition).or_default().push(doc);
+    }
+    partitions
+}
+
+/// Looks up the value of a field, possibly nested in objects, given its dotted path.
+fn lookup_value<'a>(
+    doc: &'a JsonMap<String, JsonValue>,
+    field_path: &str,
+) -> Option<&'a JsonValue> {
+    let mut path_segments = field_path.split('.');
+    let mut value = doc.get(path_segments.next()?)?;
+
+    for path_segment in path_segments {
+        value = value.as_object()?.get(path_segment)?;
+    }
+    Some(value)
+}
+
+/// Converts documents into a Parquet file. The schema of the file is inferred from the
+/// documents, and the top-level columns of `datetime_fields` holding RFC 3339 strings are
+/// converted into timestamp columns.
+pub fn docs_to_parquet(
+    docs: &[JsonMap<String, JsonValue>],
+    datetime_fields: &HashSet<String>,
+) -> anyhow::Result<Vec<u8>> {
+    let schema =
+        infer_json_schema_from_iterator(docs.iter().map(|doc| Ok(JsonValue::Object(doc.clone()))))
+            .context("Failed to infer the schema of the documents.")?;
+    let timestamp_type = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+    let output_fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            if datetime_fields.contains(field.name()) && field.data_type() == &DataType::Utf8 {
+                Field::new(field.name(), timestamp_type.clone(), field.is_nullable())
+            } else {
+                field.as_ref().clone()
+            }
+        })
+        .collect();
+    let output_schema = Arc::new(Schema::new(output_fields));
+    let cast_options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let mut decoder = ReaderBuilder::new(Arc::new(schema))
+        .with_batch_size(RECORD_BATCH_SIZE)
+        .with_coerce_primitive(true)
+        .build_decoder()?;
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buffer, output_schema.clone(), None)?;
+
+    for docs_chunk in docs.chunks(RECORD_BATCH_SIZE) {
+        decoder.serialize(docs_chunk)?;
+        let Some(record_batch) = decoder.flush()? else {
+            continue;
+        };
+        let columns = record_batch
+            .columns()
+            .iter()
+            .zip(output_schema.fields().iter())
+            .map(|(column, field)| {
+                if column.data_type() == field.data_type() {
+                    return Ok(column.clone());
+                }
+                cast_with_options(column.as_ref(), field.data_type(), &cast_options)
+                    .with_context(|| format!("Failed to convert column `{}`.", field.name()))
+            })
+            .collect::<anyhow::Result<Vec<ArrayRef>>>()?;
+        let record_batch = RecordBatch::try_new(output_schema.clone(), columns)?;
+        writer.write(&record_batch)?;
+    }
+    writer.close()?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use super::*;
+    use crate::parquet_ingest::{parquet_reader, record_batch_to_ndjson, FieldType};
+
+    fn test_docs() -> Vec<JsonMap<String, JsonValue>> {
+        [
+            json!({"ts": "2023-05-13T17:46:40Z", "body": "foo", "latency": 12}),
+            json!({"ts": "2023-05-13T23:59:59Z", "body": "bar", "status": "ok"}),
+            json!({"ts": "2023-05-14T00:00:00Z", "body": "baz", "latency": 1.5}),
+        ]
+        .into_iter()
+        .map(|doc| match doc {
+            JsonValue::Object(doc) => doc,
+            _ => unreachable!(),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_partition_docs() {
+        let partitions = partition_docs(test_docs(), Some("ts"));
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions["date=2023-05-13/"].len(), 2);
+        assert_eq!(partitions["date=2023-05-14/"].len(), 1);
+
+        let partitions = partition_docs(test_docs(), None);
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[""].len(), 3);
+
+        let partitions = partition_docs(test_docs(), Some("attributes.ts"));
+        assert_eq!(partitions["date=unknown/"].len(), 3);
+    }
+
+    #[test]
+    fn test_docs_to_parquet_round_trip() {
+        let datetime_fields = HashSet::from_iter(["ts".to_string()]);
+        let parquet_bytes = docs_to_parquet(&test_docs(), &datetime_fields).unwrap();
+
+        let (reader, num_rows) = parquet_reader(Bytes::from(parquet_bytes), 1_024).unwrap();
+        assert_eq!(num_rows, 3);
+
+        let record_batches: Vec<RecordBatch> = reader.map(Result::unwrap).collect();
+        assert_eq!(record_batches.len(), 1);
+        let schema = record_batches[0].schema();
+        assert!(matches!(
+            schema.field_with_name("ts").unwrap().data_type(),
+            DataType::Timestamp(TimeUnit::Microsecond, _)
+        ));
+        assert_eq!(
+            schema.field_with_name("latency").unwrap().data_type(),
+            &DataType::Float64
+        );
+        let field_types = HashMap::from_iter([(
+            "ts".to_string(),
+            FieldType::DateTime {
+                accepts_rfc3339: true,
+            },
+        )]);
+        let ndjson = record_batch_to_ndjson(&record_batches[0], &field_types).unwrap();
+        let docs: Vec<JsonValue> = ndjson
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(
+            docs,
+            vec![
+                json!({"ts": "2023-05-13T17:46:40Z", "body": "foo", "latency": 12.0}),
+                json!({"ts": "2023-05-13T23:59:59Z", "body": "bar", "status": "ok"}),
+                json!({"ts": "2023-05-14T00:00:00Z", "body": "baz", "latency": 1.5}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_rfc3339_datetime_output() {
+        let field_mappings: Vec<FieldMappingEntry> = serde_json::from_value(json!([
+            {
+                "name": "ts",
+                "type": "datetime",
+                "input_formats": ["unix_timestamp"],
+                "output_format": "unix_timestamp_secs",
+            },
+            {
+                "name": "resource",
+                "type": "object",
+                "field_mappings": [
+                    {
+                        "name": "created_at",
+                        "type": "array<datetime>",
+                        "output_format": "unix_timestamp_millis",
+                    },
+                ],
+            },
+        ]))
+        .unwrap();
+        let field_mappings = with_rfc3339_datetime_output(&field_mappings).unwrap();
+        let field_mappings_json = serde_json::to_value(&field_mappings).unwrap();
+        assert_eq!(field_mappings_json[0]["output_format"], json!("rfc3339"));
+        assert_eq!(
+            field_mappings_json[1]["field_mappings"][0]["output_format"],
+            json!("rfc3339")
+        );
+    }
+
+    #[test]
+    fn test_restore_index_config() {
+        let index_config = IndexConfig::for_test("archived-index", "s3://bucket/archived-index");
+        let index_config_json = restore_index_config(&index_config, "restored-index").unwrap();
+        assert_eq!(index_config_json["index_id"], json!("restored-index"));
+        assert!(index_config_json.get("index_uri").is_none());
+        assert!(index_config_json.get("retention").is_none());
+        assert_eq!(
+            index_config_json["doc_mapping"],
+            serde_json::to_value(&index_config).unwrap()["doc_mapping"]
+        );
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Conversion of Parquet files into NDJSON documents.
+//!
+//! Columns are mapped to the top-level fields of the doc mapping sharing their name, and their
+//! values are coerced to the types of those fields. Columns without a matching field are kept
+//! as is, for indexes in dynamic mode.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use arrow::array::ArrayRef;
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::json::writer::record_batches_to_json_rows;
+use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use quickwit_doc_mapper::FieldMappingEntry;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Type of a doc mapping field, as far as coercion is concerned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FieldType {
+    Text,
+    I64,
+    U64,
+    F64,
+    Bool,
+    DateTime { accepts_rfc3339: bool },
+    Other,
+}
+
+/// Returns the types of the top-level fields of a doc mapping. Array fields get the type of
+/// their elements.
+pub fn field_types_from_doc_mapping(
+    field_mappings: &[FieldMappingEntry],
+) -> anyhow::Result<HashMap<String, FieldType>> {
+    let mut field_types = HashMap::with_capacity(field_mappings.len());
+
+    for field_mapping in field_mappings {
+        let field_mapping_json = serde_json::to_value(field_mapping)?;
+        let type_str = field_mapping_json
+            .get("type")
+            .and_then(JsonValue::as_str)
+            .context("Field mapping should have a type.")?;
+        let element_type_str = type_str
+            .strip_prefix("array<")
+            .and_then(|type_str| type_str.strip_suffix('>'))
+            .unwrap_or(type_str);
+        let field_type = match element_type_str {
+            "text" => FieldType::Text,
+            "i64" => FieldType::I64,
+            "u64" => FieldType::U64,
+            "f64" => FieldType::F64,
+            "bool" => FieldType::Bool,
+            "datetime" => {
+                let accepts_rfc3339 = field_mapping_json
+                    .get("input_formats")
+                    .and_then(JsonValue::as_array)
+                    .map(|input_formats| {
+                        input_formats
+                            .iter()
+                            .any(|input_format| input_format == "rfc3339")
+                    })
+                    .unwrap_or(true);
+                FieldType::DateTime { accepts_rfc3339 }
+            }
+            _ => FieldType::Other,
+        };
+        field_types.insert(field_mapping.name.clone(), field_type);
+    }
+    Ok(field_types)
+}
+
+/// Opens a reader yielding the rows of a Parquet file in batches of `batch_size` rows.
+///
+/// Returns the reader and the total number of rows of the file.
+pub fn parquet_reader(
+    bytes: Bytes,
+    batch_size: usize,
+) -> anyhow::Result<(ParquetRecordBatchReader, u64)> {
+    let reader_builder = ParquetRecordBatchReaderBuilder::try_new(bytes)
+        .context("Failed to read Parquet file metadata.")?;
+    let num_rows = reader_builder.metadata().file_metadata().num_rows() as u64;
+    let reader = reader_builder.with_batch_size(batch_size).build()?;
+    Ok((reader, num_rows))
+}
+
+/// Unit of the integers temporal columns are converted to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TemporalUnit {
+    Days,
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TemporalUnit {
+    fn from_data_type(data_type: &DataType) -> Option<(Self, DataType)> {
+        let temporal_unit = match data_type {
+            DataType::Date32 => return Some((TemporalUnit::Days, DataType::Int32)),
+            DataType::Date64 => TemporalUnit::Millis,
+            DataType::Timestamp(TimeUnit::Second, _) => TemporalUnit::Seconds,
+            DataType::Timestamp(TimeUnit::Millisecond, _) => TemporalUnit::Millis,
+            DataType::Timestamp(TimeUnit::Microsecond, _) => TemporalUnit::Micros,
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => TemporalUnit::Nanos,
+            _ => return None,
+        };
+        Some((temporal_unit, DataType::Int64))
+    }
+
+    fn to_nanos(self, value: i64) -> i128 {
+        let nanos_per_unit: i128 = match self {
+            TemporalUnit::Days => 86_400 * 1_000_000_000,
+            TemporalUnit::Seconds => 1_000_000_000,
+            TemporalUnit::Millis => 1_000_000,
+            TemporalUnit::Micros => 1_000,
+            TemporalUnit::Nanos => 1,
+        };
+        value as i128 * nanos_per_unit
+    }
+}
+
+/// Converts the rows of a record batch into NDJSON documents.
+pub fn record_batch_to_ndjson(
+    record_batch: &RecordBatch,
+    field_types: &HashMap<String, FieldType>,
+) -> anyhow::Result<Vec<u8>> {
+    // Top-level temporal columns are converted to integers, so that they can be formatted
+    // according to the input formats of the target fields.
+    let mut temporal_units: HashMap<String, TemporalUnit> = HashMap::new();
+    let mut fields: Vec<Field> = Vec::with_capacity(record_batch.num_columns());
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(record_batch.num_columns());
+
+    for (field, column) in record_batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(record_batch.columns())
+    {
+        if let Some((temporal_unit, data_type)) = TemporalUnit::from_data_type(field.data_type()) {
+            temporal_units.insert(field.name().clone(), temporal_unit);
+            fields.push(Field::new(
+                field.name(),
+                data_type.clone(),
+                field.is_nullable(),
+            ));
+            columns.push(cast(column.as_ref(), &data_type)?);
+        } else {
+            fields.push(field.as_ref().clone());
+            columns.push(column.clone());
+        }
+    }
+    let record_batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+    let rows: Vec<JsonMap<String, JsonValue>> = record_batches_to_json_rows(&[&record_batch])?;
+
+    let mut ndjson = Vec::new();
+    for row in rows {
+        let doc: JsonMap<String, JsonValue> = row
+            .into_iter()
+            .map(|(column_name, value)| {
+                let field_type = field_types
+                    .get(&column_name)
+                    .copied()
+                    .unwrap_or(FieldType::Other);
+                let temporal_unit_opt = temporal_units.get(&column_name).copied();
+                let value = coerce_value(value, field_type, temporal_unit_opt);
+                (column_name, value)
+            })
+            .collect();
+        serde_json::to_writer(&mut ndjson, &doc)?;
+        ndjson.push(b'\n');
+    }
+    Ok(ndjson)
+}
+
+/// Coerces a value to a field type. Values that cannot be coerced are left untouched, and get
+/// rejected by the doc processor.
+fn coerce_value(
+    value: JsonValue,
+    field_type: FieldType,
+    temporal_unit_opt: Option<TemporalUnit>,
+) -> JsonValue {
+    if let Some(temporal_unit) = temporal_unit_opt {
+        return coerce_temporal_value(value, field_type, temporal_unit);
+    }
+    match (field_type, value) {
+        (_, JsonValue::Array(values)) => JsonValue::Array(
+            values
+                .into_iter()
+                .map(|value| coerce_value(value, field_type, None))
+                .collect(),
+        ),
+        (FieldType::Text, JsonValue::Number(number)) => JsonValue::String(number.to_string()),
+        (FieldType::Text, JsonValue::Bool(boolean)) => JsonValue::String(boolean.to_string()),
+        (FieldType::I64, JsonValue::String(value_str)) => value_str
+            .trim()
+            .parse::<i64>()
+            .map(JsonValue::from)
+            .unwrap_or(JsonValue::String(value_str)),
+        (FieldType::U64, JsonValue::String(value_str)) => value_str
+            .trim()
+            .parse::<u64>()
+            .map(JsonValue::from)
+            .unwrap_or(JsonValue::String(value_str)),
+        (FieldType::F64, JsonValue::String(value_str)) => value_str
+            .trim()
+            .parse::<f64>()
+            .map(JsonValue::from)
+            .unwrap_or(JsonValue::String(value_str)),
+        (FieldType::Bool, JsonValue::String(value_str)) => {
+            match value_str.trim().to_ascii_lowercase().as_str() {
+                "true" => JsonValue::Bool(true),
+                "false" => JsonValue::Bool(false),
+                _ => JsonValue::String(value_str),
+            }
+        }
+        (FieldType::Bool, JsonValue::Number(number)) => match number.as_i64() {
+            Some(0) => JsonValue::Bool(false),
+            Some(1) => JsonValue::Bool(true),
+            _ => JsonValue::Number(number),
+        },
+        (_, value) => value,
+    }
+}
+
+fn coerce_temporal_value(
+    value: JsonValue,
+    field_type: FieldType,
+    temporal_unit: TemporalUnit,
+) -> JsonValue {
+    let Some(raw_timestamp) = value.as_i64() else {
+        return value;
+    };
+    match field_type {
+        FieldType::I64
+        | FieldType::DateTime {
+            accepts_rfc3339: false,
+        } => {
+            // Days are not a unit the unix timestamp input format understands.
+            if temporal_unit == TemporalUnit::Days {
+                JsonValue::from(raw_timestamp * 86_400)
+            } else {
+                value
+            }
+        }
+        _ => OffsetDateTime::from_unix_timestamp_nanos(temporal_unit.to_nanos(raw_timestamp))
+            .and_then(|datetime| datetime.format(&Rfc3339).ok())
+            .map(JsonValue::String)
+            .unwrap_or(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{
+        Date32Array, Float64Array, Int64Array, StringArray, TimestampMillisecondArray,
+    };
+    use parquet::arrow::ArrowWriter;
+    use serde_json::json;
+
+    use super::*;
+
+    fn test_record_batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("day", DataType::Date32, false),
+            Field::new("status", DataType::Utf8, true),
+            Field::new("latency", DataType::Int64, false),
+            Field::new("ratio", DataType::Float64, false),
+        ]);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from(vec![
+                1_684_000_000_123,
+                1_684_000_001_000,
+            ])),
+            Arc::new(Date32Array::from(vec![19_491, 19_492])),
+            Arc::new(StringArray::from(vec![Some("200"), None])),
+            Arc::new(Int64Array::from(vec![12, 34])),
+            Arc::new(Float64Array::from(vec![0.5, 1.0])),
+        ];
+        RecordBatch::try_new(Arc::new(schema), columns).unwrap()
+    }
+
+    fn parse_ndjson(ndjson: &[u8]) -> Vec<JsonValue> {
+        ndjson
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_record_batch_to_ndjson_with_coercion() {
+        let field_types = HashMap::from_iter([
+            (
+                "timestamp".to_string(),
+                FieldType::DateTime {
+                    accepts_rfc3339: true,
+                },
+            ),
+            (
+                "day".to_string(),
+                FieldType::DateTime {
+                    accepts_rfc3339: false,
+                },
+            ),
+            ("status".to_string(), FieldType::U64),
+            ("latency".to_string(), FieldType::Text),
+        ]);
+        let ndjson = record_batch_to_ndjson(&test_record_batch(), &field_types).unwrap();
+        let docs = parse_ndjson(&ndjson);
+        assert_eq!(
+            docs,
+            vec![
+                json!({
+                    "timestamp": "2023-05-13T17:46:40.123Z",
+                    "day": 1_684_022_400,
+                    "status": 200,
+                    "latency": "12",
+                    "ratio": 0.5,
+                }),
+                json!({
+                    "timestamp": "2023-05-13T17:46:41Z",
+                    "day": 1_684_108_800,
+                    "latency": "34",
+                    "ratio": 1.0,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coerce_value() {
+        assert_eq!(coerce_value(json!("12"), FieldType::I64, None), json!(12));
+        assert_eq!(
+            coerce_value(json!("foo"), FieldType::I64, None),
+            json!("foo")
+        );
+        assert_eq!(
+            coerce_value(json!(["1.5", "2"]), FieldType::F64, None),
+            json!([1.5, 2.0])
+        );
+        assert_eq!(
+            coerce_value(json!("True"), FieldType::Bool, None),
+            json!(true)
+        );
+        assert_eq!(
+            coerce_value(json!(true), FieldType::Text, None),
+            json!("true")
+        );
+        assert_eq!(coerce_value(json!(3), FieldType::Other, None), json!(3));
+    }
+
+    #[test]
+    fn test_parquet_reader() {
+        let record_batch = test_record_batch();
+        let mut buffer = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut buffer, record_batch.schema(), None).unwrap();
+        writer.write(&record_batch).unwrap();
+        writer.close().unwrap();
+
+        let (reader, num_rows) = parquet_reader(Bytes::from(buffer), 1).unwrap();
+        assert_eq!(num_rows, 2);
+
+        let record_batches: Vec<RecordBatch> = reader.map(Result::unwrap).collect();
+        assert_eq!(record_batches.len(), 2);
+        assert_eq!(record_batches[0].num_rows(), 1);
+        assert_eq!(record_batches[0].schema(), record_batch.schema());
+    }
+
+    #[test]
+    fn test_field_types_from_doc_mapping() {
+        let field_mappings: Vec<FieldMappingEntry> = serde_json::from_value(json!([
+            { "name": "timestamp", "type": "datetime", "input_formats": ["unix_timestamp"], "fast": true },
+            { "name": "body", "type": "text" },
+            { "name": "tags", "type": "array<u64>" },
+            { "name": "attributes", "type": "json" },
+        ]))
+        .unwrap();
+        let field_types = field_types_from_doc_mapping(&field_mappings).unwrap();
+        assert_eq!(
+            field_types["timestamp"],
+            FieldType::DateTime {
+                accepts_rfc3339: false
+            }
+        );
+        assert_eq!(field_types["body"], FieldType::Text);
+        assert_eq!(field_types["tags"], FieldType::U64);
+        assert_eq!(field_types["attributes"], FieldType::Other);
+    }
+}
@@ -29,7 +29,7 @@ use quickwit_config::QuickwitConfig;
 use quickwit_serve::serve_quickwit;
 use quickwit_telemetry::payload::{QuickwitFeature, QuickwitTelemetryInfo, TelemetryEvent};
 use tokio::signal;
-use tracing::debug;
+use tracing::{debug, info};
 
 use crate::{config_cli_arg, get_resolvers, load_node_config, start_actor_runtimes};
 
@@ -89,11 +89,7 @@ impl RunCliCommand {
         // TODO move in serve quickwit?
         let runtimes_config = RuntimesConfig::default();
         start_actor_runtimes(runtimes_config, &config.enabled_services)?;
-        let shutdown_signal = Box::pin(async move {
-            signal::ctrl_c()
-                .await
-                .expect("Registering a signal handler for SIGINT should not fail.");
-        });
+        let shutdown_signal = Box::pin(shutdown_signal());
         let serve_result = serve_quickwit(
             config,
             runtimes_config,
@@ -115,6 +111,30 @@ impl RunCliCommand {
     }
 }
 
+/// Resolves once the process receives SIGINT or, on Unix, SIGTERM, which is the signal sent by
+/// Kubernetes and most process managers to stop a process. The node then drains before exiting.
+async fn shutdown_signal() {
+    let sigint = async {
+        signal::ctrl_c()
+            .await
+            .expect("Registering a signal handler for SIGINT should not fail.");
+    };
+    #[cfg(unix)]
+    let sigterm = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Registering a signal handler for SIGTERM should not fail.")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let sigterm = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = sigint => info!("Received SIGINT, shutting down."),
+        _ = sigterm => info!("Received SIGTERM, shutting down."),
+    }
+}
+
 fn quickwit_telemetry_info(config: &QuickwitConfig) -> QuickwitTelemetryInfo {
     let mut features = HashSet::new();
     if config.indexer_config.enable_otlp_endpoint {
@@ -26,14 +26,15 @@ use colored::Colorize;
 use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_common::GREEN_COLOR;
-use quickwit_config::{validate_identifier, ConfigFormat, SourceConfig};
+use quickwit_config::{validate_identifier, ConfigFormat, LabelFilter, SourceConfig};
 use quickwit_metastore::checkpoint::SourceCheckpoint;
+use quickwit_serve::LabelsQueryParams;
 use quickwit_storage::{load_file, StorageResolver};
 use serde_json::Value as JsonValue;
 use tabled::{Table, Tabled};
 use tracing::debug;
 
-use crate::{client_args, make_table, prompt_confirmation, ClientArgs};
+use crate::{client_args, make_table, parse_label_filters, prompt_confirmation, ClientArgs};
 
 pub fn build_source_command() -> Command {
     Command::new("source")
@@ -122,6 +123,10 @@ pub fn build_source_command() -> Command {
                     arg!(--index <INDEX_ID> "ID of the target index")
                         .display_order(1)
                         .required(true),
+                    arg!(--labels <LABEL_FILTERS> "Selects the sources whose labels match all the filters of this comma-separated list of `key` or `key:value` filters.")
+                        .display_order(2)
+                        .required(false)
+                        .value_delimiter(','),
                 ])
             )
         .subcommand(
@@ -174,6 +179,7 @@ pub struct DescribeSourceArgs {
 pub struct ListSourcesArgs {
     pub client_args: ClientArgs,
     pub index_id: String,
+    pub labels: Option<Vec<LabelFilter>>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -301,9 +307,11 @@ impl SourceCliCommand {
         let index_id = matches
             .remove_one::<String>("index")
             .expect("`index` should be a required arg.");
+        let labels = parse_label_filters(&mut matches)?;
         Ok(ListSourcesArgs {
             client_args,
             index_id,
+            labels,
         })
     }
 
@@ -442,12 +450,15 @@ where
 
 async fn list_sources_cli(args: ListSourcesArgs) -> anyhow::Result<()> {
     let qw_client = args.client_args.client();
-    let index_metadata = qw_client
-        .indexes()
-        .get(&args.index_id)
+    let labels_query_params = LabelsQueryParams {
+        labels: args.labels,
+    };
+    let sources = qw_client
+        .sources(&args.index_id)
+        .list(labels_query_params)
         .await
-        .context("Failed to fetch indexes metadatas.")?;
-    let table = make_list_sources_table(index_metadata.sources.into_values());
+        .context("Failed to fetch sources.")?;
+    let table = make_list_sources_table(sources);
     display_tables(&[table]);
     Ok(())
 }
@@ -743,6 +754,7 @@ mod tests {
             source_params: SourceParams::file("path/to/file"),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         }];
         let expected_source = vec![SourceRow {
             source_id: "foo-source".to_string(),
@@ -789,6 +801,34 @@ mod tests {
         let expected_command = CliCommand::Source(SourceCliCommand::ListSources(ListSourcesArgs {
             client_args: ClientArgs::default(),
             index_id: "hdfs-logs".to_string(),
+            labels: None,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from(vec![
+                "source",
+                "list",
+                "--index",
+                "hdfs-logs",
+                "--labels",
+                "team:storage,env",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_command = CliCommand::Source(SourceCliCommand::ListSources(ListSourcesArgs {
+            client_args: ClientArgs::default(),
+            index_id: "hdfs-logs".to_string(),
+            labels: Some(vec![
+                LabelFilter::Equals {
+                    key: "team".to_string(),
+                    value: "storage".to_string(),
+                },
+                LabelFilter::Exists {
+                    key: "env".to_string(),
+                },
+            ]),
         }));
         assert_eq!(command, expected_command);
     }
@@ -804,6 +844,7 @@ mod tests {
                 source_params: SourceParams::stdin(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
             SourceConfig {
                 source_id: "bar-source".to_string(),
@@ -813,6 +854,7 @@ mod tests {
                 source_params: SourceParams::stdin(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         ];
         let expected_sources = [
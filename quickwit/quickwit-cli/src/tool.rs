@@ -17,20 +17,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::io::{stdout, Stdout, Write};
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{env, fmt, io};
 
 use anyhow::{bail, Context};
+use bytes::Bytes;
 use chitchat::transport::ChannelTransport;
 use chitchat::FailureDetectorConfig;
 use clap::{arg, ArgMatches, Command};
 use colored::{ColoredString, Colorize};
 use humantime::format_duration;
+use indicatif::{ProgressBar, ProgressStyle};
+use itertools::Itertools;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
 use quickwit_actors::{ActorExitStatus, ActorHandle, ObservationType, Universe};
 use quickwit_cluster::{Cluster, ClusterMember};
 use quickwit_common::runtimes::RuntimesConfig;
@@ -38,22 +44,42 @@ use quickwit_common::uri::Uri;
 use quickwit_common::{GREEN_COLOR, RED_COLOR};
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{
-    IndexerConfig, QuickwitConfig, SourceConfig, SourceInputFormat, SourceParams, TransformConfig,
+    build_doc_mapper, ConfigFormat, CsvColumnType, CsvParams, FileSourceParams, IndexerConfig,
+    QuickwitConfig, SourceConfig, SourceInputFormat, SourceParams, TransformConfig,
     VecSourceParams, CLI_INGEST_SOURCE_ID,
 };
-use quickwit_core::{clear_cache_directory, IndexService};
+use quickwit_core::{clear_cache_directory, read_split_docs, IndexService};
+use quickwit_doc_mapper::SOURCE_FIELD_NAME;
 use quickwit_indexing::actors::{IndexingService, MergePipeline, MergePipelineId};
 use quickwit_indexing::models::{
     DetachIndexingPipeline, DetachMergePipeline, IndexingStatistics, SpawnPipeline,
 };
 use quickwit_indexing::IndexingPipeline;
-use quickwit_storage::{BundleStorage, Storage};
+use quickwit_metastore::{ListSplitsQuery, SplitState};
+use quickwit_rest_client::models::IngestSource;
+use quickwit_rest_client::rest_client::{CommitType, QuickwitClient};
+use quickwit_serve::SearchRequestQueryString;
+use quickwit_storage::{load_file, BundleStorage, Storage, StorageResolver};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use reqwest::Url;
 use thousands::Separable;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
+use crate::es_import::{
+    build_index_config, es_field_type, EsClient, EsHit, ImportCheckpoint, SCROLL_KEEP_ALIVE,
+};
+use crate::parquet_archive::{
+    docs_to_parquet, partition_docs, restore_index_config, with_rfc3339_datetime_output,
+    ArchiveFile, ArchiveManifest, MANIFEST_FILE_NAME,
+};
+use crate::parquet_ingest::{
+    field_types_from_doc_mapping, parquet_reader, record_batch_to_ndjson, FieldType,
+};
+use crate::stats::percentile;
 use crate::{
-    config_cli_arg, get_resolvers, load_node_config, parse_duration_with_unit, run_index_checklist,
-    start_actor_runtimes, THROUGHPUT_WINDOW_SIZE,
+    client_args, config_cli_arg, get_resolvers, load_node_config, parse_duration_with_unit,
+    run_index_checklist, start_actor_runtimes, ClientArgs, THROUGHPUT_WINDOW_SIZE,
 };
 
 pub fn build_tool_command() -> Command {
@@ -63,17 +89,31 @@ pub fn build_tool_command() -> Command {
         .subcommand(
             Command::new("local-ingest")
                 .display_order(10)
-                .about("Indexes NDJSON documents locally.")
-                .long_about("Local ingest indexes locally NDJSON documents from a file or from stdin and uploads splits on the configured storage.")
+                .about("Indexes NDJSON or CSV documents locally.")
+                .long_about("Local ingest indexes locally NDJSON or CSV documents from a file or from stdin and uploads splits on the configured storage.")
                 .args(&[
                     arg!(--index <INDEX> "ID of the target index")
                         .display_order(1)
                         .required(true),
                     arg!(--"input-path" <INPUT_PATH> "Location of the input file.")
                         .required(false),
-                    arg!(--"input-format" <INPUT_FORMAT> "Format of the input data.")
+                    arg!(--"input-format" <INPUT_FORMAT> "Format of the input data: `json`, `plain`, or `csv`.")
                         .default_value("json")
                         .required(false),
+                    arg!(--"csv-delimiter" <CSV_DELIMITER> "Field delimiter of CSV input. Use `\\t` for TSV input.")
+                        .default_value(",")
+                        .required(false),
+                    arg!(--"csv-quote" <CSV_QUOTE> "Quote character of CSV input.")
+                        .default_value("\"")
+                        .required(false),
+                    arg!(--"csv-no-header" "Indicates that CSV input has no header record. Requires `--csv-columns`.")
+                        .required(false),
+                    arg!(--"csv-columns" <CSV_COLUMNS> "Comma-separated column names of CSV input. Take precedence over the header record.")
+                        .required(false),
+                    arg!(--"csv-schema" <CSV_SCHEMA> "Comma-separated column types of CSV input, e.g. `age:u64,zip:text`. Types: `text`, `i64`, `u64`, `f64`, `bool`.")
+                        .required(false),
+                    arg!(--"csv-no-type-inference" "Keeps the values of CSV columns absent from `--csv-schema` as strings instead of inferring their types.")
+                        .required(false),
                     arg!(--overwrite "Overwrites pre-existing index.")
                         .required(false),
                     arg!(--"transform-script" <SCRIPT> "VRL program to transform docs before ingesting.")
@@ -123,6 +163,148 @@ pub fn build_tool_command() -> Command {
                         .required(true),
                 ])
             )
+        .subcommand(
+            Command::new("bench-search")
+                .display_order(10)
+                .about("Replays a query log against a cluster and reports latency percentiles and error rate.")
+                .long_about("Replays a query log against a cluster with a configurable number of concurrent clients. Each line of the query log is either a JSON search request body, as accepted by the search REST API, or a raw query expressed in the natural query language.")
+                .args(client_args())
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"query-log" <QUERY_LOG> "Location of the query log file.")
+                        .display_order(2)
+                        .required(true),
+                    arg!(--concurrency <CONCURRENCY> "Number of concurrent search clients.")
+                        .default_value("4")
+                        .required(false),
+                    arg!(--"num-iterations" <NUM_ITERATIONS> "Number of times the query log is replayed.")
+                        .default_value("1")
+                        .required(false),
+                    arg!(--duration <DURATION> "Replays the query log in a loop for the given duration (soak test). Overrides `--num-iterations`.")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("bench-ingest")
+                .display_order(10)
+                .about("Generates synthetic logs and ingests them for capacity testing.")
+                .long_about("Generates synthetic structured logs and pushes them through the ingest API of a cluster (`api` target) or directly into a local indexing pipeline (`local` target, requires a node config).")
+                .args(client_args())
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"num-docs" <NUM_DOCS> "Number of documents to generate.")
+                        .default_value("100000")
+                        .required(false),
+                    arg!(--"num-fields" <NUM_FIELDS> "Number of extra attribute fields per document.")
+                        .default_value("8")
+                        .required(false),
+                    arg!(--cardinality <CARDINALITY> "Number of distinct values per attribute field.")
+                        .default_value("1000")
+                        .required(false),
+                    arg!(--"timestamp-skew" <TIMESTAMP_SKEW> "Timestamps are spread uniformly over this period before now.")
+                        .default_value("1h")
+                        .required(false),
+                    arg!(--target <TARGET> "Where to send the documents: `api` or `local`.")
+                        .default_value("api")
+                        .value_parser(["api", "local"])
+                        .required(false),
+                    arg!(--seed <SEED> "Seed of the random generator, for reproducible runs.")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("import-es")
+                .display_order(10)
+                .about("Imports the documents of an Elasticsearch or OpenSearch index.")
+                .long_about("Scrolls the documents out of an Elasticsearch or OpenSearch index and ingests them into a Quickwit index through the ingest API. Documents are read in the order of the sort field, which lets an interrupted import resume from its checkpoint.")
+                .args(client_args())
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"es-endpoint" <ES_ENDPOINT> "Endpoint of the Elasticsearch or OpenSearch cluster.")
+                        .display_order(2)
+                        .required(true),
+                    arg!(--"es-index" <ES_INDEX> "Name of the source index, alias, or index pattern.")
+                        .display_order(3)
+                        .required(true),
+                    arg!(--"sort-field" <SORT_FIELD> "Field the documents are read in order of, usually the timestamp field.")
+                        .display_order(4)
+                        .required(true),
+                    arg!(--"es-auth" <ES_AUTH> "Credentials of the source cluster, formatted as `username:password`.")
+                        .required(false),
+                    arg!(--"batch-size" <BATCH_SIZE> "Number of documents read per scroll page.")
+                        .default_value("1000")
+                        .required(false),
+                    arg!(--"checkpoint-path" <CHECKPOINT_PATH> "File recording the progress of the import. An import is resumed from this file if it exists.")
+                        .required(false),
+                    arg!(--"create-index" "Creates the target index from the mapping of the source index if it does not exist.")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("ingest-parquet")
+                .display_order(10)
+                .about("Ingests the rows of a Parquet file.")
+                .long_about("Reads the rows of a local or remote Parquet file and ingests them into an index through the ingest API. Columns are mapped to the top-level fields of the doc mapping sharing their name, and their values are coerced to the types of those fields.")
+                .args(client_args())
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"input-path" <INPUT_PATH> "Location of the Parquet file, e.g. `s3://my-bucket/logs.parquet`.")
+                        .display_order(2)
+                        .required(true),
+                    arg!(--"batch-size" <BATCH_SIZE> "Number of rows sent per ingest request.")
+                        .default_value("8192")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("export-parquet")
+                .display_order(10)
+                .about("Exports the documents of an index to a Parquet archive.")
+                .long_about("Reads the documents of the published splits of an index, or of a time range of an index, and writes them to Parquet files partitioned by day, along with a manifest recording the index config. The archive can be restored with `restore-parquet`.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the source index.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"output-path" <OUTPUT_PATH> "Location of the archive, e.g. `s3://my-bucket/archives/logs`.")
+                        .display_order(2)
+                        .required(true),
+                    arg!(--"start-timestamp" <START_TIMESTAMP> "Exports only the documents with a timestamp greater than or equal to this Unix timestamp, in seconds.")
+                        .required(false),
+                    arg!(--"end-timestamp" <END_TIMESTAMP> "Exports only the documents with a timestamp strictly less than this Unix timestamp, in seconds.")
+                        .required(false),
+                    arg!(--"max-rows-per-file" <MAX_ROWS_PER_FILE> "Maximum number of rows per Parquet file.")
+                        .default_value("1000000")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("restore-parquet")
+                .display_order(10)
+                .about("Restores a Parquet archive into an index.")
+                .long_about("Reads the manifest and the Parquet files of an archive written by `export-parquet` and ingests their rows into an index through the ingest API.")
+                .args(client_args())
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index.")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"input-path" <INPUT_PATH> "Location of the archive, e.g. `s3://my-bucket/archives/logs`.")
+                        .display_order(2)
+                        .required(true),
+                    arg!(--"create-index" "Creates the target index from the config recorded in the archive if it does not exist.")
+                        .required(false),
+                    arg!(--"batch-size" <BATCH_SIZE> "Number of rows sent per ingest request.")
+                        .default_value("8192")
+                        .required(false),
+                ])
+            )
         .arg_required_else_help(true)
 }
 
@@ -132,6 +314,7 @@ pub struct LocalIngestDocsArgs {
     pub index_id: String,
     pub input_path_opt: Option<PathBuf>,
     pub input_format: SourceInputFormat,
+    pub csv_params: CsvParams,
     pub overwrite: bool,
     pub vrl_script: Option<String>,
     pub clear_cache: bool,
@@ -160,12 +343,105 @@ pub struct ExtractSplitArgs {
     pub target_dir: PathBuf,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct BenchSearchArgs {
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub query_log_path: PathBuf,
+    pub concurrency: usize,
+    pub num_iterations: usize,
+    pub duration_opt: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BenchIngestTarget {
+    /// Documents are sent to the ingest API of the cluster.
+    Api,
+    /// Documents are indexed by a local indexing pipeline.
+    Local,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct BenchIngestArgs {
+    pub config_uri: Uri,
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub num_docs: usize,
+    pub num_fields: usize,
+    pub cardinality: usize,
+    pub timestamp_skew: Duration,
+    pub target: BenchIngestTarget,
+    pub seed_opt: Option<u64>,
+}
+
+#[derive(Eq, PartialEq)]
+pub struct ImportEsArgs {
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub es_endpoint: Url,
+    pub es_index: String,
+    pub sort_field: String,
+    pub es_credentials_opt: Option<(String, String)>,
+    pub batch_size: usize,
+    pub checkpoint_path_opt: Option<PathBuf>,
+    pub create_index: bool,
+}
+
+impl fmt::Debug for ImportEsArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Credentials are deliberately left out.
+        f.debug_struct("ImportEsArgs")
+            .field("client_args", &self.client_args)
+            .field("index_id", &self.index_id)
+            .field("es_endpoint", &self.es_endpoint)
+            .field("es_index", &self.es_index)
+            .field("sort_field", &self.sort_field)
+            .field("batch_size", &self.batch_size)
+            .field("checkpoint_path_opt", &self.checkpoint_path_opt)
+            .field("create_index", &self.create_index)
+            .finish()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct IngestParquetArgs {
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub input_uri: Uri,
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ExportParquetArgs {
+    pub config_uri: Uri,
+    pub index_id: String,
+    pub output_uri: Uri,
+    pub start_timestamp_opt: Option<i64>,
+    pub end_timestamp_opt: Option<i64>,
+    pub max_rows_per_file: usize,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct RestoreParquetArgs {
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub input_uri: Uri,
+    pub create_index: bool,
+    pub batch_size: usize,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ToolCliCommand {
     GarbageCollect(GarbageCollectIndexArgs),
     LocalIngest(LocalIngestDocsArgs),
     Merge(MergeArgs),
     ExtractSplit(ExtractSplitArgs),
+    BenchSearch(BenchSearchArgs),
+    BenchIngest(BenchIngestArgs),
+    ImportEs(ImportEsArgs),
+    IngestParquet(IngestParquetArgs),
+    ExportParquet(ExportParquetArgs),
+    RestoreParquet(RestoreParquetArgs),
 }
 
 impl ToolCliCommand {
@@ -178,6 +454,12 @@ impl ToolCliCommand {
             "local-ingest" => Self::parse_local_ingest_args(submatches),
             "merge" => Self::parse_merge_args(submatches),
             "extract-split" => Self::parse_extract_split_args(submatches),
+            "bench-search" => Self::parse_bench_search_args(submatches),
+            "bench-ingest" => Self::parse_bench_ingest_args(submatches),
+            "import-es" => Self::parse_import_es_args(submatches),
+            "ingest-parquet" => Self::parse_ingest_parquet_args(submatches),
+            "export-parquet" => Self::parse_export_parquet_args(submatches),
+            "restore-parquet" => Self::parse_restore_parquet_args(submatches),
             _ => bail!("Unknown tool subcommand `{subcommand}`."),
         }
     }
@@ -203,6 +485,10 @@ impl ToolCliCommand {
             .map(|input_format| SourceInputFormat::from_str(&input_format))
             .expect("`input-format` should have a default value.")
             .map_err(|err| anyhow::anyhow!(err))?;
+        let csv_params = parse_csv_params(&mut matches)?;
+        if input_format == SourceInputFormat::Csv {
+            csv_params.validate()?;
+        }
         let overwrite = matches.get_flag("overwrite");
         let vrl_script = matches.remove_one::<String>("transform-script");
         let clear_cache = !matches.get_flag("keep-cache");
@@ -212,6 +498,7 @@ impl ToolCliCommand {
             index_id,
             input_path_opt,
             input_format,
+            csv_params,
             overwrite,
             vrl_script,
             clear_cache,
@@ -280,14 +567,302 @@ impl ToolCliCommand {
         }))
     }
 
+    fn parse_bench_search_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let query_log_path = matches
+            .remove_one::<String>("query-log")
+            .map(PathBuf::from)
+            .expect("`query-log` should be a required arg.");
+        let concurrency: usize = matches
+            .remove_one::<String>("concurrency")
+            .expect("`concurrency` should have a default value.")
+            .parse()?;
+        if concurrency == 0 {
+            bail!("`concurrency` must be strictly positive.");
+        }
+        let num_iterations: usize = matches
+            .remove_one::<String>("num-iterations")
+            .expect("`num-iterations` should have a default value.")
+            .parse()?;
+        let duration_opt = matches
+            .remove_one::<String>("duration")
+            .map(|duration| parse_duration_with_unit(&duration))
+            .transpose()?;
+        Ok(Self::BenchSearch(BenchSearchArgs {
+            client_args,
+            index_id,
+            query_log_path,
+            concurrency,
+            num_iterations,
+            duration_opt,
+        }))
+    }
+
+    fn parse_bench_ingest_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let config_uri = matches
+            .remove_one::<String>("config")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`config` should be a required arg.")?;
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let num_docs = matches
+            .remove_one::<String>("num-docs")
+            .expect("`num-docs` should have a default value.")
+            .parse()?;
+        let num_fields = matches
+            .remove_one::<String>("num-fields")
+            .expect("`num-fields` should have a default value.")
+            .parse()?;
+        let cardinality: usize = matches
+            .remove_one::<String>("cardinality")
+            .expect("`cardinality` should have a default value.")
+            .parse()?;
+        if cardinality == 0 {
+            bail!("`cardinality` must be strictly positive.");
+        }
+        let timestamp_skew = matches
+            .remove_one::<String>("timestamp-skew")
+            .map(|duration| parse_duration_with_unit(&duration))
+            .expect("`timestamp-skew` should have a default value.")?;
+        let target = match matches
+            .remove_one::<String>("target")
+            .expect("`target` should have a default value.")
+            .as_str()
+        {
+            "api" => BenchIngestTarget::Api,
+            "local" => BenchIngestTarget::Local,
+            target => bail!("Unknown bench ingest target `{target}`."),
+        };
+        let seed_opt = matches
+            .remove_one::<String>("seed")
+            .map(|seed| seed.parse())
+            .transpose()?;
+        Ok(Self::BenchIngest(BenchIngestArgs {
+            config_uri,
+            client_args,
+            index_id,
+            num_docs,
+            num_fields,
+            cardinality,
+            timestamp_skew,
+            target,
+            seed_opt,
+        }))
+    }
+
+    fn parse_import_es_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let es_endpoint = matches
+            .remove_one::<String>("es-endpoint")
+            .map(|endpoint| Url::parse(&endpoint))
+            .expect("`es-endpoint` should be a required arg.")?;
+        let es_index = matches
+            .remove_one::<String>("es-index")
+            .expect("`es-index` should be a required arg.");
+        let sort_field = matches
+            .remove_one::<String>("sort-field")
+            .expect("`sort-field` should be a required arg.");
+        let es_credentials_opt = matches
+            .remove_one::<String>("es-auth")
+            .map(|es_auth| {
+                es_auth
+                    .split_once(':')
+                    .map(|(username, password)| (username.to_string(), password.to_string()))
+                    .context("`es-auth` must be formatted as `username:password`.")
+            })
+            .transpose()?;
+        let batch_size: usize = matches
+            .remove_one::<String>("batch-size")
+            .expect("`batch-size` should have a default value.")
+            .parse()?;
+        if batch_size == 0 {
+            bail!("`batch-size` must be strictly positive.");
+        }
+        let checkpoint_path_opt = matches
+            .remove_one::<String>("checkpoint-path")
+            .map(PathBuf::from);
+        let create_index = matches.get_flag("create-index");
+        Ok(Self::ImportEs(ImportEsArgs {
+            client_args,
+            index_id,
+            es_endpoint,
+            es_index,
+            sort_field,
+            es_credentials_opt,
+            batch_size,
+            checkpoint_path_opt,
+            create_index,
+        }))
+    }
+
+    fn parse_ingest_parquet_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let input_uri = matches
+            .remove_one::<String>("input-path")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`input-path` should be a required arg.")?;
+        let batch_size: usize = matches
+            .remove_one::<String>("batch-size")
+            .expect("`batch-size` should have a default value.")
+            .parse()?;
+        if batch_size == 0 {
+            bail!("`batch-size` must be strictly positive.");
+        }
+        Ok(Self::IngestParquet(IngestParquetArgs {
+            client_args,
+            index_id,
+            input_uri,
+            batch_size,
+        }))
+    }
+
+    fn parse_export_parquet_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let config_uri = matches
+            .remove_one::<String>("config")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`config` should be a required arg.")?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let output_uri = matches
+            .remove_one::<String>("output-path")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`output-path` should be a required arg.")?;
+        let start_timestamp_opt = matches
+            .remove_one::<String>("start-timestamp")
+            .map(|timestamp| timestamp.parse())
+            .transpose()?;
+        let end_timestamp_opt = matches
+            .remove_one::<String>("end-timestamp")
+            .map(|timestamp| timestamp.parse())
+            .transpose()?;
+        if let (Some(start_timestamp), Some(end_timestamp)) =
+            (start_timestamp_opt, end_timestamp_opt)
+        {
+            if start_timestamp >= end_timestamp {
+                bail!("`start-timestamp` must be strictly less than `end-timestamp`.");
+            }
+        }
+        let max_rows_per_file: usize = matches
+            .remove_one::<String>("max-rows-per-file")
+            .expect("`max-rows-per-file` should have a default value.")
+            .parse()?;
+        if max_rows_per_file == 0 {
+            bail!("`max-rows-per-file` must be strictly positive.");
+        }
+        Ok(Self::ExportParquet(ExportParquetArgs {
+            config_uri,
+            index_id,
+            output_uri,
+            start_timestamp_opt,
+            end_timestamp_opt,
+            max_rows_per_file,
+        }))
+    }
+
+    fn parse_restore_parquet_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let input_uri = matches
+            .remove_one::<String>("input-path")
+            .map(|uri_str| Uri::from_str(&uri_str))
+            .expect("`input-path` should be a required arg.")?;
+        let create_index = matches.get_flag("create-index");
+        let batch_size: usize = matches
+            .remove_one::<String>("batch-size")
+            .expect("`batch-size` should have a default value.")
+            .parse()?;
+        if batch_size == 0 {
+            bail!("`batch-size` must be strictly positive.");
+        }
+        Ok(Self::RestoreParquet(RestoreParquetArgs {
+            client_args,
+            index_id,
+            input_uri,
+            create_index,
+            batch_size,
+        }))
+    }
+
     pub async fn execute(self) -> anyhow::Result<()> {
         match self {
             Self::GarbageCollect(args) => garbage_collect_index_cli(args).await,
             Self::LocalIngest(args) => local_ingest_docs_cli(args).await,
             Self::Merge(args) => merge_cli(args).await,
             Self::ExtractSplit(args) => extract_split_cli(args).await,
+            Self::BenchSearch(args) => bench_search_cli(args).await,
+            Self::BenchIngest(args) => bench_ingest_cli(args).await,
+            Self::ImportEs(args) => import_es_cli(args).await,
+            Self::IngestParquet(args) => ingest_parquet_cli(args).await,
+            Self::ExportParquet(args) => export_parquet_cli(args).await,
+            Self::RestoreParquet(args) => restore_parquet_cli(args).await,
+        }
+    }
+}
+
+fn parse_csv_params(matches: &mut ArgMatches) -> anyhow::Result<CsvParams> {
+    let parse_char = |arg_name: &str, arg_value: String| -> anyhow::Result<char> {
+        if arg_value == "\\t" {
+            return Ok('\t');
+        }
+        let mut chars = arg_value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(character), None) => Ok(character),
+            _ => bail!("`{arg_name}` must be a single character, got `{arg_value}`."),
+        }
+    };
+    let delimiter = matches
+        .remove_one::<String>("csv-delimiter")
+        .map(|delimiter| parse_char("csv-delimiter", delimiter))
+        .expect("`csv-delimiter` should have a default value.")?;
+    let quote = matches
+        .remove_one::<String>("csv-quote")
+        .map(|quote| parse_char("csv-quote", quote))
+        .expect("`csv-quote` should have a default value.")?;
+    let has_header = !matches.get_flag("csv-no-header");
+    let columns = matches
+        .remove_one::<String>("csv-columns")
+        .map(|columns| {
+            columns
+                .split(',')
+                .map(|column| column.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut column_types = BTreeMap::new();
+
+    if let Some(schema) = matches.remove_one::<String>("csv-schema") {
+        for column_schema in schema.split(',') {
+            let (column, column_type_str) = column_schema
+                .split_once(':')
+                .with_context(|| format!("Invalid CSV column schema `{column_schema}`."))?;
+            let column_type = CsvColumnType::from_str(column_type_str.trim())
+                .map_err(|err| anyhow::anyhow!(err))?;
+            column_types.insert(column.trim().to_string(), column_type);
         }
     }
+    let infer_types = !matches.get_flag("csv-no-type-inference");
+    Ok(CsvParams {
+        delimiter,
+        quote,
+        has_header,
+        columns,
+        column_types,
+        infer_types,
+    })
 }
 
 pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<()> {
@@ -298,11 +873,13 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
     let (storage_resolver, metastore_resolver) = get_resolvers(&config).await;
     let metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
 
-    let source_params = if let Some(filepath) = args.input_path_opt.as_ref() {
-        SourceParams::file(filepath)
+    let mut file_source_params = if let Some(filepath) = args.input_path_opt.as_ref() {
+        FileSourceParams::file(filepath)
     } else {
-        SourceParams::stdin()
+        FileSourceParams::stdin()
     };
+    file_source_params.csv = args.csv_params;
+    let source_params = SourceParams::File(file_source_params);
     let transform_config = args
         .vrl_script
         .map(|vrl_script| TransformConfig::new(vrl_script, None));
@@ -314,6 +891,7 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
         source_params,
         transform_config,
         input_format: args.input_format,
+        labels: Default::default(),
     };
     run_index_checklist(
         &*metastore,
@@ -457,6 +1035,7 @@ pub async fn merge_cli(args: MergeArgs) -> anyhow::Result<()> {
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
             pipeline_ord: 0,
         })
@@ -588,6 +1167,716 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Parses a query log. Each non-empty line is either a JSON search request body or a raw query.
+fn parse_query_log(query_log: &str) -> anyhow::Result<Vec<SearchRequestQueryString>> {
+    let mut search_requests = Vec::new();
+    for (line_idx, line) in query_log.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let search_request = if line.starts_with('{') {
+            serde_json::from_str(line).with_context(|| {
+                format!("Failed to parse search request at line {}.", line_idx + 1)
+            })?
+        } else {
+            SearchRequestQueryString {
+                query: line.to_string(),
+                max_hits: 20,
+                ..Default::default()
+            }
+        };
+        search_requests.push(search_request);
+    }
+    Ok(search_requests)
+}
+
+/// Latency and error statistics collected while replaying a query log.
+#[derive(Debug, Default)]
+struct BenchSearchReport {
+    /// Latencies of the successful requests in microseconds.
+    latencies_micros: Vec<u64>,
+    num_errors: usize,
+}
+
+impl BenchSearchReport {
+    fn merge(&mut self, other: BenchSearchReport) {
+        self.latencies_micros.extend(other.latencies_micros);
+        self.num_errors += other.num_errors;
+    }
+
+    fn num_requests(&self) -> usize {
+        self.latencies_micros.len() + self.num_errors
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.num_requests() == 0 {
+            return 0.0;
+        }
+        self.num_errors as f64 / self.num_requests() as f64 * 100.0
+    }
+
+    /// Returns the latency at the given percentile in microseconds. `latencies_micros` must be
+    /// sorted.
+    fn latency_percentile_micros(&self, percent: usize) -> Option<f32> {
+        if self.latencies_micros.is_empty() {
+            return None;
+        }
+        Some(percentile(&self.latencies_micros, percent))
+    }
+}
+
+async fn bench_search_cli(args: BenchSearchArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "bench-search");
+    println!("❯ Replaying query log...");
+
+    let query_log = tokio::fs::read_to_string(&args.query_log_path)
+        .await
+        .with_context(|| format!("Failed to read query log `{:?}`.", args.query_log_path))?;
+    let search_requests = Arc::new(parse_query_log(&query_log)?);
+    if search_requests.is_empty() {
+        bail!("Query log `{:?}` is empty.", args.query_log_path);
+    }
+    let num_requests_to_send = if args.duration_opt.is_some() {
+        usize::MAX
+    } else {
+        search_requests.len() * args.num_iterations
+    };
+    let deadline_opt = args.duration_opt.map(|duration| Instant::now() + duration);
+    let qw_client = Arc::new(args.client_args.search_client());
+    let index_id = Arc::new(args.index_id);
+    let request_counter = Arc::new(AtomicUsize::new(0));
+
+    let start_time = Instant::now();
+    let mut worker_handles = Vec::with_capacity(args.concurrency);
+
+    for _ in 0..args.concurrency {
+        let search_requests = search_requests.clone();
+        let qw_client = qw_client.clone();
+        let index_id = index_id.clone();
+        let request_counter = request_counter.clone();
+
+        let worker_handle = tokio::spawn(async move {
+            let mut report = BenchSearchReport::default();
+            loop {
+                let request_idx = request_counter.fetch_add(1, Ordering::Relaxed);
+                if request_idx >= num_requests_to_send {
+                    break;
+                }
+                if deadline_opt.map_or(false, |deadline| Instant::now() >= deadline) {
+                    break;
+                }
+                let search_request = search_requests[request_idx % search_requests.len()].clone();
+                let request_start = Instant::now();
+                match qw_client.search(&index_id, search_request).await {
+                    Ok(_) => report
+                        .latencies_micros
+                        .push(request_start.elapsed().as_micros() as u64),
+                    Err(error) => {
+                        debug!(error=?error, "search-request-failed");
+                        report.num_errors += 1;
+                    }
+                }
+            }
+            report
+        });
+        worker_handles.push(worker_handle);
+    }
+    let mut report = BenchSearchReport::default();
+
+    for worker_handle in worker_handles {
+        report.merge(worker_handle.await?);
+    }
+    let elapsed = start_time.elapsed();
+    report.latencies_micros.sort();
+
+    let num_requests = report.num_requests();
+    let throughput = num_requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    println!(
+        "Sent {} requests in {} ({throughput:.1} req/s).",
+        num_requests.separate_with_commas(),
+        format_duration(Duration::from_millis(elapsed.as_millis() as u64)),
+    );
+    for (label, percent) in [("p50", 50), ("p90", 90), ("p99", 99), ("max", 100)] {
+        if let Some(latency_micros) = report.latency_percentile_micros(percent) {
+            println!("{label:>4}: {:.1}ms", latency_micros / 1_000.0);
+        }
+    }
+    if report.num_errors == 0 {
+        println!("{} All requests succeeded.", "✔".color(GREEN_COLOR));
+        return Ok(());
+    }
+    println!(
+        "{} {} request(s) failed. {}",
+        "✘".color(RED_COLOR),
+        report.num_errors.separate_with_commas(),
+        colorize_error_rate(report.error_rate()),
+    );
+    Ok(())
+}
+
+const SEVERITY_TEXTS: [&str; 5] = ["DEBUG", "INFO", "INFO", "WARN", "ERROR"];
+
+const BODY_WORDS: [&str; 24] = [
+    "request",
+    "response",
+    "user",
+    "session",
+    "timeout",
+    "connection",
+    "cache",
+    "miss",
+    "hit",
+    "query",
+    "failed",
+    "succeeded",
+    "retry",
+    "upstream",
+    "downstream",
+    "latency",
+    "payload",
+    "token",
+    "expired",
+    "shard",
+    "replica",
+    "leader",
+    "commit",
+    "flush",
+];
+
+/// Generates synthetic structured log documents. Field values are drawn uniformly from a bounded
+/// set of values so that the term cardinality of the generated dataset is controlled.
+struct LogSynthesizer {
+    rng: StdRng,
+    num_fields: usize,
+    cardinality: usize,
+    end_timestamp: i64,
+    timestamp_skew_secs: i64,
+}
+
+impl LogSynthesizer {
+    fn new(
+        num_fields: usize,
+        cardinality: usize,
+        end_timestamp: i64,
+        timestamp_skew: Duration,
+        seed_opt: Option<u64>,
+    ) -> Self {
+        let rng = match seed_opt {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            rng,
+            num_fields,
+            cardinality,
+            end_timestamp,
+            timestamp_skew_secs: timestamp_skew.as_secs() as i64,
+        }
+    }
+
+    fn generate_doc(&mut self) -> serde_json::Value {
+        let timestamp = self.end_timestamp - self.rng.gen_range(0..=self.timestamp_skew_secs);
+        let severity_text = SEVERITY_TEXTS[self.rng.gen_range(0..SEVERITY_TEXTS.len())];
+        let service_name = format!("service-{}", self.rng.gen_range(0..self.cardinality));
+        let num_words = self.rng.gen_range(4..16);
+        let body = (0..num_words)
+            .map(|_| BODY_WORDS[self.rng.gen_range(0..BODY_WORDS.len())])
+            .join(" ");
+        let mut doc = serde_json::json!({
+            "timestamp": timestamp,
+            "severity_text": severity_text,
+            "service_name": service_name,
+            "body": body,
+        });
+        for field_idx in 0..self.num_fields {
+            let value = format!("value-{}", self.rng.gen_range(0..self.cardinality));
+            doc[format!("attr_{field_idx}")] = serde_json::Value::String(value);
+        }
+        doc
+    }
+
+    /// Generates `num_docs` documents in the NDJSON format.
+    fn generate_ndjson(&mut self, num_docs: usize) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        for _ in 0..num_docs {
+            serde_json::to_writer(&mut buffer, &self.generate_doc())?;
+            buffer.push(b'\n');
+        }
+        Ok(buffer)
+    }
+}
+
+async fn bench_ingest_cli(args: BenchIngestArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "bench-ingest");
+    println!(
+        "❯ Generating {} synthetic documents...",
+        args.num_docs.separate_with_commas()
+    );
+    let end_timestamp = time::OffsetDateTime::now_utc().unix_timestamp();
+    let mut log_synthesizer = LogSynthesizer::new(
+        args.num_fields,
+        args.cardinality,
+        end_timestamp,
+        args.timestamp_skew,
+        args.seed_opt,
+    );
+    let ndjson = log_synthesizer.generate_ndjson(args.num_docs)?;
+    let num_bytes = ndjson.len();
+
+    let start_time = Instant::now();
+    match args.target {
+        BenchIngestTarget::Api => {
+            println!("❯ Ingesting documents through the ingest API...");
+            let qw_client = args.client_args.ingest_client();
+            qw_client
+                .ingest(
+                    &args.index_id,
+                    IngestSource::Bytes(Bytes::from(ndjson)),
+                    None,
+                    None,
+                    CommitType::Auto,
+                )
+                .await?;
+        }
+        BenchIngestTarget::Local => {
+            let mut input_file = tempfile::NamedTempFile::new()?;
+            input_file.write_all(&ndjson)?;
+            input_file.flush()?;
+            local_ingest_docs_cli(LocalIngestDocsArgs {
+                config_uri: args.config_uri,
+                index_id: args.index_id,
+                input_path_opt: Some(input_file.path().to_path_buf()),
+                input_format: SourceInputFormat::Json,
+                csv_params: CsvParams::default(),
+                overwrite: false,
+                vrl_script: None,
+                clear_cache: true,
+            })
+            .await?;
+        }
+    }
+    let elapsed_secs = start_time.elapsed().as_secs_f64().max(f64::EPSILON);
+    println!(
+        "Ingested {} documents ({}MB) in {}: {:.0} docs/s, {:.2}MB/s.",
+        args.num_docs.separate_with_commas(),
+        num_bytes / 1_000_000,
+        format_duration(Duration::from_secs(elapsed_secs as u64)),
+        args.num_docs as f64 / elapsed_secs,
+        num_bytes as f64 / 1_000_000f64 / elapsed_secs,
+    );
+    Ok(())
+}
+
+async fn import_es_cli(args: ImportEsArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "import-es");
+    let es_client = EsClient::new(args.es_endpoint, args.es_credentials_opt);
+    let properties = es_client.get_mapping_properties(&args.es_index).await?;
+    let is_date_sort_field = match es_field_type(&properties, &args.sort_field) {
+        Some("date") => true,
+        Some("date_nanos") => bail!(
+            "Sort field `{}` has type `date_nanos`, which is not supported.",
+            args.sort_field
+        ),
+        Some(_) => false,
+        None => bail!(
+            "Sort field `{}` does not exist in the mapping of `{}`.",
+            args.sort_field,
+            args.es_index
+        ),
+    };
+    let qw_client = args.client_args.ingest_client();
+
+    if args.create_index && qw_client.indexes().get(&args.index_id).await.is_err() {
+        let (index_config, untranslated_fields) =
+            build_index_config(&args.index_id, &properties, &args.sort_field);
+        if !untranslated_fields.is_empty() {
+            println!(
+                "❯ The mapping of the following fields cannot be translated, they are indexed \
+                 dynamically: {}.",
+                untranslated_fields.join(", ")
+            );
+        }
+        qw_client
+            .indexes()
+            .create(
+                Bytes::from(serde_json::to_vec(&index_config)?),
+                ConfigFormat::Json,
+                false,
+            )
+            .await?;
+        println!("❯ Created index `{}`.", args.index_id);
+    }
+    let mut checkpoint = match &args.checkpoint_path_opt {
+        Some(checkpoint_path) => ImportCheckpoint::load(checkpoint_path).await?,
+        None => ImportCheckpoint::default(),
+    };
+    if checkpoint.num_imported_docs > 0 {
+        println!(
+            "❯ Resuming import after {} documents...",
+            checkpoint.num_imported_docs.separate_with_commas()
+        );
+    } else {
+        println!("❯ Importing documents from `{}`...", args.es_index);
+    }
+    let query = checkpoint.resume_query(&args.sort_field, is_date_sort_field);
+    let mut scroll_page = es_client
+        .start_scroll(
+            &args.es_index,
+            query,
+            &args.sort_field,
+            args.batch_size,
+            SCROLL_KEEP_ALIVE,
+        )
+        .await?;
+    let progress_bar = match scroll_page.total_hits_opt {
+        Some(num_remaining_docs) => {
+            ProgressBar::new(checkpoint.num_imported_docs + num_remaining_docs)
+        }
+        None => ProgressBar::new_spinner(),
+    };
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+    progress_bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.blue} [{elapsed_precise}] {pos}/{len} documents ({per_sec})",
+        )
+        .expect("Progress style should always be valid."),
+    );
+    progress_bar.set_position(checkpoint.num_imported_docs);
+
+    let mut scroll_id_opt: Option<String> = None;
+    loop {
+        if scroll_page.scroll_id_opt.is_some() {
+            scroll_id_opt = scroll_page.scroll_id_opt.take();
+        }
+        if scroll_page.hits.is_empty() {
+            break;
+        }
+        // Documents sharing the sort value of the checkpoint may have been imported already.
+        let hits_to_import: Vec<&EsHit> = scroll_page
+            .hits
+            .iter()
+            .filter(|hit| !checkpoint.is_imported(hit))
+            .collect();
+        let mut ndjson = Vec::new();
+
+        for hit in &hits_to_import {
+            serde_json::to_writer(&mut ndjson, &hit.source)?;
+            ndjson.push(b'\n');
+        }
+        if !ndjson.is_empty() {
+            qw_client
+                .ingest(
+                    &args.index_id,
+                    IngestSource::Bytes(Bytes::from(ndjson)),
+                    None,
+                    None,
+                    CommitType::Auto,
+                )
+                .await?;
+        }
+        for hit in hits_to_import {
+            checkpoint.record(hit);
+        }
+        if let Some(checkpoint_path) = &args.checkpoint_path_opt {
+            checkpoint.save(checkpoint_path).await?;
+        }
+        progress_bar.set_position(checkpoint.num_imported_docs);
+
+        let Some(scroll_id) = &scroll_id_opt else {
+            break;
+        };
+        scroll_page = es_client
+            .next_scroll_page(scroll_id, SCROLL_KEEP_ALIVE)
+            .await?;
+    }
+    if let Some(scroll_id) = scroll_id_opt {
+        if let Err(error) = es_client.clear_scroll(&scroll_id).await {
+            warn!(error=?error, "Failed to clear scroll.");
+        }
+    }
+    progress_bar.finish();
+    println!(
+        "{} Imported {} documents from `{}`.",
+        "✔".color(GREEN_COLOR),
+        checkpoint.num_imported_docs.separate_with_commas(),
+        args.es_index
+    );
+    Ok(())
+}
+
+async fn ingest_parquet_cli(args: IngestParquetArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "ingest-parquet");
+    let qw_client = args.client_args.ingest_client();
+    let index_metadata = qw_client.indexes().get(&args.index_id).await?;
+    let field_types =
+        field_types_from_doc_mapping(&index_metadata.index_config.doc_mapping.field_mappings)?;
+
+    let storage_resolver = StorageResolver::unconfigured();
+    let file_content = load_file(&storage_resolver, &args.input_uri).await?;
+    let (reader, num_rows) = parquet_reader(Bytes::from(file_content.to_vec()), args.batch_size)?;
+
+    println!("❯ Ingesting rows from `{}`...", args.input_uri);
+    let progress_bar = rows_progress_bar(num_rows);
+    ingest_parquet_rows(
+        &qw_client,
+        &args.index_id,
+        &field_types,
+        reader,
+        &progress_bar,
+    )
+    .await?;
+    progress_bar.finish();
+    println!(
+        "{} Ingested {} rows from `{}`.",
+        "✔".color(GREEN_COLOR),
+        num_rows.separate_with_commas(),
+        args.input_uri
+    );
+    Ok(())
+}
+
+fn rows_progress_bar(num_rows: u64) -> ProgressBar {
+    let progress_bar = ProgressBar::new(num_rows);
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+    progress_bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.blue} [{elapsed_precise}] {pos}/{len} rows ({per_sec})",
+        )
+        .expect("Progress style should always be valid."),
+    );
+    progress_bar
+}
+
+/// Ingests the rows yielded by a Parquet reader through the ingest API, one ingest request per
+/// record batch.
+async fn ingest_parquet_rows(
+    qw_client: &QuickwitClient,
+    index_id: &str,
+    field_types: &HashMap<String, FieldType>,
+    reader: ParquetRecordBatchReader,
+    progress_bar: &ProgressBar,
+) -> anyhow::Result<()> {
+    for record_batch_res in reader {
+        let record_batch = record_batch_res?;
+        let ndjson = record_batch_to_ndjson(&record_batch, field_types)?;
+
+        if !ndjson.is_empty() {
+            qw_client
+                .ingest(
+                    index_id,
+                    IngestSource::Bytes(Bytes::from(ndjson)),
+                    None,
+                    None,
+                    CommitType::Auto,
+                )
+                .await?;
+        }
+        progress_bar.inc(record_batch.num_rows() as u64);
+    }
+    Ok(())
+}
+
+async fn export_parquet_cli(args: ExportParquetArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "export-parquet");
+    let config = load_node_config(&args.config_uri).await?;
+    let (storage_resolver, metastore_resolver) = get_resolvers(&config).await;
+    let metastore = metastore_resolver.resolve(&config.metastore_uri).await?;
+    let index_metadata = metastore.index_metadata(&args.index_id).await?;
+    let index_uid = index_metadata.index_uid.clone();
+    let index_config = index_metadata.index_config.clone();
+
+    let timestamp_range_opt = match (args.start_timestamp_opt, args.end_timestamp_opt) {
+        (None, None) => None,
+        (start_timestamp_opt, end_timestamp_opt) => {
+            Some(start_timestamp_opt.unwrap_or(i64::MIN)..end_timestamp_opt.unwrap_or(i64::MAX))
+        }
+    };
+    if timestamp_range_opt.is_some() && index_config.doc_mapping.timestamp_field.is_none() {
+        bail!(
+            "Index `{}` has no timestamp field, a time range cannot be exported.",
+            args.index_id
+        );
+    }
+    let mut query =
+        ListSplitsQuery::for_index(index_uid.clone()).with_split_state(SplitState::Published);
+    if let Some(start_timestamp) = args.start_timestamp_opt {
+        query = query.with_time_range_start_gte(start_timestamp);
+    }
+    if let Some(end_timestamp) = args.end_timestamp_opt {
+        query = query.with_time_range_end_lt(end_timestamp);
+    }
+    let splits = metastore.list_splits(query).await?;
+
+    // Delete tasks are applied to the splits asynchronously by the janitor: the documents they
+    // match are still present in the splits they have not been applied to yet.
+    let last_delete_opstamp = metastore.last_delete_opstamp(index_uid.clone()).await?;
+    let num_splits_with_pending_deletes = splits
+        .iter()
+        .filter(|split| split.split_metadata.delete_opstamp < last_delete_opstamp)
+        .count();
+    if num_splits_with_pending_deletes > 0 {
+        println!(
+            "❯ {num_splits_with_pending_deletes} split(s) have delete tasks not applied yet, the \
+             documents matching these tasks are exported."
+        );
+    }
+    // Datetime fields are exported as RFC 3339 strings, and then converted into timestamp
+    // columns, whatever their output format.
+    let mut doc_mapping = index_config.doc_mapping.clone();
+    doc_mapping.field_mappings = with_rfc3339_datetime_output(&doc_mapping.field_mappings)?;
+    let doc_mapper = build_doc_mapper(&doc_mapping, &index_config.search_settings)?;
+    let datetime_fields: HashSet<String> =
+        field_types_from_doc_mapping(&doc_mapping.field_mappings)?
+            .into_iter()
+            .filter(|(_, field_type)| matches!(field_type, FieldType::DateTime { .. }))
+            .map(|(field_name, _)| field_name)
+            .collect();
+
+    let index_storage = storage_resolver.resolve(index_metadata.index_uri()).await?;
+    let output_storage = storage_resolver.resolve(&args.output_uri).await?;
+
+    println!(
+        "❯ Exporting {} split(s) of `{}` to `{}`...",
+        splits.len(),
+        args.index_id,
+        args.output_uri
+    );
+    let num_split_docs: usize = splits
+        .iter()
+        .map(|split| split.split_metadata.num_docs)
+        .sum();
+    let progress_bar = rows_progress_bar(num_split_docs as u64);
+    let mut files = Vec::new();
+
+    for split in &splits {
+        let split_id = split.split_id();
+        let mut docs = read_split_docs(
+            &*index_storage,
+            split_id,
+            &*doc_mapper,
+            timestamp_range_opt.clone(),
+        )
+        .await?;
+        // Only the stored fields are exported, the original documents are not.
+        for doc in &mut docs {
+            doc.remove(SOURCE_FIELD_NAME);
+        }
+        for (partition, docs_in_partition) in
+            partition_docs(docs, doc_mapper.timestamp_field_name())
+        {
+            for (chunk_ord, docs_chunk) in
+                docs_in_partition.chunks(args.max_rows_per_file).enumerate()
+            {
+                let path = format!("{partition}{split_id}-{chunk_ord}.parquet");
+                let parquet_bytes = docs_to_parquet(docs_chunk, &datetime_fields)?;
+                output_storage
+                    .put(Path::new(&path), Box::new(parquet_bytes))
+                    .await
+                    .with_context(|| format!("Failed to write file `{path}`."))?;
+                files.push(ArchiveFile {
+                    path,
+                    split_id: split_id.to_string(),
+                    num_docs: docs_chunk.len() as u64,
+                });
+            }
+        }
+        progress_bar.inc(split.split_metadata.num_docs as u64);
+    }
+    progress_bar.finish();
+
+    // The manifest is written last, so that an interrupted export does not look complete.
+    let manifest = ArchiveManifest {
+        index_id: args.index_id.clone(),
+        index_uid: index_uid.to_string(),
+        index_config,
+        exported_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+        start_timestamp: args.start_timestamp_opt,
+        end_timestamp: args.end_timestamp_opt,
+        files,
+    };
+    output_storage
+        .put(
+            Path::new(MANIFEST_FILE_NAME),
+            Box::new(serde_json::to_vec_pretty(&manifest)?),
+        )
+        .await
+        .context("Failed to write the archive manifest.")?;
+    println!(
+        "{} Exported {} documents of `{}` to {} Parquet file(s).",
+        "✔".color(GREEN_COLOR),
+        manifest.num_docs().separate_with_commas(),
+        args.index_id,
+        manifest.files.len()
+    );
+    Ok(())
+}
+
+async fn restore_parquet_cli(args: RestoreParquetArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "restore-parquet");
+    let storage_resolver = StorageResolver::unconfigured();
+    let archive_storage = storage_resolver.resolve(&args.input_uri).await?;
+    let manifest_bytes = archive_storage
+        .get_all(Path::new(MANIFEST_FILE_NAME))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to read the manifest of archive `{}`.",
+                args.input_uri
+            )
+        })?;
+    let manifest: ArchiveManifest = serde_json::from_slice(manifest_bytes.as_slice())
+        .context("Failed to parse the archive manifest.")?;
+    let qw_client = args.client_args.ingest_client();
+
+    if args.create_index && qw_client.indexes().get(&args.index_id).await.is_err() {
+        let index_config = restore_index_config(&manifest.index_config, &args.index_id)?;
+        qw_client
+            .indexes()
+            .create(
+                Bytes::from(serde_json::to_vec(&index_config)?),
+                ConfigFormat::Json,
+                false,
+            )
+            .await?;
+        println!("❯ Created index `{}`.", args.index_id);
+    }
+    let index_metadata = qw_client.indexes().get(&args.index_id).await?;
+    let field_types =
+        field_types_from_doc_mapping(&index_metadata.index_config.doc_mapping.field_mappings)?;
+
+    println!(
+        "❯ Restoring documents of `{}` from `{}`...",
+        manifest.index_id, args.input_uri
+    );
+    let progress_bar = rows_progress_bar(manifest.num_docs());
+
+    for file in &manifest.files {
+        let file_content = archive_storage
+            .get_all(Path::new(&file.path))
+            .await
+            .with_context(|| format!("Failed to read file `{}`.", file.path))?;
+        let (reader, _num_rows) =
+            parquet_reader(Bytes::from(file_content.to_vec()), args.batch_size)?;
+        ingest_parquet_rows(
+            &qw_client,
+            &args.index_id,
+            &field_types,
+            reader,
+            &progress_bar,
+        )
+        .await?;
+    }
+    progress_bar.finish();
+    println!(
+        "{} Restored {} documents into `{}`.",
+        "✔".color(GREEN_COLOR),
+        manifest.num_docs().separate_with_commas(),
+        args.index_id
+    );
+    Ok(())
+}
+
 /// Starts a tokio task that displays the indexing statistics
 /// every once in awhile.
 pub async fn start_statistics_reporting_loop(
@@ -792,3 +2081,84 @@ async fn create_empty_cluster(config: &QuickwitConfig) -> anyhow::Result<Cluster
     .await?;
     Ok(cluster)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_log() {
+        let query_log = r#"
+            severity_text:ERROR
+            {"query": "body:foo", "max_hits": 5, "search_field": "body,title"}
+        "#;
+        let search_requests = parse_query_log(query_log).unwrap();
+        assert_eq!(search_requests.len(), 2);
+        assert_eq!(search_requests[0].query, "severity_text:ERROR");
+        assert_eq!(search_requests[0].max_hits, 20);
+        assert_eq!(search_requests[1].query, "body:foo");
+        assert_eq!(search_requests[1].max_hits, 5);
+        assert_eq!(
+            search_requests[1].search_fields,
+            Some(vec!["body".to_string(), "title".to_string()])
+        );
+
+        let error = parse_query_log("{\"quer\": \"foo\"}").unwrap_err();
+        assert!(error.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn test_log_synthesizer() {
+        let mut log_synthesizer =
+            LogSynthesizer::new(3, 2, 1_000_000, Duration::from_secs(60), Some(42));
+        for _ in 0..100 {
+            let doc = log_synthesizer.generate_doc();
+            assert_eq!(doc.as_object().unwrap().len(), 7);
+
+            let timestamp = doc["timestamp"].as_i64().unwrap();
+            assert!((1_000_000 - 60..=1_000_000).contains(&timestamp));
+
+            let service_name = doc["service_name"].as_str().unwrap();
+            assert!(["service-0", "service-1"].contains(&service_name));
+
+            for field_idx in 0..3 {
+                let value = doc[format!("attr_{field_idx}")].as_str().unwrap();
+                assert!(["value-0", "value-1"].contains(&value));
+            }
+        }
+        let ndjson = log_synthesizer.generate_ndjson(10).unwrap();
+        assert_eq!(ndjson.iter().filter(|byte| **byte == b'\n').count(), 10);
+
+        let mut log_synthesizer_0 =
+            LogSynthesizer::new(3, 100, 1_000_000, Duration::from_secs(60), Some(7));
+        let mut log_synthesizer_1 =
+            LogSynthesizer::new(3, 100, 1_000_000, Duration::from_secs(60), Some(7));
+        assert_eq!(
+            log_synthesizer_0.generate_ndjson(10).unwrap(),
+            log_synthesizer_1.generate_ndjson(10).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_bench_search_report() {
+        let mut report = BenchSearchReport::default();
+        assert!(report.latency_percentile_micros(50).is_none());
+        assert_eq!(report.error_rate(), 0.0);
+
+        report.merge(BenchSearchReport {
+            latencies_micros: (1..=100).collect(),
+            num_errors: 0,
+        });
+        report.merge(BenchSearchReport {
+            latencies_micros: Vec::new(),
+            num_errors: 25,
+        });
+        report.latencies_micros.sort();
+        assert_eq!(report.num_requests(), 125);
+        assert_eq!(report.error_rate(), 20.0);
+        assert_eq!(report.latency_percentile_micros(50), Some(50.5));
+        let p99_latency_micros = report.latency_percentile_micros(99).unwrap();
+        assert!((p99_latency_micros - 99.01).abs() < 0.001);
+        assert_eq!(report.latency_percentile_micros(100), Some(100.0));
+    }
+}
@@ -40,7 +40,7 @@ use quickwit_common::fs::get_cache_directory_path;
 use quickwit_common::rand::append_random_suffix;
 use quickwit_common::uri::Uri;
 use quickwit_common::ChecklistError;
-use quickwit_config::{SourceInputFormat, CLI_INGEST_SOURCE_ID};
+use quickwit_config::{CsvParams, SourceInputFormat, CLI_INGEST_SOURCE_ID};
 use quickwit_metastore::{MetastoreError, MetastoreResolver, SplitState};
 use serde_json::{json, Number, Value};
 use tokio::time::{sleep, Duration};
@@ -66,6 +66,7 @@ async fn local_ingest_docs(input_path: &Path, test_env: &TestEnv) -> anyhow::Res
         index_id: test_env.index_id.clone(),
         input_path_opt: Some(input_path.to_path_buf()),
         input_format: SourceInputFormat::Json,
+        csv_params: CsvParams::default(),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -178,6 +179,7 @@ async fn test_cmd_ingest_on_non_existing_index() {
         index_id: "index-does-not-exist".to_string(),
         input_path_opt: Some(test_env.resource_files["logs"].clone()),
         input_format: SourceInputFormat::Json,
+        csv_params: CsvParams::default(),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -208,6 +210,7 @@ async fn test_ingest_docs_cli_keep_cache() {
         index_id,
         input_path_opt: Some(test_env.resource_files["logs"].clone()),
         input_format: SourceInputFormat::Json,
+        csv_params: CsvParams::default(),
         overwrite: false,
         clear_cache: false,
         vrl_script: None,
@@ -235,6 +238,7 @@ async fn test_ingest_docs_cli() {
         index_id: index_id.clone(),
         input_path_opt: Some(test_env.resource_files["logs"].clone()),
         input_format: SourceInputFormat::Json,
+        csv_params: CsvParams::default(),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -263,6 +267,7 @@ async fn test_ingest_docs_cli() {
         index_id: test_env.index_id,
         input_path_opt: Some(test_env.data_dir_path.join("file-does-not-exist.json")),
         input_format: SourceInputFormat::Json,
+        csv_params: CsvParams::default(),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -0,0 +1,148 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use anyhow::{bail, Context};
+use base64::prelude::{Engine, BASE64_STANDARD};
+
+/// Number of bits allocated per split. Combined with `NUM_HASHES` hash functions, this yields a
+/// false positive rate of about 1%.
+const NUM_BITS_PER_SPLIT: usize = 10;
+
+const NUM_HASHES: u64 = 7;
+
+/// Maximum size of a digest, so that it fits in a gossip message. Past ~6,500 splits, the false
+/// positive rate of the digest increases.
+const MAX_DIGEST_NUM_BYTES: usize = 8 * 1024;
+
+/// Compact summary of the splits cached by a searcher, advertised to the other nodes of the
+/// cluster so that the root nodes can assign the search jobs targeting these splits to it.
+///
+/// The digest is a Bloom filter: it may report that a split is cached when it is not, but never
+/// the opposite.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CachedSplitsDigest {
+    bits: Vec<u64>,
+}
+
+impl CachedSplitsDigest {
+    /// Builds the digest of a set of splits.
+    pub fn from_split_ids<S: AsRef<str>>(split_ids: &[S]) -> Self {
+        let num_bits = (split_ids.len() * NUM_BITS_PER_SPLIT).clamp(64, MAX_DIGEST_NUM_BYTES * 8);
+        let mut bits = vec![0u64; (num_bits + 63) / 64];
+        let num_bits = bits.len() * 64;
+
+        for split_id in split_ids {
+            for bit in bit_positions(split_id.as_ref(), num_bits) {
+                bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        Self { bits }
+    }
+
+    /// Returns whether the split may be cached. False positives are possible.
+    pub fn may_contain(&self, split_id: &str) -> bool {
+        bit_positions(split_id, self.bits.len() * 64)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Serializes the digest into the value stored in the chitchat state.
+    pub(crate) fn to_base64(&self) -> String {
+        let bytes: Vec<u8> = self
+            .bits
+            .iter()
+            .flat_map(|block| block.to_le_bytes())
+            .collect();
+        BASE64_STANDARD.encode(bytes)
+    }
+
+    /// Deserializes a digest serialized with [`CachedSplitsDigest::to_base64`].
+    pub(crate) fn from_base64(digest_base64: &str) -> anyhow::Result<Self> {
+        let bytes = BASE64_STANDARD
+            .decode(digest_base64)
+            .context("Failed to decode cached splits digest.")?;
+        if bytes.is_empty() || bytes.len() % 8 != 0 || bytes.len() > MAX_DIGEST_NUM_BYTES {
+            bail!(
+                "Cached splits digest has an invalid length of {} bytes.",
+                bytes.len()
+            );
+        }
+        let bits = bytes
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().expect("Chunk should be 8 bytes.")))
+            .collect();
+        Ok(Self { bits })
+    }
+}
+
+/// Returns the positions of the bits associated with the split, using double hashing. The hash
+/// function must be the same on all the nodes of the cluster.
+fn bit_positions(split_id: &str, num_bits: usize) -> impl Iterator<Item = usize> {
+    let mut hasher = DefaultHasher::new();
+    split_id.hash(&mut hasher);
+    let hash = hasher.finish();
+    let hash_1 = hash & u32::MAX as u64;
+    let hash_2 = (hash >> 32) | 1;
+    (0..NUM_HASHES)
+        .map(move |i| (hash_1.wrapping_add(i.wrapping_mul(hash_2)) % num_bits as u64) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_splits_digest() {
+        let split_ids: Vec<String> = (0..1_000).map(|i| format!("split-{i}")).collect();
+        let digest = CachedSplitsDigest::from_split_ids(&split_ids);
+
+        for split_id in &split_ids {
+            assert!(digest.may_contain(split_id));
+        }
+        let num_false_positives = (0..1_000)
+            .filter(|i| digest.may_contain(&format!("other-split-{i}")))
+            .count();
+        assert!(num_false_positives < 50);
+
+        let empty_digest = CachedSplitsDigest::from_split_ids::<&str>(&[]);
+        assert!(!empty_digest.may_contain("split-0"));
+    }
+
+    #[test]
+    fn test_cached_splits_digest_size_is_bounded() {
+        let split_ids: Vec<String> = (0..100_000).map(|i| format!("split-{i}")).collect();
+        let digest = CachedSplitsDigest::from_split_ids(&split_ids);
+        assert_eq!(digest.bits.len() * 8, MAX_DIGEST_NUM_BYTES);
+        assert!(digest.may_contain("split-42"));
+    }
+
+    #[test]
+    fn test_cached_splits_digest_serde() {
+        let digest = CachedSplitsDigest::from_split_ids(&["split-1", "split-2"]);
+        let digest_base64 = digest.to_base64();
+        assert_eq!(
+            CachedSplitsDigest::from_base64(&digest_base64).unwrap(),
+            digest
+        );
+        CachedSplitsDigest::from_base64("not base64!").unwrap_err();
+        CachedSplitsDigest::from_base64(&BASE64_STANDARD.encode([0u8; 3])).unwrap_err();
+    }
+}
@@ -39,12 +39,15 @@ use tokio_stream::wrappers::{UnboundedReceiverStream, WatchStream};
 use tokio_stream::StreamExt;
 use tracing::{info, warn};
 
+use crate::cached_splits::CachedSplitsDigest;
 use crate::change::{compute_cluster_change_events, ClusterChange};
 use crate::member::{
-    build_cluster_member, ClusterMember, NodeStateExt, ENABLED_SERVICES_KEY,
-    GRPC_ADVERTISE_ADDR_KEY, INDEXING_TASK_PREFIX, READINESS_KEY, READINESS_VALUE_NOT_READY,
-    READINESS_VALUE_READY,
+    build_cluster_member, ClusterMember, NodeStateExt, BUILD_VERSION_KEY, CACHED_SPLITS_DIGEST_KEY,
+    ENABLED_SERVICES_KEY, GRPC_ADVERTISE_ADDR_KEY, INDEXING_TASK_PREFIX, PROTOCOL_VERSION_KEY,
+    READINESS_KEY, READINESS_VALUE_NOT_READY, READINESS_VALUE_READY, SPLIT_CACHE_NUM_BYTES_KEY,
+    SPLIT_CACHE_NUM_SPLITS_KEY,
 };
+use crate::topology::{build_node_topology, ClusterTopology, NodeStatus, SplitCacheStats};
 use crate::ClusterNode;
 
 const GOSSIP_INTERVAL: Duration = if cfg!(any(test, feature = "testsuite")) {
@@ -146,6 +149,10 @@ impl Cluster {
                     GRPC_ADVERTISE_ADDR_KEY.to_string(),
                     self_node.grpc_advertise_addr.to_string(),
                 ),
+                (
+                    PROTOCOL_VERSION_KEY.to_string(),
+                    self_node.protocol_version.to_string(),
+                ),
                 (
                     READINESS_KEY.to_string(),
                     READINESS_VALUE_NOT_READY.to_string(),
@@ -289,6 +296,54 @@ impl Cluster {
         }
     }
 
+    /// Returns the topology of the cluster: the nodes, their roles, and their running indexing
+    /// pipelines. Nodes whose state cannot be parsed are skipped.
+    pub async fn topology(&self) -> ClusterTopology {
+        let chitchat = self.chitchat().await;
+        let chitchat_guard = chitchat.lock().await;
+        let live_nodes = chitchat_guard.live_nodes().map(|chitchat_id| {
+            let node_state = chitchat_guard.node_state(chitchat_id);
+            let status = if node_state
+                .map(|node_state| node_state.is_ready())
+                .unwrap_or(false)
+            {
+                NodeStatus::Ready
+            } else {
+                NodeStatus::Live
+            };
+            (chitchat_id, node_state, status)
+        });
+        let dead_nodes = chitchat_guard.dead_nodes().map(|chitchat_id| {
+            let node_state = chitchat_guard.node_state(chitchat_id);
+            (chitchat_id, node_state, NodeStatus::Dead)
+        });
+        let mut nodes = Vec::new();
+
+        for (chitchat_id, node_state_opt, status) in live_nodes.chain(dead_nodes) {
+            let Some(node_state) = node_state_opt else {
+                continue;
+            };
+            match build_node_topology(chitchat_id.clone(), node_state, status) {
+                Ok(node_topology) => nodes.push(node_topology),
+                Err(error) => {
+                    warn!(
+                        node_id=%chitchat_id.node_id,
+                        error=?error,
+                        "Failed to build node topology from Chitchat node state."
+                    );
+                }
+            }
+        }
+        nodes.sort_by(|left, right| {
+            (&left.node_id, left.generation_id).cmp(&(&right.node_id, right.generation_id))
+        });
+        ClusterTopology {
+            cluster_id: self.cluster_id.clone(),
+            self_node_id: self.self_chitchat_id.node_id.clone(),
+            nodes,
+        }
+    }
+
     /// Leaves the cluster.
     pub async fn shutdown(self) {
         info!(
@@ -334,6 +389,37 @@ impl Cluster {
         Ok(())
     }
 
+    /// Advertises the version of Quickwit running on the self node.
+    pub async fn set_self_node_build_version(&self, build_version: &str) {
+        self.set_self_key_value(BUILD_VERSION_KEY, build_version)
+            .await
+    }
+
+    /// Updates the usage of the self node's split cache in chitchat state.
+    pub async fn update_self_node_split_cache_stats(&self, split_cache_stats: SplitCacheStats) {
+        let chitchat = self.chitchat().await;
+        let mut chitchat_guard = chitchat.lock().await;
+        let self_node_state = chitchat_guard.self_node_state();
+        self_node_state.set(
+            SPLIT_CACHE_NUM_SPLITS_KEY,
+            split_cache_stats.num_splits.to_string(),
+        );
+        self_node_state.set(
+            SPLIT_CACHE_NUM_BYTES_KEY,
+            split_cache_stats.num_bytes.to_string(),
+        );
+    }
+
+    /// Advertises the digest of the splits cached by the self node, so that the root nodes can
+    /// assign the search jobs targeting these splits to it.
+    pub async fn update_self_node_cached_splits_digest(
+        &self,
+        cached_splits_digest: &CachedSplitsDigest,
+    ) {
+        self.set_self_key_value(CACHED_SPLITS_DIGEST_KEY, cached_splits_digest.to_base64())
+            .await
+    }
+
     async fn chitchat(&self) -> Arc<Mutex<Chitchat>> {
         self.inner.read().await.chitchat_handle.chitchat()
     }
@@ -575,6 +661,7 @@ mod tests {
     use rand::Rng;
 
     use super::*;
+    use crate::protocol::{LEGACY_PROTOCOL_VERSION, PROTOCOL_VERSION};
 
     #[tokio::test]
     async fn test_single_node_cluster_readiness() {
@@ -769,6 +856,10 @@ mod tests {
         cluster2
             .set_self_key_value(GRPC_ADVERTISE_ADDR_KEY, "127.0.0.1:1001")
             .await;
+        // Simulates a node running the protocol version preceding the current one.
+        cluster2
+            .set_self_key_value(PROTOCOL_VERSION_KEY, LEGACY_PROTOCOL_VERSION.to_string())
+            .await;
         cluster2
             .update_self_node_indexing_tasks(&[indexing_task.clone(), indexing_task.clone()])
             .await
@@ -791,6 +882,7 @@ mod tests {
             HashSet::from_iter([QuickwitService::Indexer])
         );
         assert!(member_node_1.indexing_tasks.is_empty());
+        assert_eq!(member_node_1.protocol_version, PROTOCOL_VERSION);
         assert_eq!(
             member_node_2.grpc_advertise_addr,
             ([127, 0, 0, 1], 1001).into()
@@ -803,6 +895,137 @@ mod tests {
             member_node_2.indexing_tasks,
             vec![indexing_task.clone(), indexing_task.clone()]
         );
+        assert_eq!(member_node_2.protocol_version, LEGACY_PROTOCOL_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_topology() {
+        let transport = ChannelTransport::default();
+        let cluster1 = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let cluster2 = create_cluster_for_test(
+            vec![cluster1.gossip_listen_addr.to_string()],
+            &["metastore", "indexer"],
+            &transport,
+            false,
+        )
+        .await
+        .unwrap();
+        let indexing_task = IndexingTask {
+            index_uid: "index-1:11111111111111111111111111".to_string(),
+            source_id: "source-1".to_string(),
+        };
+        cluster2.set_self_node_build_version("0.6.0").await;
+        cluster2
+            .update_self_node_indexing_tasks(&[indexing_task.clone(), indexing_task.clone()])
+            .await
+            .unwrap();
+        cluster2
+            .update_self_node_split_cache_stats(SplitCacheStats {
+                num_splits: 3,
+                num_bytes: 1_000,
+            })
+            .await;
+        wait_until_predicate(
+            || {
+                let cluster1 = cluster1.clone();
+                async move {
+                    let topology = cluster1.topology().await;
+                    topology.nodes.len() == 2
+                        && topology.nodes.iter().any(|node| node.split_cache.is_some())
+                }
+            },
+            Duration::from_secs(10),
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap();
+
+        let topology = cluster1.topology().await;
+        assert_eq!(topology.cluster_id, "test-cluster");
+        assert_eq!(topology.self_node_id, cluster1.self_node_id());
+
+        let find_node = |node_id: &str| {
+            topology
+                .nodes
+                .iter()
+                .find(|node| node.node_id == node_id)
+                .unwrap()
+        };
+        let node1 = find_node(cluster1.self_node_id());
+        assert_eq!(node1.status, NodeStatus::Ready);
+        assert_eq!(node1.roles, ["searcher"]);
+        assert!(node1.version.is_none());
+        assert_eq!(node1.protocol_version, PROTOCOL_VERSION);
+        assert!(node1.indexing_pipelines.is_empty());
+        assert!(node1.split_cache.is_none());
+
+        let node2 = find_node(cluster2.self_node_id());
+        assert_eq!(node2.status, NodeStatus::Live);
+        assert_eq!(node2.roles, ["indexer", "metastore"]);
+        assert_eq!(node2.version.as_deref(), Some("0.6.0"));
+        assert_eq!(node2.indexing_pipelines.len(), 1);
+        assert_eq!(
+            node2.indexing_pipelines[0].index_uid,
+            "index-1:11111111111111111111111111"
+        );
+        assert_eq!(node2.indexing_pipelines[0].source_id, "source-1");
+        assert_eq!(node2.indexing_pipelines[0].num_pipelines, 2);
+        assert_eq!(
+            node2.split_cache,
+            Some(SplitCacheStats {
+                num_splits: 3,
+                num_bytes: 1_000,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cluster_cached_splits_digest() {
+        let transport = ChannelTransport::default();
+        let cluster1 = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let cluster2 = create_cluster_for_test(
+            vec![cluster1.gossip_listen_addr.to_string()],
+            &["searcher"],
+            &transport,
+            true,
+        )
+        .await
+        .unwrap();
+        let cached_splits_digest = CachedSplitsDigest::from_split_ids(&["split-1", "split-2"]);
+        cluster2
+            .update_self_node_cached_splits_digest(&cached_splits_digest)
+            .await;
+        let cluster2_node_id = cluster2.self_node_id().to_string();
+        cluster1
+            .wait_for_ready_members(
+                |members| {
+                    members.iter().any(|member| {
+                        member.node_id == cluster2_node_id && member.cached_splits_digest.is_some()
+                    })
+                },
+                Duration::from_secs(10),
+            )
+            .await
+            .unwrap();
+
+        let ready_members = cluster1.ready_members().await;
+        let member2 = ready_members
+            .iter()
+            .find(|member| member.node_id == cluster2_node_id)
+            .unwrap();
+        let digest = member2.cached_splits_digest.as_ref().unwrap();
+        assert_eq!(digest, &cached_splits_digest);
+        assert!(digest.may_contain("split-1"));
+
+        let member1 = ready_members
+            .iter()
+            .find(|member| member.node_id == cluster1.self_node_id())
+            .unwrap();
+        assert!(member1.cached_splits_digest.is_none());
     }
 
     #[tokio::test]
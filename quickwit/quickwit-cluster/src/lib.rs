@@ -19,10 +19,13 @@
 
 #![deny(clippy::disallowed_methods)]
 
+mod cached_splits;
 mod change;
 mod cluster;
 mod member;
 mod node;
+mod protocol;
+mod topology;
 
 use std::collections::HashSet;
 
@@ -34,12 +37,20 @@ use quickwit_config::service::QuickwitService;
 use quickwit_config::QuickwitConfig as NodeConfig;
 use time::OffsetDateTime;
 
+pub use crate::cached_splits::CachedSplitsDigest;
 pub use crate::change::ClusterChange;
 #[cfg(any(test, feature = "testsuite"))]
 pub use crate::cluster::{create_cluster_for_test, grpc_addr_from_listen_addr_for_test};
 pub use crate::cluster::{Cluster, ClusterSnapshot, NodeIdSchema};
 pub use crate::member::ClusterMember;
 pub use crate::node::ClusterNode;
+pub use crate::protocol::{
+    is_compatible_protocol_version, ProtocolFeature, LEGACY_PROTOCOL_VERSION,
+    MIN_COMPATIBLE_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+pub use crate::topology::{
+    ClusterTopology, NodeStatus, NodeTopology, PipelinePlacement, SplitCacheStats,
+};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct GenerationId(u64);
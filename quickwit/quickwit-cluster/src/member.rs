@@ -26,6 +26,8 @@ use itertools::Itertools;
 use quickwit_proto::indexing_api::IndexingTask;
 use tracing::warn;
 
+use crate::cached_splits::CachedSplitsDigest;
+use crate::protocol::{LEGACY_PROTOCOL_VERSION, PROTOCOL_VERSION};
 use crate::{GenerationId, QuickwitService};
 
 // Keys used to store member's data in chitchat state.
@@ -35,6 +37,11 @@ pub(crate) const ENABLED_SERVICES_KEY: &str = "enabled_services";
 // `{INDEXING_TASK_PREFIX}{INDEXING_TASK_SEPARATOR}{index_id}{INDEXING_TASK_SEPARATOR}{source_id}`.
 pub(crate) const INDEXING_TASK_PREFIX: &str = "indexing_task";
 pub(crate) const INDEXING_TASK_SEPARATOR: char = ':';
+pub(crate) const BUILD_VERSION_KEY: &str = "build_version";
+pub(crate) const SPLIT_CACHE_NUM_SPLITS_KEY: &str = "split_cache_num_splits";
+pub(crate) const SPLIT_CACHE_NUM_BYTES_KEY: &str = "split_cache_num_bytes";
+pub(crate) const PROTOCOL_VERSION_KEY: &str = "protocol_version";
+pub(crate) const CACHED_SPLITS_DIGEST_KEY: &str = "cached_splits_digest";
 
 // Readiness key and values used to store node's readiness in Chitchat state.
 pub(crate) const READINESS_KEY: &str = "readiness";
@@ -90,6 +97,11 @@ pub struct ClusterMember {
     /// pipelines.
     pub indexing_tasks: Vec<IndexingTask>,
     pub is_ready: bool,
+    /// Version of the protocol spoken by the node over gRPC.
+    pub protocol_version: u32,
+    /// Digest of the splits cached by the node. `None` if the node is not a searcher or has not
+    /// advertised its cached splits yet.
+    pub cached_splits_digest: Option<CachedSplitsDigest>,
 }
 
 impl ClusterMember {
@@ -110,6 +122,8 @@ impl ClusterMember {
             gossip_advertise_addr,
             grpc_advertise_addr,
             indexing_tasks,
+            protocol_version: PROTOCOL_VERSION,
+            cached_splits_digest: None,
         }
     }
 
@@ -148,7 +162,8 @@ pub(crate) fn build_cluster_member(
         })?;
     let grpc_advertise_addr = node_state.grpc_advertise_addr()?;
     let indexing_tasks = parse_indexing_tasks(node_state, &chitchat_id.node_id);
-    let member = ClusterMember::new(
+    let protocol_version = parse_protocol_version(node_state, &chitchat_id.node_id);
+    let mut member = ClusterMember::new(
         chitchat_id.node_id,
         chitchat_id.generation_id.into(),
         is_ready,
@@ -157,9 +172,46 @@ pub(crate) fn build_cluster_member(
         grpc_advertise_addr,
         indexing_tasks,
     );
+    member.protocol_version = protocol_version;
+    member.cached_splits_digest = parse_cached_splits_digest(node_state, &member.node_id);
     Ok(member)
 }
 
+/// Parses the protocol version advertised by the node. Nodes that do not advertise a protocol
+/// version predate version negotiation and speak the legacy protocol version.
+pub(crate) fn parse_protocol_version(node_state: &NodeState, node_id: &str) -> u32 {
+    let Some(protocol_version_str) = node_state.get(PROTOCOL_VERSION_KEY) else {
+        return LEGACY_PROTOCOL_VERSION;
+    };
+    match protocol_version_str.parse() {
+        Ok(protocol_version) => protocol_version,
+        Err(error) => {
+            warn!(
+                node_id=%node_id,
+                error=%error,
+                "Malformatted protocol version on node."
+            );
+            LEGACY_PROTOCOL_VERSION
+        }
+    }
+}
+
+/// Parses the digest of the splits cached by the node. A malformed digest is ignored.
+fn parse_cached_splits_digest(node_state: &NodeState, node_id: &str) -> Option<CachedSplitsDigest> {
+    let digest_base64 = node_state.get(CACHED_SPLITS_DIGEST_KEY)?;
+    match CachedSplitsDigest::from_base64(digest_base64) {
+        Ok(cached_splits_digest) => Some(cached_splits_digest),
+        Err(error) => {
+            warn!(
+                node_id=%node_id,
+                error=%error,
+                "Malformatted cached splits digest on node."
+            );
+            None
+        }
+    }
+}
+
 // Parses indexing task key into the IndexingTask.
 fn parse_indexing_task_key(key: &str) -> anyhow::Result<IndexingTask> {
     let (_prefix, reminder) = key.split_once(INDEXING_TASK_SEPARATOR).ok_or_else(|| {
@@ -27,6 +27,7 @@ use quickwit_config::service::QuickwitService;
 use quickwit_proto::indexing_api::IndexingTask;
 use tonic::transport::Channel;
 
+use crate::cached_splits::CachedSplitsDigest;
 use crate::member::build_cluster_member;
 
 #[derive(Clone)]
@@ -51,6 +52,8 @@ impl ClusterNode {
             indexing_tasks: member.indexing_tasks,
             is_ready: member.is_ready,
             is_self_node,
+            protocol_version: member.protocol_version,
+            cached_splits_digest_opt: member.cached_splits_digest.map(Arc::new),
         };
         let node = ClusterNode {
             inner: Arc::new(inner),
@@ -64,10 +67,28 @@ impl ClusterNode {
         port: u16,
         is_self_node: bool,
         enabled_services: &[&str],
+    ) -> Self {
+        Self::for_test_with_protocol_version(
+            node_id,
+            port,
+            is_self_node,
+            enabled_services,
+            crate::protocol::PROTOCOL_VERSION,
+        )
+        .await
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub async fn for_test_with_protocol_version(
+        node_id: &str,
+        port: u16,
+        is_self_node: bool,
+        enabled_services: &[&str],
+        protocol_version: u32,
     ) -> Self {
         use quickwit_common::tower::make_channel;
 
-        use crate::member::{ENABLED_SERVICES_KEY, GRPC_ADVERTISE_ADDR_KEY};
+        use crate::member::{ENABLED_SERVICES_KEY, GRPC_ADVERTISE_ADDR_KEY, PROTOCOL_VERSION_KEY};
 
         let gossip_advertise_addr = ([127, 0, 0, 1], port).into();
         let grpc_advertise_addr = ([127, 0, 0, 1], port + 1).into();
@@ -76,6 +97,7 @@ impl ClusterNode {
         let mut node_state = NodeState::default();
         node_state.set(ENABLED_SERVICES_KEY, enabled_services.join(","));
         node_state.set(GRPC_ADVERTISE_ADDR_KEY, grpc_advertise_addr.to_string());
+        node_state.set(PROTOCOL_VERSION_KEY, protocol_version.to_string());
         Self::try_new(chitchat_id, &node_state, channel, is_self_node).unwrap()
     }
 
@@ -110,6 +132,15 @@ impl ClusterNode {
     pub fn is_self_node(&self) -> bool {
         self.inner.is_self_node
     }
+
+    pub fn protocol_version(&self) -> u32 {
+        self.inner.protocol_version
+    }
+
+    /// Returns the digest of the splits cached by the node, if it advertises one.
+    pub fn cached_splits_digest(&self) -> Option<Arc<CachedSplitsDigest>> {
+        self.inner.cached_splits_digest_opt.clone()
+    }
 }
 
 impl Debug for ClusterNode {
@@ -131,6 +162,8 @@ impl PartialEq for ClusterNode {
             && self.inner.indexing_tasks == other.inner.indexing_tasks
             && self.inner.is_ready == other.inner.is_ready
             && self.inner.is_self_node == other.inner.is_self_node
+            && self.inner.protocol_version == other.inner.protocol_version
+            && self.inner.cached_splits_digest_opt == other.inner.cached_splits_digest_opt
     }
 }
 
@@ -142,4 +175,6 @@ struct InnerNode {
     indexing_tasks: Vec<IndexingTask>,
     is_ready: bool,
     is_self_node: bool,
+    protocol_version: u32,
+    cached_splits_digest_opt: Option<Arc<CachedSplitsDigest>>,
 }
@@ -0,0 +1,149 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Version of the protocol spoken by the nodes of a cluster over gRPC.
+//!
+//! Each node advertises the protocol version it speaks in its Chitchat state. During a rolling
+//! upgrade, nodes running different versions of Quickwit coexist in the same cluster: the
+//! version lets a node stop sending requests to the peers it cannot exchange requests with and
+//! avoid sending requests relying on features its peers do not support yet.
+
+/// Version of the protocol spoken by this node. It must be bumped whenever a request sent by
+/// this node may not be understood by the nodes running the previous version, and the change
+/// recorded in [`ProtocolFeature`].
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Protocol version assumed for the nodes that do not advertise one, i.e. the nodes started before
+/// version negotiation was introduced.
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest protocol version this node can exchange requests with. Nodes running an older version
+/// are excluded from the searcher and indexer pools.
+pub const MIN_COMPATIBLE_PROTOCOL_VERSION: u32 = 1;
+
+/// Returns whether this node can exchange requests with a node speaking `protocol_version`.
+pub fn is_compatible_protocol_version(protocol_version: u32) -> bool {
+    protocol_version >= MIN_COMPATIBLE_PROTOCOL_VERSION
+}
+
+/// Feature of the protocol that is not understood by all the compatible protocol versions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ProtocolFeature {
+    /// Regex queries in the query AST of search requests.
+    RegexQuery,
+    /// Field presence queries in the query AST of search requests.
+    FieldPresenceQuery,
+    /// Disjunction max queries in the query AST of search requests.
+    DisMaxQuery,
+    /// Boosting queries in the query AST of search requests.
+    BoostingQuery,
+    /// Combined fields queries in the query AST of search requests.
+    CombinedFieldsQuery,
+    /// Intervals queries in the query AST of search requests.
+    IntervalsQuery,
+    /// Span queries in the query AST of search requests.
+    SpanQuery,
+    /// k-nearest neighbors queries in the query AST of search requests.
+    KnnQuery,
+    /// Simple query string queries in the query AST of search requests.
+    SimpleUserInputQuery,
+    /// Constant score queries in the query AST of search requests.
+    ConstScoreQuery,
+    /// UDF queries in the query AST of search requests.
+    UdfQuery,
+    /// Function score queries in the query AST of search requests.
+    FunctionScoreQuery,
+}
+
+impl ProtocolFeature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolFeature::RegexQuery => "regex query",
+            ProtocolFeature::FieldPresenceQuery => "field presence query",
+            ProtocolFeature::DisMaxQuery => "dis max query",
+            ProtocolFeature::BoostingQuery => "boosting query",
+            ProtocolFeature::CombinedFieldsQuery => "combined fields query",
+            ProtocolFeature::IntervalsQuery => "intervals query",
+            ProtocolFeature::SpanQuery => "span query",
+            ProtocolFeature::KnnQuery => "knn query",
+            ProtocolFeature::SimpleUserInputQuery => "simple query string query",
+            ProtocolFeature::ConstScoreQuery => "constant score query",
+            ProtocolFeature::UdfQuery => "udf query",
+            ProtocolFeature::FunctionScoreQuery => "function score query",
+        }
+    }
+
+    /// Returns the protocol version that introduced the feature.
+    pub fn min_protocol_version(&self) -> u32 {
+        match self {
+            ProtocolFeature::RegexQuery
+            | ProtocolFeature::FieldPresenceQuery
+            | ProtocolFeature::DisMaxQuery
+            | ProtocolFeature::BoostingQuery
+            | ProtocolFeature::CombinedFieldsQuery
+            | ProtocolFeature::IntervalsQuery
+            | ProtocolFeature::SpanQuery
+            | ProtocolFeature::KnnQuery
+            | ProtocolFeature::SimpleUserInputQuery
+            | ProtocolFeature::ConstScoreQuery
+            | ProtocolFeature::UdfQuery
+            | ProtocolFeature::FunctionScoreQuery => 2,
+        }
+    }
+
+    /// Returns whether a node speaking `protocol_version` supports the feature.
+    pub fn is_supported_by(&self, protocol_version: u32) -> bool {
+        protocol_version >= self.min_protocol_version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_version_compatibility() {
+        assert!(is_compatible_protocol_version(LEGACY_PROTOCOL_VERSION));
+        assert!(is_compatible_protocol_version(PROTOCOL_VERSION));
+        assert!(is_compatible_protocol_version(PROTOCOL_VERSION + 1));
+        assert!(!is_compatible_protocol_version(0));
+    }
+
+    #[test]
+    fn test_protocol_feature_is_supported_by() {
+        for feature in [
+            ProtocolFeature::RegexQuery,
+            ProtocolFeature::FieldPresenceQuery,
+            ProtocolFeature::DisMaxQuery,
+            ProtocolFeature::BoostingQuery,
+            ProtocolFeature::CombinedFieldsQuery,
+            ProtocolFeature::IntervalsQuery,
+            ProtocolFeature::SpanQuery,
+            ProtocolFeature::KnnQuery,
+            ProtocolFeature::SimpleUserInputQuery,
+            ProtocolFeature::ConstScoreQuery,
+            ProtocolFeature::UdfQuery,
+            ProtocolFeature::FunctionScoreQuery,
+        ] {
+            assert!(feature.min_protocol_version() <= PROTOCOL_VERSION);
+            assert!(feature.is_supported_by(PROTOCOL_VERSION));
+            assert!(!feature.is_supported_by(LEGACY_PROTOCOL_VERSION));
+        }
+    }
+}
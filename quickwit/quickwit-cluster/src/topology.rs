@@ -0,0 +1,173 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+
+use chitchat::{ChitchatId, NodeState};
+use serde::{Deserialize, Serialize};
+
+use crate::member::{
+    build_cluster_member, BUILD_VERSION_KEY, SPLIT_CACHE_NUM_BYTES_KEY, SPLIT_CACHE_NUM_SPLITS_KEY,
+};
+
+/// Machine-readable view of the cluster: the nodes, their roles, and what runs on them.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ClusterTopology {
+    #[schema(example = "qw-cluster-1")]
+    /// The ID of the cluster that the node is a part of.
+    pub cluster_id: String,
+
+    #[schema(example = "node-1")]
+    /// The ID of the node that served the request.
+    pub self_node_id: String,
+
+    /// The nodes of the cluster, sorted by node ID.
+    pub nodes: Vec<NodeTopology>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    /// The node is alive and ready to handle requests.
+    Ready,
+    /// The node is alive but not ready.
+    Live,
+    /// The node is flagged as dead or faulty.
+    Dead,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct NodeTopology {
+    #[schema(example = "node-1")]
+    /// The unique identifier of the node in the cluster.
+    pub node_id: String,
+
+    #[schema(example = "1683736537")]
+    /// A numeric identifier incremented every time the node leaves and rejoins the cluster.
+    pub generation_id: u64,
+
+    /// Whether the node is ready, alive but not ready, or dead.
+    pub status: NodeStatus,
+
+    #[schema(example = json!(["indexer", "searcher"]))]
+    /// The services enabled on the node, sorted by name.
+    pub roles: Vec<String>,
+
+    #[schema(example = "127.0.0.1:7280", value_type = String)]
+    /// The socket address peers should use to gossip with the node.
+    pub gossip_advertise_addr: SocketAddr,
+
+    #[schema(example = "127.0.0.1:7281", value_type = String)]
+    /// The socket address peers should use to communicate with the node via gRPC.
+    pub grpc_advertise_addr: SocketAddr,
+
+    #[schema(example = "0.6.0")]
+    /// The version of Quickwit running on the node, if advertised.
+    pub version: Option<String>,
+
+    #[schema(example = 2)]
+    /// The version of the protocol spoken by the node over gRPC.
+    pub protocol_version: u32,
+
+    /// The indexing pipelines running on the node, sorted by index UID and source ID.
+    pub indexing_pipelines: Vec<PipelinePlacement>,
+
+    /// The usage of the split cache of the node. `None` if the node is not an indexer.
+    pub split_cache: Option<SplitCacheStats>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PipelinePlacement {
+    #[schema(example = "my-index:01H2RHKBQW6TZ9X2F1SH0CPEM3")]
+    pub index_uid: String,
+
+    #[schema(example = "_ingest-api-source")]
+    pub source_id: String,
+
+    /// The number of pipelines running on the node for this index and source.
+    pub num_pipelines: usize,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SplitCacheStats {
+    /// The number of splits in the cache.
+    pub num_splits: u64,
+
+    /// The total size of the splits in the cache.
+    pub num_bytes: u64,
+}
+
+/// Builds the topology of a node from its [`NodeState`].
+pub(crate) fn build_node_topology(
+    chitchat_id: ChitchatId,
+    node_state: &NodeState,
+    status: NodeStatus,
+) -> anyhow::Result<NodeTopology> {
+    let member = build_cluster_member(chitchat_id, node_state)?;
+    let mut roles: Vec<String> = member
+        .enabled_services
+        .iter()
+        .map(|service| service.as_str().to_string())
+        .collect();
+    roles.sort();
+
+    let mut num_pipelines_per_task: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for indexing_task in member.indexing_tasks {
+        *num_pipelines_per_task
+            .entry((indexing_task.index_uid, indexing_task.source_id))
+            .or_default() += 1;
+    }
+    let indexing_pipelines = num_pipelines_per_task
+        .into_iter()
+        .map(
+            |((index_uid, source_id), num_pipelines)| PipelinePlacement {
+                index_uid,
+                source_id,
+                num_pipelines,
+            },
+        )
+        .collect();
+
+    let version = node_state.get(BUILD_VERSION_KEY).map(ToString::to_string);
+    let split_cache = parse_split_cache_stats(node_state);
+
+    let node_topology = NodeTopology {
+        node_id: member.node_id,
+        generation_id: member.generation_id.as_u64(),
+        status,
+        roles,
+        gossip_advertise_addr: member.gossip_advertise_addr,
+        grpc_advertise_addr: member.grpc_advertise_addr,
+        version,
+        protocol_version: member.protocol_version,
+        indexing_pipelines,
+        split_cache,
+    };
+    Ok(node_topology)
+}
+
+fn parse_split_cache_stats(node_state: &NodeState) -> Option<SplitCacheStats> {
+    let num_splits = node_state.get(SPLIT_CACHE_NUM_SPLITS_KEY)?.parse().ok()?;
+    let num_bytes = node_state.get(SPLIT_CACHE_NUM_BYTES_KEY)?.parse().ok()?;
+    Some(SplitCacheStats {
+        num_splits,
+        num_bytes,
+    })
+}
@@ -17,6 +17,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
 use heck::ToSnakeCase;
 use proc_macro2::TokenStream;
 use prost_build::{Method, Service, ServiceGenerator};
@@ -54,9 +57,21 @@ impl Codegen {
             .service_generator(service_generator)
             .out_dir(out_dir);
 
+        // The file descriptor sets, used by the gRPC reflection service, are written to the
+        // build script output directory as `<proto file stem>_descriptor.bin`.
+        let descriptor_dir_path = PathBuf::from(std::env::var("OUT_DIR")?);
+
         for proto in protos {
             println!("cargo:rerun-if-changed={proto}");
-            prost_config.compile_protos(&[proto], includes)?;
+            let proto_file_stem = Path::new(proto)
+                .file_stem()
+                .and_then(|file_stem| file_stem.to_str())
+                .with_context(|| format!("Invalid proto file path `{proto}`."))?;
+            let descriptor_path =
+                descriptor_dir_path.join(format!("{proto_file_stem}_descriptor.bin"));
+            prost_config
+                .file_descriptor_set_path(descriptor_path)
+                .compile_protos(&[proto], includes)?;
         }
         Ok(())
     }
@@ -0,0 +1,150 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Fault injection for testing the resilience of distributed code paths.
+//!
+//! A [`FaultInjector`] draws the faults (latency, error, truncation) to inject in each call
+//! made through the storage or client it is plugged into. Faults are drawn from a seeded random
+//! number generator, so that a sequence of calls always gets the same sequence of faults.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Configures the faults injected by a [`FaultInjector`].
+///
+/// Rates are probabilities between 0 and 1 applying independently to each call.
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjectionConfig {
+    /// Latency added to the calls drawn for it.
+    pub latency: Duration,
+    /// Rate of calls delayed by `latency`.
+    pub latency_rate: f64,
+    /// Rate of calls failing with an error.
+    pub error_rate: f64,
+    /// Rate of calls returning a truncated response.
+    pub truncation_rate: f64,
+    /// Seed of the random number generator.
+    pub seed: u64,
+}
+
+/// Faults to inject in a call.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Fault {
+    pub latency_opt: Option<Duration>,
+    pub is_error: bool,
+    pub is_truncation: bool,
+}
+
+impl Fault {
+    /// Sleeps for the latency of the fault, if any.
+    pub async fn inject_latency(&self) {
+        if let Some(latency) = self.latency_opt {
+            tokio::time::sleep(latency).await;
+        }
+    }
+}
+
+/// Draws faults according to a [`FaultInjectionConfig`].
+pub struct FaultInjector {
+    config: FaultInjectionConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl FaultInjector {
+    pub fn new(config: FaultInjectionConfig) -> Self {
+        let rng = StdRng::seed_from_u64(config.seed);
+        Self {
+            config,
+            rng: Mutex::new(rng),
+        }
+    }
+
+    /// Draws the faults to inject in the next call.
+    ///
+    /// The draws are deterministic for a given seed as long as calls are made in the same order.
+    pub fn next_fault(&self) -> Fault {
+        let mut rng = self.rng.lock().unwrap();
+        // The three draws always happen, so that a rate does not shift the draws of the others.
+        let is_delayed = rng.gen_bool(self.config.latency_rate);
+        let is_error = rng.gen_bool(self.config.error_rate);
+        let is_truncation = rng.gen_bool(self.config.truncation_rate);
+        Fault {
+            latency_opt: is_delayed.then_some(self.config.latency),
+            is_error,
+            is_truncation,
+        }
+    }
+}
+
+/// Returns the length a collection of `len` items is truncated to when a truncation is injected.
+pub fn truncated_len(len: usize) -> usize {
+    len / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_injector_is_deterministic() {
+        let config = FaultInjectionConfig {
+            latency: Duration::from_millis(10),
+            latency_rate: 0.5,
+            error_rate: 0.5,
+            truncation_rate: 0.5,
+            seed: 42,
+        };
+        let fault_injector = FaultInjector::new(config.clone());
+        let faults: Vec<Fault> = (0..100).map(|_| fault_injector.next_fault()).collect();
+
+        let other_fault_injector = FaultInjector::new(config);
+        let other_faults: Vec<Fault> = (0..100)
+            .map(|_| other_fault_injector.next_fault())
+            .collect();
+        assert_eq!(faults, other_faults);
+
+        let num_errors = faults.iter().filter(|fault| fault.is_error).count();
+        assert!(num_errors > 0 && num_errors < 100);
+        assert!(faults
+            .iter()
+            .any(|fault| fault.latency_opt == Some(Duration::from_millis(10))));
+    }
+
+    #[test]
+    fn test_fault_injector_no_fault_by_default() {
+        let fault_injector = FaultInjector::new(FaultInjectionConfig::default());
+        for _ in 0..100 {
+            assert_eq!(fault_injector.next_fault(), Fault::default());
+        }
+    }
+
+    #[test]
+    fn test_fault_injector_always_fails() {
+        let fault_injector = FaultInjector::new(FaultInjectionConfig {
+            error_rate: 1.0,
+            ..Default::default()
+        });
+        for _ in 0..100 {
+            assert!(fault_injector.next_fault().is_error);
+        }
+    }
+}
@@ -23,6 +23,8 @@ mod checklist;
 mod coolid;
 
 pub mod binary_heap;
+#[cfg(any(test, feature = "testsuite"))]
+pub mod fault_injection;
 mod file_entry;
 pub mod fs;
 pub mod io;
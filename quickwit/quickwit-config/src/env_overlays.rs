@@ -0,0 +1,292 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Environment variable overlays for configuration files.
+//!
+//! An overlay sets the value of a single configuration key from an environment variable whose
+//! name is the path of the key, prefixed and joined with double underscores. For instance,
+//! `QW__INDEXER__SPLIT_STORE_MAX_NUM_SPLITS=500` overrides `indexer.split_store_max_num_splits`
+//! in the node configuration file. Missing sections are created on the fly.
+//!
+//! Values are parsed as YAML scalars or flow sequences (`true`, `42`, `[a, b]`, ...). When the
+//! overridden key already holds a string, the value is kept verbatim; when it holds a list, a
+//! plain value is split on commas. Quoting a value forces it to be read as a string.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+use serde::de::DeserializeOwned;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::ConfigFormat;
+
+/// Prefix of the environment variables overriding node configuration keys.
+pub const NODE_CONFIG_ENV_OVERLAY_PREFIX: &str = "QW__";
+
+/// Prefix of the environment variables overriding index configuration keys.
+pub const INDEX_CONFIG_ENV_OVERLAY_PREFIX: &str = "QW_INDEX__";
+
+const KEY_PATH_SEPARATOR: &str = "__";
+
+/// Returns whether `env_vars` contains at least one overlay for the given prefix.
+pub fn has_env_overlays(prefix: &str, env_vars: &HashMap<String, String>) -> bool {
+    env_vars
+        .keys()
+        .any(|env_var_key| env_var_key.starts_with(prefix))
+}
+
+/// Parses a configuration file and applies the environment variable overlays matching `prefix`
+/// before deserializing it into `T`. Falls back to [`ConfigFormat::parse`] when no overlay is
+/// set.
+pub fn parse_with_env_overlays<T>(
+    config_format: ConfigFormat,
+    payload: &[u8],
+    prefix: &str,
+    env_vars: &HashMap<String, String>,
+) -> anyhow::Result<T>
+where T: DeserializeOwned {
+    if !has_env_overlays(prefix, env_vars) {
+        return config_format.parse(payload);
+    }
+    let mut config_value: JsonValue = config_format.parse(payload)?;
+    // YAML and TOML files may express the version as a number (`version: 0.6`).
+    if let Some(version_value) = config_value.get_mut("version") {
+        if version_value.is_number() {
+            *version_value = JsonValue::String(version_value.to_string());
+        }
+    }
+    apply_env_overlays(&mut config_value, prefix, env_vars)?;
+    serde_json::from_value(config_value)
+        .context("Failed to read configuration after applying environment variable overlays.")
+}
+
+/// Applies the environment variable overlays matching `prefix` to `config_value`.
+///
+/// Overlays are applied in the lexicographic order of the environment variable names, so an
+/// overlay replacing a whole section is always applied before the overlays of its keys.
+pub fn apply_env_overlays(
+    config_value: &mut JsonValue,
+    prefix: &str,
+    env_vars: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    let mut overlays: Vec<(&String, &String)> = env_vars
+        .iter()
+        .filter(|(env_var_key, _)| env_var_key.starts_with(prefix))
+        .collect();
+    overlays.sort_unstable();
+
+    for (env_var_key, env_var_value) in overlays {
+        let key_path = parse_key_path(&env_var_key[prefix.len()..])
+            .with_context(|| format!("Invalid configuration overlay `{env_var_key}`."))?;
+        apply_env_overlay(config_value, &key_path, env_var_value)
+            .with_context(|| format!("Failed to apply configuration overlay `{env_var_key}`."))?;
+    }
+    Ok(())
+}
+
+fn parse_key_path(key_path_str: &str) -> anyhow::Result<Vec<String>> {
+    let key_path: Vec<String> = key_path_str
+        .split(KEY_PATH_SEPARATOR)
+        .map(|key| key.to_lowercase())
+        .collect();
+    if key_path.iter().any(|key| key.is_empty()) {
+        bail!("Configuration keys must not be empty.");
+    }
+    Ok(key_path)
+}
+
+fn apply_env_overlay(
+    config_value: &mut JsonValue,
+    key_path: &[String],
+    env_var_value: &str,
+) -> anyhow::Result<()> {
+    let (leaf_key, section_keys) = key_path
+        .split_last()
+        .expect("The key path should not be empty.");
+    let mut section = config_value;
+
+    for section_key in section_keys {
+        let JsonValue::Object(section_map) = section else {
+            bail!("Parent of section `{section_key}` is not a section.");
+        };
+        section = section_map
+            .entry(section_key.clone())
+            .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+        if section.is_null() {
+            *section = JsonValue::Object(JsonMap::new());
+        }
+    }
+    let JsonValue::Object(section_map) = section else {
+        bail!("Parent of key `{leaf_key}` is not a section.");
+    };
+    let new_value = coerce_env_var_value(env_var_value, section_map.get(leaf_key));
+    section_map.insert(leaf_key.clone(), new_value);
+    Ok(())
+}
+
+/// Infers the type of an overlay value, taking into account the type of the value it replaces.
+fn coerce_env_var_value(env_var_value: &str, current_value_opt: Option<&JsonValue>) -> JsonValue {
+    let inferred_value: JsonValue = match serde_yaml::from_str(env_var_value) {
+        Ok(JsonValue::Null) | Err(_) => JsonValue::String(env_var_value.to_string()),
+        Ok(inferred_value) => inferred_value,
+    };
+    match (current_value_opt, inferred_value) {
+        (Some(JsonValue::String(_)), inferred_value @ JsonValue::String(_)) => inferred_value,
+        (Some(JsonValue::String(_)), _) => JsonValue::String(env_var_value.to_string()),
+        (Some(JsonValue::Array(_)), JsonValue::String(list_str)) => list_str
+            .split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(|item| coerce_env_var_value(item, None))
+            .collect(),
+        (_, inferred_value) => inferred_value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn to_env_vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_env_overlays() {
+        let mut config_value = json!({
+            "version": "0.6",
+            "node_id": "node-1",
+            "peer_seeds": ["seed-1"],
+            "indexer": {
+                "split_store_max_num_splits": 100,
+                "enable_otlp_endpoint": true,
+            },
+        });
+        let env_vars = to_env_vars(&[
+            ("QW__NODE_ID", "123"),
+            ("QW__PEER_SEEDS", "seed-2, seed-3"),
+            ("QW__INDEXER__SPLIT_STORE_MAX_NUM_SPLITS", "500"),
+            ("QW__INDEXER__ENABLE_OTLP_ENDPOINT", "false"),
+            ("QW__SEARCHER__FAST_FIELD_CACHE_CAPACITY", "4G"),
+            ("QW__SEARCHER__MAX_NUM_CONCURRENT_SPLIT_SEARCHES", "\"8\""),
+            ("QW_NODE_ID", "ignored"),
+        ]);
+        apply_env_overlays(&mut config_value, NODE_CONFIG_ENV_OVERLAY_PREFIX, &env_vars).unwrap();
+        assert_eq!(
+            config_value,
+            json!({
+                "version": "0.6",
+                "node_id": "123",
+                "peer_seeds": ["seed-2", "seed-3"],
+                "indexer": {
+                    "split_store_max_num_splits": 500,
+                    "enable_otlp_endpoint": false,
+                },
+                "searcher": {
+                    "fast_field_cache_capacity": "4G",
+                    "max_num_concurrent_split_searches": "8",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_env_overlays_section_then_keys() {
+        let mut config_value = json!({"version": "0.6"});
+        let env_vars = to_env_vars(&[
+            (
+                "QW__INDEXER",
+                "{split_store_max_num_splits: 10, split_store_max_num_bytes: 1G}",
+            ),
+            ("QW__INDEXER__SPLIT_STORE_MAX_NUM_SPLITS", "20"),
+        ]);
+        apply_env_overlays(&mut config_value, NODE_CONFIG_ENV_OVERLAY_PREFIX, &env_vars).unwrap();
+        assert_eq!(
+            config_value,
+            json!({
+                "version": "0.6",
+                "indexer": {
+                    "split_store_max_num_splits": 20,
+                    "split_store_max_num_bytes": "1G",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_env_overlays_errors() {
+        let mut config_value = json!({"version": "0.6", "node_id": "node-1"});
+        let env_vars = to_env_vars(&[("QW__NODE_ID__FOO", "bar")]);
+        let error =
+            apply_env_overlays(&mut config_value, NODE_CONFIG_ENV_OVERLAY_PREFIX, &env_vars)
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to apply configuration overlay `QW__NODE_ID__FOO`."
+        );
+
+        let env_vars = to_env_vars(&[("QW__INDEXER____FOO", "bar")]);
+        let error =
+            apply_env_overlays(&mut config_value, NODE_CONFIG_ENV_OVERLAY_PREFIX, &env_vars)
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration overlay `QW__INDEXER____FOO`."
+        );
+    }
+
+    #[test]
+    fn test_parse_with_env_overlays() {
+        let config_yaml = r#"
+            version: 0.6
+            index_id: hdfs-logs
+            doc_mapping:
+              field_mappings:
+                - name: body
+                  type: text
+            indexing_settings:
+              commit_timeout_secs: 60
+        "#;
+        let env_vars = to_env_vars(&[
+            ("QW_INDEX__INDEX_URI", "s3://quickwit-indexes/hdfs-logs"),
+            ("QW_INDEX__INDEXING_SETTINGS__COMMIT_TIMEOUT_SECS", "10"),
+        ]);
+        let config_value: JsonValue = parse_with_env_overlays(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            INDEX_CONFIG_ENV_OVERLAY_PREFIX,
+            &env_vars,
+        )
+        .unwrap();
+        assert_eq!(config_value["version"], json!("0.6"));
+        assert_eq!(
+            config_value["index_uri"],
+            json!("s3://quickwit-indexes/hdfs-logs")
+        );
+        assert_eq!(
+            config_value["indexing_settings"]["commit_timeout_secs"],
+            json!(10)
+        );
+    }
+}
@@ -19,8 +19,9 @@
 
 pub(crate) mod serialize;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::num::NonZeroU32;
+use std::ops::Bound;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -32,15 +33,19 @@ use cron::Schedule;
 use humantime::parse_duration;
 use quickwit_common::uri::Uri;
 use quickwit_doc_mapper::{
-    DefaultDocMapper, DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, ModeType,
-    QuickwitJsonOptions,
+    check_field_mappings_compatibility, DefaultDocMapper, DefaultDocMapperBuilder, DocIdConfig,
+    DocMapper, FieldMappingEntry, MappingChange, MappingChangeImpact, MappingCompatibilityReport,
+    ModeType, QuickwitJsonOptions,
 };
+use quickwit_query::query_ast::{QueryAst, RangeQuery, SynonymDictionary};
+use quickwit_query::JsonLiteral;
 use serde::{Deserialize, Serialize};
 pub use serialize::load_index_config_from_user_config;
 
 use crate::index_config::serialize::VersionedIndexConfig;
+use crate::ingest_processor_config::IngestProcessorConfig;
 use crate::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
-use crate::TestableForRegression;
+use crate::{validate_identifier, LookupTable, SearchTemplate, TestableForRegression};
 
 // Note(fmassot): `DocMapping` is a struct only used for
 // serialization/deserialization of `DocMapper` parameters.
@@ -64,8 +69,17 @@ pub struct DocMapping {
     pub tag_fields: BTreeSet<String>,
     #[serde(default)]
     pub store_source: bool,
+    /// Indexes the paths of the fields present in each document, which enables `exists` queries.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub index_field_presence: bool,
     #[serde(default)]
     pub timestamp_field: Option<String>,
+    /// Defines the field holding the unique ID of the documents, which can then be retrieved by
+    /// ID.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_id: Option<DocIdConfig>,
     #[serde(default)]
     pub mode: ModeType,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -78,6 +92,127 @@ pub struct DocMapping {
     pub max_num_partitions: NonZeroU32,
 }
 
+impl DocMapping {
+    /// Lists the differences between this doc mapping and `new_doc_mapping`, and classifies them
+    /// according to their impact on the splits already indexed with this doc mapping.
+    pub fn check_compatibility(&self, new_doc_mapping: &DocMapping) -> MappingCompatibilityReport {
+        let mut report = check_field_mappings_compatibility(
+            &self.field_mappings,
+            &new_doc_mapping.field_mappings,
+        );
+        if self.timestamp_field != new_doc_mapping.timestamp_field {
+            report.changes.push(MappingChange::new(
+                "timestamp_field",
+                format!(
+                    "Timestamp field changed from `{}` to `{}`. Time range pruning of existing \
+                     splits would be incorrect.",
+                    self.timestamp_field.as_deref().unwrap_or_default(),
+                    new_doc_mapping
+                        .timestamp_field
+                        .as_deref()
+                        .unwrap_or_default()
+                ),
+                MappingChangeImpact::RequiresReindex,
+            ));
+        }
+        if self.doc_id != new_doc_mapping.doc_id {
+            let doc_id_field = |doc_mapping: &DocMapping| {
+                doc_mapping
+                    .doc_id
+                    .as_ref()
+                    .map(|doc_id_config| doc_id_config.field.clone())
+            };
+            let (description, impact) = match (doc_id_field(self), doc_id_field(new_doc_mapping)) {
+                (Some(field), Some(new_field)) if field == new_field => (
+                    "Doc ID generation changed. Only new documents will be affected.".to_string(),
+                    MappingChangeImpact::NonBreaking,
+                ),
+                (None, Some(_)) => (
+                    "Doc ID field added. Only new documents can be retrieved by ID.".to_string(),
+                    MappingChangeImpact::NonBreaking,
+                ),
+                (field_opt, new_field_opt) => (
+                    format!(
+                        "Doc ID field changed from `{}` to `{}`. Existing documents can no \
+                         longer be retrieved by ID.",
+                        field_opt.unwrap_or_default(),
+                        new_field_opt.unwrap_or_default()
+                    ),
+                    MappingChangeImpact::Breaking,
+                ),
+            };
+            report
+                .changes
+                .push(MappingChange::new("doc_id", description, impact));
+        }
+        if self.tag_fields != new_doc_mapping.tag_fields {
+            report.changes.push(MappingChange::new(
+                "tag_fields",
+                "Tag fields changed. Only new splits will be pruned using the new tag fields.",
+                MappingChangeImpact::NonBreaking,
+            ));
+        }
+        if self.partition_key != new_doc_mapping.partition_key {
+            report.changes.push(MappingChange::new(
+                "partition_key",
+                "Partition key changed. Only new splits will be partitioned using the new key.",
+                MappingChangeImpact::NonBreaking,
+            ));
+        }
+        if self.mode != new_doc_mapping.mode {
+            let impact = if self.mode == ModeType::Dynamic {
+                MappingChangeImpact::Breaking
+            } else {
+                MappingChangeImpact::NonBreaking
+            };
+            let mode_name = |mode: ModeType| format!("{mode:?}").to_lowercase();
+            report.changes.push(MappingChange::new(
+                "mode",
+                format!(
+                    "Mode changed from `{}` to `{}`.",
+                    mode_name(self.mode),
+                    mode_name(new_doc_mapping.mode)
+                ),
+                impact,
+            ));
+        }
+        if self.store_source != new_doc_mapping.store_source {
+            let (description, impact) = if new_doc_mapping.store_source {
+                (
+                    "Source is now stored. Existing documents will not return it.",
+                    MappingChangeImpact::NonBreaking,
+                )
+            } else {
+                ("Source is no longer stored.", MappingChangeImpact::Breaking)
+            };
+            report
+                .changes
+                .push(MappingChange::new("store_source", description, impact));
+        }
+        if self.index_field_presence != new_doc_mapping.index_field_presence {
+            let (description, impact) = if new_doc_mapping.index_field_presence {
+                (
+                    "Field presence is now indexed. Existing documents will not match `exists` \
+                     queries.",
+                    MappingChangeImpact::NonBreaking,
+                )
+            } else {
+                (
+                    "Field presence is no longer indexed. `exists` queries will fail on new \
+                     splits.",
+                    MappingChangeImpact::Breaking,
+                )
+            };
+            report.changes.push(MappingChange::new(
+                "index_field_presence",
+                description,
+                impact,
+            ));
+        }
+        report
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct IndexingResources {
@@ -142,10 +277,22 @@ pub struct IndexingSettings {
     /// `split_num_docs_target` are considered mature and never merged.
     #[serde(default = "IndexingSettings::default_split_num_docs_target")]
     pub split_num_docs_target: usize,
+    /// Optional target size of the documents indexed in a split. When set, the indexer commits
+    /// the split under construction as soon as the documents it contains weigh more than
+    /// `split_num_bytes_target`, even if `split_num_docs_target` or `commit_timeout_secs` have
+    /// not been reached yet.
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_num_bytes_target: Option<Byte>,
     #[serde(default)]
     pub merge_policy: MergePolicyConfig,
     #[serde(default)]
     pub resources: IndexingResources,
+    /// Processors applied, in order, to every document before it gets indexed.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub processors: Vec<IngestProcessorConfig>,
 }
 
 impl IndexingSettings {
@@ -169,6 +316,41 @@ impl IndexingSettings {
         10_000_000
     }
 
+    /// Checks that the commit policy (commit timeout, split targets) is consistent.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.commit_timeout_secs == 0 {
+            anyhow::bail!("Indexing settings `commit_timeout_secs` must be strictly positive.");
+        }
+        if self.split_num_docs_target == 0 {
+            anyhow::bail!("Indexing settings `split_num_docs_target` must be strictly positive.");
+        }
+        if self
+            .split_num_bytes_target
+            .map_or(false, |split_num_bytes_target| {
+                split_num_bytes_target.get_bytes() == 0
+            })
+        {
+            anyhow::bail!("Indexing settings `split_num_bytes_target` must be strictly positive.");
+        }
+        self.merge_policy.validate()?;
+        Ok(())
+    }
+
+    /// Returns a copy of these settings with the commit policy overrides of `update` applied.
+    pub fn with_update(&self, update: &IndexingSettingsUpdate) -> Self {
+        let mut indexing_settings = self.clone();
+        if let Some(commit_timeout_secs) = update.commit_timeout_secs {
+            indexing_settings.commit_timeout_secs = commit_timeout_secs;
+        }
+        if let Some(split_num_docs_target) = update.split_num_docs_target {
+            indexing_settings.split_num_docs_target = split_num_docs_target;
+        }
+        if let Some(split_num_bytes_target) = update.split_num_bytes_target {
+            indexing_settings.split_num_bytes_target = Some(split_num_bytes_target);
+        }
+        indexing_settings
+    }
+
     #[cfg(any(test, feature = "testsuite"))]
     pub fn for_test() -> Self {
         Self {
@@ -185,17 +367,166 @@ impl Default for IndexingSettings {
             docstore_blocksize: Self::default_docstore_blocksize(),
             docstore_compression_level: Self::default_docstore_compression_level(),
             split_num_docs_target: Self::default_split_num_docs_target(),
+            split_num_bytes_target: None,
             merge_policy: MergePolicyConfig::default(),
             resources: IndexingResources::default(),
+            processors: Vec::new(),
         }
     }
 }
 
+/// Partial update of the commit policy of an index, applied at runtime to the indexing settings
+/// of an existing index. Fields left unset keep their current value.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IndexingSettingsUpdate {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_timeout_secs: Option<usize>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_num_docs_target: Option<usize>,
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_num_bytes_target: Option<Byte>,
+}
+
+impl IndexingSettingsUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.commit_timeout_secs.is_none()
+            && self.split_num_docs_target.is_none()
+            && self.split_num_bytes_target.is_none()
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SearchSettings {
+    /// Fields searched when a query does not explicitly target a field. Each field can be given a
+    /// static boost with the `field_name^boost` syntax, e.g. `error.message^3`.
     #[serde(default)]
     pub default_search_fields: Vec<String>,
+    /// Sets of equivalent terms expanded at query time. Since the synonyms are not applied at
+    /// indexing time, they can be changed without reindexing.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub synonyms: Vec<SynonymSet>,
+    /// Turns query features on or off for the index, overriding their default state. Requests
+    /// using a disabled feature are rejected.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub feature_flags: BTreeMap<SearchFeature, bool>,
+}
+
+impl SearchSettings {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for synonym_set in &self.synonyms {
+            synonym_set.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether the feature is enabled for the index, either explicitly or by default.
+    pub fn is_feature_enabled(&self, feature: SearchFeature) -> bool {
+        self.feature_flags
+            .get(&feature)
+            .copied()
+            .unwrap_or_else(|| feature.is_enabled_by_default())
+    }
+
+    /// Returns the dictionary used to expand the synonyms of the queries targeting the index.
+    pub fn synonym_dictionary(&self) -> SynonymDictionary {
+        let mut synonym_dictionary = SynonymDictionary::default();
+        for synonym_set in &self.synonyms {
+            synonym_dictionary.add_synonym_set(&synonym_set.fields, &synonym_set.synonyms);
+        }
+        synonym_dictionary
+    }
+}
+
+/// Query features that can be turned on or off per index with
+/// [`SearchSettings::feature_flags`], so that risky features can be rolled out gradually.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchFeature {
+    /// Regex queries, which can be expensive on large term dictionaries. Enabled by default.
+    RegexQueries,
+    /// Searches over the documents that have not been published yet, with
+    /// `realtime_window_secs`. Disabled by default.
+    RealtimeSearch,
+    /// Approximate nearest neighbor queries on vector fields. Disabled by default.
+    KnnQueries,
+    /// Queries filtering or scoring documents with WebAssembly user-defined functions. Disabled
+    /// by default.
+    UdfQueries,
+}
+
+impl SearchFeature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchFeature::RegexQueries => "regex_queries",
+            SearchFeature::RealtimeSearch => "realtime_search",
+            SearchFeature::KnnQueries => "knn_queries",
+            SearchFeature::UdfQueries => "udf_queries",
+        }
+    }
+
+    /// Returns whether the feature is enabled on the indexes that do not set its flag.
+    pub fn is_enabled_by_default(&self) -> bool {
+        match self {
+            SearchFeature::RegexQueries => true,
+            SearchFeature::RealtimeSearch
+            | SearchFeature::KnnQueries
+            | SearchFeature::UdfQueries => false,
+        }
+    }
+}
+
+/// Terms that match the same documents when searched in one of the `fields`, e.g. `k8s` and
+/// `kubernetes`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SynonymSet {
+    pub fields: Vec<String>,
+    pub synonyms: Vec<String>,
+}
+
+impl SynonymSet {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.fields.is_empty() {
+            anyhow::bail!(
+                "Failed to validate synonyms `{}`. At least one field must be specified.",
+                self.synonyms.join(", ")
+            );
+        }
+        if self.synonyms.len() < 2 {
+            anyhow::bail!(
+                "Failed to validate synonyms `{}`. A synonym set must contain at least two terms.",
+                self.synonyms.join(", ")
+            );
+        }
+        if self
+            .synonyms
+            .iter()
+            .any(|synonym| synonym.trim().is_empty())
+        {
+            anyhow::bail!("Failed to validate synonyms. Synonyms must not be empty.");
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -265,6 +596,279 @@ impl RetentionPolicy {
     }
 }
 
+/// Document-level time-to-live policy: the documents whose TTL field holds a date in the past are
+/// deleted periodically, independently of the age of the split they belong to.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DocumentTtlPolicy {
+    /// Name of the fast datetime field holding the expiration date of the documents.
+    field: String,
+
+    /// Defines the frequency at which the expired documents are deleted, expressed in a
+    /// human-friendly way (`hourly`, `daily`, ...) or as a cron expression (`0 0 * * * *`,
+    /// `0 0 0 * * *`).
+    #[serde(default = "DocumentTtlPolicy::default_schedule")]
+    #[serde(rename = "schedule")]
+    evaluation_schedule: String,
+}
+
+impl DocumentTtlPolicy {
+    pub fn new(field: String, evaluation_schedule: String) -> Self {
+        Self {
+            field,
+            evaluation_schedule,
+        }
+    }
+
+    fn default_schedule() -> String {
+        "hourly".to_string()
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn evaluation_schedule(&self) -> anyhow::Result<Schedule> {
+        let evaluation_schedule = prepend_at_char(&self.evaluation_schedule);
+
+        Schedule::from_str(&evaluation_schedule).with_context(|| {
+            format!(
+                "Failed to parse document TTL evaluation schedule `{}`.",
+                self.evaluation_schedule
+            )
+        })
+    }
+
+    pub fn duration_until_next_evaluation(&self) -> anyhow::Result<Duration> {
+        let schedule = self.evaluation_schedule()?;
+        let future_date = schedule
+            .upcoming(Utc)
+            .next()
+            .expect("Failed to obtain next evaluation date.");
+        let duration = (future_date - Utc::now())
+            .to_std()
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+        Ok(duration)
+    }
+
+    /// Returns the query matching the documents expired at `timestamp_secs`.
+    pub fn expired_documents_query_ast(&self, timestamp_secs: i64) -> QueryAst {
+        RangeQuery {
+            field: self.field.clone(),
+            lower_bound: Bound::Unbounded,
+            upper_bound: Bound::Included(JsonLiteral::Number(timestamp_secs.into())),
+        }
+        .into()
+    }
+
+    fn validate(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<()> {
+        self.evaluation_schedule()?;
+        doc_mapper
+            .query(
+                doc_mapper.schema(),
+                &self.expired_documents_query_ast(0),
+                true,
+            )
+            .with_context(|| {
+                format!(
+                    "Failed to validate document TTL policy. The field `{}` must be an \
+                     existing fast datetime field.",
+                    self.field
+                )
+            })?;
+        Ok(())
+    }
+}
+
+/// Rollup policy: the documents of the index are periodically counted per time bucket of
+/// `interval` and per combination of values of the `group_by` fields, and the counts are written
+/// to the rollup index. The date histograms over time ranges already rolled up are then answered
+/// from the rollup index.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RollupPolicy {
+    /// ID of the index the rollup documents are written to. Its timestamp field and the
+    /// `group_by` fields must have the same names as in the rolled up index.
+    rollup_index_id: String,
+
+    /// Width of the time buckets, expressed in a human-friendly way (`1 minute`, `1 hour`, ...).
+    interval: String,
+
+    /// Names of the fast fields whose values the documents are grouped by. The documents missing
+    /// one of these fields are not counted.
+    #[serde(default)]
+    group_by: Vec<String>,
+
+    /// Name of the field of the rollup index holding the number of documents of each group.
+    #[serde(default = "RollupPolicy::default_count_field")]
+    count_field: String,
+
+    /// Time waited after the end of a time bucket before rolling it up, so that late documents
+    /// are counted, expressed in a human-friendly way (`5 minutes`, ...).
+    #[serde(default = "RollupPolicy::default_delay")]
+    delay: String,
+
+    /// Defines the frequency at which the rollup is updated, expressed in a human-friendly way
+    /// (`hourly`, `daily`, ...) or as a cron expression (`0 0 * * * *`, `0 0 0 * * *`).
+    #[serde(default = "RollupPolicy::default_schedule")]
+    #[serde(rename = "schedule")]
+    evaluation_schedule: String,
+}
+
+impl RollupPolicy {
+    pub fn new(rollup_index_id: String, interval: String, group_by: Vec<String>) -> Self {
+        Self {
+            rollup_index_id,
+            interval,
+            group_by,
+            count_field: Self::default_count_field(),
+            delay: Self::default_delay(),
+            evaluation_schedule: Self::default_schedule(),
+        }
+    }
+
+    fn default_count_field() -> String {
+        "count".to_string()
+    }
+
+    fn default_delay() -> String {
+        "5 minutes".to_string()
+    }
+
+    fn default_schedule() -> String {
+        "hourly".to_string()
+    }
+
+    pub fn rollup_index_id(&self) -> &str {
+        &self.rollup_index_id
+    }
+
+    pub fn group_by(&self) -> &[String] {
+        &self.group_by
+    }
+
+    pub fn count_field(&self) -> &str {
+        &self.count_field
+    }
+
+    /// Returns the width of the time buckets, in seconds.
+    pub fn interval_secs(&self) -> anyhow::Result<i64> {
+        let interval = parse_duration(&self.interval)
+            .with_context(|| format!("Failed to parse rollup interval `{}`.", self.interval))?;
+        if interval.as_secs() == 0 || interval.subsec_nanos() != 0 {
+            anyhow::bail!(
+                "Failed to validate rollup interval `{}`. The interval must be a whole number of \
+                 seconds.",
+                self.interval
+            );
+        }
+        Ok(interval.as_secs() as i64)
+    }
+
+    /// Returns the time waited after the end of a time bucket before rolling it up, in seconds.
+    pub fn delay_secs(&self) -> anyhow::Result<i64> {
+        let delay = parse_duration(&self.delay)
+            .with_context(|| format!("Failed to parse rollup delay `{}`.", self.delay))?;
+        Ok(delay.as_secs() as i64)
+    }
+
+    pub fn evaluation_schedule(&self) -> anyhow::Result<Schedule> {
+        let evaluation_schedule = prepend_at_char(&self.evaluation_schedule);
+
+        Schedule::from_str(&evaluation_schedule).with_context(|| {
+            format!(
+                "Failed to parse rollup evaluation schedule `{}`.",
+                self.evaluation_schedule
+            )
+        })
+    }
+
+    pub fn duration_until_next_evaluation(&self) -> anyhow::Result<Duration> {
+        let schedule = self.evaluation_schedule()?;
+        let future_date = schedule
+            .upcoming(Utc)
+            .next()
+            .expect("Failed to obtain next evaluation date.");
+        let duration = (future_date - Utc::now())
+            .to_std()
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+        Ok(duration)
+    }
+
+    fn validate(&self, index_id: &str, doc_mapper: &dyn DocMapper) -> anyhow::Result<()> {
+        validate_identifier("Rollup index ID", &self.rollup_index_id)?;
+        if self.rollup_index_id == index_id {
+            anyhow::bail!(
+                "Failed to validate rollup policy. The rollup index must differ from the rolled \
+                 up index `{index_id}`."
+            );
+        }
+        self.interval_secs()?;
+        self.delay_secs()?;
+        self.evaluation_schedule()?;
+
+        let Some(timestamp_field) = doc_mapper.timestamp_field_name() else {
+            anyhow::bail!(
+                "Failed to validate rollup policy. Rollups require a timestamp field, but the doc \
+                 mapping does not declare one."
+            );
+        };
+        let schema = doc_mapper.schema();
+        for field_name in &self.group_by {
+            let is_fast_field = schema
+                .get_field(field_name)
+                .map_or(false, |field| schema.get_field_entry(field).is_fast());
+            if !is_fast_field {
+                anyhow::bail!(
+                    "Failed to validate rollup policy. The `group_by` field `{field_name}` must \
+                     be an existing fast field."
+                );
+            }
+        }
+        if self.count_field.is_empty()
+            || self.count_field == timestamp_field
+            || self.group_by.contains(&self.count_field)
+        {
+            anyhow::bail!(
+                "Failed to validate rollup policy. The count field `{}` must not be empty nor \
+                 be the timestamp field or a `group_by` field.",
+                self.count_field
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Split resharding policy: the mature splits holding documents with several values for the
+/// tag field `field` are rewritten into one split per value, e.g. to isolate the historical data
+/// of each tenant of a shared index.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReshardingPolicy {
+    /// Name of the tag field whose values are used to partition the splits.
+    field: String,
+}
+
+impl ReshardingPolicy {
+    pub fn new(field: String) -> Self {
+        Self { field }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    fn validate(&self, doc_mapping: &DocMapping) -> anyhow::Result<()> {
+        if !doc_mapping.tag_fields.contains(&self.field) {
+            anyhow::bail!(
+                "Failed to validate resharding policy. The field `{}` must be a tag field.",
+                self.field
+            );
+        }
+        Ok(())
+    }
+}
+
 /// Prepends an `@` char at the start of the cron expression if necessary:
 /// `hourly` -> `@hourly`
 fn prepend_at_char(schedule: &str) -> String {
@@ -290,6 +894,16 @@ pub struct IndexConfig {
     pub indexing_settings: IndexingSettings,
     pub search_settings: SearchSettings,
     pub retention_policy: Option<RetentionPolicy>,
+    pub document_ttl_policy: Option<DocumentTtlPolicy>,
+    pub resharding_policy: Option<ReshardingPolicy>,
+    /// Policies aggregating the index into coarser rollup indexes.
+    pub rollup_policies: Vec<RollupPolicy>,
+    /// Arbitrary key/value pairs used to organize indexes (e.g. `team=payments`).
+    pub labels: BTreeMap<String, String>,
+    /// Static dictionaries used to enrich search hits and aggregation buckets, keyed by name.
+    pub lookup_tables: BTreeMap<String, LookupTable>,
+    /// Parameterized search request bodies, keyed by name.
+    pub search_templates: BTreeMap<String, SearchTemplate>,
 }
 
 impl IndexConfig {
@@ -367,6 +981,7 @@ impl IndexConfig {
                 r#"attributes.server"#.to_string(),
                 r#"attributes.server\.status"#.to_string(),
             ],
+            ..Default::default()
         };
         IndexConfig {
             index_id: index_id.to_string(),
@@ -375,6 +990,12 @@ impl IndexConfig {
             indexing_settings,
             search_settings,
             retention_policy: Default::default(),
+            document_ttl_policy: None,
+            resharding_policy: None,
+            rollup_policies: Vec::new(),
+            labels: BTreeMap::new(),
+            lookup_tables: BTreeMap::new(),
+            search_templates: BTreeMap::new(),
         }
     }
 }
@@ -426,11 +1047,13 @@ impl TestableForRegression for IndexConfig {
                 .map(|tag_field| tag_field.to_string())
                 .collect::<BTreeSet<String>>(),
             store_source: true,
+            index_field_presence: false,
             mode: ModeType::Dynamic,
             dynamic_mapping: None,
             partition_key: Some("tenant_id".to_string()),
             max_num_partitions: NonZeroU32::new(100).unwrap(),
             timestamp_field: Some("timestamp".to_string()),
+            doc_id: None,
         };
         let retention_policy = Some(RetentionPolicy::new(
             "90 days".to_string(),
@@ -455,6 +1078,7 @@ impl TestableForRegression for IndexConfig {
         };
         let search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string()],
+            ..Default::default()
         };
         IndexConfig {
             index_id: "my-index".to_string(),
@@ -462,7 +1086,13 @@ impl TestableForRegression for IndexConfig {
             doc_mapping,
             indexing_settings,
             retention_policy,
+            document_ttl_policy: None,
+            resharding_policy: None,
+            rollup_policies: Vec::new(),
             search_settings,
+            labels: BTreeMap::new(),
+            lookup_tables: BTreeMap::new(),
+            search_templates: BTreeMap::new(),
         }
     }
 
@@ -489,6 +1119,10 @@ impl TestableForRegression for IndexConfig {
         );
         assert_eq!(self.indexing_settings, other.indexing_settings);
         assert_eq!(self.search_settings, other.search_settings);
+        assert_eq!(self.rollup_policies, other.rollup_policies);
+        assert_eq!(self.labels, other.labels);
+        assert_eq!(self.lookup_tables, other.lookup_tables);
+        assert_eq!(self.search_templates, other.search_templates);
     }
 }
 
@@ -499,10 +1133,12 @@ pub fn build_doc_mapper(
 ) -> anyhow::Result<Arc<dyn DocMapper>> {
     let builder = DefaultDocMapperBuilder {
         store_source: doc_mapping.store_source,
+        index_field_presence: doc_mapping.index_field_presence,
         default_search_fields: search_settings.default_search_fields.clone(),
         timestamp_field: doc_mapping.timestamp_field.clone(),
         field_mappings: doc_mapping.field_mappings.clone(),
         tag_fields: doc_mapping.tag_fields.iter().cloned().collect(),
+        doc_id: doc_mapping.doc_id.clone(),
         mode: doc_mapping.mode,
         dynamic_mapping: doc_mapping.dynamic_mapping.clone(),
         partition_key: doc_mapping.partition_key.clone(),
@@ -592,8 +1228,16 @@ mod tests {
             index_config.search_settings,
             SearchSettings {
                 default_search_fields: vec!["severity_text".to_string(), "body".to_string()],
+                ..Default::default()
             }
         );
+        assert_eq!(
+            index_config.labels,
+            BTreeMap::from([
+                ("env".to_string(), "prod".to_string()),
+                ("team".to_string(), "storage".to_string()),
+            ])
+        );
     }
 
     #[test]
@@ -634,6 +1278,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    ..Default::default()
                 }
             );
         }
@@ -668,6 +1313,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    ..Default::default()
                 }
             );
         }
@@ -862,6 +1508,93 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_doc_mapping_check_compatibility() {
+        let current_doc_mapping: DocMapping = serde_yaml::from_str(
+            r#"
+            field_mappings:
+              - name: timestamp
+                type: datetime
+                fast: true
+              - name: body
+                type: text
+            timestamp_field: timestamp
+            tag_fields: []
+            mode: dynamic
+            "#,
+        )
+        .unwrap();
+        let report = current_doc_mapping.check_compatibility(&current_doc_mapping);
+        assert!(report.changes.is_empty());
+
+        let new_doc_mapping: DocMapping = serde_yaml::from_str(
+            r#"
+            field_mappings:
+              - name: timestamp
+                type: datetime
+                fast: true
+              - name: body
+                type: text
+              - name: severity
+                type: text
+                tokenizer: raw
+            timestamp_field: timestamp
+            tag_fields: [severity]
+            mode: lenient
+            "#,
+        )
+        .unwrap();
+        let report = current_doc_mapping.check_compatibility(&new_doc_mapping);
+        let changes: Vec<(&str, MappingChangeImpact)> = report
+            .changes
+            .iter()
+            .map(|change| (change.path.as_str(), change.impact))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("severity", MappingChangeImpact::NonBreaking),
+                ("tag_fields", MappingChangeImpact::NonBreaking),
+                ("mode", MappingChangeImpact::Breaking),
+            ]
+        );
+        assert_eq!(
+            report.changes[2].description,
+            "Mode changed from `dynamic` to `lenient`."
+        );
+        assert!(!report.is_compatible());
+        assert!(!report.requires_reindex());
+
+        let new_doc_mapping: DocMapping = serde_yaml::from_str(
+            r#"
+            field_mappings:
+              - name: ts
+                type: datetime
+                fast: true
+              - name: body
+                type: text
+            timestamp_field: ts
+            mode: dynamic
+            "#,
+        )
+        .unwrap();
+        let report = current_doc_mapping.check_compatibility(&new_doc_mapping);
+        assert!(report.requires_reindex());
+        assert_eq!(report.changes.last().unwrap().path, "timestamp_field");
+
+        let mut new_doc_mapping = current_doc_mapping.clone();
+        new_doc_mapping.doc_id = Some(DocIdConfig {
+            field: "id".to_string(),
+            generation: Default::default(),
+        });
+        let report = current_doc_mapping.check_compatibility(&new_doc_mapping);
+        assert_eq!(report.changes[0].path, "doc_id");
+        assert_eq!(report.changes[0].impact, MappingChangeImpact::NonBreaking);
+
+        let report = new_doc_mapping.check_compatibility(&current_doc_mapping);
+        assert_eq!(report.changes[0].impact, MappingChangeImpact::Breaking);
+    }
+
     #[test]
     fn test_retention_schedule_duration() {
         let schedule_test_helper_fn = |schedule_str: &str| {
@@ -888,4 +1621,165 @@ mod tests {
         schedule_test_helper_fn("monthly");
         schedule_test_helper_fn("* * * ? * ?");
     }
+
+    #[test]
+    fn test_indexing_settings_validate() {
+        IndexingSettings::default().validate().unwrap();
+
+        let indexing_settings = IndexingSettings {
+            commit_timeout_secs: 0,
+            ..Default::default()
+        };
+        let error = indexing_settings.validate().unwrap_err();
+        assert!(error.to_string().contains("commit_timeout_secs"));
+
+        let indexing_settings = IndexingSettings {
+            split_num_docs_target: 0,
+            ..Default::default()
+        };
+        let error = indexing_settings.validate().unwrap_err();
+        assert!(error.to_string().contains("split_num_docs_target"));
+
+        let indexing_settings = IndexingSettings {
+            split_num_bytes_target: Some(Byte::from_bytes(0)),
+            ..Default::default()
+        };
+        let error = indexing_settings.validate().unwrap_err();
+        assert!(error.to_string().contains("split_num_bytes_target"));
+    }
+
+    #[test]
+    fn test_indexing_settings_with_update() {
+        let indexing_settings = IndexingSettings::default();
+        let update = IndexingSettingsUpdate::default();
+        assert!(update.is_empty());
+        assert_eq!(indexing_settings.with_update(&update), indexing_settings);
+
+        let update: IndexingSettingsUpdate = serde_json::from_str(
+            r#"{"commit_timeout_secs": 5, "split_num_bytes_target": "100MB"}"#,
+        )
+        .unwrap();
+        assert!(!update.is_empty());
+        let updated_indexing_settings = indexing_settings.with_update(&update);
+        assert_eq!(updated_indexing_settings.commit_timeout_secs, 5);
+        assert_eq!(
+            updated_indexing_settings.split_num_docs_target,
+            IndexingSettings::default_split_num_docs_target()
+        );
+        assert_eq!(
+            updated_indexing_settings.split_num_bytes_target,
+            Some(Byte::from_bytes(100_000_000))
+        );
+
+        serde_json::from_str::<IndexingSettingsUpdate>(r#"{"merge_policy": {}}"#).unwrap_err();
+    }
+
+    #[test]
+    fn test_index_config_with_synonyms() {
+        let config_yaml = r#"
+            version: 0.6
+            index_id: hdfs-logs
+            index_uri: "s3://my-index"
+            doc_mapping: {}
+            search_settings:
+              default_search_fields: [body]
+              synonyms:
+                - fields: [body, service]
+                  synonyms: [k8s, kubernetes]
+        "#;
+        let index_config: IndexConfig = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Uri::from_well_formed("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(
+            index_config.search_settings.synonyms,
+            [SynonymSet {
+                fields: vec!["body".to_string(), "service".to_string()],
+                synonyms: vec!["k8s".to_string(), "kubernetes".to_string()],
+            }]
+        );
+        assert!(!index_config.search_settings.synonym_dictionary().is_empty());
+    }
+
+    #[test]
+    fn test_index_config_with_feature_flags() {
+        let config_yaml = r#"
+            version: 0.6
+            index_id: hdfs-logs
+            index_uri: "s3://my-index"
+            doc_mapping: {}
+            search_settings:
+              feature_flags:
+                regex_queries: false
+                realtime_search: true
+        "#;
+        let index_config: IndexConfig = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Uri::from_well_formed("s3://my-index"),
+        )
+        .unwrap();
+        let search_settings = &index_config.search_settings;
+        assert!(!search_settings.is_feature_enabled(SearchFeature::RegexQueries));
+        assert!(search_settings.is_feature_enabled(SearchFeature::RealtimeSearch));
+        assert!(!search_settings.is_feature_enabled(SearchFeature::KnnQueries));
+        assert!(!search_settings.is_feature_enabled(SearchFeature::UdfQueries));
+
+        let default_search_settings = SearchSettings::default();
+        assert!(default_search_settings.is_feature_enabled(SearchFeature::RegexQueries));
+        assert!(!default_search_settings.is_feature_enabled(SearchFeature::RealtimeSearch));
+
+        let config_yaml = r#"
+            version: 0.6
+            index_id: hdfs-logs
+            index_uri: "s3://my-index"
+            doc_mapping: {}
+            search_settings:
+              feature_flags:
+                wildcard_everything: true
+        "#;
+        load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Uri::from_well_formed("s3://my-index"),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_search_settings_validate() {
+        SearchSettings::default().validate().unwrap();
+
+        let search_settings = SearchSettings {
+            synonyms: vec![SynonymSet {
+                fields: Vec::new(),
+                synonyms: vec!["k8s".to_string(), "kubernetes".to_string()],
+            }],
+            ..Default::default()
+        };
+        let error = search_settings.validate().unwrap_err();
+        assert!(error.to_string().contains("At least one field"));
+
+        let search_settings = SearchSettings {
+            synonyms: vec![SynonymSet {
+                fields: vec!["body".to_string()],
+                synonyms: vec!["k8s".to_string()],
+            }],
+            ..Default::default()
+        };
+        let error = search_settings.validate().unwrap_err();
+        assert!(error.to_string().contains("at least two terms"));
+
+        let search_settings = SearchSettings {
+            synonyms: vec![SynonymSet {
+                fields: vec!["body".to_string()],
+                synonyms: vec!["k8s".to_string(), " ".to_string()],
+            }],
+            ..Default::default()
+        };
+        let error = search_settings.validate().unwrap_err();
+        assert!(error.to_string().contains("must not be empty"));
+    }
 }
@@ -17,14 +17,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::{BTreeMap, HashSet};
+
 use anyhow::Context;
 use quickwit_common::uri::Uri;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use crate::{
-    build_doc_mapper, validate_identifier, ConfigFormat, DocMapping, IndexConfig, IndexingSettings,
-    RetentionPolicy, SearchSettings,
+    build_doc_mapper, validate_identifier, validate_labels, validate_lookup_tables,
+    validate_search_templates, ConfigFormat, DocMapping, DocumentTtlPolicy, IndexConfig,
+    IndexingSettings, LookupTable, ReshardingPolicy, RetentionPolicy, RollupPolicy, SearchSettings,
+    SearchTemplate,
 };
 
 /// Alias for the latest serialization format.
@@ -101,9 +105,38 @@ impl IndexConfigForSerialization {
         // Note: this needs a deep refactoring to separate the doc mapping configuration,
         // and doc mapper implementations.
         // TODO see if we should store the byproducton the IndexConfig.
-        build_doc_mapper(&self.doc_mapping, &self.search_settings)?;
+        let doc_mapper = build_doc_mapper(&self.doc_mapping, &self.search_settings)?;
+
+        if let Some(document_ttl_policy) = &self.document_ttl_policy {
+            document_ttl_policy.validate(&*doc_mapper)?;
+        }
+
+        if let Some(resharding_policy) = &self.resharding_policy {
+            resharding_policy.validate(&self.doc_mapping)?;
+        }
 
-        self.indexing_settings.merge_policy.validate()?;
+        let mut rollup_index_ids = HashSet::new();
+        for rollup_policy in &self.rollup_policies {
+            rollup_policy.validate(&self.index_id, &*doc_mapper)?;
+
+            if !rollup_index_ids.insert(rollup_policy.rollup_index_id()) {
+                anyhow::bail!(
+                    "Failed to validate index config. The rollup index `{}` is the target of \
+                     several rollup policies.",
+                    rollup_policy.rollup_index_id()
+                );
+            }
+        }
+
+        self.indexing_settings.validate()?;
+        self.search_settings.validate()?;
+
+        for processor_config in &self.indexing_settings.processors {
+            processor_config.validate()?;
+        }
+        validate_labels(&self.labels)?;
+        validate_lookup_tables(&self.lookup_tables)?;
+        validate_search_templates(&self.search_templates)?;
 
         Ok(IndexConfig {
             index_id: self.index_id,
@@ -112,6 +145,12 @@ impl IndexConfigForSerialization {
             indexing_settings: self.indexing_settings,
             search_settings: self.search_settings,
             retention_policy: self.retention_policy,
+            document_ttl_policy: self.document_ttl_policy,
+            resharding_policy: self.resharding_policy,
+            rollup_policies: self.rollup_policies,
+            labels: self.labels,
+            lookup_tables: self.lookup_tables,
+            search_templates: self.search_templates,
         })
     }
 }
@@ -147,6 +186,27 @@ pub struct IndexConfigV0_6 {
     #[serde(rename = "retention")]
     #[serde(default)]
     pub retention_policy: Option<RetentionPolicy>,
+    #[serde(rename = "document_ttl")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_ttl_policy: Option<DocumentTtlPolicy>,
+    #[serde(rename = "resharding")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resharding_policy: Option<ReshardingPolicy>,
+    #[serde(rename = "rollups")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rollup_policies: Vec<RollupPolicy>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub lookup_tables: BTreeMap<String, LookupTable>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub search_templates: BTreeMap<String, SearchTemplate>,
 }
 
 impl From<IndexConfig> for IndexConfigV0_6 {
@@ -158,6 +218,12 @@ impl From<IndexConfig> for IndexConfigV0_6 {
             indexing_settings: index_config.indexing_settings,
             search_settings: index_config.search_settings,
             retention_policy: index_config.retention_policy,
+            document_ttl_policy: index_config.document_ttl_policy,
+            resharding_policy: index_config.resharding_policy,
+            rollup_policies: index_config.rollup_policies,
+            labels: index_config.labels,
+            lookup_tables: index_config.lookup_tables,
+            search_templates: index_config.search_templates,
         }
     }
 }
@@ -225,6 +291,172 @@ mod test {
         assert!(validation_err.contains("The retention policy requires a timestamp field"));
     }
 
+    #[test]
+    fn test_validate_document_ttl_policy() {
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.document_ttl_policy = Some(DocumentTtlPolicy::new(
+            "body".to_string(),
+            "hourly".to_string(),
+        ));
+        let validation_err = invalid_index_config
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "Failed to validate document TTL policy. The field `body` must be an existing fast \
+             datetime field."
+        );
+
+        let index_config_for_serialization: IndexConfigForSerialization = serde_yaml::from_str(
+            r#"
+            index_id: hdfs-logs
+            index_uri: s3://quickwit-indexes/hdfs-logs
+            doc_mapping:
+                field_mappings:
+                    - name: expires_at
+                      type: datetime
+                      fast: true
+            document_ttl:
+                field: expires_at
+        "#,
+        )
+        .unwrap();
+        let index_config = index_config_for_serialization
+            .validate_and_build(None)
+            .unwrap();
+        let document_ttl_policy = index_config.document_ttl_policy.unwrap();
+        assert_eq!(document_ttl_policy.field(), "expires_at");
+        assert_eq!(
+            document_ttl_policy,
+            DocumentTtlPolicy::new("expires_at".to_string(), "hourly".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_rollup_policies() {
+        let index_config_yaml = r#"
+            index_id: hdfs-logs
+            index_uri: s3://quickwit-indexes/hdfs-logs
+            doc_mapping:
+                field_mappings:
+                    - name: timestamp
+                      type: datetime
+                      fast: true
+                    - name: service
+                      type: text
+                      tokenizer: raw
+                      fast: true
+                    - name: body
+                      type: text
+                timestamp_field: timestamp
+            rollups:
+                - rollup_index_id: hdfs-logs-per-minute
+                  interval: 1 minute
+                  group_by: [service]
+        "#;
+        let index_config_for_serialization: IndexConfigForSerialization =
+            serde_yaml::from_str(index_config_yaml).unwrap();
+        let index_config = index_config_for_serialization
+            .validate_and_build(None)
+            .unwrap();
+        assert_eq!(index_config.rollup_policies.len(), 1);
+        let rollup_policy = &index_config.rollup_policies[0];
+        assert_eq!(
+            rollup_policy,
+            &RollupPolicy::new(
+                "hdfs-logs-per-minute".to_string(),
+                "1 minute".to_string(),
+                vec!["service".to_string()]
+            )
+        );
+        assert_eq!(rollup_policy.interval_secs().unwrap(), 60);
+        assert_eq!(rollup_policy.delay_secs().unwrap(), 300);
+        assert_eq!(rollup_policy.count_field(), "count");
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            serde_yaml::from_str(index_config_yaml).unwrap();
+        invalid_index_config.rollup_policies = vec![RollupPolicy::new(
+            "hdfs-logs-per-minute".to_string(),
+            "1 minute".to_string(),
+            vec!["body".to_string()],
+        )];
+        let validation_err = invalid_index_config
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "Failed to validate rollup policy. The `group_by` field `body` must be an existing \
+             fast field."
+        );
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            serde_yaml::from_str(index_config_yaml).unwrap();
+        invalid_index_config.rollup_policies = vec![RollupPolicy::new(
+            "hdfs-logs-per-minute".to_string(),
+            "1500 ms".to_string(),
+            Vec::new(),
+        )];
+        let validation_err = invalid_index_config
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert!(validation_err.contains("The interval must be a whole number of seconds."));
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            serde_yaml::from_str(index_config_yaml).unwrap();
+        let rollup_policy = invalid_index_config.rollup_policies[0].clone();
+        invalid_index_config.rollup_policies.push(rollup_policy);
+        let validation_err = invalid_index_config
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "Failed to validate index config. The rollup index `hdfs-logs-per-minute` is the \
+             target of several rollup policies."
+        );
+    }
+
+    #[test]
+    fn test_validate_resharding_policy() {
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.resharding_policy =
+            Some(ReshardingPolicy::new("tenant_id".to_string()));
+        let validation_err = invalid_index_config
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "Failed to validate resharding policy. The field `tenant_id` must be a tag field."
+        );
+
+        let index_config_for_serialization: IndexConfigForSerialization = serde_yaml::from_str(
+            r#"
+            index_id: hdfs-logs
+            index_uri: s3://quickwit-indexes/hdfs-logs
+            doc_mapping:
+                field_mappings:
+                    - name: tenant_id
+                      type: text
+                      tokenizer: raw
+                tag_fields: [tenant_id]
+            resharding:
+                field: tenant_id
+        "#,
+        )
+        .unwrap();
+        let index_config = index_config_for_serialization
+            .validate_and_build(None)
+            .unwrap();
+        let resharding_policy = index_config.resharding_policy.unwrap();
+        assert_eq!(resharding_policy.field(), "tenant_id");
+    }
+
     #[test]
     fn test_minimal_index_config_missing_root_uri_no_default_uri() {
         let config_yaml = r#"
@@ -255,4 +487,111 @@ mod test {
             assert_eq!(index_config.index_uri.as_str(), "s3://mybucket/hdfs-logs");
         }
     }
+
+    #[test]
+    fn test_validate_labels() {
+        let mut index_config_for_serialization = minimal_index_config_for_serialization();
+        index_config_for_serialization
+            .labels
+            .insert("team".to_string(), "pay:ments".to_string());
+        let validation_err = index_config_for_serialization
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert!(validation_err.contains("Label value `pay:ments` for key `team` is invalid"));
+    }
+
+    #[test]
+    fn test_index_config_labels_are_not_serialized_when_empty() {
+        let index_config = minimal_index_config_for_serialization()
+            .validate_and_build(None)
+            .unwrap();
+        assert!(index_config.labels.is_empty());
+        let index_config_json = serde_json::to_value(&index_config).unwrap();
+        assert!(index_config_json.get("labels").is_none());
+    }
+
+    #[test]
+    fn test_index_config_lookup_tables() {
+        let index_config_yaml = r#"
+            version: 0.6
+            index_id: hdfs-logs
+            index_uri: s3://quickwit-indexes/hdfs-logs
+            doc_mapping: {}
+            lookup_tables:
+                http_status:
+                    field: status
+                    rows:
+                        "200":
+                            description: OK
+        "#;
+        let index_config: IndexConfig = ConfigFormat::Yaml
+            .parse(index_config_yaml.as_bytes())
+            .unwrap();
+        let lookup_table = &index_config.lookup_tables["http_status"];
+        assert_eq!(lookup_table.field, "status");
+        assert_eq!(lookup_table.get("200").unwrap()["description"], "OK");
+
+        let index_config_json = serde_json::to_value(&index_config).unwrap();
+        assert_eq!(
+            index_config_json["lookup_tables"]["http_status"]["rows"]["200"]["description"],
+            "OK"
+        );
+
+        let mut index_config_for_serialization = minimal_index_config_for_serialization();
+        index_config_for_serialization.lookup_tables.insert(
+            "http_status".to_string(),
+            LookupTable {
+                field: "".to_string(),
+                rows: BTreeMap::new(),
+            },
+        );
+        let validation_err = index_config_for_serialization
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert!(validation_err.contains("Lookup table `http_status` must specify a field"));
+    }
+
+    #[test]
+    fn test_index_config_search_templates() {
+        let index_config_yaml = r#"
+            version: 0.6
+            index_id: hdfs-logs
+            index_uri: s3://quickwit-indexes/hdfs-logs
+            doc_mapping: {}
+            search_templates:
+                by_severity:
+                    source: '{"query": {"term": {"severity_text": "{{severity}}"}}}'
+                by_tenant:
+                    source:
+                        query:
+                            term:
+                                tenant_id: "{{tenant_id}}"
+        "#;
+        let index_config: IndexConfig = ConfigFormat::Yaml
+            .parse(index_config_yaml.as_bytes())
+            .unwrap();
+        assert_eq!(
+            index_config.search_templates["by_severity"].source,
+            r#"{"query": {"term": {"severity_text": "{{severity}}"}}}"#
+        );
+        assert_eq!(
+            index_config.search_templates["by_tenant"].source,
+            r#"{"query":{"term":{"tenant_id":"{{tenant_id}}"}}}"#
+        );
+
+        let mut index_config_for_serialization = minimal_index_config_for_serialization();
+        index_config_for_serialization.search_templates.insert(
+            "by_severity".to_string(),
+            SearchTemplate {
+                source: "{{#severity}}".to_string(),
+            },
+        );
+        let validation_err = index_config_for_serialization
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(validation_err, "Search template `by_severity` is invalid.");
+    }
 }
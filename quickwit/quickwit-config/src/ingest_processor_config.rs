@@ -0,0 +1,298 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use byte_unit::Byte;
+use quickwit_common::uri::Uri;
+use serde::{Deserialize, Serialize};
+
+/// Configuration of a processor applied to every document of an index at ingest time, after the
+/// source transform (if any) and before the document is handed over to the doc mapper.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, utoipa::ToSchema)]
+#[serde(tag = "type")]
+#[serde(deny_unknown_fields)]
+pub enum IngestProcessorConfig {
+    #[serde(rename = "wasm")]
+    Wasm(WasmProcessorConfig),
+    #[serde(rename = "geoip")]
+    GeoIp(GeoIpProcessorConfig),
+    #[serde(rename = "user_agent")]
+    UserAgent(UserAgentProcessorConfig),
+}
+
+impl IngestProcessorConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            IngestProcessorConfig::Wasm(wasm_config) => wasm_config.validate(),
+            IngestProcessorConfig::GeoIp(geoip_config) => geoip_config.validate(),
+            IngestProcessorConfig::UserAgent(user_agent_config) => user_agent_config.validate(),
+        }
+    }
+}
+
+/// Runs a user-supplied WebAssembly module over each document.
+///
+/// The module is executed in a sandbox: it cannot perform any IO and its execution is bounded
+/// by `max_fuel_per_doc` and `max_memory`.
+///
+/// The module must export:
+/// - a linear memory named `memory`;
+/// - an `alloc(len: i32) -> i32` function returning a buffer of `len` bytes;
+/// - the `entrypoint` function, with the signature `(ptr: i32, len: i32) -> i64`. It receives the
+///   JSON-serialized document and returns the location of the JSON-serialized transformed document
+///   packed as `(ptr << 32) | len`. A negative value signals an error.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WasmProcessorConfig {
+    /// URI of the compiled WebAssembly module (`.wasm`).
+    #[schema(value_type = String)]
+    pub module_uri: Uri,
+    /// Name of the function exported by the module and called for each document.
+    #[serde(default = "WasmProcessorConfig::default_entrypoint")]
+    pub entrypoint: String,
+    /// Amount of fuel (roughly, the number of executed instructions) a single document can
+    /// consume before its processing is aborted.
+    #[serde(default = "WasmProcessorConfig::default_max_fuel_per_doc")]
+    pub max_fuel_per_doc: u64,
+    /// Maximum size of the linear memory of the module.
+    #[schema(value_type = String, default = "16 MB")]
+    #[serde(default = "WasmProcessorConfig::default_max_memory")]
+    pub max_memory: Byte,
+}
+
+impl WasmProcessorConfig {
+    fn default_entrypoint() -> String {
+        "process".to_string()
+    }
+
+    fn default_max_fuel_per_doc() -> u64 {
+        10_000_000
+    }
+
+    fn default_max_memory() -> Byte {
+        Byte::from_bytes(16_000_000) // 16MB
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(module_uri: &str) -> Self {
+        Self {
+            module_uri: Uri::from_well_formed(module_uri),
+            entrypoint: Self::default_entrypoint(),
+            max_fuel_per_doc: Self::default_max_fuel_per_doc(),
+            max_memory: Self::default_max_memory(),
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.entrypoint.is_empty() {
+            anyhow::bail!("WASM processor `entrypoint` must not be empty.");
+        }
+        if self.max_fuel_per_doc == 0 {
+            anyhow::bail!("WASM processor `max_fuel_per_doc` must be strictly positive.");
+        }
+        // A linear memory is made of 64KiB pages: we need at least one.
+        if self.max_memory.get_bytes() < 65_536 {
+            anyhow::bail!("WASM processor `max_memory` must be at least 64KiB.");
+        }
+        Ok(())
+    }
+}
+
+/// Resolves the IP address held by `source_field` to geographical and autonomous system
+/// information, written as an object under `target_field`.
+///
+/// The MaxMind-format databases are configured per indexer node (see
+/// [`GeoIpConfig`](crate::GeoIpConfig)). Documents whose source field is missing, is not a valid
+/// IP address, or is not found in the databases are left untouched.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GeoIpProcessorConfig {
+    /// Field holding the IP address. Nested fields are expressed with dots (e.g. `client.ip`).
+    pub source_field: String,
+    /// Root field under which the derived fields are written.
+    #[serde(default = "GeoIpProcessorConfig::default_target_field")]
+    pub target_field: String,
+}
+
+impl GeoIpProcessorConfig {
+    fn default_target_field() -> String {
+        "geoip".to_string()
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(source_field: &str) -> Self {
+        Self {
+            source_field: source_field.to_string(),
+            target_field: Self::default_target_field(),
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.source_field.is_empty() {
+            anyhow::bail!("GeoIP processor `source_field` must not be empty.");
+        }
+        if self.target_field.is_empty() {
+            anyhow::bail!("GeoIP processor `target_field` must not be empty.");
+        }
+        if self.source_field == self.target_field {
+            anyhow::bail!("GeoIP processor `target_field` must differ from `source_field`.");
+        }
+        Ok(())
+    }
+}
+
+/// Parses the user-agent string held by `source_field` into browser, operating system, and device
+/// fields, written as an object under `target_field`.
+///
+/// Parsing relies on the regular expressions maintained by the
+/// [ua-parser](https://github.com/ua-parser/uap-core) project, loaded from `regexes_uri`. Documents
+/// whose source field is missing or is not a string are left untouched.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UserAgentProcessorConfig {
+    /// Field holding the user-agent string. Nested fields are expressed with dots (e.g.
+    /// `http.user_agent`).
+    pub source_field: String,
+    /// Root field under which the derived fields are written.
+    #[serde(default = "UserAgentProcessorConfig::default_target_field")]
+    pub target_field: String,
+    /// URI of the ua-parser `regexes.yaml` file.
+    #[schema(value_type = String)]
+    pub regexes_uri: Uri,
+}
+
+impl UserAgentProcessorConfig {
+    fn default_target_field() -> String {
+        "user_agent".to_string()
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(source_field: &str, regexes_uri: &str) -> Self {
+        Self {
+            source_field: source_field.to_string(),
+            target_field: Self::default_target_field(),
+            regexes_uri: Uri::from_well_formed(regexes_uri),
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.source_field.is_empty() {
+            anyhow::bail!("User-agent processor `source_field` must not be empty.");
+        }
+        if self.target_field.is_empty() {
+            anyhow::bail!("User-agent processor `target_field` must not be empty.");
+        }
+        if self.source_field == self.target_field {
+            anyhow::bail!("User-agent processor `target_field` must differ from `source_field`.");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ingest_processor_config_deserialization() {
+        let processor_config_yaml = r#"
+            type: wasm
+            module_uri: s3://my-bucket/plugins/extract.wasm
+            max_fuel_per_doc: 1000
+        "#;
+        let processor_config: IngestProcessorConfig =
+            serde_yaml::from_str(processor_config_yaml).unwrap();
+        let IngestProcessorConfig::Wasm(wasm_config) = &processor_config else {
+            panic!("Expected a WASM processor config, got `{processor_config:?}`.");
+        };
+        assert_eq!(
+            wasm_config.module_uri,
+            "s3://my-bucket/plugins/extract.wasm"
+        );
+        assert_eq!(wasm_config.entrypoint, "process");
+        assert_eq!(wasm_config.max_fuel_per_doc, 1000);
+        assert_eq!(wasm_config.max_memory, Byte::from_bytes(16_000_000));
+        processor_config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_ingest_processor_config_validate() {
+        let mut wasm_config = WasmProcessorConfig::for_test("file:///plugins/extract.wasm");
+        wasm_config.validate().unwrap();
+
+        wasm_config.max_fuel_per_doc = 0;
+        wasm_config.validate().unwrap_err();
+
+        wasm_config.max_fuel_per_doc = 1;
+        wasm_config.max_memory = Byte::from_bytes(1_000);
+        wasm_config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_geoip_processor_config() {
+        let processor_config_yaml = r#"
+            type: geoip
+            source_field: client.ip
+        "#;
+        let processor_config: IngestProcessorConfig =
+            serde_yaml::from_str(processor_config_yaml).unwrap();
+        assert_eq!(
+            processor_config,
+            IngestProcessorConfig::GeoIp(GeoIpProcessorConfig::for_test("client.ip"))
+        );
+        processor_config.validate().unwrap();
+
+        let mut geoip_config = GeoIpProcessorConfig::for_test("");
+        geoip_config.validate().unwrap_err();
+
+        geoip_config.source_field = "geoip".to_string();
+        geoip_config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_user_agent_processor_config() {
+        let processor_config_yaml = r#"
+            type: user_agent
+            source_field: http.user_agent
+            regexes_uri: s3://my-bucket/ua-parser/regexes.yaml
+        "#;
+        let processor_config: IngestProcessorConfig =
+            serde_yaml::from_str(processor_config_yaml).unwrap();
+        assert_eq!(
+            processor_config,
+            IngestProcessorConfig::UserAgent(UserAgentProcessorConfig::for_test(
+                "http.user_agent",
+                "s3://my-bucket/ua-parser/regexes.yaml"
+            ))
+        );
+        processor_config.validate().unwrap();
+
+        let processor_config_yaml = r#"
+            type: user_agent
+            source_field: http.user_agent
+        "#;
+        serde_yaml::from_str::<IngestProcessorConfig>(processor_config_yaml).unwrap_err();
+
+        let mut user_agent_config =
+            UserAgentProcessorConfig::for_test("user_agent", "file:///regexes.yaml");
+        user_agent_config.validate().unwrap_err();
+
+        user_agent_config.source_field = "".to_string();
+        user_agent_config.validate().unwrap_err();
+    }
+}
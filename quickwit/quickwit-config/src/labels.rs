@@ -0,0 +1,213 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+use once_cell::sync::OnceCell;
+use regex::Regex;
+
+/// Maximum number of labels that can be attached to an index or a source.
+pub const MAX_NUM_LABELS: usize = 64;
+
+const LABEL_KEY_PATTERN: &str = r"^[a-zA-Z][a-zA-Z0-9-_./]{0,62}$";
+
+const LABEL_VALUE_PATTERN: &str = r"^[a-zA-Z0-9-_./]{0,255}$";
+
+/// Checks that a set of labels conforms to Quickwit label naming conventions.
+///
+/// Keys and values must not contain `:` or `,` so that they can be expressed unambiguously in a
+/// [`LabelFilter`].
+pub fn validate_labels(labels: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    static LABEL_KEY_REGEX: OnceCell<Regex> = OnceCell::new();
+    static LABEL_VALUE_REGEX: OnceCell<Regex> = OnceCell::new();
+
+    if labels.len() > MAX_NUM_LABELS {
+        bail!("Too many labels: {} (max {MAX_NUM_LABELS}).", labels.len());
+    }
+    let label_key_regex = LABEL_KEY_REGEX.get_or_init(|| {
+        Regex::new(LABEL_KEY_PATTERN).expect("Failed to compile regular expression. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.")
+    });
+    let label_value_regex = LABEL_VALUE_REGEX.get_or_init(|| {
+        Regex::new(LABEL_VALUE_PATTERN).expect("Failed to compile regular expression. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.")
+    });
+    for (key, value) in labels {
+        if !label_key_regex.is_match(key) {
+            bail!(
+                "Label key `{key}` is invalid. Label keys must match the following regular \
+                 expression: `{LABEL_KEY_PATTERN}`."
+            );
+        }
+        if !label_value_regex.is_match(value) {
+            bail!(
+                "Label value `{value}` for key `{key}` is invalid. Label values must match the \
+                 following regular expression: `{LABEL_VALUE_PATTERN}`."
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A filter on the labels of an index or a source, expressed as `key` (the label must be set)
+/// or `key:value` (the label must be set to `value`).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LabelFilter {
+    Exists { key: String },
+    Equals { key: String, value: String },
+}
+
+impl LabelFilter {
+    /// Returns whether `labels` satisfy the filter.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        match self {
+            LabelFilter::Exists { key } => labels.contains_key(key),
+            LabelFilter::Equals { key, value } => labels.get(key) == Some(value),
+        }
+    }
+
+    /// Returns whether `labels` satisfy all the filters.
+    pub fn matches_all(filters: &[LabelFilter], labels: &BTreeMap<String, String>) -> bool {
+        filters.iter().all(|filter| filter.matches(labels))
+    }
+}
+
+impl FromStr for LabelFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(filter_str: &str) -> anyhow::Result<Self> {
+        let filter = match filter_str.split_once(':') {
+            Some((key, value)) => LabelFilter::Equals {
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            },
+            None => LabelFilter::Exists {
+                key: filter_str.trim().to_string(),
+            },
+        };
+        let key = match &filter {
+            LabelFilter::Exists { key } | LabelFilter::Equals { key, .. } => key,
+        };
+        if key.is_empty() {
+            bail!("Label filter `{filter_str}` is invalid: the label key is empty.");
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LabelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LabelFilter::Exists { key } => write!(f, "{key}"),
+            LabelFilter::Equals { key, value } => write!(f, "{key}:{value}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_labels() {
+        validate_labels(&BTreeMap::new()).unwrap();
+        validate_labels(&labels(&[
+            ("team", "payments"),
+            ("env", "prod"),
+            ("app.kubernetes.io/name", "quickwit"),
+            ("empty", ""),
+        ]))
+        .unwrap();
+
+        let error = validate_labels(&labels(&[("1team", "payments")])).unwrap_err();
+        assert!(error.to_string().contains("Label key `1team` is invalid"));
+
+        let error = validate_labels(&labels(&[("team", "pay:ments")])).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Label value `pay:ments` for key `team` is invalid"));
+
+        let too_many_labels: BTreeMap<String, String> = (0..=MAX_NUM_LABELS)
+            .map(|i| (format!("key-{i}"), "value".to_string()))
+            .collect();
+        let error = validate_labels(&too_many_labels).unwrap_err();
+        assert!(error.to_string().contains("Too many labels"));
+    }
+
+    #[test]
+    fn test_label_filter_from_str() {
+        assert_eq!(
+            "team".parse::<LabelFilter>().unwrap(),
+            LabelFilter::Exists {
+                key: "team".to_string()
+            }
+        );
+        assert_eq!(
+            "team:payments".parse::<LabelFilter>().unwrap(),
+            LabelFilter::Equals {
+                key: "team".to_string(),
+                value: "payments".to_string()
+            }
+        );
+        assert_eq!(
+            "team:".parse::<LabelFilter>().unwrap(),
+            LabelFilter::Equals {
+                key: "team".to_string(),
+                value: "".to_string()
+            }
+        );
+        assert!("".parse::<LabelFilter>().is_err());
+        assert!(":payments".parse::<LabelFilter>().is_err());
+
+        for filter_str in ["team", "team:payments"] {
+            assert_eq!(
+                filter_str.parse::<LabelFilter>().unwrap().to_string(),
+                filter_str
+            );
+        }
+    }
+
+    #[test]
+    fn test_label_filter_matches() {
+        let labels = labels(&[("team", "payments"), ("env", "prod")]);
+        let filters: Vec<LabelFilter> = ["team:payments", "env"]
+            .into_iter()
+            .map(|filter_str| filter_str.parse().unwrap())
+            .collect();
+        assert!(LabelFilter::matches_all(&filters, &labels));
+        assert!(LabelFilter::matches_all(&[], &labels));
+
+        let filters: Vec<LabelFilter> = ["team:payments", "env:staging"]
+            .into_iter()
+            .map(|filter_str| filter_str.parse().unwrap())
+            .collect();
+        assert!(!LabelFilter::matches_all(&filters, &labels));
+
+        let filter: LabelFilter = "owner".parse().unwrap();
+        assert!(!filter.matches(&labels));
+    }
+}
@@ -29,34 +29,53 @@ use quickwit_common::uri::Uri;
 use regex::Regex;
 
 mod config_value;
+mod env_overlays;
 mod index_config;
+pub mod ingest_processor_config;
+mod labels;
+mod lookup_tables;
 pub mod merge_policy_config;
 mod metastore_config;
 mod quickwit_config;
 mod qw_env_vars;
+mod search_templates;
 pub mod service;
 mod source_config;
 mod storage_config;
 mod templating;
+mod udf_config;
 
 // We export that one for backward compatibility.
 // See #2048
 use index_config::serialize::{IndexConfigV0_6, VersionedIndexConfig};
+pub use env_overlays::{
+    apply_env_overlays, has_env_overlays, parse_with_env_overlays,
+    INDEX_CONFIG_ENV_OVERLAY_PREFIX, NODE_CONFIG_ENV_OVERLAY_PREFIX,
+};
 pub use index_config::{
-    build_doc_mapper, load_index_config_from_user_config, DocMapping, IndexConfig,
-    IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
+    build_doc_mapper, load_index_config_from_user_config, DocMapping, DocumentTtlPolicy,
+    IndexConfig, IndexingResources, IndexingSettings, IndexingSettingsUpdate, ReshardingPolicy,
+    RetentionPolicy, RollupPolicy, SearchFeature, SearchSettings, SynonymSet,
+};
+pub use labels::{validate_labels, LabelFilter, MAX_NUM_LABELS};
+pub use lookup_tables::{
+    validate_lookup_tables, LookupTable, MAX_NUM_LOOKUP_TABLES, MAX_NUM_LOOKUP_TABLE_ROWS,
 };
+pub use search_templates::{validate_search_templates, SearchTemplate, MAX_NUM_SEARCH_TEMPLATES};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 pub use source_config::{
-    load_source_config_from_user_config, FileSourceParams, KafkaSourceParams, KinesisSourceParams,
-    PulsarSourceAuth, PulsarSourceParams, RegionOrEndpoint, SourceConfig, SourceInputFormat,
-    SourceParams, TransformConfig, VecSourceParams, VoidSourceParams, CLI_INGEST_SOURCE_ID,
-    INGEST_API_SOURCE_ID,
+    load_source_config_from_user_config, CsvColumnType, CsvParams, FileSourceParams,
+    KafkaSourceParams, KinesisSourceParams, PulsarSourceAuth, PulsarSourceParams, RegionOrEndpoint,
+    SourceConfig, SourceInputFormat, SourceParams, TransformConfig, VecSourceParams,
+    VoidSourceParams, CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
 };
 use tracing::warn;
 
+use crate::ingest_processor_config::{
+    GeoIpProcessorConfig, IngestProcessorConfig, UserAgentProcessorConfig, WasmProcessorConfig,
+};
 use crate::merge_policy_config::{
     ConstWriteAmplificationMergePolicyConfig, MergePolicyConfig, StableLogMergePolicyConfig,
 };
@@ -64,22 +83,34 @@ pub use crate::metastore_config::{
     MetastoreBackend, MetastoreConfig, MetastoreConfigs, PostgresMetastoreConfig,
 };
 pub use crate::quickwit_config::{
-    IndexerConfig, IngestApiConfig, JaegerConfig, QuickwitConfig, SearcherConfig,
-    DEFAULT_QW_CONFIG_PATH,
+    ClusterHistoryConfig, GeoIpConfig, IndexerConfig, IngestApiConfig, JaegerConfig,
+    QuickwitConfig, RemoteCacheProtocol, RequestTemplateConfig, SearcherConfig, ShadowSearchConfig,
+    SplitCacheConfig, SplitUpgradeConfig, UiPreferencesConfig, DEFAULT_QW_CONFIG_PATH,
 };
 use crate::source_config::serialize::{SourceConfigV0_6, VersionedSourceConfig};
 pub use crate::storage_config::{
     AzureStorageConfig, FileStorageConfig, RamStorageConfig, S3StorageConfig, StorageBackend,
     StorageConfig, StorageConfigs,
 };
+pub use crate::udf_config::UdfConfig;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(components(schemas(
     IndexingResources,
     IndexingSettings,
+    IndexingSettingsUpdate,
     SearchSettings,
+    SearchFeature,
+    SynonymSet,
     RetentionPolicy,
+    DocumentTtlPolicy,
+    ReshardingPolicy,
+    RollupPolicy,
     MergePolicyConfig,
+    IngestProcessorConfig,
+    WasmProcessorConfig,
+    GeoIpProcessorConfig,
+    UserAgentProcessorConfig,
     DocMapping,
     VersionedSourceConfig,
     SourceConfigV0_6,
@@ -88,6 +119,8 @@ pub use crate::storage_config::{
     SourceInputFormat,
     SourceParams,
     FileSourceParams,
+    CsvParams,
+    CsvColumnType,
     KafkaSourceParams,
     KinesisSourceParams,
     PulsarSourceParams,
@@ -0,0 +1,205 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::validate_identifier;
+
+/// Maximum number of lookup tables that can be attached to an index.
+pub const MAX_NUM_LOOKUP_TABLES: usize = 16;
+
+/// Maximum number of rows of a lookup table. Lookup tables are stored in the metastore along with
+/// the rest of the index config, so they must remain small.
+pub const MAX_NUM_LOOKUP_TABLE_ROWS: usize = 10_000;
+
+/// A small static dictionary mapping the values of a field to extra columns. Lookup tables are
+/// used to enrich search hits and terms aggregation buckets at query time, for instance to
+/// attach a description to an HTTP status code or an organization name to an ASN.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LookupTable {
+    /// Name of the field whose values are looked up in the table. Nested fields are expressed
+    /// with dots (e.g. `source.asn`).
+    pub field: String,
+    /// Extra columns attached to each field value.
+    #[serde(default)]
+    pub rows: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl LookupTable {
+    /// Parses a lookup table from a CSV document. The first line is the header: the first column
+    /// holds the field values and the remaining columns hold the extra columns.
+    pub fn from_csv(field: String, csv_bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .trim(csv::Trim::All)
+            .from_reader(csv_bytes);
+        let headers = reader
+            .headers()
+            .context("Failed to read the header of the CSV lookup table.")?
+            .clone();
+        if headers.len() < 2 {
+            bail!(
+                "The header of a CSV lookup table must contain the key column followed by at \
+                 least one extra column."
+            );
+        }
+        let mut rows = BTreeMap::new();
+        for (line_idx, record_res) in reader.records().enumerate() {
+            let record = record_res.with_context(|| {
+                format!(
+                    "Failed to parse line {} of the CSV lookup table.",
+                    line_idx + 2
+                )
+            })?;
+            let key = record[0].to_string();
+            let columns: BTreeMap<String, String> = headers
+                .iter()
+                .skip(1)
+                .zip(record.iter().skip(1))
+                .map(|(column, value)| (column.to_string(), value.to_string()))
+                .collect();
+            if rows.insert(key, columns).is_some() {
+                bail!(
+                    "Duplicate key `{}` on line {} of the CSV lookup table.",
+                    &record[0],
+                    line_idx + 2
+                );
+            }
+        }
+        Ok(LookupTable { field, rows })
+    }
+
+    /// Returns the extra columns attached to `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&BTreeMap<String, String>> {
+        self.rows.get(key)
+    }
+}
+
+/// Checks that a set of lookup tables is valid: table names must be valid identifiers, each
+/// table must target a field, and tables must not exceed [`MAX_NUM_LOOKUP_TABLE_ROWS`] rows.
+pub fn validate_lookup_tables(lookup_tables: &BTreeMap<String, LookupTable>) -> anyhow::Result<()> {
+    if lookup_tables.len() > MAX_NUM_LOOKUP_TABLES {
+        bail!(
+            "Too many lookup tables: {} (max {MAX_NUM_LOOKUP_TABLES}).",
+            lookup_tables.len()
+        );
+    }
+    for (table_name, lookup_table) in lookup_tables {
+        validate_identifier("Lookup table", table_name)?;
+
+        if lookup_table.field.trim().is_empty() {
+            bail!("Lookup table `{table_name}` must specify a field.");
+        }
+        if lookup_table.rows.len() > MAX_NUM_LOOKUP_TABLE_ROWS {
+            bail!(
+                "Lookup table `{table_name}` has too many rows: {} (max \
+                 {MAX_NUM_LOOKUP_TABLE_ROWS}).",
+                lookup_table.rows.len()
+            );
+        }
+        if lookup_table
+            .rows
+            .values()
+            .any(|columns| columns.contains_key(""))
+        {
+            bail!("Lookup table `{table_name}` has a column with an empty name.");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_table_from_csv() {
+        let csv = b"status, description, class\n200, OK, success\n404,Not Found,client_error\n";
+        let lookup_table = LookupTable::from_csv("status".to_string(), csv).unwrap();
+        assert_eq!(lookup_table.field, "status");
+        assert_eq!(lookup_table.rows.len(), 2);
+        assert_eq!(
+            lookup_table.get("404").unwrap(),
+            &BTreeMap::from([
+                ("class".to_string(), "client_error".to_string()),
+                ("description".to_string(), "Not Found".to_string()),
+            ])
+        );
+        assert!(lookup_table.get("500").is_none());
+    }
+
+    #[test]
+    fn test_lookup_table_from_csv_errors() {
+        let error = LookupTable::from_csv("status".to_string(), b"status\n200\n").unwrap_err();
+        assert!(error.to_string().contains("at least one extra column"));
+
+        let error = LookupTable::from_csv(
+            "status".to_string(),
+            b"status,description\n200,OK\n200,Ok\n",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("Duplicate key `200` on line 3"));
+
+        let error =
+            LookupTable::from_csv("status".to_string(), b"status,description\n200\n").unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_validate_lookup_tables() {
+        let lookup_table = LookupTable {
+            field: "status".to_string(),
+            rows: BTreeMap::from([(
+                "200".to_string(),
+                BTreeMap::from([("description".to_string(), "OK".to_string())]),
+            )]),
+        };
+        let lookup_tables = BTreeMap::from([("http_status".to_string(), lookup_table.clone())]);
+        validate_lookup_tables(&lookup_tables).unwrap();
+
+        let lookup_tables = BTreeMap::from([("0-invalid".to_string(), lookup_table.clone())]);
+        validate_lookup_tables(&lookup_tables).unwrap_err();
+
+        let lookup_tables = BTreeMap::from([(
+            "http_status".to_string(),
+            LookupTable {
+                field: "".to_string(),
+                ..lookup_table.clone()
+            },
+        )]);
+        validate_lookup_tables(&lookup_tables).unwrap_err();
+
+        let rows = (0..=MAX_NUM_LOOKUP_TABLE_ROWS)
+            .map(|key| (key.to_string(), BTreeMap::new()))
+            .collect();
+        let lookup_tables = BTreeMap::from([(
+            "http_status".to_string(),
+            LookupTable {
+                field: "status".to_string(),
+                rows,
+            },
+        )]);
+        let error = validate_lookup_tables(&lookup_tables).unwrap_err();
+        assert!(error.to_string().contains("too many rows"));
+    }
+}
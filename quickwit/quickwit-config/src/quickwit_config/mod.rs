@@ -19,10 +19,10 @@
 
 mod serialize;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::net::SocketAddr;
-use std::num::NonZeroU64;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -36,7 +36,7 @@ use tracing::warn;
 use crate::quickwit_config::serialize::load_quickwit_config_with_env;
 use crate::service::QuickwitService;
 use crate::storage_config::StorageConfigs;
-use crate::{ConfigFormat, MetastoreConfigs};
+use crate::{validate_identifier, ConfigFormat, MetastoreConfigs, UdfConfig};
 
 pub const DEFAULT_QW_CONFIG_PATH: &str = "config/quickwit.yaml";
 
@@ -55,6 +55,10 @@ pub struct IndexerConfig {
     pub enable_otlp_endpoint: bool,
     #[serde(default = "IndexerConfig::default_enable_cooperative_indexing")]
     pub enable_cooperative_indexing: bool,
+    /// MaxMind-format databases used by the `geoip` ingest processors of the node.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geoip: Option<GeoIpConfig>,
 }
 
 impl IndexerConfig {
@@ -93,6 +97,7 @@ impl IndexerConfig {
             split_store_max_num_bytes: Byte::from_bytes(1_000_000),
             split_store_max_num_splits: 3,
             max_concurrent_split_uploads: 4,
+            geoip: None,
         };
         Ok(indexer_config)
     }
@@ -106,20 +111,75 @@ impl Default for IndexerConfig {
             split_store_max_num_bytes: Self::default_split_store_max_num_bytes(),
             split_store_max_num_splits: Self::default_split_store_max_num_splits(),
             max_concurrent_split_uploads: Self::default_max_concurrent_split_uploads(),
+            geoip: None,
         }
     }
 }
 
+/// Location of the MaxMind-format (`.mmdb`) databases used to resolve IP addresses at ingest
+/// time. The databases are loaded when the indexer starts and reloaded every
+/// `refresh_interval_secs` so that updated databases are picked up without restarting the node.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeoIpConfig {
+    /// URI of a City (or Country) database, e.g. `GeoLite2-City.mmdb`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city_database_uri: Option<Uri>,
+    /// URI of an ASN database, e.g. `GeoLite2-ASN.mmdb`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn_database_uri: Option<Uri>,
+    #[serde(default = "GeoIpConfig::default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+impl GeoIpConfig {
+    fn default_refresh_interval_secs() -> u64 {
+        24 * 3_600 // 1 day
+    }
+
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.refresh_interval_secs)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.city_database_uri.is_none() && self.asn_database_uri.is_none() {
+            bail!(
+                "GeoIP config must specify at least one of `city_database_uri` and                  `asn_database_uri`."
+            );
+        }
+        if self.refresh_interval_secs == 0 {
+            bail!("GeoIP config `refresh_interval_secs` must be strictly positive.");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct SearcherConfig {
     pub aggregation_memory_limit: Byte,
     pub aggregation_bucket_limit: u32,
     pub fast_field_cache_capacity: Byte,
     pub split_footer_cache_capacity: Byte,
+    /// Maximum share of the split footer cache the splits of a single index can take up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_footer_cache_per_index_capacity: Option<Byte>,
     pub partial_request_cache_capacity: Byte,
     pub max_num_concurrent_split_searches: usize,
     pub max_num_concurrent_split_streams: usize,
+    /// WebAssembly user-defined functions callable from queries.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub udfs: Vec<UdfConfig>,
+    /// Backend of the cache holding the hotcache and footer of the searched splits.
+    pub split_cache: SplitCacheConfig,
+    /// Mirrors a fraction of the search requests to a shadow cluster or index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow_search: Option<ShadowSearchConfig>,
+    /// Guardrails enforced on the search requests authenticated with an API key.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub request_templates: Vec<RequestTemplateConfig>,
 }
 
 impl Default for SearcherConfig {
@@ -127,15 +187,189 @@ impl Default for SearcherConfig {
         Self {
             fast_field_cache_capacity: Byte::from_bytes(1_000_000_000), // 1G
             split_footer_cache_capacity: Byte::from_bytes(500_000_000), // 500M
+            split_footer_cache_per_index_capacity: None,
             partial_request_cache_capacity: Byte::from_bytes(64_000_000), // 64M
             max_num_concurrent_split_streams: 100,
             max_num_concurrent_split_searches: 100,
             aggregation_memory_limit: Byte::from_bytes(500_000_000), // 500M
             aggregation_bucket_limit: 65000,
+            udfs: Vec::new(),
+            split_cache: SplitCacheConfig::default(),
+            shadow_search: None,
+            request_templates: Vec::new(),
         }
     }
 }
 
+/// Backend of the searcher cache holding the hotcache and footer of the searched splits.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SplitCacheConfig {
+    /// Caches the footers in the memory of the searcher, within the limits set by
+    /// `split_footer_cache_capacity` and `split_footer_cache_per_index_capacity`.
+    #[default]
+    Memory,
+    /// Caches the footers on the local disk of the searcher. Cached footers survive restarts.
+    Disk {
+        /// Directory holding the cached footers.
+        root_dir: PathBuf,
+        /// Maximum number of bytes the cached footers can take up on disk.
+        capacity: Byte,
+    },
+    /// Caches the footers in a cache server shared by the searchers of the cluster.
+    Remote {
+        /// Protocol spoken by the cache server.
+        protocol: RemoteCacheProtocol,
+        /// Address of the cache server, e.g. `redis.example.com:6379`.
+        endpoint: String,
+        /// Expiration delay of the cached footers. Footers never expire if unset.
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ttl_secs: Option<NonZeroU64>,
+        /// Footers larger than this size are not cached. Defaults to 1MB, which fits in the
+        /// default item size limit of memcached.
+        #[serde(default = "SplitCacheConfig::default_max_item_size")]
+        max_item_size: Byte,
+    },
+}
+
+impl SplitCacheConfig {
+    fn default_max_item_size() -> Byte {
+        Byte::from_bytes(1_000_000) // 1M
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            SplitCacheConfig::Memory => {}
+            SplitCacheConfig::Disk { capacity, .. } => {
+                if capacity.get_bytes() == 0 {
+                    bail!("Disk split cache `capacity` must be strictly positive.");
+                }
+            }
+            SplitCacheConfig::Remote {
+                endpoint,
+                max_item_size,
+                ..
+            } => {
+                if endpoint.is_empty() {
+                    bail!("Remote split cache `endpoint` must not be empty.");
+                }
+                if max_item_size.get_bytes() == 0 {
+                    bail!("Remote split cache `max_item_size` must be strictly positive.");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Protocol spoken by a remote cache server.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteCacheProtocol {
+    /// Memcached text protocol.
+    Memcached,
+    /// Redis serialization protocol (RESP), also spoken by Redis-compatible servers such as
+    /// KeyDB or Dragonfly.
+    Redis,
+}
+
+/// Mirrors a fraction of the search requests received by the root searcher to a shadow target, a
+/// second cluster or a second version of the searched index, and logs the differences between the
+/// responses. This is handy to validate an upgrade or a mapping migration on production traffic.
+///
+/// Shadow searches run in the background: they never delay nor alter the responses returned to
+/// the clients.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShadowSearchConfig {
+    /// Fraction of the search requests mirrored to the shadow target, between 0 and 1.
+    pub sample_rate: f64,
+    /// gRPC address of a searcher of the shadow cluster, e.g. `searcher.shadow.example.com:7281`.
+    /// The requests are mirrored to the local cluster if unset.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Shadow index IDs keyed by production index ID. Indexes missing from the map are searched
+    /// under the same ID on the shadow target.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub index_ids: BTreeMap<String, String>,
+}
+
+impl ShadowSearchConfig {
+    /// Parses the gRPC address of the shadow cluster, defaulting to the default gRPC port.
+    pub fn endpoint_addr(&self) -> anyhow::Result<Option<HostAddr>> {
+        self.endpoint
+            .as_ref()
+            .map(|endpoint| HostAddr::parse_with_default_port(endpoint, 7281))
+            .transpose()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(self.sample_rate > 0.0 && self.sample_rate <= 1.0) {
+            bail!(
+                "Shadow search `sample_rate` must be in the range (0, 1], got `{}`.",
+                self.sample_rate
+            );
+        }
+        if self.endpoint_addr()?.is_none() && self.index_ids.is_empty() {
+            bail!("Shadow search config must specify at least one of `endpoint` and `index_ids`.");
+        }
+        for (index_id, shadow_index_id) in &self.index_ids {
+            validate_identifier("Index ID", index_id)?;
+            validate_identifier("Index ID", shadow_index_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Binds an API key to the parameters its search requests are locked to.
+///
+/// The search requests presenting the API key in an `Authorization: ApiKey <api_key>` header are
+/// rejected if they exceed the guardrails of the template.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestTemplateConfig {
+    pub api_key: String,
+    /// Patterns of the IDs of the indexes the requests can target, e.g. `logs-*`.
+    pub index_id_patterns: Vec<String>,
+    /// Maximum time range covered by the requests. The requests missing a bound of their time
+    /// range are restricted to the last `max_time_range_secs` seconds before their end
+    /// timestamp, or before now.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_time_range_secs: Option<NonZeroU64>,
+    /// Maximum number of hits the requests can return.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_hits: Option<u64>,
+    /// Filters, expressed in the query language, that the documents returned to the requests
+    /// must match, e.g. `tenant_id:acme`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+}
+
+impl RequestTemplateConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.api_key.trim().is_empty() {
+            bail!("Request template `api_key` must not be empty.");
+        }
+        if self.index_id_patterns.is_empty() {
+            bail!("Request template must specify at least one index ID pattern.");
+        }
+        for index_id_pattern in &self.index_id_patterns {
+            // The wildcards are replaced by a character valid in an index ID.
+            validate_identifier("Index ID pattern", &index_id_pattern.replace('*', "x"))?;
+        }
+        if self.filters.iter().any(|filter| filter.trim().is_empty()) {
+            bail!("Request template filters must not be empty.");
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct IngestApiConfig {
@@ -221,6 +455,75 @@ impl Default for JaegerConfig {
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClusterHistoryConfig {
+    /// Enables the periodic recording of the cluster state (members, indexing pipelines
+    /// placement, and node health) into an internal index for postmortem analysis. Snapshots are
+    /// recorded by the node running the control plane.
+    #[serde(default)]
+    pub enable_recording: bool,
+    /// Interval between two consecutive snapshots of the cluster state.
+    #[serde(default = "ClusterHistoryConfig::default_snapshot_interval_secs")]
+    snapshot_interval_secs: NonZeroU64,
+}
+
+impl ClusterHistoryConfig {
+    pub fn snapshot_interval(&self) -> Duration {
+        Duration::from_secs(self.snapshot_interval_secs.get())
+    }
+
+    fn default_snapshot_interval_secs() -> NonZeroU64 {
+        NonZeroU64::new(60).unwrap() // 1 minute
+    }
+}
+
+impl Default for ClusterHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enable_recording: false,
+            snapshot_interval_secs: Self::default_snapshot_interval_secs(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SplitUpgradeConfig {
+    /// Enables the background job, run by the janitor, that rewrites splits written with an older
+    /// split format version.
+    #[serde(default)]
+    pub enable_upgrades: bool,
+    /// Maximum number of splits being upgraded concurrently per index.
+    #[serde(default = "SplitUpgradeConfig::default_max_concurrent_upgrades")]
+    pub max_concurrent_upgrades: NonZeroUsize,
+}
+
+impl SplitUpgradeConfig {
+    fn default_max_concurrent_upgrades() -> NonZeroUsize {
+        NonZeroUsize::new(1).unwrap()
+    }
+}
+
+impl Default for SplitUpgradeConfig {
+    fn default() -> Self {
+        Self {
+            enable_upgrades: false,
+            max_concurrent_upgrades: Self::default_max_concurrent_upgrades(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UiPreferencesConfig {
+    /// Enables the API persisting the saved searches, column layouts, and time range presets of
+    /// the UI users into an internal index. The index is created by the node running the control
+    /// plane.
+    #[serde(default)]
+    pub enable_api: bool,
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct QuickwitConfig {
     pub cluster_id: String,
@@ -242,6 +545,9 @@ pub struct QuickwitConfig {
     pub searcher_config: SearcherConfig,
     pub ingest_api_config: IngestApiConfig,
     pub jaeger_config: JaegerConfig,
+    pub cluster_history_config: ClusterHistoryConfig,
+    pub split_upgrade_config: SplitUpgradeConfig,
+    pub ui_preferences_config: UiPreferencesConfig,
 }
 
 impl QuickwitConfig {
@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 
@@ -29,13 +29,15 @@ use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
 use crate::config_value::ConfigValue;
+use crate::env_overlays::{parse_with_env_overlays, NODE_CONFIG_ENV_OVERLAY_PREFIX};
 use crate::qw_env_vars::*;
 use crate::service::QuickwitService;
 use crate::storage_config::StorageConfigs;
 use crate::templating::render_config;
 use crate::{
-    validate_identifier, validate_node_id, ConfigFormat, IndexerConfig, IngestApiConfig,
-    JaegerConfig, MetastoreConfigs, QuickwitConfig, SearcherConfig,
+    validate_identifier, validate_node_id, ClusterHistoryConfig, ConfigFormat, IndexerConfig,
+    IngestApiConfig, JaegerConfig, MetastoreConfigs, QuickwitConfig, SearcherConfig,
+    SplitCacheConfig, SplitUpgradeConfig, UiPreferencesConfig,
 };
 
 pub const DEFAULT_CLUSTER_ID: &str = "quickwit-default-cluster";
@@ -130,8 +132,12 @@ pub async fn load_quickwit_config_with_env(
     env_vars: &HashMap<String, String>,
 ) -> anyhow::Result<QuickwitConfig> {
     let rendered_config_content = render_config(config_content)?;
-    let versioned_quickwit_config: VersionedQuickwitConfig =
-        config_format.parse(rendered_config_content.as_bytes())?;
+    let versioned_quickwit_config: VersionedQuickwitConfig = parse_with_env_overlays(
+        config_format,
+        rendered_config_content.as_bytes(),
+        NODE_CONFIG_ENV_OVERLAY_PREFIX,
+        env_vars,
+    )?;
     let quickwit_config_builder: QuickwitConfigBuilder = versioned_quickwit_config.into();
     let config = quickwit_config_builder.build_and_validate(env_vars).await?;
     Ok(config)
@@ -200,6 +206,15 @@ struct QuickwitConfigBuilder {
     #[serde(rename = "jaeger")]
     #[serde(default)]
     jaeger_config: JaegerConfig,
+    #[serde(rename = "cluster_history")]
+    #[serde(default)]
+    cluster_history_config: ClusterHistoryConfig,
+    #[serde(rename = "split_upgrade")]
+    #[serde(default)]
+    split_upgrade_config: SplitUpgradeConfig,
+    #[serde(rename = "ui_preferences")]
+    #[serde(default)]
+    ui_preferences_config: UiPreferencesConfig,
 }
 
 impl QuickwitConfigBuilder {
@@ -286,6 +301,9 @@ impl QuickwitConfigBuilder {
             searcher_config: self.searcher_config,
             ingest_api_config: self.ingest_api_config,
             jaeger_config: self.jaeger_config,
+            cluster_history_config: self.cluster_history_config,
+            split_upgrade_config: self.split_upgrade_config,
+            ui_preferences_config: self.ui_preferences_config,
         };
 
         validate(&quickwit_config)?;
@@ -297,6 +315,30 @@ fn validate(quickwit_config: &QuickwitConfig) -> anyhow::Result<()> {
     validate_identifier("Cluster ID", &quickwit_config.cluster_id)?;
     validate_node_id(&quickwit_config.node_id)?;
 
+    let mut udf_names = HashSet::new();
+    for udf_config in &quickwit_config.searcher_config.udfs {
+        udf_config.validate()?;
+        if !udf_names.insert(&udf_config.name) {
+            bail!("UDF `{}` is defined more than once.", udf_config.name);
+        }
+    }
+    quickwit_config.searcher_config.split_cache.validate()?;
+
+    if let Some(shadow_search_config) = &quickwit_config.searcher_config.shadow_search {
+        shadow_search_config.validate()?;
+    }
+    let mut api_keys = HashSet::new();
+    for request_template_config in &quickwit_config.searcher_config.request_templates {
+        request_template_config.validate()?;
+        if !api_keys.insert(&request_template_config.api_key) {
+            bail!("API key is bound to more than one request template.");
+        }
+    }
+
+    if let Some(geoip_config) = &quickwit_config.indexer_config.geoip {
+        geoip_config.validate()?;
+    }
+
     if quickwit_config.cluster_id == DEFAULT_CLUSTER_ID {
         warn!(
             "Cluster ID is not set, falling back to default value: `{}`.",
@@ -332,6 +374,9 @@ impl Default for QuickwitConfigBuilder {
             searcher_config: SearcherConfig::default(),
             ingest_api_config: IngestApiConfig::default(),
             jaeger_config: JaegerConfig::default(),
+            cluster_history_config: ClusterHistoryConfig::default(),
+            split_upgrade_config: SplitUpgradeConfig::default(),
+            ui_preferences_config: UiPreferencesConfig::default(),
         }
     }
 }
@@ -386,6 +431,9 @@ pub fn quickwit_config_for_test() -> QuickwitConfig {
         searcher_config: SearcherConfig::default(),
         ingest_api_config: IngestApiConfig::default(),
         jaeger_config: JaegerConfig::default(),
+        cluster_history_config: ClusterHistoryConfig::default(),
+        split_upgrade_config: SplitUpgradeConfig::default(),
+        ui_preferences_config: UiPreferencesConfig::default(),
     }
 }
 
@@ -393,13 +441,14 @@ pub fn quickwit_config_for_test() -> QuickwitConfig {
 mod tests {
     use std::env;
     use std::net::Ipv4Addr;
-    use std::num::NonZeroU64;
-    use std::path::Path;
+    use std::num::{NonZeroU64, NonZeroUsize};
+    use std::path::{Path, PathBuf};
 
     use byte_unit::Byte;
     use itertools::Itertools;
 
     use super::*;
+    use crate::{RemoteCacheProtocol, RequestTemplateConfig};
 
     fn get_config_filepath(config_filename: &str) -> String {
         format!(
@@ -482,6 +531,7 @@ mod tests {
                 split_store_max_num_splits: 10_000,
                 max_concurrent_split_uploads: 8,
                 enable_cooperative_indexing: false,
+                geoip: None,
             }
         );
         assert_eq!(
@@ -491,9 +541,14 @@ mod tests {
                 aggregation_bucket_limit: 500_000,
                 fast_field_cache_capacity: Byte::from_str("10G").unwrap(),
                 split_footer_cache_capacity: Byte::from_str("1G").unwrap(),
+                split_footer_cache_per_index_capacity: Some(Byte::from_str("250M").unwrap()),
                 partial_request_cache_capacity: Byte::from_str("64M").unwrap(),
                 max_num_concurrent_split_searches: 150,
                 max_num_concurrent_split_streams: 120,
+                udfs: Vec::new(),
+                split_cache: SplitCacheConfig::default(),
+                shadow_search: None,
+                request_templates: Vec::new(),
             }
         );
         assert_eq!(
@@ -505,6 +560,24 @@ mod tests {
                 max_fetch_spans: NonZeroU64::new(1_000).unwrap(),
             }
         );
+        assert_eq!(
+            config.cluster_history_config,
+            ClusterHistoryConfig {
+                enable_recording: true,
+                snapshot_interval_secs: NonZeroU64::new(30).unwrap(),
+            }
+        );
+        assert_eq!(
+            config.split_upgrade_config,
+            SplitUpgradeConfig {
+                enable_upgrades: true,
+                max_concurrent_upgrades: NonZeroUsize::new(2).unwrap(),
+            }
+        );
+        assert_eq!(
+            config.ui_preferences_config,
+            UiPreferencesConfig { enable_api: true }
+        );
         Ok(())
     }
 
@@ -673,6 +746,50 @@ mod tests {
         assert_eq!(config.default_index_root_uri, "s3://quickwit-indexes/prod");
     }
 
+    #[tokio::test]
+    async fn test_quickwit_config_env_overlays() {
+        let config_yaml = r#"
+            version: 0.6
+            node_id: node-from-file
+            rest_listen_port: 1111
+            indexer:
+              split_store_max_num_splits: 100
+        "#;
+        let mut env_vars = HashMap::new();
+        env_vars.insert("QW__NODE_ID".to_string(), "node-from-overlay".to_string());
+        env_vars.insert("QW__REST_LISTEN_PORT".to_string(), "2222".to_string());
+        env_vars.insert(
+            "QW__INDEXER__SPLIT_STORE_MAX_NUM_SPLITS".to_string(),
+            "500".to_string(),
+        );
+        env_vars.insert(
+            "QW__SEARCHER__FAST_FIELD_CACHE_CAPACITY".to_string(),
+            "4G".to_string(),
+        );
+        // Dedicated environment variables take precedence over overlays.
+        env_vars.insert("QW_REST_LISTEN_PORT".to_string(), "3333".to_string());
+
+        let config =
+            load_quickwit_config_with_env(ConfigFormat::Yaml, config_yaml.as_bytes(), &env_vars)
+                .await
+                .unwrap();
+        assert_eq!(config.node_id, "node-from-overlay");
+        assert_eq!(config.rest_listen_addr.port(), 3333);
+        assert_eq!(config.indexer_config.split_store_max_num_splits, 500);
+        assert_eq!(
+            config.searcher_config.fast_field_cache_capacity,
+            Byte::from_str("4G").unwrap()
+        );
+
+        env_vars.insert(
+            "QW__INDEXER__SPLIT_STORE_MAX_NUM_SPLITS".to_string(),
+            "many".to_string(),
+        );
+        load_quickwit_config_with_env(ConfigFormat::Yaml, config_yaml.as_bytes(), &env_vars)
+            .await
+            .unwrap_err();
+    }
+
     #[tokio::test]
     async fn test_quickwwit_config_default_values_storage() {
         let config_yaml = r#"
@@ -717,6 +834,280 @@ mod tests {
         assert_eq!(config.searcher_config, SearcherConfig::default());
         assert_eq!(config.ingest_api_config, IngestApiConfig::default());
         assert_eq!(config.jaeger_config, JaegerConfig::default());
+        assert_eq!(
+            config.cluster_history_config,
+            ClusterHistoryConfig::default()
+        );
+        assert_eq!(config.split_upgrade_config, SplitUpgradeConfig::default());
+        assert_eq!(config.ui_preferences_config, UiPreferencesConfig::default());
+    }
+
+    #[tokio::test]
+    async fn test_quickwit_config_geoip() {
+        let config_yaml = r#"
+            version: 0.6
+            indexer:
+              geoip:
+                city_database_uri: s3://my-bucket/GeoLite2-City.mmdb
+                asn_database_uri: s3://my-bucket/GeoLite2-ASN.mmdb
+        "#;
+        let config = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        let geoip_config = config.indexer_config.geoip.unwrap();
+        assert_eq!(
+            geoip_config.city_database_uri.unwrap(),
+            "s3://my-bucket/GeoLite2-City.mmdb"
+        );
+        assert_eq!(
+            geoip_config.asn_database_uri.unwrap(),
+            "s3://my-bucket/GeoLite2-ASN.mmdb"
+        );
+        assert_eq!(geoip_config.refresh_interval_secs, 86_400);
+
+        let config_yaml = r#"
+            version: 0.6
+            indexer:
+              geoip:
+                refresh_interval_secs: 3600
+        "#;
+        let error = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:#}").contains("at least one of"));
+    }
+
+    #[tokio::test]
+    async fn test_quickwit_config_split_cache() {
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              split_cache:
+                backend: disk
+                root_dir: /var/cache/quickwit
+                capacity: 50G
+        "#;
+        let config = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.searcher_config.split_cache,
+            SplitCacheConfig::Disk {
+                root_dir: PathBuf::from("/var/cache/quickwit"),
+                capacity: Byte::from_str("50G").unwrap(),
+            }
+        );
+
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              split_cache:
+                backend: remote
+                protocol: redis
+                endpoint: redis.example.com:6379
+                ttl_secs: 3600
+        "#;
+        let config = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.searcher_config.split_cache,
+            SplitCacheConfig::Remote {
+                protocol: RemoteCacheProtocol::Redis,
+                endpoint: "redis.example.com:6379".to_string(),
+                ttl_secs: NonZeroU64::new(3600),
+                max_item_size: Byte::from_bytes(1_000_000),
+            }
+        );
+
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              split_cache:
+                backend: remote
+                protocol: memcached
+                endpoint: memcached.example.com:11211
+                max_item_size: 500KB
+        "#;
+        let config = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.searcher_config.split_cache,
+            SplitCacheConfig::Remote {
+                protocol: RemoteCacheProtocol::Memcached,
+                endpoint: "memcached.example.com:11211".to_string(),
+                ttl_secs: None,
+                max_item_size: Byte::from_bytes(500_000),
+            }
+        );
+
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              split_cache:
+                backend: disk
+                root_dir: /var/cache/quickwit
+                capacity: 0
+        "#;
+        let error = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:#}").contains("must be strictly positive"));
+    }
+
+    #[tokio::test]
+    async fn test_quickwit_config_shadow_search() {
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              shadow_search:
+                sample_rate: 0.05
+                endpoint: searcher.shadow.example.com
+                index_ids:
+                  hdfs-logs: hdfs-logs-v2
+        "#;
+        let config = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        let shadow_search_config = config.searcher_config.shadow_search.unwrap();
+        assert_eq!(shadow_search_config.sample_rate, 0.05);
+        assert_eq!(
+            shadow_search_config
+                .endpoint_addr()
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "searcher.shadow.example.com:7281"
+        );
+        assert_eq!(
+            shadow_search_config.index_ids.get("hdfs-logs").unwrap(),
+            "hdfs-logs-v2"
+        );
+
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              shadow_search:
+                sample_rate: 1.5
+                endpoint: searcher.shadow.example.com:7281
+        "#;
+        let error = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:#}").contains("must be in the range (0, 1]"));
+
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              shadow_search:
+                sample_rate: 0.1
+        "#;
+        let error = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:#}").contains("at least one of `endpoint` and `index_ids`"));
+    }
+
+    #[tokio::test]
+    async fn test_quickwit_config_request_templates() {
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              request_templates:
+                - api_key: acme-analyst-key
+                  index_id_patterns: [acme-logs-*]
+                  max_time_range_secs: 86400
+                  max_hits: 100
+                  filters: ["tenant_id:acme"]
+        "#;
+        let config = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.searcher_config.request_templates,
+            [RequestTemplateConfig {
+                api_key: "acme-analyst-key".to_string(),
+                index_id_patterns: vec!["acme-logs-*".to_string()],
+                max_time_range_secs: NonZeroU64::new(86400),
+                max_hits: Some(100),
+                filters: vec!["tenant_id:acme".to_string()],
+            }]
+        );
+
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              request_templates:
+                - api_key: acme-analyst-key
+                  index_id_patterns: [acme-logs-*]
+                - api_key: acme-analyst-key
+                  index_id_patterns: [acme-traces-*]
+        "#;
+        let error = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:#}").contains("bound to more than one request template"));
+
+        let config_yaml = r#"
+            version: 0.6
+            searcher:
+              request_templates:
+                - api_key: acme-analyst-key
+                  index_id_patterns: []
+        "#;
+        let error = load_quickwit_config_with_env(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:#}").contains("at least one index ID pattern"));
     }
 
     #[tokio::test]
@@ -0,0 +1,451 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::validate_identifier;
+
+/// Maximum number of search templates that can be attached to an index.
+pub const MAX_NUM_SEARCH_TEMPLATES: usize = 64;
+
+/// Maximum nesting depth of the sections of a search template.
+const MAX_SECTION_DEPTH: usize = 16;
+
+/// A named search request body containing Mustache-like placeholders, rendered with parameters
+/// supplied at query time. Search templates let clients run parameterized searches without
+/// generating the request body themselves.
+///
+/// The following tags are supported:
+/// - `{{name}}`: the value of the parameter, JSON-escaped if it is a string;
+/// - `{{{name}}}`: the value of the parameter, unescaped;
+/// - `{{#toJson}}name{{/toJson}}`: the JSON representation of the parameter;
+/// - `{{#name}}...{{/name}}`: a section rendered if the parameter is truthy, once per item if it
+///   is an array;
+/// - `{{^name}}...{{/name}}`: a section rendered if the parameter is missing or falsy.
+///
+/// Parameter names may contain dots to access nested objects and `.` refers to the current item
+/// of an array section.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SearchTemplate {
+    /// Elasticsearch search request body with placeholders. It is provided either as a string
+    /// or, if it is valid JSON as is, as a JSON object.
+    #[serde(deserialize_with = "deserialize_template_source")]
+    pub source: String,
+}
+
+fn deserialize_template_source<'de, D>(deserializer: D) -> Result<String, D::Error>
+where D: Deserializer<'de> {
+    match JsonValue::deserialize(deserializer)? {
+        JsonValue::String(source) => Ok(source),
+        source => Ok(source.to_string()),
+    }
+}
+
+impl SearchTemplate {
+    /// Renders the template with the given parameters.
+    pub fn render(&self, params: &JsonMap<String, JsonValue>) -> anyhow::Result<String> {
+        let nodes = parse_template(&self.source)?;
+        let params = JsonValue::Object(params.clone());
+        let mut rendered = String::with_capacity(self.source.len());
+        render_nodes(&nodes, &mut vec![&params], &mut rendered);
+        Ok(rendered)
+    }
+
+    /// Renders the template with the given parameters and parses the result as JSON.
+    pub fn render_json(&self, params: &JsonMap<String, JsonValue>) -> anyhow::Result<JsonValue> {
+        let rendered = self.render(params)?;
+        serde_json::from_str(&rendered).map_err(|error| {
+            anyhow::anyhow!("The rendered search template is not valid JSON: {error}.")
+        })
+    }
+}
+
+/// Checks that a set of search templates is valid: template names must be valid identifiers and
+/// the placeholders of each template must be well-formed.
+pub fn validate_search_templates(
+    search_templates: &BTreeMap<String, SearchTemplate>,
+) -> anyhow::Result<()> {
+    if search_templates.len() > MAX_NUM_SEARCH_TEMPLATES {
+        bail!(
+            "Too many search templates: {} (max {MAX_NUM_SEARCH_TEMPLATES}).",
+            search_templates.len()
+        );
+    }
+    for (template_name, search_template) in search_templates {
+        validate_identifier("Search template", template_name)?;
+
+        parse_template(&search_template.source)
+            .with_context(|| format!("Search template `{template_name}` is invalid."))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Node<'a> {
+    Text(&'a str),
+    Variable {
+        name: &'a str,
+        escape: bool,
+    },
+    ToJson(&'a str),
+    Section {
+        name: &'a str,
+        inverted: bool,
+        children: Vec<Node<'a>>,
+    },
+}
+
+fn parse_template(source: &str) -> anyhow::Result<Vec<Node>> {
+    let mut remaining = source;
+    let nodes = parse_nodes(&mut remaining, None, 0)?;
+    Ok(nodes)
+}
+
+/// Parses nodes until the end of the template or until the closing tag of the enclosing section.
+fn parse_nodes<'a>(
+    remaining: &mut &'a str,
+    enclosing_section_opt: Option<&str>,
+    depth: usize,
+) -> anyhow::Result<Vec<Node<'a>>> {
+    if depth > MAX_SECTION_DEPTH {
+        bail!("Sections cannot be nested more than {MAX_SECTION_DEPTH} levels deep.");
+    }
+    let mut nodes = Vec::new();
+
+    while !remaining.is_empty() {
+        let Some(tag_start) = remaining.find("{{") else {
+            nodes.push(Node::Text(remaining));
+            *remaining = "";
+            break;
+        };
+        if tag_start > 0 {
+            nodes.push(Node::Text(&remaining[..tag_start]));
+        }
+        let after_tag_start = &remaining[tag_start..];
+
+        if let Some(tag_body) = after_tag_start.strip_prefix("{{{") {
+            let tag_end = tag_body
+                .find("}}}")
+                .context("Unclosed tag `{{{`: expected `}}}`.")?;
+            nodes.push(Node::Variable {
+                name: parse_name(&tag_body[..tag_end])?,
+                escape: false,
+            });
+            *remaining = &tag_body[tag_end + 3..];
+            continue;
+        }
+        let tag_body = &after_tag_start[2..];
+        let tag_end = tag_body
+            .find("}}")
+            .context("Unclosed tag `{{`: expected `}}`.")?;
+        let tag = tag_body[..tag_end].trim();
+        *remaining = &tag_body[tag_end + 2..];
+
+        if let Some(section_name) = tag.strip_prefix('/') {
+            let section_name = parse_name(section_name)?;
+
+            if enclosing_section_opt != Some(section_name) {
+                bail!("Unexpected closing tag `{{{{/{section_name}}}}}`.");
+            }
+            return Ok(nodes);
+        }
+        if let Some(section_name) = tag.strip_prefix('#') {
+            let section_name = parse_name(section_name)?;
+
+            if section_name == "toJson" {
+                let Some(closing_tag_start) = remaining.find("{{/toJson}}") else {
+                    bail!("Unclosed section `toJson`.");
+                };
+                let name = parse_name(&remaining[..closing_tag_start])?;
+                nodes.push(Node::ToJson(name));
+                *remaining = &remaining[closing_tag_start + "{{/toJson}}".len()..];
+                continue;
+            }
+            let children = parse_nodes(remaining, Some(section_name), depth + 1)?;
+            nodes.push(Node::Section {
+                name: section_name,
+                inverted: false,
+                children,
+            });
+            continue;
+        }
+        if let Some(section_name) = tag.strip_prefix('^') {
+            let section_name = parse_name(section_name)?;
+            let children = parse_nodes(remaining, Some(section_name), depth + 1)?;
+            nodes.push(Node::Section {
+                name: section_name,
+                inverted: true,
+                children,
+            });
+            continue;
+        }
+        nodes.push(Node::Variable {
+            name: parse_name(tag)?,
+            escape: true,
+        });
+    }
+    if let Some(section_name) = enclosing_section_opt {
+        bail!("Unclosed section `{section_name}`.");
+    }
+    Ok(nodes)
+}
+
+fn parse_name(name: &str) -> anyhow::Result<&str> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        bail!("Tags must reference a parameter.");
+    }
+    if name != "."
+        && !name
+            .split('.')
+            .all(|part| !part.is_empty() && !part.contains(char::is_whitespace))
+    {
+        bail!("Invalid parameter name `{name}`.");
+    }
+    Ok(name)
+}
+
+/// Resolves a parameter name against the context stack, innermost context first.
+fn lookup<'a>(context_stack: &[&'a JsonValue], name: &str) -> Option<&'a JsonValue> {
+    let innermost_context = context_stack.last()?;
+
+    if name == "." {
+        return Some(innermost_context);
+    }
+    let mut parts = name.split('.');
+    let first_part = parts.next()?;
+    let mut value = context_stack
+        .iter()
+        .rev()
+        .find_map(|context| context.get(first_part))?;
+
+    for part in parts {
+        value = value.get(part)?;
+    }
+    Some(value)
+}
+
+fn is_truthy(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Null => false,
+        JsonValue::Bool(value) => *value,
+        JsonValue::String(value) => !value.is_empty(),
+        JsonValue::Array(values) => !values.is_empty(),
+        JsonValue::Number(_) | JsonValue::Object(_) => true,
+    }
+}
+
+fn render_nodes<'a>(nodes: &[Node], context_stack: &mut Vec<&'a JsonValue>, rendered: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => rendered.push_str(text),
+            Node::Variable { name, escape } => {
+                let Some(value) = lookup(context_stack, name) else {
+                    continue;
+                };
+                match value {
+                    JsonValue::Null => {}
+                    JsonValue::String(value) if *escape => {
+                        let escaped = JsonValue::String(value.clone()).to_string();
+                        rendered.push_str(&escaped[1..escaped.len() - 1]);
+                    }
+                    JsonValue::String(value) => rendered.push_str(value),
+                    value => rendered.push_str(&value.to_string()),
+                }
+            }
+            Node::ToJson(name) => {
+                let value = lookup(context_stack, name).unwrap_or(&JsonValue::Null);
+                rendered.push_str(&value.to_string());
+            }
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let value_opt = lookup(context_stack, name);
+                let is_truthy = value_opt.map_or(false, is_truthy);
+
+                if *inverted {
+                    if !is_truthy {
+                        render_nodes(children, context_stack, rendered);
+                    }
+                    continue;
+                }
+                let Some(value) = value_opt.filter(|_| is_truthy) else {
+                    continue;
+                };
+                let items: &[JsonValue] = match value {
+                    JsonValue::Array(items) => items,
+                    value => std::slice::from_ref(value),
+                };
+                for item in items {
+                    context_stack.push(item);
+                    render_nodes(children, context_stack, rendered);
+                    context_stack.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render_for_test(source: &str, params: JsonValue) -> anyhow::Result<String> {
+        let search_template = SearchTemplate {
+            source: source.to_string(),
+        };
+        let JsonValue::Object(params) = params else {
+            panic!("Parameters should be a JSON object.");
+        };
+        search_template.render(&params)
+    }
+
+    #[test]
+    fn test_search_template_deserialize_source() {
+        let search_template: SearchTemplate =
+            serde_json::from_str(r#"{"source": "{\"size\": {{size}}}"}"#).unwrap();
+        assert_eq!(search_template.source, r#"{"size": {{size}}}"#);
+
+        let search_template: SearchTemplate = serde_json::from_value(json!({
+            "source": {"query": {"term": {"user": "{{user}}"}}}
+        }))
+        .unwrap();
+        assert_eq!(
+            search_template.source,
+            r#"{"query":{"term":{"user":"{{user}}"}}}"#
+        );
+    }
+
+    #[test]
+    fn test_search_template_render_variables() {
+        let rendered = render_for_test(
+            r#"{"query": {"query_string": {"query": "{{query}}"}}, "size": {{size}}}"#,
+            json!({"query": "body:\"hello world\"", "size": 20}),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            r#"{"query": {"query_string": {"query": "body:\"hello world\""}}, "size": 20}"#
+        );
+
+        let rendered = render_for_test(
+            "{{{raw}}}|{{missing}}|{{null}}|{{ user.name }}",
+            json!({"raw": "\"", "null": null, "user": {"name": "Gus"}}),
+        )
+        .unwrap();
+        assert_eq!(rendered, r#""|||Gus"#);
+
+        let rendered = render_for_test(
+            r#"{"terms": {{#toJson}}tags{{/toJson}}}"#,
+            json!({"tags": ["a", "b"]}),
+        )
+        .unwrap();
+        assert_eq!(rendered, r#"{"terms": ["a","b"]}"#);
+    }
+
+    #[test]
+    fn test_search_template_render_sections() {
+        let source = r#"{{#size}}"size": {{size}}{{/size}}{{^size}}"size": 10{{/size}}"#;
+        let rendered = render_for_test(source, json!({"size": 5})).unwrap();
+        assert_eq!(rendered, r#""size": 5"#);
+
+        let rendered = render_for_test(source, json!({})).unwrap();
+        assert_eq!(rendered, r#""size": 10"#);
+
+        let rendered = render_for_test(
+            "{{#users}}{{name}}@{{domain}},{{/users}}",
+            json!({"domain": "quickwit.io", "users": [{"name": "paul"}, {"name": "fulmicoton"}]}),
+        )
+        .unwrap();
+        assert_eq!(rendered, "paul@quickwit.io,fulmicoton@quickwit.io,");
+
+        let rendered =
+            render_for_test("{{#tags}}[{{.}}]{{/tags}}", json!({"tags": ["a", "b"]})).unwrap();
+        assert_eq!(rendered, "[a][b]");
+
+        let rendered = render_for_test("{{#empty}}never{{/empty}}", json!({"empty": []})).unwrap();
+        assert_eq!(rendered, "");
+    }
+
+    #[test]
+    fn test_search_template_render_json() {
+        let search_template = SearchTemplate {
+            source: r#"{"size": {{size}}}"#.to_string(),
+        };
+        let params = JsonMap::from_iter([("size".to_string(), json!(3))]);
+        assert_eq!(
+            search_template.render_json(&params).unwrap(),
+            json!({"size": 3})
+        );
+
+        let error = search_template.render_json(&JsonMap::new()).unwrap_err();
+        assert!(error.to_string().contains("not valid JSON"));
+    }
+
+    #[test]
+    fn test_search_template_parse_errors() {
+        for (source, expected_error) in [
+            ("{{query", "Unclosed tag `{{`"),
+            ("{{{query}}", "Unclosed tag `{{{`"),
+            ("{{}}", "must reference a parameter"),
+            ("{{#size}}", "Unclosed section `size`"),
+            ("{{#size}}{{/from}}", "Unexpected closing tag `{{/from}}`"),
+            ("{{/size}}", "Unexpected closing tag `{{/size}}`"),
+            ("{{#toJson}}tags", "Unclosed section `toJson`"),
+            ("{{user..name}}", "Invalid parameter name `user..name`"),
+        ] {
+            let error = render_for_test(source, json!({})).unwrap_err();
+            assert!(
+                error.to_string().contains(expected_error),
+                "`{source}`: {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_search_templates() {
+        let search_template = SearchTemplate {
+            source: r#"{"size": {{size}}}"#.to_string(),
+        };
+        let search_templates = BTreeMap::from([("by_size".to_string(), search_template.clone())]);
+        validate_search_templates(&search_templates).unwrap();
+
+        let search_templates = BTreeMap::from([("0-invalid".to_string(), search_template)]);
+        validate_search_templates(&search_templates).unwrap_err();
+
+        let search_templates = BTreeMap::from([(
+            "by_size".to_string(),
+            SearchTemplate {
+                source: "{{#size}}".to_string(),
+            },
+        )]);
+        let error = validate_search_templates(&search_templates).unwrap_err();
+        assert_eq!(error.to_string(), "Search template `by_size` is invalid.");
+    }
+}
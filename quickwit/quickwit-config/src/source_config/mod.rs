@@ -19,6 +19,7 @@
 
 pub(crate) mod serialize;
 
+use std::collections::BTreeMap;
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -83,6 +84,11 @@ pub struct SourceConfig {
     // Denotes the input data format.
     #[serde(default)]
     pub input_format: SourceInputFormat,
+
+    /// Arbitrary key/value pairs used to organize sources (e.g. `team=payments`).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 }
 
 impl SourceConfig {
@@ -124,6 +130,7 @@ impl SourceConfig {
             source_params: SourceParams::IngestApi,
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: BTreeMap::new(),
         }
     }
 
@@ -137,6 +144,7 @@ impl SourceConfig {
             source_params: SourceParams::IngestCli,
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: BTreeMap::new(),
         }
     }
 
@@ -150,6 +158,7 @@ impl SourceConfig {
             source_params,
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: BTreeMap::new(),
         }
     }
 }
@@ -172,6 +181,7 @@ impl TestableForRegression for SourceConfig {
                 timezone_opt: None,
             }),
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         }
     }
 
@@ -187,6 +197,9 @@ pub enum SourceInputFormat {
     Json,
     #[serde(alias = "plain")]
     PlainText,
+    /// Delimiter-separated values, only supported by file sources. The file source converts the
+    /// records into JSON objects according to [`CsvParams`].
+    Csv,
 }
 
 impl FromStr for SourceInputFormat {
@@ -196,6 +209,7 @@ impl FromStr for SourceInputFormat {
         match format_str {
             "json" => Ok(Self::Json),
             "plain" => Ok(Self::PlainText),
+            "csv" => Ok(Self::Csv),
             unknown => Err(format!("Unknown source input format: `{unknown}`.")),
         }
     }
@@ -245,6 +259,10 @@ pub struct FileSourceParams {
     #[serde(default)]
     #[serde(deserialize_with = "absolute_filepath_from_str")]
     pub filepath: Option<PathBuf>, //< If None read from stdin.
+    /// Parsing options used when the input format is `csv`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "CsvParams::is_default")]
+    pub csv: CsvParams,
 }
 
 // Deserializing a filepath string into an absolute filepath.
@@ -263,11 +281,113 @@ impl FileSourceParams {
     pub fn file<P: AsRef<Path>>(filepath: P) -> Self {
         FileSourceParams {
             filepath: Some(filepath.as_ref().to_path_buf()),
+            csv: CsvParams::default(),
         }
     }
 
     pub fn stdin() -> Self {
-        FileSourceParams { filepath: None }
+        FileSourceParams {
+            filepath: None,
+            csv: CsvParams::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CsvParams {
+    /// Character separating the fields of a record, `,` by default. Use `\t` for TSV files.
+    #[serde(default = "CsvParams::default_delimiter")]
+    pub delimiter: char,
+    /// Character quoting fields containing delimiters or line breaks.
+    #[serde(default = "CsvParams::default_quote")]
+    pub quote: char,
+    /// Whether the first record holds the column names.
+    #[serde(default = "CsvParams::default_has_header")]
+    pub has_header: bool,
+    /// Column names. When set, they take precedence over the header record.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    /// Explicit column types. Columns missing from this map have their type inferred from their
+    /// values if `infer_types` is set, and are kept as strings otherwise.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub column_types: BTreeMap<String, CsvColumnType>,
+    #[serde(default = "CsvParams::default_infer_types")]
+    pub infer_types: bool,
+}
+
+impl Default for CsvParams {
+    fn default() -> Self {
+        Self {
+            delimiter: Self::default_delimiter(),
+            quote: Self::default_quote(),
+            has_header: Self::default_has_header(),
+            columns: Vec::new(),
+            column_types: BTreeMap::new(),
+            infer_types: Self::default_infer_types(),
+        }
+    }
+}
+
+impl CsvParams {
+    fn default_delimiter() -> char {
+        ','
+    }
+
+    fn default_quote() -> char {
+        '"'
+    }
+
+    fn default_has_header() -> bool {
+        true
+    }
+
+    fn default_infer_types() -> bool {
+        true
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.delimiter.is_ascii() || !self.quote.is_ascii() {
+            bail!("CSV delimiter and quote characters must be ASCII characters.");
+        }
+        if self.delimiter == self.quote {
+            bail!("CSV delimiter and quote characters must differ.");
+        }
+        if !self.has_header && self.columns.is_empty() {
+            bail!("CSV column names must be specified when the input has no header record.");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvColumnType {
+    Text,
+    I64,
+    U64,
+    F64,
+    Bool,
+}
+
+impl FromStr for CsvColumnType {
+    type Err = String;
+
+    fn from_str(type_str: &str) -> Result<Self, String> {
+        match type_str {
+            "text" => Ok(Self::Text),
+            "i64" => Ok(Self::I64),
+            "u64" => Ok(Self::U64),
+            "f64" => Ok(Self::F64),
+            "bool" => Ok(Self::Bool),
+            unknown => Err(format!("Unknown CSV column type: `{unknown}`.")),
+        }
     }
 }
 
@@ -519,6 +639,7 @@ mod tests {
                 timezone_opt: Some("local".to_string()),
             }),
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         assert_eq!(source_config, expected_source_config);
         assert_eq!(source_config.desired_num_pipelines.get(), 2);
@@ -615,6 +736,7 @@ mod tests {
                 timezone_opt: Some("local".to_string()),
             }),
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         assert_eq!(source_config, expected_source_config);
         assert_eq!(source_config.desired_num_pipelines.get(), 1);
@@ -1018,6 +1140,7 @@ mod tests {
                 timezone_opt: None,
             }),
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         assert_eq!(source_config, expected_source_config);
         assert_eq!(source_config.desired_num_pipelines.get(), 1);
@@ -1136,4 +1259,89 @@ mod tests {
                 .unwrap();
         assert_eq!(source_config.input_format, SourceInputFormat::PlainText);
     }
+
+    #[tokio::test]
+    async fn test_source_config_csv_input_format() {
+        let file_content = r#"{
+            "version": "0.6",
+            "source_id": "users-file-source",
+            "source_type": "file",
+            "params": {
+                "filepath": "/users.tsv",
+                "csv": {
+                    "delimiter": "\t",
+                    "has_header": false,
+                    "columns": ["name", "age"],
+                    "column_types": {"age": "u64"}
+                }
+            },
+            "input_format": "csv"
+        }"#;
+        let source_config =
+            load_source_config_from_user_config(ConfigFormat::Json, file_content.as_bytes())
+                .unwrap();
+        assert_eq!(source_config.input_format, SourceInputFormat::Csv);
+
+        let SourceParams::File(file_params) = source_config.source_params else {
+            panic!("Source params should be file source params.");
+        };
+        assert_eq!(
+            file_params.csv,
+            CsvParams {
+                delimiter: '\t',
+                has_header: false,
+                columns: vec!["name".to_string(), "age".to_string()],
+                column_types: BTreeMap::from_iter([("age".to_string(), CsvColumnType::U64)]),
+                ..Default::default()
+            }
+        );
+        let file_content = r#"{
+            "version": "0.6",
+            "source_id": "users-kafka-source",
+            "source_type": "kafka",
+            "params": {"topic": "users"},
+            "input_format": "csv"
+        }"#;
+        load_source_config_from_user_config(ConfigFormat::Json, file_content.as_bytes())
+            .unwrap_err();
+    }
+    #[tokio::test]
+    async fn test_source_config_labels() {
+        let file_content = r#"{
+            "version": "0.6",
+            "source_id": "hdfs-logs-void-source",
+            "source_type": "void",
+            "params": {},
+            "labels": {"team": "storage", "env": "prod"}
+        }"#;
+        let source_config =
+            load_source_config_from_user_config(ConfigFormat::Json, file_content.as_bytes())
+                .unwrap();
+        assert_eq!(
+            source_config.labels,
+            BTreeMap::from([
+                ("env".to_string(), "prod".to_string()),
+                ("team".to_string(), "storage".to_string()),
+            ])
+        );
+        let source_config_json = serde_json::to_value(&source_config).unwrap();
+        assert_eq!(
+            source_config_json["labels"],
+            serde_json::json!({"env": "prod", "team": "storage"})
+        );
+
+        let file_content = r#"{
+            "version": "0.6",
+            "source_id": "hdfs-logs-void-source",
+            "source_type": "void",
+            "params": {},
+            "labels": {"team": "pay,ments"}
+        }"#;
+        let error =
+            load_source_config_from_user_config(ConfigFormat::Json, file_content.as_bytes())
+                .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Label value `pay,ments` for key `team` is invalid"));
+    }
 }
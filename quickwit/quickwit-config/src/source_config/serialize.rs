@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::num::NonZeroUsize;
 
 use anyhow::bail;
@@ -24,8 +25,8 @@ use serde::{Deserialize, Serialize};
 
 use super::TransformConfig;
 use crate::{
-    validate_identifier, ConfigFormat, SourceConfig, SourceInputFormat, SourceParams,
-    CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
+    validate_identifier, validate_labels, ConfigFormat, SourceConfig, SourceInputFormat,
+    SourceParams, CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
 };
 
 type SourceConfigForSerialization = SourceConfigV0_6;
@@ -90,6 +91,9 @@ impl SourceConfigForSerialization {
                         self.source_id
                     )
                 }
+                if self.input_format == SourceInputFormat::Csv {
+                    file_params.csv.validate()?;
+                }
             }
             SourceParams::Kafka(_) | SourceParams::Kinesis(_) | SourceParams::Pulsar(_) => {
                 // TODO consider any validation opportunity
@@ -99,6 +103,11 @@ impl SourceConfigForSerialization {
             | SourceParams::IngestApi
             | SourceParams::IngestCli => {}
         }
+        if self.input_format == SourceInputFormat::Csv
+            && !matches!(self.source_params, SourceParams::File(_))
+        {
+            bail!("The `csv` input format is only supported by file sources.");
+        }
         match &self.source_params {
             SourceParams::Kafka(_) => {}
             _ => {
@@ -110,6 +119,7 @@ impl SourceConfigForSerialization {
         if let Some(transform_config) = &self.transform {
             transform_config.compile_vrl_script()?;
         }
+        validate_labels(&self.labels)?;
         Ok(SourceConfig {
             source_id: self.source_id,
             max_num_pipelines_per_indexer,
@@ -118,6 +128,7 @@ impl SourceConfigForSerialization {
             source_params: self.source_params,
             transform_config: self.transform,
             input_format: self.input_format,
+            labels: self.labels,
         })
     }
 }
@@ -132,6 +143,7 @@ impl From<SourceConfig> for SourceConfigV0_6 {
             source_params: source_config.source_params,
             transform: source_config.transform_config,
             input_format: source_config.input_format,
+            labels: source_config.labels,
         }
     }
 }
@@ -189,4 +201,8 @@ pub struct SourceConfigV0_6 {
     // Denotes the input data format.
     #[serde(default)]
     pub input_format: SourceInputFormat,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 }
@@ -0,0 +1,126 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use byte_unit::Byte;
+use quickwit_common::uri::Uri;
+use serde::{Deserialize, Serialize};
+
+use crate::validate_identifier;
+
+/// Registers a user-supplied WebAssembly function callable from the query DSL (see the `udf`
+/// query) to filter or score documents based on their fast field values.
+///
+/// The function is executed in a sandbox: it cannot perform any IO and its execution is bounded
+/// by `max_fuel_per_call` and `max_memory`.
+///
+/// The module must export the `entrypoint` function, taking as many `f64` parameters as the
+/// number of fields passed by the query and returning a single `f64`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UdfConfig {
+    /// Name under which the function is referenced in queries.
+    pub name: String,
+    /// URI of the compiled WebAssembly module (`.wasm`).
+    pub module_uri: Uri,
+    /// Name of the function exported by the module.
+    #[serde(default = "UdfConfig::default_entrypoint")]
+    pub entrypoint: String,
+    /// Amount of fuel (roughly, the number of executed instructions) a single call can consume
+    /// before it is aborted.
+    #[serde(default = "UdfConfig::default_max_fuel_per_call")]
+    pub max_fuel_per_call: u64,
+    /// Maximum size of the linear memory of the module.
+    #[serde(default = "UdfConfig::default_max_memory")]
+    pub max_memory: Byte,
+}
+
+impl UdfConfig {
+    fn default_entrypoint() -> String {
+        "call".to_string()
+    }
+
+    fn default_max_fuel_per_call() -> u64 {
+        100_000
+    }
+
+    fn default_max_memory() -> Byte {
+        Byte::from_bytes(1_000_000) // 1MB
+    }
+
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn for_test(name: &str, module_uri: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            module_uri: Uri::from_well_formed(module_uri),
+            entrypoint: Self::default_entrypoint(),
+            max_fuel_per_call: Self::default_max_fuel_per_call(),
+            max_memory: Self::default_max_memory(),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        validate_identifier("UDF name", &self.name)?;
+        if self.entrypoint.is_empty() {
+            anyhow::bail!("UDF `{}` `entrypoint` must not be empty.", self.name);
+        }
+        if self.max_fuel_per_call == 0 {
+            anyhow::bail!(
+                "UDF `{}` `max_fuel_per_call` must be strictly positive.",
+                self.name
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_udf_config_deserialization() {
+        let udf_config_yaml = r#"
+            name: price_boost
+            module_uri: s3://my-bucket/udfs/price_boost.wasm
+            max_fuel_per_call: 1000
+        "#;
+        let udf_config: UdfConfig = serde_yaml::from_str(udf_config_yaml).unwrap();
+        assert_eq!(udf_config.name, "price_boost");
+        assert_eq!(
+            udf_config.module_uri,
+            "s3://my-bucket/udfs/price_boost.wasm"
+        );
+        assert_eq!(udf_config.entrypoint, "call");
+        assert_eq!(udf_config.max_fuel_per_call, 1000);
+        assert_eq!(udf_config.max_memory, Byte::from_bytes(1_000_000));
+        udf_config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_udf_config_validate() {
+        let mut udf_config = UdfConfig::for_test("price_boost", "file:///udfs/price_boost.wasm");
+        udf_config.validate().unwrap();
+
+        udf_config.max_fuel_per_call = 0;
+        udf_config.validate().unwrap_err();
+
+        let udf_config = UdfConfig::for_test("-", "file:///udfs/price_boost.wasm");
+        udf_config.validate().unwrap_err();
+    }
+}
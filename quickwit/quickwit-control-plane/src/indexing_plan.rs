@@ -401,6 +401,7 @@ mod tests {
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
 
@@ -438,6 +439,7 @@ mod tests {
                 source_params: SourceParams::IngestApi,
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
 
@@ -484,9 +486,10 @@ mod tests {
                 max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
                 desired_num_pipelines: NonZeroUsize::new(3).unwrap(),
                 enabled: true,
-                source_params: SourceParams::File(FileSourceParams { filepath: None }),
+                source_params: SourceParams::File(FileSourceParams::stdin()),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
         source_configs_map.insert(
@@ -499,6 +502,7 @@ mod tests {
                 source_params: SourceParams::IngestCli,
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
         source_configs_map.insert(
@@ -511,6 +515,7 @@ mod tests {
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
         let indexing_tasks = build_indexing_plan(&indexers, &source_configs_map);
@@ -546,6 +551,7 @@ mod tests {
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
         source_configs_map.insert(
@@ -558,6 +564,7 @@ mod tests {
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
         let mut indexing_tasks = Vec::new();
@@ -634,6 +641,7 @@ mod tests {
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             },
         );
         let indexing_tasks = vec![
@@ -696,6 +704,7 @@ mod tests {
               source_params: kafka_source_params_for_test(),
               transform_config: None,
               input_format: SourceInputFormat::Json,
+              labels: Default::default(),
           })
       }
     }
@@ -37,6 +37,11 @@ use tracing::error;
 
 pub type Result<T> = std::result::Result<T, ControlPlaneError>;
 
+/// Encoded file descriptor set of the control plane service, served by the gRPC reflection
+/// service.
+pub const CONTROL_PLANE_FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/control_plane_descriptor.bin"));
+
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum ControlPlaneError {
     #[error("An internal error occurred: {0}.")]
@@ -97,6 +102,7 @@ pub async fn start_control_plane_service(
 /// - an index is deleted.
 /// - a source, other than the ingest CLI source, is created.
 /// - a source is deleted.
+/// - an index is opened, closed, or frozen.
 /// Note: we don't need to send an event to the control plane on index creation.
 /// A new index has no source and thus will not change the scheduling of indexing tasks.
 // TODO(fmassot):
@@ -119,6 +125,9 @@ impl EventSubscriber<MetastoreEvent> for ControlPlaneServiceClient {
             }
             MetastoreEvent::ToggleSource { .. } => "toggle-source",
             MetastoreEvent::DeleteSource { .. } => "delete-source",
+            MetastoreEvent::SetIndexState { .. } => "set-index-state",
+            // Publishing splits does not change the indexing plan.
+            MetastoreEvent::PublishSplits { .. } => return,
         };
         if let Err(error) = self.notify_index_change(NotifyIndexChangeRequest {}).await {
             error!(error=?error, event=event, "Failed to notify control plane of index change.");
@@ -160,5 +169,11 @@ mod tests {
             source_config: SourceConfig::for_test("test-source", SourceParams::IngestCli),
         };
         control_plane.handle_event(event).await;
+
+        let event = MetastoreEvent::PublishSplits {
+            index_uid: index_uid.clone(),
+            split_ids: vec!["test-split".to_string()],
+        };
+        control_plane.handle_event(event).await;
     }
 }
@@ -27,7 +27,7 @@ use anyhow::Context;
 use async_trait::async_trait;
 use itertools::Itertools;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler};
-use quickwit_cluster::{Cluster, ClusterMember};
+use quickwit_cluster::{is_compatible_protocol_version, Cluster, ClusterMember};
 use quickwit_config::service::QuickwitService;
 use quickwit_config::SourceConfig;
 use quickwit_grpc_clients::service_client_pool::ServiceClientPool;
@@ -194,6 +194,8 @@ impl IndexingScheduler {
         let indexes_metadatas = self.metastore.list_indexes_metadatas().await?;
         let source_configs: HashMap<IndexSourceId, SourceConfig> = indexes_metadatas
             .into_iter()
+            // Closed and frozen indexes are neither indexed nor merged.
+            .filter(|index_metadata| index_metadata.state.is_writable())
             .flat_map(|index_metadata| {
                 index_metadata
                     .sources
@@ -269,6 +271,17 @@ impl IndexingScheduler {
             .await
             .into_iter()
             .filter(|member| member.enabled_services.contains(&QuickwitService::Indexer))
+            .filter(|member| {
+                let is_compatible = is_compatible_protocol_version(member.protocol_version);
+                if !is_compatible {
+                    warn!(
+                        indexer_node_id=%member.node_id,
+                        protocol_version=%member.protocol_version,
+                        "Ignoring indexer speaking an incompatible protocol version."
+                    );
+                }
+                is_compatible
+            })
             .collect()
     }
 
@@ -541,7 +554,7 @@ mod tests {
     use quickwit_grpc_clients::service_client_pool::ServiceClientPool;
     use quickwit_indexing::indexing_client::IndexingServiceClient;
     use quickwit_indexing::IndexingService;
-    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_metastore::{IndexMetadata, IndexState, MockMetastore};
     use quickwit_proto::indexing_api::{ApplyIndexingPlanRequest, IndexingTask};
     use serde_json::json;
 
@@ -573,6 +586,7 @@ mod tests {
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         index_metadata
             .sources
@@ -724,6 +738,35 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_scheduler_ignores_closed_and_frozen_indexes() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["control_plane"], &transport, true)
+            .await
+            .unwrap();
+        let open_index_metadata = index_metadata_for_test("open-index", "source", 1, 1);
+        let mut closed_index_metadata = index_metadata_for_test("closed-index", "source", 1, 1);
+        closed_index_metadata.state = IndexState::Closed;
+        let mut frozen_index_metadata = index_metadata_for_test("frozen-index", "source", 1, 1);
+        frozen_index_metadata.state = IndexState::Frozen;
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_list_indexes_metadatas()
+            .returning(move || {
+                Ok(vec![
+                    open_index_metadata.clone(),
+                    closed_index_metadata.clone(),
+                    frozen_index_metadata.clone(),
+                ])
+            });
+        let indexing_scheduler =
+            IndexingScheduler::new(cluster, Arc::new(metastore), ServiceClientPool::default());
+        let source_configs = indexing_scheduler.fetch_source_configs().await.unwrap();
+        assert_eq!(source_configs.len(), 1);
+        let index_source_id = source_configs.keys().next().unwrap();
+        assert_eq!(index_source_id.index_uid.index_id(), "open-index");
+    }
+
     #[tokio::test]
     async fn test_scheduler_scheduling_multiple_indexers() {
         quickwit_common::setup_logging_for_tests();
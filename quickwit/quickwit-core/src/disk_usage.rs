@@ -0,0 +1,220 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use quickwit_common::split_file;
+use quickwit_directories::BundleDirectory;
+use quickwit_storage::Storage;
+use serde::{Deserialize, Serialize};
+use tantivy::directory::FileSlice;
+use tantivy::space_usage::PerFieldSpaceUsage;
+use tantivy::{Index, IndexReader, ReloadPolicy};
+
+/// On-disk usage of a field, broken down by data structure.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FieldDiskUsage {
+    pub field_name: String,
+    pub total_num_bytes: u64,
+    pub term_dictionary_num_bytes: u64,
+    pub postings_num_bytes: u64,
+    pub positions_num_bytes: u64,
+    pub fast_fields_num_bytes: u64,
+    pub fieldnorms_num_bytes: u64,
+}
+
+/// On-disk usage of a set of splits, broken down per field.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SplitsDiskUsage {
+    pub split_ids: Vec<String>,
+    pub num_docs: u64,
+    pub total_num_bytes: u64,
+    /// Documents are compressed in blocks mixing all their fields, so the doc store is not
+    /// broken down per field.
+    pub doc_store_num_bytes: u64,
+    /// Fields sorted by decreasing total number of bytes.
+    pub fields: Vec<FieldDiskUsage>,
+}
+
+/// Computes the on-disk usage of the given splits of the index stored in `index_storage`.
+///
+/// Splits are downloaded and analyzed one at a time.
+pub(crate) async fn compute_splits_disk_usage(
+    index_storage: &dyn Storage,
+    split_ids: &[String],
+) -> anyhow::Result<SplitsDiskUsage> {
+    let mut splits_disk_usage = SplitsDiskUsage::default();
+    let mut field_disk_usages: HashMap<String, FieldDiskUsage> = HashMap::new();
+
+    for split_id in split_ids {
+        let split_path = split_file(split_id);
+        let split_bytes = index_storage
+            .get_all(Path::new(&split_path))
+            .await
+            .with_context(|| format!("Failed to download split `{split_id}`."))?;
+        let bundle_directory = BundleDirectory::open_split(FileSlice::new(Arc::new(split_bytes)))?;
+        let index = Index::open(bundle_directory)?;
+        let schema = index.schema();
+        let reader: IndexReader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let space_usage = reader.searcher().space_usage()?;
+
+        for segment_space_usage in space_usage.segments() {
+            splits_disk_usage.num_docs += segment_space_usage.num_docs() as u64;
+            splits_disk_usage.doc_store_num_bytes +=
+                segment_space_usage.store().total().get_bytes();
+
+            let components: [(&PerFieldSpaceUsage, fn(&mut FieldDiskUsage) -> &mut u64); 5] = [
+                (segment_space_usage.termdict(), |field_disk_usage| {
+                    &mut field_disk_usage.term_dictionary_num_bytes
+                }),
+                (segment_space_usage.postings(), |field_disk_usage| {
+                    &mut field_disk_usage.postings_num_bytes
+                }),
+                (segment_space_usage.positions(), |field_disk_usage| {
+                    &mut field_disk_usage.positions_num_bytes
+                }),
+                (segment_space_usage.fast_fields(), |field_disk_usage| {
+                    &mut field_disk_usage.fast_fields_num_bytes
+                }),
+                (segment_space_usage.fieldnorms(), |field_disk_usage| {
+                    &mut field_disk_usage.fieldnorms_num_bytes
+                }),
+            ];
+            for (per_field_space_usage, component_num_bytes) in components {
+                for (field, field_usage) in per_field_space_usage.fields() {
+                    let field_name = schema.get_field_name(*field);
+                    let field_disk_usage = field_disk_usages
+                        .entry(field_name.to_string())
+                        .or_insert_with(|| FieldDiskUsage {
+                            field_name: field_name.to_string(),
+                            ..Default::default()
+                        });
+                    let num_bytes = field_usage.total().get_bytes();
+                    *component_num_bytes(field_disk_usage) += num_bytes;
+                    field_disk_usage.total_num_bytes += num_bytes;
+                }
+            }
+        }
+        splits_disk_usage.total_num_bytes += space_usage.total().get_bytes();
+        splits_disk_usage.split_ids.push(split_id.clone());
+    }
+    let mut fields: Vec<FieldDiskUsage> = field_disk_usages.into_values().collect();
+    fields.sort_by(|left, right| {
+        right
+            .total_num_bytes
+            .cmp(&left.total_num_bytes)
+            .then_with(|| left.field_name.cmp(&right.field_name))
+    });
+    splits_disk_usage.fields = fields;
+    Ok(splits_disk_usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::TestSandbox;
+
+    use crate::{IndexService, IndexServiceError};
+
+    #[tokio::test]
+    async fn test_disk_usage() -> anyhow::Result<()> {
+        let index_id = "test-disk-usage";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+                record: basic
+              - name: timestamp
+                type: i64
+                fast: true
+        "#;
+        let test_sandbox =
+            TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["title", "body"]).await?;
+        test_sandbox
+            .add_documents(vec![
+                serde_json::json!({"title": "snoopy", "body": "Snoopy is an anthropomorphic beagle in the comic strip.", "timestamp": 1}),
+                serde_json::json!({"title": "charlie", "body": "Charlie Brown is the owner of Snoopy.", "timestamp": 2}),
+            ])
+            .await?;
+        let split_ids: Vec<String> = test_sandbox
+            .metastore()
+            .list_all_splits(test_sandbox.index_uid())
+            .await?
+            .into_iter()
+            .map(|split| split.split_id().to_string())
+            .collect();
+        let index_service =
+            IndexService::new(test_sandbox.metastore(), test_sandbox.storage_resolver());
+        let disk_usage = index_service.disk_usage(index_id, &split_ids).await?;
+        assert_eq!(disk_usage.split_ids, split_ids);
+        assert_eq!(disk_usage.num_docs, 2);
+        assert!(disk_usage.doc_store_num_bytes > 0);
+
+        let field_usage = |field_name: &str| {
+            disk_usage
+                .fields
+                .iter()
+                .find(|field_disk_usage| field_disk_usage.field_name == field_name)
+                .unwrap()
+        };
+        let title_usage = field_usage("title");
+        assert!(title_usage.postings_num_bytes > 0);
+        assert!(title_usage.positions_num_bytes > 0);
+
+        let body_usage = field_usage("body");
+        assert!(body_usage.postings_num_bytes > 0);
+        assert_eq!(body_usage.positions_num_bytes, 0);
+
+        let timestamp_usage = field_usage("timestamp");
+        assert!(timestamp_usage.fast_fields_num_bytes > 0);
+
+        for field_disk_usage in &disk_usage.fields {
+            assert_eq!(
+                field_disk_usage.total_num_bytes,
+                field_disk_usage.term_dictionary_num_bytes
+                    + field_disk_usage.postings_num_bytes
+                    + field_disk_usage.positions_num_bytes
+                    + field_disk_usage.fast_fields_num_bytes
+                    + field_disk_usage.fieldnorms_num_bytes
+            );
+        }
+        assert!(disk_usage
+            .fields
+            .windows(2)
+            .all(|window| window[0].total_num_bytes >= window[1].total_num_bytes));
+
+        let error = index_service
+            .disk_usage(index_id, &["unknown-split".to_string()])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::InvalidConfig(_)));
+
+        let error = index_service.disk_usage(index_id, &[]).await.unwrap_err();
+        assert!(matches!(error, IndexServiceError::InvalidConfig(_)));
+
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+}
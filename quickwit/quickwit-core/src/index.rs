@@ -21,6 +21,7 @@ use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
+use anyhow::anyhow;
 use quickwit_common::fs::{empty_dir, get_cache_directory_path};
 use quickwit_common::FileEntry;
 use quickwit_config::{validate_identifier, IndexConfig, SourceConfig};
@@ -34,8 +35,11 @@ use quickwit_metastore::{
 use quickwit_proto::{IndexUid, ServiceError, ServiceErrorCode};
 use quickwit_storage::{StorageResolver, StorageResolverError};
 use thiserror::Error;
+use time::OffsetDateTime;
 use tracing::{error, info};
 
+use crate::disk_usage::{compute_splits_disk_usage, SplitsDiskUsage};
+
 #[derive(Error, Debug)]
 pub enum IndexServiceError {
     #[error("Failed to resolve the storage `{0}`.")]
@@ -202,6 +206,16 @@ impl IndexService {
         dry_run: bool,
     ) -> anyhow::Result<SplitRemovalInfo> {
         let index_metadata = self.metastore.index_metadata(index_id).await?;
+        // The split files are deleted before the splits are removed from the metastore, so the
+        // retention lock has to be checked upfront.
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        if !dry_run && index_metadata.is_retention_locked(now_timestamp) {
+            anyhow::bail!(
+                "Index `{index_id}` is under a retention lock until `{}`: its splits cannot be \
+                 garbage collected.",
+                index_metadata.retention_locked_until.unwrap_or_default()
+            );
+        }
         let index_uid = index_metadata.index_uid.clone();
         let index_config = index_metadata.into_index_config();
         let storage = self
@@ -270,6 +284,50 @@ impl IndexService {
         Ok(())
     }
 
+    /// Computes the on-disk usage of the fields of the given published splits of an index.
+    ///
+    /// The splits are downloaded in full, so this operation is expensive for large splits.
+    ///
+    /// * `index_id` - The target index Id.
+    /// * `split_ids` - The IDs of the published splits to analyze.
+    pub async fn disk_usage(
+        &self,
+        index_id: &str,
+        split_ids: &[String],
+    ) -> Result<SplitsDiskUsage, IndexServiceError> {
+        if split_ids.is_empty() {
+            return Err(IndexServiceError::InvalidConfig(anyhow!(
+                "At least one split ID must be specified."
+            )));
+        }
+        let index_metadata = self.metastore.index_metadata(index_id).await?;
+        let query = ListSplitsQuery::for_index(index_metadata.index_uid.clone())
+            .with_split_state(SplitState::Published);
+        let published_splits = self.metastore.list_splits(query).await?;
+        let missing_split_ids: Vec<&str> = split_ids
+            .iter()
+            .filter(|split_id| {
+                !published_splits
+                    .iter()
+                    .any(|split| split.split_id() == split_id.as_str())
+            })
+            .map(String::as_str)
+            .collect();
+        if !missing_split_ids.is_empty() {
+            return Err(IndexServiceError::InvalidConfig(anyhow!(
+                "Splits `{}` are not published splits of index `{index_id}`.",
+                missing_split_ids.join(", ")
+            )));
+        }
+        let storage = self
+            .storage_resolver
+            .resolve(index_metadata.index_uri())
+            .await?;
+        compute_splits_disk_usage(&*storage, split_ids)
+            .await
+            .map_err(|error| IndexServiceError::Internal(format!("{error:#}")))
+    }
+
     /// Creates a source config for index `index_id`.
     pub async fn create_source(
         &self,
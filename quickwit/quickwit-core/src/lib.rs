@@ -19,9 +19,13 @@
 
 #![deny(clippy::disallowed_methods)]
 
+mod disk_usage;
 mod index;
+mod split_docs;
 
+pub use disk_usage::{FieldDiskUsage, SplitsDiskUsage};
 pub use index::{clear_cache_directory, validate_storage_uri, IndexService, IndexServiceError};
+pub use split_docs::read_split_docs;
 
 #[cfg(test)]
 mod tests {
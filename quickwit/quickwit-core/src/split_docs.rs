@@ -0,0 +1,146 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use quickwit_common::split_file;
+use quickwit_directories::BundleDirectory;
+use quickwit_doc_mapper::DocMapper;
+use quickwit_storage::Storage;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tantivy::directory::FileSlice;
+use tantivy::schema::NamedFieldDocument;
+use tantivy::{DocAddress, Index, IndexReader, ReloadPolicy};
+
+/// Reads the alive documents of a split and converts them back into JSON documents.
+///
+/// Only the stored fields of the documents are returned. If a timestamp range is given, only the
+/// documents whose timestamp falls within the range are returned, the others and the documents
+/// without a timestamp are skipped.
+pub async fn read_split_docs(
+    index_storage: &dyn Storage,
+    split_id: &str,
+    doc_mapper: &dyn DocMapper,
+    timestamp_range_opt: Option<Range<i64>>,
+) -> anyhow::Result<Vec<JsonMap<String, JsonValue>>> {
+    let split_path = split_file(split_id);
+    let split_bytes = index_storage
+        .get_all(Path::new(&split_path))
+        .await
+        .with_context(|| format!("Failed to download split `{split_id}`."))?;
+    let bundle_directory = BundleDirectory::open_split(FileSlice::new(Arc::new(split_bytes)))?;
+    let index = Index::open(bundle_directory)?;
+    let schema = index.schema();
+    let timestamp_field_opt = match (&timestamp_range_opt, doc_mapper.timestamp_field_name()) {
+        (Some(_), Some(timestamp_field_name)) => Some(schema.get_field(timestamp_field_name)?),
+        (Some(_), None) => {
+            anyhow::bail!("A timestamp range was specified but the index has no timestamp field.")
+        }
+        _ => None,
+    };
+    let reader: IndexReader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let mut docs = Vec::with_capacity(searcher.num_docs() as usize);
+
+    for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+        for doc_id in segment_reader.doc_ids_alive() {
+            let doc = searcher.doc(DocAddress::new(segment_ord as u32, doc_id))?;
+
+            if let (Some(timestamp_range), Some(timestamp_field)) =
+                (&timestamp_range_opt, timestamp_field_opt)
+            {
+                let timestamp_opt = doc
+                    .get_first(timestamp_field)
+                    .and_then(|value| value.as_date())
+                    .map(|datetime| datetime.into_timestamp_secs());
+                if !matches!(timestamp_opt, Some(timestamp) if timestamp_range.contains(&timestamp))
+                {
+                    continue;
+                }
+            }
+            let NamedFieldDocument(named_field_doc_map) = schema.to_named_doc(&doc);
+            docs.push(doc_mapper.doc_to_json(named_field_doc_map)?);
+        }
+    }
+    Ok(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::TestSandbox;
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_split_docs() -> anyhow::Result<()> {
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                input_formats:
+                  - unix_timestamp
+                fast: true
+            timestamp_field: ts
+        "#;
+        let test_sandbox =
+            TestSandbox::create("test-read-split-docs", doc_mapping_yaml, "{}", &["body"]).await?;
+        test_sandbox
+            .add_documents(vec![
+                json!({"body": "first", "ts": 1_684_000_000}),
+                json!({"body": "second", "ts": 1_684_000_100}),
+                json!({"body": "third", "ts": 1_684_000_200}),
+            ])
+            .await?;
+        let splits = test_sandbox
+            .metastore()
+            .list_all_splits(test_sandbox.index_uid())
+            .await?;
+        assert_eq!(splits.len(), 1);
+        let split_id = splits[0].split_id();
+        let storage = test_sandbox.storage();
+        let doc_mapper = test_sandbox.doc_mapper();
+
+        let docs = read_split_docs(&*storage, split_id, &*doc_mapper, None).await?;
+        assert_eq!(docs.len(), 3);
+        assert_eq!(docs[0]["body"], json!("first"));
+        assert!(docs[0].contains_key("ts"));
+
+        let docs = read_split_docs(
+            &*storage,
+            split_id,
+            &*doc_mapper,
+            Some(1_684_000_100..1_684_000_200),
+        )
+        .await?;
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0]["body"], json!("second"));
+
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+}
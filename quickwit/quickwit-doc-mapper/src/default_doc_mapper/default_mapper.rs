@@ -21,24 +21,26 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::num::NonZeroU32;
 
 use anyhow::{bail, Context};
-use quickwit_query::query_ast::QueryAst;
+use quickwit_query::query_ast::{compute_field_presence_hash, QueryAst};
+use quickwit_query::{find_field_or_hit_dynamic, parse_search_field};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 use tantivy::query::Query;
-use tantivy::schema::{Field, FieldType, Schema, Value as TantivyValue, STORED};
+use tantivy::schema::{Field, FieldType, Schema, Value as TantivyValue, INDEXED, STORED};
 use tantivy::Document;
 
 use super::field_mapping_entry::QuickwitTextTokenizer;
 use super::DefaultDocMapperBuilder;
 use crate::default_doc_mapper::mapping_tree::{build_mapping_tree, MappingNode};
-use crate::default_doc_mapper::FieldMappingType;
 pub use crate::default_doc_mapper::QuickwitJsonOptions;
+use crate::default_doc_mapper::{FastFieldOptions, FieldMappingType};
 use crate::doc_mapper::{JsonObject, Partition};
 use crate::query_builder::build_query;
 use crate::routing_expression::RoutingExpr;
 use crate::{
-    Cardinality, DocMapper, DocParsingError, ModeType, QueryParserError, WarmupInfo,
-    DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME,
+    analyze_text, Cardinality, DocIdConfig, DocMapper, DocParsingError, DocValidationReport,
+    ModeType, QueryParserError, TextAnalysis, WarmupInfo, DYNAMIC_FIELD_NAME,
+    FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
 };
 
 /// Defines how an unmapped field should be handled.
@@ -76,10 +78,14 @@ pub struct DefaultDocMapper {
     /// This field is only valid when using the schema associated with the default
     /// doc mapper, and therefore cannot be used in the `query` method.
     dynamic_field: Option<Field>,
+    /// Field in which the hashes of the paths of the fields present in the document are indexed.
+    field_presence_field: Option<Field>,
     /// Default list of field names used for search.
     default_search_field_names: Vec<String>,
     /// Timestamp field name.
     timestamp_field_name: Option<String>,
+    /// Defines the field holding the unique ID of the documents.
+    doc_id_config: Option<DocIdConfig>,
     /// Root node of the field mapping tree.
     /// See [`MappingNode`].
     field_mappings: MappingNode,
@@ -115,6 +121,27 @@ impl DefaultDocMapper {
     pub fn default_max_num_partitions() -> NonZeroU32 {
         NonZeroU32::new(200).unwrap()
     }
+
+    /// Generates the ID of a document that does not hold one, or rejects the document if ID
+    /// generation is disabled.
+    fn populate_doc_id(
+        &self,
+        doc_id_config: &DocIdConfig,
+        json_obj: &mut JsonObject,
+    ) -> Result<(), DocParsingError> {
+        if !matches!(
+            json_obj.get(&doc_id_config.field),
+            None | Some(JsonValue::Null)
+        ) {
+            return Ok(());
+        }
+        let doc_id = doc_id_config
+            .generation
+            .generate()
+            .ok_or_else(|| DocParsingError::RequiredField(doc_id_config.field.clone()))?;
+        json_obj.insert(doc_id_config.field.clone(), JsonValue::String(doc_id));
+        Ok(())
+    }
 }
 
 fn validate_timestamp_field(
@@ -137,6 +164,35 @@ fn validate_timestamp_field(
     Ok(())
 }
 
+fn validate_doc_id_field(
+    doc_id_field_name: &str,
+    mapping_root_node: &MappingNode,
+) -> anyhow::Result<()> {
+    if doc_id_field_name.contains('.') {
+        bail!("Doc ID field `{doc_id_field_name}` should be a root field.");
+    }
+    let Some(doc_id_field_type) = mapping_root_node.find_field_mapping_type(doc_id_field_name) else {
+        bail!("Could not find doc ID field `{doc_id_field_name}` in field mappings.");
+    };
+    if let FieldMappingType::Text(text_options, cardinality) = &doc_id_field_type {
+        if cardinality != &Cardinality::SingleValue {
+            bail!("Doc ID field `{doc_id_field_name}` should be single-valued.");
+        }
+        if !text_options.indexed
+            || text_options.tokenizer != Some(QuickwitTextTokenizer::Raw)
+            || !text_options.char_filters.is_empty()
+        {
+            bail!("Doc ID field `{doc_id_field_name}` should be indexed with the `raw` tokenizer.");
+        }
+        if text_options.fast == FastFieldOptions::IsEnabled(false) {
+            bail!("Doc ID field `{doc_id_field_name}` should be a fast field.");
+        }
+    } else {
+        bail!("Doc ID field `{doc_id_field_name}` should be a text field.");
+    }
+    Ok(())
+}
+
 impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
     type Error = anyhow::Error;
 
@@ -154,18 +210,31 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             validate_timestamp_field(timestamp_field_path, &field_mappings)?;
         };
 
+        if let Some(doc_id_config) = builder.doc_id.as_ref() {
+            validate_doc_id_field(&doc_id_config.field, &field_mappings)?;
+        };
+
         let dynamic_field = if let Mode::Dynamic(json_options) = &mode {
             Some(schema_builder.add_json_field(DYNAMIC_FIELD_NAME, json_options.clone()))
         } else {
             None
         };
 
+        let field_presence_field = if builder.index_field_presence {
+            Some(schema_builder.add_u64_field(FIELD_PRESENCE_FIELD_NAME, INDEXED))
+        } else {
+            None
+        };
+
         let schema = schema_builder.build();
 
-        // Resolve default search fields
+        // Resolve default search fields. They may carry a boost (`field_name^boost`), which is
+        // kept as is and applied at query time.
         let mut default_search_field_names = Vec::new();
-        for default_search_field_name in &builder.default_search_fields {
-            if default_search_field_names.contains(default_search_field_name) {
+        let mut seen_default_search_field_names = BTreeSet::new();
+        for default_search_field in &builder.default_search_fields {
+            let (default_search_field_name, _boost_opt) = parse_search_field(default_search_field)?;
+            if !seen_default_search_field_names.insert(default_search_field_name) {
                 bail!(
                     "Duplicated default search field: `{}`",
                     default_search_field_name
@@ -180,7 +249,7 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             if !schema.get_field_entry(default_search_field).is_indexed() {
                 bail!("Default search field `{default_search_field_name}` is not indexed.",);
             }
-            default_search_field_names.push(default_search_field_name.clone());
+            default_search_field_names.push(default_search_field.clone());
         }
 
         // Resolve tag fields
@@ -206,8 +275,10 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             schema,
             source_field,
             dynamic_field,
+            field_presence_field,
             default_search_field_names,
             timestamp_field_name: builder.timestamp_field,
+            doc_id_config: builder.doc_id,
             field_mappings,
             tag_field_names,
             required_fields,
@@ -279,11 +350,13 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
         };
         Self {
             store_source: default_doc_mapper.source_field.is_some(),
+            index_field_presence: default_doc_mapper.field_presence_field.is_some(),
             timestamp_field: default_doc_mapper
                 .timestamp_field_name()
                 .map(ToString::to_string),
             field_mappings: default_doc_mapper.field_mappings.into(),
             tag_fields: default_doc_mapper.tag_field_names.into_iter().collect(),
+            doc_id: default_doc_mapper.doc_id_config,
             default_search_fields: default_doc_mapper.default_search_field_names,
             mode,
             dynamic_mapping,
@@ -308,6 +381,55 @@ impl std::fmt::Debug for DefaultDocMapper {
     }
 }
 
+/// Collects the hashes of the paths of the fields of `json_obj` that hold at least one non-null
+/// value. The path of an object is collected if one of its subfields holds a value.
+///
+/// Returns whether any field holds a value.
+fn collect_field_presence_hashes(
+    json_obj: &JsonObject,
+    path_prefix: &str,
+    field_presence_hashes: &mut BTreeSet<u64>,
+) -> bool {
+    let mut is_any_field_present = false;
+    for (field_name, json_value) in json_obj {
+        let field_path = if path_prefix.is_empty() {
+            field_name.clone()
+        } else {
+            format!("{path_prefix}.{field_name}")
+        };
+        if collect_field_presence_hashes_in_value(json_value, &field_path, field_presence_hashes) {
+            field_presence_hashes.insert(compute_field_presence_hash(&field_path));
+            is_any_field_present = true;
+        }
+    }
+    is_any_field_present
+}
+
+fn collect_field_presence_hashes_in_value(
+    json_value: &JsonValue,
+    field_path: &str,
+    field_presence_hashes: &mut BTreeSet<u64>,
+) -> bool {
+    match json_value {
+        JsonValue::Null => false,
+        JsonValue::Bool(_) | JsonValue::Number(_) | JsonValue::String(_) => true,
+        JsonValue::Array(json_values) => {
+            let mut is_any_value_present = false;
+            for json_value in json_values {
+                is_any_value_present |= collect_field_presence_hashes_in_value(
+                    json_value,
+                    field_path,
+                    field_presence_hashes,
+                );
+            }
+            is_any_value_present
+        }
+        JsonValue::Object(json_obj) => {
+            collect_field_presence_hashes(json_obj, field_path, field_presence_hashes)
+        }
+    }
+}
+
 fn extract_single_obj(
     doc: &mut BTreeMap<String, Vec<TantivyValue>>,
     key: &str,
@@ -335,8 +457,11 @@ fn extract_single_obj(
 impl DocMapper for DefaultDocMapper {
     fn doc_from_json_obj(
         &self,
-        json_obj: JsonObject,
+        mut json_obj: JsonObject,
     ) -> Result<(Partition, Document), DocParsingError> {
+        if let Some(doc_id_config) = &self.doc_id_config {
+            self.populate_doc_id(doc_id_config, &mut json_obj)?;
+        }
         let partition: Partition = self.partition_key.eval_hash(&json_obj);
 
         let mut dynamic_json_obj = serde_json::Map::default();
@@ -347,6 +472,14 @@ impl DocMapper for DefaultDocMapper {
             document.add_json_object(source_field, json_obj.clone());
         }
 
+        if let Some(field_presence_field) = self.field_presence_field {
+            let mut field_presence_hashes = BTreeSet::new();
+            collect_field_presence_hashes(&json_obj, "", &mut field_presence_hashes);
+            for field_presence_hash in field_presence_hashes {
+                document.add_u64(field_presence_field, field_presence_hash);
+            }
+        }
+
         let mode = self.mode.mode_type();
         self.field_mappings.doc_from_json(
             json_obj,
@@ -366,6 +499,40 @@ impl DocMapper for DefaultDocMapper {
         Ok((partition, document))
     }
 
+    fn validate_json_obj(&self, json_obj: JsonObject) -> DocValidationReport {
+        // The document is also parsed for real so that the verdict of the report matches the
+        // indexer's, including for the checks that do not apply to a single field.
+        let error_opt = self
+            .doc_from_json_obj(json_obj.clone())
+            .err()
+            .map(|error| error.to_string());
+        let mut report = DocValidationReport {
+            is_valid: error_opt.is_none(),
+            error: error_opt,
+            ..Default::default()
+        };
+        let mut field_path = Vec::new();
+        self.field_mappings.validate_json(
+            json_obj,
+            self.mode.mode_type(),
+            &mut field_path,
+            &mut report,
+        );
+        report
+    }
+
+    fn analyze_field_text(&self, field_path: &str, text: &str) -> anyhow::Result<TextAnalysis> {
+        let (_, field_entry, _) = find_field_or_hit_dynamic(field_path, &self.schema)?;
+        let text_indexing_options_opt = match field_entry.field_type() {
+            FieldType::Str(text_options) => text_options.get_indexing_options(),
+            FieldType::JsonObject(json_options) => json_options.get_text_indexing_options(),
+            _ => bail!("Field `{field_path}` is not a text or JSON field."),
+        };
+        let text_indexing_options = text_indexing_options_opt
+            .with_context(|| format!("Field `{field_path}` is not indexed."))?;
+        analyze_text(text_indexing_options.tokenizer(), text)
+    }
+
     fn doc_to_json(
         &self,
         mut named_doc: BTreeMap<String, Vec<TantivyValue>>,
@@ -416,6 +583,12 @@ impl DocMapper for DefaultDocMapper {
         self.tag_field_names.clone()
     }
 
+    fn doc_id_field_name(&self) -> Option<&str> {
+        self.doc_id_config
+            .as_ref()
+            .map(|doc_id_config| doc_id_config.field.as_str())
+    }
+
     fn max_num_partitions(&self) -> NonZeroU32 {
         self.max_num_partitions
     }
@@ -423,15 +596,17 @@ impl DocMapper for DefaultDocMapper {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{BTreeSet, HashMap};
 
     use quickwit_proto::query_ast_from_user_text;
+    use quickwit_query::query_ast::compute_field_presence_hash;
     use serde_json::{self, json, Value as JsonValue};
     use tantivy::schema::{FieldType, IndexRecordOption, Type, Value as TantivyValue};
 
     use super::DefaultDocMapper;
     use crate::{
-        DefaultDocMapperBuilder, DocMapper, DocParsingError, DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME,
+        DefaultDocMapperBuilder, DocMapper, DocParsingError, DYNAMIC_FIELD_NAME,
+        FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
     };
 
     fn example_json_doc_value() -> JsonValue {
@@ -965,6 +1140,47 @@ mod tests {
         serde_json::from_str::<DefaultDocMapper>(doc_mapper).unwrap();
     }
 
+    #[test]
+    fn test_build_doc_mapper_with_boosted_default_search_fields() {
+        let doc_mapper_json = |default_search_fields: &str| {
+            format!(
+                r#"{{
+                "default_search_fields": {default_search_fields},
+                "field_mappings": [
+                    {{"name": "message", "type": "text"}},
+                    {{
+                        "name": "error",
+                        "type": "object",
+                        "field_mappings": [{{"name": "message", "type": "text"}}]
+                    }}
+                ]
+            }}"#
+            )
+        };
+        let doc_mapper = serde_json::from_str::<DefaultDocMapper>(&doc_mapper_json(
+            r#"["message^1", "error.message^3"]"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            doc_mapper.default_search_fields(),
+            &["message^1".to_string(), "error.message^3".to_string()]
+        );
+
+        let error = serde_json::from_str::<DefaultDocMapper>(&doc_mapper_json(
+            r#"["message", "message^2"]"#,
+        ))
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Duplicated default search field: `message`"));
+
+        let error = serde_json::from_str::<DefaultDocMapper>(&doc_mapper_json(r#"["unknown^2"]"#))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unknown default search field: `unknown`"));
+    }
+
     #[test]
     fn test_fail_to_build_doc_mapper_with_wrong_tag_fields_types() -> anyhow::Result<()> {
         let doc_mapper_one = r#"{
@@ -1005,6 +1221,88 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_doc_id_generation() {
+        let doc_mapper = r#"{
+            "store_source": true,
+            "doc_id": {
+                "field": "id",
+                "generation": "ulid"
+            },
+            "field_mappings": [
+                {
+                    "name": "id",
+                    "type": "text",
+                    "tokenizer": "raw",
+                    "fast": true
+                }
+            ]
+        }"#;
+        let doc_mapper = serde_json::from_str::<DefaultDocMapper>(doc_mapper).unwrap();
+        assert_eq!(doc_mapper.doc_id_field_name(), Some("id"));
+
+        let id_field = doc_mapper.schema().get_field("id").unwrap();
+        let source_field = doc_mapper.schema().get_field(SOURCE_FIELD_NAME).unwrap();
+
+        let (_, document) = doc_mapper.doc_from_json_str(r#"{"id": "doc-1"}"#).unwrap();
+        assert_eq!(
+            document.get_first(id_field).unwrap().as_text(),
+            Some("doc-1")
+        );
+        let (_, document) = doc_mapper.doc_from_json_str("{}").unwrap();
+        let generated_id = document.get_first(id_field).unwrap().as_text().unwrap();
+        assert!(ulid::Ulid::from_string(generated_id).is_ok());
+
+        // The generated ID is also part of the stored source.
+        let Some(TantivyValue::JsonObject(source)) = document.get_first(source_field) else {
+            panic!("The source should be stored.");
+        };
+        assert_eq!(source["id"], JsonValue::String(generated_id.to_string()));
+
+        let mut builder: DefaultDocMapperBuilder = doc_mapper.into();
+        builder.doc_id.as_mut().unwrap().generation = Default::default();
+        let doc_mapper = builder.try_build().unwrap();
+        let parsing_error = doc_mapper.doc_from_json_str(r#"{"id": null}"#).unwrap_err();
+        assert!(matches!(parsing_error, DocParsingError::RequiredField(field) if field == "id"));
+    }
+
+    #[test]
+    fn test_fail_to_build_doc_mapper_with_invalid_doc_id_field() {
+        let build_doc_mapper = |field_mapping: &str| -> String {
+            let doc_mapper = format!(
+                r#"{{
+                "doc_id": {{ "field": "id" }},
+                "field_mappings": [{field_mapping}]
+            }}"#
+            );
+            serde_json::from_str::<DefaultDocMapperBuilder>(&doc_mapper)
+                .unwrap()
+                .try_build()
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            build_doc_mapper(r#"{"name": "body", "type": "text"}"#),
+            "Could not find doc ID field `id` in field mappings."
+        );
+        assert_eq!(
+            build_doc_mapper(r#"{"name": "id", "type": "u64", "fast": true}"#),
+            "Doc ID field `id` should be a text field."
+        );
+        assert_eq!(
+            build_doc_mapper(r#"{"name": "id", "type": "array<text>", "tokenizer": "raw"}"#),
+            "Doc ID field `id` should be single-valued."
+        );
+        assert_eq!(
+            build_doc_mapper(r#"{"name": "id", "type": "text", "fast": true}"#),
+            "Doc ID field `id` should be indexed with the `raw` tokenizer."
+        );
+        assert_eq!(
+            build_doc_mapper(r#"{"name": "id", "type": "text", "tokenizer": "raw"}"#),
+            "Doc ID field `id` should be a fast field."
+        );
+    }
+
     // See #1132
     #[test]
     fn test_by_default_store_source_is_false_and_fields_are_stored_individually() {
@@ -1154,6 +1452,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_index_field_presence() {
+        let default_doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+                "index_field_presence": true,
+                "field_mappings": [
+                    {
+                        "name": "user",
+                        "type": "object",
+                        "field_mappings": [{ "name": "id", "type": "text" }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let schema = default_doc_mapper.schema();
+        let field_presence_field = schema.get_field(FIELD_PRESENCE_FIELD_NAME).unwrap();
+        let (_, doc) = default_doc_mapper
+            .doc_from_json_str(
+                r#"{
+                    "user": { "id": "foo", "name": null },
+                    "http": { "headers": [{ "host": "bar" }], "body": {} },
+                    "tags": []
+                }"#,
+            )
+            .unwrap();
+        let field_presence_hashes: BTreeSet<u64> = doc
+            .get_all(field_presence_field)
+            .map(|value| value.as_u64().unwrap())
+            .collect();
+        let expected_field_presence_hashes: BTreeSet<u64> = [
+            "user",
+            "user.id",
+            "http",
+            "http.headers",
+            "http.headers.host",
+        ]
+        .into_iter()
+        .map(compute_field_presence_hash)
+        .collect();
+        assert_eq!(field_presence_hashes, expected_field_presence_hashes);
+
+        let default_doc_mapper: DefaultDocMapper = serde_json::from_str("{}").unwrap();
+        assert!(default_doc_mapper
+            .schema()
+            .get_field(FIELD_PRESENCE_FIELD_NAME)
+            .is_err());
+    }
+
     #[test]
     fn test_dymamic_mode_inner() {
         let default_doc_mapper: DefaultDocMapper = serde_json::from_str(
@@ -1335,6 +1682,95 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_doc_mapper_text_field_with_sub_fields() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "message",
+                    "type": "text",
+                    "fields": [
+                        {"name": "raw", "type": "text", "tokenizer": "raw", "fast": true}
+                    ]
+                }
+            ]
+        }"#,
+        )
+        .unwrap();
+        let schema = doc_mapper.schema();
+        let message_field = schema.get_field("message").unwrap();
+        let message_raw_field = schema.get_field("message.raw").unwrap();
+        let message_raw_field_entry = schema.get_field_entry(message_raw_field);
+        assert!(!message_raw_field_entry.is_stored());
+        assert!(message_raw_field_entry.is_fast());
+
+        let (_, document) = doc_mapper
+            .doc_from_json_str(r#"{"message": "Hello World"}"#)
+            .unwrap();
+        assert_eq!(
+            document.get_first(message_field).unwrap().as_text(),
+            Some("Hello World")
+        );
+        assert_eq!(
+            document.get_first(message_raw_field).unwrap().as_text(),
+            Some("Hello World")
+        );
+
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "message:hello").unwrap(),
+            r#"TermQuery(Term(field=0, type=Str, "hello"))"#
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, r#"message.raw:"Hello World""#).unwrap(),
+            r#"TermQuery(Term(field=1, type=Str, "Hello World"))"#
+        );
+        assert!(doc_mapper
+            .field_mappings
+            .find_field_mapping_type("message.raw")
+            .is_some());
+        assert!(doc_mapper
+            .field_mappings
+            .find_field_mapping_type("message.unknown")
+            .is_none());
+    }
+
+    #[test]
+    fn test_doc_mapper_analyze_field_text() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {"name": "body", "type": "text", "tokenizer": "en_stem"},
+                {"name": "attributes", "type": "json"},
+                {"name": "count", "type": "u64"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        let text_analysis = doc_mapper
+            .analyze_field_text("body", "Running dogs")
+            .unwrap();
+        assert_eq!(text_analysis.tokenizer, "en_stem");
+        let tokens: Vec<&str> = text_analysis
+            .tokens
+            .iter()
+            .map(|token| token.token.as_str())
+            .collect();
+        assert_eq!(tokens, ["run", "dog"]);
+
+        let text_analysis = doc_mapper
+            .analyze_field_text("attributes.color", "Dark Blue")
+            .unwrap();
+        assert_eq!(text_analysis.tokenizer, "raw");
+        assert_eq!(text_analysis.tokens.len(), 1);
+
+        let error = doc_mapper.analyze_field_text("count", "42").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Field `count` is not a text or JSON field."
+        );
+    }
+
     #[test]
     fn test_doc_mapper_default_tokenizers() {
         let doc_mapper: DefaultDocMapper = serde_json::from_str(
@@ -1434,4 +1870,76 @@ mod tests {
             .find_field_mapping_type("my\\.timestamp")
             .unwrap();
     }
+
+    #[test]
+    fn test_validate_json_obj() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "mode": "dynamic",
+            "field_mappings": [
+                {
+                    "name": "ts",
+                    "type": "datetime",
+                    "input_formats": ["unix_timestamp"],
+                    "output_format": "rfc3339",
+                    "fast": true
+                },
+                {"name": "status", "type": "u64"},
+                {"name": "tags", "type": "array<text>"},
+                {
+                    "name": "http",
+                    "type": "object",
+                    "field_mappings": [{"name": "method", "type": "text"}]
+                }
+            ],
+            "timestamp_field": "ts"
+        }"#,
+        )
+        .unwrap();
+        let json_obj = json!({
+            "ts": 1684000000,
+            "status": 200,
+            "tags": ["a", "b"],
+            "http": {"method": "GET", "version": "1.1"},
+            "user": {"id": 5, "roles": ["admin"]}
+        });
+        let report = doc_mapper.validate_json_obj(json_obj.as_object().unwrap().clone());
+        assert!(report.is_valid);
+        assert!(report.error.is_none());
+        assert_eq!(report.fields.len(), 4);
+
+        let ts_validation = report
+            .fields
+            .iter()
+            .find(|field| field.path == "ts")
+            .unwrap();
+        assert_eq!(ts_validation.field_type, "datetime");
+        assert_eq!(ts_validation.input, json!(1684000000));
+        assert_eq!(ts_validation.values, [json!("2023-05-13T17:46:40Z")]);
+
+        let tags_validation = report
+            .fields
+            .iter()
+            .find(|field| field.path == "tags")
+            .unwrap();
+        assert_eq!(tags_validation.field_type, "array<text>");
+        assert_eq!(tags_validation.values, [json!("a"), json!("b")]);
+
+        let mut dynamic_paths = report.dynamic_paths.clone();
+        dynamic_paths.sort();
+        assert_eq!(dynamic_paths, ["http.version", "user.id", "user.roles"]);
+        assert!(report.unmapped_paths.is_empty());
+
+        let json_obj = json!({
+            "status": "200",
+            "tags": ["a", 1],
+            "http": "GET"
+        });
+        let report = doc_mapper.validate_json_obj(json_obj.as_object().unwrap().clone());
+        assert!(!report.is_valid);
+        assert!(report.error.is_some());
+        assert_eq!(report.fields.len(), 3);
+        assert!(report.fields.iter().all(|field| field.error.is_some()));
+        assert!(report.fields.iter().all(|field| field.values.is_empty()));
+    }
 }
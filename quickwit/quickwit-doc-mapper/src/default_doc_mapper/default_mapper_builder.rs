@@ -25,7 +25,7 @@ use serde::{Deserialize, Serialize};
 use super::FieldMappingEntry;
 use crate::default_doc_mapper::default_mapper::Mode;
 use crate::default_doc_mapper::QuickwitJsonOptions;
-use crate::DefaultDocMapper;
+use crate::{DefaultDocMapper, DocIdConfig};
 
 /// DefaultDocMapperBuilder is here
 /// to create a valid DocMapper.
@@ -39,6 +39,11 @@ pub struct DefaultDocMapperBuilder {
     /// Stores the original source document when set to true.
     #[serde(default)]
     pub store_source: bool,
+    /// Indexes the paths of the fields present in each document when set to true, which enables
+    /// `exists` queries.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub index_field_presence: bool,
     /// Name of the fields that are searched by default, unless overridden.
     #[serde(default)]
     pub default_search_fields: Vec<String>,
@@ -52,6 +57,10 @@ pub struct DefaultDocMapperBuilder {
     /// Name of the fields that are tagged.
     #[serde(default)]
     pub tag_fields: Vec<String>,
+    /// Defines the field holding the unique ID of the documents.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_id: Option<DocIdConfig>,
     /// The partition key is a DSL used to route documents
     /// into specific splits.
     #[serde(default)]
@@ -138,6 +147,7 @@ mod tests {
         assert!(default_mapper_builder.dynamic_mapping.is_none());
         assert_eq!(default_mapper_builder.store_source, false);
         assert!(default_mapper_builder.timestamp_field.is_none());
+        assert!(default_mapper_builder.doc_id.is_none());
     }
 
     #[test]
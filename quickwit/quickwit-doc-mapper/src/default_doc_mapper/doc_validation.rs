@@ -0,0 +1,93 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Outcome of the coercion of the value of a mapped field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FieldValidation {
+    /// Path of the field.
+    pub path: String,
+    /// Type of the field, as declared in the doc mapping.
+    pub field_type: String,
+    /// Value of the field in the document.
+    #[schema(value_type = Object)]
+    pub input: JsonValue,
+    /// Values the input is coerced into, formatted as they are returned by search requests.
+    #[schema(value_type = Vec<Object>)]
+    pub values: Vec<JsonValue>,
+    /// Reason why the input cannot be coerced, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Describes how a document would be indexed with a doc mapping, without indexing it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DocValidationReport {
+    /// Whether the document would be accepted by the indexer.
+    pub is_valid: bool,
+    /// Error the document would be rejected with, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Coercion outcomes of the mapped fields present in the document.
+    pub fields: Vec<FieldValidation>,
+    /// Paths of the unmapped values captured by the dynamic field.
+    pub dynamic_paths: Vec<String>,
+    /// Paths of the unmapped values that are dropped (lenient mode) or rejected (strict mode).
+    pub unmapped_paths: Vec<String>,
+}
+
+/// Appends the paths of the leaves of an unmapped JSON value to `paths`. Arrays are leaves.
+pub(crate) fn collect_leaf_paths(path: &str, json_val: &JsonValue, paths: &mut Vec<String>) {
+    match json_val {
+        JsonValue::Object(json_obj) if !json_obj.is_empty() => {
+            for (key, child_json_val) in json_obj {
+                collect_leaf_paths(&format!("{path}.{key}"), child_json_val, paths);
+            }
+        }
+        _ => paths.push(path.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_collect_leaf_paths() {
+        let mut paths = Vec::new();
+        collect_leaf_paths(
+            "attributes",
+            &json!({"http": {"status": 200, "headers": ["a", "b"]}, "empty": {}}),
+            &mut paths,
+        );
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "attributes.empty",
+                "attributes.http.headers",
+                "attributes.http.status"
+            ]
+        );
+    }
+}
@@ -17,10 +17,15 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
 use std::convert::TryFrom;
 
 use anyhow::bail;
 use base64::prelude::{Engine, BASE64_STANDARD};
+use quickwit_query::{
+    char_filter_tokenizer_name, is_stemming_tokenizer, protected_words_tokenizer_name,
+    register_char_filter_tokenizer, register_protected_words_tokenizer, CharFilter,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use tantivy::schema::{
@@ -221,6 +226,12 @@ pub enum QuickwitTextTokenizer {
     StemEn,
     #[serde(rename = "chinese_compatible")]
     Chinese,
+    #[serde(rename = "trace_id")]
+    TraceId,
+    #[serde(rename = "span_id")]
+    SpanId,
+    #[serde(rename = "double_metaphone")]
+    DoubleMetaphone,
 }
 
 impl QuickwitTextTokenizer {
@@ -230,6 +241,9 @@ impl QuickwitTextTokenizer {
             QuickwitTextTokenizer::Default => "default",
             QuickwitTextTokenizer::StemEn => "en_stem",
             QuickwitTextTokenizer::Chinese => "chinese_compatible",
+            QuickwitTextTokenizer::TraceId => "trace_id",
+            QuickwitTextTokenizer::SpanId => "span_id",
+            QuickwitTextTokenizer::DoubleMetaphone => "double_metaphone",
         }
     }
 }
@@ -239,6 +253,8 @@ impl QuickwitTextTokenizer {
 pub enum QuickwitTextNormalizer {
     Raw,
     Lowercase,
+    TraceId,
+    SpanId,
 }
 
 impl QuickwitTextNormalizer {
@@ -246,6 +262,8 @@ impl QuickwitTextNormalizer {
         match self {
             QuickwitTextNormalizer::Raw => "raw",
             QuickwitTextNormalizer::Lowercase => "lowercase",
+            QuickwitTextNormalizer::TraceId => "trace_id",
+            QuickwitTextNormalizer::SpanId => "span_id",
         }
     }
 }
@@ -262,6 +280,17 @@ pub struct QuickwitTextOptions {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tokenizer: Option<QuickwitTextTokenizer>,
+    /// Character filters applied to the text before tokenization, both at indexing and query
+    /// time.
+    #[schema(value_type = Vec<Object>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub char_filters: Vec<CharFilter>,
+    /// Words that are not stemmed by the stemming tokenizer, e.g. product names. Matching is
+    /// case insensitive.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub protected_words: Vec<String>,
     #[schema(value_type = IndexRecordOptionSchema)]
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -272,6 +301,12 @@ pub struct QuickwitTextOptions {
     pub stored: bool,
     #[serde(default)]
     pub fast: FastFieldOptions,
+    /// Sub-fields indexing the same value in other ways, e.g. as a raw keyword. A sub-field is
+    /// addressed in queries as `<field>.<sub-field>` and is never stored.
+    #[schema(value_type = Vec<Object>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldMappingEntry>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -293,14 +328,54 @@ impl Default for QuickwitTextOptions {
             description: None,
             indexed: true,
             tokenizer: None,
+            char_filters: Vec::new(),
+            protected_words: Vec::new(),
             record: None,
             fieldnorms: false,
             stored: true,
             fast: FastFieldOptions::default(),
+            fields: Vec::new(),
         }
     }
 }
 
+impl QuickwitTextOptions {
+    /// Returns the name of the tokenizer of the field, which applies the protected words and the
+    /// character filters on top of the configured tokenizer.
+    fn tokenizer_name(&self) -> String {
+        let mut tokenizer_name = self
+            .tokenizer
+            .unwrap_or(QuickwitTextTokenizer::Default)
+            .get_name()
+            .to_string();
+        if !self.protected_words.is_empty() {
+            tokenizer_name = protected_words_tokenizer_name(&tokenizer_name, &self.protected_words);
+        }
+        if !self.char_filters.is_empty() {
+            tokenizer_name = char_filter_tokenizer_name(&tokenizer_name, &self.char_filters);
+        }
+        tokenizer_name
+    }
+
+    /// Registers the tokenizer of the field in the Quickwit tokenizer manager. The tokenizer must
+    /// be registered before the field is indexed or searched.
+    fn register_tokenizer(&self) -> anyhow::Result<()> {
+        let mut tokenizer_name = self
+            .tokenizer
+            .unwrap_or(QuickwitTextTokenizer::Default)
+            .get_name()
+            .to_string();
+        if !self.protected_words.is_empty() {
+            tokenizer_name =
+                register_protected_words_tokenizer(&tokenizer_name, &self.protected_words)?;
+        }
+        if !self.char_filters.is_empty() {
+            register_char_filter_tokenizer(&tokenizer_name, &self.char_filters)?;
+        }
+        Ok(())
+    }
+}
+
 impl From<QuickwitTextOptions> for TextOptions {
     fn from(quickwit_text_options: QuickwitTextOptions) -> Self {
         let mut text_options = TextOptions::default();
@@ -320,13 +395,11 @@ impl From<QuickwitTextOptions> for TextOptions {
             let index_record_option = quickwit_text_options
                 .record
                 .unwrap_or(IndexRecordOption::Basic);
-            let tokenizer = quickwit_text_options
-                .tokenizer
-                .unwrap_or(QuickwitTextTokenizer::Default);
+            let tokenizer_name = quickwit_text_options.tokenizer_name();
             let text_field_indexing = TextFieldIndexing::default()
                 .set_index_option(index_record_option)
                 .set_fieldnorms(quickwit_text_options.fieldnorms)
-                .set_tokenizer(tokenizer.get_name());
+                .set_tokenizer(&tokenizer_name);
 
             text_options = text_options.set_indexing_options(text_field_indexing);
         }
@@ -465,7 +538,39 @@ fn deserialize_mapping_type(
                          indexed is true."
                     );
                 }
+                if !text_options.char_filters.is_empty() {
+                    bail!("`char_filters` parameter is allowed only if indexed is true.");
+                }
+                if !text_options.protected_words.is_empty() {
+                    bail!("`protected_words` parameter is allowed only if indexed is true.");
+                }
+            }
+            if !text_options.protected_words.is_empty() {
+                let tokenizer = text_options
+                    .tokenizer
+                    .unwrap_or(QuickwitTextTokenizer::Default);
+                if !is_stemming_tokenizer(tokenizer.get_name()) {
+                    bail!(
+                        "`protected_words` parameter is allowed only with a stemming tokenizer, \
+                         such as `en_stem`."
+                    );
+                }
+            }
+            let mut sub_field_names = HashSet::new();
+            for sub_field in &text_options.fields {
+                if !sub_field_names.insert(sub_field.name.as_str()) {
+                    bail!("Duplicated sub-field definition `{}`.", sub_field.name);
+                }
+                match &sub_field.mapping_type {
+                    FieldMappingType::Text(sub_field_options, Cardinality::SingleValue)
+                        if sub_field_options.fields.is_empty() => {}
+                    _ => bail!(
+                        "Sub-field `{}` must be of type `text` and cannot have sub-fields.",
+                        sub_field.name
+                    ),
+                }
             }
+            text_options.register_tokenizer()?;
             Ok(FieldMappingType::Text(text_options, cardinality))
         }
         Type::U64 => {
@@ -591,7 +696,7 @@ mod tests {
 
     use super::FieldMappingEntry;
     use crate::default_doc_mapper::field_mapping_entry::{
-        QuickwitJsonOptions, QuickwitTextOptions, QuickwitTextTokenizer,
+        QuickwitJsonOptions, QuickwitTextNormalizer, QuickwitTextOptions, QuickwitTextTokenizer,
     };
     use crate::default_doc_mapper::{FastFieldOptions, FieldMappingType};
     use crate::Cardinality;
@@ -737,7 +842,8 @@ mod tests {
         assert_eq!(
             mapping_entry.unwrap_err().to_string(),
             "Error while parsing field `my_field_name`: unknown variant `notexist`, expected one \
-             of `raw`, `default`, `en_stem`, `chinese_compatible`"
+             of `raw`, `default`, `en_stem`, `chinese_compatible`, `trace_id`, `span_id`, \
+             `double_metaphone`"
                 .to_string()
         );
         Ok(())
@@ -788,6 +894,203 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_deserialize_text_mapping_entry_with_char_filters() {
+        let mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "my_field_name",
+            "type": "text",
+            "char_filters": [
+                {"type": "html_strip"},
+                {"type": "pattern_replace", "pattern": "\\d+", "replacement": "N"}
+            ]
+        }
+        "#,
+        )
+        .unwrap();
+        let FieldMappingType::Text(options, _) = mapping_entry.mapping_type else {
+            panic!("wrong property type");
+        };
+        assert_eq!(options.char_filters.len(), 2);
+
+        let text_options = TextOptions::from(options.clone());
+        let tokenizer_name = text_options.get_indexing_options().unwrap().tokenizer();
+        assert_eq!(
+            tokenizer_name,
+            char_filter_tokenizer_name("default", &options.char_filters)
+        );
+        assert!(quickwit_query::get_quickwit_tokenizer_manager()
+            .get(tokenizer_name)
+            .is_some());
+    }
+
+    #[test]
+    fn test_deserialize_text_mapping_entry_with_protected_words() {
+        let mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "my_field_name",
+            "type": "text",
+            "tokenizer": "en_stem",
+            "protected_words": ["Pixel", "running"],
+            "char_filters": [{"type": "html_strip"}]
+        }
+        "#,
+        )
+        .unwrap();
+        let FieldMappingType::Text(options, _) = mapping_entry.mapping_type else {
+            panic!("wrong property type");
+        };
+        assert_eq!(options.protected_words, ["Pixel", "running"]);
+
+        let text_options = TextOptions::from(options.clone());
+        let tokenizer_name = text_options.get_indexing_options().unwrap().tokenizer();
+        assert_eq!(
+            tokenizer_name,
+            char_filter_tokenizer_name(
+                &protected_words_tokenizer_name("en_stem", &options.protected_words),
+                &options.char_filters
+            )
+        );
+        assert!(quickwit_query::get_quickwit_tokenizer_manager()
+            .get(tokenizer_name)
+            .is_some());
+    }
+
+    #[test]
+    fn test_deserialize_text_mapping_entry_with_invalid_protected_words() {
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "my_field_name",
+            "type": "text",
+            "protected_words": ["Pixel"]
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`protected_words` parameter is allowed only with a stemming tokenizer"));
+
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "my_field_name",
+            "type": "text",
+            "indexed": false,
+            "protected_words": ["Pixel"]
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`protected_words` parameter is allowed only if indexed is true."));
+    }
+
+    #[test]
+    fn test_deserialize_text_mapping_entry_with_sub_fields() {
+        let mapping_entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "message",
+            "type": "text",
+            "fields": [
+                {"name": "raw", "type": "text", "tokenizer": "raw", "fast": true}
+            ]
+        }
+        "#,
+        )
+        .unwrap();
+        let FieldMappingType::Text(options, _) = &mapping_entry.mapping_type else {
+            panic!("wrong property type");
+        };
+        assert_eq!(options.fields.len(), 1);
+        assert_eq!(options.fields[0].name, "raw");
+        let FieldMappingType::Text(sub_field_options, _) = &options.fields[0].mapping_type else {
+            panic!("wrong property type");
+        };
+        assert_eq!(
+            sub_field_options.tokenizer,
+            Some(QuickwitTextTokenizer::Raw)
+        );
+        assert_eq!(sub_field_options.fast, FastFieldOptions::IsEnabled(true));
+
+        let mapping_entry_json = serde_json::to_value(&mapping_entry).unwrap();
+        assert_eq!(mapping_entry_json["fields"][0]["name"], "raw");
+        let mapping_entry_deser: FieldMappingEntry =
+            serde_json::from_value(mapping_entry_json).unwrap();
+        assert_eq!(mapping_entry_deser, mapping_entry);
+    }
+
+    #[test]
+    fn test_deserialize_text_mapping_entry_with_invalid_sub_fields() {
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "message",
+            "type": "text",
+            "fields": [{"name": "count", "type": "u64"}]
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Sub-field `count` must be of type `text` and cannot have sub-fields."));
+
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "message",
+            "type": "text",
+            "fields": [
+                {"name": "raw", "type": "text", "tokenizer": "raw"},
+                {"name": "raw", "type": "text"}
+            ]
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Duplicated sub-field definition `raw`."));
+    }
+
+    #[test]
+    fn test_deserialize_text_mapping_entry_with_invalid_char_filters() {
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "my_field_name",
+            "type": "text",
+            "char_filters": [{"type": "pattern_replace", "pattern": "("}]
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Invalid `pattern_replace` pattern `(`."));
+
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+        {
+            "name": "my_field_name",
+            "type": "text",
+            "indexed": false,
+            "char_filters": [{"type": "html_strip"}]
+        }
+        "#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`char_filters` parameter is allowed only if indexed is true."));
+    }
+
     #[test]
     fn test_deserialize_valid_fieldnorms() -> anyhow::Result<()> {
         let result = serde_json::from_str::<FieldMappingEntry>(
@@ -1235,6 +1538,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_text_trace_id_tokenizer_and_normalizer() {
+        let entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "trace_id",
+                "type": "text",
+                "tokenizer": "trace_id",
+                "fast": {"normalizer": "trace_id"}
+            }
+            "#,
+        )
+        .unwrap();
+        let FieldMappingType::Text(options, _) = entry.mapping_type else {
+            panic!("wrong property type");
+        };
+        assert_eq!(options.tokenizer, Some(QuickwitTextTokenizer::TraceId));
+        assert_eq!(
+            options.fast,
+            FastFieldOptions::EnabledWithNormalizer {
+                normalizer: QuickwitTextNormalizer::TraceId
+            }
+        );
+    }
+
     #[test]
     fn test_parse_text_mapping_multivalued() {
         let entry = serde_json::from_str::<FieldMappingEntry>(
@@ -0,0 +1,593 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::bail;
+use quickwit_query::CharFilter;
+use serde::{Deserialize, Serialize};
+use tantivy::schema::IndexRecordOption;
+use tantivy::DateTimePrecision;
+
+use super::field_mapping_entry::FastFieldOptions;
+use super::mapping_tree::field_name_for_field_path;
+use super::{FieldMappingEntry, FieldMappingType, QuickwitTextTokenizer};
+
+/// Describes how a mapping change affects the documents already indexed. Variants are ordered
+/// from the least to the most disruptive.
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingChangeImpact {
+    /// Existing splits remain fully consistent with the new mapping.
+    NonBreaking,
+    /// Some queries or aggregations that used to work stop working, but existing splits do not
+    /// need to be rebuilt.
+    Breaking,
+    /// Existing splits are not consistent with the new mapping and the index must be reindexed.
+    RequiresReindex,
+}
+
+impl fmt::Display for MappingChangeImpact {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let impact_str = match self {
+            MappingChangeImpact::NonBreaking => "non-breaking",
+            MappingChangeImpact::Breaking => "breaking",
+            MappingChangeImpact::RequiresReindex => "requires reindex",
+        };
+        formatter.write_str(impact_str)
+    }
+}
+
+/// A single difference between two doc mappings.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MappingChange {
+    /// Path of the field affected by the change, or name of the doc mapping setting.
+    pub path: String,
+    /// Human readable description of the change.
+    pub description: String,
+    /// Impact of the change on the documents already indexed.
+    pub impact: MappingChangeImpact,
+}
+
+impl MappingChange {
+    /// Creates a new mapping change.
+    pub fn new(
+        path: impl Into<String>,
+        description: impl Into<String>,
+        impact: MappingChangeImpact,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            description: description.into(),
+            impact,
+        }
+    }
+}
+
+/// Lists the differences between two doc mappings and their impact on existing splits.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MappingCompatibilityReport {
+    /// Differences between the current and the new doc mappings.
+    pub changes: Vec<MappingChange>,
+}
+
+impl MappingCompatibilityReport {
+    /// Returns the highest impact among all the changes, if any.
+    pub fn max_impact(&self) -> Option<MappingChangeImpact> {
+        self.changes.iter().map(|change| change.impact).max()
+    }
+
+    /// Returns true if no change is breaking.
+    pub fn is_compatible(&self) -> bool {
+        matches!(
+            self.max_impact(),
+            None | Some(MappingChangeImpact::NonBreaking)
+        )
+    }
+
+    /// Returns true if at least one change requires reindexing existing data.
+    pub fn requires_reindex(&self) -> bool {
+        self.max_impact() == Some(MappingChangeImpact::RequiresReindex)
+    }
+
+    /// Returns an error listing the breaking changes, if any.
+    pub fn ensure_compatible(&self) -> anyhow::Result<()> {
+        if self.is_compatible() {
+            return Ok(());
+        }
+        let breaking_changes: Vec<String> = self
+            .changes
+            .iter()
+            .filter(|change| change.impact != MappingChangeImpact::NonBreaking)
+            .map(|change| {
+                format!(
+                    "`{}`: {} ({})",
+                    change.path, change.description, change.impact
+                )
+            })
+            .collect();
+        bail!(
+            "Doc mapping update contains breaking changes: {}.",
+            breaking_changes.join(", ")
+        );
+    }
+}
+
+/// Normalized view of the properties of a field mapping that matter for compatibility.
+#[derive(Debug)]
+struct FieldProperties {
+    type_id: String,
+    indexed: bool,
+    stored: bool,
+    fast: FastFieldOptions,
+    tokenizer: Option<QuickwitTextTokenizer>,
+    char_filters: Vec<CharFilter>,
+    protected_words: Vec<String>,
+    record: Option<IndexRecordOption>,
+    fieldnorms: bool,
+    precision: Option<DateTimePrecision>,
+}
+
+impl FieldProperties {
+    fn from_mapping_type(mapping_type: &FieldMappingType) -> Self {
+        let type_id = mapping_type.quickwit_field_type().to_type_id();
+        let mut properties = FieldProperties {
+            type_id,
+            indexed: false,
+            stored: false,
+            fast: FastFieldOptions::IsEnabled(false),
+            tokenizer: None,
+            char_filters: Vec::new(),
+            protected_words: Vec::new(),
+            record: None,
+            fieldnorms: false,
+            precision: None,
+        };
+        match mapping_type {
+            FieldMappingType::Text(options, _) => {
+                properties.indexed = options.indexed;
+                properties.stored = options.stored;
+                properties.fast = options.fast.clone();
+                if options.indexed {
+                    properties.tokenizer =
+                        Some(options.tokenizer.unwrap_or(QuickwitTextTokenizer::Default));
+                    properties.char_filters = options.char_filters.clone();
+                    properties.protected_words = options.protected_words.clone();
+                    properties.record = Some(options.record.unwrap_or(IndexRecordOption::Basic));
+                    properties.fieldnorms = options.fieldnorms;
+                }
+            }
+            FieldMappingType::Json(options, _) => {
+                properties.indexed = options.indexed;
+                properties.stored = options.stored;
+                properties.fast = options.fast.clone();
+                if options.indexed {
+                    properties.tokenizer =
+                        Some(options.tokenizer.unwrap_or(QuickwitTextTokenizer::Raw));
+                    properties.record = Some(options.record.unwrap_or(IndexRecordOption::Basic));
+                }
+            }
+            FieldMappingType::I64(options, _)
+            | FieldMappingType::U64(options, _)
+            | FieldMappingType::F64(options, _)
+            | FieldMappingType::Bool(options, _) => {
+                properties.indexed = options.indexed;
+                properties.stored = options.stored;
+                properties.fast = FastFieldOptions::IsEnabled(options.fast);
+            }
+            FieldMappingType::IpAddr(options, _) => {
+                properties.indexed = options.indexed;
+                properties.stored = options.stored;
+                properties.fast = FastFieldOptions::IsEnabled(options.fast);
+            }
+            FieldMappingType::Bytes(options, _) => {
+                properties.indexed = options.indexed;
+                properties.stored = options.stored;
+                properties.fast = FastFieldOptions::IsEnabled(options.fast);
+            }
+            FieldMappingType::DateTime(options, _) => {
+                properties.indexed = options.indexed;
+                properties.stored = options.stored;
+                properties.fast = FastFieldOptions::IsEnabled(options.fast);
+                properties.precision = Some(options.precision);
+            }
+            FieldMappingType::Object(_) => {}
+        }
+        properties
+    }
+}
+
+fn is_fast(fast: &FastFieldOptions) -> bool {
+    !matches!(fast, FastFieldOptions::IsEnabled(false))
+}
+
+fn flatten_field_mappings<'a>(
+    field_mappings: &'a [FieldMappingEntry],
+    field_path: &mut Vec<&'a str>,
+    flattened: &mut BTreeMap<String, &'a FieldMappingType>,
+) {
+    for field_mapping in field_mappings {
+        field_path.push(&field_mapping.name);
+        flattened.insert(
+            field_name_for_field_path(field_path),
+            &field_mapping.mapping_type,
+        );
+        match &field_mapping.mapping_type {
+            FieldMappingType::Object(object_options) => {
+                flatten_field_mappings(&object_options.field_mappings, field_path, flattened);
+            }
+            FieldMappingType::Text(text_options, _) => {
+                flatten_field_mappings(&text_options.fields, field_path, flattened);
+            }
+            _ => {}
+        }
+        field_path.pop();
+    }
+}
+
+fn compare_field_properties(
+    path: &str,
+    current: &FieldProperties,
+    new: &FieldProperties,
+    changes: &mut Vec<MappingChange>,
+) {
+    if current.type_id != new.type_id {
+        changes.push(MappingChange::new(
+            path,
+            format!(
+                "Type changed from `{}` to `{}`.",
+                current.type_id, new.type_id
+            ),
+            MappingChangeImpact::RequiresReindex,
+        ));
+        // Other properties are not comparable across types.
+        return;
+    }
+    if current.indexed != new.indexed {
+        let change = if new.indexed {
+            MappingChange::new(
+                path,
+                "Field is now indexed.",
+                MappingChangeImpact::RequiresReindex,
+            )
+        } else {
+            MappingChange::new(
+                path,
+                "Field is no longer indexed.",
+                MappingChangeImpact::Breaking,
+            )
+        };
+        changes.push(change);
+    }
+    if current.tokenizer.is_some() && new.tokenizer.is_some() && current.tokenizer != new.tokenizer
+    {
+        let tokenizer_name = |tokenizer: &Option<QuickwitTextTokenizer>| {
+            tokenizer
+                .as_ref()
+                .map(|tokenizer| tokenizer.get_name().to_string())
+                .unwrap_or_default()
+        };
+        changes.push(MappingChange::new(
+            path,
+            format!(
+                "Tokenizer changed from `{}` to `{}`.",
+                tokenizer_name(&current.tokenizer),
+                tokenizer_name(&new.tokenizer)
+            ),
+            MappingChangeImpact::RequiresReindex,
+        ));
+    }
+    if current.indexed && new.indexed && current.char_filters != new.char_filters {
+        changes.push(MappingChange::new(
+            path,
+            "Character filters changed.",
+            MappingChangeImpact::RequiresReindex,
+        ));
+    }
+    if current.indexed && new.indexed && current.protected_words != new.protected_words {
+        changes.push(MappingChange::new(
+            path,
+            "Protected words changed.",
+            MappingChangeImpact::RequiresReindex,
+        ));
+    }
+    if current.record.is_some() && new.record.is_some() && current.record != new.record {
+        changes.push(MappingChange::new(
+            path,
+            format!(
+                "Record option changed from `{:?}` to `{:?}`.",
+                current.record.unwrap(),
+                new.record.unwrap()
+            ),
+            MappingChangeImpact::RequiresReindex,
+        ));
+    }
+    if current.indexed && new.indexed && current.fieldnorms != new.fieldnorms {
+        changes.push(MappingChange::new(
+            path,
+            "Fieldnorms setting changed. Scoring of existing documents is unaffected.",
+            MappingChangeImpact::NonBreaking,
+        ));
+    }
+    match (is_fast(&current.fast), is_fast(&new.fast)) {
+        (false, true) => changes.push(MappingChange::new(
+            path,
+            "Fast field enabled.",
+            MappingChangeImpact::RequiresReindex,
+        )),
+        (true, false) => changes.push(MappingChange::new(
+            path,
+            "Fast field disabled. Sorting and aggregations on this field will fail.",
+            MappingChangeImpact::Breaking,
+        )),
+        (true, true) if current.fast != new.fast => changes.push(MappingChange::new(
+            path,
+            "Fast field normalizer changed.",
+            MappingChangeImpact::RequiresReindex,
+        )),
+        _ => {}
+    }
+    if current.stored != new.stored {
+        let description = if new.stored {
+            "Field is now stored. Existing documents will not return it."
+        } else {
+            "Field is no longer stored."
+        };
+        changes.push(MappingChange::new(
+            path,
+            description,
+            MappingChangeImpact::NonBreaking,
+        ));
+    }
+    if current.precision != new.precision {
+        changes.push(MappingChange::new(
+            path,
+            format!(
+                "Datetime precision changed from `{:?}` to `{:?}`.",
+                current.precision.unwrap_or_default(),
+                new.precision.unwrap_or_default()
+            ),
+            MappingChangeImpact::RequiresReindex,
+        ));
+    }
+}
+
+/// Lists the differences between two sets of field mappings and classifies them as breaking or
+/// non-breaking for the documents already indexed with `current_field_mappings`.
+pub fn check_field_mappings_compatibility(
+    current_field_mappings: &[FieldMappingEntry],
+    new_field_mappings: &[FieldMappingEntry],
+) -> MappingCompatibilityReport {
+    let mut current_fields = BTreeMap::new();
+    flatten_field_mappings(current_field_mappings, &mut Vec::new(), &mut current_fields);
+    let mut new_fields = BTreeMap::new();
+    flatten_field_mappings(new_field_mappings, &mut Vec::new(), &mut new_fields);
+
+    let mut changes = Vec::new();
+
+    for (path, current_mapping_type) in &current_fields {
+        let Some(new_mapping_type) = new_fields.get(path) else {
+            changes.push(MappingChange::new(
+                path,
+                "Field removed. Data indexed in existing splits is no longer searchable.",
+                MappingChangeImpact::Breaking,
+            ));
+            continue;
+        };
+        let current_properties = FieldProperties::from_mapping_type(current_mapping_type);
+        let new_properties = FieldProperties::from_mapping_type(new_mapping_type);
+        compare_field_properties(path, &current_properties, &new_properties, &mut changes);
+    }
+    for path in new_fields.keys() {
+        if !current_fields.contains_key(path) {
+            changes.push(MappingChange::new(
+                path,
+                "Field added. Existing documents will not match queries on this field.",
+                MappingChangeImpact::NonBreaking,
+            ));
+        }
+    }
+    MappingCompatibilityReport { changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_mappings(field_mappings_json: &str) -> Vec<FieldMappingEntry> {
+        serde_json::from_str(field_mappings_json).unwrap()
+    }
+
+    const CURRENT_FIELD_MAPPINGS: &str = r#"[
+        {"name": "timestamp", "type": "datetime", "fast": true},
+        {"name": "body", "type": "text"},
+        {"name": "severity", "type": "text", "tokenizer": "raw", "fast": true},
+        {"name": "latency", "type": "u64", "fast": true},
+        {
+            "name": "attributes",
+            "type": "object",
+            "field_mappings": [
+                {"name": "host.name", "type": "text", "tokenizer": "raw"}
+            ]
+        }
+    ]"#;
+
+    #[test]
+    fn test_mapping_compatibility_identical() {
+        let current = field_mappings(CURRENT_FIELD_MAPPINGS);
+        let report = check_field_mappings_compatibility(&current, &current);
+        assert!(report.changes.is_empty());
+        assert!(report.is_compatible());
+        assert!(!report.requires_reindex());
+        report.ensure_compatible().unwrap();
+    }
+
+    #[test]
+    fn test_mapping_compatibility_non_breaking() {
+        let current = field_mappings(CURRENT_FIELD_MAPPINGS);
+        let new = field_mappings(
+            r#"[
+            {"name": "timestamp", "type": "datetime", "fast": true},
+            {"name": "body", "type": "text", "stored": false, "description": "Log body"},
+            {"name": "severity", "type": "text", "tokenizer": "raw", "fast": true},
+            {"name": "latency", "type": "u64", "fast": true},
+            {"name": "trace_id", "type": "text", "tokenizer": "raw"},
+            {
+                "name": "attributes",
+                "type": "object",
+                "field_mappings": [
+                    {"name": "host.name", "type": "text", "tokenizer": "raw"}
+                ]
+            }
+        ]"#,
+        );
+        let report = check_field_mappings_compatibility(&current, &new);
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(report.changes[0].path, "body");
+        assert_eq!(report.changes[1].path, "trace_id");
+        assert!(report.is_compatible());
+        assert_eq!(report.max_impact(), Some(MappingChangeImpact::NonBreaking));
+    }
+
+    #[test]
+    fn test_mapping_compatibility_breaking() {
+        let current = field_mappings(CURRENT_FIELD_MAPPINGS);
+        let new = field_mappings(
+            r#"[
+            {"name": "timestamp", "type": "datetime", "fast": true},
+            {"name": "body", "type": "text"},
+            {"name": "severity", "type": "text", "tokenizer": "raw"},
+            {
+                "name": "attributes",
+                "type": "object",
+                "field_mappings": [
+                    {"name": "host.name", "type": "text", "tokenizer": "raw"}
+                ]
+            }
+        ]"#,
+        );
+        let report = check_field_mappings_compatibility(&current, &new);
+        assert_eq!(report.changes.len(), 2);
+        assert_eq!(report.changes[0].path, "latency");
+        assert_eq!(report.changes[0].impact, MappingChangeImpact::Breaking);
+        assert_eq!(report.changes[1].path, "severity");
+        assert_eq!(report.changes[1].impact, MappingChangeImpact::Breaking);
+        assert!(!report.is_compatible());
+        assert!(!report.requires_reindex());
+        let error = report.ensure_compatible().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`severity`: Fast field disabled"));
+    }
+
+    #[test]
+    fn test_mapping_compatibility_requires_reindex() {
+        let current = field_mappings(CURRENT_FIELD_MAPPINGS);
+        let new = field_mappings(
+            r#"[
+            {"name": "timestamp", "type": "datetime", "fast": true, "precision": "milliseconds"},
+            {"name": "body", "type": "text", "tokenizer": "en_stem", "fast": true},
+            {"name": "severity", "type": "text", "tokenizer": "raw", "fast": true},
+            {"name": "latency", "type": "f64", "fast": true},
+            {
+                "name": "attributes",
+                "type": "object",
+                "field_mappings": [
+                    {"name": "host.name", "type": "text", "record": "position", "tokenizer": "raw"}
+                ]
+            }
+        ]"#,
+        );
+        let report = check_field_mappings_compatibility(&current, &new);
+        let changes: Vec<(&str, &str)> = report
+            .changes
+            .iter()
+            .map(|change| (change.path.as_str(), change.description.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (
+                    "attributes.host\\.name",
+                    "Record option changed from `Basic` to `WithFreqsAndPositions`."
+                ),
+                ("body", "Tokenizer changed from `default` to `en_stem`."),
+                ("body", "Fast field enabled."),
+                ("latency", "Type changed from `u64` to `f64`."),
+                (
+                    "timestamp",
+                    "Datetime precision changed from `Seconds` to `Milliseconds`."
+                ),
+            ]
+        );
+        assert!(report
+            .changes
+            .iter()
+            .all(|change| change.impact == MappingChangeImpact::RequiresReindex));
+        assert!(report.requires_reindex());
+        assert!(!report.is_compatible());
+    }
+
+    #[test]
+    fn test_mapping_compatibility_char_filters_changed() {
+        let current = field_mappings(CURRENT_FIELD_MAPPINGS);
+        let new = field_mappings(
+            r#"[
+            {"name": "timestamp", "type": "datetime", "fast": true},
+            {"name": "body", "type": "text", "char_filters": [{"type": "html_strip"}]},
+            {"name": "severity", "type": "text", "tokenizer": "raw", "fast": true},
+            {"name": "latency", "type": "u64", "fast": true},
+            {
+                "name": "attributes",
+                "type": "object",
+                "field_mappings": [
+                    {"name": "host.name", "type": "text", "tokenizer": "raw"}
+                ]
+            }
+        ]"#,
+        );
+        let report = check_field_mappings_compatibility(&current, &new);
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].path, "body");
+        assert_eq!(report.changes[0].description, "Character filters changed.");
+        assert!(report.requires_reindex());
+    }
+
+    #[test]
+    fn test_mapping_compatibility_protected_words_changed() {
+        let current = field_mappings(
+            r#"[
+            {"name": "body", "type": "text", "tokenizer": "en_stem"}
+        ]"#,
+        );
+        let new = field_mappings(
+            r#"[
+            {"name": "body", "type": "text", "tokenizer": "en_stem", "protected_words": ["Pixel"]}
+        ]"#,
+        );
+        let report = check_field_mappings_compatibility(&current, &new);
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].path, "body");
+        assert_eq!(report.changes[0].description, "Protected words changed.");
+        assert!(report.requires_reindex());
+    }
+}
@@ -33,6 +33,7 @@ use tantivy::{DateOptions, Document};
 use tracing::warn;
 
 use super::date_time_type::QuickwitDateTimeOptions;
+use super::doc_validation::{collect_leaf_paths, DocValidationReport, FieldValidation};
 use crate::default_doc_mapper::field_mapping_entry::{
     QuickwitBytesOptions, QuickwitIpAddrOptions, QuickwitNumericOptions, QuickwitObjectOptions,
     QuickwitTextOptions,
@@ -101,6 +102,8 @@ pub(crate) struct MappingLeaf {
     field: Field,
     typ: LeafType,
     cardinality: Cardinality,
+    /// Fields of the text sub-fields (multi-fields), in the order of their definition.
+    sub_fields: Vec<Field>,
 }
 
 impl MappingLeaf {
@@ -110,14 +113,29 @@ impl MappingLeaf {
         document: &mut Document,
         path: &mut [String],
     ) -> Result<(), DocParsingError> {
+        for value in self.values_from_json(json_val, path)? {
+            for sub_field in &self.sub_fields {
+                document.add_field_value(*sub_field, value.clone());
+            }
+            document.add_field_value(self.field, value);
+        }
+        Ok(())
+    }
+
+    fn values_from_json(
+        &self,
+        json_val: JsonValue,
+        path: &[String],
+    ) -> Result<Vec<TantivyValue>, DocParsingError> {
         if json_val.is_null() {
             // We just ignore `null`.
-            return Ok(());
+            return Ok(Vec::new());
         }
         if let JsonValue::Array(els) = json_val {
             if self.cardinality == Cardinality::SingleValue {
                 return Err(DocParsingError::MultiValuesNotSupported(path.join(".")));
             }
+            let mut values = Vec::with_capacity(els.len());
             for el_json_val in els {
                 if el_json_val.is_null() {
                     // We just ignore `null`.
@@ -127,16 +145,46 @@ impl MappingLeaf {
                     .typ
                     .value_from_json(el_json_val)
                     .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
-                document.add_field_value(self.field, value);
+                values.push(value);
             }
-            return Ok(());
+            return Ok(values);
         }
         let value = self
             .typ
             .value_from_json(json_val)
             .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
-        document.add_field_value(self.field, value);
-        Ok(())
+        Ok(vec![value])
+    }
+
+    fn validate_json(
+        &self,
+        json_val: JsonValue,
+        path: &[String],
+        report: &mut DocValidationReport,
+    ) {
+        let field_type = FieldMappingType::from(self.clone())
+            .quickwit_field_type()
+            .to_type_id();
+        let field_validation = match self.values_from_json(json_val.clone(), path) {
+            Ok(values) => FieldValidation {
+                path: path.join("."),
+                field_type,
+                input: json_val,
+                values: values
+                    .into_iter()
+                    .flat_map(|value| value_to_json(value, &self.typ))
+                    .collect(),
+                error: None,
+            },
+            Err(error) => FieldValidation {
+                path: path.join("."),
+                field_type,
+                input: json_val,
+                values: Vec::new(),
+                error: Some(error.to_string()),
+            },
+        };
+        report.fields.push(field_validation);
     }
 
     fn populate_json<'a>(
@@ -152,6 +200,21 @@ impl MappingLeaf {
         }
     }
 
+    /// Finds the field mapping type of a text sub-field of this leaf.
+    fn find_sub_field_mapping_type(&self, field_path: &[String]) -> Option<FieldMappingType> {
+        let LeafType::Text(text_options) = &self.typ else {
+            return None;
+        };
+        let [sub_field_name] = field_path else {
+            return None;
+        };
+        text_options
+            .fields
+            .iter()
+            .find(|sub_field_entry| &sub_field_entry.name == sub_field_name)
+            .map(|sub_field_entry| sub_field_entry.mapping_type.clone())
+    }
+
     pub fn get_type(&self) -> &LeafType {
         &self.typ
     }
@@ -162,6 +225,7 @@ fn extract_json_val(
     named_doc: &mut BTreeMap<String, Vec<TantivyValue>>,
     field_path: &[&str],
     cardinality: Cardinality,
+    sub_fields: Vec::new(),
 ) -> Option<JsonValue> {
     let full_path = field_path.join(".");
     let vals = named_doc.remove(&full_path)?;
@@ -309,7 +373,7 @@ impl MappingNode {
         let child_tree = self.branches.get(field_name).expect("Missing field");
         match (child_tree, sub_field_path.is_empty()) {
             (_, true) => Some(child_tree.clone().into()),
-            (MappingTree::Leaf(_), false) => None,
+            (MappingTree::Leaf(leaf), false) => leaf.find_sub_field_mapping_type(sub_field_path),
             (MappingTree::Node(child_node), false) => {
                 child_node.internal_find_field_mapping_type(sub_field_path)
             }
@@ -374,6 +438,30 @@ impl MappingNode {
         Ok(())
     }
 
+    /// Reports how the fields of a JSON object would be coerced and where its unmapped fields
+    /// would end up, without stopping at the first invalid field.
+    pub fn validate_json(
+        &self,
+        json_obj: serde_json::Map<String, JsonValue>,
+        mode: ModeType,
+        path: &mut Vec<String>,
+        report: &mut DocValidationReport,
+    ) {
+        for (field_name, val) in json_obj {
+            let child_tree_opt = self.branches.get(&field_name);
+            path.push(field_name);
+
+            if let Some(child_tree) = child_tree_opt {
+                child_tree.validate_json(val, mode, path, report);
+            } else if mode == ModeType::Dynamic {
+                collect_leaf_paths(&path.join("."), &val, &mut report.dynamic_paths);
+            } else {
+                collect_leaf_paths(&path.join("."), &val, &mut report.unmapped_paths);
+            }
+            path.pop();
+        }
+    }
+
     pub fn populate_json<'a>(
         &'a self,
         named_doc: &mut BTreeMap<String, Vec<TantivyValue>>,
@@ -453,6 +541,31 @@ impl MappingTree {
         }
     }
 
+    fn validate_json(
+        &self,
+        json_value: JsonValue,
+        mode: ModeType,
+        path: &mut Vec<String>,
+        report: &mut DocValidationReport,
+    ) {
+        match self {
+            MappingTree::Leaf(mapping_leaf) => mapping_leaf.validate_json(json_value, path, report),
+            MappingTree::Node(mapping_node) => {
+                if let JsonValue::Object(json_obj) = json_value {
+                    mapping_node.validate_json(json_obj, mode, path, report);
+                } else {
+                    report.fields.push(FieldValidation {
+                        path: path.join("."),
+                        field_type: "object".to_string(),
+                        error: Some(format!("Expected an JSON Object, got {json_value}")),
+                        input: json_value,
+                        values: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+
     fn populate_json<'a>(
         &'a self,
         named_doc: &mut BTreeMap<String, Vec<TantivyValue>>,
@@ -561,7 +674,7 @@ fn get_ip_address_options(quickwit_ip_address_options: &QuickwitIpAddrOptions) -
 /// We simply concatenate these field names, interleaving them with '.'.
 /// If a fieldname itself contains a '.', we escape it with '\'.
 /// ('\' itself is forbidden).
-fn field_name_for_field_path(field_path: &[&str]) -> String {
+pub(crate) fn field_name_for_field_path(field_path: &[&str]) -> String {
     field_path.iter().cloned().map(escape_dots).join(".")
 }
 
@@ -613,10 +726,31 @@ fn build_mapping_from_field_type<'a>(
         FieldMappingType::Text(options, cardinality) => {
             let text_options: TextOptions = options.clone().into();
             let field = schema_builder.add_text_field(&field_name, text_options);
+            let mut sub_fields = Vec::with_capacity(options.fields.len());
+            for sub_field_entry in &options.fields {
+                let FieldMappingType::Text(sub_field_options, _) = &sub_field_entry.mapping_type
+                else {
+                    bail!("Sub-field `{}` must be of type `text`.", sub_field_entry.name);
+                };
+                field_path.push(&sub_field_entry.name);
+                let sub_field_name = field_name_for_field_path(field_path);
+                field_path.pop();
+                // Sub-fields index the value of their parent field, which is the one being
+                // stored.
+                let sub_field_text_options: TextOptions = QuickwitTextOptions {
+                    stored: false,
+                    ..sub_field_options.clone()
+                }
+                .into();
+                let sub_field =
+                    schema_builder.add_text_field(&sub_field_name, sub_field_text_options);
+                sub_fields.push(sub_field);
+            }
             let mapping_leaf = MappingLeaf {
                 field,
                 typ: LeafType::Text(options.clone()),
                 cardinality: *cardinality,
+                sub_fields,
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -627,6 +761,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::I64(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -637,6 +772,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::U64(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -647,6 +783,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::F64(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -657,6 +794,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::Bool(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -667,6 +805,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::IpAddr(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -677,6 +816,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::DateTime(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -687,6 +827,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::Bytes(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             };
             Ok(MappingTree::Leaf(mapping_leaf))
         }
@@ -697,6 +838,7 @@ fn build_mapping_from_field_type<'a>(
                 field,
                 typ: LeafType::Json(options.clone()),
                 cardinality: *cardinality,
+                sub_fields: Vec::new(),
             }))
         }
         FieldMappingType::Object(entries) => {
@@ -880,6 +1022,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            sub_fields: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -931,6 +1074,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            sub_fields: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -953,6 +1097,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            sub_fields: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -970,6 +1115,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            sub_fields: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = Vec::new();
@@ -988,6 +1134,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            sub_fields: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = vec!["root".to_string(), "my_field".to_string()];
@@ -1103,6 +1250,7 @@ mod tests {
             field,
             typ,
             cardinality: Cardinality::MultiValues,
+            sub_fields: Vec::new(),
         };
         let mut document = Document::default();
         let mut path = vec!["root".to_string(), "my_field".to_string()];
@@ -20,9 +20,12 @@
 mod date_time_type;
 mod default_mapper;
 mod default_mapper_builder;
+mod doc_validation;
 mod field_mapping_entry;
 mod field_mapping_type;
+mod mapping_compatibility;
 mod mapping_tree;
+mod text_analysis;
 
 use anyhow::bail;
 use once_cell::sync::Lazy;
@@ -30,10 +33,16 @@ use regex::Regex;
 
 pub use self::default_mapper::DefaultDocMapper;
 pub use self::default_mapper_builder::{DefaultDocMapperBuilder, ModeType};
+pub use self::doc_validation::{DocValidationReport, FieldValidation};
 pub use self::field_mapping_entry::{
     FastFieldOptions, FieldMappingEntry, QuickwitBytesOptions, QuickwitJsonOptions,
     QuickwitNumericOptions, QuickwitTextNormalizer, QuickwitTextOptions,
 };
+pub use self::mapping_compatibility::{
+    check_field_mappings_compatibility, MappingChange, MappingChangeImpact,
+    MappingCompatibilityReport,
+};
+pub use self::text_analysis::{analyze_text, AnalyzedToken, TextAnalysis};
 pub(crate) use self::field_mapping_entry::{
     FieldMappingEntryForSerialization, IndexRecordOptionSchema, QuickwitTextTokenizer,
 };
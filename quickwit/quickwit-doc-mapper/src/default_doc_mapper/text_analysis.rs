@@ -0,0 +1,96 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::Context;
+use quickwit_query::get_quickwit_tokenizer_manager;
+use serde::{Deserialize, Serialize};
+use tantivy::tokenizer::TokenStream;
+
+/// Token produced by the analysis of a text.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AnalyzedToken {
+    /// Text of the token, as it is indexed.
+    pub token: String,
+    /// Byte offset of the start of the token in the analyzed text.
+    pub start_offset: usize,
+    /// Byte offset of the end of the token in the analyzed text.
+    pub end_offset: usize,
+    /// Position of the token, used by phrase queries.
+    pub position: usize,
+}
+
+/// Describes how a text is split into tokens when it is indexed or searched.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TextAnalysis {
+    /// Name of the tokenizer the text is analyzed with.
+    pub tokenizer: String,
+    /// Tokens produced by the tokenizer, in order.
+    pub tokens: Vec<AnalyzedToken>,
+}
+
+/// Analyzes `text` with the tokenizer registered as `tokenizer_name`.
+pub fn analyze_text(tokenizer_name: &str, text: &str) -> anyhow::Result<TextAnalysis> {
+    let text_analyzer = get_quickwit_tokenizer_manager()
+        .get(tokenizer_name)
+        .with_context(|| format!("Unknown tokenizer `{tokenizer_name}`."))?;
+    let mut token_stream = text_analyzer.token_stream(text);
+    let mut tokens = Vec::new();
+    while let Some(token) = token_stream.next() {
+        tokens.push(AnalyzedToken {
+            token: token.text.clone(),
+            start_offset: token.offset_from,
+            end_offset: token.offset_to,
+            position: token.position,
+        });
+    }
+    Ok(TextAnalysis {
+        tokenizer: tokenizer_name.to_string(),
+        tokens,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_text() {
+        let text_analysis = analyze_text("default", "Hello, World!").unwrap();
+        assert_eq!(text_analysis.tokenizer, "default");
+        assert_eq!(
+            text_analysis.tokens,
+            [
+                AnalyzedToken {
+                    token: "hello".to_string(),
+                    start_offset: 0,
+                    end_offset: 5,
+                    position: 0,
+                },
+                AnalyzedToken {
+                    token: "world".to_string(),
+                    start_offset: 7,
+                    end_offset: 12,
+                    position: 1,
+                },
+            ]
+        );
+        let error = analyze_text("unknown", "Hello").unwrap_err();
+        assert_eq!(error.to_string(), "Unknown tokenizer `unknown`.");
+    }
+}
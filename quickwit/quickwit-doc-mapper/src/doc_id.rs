@@ -0,0 +1,502 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+use std::hash::Hasher;
+
+use anyhow::bail;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use quickwit_query::query_ast::{BoolQuery, BoostingQuery, DisMaxQuery, QueryAst, UdfQuery};
+use quickwit_query::DOC_ID_FIELD_ALIAS;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use siphasher::sip128::{Hasher128, SipHasher13};
+
+/// Number of bits allocated per document ID in bloom filters. Along with
+/// [`BLOOM_FILTER_NUM_HASHES`], it yields a false positive rate below 1%.
+const BLOOM_FILTER_NUM_BITS_PER_DOC_ID: usize = 10;
+
+const BLOOM_FILTER_NUM_HASHES: u8 = 7;
+
+/// Maximum size of a document ID bloom filter.
+///
+/// Bloom filters are stored in the split metadata, which is listed by every search: splits
+/// holding too many document IDs to fit in this budget are not given a bloom filter and are
+/// never pruned.
+pub const MAX_DOC_ID_BLOOM_FILTER_NUM_BYTES: usize = 128 * 1024;
+
+/// Defines the field holding the unique ID of the documents of an index.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DocIdConfig {
+    /// Name of the field holding the document ID. It must be a root text field, single-valued,
+    /// indexed with the `raw` tokenizer and fast.
+    pub field: String,
+    /// Defines how an ID is generated for the documents that do not hold one.
+    #[serde(default)]
+    pub generation: DocIdGeneration,
+}
+
+/// Defines how an ID is generated for the documents that do not hold one.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DocIdGeneration {
+    /// Documents without an ID are rejected.
+    #[default]
+    None,
+    /// Documents without an ID are given a random UUID (v4).
+    Uuid,
+    /// Documents without an ID are given a ULID.
+    Ulid,
+}
+
+impl DocIdGeneration {
+    /// Generates a new document ID, or returns `None` if generation is disabled.
+    pub fn generate(&self) -> Option<String> {
+        match self {
+            DocIdGeneration::None => None,
+            DocIdGeneration::Uuid => Some(uuid::Uuid::new_v4().to_string()),
+            DocIdGeneration::Ulid => Some(ulid::Ulid::new().to_string()),
+        }
+    }
+}
+
+/// Bloom filter over the document IDs of a split.
+///
+/// It is used to skip the splits that cannot hold the documents targeted by a query on the
+/// document ID field.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocIdBloomFilter {
+    num_hashes: u8,
+    bits: Vec<u64>,
+}
+
+impl DocIdBloomFilter {
+    /// Creates an empty bloom filter sized for `num_doc_ids` document IDs. Returns `None` if the
+    /// bloom filter would exceed [`MAX_DOC_ID_BLOOM_FILTER_NUM_BYTES`].
+    pub fn with_num_doc_ids(num_doc_ids: usize) -> Option<Self> {
+        let num_bits = num_doc_ids.max(1) * BLOOM_FILTER_NUM_BITS_PER_DOC_ID;
+        let num_words = (num_bits + 63) / 64;
+
+        if num_words * 8 > MAX_DOC_ID_BLOOM_FILTER_NUM_BYTES {
+            return None;
+        }
+        Some(Self {
+            num_hashes: BLOOM_FILTER_NUM_HASHES,
+            bits: vec![0; num_words],
+        })
+    }
+
+    /// Adds a document ID to the bloom filter.
+    pub fn insert(&mut self, doc_id: &[u8]) {
+        for bit_position in self.bit_positions(doc_id) {
+            self.bits[bit_position / 64] |= 1 << (bit_position % 64);
+        }
+    }
+
+    /// Returns false if the document ID was definitely not added to the bloom filter.
+    pub fn may_contain(&self, doc_id: &[u8]) -> bool {
+        self.bit_positions(doc_id)
+            .all(|bit_position| self.bits[bit_position / 64] & (1 << (bit_position % 64)) != 0)
+    }
+
+    /// Returns the size of the bloom filter in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    /// Derives the positions of the bits of a document ID from two independent hashes
+    /// (Kirsch-Mitzenmacher). The hasher keys are fixed so that positions remain stable across
+    /// releases.
+    fn bit_positions(&self, doc_id: &[u8]) -> impl Iterator<Item = usize> {
+        let mut hasher = SipHasher13::new();
+        hasher.write(doc_id);
+        let hash = hasher.finish128();
+        let num_bits = self.bits.len() as u64 * 64;
+        (0..self.num_hashes as u64)
+            .map(move |i| (hash.h1.wrapping_add(i.wrapping_mul(hash.h2)) % num_bits) as usize)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.num_bytes());
+        bytes.push(self.num_hashes);
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some((&num_hashes, bits_bytes)) = bytes.split_first() else {
+            anyhow::bail!("Bloom filter is empty.");
+        };
+        if num_hashes == 0 || bits_bytes.is_empty() || bits_bytes.len() % 8 != 0 {
+            anyhow::bail!("Bloom filter is malformed.");
+        }
+        let bits = bits_bytes
+            .chunks_exact(8)
+            .map(|word_bytes| u64::from_le_bytes(word_bytes.try_into().unwrap()))
+            .collect();
+        Ok(Self { num_hashes, bits })
+    }
+}
+
+impl Serialize for DocIdBloomFilter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(self.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for DocIdBloomFilter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let bytes = BASE64_STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)?;
+        DocIdBloomFilter::from_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Extracts the set of document IDs a document has to hold one of to match the query.
+///
+/// Returns `None` if the query does not constrain the document ID field, in which case no split
+/// can be pruned.
+pub fn extract_doc_ids_from_query(
+    query_ast: &QueryAst,
+    doc_id_field: &str,
+) -> Option<BTreeSet<String>> {
+    match query_ast {
+        QueryAst::Term(term_query) if term_query.field == doc_id_field => {
+            Some(BTreeSet::from([term_query.value.clone()]))
+        }
+        QueryAst::TermSet(term_set_query) if term_set_query.terms_per_field.len() == 1 => {
+            term_set_query.terms_per_field.get(doc_id_field).cloned()
+        }
+        QueryAst::Bool(bool_query) => {
+            let mut doc_ids_opt: Option<BTreeSet<String>> = None;
+
+            for clause in bool_query.must.iter().chain(bool_query.filter.iter()) {
+                let Some(clause_doc_ids) = extract_doc_ids_from_query(clause, doc_id_field) else {
+                    continue;
+                };
+                doc_ids_opt = Some(match doc_ids_opt {
+                    Some(doc_ids) => doc_ids.intersection(&clause_doc_ids).cloned().collect(),
+                    None => clause_doc_ids,
+                });
+            }
+            if doc_ids_opt.is_some()
+                || bool_query.should.is_empty()
+                || !bool_query.must.is_empty()
+                || !bool_query.filter.is_empty()
+            {
+                return doc_ids_opt;
+            }
+            // Without must or filter clauses, one of the should clauses has to match.
+            let mut doc_ids = BTreeSet::new();
+
+            for clause in &bool_query.should {
+                doc_ids.extend(extract_doc_ids_from_query(clause, doc_id_field)?);
+            }
+            Some(doc_ids)
+        }
+        QueryAst::Boost { underlying, .. } | QueryAst::ConstScore { underlying, .. } => {
+            extract_doc_ids_from_query(underlying, doc_id_field)
+        }
+        _ => None,
+    }
+}
+
+/// Rewrites the term, term set and full-text clauses targeting the
+/// [`DOC_ID_FIELD_ALIAS`] so that they target the doc ID field of the index instead.
+///
+/// Returns an error if the query uses the alias but the index does not define a doc ID field.
+pub fn resolve_doc_id_field_alias(
+    query_ast: QueryAst,
+    doc_id_field_opt: Option<&str>,
+) -> anyhow::Result<QueryAst> {
+    let resolve_field = |field: String| -> anyhow::Result<String> {
+        if field != DOC_ID_FIELD_ALIAS {
+            return Ok(field);
+        }
+        let Some(doc_id_field) = doc_id_field_opt else {
+            bail!(
+                "Field `{DOC_ID_FIELD_ALIAS}` can only be queried on indexes defining a doc ID \
+                 field."
+            );
+        };
+        Ok(doc_id_field.to_string())
+    };
+    let resolve_asts = |asts: Vec<QueryAst>| -> anyhow::Result<Vec<QueryAst>> {
+        asts.into_iter()
+            .map(|ast| resolve_doc_id_field_alias(ast, doc_id_field_opt))
+            .collect()
+    };
+    let resolved_query_ast = match query_ast {
+        QueryAst::Bool(BoolQuery {
+            must,
+            must_not,
+            should,
+            filter,
+            minimum_should_match,
+        }) => BoolQuery {
+            must: resolve_asts(must)?,
+            must_not: resolve_asts(must_not)?,
+            should: resolve_asts(should)?,
+            filter: resolve_asts(filter)?,
+            minimum_should_match,
+        }
+        .into(),
+        QueryAst::DisMax(DisMaxQuery {
+            queries,
+            tie_breaker,
+        }) => DisMaxQuery {
+            queries: resolve_asts(queries)?,
+            tie_breaker,
+        }
+        .into(),
+        QueryAst::Boosting(BoostingQuery {
+            positive,
+            negative,
+            negative_boost,
+        }) => BoostingQuery {
+            positive: Box::new(resolve_doc_id_field_alias(*positive, doc_id_field_opt)?),
+            negative: Box::new(resolve_doc_id_field_alias(*negative, doc_id_field_opt)?),
+            negative_boost,
+        }
+        .into(),
+        QueryAst::Term(mut term_query) => {
+            term_query.field = resolve_field(term_query.field)?;
+            term_query.into()
+        }
+        QueryAst::TermSet(mut term_set_query) => {
+            if let Some(doc_ids) = term_set_query.terms_per_field.remove(DOC_ID_FIELD_ALIAS) {
+                let doc_id_field = resolve_field(DOC_ID_FIELD_ALIAS.to_string())?;
+                term_set_query
+                    .terms_per_field
+                    .entry(doc_id_field)
+                    .or_default()
+                    .extend(doc_ids);
+            }
+            term_set_query.into()
+        }
+        QueryAst::TermsLookup(mut terms_lookup_query) => {
+            terms_lookup_query.field = resolve_field(terms_lookup_query.field)?;
+            terms_lookup_query.into()
+        }
+        QueryAst::FullText(mut full_text_query) => {
+            full_text_query.field = resolve_field(full_text_query.field)?;
+            full_text_query.into()
+        }
+        QueryAst::Boost { underlying, boost } => QueryAst::Boost {
+            underlying: Box::new(resolve_doc_id_field_alias(*underlying, doc_id_field_opt)?),
+            boost,
+        },
+        QueryAst::ConstScore { underlying, score } => QueryAst::ConstScore {
+            underlying: Box::new(resolve_doc_id_field_alias(*underlying, doc_id_field_opt)?),
+            score,
+        },
+        QueryAst::Udf(udf_query) => UdfQuery {
+            underlying: Box::new(resolve_doc_id_field_alias(
+                *udf_query.underlying,
+                doc_id_field_opt,
+            )?),
+            ..udf_query
+        }
+        .into(),
+        QueryAst::FunctionScore(function_score_query) => function_score_query
+            .try_map_subqueries(|query_ast| {
+                resolve_doc_id_field_alias(query_ast, doc_id_field_opt)
+            })?
+            .into(),
+        ast @ QueryAst::PhrasePrefix(_)
+        | ast @ QueryAst::CombinedFields(_)
+        | ast @ QueryAst::Intervals(_)
+        | ast @ QueryAst::Span(_)
+        | ast @ QueryAst::Knn(_)
+        | ast @ QueryAst::Regex(_)
+        | ast @ QueryAst::Range(_)
+        | ast @ QueryAst::FieldPresence(_)
+        | ast @ QueryAst::UserInput(_)
+        | ast @ QueryAst::SimpleUserInput(_)
+        | ast @ QueryAst::MatchAll
+        | ast @ QueryAst::MatchNone => ast,
+    };
+    Ok(resolved_query_ast)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use quickwit_query::query_ast::{BoolQuery, TermQuery, TermSetQuery};
+
+    use super::*;
+
+    #[test]
+    fn test_doc_id_generation() {
+        assert!(DocIdGeneration::None.generate().is_none());
+
+        let uuid = DocIdGeneration::Uuid.generate().unwrap();
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+
+        let ulid = DocIdGeneration::Ulid.generate().unwrap();
+        assert!(ulid::Ulid::from_string(&ulid).is_ok());
+    }
+
+    #[test]
+    fn test_doc_id_bloom_filter() {
+        let mut bloom_filter = DocIdBloomFilter::with_num_doc_ids(1_000).unwrap();
+        assert_eq!(bloom_filter.num_bytes(), 1_256);
+
+        for i in 0..1_000 {
+            bloom_filter.insert(format!("doc-{i}").as_bytes());
+        }
+        for i in 0..1_000 {
+            assert!(bloom_filter.may_contain(format!("doc-{i}").as_bytes()));
+        }
+        let num_false_positives = (1_000..11_000)
+            .filter(|i| bloom_filter.may_contain(format!("doc-{i}").as_bytes()))
+            .count();
+        assert!(num_false_positives < 200);
+
+        let serialized = serde_json::to_string(&bloom_filter).unwrap();
+        let deserialized: DocIdBloomFilter = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, bloom_filter);
+
+        assert!(DocIdBloomFilter::with_num_doc_ids(10_000_000).is_none());
+        assert!(serde_json::from_str::<DocIdBloomFilter>(r#""AAA=""#).is_err());
+    }
+
+    #[test]
+    fn test_extract_doc_ids_from_query() {
+        let doc_ids = |values: &[&str]| -> Option<BTreeSet<String>> {
+            Some(values.iter().map(|value| value.to_string()).collect())
+        };
+        let term_query = |field: &str, value: &str| -> QueryAst {
+            TermQuery::from_field_value(field, value).into()
+        };
+        assert_eq!(
+            extract_doc_ids_from_query(&term_query("id", "doc-1"), "id"),
+            doc_ids(&["doc-1"])
+        );
+        assert_eq!(
+            extract_doc_ids_from_query(&term_query("body", "doc-1"), "id"),
+            None
+        );
+        let term_set_query: QueryAst = TermSetQuery {
+            terms_per_field: [(
+                "id".to_string(),
+                ["doc-1".to_string(), "doc-2".to_string()]
+                    .into_iter()
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+        }
+        .into();
+        assert_eq!(
+            extract_doc_ids_from_query(&term_set_query, "id"),
+            doc_ids(&["doc-1", "doc-2"])
+        );
+        let must_query: QueryAst = BoolQuery {
+            must: vec![term_set_query.clone(), term_query("body", "hello")],
+            filter: vec![term_query("id", "doc-2")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            extract_doc_ids_from_query(&must_query, "id"),
+            doc_ids(&["doc-2"])
+        );
+        let should_query: QueryAst = BoolQuery {
+            should: vec![term_query("id", "doc-1"), term_query("id", "doc-3")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            extract_doc_ids_from_query(&should_query, "id"),
+            doc_ids(&["doc-1", "doc-3"])
+        );
+        let uninformative_should_query: QueryAst = BoolQuery {
+            should: vec![term_query("id", "doc-1"), term_query("body", "hello")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            extract_doc_ids_from_query(&uninformative_should_query, "id"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_doc_id_field_alias() {
+        let ids_query: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "_id".to_string(),
+                BTreeSet::from(["doc-1".to_string(), "doc-2".to_string()]),
+            )]),
+        }
+        .into();
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![TermQuery::from_field_value("body", "hello").into()],
+            filter: vec![BoolQuery {
+                should: vec![ids_query],
+                ..Default::default()
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        let resolved_query_ast = resolve_doc_id_field_alias(query_ast.clone(), Some("id")).unwrap();
+        let expected_ids_query: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "id".to_string(),
+                BTreeSet::from(["doc-1".to_string(), "doc-2".to_string()]),
+            )]),
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![TermQuery::from_field_value("body", "hello").into()],
+            filter: vec![BoolQuery {
+                should: vec![expected_ids_query],
+                ..Default::default()
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(resolved_query_ast, expected_query_ast);
+        assert_eq!(
+            extract_doc_ids_from_query(&resolved_query_ast, "id"),
+            Some(BTreeSet::from(["doc-1".to_string(), "doc-2".to_string()]))
+        );
+
+        let error = resolve_doc_id_field_alias(query_ast, None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Field `_id` can only be queried on indexes defining a doc ID field."
+        );
+
+        let term_query: QueryAst = TermQuery::from_field_value("_id", "doc-1").into();
+        let expected_term_query: QueryAst = TermQuery::from_field_value("id", "doc-1").into();
+        assert_eq!(
+            resolve_doc_id_field_alias(term_query, Some("id")).unwrap(),
+            expected_term_query
+        );
+    }
+}
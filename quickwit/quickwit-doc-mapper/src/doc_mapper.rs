@@ -35,7 +35,7 @@ pub type Partition = u64;
 /// An alias for serde_json's object type.
 pub type JsonObject = serde_json::Map<String, JsonValue>;
 
-use crate::{DocParsingError, QueryParserError};
+use crate::{DocParsingError, DocValidationReport, QueryParserError, TextAnalysis};
 
 /// The `DocMapper` trait defines the way of defining how a (json) document,
 /// and the fields it contains, are stored and indexed.
@@ -76,6 +76,14 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         self.doc_from_json_obj(json_obj)
     }
 
+    /// Reports how a JSON object would be indexed, field by field, without stopping at the first
+    /// invalid field.
+    fn validate_json_obj(&self, json_obj: JsonObject) -> DocValidationReport;
+
+    /// Analyzes `text` with the tokenizer of the field `field_path`, as it would be indexed or
+    /// searched in this field.
+    fn analyze_field_text(&self, field_path: &str, text: &str) -> anyhow::Result<TextAnalysis>;
+
     /// Converts a tantivy named Document to the json format.
     ///
     /// Tantivy does not have any notion of cardinality nor object.
@@ -122,6 +130,28 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         Default::default()
     }
 
+    /// Returns the name of the field holding the unique ID of the documents, if any.
+    fn doc_id_field_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the `NamedField` holding the document IDs on the current schema, if any.
+    /// Returns an error if the field is not found in this schema.
+    fn doc_id_named_field(&self) -> anyhow::Result<Option<NamedField>> {
+        let Some(field_name) = self.doc_id_field_name() else {
+            return Ok(None);
+        };
+        let index_schema = self.schema();
+        let field = index_schema
+            .get_field(field_name)
+            .context(format!("Field `{field_name}` must exist in the schema."))?;
+        Ok(Some(NamedField {
+            name: field_name.to_string(),
+            field,
+            field_type: index_schema.get_field_entry(field).field_type().clone(),
+        }))
+    }
+
     /// Returns the tag `NameField`s on the current schema.
     /// Returns an error if a tag field is not found in this schema.
     fn tag_named_fields(&self) -> anyhow::Result<Vec<NamedField>> {
@@ -344,6 +374,10 @@ mod tests {
             user_text: "json_field.toto.titi:hello".to_string(),
             default_fields: None,
             default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .parse_user_query(&[])
         .unwrap();
@@ -26,6 +26,7 @@
 //! engine, aka tantivy::Document.
 
 mod default_doc_mapper;
+mod doc_id;
 mod doc_mapper;
 mod error;
 mod query_builder;
@@ -35,12 +36,19 @@ mod routing_expression;
 pub mod tag_pruning;
 
 pub use default_doc_mapper::{
-    DefaultDocMapper, DefaultDocMapperBuilder, FieldMappingEntry, ModeType, QuickwitJsonOptions,
+    analyze_text, check_field_mappings_compatibility, AnalyzedToken, DefaultDocMapper,
+    DefaultDocMapperBuilder, DocValidationReport, FieldMappingEntry, FieldValidation,
+    MappingChange, MappingChangeImpact, MappingCompatibilityReport, ModeType, QuickwitJsonOptions,
+    TextAnalysis,
 };
 use default_doc_mapper::{
     FastFieldOptions, FieldMappingEntryForSerialization, IndexRecordOptionSchema,
     QuickwitTextNormalizer, QuickwitTextTokenizer,
 };
+pub use doc_id::{
+    extract_doc_ids_from_query, resolve_doc_id_field_alias, DocIdBloomFilter, DocIdConfig,
+    DocIdGeneration, MAX_DOC_ID_BLOOM_FILTER_NUM_BYTES,
+};
 pub use doc_mapper::{DocMapper, JsonObject, NamedField, TermRange, WarmupInfo};
 pub use error::{DocParsingError, QueryParserError};
 
@@ -50,8 +58,15 @@ pub const SOURCE_FIELD_NAME: &str = "_source";
 /// Field name reserved for storing the dynamically indexed fields.
 pub const DYNAMIC_FIELD_NAME: &str = "_dynamic";
 
+/// Field name reserved for indexing the paths of the fields present in each document.
+pub use quickwit_query::query_ast::FIELD_PRESENCE_FIELD_NAME;
+
 /// Quickwit reserved field names.
-const QW_RESERVED_FIELD_NAMES: &[&str] = &[SOURCE_FIELD_NAME, DYNAMIC_FIELD_NAME];
+const QW_RESERVED_FIELD_NAMES: &[&str] = &[
+    SOURCE_FIELD_NAME,
+    DYNAMIC_FIELD_NAME,
+    FIELD_PRESENCE_FIELD_NAME,
+];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) enum Cardinality {
@@ -68,6 +83,15 @@ pub(crate) enum Cardinality {
     QuickwitTextTokenizer,
     IndexRecordOptionSchema,
     FieldMappingEntryForSerialization,
+    MappingChange,
+    MappingChangeImpact,
+    MappingCompatibilityReport,
+    DocValidationReport,
+    FieldValidation,
+    TextAnalysis,
+    AnalyzedToken,
+    DocIdConfig,
+    DocIdGeneration,
 )))]
 /// Schema used for the OpenAPI generation which are apart of this crate.
 pub struct DocMapperApiSchemas;
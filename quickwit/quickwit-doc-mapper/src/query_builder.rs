@@ -22,9 +22,10 @@ use std::convert::Infallible;
 use std::ops::Bound;
 
 use quickwit_query::query_ast::{
-    PhrasePrefixQuery, QueryAst, QueryAstVisitor, RangeQuery, TermSetQuery,
+    optimize_query_ast, FieldPresenceQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor,
+    RangeQuery, RegexQuery, TermSetQuery, FIELD_PRESENCE_FIELD_NAME,
 };
-use quickwit_query::InvalidQuery;
+use quickwit_query::{find_field_or_hit_dynamic, InvalidQuery};
 use tantivy::query::Query;
 use tantivy::schema::{Field, Schema};
 use tantivy::Term;
@@ -46,6 +47,25 @@ impl<'a> QueryAstVisitor<'a> for RangeQueryFields {
     }
 }
 
+/// Without field presence indexing, field presence queries read the fast field of their field.
+#[derive(Default)]
+struct FieldPresenceQueryFields {
+    field_presence_query_field_names: HashSet<String>,
+}
+
+impl<'a> QueryAstVisitor<'a> for FieldPresenceQueryFields {
+    type Err = Infallible;
+
+    fn visit_field_presence(
+        &mut self,
+        field_presence_query: &'a FieldPresenceQuery,
+    ) -> Result<(), Infallible> {
+        self.field_presence_query_field_names
+            .insert(field_presence_query.field.to_string());
+        Ok(())
+    }
+}
+
 /// Build a `Query` with field resolution & forbidding range clauses.
 pub(crate) fn build_query(
     query_ast: &QueryAst,
@@ -53,14 +73,26 @@ pub(crate) fn build_query(
     search_fields: &[String],
     with_validation: bool,
 ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
+    // The warmup info is extracted from the optimized query AST, as the optimizer can turn term
+    // queries into term set queries.
+    let optimized_query_ast = optimize_query_ast(query_ast.clone());
+    let query_ast = &optimized_query_ast;
     let mut range_query_fields = RangeQueryFields::default();
     // This cannot fail. The error type is Infallible.
     let _: Result<(), Infallible> = range_query_fields.visit(query_ast);
-    let fast_field_names: HashSet<String> = range_query_fields.range_query_field_names;
+    let mut fast_field_names: HashSet<String> = range_query_fields.range_query_field_names;
 
-    let query = query_ast.build_tantivy_query(&schema, search_fields, with_validation)?;
+    if schema.get_field(FIELD_PRESENCE_FIELD_NAME).is_err() {
+        let mut field_presence_query_fields = FieldPresenceQueryFields::default();
+        // This cannot fail. The error type is Infallible.
+        let _: Result<(), Infallible> = field_presence_query_fields.visit(query_ast);
+        fast_field_names.extend(field_presence_query_fields.field_presence_query_field_names);
+    }
 
-    let term_set_query_fields = extract_term_set_query_fields(query_ast);
+    let query = query_ast.build_tantivy_query(&schema, search_fields, with_validation, true)?;
+
+    let mut term_set_query_fields = extract_term_set_query_fields(query_ast);
+    term_set_query_fields.extend(extract_regex_query_fields(query_ast, &schema));
     let term_ranges_grouped_by_field = extract_phrase_prefix_term_ranges(query_ast, &schema)?;
 
     let mut terms_grouped_by_field: HashMap<Field, HashMap<_, bool>> = Default::default();
@@ -109,6 +141,38 @@ fn extract_term_set_query_fields(query_ast: &QueryAst) -> HashSet<String> {
     visitor.term_dict_fields_to_warm_up
 }
 
+/// Regex queries go through the whole term dictionary of their field, and can match any term of
+/// it.
+struct ExtractRegexFields<'a> {
+    schema: &'a Schema,
+    term_dict_fields_to_warm_up: HashSet<String>,
+}
+
+impl<'a, 'b: 'a> QueryAstVisitor<'a> for ExtractRegexFields<'b> {
+    type Err = Infallible;
+
+    fn visit_regex(&mut self, regex_query: &'a RegexQuery) -> Result<(), Infallible> {
+        // The query was already built successfully, unless validation is disabled, in which case
+        // a regex query on a missing field matches no documents.
+        if let Ok((_, field_entry, _)) = find_field_or_hit_dynamic(&regex_query.field, self.schema)
+        {
+            self.term_dict_fields_to_warm_up
+                .insert(field_entry.name().to_string());
+        }
+        Ok(())
+    }
+}
+
+fn extract_regex_query_fields(query_ast: &QueryAst, schema: &Schema) -> HashSet<String> {
+    let mut visitor = ExtractRegexFields {
+        schema,
+        term_dict_fields_to_warm_up: HashSet::new(),
+    };
+    // This cannot fail. The error type is Infallible.
+    let _: Result<(), Infallible> = visitor.visit(query_ast);
+    visitor.term_dict_fields_to_warm_up
+}
+
 fn prefix_term_to_range(prefix: Term) -> (Bound<Term>, Bound<Term>) {
     let mut end_bound = prefix.serialized_term().to_vec();
     while !end_bound.is_empty() {
@@ -176,6 +240,9 @@ fn extract_phrase_prefix_term_ranges(
 #[cfg(test)]
 mod test {
     use quickwit_proto::query_ast_from_user_text;
+    use quickwit_query::query_ast::{
+        BoolQuery, FieldPresenceQuery, QueryAst, RegexQuery, TermQuery,
+    };
     use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
 
     use super::build_query;
@@ -518,5 +585,39 @@ mod test {
             build_query(&query_without_set, make_schema(true), &[], true).unwrap();
         assert!(warmup_info.term_dict_field_names.is_empty());
         assert!(warmup_info.posting_field_names.is_empty());
+
+        let query_with_regex: QueryAst = RegexQuery {
+            field: "title".to_string(),
+            regex: "hel+o".to_string(),
+            case_insensitive: false,
+        }
+        .into();
+        let (_, warmup_info) =
+            build_query(&query_with_regex, make_schema(true), &[], true).unwrap();
+        assert_eq!(warmup_info.term_dict_field_names.len(), 1);
+        assert!(warmup_info.term_dict_field_names.contains("title"));
+        assert!(warmup_info.posting_field_names.contains("title"));
+
+        let query_with_field_presence: QueryAst = FieldPresenceQuery {
+            field: "u64_fast".to_string(),
+        }
+        .into();
+        let (_, warmup_info) =
+            build_query(&query_with_field_presence, make_schema(true), &[], true).unwrap();
+        assert!(warmup_info.fast_field_names.contains("u64_fast"));
+
+        // The optimizer merges the term queries into a term set query.
+        let query_with_merged_terms: QueryAst = BoolQuery {
+            must_not: vec![
+                TermQuery::from_field_value("title", "hello").into(),
+                TermQuery::from_field_value("title", "world").into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        let (_, warmup_info) =
+            build_query(&query_with_merged_terms, make_schema(true), &[], true).unwrap();
+        assert!(warmup_info.term_dict_field_names.contains("title"));
+        assert!(warmup_info.posting_field_names.contains("title"));
     }
 }
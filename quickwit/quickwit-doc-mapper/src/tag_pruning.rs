@@ -61,6 +61,21 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
             }
             collect_tag_filters_for_clause(clause_with_resolved_occur)
         }
+        QueryAst::DisMax(dis_max_query) => {
+            if dis_max_query.queries.is_empty() {
+                return UnsimplifiedTagFilterAst::Uninformative;
+            }
+            let children: Vec<UnsimplifiedTagFilterAst> = dis_max_query
+                .queries
+                .into_iter()
+                .map(extract_unsimplified_tags_filter_ast)
+                .collect();
+            UnsimplifiedTagFilterAst::Or(children)
+        }
+        // The negative query only demotes documents.
+        QueryAst::Boosting(boosting_query) => {
+            extract_unsimplified_tags_filter_ast(*boosting_query.positive)
+        }
         QueryAst::Term(term_query) => UnsimplifiedTagFilterAst::Tag {
             is_present: true,
             field: term_query.field,
@@ -88,6 +103,8 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
                 .collect();
             UnsimplifiedTagFilterAst::Or(children)
         }
+        // The terms are only known once the lookup document has been fetched.
+        QueryAst::TermsLookup(_) => UnsimplifiedTagFilterAst::Uninformative,
         QueryAst::FullText(full_text_query) => {
             // TODO This is a bug in a sense.
             // A phrase is supposed to go through the tokenizer.
@@ -105,8 +122,24 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
                 value: phrase_prefix_query.phrase,
             }
         }
-        QueryAst::Boost { underlying, .. } => extract_unsimplified_tags_filter_ast(*underlying),
-        QueryAst::UserInput(_user_text_query) => {
+        QueryAst::Intervals(_) | QueryAst::Span(_) => {
+            // Like for phrases, the terms of the rules would have to go through the tokenizer.
+            UnsimplifiedTagFilterAst::Uninformative
+        }
+        QueryAst::Regex(_) | QueryAst::FieldPresence(_) => UnsimplifiedTagFilterAst::Uninformative,
+        QueryAst::Knn(_) => UnsimplifiedTagFilterAst::Uninformative,
+        // The fields of a combined fields query are text fields, which cannot be tag fields.
+        QueryAst::CombinedFields(_) => UnsimplifiedTagFilterAst::Uninformative,
+        QueryAst::Boost { underlying, .. } | QueryAst::ConstScore { underlying, .. } => {
+            extract_unsimplified_tags_filter_ast(*underlying)
+        }
+        // A UDF can only narrow down the set of documents matched by the underlying query.
+        QueryAst::Udf(udf_query) => extract_unsimplified_tags_filter_ast(*udf_query.underlying),
+        // Score functions only impact the scores of the documents matched by the underlying query.
+        QueryAst::FunctionScore(function_score_query) => {
+            extract_unsimplified_tags_filter_ast(*function_score_query.underlying)
+        }
+        QueryAst::UserInput(_) | QueryAst::SimpleUserInput(_) => {
             panic!("Extract unsimplified should only be called on AST without UserInputQuery.");
         }
     }
@@ -391,6 +424,10 @@ mod test {
             user_text: user_query.to_string(),
             default_fields: None,
             default_operator: BooleanOperand::Or,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .into();
         let parsed_query_ast = query_ast.parse_user_query(&[]).unwrap();
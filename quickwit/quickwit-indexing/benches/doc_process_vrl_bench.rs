@@ -7,6 +7,7 @@ use quickwit_config::{SourceInputFormat, TransformConfig};
 use quickwit_doc_mapper::DefaultDocMapper;
 use quickwit_indexing::actors::DocProcessor;
 use quickwit_indexing::models::RawDocBatch;
+use quickwit_indexing::processors::IngestProcessors;
 use quickwit_metastore::checkpoint::SourceCheckpointDelta;
 
 const JSON_NORMAL: &str = include_str!("data/bench_data.json");
@@ -132,6 +133,7 @@ fn create_doc_processor(
         doc_mapper,
         indexer_mailbox,
         transform_config_opt,
+        &IngestProcessors::default(),
         SourceInputFormat::Json,
     )
     .unwrap();
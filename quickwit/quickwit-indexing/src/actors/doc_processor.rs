@@ -20,6 +20,7 @@
 use std::collections::BTreeMap;
 use std::string::FromUtf8Error;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -40,7 +41,9 @@ use vrl::compiler::{Program, TargetValueRef, TimeZone};
 use vrl::value::{Secrets as VrlSecrets, Value as VrlValue};
 
 use crate::actors::Indexer;
+use crate::metrics::record_stage_duration;
 use crate::models::{NewPublishLock, ProcessedDoc, ProcessedDocBatch, PublishLock, RawDocBatch};
+use crate::processors::{IngestProcessor, IngestProcessors};
 
 const PLAIN_TEXT: &str = "plain_text";
 
@@ -52,7 +55,8 @@ enum InputDoc {
 impl InputDoc {
     fn from_bytes(input_format: &SourceInputFormat, bytes: Bytes) -> Self {
         match input_format {
-            SourceInputFormat::Json => InputDoc::Json(bytes),
+            // The file source converts CSV records into JSON objects.
+            SourceInputFormat::Json | SourceInputFormat::Csv => InputDoc::Json(bytes),
             SourceInputFormat::PlainText => InputDoc::PlainText(bytes),
         }
     }
@@ -91,6 +95,7 @@ pub enum DocProcessorError {
     ParsingError,
     MissingField,
     TransformError(Terminate),
+    IngestProcessorError,
 }
 
 impl From<serde_json::Error> for DocProcessorError {
@@ -240,7 +245,11 @@ pub struct DocProcessor {
     counters: DocProcessorCounters,
     publish_lock: PublishLock,
     transform_opt: Option<VrlProgram>,
+    ingest_processors: Vec<Box<dyn IngestProcessor>>,
     input_format: SourceInputFormat,
+    // Time spent in the transform and doc mapping stages since the last batch was flushed.
+    transform_duration: Duration,
+    doc_mapping_duration: Duration,
 }
 
 impl DocProcessor {
@@ -250,12 +259,14 @@ impl DocProcessor {
         doc_mapper: Arc<dyn DocMapper>,
         indexer_mailbox: Mailbox<Indexer>,
         transform_config_opt: Option<TransformConfig>,
+        ingest_processors: &IngestProcessors,
         input_format: SourceInputFormat,
     ) -> anyhow::Result<Self> {
         let timestamp_field_opt = extract_timestamp_field(doc_mapper.as_ref())?;
         let transform_opt = transform_config_opt
             .map(VrlProgram::try_from_transform_config)
             .transpose()?;
+        let ingest_processors = ingest_processors.build()?;
 
         let doc_processor = Self {
             doc_mapper,
@@ -264,7 +275,10 @@ impl DocProcessor {
             counters: DocProcessorCounters::new(index_id, source_id),
             publish_lock: PublishLock::default(),
             transform_opt,
+            ingest_processors,
             input_format,
+            transform_duration: Duration::default(),
+            doc_mapping_duration: Duration::default(),
         };
         Ok(doc_processor)
     }
@@ -284,17 +298,11 @@ impl DocProcessor {
         Ok(Some(timestamp))
     }
 
-    fn process_document(
-        &mut self,
-        doc_bytes: Bytes,
-        ctx: &ActorContext<Self>,
-    ) -> Result<ProcessedDoc, DocProcessorError> {
-        let _protect_guard = ctx.protect_zone();
-
-        let num_bytes = doc_bytes.len();
+    /// Parses the raw document and runs it through the VRL transform and the ingest processors.
+    fn transform_document(&mut self, doc_bytes: Bytes) -> Result<JsonObject, DocProcessorError> {
         let input_doc = InputDoc::from_bytes(&self.input_format, doc_bytes);
 
-        let json_doc: JsonObject = if let Some(vrl_program) = self.transform_opt.as_mut() {
+        let mut json_doc: JsonObject = if let Some(vrl_program) = self.transform_opt.as_mut() {
             let vrl_doc = input_doc.try_into_vrl_doc()?;
             let transformed_vrl_doc = vrl_program.transform_doc(vrl_doc)?;
 
@@ -305,16 +313,47 @@ impl DocProcessor {
         } else {
             input_doc.try_into_json_doc()?
         };
-        let (partition, doc) = self
-            .doc_mapper
-            .doc_from_json_obj(json_doc)
-            .map_err(|error| {
+        for ingest_processor in &mut self.ingest_processors {
+            json_doc = ingest_processor.process(json_doc).map_err(|error| {
                 warn!(error=?error);
-                match error {
-                    DocParsingError::RequiredField(_) => DocProcessorError::MissingField,
-                    _ => DocProcessorError::ParsingError,
-                }
+                DocProcessorError::IngestProcessorError
             })?;
+        }
+        Ok(json_doc)
+    }
+
+    /// Flushes the time spent in the transform and doc mapping stages to the metrics.
+    fn record_stage_durations(&mut self) {
+        let index_id = self.counters.index_id.as_str();
+        let transform_duration = std::mem::take(&mut self.transform_duration);
+        record_stage_duration(index_id, "transform", transform_duration);
+        let doc_mapping_duration = std::mem::take(&mut self.doc_mapping_duration);
+        record_stage_duration(index_id, "doc_mapping", doc_mapping_duration);
+    }
+
+    fn process_document(
+        &mut self,
+        doc_bytes: Bytes,
+        ctx: &ActorContext<Self>,
+    ) -> Result<ProcessedDoc, DocProcessorError> {
+        let _protect_guard = ctx.protect_zone();
+
+        let num_bytes = doc_bytes.len();
+        let transform_start = Instant::now();
+        let json_doc_res = self.transform_document(doc_bytes);
+        self.transform_duration += transform_start.elapsed();
+        let json_doc = json_doc_res?;
+
+        let doc_mapping_start = Instant::now();
+        let doc_res = self.doc_mapper.doc_from_json_obj(json_doc);
+        self.doc_mapping_duration += doc_mapping_start.elapsed();
+        let (partition, doc) = doc_res.map_err(|error| {
+            warn!(error=?error);
+            match error {
+                DocParsingError::RequiredField(_) => DocProcessorError::MissingField,
+                _ => DocProcessorError::ParsingError,
+            }
+        })?;
         let timestamp_opt = self.extract_timestamp(&doc)?;
         Ok(ProcessedDoc {
             doc,
@@ -399,7 +438,8 @@ impl Handler<RawDocBatch> for DocProcessor {
                 Err(DocProcessorError::ParsingError) => {
                     self.counters.record_parsing_error(doc_num_bytes);
                 }
-                Err(DocProcessorError::TransformError(_)) => {
+                Err(DocProcessorError::TransformError(_))
+                | Err(DocProcessorError::IngestProcessorError) => {
                     self.counters.record_transform_error(doc_num_bytes);
                 }
                 Err(DocProcessorError::MissingField) => {
@@ -408,6 +448,7 @@ impl Handler<RawDocBatch> for DocProcessor {
             }
             ctx.record_progress();
         }
+        self.record_stage_durations();
         let processed_doc_batch = ProcessedDocBatch {
             docs: processed_docs,
             checkpoint_delta: raw_doc_batch.checkpoint_delta,
@@ -503,6 +544,7 @@ mod tests {
             doc_mapper.clone(),
             indexer_mailbox,
             None,
+            &IngestProcessors::default(),
             SourceInputFormat::Json,
         )
         .unwrap();
@@ -594,6 +636,7 @@ mod tests {
             doc_mapper,
             indexer_mailbox,
             None,
+            &IngestProcessors::default(),
             SourceInputFormat::Json,
         )
         .unwrap();
@@ -650,6 +693,7 @@ mod tests {
             doc_mapper,
             indexer_mailbox,
             None,
+            &IngestProcessors::default(),
             SourceInputFormat::Json,
         )
         .unwrap();
@@ -682,6 +726,7 @@ mod tests {
             doc_mapper,
             indexer_mailbox,
             None,
+            &IngestProcessors::default(),
             SourceInputFormat::Json,
         )
         .unwrap();
@@ -727,6 +772,7 @@ mod tests {
             doc_mapper.clone(),
             indexer_mailbox,
             Some(transform_config),
+            &IngestProcessors::default(),
             SourceInputFormat::Json,
         )
         .unwrap();
@@ -821,6 +867,7 @@ mod tests {
             doc_mapper.clone(),
             indexer_mailbox,
             Some(transform_config),
+            &IngestProcessors::default(),
             SourceInputFormat::PlainText,
         )
         .unwrap();
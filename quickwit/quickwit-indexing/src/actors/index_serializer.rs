@@ -17,6 +17,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::time::Instant;
+
 use async_trait::async_trait;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
 use quickwit_common::io::IoControls;
@@ -25,6 +27,7 @@ use tokio::runtime::Handle;
 use tracing::instrument;
 
 use crate::actors::Packager;
+use crate::metrics::record_stage_duration;
 use crate::models::{EmptySplit, IndexedSplit, IndexedSplitBatch, IndexedSplitBatchBuilder};
 
 /// The index serializer takes a non-serialized split,
@@ -90,7 +93,14 @@ impl Handler<IndexedSplitBatchBuilder> for IndexSerializer {
                     );
                 controlled_directory.set_io_controls(io_controls);
             }
+            let index_uid = split_builder.split_attrs.pipeline_id.index_uid.clone();
+            let serialization_start = Instant::now();
             let split = split_builder.finalize()?;
+            record_stage_duration(
+                index_uid.index_id(),
+                "serialization",
+                serialization_start.elapsed(),
+            );
             splits.push(split);
         }
         let indexed_split_batch = IndexedSplitBatch {
@@ -21,7 +21,7 @@ use std::collections::hash_map::Entry;
 use std::num::NonZeroU32;
 use std::ops::RangeInclusive;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -50,10 +50,16 @@ use tracing::{info, info_span, warn, Span};
 use ulid::Ulid;
 
 use crate::actors::IndexSerializer;
+use crate::field_profiler::FieldProfiler;
+use crate::metrics::{record_stage_duration, INDEXER_METRICS};
 use crate::models::{
     CommitTrigger, EmptySplit, IndexedSplitBatchBuilder, IndexedSplitBuilder, IndexingPipelineId,
     NewPublishLock, ProcessedDoc, ProcessedDocBatch, PublishLock,
 };
+use crate::source::{NotifyIndexed, SourceActor};
+
+/// Documents taking longer than this to be added to the index writer are reported as slow.
+const SLOW_DOC_THRESHOLD: Duration = Duration::from_millis(100);
 
 // Random partition id used to gather partitions exceeding the maximum number of partitions.
 const OTHER_PARTITION_ID: u64 = 3264326757911759461u64;
@@ -63,6 +69,15 @@ struct CommitTimeout {
     workbench_id: Ulid,
 }
 
+/// Replaces the indexing settings of the indexer at runtime. The new commit policy applies to the
+/// split under construction, while docstore settings only apply to the splits created afterwards.
+#[derive(Debug)]
+pub struct UpdateIndexingSettings(pub IndexingSettings);
+
+/// Commits the split under construction right away, regardless of the commit policy.
+#[derive(Debug)]
+pub struct ForceCommit;
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
 pub struct IndexerCounters {
     /// Number of splits that were emitted by the indexer.
@@ -204,6 +219,7 @@ impl IndexerState {
             publish_lock: self.publish_lock.clone(),
             last_delete_opstamp,
             memory_usage: Byte::from_bytes(0),
+            num_bytes: 0,
         };
         Ok(workbench)
     }
@@ -240,6 +256,7 @@ impl IndexerState {
         batch: ProcessedDocBatch,
         indexing_workbench_opt: &mut Option<IndexingWorkbench>,
         counters: &mut IndexerCounters,
+        field_profiler: &mut FieldProfiler,
         ctx: &ActorContext<Indexer>,
     ) -> Result<(), ActorExitStatus> {
         let IndexingWorkbench {
@@ -249,6 +266,7 @@ impl IndexerState {
             publish_lock,
             last_delete_opstamp,
             memory_usage,
+            num_bytes: workbench_num_bytes,
             ..
         } = self
             .get_or_create_workbench(indexing_workbench_opt, ctx)
@@ -262,7 +280,9 @@ impl IndexerState {
             .source_delta
             .extend(batch.checkpoint_delta)
             .context("Batch delta does not follow indexer checkpoint")?;
+        let index_id = self.pipeline_id.index_uid.index_id();
         let mut memory_usage_delta: u64 = 0;
+        let mut indexing_duration = Duration::default();
         for doc in batch.docs {
             let ProcessedDoc {
                 doc,
@@ -281,19 +301,38 @@ impl IndexerState {
             )?;
             let mem_usage_before = indexed_split.index_writer.mem_usage() as u64;
             indexed_split.split_attrs.uncompressed_docs_size_in_bytes += num_bytes as u64;
+            *workbench_num_bytes += num_bytes as u64;
             indexed_split.split_attrs.num_docs += 1;
             if let Some(timestamp) = timestamp_opt {
                 record_timestamp(timestamp, &mut indexed_split.split_attrs.time_range);
             }
+            field_profiler.record_doc(&doc);
             let _protect_guard = ctx.protect_zone();
+            let add_document_start = Instant::now();
             indexed_split
                 .index_writer
                 .add_document(doc)
                 .context("Failed to add document.")?;
+            let add_document_duration = add_document_start.elapsed();
+            indexing_duration += add_document_duration;
+
+            if add_document_duration > SLOW_DOC_THRESHOLD {
+                INDEXER_METRICS
+                    .slow_docs_total
+                    .with_label_values([index_id])
+                    .inc();
+                warn!(
+                    index_id=%index_id,
+                    num_bytes=num_bytes,
+                    elapsed_ms=add_document_duration.as_millis(),
+                    "slow-doc"
+                );
+            }
             let mem_usage_after = indexed_split.index_writer.mem_usage() as u64;
             memory_usage_delta += mem_usage_after - mem_usage_before;
             ctx.record_progress();
         }
+        record_stage_duration(index_id, "indexing", indexing_duration);
         *memory_usage = Byte::from_bytes(memory_usage.get_bytes() + memory_usage_delta);
         Ok(())
     }
@@ -320,13 +359,17 @@ struct IndexingWorkbench {
     last_delete_opstamp: u64,
     // Number of bytes declared as used by tantivy.
     memory_usage: Byte,
+    // Number of bytes of the documents indexed in the workbench.
+    num_bytes: u64,
 }
 
 pub struct Indexer {
     indexer_state: IndexerState,
     index_serializer_mailbox: Mailbox<IndexSerializer>,
+    source_mailbox_opt: Option<Mailbox<SourceActor>>,
     indexing_workbench_opt: Option<IndexingWorkbench>,
     counters: IndexerCounters,
+    field_profiler: FieldProfiler,
 }
 
 #[async_trait]
@@ -398,6 +441,18 @@ impl Actor for Indexer {
     }
 }
 
+fn build_index_settings(indexing_settings: &IndexingSettings) -> IndexSettings {
+    let docstore_compression = Compressor::Zstd(ZstdCompressor {
+        compression_level: Some(indexing_settings.docstore_compression_level),
+    });
+    IndexSettings {
+        docstore_blocksize: indexing_settings.docstore_blocksize,
+        docstore_compression,
+        docstore_compress_dedicated_thread: true,
+        ..Default::default()
+    }
+}
+
 fn record_timestamp(timestamp: DateTime, time_range: &mut Option<RangeInclusive<DateTime>>) {
     let new_timestamp_range = match time_range {
         Some(range) => timestamp.min(*range.start())..=timestamp.max(*range.end()),
@@ -439,6 +494,61 @@ impl Handler<ProcessedDocBatch> for Indexer {
     }
 }
 
+#[async_trait]
+impl Handler<UpdateIndexingSettings> for Indexer {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: UpdateIndexingSettings,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        let UpdateIndexingSettings(indexing_settings) = message;
+        info!(
+            commit_timeout_secs=%indexing_settings.commit_timeout_secs,
+            split_num_docs_target=%indexing_settings.split_num_docs_target,
+            split_num_bytes_target=?indexing_settings.split_num_bytes_target,
+            "update-indexing-settings"
+        );
+        self.indexer_state.index_settings = build_index_settings(&indexing_settings);
+        self.indexer_state.indexing_settings = indexing_settings;
+
+        let Some(indexing_workbench) = &self.indexing_workbench_opt else {
+            return Ok(());
+        };
+        let elapsed = indexing_workbench.create_instant.elapsed();
+        let commit_timeout = self.indexer_state.indexing_settings.commit_timeout();
+        if elapsed >= commit_timeout {
+            self.send_to_serializer(CommitTrigger::Timeout, ctx).await?;
+            return Ok(());
+        }
+        // The commit timeout scheduled on workbench creation may fire too late if the timeout was
+        // shortened.
+        let commit_timeout_message = CommitTimeout {
+            workbench_id: indexing_workbench.workbench_id,
+        };
+        ctx.schedule_self_msg(commit_timeout - elapsed, commit_timeout_message)
+            .await;
+        self.commit_if_limits_reached(ctx).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<ForceCommit> for Indexer {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: ForceCommit,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.send_to_serializer(CommitTrigger::ForceCommit, ctx)
+            .await?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<NewPublishLock> for Indexer {
     type Reply = ();
@@ -464,17 +574,12 @@ impl Indexer {
         indexing_settings: IndexingSettings,
         cooperative_indexing_permits: Option<Arc<Semaphore>>,
         index_serializer_mailbox: Mailbox<IndexSerializer>,
+        source_mailbox_opt: Option<Mailbox<SourceActor>>,
     ) -> Self {
         let schema = doc_mapper.schema();
-        let docstore_compression = Compressor::Zstd(ZstdCompressor {
-            compression_level: Some(indexing_settings.docstore_compression_level),
-        });
-        let index_settings = IndexSettings {
-            docstore_blocksize: indexing_settings.docstore_blocksize,
-            docstore_compression,
-            docstore_compress_dedicated_thread: true,
-            ..Default::default()
-        };
+        let index_settings = build_index_settings(&indexing_settings);
+        let field_profiler =
+            FieldProfiler::new(pipeline_id.index_uid.index_id().to_string(), &schema);
         Self {
             indexer_state: IndexerState {
                 pipeline_id,
@@ -488,8 +593,10 @@ impl Indexer {
                 cooperative_indexing_permits,
             },
             index_serializer_mailbox,
+            source_mailbox_opt,
             indexing_workbench_opt: None,
             counters: IndexerCounters::default(),
+            field_profiler,
         }
     }
 
@@ -501,6 +608,39 @@ impl Indexer {
         }
     }
 
+    fn num_bytes_in_workbench(&self) -> u64 {
+        if let Some(workbench) = &self.indexing_workbench_opt {
+            workbench.num_bytes
+        } else {
+            0
+        }
+    }
+
+    /// Commits the workbench if it exceeds the memory limit or one of the split targets.
+    async fn commit_if_limits_reached(&mut self, ctx: &ActorContext<Self>) -> anyhow::Result<()> {
+        let indexing_settings = &self.indexer_state.indexing_settings;
+        let commit_trigger_opt = if self.memory_usage() >= indexing_settings.resources.heap_size {
+            Some(CommitTrigger::MemoryLimit)
+        } else if self.counters.num_docs_in_workbench
+            >= indexing_settings.split_num_docs_target as u64
+        {
+            Some(CommitTrigger::NumDocsLimit)
+        } else if indexing_settings
+            .split_num_bytes_target
+            .map_or(false, |split_num_bytes_target| {
+                self.num_bytes_in_workbench() >= split_num_bytes_target.get_bytes()
+            })
+        {
+            Some(CommitTrigger::NumBytesLimit)
+        } else {
+            None
+        };
+        if let Some(commit_trigger) = commit_trigger_opt {
+            self.send_to_serializer(commit_trigger, ctx).await?;
+        }
+        Ok(())
+    }
+
     async fn index_batch(
         &mut self,
         batch: ProcessedDocBatch,
@@ -508,24 +648,33 @@ impl Indexer {
     ) -> Result<(), ActorExitStatus> {
         fail_point!("indexer:batch:before");
         let force_commit = batch.force_commit;
+        let indexed_checkpoint_opt = self
+            .source_mailbox_opt
+            .as_ref()
+            .map(|_| batch.checkpoint_delta.get_source_checkpoint());
         self.indexer_state
             .index_batch(
                 batch,
                 &mut self.indexing_workbench_opt,
                 &mut self.counters,
+                &mut self.field_profiler,
                 ctx,
             )
             .await?;
-        if self.memory_usage() >= self.indexer_state.indexing_settings.resources.heap_size {
-            self.send_to_serializer(CommitTrigger::MemoryLimit, ctx)
-                .await?;
-        }
-        if self.counters.num_docs_in_workbench
-            >= self.indexer_state.indexing_settings.split_num_docs_target as u64
+        if let (Some(source_mailbox), Some(indexed_checkpoint)) =
+            (&self.source_mailbox_opt, indexed_checkpoint_opt)
         {
-            self.send_to_serializer(CommitTrigger::NumDocsLimit, ctx)
-                .await?;
+            // Documents are dropped when the publish lock is dead, so they must not be reported
+            // as indexed.
+            if !self.indexer_state.publish_lock.is_dead() {
+                // Like for truncation, the source may have terminated already: this is not an
+                // error.
+                let _ = ctx
+                    .send_message(source_mailbox, NotifyIndexed(indexed_checkpoint))
+                    .await;
+            }
         }
+        self.commit_if_limits_reached(ctx).await?;
         if force_commit {
             self.send_to_serializer(CommitTrigger::ForceCommit, ctx)
                 .await?;
@@ -679,6 +828,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
         indexer_mailbox
@@ -813,6 +963,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, _indexer_handle) = universe.spawn_builder().spawn(indexer);
 
@@ -890,6 +1041,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
         tokio::task::spawn({
@@ -973,6 +1125,7 @@ mod tests {
             indexing_settings,
             Some(Arc::new(Semaphore::new(1))),
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
         indexer_mailbox
@@ -1047,6 +1200,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
         indexer_mailbox
@@ -1131,6 +1285,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
         indexer_mailbox
@@ -1225,6 +1380,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
 
@@ -1299,6 +1455,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
 
@@ -1374,6 +1531,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
 
@@ -1441,6 +1599,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
         indexer_mailbox
@@ -1474,6 +1633,221 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_indexer_triggers_commit_on_target_num_bytes() {
+        let universe = Universe::with_accelerated_time();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: IndexUid::new("test-index"),
+            source_id: "test-source".to_string(),
+            node_id: "test-node".to_string(),
+            pipeline_ord: 0,
+        };
+        let doc_mapper: Arc<dyn DocMapper> =
+            Arc::new(serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_SIMPLE_JSON).unwrap());
+        let body_field = doc_mapper.schema().get_field("body").unwrap();
+        let indexing_directory = TempDirectory::for_test();
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.split_num_bytes_target = Some(Byte::from_bytes(50));
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_last_delete_opstamp()
+            .times(2)
+            .returning(move |index_uid| {
+                assert_eq!(index_uid.index_id(), "test-index");
+                Ok(10)
+            });
+        metastore.expect_publish_splits().never();
+        let (index_serializer_mailbox, index_serializer_inbox) = universe.create_test_mailbox();
+        let indexer = Indexer::new(
+            pipeline_id,
+            doc_mapper,
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            index_serializer_mailbox,
+            None,
+        );
+        let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
+        for (doc_ord, body) in ["doc 1", "doc 2", "doc 3"].into_iter().enumerate() {
+            indexer_mailbox
+                .send_message(ProcessedDocBatch {
+                    docs: vec![ProcessedDoc {
+                        doc: doc!(body_field=>body),
+                        timestamp_opt: None,
+                        partition: 0,
+                        num_bytes: 30,
+                    }],
+                    checkpoint_delta: SourceCheckpointDelta::from_range(
+                        doc_ord as u64..doc_ord as u64 + 1,
+                    ),
+                    force_commit: false,
+                })
+                .await
+                .unwrap();
+        }
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 1);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+
+        let output_messages: Vec<IndexedSplitBatchBuilder> =
+            index_serializer_inbox.drain_for_test_typed();
+        assert_eq!(output_messages.len(), 1);
+        assert_eq!(
+            output_messages[0].commit_trigger,
+            CommitTrigger::NumBytesLimit
+        );
+        assert_eq!(output_messages[0].splits[0].split_attrs.num_docs, 2);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_indexer_honors_force_commit_message() {
+        let universe = Universe::with_accelerated_time();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: IndexUid::new("test-index"),
+            source_id: "test-source".to_string(),
+            node_id: "test-node".to_string(),
+            pipeline_ord: 0,
+        };
+        let doc_mapper: Arc<dyn DocMapper> =
+            Arc::new(serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_SIMPLE_JSON).unwrap());
+        let body_field = doc_mapper.schema().get_field("body").unwrap();
+        let indexing_directory = TempDirectory::for_test();
+        let indexing_settings = IndexingSettings::for_test();
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_last_delete_opstamp()
+            .times(1)
+            .returning(move |index_uid| {
+                assert_eq!(index_uid.index_id(), "test-index");
+                Ok(10)
+            });
+        metastore.expect_publish_splits().never();
+        let (index_serializer_mailbox, index_serializer_inbox) = universe.create_test_mailbox();
+        let indexer = Indexer::new(
+            pipeline_id,
+            doc_mapper,
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            index_serializer_mailbox,
+            None,
+        );
+        let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
+        indexer_mailbox
+            .send_message(ProcessedDocBatch {
+                docs: vec![ProcessedDoc {
+                    doc: doc!(body_field=>"doc 1"),
+                    timestamp_opt: None,
+                    partition: 0,
+                    num_bytes: 30,
+                }],
+                checkpoint_delta: SourceCheckpointDelta::from_range(0..1),
+                force_commit: false,
+            })
+            .await
+            .unwrap();
+        indexer_mailbox.send_message(ForceCommit).await.unwrap();
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 0);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+
+        let output_messages: Vec<IndexedSplitBatchBuilder> =
+            index_serializer_inbox.drain_for_test_typed();
+        assert_eq!(output_messages.len(), 1);
+        assert_eq!(
+            output_messages[0].commit_trigger,
+            CommitTrigger::ForceCommit
+        );
+        assert_eq!(output_messages[0].splits[0].split_attrs.num_docs, 1);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_indexer_applies_updated_indexing_settings() {
+        let universe = Universe::with_accelerated_time();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: IndexUid::new("test-index"),
+            source_id: "test-source".to_string(),
+            node_id: "test-node".to_string(),
+            pipeline_ord: 0,
+        };
+        let doc_mapper: Arc<dyn DocMapper> =
+            Arc::new(serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_SIMPLE_JSON).unwrap());
+        let body_field = doc_mapper.schema().get_field("body").unwrap();
+        let indexing_directory = TempDirectory::for_test();
+        let indexing_settings = IndexingSettings::for_test();
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_last_delete_opstamp()
+            .times(1)
+            .returning(move |index_uid| {
+                assert_eq!(index_uid.index_id(), "test-index");
+                Ok(10)
+            });
+        metastore.expect_publish_splits().never();
+        let (index_serializer_mailbox, index_serializer_inbox) = universe.create_test_mailbox();
+        let indexer = Indexer::new(
+            pipeline_id,
+            doc_mapper,
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings.clone(),
+            None,
+            index_serializer_mailbox,
+            None,
+        );
+        let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
+        indexer_mailbox
+            .send_message(ProcessedDocBatch {
+                docs: vec![
+                    ProcessedDoc {
+                        doc: doc!(body_field=>"doc 1"),
+                        timestamp_opt: None,
+                        partition: 0,
+                        num_bytes: 30,
+                    },
+                    ProcessedDoc {
+                        doc: doc!(body_field=>"doc 2"),
+                        timestamp_opt: None,
+                        partition: 0,
+                        num_bytes: 30,
+                    },
+                ],
+                checkpoint_delta: SourceCheckpointDelta::from_range(0..2),
+                force_commit: false,
+            })
+            .await
+            .unwrap();
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 2);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 0);
+
+        let updated_indexing_settings = IndexingSettings {
+            split_num_docs_target: 2,
+            ..indexing_settings
+        };
+        indexer_mailbox
+            .send_message(UpdateIndexingSettings(updated_indexing_settings))
+            .await
+            .unwrap();
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 0);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+
+        let output_messages: Vec<IndexedSplitBatchBuilder> =
+            index_serializer_inbox.drain_for_test_typed();
+        assert_eq!(output_messages.len(), 1);
+        assert_eq!(
+            output_messages[0].commit_trigger,
+            CommitTrigger::NumDocsLimit
+        );
+        assert_eq!(output_messages[0].splits[0].split_attrs.num_docs, 2);
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_indexer_checkpoint_on_all_failed_docs() -> anyhow::Result<()> {
         let pipeline_id = IndexingPipelineId {
@@ -1510,6 +1884,7 @@ mod tests {
             indexing_settings,
             None,
             index_serializer_mailbox,
+            None,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
         indexer_mailbox
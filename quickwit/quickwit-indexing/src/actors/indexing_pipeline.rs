@@ -23,8 +23,8 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use quickwit_actors::{
-    Actor, ActorContext, ActorExitStatus, ActorHandle, Handler, Health, Mailbox, QueueCapacity,
-    Supervisable,
+    Actor, ActorContext, ActorExitStatus, ActorHandle, ActorState, Command, Handler, Health,
+    Mailbox, QueueCapacity, Supervisable,
 };
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_common::KillSwitch;
@@ -42,8 +42,9 @@ use crate::actors::index_serializer::IndexSerializer;
 use crate::actors::publisher::PublisherType;
 use crate::actors::sequencer::Sequencer;
 use crate::actors::uploader::UploaderType;
-use crate::actors::{Indexer, Packager, Publisher, Uploader};
+use crate::actors::{ForceCommit, Indexer, Packager, Publisher, UpdateIndexingSettings, Uploader};
 use crate::models::{IndexingPipelineId, IndexingStatistics, Observe};
+use crate::processors::IngestProcessors;
 use crate::source::{quickwit_supported_sources, SourceActor, SourceExecutionContext};
 use crate::split_store::IndexingSplitStore;
 use crate::SplitsUpdateMailbox;
@@ -93,6 +94,11 @@ pub struct Spawn {
     retry_count: usize,
 }
 
+/// Asks the pipeline to stop reading from its source, commit the split under construction, and
+/// exit successfully.
+#[derive(Clone, Copy, Debug)]
+pub struct DrainPipeline;
+
 pub struct IndexingPipeline {
     params: IndexingPipelineParams,
     previous_generations_statistics: IndexingStatistics,
@@ -100,6 +106,8 @@ pub struct IndexingPipeline {
     handles: Option<IndexingPipelineHandles>,
     // Killswitch used for the actors in the pipeline. This is not the supervisor killswitch.
     kill_switch: KillSwitch,
+    is_draining: bool,
+    doc_processor_drain_requested: bool,
 }
 
 #[async_trait]
@@ -159,6 +167,8 @@ impl IndexingPipeline {
             handles: None,
             kill_switch: KillSwitch::default(),
             statistics: IndexingStatistics::default(),
+            is_draining: false,
+            doc_processor_drain_requested: false,
         }
     }
 
@@ -314,7 +324,8 @@ impl IndexingPipeline {
 
         // Packager
         let tag_fields = self.params.doc_mapper.tag_named_fields()?;
-        let packager = Packager::new("Packager", tag_fields, uploader_mailbox);
+        let doc_id_field_opt = self.params.doc_mapper.doc_id_named_field()?;
+        let packager = Packager::new("Packager", tag_fields, doc_id_field_opt, uploader_mailbox);
         let (packager_mailbox, packager_handle) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
@@ -336,6 +347,7 @@ impl IndexingPipeline {
             self.params.indexing_settings.clone(),
             self.params.cooperative_indexing_permits.clone(),
             index_serializer_mailbox,
+            Some(source_mailbox.clone()),
         );
         let (indexer_mailbox, indexer_handle) = ctx
             .spawn_actor()
@@ -353,6 +365,7 @@ impl IndexingPipeline {
             self.params.doc_mapper.clone(),
             indexer_mailbox,
             self.params.source_config.transform_config.clone(),
+            &self.params.ingest_processors,
             self.params.source_config.input_format.clone(),
         )?;
         let (doc_processor_mailbox, doc_processor_handle) = ctx
@@ -411,6 +424,25 @@ impl IndexingPipeline {
         Ok(())
     }
 
+    /// The source does not propagate its exit to the downstream actors, so once it has stopped
+    /// emitting batches, we ask the doc processor to exit after processing the pending ones. The
+    /// exit then cascades down the pipeline and the indexer commits the split under construction.
+    async fn drain_doc_processor_if_source_exited(&mut self) {
+        let Some(handles) = &self.handles else {
+            return;
+        };
+        if self.doc_processor_drain_requested || handles.source.state() != ActorState::Success {
+            return;
+        }
+        // The doc processor may have already exited if the source reached the end of its input.
+        let _ = handles
+            .doc_processor
+            .mailbox()
+            .send_message(Command::ExitWithSuccess)
+            .await;
+        self.doc_processor_drain_requested = true;
+    }
+
     async fn terminate(&mut self) {
         self.kill_switch.kill();
         if let Some(handles) = self.handles.take() {
@@ -468,9 +500,18 @@ impl Handler<Supervise> for IndexingPipeline {
     ) -> Result<(), ActorExitStatus> {
         if self.handles.is_some() {
             match self.healthcheck() {
-                Health::Healthy => {}
+                Health::Healthy => {
+                    if self.is_draining {
+                        self.drain_doc_processor_if_source_exited().await;
+                    }
+                }
                 Health::FailureOrUnhealthy => {
                     self.terminate().await;
+                    if self.is_draining {
+                        // The documents that were not published will be read again from the last
+                        // checkpoint by the pipeline taking over.
+                        return Err(ActorExitStatus::Success);
+                    }
                     ctx.schedule_self_msg(*quickwit_actors::HEARTBEAT, Spawn { retry_count: 0 })
                         .await;
                 }
@@ -494,7 +535,7 @@ impl Handler<Spawn> for IndexingPipeline {
         spawn: Spawn,
         ctx: &ActorContext<Self>,
     ) -> Result<(), ActorExitStatus> {
-        if self.handles.is_some() {
+        if self.handles.is_some() || self.is_draining {
             return Ok(());
         }
         self.previous_generations_statistics.num_spawn_attempts = 1 + spawn.retry_count;
@@ -519,6 +560,78 @@ impl Handler<Spawn> for IndexingPipeline {
     }
 }
 
+#[async_trait]
+impl Handler<DrainPipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: DrainPipeline,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if self.is_draining {
+            return Ok(());
+        }
+        info!(pipeline_id=?self.params.pipeline_id, "Draining indexing pipeline.");
+        self.is_draining = true;
+        let Some(handles) = &self.handles else {
+            // The pipeline is waiting to be respawned: there is nothing to commit.
+            return Err(ActorExitStatus::Success);
+        };
+        // The source processes this command once it is done emitting its current batch.
+        let _ = handles
+            .source
+            .mailbox()
+            .send_message(Command::ExitWithSuccess)
+            .await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<UpdateIndexingSettings> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: UpdateIndexingSettings,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        let UpdateIndexingSettings(indexing_settings) = message;
+        if self.params.indexing_settings == indexing_settings {
+            return Ok(());
+        }
+        info!(pipeline_id=?self.params.pipeline_id, "Updating indexing settings.");
+        self.params.indexing_settings = indexing_settings.clone();
+        // If the pipeline is waiting to be respawned, the next generation picks up the new
+        // settings from the params.
+        if let Some(handles) = &self.handles {
+            let _ = handles
+                .indexer
+                .mailbox()
+                .send_message(UpdateIndexingSettings(indexing_settings))
+                .await;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<ForceCommit> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: ForceCommit,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if let Some(handles) = &self.handles {
+            let _ = handles.indexer.mailbox().send_message(ForceCommit).await;
+        }
+        Ok(())
+    }
+}
+
 pub struct IndexingPipelineParams {
     pub pipeline_id: IndexingPipelineId,
     pub doc_mapper: Arc<dyn DocMapper>,
@@ -526,6 +639,7 @@ pub struct IndexingPipelineParams {
     pub queues_dir_path: PathBuf,
     pub indexing_settings: IndexingSettings,
     pub source_config: SourceConfig,
+    pub ingest_processors: IngestProcessors,
     pub metastore: Arc<dyn Metastore>,
     pub storage: Arc<dyn Storage>,
     pub split_store: IndexingSplitStore,
@@ -626,6 +740,7 @@ mod tests {
             source_params: SourceParams::file(PathBuf::from("data/test_corpus.json")),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
@@ -634,6 +749,7 @@ mod tests {
             pipeline_id,
             doc_mapper: Arc::new(default_doc_mapper_for_test()),
             source_config,
+            ingest_processors: IngestProcessors::default(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
             metastore: metastore.clone(),
@@ -720,6 +836,7 @@ mod tests {
             source_params: SourceParams::file(PathBuf::from("data/test_corpus.json")),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
@@ -728,6 +845,7 @@ mod tests {
             pipeline_id,
             doc_mapper: Arc::new(default_doc_mapper_for_test()),
             source_config,
+            ingest_processors: IngestProcessors::default(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
             metastore: metastore.clone(),
@@ -781,6 +899,7 @@ mod tests {
             source_params: SourceParams::Void(VoidSourceParams),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
@@ -802,6 +921,7 @@ mod tests {
             pipeline_id,
             doc_mapper,
             source_config,
+            ingest_processors: IngestProcessors::default(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
             metastore: metastore.clone(),
@@ -892,6 +1012,7 @@ mod tests {
             source_params: SourceParams::file(PathBuf::from("data/test_corpus.json")),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
@@ -918,6 +1039,7 @@ mod tests {
             pipeline_id,
             doc_mapper: Arc::new(broken_mapper),
             source_config,
+            ingest_processors: IngestProcessors::default(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
             metastore: metastore.clone(),
@@ -20,6 +20,7 @@
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -27,9 +28,9 @@ use futures::future::try_join_all;
 use itertools::Itertools;
 use quickwit_actors::{
     Actor, ActorContext, ActorExitStatus, ActorHandle, ActorState, Handler, Healthz, Mailbox,
-    Observation,
+    Observation, Readyz,
 };
-use quickwit_cluster::Cluster;
+use quickwit_cluster::{Cluster, SplitCacheStats};
 use quickwit_common::fs::get_cache_directory_path;
 use quickwit_common::temp_dir;
 use quickwit_config::{
@@ -37,7 +38,9 @@ use quickwit_config::{
 };
 use quickwit_ingest::{DropQueueRequest, IngestApiService, ListQueuesRequest, QUEUES_DIR_NAME};
 use quickwit_metastore::{IndexMetadata, Metastore, MetastoreError};
-use quickwit_proto::indexing_api::{ApplyIndexingPlanRequest, IndexingTask};
+use quickwit_proto::indexing_api::{
+    ApplyIndexingPlanRequest, ForceCommitRequest, ForceCommitResponse, IndexingTask,
+};
 use quickwit_proto::{IndexUid, ServiceError, ServiceErrorCode};
 use quickwit_storage::{StorageError, StorageResolver, StorageResolverError};
 use serde::{Deserialize, Serialize};
@@ -45,18 +48,24 @@ use thiserror::Error;
 use tokio::sync::Semaphore;
 use tracing::{debug, error, info, warn};
 
+use super::indexing_pipeline::DrainPipeline;
 use super::merge_pipeline::{MergePipeline, MergePipelineParams};
-use super::MergePlanner;
+use super::{ForceCommit, MergePlanner, UpdateIndexingSettings};
 use crate::models::{
-    DetachIndexingPipeline, DetachMergePipeline, IndexingPipelineId, Observe, ObservePipeline,
-    SpawnPipeline,
+    DetachIndexingPipeline, DetachMergePipeline, DrainPipelines, IndexingPipelineId, Observe,
+    ObservePipeline, SpawnPipeline,
 };
+use crate::processors::{GeoIpResolver, IngestProcessors};
 use crate::split_store::{LocalSplitStore, SplitStoreQuota};
 use crate::{IndexingPipeline, IndexingPipelineParams, IndexingSplitStore, IndexingStatistics};
 
 /// Name of the indexing directory, usually located at `<data_dir_path>/indexing`.
 pub const INDEXING_DIR_NAME: &str = "indexing";
 
+/// Interval at which the indexing settings of the running pipelines are refreshed from the
+/// metastore, so that updates of the commit policy of an index are applied without a restart.
+const REFRESH_INDEXING_SETTINGS_INTERVAL: Duration = Duration::from_secs(10);
+
 #[derive(Error, Debug)]
 pub enum IndexingServiceError {
     #[error("Indexing pipeline `{index_id}` for source `{source_id}` does not exist.")]
@@ -140,6 +149,8 @@ pub struct IndexingService {
     max_concurrent_split_uploads: usize,
     merge_pipeline_handles: HashMap<MergePipelineId, MergePipelineHandle>,
     cooperative_indexing_permits: Option<Arc<Semaphore>>,
+    geoip_resolver_opt: Option<GeoIpResolver>,
+    is_draining: bool,
 }
 
 impl IndexingService {
@@ -169,6 +180,11 @@ impl IndexingService {
         } else {
             None
         };
+        let geoip_resolver_opt = if let Some(geoip_config) = indexer_config.geoip {
+            Some(GeoIpResolver::load(geoip_config, storage_resolver.clone()).await?)
+        } else {
+            None
+        };
         Ok(Self {
             node_id,
             indexing_root_directory,
@@ -183,6 +199,8 @@ impl IndexingService {
             max_concurrent_split_uploads: indexer_config.max_concurrent_split_uploads,
             merge_pipeline_handles: HashMap::new(),
             cooperative_indexing_permits,
+            geoip_resolver_opt,
+            is_draining: false,
         })
     }
 
@@ -289,6 +307,13 @@ impl IndexingService {
 
         let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
             .map_err(IndexingServiceError::InvalidParams)?;
+        let ingest_processors = IngestProcessors::load(
+            &index_config.indexing_settings.processors,
+            &self.storage_resolver,
+            self.geoip_resolver_opt.as_ref(),
+        )
+        .await
+        .map_err(IndexingServiceError::InvalidParams)?;
 
         let merge_pipeline_params = MergePipelineParams {
             pipeline_id: pipeline_id.clone(),
@@ -318,6 +343,7 @@ impl IndexingService {
             doc_mapper,
             indexing_settings: index_config.indexing_settings.clone(),
             source_config,
+            ingest_processors,
             indexing_directory,
             metastore: self.metastore.clone(),
             storage,
@@ -447,6 +473,12 @@ impl IndexingService {
         ctx: &ActorContext<Self>,
         physical_indexing_plan_request: ApplyIndexingPlanRequest,
     ) -> Result<(), IndexingServiceError> {
+        if self.is_draining {
+            // The node is leaving the cluster: the control plane will assign the tasks to
+            // the other indexers.
+            info!("Indexing service is draining, ignoring indexing plan.");
+            return Ok(());
+        }
         let mut updated_pipeline_ids: HashSet<IndexingPipelineId> = HashSet::new();
         let mut pipeline_ordinals: HashMap<&IndexingTask, usize> = HashMap::new();
         for indexing_task in physical_indexing_plan_request.indexing_tasks.iter() {
@@ -586,6 +618,32 @@ impl IndexingService {
         }
     }
 
+    /// Pushes the current indexing settings of each index to its running pipelines.
+    async fn refresh_indexing_settings(&self, ctx: &ActorContext<Self>) {
+        let index_uids: HashSet<IndexUid> = self
+            .indexing_pipeline_handles
+            .keys()
+            .map(|pipeline_id| pipeline_id.index_uid.clone())
+            .collect();
+        for index_uid in index_uids {
+            let index_metadata = match self.index_metadata(ctx, index_uid.index_id()).await {
+                Ok(index_metadata) => index_metadata,
+                Err(error) => {
+                    warn!(index_id=%index_uid.index_id(), error=?error, "Failed to refresh indexing settings.");
+                    continue;
+                }
+            };
+            for (pipeline_id, pipeline_handle) in &self.indexing_pipeline_handles {
+                if pipeline_id.index_uid != index_metadata.index_uid {
+                    continue;
+                }
+                let update_message =
+                    UpdateIndexingSettings(index_metadata.index_config.indexing_settings.clone());
+                let _ = pipeline_handle.mailbox().send_message(update_message).await;
+            }
+        }
+    }
+
     /// Updates running indexing tasks in chitchat cluster state.
     async fn update_cluster_running_indexing_tasks(&self) {
         let indexing_tasks = self
@@ -613,6 +671,18 @@ impl IndexingService {
         }
     }
 
+    /// Updates the usage of the split cache in chitchat cluster state.
+    async fn update_cluster_split_cache_stats(&self) {
+        let (num_splits, num_bytes) = self.local_split_store.cache_usage().await;
+        let split_cache_stats = SplitCacheStats {
+            num_splits: num_splits as u64,
+            num_bytes: num_bytes.get_bytes(),
+        };
+        self.cluster
+            .update_self_node_split_cache_stats(split_cache_stats)
+            .await;
+    }
+
     /// Garbage collects ingest API queues of deleted indexes.
     async fn run_ingest_api_queues_gc(&mut self) -> anyhow::Result<()> {
         let Some(ingest_api_service) = &self.ingest_api_service_opt else {
@@ -710,12 +780,35 @@ impl Handler<SuperviseLoop> for IndexingService {
         ctx: &ActorContext<Self>,
     ) -> Result<(), ActorExitStatus> {
         self.handle_supervise().await?;
+        self.update_cluster_split_cache_stats().await;
         ctx.schedule_self_msg(*quickwit_actors::HEARTBEAT, SuperviseLoop)
             .await;
         Ok(())
     }
 }
 
+#[derive(Debug)]
+struct RefreshIndexingSettingsLoop;
+
+#[async_trait]
+impl Handler<RefreshIndexingSettingsLoop> for IndexingService {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: RefreshIndexingSettingsLoop,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.refresh_indexing_settings(ctx).await;
+        ctx.schedule_self_msg(
+            REFRESH_INDEXING_SETTINGS_INTERVAL,
+            RefreshIndexingSettingsLoop,
+        )
+        .await;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Actor for IndexingService {
     type ObservableState = IndexingServiceCounters;
@@ -726,7 +819,13 @@ impl Actor for IndexingService {
 
     async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
         self.run_ingest_api_queues_gc().await?;
-        self.handle(SuperviseLoop, ctx).await
+        self.handle(SuperviseLoop, ctx).await?;
+        ctx.schedule_self_msg(
+            REFRESH_INDEXING_SETTINGS_INTERVAL,
+            RefreshIndexingSettingsLoop,
+        )
+        .await;
+        Ok(())
     }
 }
 
@@ -774,6 +873,59 @@ impl Handler<ApplyIndexingPlanRequest> for IndexingService {
     }
 }
 
+#[async_trait]
+impl Handler<ForceCommitRequest> for IndexingService {
+    type Reply = ForceCommitResponse;
+
+    async fn handle(
+        &mut self,
+        request: ForceCommitRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<ForceCommitResponse, ActorExitStatus> {
+        let mut num_pipelines = 0;
+        for (pipeline_id, pipeline_handle) in &self.indexing_pipeline_handles {
+            if pipeline_id.index_uid.index_id() != request.index_id {
+                continue;
+            }
+            if pipeline_handle
+                .mailbox()
+                .send_message(ForceCommit)
+                .await
+                .is_ok()
+            {
+                num_pipelines += 1;
+            }
+        }
+        info!(
+            index_id=%request.index_id,
+            num_pipelines=num_pipelines,
+            "Forcing commit of indexing pipelines."
+        );
+        Ok(ForceCommitResponse { num_pipelines })
+    }
+}
+
+#[async_trait]
+impl Handler<DrainPipelines> for IndexingService {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: DrainPipelines,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        info!(
+            num_pipelines = self.indexing_pipeline_handles.len(),
+            "Draining indexing pipelines."
+        );
+        self.is_draining = true;
+        for pipeline_handle in self.indexing_pipeline_handles.values() {
+            let _ = pipeline_handle.mailbox().send_message(DrainPipeline).await;
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<Healthz> for IndexingService {
     type Reply = bool;
@@ -788,6 +940,26 @@ impl Handler<Healthz> for IndexingService {
     }
 }
 
+#[async_trait]
+impl Handler<Readyz> for IndexingService {
+    type Reply = bool;
+
+    /// The indexer is ready as long as it is not draining and its ingest API queues are writable.
+    async fn handle(
+        &mut self,
+        _msg: Readyz,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<bool, ActorExitStatus> {
+        if self.is_draining {
+            return Ok(false);
+        }
+        let Some(ingest_api_service) = &self.ingest_api_service_opt else {
+            return Ok(true);
+        };
+        Ok(ingest_api_service.ask(Readyz).await.unwrap_or(false))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroUsize;
@@ -872,6 +1044,7 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let spawn_pipeline_msg = SpawnPipeline {
             index_id: index_id.clone(),
@@ -962,6 +1135,7 @@ mod tests {
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         indexing_service
             .ask_for_res(SpawnPipeline {
@@ -983,6 +1157,150 @@ mod tests {
         panic!("Pipeline not exited successfully.");
     }
 
+    #[tokio::test]
+    async fn test_indexing_service_drain_pipelines() {
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config).await.unwrap();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source".to_string(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+            labels: Default::default(),
+        };
+        metastore
+            .add_source(index_uid.clone(), source_config.clone())
+            .await
+            .unwrap();
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, indexing_service_handle) =
+            spawn_indexing_service(temp_dir.path(), &universe, metastore, cluster.clone()).await;
+
+        let indexing_tasks = vec![IndexingTask {
+            index_uid: index_uid.to_string(),
+            source_id: source_config.source_id.clone(),
+        }];
+        indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks: indexing_tasks.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            indexing_service_handle
+                .observe()
+                .await
+                .num_running_pipelines,
+            1
+        );
+        assert!(indexing_service.ask(Readyz).await.unwrap());
+        indexing_service.ask(DrainPipelines).await.unwrap();
+        assert!(!indexing_service.ask(Readyz).await.unwrap());
+
+        let mut num_attempts = 0;
+        loop {
+            let observation = indexing_service_handle.observe().await;
+            if observation.num_successful_pipelines == 1 {
+                assert_eq!(observation.num_running_pipelines, 0);
+                assert_eq!(observation.num_failed_pipelines, 0);
+                break;
+            }
+            num_attempts += 1;
+            assert!(num_attempts < 100, "Pipeline was not drained.");
+            universe.sleep(Duration::from_millis(100)).await;
+        }
+        cluster
+            .wait_for_ready_members(
+                |members| members[0].indexing_tasks.is_empty(),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        // A draining indexing service no longer spawns pipelines.
+        indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest { indexing_tasks })
+            .await
+            .unwrap();
+        let observation = indexing_service_handle.process_pending_and_observe().await;
+        assert_eq!(observation.num_running_pipelines, 0);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_indexing_service_force_commit() {
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config).await.unwrap();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source".to_string(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+            labels: Default::default(),
+        };
+        metastore
+            .add_source(index_uid.clone(), source_config.clone())
+            .await
+            .unwrap();
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, _indexing_service_handle) =
+            spawn_indexing_service(temp_dir.path(), &universe, metastore, cluster).await;
+
+        indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks: vec![IndexingTask {
+                    index_uid: index_uid.to_string(),
+                    source_id: source_config.source_id.clone(),
+                }],
+            })
+            .await
+            .unwrap();
+        let force_commit_response = indexing_service
+            .ask(ForceCommitRequest {
+                index_id: index_id.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(force_commit_response.num_pipelines, 1);
+
+        let force_commit_response = indexing_service
+            .ask(ForceCommitRequest {
+                index_id: "non-existing-index".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(force_commit_response.num_pipelines, 0);
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_indexing_service_apply_plan() {
         quickwit_common::setup_logging_for_tests();
@@ -1020,6 +1338,7 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         metastore
             .add_source(index_uid.clone(), source_config_1.clone())
@@ -1056,6 +1375,7 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         metastore
             .add_source(index_uid.clone(), source_config_2.clone())
@@ -1195,6 +1515,7 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let index_uid = metastore.create_index(index_config).await.unwrap();
         metastore
@@ -1314,6 +1635,7 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         index_metadata
             .sources
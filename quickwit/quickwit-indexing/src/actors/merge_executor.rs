@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::ops::RangeInclusive;
 use std::path::Path;
 use std::sync::Arc;
@@ -34,13 +34,15 @@ use quickwit_common::temp_dir::TempDirectory;
 use quickwit_directories::UnionDirectory;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_metastore::{Metastore, SplitMetadata};
-use quickwit_proto::metastore_api::DeleteTask;
-use quickwit_query::query_ast::QueryAst;
+use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
+use quickwit_query::query_ast::{
+    query_ast_from_json, query_ast_to_json, BoolQuery, QueryAst, TermQuery, TermSetQuery,
+};
 use quickwit_query::{get_quickwit_fastfield_normalizer_manager, get_quickwit_tokenizer_manager};
 use tantivy::directory::{DirectoryClone, MmapDirectory, RamDirectory};
 use tantivy::{Advice, DateTime, Directory, Index, IndexMeta, SegmentId, SegmentReader};
 use tokio::runtime::Handle;
-use tracing::{debug, info, instrument, warn};
+use tracing::{debug, error, info, instrument, warn};
 
 use crate::actors::Packager;
 use crate::controlled_directory::ControlledDirectory;
@@ -48,6 +50,7 @@ use crate::merge_policy::MergeOperationType;
 use crate::models::{
     IndexedSplit, IndexedSplitBatch, IndexingPipelineId, MergeScratch, PublishLock, SplitAttrs,
 };
+use crate::new_split_id;
 
 #[derive(Clone)]
 pub struct MergeExecutor {
@@ -89,8 +92,8 @@ impl Handler<MergeScratch> for MergeExecutor {
     ) -> Result<(), ActorExitStatus> {
         let start = Instant::now();
         let merge_op = merge_scratch.merge_operation;
-        let indexed_split_opt: Option<IndexedSplit> = match merge_op.operation_type {
-            MergeOperationType::Merge => Some(
+        let indexed_splits: Vec<IndexedSplit> = match &merge_op.operation_type {
+            MergeOperationType::Merge => vec![
                 self.process_merge(
                     merge_op.merge_split_id.clone(),
                     merge_op.splits.clone(),
@@ -99,7 +102,7 @@ impl Handler<MergeScratch> for MergeExecutor {
                     ctx,
                 )
                 .await?,
-            ),
+            ],
             MergeOperationType::DeleteAndMerge => {
                 assert_eq!(
                     merge_op.splits.len(),
@@ -116,11 +119,55 @@ impl Handler<MergeScratch> for MergeExecutor {
                     ctx,
                 )
                 .await?
+                .into_iter()
+                .collect()
+            }
+            MergeOperationType::Upgrade => {
+                assert_eq!(
+                    merge_op.splits.len(),
+                    1,
+                    "Upgrades can be applied only on one split."
+                );
+                assert_eq!(merge_scratch.tantivy_dirs.len(), 1);
+                let split_to_upgrade = merge_op.splits[0].clone();
+                self.process_upgrade(
+                    merge_op.merge_split_id.clone(),
+                    split_to_upgrade,
+                    merge_scratch.tantivy_dirs,
+                    merge_scratch.merge_scratch_directory,
+                    ctx,
+                )
+                .await?
+                .into_iter()
+                .collect()
+            }
+            MergeOperationType::Reshard { field } => {
+                assert_eq!(
+                    merge_op.splits.len(),
+                    1,
+                    "Resharding can be applied only on one split."
+                );
+                assert_eq!(merge_scratch.tantivy_dirs.len(), 1);
+                let split_to_reshard = merge_op.splits[0].clone();
+                self.process_reshard(
+                    merge_op.merge_split_id.clone(),
+                    split_to_reshard,
+                    field,
+                    merge_scratch.tantivy_dirs,
+                    merge_scratch.merge_scratch_directory,
+                    ctx,
+                )
+                .await?
             }
         };
-        if let Some(indexed_split) = indexed_split_opt {
+        if !indexed_splits.is_empty() {
+            let merged_num_docs: u64 = indexed_splits
+                .iter()
+                .map(|indexed_split| indexed_split.split_attrs.num_docs)
+                .sum();
             info!(
-                merged_num_docs = %indexed_split.split_attrs.num_docs,
+                merged_num_docs = %merged_num_docs,
+                num_splits = %indexed_splits.len(),
                 elapsed_secs = %start.elapsed().as_secs_f32(),
                 operation_type = %merge_op.operation_type,
                 "merge-operation-success"
@@ -129,7 +176,7 @@ impl Handler<MergeScratch> for MergeExecutor {
                 &self.merge_packager_mailbox,
                 IndexedSplitBatch {
                     batch_parent_span: merge_op.merge_parent_span.clone(),
-                    splits: vec![indexed_split],
+                    splits: indexed_splits,
                     checkpoint_delta: Default::default(),
                     publish_lock: PublishLock::default(),
                     merge_operation: Some(merge_op),
@@ -298,6 +345,7 @@ impl MergeExecutor {
                 split_directories,
                 Vec::new(),
                 None,
+                false,
                 merge_scratch_directory.path(),
                 ctx,
             )
@@ -358,6 +406,7 @@ impl MergeExecutor {
                 split_directories,
                 delete_tasks,
                 Some(self.doc_mapper.clone()),
+                false,
                 merge_scratch_directory.path(),
                 ctx,
             )
@@ -426,12 +475,227 @@ impl MergeExecutor {
         Ok(Some(indexed_split))
     }
 
+    /// Rewrites a single split with the current split format version. The upgraded split is
+    /// only handed over to the packager if it contains exactly the same number of documents as
+    /// the original split.
+    async fn process_upgrade(
+        &mut self,
+        upgraded_split_id: String,
+        split: SplitMetadata,
+        tantivy_dirs: Vec<Box<dyn Directory>>,
+        merge_scratch_directory: TempDirectory,
+        ctx: &ActorContext<Self>,
+    ) -> anyhow::Result<Option<IndexedSplit>> {
+        let (union_index_meta, split_directories) = open_split_directories(&tantivy_dirs)?;
+        let controlled_directory = self
+            .merge_split_directories(
+                union_index_meta,
+                split_directories,
+                Vec::new(),
+                None,
+                true,
+                merge_scratch_directory.path(),
+                ctx,
+            )
+            .await?;
+
+        // This will have the side effect of deleting the directory containing the downloaded split.
+        let upgraded_index = open_index(controlled_directory.clone())?;
+        ctx.record_progress();
+
+        let num_docs: u64 = upgraded_index
+            .searchable_segment_metas()?
+            .iter()
+            .map(|segment_meta| segment_meta.num_docs() as u64)
+            .sum();
+        if num_docs != split.num_docs as u64 {
+            error!(
+                split_id = split.split_id(),
+                expected_num_docs = split.num_docs,
+                num_docs = num_docs,
+                "Upgraded split does not contain the same number of documents as the original \
+                 split. The original split is left untouched."
+            );
+            return Ok(None);
+        }
+        let index_pipeline_id = IndexingPipelineId {
+            index_uid: split.index_uid.clone(),
+            node_id: split.node_id.clone(),
+            pipeline_ord: 0,
+            source_id: split.source_id.clone(),
+        };
+        let mut split_attrs = merge_split_attrs(
+            upgraded_split_id,
+            &index_pipeline_id,
+            std::slice::from_ref(&split),
+        );
+        // Rewriting a split does not count as a merge operation.
+        split_attrs.num_merge_ops = split.num_merge_ops;
+        let indexed_split = IndexedSplit {
+            split_attrs,
+            index: upgraded_index,
+            split_scratch_directory: merge_scratch_directory,
+            controlled_directory_opt: Some(controlled_directory),
+        };
+        Ok(Some(indexed_split))
+    }
+
+    /// Rewrites a single split into one split per value of the tag field `field`, plus one split
+    /// gathering the documents without any value for that field, if any. The values are read
+    /// from the tags of the split. The resulting splits are handed over to the packager in a
+    /// single batch so that they replace the original split atomically on publish, and only if
+    /// they contain, all together, exactly the same number of documents as the original split.
+    async fn process_reshard(
+        &mut self,
+        reshard_split_id: String,
+        split: SplitMetadata,
+        field: &str,
+        tantivy_dirs: Vec<Box<dyn Directory>>,
+        merge_scratch_directory: TempDirectory,
+        ctx: &ActorContext<Self>,
+    ) -> anyhow::Result<Vec<IndexedSplit>> {
+        let tag_prefix = format!("{field}:");
+        let values: BTreeSet<String> = split
+            .tags
+            .iter()
+            .filter_map(|tag| tag.strip_prefix(&tag_prefix))
+            .map(|value| value.to_string())
+            .collect();
+        if values.is_empty() {
+            warn!(
+                split_id = split.split_id(),
+                field = field,
+                "No value found in the tags of the split. The split is left untouched."
+            );
+            return Ok(Vec::new());
+        }
+        // Each resulting split is obtained by deleting, from the original split, the documents
+        // that do not belong to it.
+        let mut delete_query_asts: Vec<(Option<&str>, QueryAst)> = values
+            .iter()
+            .map(|value| {
+                let not_value_query_ast: QueryAst = BoolQuery {
+                    must: vec![QueryAst::MatchAll],
+                    must_not: vec![TermQuery {
+                        field: field.to_string(),
+                        value: value.clone(),
+                    }
+                    .into()],
+                    ..Default::default()
+                }
+                .into();
+                (Some(value.as_str()), not_value_query_ast)
+            })
+            .collect();
+        let any_value_query_ast: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(field.to_string(), values.clone())]),
+        }
+        .into();
+        delete_query_asts.push((None, any_value_query_ast));
+
+        let index_pipeline_id = IndexingPipelineId {
+            index_uid: split.index_uid.clone(),
+            node_id: split.node_id.clone(),
+            pipeline_ord: 0,
+            source_id: split.source_id.clone(),
+        };
+        let mut indexed_splits = Vec::with_capacity(delete_query_asts.len());
+
+        for (value_opt, delete_query_ast) in delete_query_asts {
+            let delete_task = DeleteTask {
+                create_timestamp: 0,
+                opstamp: 0,
+                delete_query: Some(DeleteQuery {
+                    index_uid: split.index_uid.to_string(),
+                    start_timestamp: None,
+                    end_timestamp: None,
+                    query_ast: query_ast_to_json(&delete_query_ast)?,
+                }),
+            };
+            let (union_index_meta, split_directories) = open_split_directories(&tantivy_dirs)?;
+            let shard_scratch_directory = merge_scratch_directory.named_temp_child("shard-")?;
+            let controlled_directory = self
+                .merge_split_directories(
+                    union_index_meta,
+                    split_directories,
+                    vec![delete_task],
+                    Some(self.doc_mapper.clone()),
+                    false,
+                    shard_scratch_directory.path(),
+                    ctx,
+                )
+                .await?;
+            let shard_index = open_index(controlled_directory.clone())?;
+            ctx.record_progress();
+
+            let Some(shard_segment) = shard_index.searchable_segments()?.into_iter().next() else {
+                debug!(value = ?value_opt, "empty-shard");
+                continue;
+            };
+            let shard_segment_reader = SegmentReader::open(&shard_segment)?;
+            let num_docs = shard_segment_reader.num_docs() as u64;
+            let uncompressed_docs_size_in_bytes = (num_docs as f32
+                * split.uncompressed_docs_size_in_bytes as f32
+                / split.num_docs as f32) as u64;
+            let time_range =
+                if let Some(timestamp_field_name) = self.doc_mapper.timestamp_field_name() {
+                    let reader = shard_segment_reader
+                        .fast_fields()
+                        .date(timestamp_field_name)?;
+                    Some(reader.min_value()..=reader.max_value())
+                } else {
+                    None
+                };
+            // The first shard reuses the split ID of the operation so that it can be traced.
+            let shard_split_id = if indexed_splits.is_empty() {
+                reshard_split_id.clone()
+            } else {
+                new_split_id()
+            };
+            let indexed_split = IndexedSplit {
+                split_attrs: SplitAttrs {
+                    split_id: shard_split_id,
+                    partition_id: split.partition_id,
+                    pipeline_id: index_pipeline_id.clone(),
+                    replaced_split_ids: vec![split.split_id.clone()],
+                    time_range,
+                    num_docs,
+                    uncompressed_docs_size_in_bytes,
+                    delete_opstamp: split.delete_opstamp,
+                    // Resharding a split does not count as a merge operation.
+                    num_merge_ops: split.num_merge_ops,
+                },
+                index: shard_index,
+                split_scratch_directory: shard_scratch_directory,
+                controlled_directory_opt: Some(controlled_directory),
+            };
+            indexed_splits.push(indexed_split);
+        }
+        let num_docs: u64 = indexed_splits
+            .iter()
+            .map(|indexed_split| indexed_split.split_attrs.num_docs)
+            .sum();
+        if num_docs != split.num_docs as u64 {
+            // This happens when documents hold several values for the field.
+            error!(
+                split_id = split.split_id(),
+                expected_num_docs = split.num_docs,
+                num_docs = num_docs,
+                "Resharded splits do not contain the same number of documents as the original \
+                 split. The original split is left untouched."
+            );
+            return Ok(Vec::new());
+        }
+        Ok(indexed_splits)
+    }
+
     async fn merge_split_directories(
         &self,
         union_index_meta: IndexMeta,
         split_directories: Vec<Box<dyn Directory>>,
         delete_tasks: Vec<DeleteTask>,
         doc_mapper_opt: Option<Arc<dyn DocMapper>>,
+        force_rewrite: bool,
         output_path: &Path,
         ctx: &ActorContext<MergeExecutor>,
     ) -> anyhow::Result<ControlledDirectory> {
@@ -468,7 +732,7 @@ impl MergeExecutor {
                 let delete_query = delete_task
                     .delete_query
                     .expect("A delete task must have a delete query.");
-                let query_ast: QueryAst = serde_json::from_str(&delete_query.query_ast)
+                let query_ast: QueryAst = query_ast_from_json(&delete_query.query_ast)
                     .context("Invalid query_ast json")?;
                 // We ignore the docmapper default fields when we consider delete query.
                 // We reparse the query here defensivley, but actually, it should already have been
@@ -492,8 +756,9 @@ impl MergeExecutor {
             .map(|segment_meta| segment_meta.id())
             .collect();
 
-        // A merge is useless if there is no delete and only one segment.
-        if num_delete_tasks == 0 && segment_ids.len() <= 1 {
+        // A merge is useless if there is no delete and only one segment, unless we want to rewrite
+        // the segment with the current split format.
+        if num_delete_tasks == 0 && segment_ids.len() <= 1 && !force_rewrite {
             return Ok(output_directory);
         }
 
@@ -853,4 +1118,224 @@ mod tests {
         )
         .await
     }
+
+    #[tokio::test]
+    async fn test_upgrade_executor() -> anyhow::Result<()> {
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                input_formats:
+                - unix_timestamp
+                fast: true
+            timestamp_field: ts
+        "#;
+        let test_sandbox =
+            TestSandbox::create("test-upgrade-executor", doc_mapping_yaml, "", &["body"]).await?;
+        let index_uid = test_sandbox.index_uid();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: index_uid.clone(),
+            node_id: "unknown".to_string(),
+            pipeline_ord: 0,
+            source_id: "unknown".to_string(),
+        };
+        test_sandbox
+            .add_documents(vec![
+                serde_json::json!({"body": "info", "ts": 1624928208 }),
+                serde_json::json!({"body": "warn", "ts": 1634928208 }),
+            ])
+            .await?;
+        let metastore = test_sandbox.metastore();
+        let mut split_metadata = metastore
+            .list_all_splits(index_uid.clone())
+            .await?
+            .into_iter()
+            .next()
+            .unwrap()
+            .split_metadata;
+        split_metadata.num_merge_ops = 2;
+        split_metadata.format_version = 0;
+
+        let merge_scratch_directory = TempDirectory::for_test();
+        let downloaded_splits_directory =
+            merge_scratch_directory.named_temp_child("downloaded-splits-")?;
+        let split_filename = split_file(split_metadata.split_id());
+        let dest_filepath = downloaded_splits_directory.path().join(&split_filename);
+        test_sandbox
+            .storage()
+            .copy_to_file(Path::new(&split_filename), &dest_filepath)
+            .await?;
+        let tantivy_dir = get_tantivy_directory_from_split_bundle(&dest_filepath).unwrap();
+        let merge_ops_inventory = Inventory::new();
+        let merge_operation = merge_ops_inventory.track(MergeOperation::new_upgrade_operation(
+            split_metadata.clone(),
+        ));
+        let merge_scratch = MergeScratch {
+            merge_operation,
+            tantivy_dirs: vec![tantivy_dir],
+            merge_scratch_directory,
+            downloaded_splits_directory,
+        };
+        let (merge_packager_mailbox, merge_packager_inbox) =
+            test_sandbox.universe().create_test_mailbox();
+        let merge_executor = MergeExecutor::new(
+            pipeline_id,
+            metastore,
+            test_sandbox.doc_mapper(),
+            IoControls::default(),
+            merge_packager_mailbox,
+        );
+        let (merge_executor_mailbox, merge_executor_handle) = test_sandbox
+            .universe()
+            .spawn_builder()
+            .spawn(merge_executor);
+        merge_executor_mailbox.send_message(merge_scratch).await?;
+        merge_executor_handle.process_pending_and_observe().await;
+
+        let packager_msgs: Vec<IndexedSplitBatch> = merge_packager_inbox.drain_for_test_typed();
+        assert_eq!(packager_msgs.len(), 1);
+        let split = &packager_msgs[0].splits[0];
+        assert_eq!(split.split_attrs.num_docs, 2);
+        // Upgrades do not update the num_merge_ops value.
+        assert_eq!(split.split_attrs.num_merge_ops, 2);
+        assert_eq!(
+            split.split_attrs.replaced_split_ids,
+            vec![split_metadata.split_id().to_string()]
+        );
+        assert_eq!(
+            split.split_attrs.uncompressed_docs_size_in_bytes,
+            split_metadata.uncompressed_docs_size_in_bytes
+        );
+        let reader = split
+            .index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(searcher.num_docs(), 2);
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reshard_executor() -> anyhow::Result<()> {
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: tenant_id
+                type: text
+                tokenizer: raw
+              - name: ts
+                type: datetime
+                input_formats:
+                - unix_timestamp
+                fast: true
+            timestamp_field: ts
+            tag_fields: [tenant_id]
+        "#;
+        let test_sandbox =
+            TestSandbox::create("test-reshard-executor", doc_mapping_yaml, "", &["body"]).await?;
+        let index_uid = test_sandbox.index_uid();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: index_uid.clone(),
+            node_id: "unknown".to_string(),
+            pipeline_ord: 0,
+            source_id: "unknown".to_string(),
+        };
+        test_sandbox
+            .add_documents(vec![
+                serde_json::json!({"body": "info", "tenant_id": "tenant-a", "ts": 1624928208 }),
+                serde_json::json!({"body": "warn", "tenant_id": "tenant-b", "ts": 1634928208 }),
+                serde_json::json!({"body": "error", "tenant_id": "tenant-a", "ts": 1644928208 }),
+                serde_json::json!({"body": "debug", "ts": 1654928208 }),
+            ])
+            .await?;
+        let metastore = test_sandbox.metastore();
+        let split_metadata = metastore
+            .list_all_splits(index_uid.clone())
+            .await?
+            .into_iter()
+            .next()
+            .unwrap()
+            .split_metadata;
+        assert!(split_metadata.tags.contains("tenant_id:tenant-a"));
+        assert!(split_metadata.tags.contains("tenant_id:tenant-b"));
+
+        let merge_scratch_directory = TempDirectory::for_test();
+        let downloaded_splits_directory =
+            merge_scratch_directory.named_temp_child("downloaded-splits-")?;
+        let split_filename = split_file(split_metadata.split_id());
+        let dest_filepath = downloaded_splits_directory.path().join(&split_filename);
+        test_sandbox
+            .storage()
+            .copy_to_file(Path::new(&split_filename), &dest_filepath)
+            .await?;
+        let tantivy_dir = get_tantivy_directory_from_split_bundle(&dest_filepath).unwrap();
+        let merge_ops_inventory = Inventory::new();
+        let merge_operation = merge_ops_inventory.track(MergeOperation::new_reshard_operation(
+            split_metadata.clone(),
+            "tenant_id".to_string(),
+        ));
+        let merge_scratch = MergeScratch {
+            merge_operation,
+            tantivy_dirs: vec![tantivy_dir],
+            merge_scratch_directory,
+            downloaded_splits_directory,
+        };
+        let (merge_packager_mailbox, merge_packager_inbox) =
+            test_sandbox.universe().create_test_mailbox();
+        let merge_executor = MergeExecutor::new(
+            pipeline_id,
+            metastore,
+            test_sandbox.doc_mapper(),
+            IoControls::default(),
+            merge_packager_mailbox,
+        );
+        let (merge_executor_mailbox, merge_executor_handle) = test_sandbox
+            .universe()
+            .spawn_builder()
+            .spawn(merge_executor);
+        merge_executor_mailbox.send_message(merge_scratch).await?;
+        merge_executor_handle.process_pending_and_observe().await;
+
+        let packager_msgs: Vec<IndexedSplitBatch> = merge_packager_inbox.drain_for_test_typed();
+        assert_eq!(packager_msgs.len(), 1);
+        // One split per tenant, plus one split for the document without tenant.
+        let splits = &packager_msgs[0].splits;
+        assert_eq!(splits.len(), 3);
+        let num_docs_per_split: Vec<u64> = splits
+            .iter()
+            .map(|split| split.split_attrs.num_docs)
+            .collect();
+        assert_eq!(num_docs_per_split, vec![2, 1, 1]);
+        for split in splits {
+            assert_eq!(
+                split.split_attrs.replaced_split_ids,
+                vec![split_metadata.split_id().to_string()]
+            );
+            let reader = split
+                .index
+                .reader_builder()
+                .reload_policy(ReloadPolicy::Manual)
+                .try_into()?;
+            let searcher = reader.searcher();
+            assert_eq!(searcher.segment_readers().len(), 1);
+            assert_eq!(searcher.num_docs(), split.split_attrs.num_docs);
+        }
+        let first_split_time_range = splits[0].split_attrs.time_range.clone().unwrap();
+        assert_eq!(
+            first_split_time_range.start().into_timestamp_secs(),
+            1624928208
+        );
+        assert_eq!(
+            first_split_time_range.end().into_timestamp_secs(),
+            1644928208
+        );
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
 }
@@ -234,7 +234,13 @@ impl MergePipeline {
 
         // Merge Packager
         let tag_fields = self.params.doc_mapper.tag_named_fields()?;
-        let merge_packager = Packager::new("MergePackager", tag_fields, merge_uploader_mailbox);
+        let doc_id_field_opt = self.params.doc_mapper.doc_id_named_field()?;
+        let merge_packager = Packager::new(
+            "MergePackager",
+            tag_fields,
+            doc_id_field_opt,
+            merge_uploader_mailbox,
+        );
         let (merge_packager_mailbox, merge_packager_handler) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
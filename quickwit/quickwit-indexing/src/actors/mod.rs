@@ -41,7 +41,7 @@ mod merge_split_downloader;
 
 pub use self::doc_processor::{DocProcessor, DocProcessorCounters};
 pub use self::index_serializer::IndexSerializer;
-pub use self::indexer::{Indexer, IndexerCounters};
+pub use self::indexer::{ForceCommit, Indexer, IndexerCounters, UpdateIndexingSettings};
 pub use self::merge_executor::{combine_partition_ids, merge_split_attrs, MergeExecutor};
 pub use self::merge_pipeline::MergePipeline;
 pub use self::merge_planner::MergePlanner;
@@ -31,7 +31,7 @@ use quickwit_common::runtimes::RuntimeType;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_directories::write_hotcache;
 use quickwit_doc_mapper::tag_pruning::append_to_tag_set;
-use quickwit_doc_mapper::NamedField;
+use quickwit_doc_mapper::{DocIdBloomFilter, NamedField};
 use tantivy::schema::FieldType;
 use tantivy::{InvertedIndexReader, ReloadPolicy, SegmentMeta};
 use tokio::runtime::Handle;
@@ -55,6 +55,8 @@ use crate::models::{
 /// This includes the following steps:
 /// - commit: this step is CPU heavy
 /// - identifying the list of tags for the splits, and labelling it accordingly
+/// - building the bloom filter of the document IDs of the split, if the index defines a doc ID
+///   field
 /// - creating a bundle file
 /// - computing the hotcache
 /// - appending it to the split file.
@@ -66,18 +68,22 @@ pub struct Packager {
     uploader_mailbox: Mailbox<Uploader>,
     /// List of tag fields ([`Vec<NamedField>`]) defined in the index config.
     tag_fields: Vec<NamedField>,
+    /// Doc ID field defined in the index config, if any.
+    doc_id_field_opt: Option<NamedField>,
 }
 
 impl Packager {
     pub fn new(
         actor_name: &'static str,
         tag_fields: Vec<NamedField>,
+        doc_id_field_opt: Option<NamedField>,
         uploader_mailbox: Mailbox<Uploader>,
     ) -> Packager {
         Packager {
             actor_name,
             uploader_mailbox,
             tag_fields,
+            doc_id_field_opt,
         }
     }
 
@@ -88,8 +94,13 @@ impl Packager {
     ) -> anyhow::Result<PackagedSplit> {
         let segment_metas = split.index.searchable_segment_metas()?;
         assert_eq!(segment_metas.len(), 1);
-        let packaged_split =
-            create_packaged_split(&segment_metas[..], split, &self.tag_fields, ctx)?;
+        let packaged_split = create_packaged_split(
+            &segment_metas[..],
+            split,
+            &self.tag_fields,
+            self.doc_id_field_opt.as_ref(),
+            ctx,
+        )?;
         Ok(packaged_split)
     }
 }
@@ -269,10 +280,34 @@ fn try_extract_terms(
     Ok(terms)
 }
 
+/// Builds a bloom filter over the terms of the doc ID field.
+///
+/// Returns `None` if the split holds too many document IDs for the bloom filter to fit in the
+/// split metadata. The split is then never pruned when documents are retrieved by ID.
+fn try_build_doc_id_bloom_filter(
+    inv_indexes: &[Arc<InvertedIndexReader>],
+) -> anyhow::Result<Option<DocIdBloomFilter>> {
+    let num_terms = inv_indexes
+        .iter()
+        .map(|inv_index| inv_index.terms().num_terms())
+        .sum::<usize>();
+    let Some(mut bloom_filter) = DocIdBloomFilter::with_num_doc_ids(num_terms) else {
+        return Ok(None);
+    };
+    for inv_index in inv_indexes {
+        let mut terms_streamer = inv_index.terms().stream()?;
+        while let Some((term_data, _)) = terms_streamer.next() {
+            bloom_filter.insert(term_data);
+        }
+    }
+    Ok(Some(bloom_filter))
+}
+
 fn create_packaged_split(
     segment_metas: &[SegmentMeta],
     split: IndexedSplit,
     tag_fields: &[NamedField],
+    doc_id_field_opt: Option<&NamedField>,
     ctx: &ActorContext<Packager>,
 ) -> anyhow::Result<PackagedSplit> {
     info!(split_id = split.split_id(), "create-packaged-split");
@@ -307,6 +342,27 @@ fn create_packaged_split(
 
     ctx.record_progress();
 
+    let mut doc_id_bloom_filter = None;
+
+    if let Some(doc_id_field) = doc_id_field_opt {
+        debug!(split_id = split.split_id(), "build-doc-id-bloom-filter");
+        let inverted_indexes = index_reader
+            .searcher()
+            .segment_readers()
+            .iter()
+            .map(|segment| segment.inverted_index(doc_id_field.field))
+            .collect::<Result<Vec<_>, _>>()?;
+        doc_id_bloom_filter = try_build_doc_id_bloom_filter(&inverted_indexes)?;
+
+        if doc_id_bloom_filter.is_none() {
+            debug!(
+                split_id = split.split_id(),
+                "Too many doc IDs for the split to have a bloom filter."
+            );
+        }
+        ctx.record_progress();
+    }
+
     debug!(split_id = split.split_id(), "build-hotcache");
     let mut hotcache_bytes = Vec::new();
     build_hotcache(split.split_scratch_directory.path(), &mut hotcache_bytes)?;
@@ -316,6 +372,7 @@ fn create_packaged_split(
         split_attrs: split.split_attrs,
         split_scratch_directory: split.split_scratch_directory,
         tags,
+        doc_id_bloom_filter,
         split_files,
         hotcache_bytes,
     };
@@ -454,7 +511,8 @@ mod tests {
                 "tag_str", "tag_many", "tag_u64", "tag_i64", "tag_f64", "tag_bool",
             ],
         );
-        let packager = Packager::new("TestPackager", tag_fields, mailbox);
+        let doc_id_field_opt = get_tag_fields(indexed_split.index.schema(), &["tag_many"]).pop();
+        let packager = Packager::new("TestPackager", tag_fields, doc_id_field_opt, mailbox);
         let (packager_mailbox, packager_handle) = universe.spawn_builder().spawn(packager);
         packager_mailbox
             .send_message(IndexedSplitBatch {
@@ -490,6 +548,10 @@ mod tests {
                 "tag_u64:42"
             ]
         );
+        let doc_id_bloom_filter = split.doc_id_bloom_filter.as_ref().unwrap();
+        assert!(doc_id_bloom_filter.may_contain(b"many-1"));
+        assert!(doc_id_bloom_filter.may_contain(b"many-9"));
+        assert!(!doc_id_bloom_filter.may_contain(b"value"));
         assert_eq!(
             split.split_attrs.time_range,
             Some(
@@ -310,11 +310,13 @@ impl Handler<PackagedSplitBatch> for Uploader {
                         &packaged_split.split_files,
                         &packaged_split.hotcache_bytes,
                     )?;
-                    let split_metadata = create_split_metadata(
+                    let mut split_metadata = create_split_metadata(
                         &packaged_split.split_attrs,
                         packaged_split.tags.clone(),
                         split_streamer.footer_range.start..split_streamer.footer_range.end,
                     );
+                    split_metadata.doc_id_bloom_filter =
+                        packaged_split.doc_id_bloom_filter.clone();
 
                     split_metadata_list.push(split_metadata);
                 }
@@ -525,6 +527,7 @@ mod tests {
                     },
                     split_scratch_directory,
                     tags: Default::default(),
+                    doc_id_bloom_filter: None,
                     hotcache_bytes: Vec::new(),
                     split_files: Vec::new(),
                 }],
@@ -631,6 +634,7 @@ mod tests {
             },
             split_scratch_directory: split_scratch_directory_1,
             tags: Default::default(),
+            doc_id_bloom_filter: None,
             split_files: Vec::new(),
             hotcache_bytes: Vec::new(),
         };
@@ -654,6 +658,7 @@ mod tests {
             },
             split_scratch_directory: split_scratch_directory_2,
             tags: Default::default(),
+            doc_id_bloom_filter: None,
             split_files: Vec::new(),
             hotcache_bytes: Vec::new(),
         };
@@ -766,6 +771,7 @@ mod tests {
                     },
                     split_scratch_directory,
                     tags: Default::default(),
+                    doc_id_bloom_filter: None,
                     hotcache_bytes: Vec::new(),
                     split_files: Vec::new(),
                 }],
@@ -0,0 +1,238 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use quickwit_query::get_quickwit_tokenizer_manager;
+use serde_json::Value as JsonValue;
+use tantivy::schema::{FieldType, Schema, Value};
+use tantivy::tokenizer::{TextAnalyzer, TokenStream};
+use tantivy::Document;
+
+use crate::metrics::INDEXER_METRICS;
+
+/// One document out of `FIELD_PROFILING_SAMPLING_PERIOD` is profiled.
+const FIELD_PROFILING_SAMPLING_PERIOD: u64 = 100;
+
+/// Cost of tokenizing the values of a field.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct FieldTokenizationCost {
+    duration: Duration,
+    num_tokens: u64,
+    num_bytes: u64,
+}
+
+struct FieldAnalyzer {
+    field_name: String,
+    text_analyzer: TextAnalyzer,
+}
+
+/// Estimates the time spent tokenizing each field of an index and the number of tokens each field
+/// produces, which drives the memory usage of the indexer.
+///
+/// Tantivy tokenizes all the fields of a document at once when the document is added to the
+/// index writer, so the per-field cost cannot be observed directly. Instead, the profiler tokenizes
+/// again the text fields of a sample of the documents and extrapolates the measured costs to all
+/// the indexed documents.
+pub(crate) struct FieldProfiler {
+    index_id: String,
+    // Indexed by field ID. `None` for the fields that are not tokenized.
+    field_analyzers: Vec<Option<FieldAnalyzer>>,
+    num_docs: u64,
+}
+
+impl FieldProfiler {
+    pub fn new(index_id: String, schema: &Schema) -> Self {
+        let tokenizer_manager = get_quickwit_tokenizer_manager();
+        let field_analyzers = schema
+            .fields()
+            .map(|(_field, field_entry)| {
+                let tokenizer_name = match field_entry.field_type() {
+                    FieldType::Str(text_options) => {
+                        text_options.get_indexing_options()?.tokenizer()
+                    }
+                    FieldType::JsonObject(json_options) => {
+                        json_options.get_text_indexing_options()?.tokenizer()
+                    }
+                    _ => return None,
+                };
+                let text_analyzer = tokenizer_manager.get(tokenizer_name)?;
+                Some(FieldAnalyzer {
+                    field_name: field_entry.name().to_string(),
+                    text_analyzer,
+                })
+            })
+            .collect();
+        Self {
+            index_id,
+            field_analyzers,
+            num_docs: 0,
+        }
+    }
+
+    /// Profiles the document if it belongs to the sample and records the extrapolated costs in
+    /// the metrics.
+    pub fn record_doc(&mut self, doc: &Document) {
+        let is_sampled = self.num_docs % FIELD_PROFILING_SAMPLING_PERIOD == 0;
+        self.num_docs += 1;
+
+        if !is_sampled {
+            return;
+        }
+        for (field_name, cost) in self.profile_doc(doc) {
+            let label_values = [self.index_id.as_str(), field_name];
+            INDEXER_METRICS
+                .field_tokenization_micros
+                .with_label_values(label_values)
+                .inc_by(cost.duration.as_micros() as u64 * FIELD_PROFILING_SAMPLING_PERIOD);
+            INDEXER_METRICS
+                .field_num_tokens
+                .with_label_values(label_values)
+                .inc_by(cost.num_tokens * FIELD_PROFILING_SAMPLING_PERIOD);
+            INDEXER_METRICS
+                .field_num_bytes
+                .with_label_values(label_values)
+                .inc_by(cost.num_bytes * FIELD_PROFILING_SAMPLING_PERIOD);
+        }
+    }
+
+    /// Returns the tokenization cost of each tokenized field of the document.
+    fn profile_doc(&self, doc: &Document) -> Vec<(&str, FieldTokenizationCost)> {
+        let mut field_costs: Vec<(&str, FieldTokenizationCost)> = Vec::new();
+
+        for field_value in doc.field_values() {
+            let field_id = field_value.field().field_id() as usize;
+            let Some(Some(field_analyzer)) = self.field_analyzers.get(field_id) else {
+                continue;
+            };
+            let mut cost = FieldTokenizationCost::default();
+            let start = Instant::now();
+
+            match field_value.value() {
+                Value::Str(text) => tokenize(&field_analyzer.text_analyzer, text, &mut cost),
+                Value::JsonObject(json_obj) => {
+                    for json_value in json_obj.values() {
+                        tokenize_json(&field_analyzer.text_analyzer, json_value, &mut cost);
+                    }
+                }
+                _ => continue,
+            }
+            cost.duration = start.elapsed();
+
+            let field_name = field_analyzer.field_name.as_str();
+            if let Some((_, field_cost)) = field_costs
+                .iter_mut()
+                .find(|(other_field_name, _)| *other_field_name == field_name)
+            {
+                field_cost.duration += cost.duration;
+                field_cost.num_tokens += cost.num_tokens;
+                field_cost.num_bytes += cost.num_bytes;
+            } else {
+                field_costs.push((field_name, cost));
+            }
+        }
+        field_costs
+    }
+}
+
+fn tokenize(text_analyzer: &TextAnalyzer, text: &str, cost: &mut FieldTokenizationCost) {
+    let mut token_stream = text_analyzer.token_stream(text);
+    while token_stream.advance() {
+        cost.num_tokens += 1;
+    }
+    cost.num_bytes += text.len() as u64;
+}
+
+/// Tokenizes the string leaves of a JSON value, as tantivy does when indexing JSON fields.
+fn tokenize_json(
+    text_analyzer: &TextAnalyzer,
+    json_value: &JsonValue,
+    cost: &mut FieldTokenizationCost,
+) {
+    match json_value {
+        JsonValue::String(text) => tokenize(text_analyzer, text, cost),
+        JsonValue::Array(json_values) => {
+            for json_value in json_values {
+                tokenize_json(text_analyzer, json_value, cost);
+            }
+        }
+        JsonValue::Object(json_obj) => {
+            for json_value in json_obj.values() {
+                tokenize_json(text_analyzer, json_value, cost);
+            }
+        }
+        JsonValue::Null | JsonValue::Bool(_) | JsonValue::Number(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tantivy::schema::{JsonObjectOptions, TextFieldIndexing, TextOptions, FAST, STRING, TEXT};
+
+    use super::*;
+
+    #[test]
+    fn test_field_profiler_profile_doc() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let service_field = schema_builder.add_text_field("service", STRING);
+        let stored_field = schema_builder.add_text_field("stored", TextOptions::default());
+        let timestamp_field = schema_builder.add_u64_field("timestamp", FAST);
+        let attributes_field = schema_builder.add_json_field(
+            "attributes",
+            JsonObjectOptions::default()
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("default")),
+        );
+        let schema = schema_builder.build();
+        let field_profiler = FieldProfiler::new("test-index".to_string(), &schema);
+
+        let mut doc = Document::default();
+        doc.add_text(body_field, "hello happy tax payer");
+        doc.add_text(body_field, "goodbye");
+        doc.add_text(service_field, "my service");
+        doc.add_text(stored_field, "not tokenized");
+        doc.add_u64(timestamp_field, 1_684_993_002);
+        let JsonValue::Object(attributes) =
+            json!({"http": {"method": "GET", "path": ["foo bar"], "status": 200}})
+        else {
+            unreachable!()
+        };
+        doc.add_json_object(attributes_field, attributes);
+
+        let field_costs = field_profiler.profile_doc(&doc);
+        let field_names: Vec<&str> = field_costs
+            .iter()
+            .map(|(field_name, _)| *field_name)
+            .collect();
+        assert_eq!(field_names, ["body", "service", "attributes"]);
+
+        let (_, body_cost) = &field_costs[0];
+        assert_eq!(body_cost.num_tokens, 5);
+        assert_eq!(body_cost.num_bytes, 28);
+
+        let (_, service_cost) = &field_costs[1];
+        assert_eq!(service_cost.num_tokens, 1);
+        assert_eq!(service_cost.num_bytes, 10);
+
+        let (_, attributes_cost) = &field_costs[2];
+        assert_eq!(attributes_cost.num_tokens, 3);
+        assert_eq!(attributes_cost.num_bytes, 10);
+    }
+}
@@ -20,7 +20,9 @@
 use async_trait::async_trait;
 use quickwit_actors::Mailbox;
 use quickwit_proto::indexing_api::indexing_service_server::{self as grpc};
-use quickwit_proto::indexing_api::{ApplyIndexingPlanRequest, ApplyIndexingPlanResponse};
+use quickwit_proto::indexing_api::{
+    ApplyIndexingPlanRequest, ApplyIndexingPlanResponse, ForceCommitRequest, ForceCommitResponse,
+};
 use quickwit_proto::tonic;
 
 use crate::IndexingService;
@@ -50,4 +52,19 @@ impl grpc::IndexingService for GrpcIndexingAdapter {
             })?;
         Ok(tonic::Response::new(ApplyIndexingPlanResponse {}))
     }
+
+    async fn force_commit(
+        &self,
+        request: tonic::Request<ForceCommitRequest>,
+    ) -> Result<tonic::Response<ForceCommitResponse>, tonic::Status> {
+        let force_commit_request = request.into_inner();
+        let force_commit_response =
+            self.0
+                .ask(force_commit_request)
+                .await
+                .map_err(|ask_error| {
+                    tonic::Status::new(tonic::Code::Internal, ask_error.to_string())
+                })?;
+        Ok(tonic::Response::new(force_commit_response))
+    }
 }
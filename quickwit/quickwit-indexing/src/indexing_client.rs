@@ -25,7 +25,9 @@ use async_trait::async_trait;
 use quickwit_actors::Mailbox;
 use quickwit_config::service::QuickwitService;
 use quickwit_grpc_clients::service_client_pool::ServiceClient;
-use quickwit_proto::indexing_api::ApplyIndexingPlanRequest;
+use quickwit_proto::indexing_api::{
+    ApplyIndexingPlanRequest, ForceCommitRequest, ForceCommitResponse,
+};
 use quickwit_proto::tonic::transport::{Channel, Endpoint, Uri};
 
 use crate::IndexingService;
@@ -106,6 +108,25 @@ impl IndexingServiceClient {
         }
     }
 
+    pub async fn force_commit(
+        &mut self,
+        force_commit_request: ForceCommitRequest,
+    ) -> anyhow::Result<ForceCommitResponse> {
+        match &mut self.client_impl {
+            IndexingServiceClientImpl::Local(service) => {
+                let force_commit_response = service.ask(force_commit_request).await?;
+                Ok(force_commit_response)
+            }
+            IndexingServiceClientImpl::Grpc(client) => {
+                let force_commit_response = client
+                    .force_commit(force_commit_request)
+                    .await?
+                    .into_inner();
+                Ok(force_commit_response)
+            }
+        }
+    }
+
     pub fn grpc_addr(&self) -> SocketAddr {
         self.grpc_addr
     }
@@ -39,11 +39,13 @@ pub use crate::split_store::{get_tantivy_directory_from_split_bundle, IndexingSp
 
 pub mod actors;
 mod controlled_directory;
+mod field_profiler;
 pub mod grpc_adapter;
 pub mod indexing_client;
 pub mod merge_policy;
 mod metrics;
 pub mod models;
+pub mod processors;
 pub mod source;
 mod split_store;
 #[cfg(any(test, feature = "testsuite"))]
@@ -40,6 +40,12 @@ use crate::new_split_id;
 pub enum MergeOperationType {
     Merge,
     DeleteAndMerge,
+    /// Rewrites a single split with the current split format version.
+    Upgrade,
+    /// Rewrites a single split into one split per value of a tag field.
+    Reshard {
+        field: String,
+    },
 }
 
 impl fmt::Display for MergeOperationType {
@@ -81,6 +87,29 @@ impl MergeOperation {
         }
     }
 
+    pub fn new_upgrade_operation(split: SplitMetadata) -> Self {
+        let merge_split_id = new_split_id();
+        let merge_parent_span = info_span!("upgrade", merge_split_id=%merge_split_id, split_ids=?split.split_id(), typ=%MergeOperationType::Upgrade);
+        Self {
+            merge_parent_span,
+            merge_split_id,
+            splits: vec![split],
+            operation_type: MergeOperationType::Upgrade,
+        }
+    }
+
+    pub fn new_reshard_operation(split: SplitMetadata, field: String) -> Self {
+        let merge_split_id = new_split_id();
+        let operation_type = MergeOperationType::Reshard { field };
+        let merge_parent_span = info_span!("reshard", merge_split_id=%merge_split_id, split_ids=?split.split_id(), typ=%operation_type);
+        Self {
+            merge_parent_span,
+            merge_split_id,
+            splits: vec![split],
+            operation_type,
+        }
+    }
+
     pub fn splits_as_slice(&self) -> &[SplitMetadata] {
         self.splits.as_slice()
     }
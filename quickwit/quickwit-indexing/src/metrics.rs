@@ -17,6 +17,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::time::Duration;
+
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{new_counter_vec, new_gauge_vec, IntCounterVec, IntGaugeVec};
 
@@ -26,6 +28,11 @@ pub struct IndexerMetrics {
     pub backpressure_micros: IntCounterVec<2>,
     pub available_concurrent_upload_permits: IntGaugeVec<1>,
     pub ongoing_merge_operations: IntGaugeVec<2>,
+    pub stage_duration_micros: IntCounterVec<2>,
+    pub field_tokenization_micros: IntCounterVec<2>,
+    pub field_num_tokens: IntCounterVec<2>,
+    pub field_num_bytes: IntCounterVec<2>,
+    pub slow_docs_total: IntCounterVec<1>,
 }
 
 impl Default for IndexerMetrics {
@@ -64,6 +71,40 @@ impl Default for IndexerMetrics {
                 "quickwit_indexing",
                 ["index", "source"],
             ),
+            stage_duration_micros: new_counter_vec(
+                "stage_duration_micros",
+                "Amount of time spent by the indexing pipelines in each stage (in micros) by index \
+                 and stage in [transform, doc_mapping, indexing, serialization]",
+                "quickwit_indexing",
+                ["index", "stage"],
+            ),
+            field_tokenization_micros: new_counter_vec(
+                "field_tokenization_micros",
+                "Estimated amount of time spent tokenizing the values of each field (in micros), \
+                 extrapolated from a sample of the indexed documents",
+                "quickwit_indexing",
+                ["index", "field"],
+            ),
+            field_num_tokens: new_counter_vec(
+                "field_num_tokens",
+                "Estimated number of tokens produced by each field, extrapolated from a sample of \
+                 the indexed documents",
+                "quickwit_indexing",
+                ["index", "field"],
+            ),
+            field_num_bytes: new_counter_vec(
+                "field_num_bytes",
+                "Estimated number of bytes of text tokenized for each field, extrapolated from a \
+                 sample of the indexed documents",
+                "quickwit_indexing",
+                ["index", "field"],
+            ),
+            slow_docs_total: new_counter_vec(
+                "slow_docs_total",
+                "Number of documents whose indexing took longer than 100ms",
+                "quickwit_indexing",
+                ["index"],
+            ),
         }
     }
 }
@@ -71,3 +112,11 @@ impl Default for IndexerMetrics {
 /// `INDEXER_METRICS` exposes indexing related metrics through a prometheus
 /// endpoint.
 pub static INDEXER_METRICS: Lazy<IndexerMetrics> = Lazy::new(IndexerMetrics::default);
+
+/// Records the time spent by the indexing pipelines of an index in a given stage.
+pub(crate) fn record_stage_duration(index_id: &str, stage: &str, duration: Duration) {
+    INDEXER_METRICS
+        .stage_duration_micros
+        .with_label_values([index_id, stage])
+        .inc_by(duration.as_micros() as u64);
+}
@@ -169,6 +169,7 @@ pub enum CommitTrigger {
     ForceCommit,
     MemoryLimit,
     NoMoreDocs,
+    NumBytesLimit,
     NumDocsLimit,
     Timeout,
 }
@@ -58,6 +58,12 @@ pub struct DetachMergePipeline {
     pub pipeline_id: MergePipelineId,
 }
 
+/// Gracefully drains the indexing pipelines running on the node: sources stop emitting new
+/// batches, the splits under construction are committed and published, and the pipelines exit
+/// successfully. Once draining, the indexing service no longer spawns new pipelines.
+#[derive(Clone, Copy, Debug)]
+pub struct DrainPipelines;
+
 #[derive(Debug)]
 pub struct ObservePipeline {
     pub pipeline_id: IndexingPipelineId,
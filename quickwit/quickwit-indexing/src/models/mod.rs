@@ -39,7 +39,7 @@ pub use indexed_split::{
 };
 pub use indexing_pipeline_id::IndexingPipelineId;
 pub use indexing_service_message::{
-    DetachIndexingPipeline, DetachMergePipeline, ObservePipeline, SpawnPipeline,
+    DetachIndexingPipeline, DetachMergePipeline, DrainPipelines, ObservePipeline, SpawnPipeline,
 };
 pub use indexing_statistics::IndexingStatistics;
 pub use merge_planner_message::NewSplits;
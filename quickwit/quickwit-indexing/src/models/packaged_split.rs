@@ -21,6 +21,7 @@ use std::collections::{BTreeSet, HashSet};
 use std::fmt;
 
 use quickwit_common::temp_dir::TempDirectory;
+use quickwit_doc_mapper::DocIdBloomFilter;
 use quickwit_metastore::checkpoint::IndexCheckpointDelta;
 use quickwit_proto::IndexUid;
 use tantivy::TrackedObject;
@@ -33,6 +34,7 @@ pub struct PackagedSplit {
     pub split_attrs: SplitAttrs,
     pub split_scratch_directory: TempDirectory,
     pub tags: BTreeSet<String>,
+    pub doc_id_bloom_filter: Option<DocIdBloomFilter>,
     pub split_files: Vec<std::path::PathBuf>,
     pub hotcache_bytes: Vec<u8>,
 }
@@ -49,6 +51,13 @@ impl fmt::Debug for PackagedSplit {
             .field("split_attrs", &self.split_attrs)
             .field("split_scratch_directory", &self.split_scratch_directory)
             .field("tags", &self.tags)
+            .field(
+                "doc_id_bloom_filter_num_bytes",
+                &self
+                    .doc_id_bloom_filter
+                    .as_ref()
+                    .map(|bloom_filter| bloom_filter.num_bytes()),
+            )
             .field("split_files", &self.split_files)
             .finish()
     }
@@ -21,7 +21,7 @@ use std::collections::BTreeSet;
 use std::fmt;
 use std::ops::{Range, RangeInclusive};
 
-use quickwit_metastore::SplitMetadata;
+use quickwit_metastore::{SplitMetadata, SPLIT_FORMAT_VERSION};
 use tantivy::DateTime;
 use time::OffsetDateTime;
 
@@ -102,5 +102,6 @@ pub fn create_split_metadata(
         footer_offsets,
         delete_opstamp: split_attrs.delete_opstamp,
         num_merge_ops: split_attrs.num_merge_ops,
+        format_version: SPLIT_FORMAT_VERSION,
     }
 }
@@ -0,0 +1,316 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Weak};
+
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use maxminddb::{geoip2, Reader};
+use quickwit_common::uri::Uri;
+use quickwit_config::ingest_processor_config::GeoIpProcessorConfig;
+use quickwit_config::GeoIpConfig;
+use quickwit_doc_mapper::JsonObject;
+use quickwit_storage::{load_file, StorageResolver};
+use serde_json::{json, Value as JsonValue};
+use tracing::{info, warn};
+
+use super::{get_field_value, IngestProcessor};
+
+/// The MaxMind-format databases of the node.
+#[derive(Default)]
+struct GeoIpDatabases {
+    city_reader_opt: Option<Reader<Vec<u8>>>,
+    asn_reader_opt: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIpDatabases {
+    async fn load(
+        geoip_config: &GeoIpConfig,
+        storage_resolver: &StorageResolver,
+    ) -> anyhow::Result<Self> {
+        let city_reader_opt = match &geoip_config.city_database_uri {
+            Some(database_uri) => Some(load_database(database_uri, storage_resolver).await?),
+            None => None,
+        };
+        let asn_reader_opt = match &geoip_config.asn_database_uri {
+            Some(database_uri) => Some(load_database(database_uri, storage_resolver).await?),
+            None => None,
+        };
+        Ok(Self {
+            city_reader_opt,
+            asn_reader_opt,
+        })
+    }
+
+    /// Returns the fields derived from `ip_addr`. The object is empty if the address is not found
+    /// in any database.
+    fn lookup(&self, ip_addr: IpAddr) -> JsonObject {
+        let mut geoip = JsonObject::new();
+
+        if let Some(city) = self
+            .city_reader_opt
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::City>(ip_addr).ok())
+        {
+            if let Some(continent_code) = city.continent.and_then(|continent| continent.code) {
+                geoip.insert("continent_code".to_string(), continent_code.into());
+            }
+            if let Some(country) = city.country {
+                if let Some(iso_code) = country.iso_code {
+                    geoip.insert("country_iso_code".to_string(), iso_code.into());
+                }
+                if let Some(name) = english_name(country.names) {
+                    geoip.insert("country_name".to_string(), name.into());
+                }
+            }
+            if let Some(subdivision) = city
+                .subdivisions
+                .and_then(|subdivisions| subdivisions.into_iter().next())
+            {
+                if let Some(iso_code) = subdivision.iso_code {
+                    geoip.insert("region_iso_code".to_string(), iso_code.into());
+                }
+                if let Some(name) = english_name(subdivision.names) {
+                    geoip.insert("region_name".to_string(), name.into());
+                }
+            }
+            if let Some(name) = city.city.and_then(|city| english_name(city.names)) {
+                geoip.insert("city_name".to_string(), name.into());
+            }
+            if let Some(postal_code) = city.postal.and_then(|postal| postal.code) {
+                geoip.insert("postal_code".to_string(), postal_code.into());
+            }
+            if let Some(location) = city.location {
+                if let (Some(lat), Some(lon)) = (location.latitude, location.longitude) {
+                    geoip.insert("location".to_string(), json!({"lat": lat, "lon": lon}));
+                }
+                if let Some(time_zone) = location.time_zone {
+                    geoip.insert("timezone".to_string(), time_zone.into());
+                }
+            }
+        }
+        if let Some(asn) = self
+            .asn_reader_opt
+            .as_ref()
+            .and_then(|reader| reader.lookup::<geoip2::Asn>(ip_addr).ok())
+        {
+            if let Some(number) = asn.autonomous_system_number {
+                geoip.insert("asn".to_string(), number.into());
+            }
+            if let Some(organization) = asn.autonomous_system_organization {
+                geoip.insert("as_org".to_string(), organization.into());
+            }
+        }
+        geoip
+    }
+}
+
+fn english_name<'a>(names_opt: Option<BTreeMap<&'a str, &'a str>>) -> Option<&'a str> {
+    names_opt.and_then(|names| names.get("en").copied())
+}
+
+async fn load_database(
+    database_uri: &Uri,
+    storage_resolver: &StorageResolver,
+) -> anyhow::Result<Reader<Vec<u8>>> {
+    let database_bytes = load_file(storage_resolver, database_uri)
+        .await
+        .with_context(|| format!("Failed to load GeoIP database `{database_uri}`."))?;
+    open_database(database_bytes.as_slice().to_vec())
+        .with_context(|| format!("Failed to open GeoIP database `{database_uri}`."))
+}
+
+fn open_database(database_bytes: Vec<u8>) -> anyhow::Result<Reader<Vec<u8>>> {
+    let reader = Reader::from_source(database_bytes)?;
+    Ok(reader)
+}
+
+/// Shared handle over the GeoIP databases of the node.
+///
+/// The databases are loaded once when the indexing service starts, then reloaded in the
+/// background every `refresh_interval_secs`. Processors always see a consistent snapshot of the
+/// databases.
+#[derive(Clone)]
+pub(crate) struct GeoIpResolver {
+    databases: Arc<ArcSwap<GeoIpDatabases>>,
+}
+
+impl GeoIpResolver {
+    pub async fn load(
+        geoip_config: GeoIpConfig,
+        storage_resolver: StorageResolver,
+    ) -> anyhow::Result<Self> {
+        let databases = GeoIpDatabases::load(&geoip_config, &storage_resolver).await?;
+        info!(
+            city_database_uri=?geoip_config.city_database_uri,
+            asn_database_uri=?geoip_config.asn_database_uri,
+            "Loaded GeoIP databases."
+        );
+        let databases = Arc::new(ArcSwap::from_pointee(databases));
+        tokio::spawn(refresh_databases_loop(
+            Arc::downgrade(&databases),
+            geoip_config,
+            storage_resolver,
+        ));
+        Ok(Self { databases })
+    }
+
+    #[cfg(test)]
+    fn for_test() -> Self {
+        Self {
+            databases: Arc::new(ArcSwap::from_pointee(GeoIpDatabases::default())),
+        }
+    }
+
+    fn lookup(&self, ip_addr: IpAddr) -> JsonObject {
+        self.databases.load().lookup(ip_addr)
+    }
+}
+
+/// Reloads the databases periodically until the resolver is dropped. On failure, the current
+/// databases are kept.
+async fn refresh_databases_loop(
+    databases_weak: Weak<ArcSwap<GeoIpDatabases>>,
+    geoip_config: GeoIpConfig,
+    storage_resolver: StorageResolver,
+) {
+    let mut refresh_interval = tokio::time::interval(geoip_config.refresh_interval());
+    // The first tick completes immediately.
+    refresh_interval.tick().await;
+    loop {
+        refresh_interval.tick().await;
+        let Some(databases) = databases_weak.upgrade() else {
+            return;
+        };
+        match GeoIpDatabases::load(&geoip_config, &storage_resolver).await {
+            Ok(new_databases) => {
+                databases.store(Arc::new(new_databases));
+                info!("Reloaded GeoIP databases.");
+            }
+            Err(error) => {
+                warn!(error=?error, "Failed to reload GeoIP databases. Keeping the current ones.");
+            }
+        }
+    }
+}
+
+/// Ingest processor resolving an IP address field to geographical and autonomous system fields.
+///
+/// See [`GeoIpProcessorConfig`] for the configuration.
+pub(crate) struct GeoIpProcessor {
+    source_field: String,
+    target_field: String,
+    resolver: GeoIpResolver,
+}
+
+impl GeoIpProcessor {
+    pub fn new(geoip_config: &GeoIpProcessorConfig, resolver: GeoIpResolver) -> Self {
+        Self {
+            source_field: geoip_config.source_field.clone(),
+            target_field: geoip_config.target_field.clone(),
+            resolver,
+        }
+    }
+}
+
+impl IngestProcessor for GeoIpProcessor {
+    fn process(&mut self, mut json_doc: JsonObject) -> anyhow::Result<JsonObject> {
+        let Some(ip_addr) = get_field_value(&json_doc, &self.source_field)
+            .and_then(JsonValue::as_str)
+            .and_then(|ip_addr_str| ip_addr_str.trim().parse::<IpAddr>().ok())
+        else {
+            return Ok(json_doc);
+        };
+        let geoip = self.resolver.lookup(ip_addr);
+
+        if !geoip.is_empty() {
+            json_doc.insert(self.target_field.clone(), JsonValue::Object(geoip));
+        }
+        Ok(json_doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::ingest_processor_config::IngestProcessorConfig;
+
+    use super::*;
+    use crate::processors::IngestProcessors;
+
+    fn json_doc(json_value: JsonValue) -> JsonObject {
+        let JsonValue::Object(json_doc) = json_value else {
+            panic!("Expected a JSON object.");
+        };
+        json_doc
+    }
+
+    #[test]
+    fn test_geoip_processor_leaves_unresolved_docs_untouched() {
+        let geoip_config = GeoIpProcessorConfig::for_test("client.ip");
+        let mut processor = GeoIpProcessor::new(&geoip_config, GeoIpResolver::for_test());
+
+        for doc in [
+            json!({"client": {"ip": "203.0.113.7"}}),
+            json!({"client": {"ip": "not-an-ip"}}),
+            json!({"client": {"ip": 42}}),
+            json!({"body": "no ip"}),
+        ] {
+            let processed_doc = processor.process(json_doc(doc.clone())).unwrap();
+            assert_eq!(JsonValue::Object(processed_doc), doc);
+        }
+    }
+
+    #[test]
+    fn test_get_field_value() {
+        let doc = json_doc(json!({"client": {"ip": "::1"}, "server.ip": "127.0.0.1"}));
+        assert_eq!(get_field_value(&doc, "client.ip").unwrap(), "::1");
+        assert_eq!(get_field_value(&doc, "server.ip").unwrap(), "127.0.0.1");
+        assert!(get_field_value(&doc, "client.port").is_none());
+        assert!(get_field_value(&doc, "source.ip").is_none());
+    }
+
+    #[test]
+    fn test_open_invalid_geoip_database() {
+        open_database(b"not a MaxMind database".to_vec()).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_geoip_processor_requires_geoip_databases() {
+        let processor_configs = [IngestProcessorConfig::GeoIp(
+            GeoIpProcessorConfig::for_test("client_ip"),
+        )];
+        let error =
+            IngestProcessors::load(&processor_configs, &StorageResolver::unconfigured(), None)
+                .await
+                .err()
+                .unwrap();
+        assert!(error.to_string().contains("indexer.geoip"));
+
+        let ingest_processors = IngestProcessors::load(
+            &processor_configs,
+            &StorageResolver::unconfigured(),
+            Some(&GeoIpResolver::for_test()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ingest_processors.build().unwrap().len(), 1);
+    }
+}
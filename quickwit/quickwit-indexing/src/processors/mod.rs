@@ -0,0 +1,148 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Ingest processors transform documents after the source transform (if any) and before they
+//! are handed over to the doc mapper. They are configured per index in the indexing settings.
+
+mod geoip_processor;
+mod user_agent_processor;
+mod wasm_processor;
+
+use anyhow::Context;
+use quickwit_config::ingest_processor_config::{GeoIpProcessorConfig, IngestProcessorConfig};
+use quickwit_doc_mapper::JsonObject;
+use quickwit_storage::{load_file, StorageResolver};
+use serde_json::Value as JsonValue;
+
+use self::geoip_processor::GeoIpProcessor;
+pub(crate) use self::geoip_processor::GeoIpResolver;
+use self::user_agent_processor::UserAgentProcessorFactory;
+use self::wasm_processor::WasmProcessorFactory;
+
+pub(crate) trait IngestProcessor: Send {
+    /// Transforms a document. Returning an error drops the document.
+    fn process(&mut self, json_doc: JsonObject) -> anyhow::Result<JsonObject>;
+}
+
+#[derive(Clone)]
+enum IngestProcessorFactory {
+    Wasm(WasmProcessorFactory),
+    GeoIp(GeoIpProcessorConfig, GeoIpResolver),
+    UserAgent(UserAgentProcessorFactory),
+}
+
+impl IngestProcessorFactory {
+    fn build(&self) -> anyhow::Result<Box<dyn IngestProcessor>> {
+        match self {
+            IngestProcessorFactory::Wasm(wasm_processor_factory) => {
+                Ok(Box::new(wasm_processor_factory.build()?))
+            }
+            IngestProcessorFactory::GeoIp(geoip_config, geoip_resolver) => Ok(Box::new(
+                GeoIpProcessor::new(geoip_config, geoip_resolver.clone()),
+            )),
+            IngestProcessorFactory::UserAgent(user_agent_processor_factory) => {
+                Ok(Box::new(user_agent_processor_factory.build()))
+            }
+        }
+    }
+}
+
+/// Holds the resources (compiled WASM modules, GeoIP databases, user-agent parsers, ...) required
+/// to instantiate the ingest processors of an index.
+///
+/// Loading those resources is expensive and requires access to the storage, so it is done once,
+/// when the indexing pipeline is spawned. The processors themselves are instantiated every time
+/// the doc processor is (re)spawned.
+#[derive(Clone, Default)]
+pub struct IngestProcessors {
+    processor_factories: Vec<IngestProcessorFactory>,
+}
+
+impl IngestProcessors {
+    pub async fn load(
+        processor_configs: &[IngestProcessorConfig],
+        storage_resolver: &StorageResolver,
+        geoip_resolver_opt: Option<&GeoIpResolver>,
+    ) -> anyhow::Result<Self> {
+        let mut processor_factories = Vec::with_capacity(processor_configs.len());
+        for processor_config in processor_configs {
+            let processor_factory = match processor_config {
+                IngestProcessorConfig::Wasm(wasm_config) => {
+                    let module_bytes = load_file(storage_resolver, &wasm_config.module_uri)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Failed to load WASM module `{}`.",
+                                wasm_config.module_uri
+                            )
+                        })?;
+                    let wasm_processor_factory =
+                        WasmProcessorFactory::try_new(wasm_config, module_bytes.as_slice())?;
+                    IngestProcessorFactory::Wasm(wasm_processor_factory)
+                }
+                IngestProcessorConfig::GeoIp(geoip_config) => {
+                    let geoip_resolver = geoip_resolver_opt.context(
+                        "GeoIP processors require GeoIP databases to be configured on the indexer \
+                         node (`indexer.geoip`).",
+                    )?;
+                    IngestProcessorFactory::GeoIp(geoip_config.clone(), geoip_resolver.clone())
+                }
+                IngestProcessorConfig::UserAgent(user_agent_config) => {
+                    let regexes_bytes = load_file(storage_resolver, &user_agent_config.regexes_uri)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Failed to load user-agent regular expressions `{}`.",
+                                user_agent_config.regexes_uri
+                            )
+                        })?;
+                    let user_agent_processor_factory = UserAgentProcessorFactory::try_new(
+                        user_agent_config,
+                        regexes_bytes.as_slice(),
+                    )?;
+                    IngestProcessorFactory::UserAgent(user_agent_processor_factory)
+                }
+            };
+            processor_factories.push(processor_factory);
+        }
+        Ok(Self {
+            processor_factories,
+        })
+    }
+
+    pub(crate) fn build(&self) -> anyhow::Result<Vec<Box<dyn IngestProcessor>>> {
+        self.processor_factories
+            .iter()
+            .map(|processor_factory| processor_factory.build())
+            .collect()
+    }
+}
+
+/// Resolves a dotted field path (e.g. `client.ip`) in a document.
+fn get_field_value<'a>(json_doc: &'a JsonObject, field_path: &str) -> Option<&'a JsonValue> {
+    if let Some(value) = json_doc.get(field_path) {
+        return Some(value);
+    }
+    let mut field_path_parts = field_path.split('.');
+    let mut value = json_doc.get(field_path_parts.next()?)?;
+    for field_path_part in field_path_parts {
+        value = value.get(field_path_part)?;
+    }
+    Some(value)
+}
@@ -0,0 +1,239 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use quickwit_config::ingest_processor_config::UserAgentProcessorConfig;
+use quickwit_doc_mapper::JsonObject;
+use serde_json::Value as JsonValue;
+use uaparser::{Parser, UserAgentParser};
+
+use super::{get_field_value, IngestProcessor};
+
+/// Family reported by ua-parser when no regular expression matches.
+const UNKNOWN_FAMILY: &str = "Other";
+
+#[derive(Clone)]
+pub(crate) struct UserAgentProcessorFactory {
+    parser: Arc<UserAgentParser>,
+    source_field: String,
+    target_field: String,
+}
+
+impl UserAgentProcessorFactory {
+    pub fn try_new(
+        user_agent_config: &UserAgentProcessorConfig,
+        regexes_bytes: &[u8],
+    ) -> anyhow::Result<Self> {
+        let parser = UserAgentParser::from_bytes(regexes_bytes).map_err(|error| {
+            anyhow::anyhow!("Failed to parse user-agent regular expressions: {error:?}.")
+        })?;
+        Ok(Self {
+            parser: Arc::new(parser),
+            source_field: user_agent_config.source_field.clone(),
+            target_field: user_agent_config.target_field.clone(),
+        })
+    }
+
+    pub fn build(&self) -> UserAgentProcessor {
+        UserAgentProcessor {
+            parser: self.parser.clone(),
+            source_field: self.source_field.clone(),
+            target_field: self.target_field.clone(),
+        }
+    }
+}
+
+/// Ingest processor expanding a user-agent string field into browser, operating system, and
+/// device fields.
+///
+/// See [`UserAgentProcessorConfig`] for the configuration.
+pub(crate) struct UserAgentProcessor {
+    parser: Arc<UserAgentParser>,
+    source_field: String,
+    target_field: String,
+}
+
+impl UserAgentProcessor {
+    /// Returns the fields derived from `user_agent`. Components ua-parser does not recognize are
+    /// omitted, so the object is empty if the user agent is not recognized at all.
+    fn parse(&self, user_agent: &str) -> JsonObject {
+        let client = self.parser.parse(user_agent);
+        let mut parsed_user_agent = JsonObject::new();
+
+        let mut browser = JsonObject::new();
+        insert_family(&mut browser, &client.user_agent.family);
+        insert_version(
+            &mut browser,
+            [
+                client.user_agent.major.as_deref(),
+                client.user_agent.minor.as_deref(),
+                client.user_agent.patch.as_deref(),
+            ],
+        );
+        insert_object(&mut parsed_user_agent, "browser", browser);
+
+        let mut os = JsonObject::new();
+        insert_family(&mut os, &client.os.family);
+        insert_version(
+            &mut os,
+            [
+                client.os.major.as_deref(),
+                client.os.minor.as_deref(),
+                client.os.patch.as_deref(),
+            ],
+        );
+        insert_object(&mut parsed_user_agent, "os", os);
+
+        let mut device = JsonObject::new();
+        insert_family(&mut device, &client.device.family);
+        if let Some(brand) = client.device.brand.as_deref() {
+            device.insert("brand".to_string(), brand.into());
+        }
+        if let Some(model) = client.device.model.as_deref() {
+            device.insert("model".to_string(), model.into());
+        }
+        insert_object(&mut parsed_user_agent, "device", device);
+
+        parsed_user_agent
+    }
+}
+
+impl IngestProcessor for UserAgentProcessor {
+    fn process(&mut self, mut json_doc: JsonObject) -> anyhow::Result<JsonObject> {
+        let Some(user_agent) = get_field_value(&json_doc, &self.source_field)
+            .and_then(JsonValue::as_str)
+            .map(str::trim)
+            .filter(|user_agent| !user_agent.is_empty())
+        else {
+            return Ok(json_doc);
+        };
+        let parsed_user_agent = self.parse(user_agent);
+
+        if !parsed_user_agent.is_empty() {
+            json_doc.insert(
+                self.target_field.clone(),
+                JsonValue::Object(parsed_user_agent),
+            );
+        }
+        Ok(json_doc)
+    }
+}
+
+fn insert_family(object: &mut JsonObject, family: &str) {
+    if family != UNKNOWN_FAMILY {
+        object.insert("name".to_string(), family.into());
+    }
+}
+
+/// Inserts the dotted version made of the leading components that are present, e.g. `115.0`.
+fn insert_version(object: &mut JsonObject, version_components: [Option<&str>; 3]) {
+    let version = version_components
+        .into_iter()
+        .map_while(|version_component_opt| version_component_opt)
+        .filter(|version_component| !version_component.is_empty())
+        .collect::<Vec<&str>>()
+        .join(".");
+    if !version.is_empty() {
+        object.insert("version".to_string(), version.into());
+    }
+}
+
+fn insert_object(parent: &mut JsonObject, key: &str, object: JsonObject) {
+    if !object.is_empty() {
+        parent.insert(key.to_string(), JsonValue::Object(object));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const REGEXES_YAML: &str = r#"
+user_agent_parsers:
+  - regex: '(Firefox)/(\d+)\.(\d+)'
+os_parsers:
+  - regex: '(Android) (\d+)'
+device_parsers:
+  - regex: '; (Pixel \d+)'
+    device_replacement: '$1'
+    brand_replacement: 'Google'
+    model_replacement: '$1'
+"#;
+
+    fn json_doc(json_value: JsonValue) -> JsonObject {
+        let JsonValue::Object(json_doc) = json_value else {
+            panic!("Expected a JSON object.");
+        };
+        json_doc
+    }
+
+    fn build_processor() -> UserAgentProcessor {
+        let user_agent_config =
+            UserAgentProcessorConfig::for_test("http.user_agent", "file:///regexes.yaml");
+        UserAgentProcessorFactory::try_new(&user_agent_config, REGEXES_YAML.as_bytes())
+            .unwrap()
+            .build()
+    }
+
+    #[test]
+    fn test_user_agent_processor() {
+        let mut processor = build_processor();
+        let doc = json_doc(json!({
+            "http": {
+                "user_agent": "Mozilla/5.0 (Android 14; Mobile; Pixel 8) Gecko/115.0 Firefox/115.0"
+            }
+        }));
+        let processed_doc = processor.process(doc).unwrap();
+        assert_eq!(
+            processed_doc["user_agent"],
+            json!({
+                "browser": {"name": "Firefox", "version": "115.0"},
+                "os": {"name": "Android", "version": "14"},
+                "device": {"name": "Pixel 8", "brand": "Google", "model": "Pixel 8"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_user_agent_processor_leaves_unparsed_docs_untouched() {
+        let mut processor = build_processor();
+
+        for doc in [
+            json!({"http": {"user_agent": "curl/8.1.2"}}),
+            json!({"http": {"user_agent": ""}}),
+            json!({"http": {"user_agent": 42}}),
+            json!({"body": "no user agent"}),
+        ] {
+            let processed_doc = processor.process(json_doc(doc.clone())).unwrap();
+            assert_eq!(JsonValue::Object(processed_doc), doc);
+        }
+    }
+
+    #[test]
+    fn test_user_agent_processor_factory_invalid_regexes() {
+        let user_agent_config =
+            UserAgentProcessorConfig::for_test("user_agent", "file:///regexes.yaml");
+        assert!(
+            UserAgentProcessorFactory::try_new(&user_agent_config, b"not: [valid regexes").is_err()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use quickwit_config::ingest_processor_config::WasmProcessorConfig;
+use quickwit_doc_mapper::JsonObject;
+use wasmi::{Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use super::IngestProcessor;
+
+const MEMORY_EXPORT_NAME: &str = "memory";
+const ALLOC_EXPORT_NAME: &str = "alloc";
+
+/// Holds a compiled WASM module, ready to be instantiated.
+#[derive(Clone)]
+pub(crate) struct WasmProcessorFactory {
+    engine: Engine,
+    module: Arc<Module>,
+    entrypoint: String,
+    max_fuel_per_doc: u64,
+    max_memory_num_bytes: usize,
+}
+
+impl WasmProcessorFactory {
+    pub fn try_new(wasm_config: &WasmProcessorConfig, module_bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut engine_config = wasmi::Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, module_bytes).with_context(|| {
+            format!(
+                "Failed to compile WASM module `{}`.",
+                wasm_config.module_uri
+            )
+        })?;
+        Ok(Self {
+            engine,
+            module: Arc::new(module),
+            entrypoint: wasm_config.entrypoint.clone(),
+            max_fuel_per_doc: wasm_config.max_fuel_per_doc,
+            max_memory_num_bytes: wasm_config.max_memory.get_bytes() as usize,
+        })
+    }
+
+    pub fn build(&self) -> anyhow::Result<WasmProcessor> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_num_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        // The linker does not define any import: the module is not allowed to interact with the
+        // host, beyond the input and output buffers.
+        let linker: Linker<StoreLimits> = Linker::new(&self.engine);
+        // Running the start function consumes fuel too.
+        store
+            .add_fuel(self.max_fuel_per_doc)
+            .map_err(wasmi::Error::from)?;
+        let instance = linker
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)?;
+        let memory = instance
+            .get_memory(&store, MEMORY_EXPORT_NAME)
+            .with_context(|| format!("WASM module must export a `{MEMORY_EXPORT_NAME}` memory."))?;
+        let alloc_func = instance
+            .get_typed_func::<i32, i32>(&store, ALLOC_EXPORT_NAME)
+            .with_context(|| {
+                format!("WASM module must export an `{ALLOC_EXPORT_NAME}(i32) -> i32` function.")
+            })?;
+        let process_func = instance
+            .get_typed_func::<(i32, i32), i64>(&store, &self.entrypoint)
+            .with_context(|| {
+                format!(
+                    "WASM module must export an `{}(i32, i32) -> i64` function.",
+                    self.entrypoint
+                )
+            })?;
+        Ok(WasmProcessor {
+            store,
+            memory,
+            alloc_func,
+            process_func,
+            max_fuel_per_doc: self.max_fuel_per_doc,
+        })
+    }
+}
+
+/// Ingest processor running a sandboxed WASM module over documents.
+///
+/// See [`WasmProcessorConfig`] for the contract the module must fulfill.
+pub(crate) struct WasmProcessor {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc_func: TypedFunc<i32, i32>,
+    process_func: TypedFunc<(i32, i32), i64>,
+    max_fuel_per_doc: u64,
+}
+
+impl WasmProcessor {
+    /// Refills the fuel tank so that each document gets exactly `max_fuel_per_doc` fuel.
+    fn refill_fuel(&mut self) -> anyhow::Result<()> {
+        let remaining_fuel = self.store.consume_fuel(0).map_err(wasmi::Error::from)?;
+        self.store
+            .add_fuel(self.max_fuel_per_doc.saturating_sub(remaining_fuel))
+            .map_err(wasmi::Error::from)?;
+        Ok(())
+    }
+}
+
+impl IngestProcessor for WasmProcessor {
+    fn process(&mut self, json_doc: JsonObject) -> anyhow::Result<JsonObject> {
+        self.refill_fuel()?;
+        let input = serde_json::to_vec(&json_doc)?;
+        let input_len = i32::try_from(input.len()).context("Document is too large.")?;
+        let input_ptr = self
+            .alloc_func
+            .call(&mut self.store, input_len)
+            .map_err(wasmi::Error::from)?;
+        self.memory
+            .write(&mut self.store, input_ptr as u32 as usize, &input)
+            .map_err(wasmi::Error::from)?;
+        let packed_output = self
+            .process_func
+            .call(&mut self.store, (input_ptr, input_len))
+            .map_err(wasmi::Error::from)?;
+        if packed_output < 0 {
+            bail!("WASM processor rejected the document (error code `{packed_output}`).");
+        }
+        let output_ptr = (packed_output >> 32) as usize;
+        let output_len = (packed_output & 0xFFFF_FFFF) as usize;
+        let mut output = vec![0u8; output_len];
+        self.memory
+            .read(&self.store, output_ptr, &mut output)
+            .map_err(wasmi::Error::from)?;
+        let transformed_json_doc: JsonObject = serde_json::from_slice(&output)
+            .context("WASM processor did not return a valid JSON object.")?;
+        Ok(transformed_json_doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // Echoes the input document back, untouched. The input buffer is always allocated at
+    // offset 1024.
+    const ECHO_MODULE_WAT: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32)
+                i32.const 1024)
+            (func (export "process") (param $ptr i32) (param $len i32) (result i64)
+                local.get $ptr
+                i64.extend_i32_u
+                i64.const 32
+                i64.shl
+                local.get $len
+                i64.extend_i32_u
+                i64.or))
+    "#;
+
+    const INFINITE_LOOP_MODULE_WAT: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32)
+                i32.const 1024)
+            (func (export "process") (param i32) (param i32) (result i64)
+                (loop $forever
+                    br $forever)
+                i64.const 0))
+    "#;
+
+    fn build_processor(module_wat: &str) -> WasmProcessor {
+        let module_bytes = wat::parse_str(module_wat).unwrap();
+        let mut wasm_config = WasmProcessorConfig::for_test("file:///plugin.wasm");
+        wasm_config.max_fuel_per_doc = 10_000;
+        WasmProcessorFactory::try_new(&wasm_config, &module_bytes)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wasm_processor_echo() {
+        let mut processor = build_processor(ECHO_MODULE_WAT);
+        for i in 0..3 {
+            let json_doc = json!({"body": "hello", "count": i});
+            let processed_doc = processor
+                .process(json_doc.as_object().unwrap().clone())
+                .unwrap();
+            assert_eq!(processed_doc, *json_doc.as_object().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_wasm_processor_runs_out_of_fuel() {
+        let mut processor = build_processor(INFINITE_LOOP_MODULE_WAT);
+        let json_doc = json!({"body": "hello"});
+        // The fuel is refilled for each document, so the processor fails consistently rather
+        // than once.
+        for _ in 0..2 {
+            processor
+                .process(json_doc.as_object().unwrap().clone())
+                .unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_wasm_processor_missing_exports() {
+        let module_bytes = wat::parse_str("(module)").unwrap();
+        let wasm_config = WasmProcessorConfig::for_test("file:///plugin.wasm");
+        let factory = WasmProcessorFactory::try_new(&wasm_config, &module_bytes).unwrap();
+        assert!(factory.build().is_err());
+    }
+}
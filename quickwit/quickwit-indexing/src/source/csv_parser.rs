@@ -0,0 +1,269 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::Context;
+use bytes::Bytes;
+use quickwit_config::{CsvColumnType, CsvParams};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+/// Converts the records of a CSV input, fed line by line, into JSON documents.
+///
+/// Records whose quoted fields span several lines are buffered until their last line is fed.
+pub(crate) struct CsvParser {
+    params: CsvParams,
+    columns: Vec<String>,
+    expects_header: bool,
+    pending_record: String,
+}
+
+impl CsvParser {
+    pub fn new(params: CsvParams) -> Self {
+        Self {
+            columns: params.columns.clone(),
+            expects_header: params.has_header,
+            params,
+            pending_record: String::new(),
+        }
+    }
+
+    /// Returns whether the header record remains to be read.
+    pub fn expects_header(&self) -> bool {
+        self.expects_header
+    }
+
+    /// Returns whether the lines fed so far end in the middle of a record.
+    pub fn has_pending_record(&self) -> bool {
+        !self.pending_record.is_empty()
+    }
+
+    /// Feeds a line and returns the document built from the record it completes, if any.
+    ///
+    /// Records that cannot be parsed are returned as is, so that the doc processor counts them
+    /// as invalid documents.
+    pub fn push_line(&mut self, line: &str) -> anyhow::Result<Option<Bytes>> {
+        self.pending_record.push_str(line);
+
+        // An odd number of quote characters means a quoted field is still open. Escaped quotes
+        // come in pairs and do not alter the parity.
+        let quote = self.params.quote;
+        if self.pending_record.matches(quote).count() % 2 == 1 {
+            return Ok(None);
+        }
+        self.parse_pending_record()
+    }
+
+    /// Returns the document built from the record left pending at the end of the input, if any.
+    pub fn finish(&mut self) -> anyhow::Result<Option<Bytes>> {
+        if !self.has_pending_record() {
+            return Ok(None);
+        }
+        self.parse_pending_record()
+    }
+
+    fn parse_pending_record(&mut self) -> anyhow::Result<Option<Bytes>> {
+        let record_str = std::mem::take(&mut self.pending_record);
+
+        if record_str.trim_end_matches(['\r', '\n']).is_empty() {
+            return Ok(None);
+        }
+        let record_res = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .delimiter(self.params.delimiter as u8)
+            .quote(self.params.quote as u8)
+            .from_reader(record_str.as_bytes())
+            .into_records()
+            .next()
+            .context("CSV record should not be empty.")?;
+
+        if self.expects_header {
+            self.expects_header = false;
+            let header = record_res.context("Failed to parse CSV header record.")?;
+            // Column names supplied by the user take precedence over the header.
+            if self.columns.is_empty() {
+                self.columns = header.iter().map(|column| column.to_string()).collect();
+            }
+            return Ok(None);
+        }
+        let Ok(record) = record_res else {
+            return Ok(Some(Bytes::from(record_str)));
+        };
+        if record.len() > self.columns.len() {
+            return Ok(Some(Bytes::from(record_str)));
+        }
+        let mut doc = JsonMap::with_capacity(record.len());
+
+        for (column, value_str) in self.columns.iter().zip(record.iter()) {
+            let column_type_opt = self.params.column_types.get(column).copied();
+            if let Some(value) = convert_value(value_str, column_type_opt, self.params.infer_types)
+            {
+                doc.insert(column.clone(), value);
+            }
+        }
+        let doc_json = serde_json::to_vec(&doc).expect("JSON object should be serializable.");
+        Ok(Some(Bytes::from(doc_json)))
+    }
+}
+
+/// Converts a CSV value into a JSON value. Empty values are omitted, and values that cannot be
+/// parsed as their explicit type are kept as strings.
+fn convert_value(
+    value_str: &str,
+    column_type_opt: Option<CsvColumnType>,
+    infer_types: bool,
+) -> Option<JsonValue> {
+    if value_str.is_empty() {
+        return None;
+    }
+    let value_opt = match column_type_opt {
+        Some(CsvColumnType::Text) => None,
+        Some(CsvColumnType::I64) => value_str.parse::<i64>().ok().map(JsonValue::from),
+        Some(CsvColumnType::U64) => value_str.parse::<u64>().ok().map(JsonValue::from),
+        Some(CsvColumnType::F64) => parse_f64(value_str),
+        Some(CsvColumnType::Bool) => parse_bool(value_str),
+        None if infer_types => value_str
+            .parse::<i64>()
+            .ok()
+            .map(JsonValue::from)
+            .or_else(|| value_str.parse::<u64>().ok().map(JsonValue::from))
+            .or_else(|| parse_f64(value_str))
+            .or_else(|| parse_bool(value_str)),
+        None => None,
+    };
+    Some(value_opt.unwrap_or_else(|| JsonValue::String(value_str.to_string())))
+}
+
+fn parse_f64(value_str: &str) -> Option<JsonValue> {
+    value_str
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(JsonValue::from)
+}
+
+fn parse_bool(value_str: &str) -> Option<JsonValue> {
+    match value_str {
+        "true" | "TRUE" | "True" => Some(JsonValue::Bool(true)),
+        "false" | "FALSE" | "False" => Some(JsonValue::Bool(false)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn parse_lines(params: CsvParams, lines: &[&str]) -> Vec<Bytes> {
+        let mut parser = CsvParser::new(params);
+        let mut docs: Vec<Bytes> = lines
+            .iter()
+            .filter_map(|line| parser.push_line(line).unwrap())
+            .collect();
+        docs.extend(parser.finish().unwrap());
+        docs
+    }
+
+    fn to_json(doc: &Bytes) -> JsonValue {
+        serde_json::from_slice(doc).unwrap()
+    }
+
+    #[test]
+    fn test_csv_parser_with_header() {
+        let docs = parse_lines(
+            CsvParams::default(),
+            &[
+                "name,age,ratio,active,zip\n",
+                "alice,32,0.5,true,01234\n",
+                "\"bob, jr\",,1e3,no,\n",
+                "\n",
+                "carol,18446744073709551615\r\n",
+            ],
+        );
+        assert_eq!(docs.len(), 3);
+        assert_eq!(
+            to_json(&docs[0]),
+            json!({"name": "alice", "age": 32, "ratio": 0.5, "active": true, "zip": 1234})
+        );
+        assert_eq!(
+            to_json(&docs[1]),
+            json!({"name": "bob, jr", "ratio": 1000.0, "active": "no"})
+        );
+        assert_eq!(
+            to_json(&docs[2]),
+            json!({"name": "carol", "age": 18446744073709551615u64})
+        );
+    }
+
+    #[test]
+    fn test_csv_parser_with_explicit_schema() {
+        let params = CsvParams {
+            delimiter: '\t',
+            has_header: false,
+            columns: vec!["zip".to_string(), "count".to_string(), "note".to_string()],
+            column_types: BTreeMap::from_iter([
+                ("zip".to_string(), CsvColumnType::Text),
+                ("count".to_string(), CsvColumnType::U64),
+            ]),
+            infer_types: false,
+            ..Default::default()
+        };
+        let docs = parse_lines(params, &["01234\t12\t3\n", "56789\t-1\tfoo\n"]);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(
+            to_json(&docs[0]),
+            json!({"zip": "01234", "count": 12, "note": "3"})
+        );
+        assert_eq!(
+            to_json(&docs[1]),
+            json!({"zip": "56789", "count": "-1", "note": "foo"})
+        );
+    }
+
+    #[test]
+    fn test_csv_parser_multiline_and_invalid_records() {
+        let mut parser = CsvParser::new(CsvParams::default());
+        assert!(parser.expects_header());
+        assert!(parser.push_line("id,body\n").unwrap().is_none());
+        assert!(!parser.expects_header());
+
+        assert!(parser.push_line("1,\"first line\n").unwrap().is_none());
+        assert!(parser.has_pending_record());
+        let doc = parser
+            .push_line("second \"\"line\"\"\"\n")
+            .unwrap()
+            .unwrap();
+        assert!(!parser.has_pending_record());
+        assert_eq!(
+            to_json(&doc),
+            json!({"id": 1, "body": "first line\nsecond \"line\""})
+        );
+        // Records with more fields than columns are passed through as is.
+        let doc = parser.push_line("2,foo,bar\n").unwrap().unwrap();
+        assert_eq!(doc, Bytes::from_static(b"2,foo,bar\n"));
+
+        // A record left open at the end of the input is flushed.
+        assert!(parser.push_line("3,\"unterminated\n").unwrap().is_none());
+        let doc = parser.finish().unwrap().unwrap();
+        assert_eq!(to_json(&doc), json!({"id": 3, "body": "unterminated\n"}));
+        assert!(parser.finish().unwrap().is_none());
+    }
+}
@@ -22,11 +22,11 @@ use std::io::SeekFrom;
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
 use bytes::Bytes;
 use quickwit_actors::{ActorExitStatus, Mailbox};
-use quickwit_config::FileSourceParams;
+use quickwit_config::{FileSourceParams, SourceInputFormat};
 use quickwit_metastore::checkpoint::{PartitionId, Position, SourceCheckpoint};
 use serde::Serialize;
 use tokio::fs::File;
@@ -35,6 +35,7 @@ use tracing::info;
 
 use crate::actors::DocProcessor;
 use crate::models::RawDocBatch;
+use crate::source::csv_parser::CsvParser;
 use crate::source::{Source, SourceContext, SourceExecutionContext, TypedSourceFactory};
 
 /// Number of bytes after which a new batch is cut.
@@ -52,6 +53,7 @@ pub struct FileSource {
     params: FileSourceParams,
     counters: FileSourceCounters,
     reader: BufReader<Box<dyn AsyncRead + Send + Sync + Unpin>>,
+    csv_parser_opt: Option<CsvParser>,
 }
 
 impl fmt::Debug for FileSource {
@@ -71,7 +73,9 @@ impl Source for FileSource {
         let limit_num_bytes = self.counters.previous_offset + BATCH_NUM_BYTES_LIMIT;
         let mut reached_eof = false;
         let mut doc_batch = RawDocBatch::default();
-        while self.counters.current_offset < limit_num_bytes {
+        // A batch is not cut in the middle of a CSV record, so that the checkpoint never points
+        // inside a record.
+        while self.counters.current_offset < limit_num_bytes || self.has_pending_csv_record() {
             let mut doc_line = String::new();
             // guard the zone in case of slow read, such as reading from someone
             // typing to stdin
@@ -80,14 +84,21 @@ impl Source for FileSource {
                 .await
                 .map_err(anyhow::Error::from)?;
             if num_bytes == 0 {
+                if let Some(csv_parser) = &mut self.csv_parser_opt {
+                    doc_batch.docs.extend(csv_parser.finish()?);
+                }
                 reached_eof = true;
                 break;
             }
-            doc_batch.docs.push(Bytes::from(doc_line));
+            if let Some(csv_parser) = &mut self.csv_parser_opt {
+                doc_batch.docs.extend(csv_parser.push_line(&doc_line)?);
+            } else {
+                doc_batch.docs.push(Bytes::from(doc_line));
+            }
             self.counters.current_offset += num_bytes as u64;
             self.counters.num_lines_processed += 1;
         }
-        if !doc_batch.docs.is_empty() {
+        if self.counters.current_offset > self.counters.previous_offset {
             if let Some(filepath) = &self.params.filepath {
                 let filepath_str = filepath
                     .to_str()
@@ -123,6 +134,15 @@ impl Source for FileSource {
     }
 }
 
+impl FileSource {
+    fn has_pending_csv_record(&self) -> bool {
+        self.csv_parser_opt
+            .as_ref()
+            .map(CsvParser::has_pending_record)
+            .unwrap_or(false)
+    }
+}
+
 pub struct FileSourceFactory;
 
 #[async_trait]
@@ -137,6 +157,11 @@ impl TypedSourceFactory for FileSourceFactory {
         checkpoint: SourceCheckpoint,
     ) -> anyhow::Result<FileSource> {
         let mut offset = 0;
+        let mut csv_parser_opt = if ctx.source_config.input_format == SourceInputFormat::Csv {
+            Some(CsvParser::new(params.csv.clone()))
+        } else {
+            None
+        };
         let reader: Box<dyn AsyncRead + Send + Sync + Unpin> =
             if let Some(filepath) = &params.filepath {
                 let mut file = File::open(&filepath).await.with_context(|| {
@@ -147,6 +172,11 @@ impl TypedSourceFactory for FileSourceFactory {
                     checkpoint.position_for_partition(&partition_id).cloned()
                 {
                     offset = offset_str.parse::<u64>()?;
+
+                    // The header record precedes the checkpoint and must be read again.
+                    if let Some(csv_parser) = csv_parser_opt.as_mut().filter(|_| offset > 0) {
+                        read_csv_header(&mut file, csv_parser).await?;
+                    }
                     file.seek(SeekFrom::Start(offset)).await?;
                 }
                 Box::new(file)
@@ -163,11 +193,27 @@ impl TypedSourceFactory for FileSourceFactory {
             },
             reader: BufReader::new(reader),
             params,
+            csv_parser_opt,
         };
         Ok(file_source)
     }
 }
 
+async fn read_csv_header(file: &mut File, csv_parser: &mut CsvParser) -> anyhow::Result<()> {
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+
+    while csv_parser.expects_header() {
+        line.clear();
+        let num_bytes = reader.read_line(&mut line).await?;
+        if num_bytes == 0 {
+            bail!("Source file should start with a CSV header record.");
+        }
+        csv_parser.push_line(&line)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -203,6 +249,7 @@ mod tests {
                     source_params: SourceParams::File(params.clone()),
                     transform_config: None,
                     input_format: SourceInputFormat::Json,
+                    labels: Default::default(),
                 },
             ),
             params,
@@ -269,6 +316,7 @@ mod tests {
                     source_params: SourceParams::File(params.clone()),
                     transform_config: None,
                     input_format: SourceInputFormat::Json,
+                    labels: Default::default(),
                 },
             ),
             params,
@@ -359,6 +407,7 @@ mod tests {
                     source_params: SourceParams::File(params.clone()),
                     transform_config: None,
                     input_format: SourceInputFormat::Json,
+                    labels: Default::default(),
                 },
             ),
             params,
@@ -385,4 +434,71 @@ mod tests {
         let indexer_messages: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
         assert!(&indexer_messages[0].docs[0].starts_with(b"2\n"));
     }
+
+    #[tokio::test]
+    async fn test_file_source_csv_resume_from_checkpoint() {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        use tempfile::NamedTempFile;
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(b"id,body\n0,\"hello\nworld\"\n1,foo\n2,bar\n")
+            .unwrap();
+        temp_file.flush().unwrap();
+        let temp_file_path = temp_file.path().canonicalize().unwrap();
+        let params = FileSourceParams::file(&temp_file_path);
+        let mut checkpoint = SourceCheckpoint::default();
+        let partition_id = PartitionId::from(temp_file_path.to_string_lossy().to_string());
+        let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id,
+            Position::from(0u64),
+            Position::from(30u64),
+        )
+        .unwrap();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceExecutionContext::for_test(
+                metastore,
+                IndexUid::new("test-index"),
+                PathBuf::from("./queues"),
+                SourceConfig {
+                    source_id: "test-file-source".to_string(),
+                    desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+                    max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+                    enabled: true,
+                    source_params: SourceParams::File(params.clone()),
+                    transform_config: None,
+                    input_format: SourceInputFormat::Csv,
+                    labels: Default::default(),
+                },
+            ),
+            params,
+            checkpoint,
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 36u64,
+                "current_offset": 36u64,
+                "num_lines_processed": 1u64
+            })
+        );
+        let indexer_messages: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(indexer_messages.len(), 1);
+        assert_eq!(indexer_messages[0].docs.len(), 1);
+        let doc: serde_json::Value = serde_json::from_slice(&indexer_messages[0].docs[0]).unwrap();
+        assert_eq!(doc, serde_json::json!({"id": 2, "body": "bar"}));
+    }
 }
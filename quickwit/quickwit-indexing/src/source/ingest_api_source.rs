@@ -25,7 +25,7 @@ use async_trait::async_trait;
 use quickwit_actors::{ActorContext, ActorExitStatus, Mailbox};
 use quickwit_ingest::{
     get_ingest_api_service, CreateQueueIfNotExistsRequest, DocCommand, FetchRequest, FetchResponse,
-    GetPartitionId, IngestApiService, SuggestTruncateRequest,
+    GetPartitionId, IngestApiService, NotifyIndexedRequest, SuggestTruncateRequest,
 };
 use quickwit_metastore::checkpoint::{PartitionId, Position, SourceCheckpoint};
 use serde::Serialize;
@@ -124,10 +124,13 @@ impl Source for IngestApiSource {
             index_id: self.ctx.index_uid.index_id().to_string(),
             start_after: self.counters.current_offset,
             num_bytes_limit: None,
+            start_timestamp: None,
+            keep_most_recent: false,
         };
         let FetchResponse {
             first_position: first_position_opt,
             doc_batch: doc_batch_opt,
+            ..
         } = ctx
             .ask_for_res(&self.ingest_api_service, fetch_req)
             .await
@@ -190,6 +193,25 @@ impl Source for IngestApiSource {
         Ok(())
     }
 
+    async fn notify_indexed(
+        &self,
+        checkpoint: SourceCheckpoint,
+        ctx: &ActorContext<SourceActor>,
+    ) -> anyhow::Result<()> {
+        if let Some(Position::Offset(offset_str)) =
+            checkpoint.position_for_partition(&self.partition_id)
+        {
+            let up_to_position_included = offset_str.parse::<u64>()?;
+            let notify_indexed_req = NotifyIndexedRequest {
+                index_id: self.ctx.index_uid.index_id().to_string(),
+                up_to_position_included,
+            };
+            ctx.send_message(&self.ingest_api_service, notify_indexed_req)
+                .await?;
+        }
+        Ok(())
+    }
+
     fn name(&self) -> String {
         "IngestApiSource".to_string()
     }
@@ -267,6 +289,7 @@ mod tests {
             source_params: SourceParams::IngestApi,
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         }
     }
 
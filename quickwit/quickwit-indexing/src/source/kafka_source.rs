@@ -33,11 +33,12 @@ use quickwit_metastore::checkpoint::{
     PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
 };
 use quickwit_proto::IndexUid;
+use quickwit_secrets::SecretResolver;
 use rdkafka::config::{ClientConfig, RDKafkaLogLevel};
 use rdkafka::consumer::{
     BaseConsumer, Consumer, ConsumerContext, DefaultConsumerContext, Rebalance,
 };
-use rdkafka::error::KafkaError;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
 use rdkafka::message::BorrowedMessage;
 use rdkafka::util::Timeout;
 use rdkafka::{ClientContext, Message, Offset, TopicPartitionList};
@@ -45,7 +46,7 @@ use serde_json::{json, Value as JsonValue};
 use tokio::sync::mpsc;
 use tokio::task::{spawn_blocking, JoinHandle};
 use tokio::time;
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
 
 use crate::actors::DocProcessor;
 use crate::models::{NewPublishLock, PublishLock, RawDocBatch};
@@ -117,9 +118,27 @@ impl From<BorrowedMessage<'_>> for KafkaMessage {
 struct RdKafkaContext {
     topic: String,
     events_tx: mpsc::Sender<KafkaEvent>,
+    /// Client params before their secret references were resolved.
+    unresolved_client_params: JsonValue,
 }
 
-impl ClientContext for RdKafkaContext {}
+impl ClientContext for RdKafkaContext {
+    fn error(&self, error: KafkaError, reason: &str) {
+        error!(error=?error, reason=%reason, "Kafka client error.");
+
+        if matches!(
+            error.rdkafka_error_code(),
+            Some(RDKafkaErrorCode::Authentication | RDKafkaErrorCode::SaslAuthenticationFailed)
+        ) {
+            // The credentials may have been rotated: the source fails and is restarted with the
+            // secrets fetched again from their providers.
+            SecretResolver::global().invalidate_json(&self.unresolved_client_params);
+            let _ = self.events_tx.try_send(KafkaEvent::Error(anyhow!(
+                "Failed to authenticate to the Kafka brokers: {reason}"
+            )));
+        }
+    }
+}
 
 macro_rules! return_if_err {
     ($expression:expr, $lit: literal) => {
@@ -240,17 +259,22 @@ impl KafkaSource {
     /// Instantiates a new `KafkaSource`.
     pub async fn try_new(
         ctx: Arc<SourceExecutionContext>,
-        params: KafkaSourceParams,
+        mut params: KafkaSourceParams,
         _ignored_checkpoint: SourceCheckpoint,
     ) -> anyhow::Result<Self> {
         let topic = params.topic.clone();
         let backfill_mode_enabled = params.enable_backfill_mode;
+        let unresolved_client_params = params.client_params.clone();
+        params.client_params = SecretResolver::global()
+            .resolve_json(params.client_params)
+            .await?;
 
         let (events_tx, events_rx) = mpsc::channel(100);
         let (client_config, consumer) = create_consumer(
             &ctx.index_uid,
             &ctx.source_config.source_id,
             params,
+            unresolved_client_params,
             events_tx.clone(),
         )?;
         let native_client_config = client_config.create_native_config()?;
@@ -631,7 +655,10 @@ fn previous_position_for_offset(offset: i64) -> Position {
 }
 
 /// Checks whether we can establish a connection to the Kafka broker.
-pub(super) async fn check_connectivity(params: KafkaSourceParams) -> anyhow::Result<()> {
+pub(super) async fn check_connectivity(mut params: KafkaSourceParams) -> anyhow::Result<()> {
+    params.client_params = SecretResolver::global()
+        .resolve_json(params.client_params)
+        .await?;
     let mut client_config = parse_client_params(params.client_params)?;
 
     let consumer: BaseConsumer<DefaultConsumerContext> = client_config
@@ -665,6 +692,7 @@ fn create_consumer(
     index_uid: &IndexUid,
     source_id: &str,
     params: KafkaSourceParams,
+    unresolved_client_params: JsonValue,
     events_tx: mpsc::Sender<KafkaEvent>,
 ) -> anyhow::Result<(ClientConfig, RdKafkaConsumer)> {
     let mut client_config = parse_client_params(params.client_params)?;
@@ -685,6 +713,7 @@ fn create_consumer(
         .create_with_context(RdKafkaContext {
             topic: params.topic,
             events_tx,
+            unresolved_client_params,
         })
         .context("Failed to create Kafka consumer.")?;
 
@@ -881,6 +910,7 @@ mod kafka_broker_tests {
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         (source_id, source_config)
     }
@@ -57,6 +57,7 @@
 //!   that file.
 //! - the kafka source: the partition id is a kafka topic partition id, and the position is a kafka
 //!   offset.
+mod csv_parser;
 mod file_source;
 mod ingest_api_source;
 #[cfg(feature = "kafka")]
@@ -196,6 +197,22 @@ pub trait Source: Send + Sync + 'static {
         Ok(())
     }
 
+    /// After a batch of documents has been added to the split under construction,
+    /// `notify_indexed` is called with the checkpoint of the batch.
+    ///
+    /// This method is for instance useful for the ingest API, as it makes it possible to
+    /// acknowledge the ingest requests that wait for their documents to be indexed.
+    ///
+    /// Like `suggest_truncate`, it is perfectly fine for implementations to ignore this function,
+    /// and returning an error only logs it.
+    async fn notify_indexed(
+        &self,
+        _checkpoint: SourceCheckpoint,
+        _ctx: &ActorContext<SourceActor>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     /// Finalize is called once after the actor terminates.
     async fn finalize(
         &mut self,
@@ -369,6 +386,26 @@ impl Handler<SuggestTruncate> for SourceActor {
     }
 }
 
+#[derive(Debug)]
+pub struct NotifyIndexed(pub SourceCheckpoint);
+
+#[async_trait]
+impl Handler<NotifyIndexed> for SourceActor {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        notify_indexed: NotifyIndexed,
+        ctx: &SourceContext,
+    ) -> Result<(), ActorExitStatus> {
+        let NotifyIndexed(checkpoint) = notify_indexed;
+        if let Err(err) = self.source.notify_indexed(checkpoint, ctx).await {
+            error!(err=?err, "notify-indexed-error");
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -389,6 +426,7 @@ mod tests {
                 source_params: SourceParams::void(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             };
             check_source_connectivity(&source_config).await?;
         }
@@ -401,6 +439,7 @@ mod tests {
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             };
             check_source_connectivity(&source_config).await?;
         }
@@ -413,6 +452,7 @@ mod tests {
                 source_params: SourceParams::file("file-does-not-exist.json"),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             };
             assert!(check_source_connectivity(&source_config).await.is_err());
         }
@@ -425,6 +465,7 @@ mod tests {
                 source_params: SourceParams::file("data/test_corpus.json"),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             };
             assert!(check_source_connectivity(&source_config).await.is_ok());
         }
@@ -27,6 +27,7 @@ use bytes::Bytes;
 use futures::StreamExt;
 use pulsar::authentication::oauth2::{OAuth2Authentication, OAuth2Params};
 use pulsar::consumer::Message;
+use pulsar::error::AuthenticationError;
 use pulsar::message::proto::MessageIdData;
 use pulsar::{
     Authentication, Consumer, DeserializeMessage, Payload, Pulsar, SubType, TokioExecutor,
@@ -37,6 +38,7 @@ use quickwit_metastore::checkpoint::{
     PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
 };
 use quickwit_proto::IndexUid;
+use quickwit_secrets::{is_secret_ref, SecretResolver};
 use serde_json::{json, Value as JsonValue};
 use tokio::sync::Mutex;
 use tokio::time;
@@ -419,6 +421,11 @@ async fn connect_pulsar(params: &PulsarSourceParams) -> anyhow::Result<Pulsar<To
 
     match params.authentication.clone() {
         None => {}
+        Some(PulsarSourceAuth::Token(token_ref)) if is_secret_ref(&token_ref) => {
+            // Fails early if the secret cannot be resolved.
+            SecretResolver::global().resolve(&token_ref).await?;
+            builder = builder.with_auth_provider(Box::new(SecretTokenAuthentication { token_ref }));
+        }
         Some(PulsarSourceAuth::Token(token)) => {
             let auth = Authentication {
                 name: "token".to_string(),
@@ -427,6 +434,17 @@ async fn connect_pulsar(params: &PulsarSourceParams) -> anyhow::Result<Pulsar<To
 
             builder = builder.with_auth(auth);
         }
+        Some(PulsarSourceAuth::Oauth2 {
+            issuer_url,
+            credentials_url,
+            audience,
+            scope,
+        }) if is_secret_ref(&credentials_url) => {
+            let auth_provider =
+                SecretOAuth2Authentication::try_new(issuer_url, credentials_url, audience, scope)
+                    .await?;
+            builder = builder.with_auth_provider(Box::new(auth_provider));
+        }
         Some(PulsarSourceAuth::Oauth2 {
             issuer_url,
             credentials_url,
@@ -448,6 +466,101 @@ async fn connect_pulsar(params: &PulsarSourceParams) -> anyhow::Result<Pulsar<To
     Ok(pulsar)
 }
 
+/// Authenticates with a token referencing a secret. The token is resolved every time the client
+/// authenticates, so the connections opened after the secret is rotated use the new token.
+struct SecretTokenAuthentication {
+    token_ref: String,
+}
+
+#[async_trait]
+impl pulsar::authentication::Authentication for SecretTokenAuthentication {
+    fn auth_method_name(&self) -> String {
+        "token".to_string()
+    }
+
+    async fn initialize(&mut self) -> Result<(), AuthenticationError> {
+        Ok(())
+    }
+
+    async fn auth_data(&mut self) -> Result<Vec<u8>, AuthenticationError> {
+        let token = resolve_auth_secret(&self.token_ref).await?;
+        Ok(token.into_bytes())
+    }
+}
+
+/// Authenticates with OAuth2 using a credentials URL referencing a secret. The OAuth2 provider is
+/// rebuilt when the client authenticates after the secret is rotated.
+struct SecretOAuth2Authentication {
+    issuer_url: String,
+    credentials_url_ref: String,
+    audience: Option<String>,
+    scope: Option<String>,
+    credentials_url: String,
+    oauth2_authentication: Box<dyn pulsar::authentication::Authentication>,
+}
+
+impl SecretOAuth2Authentication {
+    async fn try_new(
+        issuer_url: String,
+        credentials_url_ref: String,
+        audience: Option<String>,
+        scope: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let credentials_url = SecretResolver::global()
+            .resolve(&credentials_url_ref)
+            .await?;
+        let oauth2_authentication = OAuth2Authentication::client_credentials(OAuth2Params {
+            issuer_url: issuer_url.clone(),
+            credentials_url: credentials_url.clone(),
+            audience: audience.clone(),
+            scope: scope.clone(),
+        });
+        Ok(Self {
+            issuer_url,
+            credentials_url_ref,
+            audience,
+            scope,
+            credentials_url,
+            oauth2_authentication,
+        })
+    }
+}
+
+#[async_trait]
+impl pulsar::authentication::Authentication for SecretOAuth2Authentication {
+    fn auth_method_name(&self) -> String {
+        self.oauth2_authentication.auth_method_name()
+    }
+
+    async fn initialize(&mut self) -> Result<(), AuthenticationError> {
+        self.oauth2_authentication.initialize().await
+    }
+
+    async fn auth_data(&mut self) -> Result<Vec<u8>, AuthenticationError> {
+        let credentials_url = resolve_auth_secret(&self.credentials_url_ref).await?;
+
+        if credentials_url != self.credentials_url {
+            info!("Pulsar OAuth2 credentials were rotated. Rebuilding the OAuth2 provider.");
+            self.oauth2_authentication = OAuth2Authentication::client_credentials(OAuth2Params {
+                issuer_url: self.issuer_url.clone(),
+                credentials_url: credentials_url.clone(),
+                audience: self.audience.clone(),
+                scope: self.scope.clone(),
+            });
+            self.oauth2_authentication.initialize().await?;
+            self.credentials_url = credentials_url;
+        }
+        self.oauth2_authentication.auth_data().await
+    }
+}
+
+async fn resolve_auth_secret(secret_ref: &str) -> Result<String, AuthenticationError> {
+    SecretResolver::global()
+        .resolve(secret_ref)
+        .await
+        .map_err(|error| AuthenticationError::Custom(format!("{error:#}")))
+}
+
 /// Checks whether we can establish a connection to the pulsar broker.
 pub(crate) async fn check_connectivity(params: &PulsarSourceParams) -> anyhow::Result<()> {
     connect_pulsar(params).await?;
@@ -567,6 +680,7 @@ mod pulsar_broker_tests {
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         (source_id, source_config)
     }
@@ -164,6 +164,7 @@ mod tests {
                     source_params: SourceParams::Vec(params.clone()),
                     transform_config: None,
                     input_format: SourceInputFormat::Json,
+                    labels: Default::default(),
                 },
             ),
             params,
@@ -224,6 +225,7 @@ mod tests {
                     source_params: SourceParams::Vec(params.clone()),
                     transform_config: None,
                     input_format: SourceInputFormat::Json,
+                    labels: Default::default(),
                 },
             ),
             params,
@@ -94,6 +94,7 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let metastore = metastore_for_test();
         let ctx = SourceExecutionContext::for_test(
@@ -126,6 +127,7 @@ mod tests {
                     source_params: SourceParams::void(),
                     transform_config: None,
                     input_format: SourceInputFormat::Json,
+                    labels: Default::default(),
                 },
             ),
             VoidSourceParams,
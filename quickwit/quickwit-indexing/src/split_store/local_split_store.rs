@@ -352,6 +352,15 @@ impl LocalSplitStore {
         })
     }
 
+    /// Returns the number of splits and the total size of the splits in the cache.
+    pub async fn cache_usage(&self) -> (usize, Byte) {
+        let inner = self.inner.lock().await;
+        (
+            inner.split_store_space_quota.num_splits_in_cache(),
+            inner.split_store_space_quota.size_in_bytes_in_cache(),
+        )
+    }
+
     #[cfg(any(test, feature = "testsuite"))]
     pub async fn inspect(&self) -> HashMap<String, Byte> {
         self.inner
@@ -87,6 +87,14 @@ impl SplitStoreQuota {
     pub fn max_num_bytes(&self) -> Byte {
         self.max_num_bytes
     }
+
+    pub fn num_splits_in_cache(&self) -> usize {
+        self.num_splits_in_cache
+    }
+
+    pub fn size_in_bytes_in_cache(&self) -> Byte {
+        self.size_in_bytes_in_cache
+    }
 }
 
 #[cfg(test)]
@@ -155,6 +155,7 @@ impl TestSandbox {
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         let pipeline_id = self
             .indexing_service
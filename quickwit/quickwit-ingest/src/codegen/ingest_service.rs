@@ -41,6 +41,10 @@ pub struct IngestRequest {
 pub struct IngestResponse {
     #[prost(uint64, tag = "1")]
     pub num_docs_for_processing: u64,
+    /// Opaque token recording the positions of the ingested records. Passing it to a search
+    /// request makes the search wait until these records are searchable.
+    #[prost(string, optional, tag = "2")]
+    pub position_token: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -52,6 +56,13 @@ pub struct FetchRequest {
     pub start_after: ::core::option::Option<u64>,
     #[prost(uint64, optional, tag = "3")]
     pub num_bytes_limit: ::core::option::Option<u64>,
+    /// When set, the records appended before this Unix timestamp, in seconds, are skipped.
+    #[prost(int64, optional, tag = "4")]
+    pub start_timestamp: ::core::option::Option<i64>,
+    /// When true, the most recent records fitting in `num_bytes_limit` are returned instead of the
+    /// oldest ones.
+    #[prost(bool, tag = "5")]
+    pub keep_most_recent: bool,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -61,6 +72,10 @@ pub struct FetchResponse {
     pub first_position: ::core::option::Option<u64>,
     #[prost(message, optional, tag = "2")]
     pub doc_batch: ::core::option::Option<DocBatch>,
+    /// Partition ID of the queues of the node, under which the ingest API source records its
+    /// position in the checkpoint.
+    #[prost(string, optional, tag = "3")]
+    pub partition_id: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
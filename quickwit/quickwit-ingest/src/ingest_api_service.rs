@@ -23,7 +23,7 @@ use std::{fmt, iter};
 use async_trait::async_trait;
 use bytes::Bytes;
 use quickwit_actors::{
-    Actor, ActorContext, ActorExitStatus, DeferableReplyHandler, Handler, QueueCapacity,
+    Actor, ActorContext, ActorExitStatus, DeferableReplyHandler, Handler, QueueCapacity, Readyz,
 };
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_common::tower::Cost;
@@ -35,8 +35,8 @@ use crate::notifications::Notifications;
 use crate::{
     CommitType, CreateQueueIfNotExistsRequest, CreateQueueRequest, DocCommand, DropQueueRequest,
     FetchRequest, FetchResponse, IngestRequest, IngestResponse, IngestServiceError,
-    ListQueuesRequest, ListQueuesResponse, MemoryCapacity, Queues, SuggestTruncateRequest,
-    TailRequest,
+    ListQueuesRequest, ListQueuesResponse, MemoryCapacity, PositionToken, Queues,
+    SuggestTruncateRequest, TailRequest,
 };
 
 impl Cost for IngestRequest {
@@ -54,7 +54,10 @@ pub struct IngestApiService {
     memory_limit: usize,
     disk_limit: usize,
     memory_capacity: MemoryCapacity,
+    // Ingest requests waiting for their records to be published.
     notifications: Notifications,
+    // Ingest requests waiting for their records to be indexed.
+    indexed_notifications: Notifications,
 }
 
 impl fmt::Debug for IngestApiService {
@@ -102,6 +105,7 @@ impl IngestApiService {
         let partition_id = get_or_initialize_partition_id(queues_dir_path).await?;
         let memory_capacity = MemoryCapacity::new(memory_limit);
         let notifications = Notifications::new();
+        let indexed_notifications = Notifications::new();
         info!(ingest_partition_id=%partition_id, "Ingest API partition id");
         Ok(Self {
             partition_id,
@@ -110,6 +114,7 @@ impl IngestApiService {
             disk_limit,
             memory_capacity,
             notifications,
+            indexed_notifications,
         })
     }
 
@@ -119,13 +124,19 @@ impl IngestApiService {
         reply: impl FnOnce(crate::Result<IngestResponse>) + Send + Sync + 'static,
         ctx: &ActorContext<Self>,
     ) -> Result<(), ActorExitStatus> {
+        let commit = CommitType::from(request.commit);
         let notification = self.ingest_inner(request, ctx).await;
         match notification {
             Ok((response, index_positions)) => {
                 if index_positions.is_empty() {
                     reply(Ok(response));
                 } else {
-                    self.notifications
+                    let notifications = if commit == CommitType::Indexed {
+                        &self.indexed_notifications
+                    } else {
+                        &self.notifications
+                    };
+                    notifications
                         .register(index_positions, move || {
                             reply(Ok(response));
                         })
@@ -174,6 +185,10 @@ impl IngestApiService {
         }
         let mut num_docs = 0usize;
         let mut notifications = Vec::new();
+        let mut position_token = PositionToken {
+            partition_id: self.partition_id.clone(),
+            ..Default::default()
+        };
         for doc_batch in &request.doc_batches {
             // TODO better error handling.
             // If there is an error, we probably want a transactional behavior.
@@ -184,6 +199,9 @@ impl IngestApiService {
                 .await?;
             let commit = CommitType::from(request.commit);
             if let Some(max_position) = max_position {
+                position_token
+                    .positions
+                    .insert(doc_batch.index_id.clone(), max_position);
                 if commit != CommitType::Auto {
                     if commit == CommitType::Force {
                         self.queues
@@ -212,6 +230,8 @@ impl IngestApiService {
         Ok((
             IngestResponse {
                 num_docs_for_processing: num_docs as u64,
+                position_token: (!position_token.positions.is_empty())
+                    .then(|| position_token.encode()),
             },
             notifications,
         ))
@@ -221,11 +241,23 @@ impl IngestApiService {
         let num_bytes_limit_opt: Option<usize> = fetch_req
             .num_bytes_limit
             .map(|num_bytes_limit| num_bytes_limit as usize);
-        self.queues.fetch(
-            &fetch_req.index_id,
-            fetch_req.start_after,
-            num_bytes_limit_opt,
-        )
+        let mut start_after = fetch_req.start_after;
+
+        if let Some(start_timestamp) = fetch_req.start_timestamp {
+            let appended_before_position_opt = self
+                .queues
+                .last_position_appended_before(&fetch_req.index_id, start_timestamp);
+            start_after = start_after.max(appended_before_position_opt);
+        }
+        let mut fetch_response = if fetch_req.keep_most_recent {
+            self.queues
+                .fetch_most_recent(&fetch_req.index_id, start_after, num_bytes_limit_opt)?
+        } else {
+            self.queues
+                .fetch(&fetch_req.index_id, start_after, num_bytes_limit_opt)?
+        };
+        fetch_response.partition_id = Some(self.partition_id.clone());
+        Ok(fetch_response)
     }
 
     async fn suggest_truncate(
@@ -236,6 +268,10 @@ impl IngestApiService {
         self.notifications
             .notify(&request.index_id, request.up_to_position_included)
             .await;
+        // Published records have necessarily been indexed.
+        self.indexed_notifications
+            .notify(&request.index_id, request.up_to_position_included)
+            .await;
         self.queues
             .suggest_truncate(&request.index_id, request.up_to_position_included, ctx)
             .await?;
@@ -280,6 +316,30 @@ impl Handler<GetPartitionId> for IngestApiService {
     }
 }
 
+/// Notifies the ingest API service that the records of a queue have been indexed up to a given
+/// position, which releases the ingest requests waiting for them with [`CommitType::Indexed`].
+#[derive(Debug)]
+pub struct NotifyIndexedRequest {
+    pub index_id: String,
+    pub up_to_position_included: u64,
+}
+
+#[async_trait]
+impl Handler<NotifyIndexedRequest> for IngestApiService {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        request: NotifyIndexedRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        self.indexed_notifications
+            .notify(&request.index_id, request.up_to_position_included)
+            .await;
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct GetMemoryCapacity;
 
@@ -296,6 +356,23 @@ impl Handler<GetMemoryCapacity> for IngestApiService {
     }
 }
 
+#[async_trait]
+impl Handler<Readyz> for IngestApiService {
+    type Reply = bool;
+
+    /// The queues are writable as long as neither the disk limit nor the memory limit is
+    /// reached.
+    async fn handle(
+        &mut self,
+        _request: Readyz,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let is_writable =
+            self.queues.disk_usage() <= self.disk_limit && self.memory_capacity.capacity() > 0;
+        Ok(is_writable)
+    }
+}
+
 #[async_trait]
 impl Handler<CreateQueueRequest> for IngestApiService {
     type Reply = crate::Result<()>;
@@ -407,6 +484,7 @@ impl Handler<ListQueuesRequest> for IngestApiService {
 mod tests {
     use std::time::Duration;
 
+    use byte_unit::Byte;
     use bytes::Bytes;
     use quickwit_actors::Universe;
     use quickwit_config::IngestApiConfig;
@@ -469,6 +547,8 @@ mod tests {
             index_id: "index-1".to_string(),
             start_after: None,
             num_bytes_limit: None,
+            start_timestamp: None,
+            keep_most_recent: false,
         };
         let fetch_response = ingest_api_service.ask_for_res(fetch_request).await.unwrap();
         let doc_batch = fetch_response.doc_batch.unwrap();
@@ -493,6 +573,39 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_service_readiness() -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let temp_dir = tempfile::tempdir()?;
+        let queues_dir_path = temp_dir.path();
+        let ingest_api_config = IngestApiConfig {
+            max_queue_memory_usage: Byte::from_bytes(10),
+            ..Default::default()
+        };
+        let ingest_api_service =
+            init_ingest_api(&universe, queues_dir_path, &ingest_api_config).await?;
+        assert!(ingest_api_service.ask(Readyz).await?);
+
+        let create_queue_req = CreateQueueIfNotExistsRequest {
+            queue_id: "index-1".to_string(),
+        };
+        ingest_api_service.ask_for_res(create_queue_req).await?;
+
+        let mut batch = DocBatchBuilder::new("index-1".to_string());
+        batch.ingest_doc(Bytes::from_static(b"0123456789"));
+        let ingest_request = IngestRequest {
+            doc_batches: vec![batch.build()],
+            commit: CommitType::Auto as u32,
+        };
+        ingest_api_service.ask_for_res(ingest_request).await?;
+
+        // The memory limit is reached: the queues are no longer writable.
+        assert!(!ingest_api_service.ask(Readyz).await?);
+
+        universe.assert_quit().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_ingest_api_service_with_wait() -> anyhow::Result<()> {
         let universe = Universe::with_accelerated_time();
@@ -528,6 +641,8 @@ mod tests {
             index_id: "index-1".to_string(),
             start_after: None,
             num_bytes_limit: None,
+            start_timestamp: None,
+            keep_most_recent: false,
         };
         let fetch_response = ingest_api_service.ask_for_res(fetch_request).await.unwrap();
         let doc_batch = fetch_response.doc_batch.unwrap();
@@ -547,4 +662,111 @@ mod tests {
         universe.assert_quit().await;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_ingest_api_service_with_indexed() -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let temp_dir = tempfile::tempdir()?;
+        let queues_dir_path = temp_dir.path();
+
+        let ingest_api_service =
+            init_ingest_api(&universe, queues_dir_path, &IngestApiConfig::default()).await?;
+
+        let create_queue_req = CreateQueueIfNotExistsRequest {
+            queue_id: "index-1".to_string(),
+        };
+        ingest_api_service.ask_for_res(create_queue_req).await?;
+
+        let mut batch = DocBatchBuilder::new("index-1".to_string());
+        batch.ingest_doc(Bytes::from_static(b"Test1"));
+        batch.ingest_doc(Bytes::from_static(b"Test2"));
+
+        let ingest_request = IngestRequest {
+            doc_batches: vec![batch.build()],
+            commit: CommitType::Indexed as u32,
+        };
+        let ingest_response = ingest_api_service
+            .send_message(ingest_request)
+            .await
+            .unwrap();
+
+        ingest_api_service
+            .send_message(NotifyIndexedRequest {
+                index_id: "index-1".to_string(),
+                up_to_position_included: 1,
+            })
+            .await
+            .unwrap();
+
+        let ingest_response = ingest_response.await.unwrap().unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 2);
+
+        // Unlike publication, indexing does not truncate the queue.
+        let fetch_request = FetchRequest {
+            index_id: "index-1".to_string(),
+            start_after: None,
+            num_bytes_limit: None,
+            start_timestamp: None,
+            keep_most_recent: false,
+        };
+        let fetch_response = ingest_api_service.ask_for_res(fetch_request).await.unwrap();
+        assert_eq!(fetch_response.doc_batch.unwrap().num_docs(), 2);
+
+        universe.assert_quit().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_service_returns_position_token() -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let temp_dir = tempfile::tempdir()?;
+        let queues_dir_path = temp_dir.path();
+
+        let ingest_api_service =
+            init_ingest_api(&universe, queues_dir_path, &IngestApiConfig::default()).await?;
+        let partition_id = ingest_api_service.ask(GetPartitionId).await?;
+
+        for queue_id in ["index-1", "index-2"] {
+            let create_queue_req = CreateQueueIfNotExistsRequest {
+                queue_id: queue_id.to_string(),
+            };
+            ingest_api_service.ask_for_res(create_queue_req).await?;
+        }
+        let mut batch_1 = DocBatchBuilder::new("index-1".to_string());
+        batch_1.ingest_doc(Bytes::from_static(b"Test1"));
+        batch_1.ingest_doc(Bytes::from_static(b"Test2"));
+        let mut batch_2 = DocBatchBuilder::new("index-2".to_string());
+        batch_2.ingest_doc(Bytes::from_static(b"Test3"));
+
+        let ingest_request = IngestRequest {
+            doc_batches: vec![batch_1.build(), batch_2.build()],
+            commit: CommitType::Auto as u32,
+        };
+        let ingest_response = ingest_api_service.ask_for_res(ingest_request).await?;
+        let position_token =
+            PositionToken::decode(ingest_response.position_token.as_ref().unwrap())?;
+        assert_eq!(position_token.partition_id, partition_id);
+        assert_eq!(position_token.position_for_index("index-1"), Some(1));
+        assert_eq!(position_token.position_for_index("index-2"), Some(0));
+
+        let fetch_request = FetchRequest {
+            index_id: "index-1".to_string(),
+            start_after: None,
+            num_bytes_limit: None,
+            start_timestamp: None,
+            keep_most_recent: false,
+        };
+        let fetch_response = ingest_api_service.ask_for_res(fetch_request).await?;
+        assert_eq!(fetch_response.partition_id, Some(partition_id));
+
+        let ingest_request = IngestRequest {
+            doc_batches: Vec::new(),
+            commit: CommitType::Auto as u32,
+        };
+        let ingest_response = ingest_api_service.ask_for_res(ingest_request).await?;
+        assert!(ingest_response.position_token.is_none());
+
+        universe.assert_quit().await;
+        Ok(())
+    }
 }
@@ -27,31 +27,44 @@ mod memory_capacity;
 mod metrics;
 mod notifications;
 mod position;
+mod position_token;
 mod queue;
 
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context};
 pub use errors::IngestServiceError;
-pub use ingest_api_service::{GetMemoryCapacity, GetPartitionId, IngestApiService};
+pub use ingest_api_service::{
+    GetMemoryCapacity, GetPartitionId, IngestApiService, NotifyIndexedRequest,
+};
 pub use ingest_service::*;
 pub use memory_capacity::MemoryCapacity;
 use once_cell::sync::OnceCell;
 pub use position::Position;
+pub use position_token::PositionToken;
 pub use queue::Queues;
 use quickwit_actors::{Mailbox, Universe};
+use quickwit_common::tower::Pool;
 use quickwit_config::IngestApiConfig;
 use serde::Deserialize;
 use tokio::sync::Mutex;
 
 mod doc_batch;
+
+/// Encoded file descriptor set of the ingest service, served by the gRPC reflection service.
+pub const INGEST_FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/ingest_service_descriptor.bin"));
 pub use doc_batch::*;
 
 pub const QUEUES_DIR_NAME: &str = "queues";
 
 pub type Result<T> = std::result::Result<T, IngestServiceError>;
 
+/// A pool of ingest service clients identified by their gRPC socket address.
+pub type IngesterPool = Pool<SocketAddr, IngestServiceClient>;
+
 type IngestApiServiceMailboxes = HashMap<PathBuf, Mailbox<IngestApiService>>;
 
 pub static INGEST_API_SERVICE_MAILBOXES: OnceCell<Mutex<IngestApiServiceMailboxes>> =
@@ -121,13 +134,20 @@ pub async fn start_ingest_api_service(
 #[derive(Default)]
 pub enum CommitType {
     #[default]
-    /// The request doesn't wait for commit
+    /// The request doesn't wait for commit: it returns as soon as the records are durably written
+    /// to the ingest queue.
+    #[serde(alias = "queued")]
     Auto = 0,
-    /// The request waits for the next scheduled commit to finish.
+    /// The request waits for the next scheduled commit to finish, i.e. for the records to be
+    /// published and searchable.
+    #[serde(alias = "published")]
     WaitFor = 1,
     /// The request forces an immediate commit after the last document in the batch and waits for
     /// it to finish.
     Force = 2,
+    /// The request waits for the records to be indexed in a split under construction. They are
+    /// not searchable yet.
+    Indexed = 3,
 }
 
 impl From<u32> for CommitType {
@@ -136,6 +156,7 @@ impl From<u32> for CommitType {
             0 => CommitType::Auto,
             1 => CommitType::WaitFor,
             2 => CommitType::Force,
+            3 => CommitType::Indexed,
             _ => panic!("Unknown commit type {value}"),
         }
     }
@@ -147,6 +168,7 @@ impl CommitType {
             CommitType::Auto => None,
             CommitType::WaitFor => Some(&[("commit", "wait_for")]),
             CommitType::Force => Some(&[("commit", "force")]),
+            CommitType::Indexed => Some(&[("commit", "indexed")]),
         }
     }
 }
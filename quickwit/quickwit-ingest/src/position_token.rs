@@ -0,0 +1,79 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+
+/// Opaque token returned by the ingest API, recording the position of the last ingested record of
+/// each index in the queues of the node that received the request.
+///
+/// Passing the token to a search request makes the search wait until these records are
+/// searchable, i.e. until the checkpoint of the ingest API source reaches these positions.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PositionToken {
+    /// Partition ID of the queues that received the records.
+    pub partition_id: String,
+    /// Position of the last ingested record, per index ID.
+    pub positions: BTreeMap<String, u64>,
+}
+
+impl PositionToken {
+    /// Returns the position of the last ingested record of the index, if any.
+    pub fn position_for_index(&self, index_id: &str) -> Option<u64> {
+        self.positions.get(index_id).copied()
+    }
+
+    pub fn encode(&self) -> String {
+        let token_json = serde_json::to_vec(self).expect("Position token should be serializable.");
+        BASE64_URL_SAFE_NO_PAD.encode(token_json)
+    }
+
+    pub fn decode(token: &str) -> anyhow::Result<Self> {
+        let token_json = BASE64_URL_SAFE_NO_PAD
+            .decode(token)
+            .context("Position token is not valid base64.")?;
+        serde_json::from_slice(&token_json).context("Position token is malformed.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_token_encode_decode() {
+        let position_token = PositionToken {
+            partition_id: "01GZ2ZN3AZFF2W6B0S5RG5N2HW".to_string(),
+            positions: BTreeMap::from([("index-1".to_string(), 42), ("index-2".to_string(), 0)]),
+        };
+        let token = position_token.encode();
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(PositionToken::decode(&token).unwrap(), position_token);
+        assert_eq!(position_token.position_for_index("index-1"), Some(42));
+        assert_eq!(position_token.position_for_index("index-3"), None);
+
+        PositionToken::decode("not a token").unwrap_err();
+        PositionToken::decode(&BASE64_URL_SAFE_NO_PAD.encode("{}")).unwrap_err();
+    }
+}
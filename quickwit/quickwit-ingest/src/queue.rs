@@ -17,8 +17,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::{HashMap, VecDeque};
 use std::ops::Bound;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use bytes::Buf;
 use mrecordlog::error::CreateQueueError;
@@ -36,13 +38,20 @@ const QUICKWIT_CF_PREFIX: &str = ".queue_";
 
 pub struct Queues {
     record_log: MultiRecordLog,
+    // Position of the last record appended to each queue, for every second during which records
+    // were appended. It is not persisted: the records appended before the queues were opened are
+    // never skipped by time.
+    append_timestamps: HashMap<String, VecDeque<(i64, u64)>>,
 }
 
 impl Queues {
     pub async fn open(queues_dir_path: &Path) -> crate::Result<Queues> {
         tokio::fs::create_dir_all(queues_dir_path).await.unwrap();
         let record_log = MultiRecordLog::open(queues_dir_path).await?;
-        Ok(Queues { record_log })
+        Ok(Queues {
+            record_log,
+            append_timestamps: HashMap::new(),
+        })
     }
 
     pub fn queue_exists(&self, queue_id: &str) -> bool {
@@ -80,6 +89,7 @@ impl Queues {
         let real_queue_id = format!("{QUICKWIT_CF_PREFIX}{queue_id}");
         ctx.protect_future(self.record_log.delete_queue(&real_queue_id))
             .await?;
+        self.append_timestamps.remove(queue_id);
         Ok(())
     }
 
@@ -110,6 +120,14 @@ impl Queues {
         )
         .await?;
 
+        if let Some(append_timestamps) = self.append_timestamps.get_mut(queue_id) {
+            while let Some((_, position)) = append_timestamps.front() {
+                if *position > up_to_offset_included {
+                    break;
+                }
+                append_timestamps.pop_front();
+            }
+        }
         Ok(())
     }
 
@@ -144,9 +162,38 @@ impl Queues {
             )
             .await?;
 
+        if let Some(max_position) = max_position {
+            let now_timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs() as i64);
+            self.record_append_timestamp(queue_id, now_timestamp, max_position);
+        }
         Ok(max_position)
     }
 
+    fn record_append_timestamp(&mut self, queue_id: &str, timestamp: i64, position: u64) {
+        let append_timestamps = self
+            .append_timestamps
+            .entry(queue_id.to_string())
+            .or_default();
+        match append_timestamps.back_mut() {
+            Some((last_timestamp, last_position)) if *last_timestamp >= timestamp => {
+                *last_position = position;
+            }
+            _ => append_timestamps.push_back((timestamp, position)),
+        }
+    }
+
+    // Returns the position of the last record appended to the queue before `timestamp`, if known.
+    pub fn last_position_appended_before(&self, queue_id: &str, timestamp: i64) -> Option<u64> {
+        self.append_timestamps
+            .get(queue_id)?
+            .iter()
+            .rev()
+            .find(|(append_timestamp, _)| *append_timestamp < timestamp)
+            .map(|(_, position)| *position)
+    }
+
     // Streams messages from in `]after_position, +∞[`.
     //
     // If after_position is set to None, then fetch from the start of the Stream.
@@ -187,6 +234,57 @@ impl Queues {
         Ok(FetchResponse {
             first_position: first_key_opt,
             doc_batch: Some(doc_batch.build()),
+            partition_id: None,
+        })
+    }
+
+    // Returns the most recent messages in `]after_position, +∞[` fitting in `num_bytes_limit`.
+    //
+    // Unlike `fetch`, the entire range is read so that only its end is returned.
+    pub fn fetch_most_recent(
+        &self,
+        queue_id: &str,
+        start_after: Option<u64>,
+        num_bytes_limit: Option<usize>,
+    ) -> crate::Result<FetchResponse> {
+        let real_queue_id = format!("{QUICKWIT_CF_PREFIX}{queue_id}");
+
+        let starting_bound = match start_after {
+            Some(pos) => Bound::Excluded(pos),
+            None => Bound::Unbounded,
+        };
+        let records = self
+            .record_log
+            .range(&real_queue_id, (starting_bound, Bound::Unbounded))
+            .ok_or_else(|| crate::IngestServiceError::IndexNotFound {
+                index_id: queue_id.to_string(),
+            })?;
+
+        let size_limit = num_bytes_limit.unwrap_or(FETCH_PAYLOAD_LIMIT);
+        let mut most_recent_records = VecDeque::new();
+        let mut num_bytes = 0;
+
+        for (pos, record) in records {
+            num_bytes += record.len();
+            most_recent_records.push_back((pos, record));
+
+            while num_bytes > size_limit {
+                let Some((_, oldest_record)) = most_recent_records.pop_front() else {
+                    break;
+                };
+                num_bytes -= oldest_record.len();
+            }
+        }
+        let first_key_opt = most_recent_records.front().map(|(pos, _)| *pos);
+        let mut doc_batch = DocBatchBuilder::new(queue_id.to_string());
+
+        for (_, record) in most_recent_records {
+            doc_batch.command_from_buf(record.as_ref());
+        }
+        Ok(FetchResponse {
+            first_position: first_key_opt,
+            doc_batch: Some(doc_batch.build()),
+            partition_id: None,
         })
     }
 
@@ -429,6 +527,103 @@ mod tests {
         queues.fetch_test(TEST_QUEUE_ID, Some(1), Some(2), &[&b"tax"[..]]);
     }
 
+    #[tokio::test]
+    async fn test_fetch_most_recent() {
+        let (mut queues, ctx) = QueuesForTest::new().await;
+        queues.create_queue(TEST_QUEUE_ID, &ctx).await.unwrap();
+        queues
+            .append_batch(
+                TEST_QUEUE_ID,
+                [b"hello", b"happy", b"tax"]
+                    .iter()
+                    .map(|bytes| bytes.as_slice()),
+                &ctx,
+            )
+            .await
+            .unwrap();
+
+        let fetch_resp = queues
+            .fetch_most_recent(TEST_QUEUE_ID, None, Some(8))
+            .unwrap();
+        assert_eq!(fetch_resp.first_position, Some(1));
+        let records: Vec<Bytes> = fetch_resp.doc_batch.unwrap().iter_raw().collect();
+        assert_eq!(&records, &[&b"happy"[..], &b"tax"[..]]);
+
+        let fetch_resp = queues
+            .fetch_most_recent(TEST_QUEUE_ID, Some(1), None)
+            .unwrap();
+        assert_eq!(fetch_resp.first_position, Some(2));
+        let records: Vec<Bytes> = fetch_resp.doc_batch.unwrap().iter_raw().collect();
+        assert_eq!(&records, &[&b"tax"[..]]);
+
+        let fetch_resp = queues
+            .fetch_most_recent(TEST_QUEUE_ID, Some(2), None)
+            .unwrap();
+        assert_eq!(fetch_resp.first_position, None);
+    }
+
+    #[tokio::test]
+    async fn test_last_position_appended_before() {
+        let (mut queues, ctx) = QueuesForTest::new().await;
+        queues.create_queue(TEST_QUEUE_ID, &ctx).await.unwrap();
+        queues
+            .append_batch(
+                TEST_QUEUE_ID,
+                [b"hello", b"happy", b"tax", b"fox"]
+                    .iter()
+                    .map(|bytes| bytes.as_slice()),
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID, 1_000),
+            None
+        );
+        // Replace the timestamp recorded by the append with fixed ones.
+        queues.append_timestamps.clear();
+        queues.record_append_timestamp(TEST_QUEUE_ID, 10, 0);
+        queues.record_append_timestamp(TEST_QUEUE_ID, 10, 1);
+        queues.record_append_timestamp(TEST_QUEUE_ID, 20, 2);
+        queues.record_append_timestamp(TEST_QUEUE_ID, 30, 3);
+
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID, 10),
+            None
+        );
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID, 11),
+            Some(1)
+        );
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID, 30),
+            Some(2)
+        );
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID2, 30),
+            None
+        );
+
+        queues
+            .suggest_truncate(TEST_QUEUE_ID, 1, &ctx)
+            .await
+            .unwrap();
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID, 11),
+            None
+        );
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID, 31),
+            Some(3)
+        );
+
+        queues.drop_queue(TEST_QUEUE_ID, &ctx).await.unwrap();
+        assert_eq!(
+            queues.last_position_appended_before(TEST_QUEUE_ID, 31),
+            None
+        );
+    }
+
     struct Record {
         queue_id: String,
         payload: Vec<u8>,
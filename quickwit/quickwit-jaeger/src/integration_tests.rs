@@ -30,7 +30,7 @@ use quickwit_indexing::models::SpawnPipeline;
 use quickwit_indexing::IndexingService;
 use quickwit_ingest::{
     init_ingest_api, CommitType, CreateQueueRequest, IngestApiService, IngestServiceClient,
-    QUEUES_DIR_NAME,
+    IngesterPool, QUEUES_DIR_NAME,
 };
 use quickwit_metastore::{FileBackedMetastore, Metastore};
 use quickwit_opentelemetry::otlp::OtlpGrpcTracesService;
@@ -367,6 +367,8 @@ async fn searcher_for_test(
         metastore,
         storage_resolver,
         search_job_placer,
+        IngesterPool::default(),
+        None,
     )
     .await
     .unwrap();
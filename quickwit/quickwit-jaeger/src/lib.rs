@@ -1122,7 +1122,11 @@ mod tests {
                 quickwit_query::query_ast::UserInputQuery {
                     user_text: "query".to_string(),
                     default_fields: None,
-                    default_operator: quickwit_query::BooleanOperand::And
+                    default_operator: quickwit_query::BooleanOperand::And,
+                    quote_field_suffix: None,
+                    phrase_slop: 0,
+                    lenient: false,
+                    fuzziness: None,
                 }
                 .into()
             );
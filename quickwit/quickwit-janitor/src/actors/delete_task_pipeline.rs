@@ -28,7 +28,7 @@ use quickwit_actors::{
 use quickwit_common::io::IoControls;
 use quickwit_common::temp_dir::{self};
 use quickwit_common::uri::Uri;
-use quickwit_config::{build_doc_mapper, IndexingSettings};
+use quickwit_config::{build_doc_mapper, IndexingSettings, SplitUpgradeConfig};
 use quickwit_indexing::actors::{
     MergeExecutor, MergeSplitDownloader, Packager, Publisher, Uploader, UploaderType,
 };
@@ -44,6 +44,8 @@ use tokio::join;
 use tracing::info;
 
 use super::delete_task_planner::DeleteTaskPlanner;
+use super::split_reshard_planner::SplitReshardPlanner;
+use super::split_upgrade_planner::SplitUpgradePlanner;
 
 const OBSERVE_PIPELINE_INTERVAL: Duration = if cfg!(any(test, feature = "testsuite")) {
     Duration::from_millis(500)
@@ -55,6 +57,8 @@ const OBSERVE_PIPELINE_INTERVAL: Duration = if cfg!(any(test, feature = "testsui
 
 struct DeletePipelineHandle {
     pub delete_task_planner: ActorHandle<Supervisor<DeleteTaskPlanner>>,
+    pub split_upgrade_planner_opt: Option<ActorHandle<Supervisor<SplitUpgradePlanner>>>,
+    pub split_reshard_planner_opt: Option<ActorHandle<Supervisor<SplitReshardPlanner>>>,
     pub downloader: ActorHandle<Supervisor<MergeSplitDownloader>>,
     pub delete_task_executor: ActorHandle<Supervisor<MergeExecutor>>,
     pub packager: ActorHandle<Supervisor<Packager>>,
@@ -66,6 +70,8 @@ struct DeletePipelineHandle {
 #[derive(Clone, Debug, Default, Serialize)]
 pub struct DeleteTaskPipelineState {
     pub delete_task_planner: SupervisorState,
+    pub split_upgrade_planner: Option<SupervisorState>,
+    pub split_reshard_planner: Option<SupervisorState>,
     pub downloader: SupervisorState,
     pub delete_task_executor: SupervisorState,
     pub packager: SupervisorState,
@@ -82,6 +88,7 @@ pub struct DeleteTaskPipeline {
     delete_service_task_dir: PathBuf,
     handles: Option<DeletePipelineHandle>,
     max_concurrent_split_uploads: usize,
+    split_upgrade_config: SplitUpgradeConfig,
     state: DeleteTaskPipelineState,
 }
 
@@ -109,6 +116,12 @@ impl Actor for DeleteTaskPipeline {
         _ctx: &ActorContext<Self>,
     ) -> anyhow::Result<()> {
         if let Some(handles) = self.handles.take() {
+            if let Some(split_upgrade_planner) = handles.split_upgrade_planner_opt {
+                split_upgrade_planner.quit().await;
+            }
+            if let Some(split_reshard_planner) = handles.split_reshard_planner_opt {
+                split_reshard_planner.quit().await;
+            }
             join!(
                 handles.delete_task_planner.quit(),
                 handles.downloader.quit(),
@@ -131,6 +144,7 @@ impl DeleteTaskPipeline {
         index_storage: Arc<dyn Storage>,
         delete_service_task_dir: PathBuf,
         max_concurrent_split_uploads: usize,
+        split_upgrade_config: SplitUpgradeConfig,
     ) -> Self {
         Self {
             index_uid,
@@ -141,6 +155,7 @@ impl DeleteTaskPipeline {
             delete_service_task_dir,
             handles: Default::default(),
             max_concurrent_split_uploads,
+            split_upgrade_config,
             state: DeleteTaskPipelineState::default(),
         }
     }
@@ -178,7 +193,13 @@ impl DeleteTaskPipeline {
         let doc_mapper =
             build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)?;
         let tag_fields = doc_mapper.tag_named_fields()?;
-        let packager = Packager::new("MergePackager", tag_fields, uploader_mailbox);
+        let doc_id_field_opt = doc_mapper.doc_id_named_field()?;
+        let packager = Packager::new(
+            "MergePackager",
+            tag_fields,
+            doc_id_field_opt,
+            uploader_mailbox,
+        );
         let (packager_mailbox, packager_supervisor_handler) = ctx.spawn_actor().supervise(packager);
         let index_pipeline_id = IndexingPipelineId {
             index_uid: self.index_uid.clone(),
@@ -223,6 +244,38 @@ impl DeleteTaskPipeline {
         let merge_policy = merge_policy_from_settings(&self.indexing_settings);
         let doc_mapper_str = serde_json::to_string(&doc_mapper)?;
         let index_uri: &Uri = &index_config.index_uri;
+        // Split upgrades share the rest of the pipeline, and therefore its write throughput
+        // limit, with delete operations.
+        let split_upgrade_planner_supervisor_handler_opt =
+            if self.split_upgrade_config.enable_upgrades {
+                let split_upgrade_planner = SplitUpgradePlanner::new(
+                    self.index_uid.clone(),
+                    self.metastore.clone(),
+                    merge_policy.clone(),
+                    downloader_mailbox.clone(),
+                    self.split_upgrade_config.max_concurrent_upgrades.get(),
+                );
+                let (_, split_upgrade_planner_supervisor_handler) =
+                    ctx.spawn_actor().supervise(split_upgrade_planner);
+                Some(split_upgrade_planner_supervisor_handler)
+            } else {
+                None
+            };
+        let split_reshard_planner_supervisor_handler_opt =
+            if let Some(resharding_policy) = &index_config.resharding_policy {
+                let split_reshard_planner = SplitReshardPlanner::new(
+                    self.index_uid.clone(),
+                    resharding_policy.field().to_string(),
+                    self.metastore.clone(),
+                    merge_policy.clone(),
+                    downloader_mailbox.clone(),
+                );
+                let (_, split_reshard_planner_supervisor_handler) =
+                    ctx.spawn_actor().supervise(split_reshard_planner);
+                Some(split_reshard_planner_supervisor_handler)
+            } else {
+                None
+            };
         let task_planner = DeleteTaskPlanner::new(
             self.index_uid.clone(),
             index_uri.clone(),
@@ -235,6 +288,8 @@ impl DeleteTaskPipeline {
         let (_, task_planner_supervisor_handler) = ctx.spawn_actor().supervise(task_planner);
         self.handles = Some(DeletePipelineHandle {
             delete_task_planner: task_planner_supervisor_handler,
+            split_upgrade_planner_opt: split_upgrade_planner_supervisor_handler_opt,
+            split_reshard_planner_opt: split_reshard_planner_supervisor_handler_opt,
             downloader: downloader_supervisor_handler,
             delete_task_executor: task_executor_supervisor_handler,
             packager: packager_supervisor_handler,
@@ -272,8 +327,22 @@ impl Handler<Observe> for DeleteTaskPipeline {
                 handles.uploader.observe(),
                 handles.publisher.observe(),
             );
+            let split_upgrade_planner_opt =
+                if let Some(split_upgrade_planner) = &handles.split_upgrade_planner_opt {
+                    Some(split_upgrade_planner.observe().await.state)
+                } else {
+                    None
+                };
+            let split_reshard_planner_opt =
+                if let Some(split_reshard_planner) = &handles.split_reshard_planner_opt {
+                    Some(split_reshard_planner.observe().await.state)
+                } else {
+                    None
+                };
             self.state = DeleteTaskPipelineState {
                 delete_task_planner: delete_task_planner.state,
+                split_upgrade_planner: split_upgrade_planner_opt,
+                split_reshard_planner: split_reshard_planner_opt,
                 downloader: downloader.state,
                 delete_task_executor: delete_task_executor.state,
                 packager: packager.state,
@@ -293,7 +362,7 @@ mod tests {
     use quickwit_actors::Handler;
     use quickwit_common::temp_dir::TempDirectory;
     use quickwit_config::merge_policy_config::MergePolicyConfig;
-    use quickwit_config::IndexingSettings;
+    use quickwit_config::{IndexingSettings, SplitUpgradeConfig};
     use quickwit_indexing::TestSandbox;
     use quickwit_metastore::SplitState;
     use quickwit_proto::metastore_api::DeleteQuery;
@@ -388,6 +457,7 @@ mod tests {
             test_sandbox.storage(),
             delete_service_task_dir.path().into(),
             4,
+            SplitUpgradeConfig::default(),
         );
 
         let (pipeline_mailbox, pipeline_handler) =
@@ -461,6 +531,7 @@ mod tests {
             test_sandbox.storage(),
             delete_service_task_dir.path().into(),
             4,
+            SplitUpgradeConfig::default(),
         );
 
         let (_pipeline_mailbox, pipeline_handler) =
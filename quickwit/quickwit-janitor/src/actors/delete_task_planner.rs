@@ -34,6 +34,7 @@ use quickwit_metastore::{
 };
 use quickwit_proto::metastore_api::DeleteTask;
 use quickwit_proto::{IndexUid, SearchRequest};
+use quickwit_query::query_ast::query_ast_from_json;
 use quickwit_search::{jobs_to_leaf_request, SearchJob, SearchJobPlacer};
 use serde::Serialize;
 use tantivy::Inventory;
@@ -238,7 +239,7 @@ impl DeleteTaskPlanner {
                         delete_query.end_timestamp,
                     );
                     // TODO: validate the query at the beginning and return an appropriate error.
-                    let delete_query_ast = serde_json::from_str(&delete_query.query_ast)
+                    let delete_query_ast = query_ast_from_json(&delete_query.query_ast)
                         .expect("Failed to deserialize query_ast json");
                     let tags_filter = extract_tags_from_query(delete_query_ast);
                     split_time_range_filter(stale_split, time_range.as_ref())
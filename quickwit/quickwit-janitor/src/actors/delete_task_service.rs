@@ -25,12 +25,13 @@ use std::time::Duration;
 use async_trait::async_trait;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, ActorHandle, Handler};
 use quickwit_common::temp_dir::{self};
-use quickwit_config::IndexConfig;
+use quickwit_config::{IndexConfig, SplitUpgradeConfig};
 use quickwit_metastore::Metastore;
 use quickwit_proto::IndexUid;
 use quickwit_search::SearchJobPlacer;
 use quickwit_storage::StorageResolver;
 use serde::Serialize;
+use time::OffsetDateTime;
 use tracing::{error, info, warn};
 
 use super::delete_task_pipeline::DeleteTaskPipeline;
@@ -57,6 +58,7 @@ pub struct DeleteTaskService {
     delete_service_task_dir: PathBuf,
     pipeline_handles_by_index_uid: HashMap<IndexUid, ActorHandle<DeleteTaskPipeline>>,
     max_concurrent_split_uploads: usize,
+    split_upgrade_config: SplitUpgradeConfig,
 }
 
 impl DeleteTaskService {
@@ -66,6 +68,7 @@ impl DeleteTaskService {
         storage_resolver: StorageResolver,
         data_dir_path: PathBuf,
         max_concurrent_split_uploads: usize,
+        split_upgrade_config: SplitUpgradeConfig,
     ) -> anyhow::Result<Self> {
         let delete_service_task_path = data_dir_path.join(DELETE_SERVICE_TASK_DIR_NAME);
         let delete_service_task_dir =
@@ -77,6 +80,7 @@ impl DeleteTaskService {
             delete_service_task_dir,
             pipeline_handles_by_index_uid: Default::default(),
             max_concurrent_split_uploads,
+            split_upgrade_config,
         })
     }
 }
@@ -106,11 +110,17 @@ impl DeleteTaskService {
         &mut self,
         ctx: &ActorContext<Self>,
     ) -> anyhow::Result<()> {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
         let mut index_config_by_index_id: HashMap<IndexUid, IndexConfig> = self
             .metastore
             .list_indexes_metadatas()
             .await?
             .into_iter()
+            // Splits of closed, frozen, and retention locked indexes must not be rewritten.
+            .filter(|index_metadata| {
+                index_metadata.state.is_writable()
+                    && !index_metadata.is_retention_locked(now_timestamp)
+            })
             .map(|index_metadata| {
                 (
                     index_metadata.index_uid.clone(),
@@ -122,11 +132,11 @@ impl DeleteTaskService {
         let pipeline_index_uids: HashSet<IndexUid> =
             self.pipeline_handles_by_index_uid.keys().cloned().collect();
 
-        // Remove pipelines on deleted indexes.
+        // Remove pipelines on deleted, closed, or frozen indexes.
         for deleted_index_uid in pipeline_index_uids.difference(&index_uids) {
             info!(
                 deleted_index_id = deleted_index_uid.index_id(),
-                "Remove deleted index from delete task pipelines."
+                "Remove deleted or read-only index from delete task pipelines."
             );
             let pipeline_handle = self
                 .pipeline_handles_by_index_uid
@@ -171,6 +181,7 @@ impl DeleteTaskService {
             index_storage,
             self.delete_service_task_dir.clone(),
             self.max_concurrent_split_uploads,
+            self.split_upgrade_config.clone(),
         );
         let (_pipeline_mailbox, pipeline_handler) = ctx.spawn_actor().spawn(pipeline);
         self.pipeline_handles_by_index_uid
@@ -203,7 +214,9 @@ impl Handler<UpdatePipelines> for DeleteTaskService {
 
 #[cfg(test)]
 mod tests {
+    use quickwit_config::SplitUpgradeConfig;
     use quickwit_indexing::TestSandbox;
+    use quickwit_metastore::IndexState;
     use quickwit_proto::metastore_api::DeleteQuery;
     use quickwit_search::{searcher_pool_for_test, MockSearchService, SearchJobPlacer};
     use quickwit_storage::StorageResolver;
@@ -236,6 +249,7 @@ mod tests {
             StorageResolver::unconfigured(),
             data_dir_path,
             4,
+            SplitUpgradeConfig::default(),
         )
         .await
         .unwrap();
@@ -263,6 +277,31 @@ mod tests {
                 .len(),
             1
         );
+        // Frozen indexes are read-only: their delete task pipeline is stopped.
+        metastore
+            .set_index_state(index_uid.clone(), IndexState::Frozen)
+            .await
+            .unwrap();
+        test_sandbox
+            .universe()
+            .sleep(UPDATE_PIPELINES_INTERVAL * 2)
+            .await;
+        let state_after_freeze = delete_task_service_handler
+            .process_pending_and_observe()
+            .await;
+        assert_eq!(state_after_freeze.num_running_pipelines, 0);
+        metastore
+            .set_index_state(index_uid.clone(), IndexState::Open)
+            .await
+            .unwrap();
+        test_sandbox
+            .universe()
+            .sleep(UPDATE_PIPELINES_INTERVAL * 2)
+            .await;
+        let state_after_reopen = delete_task_service_handler
+            .process_pending_and_observe()
+            .await;
+        assert_eq!(state_after_reopen.num_running_pipelines, 1);
         metastore.delete_index(index_uid.clone()).await.unwrap();
         test_sandbox
             .universe()
@@ -0,0 +1,285 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use quickwit_actors::{Actor, ActorContext, Handler};
+use quickwit_config::IndexConfig;
+use quickwit_metastore::Metastore;
+use quickwit_proto::IndexUid;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::{debug, error, info};
+
+use super::retention_policy_executor::compute_deleted_indexes;
+use crate::document_ttl_execution::run_execute_document_ttl_policy;
+
+const RUN_INTERVAL: Duration = Duration::from_secs(60 * 60); // 1 hours
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DocumentTtlExecutorCounters {
+    /// The number of refresh the config passes.
+    pub num_refresh_passes: usize,
+
+    /// The number of execution passes.
+    pub num_execution_passes: usize,
+
+    /// The number of delete tasks created to remove expired documents.
+    pub num_delete_tasks: usize,
+}
+
+#[derive(Debug)]
+struct Loop;
+
+#[derive(Debug)]
+struct Execute {
+    index_uid: IndexUid,
+}
+
+/// An actor for scheduling the deletion of expired documents on all indexes.
+/// It keeps a list of indexes that have a document TTL policy configured
+/// in a cache and periodically update this list.
+pub struct DocumentTtlExecutor {
+    metastore: Arc<dyn Metastore>,
+    /// A map of index_id to index config that are managed by this executor.
+    index_configs: HashMap<String, IndexConfig>,
+    counters: DocumentTtlExecutorCounters,
+}
+
+impl DocumentTtlExecutor {
+    pub fn new(metastore: Arc<dyn Metastore>) -> Self {
+        Self {
+            metastore,
+            index_configs: HashMap::new(),
+            counters: DocumentTtlExecutorCounters::default(),
+        }
+    }
+
+    /// Indexes refresh Loop handler logic.
+    /// Should not return an error to prevent the actor from crashing.
+    async fn handle_refresh_loop(&mut self, ctx: &ActorContext<Self>) {
+        debug!("document-ttl-refresh-indexes-operation");
+        self.counters.num_refresh_passes += 1;
+
+        let index_metadatas = match self.metastore.list_indexes_metadatas().await {
+            Ok(metadatas) => metadatas,
+            Err(error) => {
+                error!(error=?error, "Failed to list indexes from the metastore.");
+                return;
+            }
+        };
+        let deleted_indexes = compute_deleted_indexes(
+            self.index_configs.keys().map(String::as_str),
+            index_metadatas
+                .iter()
+                .map(|index_metadata| index_metadata.index_id()),
+        );
+        if !deleted_indexes.is_empty() {
+            debug!(index_ids=%deleted_indexes.iter().join(", "), "Deleting indexes from cache.");
+            for index_id in deleted_indexes {
+                self.index_configs.remove(&index_id);
+            }
+        }
+
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        for index_metadata in index_metadatas {
+            // Indexes under a retention lock are left untouched until the lock expires.
+            if index_metadata.is_retention_locked(now_timestamp) {
+                self.index_configs.remove(index_metadata.index_id());
+                continue;
+            }
+            let index_uid = index_metadata.index_uid.clone();
+            let index_config = index_metadata.into_index_config();
+            let Some(document_ttl_policy) = &index_config.document_ttl_policy else {
+                self.index_configs.remove(&index_config.index_id);
+                continue;
+            };
+            if let Some(value) = self.index_configs.get_mut(&index_config.index_id) {
+                *value = index_config;
+                continue;
+            }
+            if let Ok(next_interval) = document_ttl_policy.duration_until_next_evaluation() {
+                let message = Execute { index_uid };
+                info!(index_id=?index_config.index_id, scheduled_in=?next_interval, "document-ttl-schedule-operation");
+                self.index_configs
+                    .insert(index_config.index_id.clone(), index_config);
+                ctx.schedule_self_msg(next_interval, message).await;
+            } else {
+                error!(index_id=%index_config.index_id, "Couldn't extract the index next schedule time.")
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Actor for DocumentTtlExecutor {
+    type ObservableState = DocumentTtlExecutorCounters;
+
+    fn observable_state(&self) -> Self::ObservableState {
+        self.counters.clone()
+    }
+
+    fn name(&self) -> String {
+        "DocumentTtlExecutor".to_string()
+    }
+
+    async fn initialize(
+        &mut self,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), quickwit_actors::ActorExitStatus> {
+        self.handle(Loop, ctx).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<Loop> for DocumentTtlExecutor {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: Loop,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), quickwit_actors::ActorExitStatus> {
+        self.handle_refresh_loop(ctx).await;
+        ctx.schedule_self_msg(RUN_INTERVAL, Loop).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<Execute> for DocumentTtlExecutor {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: Execute,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), quickwit_actors::ActorExitStatus> {
+        info!(index_id=%message.index_uid.index_id(), "document-ttl-execute-operation");
+        self.counters.num_execution_passes += 1;
+
+        let Some(index_config) = self.index_configs.get(message.index_uid.index_id()) else {
+            debug!(index_id=%message.index_uid.index_id(), "The index might have been deleted.");
+            return Ok(());
+        };
+        let document_ttl_policy = index_config
+            .document_ttl_policy
+            .as_ref()
+            .expect("Expected index to have a document TTL policy configured.");
+
+        let execution_result = run_execute_document_ttl_policy(
+            message.index_uid.clone(),
+            self.metastore.clone(),
+            document_ttl_policy,
+            ctx,
+        )
+        .await;
+        match execution_result {
+            Ok(_delete_task) => self.counters.num_delete_tasks += 1,
+            Err(error) => {
+                error!(index_id=%message.index_uid.index_id(), error=?error, "Failed to execute the document TTL policy on the index.")
+            }
+        }
+
+        if let Ok(next_interval) = document_ttl_policy.duration_until_next_evaluation() {
+            info!(index_id=?index_config.index_id, scheduled_in=?next_interval, "document-ttl-schedule-operation");
+            ctx.schedule_self_msg(next_interval, message).await;
+        } else {
+            // The index is added back and rescheduled by the next cache refresh loop.
+            self.index_configs.remove(message.index_uid.index_id());
+            error!(index_id=%message.index_uid.index_id(), "Couldn't extract the index next schedule interval.");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_actors::Universe;
+    use quickwit_config::DocumentTtlPolicy;
+    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_proto::metastore_api::DeleteTask;
+    use quickwit_query::query_ast::{query_ast_from_json, QueryAst};
+
+    use super::*;
+
+    const SCHEDULE_EXPR: &str = "hourly";
+
+    fn make_index_metadata(index_id: &str, ttl_field_opt: Option<&str>) -> IndexMetadata {
+        let mut index_config =
+            IndexConfig::for_test(index_id, &format!("ram://indexes/{index_id}"));
+        index_config.document_ttl_policy = ttl_field_opt.map(|ttl_field| {
+            DocumentTtlPolicy::new(ttl_field.to_string(), SCHEDULE_EXPR.to_string())
+        });
+        IndexMetadata::new(index_config)
+    }
+
+    #[tokio::test]
+    async fn test_document_ttl_execution_creates_delete_tasks() -> anyhow::Result<()> {
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_list_indexes_metadatas()
+            .times(..)
+            .returning(|| {
+                Ok(vec![
+                    make_index_metadata("a", Some("response_date")),
+                    make_index_metadata("b", None),
+                ])
+            });
+        mock_metastore
+            .expect_create_delete_task()
+            .times(1..=2)
+            .returning(|delete_query| {
+                let index_uid = IndexUid::from(delete_query.index_uid.clone());
+                assert_eq!(index_uid.index_id(), "a");
+                let query_ast = query_ast_from_json(&delete_query.query_ast).unwrap();
+                let QueryAst::Range(range_query) = query_ast else {
+                    panic!("Expected a range query, got `{query_ast:?}`.");
+                };
+                assert_eq!(range_query.field, "response_date");
+                Ok(DeleteTask {
+                    opstamp: 1,
+                    create_timestamp: 0,
+                    delete_query: Some(delete_query),
+                })
+            });
+
+        let document_ttl_executor = DocumentTtlExecutor::new(Arc::new(mock_metastore));
+        let universe = Universe::with_accelerated_time();
+        let (_mailbox, handle) = universe.spawn_builder().spawn(document_ttl_executor);
+
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_refresh_passes, 1);
+        assert_eq!(counters.num_execution_passes, 0);
+
+        let schedule_policy = DocumentTtlPolicy::new(String::new(), SCHEDULE_EXPR.to_string());
+        let shift_time_by =
+            schedule_policy.duration_until_next_evaluation()? + Duration::from_secs(1);
+        universe.sleep(shift_time_by).await;
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_execution_passes, 1);
+        assert_eq!(counters.num_delete_tasks, 1);
+        universe.assert_quit().await;
+        Ok(())
+    }
+}
@@ -28,6 +28,7 @@ use quickwit_actors::{Actor, ActorContext, Handler};
 use quickwit_metastore::Metastore;
 use quickwit_storage::StorageResolver;
 use serde::Serialize;
+use time::OffsetDateTime;
 use tracing::{error, info};
 
 use crate::garbage_collection::run_garbage_collect;
@@ -99,6 +100,14 @@ impl GarbageCollector {
                 return;
             }
         };
+        // The splits of the indexes under a retention lock are kept until the lock expires.
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let (locked_indexes, indexes): (Vec<_>, Vec<_>) = indexes
+            .into_iter()
+            .partition(|index| index.is_retention_locked(now_timestamp));
+        if !locked_indexes.is_empty() {
+            info!(index_ids=%locked_indexes.iter().map(|im| im.index_id()).join(", "), "Skipping indexes under a retention lock.");
+        }
         info!(index_ids=%indexes.iter().map(|im| im.index_id()).join(", "), "Garbage collecting indexes.");
 
         let mut gc_futures = stream::iter(indexes).map(|index| {
@@ -472,6 +481,37 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_garbage_collect_skips_retention_locked_indexes() {
+        let storage_resolver = StorageResolver::unconfigured();
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_list_indexes_metadatas()
+            .times(1)
+            .returning(|| {
+                let mut index_metadata =
+                    IndexMetadata::for_test("test-index", "ram://indexes/test-index");
+                index_metadata.retention_locked_until =
+                    Some(OffsetDateTime::now_utc().unix_timestamp() + 3600);
+                Ok(vec![index_metadata])
+            });
+        mock_metastore.expect_list_splits().never();
+        mock_metastore.expect_mark_splits_for_deletion().never();
+        mock_metastore.expect_delete_splits().never();
+
+        let garbage_collect_actor =
+            GarbageCollector::new(Arc::new(mock_metastore), storage_resolver);
+        let universe = Universe::with_accelerated_time();
+        let (_mailbox, handle) = universe.spawn_builder().spawn(garbage_collect_actor);
+
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_passes, 1);
+        assert_eq!(counters.num_deleted_files, 0);
+        assert_eq!(counters.num_successful_gc_run_on_index, 0);
+        assert_eq!(counters.num_failed_gc_run_on_index, 0);
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_garbage_collect_get_called_repeatedly_on_failure() {
         let storage_resolver = StorageResolver::unconfigured();
@@ -20,9 +20,17 @@
 mod delete_task_pipeline;
 mod delete_task_planner;
 mod delete_task_service;
+mod document_ttl_executor;
 mod garbage_collector;
 mod retention_policy_executor;
+mod rollup_executor;
+mod split_reshard_planner;
+mod split_upgrade_planner;
 
 pub use delete_task_service::{DeleteTaskService, DELETE_SERVICE_TASK_DIR_NAME};
+pub use document_ttl_executor::DocumentTtlExecutor;
 pub use garbage_collector::GarbageCollector;
 pub use retention_policy_executor::RetentionPolicyExecutor;
+pub use rollup_executor::RollupExecutor;
+pub use split_reshard_planner::{SplitReshardPlanner, SplitReshardPlannerState};
+pub use split_upgrade_planner::{SplitUpgradePlanner, SplitUpgradePlannerState};
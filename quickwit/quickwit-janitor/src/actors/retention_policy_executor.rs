@@ -28,6 +28,7 @@ use quickwit_config::IndexConfig;
 use quickwit_metastore::Metastore;
 use quickwit_proto::IndexUid;
 use serde::Serialize;
+use time::OffsetDateTime;
 use tracing::{debug, error, info};
 
 use crate::retention_policy_execution::run_execute_retention_policy;
@@ -103,7 +104,13 @@ impl RetentionPolicyExecutor {
             }
         }
 
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
         for index_metadata in index_metadatas {
+            // Indexes under a retention lock are left untouched until the lock expires.
+            if index_metadata.is_retention_locked(now_timestamp) {
+                self.index_configs.remove(index_metadata.index_id());
+                continue;
+            }
             let index_uid = index_metadata.index_uid.clone();
             let index_config = index_metadata.into_index_config();
             // We only care about indexes with a retention policy configured.
@@ -229,7 +236,7 @@ impl Handler<Execute> for RetentionPolicyExecutor {
 }
 
 /// Extract the list of deleted indexes.
-fn compute_deleted_indexes<'a>(
+pub(super) fn compute_deleted_indexes<'a>(
     cached_indexes: impl Iterator<Item = &'a str>,
     indexes: impl Iterator<Item = &'a str>,
 ) -> HashSet<String> {
@@ -411,6 +418,34 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_retention_executor_skips_retention_locked_indexes() -> anyhow::Result<()> {
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_list_indexes_metadatas()
+            .times(1)
+            .returning(|| {
+                let mut indexes = make_indexes(&[("a", Some("1 hour")), ("b", Some("1 hour"))]);
+                indexes[0].retention_locked_until =
+                    Some(OffsetDateTime::now_utc().unix_timestamp() + 3600);
+                Ok(indexes)
+            });
+        mock_metastore.expect_mark_splits_for_deletion().never();
+
+        let retention_policy_executor = RetentionPolicyExecutor::new(Arc::new(mock_metastore));
+        let universe = Universe::with_accelerated_time();
+        let (mailbox, handle) = universe.spawn_builder().spawn(retention_policy_executor);
+
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_refresh_passes, 1);
+        mailbox
+            .ask(AssertState(vec![("b", Some("1 hour"))]))
+            .await?;
+        universe.assert_quit().await;
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_retention_policy_execution_calls_dependencies() -> anyhow::Result<()> {
         let mut mock_metastore = MockMetastore::default();
@@ -0,0 +1,326 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use quickwit_actors::{Actor, ActorContext, Handler};
+use quickwit_config::RollupPolicy;
+use quickwit_ingest::IngestServiceClient;
+use quickwit_metastore::Metastore;
+use quickwit_search::SearchService;
+use serde::Serialize;
+use tracing::{debug, error, info};
+
+use crate::rollup_execution::run_execute_rollup_policy;
+
+const RUN_INTERVAL: Duration = Duration::from_secs(60 * 60); // 1 hours
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RollupExecutorCounters {
+    /// The number of refresh the config passes.
+    pub num_refresh_passes: usize,
+
+    /// The number of execution passes.
+    pub num_execution_passes: usize,
+
+    /// The number of rollup documents ingested into the rollup indexes.
+    pub num_rollup_docs: usize,
+}
+
+#[derive(Debug)]
+struct Loop;
+
+/// Identifies a rollup policy by the IDs of the rolled up index and of the rollup index.
+type RollupKey = (String, String);
+
+#[derive(Debug)]
+struct Execute {
+    rollup_key: RollupKey,
+}
+
+/// An actor for scheduling the rollup jobs of all indexes.
+/// It keeps a list of the rollup policies configured on the indexes
+/// in a cache and periodically update this list.
+pub struct RollupExecutor {
+    metastore: Arc<dyn Metastore>,
+    search_service: Arc<dyn SearchService>,
+    ingest_service: IngestServiceClient,
+    /// A map of (index_id, rollup_index_id) to rollup policy that are managed by this executor.
+    rollup_policies: HashMap<RollupKey, RollupPolicy>,
+    counters: RollupExecutorCounters,
+}
+
+impl RollupExecutor {
+    pub fn new(
+        metastore: Arc<dyn Metastore>,
+        search_service: Arc<dyn SearchService>,
+        ingest_service: IngestServiceClient,
+    ) -> Self {
+        Self {
+            metastore,
+            search_service,
+            ingest_service,
+            rollup_policies: HashMap::new(),
+            counters: RollupExecutorCounters::default(),
+        }
+    }
+
+    /// Rollup policies refresh Loop handler logic.
+    /// Should not return an error to prevent the actor from crashing.
+    async fn handle_refresh_loop(&mut self, ctx: &ActorContext<Self>) {
+        debug!("rollup-refresh-indexes-operation");
+        self.counters.num_refresh_passes += 1;
+
+        let index_metadatas = match self.metastore.list_indexes_metadatas().await {
+            Ok(metadatas) => metadatas,
+            Err(error) => {
+                error!(error=?error, "Failed to list indexes from the metastore.");
+                return;
+            }
+        };
+        let mut rollup_policies: HashMap<RollupKey, RollupPolicy> = HashMap::new();
+
+        for index_metadata in index_metadatas {
+            let index_config = index_metadata.into_index_config();
+
+            for rollup_policy in index_config.rollup_policies {
+                let rollup_key = (
+                    index_config.index_id.clone(),
+                    rollup_policy.rollup_index_id().to_string(),
+                );
+                rollup_policies.insert(rollup_key, rollup_policy);
+            }
+        }
+        self.rollup_policies
+            .retain(|rollup_key, _| rollup_policies.contains_key(rollup_key));
+
+        for (rollup_key, rollup_policy) in rollup_policies {
+            if let Some(value) = self.rollup_policies.get_mut(&rollup_key) {
+                *value = rollup_policy;
+                continue;
+            }
+            let (index_id, rollup_index_id) = &rollup_key;
+
+            if let Ok(next_interval) = rollup_policy.duration_until_next_evaluation() {
+                info!(index_id=%index_id, rollup_index_id=%rollup_index_id, scheduled_in=?next_interval, "rollup-schedule-operation");
+                let message = Execute {
+                    rollup_key: rollup_key.clone(),
+                };
+                self.rollup_policies.insert(rollup_key, rollup_policy);
+                ctx.schedule_self_msg(next_interval, message).await;
+            } else {
+                error!(index_id=%index_id, rollup_index_id=%rollup_index_id, "Couldn't extract the rollup next schedule time.")
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Actor for RollupExecutor {
+    type ObservableState = RollupExecutorCounters;
+
+    fn observable_state(&self) -> Self::ObservableState {
+        self.counters.clone()
+    }
+
+    fn name(&self) -> String {
+        "RollupExecutor".to_string()
+    }
+
+    async fn initialize(
+        &mut self,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), quickwit_actors::ActorExitStatus> {
+        self.handle(Loop, ctx).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<Loop> for RollupExecutor {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: Loop,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), quickwit_actors::ActorExitStatus> {
+        self.handle_refresh_loop(ctx).await;
+        ctx.schedule_self_msg(RUN_INTERVAL, Loop).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<Execute> for RollupExecutor {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: Execute,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), quickwit_actors::ActorExitStatus> {
+        let (index_id, rollup_index_id) = &message.rollup_key;
+        info!(index_id=%index_id, rollup_index_id=%rollup_index_id, "rollup-execute-operation");
+        self.counters.num_execution_passes += 1;
+
+        let Some(rollup_policy) = self.rollup_policies.get(&message.rollup_key) else {
+            debug!(index_id=%index_id, rollup_index_id=%rollup_index_id, "The rollup policy might have been removed.");
+            return Ok(());
+        };
+        let execution_result = run_execute_rollup_policy(
+            index_id,
+            self.metastore.clone(),
+            self.search_service.clone(),
+            self.ingest_service.clone(),
+            rollup_policy,
+            ctx,
+        )
+        .await;
+        match execution_result {
+            Ok(num_rollup_docs) => self.counters.num_rollup_docs += num_rollup_docs,
+            Err(error) => {
+                error!(index_id=%index_id, rollup_index_id=%rollup_index_id, error=?error, "Failed to execute the rollup policy on the index.")
+            }
+        }
+
+        if let Ok(next_interval) = rollup_policy.duration_until_next_evaluation() {
+            info!(index_id=%index_id, rollup_index_id=%rollup_index_id, scheduled_in=?next_interval, "rollup-schedule-operation");
+            ctx.schedule_self_msg(next_interval, message).await;
+        } else {
+            // The rollup policy is added back and rescheduled by the next cache refresh loop.
+            error!(index_id=%index_id, rollup_index_id=%rollup_index_id, "Couldn't extract the rollup next schedule interval.");
+            self.rollup_policies.remove(&message.rollup_key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_actors::Universe;
+    use quickwit_config::IndexConfig;
+    use quickwit_metastore::{IndexMetadata, MockMetastore, Split, SplitMetadata, SplitState};
+    use quickwit_proto::SearchResponse;
+    use quickwit_search::MockSearchService;
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    fn make_index_metadata(index_id: &str, rollup_index_id_opt: Option<&str>) -> IndexMetadata {
+        let mut index_config =
+            IndexConfig::for_test(index_id, &format!("ram://indexes/{index_id}"));
+        index_config.rollup_policies = rollup_index_id_opt
+            .map(|rollup_index_id| {
+                RollupPolicy::new(
+                    rollup_index_id.to_string(),
+                    "1 minute".to_string(),
+                    Vec::new(),
+                )
+            })
+            .into_iter()
+            .collect();
+        IndexMetadata::new(index_config)
+    }
+
+    #[tokio::test]
+    async fn test_rollup_execution_ingests_rollup_documents() -> anyhow::Result<()> {
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_list_indexes_metadatas()
+            .times(..)
+            .returning(|| {
+                Ok(vec![
+                    make_index_metadata("a", Some("a-rollup")),
+                    make_index_metadata("b", None),
+                ])
+            });
+        mock_metastore
+            .expect_index_metadata()
+            .times(..)
+            .returning(|index_id| Ok(make_index_metadata(index_id, None)));
+        mock_metastore
+            .expect_list_splits()
+            .times(..)
+            .returning(|query| {
+                // The rollup index is empty, the documents of the rolled up index span the last
+                // hour.
+                if query.index_uid.index_id() != "a" {
+                    return Ok(Vec::new());
+                }
+                let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+                let split = Split {
+                    split_state: SplitState::Published,
+                    update_timestamp: 0,
+                    publish_timestamp: None,
+                    split_metadata: SplitMetadata {
+                        time_range: Some(now_timestamp - 3_600..=now_timestamp),
+                        ..Default::default()
+                    },
+                };
+                Ok(vec![split])
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(1..)
+            .returning(|search_request| {
+                assert_eq!(search_request.index_id, "a");
+                assert_eq!(search_request.max_hits, 0);
+                let aggregation = r#"{"rollup": {"buckets": [{"key": 60000.0, "doc_count": 3}]}}"#;
+                Ok(SearchResponse {
+                    aggregation: Some(aggregation.to_string()),
+                    ..Default::default()
+                })
+            });
+        let mut mock_ingest_service = IngestServiceClient::mock();
+        mock_ingest_service
+            .expect_ingest()
+            .times(1..)
+            .returning(|ingest_request| {
+                assert_eq!(ingest_request.doc_batches.len(), 1);
+                assert_eq!(ingest_request.doc_batches[0].index_id, "a-rollup");
+                Ok(Default::default())
+            });
+        let rollup_executor = RollupExecutor::new(
+            Arc::new(mock_metastore),
+            Arc::new(mock_search_service),
+            IngestServiceClient::from(mock_ingest_service),
+        );
+        let universe = Universe::with_accelerated_time();
+        let (_mailbox, handle) = universe.spawn_builder().spawn(rollup_executor);
+
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_refresh_passes, 1);
+        assert_eq!(counters.num_execution_passes, 0);
+
+        let schedule_policy =
+            RollupPolicy::new("a-rollup".to_string(), "1 minute".to_string(), Vec::new());
+        let shift_time_by =
+            schedule_policy.duration_until_next_evaluation()? + Duration::from_secs(1);
+        universe.sleep(shift_time_by).await;
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_execution_passes, 1);
+        assert!(counters.num_rollup_docs >= 1);
+        universe.assert_quit().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
+use quickwit_indexing::actors::MergeSplitDownloader;
+use quickwit_indexing::merge_policy::{MergeOperation, MergePolicy};
+use quickwit_metastore::{ListSplitsQuery, Metastore, SplitMetadata, SplitState};
+use quickwit_proto::IndexUid;
+use serde::Serialize;
+use tantivy::Inventory;
+use tracing::info;
+
+use crate::metrics::JANITOR_METRICS;
+
+const PLANNER_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Resharding a split rewrites all its documents, so operations are run one at a time per index.
+const MAX_CONCURRENT_RESHARD_OPERATIONS: usize = 1;
+
+/// The `SplitReshardPlanner` plans the rewrite of the published splits of an index holding
+/// documents with several values for the tag field of the index resharding policy into one
+/// split per value. Once resharded, the data of a given value (a tenant, for instance) can be
+/// pruned at search time or dropped by marking its splits for deletion.
+///
+/// The values of the field are read from the tags of the splits, so splits holding more
+/// distinct values than the number of values recorded in the tags are left untouched.
+///
+/// Like upgrade operations, reshard operations flow through the same downloader, executor,
+/// packager, uploader, and publisher as delete operations. The executor verifies that the
+/// resharded splits contain, all together, the same number of documents as the original split,
+/// the packager computes the tags of each resharded split, and the resharded splits replace the
+/// original split atomically on publish.
+pub struct SplitReshardPlanner {
+    index_uid: IndexUid,
+    field: String,
+    metastore: Arc<dyn Metastore>,
+    merge_policy: Arc<dyn MergePolicy>,
+    merge_split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+    num_splits_to_reshard: usize,
+    /// Inventory of ongoing reshard operations. A reshard operation is dropped after the publish
+    /// of the resharded splits. The inventory is used to avoid sending twice the same reshard
+    /// operation and to cap the number of concurrent operations.
+    ongoing_reshard_operations_inventory: Inventory<MergeOperation>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SplitReshardPlannerState {
+    pub num_splits_to_reshard: usize,
+    pub ongoing_reshard_operations: Vec<MergeOperation>,
+}
+
+#[async_trait]
+impl Actor for SplitReshardPlanner {
+    type ObservableState = SplitReshardPlannerState;
+
+    fn observable_state(&self) -> Self::ObservableState {
+        let ongoing_reshard_operations = self
+            .ongoing_reshard_operations_inventory
+            .list()
+            .iter()
+            .map(|tracked_operation| tracked_operation.as_ref().clone())
+            .collect_vec();
+        SplitReshardPlannerState {
+            num_splits_to_reshard: self.num_splits_to_reshard,
+            ongoing_reshard_operations,
+        }
+    }
+
+    fn name(&self) -> String {
+        "SplitReshardPlanner".to_string()
+    }
+
+    fn queue_capacity(&self) -> QueueCapacity {
+        QueueCapacity::Bounded(0)
+    }
+
+    async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
+        self.handle(PlanReshardLoop, ctx).await
+    }
+}
+
+impl SplitReshardPlanner {
+    pub fn new(
+        index_uid: IndexUid,
+        field: String,
+        metastore: Arc<dyn Metastore>,
+        merge_policy: Arc<dyn MergePolicy>,
+        merge_split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+    ) -> Self {
+        Self {
+            index_uid,
+            field,
+            metastore,
+            merge_policy,
+            merge_split_downloader_mailbox,
+            num_splits_to_reshard: 0,
+            ongoing_reshard_operations_inventory: Inventory::new(),
+        }
+    }
+
+    /// Returns true if the tags of the split show several values for the resharding field.
+    fn needs_resharding(&self, split: &SplitMetadata) -> bool {
+        let tag_prefix = format!("{}:", self.field);
+        split
+            .tags
+            .iter()
+            .filter(|tag| tag.starts_with(&tag_prefix))
+            .nth(1)
+            .is_some()
+    }
+
+    /// Sends reshard operations for the oldest splits holding several values of the resharding
+    /// field, up to `MAX_CONCURRENT_RESHARD_OPERATIONS` ongoing operations.
+    async fn send_reshard_operations(&mut self, ctx: &ActorContext<Self>) -> anyhow::Result<()> {
+        let query = ListSplitsQuery::for_index(self.index_uid.clone())
+            .with_split_state(SplitState::Published);
+        let published_splits = ctx
+            .protect_future(self.metastore.list_splits(query))
+            .await?;
+        ctx.record_progress();
+
+        let mut splits_to_reshard: Vec<SplitMetadata> = published_splits
+            .into_iter()
+            .map(|split| split.split_metadata)
+            .filter(|split| self.needs_resharding(split))
+            .collect();
+        self.num_splits_to_reshard = splits_to_reshard.len();
+        JANITOR_METRICS
+            .num_splits_to_reshard
+            .with_label_values([self.index_uid.index_id()])
+            .set(splits_to_reshard.len() as i64);
+
+        if splits_to_reshard.is_empty() {
+            return Ok(());
+        }
+        let ongoing_split_ids: HashSet<String> = self
+            .ongoing_reshard_operations_inventory
+            .list()
+            .iter()
+            .flat_map(|operation| operation.splits.iter())
+            .map(|split| split.split_id().to_string())
+            .collect();
+        let num_reshards_to_plan =
+            MAX_CONCURRENT_RESHARD_OPERATIONS.saturating_sub(ongoing_split_ids.len());
+        // Reshards the oldest splits first.
+        splits_to_reshard.sort_by_key(|split| split.create_timestamp);
+        let splits_to_reshard = splits_to_reshard
+            .into_iter()
+            .filter(|split| self.merge_policy.is_mature(split))
+            .filter(|split| !ongoing_split_ids.contains(split.split_id()))
+            .take(num_reshards_to_plan)
+            .collect_vec();
+        info!(
+            index_id = self.index_uid.index_id(),
+            field = %self.field,
+            num_splits_to_reshard = self.num_splits_to_reshard,
+            num_ongoing_reshards = ongoing_split_ids.len(),
+            num_planned_reshards = splits_to_reshard.len()
+        );
+        for split_to_reshard in splits_to_reshard {
+            let reshard_operation =
+                MergeOperation::new_reshard_operation(split_to_reshard, self.field.clone());
+            info!(reshard_operation=?reshard_operation, "Planned reshard operation.");
+            let tracked_reshard_operation = self
+                .ongoing_reshard_operations_inventory
+                .track(reshard_operation);
+            ctx.send_message(
+                &self.merge_split_downloader_mailbox,
+                tracked_reshard_operation,
+            )
+            .await?;
+            JANITOR_METRICS
+                .ongoing_num_reshard_operations_total
+                .with_label_values([self.index_uid.index_id()])
+                .set(self.ongoing_reshard_operations_inventory.list().len() as i64);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct PlanReshardOperations;
+
+#[async_trait]
+impl Handler<PlanReshardOperations> for SplitReshardPlanner {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: PlanReshardOperations,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.send_reshard_operations(ctx).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct PlanReshardLoop;
+
+#[async_trait]
+impl Handler<PlanReshardLoop> for SplitReshardPlanner {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: PlanReshardLoop,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.handle(PlanReshardOperations, ctx).await?;
+        ctx.schedule_self_msg(PLANNER_REFRESH_INTERVAL, PlanReshardLoop)
+            .await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::merge_policy::NopMergePolicy;
+    use quickwit_indexing::{new_split_id, TestSandbox};
+    use tantivy::TrackedObject;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_split_reshard_planner() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = "test-split-reshard-planner";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: tenant_id
+                type: text
+                tokenizer: raw
+            tag_fields: [tenant_id]
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+        // Creates a split shared by two tenants and a split holding the data of a single tenant.
+        test_sandbox
+            .add_documents(vec![
+                serde_json::json!({ "body": "foo", "tenant_id": "tenant-a" }),
+                serde_json::json!({ "body": "bar", "tenant_id": "tenant-b" }),
+            ])
+            .await?;
+        test_sandbox
+            .add_documents(vec![
+                serde_json::json!({ "body": "baz", "tenant_id": "tenant-a" }),
+            ])
+            .await?;
+        let metastore = test_sandbox.metastore();
+        let index_uid = test_sandbox.index_uid();
+        let split_metas: Vec<SplitMetadata> = metastore
+            .list_all_splits(index_uid.clone())
+            .await?
+            .into_iter()
+            .map(|split| split.split_metadata)
+            .collect();
+        assert_eq!(split_metas.len(), 2);
+        let shared_split_meta = split_metas
+            .iter()
+            .find(|split| split.num_docs == 2)
+            .unwrap()
+            .clone();
+
+        let (downloader_mailbox, downloader_inbox) = test_sandbox.universe().create_test_mailbox();
+        let split_reshard_planner = SplitReshardPlanner::new(
+            index_uid.clone(),
+            "tenant_id".to_string(),
+            metastore.clone(),
+            Arc::new(NopMergePolicy),
+            downloader_mailbox,
+        );
+        let (split_reshard_planner_mailbox, split_reshard_planner_handle) = test_sandbox
+            .universe()
+            .spawn_builder()
+            .spawn(split_reshard_planner);
+        let state = split_reshard_planner_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(state.num_splits_to_reshard, 1);
+        assert_eq!(state.ongoing_reshard_operations.len(), 1);
+
+        let downloader_msgs: Vec<TrackedObject<MergeOperation>> =
+            downloader_inbox.drain_for_test_typed();
+        assert_eq!(downloader_msgs.len(), 1);
+        assert_eq!(
+            downloader_msgs[0].splits[0].split_id(),
+            shared_split_meta.split_id()
+        );
+        // The ongoing operation is not planned twice.
+        split_reshard_planner_mailbox
+            .ask(PlanReshardOperations)
+            .await
+            .unwrap();
+        assert!(downloader_inbox.drain_for_test().is_empty());
+
+        // Simulates the publish of the resharded splits.
+        let mut resharded_split_metas = Vec::new();
+        for tenant_id in ["tenant-a", "tenant-b"] {
+            let mut resharded_split_meta = shared_split_meta.clone();
+            resharded_split_meta.split_id = new_split_id();
+            resharded_split_meta.num_docs = 1;
+            resharded_split_meta.tags =
+                ["tenant_id!".to_string(), format!("tenant_id:{tenant_id}")]
+                    .into_iter()
+                    .collect();
+            resharded_split_metas.push(resharded_split_meta);
+        }
+        metastore
+            .stage_splits(index_uid.clone(), resharded_split_metas.clone())
+            .await?;
+        let resharded_split_ids: Vec<&str> = resharded_split_metas
+            .iter()
+            .map(|split| split.split_id())
+            .collect();
+        metastore
+            .publish_splits(
+                index_uid.clone(),
+                &resharded_split_ids,
+                &[shared_split_meta.split_id()],
+                None,
+            )
+            .await?;
+        drop(downloader_msgs);
+
+        split_reshard_planner_mailbox
+            .ask(PlanReshardOperations)
+            .await
+            .unwrap();
+        assert!(downloader_inbox.drain_for_test().is_empty());
+        let state = split_reshard_planner_handle.observe().await.state;
+        assert_eq!(state.num_splits_to_reshard, 0);
+        assert!(state.ongoing_reshard_operations.is_empty());
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+}
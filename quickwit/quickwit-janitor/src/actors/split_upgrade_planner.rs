@@ -0,0 +1,349 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
+use quickwit_indexing::actors::MergeSplitDownloader;
+use quickwit_indexing::merge_policy::{MergeOperation, MergePolicy};
+use quickwit_metastore::{
+    ListSplitsQuery, Metastore, SplitMetadata, SplitState, SPLIT_FORMAT_VERSION,
+};
+use quickwit_proto::IndexUid;
+use serde::Serialize;
+use tantivy::Inventory;
+use tracing::info;
+
+use crate::metrics::JANITOR_METRICS;
+
+const PLANNER_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The `SplitUpgradePlanner` plans the rewrite of the published splits of an index that were
+/// written with a split format version older than [`SPLIT_FORMAT_VERSION`].
+///
+/// Upgrade operations flow through the same downloader, executor, packager, uploader, and
+/// publisher as delete operations. As a result, they are throttled by the index
+/// `max_merge_write_throughput` setting, and at most `max_concurrent_upgrades` operations are in
+/// flight at any time. The executor verifies that the rewritten split contains the same number of
+/// documents as the original split before handing it over to the packager, and the upgraded split
+/// replaces the original split atomically on publish. Since progress is tracked in the metastore
+/// through the `format_version` of splits, the job resumes where it left off after a restart.
+///
+/// Like delete operations, upgrades only run on mature splits: immature splits will eventually be
+/// merged into splits written with the current format by the indexers.
+pub struct SplitUpgradePlanner {
+    index_uid: IndexUid,
+    metastore: Arc<dyn Metastore>,
+    merge_policy: Arc<dyn MergePolicy>,
+    merge_split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+    max_concurrent_upgrades: usize,
+    num_outdated_splits: usize,
+    /// Inventory of ongoing upgrade operations. An upgrade operation is dropped after the publish
+    /// of the upgraded split. The inventory is used to avoid sending twice the same upgrade
+    /// operation and to cap the number of concurrent upgrades.
+    ongoing_upgrade_operations_inventory: Inventory<MergeOperation>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SplitUpgradePlannerState {
+    pub num_outdated_splits: usize,
+    pub ongoing_upgrade_operations: Vec<MergeOperation>,
+}
+
+#[async_trait]
+impl Actor for SplitUpgradePlanner {
+    type ObservableState = SplitUpgradePlannerState;
+
+    fn observable_state(&self) -> Self::ObservableState {
+        let ongoing_upgrade_operations = self
+            .ongoing_upgrade_operations_inventory
+            .list()
+            .iter()
+            .map(|tracked_operation| tracked_operation.as_ref().clone())
+            .collect_vec();
+        SplitUpgradePlannerState {
+            num_outdated_splits: self.num_outdated_splits,
+            ongoing_upgrade_operations,
+        }
+    }
+
+    fn name(&self) -> String {
+        "SplitUpgradePlanner".to_string()
+    }
+
+    fn queue_capacity(&self) -> QueueCapacity {
+        QueueCapacity::Bounded(0)
+    }
+
+    async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
+        self.handle(PlanUpgradeLoop, ctx).await
+    }
+}
+
+impl SplitUpgradePlanner {
+    pub fn new(
+        index_uid: IndexUid,
+        metastore: Arc<dyn Metastore>,
+        merge_policy: Arc<dyn MergePolicy>,
+        merge_split_downloader_mailbox: Mailbox<MergeSplitDownloader>,
+        max_concurrent_upgrades: usize,
+    ) -> Self {
+        Self {
+            index_uid,
+            metastore,
+            merge_policy,
+            merge_split_downloader_mailbox,
+            max_concurrent_upgrades,
+            num_outdated_splits: 0,
+            ongoing_upgrade_operations_inventory: Inventory::new(),
+        }
+    }
+
+    /// Sends upgrade operations for the oldest outdated splits, up to `max_concurrent_upgrades`
+    /// ongoing operations.
+    async fn send_upgrade_operations(&mut self, ctx: &ActorContext<Self>) -> anyhow::Result<()> {
+        let query = ListSplitsQuery::for_index(self.index_uid.clone())
+            .with_split_state(SplitState::Published);
+        let published_splits = ctx
+            .protect_future(self.metastore.list_splits(query))
+            .await?;
+        ctx.record_progress();
+
+        let mut outdated_splits: Vec<SplitMetadata> = published_splits
+            .into_iter()
+            .map(|split| split.split_metadata)
+            .filter(|split| split.format_version < SPLIT_FORMAT_VERSION)
+            .collect();
+        self.num_outdated_splits = outdated_splits.len();
+        JANITOR_METRICS
+            .num_outdated_splits
+            .with_label_values([self.index_uid.index_id()])
+            .set(outdated_splits.len() as i64);
+
+        if outdated_splits.is_empty() {
+            return Ok(());
+        }
+        let ongoing_split_ids: HashSet<String> = self
+            .ongoing_upgrade_operations_inventory
+            .list()
+            .iter()
+            .flat_map(|operation| operation.splits.iter())
+            .map(|split| split.split_id().to_string())
+            .collect();
+        let num_upgrades_to_plan = self
+            .max_concurrent_upgrades
+            .saturating_sub(ongoing_split_ids.len());
+        // Upgrades the oldest splits first.
+        outdated_splits.sort_by_key(|split| split.create_timestamp);
+        let splits_to_upgrade = outdated_splits
+            .into_iter()
+            .filter(|split| self.merge_policy.is_mature(split))
+            .filter(|split| !ongoing_split_ids.contains(split.split_id()))
+            .take(num_upgrades_to_plan)
+            .collect_vec();
+        info!(
+            index_id = self.index_uid.index_id(),
+            num_outdated_splits = self.num_outdated_splits,
+            num_ongoing_upgrades = ongoing_split_ids.len(),
+            num_planned_upgrades = splits_to_upgrade.len()
+        );
+        for split_to_upgrade in splits_to_upgrade {
+            let upgrade_operation = MergeOperation::new_upgrade_operation(split_to_upgrade);
+            info!(upgrade_operation=?upgrade_operation, "Planned upgrade operation.");
+            let tracked_upgrade_operation = self
+                .ongoing_upgrade_operations_inventory
+                .track(upgrade_operation);
+            ctx.send_message(
+                &self.merge_split_downloader_mailbox,
+                tracked_upgrade_operation,
+            )
+            .await?;
+            JANITOR_METRICS
+                .ongoing_num_upgrade_operations_total
+                .with_label_values([self.index_uid.index_id()])
+                .set(self.ongoing_upgrade_operations_inventory.list().len() as i64);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct PlanUpgradeOperations;
+
+#[async_trait]
+impl Handler<PlanUpgradeOperations> for SplitUpgradePlanner {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: PlanUpgradeOperations,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.send_upgrade_operations(ctx).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct PlanUpgradeLoop;
+
+#[async_trait]
+impl Handler<PlanUpgradeLoop> for SplitUpgradePlanner {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: PlanUpgradeLoop,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.handle(PlanUpgradeOperations, ctx).await?;
+        ctx.schedule_self_msg(PLANNER_REFRESH_INTERVAL, PlanUpgradeLoop)
+            .await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::merge_policy::NopMergePolicy;
+    use quickwit_indexing::{new_split_id, TestSandbox};
+    use tantivy::TrackedObject;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_split_upgrade_planner() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = "test-split-upgrade-planner";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+        // Creates 3 splits.
+        for body in ["foo", "bar", "baz"] {
+            test_sandbox
+                .add_documents(vec![serde_json::json!({ "body": body })])
+                .await?;
+        }
+        let metastore = test_sandbox.metastore();
+        let index_uid = test_sandbox.index_uid();
+        let split_metas: Vec<SplitMetadata> = metastore
+            .list_all_splits(index_uid.clone())
+            .await?
+            .into_iter()
+            .map(|split| split.split_metadata)
+            .sorted_by_key(|split| split.create_timestamp)
+            .collect();
+        assert_eq!(split_metas.len(), 3);
+        assert!(split_metas
+            .iter()
+            .all(|split| split.format_version == SPLIT_FORMAT_VERSION));
+
+        // Replaces the first two splits with splits written with a legacy split format.
+        let mut legacy_split_metas = Vec::new();
+        for (create_timestamp, split_meta) in split_metas[..2].iter().enumerate() {
+            let mut legacy_split_meta = split_meta.clone();
+            legacy_split_meta.split_id = new_split_id();
+            legacy_split_meta.create_timestamp = create_timestamp as i64;
+            legacy_split_meta.format_version = 0;
+            metastore
+                .stage_splits(index_uid.clone(), vec![legacy_split_meta.clone()])
+                .await?;
+            metastore
+                .publish_splits(
+                    index_uid.clone(),
+                    &[legacy_split_meta.split_id()],
+                    &[split_meta.split_id()],
+                    None,
+                )
+                .await?;
+            legacy_split_metas.push(legacy_split_meta);
+        }
+        let (downloader_mailbox, downloader_inbox) = test_sandbox.universe().create_test_mailbox();
+        let split_upgrade_planner = SplitUpgradePlanner::new(
+            index_uid.clone(),
+            metastore.clone(),
+            Arc::new(NopMergePolicy),
+            downloader_mailbox,
+            1,
+        );
+        let (split_upgrade_planner_mailbox, split_upgrade_planner_handle) = test_sandbox
+            .universe()
+            .spawn_builder()
+            .spawn(split_upgrade_planner);
+        let state = split_upgrade_planner_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(state.num_outdated_splits, 2);
+        assert_eq!(state.ongoing_upgrade_operations.len(), 1);
+
+        // Only one upgrade operation can be in flight and the oldest split goes first.
+        let downloader_msgs: Vec<TrackedObject<MergeOperation>> =
+            downloader_inbox.drain_for_test_typed();
+        assert_eq!(downloader_msgs.len(), 1);
+        assert_eq!(
+            downloader_msgs[0].splits[0].split_id(),
+            legacy_split_metas[0].split_id()
+        );
+        split_upgrade_planner_mailbox
+            .ask(PlanUpgradeOperations)
+            .await
+            .unwrap();
+        assert!(downloader_inbox.drain_for_test().is_empty());
+
+        // Simulates the publish of the upgraded split.
+        let mut upgraded_split_meta = legacy_split_metas[0].clone();
+        upgraded_split_meta.split_id = downloader_msgs[0].merge_split_id.clone();
+        upgraded_split_meta.format_version = SPLIT_FORMAT_VERSION;
+        metastore
+            .stage_splits(index_uid.clone(), vec![upgraded_split_meta.clone()])
+            .await?;
+        metastore
+            .publish_splits(
+                index_uid.clone(),
+                &[upgraded_split_meta.split_id()],
+                &[legacy_split_metas[0].split_id()],
+                None,
+            )
+            .await?;
+        drop(downloader_msgs);
+
+        split_upgrade_planner_mailbox
+            .ask(PlanUpgradeOperations)
+            .await
+            .unwrap();
+        let downloader_msgs: Vec<TrackedObject<MergeOperation>> =
+            downloader_inbox.drain_for_test_typed();
+        assert_eq!(downloader_msgs.len(), 1);
+        assert_eq!(
+            downloader_msgs[0].splits[0].split_id(),
+            legacy_split_metas[1].split_id()
+        );
+        let state = split_upgrade_planner_handle.observe().await.state;
+        assert_eq!(state.num_outdated_splits, 1);
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+}
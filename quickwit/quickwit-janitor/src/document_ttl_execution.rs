@@ -0,0 +1,63 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::sync::Arc;
+
+use quickwit_actors::ActorContext;
+use quickwit_config::DocumentTtlPolicy;
+use quickwit_metastore::Metastore;
+use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
+use quickwit_proto::IndexUid;
+use quickwit_query::query_ast::query_ast_to_json;
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::actors::DocumentTtlExecutor;
+
+/// Creates a delete task removing all the documents whose TTL field holds a date in the past.
+/// The documents are physically removed when the delete task pipeline rewrites the splits
+/// containing them, and the former splits are then collected by the garbage collector.
+///
+/// * `index_uid` - The target index uid.
+/// * `metastore` - The metastore managing the target index.
+/// * `document_ttl_policy` - The document TTL policy to enforce.
+/// * `ctx` - A context for reporting progress.
+pub async fn run_execute_document_ttl_policy(
+    index_uid: IndexUid,
+    metastore: Arc<dyn Metastore>,
+    document_ttl_policy: &DocumentTtlPolicy,
+    ctx: &ActorContext<DocumentTtlExecutor>,
+) -> anyhow::Result<DeleteTask> {
+    let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let query_ast = document_ttl_policy.expired_documents_query_ast(current_timestamp);
+    let delete_query = DeleteQuery {
+        index_uid: index_uid.to_string(),
+        start_timestamp: None,
+        end_timestamp: None,
+        query_ast: query_ast_to_json(&query_ast)?,
+    };
+    info!(
+        index_id=%index_uid.index_id(),
+        field=%document_ttl_policy.field(),
+        "Creating delete task for the documents expired at `{current_timestamp}`."
+    );
+    let delete_task = ctx
+        .protect_future(metastore.create_delete_task(delete_query))
+        .await?;
+    Ok(delete_task)
+}
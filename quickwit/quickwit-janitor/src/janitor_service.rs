@@ -23,12 +23,17 @@ use quickwit_actors::{
 };
 use serde_json::{json, Value as JsonValue};
 
-use crate::actors::{DeleteTaskService, GarbageCollector, RetentionPolicyExecutor};
+use crate::actors::{
+    DeleteTaskService, DocumentTtlExecutor, GarbageCollector, RetentionPolicyExecutor,
+    RollupExecutor,
+};
 
 pub struct JanitorService {
     delete_task_service_handle: ActorHandle<DeleteTaskService>,
     garbage_collector_handle: ActorHandle<GarbageCollector>,
     retention_policy_executor_handle: ActorHandle<RetentionPolicyExecutor>,
+    document_ttl_executor_handle: ActorHandle<DocumentTtlExecutor>,
+    rollup_executor_handle: ActorHandle<RollupExecutor>,
 }
 
 impl JanitorService {
@@ -36,11 +41,15 @@ impl JanitorService {
         delete_task_service_handle: ActorHandle<DeleteTaskService>,
         garbage_collector_handle: ActorHandle<GarbageCollector>,
         retention_policy_executor_handle: ActorHandle<RetentionPolicyExecutor>,
+        document_ttl_executor_handle: ActorHandle<DocumentTtlExecutor>,
+        rollup_executor_handle: ActorHandle<RollupExecutor>,
     ) -> Self {
         Self {
             delete_task_service_handle,
             garbage_collector_handle,
             retention_policy_executor_handle,
+            document_ttl_executor_handle,
+            rollup_executor_handle,
         }
     }
 
@@ -48,6 +57,8 @@ impl JanitorService {
         self.delete_task_service_handle.state() != ActorState::Failure
             && self.garbage_collector_handle.state() != ActorState::Failure
             && self.retention_policy_executor_handle.state() != ActorState::Failure
+            && self.document_ttl_executor_handle.state() != ActorState::Failure
+            && self.rollup_executor_handle.state() != ActorState::Failure
     }
 }
 
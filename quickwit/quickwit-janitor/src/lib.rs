@@ -24,24 +24,30 @@ use std::sync::Arc;
 use quickwit_actors::{Mailbox, Universe};
 use quickwit_common::FileEntry;
 use quickwit_config::QuickwitConfig;
+use quickwit_ingest::IngestServiceClient;
 use quickwit_metastore::Metastore;
-use quickwit_search::SearchJobPlacer;
+use quickwit_search::{SearchJobPlacer, SearchService};
 use quickwit_storage::StorageResolver;
 use tracing::info;
 
 pub mod actors;
+mod document_ttl_execution;
 pub mod error;
 mod garbage_collection;
 mod janitor_service;
 mod metrics;
 mod retention_policy_execution;
+mod rollup_execution;
 
 pub use janitor_service::JanitorService;
 
 pub use self::garbage_collection::{
     delete_splits_with_files, run_garbage_collect, SplitDeletionError, SplitRemovalInfo,
 };
-use crate::actors::{DeleteTaskService, GarbageCollector, RetentionPolicyExecutor};
+use crate::actors::{
+    DeleteTaskService, DocumentTtlExecutor, GarbageCollector, RetentionPolicyExecutor,
+    RollupExecutor,
+};
 
 #[derive(utoipa::OpenApi)]
 #[openapi(components(schemas(FileEntry)))]
@@ -54,6 +60,8 @@ pub async fn start_janitor_service(
     metastore: Arc<dyn Metastore>,
     search_job_placer: SearchJobPlacer,
     storage_resolver: StorageResolver,
+    search_service: Arc<dyn SearchService>,
+    ingest_service: IngestServiceClient,
 ) -> anyhow::Result<Mailbox<JanitorService>> {
     info!("Starting janitor service.");
     let garbage_collector = GarbageCollector::new(metastore.clone(), storage_resolver.clone());
@@ -63,12 +71,19 @@ pub async fn start_janitor_service(
     let (_, retention_policy_executor_handle) =
         universe.spawn_builder().spawn(retention_policy_executor);
 
+    let document_ttl_executor = DocumentTtlExecutor::new(metastore.clone());
+    let (_, document_ttl_executor_handle) = universe.spawn_builder().spawn(document_ttl_executor);
+
+    let rollup_executor = RollupExecutor::new(metastore.clone(), search_service, ingest_service);
+    let (_, rollup_executor_handle) = universe.spawn_builder().spawn(rollup_executor);
+
     let delete_task_service = DeleteTaskService::new(
         metastore,
         search_job_placer,
         storage_resolver,
         config.data_dir_path.clone(),
         config.indexer_config.max_concurrent_split_uploads,
+        config.split_upgrade_config.clone(),
     )
     .await?;
     let (_, delete_task_service_handle) = universe.spawn_builder().spawn(delete_task_service);
@@ -77,6 +92,8 @@ pub async fn start_janitor_service(
         delete_task_service_handle,
         garbage_collector_handle,
         retention_policy_executor_handle,
+        document_ttl_executor_handle,
+        rollup_executor_handle,
     );
     let (janitor_service_mailbox, _janitor_service_handle) =
         universe.spawn_builder().spawn(janitor_service);
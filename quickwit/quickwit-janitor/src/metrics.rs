@@ -22,6 +22,10 @@ use quickwit_common::metrics::{new_gauge_vec, IntGaugeVec};
 
 pub struct JanitorMetrics {
     pub ongoing_num_delete_operations_total: IntGaugeVec<1>,
+    pub ongoing_num_upgrade_operations_total: IntGaugeVec<1>,
+    pub num_outdated_splits: IntGaugeVec<1>,
+    pub ongoing_num_reshard_operations_total: IntGaugeVec<1>,
+    pub num_splits_to_reshard: IntGaugeVec<1>,
 }
 
 impl Default for JanitorMetrics {
@@ -33,6 +37,32 @@ impl Default for JanitorMetrics {
                 "quickwit_janitor",
                 ["index"],
             ),
+            ongoing_num_upgrade_operations_total: new_gauge_vec(
+                "ongoing_num_upgrade_operations_total",
+                "Num of ongoing split upgrade operations (per index).",
+                "quickwit_janitor",
+                ["index"],
+            ),
+            num_outdated_splits: new_gauge_vec(
+                "num_outdated_splits",
+                "Num of published splits written with an outdated split format version (per \
+                 index).",
+                "quickwit_janitor",
+                ["index"],
+            ),
+            ongoing_num_reshard_operations_total: new_gauge_vec(
+                "ongoing_num_reshard_operations_total",
+                "Num of ongoing split resharding operations (per index).",
+                "quickwit_janitor",
+                ["index"],
+            ),
+            num_splits_to_reshard: new_gauge_vec(
+                "num_splits_to_reshard",
+                "Num of published splits holding several values of the resharding field (per \
+                 index).",
+                "quickwit_janitor",
+                ["index"],
+            ),
         }
     }
 }
@@ -0,0 +1,138 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use quickwit_actors::ActorContext;
+use quickwit_config::{build_doc_mapper, RollupPolicy};
+use quickwit_ingest::{
+    CommitType, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
+};
+use quickwit_metastore::Metastore;
+use quickwit_proto::SearchRequest;
+use quickwit_query::query_ast::{query_ast_to_json, QueryAst};
+use quickwit_search::{
+    published_splits_time_range, rollup_aggregation_request, rollup_documents, SearchService,
+};
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::actors::RollupExecutor;
+
+/// Maximum number of time buckets rolled up by a single search, which bounds the number of
+/// buckets of the rollup aggregation.
+const MAX_NUM_TIME_BUCKETS_PER_SEARCH: i64 = 60;
+
+/// Rolls up the time buckets of the index that ended more than the rollup delay ago and were not
+/// rolled up yet, and ingests the resulting documents into the rollup index. The rollup resumes
+/// after the last time bucket found in the rollup index. Returns the number of rollup documents
+/// ingested.
+///
+/// * `index_id` - The ID of the rolled up index.
+/// * `metastore` - The metastore managing the rolled up and rollup indexes.
+/// * `search_service` - The search service counting the documents.
+/// * `ingest_service` - The ingest service receiving the rollup documents.
+/// * `rollup_policy` - The rollup policy to execute.
+/// * `ctx` - A context for reporting progress.
+pub async fn run_execute_rollup_policy(
+    index_id: &str,
+    metastore: Arc<dyn Metastore>,
+    search_service: Arc<dyn SearchService>,
+    mut ingest_service: IngestServiceClient,
+    rollup_policy: &RollupPolicy,
+    ctx: &ActorContext<RollupExecutor>,
+) -> anyhow::Result<usize> {
+    let index_metadata = ctx
+        .protect_future(metastore.index_metadata(index_id))
+        .await?;
+    let index_config = index_metadata.into_index_config();
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)?;
+    let timestamp_field = doc_mapper
+        .timestamp_field_name()
+        .context("Rollups require a timestamp field.")?;
+    let interval_secs = rollup_policy.interval_secs()?;
+    let delay_secs = rollup_policy.delay_secs()?;
+    let aggregation_request = rollup_aggregation_request(timestamp_field, rollup_policy)?;
+
+    let rollup_index_id = rollup_policy.rollup_index_id();
+    let rollup_time_range_opt = ctx
+        .protect_future(published_splits_time_range(&*metastore, rollup_index_id))
+        .await?;
+    let start_timestamp_opt = match rollup_time_range_opt {
+        Some(rollup_time_range) => Some(rollup_time_range.end() + interval_secs),
+        None => ctx
+            .protect_future(published_splits_time_range(&*metastore, index_id))
+            .await?
+            .map(|time_range| time_range.start().div_euclid(interval_secs) * interval_secs),
+    };
+    let Some(mut start_timestamp) = start_timestamp_opt else {
+        // The index is empty.
+        return Ok(0);
+    };
+    let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let end_timestamp = (now_timestamp - delay_secs).div_euclid(interval_secs) * interval_secs;
+    let query_ast = query_ast_to_json(&QueryAst::MatchAll)?;
+    let mut num_rollup_docs = 0;
+
+    while start_timestamp < end_timestamp {
+        let search_end_timestamp =
+            end_timestamp.min(start_timestamp + MAX_NUM_TIME_BUCKETS_PER_SEARCH * interval_secs);
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query_ast: query_ast.clone(),
+            start_timestamp: Some(start_timestamp),
+            end_timestamp: Some(search_end_timestamp),
+            max_hits: 0,
+            aggregation_request: Some(aggregation_request.clone()),
+            ..Default::default()
+        };
+        let search_response = ctx
+            .protect_future(search_service.root_search(search_request))
+            .await?;
+        let aggregation = search_response
+            .aggregation
+            .context("The rollup search response has no aggregation.")?;
+        let documents = rollup_documents(&aggregation, timestamp_field, rollup_policy)?;
+
+        if !documents.is_empty() {
+            let mut doc_batch_builder =
+                DocBatchBuilder::new(rollup_index_id.to_string()).json_writer();
+            for document in &documents {
+                doc_batch_builder.ingest_doc(document)?;
+            }
+            // The next run resumes after the last time bucket published to the rollup index.
+            let ingest_request = IngestRequest {
+                doc_batches: vec![doc_batch_builder.build()],
+                commit: CommitType::Force as u32,
+            };
+            ctx.protect_future(ingest_service.ingest(ingest_request))
+                .await?;
+            num_rollup_docs += documents.len();
+        }
+        info!(
+            index_id=%index_id,
+            rollup_index_id=%rollup_index_id,
+            num_rollup_docs=documents.len(),
+            "Rolled up the documents from `{start_timestamp}` to `{search_end_timestamp}`."
+        );
+        start_timestamp = search_end_timestamp;
+    }
+    Ok(num_rollup_docs)
+}
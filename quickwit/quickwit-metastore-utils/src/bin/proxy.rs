@@ -198,6 +198,76 @@ impl MetastoreApiService for MetastoreProxyService {
         let resp = lock.client.toggle_source(request).await?;
         Ok(resp)
     }
+    /// Replaces the labels of an index.
+    async fn update_index_labels(
+        &self,
+        request: tonic::Request<UpdateIndexLabelsRequest>,
+    ) -> Result<tonic::Response<UpdateIndexLabelsResponse>, tonic::Status> {
+        let mut lock = self.inner.lock().await;
+        lock.record(request.get_ref().clone()).await.unwrap();
+        let resp = lock.client.update_index_labels(request).await?;
+        Ok(resp)
+    }
+    /// Replaces the indexing settings of an index.
+    async fn update_indexing_settings(
+        &self,
+        request: tonic::Request<UpdateIndexingSettingsRequest>,
+    ) -> Result<tonic::Response<UpdateIndexingSettingsResponse>, tonic::Status> {
+        let mut lock = self.inner.lock().await;
+        lock.record(request.get_ref().clone()).await.unwrap();
+        let resp = lock.client.update_indexing_settings(request).await?;
+        Ok(resp)
+    }
+    /// Replaces the lookup tables of an index.
+    async fn update_lookup_tables(
+        &self,
+        request: tonic::Request<UpdateLookupTablesRequest>,
+    ) -> Result<tonic::Response<UpdateLookupTablesResponse>, tonic::Status> {
+        let mut lock = self.inner.lock().await;
+        lock.record(request.get_ref().clone()).await.unwrap();
+        let resp = lock.client.update_lookup_tables(request).await?;
+        Ok(resp)
+    }
+    /// Replaces the search templates of an index.
+    async fn update_search_templates(
+        &self,
+        request: tonic::Request<UpdateSearchTemplatesRequest>,
+    ) -> Result<tonic::Response<UpdateSearchTemplatesResponse>, tonic::Status> {
+        let mut lock = self.inner.lock().await;
+        lock.record(request.get_ref().clone()).await.unwrap();
+        let resp = lock.client.update_search_templates(request).await?;
+        Ok(resp)
+    }
+    /// Sets the lifecycle state of an index: open, closed, or frozen.
+    async fn set_index_state(
+        &self,
+        request: tonic::Request<SetIndexStateRequest>,
+    ) -> Result<tonic::Response<SetIndexStateResponse>, tonic::Status> {
+        let mut lock = self.inner.lock().await;
+        lock.record(request.get_ref().clone()).await.unwrap();
+        let resp = lock.client.set_index_state(request).await?;
+        Ok(resp)
+    }
+    /// Places an index under a retention lock until a given date.
+    async fn set_retention_lock(
+        &self,
+        request: tonic::Request<SetRetentionLockRequest>,
+    ) -> Result<tonic::Response<SetRetentionLockResponse>, tonic::Status> {
+        let mut lock = self.inner.lock().await;
+        lock.record(request.get_ref().clone()).await.unwrap();
+        let resp = lock.client.set_retention_lock(request).await?;
+        Ok(resp)
+    }
+    /// Replaces the labels of a source.
+    async fn update_source_labels(
+        &self,
+        request: tonic::Request<UpdateSourceLabelsRequest>,
+    ) -> Result<tonic::Response<SourceResponse>, tonic::Status> {
+        let mut lock = self.inner.lock().await;
+        lock.record(request.get_ref().clone()).await.unwrap();
+        let resp = lock.client.update_source_labels(request).await?;
+        Ok(resp)
+    }
     /// Removes source.
     async fn delete_source(
         &self,
@@ -67,6 +67,27 @@ async fn replay_grpc_request(
         GrpcRequest::ToggleSourceRequest(req) => {
             client.toggle_source(req).await?;
         }
+        GrpcRequest::UpdateIndexLabelsRequest(req) => {
+            client.update_index_labels(req).await?;
+        }
+        GrpcRequest::UpdateIndexingSettingsRequest(req) => {
+            client.update_indexing_settings(req).await?;
+        }
+        GrpcRequest::UpdateLookupTablesRequest(req) => {
+            client.update_lookup_tables(req).await?;
+        }
+        GrpcRequest::UpdateSearchTemplatesRequest(req) => {
+            client.update_search_templates(req).await?;
+        }
+        GrpcRequest::SetIndexStateRequest(req) => {
+            client.set_index_state(req).await?;
+        }
+        GrpcRequest::SetRetentionLockRequest(req) => {
+            client.set_retention_lock(req).await?;
+        }
+        GrpcRequest::UpdateSourceLabelsRequest(req) => {
+            client.update_source_labels(req).await?;
+        }
         GrpcRequest::DeleteSourceRequest(req) => {
             client.delete_source(req).await?;
         }
@@ -77,6 +77,13 @@ generate_req_enum!(
     DeleteSplitsRequest,
     AddSourceRequest,
     ToggleSourceRequest,
+    UpdateIndexLabelsRequest,
+    UpdateIndexingSettingsRequest,
+    UpdateLookupTablesRequest,
+    UpdateSearchTemplatesRequest,
+    SetIndexStateRequest,
+    SetRetentionLockRequest,
+    UpdateSourceLabelsRequest,
     DeleteSourceRequest,
     LastDeleteOpstampRequest,
     ResetSourceCheckpointRequest,
@@ -39,6 +39,9 @@ pub enum MetastoreError {
     #[error("Index `{index_id}` does not exist.")]
     IndexDoesNotExist { index_id: String },
 
+    #[error("Index `{index_id}` is under a retention lock until `{locked_until}`.")]
+    IndexRetentionLocked { index_id: String, locked_until: i64 },
+
     /// Any generic internal error.
     /// The message can be helpful to users, but the detail of the error
     /// are judged uncoverable and not useful for error handling.
@@ -114,6 +117,7 @@ impl ServiceError for MetastoreError {
             Self::IncompatibleCheckpointDelta(_) => ServiceErrorCode::BadRequest,
             Self::IndexAlreadyExists { .. } => ServiceErrorCode::BadRequest,
             Self::IndexDoesNotExist { .. } => ServiceErrorCode::NotFound,
+            Self::IndexRetentionLocked { .. } => ServiceErrorCode::MethodNotAllowed,
             Self::InternalError { .. } => ServiceErrorCode::Internal,
             Self::InvalidManifest { .. } => ServiceErrorCode::Internal,
             Self::Io { .. } => ServiceErrorCode::Internal,
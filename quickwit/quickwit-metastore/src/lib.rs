@@ -51,12 +51,12 @@ pub use metastore::postgresql_metastore::PostgresqlMetastore;
 pub use metastore::retrying_metastore::RetryingMetastore;
 #[cfg(any(test, feature = "testsuite"))]
 pub use metastore::MockMetastore;
-pub use metastore::{file_backed_metastore, IndexMetadata, ListSplitsQuery, Metastore};
+pub use metastore::{file_backed_metastore, IndexMetadata, IndexState, ListSplitsQuery, Metastore};
 pub use metastore_factory::{MetastoreFactory, UnsupportedMetastore};
 pub use metastore_resolver::MetastoreResolver;
 use quickwit_common::is_disjoint;
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
-pub use split_metadata::{Split, SplitMetadata, SplitState};
+pub use split_metadata::{Split, SplitMetadata, SplitState, SPLIT_FORMAT_VERSION};
 pub(crate) use split_metadata_version::{SplitMetadataV0_6, VersionedSplitMetadata};
 
 #[derive(utoipa::OpenApi)]
@@ -23,11 +23,11 @@
 
 mod serialize;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 
 use quickwit_common::PrettySample;
-use quickwit_config::SourceConfig;
+use quickwit_config::{IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::IndexUid;
 use serde::{Deserialize, Serialize};
@@ -37,8 +37,8 @@ use tracing::{info, warn};
 
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::{
-    split_tag_filter, IndexMetadata, ListSplitsQuery, MetastoreError, MetastoreResult, Split,
-    SplitMetadata, SplitState,
+    split_tag_filter, IndexMetadata, IndexState, ListSplitsQuery, MetastoreError, MetastoreResult,
+    Split, SplitMetadata, SplitState,
 };
 
 /// A `FileBackedIndex` object carries an index metadata and its split metadata.
@@ -385,6 +385,51 @@ impl FileBackedIndex {
         self.metadata.toggle_source(source_id, enable)
     }
 
+    /// Replaces the labels of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_index_labels(&mut self, labels: BTreeMap<String, String>) -> bool {
+        self.metadata.update_index_labels(labels)
+    }
+
+    /// Replaces the indexing settings of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_indexing_settings(&mut self, indexing_settings: IndexingSettings) -> bool {
+        self.metadata.update_indexing_settings(indexing_settings)
+    }
+
+    /// Replaces the lookup tables of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_lookup_tables(
+        &mut self,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> bool {
+        self.metadata.update_lookup_tables(lookup_tables)
+    }
+
+    /// Replaces the search templates of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_search_templates(
+        &mut self,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> bool {
+        self.metadata.update_search_templates(search_templates)
+    }
+
+    /// Sets the lifecycle state of the index. Returns whether a mutation occurred.
+    pub(crate) fn set_state(&mut self, state: IndexState) -> bool {
+        self.metadata.set_state(state)
+    }
+
+    /// Places the index under a retention lock. Returns whether a mutation occurred.
+    pub(crate) fn set_retention_lock(&mut self, locked_until: i64) -> MetastoreResult<bool> {
+        self.metadata.set_retention_lock(locked_until)
+    }
+
+    /// Replaces the labels of a source. Returns whether a mutation occurred.
+    pub(crate) fn update_source_labels(
+        &mut self,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<bool> {
+        self.metadata.update_source_labels(source_id, labels)
+    }
+
     /// Deletes the source. Returns whether a mutation occurred.
     pub(crate) fn delete_source(&mut self, source_id: &str) -> MetastoreResult<bool> {
         self.metadata.delete_source(source_id)
@@ -26,14 +26,14 @@ mod file_backed_metastore_factory;
 mod lazy_file_backed_index;
 mod store_operations;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::future::try_join_all;
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::IndexUid;
 use quickwit_storage::Storage;
@@ -48,7 +48,7 @@ use self::store_operations::{
 };
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::{
-    IndexMetadata, ListSplitsQuery, Metastore, MetastoreError, MetastoreResult, Split,
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreError, MetastoreResult, Split,
     SplitMetadata, SplitState,
 };
 
@@ -368,6 +368,15 @@ impl Metastore for FileBackedMetastore {
     }
 
     async fn delete_index(&self, index_uid: IndexUid) -> MetastoreResult<()> {
+        // Indexes under a retention lock cannot be deleted.
+        if let Err(error @ MetastoreError::IndexRetentionLocked { .. }) = self
+            .read_any(index_uid.index_id(), |index| {
+                index.metadata().check_retention_lock()
+            })
+            .await
+        {
+            return Err(error);
+        }
         // We pick the outer lock here, so that we enter a critical section.
         let mut per_index_metastores_wlock = self.per_index_metastores.write().await;
 
@@ -467,6 +476,7 @@ impl Metastore for FileBackedMetastore {
         split_ids: &[&'a str],
     ) -> MetastoreResult<()> {
         self.mutate(index_uid, |index| {
+            index.metadata().check_retention_lock()?;
             index
                 .mark_splits_for_deletion(
                     split_ids,
@@ -489,6 +499,7 @@ impl Metastore for FileBackedMetastore {
         split_ids: &[&'a str],
     ) -> MetastoreResult<()> {
         self.mutate(index_uid, |index| {
+            index.metadata().check_retention_lock()?;
             index.delete_splits(split_ids)?;
             Ok(MutationOccurred::Yes(()))
         })
@@ -520,6 +531,97 @@ impl Metastore for FileBackedMetastore {
         Ok(())
     }
 
+    async fn update_index_labels(
+        &self,
+        index_uid: IndexUid,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_uid, |index| {
+            Ok(MutationOccurred::from(index.update_index_labels(labels)))
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn update_source_labels(
+        &self,
+        index_uid: IndexUid,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_uid, |index| {
+            index
+                .update_source_labels(source_id, labels)
+                .map(MutationOccurred::from)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn update_indexing_settings(
+        &self,
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_uid, |index| {
+            Ok(MutationOccurred::from(
+                index.update_indexing_settings(indexing_settings),
+            ))
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn update_lookup_tables(
+        &self,
+        index_uid: IndexUid,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_uid, |index| {
+            Ok(MutationOccurred::from(
+                index.update_lookup_tables(lookup_tables),
+            ))
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn update_search_templates(
+        &self,
+        index_uid: IndexUid,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_uid, |index| {
+            Ok(MutationOccurred::from(
+                index.update_search_templates(search_templates),
+            ))
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn set_index_state(&self, index_uid: IndexUid, state: IndexState) -> MetastoreResult<()> {
+        self.mutate(index_uid, |index| {
+            Ok(MutationOccurred::from(index.set_state(state)))
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn set_retention_lock(
+        &self,
+        index_uid: IndexUid,
+        locked_until: i64,
+    ) -> MetastoreResult<()> {
+        self.mutate(index_uid, |index| {
+            index
+                .set_retention_lock(locked_until)
+                .map(MutationOccurred::from)
+        })
+        .await?;
+        Ok(())
+    }
+
     async fn delete_source(&self, index_uid: IndexUid, source_id: &str) -> MetastoreResult<()> {
         self.mutate(index_uid, |index| {
             index.delete_source(source_id).map(MutationOccurred::from)
@@ -617,6 +719,7 @@ impl Metastore for FileBackedMetastore {
         let index_uid: IndexUid = delete_query.index_uid.clone().into();
         let delete_task = self
             .mutate(index_uid, |index| {
+                index.metadata().check_retention_lock()?;
                 index
                     .create_delete_task(delete_query)
                     .map(MutationOccurred::Yes)
@@ -17,11 +17,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use itertools::Itertools;
-use quickwit_config::IndexConfig;
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate};
 use quickwit_proto::metastore_api::metastore_api_service_server::{self as grpc};
 use quickwit_proto::metastore_api::{
     AddSourceRequest, CreateIndexRequest, CreateIndexResponse, DeleteIndexRequest,
@@ -30,15 +31,19 @@ use quickwit_proto::metastore_api::{
     LastDeleteOpstampResponse, ListAllSplitsRequest, ListDeleteTasksRequest,
     ListDeleteTasksResponse, ListIndexesMetadatasRequest, ListIndexesMetadatasResponse,
     ListSplitsRequest, ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest,
-    PublishSplitsRequest, ResetSourceCheckpointRequest, SourceResponse, SplitResponse,
-    StageSplitsRequest, ToggleSourceRequest, UpdateSplitsDeleteOpstampRequest,
+    PublishSplitsRequest, ResetSourceCheckpointRequest, SetIndexStateRequest,
+    SetIndexStateResponse, SetRetentionLockRequest, SetRetentionLockResponse, SourceResponse,
+    SplitResponse, StageSplitsRequest, ToggleSourceRequest, UpdateIndexLabelsRequest,
+    UpdateIndexLabelsResponse, UpdateIndexingSettingsRequest, UpdateIndexingSettingsResponse,
+    UpdateLookupTablesRequest, UpdateLookupTablesResponse, UpdateSearchTemplatesRequest,
+    UpdateSearchTemplatesResponse, UpdateSourceLabelsRequest, UpdateSplitsDeleteOpstampRequest,
     UpdateSplitsDeleteOpstampResponse,
 };
 use quickwit_proto::tonic::{Request, Response, Status};
 use quickwit_proto::{set_parent_span_from_request_metadata, tonic};
 use tracing::instrument;
 
-use crate::{ListSplitsQuery, Metastore, MetastoreError};
+use crate::{IndexState, ListSplitsQuery, Metastore, MetastoreError};
 
 #[allow(missing_docs)]
 #[derive(Clone)]
@@ -318,6 +323,152 @@ impl grpc::MetastoreApiService for GrpcMetastoreAdapter {
         Ok(tonic::Response::new(toggle_source_reply))
     }
 
+    #[instrument(skip(self, request))]
+    async fn update_index_labels(
+        &self,
+        request: tonic::Request<UpdateIndexLabelsRequest>,
+    ) -> Result<tonic::Response<UpdateIndexLabelsResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let update_index_labels_request = request.into_inner();
+        let update_index_labels_reply = self
+            .0
+            .update_index_labels(
+                update_index_labels_request.index_uid.into(),
+                update_index_labels_request.labels.into_iter().collect(),
+            )
+            .await
+            .map(|_| UpdateIndexLabelsResponse {})?;
+        Ok(tonic::Response::new(update_index_labels_reply))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn update_source_labels(
+        &self,
+        request: tonic::Request<UpdateSourceLabelsRequest>,
+    ) -> Result<tonic::Response<SourceResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let update_source_labels_request = request.into_inner();
+        let update_source_labels_reply = self
+            .0
+            .update_source_labels(
+                update_source_labels_request.index_uid.into(),
+                &update_source_labels_request.source_id,
+                update_source_labels_request.labels.into_iter().collect(),
+            )
+            .await
+            .map(|_| SourceResponse {})?;
+        Ok(tonic::Response::new(update_source_labels_reply))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn update_indexing_settings(
+        &self,
+        request: tonic::Request<UpdateIndexingSettingsRequest>,
+    ) -> Result<tonic::Response<UpdateIndexingSettingsResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let update_indexing_settings_request = request.into_inner();
+        let indexing_settings = serde_json::from_str::<IndexingSettings>(
+            &update_indexing_settings_request.indexing_settings_serialized_json,
+        )
+        .map_err(|error| MetastoreError::JsonDeserializeError {
+            struct_name: "IndexingSettings".to_string(),
+            message: error.to_string(),
+        })?;
+        let update_indexing_settings_reply = self
+            .0
+            .update_indexing_settings(
+                update_indexing_settings_request.index_uid.into(),
+                indexing_settings,
+            )
+            .await
+            .map(|_| UpdateIndexingSettingsResponse {})?;
+        Ok(tonic::Response::new(update_indexing_settings_reply))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn update_lookup_tables(
+        &self,
+        request: tonic::Request<UpdateLookupTablesRequest>,
+    ) -> Result<tonic::Response<UpdateLookupTablesResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let update_lookup_tables_request = request.into_inner();
+        let lookup_tables = serde_json::from_str::<BTreeMap<String, LookupTable>>(
+            &update_lookup_tables_request.lookup_tables_serialized_json,
+        )
+        .map_err(|error| MetastoreError::JsonDeserializeError {
+            struct_name: "BTreeMap<String, LookupTable>".to_string(),
+            message: error.to_string(),
+        })?;
+        let update_lookup_tables_reply = self
+            .0
+            .update_lookup_tables(update_lookup_tables_request.index_uid.into(), lookup_tables)
+            .await
+            .map(|_| UpdateLookupTablesResponse {})?;
+        Ok(tonic::Response::new(update_lookup_tables_reply))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn update_search_templates(
+        &self,
+        request: tonic::Request<UpdateSearchTemplatesRequest>,
+    ) -> Result<tonic::Response<UpdateSearchTemplatesResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let update_search_templates_request = request.into_inner();
+        let search_templates = serde_json::from_str::<BTreeMap<String, SearchTemplate>>(
+            &update_search_templates_request.search_templates_serialized_json,
+        )
+        .map_err(|error| MetastoreError::JsonDeserializeError {
+            struct_name: "BTreeMap<String, SearchTemplate>".to_string(),
+            message: error.to_string(),
+        })?;
+        let update_search_templates_reply = self
+            .0
+            .update_search_templates(
+                update_search_templates_request.index_uid.into(),
+                search_templates,
+            )
+            .await
+            .map(|_| UpdateSearchTemplatesResponse {})?;
+        Ok(tonic::Response::new(update_search_templates_reply))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn set_index_state(
+        &self,
+        request: tonic::Request<SetIndexStateRequest>,
+    ) -> Result<tonic::Response<SetIndexStateResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let set_index_state_request = request.into_inner();
+        let state = set_index_state_request
+            .state
+            .parse::<IndexState>()
+            .map_err(|error| Status::invalid_argument(error.to_string()))?;
+        let set_index_state_reply = self
+            .0
+            .set_index_state(set_index_state_request.index_uid.into(), state)
+            .await
+            .map(|_| SetIndexStateResponse {})?;
+        Ok(tonic::Response::new(set_index_state_reply))
+    }
+
+    #[instrument(skip(self, request))]
+    async fn set_retention_lock(
+        &self,
+        request: tonic::Request<SetRetentionLockRequest>,
+    ) -> Result<tonic::Response<SetRetentionLockResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let set_retention_lock_request = request.into_inner();
+        let set_retention_lock_reply = self
+            .0
+            .set_retention_lock(
+                set_retention_lock_request.index_uid.into(),
+                set_retention_lock_request.locked_until,
+            )
+            .await
+            .map(|_| SetRetentionLockResponse {})?;
+        Ok(tonic::Response::new(set_retention_lock_reply))
+    }
+
     #[instrument(skip(self, request))]
     async fn delete_source(
         &self,
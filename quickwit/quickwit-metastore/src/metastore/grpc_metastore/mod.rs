@@ -19,6 +19,7 @@
 
 mod grpc_adapter;
 
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::net::SocketAddr;
 
@@ -28,15 +29,17 @@ pub use grpc_adapter::GrpcMetastoreAdapter;
 use itertools::Itertools;
 use quickwit_common::tower::BalanceChannel;
 use quickwit_common::uri::Uri as QuickwitUri;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_proto::metastore_api::metastore_api_service_client::MetastoreApiServiceClient;
 use quickwit_proto::metastore_api::{
     AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, DeleteQuery, DeleteSourceRequest,
     DeleteSplitsRequest, DeleteTask, IndexMetadataRequest, LastDeleteOpstampRequest,
     ListAllSplitsRequest, ListDeleteTasksRequest, ListIndexesMetadatasRequest, ListSplitsRequest,
     ListStaleSplitsRequest, MarkSplitsForDeletionRequest, PublishSplitsRequest,
-    ResetSourceCheckpointRequest, StageSplitsRequest, ToggleSourceRequest,
-    UpdateSplitsDeleteOpstampRequest,
+    ResetSourceCheckpointRequest, SetIndexStateRequest, SetRetentionLockRequest,
+    StageSplitsRequest, ToggleSourceRequest, UpdateIndexLabelsRequest,
+    UpdateIndexingSettingsRequest, UpdateLookupTablesRequest, UpdateSearchTemplatesRequest,
+    UpdateSourceLabelsRequest, UpdateSplitsDeleteOpstampRequest,
 };
 use quickwit_proto::tonic::codegen::InterceptedService;
 use quickwit_proto::tonic::Status;
@@ -45,7 +48,7 @@ use tower::timeout::error::Elapsed;
 
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::{
-    IndexMetadata, ListSplitsQuery, Metastore, MetastoreError, MetastoreResult, Split,
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreError, MetastoreResult, Split,
     SplitMetadata,
 };
 
@@ -403,6 +406,158 @@ impl Metastore for MetastoreGrpcClient {
         Ok(())
     }
 
+    /// Replaces the labels of an index.
+    async fn update_index_labels(
+        &self,
+        index_uid: IndexUid,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        let request = UpdateIndexLabelsRequest {
+            index_uid: index_uid.into(),
+            labels: labels.into_iter().collect(),
+        };
+        self.underlying
+            .clone()
+            .update_index_labels(request)
+            .await
+            .map(|tonic_response| tonic_response.into_inner())
+            .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+        Ok(())
+    }
+
+    /// Replaces the labels of a source.
+    async fn update_source_labels(
+        &self,
+        index_uid: IndexUid,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        let request = UpdateSourceLabelsRequest {
+            index_uid: index_uid.into(),
+            source_id: source_id.to_string(),
+            labels: labels.into_iter().collect(),
+        };
+        self.underlying
+            .clone()
+            .update_source_labels(request)
+            .await
+            .map(|tonic_response| tonic_response.into_inner())
+            .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+        Ok(())
+    }
+
+    /// Replaces the indexing settings of an index.
+    async fn update_indexing_settings(
+        &self,
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()> {
+        let indexing_settings_serialized_json =
+            serde_json::to_string(&indexing_settings).map_err(|error| {
+                MetastoreError::JsonSerializeError {
+                    struct_name: "IndexingSettings".to_string(),
+                    message: error.to_string(),
+                }
+            })?;
+        let request = UpdateIndexingSettingsRequest {
+            index_uid: index_uid.into(),
+            indexing_settings_serialized_json,
+        };
+        self.underlying
+            .clone()
+            .update_indexing_settings(request)
+            .await
+            .map(|tonic_response| tonic_response.into_inner())
+            .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+        Ok(())
+    }
+
+    /// Replaces the lookup tables of an index.
+    async fn update_lookup_tables(
+        &self,
+        index_uid: IndexUid,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()> {
+        let lookup_tables_serialized_json =
+            serde_json::to_string(&lookup_tables).map_err(|error| {
+                MetastoreError::JsonSerializeError {
+                    struct_name: "BTreeMap<String, LookupTable>".to_string(),
+                    message: error.to_string(),
+                }
+            })?;
+        let request = UpdateLookupTablesRequest {
+            index_uid: index_uid.into(),
+            lookup_tables_serialized_json,
+        };
+        self.underlying
+            .clone()
+            .update_lookup_tables(request)
+            .await
+            .map(|tonic_response| tonic_response.into_inner())
+            .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+        Ok(())
+    }
+
+    /// Replaces the search templates of an index.
+    async fn update_search_templates(
+        &self,
+        index_uid: IndexUid,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()> {
+        let search_templates_serialized_json =
+            serde_json::to_string(&search_templates).map_err(|error| {
+                MetastoreError::JsonSerializeError {
+                    struct_name: "BTreeMap<String, SearchTemplate>".to_string(),
+                    message: error.to_string(),
+                }
+            })?;
+        let request = UpdateSearchTemplatesRequest {
+            index_uid: index_uid.into(),
+            search_templates_serialized_json,
+        };
+        self.underlying
+            .clone()
+            .update_search_templates(request)
+            .await
+            .map(|tonic_response| tonic_response.into_inner())
+            .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+        Ok(())
+    }
+
+    /// Sets the lifecycle state of an index.
+    async fn set_index_state(&self, index_uid: IndexUid, state: IndexState) -> MetastoreResult<()> {
+        let request = SetIndexStateRequest {
+            index_uid: index_uid.into(),
+            state: state.to_string(),
+        };
+        self.underlying
+            .clone()
+            .set_index_state(request)
+            .await
+            .map(|tonic_response| tonic_response.into_inner())
+            .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+        Ok(())
+    }
+
+    /// Places an index under a retention lock.
+    async fn set_retention_lock(
+        &self,
+        index_uid: IndexUid,
+        locked_until: i64,
+    ) -> MetastoreResult<()> {
+        let request = SetRetentionLockRequest {
+            index_uid: index_uid.into(),
+            locked_until,
+        };
+        self.underlying
+            .clone()
+            .set_retention_lock(request)
+            .await
+            .map(|tonic_response| tonic_response.into_inner())
+            .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+        Ok(())
+    }
+
     /// Removes a source from a given index.
     async fn delete_source(&self, index_uid: IndexUid, source_id: &str) -> MetastoreResult<()> {
         let request = DeleteSourceRequest {
@@ -21,9 +21,13 @@ pub(crate) mod serialize;
 
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
 
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig, TestableForRegression};
+use quickwit_config::{
+    IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig, TestableForRegression,
+};
 use quickwit_proto::IndexUid;
 use serde::{Deserialize, Serialize};
 use serialize::VersionedIndexMetadata;
@@ -34,6 +38,64 @@ use crate::checkpoint::{
 };
 use crate::{MetastoreError, MetastoreResult};
 
+/// Lifecycle state of an index.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexState {
+    /// The index is indexed, merged, and searchable.
+    #[default]
+    Open,
+    /// The index keeps its data but is neither indexed, merged, nor searchable.
+    Closed,
+    /// The index is searchable but read-only: it is neither indexed nor merged, and searches
+    /// bypass the searchers' long-term caches.
+    Frozen,
+}
+
+impl IndexState {
+    /// Returns the string representation of the state, as used in the REST and gRPC APIs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IndexState::Open => "open",
+            IndexState::Closed => "closed",
+            IndexState::Frozen => "frozen",
+        }
+    }
+
+    /// Returns whether indexing and merge pipelines should run for the index.
+    pub fn is_writable(&self) -> bool {
+        matches!(self, IndexState::Open)
+    }
+
+    /// Returns whether the index can be searched.
+    pub fn is_searchable(&self) -> bool {
+        !matches!(self, IndexState::Closed)
+    }
+}
+
+impl fmt::Display for IndexState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for IndexState {
+    type Err = anyhow::Error;
+
+    fn from_str(state_str: &str) -> anyhow::Result<Self> {
+        match state_str {
+            "open" => Ok(IndexState::Open),
+            "closed" => Ok(IndexState::Closed),
+            "frozen" => Ok(IndexState::Frozen),
+            _ => anyhow::bail!(
+                "Unknown index state `{state_str}`. Expected one of `open`, `closed`, or `frozen`."
+            ),
+        }
+    }
+}
+
 /// An index metadata carries all meta data about an index.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(into = "VersionedIndexMetadata")]
@@ -49,6 +111,12 @@ pub struct IndexMetadata {
     pub create_timestamp: i64,
     /// Sources
     pub sources: HashMap<String, SourceConfig>,
+    /// Lifecycle state of the index.
+    pub state: IndexState,
+    /// Unix timestamp (in seconds) until which the index is under a retention lock. While the
+    /// lock holds, the documents and splits of the index can be neither deleted nor garbage
+    /// collected, and the index itself cannot be deleted.
+    pub retention_locked_until: Option<i64>,
 }
 
 impl IndexMetadata {
@@ -60,6 +128,8 @@ impl IndexMetadata {
             checkpoint: Default::default(),
             create_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
             sources: HashMap::default(),
+            state: IndexState::default(),
+            retention_locked_until: None,
         }
     }
 
@@ -116,6 +186,102 @@ impl IndexMetadata {
         Ok(mutation_occurred)
     }
 
+    /// Replaces the labels of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_index_labels(&mut self, labels: BTreeMap<String, String>) -> bool {
+        let mutation_occurred = self.index_config.labels != labels;
+        self.index_config.labels = labels;
+        mutation_occurred
+    }
+
+    /// Replaces the indexing settings of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_indexing_settings(&mut self, indexing_settings: IndexingSettings) -> bool {
+        let mutation_occurred = self.index_config.indexing_settings != indexing_settings;
+        self.index_config.indexing_settings = indexing_settings;
+        mutation_occurred
+    }
+
+    /// Replaces the lookup tables of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_lookup_tables(
+        &mut self,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> bool {
+        let mutation_occurred = self.index_config.lookup_tables != lookup_tables;
+        self.index_config.lookup_tables = lookup_tables;
+        mutation_occurred
+    }
+
+    /// Replaces the search templates of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_search_templates(
+        &mut self,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> bool {
+        let mutation_occurred = self.index_config.search_templates != search_templates;
+        self.index_config.search_templates = search_templates;
+        mutation_occurred
+    }
+
+    /// Sets the lifecycle state of the index. Returns whether a mutation occurred.
+    pub(crate) fn set_state(&mut self, state: IndexState) -> bool {
+        let mutation_occurred = self.state != state;
+        self.state = state;
+        mutation_occurred
+    }
+
+    /// Returns whether the index is under a retention lock at `now_timestamp`.
+    pub fn is_retention_locked(&self, now_timestamp: i64) -> bool {
+        self.retention_locked_until
+            .map(|locked_until| now_timestamp < locked_until)
+            .unwrap_or(false)
+    }
+
+    /// Returns an error if the index is currently under a retention lock.
+    pub(crate) fn check_retention_lock(&self) -> MetastoreResult<()> {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        match self.retention_locked_until {
+            Some(locked_until) if now_timestamp < locked_until => {
+                Err(MetastoreError::IndexRetentionLocked {
+                    index_id: self.index_id().to_string(),
+                    locked_until,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Places the index under a retention lock until `locked_until`. An active lock can be
+    /// extended but neither shortened nor lifted. Returns whether a mutation occurred.
+    pub(crate) fn set_retention_lock(&mut self, locked_until: i64) -> MetastoreResult<bool> {
+        if let Some(current_locked_until) = self.retention_locked_until {
+            let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+            if now_timestamp < current_locked_until && locked_until < current_locked_until {
+                return Err(MetastoreError::IndexRetentionLocked {
+                    index_id: self.index_id().to_string(),
+                    locked_until: current_locked_until,
+                });
+            }
+        }
+        let mutation_occurred = self.retention_locked_until != Some(locked_until);
+        self.retention_locked_until = Some(locked_until);
+        Ok(mutation_occurred)
+    }
+
+    /// Replaces the labels of a source. Returns whether a mutation occurred.
+    pub(crate) fn update_source_labels(
+        &mut self,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<bool> {
+        let source =
+            self.sources
+                .get_mut(source_id)
+                .ok_or_else(|| MetastoreError::SourceDoesNotExist {
+                    source_id: source_id.to_string(),
+                })?;
+        let mutation_occurred = source.labels != labels;
+        source.labels = labels;
+        Ok(mutation_occurred)
+    }
+
     /// Deletes a source from the index. Returns whether the index was modified (true).
     pub(crate) fn delete_source(&mut self, source_id: &str) -> MetastoreResult<bool> {
         self.sources
@@ -148,6 +314,8 @@ impl TestableForRegression for IndexMetadata {
             checkpoint,
             create_timestamp: 1789,
             sources: Default::default(),
+            state: IndexState::Frozen,
+            retention_locked_until: Some(1789),
         };
         index_metadata
             .add_source(SourceConfig::sample_for_regression())
@@ -160,5 +328,7 @@ impl TestableForRegression for IndexMetadata {
         assert_eq!(self.checkpoint, other.checkpoint);
         assert_eq!(self.create_timestamp, other.create_timestamp);
         assert_eq!(self.sources, other.sources);
+        assert_eq!(self.state, other.state);
+        assert_eq!(self.retention_locked_until, other.retention_locked_until);
     }
 }
@@ -25,7 +25,7 @@ use serde::{self, Deserialize, Serialize};
 
 use crate::checkpoint::IndexCheckpoint;
 use crate::split_metadata::utc_now_timestamp;
-use crate::IndexMetadata;
+use crate::{IndexMetadata, IndexState};
 
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(tag = "version")]
@@ -64,6 +64,8 @@ impl From<IndexMetadata> for IndexMetadataV0_6 {
             checkpoint: index_metadata.checkpoint,
             create_timestamp: index_metadata.create_timestamp,
             sources,
+            state: index_metadata.state,
+            retention_locked_until: index_metadata.retention_locked_until,
         }
     }
 }
@@ -82,6 +84,10 @@ pub(crate) struct IndexMetadataV0_6 {
     pub create_timestamp: i64,
     #[schema(value_type = Vec<VersionedSourceConfig>)]
     pub sources: Vec<SourceConfig>,
+    #[serde(default)]
+    pub state: IndexState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_locked_until: Option<i64>,
 }
 
 impl TryFrom<IndexMetadataV0_6> for IndexMetadata {
@@ -105,6 +111,8 @@ impl TryFrom<IndexMetadataV0_6> for IndexMetadata {
             checkpoint: v0_6.checkpoint,
             create_timestamp: v0_6.create_timestamp,
             sources,
+            state: v0_6.state,
+            retention_locked_until: v0_6.retention_locked_until,
         })
     }
 }
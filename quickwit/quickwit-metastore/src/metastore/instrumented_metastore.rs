@@ -17,14 +17,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::IndexUid;
 
 use crate::checkpoint::IndexCheckpointDelta;
-use crate::{IndexMetadata, ListSplitsQuery, Metastore, MetastoreResult, Split, SplitMetadata};
+use crate::{
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreResult, Split, SplitMetadata,
+};
 
 macro_rules! instrument {
     ($expr:expr, [$operation:ident, $($label:expr),*]) => {
@@ -218,6 +222,94 @@ impl Metastore for InstrumentedMetastore {
         );
     }
 
+    async fn update_index_labels(
+        &self,
+        index_uid: IndexUid,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        instrument!(
+            self.underlying
+                .update_index_labels(index_uid.clone(), labels)
+                .await,
+            [update_index_labels, index_uid.index_id()]
+        );
+    }
+
+    async fn update_source_labels(
+        &self,
+        index_uid: IndexUid,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        instrument!(
+            self.underlying
+                .update_source_labels(index_uid.clone(), source_id, labels)
+                .await,
+            [update_source_labels, index_uid.index_id()]
+        );
+    }
+
+    async fn update_indexing_settings(
+        &self,
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()> {
+        instrument!(
+            self.underlying
+                .update_indexing_settings(index_uid.clone(), indexing_settings)
+                .await,
+            [update_indexing_settings, index_uid.index_id()]
+        );
+    }
+
+    async fn update_lookup_tables(
+        &self,
+        index_uid: IndexUid,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()> {
+        instrument!(
+            self.underlying
+                .update_lookup_tables(index_uid.clone(), lookup_tables)
+                .await,
+            [update_lookup_tables, index_uid.index_id()]
+        );
+    }
+
+    async fn update_search_templates(
+        &self,
+        index_uid: IndexUid,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()> {
+        instrument!(
+            self.underlying
+                .update_search_templates(index_uid.clone(), search_templates)
+                .await,
+            [update_search_templates, index_uid.index_id()]
+        );
+    }
+
+    async fn set_index_state(&self, index_uid: IndexUid, state: IndexState) -> MetastoreResult<()> {
+        instrument!(
+            self.underlying
+                .set_index_state(index_uid.clone(), state)
+                .await,
+            [set_index_state, index_uid.index_id()]
+        );
+    }
+
+    async fn set_retention_lock(
+        &self,
+        index_uid: IndexUid,
+        locked_until: i64,
+    ) -> MetastoreResult<()> {
+        instrument!(
+            self.underlying
+                .set_retention_lock(index_uid.clone(), locked_until)
+                .await,
+            [set_retention_lock, index_uid.index_id()]
+        );
+    }
+
     async fn reset_source_checkpoint(
         &self,
         index_uid: IndexUid,
@@ -17,19 +17,22 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use quickwit_common::pubsub::{Event, EventBroker};
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::IndexUid;
 use tracing::info;
 
 use crate::checkpoint::IndexCheckpointDelta;
-use crate::{IndexMetadata, ListSplitsQuery, Metastore, MetastoreResult, Split, SplitMetadata};
+use crate::{
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreResult, Split, SplitMetadata,
+};
 
 /// Metastore events dispatched to subscribers.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -62,6 +65,20 @@ pub enum MetastoreEvent {
         /// Source ID of the deleted source.
         source_id: String,
     },
+    /// Set index state event.
+    SetIndexState {
+        /// Index ID of the index.
+        index_uid: IndexUid,
+        /// New state of the index.
+        state: IndexState,
+    },
+    /// Publish splits event.
+    PublishSplits {
+        /// Index ID of the index.
+        index_uid: IndexUid,
+        /// IDs of the published splits.
+        split_ids: Vec<String>,
+    },
 }
 
 impl Event for MetastoreEvent {}
@@ -145,6 +162,13 @@ impl Metastore for MetastoreEventPublisher {
         replaced_split_ids: &[&'a str],
         checkpoint_delta_opt: Option<IndexCheckpointDelta>,
     ) -> MetastoreResult<()> {
+        let event = MetastoreEvent::PublishSplits {
+            index_uid: index_uid.clone(),
+            split_ids: split_ids
+                .iter()
+                .map(|split_id| split_id.to_string())
+                .collect(),
+        };
         self.underlying
             .publish_splits(
                 index_uid,
@@ -152,7 +176,9 @@ impl Metastore for MetastoreEventPublisher {
                 replaced_split_ids,
                 checkpoint_delta_opt,
             )
-            .await
+            .await?;
+        self.event_broker.publish(event);
+        Ok(())
     }
 
     async fn list_splits(&self, query: ListSplitsQuery) -> MetastoreResult<Vec<Split>> {
@@ -212,6 +238,75 @@ impl Metastore for MetastoreEventPublisher {
         Ok(())
     }
 
+    async fn update_index_labels(
+        &self,
+        index_uid: IndexUid,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        self.underlying.update_index_labels(index_uid, labels).await
+    }
+
+    async fn update_source_labels(
+        &self,
+        index_uid: IndexUid,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        self.underlying
+            .update_source_labels(index_uid, source_id, labels)
+            .await
+    }
+
+    async fn update_indexing_settings(
+        &self,
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()> {
+        self.underlying
+            .update_indexing_settings(index_uid, indexing_settings)
+            .await
+    }
+
+    async fn update_lookup_tables(
+        &self,
+        index_uid: IndexUid,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()> {
+        self.underlying
+            .update_lookup_tables(index_uid, lookup_tables)
+            .await
+    }
+
+    async fn update_search_templates(
+        &self,
+        index_uid: IndexUid,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()> {
+        self.underlying
+            .update_search_templates(index_uid, search_templates)
+            .await
+    }
+
+    async fn set_index_state(&self, index_uid: IndexUid, state: IndexState) -> MetastoreResult<()> {
+        let event = MetastoreEvent::SetIndexState {
+            index_uid: index_uid.clone(),
+            state,
+        };
+        self.underlying.set_index_state(index_uid, state).await?;
+        self.event_broker.publish(event);
+        Ok(())
+    }
+
+    async fn set_retention_lock(
+        &self,
+        index_uid: IndexUid,
+        locked_until: i64,
+    ) -> MetastoreResult<()> {
+        self.underlying
+            .set_retention_lock(index_uid, locked_until)
+            .await
+    }
+
     async fn reset_source_checkpoint(
         &self,
         index_uid: IndexUid,
@@ -368,4 +463,42 @@ mod tests {
         );
         subscription.cancel();
     }
+
+    #[tokio::test]
+    async fn test_metastore_event_publisher_publish_splits() {
+        let metastore = MetastoreEventPublisher::default_for_test().await;
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let subscription = metastore.event_broker.subscribe(TxSubscriber(tx));
+
+        let index_uid = metastore
+            .create_index(IndexConfig::for_test(
+                "test-index",
+                "ram:///indexes/test-index",
+            ))
+            .await
+            .unwrap();
+        let split_metadata = SplitMetadata {
+            split_id: "test-split".to_string(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        };
+        metastore
+            .stage_splits(index_uid.clone(), vec![split_metadata])
+            .await
+            .unwrap();
+        metastore
+            .publish_splits(index_uid.clone(), &["test-split"], &[], None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            MetastoreEvent::PublishSplits {
+                index_uid,
+                split_ids: vec!["test-split".to_string()],
+            }
+        );
+        subscription.cancel();
+    }
 }
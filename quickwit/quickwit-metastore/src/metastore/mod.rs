@@ -28,12 +28,13 @@ pub mod postgresql_metastore;
 mod postgresql_model;
 pub mod retrying_metastore;
 
+use std::collections::BTreeMap;
 use std::ops::{Bound, RangeInclusive};
 
 use async_trait::async_trait;
-pub use index_metadata::IndexMetadata;
+pub use index_metadata::{IndexMetadata, IndexState};
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::IndexUid;
@@ -268,6 +269,56 @@ pub trait Metastore: Send + Sync + 'static {
         enable: bool,
     ) -> MetastoreResult<()>;
 
+    /// Replaces the labels of an index.
+    async fn update_index_labels(
+        &self,
+        index_uid: IndexUid,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()>;
+
+    /// Replaces the labels of a source.
+    /// Fails with `SourceDoesNotExist` error if the specified source doesn't exist.
+    async fn update_source_labels(
+        &self,
+        index_uid: IndexUid,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()>;
+
+    /// Replaces the indexing settings of an index.
+    async fn update_indexing_settings(
+        &self,
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()>;
+
+    /// Replaces the lookup tables of an index.
+    async fn update_lookup_tables(
+        &self,
+        index_uid: IndexUid,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()>;
+
+    /// Replaces the search templates of an index.
+    async fn update_search_templates(
+        &self,
+        index_uid: IndexUid,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()>;
+
+    /// Sets the lifecycle state of an index: open, closed, or frozen.
+    async fn set_index_state(&self, index_uid: IndexUid, state: IndexState) -> MetastoreResult<()>;
+
+    /// Places an index under a retention lock until `locked_until`, a Unix timestamp in seconds.
+    /// While the lock holds, the index cannot be deleted, its splits can be neither marked for
+    /// deletion nor deleted, and no delete task can be created for it. An active lock can be
+    /// extended but neither shortened nor lifted.
+    async fn set_retention_lock(
+        &self,
+        index_uid: IndexUid,
+        locked_until: i64,
+    ) -> MetastoreResult<()>;
+
     /// Resets the checkpoint of a source identified by `index_uid` and `source_id`.
     async fn reset_source_checkpoint(
         &self,
@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Write};
 use std::ops::Bound;
 use std::sync::Arc;
@@ -28,7 +28,8 @@ use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_common::PrettySample;
 use quickwit_config::{
-    IndexConfig, MetastoreBackend, MetastoreConfig, PostgresMetastoreConfig, SourceConfig,
+    IndexConfig, IndexingSettings, LookupTable, MetastoreBackend, MetastoreConfig,
+    PostgresMetastoreConfig, SearchTemplate, SourceConfig,
 };
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
@@ -47,7 +48,7 @@ use crate::metastore::postgresql_model::{
 };
 use crate::metastore::FilterRange;
 use crate::{
-    IndexMetadata, ListSplitsQuery, Metastore, MetastoreError, MetastoreFactory,
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreError, MetastoreFactory,
     MetastoreResolverError, MetastoreResult, Split, SplitMetadata, SplitState,
 };
 
@@ -192,6 +193,15 @@ async fn index_metadata(
         .index_metadata()
 }
 
+/// Returns an error if the index identified by `index_uid` is under a retention lock.
+async fn check_retention_lock<'a, E>(executor: E, index_uid: &IndexUid) -> MetastoreResult<()>
+where E: sqlx::Executor<'a, Database = Postgres> {
+    if let Some(pg_index) = index_opt_for_uid(executor, index_uid.clone()).await? {
+        pg_index.index_metadata()?.check_retention_lock()?;
+    }
+    Ok(())
+}
+
 /// Extends an existing SQL string with the generated filter range appended to the query.
 ///
 /// This method is **not** SQL injection proof and should not be used with user-defined values.
@@ -447,6 +457,7 @@ impl Metastore for PostgresqlMetastore {
 
     #[instrument(skip(self), fields(index_id=index_uid.index_id()))]
     async fn delete_index(&self, index_uid: IndexUid) -> MetastoreResult<()> {
+        check_retention_lock(&self.connection_pool, &index_uid).await?;
         let delete_res = sqlx::query("DELETE FROM indexes WHERE index_uid = $1")
             .bind(index_uid.to_string())
             .execute(&self.connection_pool)
@@ -755,6 +766,7 @@ impl Metastore for PostgresqlMetastore {
                 COALESCE(ARRAY_AGG(split_id) FILTER (WHERE split_state IS NULL), ARRAY[]::TEXT[])
                 FROM input_splits
         "#;
+        check_retention_lock(&self.connection_pool, &index_uid).await?;
         let (num_found_splits, num_marked_splits, not_found_split_ids): (i64, i64, Vec<String>) =
             sqlx::query_as(MARK_SPLITS_FOR_DELETION_QUERY)
                 .bind(index_uid.to_string())
@@ -833,6 +845,7 @@ impl Metastore for PostgresqlMetastore {
                 COALESCE(ARRAY_AGG(split_id) FILTER (WHERE split_state IS NULL), ARRAY[]::TEXT[])
                 FROM input_splits
         "#;
+        check_retention_lock(&self.connection_pool, &index_uid).await?;
         let (num_found_splits, num_deleted_splits, not_deletable_split_ids, not_found_split_ids): (
             i64,
             i64,
@@ -914,6 +927,108 @@ impl Metastore for PostgresqlMetastore {
         })
     }
 
+    #[instrument(skip(self, labels), fields(index_id=index_uid.index_id()))]
+    async fn update_index_labels(
+        &self,
+        index_uid: IndexUid,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                Ok::<_, MetastoreError>(index_metadata.update_index_labels(labels))
+            })
+            .await?;
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self, labels), fields(index_id=index_uid.index_id(), source_id=source_id))]
+    async fn update_source_labels(
+        &self,
+        index_uid: IndexUid,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                index_metadata.update_source_labels(source_id, labels)
+            })
+            .await?;
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self, indexing_settings), fields(index_id=index_uid.index_id()))]
+    async fn update_indexing_settings(
+        &self,
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                Ok::<_, MetastoreError>(index_metadata.update_indexing_settings(indexing_settings))
+            })
+            .await?;
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self, lookup_tables), fields(index_id=index_uid.index_id()))]
+    async fn update_lookup_tables(
+        &self,
+        index_uid: IndexUid,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                Ok::<_, MetastoreError>(index_metadata.update_lookup_tables(lookup_tables))
+            })
+            .await?;
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self, search_templates), fields(index_id=index_uid.index_id()))]
+    async fn update_search_templates(
+        &self,
+        index_uid: IndexUid,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                Ok::<_, MetastoreError>(index_metadata.update_search_templates(search_templates))
+            })
+            .await?;
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self), fields(index_id=index_uid.index_id()))]
+    async fn set_index_state(&self, index_uid: IndexUid, state: IndexState) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                Ok::<_, MetastoreError>(index_metadata.set_state(state))
+            })
+            .await?;
+            Ok(())
+        })
+    }
+
+    #[instrument(skip(self), fields(index_id=index_uid.index_id()))]
+    async fn set_retention_lock(
+        &self,
+        index_uid: IndexUid,
+        locked_until: i64,
+    ) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                index_metadata.set_retention_lock(locked_until)
+            })
+            .await?;
+            Ok(())
+        })
+    }
+
     #[instrument(skip(self), fields(index_id=index_uid.index_id(), source_id=source_id))]
     async fn delete_source(&self, index_uid: IndexUid, source_id: &str) -> MetastoreResult<()> {
         run_with_tx!(self.connection_pool, tx, {
@@ -967,6 +1082,8 @@ impl Metastore for PostgresqlMetastore {
     /// Creates a delete task from a delete query.
     #[instrument(skip(self), fields(index_id=IndexUid::from(delete_query.index_uid.to_string()).index_id()))]
     async fn create_delete_task(&self, delete_query: DeleteQuery) -> MetastoreResult<DeleteTask> {
+        let index_uid: IndexUid = delete_query.index_uid.clone().into();
+        check_retention_lock(&self.connection_pool, &index_uid).await?;
         let delete_query_json = serde_json::to_string(&delete_query).map_err(|error| {
             MetastoreError::JsonSerializeError {
                 struct_name: "DeleteQuery".to_string(),
@@ -22,15 +22,19 @@ mod retry;
 #[cfg(test)]
 mod test;
 
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::IndexUid;
 
 use self::retry::{retry, RetryParams};
 use crate::checkpoint::IndexCheckpointDelta;
-use crate::{IndexMetadata, ListSplitsQuery, Metastore, MetastoreResult, Split, SplitMetadata};
+use crate::{
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreResult, Split, SplitMetadata,
+};
 
 /// Retry layer for a [`Metastore`].
 /// This is a band-aid solution for now. This will be removed after retry can be usable on
@@ -207,6 +211,92 @@ impl Metastore for RetryingMetastore {
         .await
     }
 
+    async fn update_index_labels(
+        &self,
+        index_uid: IndexUid,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        retry(&self.retry_params, || async {
+            self.inner
+                .update_index_labels(index_uid.clone(), labels.clone())
+                .await
+        })
+        .await
+    }
+
+    async fn update_source_labels(
+        &self,
+        index_uid: IndexUid,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        retry(&self.retry_params, || async {
+            self.inner
+                .update_source_labels(index_uid.clone(), source_id, labels.clone())
+                .await
+        })
+        .await
+    }
+
+    async fn update_indexing_settings(
+        &self,
+        index_uid: IndexUid,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()> {
+        retry(&self.retry_params, || async {
+            self.inner
+                .update_indexing_settings(index_uid.clone(), indexing_settings.clone())
+                .await
+        })
+        .await
+    }
+
+    async fn update_lookup_tables(
+        &self,
+        index_uid: IndexUid,
+        lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()> {
+        retry(&self.retry_params, || async {
+            self.inner
+                .update_lookup_tables(index_uid.clone(), lookup_tables.clone())
+                .await
+        })
+        .await
+    }
+
+    async fn update_search_templates(
+        &self,
+        index_uid: IndexUid,
+        search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()> {
+        retry(&self.retry_params, || async {
+            self.inner
+                .update_search_templates(index_uid.clone(), search_templates.clone())
+                .await
+        })
+        .await
+    }
+
+    async fn set_index_state(&self, index_uid: IndexUid, state: IndexState) -> MetastoreResult<()> {
+        retry(&self.retry_params, || async {
+            self.inner.set_index_state(index_uid.clone(), state).await
+        })
+        .await
+    }
+
+    async fn set_retention_lock(
+        &self,
+        index_uid: IndexUid,
+        locked_until: i64,
+    ) -> MetastoreResult<()> {
+        retry(&self.retry_params, || async {
+            self.inner
+                .set_retention_lock(index_uid.clone(), locked_until)
+                .await
+        })
+        .await
+    }
+
     async fn reset_source_checkpoint(
         &self,
         index_uid: IndexUid,
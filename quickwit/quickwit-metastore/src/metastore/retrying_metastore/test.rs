@@ -17,19 +17,20 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use async_trait::async_trait;
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig};
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::IndexUid;
 
 use super::retry::RetryParams;
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::{
-    IndexMetadata, ListSplitsQuery, Metastore, MetastoreError, MetastoreResult, RetryingMetastore,
-    Split, SplitMetadata,
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreError, MetastoreResult,
+    RetryingMetastore, Split, SplitMetadata,
 };
 
 struct RetryTestMetastore {
@@ -164,6 +165,63 @@ impl Metastore for RetryTestMetastore {
         self.try_success()
     }
 
+    async fn update_index_labels(
+        &self,
+        _index_uid: IndexUid,
+        _labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        self.try_success()
+    }
+
+    async fn update_source_labels(
+        &self,
+        _index_uid: IndexUid,
+        _source_id: &str,
+        _labels: BTreeMap<String, String>,
+    ) -> MetastoreResult<()> {
+        self.try_success()
+    }
+
+    async fn update_indexing_settings(
+        &self,
+        _index_uid: IndexUid,
+        _indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<()> {
+        self.try_success()
+    }
+
+    async fn update_lookup_tables(
+        &self,
+        _index_uid: IndexUid,
+        _lookup_tables: BTreeMap<String, LookupTable>,
+    ) -> MetastoreResult<()> {
+        self.try_success()
+    }
+
+    async fn update_search_templates(
+        &self,
+        _index_uid: IndexUid,
+        _search_templates: BTreeMap<String, SearchTemplate>,
+    ) -> MetastoreResult<()> {
+        self.try_success()
+    }
+
+    async fn set_index_state(
+        &self,
+        _index_uid: IndexUid,
+        _state: IndexState,
+    ) -> MetastoreResult<()> {
+        self.try_success()
+    }
+
+    async fn set_retention_lock(
+        &self,
+        _index_uid: IndexUid,
+        _locked_until: i64,
+    ) -> MetastoreResult<()> {
+        self.try_success()
+    }
+
     async fn reset_source_checkpoint(
         &self,
         _index_uid: IndexUid,
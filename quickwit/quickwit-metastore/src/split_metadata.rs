@@ -23,12 +23,21 @@ use std::ops::{Range, RangeInclusive};
 use std::str::FromStr;
 
 use quickwit_common::FileEntry;
+use quickwit_doc_mapper::DocIdBloomFilter;
 use quickwit_proto::IndexUid;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
 use crate::split_metadata_version::VersionedSplitMetadata;
 
+/// Version of the split file format produced by this version of Quickwit.
+///
+/// This version must be bumped whenever the layout of split files or the codecs used to write
+/// them change in a way that existing splits do not benefit from. Splits written with an older
+/// format version are rewritten in the background by the janitor split upgrade job when it is
+/// enabled.
+pub const SPLIT_FORMAT_VERSION: u32 = 1;
+
 /// Carries split metadata.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct Split {
@@ -126,6 +135,16 @@ pub struct SplitMetadata {
     /// Number of merge operations that was involved to create
     /// this split.
     pub num_merge_ops: usize,
+
+    /// Version of the split file format this split was written with. `0` designates splits
+    /// written before the format was versioned.
+    pub format_version: u32,
+
+    /// Bloom filter over the document IDs of the split, used to prune splits when retrieving
+    /// documents by ID. It is only built when the index defines a doc ID field and the split is
+    /// small enough for the bloom filter to fit in
+    /// [`MAX_DOC_ID_BLOOM_FILTER_NUM_BYTES`](quickwit_doc_mapper::MAX_DOC_ID_BLOOM_FILTER_NUM_BYTES).
+    pub doc_id_bloom_filter: Option<DocIdBloomFilter>,
 }
 
 impl SplitMetadata {
@@ -189,6 +208,8 @@ impl quickwit_config::TestableForRegression for SplitMetadata {
             tags: ["234".to_string(), "aaa".to_string()].into_iter().collect(),
             footer_offsets: 1000..2000,
             num_merge_ops: 3,
+            format_version: 1,
+            doc_id_bloom_filter: None,
         }
     }
 
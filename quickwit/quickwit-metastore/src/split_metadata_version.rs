@@ -20,6 +20,7 @@
 use std::collections::BTreeSet;
 use std::ops::{Range, RangeInclusive};
 
+use quickwit_doc_mapper::DocIdBloomFilter;
 use quickwit_proto::IndexUid;
 use serde::{Deserialize, Serialize};
 
@@ -86,6 +87,22 @@ pub(crate) struct SplitMetadataV0_6 {
 
     #[serde(default)]
     num_merge_ops: usize,
+
+    /// Split file format version. Splits created before the split file format was versioned
+    /// do not have this field and default to `0`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero")]
+    pub format_version: u32,
+
+    /// Bloom filter over the document IDs of the split, encoded in base64.
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_id_bloom_filter: Option<DocIdBloomFilter>,
+}
+
+fn is_zero(format_version: &u32) -> bool {
+    *format_version == 0
 }
 
 impl From<SplitMetadataV0_6> for SplitMetadata {
@@ -120,6 +137,8 @@ impl From<SplitMetadataV0_6> for SplitMetadata {
             tags: v3.tags,
             footer_offsets: v3.footer_offsets,
             num_merge_ops: v3.num_merge_ops,
+            format_version: v3.format_version,
+            doc_id_bloom_filter: v3.doc_id_bloom_filter,
         }
     }
 }
@@ -140,6 +159,8 @@ impl From<SplitMetadata> for SplitMetadataV0_6 {
             tags: split.tags,
             footer_offsets: split.footer_offsets,
             num_merge_ops: split.num_merge_ops,
+            format_version: split.format_version,
+            doc_id_bloom_filter: split.doc_id_bloom_filter,
         }
     }
 }
@@ -19,7 +19,7 @@
 
 #[cfg(test)]
 pub mod test_suite {
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::num::NonZeroUsize;
     use std::sync::Arc;
     use std::time::Duration;
@@ -28,7 +28,10 @@ pub mod test_suite {
     use futures::future::try_join_all;
     use itertools::Itertools;
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::{IndexConfig, SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{
+        IndexConfig, IndexingSettings, LookupTable, SearchTemplate, SourceConfig,
+        SourceInputFormat, SourceParams,
+    };
     use quickwit_doc_mapper::tag_pruning::{no_tag, tag, TagFilterAst};
     use quickwit_proto::metastore_api::DeleteQuery;
     use quickwit_proto::{qast_helper, IndexUid};
@@ -39,7 +42,9 @@ pub mod test_suite {
     use crate::checkpoint::{
         IndexCheckpointDelta, PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
     };
-    use crate::{ListSplitsQuery, Metastore, MetastoreError, Split, SplitMetadata, SplitState};
+    use crate::{
+        IndexState, ListSplitsQuery, Metastore, MetastoreError, Split, SplitMetadata, SplitState,
+    };
 
     #[async_trait]
     pub trait DefaultForTest {
@@ -275,6 +280,7 @@ pub mod test_suite {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
 
         assert_eq!(
@@ -346,6 +352,7 @@ pub mod test_suite {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
         metastore
             .add_source(index_uid.clone(), source.clone())
@@ -376,6 +383,313 @@ pub mod test_suite {
         cleanup_index(&metastore, index_uid).await;
     }
 
+    pub async fn test_metastore_update_labels<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = append_random_suffix("test-update-labels");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config.clone()).await.unwrap();
+
+        let source_id = format!("{index_id}--source");
+        let source = SourceConfig {
+            source_id: source_id.to_string(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+            labels: Default::default(),
+        };
+        metastore
+            .add_source(index_uid.clone(), source.clone())
+            .await
+            .unwrap();
+
+        let labels = BTreeMap::from([
+            ("env".to_string(), "prod".to_string()),
+            ("team".to_string(), "payments".to_string()),
+        ]);
+        metastore
+            .update_index_labels(index_uid.clone(), labels.clone())
+            .await
+            .unwrap();
+        metastore
+            .update_source_labels(index_uid.clone(), &source_id, labels.clone())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert_eq!(index_metadata.index_config.labels, labels);
+        assert_eq!(index_metadata.sources[&source_id].labels, labels);
+
+        // Labels are replaced, not merged.
+        let labels = BTreeMap::from([("team".to_string(), "storage".to_string())]);
+        metastore
+            .update_index_labels(index_uid.clone(), labels.clone())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert_eq!(index_metadata.index_config.labels, labels);
+
+        assert!(matches!(
+            metastore
+                .update_source_labels(index_uid.clone(), "non-existing-source", labels.clone())
+                .await
+                .unwrap_err(),
+            MetastoreError::SourceDoesNotExist { .. }
+        ));
+        assert!(matches!(
+            metastore
+                .update_index_labels(IndexUid::new("non-existing-index"), labels)
+                .await
+                .unwrap_err(),
+            MetastoreError::IndexDoesNotExist { .. }
+        ));
+        cleanup_index(&metastore, index_uid).await;
+    }
+
+    pub async fn test_metastore_update_indexing_settings<
+        MetastoreToTest: Metastore + DefaultForTest,
+    >() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = append_random_suffix("test-update-indexing-settings");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config.clone()).await.unwrap();
+
+        let indexing_settings = IndexingSettings {
+            commit_timeout_secs: 5,
+            split_num_docs_target: 1_000,
+            ..index_config.indexing_settings
+        };
+        metastore
+            .update_indexing_settings(index_uid.clone(), indexing_settings.clone())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert_eq!(
+            index_metadata.index_config.indexing_settings,
+            indexing_settings
+        );
+
+        assert!(matches!(
+            metastore
+                .update_indexing_settings(IndexUid::new("non-existing-index"), indexing_settings)
+                .await
+                .unwrap_err(),
+            MetastoreError::IndexDoesNotExist { .. }
+        ));
+        cleanup_index(&metastore, index_uid).await;
+    }
+
+    pub async fn test_metastore_set_index_state<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = append_random_suffix("test-set-index-state");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config).await.unwrap();
+
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert_eq!(index_metadata.state, IndexState::Open);
+
+        for state in [IndexState::Closed, IndexState::Frozen, IndexState::Open] {
+            metastore
+                .set_index_state(index_uid.clone(), state)
+                .await
+                .unwrap();
+            let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+            assert_eq!(index_metadata.state, state);
+        }
+
+        assert!(matches!(
+            metastore
+                .set_index_state(IndexUid::new("non-existing-index"), IndexState::Closed)
+                .await
+                .unwrap_err(),
+            MetastoreError::IndexDoesNotExist { .. }
+        ));
+        cleanup_index(&metastore, index_uid).await;
+    }
+
+    pub async fn test_metastore_set_retention_lock<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = append_random_suffix("test-set-retention-lock");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config).await.unwrap();
+
+        let split_id = format!("{index_id}--split");
+        let split_metadata = SplitMetadata {
+            split_id: split_id.clone(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        };
+        metastore
+            .stage_splits(index_uid.clone(), vec![split_metadata])
+            .await
+            .unwrap();
+
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert!(index_metadata.retention_locked_until.is_none());
+
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let locked_until = now_timestamp + 3;
+        metastore
+            .set_retention_lock(index_uid.clone(), locked_until)
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert_eq!(index_metadata.retention_locked_until, Some(locked_until));
+        assert!(index_metadata.is_retention_locked(now_timestamp));
+        assert!(!index_metadata.is_retention_locked(locked_until));
+
+        // An active lock cannot be shortened.
+        let error = metastore
+            .set_retention_lock(index_uid.clone(), now_timestamp)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexRetentionLocked { .. }));
+
+        let error = metastore
+            .mark_splits_for_deletion(index_uid.clone(), &[&split_id])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexRetentionLocked { .. }));
+
+        let error = metastore
+            .delete_splits(index_uid.clone(), &[&split_id])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexRetentionLocked { .. }));
+
+        let error = metastore
+            .create_delete_task(DeleteQuery {
+                index_uid: index_uid.to_string(),
+                query_ast: qast_helper("my_field:my_value", &[]),
+                start_timestamp: None,
+                end_timestamp: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexRetentionLocked { .. }));
+
+        let error = metastore.delete_index(index_uid.clone()).await.unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexRetentionLocked { .. }));
+
+        let splits = metastore.list_all_splits(index_uid.clone()).await.unwrap();
+        assert_eq!(splits.len(), 1);
+        assert_eq!(splits[0].split_state, SplitState::Staged);
+
+        assert!(matches!(
+            metastore
+                .set_retention_lock(IndexUid::new("non-existing-index"), locked_until)
+                .await
+                .unwrap_err(),
+            MetastoreError::IndexDoesNotExist { .. }
+        ));
+
+        // Once the lock has expired, the index can be deleted.
+        sleep(Duration::from_secs(4)).await;
+        cleanup_index(&metastore, index_uid).await;
+    }
+
+    pub async fn test_metastore_update_lookup_tables<
+        MetastoreToTest: Metastore + DefaultForTest,
+    >() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = append_random_suffix("test-update-lookup-tables");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config.clone()).await.unwrap();
+
+        let lookup_tables = BTreeMap::from([(
+            "http_status".to_string(),
+            LookupTable {
+                field: "status".to_string(),
+                rows: BTreeMap::from([(
+                    "404".to_string(),
+                    BTreeMap::from([("description".to_string(), "Not Found".to_string())]),
+                )]),
+            },
+        )]);
+        metastore
+            .update_lookup_tables(index_uid.clone(), lookup_tables.clone())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert_eq!(index_metadata.index_config.lookup_tables, lookup_tables);
+
+        metastore
+            .update_lookup_tables(index_uid.clone(), BTreeMap::new())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert!(index_metadata.index_config.lookup_tables.is_empty());
+
+        assert!(matches!(
+            metastore
+                .update_lookup_tables(IndexUid::new("non-existing-index"), lookup_tables)
+                .await
+                .unwrap_err(),
+            MetastoreError::IndexDoesNotExist { .. }
+        ));
+        cleanup_index(&metastore, index_uid).await;
+    }
+
+    pub async fn test_metastore_update_search_templates<
+        MetastoreToTest: Metastore + DefaultForTest,
+    >() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = append_random_suffix("test-update-search-templates");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let index_uid = metastore.create_index(index_config.clone()).await.unwrap();
+
+        let search_templates = BTreeMap::from([(
+            "by_severity".to_string(),
+            SearchTemplate {
+                source: r#"{"query": {"term": {"severity_text": "{{severity}}"}}}"#.to_string(),
+            },
+        )]);
+        metastore
+            .update_search_templates(index_uid.clone(), search_templates.clone())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert_eq!(
+            index_metadata.index_config.search_templates,
+            search_templates
+        );
+
+        metastore
+            .update_search_templates(index_uid.clone(), BTreeMap::new())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(&index_id).await.unwrap();
+        assert!(index_metadata.index_config.search_templates.is_empty());
+
+        assert!(matches!(
+            metastore
+                .update_search_templates(IndexUid::new("non-existing-index"), search_templates)
+                .await
+                .unwrap_err(),
+            MetastoreError::IndexDoesNotExist { .. }
+        ));
+        cleanup_index(&metastore, index_uid).await;
+    }
+
     pub async fn test_metastore_delete_source<MetastoreToTest: Metastore + DefaultForTest>() {
         let metastore = MetastoreToTest::default_for_test().await;
 
@@ -391,6 +705,7 @@ pub mod test_suite {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            labels: Default::default(),
         };
 
         let index_config = IndexConfig::for_test(&index_id, index_uri.as_str());
@@ -469,6 +784,7 @@ pub mod test_suite {
                 source_params: SourceParams::void(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                labels: Default::default(),
             };
             metastore
                 .add_source(index_uid.clone(), source.clone())
@@ -2789,6 +3105,42 @@ macro_rules! metastore_test_suite {
                 crate::tests::test_suite::test_metastore_toggle_source::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_update_labels() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_update_labels::<$metastore_type>().await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_update_indexing_settings() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_update_indexing_settings::<$metastore_type>().await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_set_retention_lock() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_set_retention_lock::<$metastore_type>().await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_update_lookup_tables() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_update_lookup_tables::<$metastore_type>().await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_update_search_templates() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_update_search_templates::<$metastore_type>().await;
+            }
+
+            #[tokio::test]
+            async fn test_metastore_set_index_state() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_set_index_state::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_delete_source() {
                 let _ = tracing_subscriber::fmt::try_init();
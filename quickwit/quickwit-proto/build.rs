@@ -22,6 +22,9 @@ use std::path::PathBuf;
 use glob::glob;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The file descriptor sets are used by the gRPC reflection service.
+    let descriptor_dir_path = PathBuf::from(std::env::var("OUT_DIR")?);
+
     // Quickwit proto
     let protos = find_protos("protos/quickwit");
 
@@ -44,6 +47,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .type_attribute("OutputFormat", "#[serde(rename_all = \"snake_case\")]")
         .type_attribute("PartialHit.sort_value", "#[derive(Copy)]")
         .type_attribute("SortOrder", "#[serde(rename_all = \"lowercase\")]")
+        .file_descriptor_set_path(descriptor_dir_path.join("quickwit_descriptor.bin"))
         .out_dir("src/")
         .compile_with_config(prost_config, &protos, &["protos/quickwit"])?;
 
@@ -54,6 +58,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     prost_config.type_attribute("Operation", "#[derive(Eq, Ord, PartialOrd)]");
 
     tonic_build::configure()
+        .file_descriptor_set_path(descriptor_dir_path.join("jaeger_descriptor.bin"))
         .out_dir("src/")
         .compile_with_config(
             prost_config,
@@ -69,6 +74,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .type_attribute(".", "#[derive(Serialize, Deserialize)]")
         .type_attribute("StatusCode", r#"#[serde(rename_all = "snake_case")]"#)
+        .file_descriptor_set_path(descriptor_dir_path.join("otlp_descriptor.bin"))
         .out_dir("src/")
         .compile_with_config(prost_config, &protos, &["protos/third-party/otlp"])?;
     Ok(())
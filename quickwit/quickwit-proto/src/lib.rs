@@ -120,6 +120,19 @@ use tonic::Status;
 use tracing::Span;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
+/// Encoded file descriptor set of the Quickwit indexing, metastore, and search services, served
+/// by the gRPC reflection service.
+pub const QUICKWIT_FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/quickwit_descriptor.bin"));
+
+/// Encoded file descriptor set of the Jaeger storage plugin services.
+pub const JAEGER_FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/jaeger_descriptor.bin"));
+
+/// Encoded file descriptor set of the OpenTelemetry collector services.
+pub const OTLP_FILE_DESCRIPTOR_SET: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/otlp_descriptor.bin"));
+
 /// This enum serves as a Rosetta stone of
 /// gRPC and Http status code.
 ///
@@ -131,6 +144,7 @@ pub enum ServiceErrorCode {
     MethodNotAllowed,
     NotFound,
     RateLimited,
+    Unauthorized,
     Unavailable,
     UnsupportedMediaType,
     NotSupportedYet, //< Used for API that is available in elasticsearch but is not yet available in Quickwit.
@@ -144,6 +158,7 @@ impl ServiceErrorCode {
             ServiceErrorCode::MethodNotAllowed => tonic::Code::InvalidArgument,
             ServiceErrorCode::NotFound => tonic::Code::NotFound,
             ServiceErrorCode::RateLimited => tonic::Code::ResourceExhausted,
+            ServiceErrorCode::Unauthorized => tonic::Code::Unauthenticated,
             ServiceErrorCode::Unavailable => tonic::Code::Unavailable,
             ServiceErrorCode::UnsupportedMediaType => tonic::Code::InvalidArgument,
             ServiceErrorCode::NotSupportedYet => tonic::Code::Unimplemented,
@@ -156,6 +171,7 @@ impl ServiceErrorCode {
             ServiceErrorCode::MethodNotAllowed => http::StatusCode::METHOD_NOT_ALLOWED,
             ServiceErrorCode::NotFound => http::StatusCode::NOT_FOUND,
             ServiceErrorCode::RateLimited => http::StatusCode::TOO_MANY_REQUESTS,
+            ServiceErrorCode::Unauthorized => http::StatusCode::UNAUTHORIZED,
             ServiceErrorCode::Unavailable => http::StatusCode::SERVICE_UNAVAILABLE,
             ServiceErrorCode::UnsupportedMediaType => http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
             ServiceErrorCode::NotSupportedYet => http::StatusCode::NOT_IMPLEMENTED,
@@ -455,6 +471,10 @@ pub fn query_ast_from_user_text(user_text: &str, default_fields: Option<Vec<Stri
         user_text: user_text.to_string(),
         default_fields,
         default_operator: quickwit_query::BooleanOperand::And,
+        quote_field_suffix: None,
+        phrase_slop: 0,
+        lenient: false,
+        fuzziness: None,
     }
     .into()
 }
@@ -42,6 +42,33 @@ pub struct SearchRequest {
     /// Fields to extract snippet on
     #[prost(string, repeated, tag = "12")]
     pub snippet_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// If set, the search also covers the documents ingested over the last
+    /// `realtime_window_secs` seconds that are still sitting in the indexers' ingest
+    /// queues and have not been published yet.
+    #[prost(uint64, optional, tag = "14")]
+    pub realtime_window_secs: ::core::option::Option<u64>,
+    /// Position token returned by the ingest API. If set, the search waits until the
+    /// documents ingested up to that position are searchable.
+    #[prost(string, optional, tag = "15")]
+    pub position_token: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set to false, the number of hits returned is only a lower bound of the number of
+    /// documents matching the query. Searches sorted by descending score can then skip the
+    /// blocks of documents that cannot make it into the top hits (block-max WAND).
+    #[prost(bool, optional, tag = "16")]
+    pub track_total_hits: ::core::option::Option<bool>,
+    /// If true, the leaves record the time spent executing each node of the query and
+    /// return a per-split breakdown of the search.
+    #[prost(bool, tag = "17")]
+    pub profile: bool,
+    /// Json serialized join of the top hits with the documents of a second, small index
+    /// or with the rows of a lookup table, on a key field.
+    #[prost(string, optional, tag = "18")]
+    pub join: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, only the first hit for each value of this fast field is returned.
+    /// Hits are deduplicated by the root once fetched, so a page may hold fewer
+    /// than `max_hits` hits.
+    #[prost(string, optional, tag = "19")]
+    pub dedup_by: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -63,6 +90,32 @@ pub struct SearchResponse {
     /// Serialized aggregation response
     #[prost(string, optional, tag = "5")]
     pub aggregation: ::core::option::Option<::prost::alloc::string::String>,
+    /// Per-split profiles, only populated if the request asked for profiling.
+    #[prost(message, repeated, tag = "6")]
+    pub split_profiles: ::prost::alloc::vec::Vec<SplitSearchProfile>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SplitSearchProfile {
+    /// Split ID.
+    #[prost(string, tag = "1")]
+    pub split_id: ::prost::alloc::string::String,
+    /// Number of bytes fetched from the storage to warm up the split.
+    #[prost(uint64, tag = "2")]
+    pub warmup_num_bytes: u64,
+    /// Time spent warming up the split, in nanoseconds.
+    #[prost(uint64, tag = "3")]
+    pub warmup_time_nanos: u64,
+    /// Time spent creating the weights and scorers of the query, in nanoseconds.
+    #[prost(uint64, tag = "4")]
+    pub scorer_init_time_nanos: u64,
+    /// Time spent iterating over the matching documents and collecting them, in nanoseconds.
+    #[prost(uint64, tag = "5")]
+    pub collect_time_nanos: u64,
+    /// JSON serialized profile of the nodes of the query executed on the split.
+    #[prost(string, tag = "6")]
+    pub query_profile: ::prost::alloc::string::String,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -97,6 +150,10 @@ pub struct LeafSearchRequest {
     /// split files.
     #[prost(string, tag = "6")]
     pub index_uri: ::prost::alloc::string::String,
+    /// If true, the searcher neither reads from nor populates its long-term caches
+    /// (split footer, fast fields, and leaf search caches). Set for frozen indexes.
+    #[prost(bool, tag = "7")]
+    pub bypass_caches: bool,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -118,6 +175,9 @@ pub struct SplitIdAndFooterOffsets {
     /// The highest timestamp appearing in the split
     #[prost(int64, optional, tag = "5")]
     pub timestamp_end: ::core::option::Option<i64>,
+    /// The timestamp at which the split was created
+    #[prost(int64, optional, tag = "6")]
+    pub create_timestamp: ::core::option::Option<i64>,
 }
 /// / Hits returned by a FetchDocRequest.
 /// /
@@ -239,6 +299,9 @@ pub struct LeafSearchResponse {
     pub intermediate_aggregation_result: ::core::option::Option<
         ::prost::alloc::vec::Vec<u8>,
     >,
+    /// Per-split profiles, only populated if the request asked for profiling.
+    #[prost(message, repeated, tag = "7")]
+    pub split_profiles: ::prost::alloc::vec::Vec<SplitSearchProfile>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -354,6 +417,48 @@ pub struct LeafListTermsResponse {
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WarmupSplitsRequest {
+    /// Index URI. The index URI defines the location of the storage that contains the
+    /// split files.
+    #[prost(string, tag = "1")]
+    pub index_uri: ::prost::alloc::string::String,
+    /// Splits to warm up.
+    #[prost(message, repeated, tag = "2")]
+    pub split_offsets: ::prost::alloc::vec::Vec<SplitIdAndFooterOffsets>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WarmupSplitsResponse {
+    /// Number of splits whose hotcache and footer were fetched.
+    #[prost(uint64, tag = "1")]
+    pub num_warmed_up_splits: u64,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchCachedSplitFooterRequest {
+    /// Split ID.
+    #[prost(string, tag = "1")]
+    pub split_id: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FetchCachedSplitFooterResponse {
+    /// Whether the split footer is present in the searcher's split footer cache.
+    #[prost(bool, tag = "1")]
+    pub is_cached: bool,
+    /// Hotcache and footer of the split. Empty if the split footer is not cached.
+    #[prost(bytes = "vec", tag = "2")]
+    pub split_footer: ::prost::alloc::vec::Vec<u8>,
+    /// MD5 digest of `split_footer`, used by the caller to check the integrity of the transfer.
+    #[prost(bytes = "vec", tag = "3")]
+    pub split_footer_md5: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SearchStreamRequest {
     /// Index ID
     #[prost(string, tag = "1")]
@@ -734,6 +839,67 @@ pub mod search_service_client {
                 .insert(GrpcMethod::new("quickwit.SearchService", "LeafListTerms"));
             self.inner.unary(req, path, codec).await
         }
+        /// Fetches the hotcache and footer of the given splits into the searcher's split footer cache.
+        ///
+        /// This RPC is called when splits are published so that the first queries hitting them do not
+        /// pay for the footer fetch.
+        pub async fn warmup_splits(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WarmupSplitsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::WarmupSplitsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.SearchService/WarmupSplits",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("quickwit.SearchService", "WarmupSplits"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the hotcache and footer of a split if it is present in the searcher's split footer
+        /// cache.
+        ///
+        /// Searchers call this RPC on their peers before fetching a split footer from object storage.
+        /// It never hits the storage.
+        pub async fn fetch_cached_split_footer(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FetchCachedSplitFooterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FetchCachedSplitFooterResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.SearchService/FetchCachedSplitFooter",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("quickwit.SearchService", "FetchCachedSplitFooter"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -817,6 +983,29 @@ pub mod search_service_server {
             tonic::Response<super::LeafListTermsResponse>,
             tonic::Status,
         >;
+        /// Fetches the hotcache and footer of the given splits into the searcher's split footer cache.
+        ///
+        /// This RPC is called when splits are published so that the first queries hitting them do not
+        /// pay for the footer fetch.
+        async fn warmup_splits(
+            &self,
+            request: tonic::Request<super::WarmupSplitsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::WarmupSplitsResponse>,
+            tonic::Status,
+        >;
+        /// Returns the hotcache and footer of a split if it is present in the searcher's split footer
+        /// cache.
+        ///
+        /// Searchers call this RPC on their peers before fetching a split footer from object storage.
+        /// It never hits the storage.
+        async fn fetch_cached_split_footer(
+            &self,
+            request: tonic::Request<super::FetchCachedSplitFooterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FetchCachedSplitFooterResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct SearchServiceServer<T: SearchService> {
@@ -1169,6 +1358,98 @@ pub mod search_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.SearchService/WarmupSplits" => {
+                    #[allow(non_camel_case_types)]
+                    struct WarmupSplitsSvc<T: SearchService>(pub Arc<T>);
+                    impl<
+                        T: SearchService,
+                    > tonic::server::UnaryService<super::WarmupSplitsRequest>
+                    for WarmupSplitsSvc<T> {
+                        type Response = super::WarmupSplitsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WarmupSplitsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).warmup_splits(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WarmupSplitsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+"/quickwit.SearchService/FetchCachedSplitFooter" => {
+                    #[allow(non_camel_case_types)]
+                    struct FetchCachedSplitFooterSvc<T: SearchService>(pub Arc<T>);
+                    impl<
+                        T: SearchService,
+                    > tonic::server::UnaryService<super::FetchCachedSplitFooterRequest>
+                    for FetchCachedSplitFooterSvc<T> {
+                        type Response = super::FetchCachedSplitFooterResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FetchCachedSplitFooterRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).fetch_cached_split_footer(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = FetchCachedSplitFooterSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
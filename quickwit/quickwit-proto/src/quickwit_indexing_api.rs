@@ -10,6 +10,22 @@ pub struct ApplyIndexingPlanRequest {
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ApplyIndexingPlanResponse {}
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ForceCommitRequest {
+    /// / ID of the index to commit.
+    #[prost(string, tag = "1")]
+    pub index_id: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ForceCommitResponse {
+    /// / Number of pipelines asked to commit.
+    #[prost(uint64, tag = "1")]
+    pub num_pipelines: u64,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[derive(Eq, Hash)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -137,6 +153,37 @@ pub mod indexing_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// / Commits right away the splits under construction of the pipelines indexing an index.
+        pub async fn force_commit(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ForceCommitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ForceCommitResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_indexing_api.IndexingService/forceCommit",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_indexing_api.IndexingService",
+                        "forceCommit",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -154,6 +201,14 @@ pub mod indexing_service_server {
             tonic::Response<super::ApplyIndexingPlanResponse>,
             tonic::Status,
         >;
+        /// / Commits right away the splits under construction of the pipelines indexing an index.
+        async fn force_commit(
+            &self,
+            request: tonic::Request<super::ForceCommitRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ForceCommitResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct IndexingServiceServer<T: IndexingService> {
@@ -280,6 +335,52 @@ pub mod indexing_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit_indexing_api.IndexingService/forceCommit" => {
+                    #[allow(non_camel_case_types)]
+                    struct forceCommitSvc<T: IndexingService>(pub Arc<T>);
+                    impl<
+                        T: IndexingService,
+                    > tonic::server::UnaryService<super::ForceCommitRequest>
+                    for forceCommitSvc<T> {
+                        type Response = super::ForceCommitResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ForceCommitRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).force_commit(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = forceCommitSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
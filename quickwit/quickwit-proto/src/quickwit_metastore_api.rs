@@ -41,6 +41,89 @@ pub struct DeleteIndexResponse {}
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateIndexLabelsRequest {
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "2")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateIndexLabelsResponse {}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateIndexingSettingsRequest {
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub indexing_settings_serialized_json: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateIndexingSettingsResponse {}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateLookupTablesRequest {
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub lookup_tables_serialized_json: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateLookupTablesResponse {}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateSearchTemplatesRequest {
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub search_templates_serialized_json: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateSearchTemplatesResponse {}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetIndexStateRequest {
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+    /// One of `open`, `closed`, or `frozen`.
+    #[prost(string, tag = "2")]
+    pub state: ::prost::alloc::string::String,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetIndexStateResponse {}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetRetentionLockRequest {
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+    /// Unix timestamp (in seconds) until which the index is locked.
+    #[prost(int64, tag = "2")]
+    pub locked_until: i64,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetRetentionLockResponse {}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct IndexMetadataRequest {
     #[prost(string, tag = "1")]
     pub index_id: ::prost::alloc::string::String,
@@ -142,6 +225,20 @@ pub struct ToggleSourceRequest {
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateSourceLabelsRequest {
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DeleteSourceRequest {
     #[prost(string, tag = "1")]
     pub index_uid: ::prost::alloc::string::String,
@@ -688,6 +785,220 @@ pub mod metastore_api_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Replaces the labels of an index.
+        pub async fn update_index_labels(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateIndexLabelsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateIndexLabelsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_metastore_api.MetastoreApiService/update_index_labels",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_metastore_api.MetastoreApiService",
+                        "update_index_labels",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Replaces the indexing settings of an index.
+        pub async fn update_indexing_settings(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateIndexingSettingsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateIndexingSettingsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_metastore_api.MetastoreApiService/update_indexing_settings",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_metastore_api.MetastoreApiService",
+                        "update_indexing_settings",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Replaces the lookup tables of an index.
+        pub async fn update_lookup_tables(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateLookupTablesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateLookupTablesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_metastore_api.MetastoreApiService/update_lookup_tables",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_metastore_api.MetastoreApiService",
+                        "update_lookup_tables",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Replaces the search templates of an index.
+        pub async fn update_search_templates(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateSearchTemplatesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateSearchTemplatesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_metastore_api.MetastoreApiService/update_search_templates",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_metastore_api.MetastoreApiService",
+                        "update_search_templates",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Sets the lifecycle state of an index: open, closed, or frozen.
+        pub async fn set_index_state(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetIndexStateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetIndexStateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_metastore_api.MetastoreApiService/set_index_state",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_metastore_api.MetastoreApiService",
+                        "set_index_state",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Places an index under a retention lock until a given date.
+        pub async fn set_retention_lock(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SetRetentionLockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetRetentionLockResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_metastore_api.MetastoreApiService/set_retention_lock",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_metastore_api.MetastoreApiService",
+                        "set_retention_lock",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Replaces the labels of a source.
+        pub async fn update_source_labels(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateSourceLabelsRequest>,
+        ) -> std::result::Result<tonic::Response<super::SourceResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit_metastore_api.MetastoreApiService/update_source_labels",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit_metastore_api.MetastoreApiService",
+                        "update_source_labels",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Removes source.
         pub async fn delete_source(
             &mut self,
@@ -983,16 +1294,69 @@ pub mod metastore_api_service_server {
             &self,
             request: tonic::Request<super::ToggleSourceRequest>,
         ) -> std::result::Result<tonic::Response<super::SourceResponse>, tonic::Status>;
-        /// Removes source.
-        async fn delete_source(
+        /// Replaces the labels of an index.
+        async fn update_index_labels(
             &self,
-            request: tonic::Request<super::DeleteSourceRequest>,
-        ) -> std::result::Result<tonic::Response<super::SourceResponse>, tonic::Status>;
-        /// Resets source checkpoint.
-        async fn reset_source_checkpoint(
+            request: tonic::Request<super::UpdateIndexLabelsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateIndexLabelsResponse>,
+            tonic::Status,
+        >;
+        /// Replaces the indexing settings of an index.
+        async fn update_indexing_settings(
             &self,
-            request: tonic::Request<super::ResetSourceCheckpointRequest>,
-        ) -> std::result::Result<tonic::Response<super::SourceResponse>, tonic::Status>;
+            request: tonic::Request<super::UpdateIndexingSettingsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateIndexingSettingsResponse>,
+            tonic::Status,
+        >;
+        /// Replaces the lookup tables of an index.
+        async fn update_lookup_tables(
+            &self,
+            request: tonic::Request<super::UpdateLookupTablesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateLookupTablesResponse>,
+            tonic::Status,
+        >;
+        /// Replaces the search templates of an index.
+        async fn update_search_templates(
+            &self,
+            request: tonic::Request<super::UpdateSearchTemplatesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateSearchTemplatesResponse>,
+            tonic::Status,
+        >;
+        /// Sets the lifecycle state of an index: open, closed, or frozen.
+        async fn set_index_state(
+            &self,
+            request: tonic::Request<super::SetIndexStateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetIndexStateResponse>,
+            tonic::Status,
+        >;
+        /// Places an index under a retention lock until a given date.
+        async fn set_retention_lock(
+            &self,
+            request: tonic::Request<super::SetRetentionLockRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SetRetentionLockResponse>,
+            tonic::Status,
+        >;
+        /// Replaces the labels of a source.
+        async fn update_source_labels(
+            &self,
+            request: tonic::Request<super::UpdateSourceLabelsRequest>,
+        ) -> std::result::Result<tonic::Response<super::SourceResponse>, tonic::Status>;
+        /// Removes source.
+        async fn delete_source(
+            &self,
+            request: tonic::Request<super::DeleteSourceRequest>,
+        ) -> std::result::Result<tonic::Response<super::SourceResponse>, tonic::Status>;
+        /// Resets source checkpoint.
+        async fn reset_source_checkpoint(
+            &self,
+            request: tonic::Request<super::ResetSourceCheckpointRequest>,
+        ) -> std::result::Result<tonic::Response<super::SourceResponse>, tonic::Status>;
         /// Gets last opstamp for a given `index_id`.
         async fn last_delete_opstamp(
             &self,
@@ -1660,6 +2024,328 @@ pub mod metastore_api_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit_metastore_api.MetastoreApiService/update_index_labels" => {
+                    #[allow(non_camel_case_types)]
+                    struct update_index_labelsSvc<T: MetastoreApiService>(pub Arc<T>);
+                    impl<
+                        T: MetastoreApiService,
+                    > tonic::server::UnaryService<super::UpdateIndexLabelsRequest>
+                    for update_index_labelsSvc<T> {
+                        type Response = super::UpdateIndexLabelsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateIndexLabelsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_index_labels(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = update_index_labelsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit_metastore_api.MetastoreApiService/update_indexing_settings" => {
+                    #[allow(non_camel_case_types)]
+                    struct update_indexing_settingsSvc<T: MetastoreApiService>(pub Arc<T>);
+                    impl<
+                        T: MetastoreApiService,
+                    > tonic::server::UnaryService<super::UpdateIndexingSettingsRequest>
+                    for update_indexing_settingsSvc<T> {
+                        type Response = super::UpdateIndexingSettingsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateIndexingSettingsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_indexing_settings(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = update_indexing_settingsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit_metastore_api.MetastoreApiService/update_lookup_tables" => {
+                    #[allow(non_camel_case_types)]
+                    struct update_lookup_tablesSvc<T: MetastoreApiService>(pub Arc<T>);
+                    impl<
+                        T: MetastoreApiService,
+                    > tonic::server::UnaryService<super::UpdateLookupTablesRequest>
+                    for update_lookup_tablesSvc<T> {
+                        type Response = super::UpdateLookupTablesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateLookupTablesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_lookup_tables(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = update_lookup_tablesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit_metastore_api.MetastoreApiService/update_search_templates" => {
+                    #[allow(non_camel_case_types)]
+                    struct update_search_templatesSvc<T: MetastoreApiService>(pub Arc<T>);
+                    impl<
+                        T: MetastoreApiService,
+                    > tonic::server::UnaryService<super::UpdateSearchTemplatesRequest>
+                    for update_search_templatesSvc<T> {
+                        type Response = super::UpdateSearchTemplatesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateSearchTemplatesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_search_templates(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = update_search_templatesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit_metastore_api.MetastoreApiService/set_index_state" => {
+                    #[allow(non_camel_case_types)]
+                    struct set_index_stateSvc<T: MetastoreApiService>(pub Arc<T>);
+                    impl<
+                        T: MetastoreApiService,
+                    > tonic::server::UnaryService<super::SetIndexStateRequest>
+                    for set_index_stateSvc<T> {
+                        type Response = super::SetIndexStateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetIndexStateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).set_index_state(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = set_index_stateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit_metastore_api.MetastoreApiService/set_retention_lock" => {
+                    #[allow(non_camel_case_types)]
+                    struct set_retention_lockSvc<T: MetastoreApiService>(pub Arc<T>);
+                    impl<
+                        T: MetastoreApiService,
+                    > tonic::server::UnaryService<super::SetRetentionLockRequest>
+                    for set_retention_lockSvc<T> {
+                        type Response = super::SetRetentionLockResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetRetentionLockRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).set_retention_lock(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = set_retention_lockSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit_metastore_api.MetastoreApiService/update_source_labels" => {
+                    #[allow(non_camel_case_types)]
+                    struct update_source_labelsSvc<T: MetastoreApiService>(pub Arc<T>);
+                    impl<
+                        T: MetastoreApiService,
+                    > tonic::server::UnaryService<super::UpdateSourceLabelsRequest>
+                    for update_source_labelsSvc<T> {
+                        type Response = super::SourceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateSourceLabelsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_source_labels(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = update_source_labelsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit_metastore_api.MetastoreApiService/delete_source" => {
                     #[allow(non_camel_case_types)]
                     struct delete_sourceSvc<T: MetastoreApiService>(pub Arc<T>);
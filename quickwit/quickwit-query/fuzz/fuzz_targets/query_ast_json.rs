@@ -0,0 +1,55 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Deserializes arbitrary JSON into a `QueryAst`, checks that it survives a JSON round trip and
+//! builds it against a schema with a field of each kind. Errors are expected, panics are bugs.
+//!
+//! Run with `cargo +nightly fuzz run query_ast_json` from the `quickwit-query` directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use quickwit_query::query_ast::strategies::{schema_with_all_field_kinds, FieldKind};
+use quickwit_query::query_ast::{query_ast_from_json, query_ast_to_json};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(query_ast_json) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(query_ast) = query_ast_from_json(query_ast_json) else {
+        return;
+    };
+    let serialized_query_ast_json = query_ast_to_json(&query_ast).unwrap();
+    let deserialized_query_ast = query_ast_from_json(&serialized_query_ast_json).unwrap();
+    assert_eq!(deserialized_query_ast, query_ast);
+
+    let schema = schema_with_all_field_kinds();
+    let default_search_fields: Vec<String> = FieldKind::ALL
+        .iter()
+        .map(|field_kind| field_kind.name().to_string())
+        .collect();
+    let Ok(query_ast) = query_ast.parse_user_query(&default_search_fields) else {
+        return;
+    };
+    for with_validation in [false, true] {
+        for with_scoring in [false, true] {
+            let _ = query_ast.build_tantivy_query(&schema, &[], with_validation, with_scoring);
+        }
+    }
+});
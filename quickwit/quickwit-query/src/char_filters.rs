@@ -0,0 +1,433 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Character filters transform the text of a field before it is tokenized.
+//!
+//! A text field configured with character filters is indexed and searched with a dedicated
+//! tokenizer, registered in the Quickwit tokenizer manager under a name derived from the base
+//! tokenizer and the filters. Since the registered tokenizer is used both at indexing and query
+//! time, the filters are applied consistently to documents and queries.
+//!
+//! The offsets of the tokens refer to the original text, so that snippets highlight the right
+//! fragments.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tantivy::tokenizer::{TextAnalyzer, Token, TokenStream, Tokenizer};
+
+use crate::get_quickwit_tokenizer_manager;
+
+/// Maximum number of bytes of an HTML character reference, e.g. `&#x1F600;`.
+const MAX_HTML_ENTITY_LEN: usize = 10;
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CharFilter {
+    /// Removes HTML tags and decodes HTML character references. Each tag is replaced by a
+    /// whitespace so that the words it separates are not merged.
+    HtmlStrip,
+    /// Replaces the matches of a regular expression. The replacement can refer to capture groups
+    /// with the `$1` or `${name}` syntax.
+    PatternReplace {
+        pattern: String,
+        #[serde(default)]
+        replacement: String,
+    },
+}
+
+/// Returns the name of the tokenizer applying the `char_filters` before tokenizing the text with
+/// the tokenizer `base_tokenizer_name`.
+pub fn char_filter_tokenizer_name(
+    base_tokenizer_name: &str,
+    char_filters: &[CharFilter],
+) -> String {
+    let char_filters_json =
+        serde_json::to_string(char_filters).expect("Character filters should be serializable.");
+    format!("{base_tokenizer_name}+{char_filters_json}")
+}
+
+/// Registers in the Quickwit tokenizer manager the tokenizer applying the `char_filters` before
+/// tokenizing the text with the tokenizer `base_tokenizer_name`, and returns its name.
+pub fn register_char_filter_tokenizer(
+    base_tokenizer_name: &str,
+    char_filters: &[CharFilter],
+) -> anyhow::Result<String> {
+    let tokenizer_name = char_filter_tokenizer_name(base_tokenizer_name, char_filters);
+    let tokenizer_manager = get_quickwit_tokenizer_manager();
+
+    if tokenizer_manager.get(&tokenizer_name).is_some() {
+        return Ok(tokenizer_name);
+    }
+    let text_analyzer = tokenizer_manager
+        .get(base_tokenizer_name)
+        .with_context(|| format!("No tokenizer named `{base_tokenizer_name}` is registered."))?;
+    let compiled_char_filters = char_filters
+        .iter()
+        .map(CompiledCharFilter::compile)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let char_filter_tokenizer = CharFilterTokenizer {
+        char_filters: Arc::new(compiled_char_filters),
+        text_analyzer,
+    };
+    tokenizer_manager.register(
+        &tokenizer_name,
+        TextAnalyzer::builder(char_filter_tokenizer).build(),
+    );
+    Ok(tokenizer_name)
+}
+
+#[derive(Clone)]
+enum CompiledCharFilter {
+    HtmlStrip,
+    PatternReplace { regex: Regex, replacement: String },
+}
+
+impl CompiledCharFilter {
+    fn compile(char_filter: &CharFilter) -> anyhow::Result<Self> {
+        match char_filter {
+            CharFilter::HtmlStrip => Ok(CompiledCharFilter::HtmlStrip),
+            CharFilter::PatternReplace {
+                pattern,
+                replacement,
+            } => {
+                let regex = Regex::new(pattern)
+                    .with_context(|| format!("Invalid `pattern_replace` pattern `{pattern}`."))?;
+                Ok(CompiledCharFilter::PatternReplace {
+                    regex,
+                    replacement: replacement.clone(),
+                })
+            }
+        }
+    }
+
+    fn apply(&self, text: &str) -> FilteredText {
+        match self {
+            CompiledCharFilter::HtmlStrip => html_strip(text),
+            CompiledCharFilter::PatternReplace { regex, replacement } => {
+                pattern_replace(regex, replacement, text)
+            }
+        }
+    }
+}
+
+/// Output of a character filter.
+#[derive(Debug)]
+struct FilteredText {
+    text: String,
+    // Offset in the input text of each byte of the output text, followed by the length of the
+    // input text.
+    offsets: Vec<usize>,
+}
+
+impl FilteredText {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            text: String::with_capacity(capacity),
+            offsets: Vec::with_capacity(capacity + 1),
+        }
+    }
+
+    fn push_str(&mut self, text: &str, input_offset: usize) {
+        self.text.push_str(text);
+        self.offsets
+            .extend(std::iter::repeat(input_offset).take(text.len()));
+    }
+
+    /// Copies a slice of the input text that starts at `input_offset`.
+    fn copy_input(&mut self, text: &str, input_offset: usize) {
+        self.text.push_str(text);
+        self.offsets.extend(input_offset..input_offset + text.len());
+    }
+
+    fn finish(mut self, input_len: usize) -> Self {
+        self.offsets.push(input_len);
+        self
+    }
+}
+
+fn html_strip(text: &str) -> FilteredText {
+    let mut filtered_text = FilteredText::with_capacity(text.len());
+    let mut offset = 0;
+
+    while let Some(c) = text[offset..].chars().next() {
+        let remaining_text = &text[offset..];
+        if c == '<' {
+            if let Some(tag_len) = html_tag_len(remaining_text) {
+                filtered_text.push_str(" ", offset);
+                offset += tag_len;
+                continue;
+            }
+        }
+        if c == '&' {
+            if let Some((decoded_char, entity_len)) = decode_html_entity(remaining_text) {
+                let mut buffer = [0u8; 4];
+                filtered_text.push_str(decoded_char.encode_utf8(&mut buffer), offset);
+                offset += entity_len;
+                continue;
+            }
+        }
+        let char_len = c.len_utf8();
+        filtered_text.copy_input(&remaining_text[..char_len], offset);
+        offset += char_len;
+    }
+    filtered_text.finish(text.len())
+}
+
+/// Returns the length of the HTML tag or comment at the beginning of `text`, if any.
+fn html_tag_len(text: &str) -> Option<usize> {
+    if text.starts_with("<!--") {
+        return text.find("-->").map(|end| end + "-->".len());
+    }
+    let first_char = text[1..].chars().next()?;
+    if !first_char.is_ascii_alphabetic() && first_char != '/' && first_char != '!' {
+        return None;
+    }
+    text.find('>').map(|end| end + 1)
+}
+
+/// Decodes the HTML character reference at the beginning of `text`. Returns the decoded char and
+/// the length of the reference.
+fn decode_html_entity(text: &str) -> Option<(char, usize)> {
+    let end = text
+        .char_indices()
+        .take(MAX_HTML_ENTITY_LEN)
+        .find(|(_, c)| *c == ';')
+        .map(|(offset, _)| offset)?;
+    let entity = &text[1..end];
+    let decoded_char = match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        _ => {
+            let code_point_str = entity.strip_prefix('#')?;
+            let code_point = if let Some(hex_str) = code_point_str
+                .strip_prefix('x')
+                .or_else(|| code_point_str.strip_prefix('X'))
+            {
+                u32::from_str_radix(hex_str, 16).ok()?
+            } else {
+                code_point_str.parse::<u32>().ok()?
+            };
+            char::from_u32(code_point)?
+        }
+    };
+    Some((decoded_char, end + 1))
+}
+
+fn pattern_replace(regex: &Regex, replacement: &str, text: &str) -> FilteredText {
+    let mut filtered_text = FilteredText::with_capacity(text.len());
+    let mut offset = 0;
+    let mut expanded_replacement = String::new();
+
+    for captures in regex.captures_iter(text) {
+        let matched = captures.get(0).expect("Group 0 should always match.");
+        filtered_text.copy_input(&text[offset..matched.start()], offset);
+        expanded_replacement.clear();
+        captures.expand(replacement, &mut expanded_replacement);
+        filtered_text.push_str(&expanded_replacement, matched.start());
+        offset = matched.end();
+    }
+    filtered_text.copy_input(&text[offset..], offset);
+    filtered_text.finish(text.len())
+}
+
+/// Applies the character filters in order. The offsets of the returned text refer to the
+/// original text.
+fn apply_char_filters(char_filters: &[CompiledCharFilter], text: &str) -> FilteredText {
+    let mut filtered_text = FilteredText {
+        text: text.to_string(),
+        offsets: (0..=text.len()).collect(),
+    };
+    for char_filter in char_filters {
+        let mut new_filtered_text = char_filter.apply(&filtered_text.text);
+        for offset in &mut new_filtered_text.offsets {
+            *offset = filtered_text.offsets[*offset];
+        }
+        filtered_text = new_filtered_text;
+    }
+    filtered_text
+}
+
+#[derive(Clone)]
+struct CharFilterTokenizer {
+    char_filters: Arc<Vec<CompiledCharFilter>>,
+    text_analyzer: TextAnalyzer,
+}
+
+impl Tokenizer for CharFilterTokenizer {
+    type TokenStream<'a> = VecTokenStream;
+
+    fn token_stream<'a>(&self, text: &'a str) -> Self::TokenStream<'a> {
+        let filtered_text = apply_char_filters(&self.char_filters, text);
+        let mut token_stream = self.text_analyzer.token_stream(&filtered_text.text);
+        let mut tokens = Vec::new();
+
+        while token_stream.advance() {
+            let mut token = token_stream.token().clone();
+            token.offset_from = filtered_text.offsets[token.offset_from];
+            token.offset_to = filtered_text.offsets[token.offset_to];
+            tokens.push(token);
+        }
+        // The filtered text is owned by the tokenizer, so the tokens are computed eagerly.
+        VecTokenStream::new(tokens)
+    }
+}
+
+/// Token stream over tokens computed beforehand.
+pub(crate) struct VecTokenStream {
+    tokens: Vec<Token>,
+    num_advances: usize,
+}
+
+impl VecTokenStream {
+    pub(crate) fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            num_advances: 0,
+        }
+    }
+}
+
+impl TokenStream for VecTokenStream {
+    fn advance(&mut self) -> bool {
+        if self.num_advances >= self.tokens.len() {
+            return false;
+        }
+        self.num_advances += 1;
+        true
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.num_advances - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.num_advances - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_texts(tokenizer_name: &str, text: &str) -> Vec<(String, usize, usize)> {
+        let text_analyzer = get_quickwit_tokenizer_manager()
+            .get(tokenizer_name)
+            .unwrap();
+        let mut token_stream = text_analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while token_stream.advance() {
+            let token = token_stream.token();
+            tokens.push((token.text.clone(), token.offset_from, token.offset_to));
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_char_filter_deserialize() {
+        let char_filters: Vec<CharFilter> = serde_json::from_str(
+            r#"[{"type": "html_strip"}, {"type": "pattern_replace", "pattern": "\\d+"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            char_filters,
+            [
+                CharFilter::HtmlStrip,
+                CharFilter::PatternReplace {
+                    pattern: "\\d+".to_string(),
+                    replacement: String::new(),
+                }
+            ]
+        );
+        serde_json::from_str::<CharFilter>(r#"{"type": "html_strip", "pattern": "a"}"#)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_html_strip() {
+        let filtered_text = html_strip("<p>Hello&nbsp;<b>world</b> &amp; &#x263A;&unknown;</p>");
+        assert_eq!(filtered_text.text, " Hello  world  & ☺&unknown; ");
+        assert_eq!(filtered_text.offsets.len(), filtered_text.text.len() + 1);
+        assert_eq!(filtered_text.offsets[1], 3);
+        assert_eq!(filtered_text.offsets[8], 17);
+
+        let filtered_text = html_strip("a < b <!-- comment --> c");
+        assert_eq!(filtered_text.text, "a < b   c");
+    }
+
+    #[test]
+    fn test_pattern_replace() {
+        let regex = Regex::new(r"(\d+)ms").unwrap();
+        let filtered_text = pattern_replace(&regex, "$1 milliseconds", "took 12ms");
+        assert_eq!(filtered_text.text, "took 12 milliseconds");
+        assert_eq!(filtered_text.offsets[5], 5);
+        assert_eq!(filtered_text.offsets[19], 5);
+        assert_eq!(filtered_text.offsets[20], 9);
+    }
+
+    #[test]
+    fn test_char_filter_tokenizer() {
+        let char_filters = [
+            CharFilter::HtmlStrip,
+            CharFilter::PatternReplace {
+                pattern: r"\d+".to_string(),
+                replacement: "N".to_string(),
+            },
+        ];
+        let tokenizer_name = register_char_filter_tokenizer("default", &char_filters).unwrap();
+        assert_eq!(
+            tokenizer_name,
+            char_filter_tokenizer_name("default", &char_filters)
+        );
+        // Registering the same tokenizer twice is a no-op.
+        register_char_filter_tokenizer("default", &char_filters).unwrap();
+
+        assert_eq!(
+            token_texts(&tokenizer_name, "<b>Error</b> in pod-42"),
+            [
+                ("error".to_string(), 3, 8),
+                ("in".to_string(), 13, 15),
+                ("pod".to_string(), 16, 19),
+                ("n".to_string(), 20, 22),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_register_char_filter_tokenizer_errors() {
+        let char_filters = [CharFilter::PatternReplace {
+            pattern: "(".to_string(),
+            replacement: String::new(),
+        }];
+        let error = register_char_filter_tokenizer("default", &char_filters).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Invalid `pattern_replace` pattern"));
+
+        let error =
+            register_char_filter_tokenizer("unknown", &[CharFilter::HtmlStrip]).unwrap_err();
+        assert!(error.to_string().contains("No tokenizer named `unknown`"));
+    }
+}
@@ -24,9 +24,9 @@ use serde_with::{serde_as, OneOrMany};
 use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
 use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::{self, QueryAst};
+use crate::MinimumShouldMatch;
 
 /// # Unsupported features
-/// - minimum_should_match
 /// - named queries
 #[serde_as]
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -46,6 +46,8 @@ pub struct BoolQuery {
     filter: Vec<ElasticQueryDslInner>,
     #[serde(default)]
     pub boost: Option<NotNaNf32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    minimum_should_match: Option<MinimumShouldMatch>,
 }
 
 fn convert_vec(query_dsls: Vec<ElasticQueryDslInner>) -> anyhow::Result<Vec<QueryAst>> {
@@ -62,6 +64,7 @@ impl ConvertableToQueryAst for BoolQuery {
             must_not: convert_vec(self.must_not)?,
             should: convert_vec(self.should)?,
             filter: convert_vec(self.filter)?,
+            minimum_should_match: self.minimum_should_match,
         };
         Ok(bool_query_ast.into())
     }
@@ -71,6 +74,9 @@ impl ConvertableToQueryAst for BoolQuery {
 mod tests {
     use crate::elastic_query_dsl::bool_query::BoolQuery;
     use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+    use crate::elastic_query_dsl::ConvertableToQueryAst;
+    use crate::query_ast::QueryAst;
+    use crate::MinimumShouldMatch;
 
     #[test]
     fn test_dsl_bool_query_deserialize_simple() {
@@ -92,6 +98,7 @@ mod tests {
                 should: Vec::new(),
                 filter: Vec::new(),
                 boost: None,
+                minimum_should_match: None,
             }
         );
     }
@@ -111,7 +118,28 @@ mod tests {
                 should: Vec::new(),
                 filter: vec![term_query_from_field_value("product_id", "2").into(),],
                 boost: None,
+                minimum_should_match: None,
             }
         );
     }
+
+    #[test]
+    fn test_dsl_bool_query_minimum_should_match() {
+        let bool_query_json = r#"{
+            "should": [
+                { "term": {"product_id": {"value": "1" }} },
+                { "term": {"product_id": {"value": "2" }} }
+            ],
+            "minimum_should_match": "50%"
+        }"#;
+        let bool_query: BoolQuery = serde_json::from_str(bool_query_json).unwrap();
+        let QueryAst::Bool(bool_query_ast) = bool_query.convert_to_query_ast().unwrap() else {
+            panic!();
+        };
+        assert_eq!(bool_query_ast.should.len(), 2);
+        assert_eq!(
+            bool_query_ast.minimum_should_match,
+            Some(MinimumShouldMatch::Percentage(50))
+        );
+    }
 }
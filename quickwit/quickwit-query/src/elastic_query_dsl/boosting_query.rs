@@ -0,0 +1,113 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// Returns the documents matching the `positive` query, demoting the ones also matching the
+/// `negative` query instead of excluding them.
+///
+/// # Unsupported features
+/// - named queries
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BoostingQuery {
+    positive: Box<ElasticQueryDslInner>,
+    negative: Box<ElasticQueryDslInner>,
+    negative_boost: NotNaNf32,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl ConvertableToQueryAst for BoostingQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let negative_boost_f32: f32 = self.negative_boost.into();
+        if !(0.0..=1.0).contains(&negative_boost_f32) {
+            bail!("`negative_boost` must be between 0 and 1, got `{negative_boost_f32}`.");
+        }
+        let boosting_query_ast = query_ast::BoostingQuery {
+            positive: Box::new(self.positive.convert_to_query_ast()?),
+            negative: Box::new(self.negative.convert_to_query_ast()?),
+            negative_boost: self.negative_boost,
+        };
+        Ok(QueryAst::from(boosting_query_ast).boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+
+    #[test]
+    fn test_dsl_boosting_query_deserialize() {
+        let boosting_query_json = r#"{
+            "positive": { "term": {"title": {"value": "apple" }} },
+            "negative": { "term": {"source": {"value": "stale" }} },
+            "negative_boost": 0.5
+        }"#;
+        let boosting_query: BoostingQuery = serde_json::from_str(boosting_query_json).unwrap();
+        assert_eq!(
+            &boosting_query,
+            &BoostingQuery {
+                positive: Box::new(term_query_from_field_value("title", "apple").into()),
+                negative: Box::new(term_query_from_field_value("source", "stale").into()),
+                negative_boost: NotNaNf32::try_from(0.5).unwrap(),
+                boost: None,
+            }
+        );
+        let query_ast = boosting_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boosting(boosting_query_ast) = query_ast else {
+            panic!("Expected a boosting query, got {query_ast:?}.");
+        };
+        assert_eq!(
+            boosting_query_ast.negative_boost,
+            NotNaNf32::try_from(0.5).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dsl_boosting_query_invalid_negative_boost() {
+        let boosting_query: BoostingQuery = serde_json::from_str(
+            r#"{
+                "positive": { "match_all": {} },
+                "negative": { "match_all": {} },
+                "negative_boost": 2.0
+            }"#,
+        )
+        .unwrap();
+        let error = boosting_query.convert_to_query_ast().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`negative_boost` must be between 0 and 1, got `2`."
+        );
+    }
+
+    #[test]
+    fn test_dsl_boosting_query_requires_negative_boost() {
+        serde_json::from_str::<BoostingQuery>(
+            r#"{ "positive": { "match_all": {} }, "negative": { "match_all": {} } }"#,
+        )
+        .unwrap_err();
+    }
+}
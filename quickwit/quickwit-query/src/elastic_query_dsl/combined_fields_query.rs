@@ -0,0 +1,139 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+use crate::{BooleanOperand, MatchAllOrNone, MinimumShouldMatch};
+
+/// `CombinedFieldsQuery` as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-combined-fields-query.html>
+///
+/// Synonyms are not expanded, so `auto_generate_synonyms_phrase_query` is accepted but ignored.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CombinedFieldsQuery {
+    query: String,
+    fields: Vec<String>,
+    #[serde(default)]
+    operator: BooleanOperand,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    minimum_should_match: Option<MinimumShouldMatch>,
+    #[serde(default)]
+    zero_terms_query: MatchAllOrNone,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auto_generate_synonyms_phrase_query: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<CombinedFieldsQuery> for ElasticQueryDslInner {
+    fn from(combined_fields_query: CombinedFieldsQuery) -> Self {
+        Self::CombinedFields(combined_fields_query)
+    }
+}
+
+impl ConvertableToQueryAst for CombinedFieldsQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        if self.fields.is_empty() {
+            anyhow::bail!("The `fields` of a combined_fields query cannot be empty.");
+        }
+        let combined_fields_ast: QueryAst = query_ast::CombinedFieldsQuery {
+            fields: self.fields,
+            text: self.query,
+            operator: self.operator,
+            minimum_should_match: self.minimum_should_match,
+            zero_terms_query: self.zero_terms_query,
+        }
+        .into();
+        Ok(combined_fields_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::ElasticQueryDsl;
+
+    #[test]
+    fn test_combined_fields_query() {
+        let query_json = r#"{
+            "combined_fields": {
+                "query": "connection timeout",
+                "fields": ["title^2", "body", "tags"],
+                "operator": "AND",
+                "auto_generate_synonyms_phrase_query": false
+            }
+        }"#;
+        let elastic_query_dsl: ElasticQueryDsl = serde_json::from_str(query_json).unwrap();
+        let query_ast = QueryAst::try_from(elastic_query_dsl).unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::CombinedFields(query_ast::CombinedFieldsQuery {
+                fields: vec![
+                    "title^2".to_string(),
+                    "body".to_string(),
+                    "tags".to_string()
+                ],
+                text: "connection timeout".to_string(),
+                operator: BooleanOperand::And,
+                minimum_should_match: None,
+                zero_terms_query: MatchAllOrNone::MatchNone,
+            })
+        );
+    }
+
+    #[test]
+    fn test_combined_fields_query_with_params() {
+        let combined_fields_query_json = r#"{
+            "query": "connection timeout",
+            "fields": ["title", "body"],
+            "minimum_should_match": "50%",
+            "zero_terms_query": "all",
+            "boost": 2.0
+        }"#;
+        let combined_fields_query: CombinedFieldsQuery =
+            serde_json::from_str(combined_fields_query_json).unwrap();
+        let query_ast = combined_fields_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, .. } = query_ast else { panic!() };
+        let QueryAst::CombinedFields(combined_fields_query) = *underlying else { panic!() };
+        assert_eq!(combined_fields_query.operator, BooleanOperand::Or);
+        assert_eq!(
+            combined_fields_query.minimum_should_match,
+            Some(MinimumShouldMatch::Percentage(50))
+        );
+        assert_eq!(
+            combined_fields_query.zero_terms_query,
+            MatchAllOrNone::MatchAll
+        );
+    }
+
+    #[test]
+    fn test_combined_fields_query_invalid() {
+        let combined_fields_query: CombinedFieldsQuery =
+            serde_json::from_str(r#"{ "query": "timeout", "fields": [] }"#).unwrap();
+        let error = combined_fields_query.convert_to_query_ast().unwrap_err();
+        assert!(error.to_string().contains("cannot be empty"));
+
+        let combined_fields_query_json = r#"{ "query": "timeout", "fields": ["body"], "slop": 2 }"#;
+        assert!(serde_json::from_str::<CombinedFieldsQuery>(combined_fields_query_json).is_err());
+    }
+}
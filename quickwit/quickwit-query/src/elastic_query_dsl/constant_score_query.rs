@@ -0,0 +1,88 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::QueryAst;
+
+/// Wraps a filter query and gives every matching document a score equal to `boost`.
+///
+/// # Unsupported features
+/// - named queries
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ConstantScoreQuery {
+    filter: Box<ElasticQueryDslInner>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl ConvertableToQueryAst for ConstantScoreQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let underlying = self.filter.convert_to_query_ast()?;
+        Ok(QueryAst::ConstScore {
+            underlying: Box::new(underlying),
+            score: self.boost.unwrap_or(NotNaNf32::ONE),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+
+    #[test]
+    fn test_dsl_constant_score_query_deserialize() {
+        let constant_score_query_json = r#"{
+            "filter": { "term": {"product_id": {"value": "1" }} },
+            "boost": 1.5
+        }"#;
+        let constant_score_query: ConstantScoreQuery =
+            serde_json::from_str(constant_score_query_json).unwrap();
+        assert_eq!(
+            &constant_score_query,
+            &ConstantScoreQuery {
+                filter: Box::new(term_query_from_field_value("product_id", "1").into()),
+                boost: Some(NotNaNf32::try_from(1.5).unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_dsl_constant_score_query_default_boost() {
+        let constant_score_query: ConstantScoreQuery =
+            serde_json::from_str(r#"{ "filter": { "match_all": {} } }"#).unwrap();
+        let query_ast = constant_score_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::ConstScore {
+                underlying: Box::new(QueryAst::MatchAll),
+                score: NotNaNf32::ONE,
+            }
+        );
+    }
+
+    #[test]
+    fn test_dsl_constant_score_query_requires_filter() {
+        serde_json::from_str::<ConstantScoreQuery>(r#"{ "boost": 1.5 }"#).unwrap_err();
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// # Unsupported features
+/// - named queries
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DisMaxQuery {
+    queries: Vec<ElasticQueryDslInner>,
+    #[serde(default)]
+    tie_breaker: Option<NotNaNf32>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<DisMaxQuery> for ElasticQueryDslInner {
+    fn from(dis_max_query: DisMaxQuery) -> Self {
+        Self::DisMax(dis_max_query)
+    }
+}
+
+impl ConvertableToQueryAst for DisMaxQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let queries = self
+            .queries
+            .into_iter()
+            .map(|query| query.convert_to_query_ast())
+            .collect::<anyhow::Result<Vec<QueryAst>>>()?;
+        let dis_max_query_ast = query_ast::DisMaxQuery {
+            queries,
+            tie_breaker: self.tie_breaker,
+        };
+        Ok(QueryAst::from(dis_max_query_ast).boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+
+    #[test]
+    fn test_dsl_dis_max_query_deserialize() {
+        let dis_max_query_json = r#"{
+            "queries": [
+                { "term": {"title": {"value": "quick" }} },
+                { "term": {"body": {"value": "quick" }} }
+            ],
+            "tie_breaker": 0.7
+        }"#;
+        let dis_max_query: DisMaxQuery = serde_json::from_str(dis_max_query_json).unwrap();
+        assert_eq!(
+            &dis_max_query,
+            &DisMaxQuery {
+                queries: vec![
+                    term_query_from_field_value("title", "quick").into(),
+                    term_query_from_field_value("body", "quick").into(),
+                ],
+                tie_breaker: Some(NotNaNf32::try_from(0.7).unwrap()),
+                boost: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_dsl_dis_max_query_to_query_ast() {
+        let dis_max_query: DisMaxQuery =
+            serde_json::from_str(r#"{ "queries": [{ "match_all": {} }], "boost": 2.0 }"#).unwrap();
+        let query_ast = dis_max_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Boost {
+                underlying: Box::new(
+                    query_ast::DisMaxQuery {
+                        queries: vec![QueryAst::MatchAll],
+                        tie_breaker: None,
+                    }
+                    .into()
+                ),
+                boost: NotNaNf32::try_from(2.0).unwrap(),
+            }
+        );
+    }
+}
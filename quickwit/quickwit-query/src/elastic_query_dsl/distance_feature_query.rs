@@ -0,0 +1,128 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{
+    self, FunctionBoostMode, FunctionScoreMode, QueryAst, ScoreFunction, ScoreFunctionKind,
+};
+use crate::JsonLiteral;
+
+/// Matches the documents with a value for `field`, and scores them with
+/// `boost * pivot / (pivot + distance)`, where `distance` is the distance between the value of
+/// the field and `origin`.
+///
+/// # Unsupported features
+/// - `geo_point` fields
+/// - date math in `origin`, except `now`
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DistanceFeatureQuery {
+    field: String,
+    origin: JsonLiteral,
+    pivot: JsonLiteral,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<DistanceFeatureQuery> for ElasticQueryDslInner {
+    fn from(distance_feature_query: DistanceFeatureQuery) -> Self {
+        Self::DistanceFeature(distance_feature_query)
+    }
+}
+
+impl ConvertableToQueryAst for DistanceFeatureQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let field_presence_ast: QueryAst = query_ast::FieldPresenceQuery {
+            field: self.field.clone(),
+        }
+        .into();
+        let distance_feature_function = ScoreFunction {
+            kind: ScoreFunctionKind::DistanceFeature(query_ast::DistanceFeatureFunction {
+                field: self.field,
+                origin: self.origin,
+                pivot: self.pivot,
+            }),
+            filter: None,
+            weight: None,
+        };
+        let function_score_query = query_ast::FunctionScoreQuery {
+            underlying: Box::new(field_presence_ast),
+            functions: vec![distance_feature_function],
+            score_mode: FunctionScoreMode::Multiply,
+            boost_mode: FunctionBoostMode::Replace,
+            now_timestamp_micros: None,
+        };
+        Ok(QueryAst::from(function_score_query).boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dsl_distance_feature_query() {
+        let distance_feature_query: DistanceFeatureQuery = serde_json::from_str(
+            r#"{ "field": "timestamp", "origin": "now", "pivot": "7d", "boost": 2 }"#,
+        )
+        .unwrap();
+        let query_ast = distance_feature_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, boost } = query_ast else {
+            panic!("Expected a boost query, got {query_ast:?}.");
+        };
+        assert_eq!(boost, NotNaNf32::try_from(2.0).unwrap());
+        let QueryAst::FunctionScore(function_score_query_ast) = *underlying else {
+            panic!("Expected a function score query.");
+        };
+        assert_eq!(
+            *function_score_query_ast.underlying,
+            QueryAst::from(query_ast::FieldPresenceQuery {
+                field: "timestamp".to_string(),
+            })
+        );
+        assert_eq!(
+            function_score_query_ast.boost_mode,
+            FunctionBoostMode::Replace
+        );
+        assert_eq!(
+            function_score_query_ast.functions,
+            vec![ScoreFunction {
+                kind: ScoreFunctionKind::DistanceFeature(query_ast::DistanceFeatureFunction {
+                    field: "timestamp".to_string(),
+                    origin: JsonLiteral::from("now"),
+                    pivot: JsonLiteral::from("7d"),
+                }),
+                filter: None,
+                weight: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_dsl_distance_feature_query_invalid() {
+        let error = serde_json::from_str::<DistanceFeatureQuery>(
+            r#"{ "field": "timestamp", "origin": "now" }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("missing field `pivot`"));
+    }
+}
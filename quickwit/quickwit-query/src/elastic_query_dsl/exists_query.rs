@@ -0,0 +1,78 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExistsQuery {
+    field: String,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<ExistsQuery> for ElasticQueryDslInner {
+    fn from(exists_query: ExistsQuery) -> Self {
+        Self::Exists(exists_query)
+    }
+}
+
+impl ConvertableToQueryAst for ExistsQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let field_presence_ast: QueryAst =
+            query_ast::FieldPresenceQuery { field: self.field }.into();
+        Ok(field_presence_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exists_query() {
+        let exists_query: ExistsQuery = serde_json::from_str(r#"{ "field": "user.id" }"#).unwrap();
+        let query_ast = exists_query.convert_to_query_ast().unwrap();
+        let expected_query_ast: QueryAst = query_ast::FieldPresenceQuery {
+            field: "user.id".to_string(),
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_exists_query_with_boost() {
+        let exists_query: ExistsQuery =
+            serde_json::from_str(r#"{ "field": "user.id", "boost": 2.0 }"#).unwrap();
+        let query_ast = exists_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, .. } = query_ast else { panic!() };
+        assert!(matches!(*underlying, QueryAst::FieldPresence(_)));
+    }
+
+    #[test]
+    fn test_exists_query_unknown_param() {
+        let error =
+            serde_json::from_str::<ExistsQuery>(r#"{ "field": "user.id", "foo": 1 }"#).unwrap_err();
+        assert!(error.to_string().contains("unknown field `foo`"));
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{
+    self, DecayFunctionType, FieldValueFactorModifier, FunctionBoostMode, FunctionScoreMode,
+    QueryAst, ScoreFunctionKind,
+};
+use crate::{JsonLiteral, OneFieldMap};
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+struct FieldValueFactor {
+    field: String,
+    #[serde(default)]
+    factor: Option<NotNaNf32>,
+    #[serde(default)]
+    modifier: FieldValueFactorModifier,
+    #[serde(default)]
+    missing: Option<NotNaNf32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+struct DecayParams {
+    #[serde(default)]
+    origin: Option<JsonLiteral>,
+    scale: JsonLiteral,
+    #[serde(default)]
+    offset: Option<JsonLiteral>,
+    #[serde(default)]
+    decay: Option<NotNaNf32>,
+}
+
+/// Score function. Exactly one of `field_value_factor`, `gauss`, `exp` and `linear` can be set.
+/// A function with only a `weight` is equal to its weight. If `filter` is set, the function only
+/// applies to the documents matching it.
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+struct ScoreFunction {
+    #[serde(default)]
+    filter: Option<Box<ElasticQueryDslInner>>,
+    #[serde(default)]
+    field_value_factor: Option<FieldValueFactor>,
+    #[serde(default)]
+    gauss: Option<OneFieldMap<DecayParams>>,
+    #[serde(default)]
+    exp: Option<OneFieldMap<DecayParams>>,
+    #[serde(default)]
+    linear: Option<OneFieldMap<DecayParams>>,
+    #[serde(default)]
+    weight: Option<NotNaNf32>,
+}
+
+impl ScoreFunction {
+    /// Returns `None` if no function is defined.
+    fn convert_to_score_function(self) -> anyhow::Result<Option<query_ast::ScoreFunction>> {
+        let mut kinds = Vec::new();
+        if let Some(field_value_factor) = self.field_value_factor {
+            kinds.push(ScoreFunctionKind::FieldValueFactor(
+                query_ast::FieldValueFactorFunction {
+                    field: field_value_factor.field,
+                    factor: field_value_factor.factor.unwrap_or(NotNaNf32::ONE),
+                    modifier: field_value_factor.modifier,
+                    missing: field_value_factor.missing,
+                },
+            ));
+        }
+        for (decay_type, decay_params_opt) in [
+            (DecayFunctionType::Gauss, self.gauss),
+            (DecayFunctionType::Exp, self.exp),
+            (DecayFunctionType::Linear, self.linear),
+        ] {
+            let Some(OneFieldMap { field, value: decay_params }) = decay_params_opt else {
+                continue;
+            };
+            kinds.push(ScoreFunctionKind::Decay(query_ast::DecayFunction {
+                decay_type,
+                field,
+                origin: decay_params.origin,
+                scale: decay_params.scale,
+                offset: decay_params.offset,
+                decay: decay_params
+                    .decay
+                    .unwrap_or_else(|| NotNaNf32::try_from(0.5).unwrap()),
+            }));
+        }
+        if kinds.len() > 1 {
+            bail!(
+                "A score function can only have one of `field_value_factor`, `gauss`, `exp` and \
+                 `linear`."
+            );
+        }
+        let kind = match kinds.pop() {
+            Some(kind) => kind,
+            None if self.weight.is_some() => ScoreFunctionKind::Constant,
+            None => return Ok(None),
+        };
+        let filter = self
+            .filter
+            .map(|filter| filter.convert_to_query_ast().map(Box::new))
+            .transpose()?;
+        Ok(Some(query_ast::ScoreFunction {
+            kind,
+            filter,
+            weight: self.weight,
+        }))
+    }
+}
+
+/// Modifies the score of the documents matching `query` with score functions.
+///
+/// A single function can be defined directly in the query instead of in `functions`.
+///
+/// # Unsupported features
+/// - `script_score` and `random_score` functions
+/// - `max_boost`, `min_score`
+/// - `multi_value_mode` of decay functions: the first value of the field is used
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FunctionScoreQuery {
+    #[serde(default)]
+    query: Option<Box<ElasticQueryDslInner>>,
+    #[serde(default)]
+    functions: Vec<ScoreFunction>,
+    #[serde(default)]
+    score_mode: FunctionScoreMode,
+    #[serde(default)]
+    boost_mode: FunctionBoostMode,
+    #[serde(default)]
+    field_value_factor: Option<FieldValueFactor>,
+    #[serde(default)]
+    gauss: Option<OneFieldMap<DecayParams>>,
+    #[serde(default)]
+    exp: Option<OneFieldMap<DecayParams>>,
+    #[serde(default)]
+    linear: Option<OneFieldMap<DecayParams>>,
+    #[serde(default)]
+    weight: Option<NotNaNf32>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl ConvertableToQueryAst for FunctionScoreQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let underlying = match self.query {
+            Some(query) => query.convert_to_query_ast()?,
+            None => QueryAst::MatchAll,
+        };
+        let top_level_function = ScoreFunction {
+            filter: None,
+            field_value_factor: self.field_value_factor,
+            gauss: self.gauss,
+            exp: self.exp,
+            linear: self.linear,
+            weight: self.weight,
+        };
+        let mut functions = Vec::with_capacity(self.functions.len());
+        if let Some(function) = top_level_function.convert_to_score_function()? {
+            if !self.functions.is_empty() {
+                bail!("A function cannot be defined both in `functions` and in the query.");
+            }
+            functions.push(function);
+        }
+        for function in self.functions {
+            let Some(function) = function.convert_to_score_function()? else {
+                bail!("Score functions cannot be empty.");
+            };
+            functions.push(function);
+        }
+        let function_score_query = query_ast::FunctionScoreQuery {
+            underlying: Box::new(underlying),
+            functions,
+            score_mode: self.score_mode,
+            boost_mode: self.boost_mode,
+            now_timestamp_micros: None,
+        };
+        Ok(QueryAst::from(function_score_query).boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dsl_function_score_query_functions() {
+        let function_score_query: FunctionScoreQuery = serde_json::from_str(
+            r#"{
+                "query": { "match_all": {} },
+                "functions": [
+                    {
+                        "gauss": { "timestamp": { "origin": "now", "scale": "7d", "offset": "1d" } },
+                        "weight": 2
+                    },
+                    {
+                        "field_value_factor": { "field": "likes", "modifier": "log1p", "factor": 1.2 },
+                        "filter": { "term": { "category": { "value": "news" } } }
+                    },
+                    { "weight": 0.5 }
+                ],
+                "score_mode": "sum",
+                "boost_mode": "replace",
+                "boost": 3
+            }"#,
+        )
+        .unwrap();
+        let query_ast = function_score_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, boost } = query_ast else {
+            panic!("Expected a boost query, got {query_ast:?}.");
+        };
+        assert_eq!(boost, NotNaNf32::try_from(3.0).unwrap());
+        let QueryAst::FunctionScore(function_score_query_ast) = *underlying else {
+            panic!("Expected a function score query.");
+        };
+        assert_eq!(*function_score_query_ast.underlying, QueryAst::MatchAll);
+        assert_eq!(function_score_query_ast.score_mode, FunctionScoreMode::Sum);
+        assert_eq!(
+            function_score_query_ast.boost_mode,
+            FunctionBoostMode::Replace
+        );
+        let functions = &function_score_query_ast.functions;
+        assert_eq!(functions.len(), 3);
+        assert_eq!(
+            functions[0],
+            query_ast::ScoreFunction {
+                kind: ScoreFunctionKind::Decay(query_ast::DecayFunction {
+                    decay_type: DecayFunctionType::Gauss,
+                    field: "timestamp".to_string(),
+                    origin: Some(JsonLiteral::from("now")),
+                    scale: JsonLiteral::from("7d"),
+                    offset: Some(JsonLiteral::from("1d")),
+                    decay: NotNaNf32::try_from(0.5).unwrap(),
+                }),
+                filter: None,
+                weight: Some(NotNaNf32::try_from(2.0).unwrap()),
+            }
+        );
+        assert_eq!(
+            functions[1],
+            query_ast::ScoreFunction {
+                kind: ScoreFunctionKind::FieldValueFactor(query_ast::FieldValueFactorFunction {
+                    field: "likes".to_string(),
+                    factor: NotNaNf32::try_from(1.2).unwrap(),
+                    modifier: FieldValueFactorModifier::Log1p,
+                    missing: None,
+                }),
+                filter: Some(Box::new(QueryAst::term("category", "news"))),
+                weight: None,
+            }
+        );
+        assert_eq!(functions[2].kind, ScoreFunctionKind::Constant);
+    }
+
+    #[test]
+    fn test_dsl_function_score_query_single_function() {
+        let function_score_query: FunctionScoreQuery = serde_json::from_str(
+            r#"{ "linear": { "price": { "origin": 10, "scale": 5, "decay": 0.2 } } }"#,
+        )
+        .unwrap();
+        let QueryAst::FunctionScore(function_score_query_ast) =
+            function_score_query.convert_to_query_ast().unwrap()
+        else {
+            panic!("Expected a function score query.");
+        };
+        assert_eq!(*function_score_query_ast.underlying, QueryAst::MatchAll);
+        assert_eq!(function_score_query_ast.functions.len(), 1);
+        let ScoreFunctionKind::Decay(decay_function) = &function_score_query_ast.functions[0].kind
+        else {
+            panic!("Expected a decay function.");
+        };
+        assert_eq!(decay_function.decay_type, DecayFunctionType::Linear);
+        assert_eq!(decay_function.origin, Some(JsonLiteral::from(10u64)));
+        assert_eq!(decay_function.decay, NotNaNf32::try_from(0.2).unwrap());
+    }
+
+    #[test]
+    fn test_dsl_function_score_query_invalid() {
+        let function_score_query: FunctionScoreQuery = serde_json::from_str(
+            r#"{ "functions": [{ "gauss": { "a": { "scale": 1 } }, "exp": { "a": { "scale": 1 } } }] }"#,
+        )
+        .unwrap();
+        let error = function_score_query.convert_to_query_ast().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "A score function can only have one of `field_value_factor`, `gauss`, `exp` and \
+             `linear`."
+        );
+
+        let function_score_query: FunctionScoreQuery =
+            serde_json::from_str(r#"{ "functions": [{}] }"#).unwrap();
+        function_score_query.convert_to_query_ast().unwrap_err();
+
+        serde_json::from_str::<FunctionScoreQuery>(r#"{ "functions": [{ "random_score": {} }] }"#)
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Golden-file tests for the conversion of Elasticsearch queries.
+//!
+//! Each `test-data/elastic-query-dsl/{name}.json` file holds an Elasticsearch query. The test
+//! converts it into a `QueryAst`, builds the matching tantivy query against the schema below and
+//! compares both with the snapshot stored in `{name}.expected.json`. Failures are part of the
+//! snapshot, so that rejecting a query is also covered.
+//!
+//! To add a test case, drop a query in a new `.json` file and run the test: it writes the missing
+//! snapshot and fails. Outdated snapshots are rewritten the same way. Review the snapshots with
+//! `git diff` before committing them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tantivy::schema::{Schema as TantivySchema, FAST, INDEXED, STRING, TEXT};
+
+use crate::query_ast::QueryAst;
+use crate::ElasticQueryDsl;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct Snapshot {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    query_ast: Option<JsonValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tantivy_query: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn make_schema() -> TantivySchema {
+    let mut schema_builder = TantivySchema::builder();
+    schema_builder.add_text_field("title", TEXT);
+    schema_builder.add_text_field("status", STRING | FAST);
+    schema_builder.add_u64_field("count", INDEXED | FAST);
+    schema_builder.add_date_field("timestamp", INDEXED | FAST);
+    schema_builder.add_json_field("attributes", TEXT | FAST);
+    schema_builder.build()
+}
+
+fn take_snapshot(elastic_query_json: &str) -> Snapshot {
+    let mut snapshot = Snapshot::default();
+    let elastic_query_dsl: ElasticQueryDsl = match serde_json::from_str(elastic_query_json) {
+        Ok(elastic_query_dsl) => elastic_query_dsl,
+        Err(error) => {
+            snapshot.error = Some(error.to_string());
+            return snapshot;
+        }
+    };
+    let query_ast: QueryAst = match elastic_query_dsl.try_into() {
+        Ok(query_ast) => query_ast,
+        Err(error) => {
+            snapshot.error = Some(error.to_string());
+            return snapshot;
+        }
+    };
+    snapshot.query_ast = Some(serde_json::to_value(&query_ast).unwrap());
+    let default_search_fields = vec!["title".to_string()];
+    let tantivy_query_res = query_ast
+        .parse_user_query(&default_search_fields)
+        .map_err(|error| error.to_string())
+        .and_then(|query_ast| {
+            query_ast
+                .build_tantivy_query(&make_schema(), &[], true, true)
+                .map_err(|error| error.to_string())
+        });
+    match tantivy_query_res {
+        Ok(tantivy_query) => snapshot.tantivy_query = Some(format!("{tantivy_query:?}")),
+        Err(error) => snapshot.error = Some(error),
+    }
+    snapshot
+}
+
+/// Checks the snapshot of the query stored at `path`. Returns the path of the snapshot if it was
+/// missing or outdated and had to be written.
+fn check_snapshot(path: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let elastic_query_json = fs::read_to_string(path)?;
+    let snapshot = take_snapshot(&elastic_query_json);
+    let expected_path = path.with_extension("expected.json");
+    let expected_snapshot_opt: Option<Snapshot> = if expected_path.try_exists()? {
+        Some(serde_json::from_slice(&fs::read(&expected_path)?)?)
+    } else {
+        None
+    };
+    if expected_snapshot_opt.as_ref() == Some(&snapshot) {
+        return Ok(None);
+    }
+    let mut snapshot_json = serde_json::to_string_pretty(&snapshot)?;
+    snapshot_json.push('\n');
+    fs::write(&expected_path, snapshot_json)?;
+    Ok(Some(expected_path))
+}
+
+#[test]
+fn test_elastic_query_dsl_golden_files() {
+    let test_dir = Path::new("test-data").join("elastic-query-dsl");
+    let mut written_snapshot_paths = Vec::new();
+    for entry in fs::read_dir(test_dir).unwrap() {
+        let path = entry.unwrap().path();
+        let path_str = path.to_string_lossy();
+        if !path_str.ends_with(".json") || path_str.ends_with(".expected.json") {
+            continue;
+        }
+        if let Some(snapshot_path) = check_snapshot(&path).unwrap() {
+            written_snapshot_paths.push(snapshot_path);
+        }
+    }
+    assert!(
+        written_snapshot_paths.is_empty(),
+        "The following snapshots were missing or outdated and have been written. Review them \
+         before committing: {written_snapshot_paths:?}"
+    );
+}
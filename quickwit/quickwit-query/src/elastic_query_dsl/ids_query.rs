@@ -0,0 +1,91 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{QueryAst, TermSetQuery};
+use crate::DOC_ID_FIELD_ALIAS;
+
+/// Matches the documents whose doc ID is one of `values`. It requires the index to define a doc
+/// ID field.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IdsQuery {
+    values: BTreeSet<String>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<IdsQuery> for ElasticQueryDslInner {
+    fn from(ids_query: IdsQuery) -> Self {
+        Self::Ids(ids_query)
+    }
+}
+
+impl ConvertableToQueryAst for IdsQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        if self.values.is_empty() {
+            return Ok(QueryAst::MatchNone);
+        }
+        let term_set_ast: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(DOC_ID_FIELD_ALIAS.to_string(), self.values)]),
+        }
+        .into();
+        Ok(term_set_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_query() {
+        let ids_query: IdsQuery =
+            serde_json::from_str(r#"{ "values": ["doc-1", "doc-4", "doc-1"] }"#).unwrap();
+        let query_ast = ids_query.convert_to_query_ast().unwrap();
+        let expected_query_ast: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "_id".to_string(),
+                BTreeSet::from(["doc-1".to_string(), "doc-4".to_string()]),
+            )]),
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_ids_query_with_boost() {
+        let ids_query: IdsQuery =
+            serde_json::from_str(r#"{ "values": ["doc-1"], "boost": 2.0 }"#).unwrap();
+        let query_ast = ids_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, .. } = query_ast else { panic!() };
+        assert!(matches!(*underlying, QueryAst::TermSet(_)));
+    }
+
+    #[test]
+    fn test_ids_query_without_values() {
+        let ids_query: IdsQuery = serde_json::from_str(r#"{ "values": [] }"#).unwrap();
+        let query_ast = ids_query.convert_to_query_ast().unwrap();
+        assert_eq!(query_ast, QueryAst::MatchNone);
+    }
+}
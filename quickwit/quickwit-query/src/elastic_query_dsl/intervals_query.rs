@@ -0,0 +1,107 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::query_ast::{self, IntervalsRule, QueryAst};
+
+/// `IntervalsQuery` as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-intervals-query.html>
+///
+/// Only the `match`, `any_of` and `all_of` rules are supported, without filters.
+pub type IntervalsQuery = OneFieldMap<IntervalsRule>;
+
+impl From<IntervalsQuery> for ElasticQueryDslInner {
+    fn from(intervals_query: IntervalsQuery) -> Self {
+        Self::Intervals(intervals_query)
+    }
+}
+
+impl ConvertableToQueryAst for IntervalsQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let intervals_query_ast = query_ast::IntervalsQuery {
+            field: self.field,
+            rule: self.value,
+        };
+        Ok(intervals_query_ast.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntervalsQuery;
+    use crate::elastic_query_dsl::ConvertableToQueryAst;
+    use crate::query_ast::{IntervalsAllOfRule, IntervalsRule, QueryAst};
+
+    #[test]
+    fn test_intervals_query_deserialize() {
+        let intervals_query_json = r#"{
+            "my_text": {
+                "all_of": {
+                    "ordered": true,
+                    "max_gaps": 1,
+                    "intervals": [
+                        {
+                            "match": { "query": "my favorite food", "max_gaps": 0, "ordered": true }
+                        },
+                        { "any_of": { "intervals": [
+                            { "match": { "query": "hot water" } },
+                            { "match": { "query": "cold porridge" } }
+                        ] } }
+                    ]
+                }
+            }
+        }"#;
+        let intervals_query: IntervalsQuery = serde_json::from_str(intervals_query_json).unwrap();
+        assert_eq!(intervals_query.field, "my_text");
+        let query_ast = intervals_query.convert_to_query_ast().unwrap();
+        let QueryAst::Intervals(intervals_query_ast) = query_ast else { panic!() };
+        let IntervalsRule::AllOf(all_of_rule) = intervals_query_ast.rule else { panic!() };
+        let IntervalsAllOfRule {
+            intervals,
+            max_gaps,
+            ordered,
+        } = all_of_rule;
+        assert_eq!(intervals.len(), 2);
+        assert_eq!(max_gaps, 1);
+        assert!(ordered);
+        let IntervalsRule::Match(match_rule) = &intervals[0] else { panic!() };
+        assert_eq!(match_rule.query, "my favorite food");
+        assert_eq!(match_rule.max_gaps, 0);
+        let IntervalsRule::AnyOf(any_of_rule) = &intervals[1] else { panic!() };
+        assert_eq!(any_of_rule.intervals.len(), 2);
+        let IntervalsRule::Match(match_rule) = &any_of_rule.intervals[0] else { panic!() };
+        assert_eq!(match_rule.max_gaps, -1);
+        assert!(!match_rule.ordered);
+    }
+
+    #[test]
+    fn test_intervals_query_unsupported_filter() {
+        let intervals_query_json = r#"{
+            "my_text": {
+                "match": {
+                    "query": "hot water",
+                    "filter": { "not_containing": { "match": { "query": "salty" } } }
+                }
+            }
+        }"#;
+        let error = serde_json::from_str::<IntervalsQuery>(intervals_query_json).unwrap_err();
+        assert!(error.to_string().contains("unknown field `filter`"));
+    }
+}
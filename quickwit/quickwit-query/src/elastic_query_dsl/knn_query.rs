@@ -0,0 +1,138 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst, MAX_KNN_NUM_CANDIDATES};
+
+const DEFAULT_K: u32 = 10;
+
+/// Elasticsearch `knn` query clause.
+///
+/// Unlike the top-level `knn` search option, the clause can be combined with other queries, for
+/// instance in the `must` clause of a `bool` query whose `filter` restricts the candidates.
+/// The `filter`, `similarity` and `query_vector_builder` parameters are not supported.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct KnnQuery {
+    field: String,
+    query_vector: Vec<NotNaNf32>,
+    #[serde(default)]
+    k: Option<u32>,
+    #[serde(default)]
+    num_candidates: Option<u32>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<KnnQuery> for ElasticQueryDslInner {
+    fn from(knn_query: KnnQuery) -> Self {
+        Self::Knn(knn_query)
+    }
+}
+
+impl ConvertableToQueryAst for KnnQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let k = self.k.unwrap_or(DEFAULT_K);
+        // Same default as Elasticsearch: 1.5 times `k`.
+        let num_candidates = self
+            .num_candidates
+            .unwrap_or_else(|| (k.saturating_mul(3) / 2 + k % 2).min(MAX_KNN_NUM_CANDIDATES));
+        let knn_query = query_ast::KnnQuery {
+            field: self.field,
+            query_vector: self.query_vector,
+            k,
+            num_candidates,
+        };
+        knn_query.validate()?;
+        let knn_ast: QueryAst = knn_query.into();
+        Ok(knn_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::ElasticQueryDsl;
+
+    #[test]
+    fn test_knn_query_defaults() {
+        let knn_query_json = r#"{ "field": "embedding", "query_vector": [0.5, -1.0] }"#;
+        let knn_query: KnnQuery = serde_json::from_str(knn_query_json).unwrap();
+        let query_ast = knn_query.convert_to_query_ast().unwrap();
+        let QueryAst::Knn(knn_query) = query_ast else {
+            panic!("expected a knn query, got {query_ast:?}");
+        };
+        assert_eq!(knn_query.field, "embedding");
+        assert_eq!(knn_query.query_vector.len(), 2);
+        assert_eq!(knn_query.k, 10);
+        assert_eq!(knn_query.num_candidates, 15);
+    }
+
+    #[test]
+    fn test_knn_query_with_params() {
+        let knn_query_json = r#"{
+            "field": "embedding",
+            "query_vector": [0.5, -1.0],
+            "k": 3,
+            "num_candidates": 50,
+            "boost": 2.0
+        }"#;
+        let knn_query: KnnQuery = serde_json::from_str(knn_query_json).unwrap();
+        let query_ast = knn_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, .. } = query_ast else { panic!() };
+        let QueryAst::Knn(knn_query) = *underlying else { panic!() };
+        assert_eq!(knn_query.k, 3);
+        assert_eq!(knn_query.num_candidates, 50);
+    }
+
+    #[test]
+    fn test_knn_query_invalid() {
+        let knn_query_json = r#"{
+            "field": "embedding",
+            "query_vector": [0.5],
+            "k": 100,
+            "num_candidates": 10
+        }"#;
+        let knn_query: KnnQuery = serde_json::from_str(knn_query_json).unwrap();
+        let error = knn_query.convert_to_query_ast().unwrap_err();
+        assert!(error.to_string().contains("cannot be lower than `k`"));
+
+        let knn_query_json =
+            r#"{ "field": "embedding", "query_vector": [0.5], "filter": { "match_all": {} } }"#;
+        assert!(serde_json::from_str::<KnnQuery>(knn_query_json).is_err());
+    }
+
+    #[test]
+    fn test_knn_query_in_bool_query() {
+        let query_json = r#"{
+            "bool": {
+                "must": [{ "knn": { "field": "embedding", "query_vector": [0.5], "k": 2 } }],
+                "filter": [{ "term": { "lang": "en" } }]
+            }
+        }"#;
+        let elastic_query_dsl: ElasticQueryDsl = serde_json::from_str(query_json).unwrap();
+        let query_ast = QueryAst::try_from(elastic_query_dsl).unwrap();
+        let QueryAst::Bool(bool_query) = query_ast else { panic!() };
+        assert!(matches!(bool_query.must[..], [QueryAst::Knn(_)]));
+        assert_eq!(bool_query.filter.len(), 1);
+    }
+}
@@ -23,8 +23,10 @@ use serde::de::{self, MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::elastic_query_dsl::ConvertableToQueryAst;
-use crate::query_ast::{FullTextParams, FullTextQuery, QueryAst};
-use crate::{BooleanOperand, MatchAllOrNone, OneFieldMap};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::utils::is_false;
+use crate::query_ast::{FullTextMode, FullTextParams, FullTextQuery, QueryAst};
+use crate::{BooleanOperand, Fuzziness, MatchAllOrNone, MinimumShouldMatch, OneFieldMap};
 
 /// `MatchQuery` as defined in
 /// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-match-query.html>
@@ -46,20 +48,42 @@ struct MatchQueryParams {
     operator: BooleanOperand,
     #[serde(default)]
     zero_terms_query: MatchAllOrNone,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    minimum_should_match: Option<MinimumShouldMatch>,
+    // Name of the tokenizer used to tokenize the query, in place of the field's tokenizer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    analyzer: Option<String>,
+    // Maximum number of edits for a token of the query to match a term of a text field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fuzziness: Option<Fuzziness>,
+    // If true, a query that cannot be interpreted with the type of the field, e.g. `hello` on a
+    // numeric field, matches no documents instead of failing.
+    #[serde(default, skip_serializing_if = "is_false")]
+    lenient: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    boost: Option<NotNaNf32>,
 }
 
 impl ConvertableToQueryAst for MatchQuery {
     fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
         let full_text_params = FullTextParams {
-            tokenizer: None,
-            mode: self.params.operator.into(),
+            tokenizer: self.params.analyzer,
+            mode: FullTextMode::Bool {
+                operator: self.params.operator,
+                minimum_should_match: self.params.minimum_should_match,
+                fuzziness: self.params.fuzziness,
+            },
             zero_terms_query: self.params.zero_terms_query,
+            lenient: self.params.lenient,
         };
-        Ok(QueryAst::FullText(FullTextQuery {
+        let full_text_ast: QueryAst = FullTextQuery {
             field: self.field,
             text: self.params.query,
             params: full_text_params,
-        }))
+            preferred_field: None,
+        }
+        .into();
+        Ok(full_text_ast.boost(self.params.boost))
     }
 }
 
@@ -116,6 +140,11 @@ impl<'de> Visitor<'de> for MatchQueryParamsStringOrStructVisitor {
             query: query.to_string(),
             zero_terms_query: Default::default(),
             operator: Default::default(),
+            minimum_should_match: None,
+            analyzer: None,
+            fuzziness: None,
+            lenient: false,
+            boost: None,
         })
     }
 
@@ -136,7 +165,7 @@ mod tests {
     use crate::elastic_query_dsl::match_query::MatchQuery;
     use crate::elastic_query_dsl::ConvertableToQueryAst;
     use crate::query_ast::{FullTextMode, FullTextQuery, QueryAst};
-    use crate::{BooleanOperand, MatchAllOrNone};
+    use crate::{BooleanOperand, Fuzziness, MatchAllOrNone, MinimumShouldMatch};
 
     #[test]
     fn test_deserialize_match_query_string() {
@@ -177,18 +206,68 @@ mod tests {
                 query: "hello".to_string(),
                 operator: BooleanOperand::And,
                 zero_terms_query: crate::MatchAllOrNone::MatchAll,
+                minimum_should_match: None,
+                analyzer: None,
+                fuzziness: None,
+                lenient: false,
+                boost: None,
             },
         };
         let ast = match_query.convert_to_query_ast().unwrap();
-        let QueryAst::FullText(FullTextQuery { field, text, params }) = ast else { panic!() } ;
+        let QueryAst::FullText(FullTextQuery { field, text, params, .. }) = ast else { panic!() } ;
         assert_eq!(field, "body");
         assert_eq!(text, "hello");
         assert_eq!(
             params.mode,
             FullTextMode::Bool {
-                operator: BooleanOperand::And
+                operator: BooleanOperand::And,
+                minimum_should_match: None,
+                fuzziness: None,
             }
         );
         assert_eq!(params.zero_terms_query, MatchAllOrNone::MatchAll);
     }
+
+    #[test]
+    fn test_match_query_minimum_should_match_and_analyzer() {
+        let match_query: MatchQuery = serde_json::from_str(
+            r#"{"body": {"query": "Hello", "minimum_should_match": "75%", "analyzer": "raw"}}"#,
+        )
+        .unwrap();
+        let ast = match_query.convert_to_query_ast().unwrap();
+        let QueryAst::FullText(FullTextQuery { params, .. }) = ast else { panic!() };
+        assert_eq!(params.tokenizer.as_deref(), Some("raw"));
+        assert_eq!(
+            params.mode,
+            FullTextMode::Bool {
+                operator: BooleanOperand::Or,
+                minimum_should_match: Some(MinimumShouldMatch::Percentage(75)),
+                fuzziness: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_match_query_fuzziness_lenient_and_boost() {
+        let match_query: MatchQuery = serde_json::from_str(
+            r#"{"body": {"query": "helo", "fuzziness": "AUTO", "lenient": true, "boost": 2.0}}"#,
+        )
+        .unwrap();
+        let ast = match_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, .. } = ast else { panic!() };
+        let QueryAst::FullText(FullTextQuery { params, .. }) = *underlying else { panic!() };
+        assert!(params.lenient);
+        assert_eq!(
+            params.mode,
+            FullTextMode::Bool {
+                operator: BooleanOperand::Or,
+                minimum_should_match: None,
+                fuzziness: Some(Fuzziness::Auto { low: 3, high: 6 }),
+            }
+        );
+        assert!(serde_json::from_str::<MatchQuery>(
+            r#"{"body": {"query": "helo", "fuzziness": 3}}"#
+        )
+        .is_err());
+    }
 }
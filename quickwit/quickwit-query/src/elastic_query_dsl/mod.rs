@@ -20,19 +20,55 @@
 use serde::{Deserialize, Serialize};
 
 mod bool_query;
+mod boosting_query;
+mod combined_fields_query;
+mod constant_score_query;
+mod dis_max_query;
+mod distance_feature_query;
+mod exists_query;
+mod function_score_query;
+#[cfg(test)]
+mod golden_file_tests;
+mod ids_query;
+mod intervals_query;
+mod knn_query;
 mod match_query;
 mod one_field_map;
 mod phrase_prefix_query;
+mod pinned_query;
 mod query_string_query;
 mod range_query;
+mod regexp_query;
+mod simple_query_string_query;
+mod span_query;
 mod term_query;
+mod terms_query;
+mod trace_context_query;
 
 use bool_query::BoolQuery;
+use boosting_query::BoostingQuery;
+use combined_fields_query::CombinedFieldsQuery;
+use constant_score_query::ConstantScoreQuery;
+use dis_max_query::DisMaxQuery;
+use distance_feature_query::DistanceFeatureQuery;
+use exists_query::ExistsQuery;
+use function_score_query::FunctionScoreQuery;
+use ids_query::IdsQuery;
+use intervals_query::IntervalsQuery;
+use knn_query::KnnQuery;
 pub use one_field_map::OneFieldMap;
 use phrase_prefix_query::MatchPhrasePrefix;
+use pinned_query::PinnedQuery;
 pub(crate) use query_string_query::QueryStringQuery;
 use range_query::RangeQuery;
+use regexp_query::RegexpQuery;
+use simple_query_string_query::SimpleQueryStringQuery;
+use span_query::{
+    SpanFirstQuery, SpanNearQuery, SpanNotQuery, SpanOrQuery, SpanQuery, SpanTermQuery,
+};
 use term_query::TermQuery;
+use terms_query::TermsQuery;
+use trace_context_query::TraceContextQuery;
 
 use crate::elastic_query_dsl::match_query::MatchQuery;
 use crate::not_nan_f32::NotNaNf32;
@@ -50,13 +86,33 @@ struct MatchNoneQuery;
 #[serde(rename_all = "snake_case", deny_unknown_fields)]
 enum ElasticQueryDslInner {
     QueryString(QueryStringQuery),
+    SimpleQueryString(SimpleQueryStringQuery),
     Bool(BoolQuery),
+    Boosting(BoostingQuery),
+    ConstantScore(ConstantScoreQuery),
+    DisMax(DisMaxQuery),
+    DistanceFeature(DistanceFeatureQuery),
+    FunctionScore(FunctionScoreQuery),
     Term(TermQuery),
+    Terms(TermsQuery),
     MatchAll(MatchAllQuery),
     MatchNone(MatchNoneQuery),
     Match(MatchQuery),
+    CombinedFields(CombinedFieldsQuery),
     MatchPhrasePrefix(MatchPhrasePrefix),
+    Intervals(IntervalsQuery),
+    SpanTerm(SpanTermQuery),
+    SpanOr(SpanOrQuery),
+    SpanNear(SpanNearQuery),
+    SpanFirst(SpanFirstQuery),
+    SpanNot(SpanNotQuery),
     Range(RangeQuery),
+    Regexp(RegexpQuery),
+    Exists(ExistsQuery),
+    Ids(IdsQuery),
+    Pinned(PinnedQuery),
+    Knn(KnnQuery),
+    TraceContext(TraceContextQuery),
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -79,8 +135,23 @@ impl ConvertableToQueryAst for ElasticQueryDslInner {
     fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
         match self {
             Self::QueryString(query_string_query) => query_string_query.convert_to_query_ast(),
+            Self::SimpleQueryString(simple_query_string_query) => {
+                simple_query_string_query.convert_to_query_ast()
+            }
             Self::Bool(bool_query) => bool_query.convert_to_query_ast(),
+            Self::Boosting(boosting_query) => boosting_query.convert_to_query_ast(),
+            Self::ConstantScore(constant_score_query) => {
+                constant_score_query.convert_to_query_ast()
+            }
+            Self::DisMax(dis_max_query) => dis_max_query.convert_to_query_ast(),
+            Self::DistanceFeature(distance_feature_query) => {
+                distance_feature_query.convert_to_query_ast()
+            }
+            Self::FunctionScore(function_score_query) => {
+                function_score_query.convert_to_query_ast()
+            }
             Self::Term(term_query) => term_query.convert_to_query_ast(),
+            Self::Terms(terms_query) => terms_query.convert_to_query_ast(),
             Self::MatchAll(match_all_query) => {
                 if let Some(boost) = match_all_query.boost {
                     Ok(QueryAst::Boost {
@@ -95,8 +166,31 @@ impl ConvertableToQueryAst for ElasticQueryDslInner {
             Self::MatchPhrasePrefix(match_phrase_prefix) => {
                 match_phrase_prefix.convert_to_query_ast()
             }
+            Self::Intervals(intervals_query) => intervals_query.convert_to_query_ast(),
+            Self::SpanTerm(span_term_query) => {
+                SpanQuery::SpanTerm(span_term_query).convert_to_query_ast()
+            }
+            Self::SpanOr(span_or_query) => SpanQuery::SpanOr(span_or_query).convert_to_query_ast(),
+            Self::SpanNear(span_near_query) => {
+                SpanQuery::SpanNear(span_near_query).convert_to_query_ast()
+            }
+            Self::SpanFirst(span_first_query) => {
+                SpanQuery::SpanFirst(span_first_query).convert_to_query_ast()
+            }
+            Self::SpanNot(span_not_query) => {
+                SpanQuery::SpanNot(span_not_query).convert_to_query_ast()
+            }
             Self::Range(range_query) => range_query.convert_to_query_ast(),
+            Self::Regexp(regexp_query) => regexp_query.convert_to_query_ast(),
+            Self::Exists(exists_query) => exists_query.convert_to_query_ast(),
+            Self::Ids(ids_query) => ids_query.convert_to_query_ast(),
+            Self::Pinned(pinned_query) => pinned_query.convert_to_query_ast(),
+            Self::Knn(knn_query) => knn_query.convert_to_query_ast(),
             Self::Match(match_query) => match_query.convert_to_query_ast(),
+            Self::CombinedFields(combined_fields_query) => {
+                combined_fields_query.convert_to_query_ast()
+            }
+            Self::TraceContext(trace_context_query) => trace_context_query.convert_to_query_ast(),
         }
     }
 }
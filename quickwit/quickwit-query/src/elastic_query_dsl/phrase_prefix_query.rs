@@ -63,6 +63,7 @@ impl ConvertableToQueryAst for MatchPhrasePrefix {
             tokenizer: analyzer,
             mode: FullTextMode::Phrase { slop },
             zero_terms_query,
+            lenient: false,
         };
         let phrase_prefix_query_ast = query_ast::PhrasePrefixQuery {
             field: self.field,
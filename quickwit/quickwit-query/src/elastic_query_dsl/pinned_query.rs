@@ -0,0 +1,188 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{
+    DisMaxQuery, FunctionBoostMode, FunctionScoreMode, FunctionScoreQuery, QueryAst, ScoreFunction,
+    ScoreFunctionKind, TermQuery,
+};
+use crate::DOC_ID_FIELD_ALIAS;
+
+/// Maximum number of pinned documents.
+const MAX_PINNED_IDS: usize = 100;
+
+/// The scores of the organic results are capped to this value. The pinned documents are scored
+/// with greater multiples of it, so that they rank above the organic results.
+const MAX_ORGANIC_SCORE: f32 = 1e30;
+
+/// Returns the documents matching the `organic` query, promoting the documents whose doc ID is
+/// one of `ids` above them, in the order of `ids`. It requires the index to define a doc ID
+/// field, and only affects the order of hits sorted by score.
+///
+/// # Unsupported features
+/// - `docs`: documents can only be pinned by ID.
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PinnedQuery {
+    ids: Vec<String>,
+    organic: Box<ElasticQueryDslInner>,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<PinnedQuery> for ElasticQueryDslInner {
+    fn from(pinned_query: PinnedQuery) -> Self {
+        Self::Pinned(pinned_query)
+    }
+}
+
+impl ConvertableToQueryAst for PinnedQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let mut seen_ids: HashSet<&str> = HashSet::with_capacity(self.ids.len());
+        let ids: Vec<&String> = self
+            .ids
+            .iter()
+            .filter(|id| seen_ids.insert(id.as_str()))
+            .collect();
+        if ids.len() > MAX_PINNED_IDS {
+            bail!(
+                "The pinned query accepts at most {MAX_PINNED_IDS} ids, got {}.",
+                ids.len()
+            );
+        }
+        let organic_ast = self.organic.convert_to_query_ast()?;
+        let capped_organic_ast: QueryAst = FunctionScoreQuery {
+            underlying: Box::new(organic_ast),
+            functions: vec![ScoreFunction {
+                kind: ScoreFunctionKind::Constant,
+                filter: None,
+                weight: Some(NotNaNf32::try_from(MAX_ORGANIC_SCORE).unwrap()),
+            }],
+            score_mode: FunctionScoreMode::default(),
+            boost_mode: FunctionBoostMode::Min,
+            now_timestamp_micros: None,
+        }
+        .into();
+        let num_ids = ids.len();
+        let mut queries: Vec<QueryAst> = Vec::with_capacity(num_ids + 1);
+
+        for (position, id) in ids.into_iter().enumerate() {
+            let score = MAX_ORGANIC_SCORE * (num_ids - position + 1) as f32;
+            let pinned_ast = QueryAst::ConstScore {
+                underlying: Box::new(
+                    TermQuery {
+                        field: DOC_ID_FIELD_ALIAS.to_string(),
+                        value: id.clone(),
+                    }
+                    .into(),
+                ),
+                score: NotNaNf32::try_from(score).unwrap(),
+            };
+            queries.push(pinned_ast);
+        }
+        queries.push(capped_organic_ast);
+        let dis_max_ast: QueryAst = DisMaxQuery {
+            queries,
+            tie_breaker: None,
+        }
+        .into();
+        Ok(dis_max_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::term_query::term_query_from_field_value;
+
+    #[test]
+    fn test_dsl_pinned_query() {
+        let pinned_query: PinnedQuery = serde_json::from_str(
+            r#"{
+                "ids": ["doc-2", "doc-1", "doc-2"],
+                "organic": { "term": {"title": {"value": "shoes" }} }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            pinned_query.organic,
+            Box::new(term_query_from_field_value("title", "shoes").into())
+        );
+        let query_ast = pinned_query.convert_to_query_ast().unwrap();
+        let QueryAst::DisMax(dis_max_query) = query_ast else {
+            panic!("Expected a dis max query, got {query_ast:?}.");
+        };
+        assert_eq!(dis_max_query.queries.len(), 3);
+
+        let pinned_scores: Vec<f32> = dis_max_query.queries[..2]
+            .iter()
+            .map(|query_ast| {
+                let QueryAst::ConstScore { underlying, score } = query_ast else {
+                    panic!("Expected a const score query, got {query_ast:?}.");
+                };
+                let QueryAst::Term(term_query) = underlying.as_ref() else {
+                    panic!("Expected a term query, got {underlying:?}.");
+                };
+                assert_eq!(term_query.field, "_id");
+                f32::from(*score)
+            })
+            .collect();
+        // Pinned documents rank in the order of `ids`, above the capped organic results.
+        assert!(pinned_scores[0] > pinned_scores[1]);
+        assert!(pinned_scores[1] > MAX_ORGANIC_SCORE);
+
+        let QueryAst::FunctionScore(function_score_query) = &dis_max_query.queries[2] else {
+            panic!("Expected a function score query.");
+        };
+        assert_eq!(function_score_query.boost_mode, FunctionBoostMode::Min);
+        assert!(matches!(
+            function_score_query.underlying.as_ref(),
+            QueryAst::Term(_)
+        ));
+    }
+
+    #[test]
+    fn test_dsl_pinned_query_too_many_ids() {
+        let ids: Vec<String> = (0..=MAX_PINNED_IDS).map(|id| id.to_string()).collect();
+        let pinned_query = PinnedQuery {
+            ids,
+            organic: Box::new(ElasticQueryDslInner::MatchAll(Default::default())),
+            boost: None,
+        };
+        let error = pinned_query.convert_to_query_ast().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The pinned query accepts at most 100 ids, got 101."
+        );
+    }
+
+    #[test]
+    fn test_dsl_pinned_query_docs_unsupported() {
+        serde_json::from_str::<PinnedQuery>(
+            r#"{ "docs": [{"_index": "shop", "_id": "1"}], "organic": { "match_all": {} } }"#,
+        )
+        .unwrap_err();
+    }
+}
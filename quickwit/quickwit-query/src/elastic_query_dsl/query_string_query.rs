@@ -21,8 +21,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::elastic_query_dsl::ConvertableToQueryAst;
 use crate::not_nan_f32::NotNaNf32;
-use crate::query_ast::UserInputQuery;
-use crate::BooleanOperand;
+use crate::query_ast::{QueryAst, UserInputQuery};
+use crate::{BooleanOperand, Fuzziness};
 
 fn is_default<T: Default + Eq>(val: &T) -> bool {
     *val == Default::default()
@@ -38,28 +38,77 @@ pub(crate) struct QueryStringQuery {
     /// Note that following elastic, we do not support "string" and require an array here.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     fields: Option<Vec<String>>,
+    /// Field searched when `fields` is not set. `*` stands for the default search fields of
+    /// the index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_field: Option<String>,
     #[serde(default, skip_serializing_if = "is_default")]
     default_operator: BooleanOperand,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     boost: Option<NotNaNf32>,
+    /// Wildcard terms are not supported, so there is nothing to analyze: only `false` is
+    /// accepted.
+    #[serde(default, skip_serializing_if = "is_default")]
+    analyze_wildcard: bool,
+    /// Wildcard terms are not supported, so leading wildcards cannot be allowed: only `false` is
+    /// accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    allow_leading_wildcard: Option<bool>,
+    /// Suffix appended to the target fields of quoted phrases. Fields without a suffixed
+    /// counterpart in the schema are searched as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quote_field_suffix: Option<String>,
+    /// Slop of the quoted phrases that do not set one with `~N`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    phrase_slop: u32,
+    /// Fuzziness of the terms suffixed with `~` without an edit distance. Defaults to `AUTO`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fuzziness: Option<Fuzziness>,
+    /// If true, terms that cannot be interpreted with the type of the field they target match
+    /// no documents instead of failing the query.
+    #[serde(default, skip_serializing_if = "is_default")]
+    lenient: bool,
 }
 
 impl ConvertableToQueryAst for QueryStringQuery {
-    fn convert_to_query_ast(self) -> anyhow::Result<crate::query_ast::QueryAst> {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        if self.analyze_wildcard {
+            anyhow::bail!("`analyze_wildcard` is not supported in `query_string` queries.");
+        }
+        if self.allow_leading_wildcard == Some(true) {
+            anyhow::bail!("`allow_leading_wildcard` is not supported in `query_string` queries.");
+        }
+        let default_fields = match (self.fields, self.default_field) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!(
+                    "`fields` and `default_field` cannot be both set in `query_string` queries."
+                );
+            }
+            (Some(fields), None) => Some(fields),
+            (None, Some(default_field)) if default_field == "*" => None,
+            (None, Some(default_field)) => Some(vec![default_field]),
+            (None, None) => None,
+        };
         let user_text_query = UserInputQuery {
             user_text: self.query,
-            default_fields: self.fields,
+            default_fields,
             default_operator: self.default_operator,
+            quote_field_suffix: self.quote_field_suffix,
+            phrase_slop: self.phrase_slop,
+            lenient: self.lenient,
+            // `query_string` queries support the `term~N` fuzzy syntax.
+            fuzziness: Some(self.fuzziness.unwrap_or_default()),
         };
-        Ok(user_text_query.into())
+        Ok(QueryAst::from(user_text_query).boost(self.boost))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::elastic_query_dsl::{ConvertableToQueryAst, QueryStringQuery};
+    use crate::not_nan_f32::NotNaNf32;
     use crate::query_ast::{QueryAst, UserInputQuery};
-    use crate::BooleanOperand;
+    use crate::{BooleanOperand, Fuzziness};
 
     #[test]
     fn test_build_query_string_query_with_default_field_non_empty() {
@@ -68,6 +117,13 @@ mod tests {
             fields: Some(vec!["hello".to_string()]),
             default_operator: crate::BooleanOperand::Or,
             boost: None,
+            default_field: None,
+            analyze_wildcard: false,
+            allow_leading_wildcard: None,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            fuzziness: None,
+            lenient: false,
         };
         let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
             panic!();
@@ -86,6 +142,13 @@ mod tests {
             fields: Some(Vec::new()),
             default_operator: crate::BooleanOperand::And,
             boost: None,
+            default_field: None,
+            analyze_wildcard: false,
+            allow_leading_wildcard: None,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            fuzziness: None,
+            lenient: false,
         };
         let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
             panic!();
@@ -100,6 +163,13 @@ mod tests {
             fields: Some(Vec::new()),
             default_operator: crate::BooleanOperand::Or,
             boost: None,
+            default_field: None,
+            analyze_wildcard: false,
+            allow_leading_wildcard: None,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            fuzziness: None,
+            lenient: false,
         };
         let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
             panic!();
@@ -115,6 +185,13 @@ mod tests {
             fields: None,
             default_operator: crate::BooleanOperand::Or,
             boost: None,
+            default_field: None,
+            analyze_wildcard: false,
+            allow_leading_wildcard: None,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            fuzziness: None,
+            lenient: false,
         };
         let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
             panic!();
@@ -135,9 +212,109 @@ mod tests {
         assert!(matches!(query_ast, QueryAst::UserInput(UserInputQuery {
             user_text,
             default_fields,
-            default_operator
+            default_operator,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: Some(_),
         }) if user_text == "hello world"
             && default_operator == BooleanOperand::Or
             && default_fields == Some(vec!["text".to_string()])));
     }
+
+    #[test]
+    fn test_build_query_string_default_field() {
+        let query_string_query: QueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello", "default_field": "text" }"#).unwrap();
+        let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
+            panic!();
+        };
+        assert_eq!(
+            user_input_query.default_fields,
+            Some(vec!["text".to_string()])
+        );
+
+        let query_string_query: QueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello", "default_field": "*" }"#).unwrap();
+        let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
+            panic!();
+        };
+        assert!(user_input_query.default_fields.is_none());
+
+        let query_string_query: QueryStringQuery = serde_json::from_str(
+            r#"{ "query": "hello", "default_field": "text", "fields": ["title"] }"#,
+        )
+        .unwrap();
+        let error = query_string_query.convert_to_query_ast().unwrap_err();
+        assert!(error.to_string().contains("cannot be both set"));
+    }
+
+    #[test]
+    fn test_build_query_string_wildcard_options() {
+        let query_string_query: QueryStringQuery = serde_json::from_str(
+            r#"{ "query": "hello", "allow_leading_wildcard": false, "analyze_wildcard": false }"#,
+        )
+        .unwrap();
+        assert!(query_string_query.convert_to_query_ast().is_ok());
+
+        let query_string_query: QueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello", "analyze_wildcard": true }"#).unwrap();
+        let error = query_string_query.convert_to_query_ast().unwrap_err();
+        assert!(error.to_string().contains("analyze_wildcard"));
+
+        let query_string_query: QueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello", "allow_leading_wildcard": true }"#)
+                .unwrap();
+        let error = query_string_query.convert_to_query_ast().unwrap_err();
+        assert!(error.to_string().contains("allow_leading_wildcard"));
+    }
+
+    #[test]
+    fn test_build_query_string_quote_field_suffix() {
+        let query_string_query: QueryStringQuery = serde_json::from_str(
+            r#"{ "query": "\"hello world\"", "quote_field_suffix": ".exact" }"#,
+        )
+        .unwrap();
+        let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
+            panic!();
+        };
+        assert_eq!(
+            user_input_query.quote_field_suffix.as_deref(),
+            Some(".exact")
+        );
+    }
+
+    #[test]
+    fn test_build_query_string_phrase_slop_fuzziness_lenient() {
+        let query_string_query: QueryStringQuery = serde_json::from_str(
+            r#"{ "query": "\"hello world\"", "phrase_slop": 2, "fuzziness": "AUTO", "lenient": true }"#,
+        )
+        .unwrap();
+        let QueryAst::UserInput(user_input_query) = query_string_query.convert_to_query_ast().unwrap() else {
+            panic!();
+        };
+        assert_eq!(user_input_query.phrase_slop, 2);
+        assert_eq!(
+            user_input_query.fuzziness,
+            Some(Fuzziness::Auto { low: 3, high: 6 })
+        );
+        assert!(user_input_query.lenient);
+
+        let error = serde_json::from_str::<QueryStringQuery>(
+            r#"{ "query": "hello~", "fuzziness": "fuzzy" }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("Invalid fuzziness"));
+    }
+
+    #[test]
+    fn test_build_query_string_boost() {
+        let query_string_query: QueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello", "boost": 2.0 }"#).unwrap();
+        let QueryAst::Boost { underlying, boost } = query_string_query.convert_to_query_ast().unwrap() else {
+            panic!();
+        };
+        assert!(matches!(*underlying, QueryAst::UserInput(_)));
+        assert_eq!(boost, NotNaNf32::try_from(2.0f32).unwrap());
+    }
 }
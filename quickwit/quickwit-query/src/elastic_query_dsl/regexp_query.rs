@@ -0,0 +1,124 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst, MAX_DETERMINIZED_STATES};
+
+pub type RegexpQuery = OneFieldMap<RegexpQueryParams>;
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RegexpQueryParams {
+    pub value: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default)]
+    pub max_determinized_states: Option<u32>,
+    #[serde(default)]
+    pub boost: Option<NotNaNf32>,
+}
+
+impl From<RegexpQuery> for ElasticQueryDslInner {
+    fn from(regexp_query: RegexpQuery) -> Self {
+        Self::Regexp(regexp_query)
+    }
+}
+
+impl ConvertableToQueryAst for RegexpQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let RegexpQueryParams {
+            value,
+            case_insensitive,
+            max_determinized_states,
+            boost,
+        } = self.value;
+        if let Some(max_determinized_states) = max_determinized_states {
+            if max_determinized_states > MAX_DETERMINIZED_STATES {
+                bail!(
+                    "`max_determinized_states` cannot exceed {MAX_DETERMINIZED_STATES}, got \
+                     {max_determinized_states}"
+                );
+            }
+        }
+        let regex_ast: QueryAst = query_ast::RegexQuery {
+            field: self.field,
+            regex: value,
+            case_insensitive,
+        }
+        .into();
+        Ok(regex_ast.boost(boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regexp_query_simple() {
+        let regexp_query_json = r#"{ "request.path": { "value": "/api/v[0-9]+/.*" } }"#;
+        let regexp_query: RegexpQuery = serde_json::from_str(regexp_query_json).unwrap();
+        let query_ast = regexp_query.convert_to_query_ast().unwrap();
+        let expected_query_ast: QueryAst = query_ast::RegexQuery {
+            field: "request.path".to_string(),
+            regex: "/api/v[0-9]+/.*".to_string(),
+            case_insensitive: false,
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_regexp_query_with_params() {
+        let regexp_query_json = r#"{
+            "error_code": {
+                "value": "e5[0-9]{2}",
+                "case_insensitive": true,
+                "max_determinized_states": 1000,
+                "boost": 2.0
+            }
+        }"#;
+        let regexp_query: RegexpQuery = serde_json::from_str(regexp_query_json).unwrap();
+        let query_ast = regexp_query.convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, .. } = query_ast else { panic!() };
+        let QueryAst::Regex(regex_query) = *underlying else { panic!() };
+        assert_eq!(regex_query.field, "error_code");
+        assert_eq!(regex_query.regex, "e5[0-9]{2}");
+        assert!(regex_query.case_insensitive);
+    }
+
+    #[test]
+    fn test_regexp_query_invalid() {
+        let regexp_query_json =
+            r#"{ "error_code": { "value": "E.*", "max_determinized_states": 100000 } }"#;
+        let regexp_query: RegexpQuery = serde_json::from_str(regexp_query_json).unwrap();
+        let error = regexp_query.convert_to_query_ast().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`max_determinized_states` cannot exceed 10000"));
+
+        let regexp_query_json = r#"{ "error_code": { "value": "E.*", "flags": "ALL" } }"#;
+        serde_json::from_str::<RegexpQuery>(regexp_query_json).unwrap_err();
+    }
+}
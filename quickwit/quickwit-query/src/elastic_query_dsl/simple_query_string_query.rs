@@ -0,0 +1,104 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::ConvertableToQueryAst;
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{QueryAst, SimpleQueryStringFlags, SimpleUserInputQuery};
+use crate::BooleanOperand;
+
+fn is_default<T: Default + Eq>(val: &T) -> bool {
+    *val == Default::default()
+}
+
+/// `SimpleQueryStringQuery` as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html>
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SimpleQueryStringQuery {
+    query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    default_operator: BooleanOperand,
+    #[serde(default, skip_serializing_if = "SimpleQueryStringFlags::is_all")]
+    flags: SimpleQueryStringFlags,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    boost: Option<NotNaNf32>,
+}
+
+impl ConvertableToQueryAst for SimpleQueryStringQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let simple_user_input_query = SimpleUserInputQuery {
+            user_text: self.query,
+            default_fields: self.fields,
+            default_operator: self.default_operator,
+            flags: self.flags,
+        };
+        Ok(QueryAst::from(simple_user_input_query).boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimpleQueryStringQuery;
+    use crate::elastic_query_dsl::ConvertableToQueryAst;
+    use crate::query_ast::{QueryAst, SimpleQueryStringFlag};
+    use crate::BooleanOperand;
+
+    #[test]
+    fn test_simple_query_string_query_deserialize() {
+        let simple_query_string_query: SimpleQueryStringQuery = serde_json::from_str(
+            r#"{ "query": "hello +world", "fields": ["body"], "flags": "AND|PHRASE" }"#,
+        )
+        .unwrap();
+        let query_ast = simple_query_string_query.convert_to_query_ast().unwrap();
+        let QueryAst::SimpleUserInput(simple_user_input_query) = query_ast else { panic!() };
+        assert_eq!(simple_user_input_query.user_text, "hello +world");
+        assert_eq!(
+            simple_user_input_query.default_fields,
+            Some(vec!["body".to_string()])
+        );
+        assert_eq!(simple_user_input_query.default_operator, BooleanOperand::Or);
+        assert!(simple_user_input_query
+            .flags
+            .contains(SimpleQueryStringFlag::And));
+        assert!(!simple_user_input_query
+            .flags
+            .contains(SimpleQueryStringFlag::Not));
+    }
+
+    #[test]
+    fn test_simple_query_string_query_with_boost() {
+        let simple_query_string_query: SimpleQueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello", "boost": 2.0 }"#).unwrap();
+        let query_ast = simple_query_string_query.convert_to_query_ast().unwrap();
+        assert!(matches!(query_ast, QueryAst::Boost { .. }));
+    }
+
+    #[test]
+    fn test_simple_query_string_query_invalid_flag() {
+        let error = serde_json::from_str::<SimpleQueryStringQuery>(
+            r#"{ "query": "hello", "flags": "OR|UNKNOWN" }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("UNKNOWN"));
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use anyhow::Context;
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::query_ast::{
+    self, QueryAst, SpanFirstRule, SpanNearRule, SpanNotRule, SpanOrRule, SpanRule, SpanTermRule,
+};
+
+fn default_in_order() -> bool {
+    true
+}
+
+/// Span queries as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/span-queries.html>
+///
+/// Only the `span_term`, `span_or`, `span_near`, `span_first` and `span_not` queries are
+/// supported. All the `span_term` clauses of a span query must target the same field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum SpanQuery {
+    SpanTerm(SpanTermQuery),
+    SpanOr(SpanOrQuery),
+    SpanNear(SpanNearQuery),
+    SpanFirst(SpanFirstQuery),
+    SpanNot(SpanNotQuery),
+}
+
+/// `{"my_field": "value"}` or `{"my_field": {"value": "value"}}`.
+pub type SpanTermQuery = OneFieldMap<SpanTermQueryValue>;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SpanTermQueryValue {
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanOrQuery {
+    pub clauses: Vec<SpanQuery>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanNearQuery {
+    pub clauses: Vec<SpanQuery>,
+    pub slop: u32,
+    #[serde(default = "default_in_order")]
+    pub in_order: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanFirstQuery {
+    #[serde(rename = "match")]
+    pub span_match: Box<SpanQuery>,
+    pub end: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanNotQuery {
+    pub include: Box<SpanQuery>,
+    pub exclude: Box<SpanQuery>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post: Option<u32>,
+    /// Shorthand setting both `pre` and `post`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dist: Option<u32>,
+}
+
+impl From<SpanQuery> for ElasticQueryDslInner {
+    fn from(span_query: SpanQuery) -> Self {
+        match span_query {
+            SpanQuery::SpanTerm(span_term_query) => Self::SpanTerm(span_term_query),
+            SpanQuery::SpanOr(span_or_query) => Self::SpanOr(span_or_query),
+            SpanQuery::SpanNear(span_near_query) => Self::SpanNear(span_near_query),
+            SpanQuery::SpanFirst(span_first_query) => Self::SpanFirst(span_first_query),
+            SpanQuery::SpanNot(span_not_query) => Self::SpanNot(span_not_query),
+        }
+    }
+}
+
+impl SpanQuery {
+    /// Converts the span query into a span rule, checking that its terms all target the same
+    /// field.
+    fn into_span_rule(self, field_opt: &mut Option<String>) -> anyhow::Result<SpanRule> {
+        let into_span_rules = |clauses: Vec<SpanQuery>, field_opt: &mut Option<String>| {
+            clauses
+                .into_iter()
+                .map(|clause| clause.into_span_rule(field_opt))
+                .collect::<anyhow::Result<Vec<SpanRule>>>()
+        };
+        let span_rule = match self {
+            SpanQuery::SpanTerm(span_term_query) => {
+                let field = field_opt.get_or_insert_with(|| span_term_query.field.clone());
+                if *field != span_term_query.field {
+                    anyhow::bail!(
+                        "All the clauses of a span query must target the same field. Got `{field}` \
+                         and `{}`.",
+                        span_term_query.field
+                    );
+                }
+                SpanRule::SpanTerm(SpanTermRule {
+                    value: span_term_query.value.value,
+                })
+            }
+            SpanQuery::SpanOr(span_or_query) => SpanRule::SpanOr(SpanOrRule {
+                clauses: into_span_rules(span_or_query.clauses, field_opt)?,
+            }),
+            SpanQuery::SpanNear(span_near_query) => SpanRule::SpanNear(SpanNearRule {
+                clauses: into_span_rules(span_near_query.clauses, field_opt)?,
+                slop: span_near_query.slop,
+                in_order: span_near_query.in_order,
+            }),
+            SpanQuery::SpanFirst(span_first_query) => SpanRule::SpanFirst(SpanFirstRule {
+                rule: Box::new(span_first_query.span_match.into_span_rule(field_opt)?),
+                end: span_first_query.end,
+            }),
+            SpanQuery::SpanNot(span_not_query) => {
+                let (pre, post) = match span_not_query.dist {
+                    Some(dist) => {
+                        if span_not_query.pre.is_some() || span_not_query.post.is_some() {
+                            anyhow::bail!(
+                                "`span_not` can either use `dist` or `pre` and `post`, not both."
+                            );
+                        }
+                        (dist, dist)
+                    }
+                    None => (
+                        span_not_query.pre.unwrap_or(0),
+                        span_not_query.post.unwrap_or(0),
+                    ),
+                };
+                SpanRule::SpanNot(SpanNotRule {
+                    include: Box::new(span_not_query.include.into_span_rule(field_opt)?),
+                    exclude: Box::new(span_not_query.exclude.into_span_rule(field_opt)?),
+                    pre,
+                    post,
+                })
+            }
+        };
+        Ok(span_rule)
+    }
+}
+
+impl ConvertableToQueryAst for SpanQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let mut field_opt = None;
+        let rule = self.into_span_rule(&mut field_opt)?;
+        let field = field_opt.context("A span query requires at least one `span_term` clause.")?;
+        Ok(query_ast::SpanQuery { field, rule }.into())
+    }
+}
+
+// The value of a `span_term` query is either a string or an object, as for match queries.
+// See https://serde.rs/string-or-struct.html
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpanTermQueryParams {
+    value: String,
+}
+
+impl<'de> Deserialize<'de> for SpanTermQueryValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        deserializer.deserialize_any(SpanTermQueryValueVisitor)
+    }
+}
+
+struct SpanTermQueryValueVisitor;
+
+impl<'de> Visitor<'de> for SpanTermQueryValueVisitor {
+    type Value = SpanTermQueryValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("string or map containing the value of a span term query.")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where E: de::Error {
+        Ok(SpanTermQueryValue {
+            value: value.to_string(),
+        })
+    }
+
+    fn visit_map<M>(self, map: M) -> Result<SpanTermQueryValue, M::Error>
+    where M: MapAccess<'de> {
+        let params: SpanTermQueryParams =
+            Deserialize::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        Ok(SpanTermQueryValue {
+            value: params.value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanQuery;
+    use crate::elastic_query_dsl::ConvertableToQueryAst;
+    use crate::query_ast::{QueryAst, SpanRule, SpanTermRule};
+
+    #[test]
+    fn test_span_query_deserialize_and_convert() {
+        let span_query_json = r#"{
+            "span_not": {
+                "include": {
+                    "span_near": {
+                        "clauses": [
+                            { "span_term": { "body": "refused" } },
+                            { "span_first": {
+                                "match": { "span_term": { "body": { "value": "host" } } },
+                                "end": 10
+                            } }
+                        ],
+                        "slop": 12,
+                        "in_order": false
+                    }
+                },
+                "exclude": { "span_or": { "clauses": [{ "span_term": { "body": "remote" } }] } },
+                "dist": 2
+            }
+        }"#;
+        let span_query: SpanQuery = serde_json::from_str(span_query_json).unwrap();
+        let QueryAst::Span(span_query_ast) = span_query.convert_to_query_ast().unwrap() else {
+            panic!()
+        };
+        assert_eq!(span_query_ast.field, "body");
+        let SpanRule::SpanNot(span_not_rule) = span_query_ast.rule else { panic!() };
+        assert_eq!(span_not_rule.pre, 2);
+        assert_eq!(span_not_rule.post, 2);
+        let SpanRule::SpanNear(span_near_rule) = *span_not_rule.include else { panic!() };
+        assert_eq!(span_near_rule.slop, 12);
+        assert!(!span_near_rule.in_order);
+        assert_eq!(
+            span_near_rule.clauses[0],
+            SpanRule::SpanTerm(SpanTermRule {
+                value: "refused".to_string()
+            })
+        );
+        let SpanRule::SpanFirst(span_first_rule) = &span_near_rule.clauses[1] else { panic!() };
+        assert_eq!(span_first_rule.end, 10);
+    }
+
+    #[test]
+    fn test_span_query_requires_a_single_field() {
+        let span_query_json = r#"{
+            "span_near": {
+                "clauses": [
+                    { "span_term": { "body": "refused" } },
+                    { "span_term": { "title": "host" } }
+                ],
+                "slop": 1
+            }
+        }"#;
+        let span_query: SpanQuery = serde_json::from_str(span_query_json).unwrap();
+        let error = span_query.convert_to_query_ast().unwrap_err();
+        assert!(error.to_string().contains("same field"));
+
+        let span_query_json = r#"{ "span_or": { "clauses": [] } }"#;
+        let span_query: SpanQuery = serde_json::from_str(span_query_json).unwrap();
+        assert!(span_query.convert_to_query_ast().is_err());
+    }
+
+    #[test]
+    fn test_span_query_unsupported() {
+        let span_query_json = r#"{
+            "span_containing": {
+                "little": { "span_term": { "body": "refused" } },
+                "big": { "span_term": { "body": "host" } }
+            }
+        }"#;
+        let error = serde_json::from_str::<SpanQuery>(span_query_json).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("unknown variant `span_containing`"));
+
+        let span_query_json = r#"{ "span_near": { "clauses": [] } }"#;
+        let error = serde_json::from_str::<SpanQuery>(span_query_json).unwrap_err();
+        assert!(error.to_string().contains("missing field `slop`"));
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::elastic_query_dsl::{ConvertableToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{QueryAst, TermSetQuery, TermsLookupQuery};
+
+/// Matches the documents in which `field` contains one of the terms. The terms are either listed
+/// inline, or fetched at query time from a document of another index:
+/// `{"terms": {"user_id": {"index": "allow-lists", "id": "list-1", "path": "user_ids"}}}`.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(
+    try_from = "BTreeMap<String, JsonValue>",
+    into = "BTreeMap<String, JsonValue>"
+)]
+pub struct TermsQuery {
+    field: String,
+    terms: TermsQueryTerms,
+    boost: Option<NotNaNf32>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum TermsQueryTerms {
+    Values(BTreeSet<String>),
+    Lookup(TermsLookup),
+}
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct TermsLookup {
+    index: String,
+    id: String,
+    path: String,
+}
+
+impl TryFrom<BTreeMap<String, JsonValue>> for TermsQuery {
+    type Error = anyhow::Error;
+
+    fn try_from(mut params: BTreeMap<String, JsonValue>) -> anyhow::Result<Self> {
+        let boost: Option<NotNaNf32> = params
+            .remove("boost")
+            .map(serde_json::from_value)
+            .transpose()
+            .context("Invalid `boost` parameter.")?;
+        let mut params_it = params.into_iter();
+        let Some((field, terms_json)) = params_it.next() else {
+            anyhow::bail!("Terms query must target a field.");
+        };
+        if let Some((second_field, _)) = params_it.next() {
+            anyhow::bail!(
+                "Terms query must target a single field. Got several ({field}, {second_field}, \
+                 ...)."
+            );
+        }
+        let terms = match terms_json {
+            JsonValue::Array(values) => {
+                let terms = values
+                    .into_iter()
+                    .map(|value| match value {
+                        JsonValue::String(value) => Ok(value),
+                        JsonValue::Number(value) => Ok(value.to_string()),
+                        JsonValue::Bool(value) => Ok(value.to_string()),
+                        _ => anyhow::bail!(
+                            "Terms of field `{field}` must be strings, numbers, or booleans."
+                        ),
+                    })
+                    .collect::<anyhow::Result<BTreeSet<String>>>()?;
+                TermsQueryTerms::Values(terms)
+            }
+            lookup_json @ JsonValue::Object(_) => {
+                let lookup: TermsLookup = serde_json::from_value(lookup_json)
+                    .with_context(|| format!("Invalid terms lookup for field `{field}`."))?;
+                TermsQueryTerms::Lookup(lookup)
+            }
+            _ => anyhow::bail!(
+                "Terms of field `{field}` must be an array of terms or a terms lookup."
+            ),
+        };
+        Ok(TermsQuery {
+            field,
+            terms,
+            boost,
+        })
+    }
+}
+
+impl From<TermsQuery> for BTreeMap<String, JsonValue> {
+    fn from(terms_query: TermsQuery) -> Self {
+        let terms_json = match terms_query.terms {
+            TermsQueryTerms::Values(terms) => {
+                JsonValue::Array(terms.into_iter().map(JsonValue::String).collect())
+            }
+            TermsQueryTerms::Lookup(lookup) => {
+                serde_json::to_value(lookup).expect("Failed to serialize terms lookup.")
+            }
+        };
+        let mut params = BTreeMap::from([(terms_query.field, terms_json)]);
+        if let Some(boost) = terms_query.boost {
+            params.insert("boost".to_string(), JsonValue::from(f32::from(boost)));
+        }
+        params
+    }
+}
+
+impl From<TermsQuery> for ElasticQueryDslInner {
+    fn from(terms_query: TermsQuery) -> Self {
+        Self::Terms(terms_query)
+    }
+}
+
+impl ConvertableToQueryAst for TermsQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let terms_ast: QueryAst = match self.terms {
+            TermsQueryTerms::Values(terms) if terms.is_empty() => return Ok(QueryAst::MatchNone),
+            TermsQueryTerms::Values(terms) => TermSetQuery {
+                terms_per_field: HashMap::from([(self.field, terms)]),
+            }
+            .into(),
+            TermsQueryTerms::Lookup(lookup) => TermsLookupQuery {
+                field: self.field,
+                index_id: lookup.index,
+                doc_id: lookup.id,
+                path: lookup.path,
+            }
+            .into(),
+        };
+        Ok(terms_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terms_query_with_values() {
+        let terms_query: TermsQuery =
+            serde_json::from_str(r#"{ "status": ["ok", 404, true, "ok"] }"#).unwrap();
+        let query_ast = terms_query.convert_to_query_ast().unwrap();
+        let expected_query_ast: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "status".to_string(),
+                BTreeSet::from(["404".to_string(), "ok".to_string(), "true".to_string()]),
+            )]),
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_terms_query_without_values() {
+        let terms_query: TermsQuery = serde_json::from_str(r#"{ "status": [] }"#).unwrap();
+        let query_ast = terms_query.convert_to_query_ast().unwrap();
+        assert_eq!(query_ast, QueryAst::MatchNone);
+    }
+
+    #[test]
+    fn test_terms_query_with_lookup() {
+        let terms_query_json = r#"{
+            "user_id": { "index": "allow-lists", "id": "list-1", "path": "user_ids" },
+            "boost": 2.0
+        }"#;
+        let terms_query: TermsQuery = serde_json::from_str(terms_query_json).unwrap();
+        let query_ast = terms_query.clone().convert_to_query_ast().unwrap();
+        let QueryAst::Boost { underlying, .. } = query_ast else { panic!() };
+        let expected_query_ast: QueryAst = TermsLookupQuery {
+            field: "user_id".to_string(),
+            index_id: "allow-lists".to_string(),
+            doc_id: "list-1".to_string(),
+            path: "user_ids".to_string(),
+        }
+        .into();
+        assert_eq!(*underlying, expected_query_ast);
+
+        let terms_query_json = serde_json::to_string(&terms_query).unwrap();
+        let deserialized_terms_query: TermsQuery = serde_json::from_str(&terms_query_json).unwrap();
+        assert_eq!(deserialized_terms_query, terms_query);
+    }
+
+    #[test]
+    fn test_terms_query_invalid() {
+        assert!(serde_json::from_str::<TermsQuery>(r#"{ "boost": 2.0 }"#).is_err());
+        assert!(
+            serde_json::from_str::<TermsQuery>(r#"{ "status": ["ok"], "code": ["200"] }"#).is_err()
+        );
+        assert!(serde_json::from_str::<TermsQuery>(r#"{ "status": "ok" }"#).is_err());
+        assert!(serde_json::from_str::<TermsQuery>(
+            r#"{ "user_id": { "index": "allow-lists", "id": "list-1" } }"#
+        )
+        .is_err());
+    }
+}
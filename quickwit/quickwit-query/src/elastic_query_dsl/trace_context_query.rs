@@ -0,0 +1,134 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::elastic_query_dsl::ConvertableToQueryAst;
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst, TermSetQuery};
+use crate::trace_context::{span_id_variants, trace_id_variants};
+
+/// Quickwit-specific query matching documents belonging to a given trace and, optionally, to a
+/// given span.
+///
+/// Trace and span IDs are normalized before being matched: hex case and `0x` prefixes are
+/// ignored, and a trace ID matches both its 128-bit form and its 64-bit form, as emitted by
+/// 64-bit tracers such as Jaeger or Zipkin.
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TraceContextQuery {
+    trace_id: String,
+    #[serde(default)]
+    span_id: Option<String>,
+    #[serde(default = "default_trace_id_field")]
+    trace_id_field: String,
+    #[serde(default = "default_span_id_field")]
+    span_id_field: String,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+fn default_trace_id_field() -> String {
+    "trace_id".to_string()
+}
+
+fn default_span_id_field() -> String {
+    "span_id".to_string()
+}
+
+fn term_set_query(field: String, values: BTreeSet<String>) -> QueryAst {
+    let terms_per_field = HashMap::from([(field, values)]);
+    TermSetQuery { terms_per_field }.into()
+}
+
+impl ConvertableToQueryAst for TraceContextQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let trace_id_values = trace_id_variants(&self.trace_id)
+            .with_context(|| format!("Invalid trace ID `{}`.", self.trace_id))?;
+        let mut must = vec![term_set_query(self.trace_id_field, trace_id_values)];
+        if let Some(span_id) = self.span_id {
+            let span_id_values = span_id_variants(&span_id)
+                .with_context(|| format!("Invalid span ID `{span_id}`."))?;
+            must.push(term_set_query(self.span_id_field, span_id_values));
+        }
+        let query_ast: QueryAst = query_ast::BoolQuery {
+            must,
+            ..Default::default()
+        }
+        .into();
+        Ok(query_ast.boost(self.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_context_query_default_fields() {
+        let trace_context_query: TraceContextQuery =
+            serde_json::from_str(r#"{ "trace_id": "0xA3CE929D0E0E4736" }"#).unwrap();
+        let QueryAst::Bool(bool_query) = trace_context_query.convert_to_query_ast().unwrap() else {
+            panic!()
+        };
+        assert_eq!(bool_query.must.len(), 1);
+        let QueryAst::TermSet(term_set_query) = &bool_query.must[0] else {
+            panic!()
+        };
+        let trace_id_values = &term_set_query.terms_per_field["trace_id"];
+        assert!(trace_id_values.contains("a3ce929d0e0e4736"));
+        assert!(trace_id_values.contains("0000000000000000a3ce929d0e0e4736"));
+    }
+
+    #[test]
+    fn test_trace_context_query_with_span_id() {
+        let trace_context_query: TraceContextQuery = serde_json::from_str(
+            r#"{
+                "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736",
+                "span_id": "00F067AA0BA902B7",
+                "trace_id_field": "attributes.trace_id",
+                "span_id_field": "attributes.span_id"
+            }"#,
+        )
+        .unwrap();
+        let QueryAst::Bool(bool_query) = trace_context_query.convert_to_query_ast().unwrap() else {
+            panic!()
+        };
+        assert_eq!(bool_query.must.len(), 2);
+        let QueryAst::TermSet(term_set_query) = &bool_query.must[1] else {
+            panic!()
+        };
+        let span_id_values = &term_set_query.terms_per_field["attributes.span_id"];
+        assert_eq!(
+            span_id_values.iter().collect::<Vec<_>>(),
+            ["00F067AA0BA902B7", "00f067aa0ba902b7"]
+        );
+    }
+
+    #[test]
+    fn test_trace_context_query_invalid_trace_id() {
+        let trace_context_query: TraceContextQuery =
+            serde_json::from_str(r#"{ "trace_id": "not-a-trace-id" }"#).unwrap();
+        let error = trace_context_query.convert_to_query_ast().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid trace ID `not-a-trace-id`.");
+    }
+}
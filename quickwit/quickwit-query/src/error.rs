@@ -45,8 +45,12 @@ pub enum InvalidQuery {
     FieldDoesNotExist { full_path: String },
     #[error("Json field root is not a valid search field: `{full_path}`")]
     JsonFieldRootNotSearchable { full_path: String },
+    #[error("UDF does not exist: `{udf_name}`")]
+    UdfNotFound { udf_name: String },
     #[error("User query should have been parsed")]
     UserQueryNotParsed,
+    #[error("Terms lookup should have been resolved")]
+    TermsLookupNotResolved,
     #[error("{0}")]
     Other(#[from] anyhow::Error),
 }
@@ -0,0 +1,187 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum edit distance supported by fuzzy matching.
+const MAX_FUZZY_DISTANCE: u8 = 2;
+
+const DEFAULT_AUTO_LOW: u32 = 3;
+const DEFAULT_AUTO_HIGH: u32 = 6;
+
+/// Maximum number of edits allowed for a term to match a token of the query.
+///
+/// It follows the syntax of the `fuzziness` parameter of Elasticsearch.
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/common-options.html#fuzziness>
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[serde(
+    into = "FuzzinessForSerialization",
+    try_from = "FuzzinessForSerialization"
+)]
+pub enum Fuzziness {
+    /// A fixed edit distance, between 0 and 2.
+    Distance(u8),
+    /// An edit distance depending on the number of characters of the token: 0 below `low`, 1
+    /// below `high`, and 2 otherwise.
+    Auto { low: u32, high: u32 },
+}
+
+impl Fuzziness {
+    /// Returns the edit distance allowed for `token`.
+    pub fn resolve(&self, token: &str) -> u8 {
+        match *self {
+            Fuzziness::Distance(distance) => distance,
+            Fuzziness::Auto { low, high } => {
+                let num_chars = token.chars().count() as u32;
+                if num_chars < low {
+                    0
+                } else if num_chars < high {
+                    1
+                } else {
+                    2
+                }
+            }
+        }
+    }
+
+    /// Returns a fixed edit distance, failing if it exceeds the supported maximum.
+    pub(crate) fn distance(distance: u8) -> Result<Fuzziness, String> {
+        if distance > MAX_FUZZY_DISTANCE {
+            return Err(format!(
+                "Fuzziness must be at most {MAX_FUZZY_DISTANCE}, got `{distance}`."
+            ));
+        }
+        Ok(Fuzziness::Distance(distance))
+    }
+}
+
+/// `AUTO`, as in Elasticsearch.
+impl Default for Fuzziness {
+    fn default() -> Self {
+        Fuzziness::Auto {
+            low: DEFAULT_AUTO_LOW,
+            high: DEFAULT_AUTO_HIGH,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum FuzzinessForSerialization {
+    Distance(u8),
+    Str(String),
+}
+
+impl From<Fuzziness> for FuzzinessForSerialization {
+    fn from(fuzziness: Fuzziness) -> Self {
+        match fuzziness {
+            Fuzziness::Distance(distance) => FuzzinessForSerialization::Distance(distance),
+            Fuzziness::Auto {
+                low: DEFAULT_AUTO_LOW,
+                high: DEFAULT_AUTO_HIGH,
+            } => FuzzinessForSerialization::Str("AUTO".to_string()),
+            Fuzziness::Auto { low, high } => {
+                FuzzinessForSerialization::Str(format!("AUTO:{low},{high}"))
+            }
+        }
+    }
+}
+
+impl TryFrom<FuzzinessForSerialization> for Fuzziness {
+    type Error = String;
+
+    fn try_from(fuzziness: FuzzinessForSerialization) -> Result<Fuzziness, String> {
+        let fuzziness_str = match fuzziness {
+            FuzzinessForSerialization::Distance(distance) => {
+                return Fuzziness::distance(distance);
+            }
+            FuzzinessForSerialization::Str(fuzziness_str) => fuzziness_str,
+        };
+        let trimmed_str = fuzziness_str.trim();
+        let Some(auto_params_str) = trimmed_str.strip_prefix("AUTO") else {
+            let distance: u8 = trimmed_str
+                .parse()
+                .map_err(|_| format!("Invalid fuzziness `{fuzziness_str}`."))?;
+            return Fuzziness::distance(distance);
+        };
+        if auto_params_str.is_empty() {
+            return Ok(Fuzziness::Auto {
+                low: DEFAULT_AUTO_LOW,
+                high: DEFAULT_AUTO_HIGH,
+            });
+        }
+        let (low, high) = auto_params_str
+            .strip_prefix(':')
+            .and_then(|bounds_str| bounds_str.split_once(','))
+            .and_then(|(low_str, high_str)| {
+                Some((low_str.trim().parse().ok()?, high_str.trim().parse().ok()?))
+            })
+            .ok_or_else(|| format!("Invalid fuzziness `{fuzziness_str}`."))?;
+        if low > high {
+            return Err(format!(
+                "Invalid fuzziness `{fuzziness_str}`: the low bound exceeds the high bound."
+            ));
+        }
+        Ok(Fuzziness::Auto { low, high })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fuzziness;
+
+    #[test]
+    fn test_fuzziness_deserialize() {
+        let fuzziness: Fuzziness = serde_json::from_str("1").unwrap();
+        assert_eq!(fuzziness, Fuzziness::Distance(1));
+        let fuzziness: Fuzziness = serde_json::from_str(r#""2""#).unwrap();
+        assert_eq!(fuzziness, Fuzziness::Distance(2));
+        let fuzziness: Fuzziness = serde_json::from_str(r#""AUTO""#).unwrap();
+        assert_eq!(fuzziness, Fuzziness::Auto { low: 3, high: 6 });
+        let fuzziness: Fuzziness = serde_json::from_str(r#""AUTO:4,8""#).unwrap();
+        assert_eq!(fuzziness, Fuzziness::Auto { low: 4, high: 8 });
+        assert!(serde_json::from_str::<Fuzziness>("3").is_err());
+        assert!(serde_json::from_str::<Fuzziness>(r#""AUTO:8,4""#).is_err());
+        assert!(serde_json::from_str::<Fuzziness>(r#""AUTO:4""#).is_err());
+        assert!(serde_json::from_str::<Fuzziness>(r#""fuzzy""#).is_err());
+    }
+
+    #[test]
+    fn test_fuzziness_serialize() {
+        assert_eq!(serde_json::to_string(&Fuzziness::Distance(1)).unwrap(), "1");
+        assert_eq!(
+            serde_json::to_string(&Fuzziness::Auto { low: 3, high: 6 }).unwrap(),
+            r#""AUTO""#
+        );
+        assert_eq!(
+            serde_json::to_string(&Fuzziness::Auto { low: 4, high: 8 }).unwrap(),
+            r#""AUTO:4,8""#
+        );
+    }
+
+    #[test]
+    fn test_fuzziness_resolve() {
+        assert_eq!(Fuzziness::Distance(1).resolve("a"), 1);
+        let auto_fuzziness = Fuzziness::Auto { low: 3, high: 6 };
+        assert_eq!(auto_fuzziness.resolve("ab"), 0);
+        assert_eq!(auto_fuzziness.resolve("abc"), 1);
+        assert_eq!(auto_fuzziness.resolve("héllo"), 1);
+        assert_eq!(auto_fuzziness.resolve("abcdef"), 2);
+    }
+}
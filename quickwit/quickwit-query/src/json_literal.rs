@@ -288,6 +288,12 @@ impl From<String> for JsonLiteral {
     }
 }
 
+impl From<&str> for JsonLiteral {
+    fn from(s: &str) -> JsonLiteral {
+        JsonLiteral::String(s.to_string())
+    }
+}
+
 impl From<u64> for JsonLiteral {
     fn from(number: u64) -> JsonLiteral {
         JsonLiteral::Number(number.into())
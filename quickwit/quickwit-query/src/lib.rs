@@ -28,22 +28,43 @@
 // For the individual detailed API documentation however, you should refer to elastic
 // documentation.
 
+mod char_filters;
 mod elastic_query_dsl;
+mod fuzziness;
 mod json_literal;
+mod minimum_should_match;
+mod protected_words;
 pub mod query_ast;
+mod search_field;
 mod tokenizers;
+mod trace_context;
+pub mod udf;
 
 mod error;
 mod not_nan_f32;
 
+pub use char_filters::{char_filter_tokenizer_name, register_char_filter_tokenizer, CharFilter};
 pub use elastic_query_dsl::{ElasticQueryDsl, OneFieldMap};
 pub use error::InvalidQuery;
+pub use fuzziness::Fuzziness;
 pub use json_literal::{InterpretUserInput, JsonLiteral};
-pub(crate) use not_nan_f32::NotNaNf32;
+pub use minimum_should_match::MinimumShouldMatch;
+pub use not_nan_f32::NotNaNf32;
+pub use protected_words::{
+    is_stemming_tokenizer, protected_words_tokenizer_name, register_protected_words_tokenizer,
+};
 pub use query_ast::utils::find_field_or_hit_dynamic;
+pub use search_field::parse_search_field;
 use serde::{Deserialize, Serialize};
 pub use tantivy::query::Query as TantivyQuery;
 pub use tokenizers::{get_quickwit_fastfield_normalizer_manager, get_quickwit_tokenizer_manager};
+pub use trace_context::{
+    normalize_span_id, normalize_trace_id, span_id_variants, trace_id_variants,
+};
+
+/// Name under which queries can target the doc ID field of an index, whatever its actual name.
+/// The alias is resolved against the doc mapping of the index before the query is executed.
+pub const DOC_ID_FIELD_ALIAS: &str = "_id";
 
 #[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
 pub enum BooleanOperand {
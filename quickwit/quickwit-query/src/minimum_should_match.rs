@@ -0,0 +1,155 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+/// Minimum number of optional clauses that must match for a document to match.
+///
+/// It follows the syntax of the `minimum_should_match` parameter of Elasticsearch,
+/// combinations (e.g. `3<90%`) excepted.
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-minimum-should-match.html>
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[serde(
+    into = "MinimumShouldMatchForSerialization",
+    try_from = "MinimumShouldMatchForSerialization"
+)]
+pub enum MinimumShouldMatch {
+    /// A fixed number of clauses. A negative value is the number of clauses that may be
+    /// missing.
+    Absolute(i32),
+    /// A percentage of the number of clauses, rounded down. A negative value is the
+    /// percentage of clauses that may be missing.
+    Percentage(i32),
+}
+
+impl MinimumShouldMatch {
+    /// Returns the number of clauses, among `num_clauses`, that must match.
+    ///
+    /// The result may exceed `num_clauses`, in which case no document can match.
+    pub fn resolve(&self, num_clauses: usize) -> usize {
+        match *self {
+            MinimumShouldMatch::Absolute(num) if num >= 0 => num as usize,
+            MinimumShouldMatch::Absolute(num) => {
+                num_clauses.saturating_sub(num.unsigned_abs() as usize)
+            }
+            MinimumShouldMatch::Percentage(percent) if percent >= 0 => {
+                num_clauses * percent as usize / 100
+            }
+            MinimumShouldMatch::Percentage(percent) => {
+                num_clauses - num_clauses * percent.unsigned_abs() as usize / 100
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MinimumShouldMatchForSerialization {
+    Absolute(i32),
+    Str(String),
+}
+
+impl From<MinimumShouldMatch> for MinimumShouldMatchForSerialization {
+    fn from(minimum_should_match: MinimumShouldMatch) -> Self {
+        match minimum_should_match {
+            MinimumShouldMatch::Absolute(num) => MinimumShouldMatchForSerialization::Absolute(num),
+            MinimumShouldMatch::Percentage(percent) => {
+                MinimumShouldMatchForSerialization::Str(format!("{percent}%"))
+            }
+        }
+    }
+}
+
+impl TryFrom<MinimumShouldMatchForSerialization> for MinimumShouldMatch {
+    type Error = String;
+
+    fn try_from(
+        minimum_should_match: MinimumShouldMatchForSerialization,
+    ) -> Result<MinimumShouldMatch, String> {
+        let minimum_should_match_str = match minimum_should_match {
+            MinimumShouldMatchForSerialization::Absolute(num) => {
+                return Ok(MinimumShouldMatch::Absolute(num));
+            }
+            MinimumShouldMatchForSerialization::Str(minimum_should_match_str) => {
+                minimum_should_match_str
+            }
+        };
+        let trimmed_str = minimum_should_match_str.trim();
+        if let Some(percent_str) = trimmed_str.strip_suffix('%') {
+            let percent: i32 = percent_str.trim().parse().map_err(|_| {
+                format!("Invalid minimum_should_match percentage `{minimum_should_match_str}`.")
+            })?;
+            if !(-100..=100).contains(&percent) {
+                return Err(format!(
+                    "minimum_should_match percentage must be between -100% and 100%, got \
+                     `{minimum_should_match_str}`."
+                ));
+            }
+            return Ok(MinimumShouldMatch::Percentage(percent));
+        }
+        let num: i32 = trimmed_str.parse().map_err(|_| {
+            format!(
+                "Invalid or unsupported minimum_should_match value `{minimum_should_match_str}`."
+            )
+        })?;
+        Ok(MinimumShouldMatch::Absolute(num))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinimumShouldMatch;
+
+    #[test]
+    fn test_minimum_should_match_deserialize() {
+        let minimum_should_match: MinimumShouldMatch = serde_json::from_str("2").unwrap();
+        assert_eq!(minimum_should_match, MinimumShouldMatch::Absolute(2));
+        let minimum_should_match: MinimumShouldMatch = serde_json::from_str(r#""-1""#).unwrap();
+        assert_eq!(minimum_should_match, MinimumShouldMatch::Absolute(-1));
+        let minimum_should_match: MinimumShouldMatch = serde_json::from_str(r#""75%""#).unwrap();
+        assert_eq!(minimum_should_match, MinimumShouldMatch::Percentage(75));
+        let minimum_should_match: MinimumShouldMatch = serde_json::from_str(r#""-25%""#).unwrap();
+        assert_eq!(minimum_should_match, MinimumShouldMatch::Percentage(-25));
+        assert!(serde_json::from_str::<MinimumShouldMatch>(r#""3<90%""#).is_err());
+        assert!(serde_json::from_str::<MinimumShouldMatch>(r#""150%""#).is_err());
+    }
+
+    #[test]
+    fn test_minimum_should_match_serialize() {
+        assert_eq!(
+            serde_json::to_string(&MinimumShouldMatch::Absolute(-2)).unwrap(),
+            "-2"
+        );
+        assert_eq!(
+            serde_json::to_string(&MinimumShouldMatch::Percentage(75)).unwrap(),
+            r#""75%""#
+        );
+    }
+
+    #[test]
+    fn test_minimum_should_match_resolve() {
+        assert_eq!(MinimumShouldMatch::Absolute(2).resolve(4), 2);
+        assert_eq!(MinimumShouldMatch::Absolute(5).resolve(4), 5);
+        assert_eq!(MinimumShouldMatch::Absolute(-1).resolve(4), 3);
+        assert_eq!(MinimumShouldMatch::Absolute(-5).resolve(4), 0);
+        assert_eq!(MinimumShouldMatch::Percentage(75).resolve(3), 2);
+        assert_eq!(MinimumShouldMatch::Percentage(-25).resolve(3), 3);
+        assert_eq!(MinimumShouldMatch::Percentage(-100).resolve(3), 0);
+    }
+}
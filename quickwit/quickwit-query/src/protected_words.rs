@@ -0,0 +1,194 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Protected words are not stemmed by stemming tokenizers, so that product names and
+//! identifiers can be searched as they are written.
+//!
+//! Like character filters, a text field configured with protected words is indexed and searched
+//! with a dedicated tokenizer, registered in the Quickwit tokenizer manager under a name derived
+//! from the stemming tokenizer and the protected words.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Context;
+use tantivy::tokenizer::{Language, RawTokenizer, Stemmer, TextAnalyzer, Tokenizer};
+
+use crate::char_filters::VecTokenStream;
+use crate::get_quickwit_tokenizer_manager;
+
+/// Returns the name of the tokenizer that does not stem and the stemming language of the
+/// stemming tokenizer `tokenizer_name`, or `None` if it is not a stemming tokenizer.
+fn stemming_tokenizer_parts(tokenizer_name: &str) -> Option<(&'static str, Language)> {
+    match tokenizer_name {
+        "en_stem" => Some(("default", Language::English)),
+        _ => None,
+    }
+}
+
+/// Returns true if `tokenizer_name` is a stemming tokenizer, supporting protected words.
+pub fn is_stemming_tokenizer(tokenizer_name: &str) -> bool {
+    stemming_tokenizer_parts(tokenizer_name).is_some()
+}
+
+/// Returns the name of the tokenizer behaving as the stemming tokenizer
+/// `stemming_tokenizer_name`, except that the `protected_words` are not stemmed.
+pub fn protected_words_tokenizer_name(
+    stemming_tokenizer_name: &str,
+    protected_words: &[String],
+) -> String {
+    let protected_words_json =
+        serde_json::to_string(protected_words).expect("Protected words should be serializable.");
+    format!("{stemming_tokenizer_name}+protected_words={protected_words_json}")
+}
+
+/// Registers in the Quickwit tokenizer manager the tokenizer behaving as the stemming tokenizer
+/// `stemming_tokenizer_name`, except that the `protected_words` are not stemmed, and returns its
+/// name.
+///
+/// Protected words are matched against the lowercased tokens, so they are case insensitive.
+pub fn register_protected_words_tokenizer(
+    stemming_tokenizer_name: &str,
+    protected_words: &[String],
+) -> anyhow::Result<String> {
+    let tokenizer_name = protected_words_tokenizer_name(stemming_tokenizer_name, protected_words);
+    let tokenizer_manager = get_quickwit_tokenizer_manager();
+
+    if tokenizer_manager.get(&tokenizer_name).is_some() {
+        return Ok(tokenizer_name);
+    }
+    let (unstemmed_tokenizer_name, language) = stemming_tokenizer_parts(stemming_tokenizer_name)
+        .with_context(|| {
+            format!(
+                "Tokenizer `{stemming_tokenizer_name}` does not stem, protected words are not \
+                 supported."
+            )
+        })?;
+    let text_analyzer = tokenizer_manager
+        .get(unstemmed_tokenizer_name)
+        .with_context(|| {
+            format!("No tokenizer named `{unstemmed_tokenizer_name}` is registered.")
+        })?;
+    let stemmer = TextAnalyzer::builder(RawTokenizer)
+        .filter(Stemmer::new(language))
+        .build();
+    let protected_words = protected_words
+        .iter()
+        .map(|protected_word| protected_word.to_lowercase())
+        .collect();
+    let protected_words_tokenizer = ProtectedWordsTokenizer {
+        protected_words: Arc::new(protected_words),
+        text_analyzer,
+        stemmer,
+    };
+    tokenizer_manager.register(
+        &tokenizer_name,
+        TextAnalyzer::builder(protected_words_tokenizer).build(),
+    );
+    Ok(tokenizer_name)
+}
+
+#[derive(Clone)]
+struct ProtectedWordsTokenizer {
+    protected_words: Arc<HashSet<String>>,
+    // Tokenizes the text as the stemming tokenizer, without stemming.
+    text_analyzer: TextAnalyzer,
+    // Stems a single token.
+    stemmer: TextAnalyzer,
+}
+
+impl ProtectedWordsTokenizer {
+    fn stem(&self, word: &str) -> Option<String> {
+        let mut token_stream = self.stemmer.token_stream(word);
+        if token_stream.advance() {
+            Some(token_stream.token().text.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl Tokenizer for ProtectedWordsTokenizer {
+    type TokenStream<'a> = VecTokenStream;
+
+    fn token_stream<'a>(&self, text: &'a str) -> Self::TokenStream<'a> {
+        let mut token_stream = self.text_analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+
+        while token_stream.advance() {
+            let mut token = token_stream.token().clone();
+            if !self.protected_words.contains(&token.text) {
+                if let Some(stemmed_word) = self.stem(&token.text) {
+                    token.text = stemmed_word;
+                }
+            }
+            tokens.push(token);
+        }
+        VecTokenStream::new(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_texts(tokenizer_name: &str, text: &str) -> Vec<String> {
+        let text_analyzer = get_quickwit_tokenizer_manager()
+            .get(tokenizer_name)
+            .unwrap();
+        let mut token_stream = text_analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while token_stream.advance() {
+            tokens.push(token_stream.token().text.clone());
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_protected_words_tokenizer() {
+        let protected_words = ["Pixel".to_string(), "running".to_string()];
+        let tokenizer_name =
+            register_protected_words_tokenizer("en_stem", &protected_words).unwrap();
+        assert_eq!(
+            tokenizer_name,
+            protected_words_tokenizer_name("en_stem", &protected_words)
+        );
+        // Registering the same tokenizer twice is a no-op.
+        register_protected_words_tokenizer("en_stem", &protected_words).unwrap();
+
+        assert_eq!(
+            token_texts("en_stem", "Pixels running pixel apps"),
+            ["pixel", "run", "pixel", "app"]
+        );
+        assert_eq!(
+            token_texts(&tokenizer_name, "Pixels running pixel apps"),
+            ["pixel", "running", "pixel", "app"]
+        );
+    }
+
+    #[test]
+    fn test_register_protected_words_tokenizer_errors() {
+        assert!(is_stemming_tokenizer("en_stem"));
+        assert!(!is_stemming_tokenizer("default"));
+
+        let error =
+            register_protected_words_tokenizer("default", &["pixel".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("does not stem"));
+    }
+}
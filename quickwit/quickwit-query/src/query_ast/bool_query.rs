@@ -22,10 +22,9 @@ use tantivy::schema::Schema as TantivySchema;
 
 use super::{BuildTantivyAst, TantivyQueryAst};
 use crate::query_ast::QueryAst;
-use crate::InvalidQuery;
+use crate::{InvalidQuery, MinimumShouldMatch};
 
 /// # Unsupported features
-/// - minimum_should_match
 /// - named queries
 ///
 /// Edge cases of BooleanQuery are not obvious,
@@ -47,6 +46,11 @@ pub struct BoolQuery {
     pub should: Vec<QueryAst>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub filter: Vec<QueryAst>,
+    // Minimum number of should clauses that must match. If not set, the should clauses are
+    // optional, unless there are no must or filter clauses, in which case at least one of them
+    // has to match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_should_match: Option<MinimumShouldMatch>,
 }
 
 impl From<BoolQuery> for QueryAst {
@@ -61,27 +65,169 @@ impl BuildTantivyAst for BoolQuery {
         schema: &TantivySchema,
         search_fields: &[String],
         with_validation: bool,
+        with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let mut boolean_query = super::tantivy_query_ast::TantivyBoolQuery::default();
         for must in &self.must {
-            let must_leaf = must.build_tantivy_ast_call(schema, search_fields, with_validation)?;
+            let must_leaf =
+                must.build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring)?;
             boolean_query.must.push(must_leaf);
         }
         for must_not in &self.must_not {
-            let must_not_leaf =
-                must_not.build_tantivy_ast_call(schema, search_fields, with_validation)?;
+            let must_not_leaf = must_not.build_tantivy_ast_call_without_scoring(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            )?;
             boolean_query.must_not.push(must_not_leaf);
         }
         for should in &self.should {
-            let should_leaf =
-                should.build_tantivy_ast_call(schema, search_fields, with_validation)?;
+            let should_leaf = should.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            )?;
             boolean_query.should.push(should_leaf);
         }
         for filter in &self.filter {
-            let filter_leaf =
-                filter.build_tantivy_ast_call(schema, search_fields, with_validation)?;
+            let filter_leaf = filter.build_tantivy_ast_call_without_scoring(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            )?;
             boolean_query.filter.push(filter_leaf);
         }
+        boolean_query.minimum_should_match = self
+            .minimum_should_match
+            .map(|minimum_should_match| minimum_should_match.resolve(self.should.len()));
         Ok(TantivyQueryAst::Bool(boolean_query))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TEXT};
+    use tantivy::{doc, DocId, Index, Score};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{self, search_scored_docs};
+    use crate::query_ast::TermQuery;
+    use crate::MatchAllOrNone;
+
+    fn build_test_index() -> Index {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let docs = [
+            "apple banana",
+            "apple apple banana",
+            "apple banana cherry",
+            "apple",
+            "banana",
+        ]
+        .into_iter()
+        .map(|body| doc!(body_field => body));
+        test_helpers::build_test_index(schema_builder.build(), docs)
+    }
+
+    /// Returns the matching documents with their scores, sorted by doc ID.
+    fn search(index: &Index, query_ast: &QueryAst, with_scoring: bool) -> Vec<(DocId, Score)> {
+        let mut hits: Vec<(DocId, Score)> = search_scored_docs(index, query_ast, with_scoring)
+            .unwrap()
+            .into_iter()
+            .map(|(score, doc_id)| (doc_id, score))
+            .collect();
+        hits.sort_by_key(|(doc_id, _)| *doc_id);
+        hits
+    }
+
+    fn test_bool_query() -> QueryAst {
+        BoolQuery {
+            must: vec![TermQuery::from_field_value("body", "apple").into()],
+            must_not: vec![TermQuery::from_field_value("body", "cherry").into()],
+            filter: vec![BoolQuery {
+                filter: vec![TermQuery::from_field_value("body", "banana").into()],
+                ..Default::default()
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_bool_query_filter_context_does_not_score() {
+        let index = build_test_index();
+        let hits = search(&index, &test_bool_query(), true);
+        let hit_doc_ids: Vec<DocId> = hits.iter().map(|(doc_id, _)| *doc_id).collect();
+        assert_eq!(hit_doc_ids, [0, 1]);
+
+        // Only the `must` clause contributes to the score.
+        let must_query: QueryAst = TermQuery::from_field_value("body", "apple").into();
+        let must_hits = search(&index, &must_query, true);
+        for (doc_id, score) in hits {
+            let (_, must_score) = must_hits
+                .iter()
+                .find(|(must_doc_id, _)| *must_doc_id == doc_id)
+                .unwrap();
+            assert_eq!(score, *must_score);
+        }
+    }
+
+    #[test]
+    fn test_bool_query_without_scoring() {
+        let index = build_test_index();
+        let hits = search(&index, &test_bool_query(), false);
+        let hit_doc_ids: Vec<DocId> = hits.iter().map(|(doc_id, _)| *doc_id).collect();
+        assert_eq!(hit_doc_ids, [0, 1]);
+    }
+
+    #[test]
+    fn test_bool_query_minimum_should_match() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let build_ast = |minimum_should_match: MinimumShouldMatch| {
+            BoolQuery {
+                must: vec![TermQuery::from_field_value("body", "must").into()],
+                should: vec![
+                    TermQuery::from_field_value("body", "a").into(),
+                    TermQuery::from_field_value("body", "b").into(),
+                    TermQuery::from_field_value("body", "c").into(),
+                    TermQuery::from_field_value("body", "d").into(),
+                ],
+                minimum_should_match: Some(minimum_should_match),
+                ..Default::default()
+            }
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap()
+        };
+        let tantivy_ast = build_ast(MinimumShouldMatch::Percentage(50));
+        let tantivy_bool_query = tantivy_ast.as_bool_query().unwrap();
+        assert_eq!(tantivy_bool_query.minimum_should_match, Some(2));
+
+        let tantivy_ast = build_ast(MinimumShouldMatch::Absolute(-1));
+        let tantivy_bool_query = tantivy_ast.as_bool_query().unwrap();
+        assert_eq!(tantivy_bool_query.minimum_should_match, Some(3));
+
+        let tantivy_ast = build_ast(MinimumShouldMatch::Absolute(5)).simplify();
+        assert_eq!(
+            tantivy_ast.const_predicate(),
+            Some(MatchAllOrNone::MatchNone)
+        );
+    }
+
+    #[test]
+    fn test_bool_query_minimum_should_match_serde() {
+        let bool_query: BoolQuery = serde_json::from_str(
+            r#"{"should": [{"type": "match_all"}], "minimum_should_match": "75%"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            bool_query.minimum_should_match,
+            Some(MinimumShouldMatch::Percentage(75))
+        );
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema as TantivySchema;
+
+use super::negative_boost_query::NegativeBoostQuery;
+use super::{BuildTantivyAst, TantivyQueryAst};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::QueryAst;
+use crate::{InvalidQuery, MatchAllOrNone};
+
+/// Matches the documents matching the `positive` query. The documents also matching the
+/// `negative` query are demoted rather than excluded: their score is multiplied by
+/// `negative_boost`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct BoostingQuery {
+    pub positive: Box<QueryAst>,
+    pub negative: Box<QueryAst>,
+    pub negative_boost: NotNaNf32,
+}
+
+impl From<BoostingQuery> for QueryAst {
+    fn from(boosting_query: BoostingQuery) -> Self {
+        QueryAst::Boosting(boosting_query)
+    }
+}
+
+impl BuildTantivyAst for BoostingQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        search_fields: &[String],
+        with_validation: bool,
+        with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let positive = self
+            .positive
+            .build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring)?
+            .simplify();
+        let negative = self
+            .negative
+            .build_tantivy_ast_call(schema, search_fields, with_validation, false)?
+            .simplify();
+        // The negative query only impacts the scores.
+        if !with_scoring
+            || positive.const_predicate() == Some(MatchAllOrNone::MatchNone)
+            || negative.const_predicate() == Some(MatchAllOrNone::MatchNone)
+        {
+            return Ok(positive);
+        }
+        let negative_boost_query =
+            NegativeBoostQuery::new(positive.into(), negative.into(), self.negative_boost.into());
+        Ok(negative_boost_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TEXT};
+
+    use super::*;
+    use crate::query_ast::TermQuery;
+
+    fn build_boosting_query(negative: QueryAst, with_scoring: bool) -> TantivyQueryAst {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        BoostingQuery {
+            positive: Box::new(TermQuery::from_field_value("body", "apple").into()),
+            negative: Box::new(negative),
+            negative_boost: NotNaNf32::try_from(0.2).unwrap(),
+        }
+        .build_tantivy_ast_call(&schema, &[], true, with_scoring)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_boosting_query() {
+        let tantivy_ast =
+            build_boosting_query(TermQuery::from_field_value("body", "pie").into(), true);
+        let tantivy_query_str = format!("{:?}", tantivy_ast.as_leaf().unwrap());
+        assert!(tantivy_query_str.starts_with("NegativeBoostQuery"));
+        assert!(tantivy_query_str.contains("negative_boost: 0.2"));
+    }
+
+    #[test]
+    fn test_boosting_query_simplification() {
+        let positive_str = r#"TermQuery(Term(field=0, type=Str, "apple"))"#;
+        let tantivy_ast =
+            build_boosting_query(TermQuery::from_field_value("body", "pie").into(), false);
+        assert_eq!(
+            format!("{:?}", tantivy_ast.as_leaf().unwrap()),
+            positive_str
+        );
+
+        let tantivy_ast = build_boosting_query(QueryAst::MatchNone, true);
+        assert_eq!(
+            format!("{:?}", tantivy_ast.as_leaf().unwrap()),
+            positive_str
+        );
+    }
+}
@@ -0,0 +1,510 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers to construct a [`QueryAst`] programmatically.
+//!
+//! ```
+//! use quickwit_query::query_ast::QueryAst;
+//!
+//! let query_ast = QueryAst::term("severity", "ERROR")
+//!     .and(QueryAst::full_text("message", "connection refused"))
+//!     .and(!QueryAst::term("service", "healthcheck"))
+//!     .filter(QueryAst::range("status_code", 500u64..600));
+//! ```
+
+use std::collections::HashMap;
+use std::ops::{Bound, Not, RangeBounds};
+
+use super::{BoolQuery, FullTextMode, FullTextParams, FullTextQuery, QueryAst, RangeQuery};
+use crate::query_ast::{TermQuery, TermSetQuery};
+use crate::{BooleanOperand, JsonLiteral, MatchAllOrNone};
+
+impl QueryAst {
+    /// Matches the documents in which `field` holds exactly `value`. The value is not tokenized.
+    pub fn term(field: impl ToString, value: impl ToString) -> QueryAst {
+        TermQuery::from_field_value(field, value).into()
+    }
+
+    /// Matches the documents in which `field` holds exactly one of `values`.
+    pub fn term_set(
+        field: impl ToString,
+        values: impl IntoIterator<Item = impl ToString>,
+    ) -> QueryAst {
+        let terms = values.into_iter().map(|value| value.to_string()).collect();
+        let terms_per_field = HashMap::from([(field.to_string(), terms)]);
+        TermSetQuery { terms_per_field }.into()
+    }
+
+    /// Matches the documents in which `field` contains at least one of the tokens of `text`.
+    pub fn full_text(field: impl ToString, text: impl ToString) -> QueryAst {
+        let mode = FullTextMode::Bool {
+            operator: BooleanOperand::Or,
+            minimum_should_match: None,
+            fuzziness: None,
+        };
+        full_text_query(field, text, mode)
+    }
+
+    /// Matches the documents in which `field` contains the tokens of `phrase`, in the same order
+    /// and next to each other. The field must be indexed with positions.
+    pub fn phrase(field: impl ToString, phrase: impl ToString) -> QueryAst {
+        full_text_query(field, phrase, FullTextMode::Phrase { slop: 0 })
+    }
+
+    /// Matches the documents in which `field` holds a value within `bounds`.
+    ///
+    /// ```
+    /// use quickwit_query::query_ast::QueryAst;
+    ///
+    /// let query_ast = QueryAst::range("status_code", 400u64..=499);
+    /// let query_ast = QueryAst::range("service", "a".."n");
+    /// ```
+    pub fn range<T>(field: impl ToString, bounds: impl RangeBounds<T>) -> QueryAst
+    where T: Clone + Into<JsonLiteral> {
+        RangeQuery {
+            field: field.to_string(),
+            lower_bound: into_json_bound(bounds.start_bound()),
+            upper_bound: into_json_bound(bounds.end_bound()),
+        }
+        .into()
+    }
+
+    /// Matches the documents matching both `self` and `other`.
+    pub fn and(self, other: QueryAst) -> QueryAst {
+        let mut must = self.into_clauses(BoolQuery::is_conjunction, |bool_query| bool_query.must);
+        must.extend(other.into_clauses(BoolQuery::is_conjunction, |bool_query| bool_query.must));
+        BoolQuery {
+            must,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    /// Matches the documents matching `self`, `other`, or both.
+    pub fn or(self, other: QueryAst) -> QueryAst {
+        let mut should =
+            self.into_clauses(BoolQuery::is_disjunction, |bool_query| bool_query.should);
+        should
+            .extend(other.into_clauses(BoolQuery::is_disjunction, |bool_query| bool_query.should));
+        BoolQuery {
+            should,
+            ..Default::default()
+        }
+        .into()
+    }
+
+    /// Matches the documents matching both `self` and `filter`. Unlike [`QueryAst::and`],
+    /// `filter` does not contribute to the score of the documents.
+    pub fn filter(self, filter: QueryAst) -> QueryAst {
+        match self {
+            QueryAst::Bool(mut bool_query) if bool_query.should.is_empty() => {
+                bool_query.filter.push(filter);
+                bool_query.into()
+            }
+            query_ast => BoolQuery {
+                must: vec![query_ast],
+                filter: vec![filter],
+                ..Default::default()
+            }
+            .into(),
+        }
+    }
+
+    fn into_clauses(
+        self,
+        is_flattenable: impl Fn(&BoolQuery) -> bool,
+        clauses: impl Fn(BoolQuery) -> Vec<QueryAst>,
+    ) -> Vec<QueryAst> {
+        match self {
+            QueryAst::Bool(bool_query) if is_flattenable(&bool_query) => clauses(bool_query),
+            query_ast => vec![query_ast],
+        }
+    }
+}
+
+/// Matches the documents that do not match the query.
+impl Not for QueryAst {
+    type Output = QueryAst;
+
+    fn not(self) -> QueryAst {
+        BoolQuery {
+            must: vec![QueryAst::MatchAll],
+            must_not: vec![self],
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl BoolQuery {
+    fn is_conjunction(&self) -> bool {
+        !self.must.is_empty()
+            && self.must_not.is_empty()
+            && self.should.is_empty()
+            && self.filter.is_empty()
+    }
+
+    fn is_disjunction(&self) -> bool {
+        self.must.is_empty()
+            && self.must_not.is_empty()
+            && !self.should.is_empty()
+            && self.filter.is_empty()
+    }
+}
+
+fn full_text_query(field: impl ToString, text: impl ToString, mode: FullTextMode) -> QueryAst {
+    FullTextQuery {
+        field: field.to_string(),
+        text: text.to_string(),
+        params: FullTextParams {
+            tokenizer: None,
+            mode,
+            zero_terms_query: MatchAllOrNone::MatchNone,
+            lenient: false,
+        },
+        preferred_field: None,
+    }
+    .into()
+}
+
+fn into_json_bound<T: Clone + Into<JsonLiteral>>(bound: Bound<&T>) -> Bound<JsonLiteral> {
+    match bound {
+        Bound::Included(value) => Bound::Included(value.clone().into()),
+        Bound::Excluded(value) => Bound::Excluded(value.clone().into()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::ops::Bound;
+
+    use crate::query_ast::{
+        BoolQuery, BoostingQuery, CombinedFieldsQuery, DecayFunction, DecayFunctionType,
+        DisMaxQuery, DistanceFeatureFunction, FieldPresenceQuery, FieldValueFactorFunction,
+        FieldValueFactorModifier, FullTextMode, FullTextParams, FunctionBoostMode,
+        FunctionScoreMode, FunctionScoreQuery, IntervalsMatchRule, IntervalsQuery, IntervalsRule,
+        KnnQuery, PhrasePrefixQuery, QueryAst, RangeQuery, RegexQuery, ScoreFunction,
+        ScoreFunctionKind, SimpleQueryStringFlags, SimpleUserInputQuery, SpanNearRule, SpanQuery,
+        SpanRule, SpanTermRule, TermsLookupQuery, UdfMode, UdfQuery, UserInputQuery,
+    };
+    use crate::{BooleanOperand, JsonLiteral, MatchAllOrNone, NotNaNf32};
+
+    #[test]
+    fn test_query_ast_and_flattens_conjunctions() {
+        let query_ast = QueryAst::term("a", "1")
+            .and(QueryAst::term("b", "2"))
+            .and(QueryAst::term("c", "3"));
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![
+                QueryAst::term("a", "1"),
+                QueryAst::term("b", "2"),
+                QueryAst::term("c", "3"),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_query_ast_or_does_not_flatten_conjunctions() {
+        let query_ast = QueryAst::term("a", "1")
+            .and(QueryAst::term("b", "2"))
+            .or(QueryAst::term("c", "3"))
+            .or(QueryAst::term("d", "4"));
+        let expected_query_ast: QueryAst = BoolQuery {
+            should: vec![
+                BoolQuery {
+                    must: vec![QueryAst::term("a", "1"), QueryAst::term("b", "2")],
+                    ..Default::default()
+                }
+                .into(),
+                QueryAst::term("c", "3"),
+                QueryAst::term("d", "4"),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_query_ast_not_and_filter() {
+        let query_ast = (!QueryAst::term("a", "1")).filter(QueryAst::term("b", "2"));
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![QueryAst::MatchAll],
+            must_not: vec![QueryAst::term("a", "1")],
+            filter: vec![QueryAst::term("b", "2")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_query_ast_range_typed_bounds() {
+        let QueryAst::Range(range_query) = QueryAst::range("count", 10u64..=20) else { panic!() };
+        assert_eq!(
+            range_query.lower_bound,
+            Bound::Included(JsonLiteral::from(10u64))
+        );
+        assert_eq!(
+            range_query.upper_bound,
+            Bound::Included(JsonLiteral::from(20u64))
+        );
+
+        let QueryAst::Range(range_query) = QueryAst::range("count", ..-5i64) else { panic!() };
+        assert_eq!(range_query.lower_bound, Bound::Unbounded);
+        assert_eq!(
+            range_query.upper_bound,
+            Bound::Excluded(JsonLiteral::from(-5i64))
+        );
+
+        let QueryAst::Range(range_query) = QueryAst::range("service", "a"..) else { panic!() };
+        assert_eq!(
+            range_query.lower_bound,
+            Bound::Included(JsonLiteral::String("a".to_string()))
+        );
+        assert_eq!(range_query.upper_bound, Bound::Unbounded);
+    }
+
+    fn variant_name(query_ast: &QueryAst) -> &'static str {
+        // No wildcard here: adding a variant to `QueryAst` requires adding it to
+        // `query_ast_samples` below.
+        match query_ast {
+            QueryAst::Bool(_) => "bool",
+            QueryAst::DisMax(_) => "dis_max",
+            QueryAst::Boosting(_) => "boosting",
+            QueryAst::Term(_) => "term",
+            QueryAst::TermSet(_) => "term_set",
+            QueryAst::TermsLookup(_) => "terms_lookup",
+            QueryAst::FullText(_) => "full_text",
+            QueryAst::CombinedFields(_) => "combined_fields",
+            QueryAst::PhrasePrefix(_) => "phrase_prefix",
+            QueryAst::Intervals(_) => "intervals",
+            QueryAst::Span(_) => "span",
+            QueryAst::Knn(_) => "knn",
+            QueryAst::Regex(_) => "regex",
+            QueryAst::Range(_) => "range",
+            QueryAst::FieldPresence(_) => "field_presence",
+            QueryAst::UserInput(_) => "user_input",
+            QueryAst::SimpleUserInput(_) => "simple_user_input",
+            QueryAst::MatchAll => "match_all",
+            QueryAst::MatchNone => "match_none",
+            QueryAst::Boost { .. } => "boost",
+            QueryAst::ConstScore { .. } => "const_score",
+            QueryAst::Udf(_) => "udf",
+            QueryAst::FunctionScore(_) => "function_score",
+        }
+    }
+
+    const NUM_VARIANTS: usize = 23;
+
+    fn query_ast_samples() -> Vec<QueryAst> {
+        vec![
+            QueryAst::term("a", "1").and(!QueryAst::term("b", "2")),
+            DisMaxQuery {
+                queries: vec![QueryAst::term("a", "1"), QueryAst::term("b", "1")],
+                tie_breaker: Some(NotNaNf32::try_from(0.3).unwrap()),
+            }
+            .into(),
+            BoostingQuery {
+                positive: Box::new(QueryAst::term("a", "1")),
+                negative: Box::new(QueryAst::term("b", "1")),
+                negative_boost: NotNaNf32::try_from(0.5).unwrap(),
+            }
+            .into(),
+            QueryAst::term("a", "1"),
+            QueryAst::term_set("a", ["1", "2"]),
+            TermsLookupQuery {
+                field: "a".to_string(),
+                index_id: "lookup-index".to_string(),
+                doc_id: "doc-1".to_string(),
+                path: "values".to_string(),
+            }
+            .into(),
+            QueryAst::full_text("a", "hello world"),
+            QueryAst::phrase("a", "hello world"),
+            PhrasePrefixQuery {
+                field: "a".to_string(),
+                phrase: "hello wor".to_string(),
+                max_expansions: 50,
+                analyzer: FullTextParams {
+                    tokenizer: Some("raw".to_string()),
+                    mode: FullTextMode::Phrase { slop: 0 },
+                    zero_terms_query: MatchAllOrNone::MatchAll,
+                    lenient: false,
+                },
+            }
+            .into(),
+            IntervalsQuery {
+                field: "a".to_string(),
+                rule: IntervalsRule::Match(IntervalsMatchRule {
+                    query: "hello world".to_string(),
+                    max_gaps: 2,
+                    ordered: true,
+                    analyzer: None,
+                }),
+            }
+            .into(),
+            SpanQuery {
+                field: "a".to_string(),
+                rule: SpanRule::SpanNear(SpanNearRule {
+                    clauses: vec![
+                        SpanRule::SpanTerm(SpanTermRule {
+                            value: "hello".to_string(),
+                        }),
+                        SpanRule::SpanTerm(SpanTermRule {
+                            value: "world".to_string(),
+                        }),
+                    ],
+                    slop: 1,
+                    in_order: false,
+                }),
+            }
+            .into(),
+            CombinedFieldsQuery {
+                fields: vec!["title^2".to_string(), "body".to_string()],
+                text: "hello world".to_string(),
+                operator: BooleanOperand::And,
+                minimum_should_match: None,
+                zero_terms_query: MatchAllOrNone::MatchNone,
+            }
+            .into(),
+            KnnQuery {
+                field: "embedding".to_string(),
+                query_vector: vec![
+                    NotNaNf32::try_from(0.5).unwrap(),
+                    NotNaNf32::try_from(-1.0).unwrap(),
+                ],
+                k: 10,
+                num_candidates: 15,
+            }
+            .into(),
+            RegexQuery {
+                field: "a".to_string(),
+                regex: "/api/v[0-9]+/.*".to_string(),
+                case_insensitive: true,
+            }
+            .into(),
+            QueryAst::range("a", 1u64..10),
+            RangeQuery {
+                field: "a".to_string(),
+                lower_bound: Bound::Excluded(JsonLiteral::Bool(false)),
+                upper_bound: Bound::Unbounded,
+            }
+            .into(),
+            FieldPresenceQuery {
+                field: "a.b".to_string(),
+            }
+            .into(),
+            UserInputQuery {
+                user_text: "a:1 OR b:\"hello\"".to_string(),
+                default_fields: Some(vec!["c".to_string()]),
+                default_operator: BooleanOperand::And,
+                quote_field_suffix: Some(".exact".to_string()),
+                phrase_slop: 0,
+                lenient: false,
+                fuzziness: None,
+            }
+            .into(),
+            SimpleUserInputQuery {
+                user_text: "hello -world".to_string(),
+                default_fields: None,
+                default_operator: BooleanOperand::Or,
+                flags: SimpleQueryStringFlags::NONE,
+            }
+            .into(),
+            QueryAst::MatchAll,
+            QueryAst::MatchNone,
+            QueryAst::term("a", "1").boost(Some(NotNaNf32::try_from(2.0).unwrap())),
+            QueryAst::ConstScore {
+                underlying: Box::new(QueryAst::term("a", "1")),
+                score: NotNaNf32::try_from(0.5).unwrap(),
+            },
+            UdfQuery {
+                underlying: Box::new(QueryAst::MatchAll),
+                udf: "a * 2".to_string(),
+                fields: vec!["a".to_string()],
+                mode: UdfMode::Score,
+            }
+            .into(),
+            FunctionScoreQuery {
+                underlying: Box::new(QueryAst::full_text("a", "hello")),
+                functions: vec![
+                    ScoreFunction {
+                        kind: ScoreFunctionKind::FieldValueFactor(FieldValueFactorFunction {
+                            field: "b".to_string(),
+                            factor: NotNaNf32::try_from(1.5).unwrap(),
+                            modifier: FieldValueFactorModifier::Log1p,
+                            missing: Some(NotNaNf32::ONE),
+                        }),
+                        filter: Some(Box::new(QueryAst::term("d", "1"))),
+                        weight: Some(NotNaNf32::try_from(2.0).unwrap()),
+                    },
+                    ScoreFunction {
+                        kind: ScoreFunctionKind::Decay(DecayFunction {
+                            decay_type: DecayFunctionType::Gauss,
+                            field: "c".to_string(),
+                            origin: Some(JsonLiteral::from("now")),
+                            scale: JsonLiteral::from("7d"),
+                            offset: Some(JsonLiteral::from("1d")),
+                            decay: NotNaNf32::try_from(0.3).unwrap(),
+                        }),
+                        filter: None,
+                        weight: None,
+                    },
+                    ScoreFunction {
+                        kind: ScoreFunctionKind::DistanceFeature(DistanceFeatureFunction {
+                            field: "c".to_string(),
+                            origin: JsonLiteral::from(10u64),
+                            pivot: JsonLiteral::from(5u64),
+                        }),
+                        filter: None,
+                        weight: None,
+                    },
+                    ScoreFunction {
+                        kind: ScoreFunctionKind::Constant,
+                        filter: None,
+                        weight: Some(NotNaNf32::try_from(0.5).unwrap()),
+                    },
+                ],
+                score_mode: FunctionScoreMode::Sum,
+                boost_mode: FunctionBoostMode::Replace,
+                now_timestamp_micros: Some(1_700_000_000_000_000),
+            }
+            .into(),
+        ]
+    }
+
+    #[test]
+    fn test_query_ast_serde_round_trip() {
+        let query_ast_samples = query_ast_samples();
+        let covered_variants: HashSet<&str> = query_ast_samples.iter().map(variant_name).collect();
+        assert_eq!(covered_variants.len(), NUM_VARIANTS);
+
+        for query_ast in query_ast_samples {
+            let query_ast_json = serde_json::to_string(&query_ast).unwrap();
+            let deserialized_query_ast: QueryAst = serde_json::from_str(&query_ast_json).unwrap();
+            assert_eq!(deserialized_query_ast, query_ast, "{query_ast_json}");
+        }
+    }
+}
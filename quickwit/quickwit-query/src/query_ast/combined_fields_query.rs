@@ -0,0 +1,613 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::fieldnorm::FieldNormReader;
+use tantivy::postings::{Postings, SegmentPostings};
+use tantivy::query::{EnableScoring, Explanation, Scorer, Weight};
+use tantivy::schema::{FieldType, IndexRecordOption, Schema as TantivySchema};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+
+use crate::query_ast::minimum_should_match_query::MinimumShouldMatchQuery;
+use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
+use crate::query_ast::{BuildTantivyAst, FullTextParams, QueryAst};
+use crate::{
+    find_field_or_hit_dynamic, parse_search_field, BooleanOperand, InvalidQuery, MatchAllOrNone,
+    MinimumShouldMatch, TantivyQuery,
+};
+
+// BM25 parameters, identical to the ones of tantivy.
+const K1: Score = 1.2;
+const B: Score = 0.75;
+
+/// Matches the terms of `text` in several text fields, scoring the documents as if the fields had
+/// been indexed into a single combined field (BM25F).
+///
+/// For each term, the frequencies and the lengths of the fields are summed, multiplied by the
+/// weights of the fields, before applying the BM25 formula. Contrary to a `bool` query over the
+/// fields, a term matching several fields is not rewarded several times, and its rarity is
+/// assessed over all the fields.
+///
+/// Fields may carry a weight with the `field^weight` syntax, which must be at least 1. The text is
+/// tokenized once, so all the fields must share the same tokenizer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CombinedFieldsQuery {
+    pub fields: Vec<String>,
+    pub text: String,
+    #[serde(default)]
+    pub operator: BooleanOperand,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_should_match: Option<MinimumShouldMatch>,
+    #[serde(default, skip_serializing_if = "MatchAllOrNone::is_none")]
+    pub zero_terms_query: MatchAllOrNone,
+}
+
+impl From<CombinedFieldsQuery> for QueryAst {
+    fn from(combined_fields_query: CombinedFieldsQuery) -> Self {
+        QueryAst::CombinedFields(combined_fields_query)
+    }
+}
+
+impl CombinedFieldsQuery {
+    /// Returns the names of the targeted fields, without their weights.
+    pub fn field_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.fields.iter().map(|field_spec| {
+            parse_search_field(field_spec)
+                .map(|(field_name, _)| field_name)
+                .unwrap_or(field_spec)
+        })
+    }
+
+    fn parse_fields(&self) -> Result<Vec<(&str, Score)>, InvalidQuery> {
+        if self.fields.is_empty() {
+            return Err(InvalidQuery::Other(anyhow::anyhow!(
+                "A combined_fields query requires at least one field."
+            )));
+        }
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for field_spec in &self.fields {
+            let (field_name, weight_opt) = parse_search_field(field_spec)?;
+            let weight = weight_opt.unwrap_or(1.0);
+            if weight < 1.0 {
+                return Err(InvalidQuery::Other(anyhow::anyhow!(
+                    "The weight of field `{field_name}` in a combined_fields query must be \
+                     greater than or equal to 1, got `{weight}`."
+                )));
+            }
+            fields.push((field_name, weight));
+        }
+        Ok(fields)
+    }
+}
+
+impl BuildTantivyAst for CombinedFieldsQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let full_text_params = FullTextParams {
+            tokenizer: None,
+            mode: self.operator.into(),
+            zero_terms_query: self.zero_terms_query,
+            lenient: false,
+        };
+        // The first field and its tokenizer, which all the other fields must share.
+        let mut first_field_tokenizer_opt: Option<(String, String)> = None;
+        let mut weighted_field_terms: Vec<(Vec<Term>, Score)> = Vec::new();
+        for (field_path, weight) in self.parse_fields()? {
+            let (field, field_entry, json_path) = find_field_or_hit_dynamic(field_path, schema)?;
+            let not_text_error = || {
+                InvalidQuery::SchemaError(format!(
+                    "Field `{}` targeted by a combined_fields query is not a full-text searchable \
+                     text field.",
+                    field_entry.name()
+                ))
+            };
+            let (tokenizer, terms) = match field_entry.field_type() {
+                FieldType::Str(text_options) => {
+                    let text_field_indexing = text_options
+                        .get_indexing_options()
+                        .ok_or_else(not_text_error)?;
+                    let terms = full_text_params.tokenize_text_into_terms(
+                        field,
+                        &self.text,
+                        text_field_indexing,
+                    )?;
+                    (text_field_indexing.tokenizer(), terms)
+                }
+                FieldType::JsonObject(json_options) => {
+                    let text_field_indexing = json_options
+                        .get_text_indexing_options()
+                        .ok_or_else(not_text_error)?;
+                    let terms = full_text_params.tokenize_text_into_terms_json(
+                        field,
+                        json_path,
+                        &self.text,
+                        json_options,
+                    )?;
+                    (text_field_indexing.tokenizer(), terms)
+                }
+                _ => return Err(not_text_error()),
+            };
+            match &first_field_tokenizer_opt {
+                Some((first_field_name, first_tokenizer)) if first_tokenizer != tokenizer => {
+                    return Err(InvalidQuery::SchemaError(format!(
+                        "All the fields of a combined_fields query must share the same \
+                         tokenizer, but field `{first_field_name}` uses `{first_tokenizer}` and \
+                         field `{}` uses `{tokenizer}`.",
+                        field_entry.name()
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    first_field_tokenizer_opt =
+                        Some((field_entry.name().to_string(), tokenizer.to_string()));
+                }
+            }
+            let terms = terms.into_iter().map(|(_, term)| term).collect();
+            weighted_field_terms.push((terms, weight));
+        }
+        let num_terms = weighted_field_terms[0].0.len();
+        if num_terms == 0 {
+            return Ok(self.zero_terms_query.into());
+        }
+        let term_queries: Vec<CombinedFieldsTermQuery> = (0..num_terms)
+            .map(|term_ord| CombinedFieldsTermQuery {
+                weighted_terms: weighted_field_terms
+                    .iter()
+                    .map(|(terms, weight)| (terms[term_ord].clone(), *weight))
+                    .collect(),
+            })
+            .collect();
+        if let (BooleanOperand::Or, Some(minimum_should_match)) =
+            (self.operator, self.minimum_should_match)
+        {
+            let minimum_should_match = minimum_should_match.resolve(num_terms);
+            if minimum_should_match > num_terms {
+                return Ok(MatchAllOrNone::MatchNone.into());
+            }
+            if minimum_should_match > 1 && minimum_should_match < num_terms {
+                let term_queries: Vec<Box<dyn TantivyQuery>> = term_queries
+                    .into_iter()
+                    .map(|term_query| Box::new(term_query) as Box<dyn TantivyQuery>)
+                    .collect();
+                return Ok(MinimumShouldMatchQuery::new(term_queries, minimum_should_match).into());
+            }
+            if minimum_should_match == num_terms {
+                let term_queries: Vec<TantivyQueryAst> =
+                    term_queries.into_iter().map(Into::into).collect();
+                return Ok(
+                    TantivyBoolQuery::build_clause(BooleanOperand::And, term_queries).into(),
+                );
+            }
+        }
+        let term_queries: Vec<TantivyQueryAst> = term_queries.into_iter().map(Into::into).collect();
+        Ok(TantivyBoolQuery::build_clause(self.operator, term_queries).into())
+    }
+}
+
+/// Matches the documents containing a term in any of the fields, scored with BM25F.
+///
+/// Each term is the same token in a different field, along with the weight of the field.
+#[derive(Clone, Debug)]
+struct CombinedFieldsTermQuery {
+    weighted_terms: Vec<(Term, Score)>,
+}
+
+impl TantivyQuery for CombinedFieldsTermQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let bm25f_params_opt = match enable_scoring {
+            EnableScoring::Enabled {
+                statistics_provider,
+                ..
+            } => {
+                let num_docs = statistics_provider.total_num_docs()?;
+                // The documents containing the term in any field cannot be counted from the
+                // statistics, so the document frequency is approximated by its maximum over the
+                // fields.
+                let mut doc_freq = 0u64;
+                let mut average_fieldnorm = 0.0;
+                for (term, weight) in &self.weighted_terms {
+                    doc_freq = doc_freq.max(statistics_provider.doc_freq(term)?);
+                    if num_docs > 0 {
+                        let num_tokens = statistics_provider.total_num_tokens(term.field())?;
+                        average_fieldnorm += weight * num_tokens as Score / num_docs as Score;
+                    }
+                }
+                Some(Bm25FParams {
+                    idf: idf(doc_freq, num_docs),
+                    average_fieldnorm: if average_fieldnorm > 0.0 {
+                        average_fieldnorm
+                    } else {
+                        1.0
+                    },
+                })
+            }
+            EnableScoring::Disabled { .. } => None,
+        };
+        Ok(Box::new(CombinedFieldsTermWeight {
+            weighted_terms: self.weighted_terms.clone(),
+            bm25f_params_opt,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for (term, _) in &self.weighted_terms {
+            visitor(term, false);
+        }
+    }
+}
+
+fn idf(doc_freq: u64, num_docs: u64) -> Score {
+    let doc_freq = doc_freq.min(num_docs) as Score;
+    let num_docs = num_docs as Score;
+    (1.0 + (num_docs - doc_freq + 0.5) / (doc_freq + 0.5)).ln()
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bm25FParams {
+    idf: Score,
+    /// Average length of the combined field, i.e. the weighted sum of the average lengths of
+    /// the fields.
+    average_fieldnorm: Score,
+}
+
+struct CombinedFieldsTermWeight {
+    weighted_terms: Vec<(Term, Score)>,
+    /// `None` if scoring is disabled.
+    bm25f_params_opt: Option<Bm25FParams>,
+}
+
+impl Weight for CombinedFieldsTermWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let record_option = if self.bm25f_params_opt.is_some() {
+            IndexRecordOption::WithFreqs
+        } else {
+            IndexRecordOption::Basic
+        };
+        let mut weighted_postings = Vec::with_capacity(self.weighted_terms.len());
+        let mut weighted_fieldnorm_readers = Vec::with_capacity(self.weighted_terms.len());
+        for (term, weight) in &self.weighted_terms {
+            if let Some(postings) = reader
+                .inverted_index(term.field())?
+                .read_postings(term, record_option)?
+            {
+                weighted_postings.push((postings, *weight));
+            }
+            if self.bm25f_params_opt.is_some() {
+                // The length of the field counts even if the term is absent from it.
+                let fieldnorm_reader = reader
+                    .fieldnorms_readers()
+                    .get_field(term.field())?
+                    .unwrap_or_else(|| FieldNormReader::constant(reader.max_doc(), 1));
+                weighted_fieldnorm_readers.push((fieldnorm_reader, *weight));
+            }
+        }
+        let mut scorer = CombinedFieldsTermScorer {
+            weighted_postings,
+            weighted_fieldnorm_readers,
+            doc: 0,
+            boost,
+            bm25f_params_opt: self.bm25f_params_opt,
+        };
+        scorer.doc = scorer.min_doc();
+        Ok(Box::new(scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("CombinedFieldsQuery", scorer.score()))
+    }
+}
+
+/// Union of the postings of the term in each field.
+struct CombinedFieldsTermScorer {
+    weighted_postings: Vec<(SegmentPostings, Score)>,
+    /// Empty if scoring is disabled.
+    weighted_fieldnorm_readers: Vec<(FieldNormReader, Score)>,
+    doc: DocId,
+    boost: Score,
+    bm25f_params_opt: Option<Bm25FParams>,
+}
+
+impl CombinedFieldsTermScorer {
+    fn min_doc(&self) -> DocId {
+        self.weighted_postings
+            .iter()
+            .map(|(postings, _)| postings.doc())
+            .min()
+            .unwrap_or(TERMINATED)
+    }
+}
+
+impl DocSet for CombinedFieldsTermScorer {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        for (postings, _) in &mut self.weighted_postings {
+            if postings.doc() == self.doc {
+                postings.advance();
+            }
+        }
+        self.doc = self.min_doc();
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        for (postings, _) in &mut self.weighted_postings {
+            if postings.doc() < target {
+                postings.seek(target);
+            }
+        }
+        self.doc = self.min_doc();
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.weighted_postings
+            .iter()
+            .map(|(postings, _)| postings.size_hint())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Scorer for CombinedFieldsTermScorer {
+    fn score(&mut self) -> Score {
+        let Some(bm25f_params) = self.bm25f_params_opt else {
+            return self.boost;
+        };
+        let mut term_freq = 0.0;
+        let mut fieldnorm = 0.0;
+        for (postings, weight) in &self.weighted_postings {
+            if postings.doc() == self.doc {
+                term_freq += weight * postings.term_freq() as Score;
+            }
+        }
+        for (fieldnorm_reader, weight) in &self.weighted_fieldnorm_readers {
+            fieldnorm += weight * fieldnorm_reader.fieldnorm(self.doc) as Score;
+        }
+        let norm = K1 * (1.0 - B + B * fieldnorm / bm25f_params.average_fieldnorm);
+        self.boost * bm25f_params.idf * (K1 + 1.0) * term_freq / (term_freq + norm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{DocSetCollector, TopDocs};
+    use tantivy::query::TermQuery as TantivyTermQuery;
+    use tantivy::schema::{FAST, STRING, TEXT};
+    use tantivy::{doc, DocAddress, Index};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{self, search_docs};
+
+    fn test_schema() -> TantivySchema {
+        let mut schema_builder = TantivySchema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_text_field("tag", STRING);
+        schema_builder.add_u64_field("count", FAST);
+        schema_builder.build()
+    }
+
+    fn build_test_index() -> Index {
+        let schema = test_schema();
+        let title_field = schema.get_field("title").unwrap();
+        let body_field = schema.get_field("body").unwrap();
+        let docs = [
+            ("connection timeout", "the remote host did not answer"),
+            (
+                "disk full",
+                "no space left, timeout while flushing the connection",
+            ),
+            ("startup", "service started on port 7280"),
+            ("shutdown", "service stopped after a timeout"),
+        ]
+        .into_iter()
+        .map(|(title, body)| doc!(title_field => title, body_field => body));
+        test_helpers::build_test_index(schema, docs)
+    }
+
+    fn combined_fields_query(fields: &[&str], text: &str) -> CombinedFieldsQuery {
+        CombinedFieldsQuery {
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            text: text.to_string(),
+            operator: BooleanOperand::Or,
+            minimum_should_match: None,
+            zero_terms_query: MatchAllOrNone::MatchNone,
+        }
+    }
+
+    fn search_scored_docs(index: &Index, query: &dyn TantivyQuery) -> Vec<(Score, u32)> {
+        let searcher = index.reader().unwrap().searcher();
+        let top_docs = searcher.search(query, &TopDocs::with_limit(10)).unwrap();
+        top_docs
+            .into_iter()
+            .map(|(score, DocAddress { doc_id, .. })| (score, doc_id))
+            .collect()
+    }
+
+    fn search_combined_fields(
+        index: &Index,
+        combined_fields_query: CombinedFieldsQuery,
+    ) -> Vec<u32> {
+        search_docs(index, &QueryAst::from(combined_fields_query))
+    }
+
+    #[test]
+    fn test_combined_fields_query_single_field_scores_like_bm25() {
+        let index = build_test_index();
+        let schema = index.schema();
+        let query = QueryAst::from(combined_fields_query(&["body"], "timeout"))
+            .build_tantivy_query(&schema, &[], true, true)
+            .unwrap();
+        let term = Term::from_field_text(schema.get_field("body").unwrap(), "timeout");
+        let bm25_query = TantivyTermQuery::new(term, IndexRecordOption::WithFreqs);
+        let scored_docs = search_scored_docs(&index, &*query);
+        let bm25_scored_docs = search_scored_docs(&index, &bm25_query);
+        assert_eq!(scored_docs.len(), 2);
+        assert_eq!(scored_docs.len(), bm25_scored_docs.len());
+        for ((score, doc), (bm25_score, bm25_doc)) in scored_docs.iter().zip(&bm25_scored_docs) {
+            assert_eq!(doc, bm25_doc);
+            assert!((score - bm25_score).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_combined_fields_query_matches_any_field() {
+        let index = build_test_index();
+        assert_eq!(
+            search_combined_fields(&index, combined_fields_query(&["title", "body"], "timeout")),
+            vec![0, 1, 3]
+        );
+        assert_eq!(
+            search_combined_fields(
+                &index,
+                combined_fields_query(&["title", "body"], "connection timeout")
+            ),
+            vec![0, 1, 3]
+        );
+        let mut and_query = combined_fields_query(&["title", "body"], "connection timeout");
+        and_query.operator = BooleanOperand::And;
+        // The terms may match different fields.
+        assert_eq!(search_combined_fields(&index, and_query), vec![0, 1]);
+        let mut minimum_should_match_query =
+            combined_fields_query(&["title", "body"], "connection timeout service");
+        minimum_should_match_query.minimum_should_match = Some(MinimumShouldMatch::Absolute(2));
+        assert_eq!(
+            search_combined_fields(&index, minimum_should_match_query),
+            vec![0, 1, 3]
+        );
+        minimum_should_match_query =
+            combined_fields_query(&["title", "body"], "connection timeout service");
+        minimum_should_match_query.minimum_should_match = Some(MinimumShouldMatch::Absolute(4));
+        assert!(search_combined_fields(&index, minimum_should_match_query).is_empty());
+    }
+
+    #[test]
+    fn test_combined_fields_query_field_weights() {
+        let index = build_test_index();
+        let query = QueryAst::from(combined_fields_query(&["title^5", "body"], "timeout"))
+            .build_tantivy_query(&index.schema(), &[], true, true)
+            .unwrap();
+        let scored_docs = search_scored_docs(&index, &*query);
+        // The term is in the title of the first document only.
+        assert_eq!(scored_docs[0].1, 0);
+        let query = QueryAst::from(combined_fields_query(&["title", "body"], "timeout"))
+            .build_tantivy_query(&index.schema(), &[], true, false)
+            .unwrap();
+        // The doc set collector does not need scores, so scoring is disabled.
+        let searcher = index.reader().unwrap().searcher();
+        let mut docs: Vec<u32> = searcher
+            .search(&*query, &DocSetCollector)
+            .unwrap()
+            .into_iter()
+            .map(|DocAddress { doc_id, .. }| doc_id)
+            .collect();
+        docs.sort();
+        assert_eq!(docs, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_combined_fields_query_zero_terms() {
+        let schema = test_schema();
+        let match_none_ast = combined_fields_query(&["title", "body"], "")
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap();
+        assert_eq!(
+            match_none_ast.const_predicate(),
+            Some(MatchAllOrNone::MatchNone)
+        );
+        let mut match_all_query = combined_fields_query(&["title", "body"], "");
+        match_all_query.zero_terms_query = MatchAllOrNone::MatchAll;
+        let match_all_ast = match_all_query
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap();
+        assert_eq!(
+            match_all_ast.const_predicate(),
+            Some(MatchAllOrNone::MatchAll)
+        );
+    }
+
+    #[test]
+    fn test_combined_fields_query_invalid() {
+        let schema = test_schema();
+        let build_error = |fields: &[&str]| {
+            combined_fields_query(fields, "timeout")
+                .build_tantivy_ast_call(&schema, &[], true, true)
+                .unwrap_err()
+        };
+        assert!(matches!(
+            build_error(&[]),
+            InvalidQuery::Other(error) if error.to_string().contains("requires at least one field")
+        ));
+        assert!(matches!(
+            build_error(&["title", "count"]),
+            InvalidQuery::SchemaError(message) if message.contains("Field `count`")
+        ));
+        assert!(matches!(
+            build_error(&["title", "tag"]),
+            InvalidQuery::SchemaError(message) if message.contains("must share the same tokenizer")
+        ));
+        assert!(matches!(
+            build_error(&["title^0.5", "body"]),
+            InvalidQuery::Other(error) if error.to_string().contains("greater than or equal to 1")
+        ));
+        assert!(matches!(
+            build_error(&["title^foo"]),
+            InvalidQuery::Other(error) if error.to_string().contains("Invalid boost")
+        ));
+    }
+
+    #[test]
+    fn test_combined_fields_query_serde() {
+        let combined_fields_query: CombinedFieldsQuery = serde_json::from_str(
+            r#"{"fields": ["title^2", "body"], "text": "connection timeout", "operator": "And"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            combined_fields_query.field_names().collect::<Vec<_>>(),
+            vec!["title", "body"]
+        );
+        assert_eq!(combined_fields_query.operator, BooleanOperand::And);
+        assert_eq!(
+            combined_fields_query.zero_terms_query,
+            MatchAllOrNone::MatchNone
+        );
+        serde_json::from_str::<CombinedFieldsQuery>(
+            r#"{"fields": ["title"], "text": "timeout", "boost": 2}"#,
+        )
+        .unwrap_err();
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::DisjunctionMaxQuery as TantivyDisjunctionMaxQuery;
+use tantivy::schema::Schema as TantivySchema;
+
+use super::{BuildTantivyAst, TantivyQueryAst};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::QueryAst;
+use crate::{InvalidQuery, MatchAllOrNone, TantivyQuery};
+
+/// Matches the documents matching any of the `queries`.
+///
+/// The score of a document is the score of its best matching query, plus `tie_breaker` times
+/// the scores of the other matching queries. A `tie_breaker` of 0 (the default) only retains the
+/// best score, while a `tie_breaker` of 1 sums the scores like a bool query with should clauses.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct DisMaxQuery {
+    pub queries: Vec<QueryAst>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tie_breaker: Option<NotNaNf32>,
+}
+
+impl From<DisMaxQuery> for QueryAst {
+    fn from(dis_max_query: DisMaxQuery) -> Self {
+        QueryAst::DisMax(dis_max_query)
+    }
+}
+
+impl BuildTantivyAst for DisMaxQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        search_fields: &[String],
+        with_validation: bool,
+        with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let mut disjuncts: Vec<TantivyQueryAst> = Vec::with_capacity(self.queries.len());
+        for query in &self.queries {
+            let disjunct = query
+                .build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring)?
+                .simplify();
+            if disjunct.const_predicate() == Some(MatchAllOrNone::MatchNone) {
+                continue;
+            }
+            disjuncts.push(disjunct);
+        }
+        if disjuncts.len() <= 1 {
+            return Ok(disjuncts.pop().unwrap_or_else(TantivyQueryAst::match_none));
+        }
+        let disjuncts: Vec<Box<dyn TantivyQuery>> = disjuncts
+            .into_iter()
+            .map(|disjunct| disjunct.into())
+            .collect();
+        let dis_max_query = TantivyDisjunctionMaxQuery::with_tie_breaker(
+            disjuncts,
+            self.tie_breaker.unwrap_or(NotNaNf32::ZERO).into(),
+        );
+        Ok(dis_max_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TEXT};
+
+    use super::*;
+    use crate::query_ast::TermQuery;
+
+    fn build_schema() -> TantivySchema {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.build()
+    }
+
+    #[test]
+    fn test_dis_max_query() {
+        let dis_max_query = DisMaxQuery {
+            queries: vec![
+                TermQuery::from_field_value("title", "quick").into(),
+                TermQuery::from_field_value("body", "quick").into(),
+            ],
+            tie_breaker: Some(NotNaNf32::try_from(0.3).unwrap()),
+        };
+        let tantivy_ast = dis_max_query
+            .build_tantivy_ast_call(&build_schema(), &[], true, true)
+            .unwrap();
+        let tantivy_query_str = format!("{:?}", tantivy_ast.as_leaf().unwrap());
+        assert!(tantivy_query_str.starts_with("DisjunctionMaxQuery"));
+        assert!(tantivy_query_str.contains("tie_breaker: 0.3"));
+    }
+
+    #[test]
+    fn test_dis_max_query_simplification() {
+        let dis_max_query = DisMaxQuery {
+            queries: vec![
+                QueryAst::MatchNone,
+                TermQuery::from_field_value("body", "quick").into(),
+            ],
+            tie_breaker: None,
+        };
+        let tantivy_ast = dis_max_query
+            .build_tantivy_ast_call(&build_schema(), &[], true, true)
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", tantivy_ast.as_leaf().unwrap()),
+            r#"TermQuery(Term(field=1, type=Str, "quick"))"#
+        );
+
+        let dis_max_query = DisMaxQuery {
+            queries: Vec::new(),
+            tie_breaker: None,
+        };
+        let tantivy_ast = dis_max_query
+            .build_tantivy_ast_call(&build_schema(), &[], true, true)
+            .unwrap();
+        assert_eq!(
+            tantivy_ast.const_predicate(),
+            Some(MatchAllOrNone::MatchNone)
+        );
+    }
+
+    #[test]
+    fn test_dis_max_query_serde() {
+        let dis_max_query: DisMaxQuery =
+            serde_json::from_str(r#"{"queries": [{"type": "match_all"}]}"#).unwrap();
+        assert_eq!(dis_max_query.queries, vec![QueryAst::MatchAll]);
+        assert_eq!(dis_max_query.tie_breaker, None);
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::hash::Hasher;
+use std::ops::Bound;
+
+use serde::{Deserialize, Serialize};
+use siphasher::sip::SipHasher;
+use tantivy::query::{FastFieldRangeWeight as TantivyFastFieldRangeQuery, TermQuery};
+use tantivy::schema::{FieldType, IndexRecordOption, Schema as TantivySchema, Type};
+use tantivy::Term;
+
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::utils::DYNAMIC_FIELD_NAME;
+use crate::query_ast::{BuildTantivyAst, QueryAst};
+use crate::InvalidQuery;
+
+/// Field name reserved for indexing the paths of the fields present in each document.
+pub const FIELD_PRESENCE_FIELD_NAME: &str = "_field_presence";
+
+/// Returns the value indexed in the field presence field for the field path `field_path`.
+///
+/// The hash must remain stable across versions: it is stored in the splits.
+pub fn compute_field_presence_hash(field_path: &str) -> u64 {
+    let mut hasher = SipHasher::new();
+    hasher.write(field_path.as_bytes());
+    hasher.finish()
+}
+
+/// Matches the documents that contain at least one non-null value for `field`. If `field`
+/// designates an object, the documents that contain at least one value in any of its subfields
+/// match.
+///
+/// The query relies on the field presence field, populated at indexing time when field presence
+/// indexing is enabled in the doc mapping. Without it, the query is only supported on numerical
+/// fast fields.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldPresenceQuery {
+    pub field: String,
+}
+
+impl From<FieldPresenceQuery> for QueryAst {
+    fn from(field_presence_query: FieldPresenceQuery) -> Self {
+        QueryAst::FieldPresence(field_presence_query)
+    }
+}
+
+/// Returns whether `full_path` designates a field, a JSON path, or an object of the schema.
+///
+/// The paths that do not fall under a declared field or object are looked up in the dynamic
+/// field, if any.
+fn is_field_in_schema(full_path: &str, schema: &TantivySchema) -> bool {
+    if let Some((field, json_path)) = schema.find_field(full_path) {
+        // Only JSON fields have subpaths.
+        return json_path.is_empty()
+            || schema.get_field_entry(field).field_type().value_type() == Type::Json;
+    }
+    // The subfields of an object are mapped to the fields named `{full_path}.{subfield}`.
+    let is_object = schema.fields().any(|(_field, field_entry)| {
+        field_entry
+            .name()
+            .strip_prefix(full_path)
+            .map(|suffix| suffix.starts_with('.'))
+            .unwrap_or(false)
+    });
+    is_object || schema.get_field(DYNAMIC_FIELD_NAME).is_ok()
+}
+
+impl BuildTantivyAst for FieldPresenceQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        if !is_field_in_schema(&self.field, schema) {
+            return Err(InvalidQuery::FieldDoesNotExist {
+                full_path: self.field.clone(),
+            });
+        }
+        if let Ok(field_presence_field) = schema.get_field(FIELD_PRESENCE_FIELD_NAME) {
+            let field_presence_hash = compute_field_presence_hash(&self.field);
+            let term = Term::from_field_u64(field_presence_field, field_presence_hash);
+            return Ok(TermQuery::new(term, IndexRecordOption::Basic).into());
+        }
+        // Without field presence indexing, the columns of numerical fast fields hold exactly the
+        // documents that have a value.
+        let fast_field_entry_opt = schema
+            .get_field(&self.field)
+            .ok()
+            .map(|field| schema.get_field_entry(field))
+            .filter(|field_entry| field_entry.is_fast());
+
+        if let Some(field_entry) = fast_field_entry_opt {
+            let field_name = field_entry.name().to_string();
+            match field_entry.field_type() {
+                FieldType::U64(_) => {
+                    return Ok(TantivyFastFieldRangeQuery::new::<u64>(
+                        field_name,
+                        Bound::Unbounded,
+                        Bound::Unbounded,
+                    )
+                    .into());
+                }
+                FieldType::I64(_) => {
+                    return Ok(TantivyFastFieldRangeQuery::new::<i64>(
+                        field_name,
+                        Bound::Unbounded,
+                        Bound::Unbounded,
+                    )
+                    .into());
+                }
+                FieldType::F64(_) => {
+                    return Ok(TantivyFastFieldRangeQuery::new::<f64>(
+                        field_name,
+                        Bound::Unbounded,
+                        Bound::Unbounded,
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+        }
+        Err(InvalidQuery::SchemaError(format!(
+            "Exists queries on field `{}` require field presence indexing to be enabled \
+             (`index_field_presence: true` in the doc mapping), unless the field is a numerical \
+             fast field.",
+            self.field
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, FAST, INDEXED, STRING};
+    use tantivy::{doc, Document, Index};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{build_test_index, try_search_docs};
+
+    fn search_docs(index: &Index, field: &str) -> Result<Vec<u32>, InvalidQuery> {
+        let query_ast = QueryAst::from(FieldPresenceQuery {
+            field: field.to_string(),
+        });
+        try_search_docs(index, &query_ast)
+    }
+
+    #[test]
+    fn test_field_presence_query_with_field_presence_field() {
+        let mut schema_builder = Schema::builder();
+        let user_id_field = schema_builder.add_text_field("user.id", STRING);
+        schema_builder.add_json_field(DYNAMIC_FIELD_NAME, STRING);
+        let field_presence_field = schema_builder.add_u64_field(FIELD_PRESENCE_FIELD_NAME, INDEXED);
+        let docs = [
+            &["user", "user.id"][..],
+            &["http", "http.method"][..],
+            &["user", "user.id", "http", "http.status"][..],
+        ]
+        .into_iter()
+        .map(|field_paths| {
+            let mut doc = Document::default();
+            if field_paths.contains(&"user.id") {
+                doc.add_text(user_id_field, "42");
+            }
+            for field_path in field_paths {
+                doc.add_u64(
+                    field_presence_field,
+                    compute_field_presence_hash(field_path),
+                );
+            }
+            doc
+        });
+        let index = build_test_index(schema_builder.build(), docs);
+
+        assert_eq!(search_docs(&index, "user.id").unwrap(), [0, 2]);
+        assert_eq!(search_docs(&index, "user").unwrap(), [0, 2]);
+        assert_eq!(search_docs(&index, "http").unwrap(), [1, 2]);
+        assert_eq!(search_docs(&index, "http.status").unwrap(), [2]);
+        assert!(search_docs(&index, "http.path").unwrap().is_empty());
+        // `user.id` is not an object, so the dynamic field cannot hold its subpaths.
+        let error = search_docs(&index, "user.id.value").unwrap_err();
+        assert!(matches!(error, InvalidQuery::FieldDoesNotExist { .. }));
+    }
+
+    #[test]
+    fn test_field_presence_query_on_fast_field() {
+        let mut schema_builder = Schema::builder();
+        let status_code_field = schema_builder.add_u64_field("status_code", FAST);
+        let body_field = schema_builder.add_text_field("body", STRING);
+        let docs = [
+            doc!(status_code_field => 200u64, body_field => "OK"),
+            doc!(body_field => "Connection refused"),
+            doc!(status_code_field => 404u64),
+        ];
+        let index = build_test_index(schema_builder.build(), docs);
+
+        assert_eq!(search_docs(&index, "status_code").unwrap(), [0, 2]);
+
+        let error = search_docs(&index, "body").unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+
+        let error = search_docs(&index, "missing").unwrap_err();
+        assert!(matches!(error, InvalidQuery::FieldDoesNotExist { .. }));
+    }
+}
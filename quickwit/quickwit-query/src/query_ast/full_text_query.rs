@@ -20,20 +20,26 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use tantivy::json_utils::JsonTermWriter;
-use tantivy::query::{PhraseQuery as TantivyPhraseQuery, TermQuery as TantivyTermQuery};
+use tantivy::query::{
+    FuzzyTermQuery as TantivyFuzzyTermQuery, PhraseQuery as TantivyPhraseQuery,
+    TermQuery as TantivyTermQuery,
+};
 use tantivy::schema::{
     Field, IndexRecordOption, JsonObjectOptions, Schema as TantivySchema, TextFieldIndexing,
 };
 use tantivy::tokenizer::{BoxTokenStream, TextAnalyzer};
 use tantivy::Term;
 
+use crate::query_ast::minimum_should_match_query::MinimumShouldMatchQuery;
 use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
-use crate::query_ast::utils::full_text_query;
+use crate::query_ast::utils::{full_text_query, is_false};
 use crate::query_ast::{BuildTantivyAst, QueryAst};
-use crate::{get_quickwit_tokenizer_manager, BooleanOperand, InvalidQuery, MatchAllOrNone};
+use crate::{
+    get_quickwit_tokenizer_manager, BooleanOperand, Fuzziness, InvalidQuery, MatchAllOrNone,
+    MinimumShouldMatch, TantivyQuery,
+};
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
-#[serde(deny_unknown_fields)]
 pub struct FullTextParams {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tokenizer: Option<String>,
@@ -42,6 +48,10 @@ pub struct FullTextParams {
     // By default we match no documents.
     #[serde(default, skip_serializing_if = "MatchAllOrNone::is_none")]
     pub zero_terms_query: MatchAllOrNone,
+    // If true, a text that cannot be interpreted with the type of the field, e.g. `hello`
+    // searched in a numeric field, matches no documents instead of being an invalid query.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub lenient: bool,
 }
 
 impl FullTextParams {
@@ -101,6 +111,44 @@ impl FullTextParams {
         Ok(tokens)
     }
 
+    /// Builds the query matching a single token. In bool mode with a fuzziness, the token may
+    /// match terms within the allowed number of edits, with a constant score.
+    fn make_term_query(
+        &self,
+        term: Term,
+        index_record_option: IndexRecordOption,
+    ) -> Result<Box<dyn TantivyQuery>, InvalidQuery> {
+        let FullTextMode::Bool {
+            fuzziness: Some(fuzziness),
+            ..
+        } = self.mode
+        else {
+            return Ok(Box::new(TantivyTermQuery::new(term, index_record_option)));
+        };
+        let Some(token) = term.value().as_str() else {
+            return Err(InvalidQuery::SchemaError(
+                "Fuzzy matching is only supported on text fields.".to_string(),
+            ));
+        };
+        let distance = fuzziness.resolve(token);
+        if distance == 0 {
+            return Ok(Box::new(TantivyTermQuery::new(term, index_record_option)));
+        }
+        // Like in Elasticsearch, a transposition counts as a single edit.
+        Ok(Box::new(TantivyFuzzyTermQuery::new(term, distance, true)))
+    }
+
+    fn make_term_queries(
+        &self,
+        terms: Vec<(usize, Term)>,
+        index_record_option: IndexRecordOption,
+    ) -> Result<Vec<Box<dyn TantivyQuery>>, InvalidQuery> {
+        terms
+            .into_iter()
+            .map(|(_, term)| self.make_term_query(term, index_record_option))
+            .collect()
+    }
+
     pub(crate) fn make_query(
         &self,
         mut terms: Vec<(usize, Term)>,
@@ -109,15 +157,43 @@ impl FullTextParams {
         if terms.is_empty() {
             return Ok(self.zero_terms_query.into());
         }
+        if let FullTextMode::Bool {
+            operator: BooleanOperand::Or,
+            minimum_should_match: Some(minimum_should_match),
+            ..
+        } = self.mode
+        {
+            let minimum_should_match = minimum_should_match.resolve(terms.len());
+            if minimum_should_match > terms.len() {
+                return Ok(MatchAllOrNone::MatchNone.into());
+            }
+            if minimum_should_match > 1 && minimum_should_match < terms.len() {
+                let term_queries = self.make_term_queries(terms, index_record_option)?;
+                return Ok(MinimumShouldMatchQuery::new(term_queries, minimum_should_match).into());
+            }
+            if minimum_should_match == terms.len() {
+                let term_queries: Vec<TantivyQueryAst> = self
+                    .make_term_queries(terms, index_record_option)?
+                    .into_iter()
+                    .map(TantivyQueryAst::Leaf)
+                    .collect();
+                return Ok(
+                    TantivyBoolQuery::build_clause(BooleanOperand::And, term_queries).into(),
+                );
+            }
+        }
         if terms.len() == 1 {
             let term = terms.pop().unwrap().1;
-            return Ok(TantivyTermQuery::new(term, IndexRecordOption::WithFreqs).into());
+            return Ok(TantivyQueryAst::Leaf(
+                self.make_term_query(term, IndexRecordOption::WithFreqs)?,
+            ));
         }
         match self.mode {
-            FullTextMode::Bool { operator } => {
-                let term_query: Vec<TantivyQueryAst> = terms
+            FullTextMode::Bool { operator, .. } => {
+                let term_query: Vec<TantivyQueryAst> = self
+                    .make_term_queries(terms, index_record_option)?
                     .into_iter()
-                    .map(|(_, term)| TantivyTermQuery::new(term, index_record_option).into())
+                    .map(TantivyQueryAst::Leaf)
                     .collect();
                 Ok(TantivyBoolQuery::build_clause(operator, term_query).into())
             }
@@ -151,8 +227,15 @@ fn is_zero(val: &u32) -> bool {
 pub enum FullTextMode {
     // After tokenization, the different tokens should be used to
     // create a boolean clause (conjunction or disjunction based on the operator).
+    //
+    // With the `Or` operator, `minimum_should_match` sets the number of tokens that
+    // must match. `fuzziness` lets the tokens of text fields match terms within a few edits.
     Bool {
         operator: BooleanOperand,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        minimum_should_match: Option<MinimumShouldMatch>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fuzziness: Option<Fuzziness>,
     },
     // Act as Phrase with slop 0 if the field has positions,
     // otherwise act as an intersection.
@@ -169,7 +252,11 @@ pub enum FullTextMode {
 
 impl From<BooleanOperand> for FullTextMode {
     fn from(operator: BooleanOperand) -> Self {
-        FullTextMode::Bool { operator }
+        FullTextMode::Bool {
+            operator,
+            minimum_should_match: None,
+            fuzziness: None,
+        }
     }
 }
 
@@ -193,6 +280,10 @@ pub struct FullTextQuery {
     pub field: String,
     pub text: String,
     pub params: FullTextParams,
+    /// Field searched instead of `field` if it exists in the schema. The quoted phrases of a
+    /// query string with a `quote_field_suffix` target the suffixed field this way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_field: Option<String>,
 }
 
 impl From<FullTextQuery> for QueryAst {
@@ -207,18 +298,30 @@ impl BuildTantivyAst for FullTextQuery {
         schema: &TantivySchema,
         _search_fields: &[String],
         _with_validation: bool,
+        _with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
-        full_text_query(&self.field, &self.text, &self.params, schema)
+        let field = match &self.preferred_field {
+            Some(preferred_field) if schema.get_field(preferred_field).is_ok() => preferred_field,
+            _ => &self.field,
+        };
+        match full_text_query(field, &self.text, &self.params, schema) {
+            Err(InvalidQuery::InvalidSearchTerm { .. }) if self.params.lenient => {
+                Ok(TantivyQueryAst::match_none())
+            }
+            result => result,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use tantivy::schema::{Schema, TEXT};
+    use tantivy::collector::Count;
+    use tantivy::schema::{Schema, INDEXED, STRING, TEXT};
+    use tantivy::{doc, Index};
 
     use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
-    use crate::query_ast::{BuildTantivyAst, FullTextMode, FullTextQuery};
-    use crate::BooleanOperand;
+    use crate::query_ast::{BuildTantivyAst, FullTextMode, FullTextQuery, QueryAst};
+    use crate::{BooleanOperand, Fuzziness, MinimumShouldMatch};
 
     #[test]
     fn test_zero_terms() {
@@ -229,13 +332,15 @@ mod tests {
                 tokenizer: None,
                 mode: BooleanOperand::And.into(),
                 zero_terms_query: crate::MatchAllOrNone::MatchAll,
+                lenient: false,
             },
+            preferred_field: None,
         };
         let mut schema_builder = Schema::builder();
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         assert_eq!(ast.const_predicate(), Some(crate::MatchAllOrNone::MatchAll));
     }
@@ -249,13 +354,15 @@ mod tests {
                 tokenizer: None,
                 mode: FullTextMode::Phrase { slop: 1 },
                 zero_terms_query: crate::MatchAllOrNone::MatchAll,
+                lenient: false,
             },
+            preferred_field: None,
         };
         let mut schema_builder = Schema::builder();
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let leaf = ast.as_leaf().unwrap();
         assert_eq!(
@@ -274,13 +381,15 @@ mod tests {
                 tokenizer: Some("raw".to_string()),
                 mode: FullTextMode::Phrase { slop: 1 },
                 zero_terms_query: crate::MatchAllOrNone::MatchAll,
+                lenient: false,
             },
+            preferred_field: None,
         };
         let mut schema_builder = Schema::builder();
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let leaf = ast.as_leaf().unwrap();
         assert_eq!(
@@ -298,15 +407,183 @@ mod tests {
                 tokenizer: None,
                 mode: BooleanOperand::And.into(),
                 zero_terms_query: crate::MatchAllOrNone::MatchAll,
+                lenient: false,
             },
+            preferred_field: None,
         };
         let mut schema_builder = Schema::builder();
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let bool_query = ast.as_bool_query().unwrap();
         assert_eq!(bool_query.must.len(), 2);
     }
+
+    #[test]
+    fn test_full_text_bool_mode_minimum_should_match() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let build_ast = |minimum_should_match: MinimumShouldMatch| -> TantivyQueryAst {
+            let full_text_query = FullTextQuery {
+                field: "body".to_string(),
+                text: "hello happy world".to_string(),
+                params: super::FullTextParams {
+                    tokenizer: None,
+                    mode: FullTextMode::Bool {
+                        operator: BooleanOperand::Or,
+                        minimum_should_match: Some(minimum_should_match),
+                        fuzziness: None,
+                    },
+                    zero_terms_query: crate::MatchAllOrNone::MatchNone,
+                    lenient: false,
+                },
+                preferred_field: None,
+            };
+            full_text_query
+                .build_tantivy_ast_call(&schema, &[], true, true)
+                .unwrap()
+        };
+        let ast = build_ast(MinimumShouldMatch::Absolute(2));
+        assert!(format!("{:?}", ast.as_leaf().unwrap()).starts_with("MinimumShouldMatchQuery"));
+        let ast = build_ast(MinimumShouldMatch::Percentage(100));
+        assert_eq!(ast.as_bool_query().unwrap().must.len(), 3);
+        let ast = build_ast(MinimumShouldMatch::Absolute(1));
+        assert_eq!(ast.as_bool_query().unwrap().should.len(), 3);
+        let ast = build_ast(MinimumShouldMatch::Absolute(4));
+        assert_eq!(
+            ast.const_predicate(),
+            Some(crate::MatchAllOrNone::MatchNone)
+        );
+    }
+
+    #[test]
+    fn test_lenient_invalid_search_term() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_u64_field("count", INDEXED);
+        let schema = schema_builder.build();
+        let build_ast = |lenient: bool| {
+            FullTextQuery {
+                field: "count".to_string(),
+                text: "hello".to_string(),
+                params: super::FullTextParams {
+                    tokenizer: None,
+                    mode: BooleanOperand::Or.into(),
+                    zero_terms_query: crate::MatchAllOrNone::MatchNone,
+                    lenient,
+                },
+                preferred_field: None,
+            }
+            .build_tantivy_ast_call(&schema, &[], true, true)
+        };
+        let invalid_query = build_ast(false).unwrap_err();
+        assert!(matches!(
+            invalid_query,
+            crate::InvalidQuery::InvalidSearchTerm { .. }
+        ));
+        let ast = build_ast(true).unwrap();
+        assert_eq!(
+            ast.const_predicate(),
+            Some(crate::MatchAllOrNone::MatchNone)
+        );
+    }
+
+    #[test]
+    fn test_full_text_bool_mode_fuzziness() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_json_field("attributes", TEXT);
+        let schema = schema_builder.build();
+        let full_text_query = |field: &str, text: &str| FullTextQuery {
+            field: field.to_string(),
+            text: text.to_string(),
+            params: super::FullTextParams {
+                tokenizer: None,
+                mode: FullTextMode::Bool {
+                    operator: BooleanOperand::Or,
+                    minimum_should_match: None,
+                    fuzziness: Some(Fuzziness::Auto { low: 3, high: 6 }),
+                },
+                zero_terms_query: crate::MatchAllOrNone::MatchNone,
+                lenient: false,
+            },
+            preferred_field: None,
+        };
+        let ast = full_text_query("body", "to helo wonderfull")
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap();
+        let should_clauses = &ast.as_bool_query().unwrap().should;
+        assert_eq!(should_clauses.len(), 3);
+        // Tokens shorter than 3 characters must match exactly.
+        assert!(format!("{:?}", should_clauses[0]).contains("TermQuery(Term"));
+        assert!(format!("{:?}", should_clauses[1]).contains("FuzzyTermQuery"));
+        assert!(format!("{:?}", should_clauses[2]).contains("FuzzyTermQuery"));
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(body_field => "hello world"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(body_field => "goodbye world"))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query = QueryAst::from(full_text_query("body", "helo"))
+            .build_tantivy_query(&schema, &[], true, true)
+            .unwrap();
+        assert_eq!(searcher.search(&*query, &Count).unwrap(), 1);
+
+        let error = full_text_query("attributes.color", "blue")
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap_err();
+        assert!(error.to_string().contains("only supported on text fields"));
+    }
+
+    #[test]
+    fn test_full_text_preferred_field() {
+        let full_text_query = FullTextQuery {
+            field: "body".to_string(),
+            text: "Hello".to_string(),
+            params: super::FullTextParams {
+                tokenizer: None,
+                mode: BooleanOperand::And.into(),
+                zero_terms_query: crate::MatchAllOrNone::MatchNone,
+                lenient: false,
+            },
+            preferred_field: Some("body.exact".to_string()),
+        };
+        let search = |schema_builder: tantivy::schema::SchemaBuilder| {
+            let schema = schema_builder.build();
+            let body_field = schema.get_field("body").unwrap();
+            let index = Index::create_in_ram(schema.clone());
+            let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+            let mut document = doc!(body_field => "hello");
+            if let Ok(body_exact_field) = schema.get_field("body.exact") {
+                document.add_text(body_exact_field, "Hello");
+            }
+            index_writer.add_document(document).unwrap();
+            index_writer
+                .add_document(doc!(body_field => "Hello"))
+                .unwrap();
+            index_writer.commit().unwrap();
+            let searcher = index.reader().unwrap().searcher();
+            let query = QueryAst::from(full_text_query.clone())
+                .build_tantivy_query(&schema, &[], true, true)
+                .unwrap();
+            searcher.search(&*query, &Count).unwrap()
+        };
+        // The preferred field is searched when it exists in the schema.
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_text_field("body.exact", STRING);
+        assert_eq!(search(schema_builder), 1);
+
+        // The query falls back to the base field otherwise.
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        assert_eq!(search(schema_builder), 2);
+    }
 }
@@ -0,0 +1,1238 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::{EnableScoring, Explanation, Scorer, Weight};
+use tantivy::schema::{FieldType, Schema as TantivySchema};
+use tantivy::{DateTime, DocId, DocSet, Score, SegmentReader, TantivyError, Term};
+
+use super::utils::{find_field_or_hit_dynamic, NumericalFastFieldColumn};
+use super::{BuildTantivyAst, QueryAst, TantivyQueryAst};
+use crate::json_literal::InterpretUserInput;
+use crate::not_nan_f32::NotNaNf32;
+use crate::{InvalidQuery, JsonLiteral, MatchAllOrNone, TantivyQuery};
+
+/// Defines how the values of the score functions applying to a document are combined together.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionScoreMode {
+    #[default]
+    Multiply,
+    Sum,
+    /// Weighted average of the values, using the weights of the functions.
+    Avg,
+    First,
+    Max,
+    Min,
+}
+
+/// Defines how the combined value of the score functions is combined with the score of the
+/// underlying query.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionBoostMode {
+    #[default]
+    Multiply,
+    /// The score of the underlying query is ignored.
+    Replace,
+    Sum,
+    Avg,
+    Max,
+    Min,
+}
+
+/// Modifier applied by a `field_value_factor` function to the value of the field multiplied by
+/// the factor.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldValueFactorModifier {
+    #[default]
+    None,
+    Log,
+    Log1p,
+    Log2p,
+    Ln,
+    Ln1p,
+    Ln2p,
+    Square,
+    Sqrt,
+    Reciprocal,
+}
+
+impl FieldValueFactorModifier {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            FieldValueFactorModifier::None => value,
+            FieldValueFactorModifier::Log => value.log10(),
+            FieldValueFactorModifier::Log1p => (value + 1.0).log10(),
+            FieldValueFactorModifier::Log2p => (value + 2.0).log10(),
+            FieldValueFactorModifier::Ln => value.ln(),
+            FieldValueFactorModifier::Ln1p => value.ln_1p(),
+            FieldValueFactorModifier::Ln2p => (value + 2.0).ln(),
+            FieldValueFactorModifier::Square => value * value,
+            FieldValueFactorModifier::Sqrt => value.sqrt(),
+            FieldValueFactorModifier::Reciprocal => 1.0 / value,
+        }
+    }
+}
+
+/// Shape of the curve of a decay function.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DecayFunctionType {
+    Gauss,
+    Exp,
+    Linear,
+}
+
+/// Scores the documents with the value of a numerical field, multiplied by `factor` and passed
+/// through `modifier`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldValueFactorFunction {
+    pub field: String,
+    #[serde(default = "default_factor")]
+    pub factor: NotNaNf32,
+    #[serde(default)]
+    pub modifier: FieldValueFactorModifier,
+    /// Value used for the documents without a value for the field. If not set, the function
+    /// does not apply to these documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<NotNaNf32>,
+}
+
+fn default_factor() -> NotNaNf32 {
+    NotNaNf32::ONE
+}
+
+/// Scores the documents with a value decreasing with the distance between the value of a
+/// numerical or datetime field and `origin`.
+///
+/// The value is `1` up to a distance of `offset` and `decay` at a distance of `offset + scale`.
+/// For datetime fields, `origin` defaults to now, and `scale` and `offset` are either durations
+/// with a unit (`ms`, `s`, `m`, `h`, `d`, `w`), e.g. `7d`, or numbers of seconds. Documents
+/// without a value for the field get a value of `1`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DecayFunction {
+    pub decay_type: DecayFunctionType,
+    pub field: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<JsonLiteral>,
+    pub scale: JsonLiteral,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<JsonLiteral>,
+    #[serde(default = "default_decay")]
+    pub decay: NotNaNf32,
+}
+
+fn default_decay() -> NotNaNf32 {
+    NotNaNf32::try_from(0.5).unwrap()
+}
+
+/// Scores the documents with `pivot / (pivot + distance)`, where `distance` is the distance
+/// between the value of a numerical or datetime field and `origin`: the value is `1` at `origin`
+/// and `0.5` at a distance of `pivot`.
+///
+/// For datetime fields, `origin` is either a date or `now`, and `pivot` is either a duration
+/// with a unit, e.g. `7d`, or a number of seconds. The function does not apply to the documents
+/// without a value for the field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DistanceFeatureFunction {
+    pub field: String,
+    pub origin: JsonLiteral,
+    pub pivot: JsonLiteral,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreFunctionKind {
+    FieldValueFactor(FieldValueFactorFunction),
+    Decay(DecayFunction),
+    DistanceFeature(DistanceFeatureFunction),
+    /// Always has a value of `1`, so that the function is equal to its weight.
+    Constant,
+}
+
+/// Score function of a [`FunctionScoreQuery`]. The value of the function is multiplied by
+/// `weight`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScoreFunction {
+    #[serde(flatten)]
+    pub kind: ScoreFunctionKind,
+    /// If set, the function only applies to the documents matching this query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Box<QueryAst>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<NotNaNf32>,
+}
+
+/// Matches the same documents as the underlying query, and modifies their score with score
+/// functions reading numerical or datetime fast fields.
+///
+/// The values of the functions are combined according to `score_mode`, then combined with the
+/// score of the underlying query according to `boost_mode`. If no function applies to a
+/// document, the combined value of the functions is `1`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FunctionScoreQuery {
+    pub underlying: Box<QueryAst>,
+    pub functions: Vec<ScoreFunction>,
+    #[serde(default)]
+    pub score_mode: FunctionScoreMode,
+    #[serde(default)]
+    pub boost_mode: FunctionBoostMode,
+    /// Timestamp, in microseconds, standing for `now` in the origins of the functions. The root
+    /// sets it to the time of the request, so that all the leaves score the documents alike.
+    /// Defaults to the time at which the query is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub now_timestamp_micros: Option<i64>,
+}
+
+impl FunctionScoreQuery {
+    /// Applies `map_fn` to the underlying query and to the filters of the functions.
+    pub fn try_map_subqueries<E>(
+        self,
+        mut map_fn: impl FnMut(QueryAst) -> Result<QueryAst, E>,
+    ) -> Result<Self, E> {
+        let underlying = map_fn(*self.underlying)?;
+        let functions = self
+            .functions
+            .into_iter()
+            .map(|function| {
+                let filter = function
+                    .filter
+                    .map(|filter| map_fn(*filter).map(Box::new))
+                    .transpose()?;
+                Ok(ScoreFunction { filter, ..function })
+            })
+            .collect::<Result<Vec<_>, E>>()?;
+        Ok(FunctionScoreQuery {
+            underlying: Box::new(underlying),
+            functions,
+            ..self
+        })
+    }
+}
+
+impl From<FunctionScoreQuery> for QueryAst {
+    fn from(function_score_query: FunctionScoreQuery) -> Self {
+        QueryAst::FunctionScore(function_score_query)
+    }
+}
+
+impl BuildTantivyAst for FunctionScoreQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        search_fields: &[String],
+        with_validation: bool,
+        with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let now_secs = self
+            .now_timestamp_micros
+            .map(|now_timestamp_micros| now_timestamp_micros as f64 / 1_000_000.0)
+            .unwrap_or_else(current_timestamp_secs);
+        let functions = self
+            .functions
+            .iter()
+            .map(|function| CompiledScoreFunction::compile(function, schema, now_secs))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Only whether the documents match the filters matters.
+        let function_filters = self
+            .functions
+            .iter()
+            .map(|function| {
+                let Some(filter) = &function.filter else {
+                    return Ok(None);
+                };
+                let filter_ast = filter
+                    .build_tantivy_ast_call(schema, search_fields, with_validation, false)?
+                    .simplify();
+                Ok(Some(filter_ast.into()))
+            })
+            .collect::<Result<Vec<Option<Box<dyn TantivyQuery>>>, InvalidQuery>>()?;
+        // The score functions only impact the scores.
+        if !with_scoring {
+            return self.underlying.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            );
+        }
+        let underlying = if self.boost_mode == FunctionBoostMode::Replace {
+            self.underlying.build_tantivy_ast_call_without_scoring(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            )?
+        } else {
+            self.underlying.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            )?
+        }
+        .simplify();
+        if underlying.const_predicate() == Some(MatchAllOrNone::MatchNone) {
+            return Ok(underlying);
+        }
+        let function_score_query = FunctionScoreTantivyQuery {
+            underlying: underlying.into(),
+            functions,
+            function_filters,
+            score_mode: self.score_mode,
+            boost_mode: self.boost_mode,
+        };
+        Ok(function_score_query.into())
+    }
+}
+
+/// Returns whether the field is a datetime field, after checking that it is a numerical or
+/// datetime fast field.
+fn check_score_function_field(
+    field_name: &str,
+    schema: &TantivySchema,
+) -> Result<bool, InvalidQuery> {
+    let (_field, field_entry, path) = find_field_or_hit_dynamic(field_name, schema)?;
+    let is_supported_type = matches!(
+        field_entry.field_type(),
+        FieldType::U64(_) | FieldType::I64(_) | FieldType::F64(_) | FieldType::Date(_)
+    );
+    if !path.is_empty() || !field_entry.is_fast() || !is_supported_type {
+        return Err(InvalidQuery::SchemaError(format!(
+            "Score functions can only read numerical or datetime fast fields. (`{field_name}` is \
+             not)"
+        )));
+    }
+    Ok(matches!(field_entry.field_type(), FieldType::Date(_)))
+}
+
+fn current_timestamp_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Parses a duration with a unit, e.g. `7d`, into a number of seconds.
+fn parse_duration_secs(duration_str: &str) -> Option<f64> {
+    let unit_start = duration_str.find(|ch: char| ch.is_ascii_alphabetic())?;
+    let (value_str, unit) = duration_str.split_at(unit_start);
+    let value: f64 = value_str.trim().parse().ok()?;
+    let unit_secs = match unit {
+        "ms" => 0.001,
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3_600.0,
+        "d" => 86_400.0,
+        "w" => 604_800.0,
+        _ => return None,
+    };
+    Some(value * unit_secs)
+}
+
+fn parse_origin(
+    origin_opt: Option<&JsonLiteral>,
+    function_name: &str,
+    field_name: &str,
+    is_date: bool,
+    now_secs: f64,
+) -> Result<f64, InvalidQuery> {
+    let origin_opt = match origin_opt {
+        None if is_date => Some(now_secs),
+        Some(JsonLiteral::String(origin_str)) if is_date && origin_str == "now" => Some(now_secs),
+        Some(origin) if is_date => DateTime::interpret_json(origin)
+            .map(|origin| origin.into_timestamp_micros() as f64 / 1_000_000.0),
+        Some(origin) => f64::interpret_json(origin),
+        None => {
+            return Err(InvalidQuery::Other(anyhow::anyhow!(
+                "Decay function on numerical field `{field_name}` requires an `origin`."
+            )));
+        }
+    };
+    origin_opt.ok_or_else(|| {
+        InvalidQuery::Other(anyhow::anyhow!(
+            "Invalid `origin` for {function_name} on field `{field_name}`."
+        ))
+    })
+}
+
+fn parse_distance(
+    distance: &JsonLiteral,
+    param_name: &str,
+    function_name: &str,
+    field_name: &str,
+    is_date: bool,
+) -> Result<f64, InvalidQuery> {
+    let distance_opt = match distance {
+        JsonLiteral::String(distance_str) if is_date => {
+            parse_duration_secs(distance_str).or_else(|| f64::interpret_str(distance_str))
+        }
+        _ => f64::interpret_json(distance),
+    };
+    match distance_opt {
+        Some(distance) if distance >= 0.0 => Ok(distance),
+        _ => Err(InvalidQuery::Other(anyhow::anyhow!(
+            "Invalid `{param_name}` for {function_name} on field `{field_name}`."
+        ))),
+    }
+}
+
+#[derive(Clone, Debug)]
+enum CompiledScoreFunctionKind {
+    FieldValueFactor {
+        field: String,
+        factor: f64,
+        modifier: FieldValueFactorModifier,
+        missing: Option<f64>,
+    },
+    Decay {
+        decay_type: DecayFunctionType,
+        field: String,
+        origin: f64,
+        scale: f64,
+        offset: f64,
+        decay: f64,
+    },
+    DistanceFeature {
+        field: String,
+        origin: f64,
+        pivot: f64,
+    },
+    Constant,
+}
+
+#[derive(Clone, Debug)]
+struct CompiledScoreFunction {
+    kind: CompiledScoreFunctionKind,
+    weight: f64,
+}
+
+impl CompiledScoreFunction {
+    fn compile(
+        score_function: &ScoreFunction,
+        schema: &TantivySchema,
+        now_secs: f64,
+    ) -> Result<Self, InvalidQuery> {
+        let kind = match &score_function.kind {
+            ScoreFunctionKind::FieldValueFactor(field_value_factor) => {
+                check_score_function_field(&field_value_factor.field, schema)?;
+                CompiledScoreFunctionKind::FieldValueFactor {
+                    field: field_value_factor.field.clone(),
+                    factor: f32::from(field_value_factor.factor) as f64,
+                    modifier: field_value_factor.modifier,
+                    missing: field_value_factor
+                        .missing
+                        .map(|missing| f32::from(missing) as f64),
+                }
+            }
+            ScoreFunctionKind::Decay(decay_function) => {
+                let field_name = &decay_function.field;
+                let is_date = check_score_function_field(field_name, schema)?;
+                let origin = parse_origin(
+                    decay_function.origin.as_ref(),
+                    "decay function",
+                    field_name,
+                    is_date,
+                    now_secs,
+                )?;
+                let scale = parse_distance(
+                    &decay_function.scale,
+                    "scale",
+                    "decay function",
+                    field_name,
+                    is_date,
+                )?;
+                let offset = if let Some(offset) = &decay_function.offset {
+                    parse_distance(offset, "offset", "decay function", field_name, is_date)?
+                } else {
+                    0.0
+                };
+                let decay = f32::from(decay_function.decay) as f64;
+
+                if scale == 0.0 {
+                    return Err(InvalidQuery::Other(anyhow::anyhow!(
+                        "`scale` of decay function on field `{field_name}` must be strictly \
+                         positive."
+                    )));
+                }
+                if decay <= 0.0 || decay >= 1.0 {
+                    return Err(InvalidQuery::Other(anyhow::anyhow!(
+                        "`decay` of decay function on field `{field_name}` must be between 0 \
+                         and 1 (exclusive), got `{decay}`."
+                    )));
+                }
+                CompiledScoreFunctionKind::Decay {
+                    decay_type: decay_function.decay_type,
+                    field: field_name.clone(),
+                    origin,
+                    scale,
+                    offset,
+                    decay,
+                }
+            }
+            ScoreFunctionKind::DistanceFeature(distance_feature) => {
+                let field_name = &distance_feature.field;
+                let is_date = check_score_function_field(field_name, schema)?;
+                let origin = parse_origin(
+                    Some(&distance_feature.origin),
+                    "distance feature function",
+                    field_name,
+                    is_date,
+                    now_secs,
+                )?;
+                let pivot = parse_distance(
+                    &distance_feature.pivot,
+                    "pivot",
+                    "distance feature function",
+                    field_name,
+                    is_date,
+                )?;
+                if pivot == 0.0 {
+                    return Err(InvalidQuery::Other(anyhow::anyhow!(
+                        "`pivot` of distance feature function on field `{field_name}` must be \
+                         strictly positive."
+                    )));
+                }
+                CompiledScoreFunctionKind::DistanceFeature {
+                    field: field_name.clone(),
+                    origin,
+                    pivot,
+                }
+            }
+            ScoreFunctionKind::Constant => CompiledScoreFunctionKind::Constant,
+        };
+        let weight = score_function
+            .weight
+            .map(|weight| f32::from(weight) as f64)
+            .unwrap_or(1.0);
+        Ok(CompiledScoreFunction { kind, weight })
+    }
+
+    fn field(&self) -> Option<&str> {
+        match &self.kind {
+            CompiledScoreFunctionKind::FieldValueFactor { field, .. }
+            | CompiledScoreFunctionKind::Decay { field, .. }
+            | CompiledScoreFunctionKind::DistanceFeature { field, .. } => Some(field),
+            CompiledScoreFunctionKind::Constant => None,
+        }
+    }
+
+    /// Returns the value of the function for the document, without its weight, or `None` if
+    /// the function does not apply to the document.
+    fn value(&self, column_opt: Option<&NumericalFastFieldColumn>, doc: DocId) -> Option<f64> {
+        let field_value_opt = column_opt.and_then(|column| column.value(doc));
+        match &self.kind {
+            CompiledScoreFunctionKind::FieldValueFactor {
+                factor,
+                modifier,
+                missing,
+                ..
+            } => {
+                let field_value = field_value_opt.or(*missing)?;
+                let value = modifier.apply(factor * field_value);
+                // `log` of 0 or `reciprocal` of 0 for instance.
+                value.is_finite().then_some(value)
+            }
+            CompiledScoreFunctionKind::Decay {
+                decay_type,
+                origin,
+                scale,
+                offset,
+                decay,
+                ..
+            } => {
+                let Some(field_value) = field_value_opt else {
+                    return Some(1.0);
+                };
+                let distance = ((field_value - origin).abs() - offset).max(0.0);
+                let value = match decay_type {
+                    DecayFunctionType::Gauss => {
+                        (decay.ln() * distance * distance / (scale * scale)).exp()
+                    }
+                    DecayFunctionType::Exp => (decay.ln() * distance / scale).exp(),
+                    DecayFunctionType::Linear => {
+                        let zero_distance = scale / (1.0 - decay);
+                        ((zero_distance - distance) / zero_distance).max(0.0)
+                    }
+                };
+                Some(value)
+            }
+            CompiledScoreFunctionKind::DistanceFeature { origin, pivot, .. } => {
+                let distance = (field_value_opt? - origin).abs();
+                Some(pivot / (pivot + distance))
+            }
+            CompiledScoreFunctionKind::Constant => Some(1.0),
+        }
+    }
+}
+
+struct FunctionScoreTantivyQuery {
+    underlying: Box<dyn TantivyQuery>,
+    functions: Vec<CompiledScoreFunction>,
+    // Filter of each function, if any.
+    function_filters: Vec<Option<Box<dyn TantivyQuery>>>,
+    score_mode: FunctionScoreMode,
+    boost_mode: FunctionBoostMode,
+}
+
+impl Clone for FunctionScoreTantivyQuery {
+    fn clone(&self) -> Self {
+        FunctionScoreTantivyQuery {
+            underlying: self.underlying.box_clone(),
+            functions: self.functions.clone(),
+            function_filters: self
+                .function_filters
+                .iter()
+                .map(|filter_opt| filter_opt.as_ref().map(|filter| filter.box_clone()))
+                .collect(),
+            score_mode: self.score_mode,
+            boost_mode: self.boost_mode,
+        }
+    }
+}
+
+impl fmt::Debug for FunctionScoreTantivyQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FunctionScoreTantivyQuery")
+            .field("underlying", &self.underlying)
+            .field("functions", &self.functions)
+            .field("function_filters", &self.function_filters)
+            .field("score_mode", &self.score_mode)
+            .field("boost_mode", &self.boost_mode)
+            .finish()
+    }
+}
+
+impl TantivyQuery for FunctionScoreTantivyQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let underlying_weight = self.underlying.weight(enable_scoring)?;
+        let filter_scoring = match enable_scoring.searcher() {
+            Some(searcher) => EnableScoring::disabled_from_searcher(searcher),
+            None => EnableScoring::disabled_from_schema(enable_scoring.schema()),
+        };
+        let filter_weights = self
+            .function_filters
+            .iter()
+            .map(|filter_opt| {
+                filter_opt
+                    .as_ref()
+                    .map(|filter| filter.weight(filter_scoring))
+                    .transpose()
+            })
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        Ok(Box::new(FunctionScoreWeight {
+            underlying_weight,
+            functions: self.functions.clone(),
+            filter_weights,
+            score_mode: self.score_mode,
+            boost_mode: self.boost_mode,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.underlying.query_terms(visitor)
+    }
+}
+
+struct FunctionScoreWeight {
+    underlying_weight: Box<dyn Weight>,
+    functions: Vec<CompiledScoreFunction>,
+    filter_weights: Vec<Option<Box<dyn Weight>>>,
+    score_mode: FunctionScoreMode,
+    boost_mode: FunctionBoostMode,
+}
+
+impl Weight for FunctionScoreWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        // The boost applies to the final score, not only to the score of the underlying query.
+        let underlying_scorer = self.underlying_weight.scorer(reader, 1.0)?;
+        let columns = self
+            .functions
+            .iter()
+            .map(|function| {
+                function
+                    .field()
+                    .map(|field_name| NumericalFastFieldColumn::open(reader, field_name))
+                    .transpose()
+            })
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        let filter_scorers = self
+            .filter_weights
+            .iter()
+            .map(|filter_weight_opt| {
+                filter_weight_opt
+                    .as_ref()
+                    .map(|filter_weight| filter_weight.scorer(reader, 1.0))
+                    .transpose()
+            })
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        Ok(Box::new(FunctionScoreScorer {
+            underlying_scorer,
+            functions: self.functions.clone(),
+            columns,
+            filter_scorers,
+            score_mode: self.score_mode,
+            boost_mode: self.boost_mode,
+            boost,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        let mut explanation = Explanation::new("FunctionScoreQuery", scorer.score());
+        explanation.add_detail(self.underlying_weight.explain(reader, doc)?);
+        Ok(explanation)
+    }
+}
+
+struct FunctionScoreScorer {
+    underlying_scorer: Box<dyn Scorer>,
+    functions: Vec<CompiledScoreFunction>,
+    columns: Vec<Option<NumericalFastFieldColumn>>,
+    filter_scorers: Vec<Option<Box<dyn Scorer>>>,
+    score_mode: FunctionScoreMode,
+    boost_mode: FunctionBoostMode,
+    boost: Score,
+}
+
+impl FunctionScoreScorer {
+    fn functions_value(&mut self, doc: DocId) -> f64 {
+        let mut combined_value_opt: Option<f64> = None;
+        let mut weight_sum = 0.0;
+
+        for ((function, column_opt), filter_scorer_opt) in self
+            .functions
+            .iter()
+            .zip(&self.columns)
+            .zip(self.filter_scorers.iter_mut())
+        {
+            if let Some(filter_scorer) = filter_scorer_opt {
+                // The documents are scored in increasing order, so the filter scorers only move
+                // forward.
+                if filter_scorer.doc() < doc {
+                    filter_scorer.seek(doc);
+                }
+                if filter_scorer.doc() != doc {
+                    continue;
+                }
+            }
+            let Some(value) = function.value(column_opt.as_ref(), doc) else {
+                continue;
+            };
+            let weighted_value = value * function.weight;
+            weight_sum += function.weight;
+
+            let Some(combined_value) = combined_value_opt else {
+                combined_value_opt = Some(weighted_value);
+                continue;
+            };
+            let new_combined_value = match self.score_mode {
+                FunctionScoreMode::Multiply => combined_value * weighted_value,
+                FunctionScoreMode::Sum | FunctionScoreMode::Avg => combined_value + weighted_value,
+                FunctionScoreMode::First => break,
+                FunctionScoreMode::Max => combined_value.max(weighted_value),
+                FunctionScoreMode::Min => combined_value.min(weighted_value),
+            };
+            combined_value_opt = Some(new_combined_value);
+        }
+        let Some(combined_value) = combined_value_opt else {
+            return 1.0;
+        };
+        if self.score_mode == FunctionScoreMode::Avg {
+            if weight_sum == 0.0 {
+                return 0.0;
+            }
+            return combined_value / weight_sum;
+        }
+        combined_value
+    }
+}
+
+impl DocSet for FunctionScoreScorer {
+    fn advance(&mut self) -> DocId {
+        self.underlying_scorer.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.underlying_scorer.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying_scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying_scorer.size_hint()
+    }
+}
+
+impl Scorer for FunctionScoreScorer {
+    fn score(&mut self) -> Score {
+        let query_score = self.underlying_scorer.score() as f64;
+        let functions_value = self.functions_value(self.doc());
+        let score = match self.boost_mode {
+            FunctionBoostMode::Multiply => query_score * functions_value,
+            FunctionBoostMode::Replace => functions_value,
+            FunctionBoostMode::Sum => query_score + functions_value,
+            FunctionBoostMode::Avg => (query_score + functions_value) / 2.0,
+            FunctionBoostMode::Max => query_score.max(functions_value),
+            FunctionBoostMode::Min => query_score.min(functions_value),
+        };
+        score as Score * self.boost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, FAST, INDEXED, TEXT};
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{self, search_scored_docs};
+
+    fn build_test_index() -> Index {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let likes_field = schema_builder.add_i64_field("likes", FAST);
+        let timestamp_field = schema_builder.add_date_field("timestamp", FAST);
+        schema_builder.add_i64_field("not_fast", INDEXED);
+        // 2023-01-10, 2023-01-09 and 2023-01-08.
+        let docs = [
+            (1i64, 1_673_308_800),
+            (4, 1_673_222_400),
+            (9, 1_673_136_000),
+        ]
+        .into_iter()
+        .map(|(likes, timestamp_secs)| {
+            doc!(
+                body_field => "hello",
+                likes_field => likes,
+                timestamp_field => DateTime::from_timestamp_secs(timestamp_secs),
+            )
+        })
+        .chain([doc!(body_field => "hello")]);
+        test_helpers::build_test_index(schema_builder.build(), docs)
+    }
+
+    fn function(kind: ScoreFunctionKind, weight: Option<f32>) -> ScoreFunction {
+        ScoreFunction {
+            kind,
+            filter: None,
+            weight: weight.map(|weight| NotNaNf32::try_from(weight).unwrap()),
+        }
+    }
+
+    fn field_value_factor(
+        modifier: FieldValueFactorModifier,
+        missing: Option<f32>,
+    ) -> ScoreFunctionKind {
+        ScoreFunctionKind::FieldValueFactor(FieldValueFactorFunction {
+            field: "likes".to_string(),
+            factor: NotNaNf32::ONE,
+            modifier,
+            missing: missing.map(|missing| NotNaNf32::try_from(missing).unwrap()),
+        })
+    }
+
+    fn decay(
+        decay_type: DecayFunctionType,
+        field: &str,
+        origin: Option<JsonLiteral>,
+        scale: JsonLiteral,
+    ) -> ScoreFunctionKind {
+        ScoreFunctionKind::Decay(DecayFunction {
+            decay_type,
+            field: field.to_string(),
+            origin,
+            scale,
+            offset: None,
+            decay: NotNaNf32::try_from(0.5).unwrap(),
+        })
+    }
+
+    fn function_score_query(
+        functions: Vec<ScoreFunction>,
+        score_mode: FunctionScoreMode,
+        boost_mode: FunctionBoostMode,
+    ) -> QueryAst {
+        FunctionScoreQuery {
+            underlying: Box::new(QueryAst::MatchAll),
+            functions,
+            score_mode,
+            boost_mode,
+            now_timestamp_micros: None,
+        }
+        .into()
+    }
+
+    fn search_scores(query_ast: QueryAst) -> Vec<Score> {
+        let index = build_test_index();
+        search_scored_docs(&index, &query_ast, true)
+            .unwrap()
+            .into_iter()
+            .map(|(score, _)| score)
+            .collect()
+    }
+
+    #[track_caller]
+    fn assert_scores(query_ast: QueryAst, expected_scores: &[Score]) {
+        let scores = search_scores(query_ast);
+        assert_eq!(scores.len(), expected_scores.len(), "{scores:?}");
+        for (score, expected_score) in scores.iter().zip(expected_scores) {
+            assert!(
+                (score - expected_score).abs() < 1e-4,
+                "{scores:?} != {expected_scores:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_function_score_field_value_factor() {
+        let query_ast = function_score_query(
+            vec![function(
+                field_value_factor(FieldValueFactorModifier::Sqrt, None),
+                None,
+            )],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Replace,
+        );
+        // The function does not apply to the document without likes.
+        assert_scores(query_ast, &[3.0, 2.0, 1.0, 1.0]);
+
+        let query_ast = function_score_query(
+            vec![function(
+                field_value_factor(FieldValueFactorModifier::Sqrt, Some(16.0)),
+                None,
+            )],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[4.0, 3.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_function_score_numerical_decay() {
+        let origin = Some(JsonLiteral::from(0u64));
+        let scale = JsonLiteral::from(4u64);
+        for (decay_type, expected_scores) in [
+            (DecayFunctionType::Gauss, [1.0, 0.9576, 0.5, 0.0299]),
+            (DecayFunctionType::Exp, [1.0, 0.8409, 0.5, 0.2102]),
+            (DecayFunctionType::Linear, [1.0, 0.875, 0.5, 0.0]),
+        ] {
+            let query_ast = function_score_query(
+                vec![function(
+                    decay(decay_type, "likes", origin.clone(), scale.clone()),
+                    None,
+                )],
+                FunctionScoreMode::Multiply,
+                FunctionBoostMode::Replace,
+            );
+            assert_scores(query_ast, &expected_scores);
+        }
+        let mut decay_function = decay(DecayFunctionType::Exp, "likes", origin, scale);
+        if let ScoreFunctionKind::Decay(decay_function) = &mut decay_function {
+            decay_function.offset = Some(JsonLiteral::from(5u64));
+        }
+        let query_ast = function_score_query(
+            vec![function(decay_function, None)],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[1.0, 1.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn test_function_score_datetime_decay() {
+        let query_ast = function_score_query(
+            vec![function(
+                decay(
+                    DecayFunctionType::Gauss,
+                    "timestamp",
+                    Some(JsonLiteral::from("2023-01-10T00:00:00Z")),
+                    JsonLiteral::from("1d"),
+                ),
+                None,
+            )],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[1.0, 1.0, 0.5, 0.0625]);
+    }
+
+    #[test]
+    fn test_function_score_datetime_decay_from_now() {
+        let function_score_query = FunctionScoreQuery {
+            underlying: Box::new(QueryAst::MatchAll),
+            functions: vec![function(
+                decay(
+                    DecayFunctionType::Gauss,
+                    "timestamp",
+                    None,
+                    JsonLiteral::from("1d"),
+                ),
+                None,
+            )],
+            score_mode: FunctionScoreMode::Multiply,
+            boost_mode: FunctionBoostMode::Replace,
+            // 2023-01-10.
+            now_timestamp_micros: Some(1_673_308_800_000_000),
+        };
+        assert_scores(function_score_query.into(), &[1.0, 1.0, 0.5, 0.0625]);
+    }
+
+    #[test]
+    fn test_function_score_function_filter() {
+        let mut filtered_function = function(ScoreFunctionKind::Constant, Some(2.0));
+        filtered_function.filter = Some(Box::new(QueryAst::range("likes", 4u64..)));
+        let query_ast = function_score_query(
+            vec![filtered_function.clone()],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Replace,
+        );
+        // The function only applies to the documents with at least 4 likes.
+        assert_scores(query_ast, &[2.0, 2.0, 1.0, 1.0]);
+
+        let query_ast = function_score_query(
+            vec![
+                filtered_function,
+                function(ScoreFunctionKind::Constant, Some(3.0)),
+            ],
+            FunctionScoreMode::Sum,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[5.0, 5.0, 3.0, 3.0]);
+
+        let mut invalid_filter_function = function(ScoreFunctionKind::Constant, None);
+        invalid_filter_function.filter = Some(Box::new(QueryAst::term("missing", "value")));
+        let index = build_test_index();
+        let error = function_score_query(
+            vec![invalid_filter_function],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Multiply,
+        )
+        .build_tantivy_query(&index.schema(), &[], true, true)
+        .unwrap_err();
+        assert!(matches!(error, InvalidQuery::FieldDoesNotExist { .. }));
+    }
+
+    #[test]
+    fn test_function_score_distance_feature() {
+        let distance_feature = |field: &str, origin: JsonLiteral, pivot: JsonLiteral| {
+            ScoreFunctionKind::DistanceFeature(DistanceFeatureFunction {
+                field: field.to_string(),
+                origin,
+                pivot,
+            })
+        };
+        let query_ast = function_score_query(
+            vec![function(
+                distance_feature("likes", JsonLiteral::from(9u64), JsonLiteral::from(5u64)),
+                None,
+            )],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Replace,
+        );
+        // The function does not apply to the document without likes.
+        assert_scores(query_ast, &[1.0, 1.0, 0.5, 0.3846]);
+
+        let query_ast = function_score_query(
+            vec![function(
+                distance_feature(
+                    "timestamp",
+                    JsonLiteral::from("2023-01-10T00:00:00Z"),
+                    JsonLiteral::from("2d"),
+                ),
+                Some(2.0),
+            )],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[2.0, 1.3333, 1.0, 1.0]);
+
+        let index = build_test_index();
+        let error = function_score_query(
+            vec![function(
+                distance_feature(
+                    "timestamp",
+                    JsonLiteral::from("now"),
+                    JsonLiteral::from("0d"),
+                ),
+                None,
+            )],
+            FunctionScoreMode::Multiply,
+            FunctionBoostMode::Multiply,
+        )
+        .build_tantivy_query(&index.schema(), &[], true, true)
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`pivot` of distance feature function on field `timestamp` must be strictly positive."
+        );
+    }
+
+    #[test]
+    fn test_function_score_modes() {
+        let functions = vec![
+            function(
+                field_value_factor(FieldValueFactorModifier::None, None),
+                Some(2.0),
+            ),
+            function(ScoreFunctionKind::Constant, Some(3.0)),
+        ];
+        let query_ast = function_score_query(
+            functions.clone(),
+            FunctionScoreMode::Sum,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[21.0, 11.0, 5.0, 3.0]);
+
+        let query_ast = function_score_query(
+            functions.clone(),
+            FunctionScoreMode::Avg,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[4.2, 2.2, 1.0, 1.0]);
+
+        let query_ast = function_score_query(
+            functions.clone(),
+            FunctionScoreMode::First,
+            FunctionBoostMode::Replace,
+        );
+        assert_scores(query_ast, &[18.0, 8.0, 3.0, 2.0]);
+
+        let query_ast = function_score_query(
+            functions.clone(),
+            FunctionScoreMode::Min,
+            FunctionBoostMode::Sum,
+        );
+        assert_scores(query_ast, &[4.0, 4.0, 4.0, 3.0]);
+
+        // The boost applies to the final score.
+        let query_ast =
+            function_score_query(functions, FunctionScoreMode::Max, FunctionBoostMode::Sum)
+                .boost(Some(NotNaNf32::try_from(2.0).unwrap()));
+        assert_scores(query_ast, &[38.0, 18.0, 8.0, 8.0]);
+    }
+
+    #[test]
+    fn test_function_score_invalid() {
+        let index = build_test_index();
+        let build = |kind: ScoreFunctionKind| {
+            function_score_query(
+                vec![function(kind, None)],
+                FunctionScoreMode::Multiply,
+                FunctionBoostMode::Multiply,
+            )
+            .build_tantivy_query(&index.schema(), &[], true, true)
+        };
+        let error = build(ScoreFunctionKind::FieldValueFactor(
+            FieldValueFactorFunction {
+                field: "not_fast".to_string(),
+                factor: NotNaNf32::ONE,
+                modifier: FieldValueFactorModifier::None,
+                missing: None,
+            },
+        ))
+        .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+
+        let error = build(decay(
+            DecayFunctionType::Gauss,
+            "likes",
+            None,
+            JsonLiteral::from(4u64),
+        ))
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Decay function on numerical field `likes` requires an `origin`."
+        );
+
+        let error = build(decay(
+            DecayFunctionType::Gauss,
+            "timestamp",
+            None,
+            JsonLiteral::from("1y"),
+        ))
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid `scale` for decay function on field `timestamp`."
+        );
+
+        let mut decay_function = decay(
+            DecayFunctionType::Gauss,
+            "timestamp",
+            None,
+            JsonLiteral::from("1h"),
+        );
+        if let ScoreFunctionKind::Decay(decay_function) = &mut decay_function {
+            decay_function.decay = NotNaNf32::ONE;
+        }
+        assert!(build(decay_function).is_err());
+    }
+
+    #[test]
+    fn test_function_score_query_serde() {
+        let query_ast: QueryAst = serde_json::from_str(
+            r#"{
+                "type": "function_score",
+                "underlying": {"type": "match_all"},
+                "functions": [
+                    {"type": "field_value_factor", "field": "likes", "modifier": "log1p", "weight": 2, "filter": {"type": "match_all"}},
+                    {"type": "decay", "decay_type": "gauss", "field": "timestamp", "scale": "7d"},
+                    {"type": "constant", "weight": 0.5}
+                ],
+                "boost_mode": "sum"
+            }"#,
+        )
+        .unwrap();
+        let QueryAst::FunctionScore(function_score_query) = query_ast else {
+            panic!();
+        };
+        assert_eq!(function_score_query.functions.len(), 3);
+        assert_eq!(
+            function_score_query.functions[0].weight,
+            Some(NotNaNf32::try_from(2.0).unwrap())
+        );
+        assert_eq!(
+            function_score_query.functions[0].filter.as_deref(),
+            Some(&QueryAst::MatchAll)
+        );
+        assert!(function_score_query.functions[1].filter.is_none());
+        let ScoreFunctionKind::Decay(decay_function) = &function_score_query.functions[1].kind
+        else {
+            panic!();
+        };
+        assert_eq!(decay_function.decay, NotNaNf32::try_from(0.5).unwrap());
+        assert_eq!(function_score_query.score_mode, FunctionScoreMode::Multiply);
+        assert_eq!(function_score_query.boost_mode, FunctionBoostMode::Sum);
+        assert!(function_score_query.now_timestamp_micros.is_none());
+    }
+}
@@ -0,0 +1,933 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::postings::{Postings, SegmentPostings};
+use tantivy::query::{
+    EnableScoring, Explanation, PhraseQuery as TantivyPhraseQuery, Scorer,
+    TermQuery as TantivyTermQuery, Weight,
+};
+use tantivy::schema::{FieldType, IndexRecordOption, Schema as TantivySchema};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+
+use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
+use crate::query_ast::utils::is_false;
+use crate::query_ast::{BuildTantivyAst, FullTextParams, QueryAst};
+use crate::{find_field_or_hit_dynamic, BooleanOperand, InvalidQuery, MatchAllOrNone};
+
+fn unlimited_gaps() -> i32 {
+    -1
+}
+
+fn is_unlimited_gaps(max_gaps: &i32) -> bool {
+    *max_gaps < 0
+}
+
+fn max_gaps_opt(max_gaps: i32) -> Option<u32> {
+    u32::try_from(max_gaps).ok()
+}
+
+/// Rule producing the intervals of positions matched by an [`IntervalsQuery`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntervalsRule {
+    Match(IntervalsMatchRule),
+    AnyOf(IntervalsAnyOfRule),
+    AllOf(IntervalsAllOfRule),
+}
+
+/// Matches the terms of an analyzed text.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct IntervalsMatchRule {
+    pub query: String,
+    /// Maximum number of positions between the terms. A negative value means unlimited.
+    #[serde(default = "unlimited_gaps", skip_serializing_if = "is_unlimited_gaps")]
+    pub max_gaps: i32,
+    /// If true, the terms must appear in the order of the query.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ordered: bool,
+    /// Tokenizer used to analyze the query, in place of the tokenizer of the field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analyzer: Option<String>,
+}
+
+/// Matches the intervals produced by any of its rules.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct IntervalsAnyOfRule {
+    pub intervals: Vec<IntervalsRule>,
+}
+
+/// Matches the intervals spanning an interval of each of its rules.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct IntervalsAllOfRule {
+    pub intervals: Vec<IntervalsRule>,
+    /// Maximum number of positions between the intervals of the rules. A negative value means
+    /// unlimited.
+    #[serde(default = "unlimited_gaps", skip_serializing_if = "is_unlimited_gaps")]
+    pub max_gaps: i32,
+    /// If true, the intervals must appear in the order of the rules, without overlapping.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ordered: bool,
+}
+
+/// Matches documents based on the order and proximity of terms, as described by a tree of
+/// rules.
+///
+/// The field must be a text field with positions indexed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IntervalsQuery {
+    pub field: String,
+    pub rule: IntervalsRule,
+}
+
+impl From<IntervalsQuery> for QueryAst {
+    fn from(intervals_query: IntervalsQuery) -> Self {
+        QueryAst::Intervals(intervals_query)
+    }
+}
+
+impl IntervalsRule {
+    /// Compiles the rule into an intervals source. Returns `None` if the rule cannot match any
+    /// document, for instance if the query of a match rule is empty after tokenization.
+    fn compile(&self, tokenize: &TokenizeFn) -> Result<Option<IntervalsSource>, InvalidQuery> {
+        match self {
+            IntervalsRule::Match(match_rule) => {
+                let mut terms = tokenize(&match_rule.query, &match_rule.analyzer)?;
+                if terms.len() <= 1 {
+                    return Ok(terms.pop().map(IntervalsSource::Term));
+                }
+                Ok(Some(IntervalsSource::AllOf {
+                    sources: terms.into_iter().map(IntervalsSource::Term).collect(),
+                    ordered: match_rule.ordered,
+                    max_gaps: max_gaps_opt(match_rule.max_gaps),
+                }))
+            }
+            IntervalsRule::AnyOf(any_of_rule) => {
+                let mut sources = Vec::with_capacity(any_of_rule.intervals.len());
+                for rule in &any_of_rule.intervals {
+                    if let Some(source) = rule.compile(tokenize)? {
+                        sources.push(source);
+                    }
+                }
+                if sources.is_empty() {
+                    return Ok(None);
+                }
+                Ok(Some(IntervalsSource::AnyOf(sources)))
+            }
+            IntervalsRule::AllOf(all_of_rule) => {
+                if all_of_rule.intervals.is_empty() {
+                    return Err(InvalidQuery::Other(anyhow::anyhow!(
+                        "An `all_of` intervals rule requires at least one rule."
+                    )));
+                }
+                let mut sources = Vec::with_capacity(all_of_rule.intervals.len());
+                for rule in &all_of_rule.intervals {
+                    let Some(source) = rule.compile(tokenize)? else {
+                        return Ok(None);
+                    };
+                    sources.push(source);
+                }
+                Ok(Some(IntervalsSource::AllOf {
+                    sources,
+                    ordered: all_of_rule.ordered,
+                    max_gaps: max_gaps_opt(all_of_rule.max_gaps),
+                }))
+            }
+        }
+    }
+}
+
+impl BuildTantivyAst for IntervalsQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        build_intervals_tantivy_ast(&self.field, schema, "intervals", |tokenize| {
+            self.rule.compile(tokenize)
+        })
+    }
+}
+
+/// Tokenizes a text into the terms of the targeted field, with the tokenizer of the field or the
+/// given analyzer.
+pub(super) type TokenizeFn<'a> =
+    dyn Fn(&str, &Option<String>) -> Result<Vec<Term>, InvalidQuery> + 'a;
+
+/// Builds the tantivy query of a positional query targeting `field_path`, whose rules are
+/// compiled into an intervals source by `compile`.
+pub(super) fn build_intervals_tantivy_ast(
+    field_path: &str,
+    schema: &TantivySchema,
+    query_name: &str,
+    compile: impl FnOnce(&TokenizeFn) -> Result<Option<IntervalsSource>, InvalidQuery>,
+) -> Result<TantivyQueryAst, InvalidQuery> {
+    let (field, field_entry, json_path) = find_field_or_hit_dynamic(field_path, schema)?;
+    let not_searchable_error = || {
+        InvalidQuery::SchemaError(format!(
+            "Field `{}` is not full-text searchable",
+            field_entry.name()
+        ))
+    };
+    let no_positions_error = || {
+        InvalidQuery::SchemaError(format!(
+            "Trying to run an {query_name} query on field `{}` which does not have positions \
+             indexed.",
+            field_entry.name()
+        ))
+    };
+    let full_text_params = |analyzer: &Option<String>| FullTextParams {
+        tokenizer: analyzer.clone(),
+        mode: BooleanOperand::And.into(),
+        zero_terms_query: MatchAllOrNone::MatchNone,
+        lenient: false,
+    };
+    let source_opt = match field_entry.field_type() {
+        FieldType::Str(text_options) => {
+            let text_field_indexing = text_options
+                .get_indexing_options()
+                .ok_or_else(not_searchable_error)?;
+            if !text_field_indexing.index_option().has_positions() {
+                return Err(no_positions_error());
+            }
+            compile(&|text, analyzer| {
+                let terms = full_text_params(analyzer).tokenize_text_into_terms(
+                    field,
+                    text,
+                    text_field_indexing,
+                )?;
+                Ok(terms.into_iter().map(|(_, term)| term).collect())
+            })?
+        }
+        FieldType::JsonObject(json_options) => {
+            let text_field_indexing = json_options
+                .get_text_indexing_options()
+                .ok_or_else(not_searchable_error)?;
+            if !text_field_indexing.index_option().has_positions() {
+                return Err(no_positions_error());
+            }
+            compile(&|text, analyzer| {
+                let terms = full_text_params(analyzer).tokenize_text_into_terms_json(
+                    field,
+                    json_path,
+                    text,
+                    json_options,
+                )?;
+                Ok(terms.into_iter().map(|(_, term)| term).collect())
+            })?
+        }
+        _ => {
+            return Err(InvalidQuery::SchemaError(format!(
+                "Trying to run an {query_name} query on a non-text field."
+            )));
+        }
+    };
+    let Some(source) = source_opt else {
+        return Ok(TantivyQueryAst::match_none());
+    };
+    if let Some(tantivy_ast) = source.to_phrase_ast() {
+        return Ok(tantivy_ast);
+    }
+    Ok(IntervalsTantivyQuery { source }.into())
+}
+
+/// Compiled intervals or span rule, whose leaves are terms.
+#[derive(Clone, Debug)]
+pub(super) enum IntervalsSource {
+    Term(Term),
+    AnyOf(Vec<IntervalsSource>),
+    AllOf {
+        sources: Vec<IntervalsSource>,
+        ordered: bool,
+        max_gaps: Option<u32>,
+    },
+    /// Intervals of the source ending before the position `end`.
+    EndsBefore {
+        source: Box<IntervalsSource>,
+        end: u32,
+    },
+    /// Intervals of `include` that do not overlap any interval of `exclude`, once extended by
+    /// `pre` positions before and `post` positions after.
+    NotOverlapping {
+        include: Box<IntervalsSource>,
+        exclude: Box<IntervalsSource>,
+        pre: u32,
+        post: u32,
+    },
+}
+
+impl IntervalsSource {
+    /// Compiles the source into term and phrase queries, which do not need to compute the
+    /// intervals of the matched documents. Returns `None` if the source has no such equivalent.
+    ///
+    /// The slop of a tantivy phrase query bounds the gaps between each pair of consecutive terms,
+    /// so only ordered sequences of terms without gaps, or of two terms, are compiled into phrase
+    /// queries.
+    fn to_phrase_ast(&self) -> Option<TantivyQueryAst> {
+        match self {
+            IntervalsSource::Term(term) => {
+                Some(TantivyTermQuery::new(term.clone(), IndexRecordOption::WithFreqs).into())
+            }
+            IntervalsSource::AnyOf(sources) => {
+                let should_asts = sources
+                    .iter()
+                    .map(|source| source.to_phrase_ast())
+                    .collect::<Option<Vec<_>>>()?;
+                Some(TantivyBoolQuery::build_clause(BooleanOperand::Or, should_asts).into())
+            }
+            IntervalsSource::AllOf {
+                sources,
+                ordered: true,
+                max_gaps: Some(max_gaps),
+            } if sources.len() == 2 || (sources.len() > 2 && *max_gaps == 0) => {
+                let terms = sources
+                    .iter()
+                    .map(|source| match source {
+                        IntervalsSource::Term(term) => Some(term.clone()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<Term>>>()?;
+                let mut phrase_query = TantivyPhraseQuery::new(terms);
+                phrase_query.set_slop(*max_gaps);
+                Some(phrase_query.into())
+            }
+            IntervalsSource::AllOf { .. }
+            | IntervalsSource::EndsBefore { .. }
+            | IntervalsSource::NotOverlapping { .. } => None,
+        }
+    }
+
+    fn visit_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        match self {
+            IntervalsSource::Term(term) => visitor(term, true),
+            IntervalsSource::AnyOf(sources) | IntervalsSource::AllOf { sources, .. } => {
+                for source in sources {
+                    source.visit_terms(visitor);
+                }
+            }
+            IntervalsSource::EndsBefore { source, .. } => source.visit_terms(visitor),
+            IntervalsSource::NotOverlapping {
+                include, exclude, ..
+            } => {
+                include.visit_terms(visitor);
+                exclude.visit_terms(visitor);
+            }
+        }
+    }
+
+    fn open(&self, reader: &SegmentReader) -> tantivy::Result<IntervalsNode> {
+        match self {
+            IntervalsSource::Term(term) => {
+                let postings_opt = reader
+                    .inverted_index(term.field())?
+                    .read_postings(term, IndexRecordOption::WithFreqsAndPositions)?;
+                Ok(match postings_opt {
+                    Some(postings) => IntervalsNode::Term {
+                        postings,
+                        positions: Vec::new(),
+                    },
+                    None => IntervalsNode::Empty,
+                })
+            }
+            IntervalsSource::AnyOf(sources) => {
+                let nodes = sources
+                    .iter()
+                    .map(|source| source.open(reader))
+                    .collect::<tantivy::Result<Vec<_>>>()?;
+                Ok(IntervalsNode::AnyOf(nodes))
+            }
+            IntervalsSource::AllOf {
+                sources,
+                ordered,
+                max_gaps,
+            } => {
+                let nodes = sources
+                    .iter()
+                    .map(|source| source.open(reader))
+                    .collect::<tantivy::Result<Vec<_>>>()?;
+                Ok(IntervalsNode::AllOf {
+                    nodes,
+                    ordered: *ordered,
+                    max_gaps: *max_gaps,
+                })
+            }
+            IntervalsSource::EndsBefore { source, end } => Ok(IntervalsNode::EndsBefore {
+                node: Box::new(source.open(reader)?),
+                end: *end,
+            }),
+            IntervalsSource::NotOverlapping {
+                include,
+                exclude,
+                pre,
+                post,
+            } => Ok(IntervalsNode::NotOverlapping {
+                include: Box::new(include.open(reader)?),
+                exclude: Box::new(exclude.open(reader)?),
+                pre: *pre,
+                post: *post,
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct IntervalsTantivyQuery {
+    source: IntervalsSource,
+}
+
+impl crate::TantivyQuery for IntervalsTantivyQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(IntervalsWeight {
+            source: self.source.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.source.visit_terms(visitor);
+    }
+}
+
+struct IntervalsWeight {
+    source: IntervalsSource,
+}
+
+impl Weight for IntervalsWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let mut scorer = IntervalsScorer {
+            root: self.source.open(reader)?,
+            doc: 0,
+            boost,
+            intervals: Vec::new(),
+        };
+        scorer.advance_from(0);
+        Ok(Box::new(scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("IntervalsQuery", scorer.score()))
+    }
+}
+
+/// Interval of positions, bounds included.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+struct Interval {
+    start: u32,
+    end: u32,
+}
+
+impl Interval {
+    fn width(&self) -> u32 {
+        self.end - self.start + 1
+    }
+}
+
+/// Sorts the intervals and removes the ones containing another interval.
+///
+/// The remaining intervals have strictly increasing starts and ends.
+fn minimize_intervals(intervals: &mut Vec<Interval>) {
+    intervals.sort_unstable();
+    intervals.dedup();
+    let mut num_minimal_intervals = 0;
+    for idx in 0..intervals.len() {
+        let interval = intervals[idx];
+        if num_minimal_intervals > 0 && intervals[num_minimal_intervals - 1].start == interval.start
+        {
+            // The previous interval has the same start and a smaller end.
+            continue;
+        }
+        while num_minimal_intervals > 0 && intervals[num_minimal_intervals - 1].end >= interval.end
+        {
+            num_minimal_intervals -= 1;
+        }
+        intervals[num_minimal_intervals] = interval;
+        num_minimal_intervals += 1;
+    }
+    intervals.truncate(num_minimal_intervals);
+}
+
+/// Combines the minimal intervals of each source into the minimal intervals spanning one
+/// interval of each source, in order and without overlap if `ordered` is true.
+fn all_of_intervals(
+    sources_intervals: &[Vec<Interval>],
+    ordered: bool,
+    max_gaps: Option<u32>,
+    output: &mut Vec<Interval>,
+) {
+    let mut push_if_within_gaps = |start: u32, end: u32, total_width: u32| {
+        let gaps = (end - start + 1).saturating_sub(total_width);
+        if max_gaps.map(|max_gaps| gaps <= max_gaps).unwrap_or(true) {
+            output.push(Interval { start, end });
+        }
+    };
+    if ordered {
+        'first_interval: for first_interval in &sources_intervals[0] {
+            let mut end = first_interval.end;
+            let mut total_width = first_interval.width();
+            for source_intervals in &sources_intervals[1..] {
+                // Since the intervals are minimal, the first interval starting after `end`
+                // is also the one ending first.
+                let idx = source_intervals.partition_point(|interval| interval.start <= end);
+                let Some(next_interval) = source_intervals.get(idx) else {
+                    break 'first_interval;
+                };
+                end = next_interval.end;
+                total_width += next_interval.width();
+            }
+            push_if_within_gaps(first_interval.start, end, total_width);
+        }
+    } else {
+        // Each interval is tried as the leftmost interval of the span.
+        for (leftmost_source_ord, leftmost_source_intervals) in sources_intervals.iter().enumerate()
+        {
+            'leftmost_interval: for leftmost_interval in leftmost_source_intervals {
+                let mut end = leftmost_interval.end;
+                let mut total_width = leftmost_interval.width();
+                for (source_ord, source_intervals) in sources_intervals.iter().enumerate() {
+                    if source_ord == leftmost_source_ord {
+                        continue;
+                    }
+                    let idx = source_intervals
+                        .partition_point(|interval| interval.start < leftmost_interval.start);
+                    let Some(interval) = source_intervals.get(idx) else {
+                        continue 'leftmost_interval;
+                    };
+                    end = end.max(interval.end);
+                    total_width += interval.width();
+                }
+                push_if_within_gaps(leftmost_interval.start, end, total_width);
+            }
+        }
+    }
+    minimize_intervals(output);
+}
+
+/// Per-segment counterpart of `IntervalsSource`.
+enum IntervalsNode {
+    Term {
+        postings: SegmentPostings,
+        positions: Vec<u32>,
+    },
+    AnyOf(Vec<IntervalsNode>),
+    AllOf {
+        nodes: Vec<IntervalsNode>,
+        ordered: bool,
+        max_gaps: Option<u32>,
+    },
+    EndsBefore {
+        node: Box<IntervalsNode>,
+        end: u32,
+    },
+    NotOverlapping {
+        include: Box<IntervalsNode>,
+        exclude: Box<IntervalsNode>,
+        pre: u32,
+        post: u32,
+    },
+    Empty,
+}
+
+impl IntervalsNode {
+    /// Returns the first doc greater or equal to `target` containing the terms required for
+    /// the node to match. The positions of the terms are not checked.
+    fn seek_candidate(&mut self, target: DocId) -> DocId {
+        match self {
+            IntervalsNode::Term { postings, .. } => {
+                if postings.doc() < target {
+                    postings.seek(target)
+                } else {
+                    postings.doc()
+                }
+            }
+            IntervalsNode::AnyOf(nodes) => nodes
+                .iter_mut()
+                .map(|node| node.seek_candidate(target))
+                .min()
+                .unwrap_or(TERMINATED),
+            IntervalsNode::AllOf { nodes, .. } => {
+                let mut candidate = target;
+                loop {
+                    let mut is_candidate_in_all_nodes = true;
+                    for node in nodes.iter_mut() {
+                        let node_candidate = node.seek_candidate(candidate);
+                        if node_candidate == TERMINATED {
+                            return TERMINATED;
+                        }
+                        if node_candidate != candidate {
+                            is_candidate_in_all_nodes = false;
+                            candidate = node_candidate;
+                        }
+                    }
+                    if is_candidate_in_all_nodes {
+                        return candidate;
+                    }
+                }
+            }
+            // The end positions and the excluded intervals are checked with the intervals.
+            IntervalsNode::EndsBefore { node, .. }
+            | IntervalsNode::NotOverlapping { include: node, .. } => node.seek_candidate(target),
+            IntervalsNode::Empty => TERMINATED,
+        }
+    }
+
+    /// Appends the minimal intervals matched by the node in `doc` to `output`.
+    ///
+    /// The node must have been positioned with `seek_candidate`.
+    fn intervals(&mut self, doc: DocId, output: &mut Vec<Interval>) {
+        match self {
+            IntervalsNode::Term {
+                postings,
+                positions,
+            } => {
+                if postings.doc() != doc {
+                    return;
+                }
+                positions.clear();
+                postings.positions(positions);
+                output.extend(positions.iter().map(|&position| Interval {
+                    start: position,
+                    end: position,
+                }));
+            }
+            IntervalsNode::AnyOf(nodes) => {
+                let mut any_of_intervals = Vec::new();
+                for node in nodes.iter_mut() {
+                    node.intervals(doc, &mut any_of_intervals);
+                }
+                minimize_intervals(&mut any_of_intervals);
+                output.extend(any_of_intervals);
+            }
+            IntervalsNode::AllOf {
+                nodes,
+                ordered,
+                max_gaps,
+            } => {
+                let mut nodes_intervals = Vec::with_capacity(nodes.len());
+                for node in nodes.iter_mut() {
+                    let mut node_intervals = Vec::new();
+                    node.intervals(doc, &mut node_intervals);
+                    if node_intervals.is_empty() {
+                        return;
+                    }
+                    nodes_intervals.push(node_intervals);
+                }
+                let mut all_of_intervals = Vec::new();
+                all_of_intervals(&nodes_intervals, *ordered, *max_gaps, &mut all_of_intervals);
+                output.extend(all_of_intervals);
+            }
+            IntervalsNode::EndsBefore { node, end } => {
+                let mut node_intervals = Vec::new();
+                node.intervals(doc, &mut node_intervals);
+                output.extend(
+                    node_intervals
+                        .into_iter()
+                        .filter(|interval| interval.end < *end),
+                );
+            }
+            IntervalsNode::NotOverlapping {
+                include,
+                exclude,
+                pre,
+                post,
+            } => {
+                let mut include_intervals = Vec::new();
+                include.intervals(doc, &mut include_intervals);
+                if include_intervals.is_empty() {
+                    return;
+                }
+                // Unlike the included node, the excluded node has not been positioned yet.
+                let mut exclude_intervals = Vec::new();
+                if exclude.seek_candidate(doc) == doc {
+                    exclude.intervals(doc, &mut exclude_intervals);
+                }
+                output.extend(include_intervals.into_iter().filter(|interval| {
+                    !exclude_intervals.iter().any(|exclude_interval| {
+                        exclude_interval.start <= interval.end.saturating_add(*post)
+                            && exclude_interval.end.saturating_add(*pre) >= interval.start
+                    })
+                }));
+            }
+            IntervalsNode::Empty => {}
+        }
+    }
+
+    fn size_hint(&self) -> u32 {
+        match self {
+            IntervalsNode::Term { postings, .. } => postings.size_hint(),
+            IntervalsNode::AnyOf(nodes) => nodes
+                .iter()
+                .map(|node| node.size_hint())
+                .fold(0u32, |acc, size_hint| acc.saturating_add(size_hint)),
+            IntervalsNode::AllOf { nodes, .. } => {
+                nodes.iter().map(|node| node.size_hint()).min().unwrap_or(0)
+            }
+            IntervalsNode::EndsBefore { node, .. }
+            | IntervalsNode::NotOverlapping { include: node, .. } => node.size_hint(),
+            IntervalsNode::Empty => 0,
+        }
+    }
+}
+
+struct IntervalsScorer {
+    root: IntervalsNode,
+    doc: DocId,
+    boost: Score,
+    intervals: Vec<Interval>,
+}
+
+impl IntervalsScorer {
+    /// Positions the scorer on the first matching doc greater or equal to `target`.
+    fn advance_from(&mut self, mut target: DocId) -> DocId {
+        loop {
+            let candidate = self.root.seek_candidate(target);
+            if candidate == TERMINATED {
+                self.doc = TERMINATED;
+                return TERMINATED;
+            }
+            self.intervals.clear();
+            self.root.intervals(candidate, &mut self.intervals);
+            if !self.intervals.is_empty() {
+                self.doc = candidate;
+                return candidate;
+            }
+            target = candidate + 1;
+        }
+    }
+}
+
+impl DocSet for IntervalsScorer {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        self.advance_from(self.doc + 1)
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.advance_from(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.root.size_hint()
+    }
+}
+
+impl Scorer for IntervalsScorer {
+    /// Narrow intervals contribute more to the score than wide ones.
+    fn score(&mut self) -> Score {
+        let score: Score = self
+            .intervals
+            .iter()
+            .map(|interval| 1.0 / interval.width() as Score)
+            .sum();
+        self.boost * score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{Count, TopDocs};
+    use tantivy::schema::{Schema, STRING, TEXT};
+    use tantivy::{doc, DocAddress, Index};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{self, search_docs};
+
+    fn match_rule(query: &str, max_gaps: i32, ordered: bool) -> IntervalsRule {
+        IntervalsRule::Match(IntervalsMatchRule {
+            query: query.to_string(),
+            max_gaps,
+            ordered,
+            analyzer: None,
+        })
+    }
+
+    fn search_rule(index: &Index, rule: IntervalsRule) -> Vec<u32> {
+        let query_ast = QueryAst::from(IntervalsQuery {
+            field: "body".to_string(),
+            rule,
+        });
+        search_docs(index, &query_ast)
+    }
+
+    fn build_test_index() -> Index {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_text_field("id", STRING);
+        let docs = [
+            "connection refused by remote host",
+            "remote host refused the connection",
+            "connection to the remote server was refused by host",
+            "nothing to see here",
+        ]
+        .into_iter()
+        .map(|body| doc!(body_field => body));
+        test_helpers::build_test_index(schema_builder.build(), docs)
+    }
+
+    #[test]
+    fn test_minimize_intervals() {
+        let interval = |start, end| Interval { start, end };
+        let mut intervals = vec![
+            interval(3, 6),
+            interval(0, 4),
+            interval(1, 2),
+            interval(1, 3),
+            interval(4, 5),
+            interval(4, 5),
+        ];
+        minimize_intervals(&mut intervals);
+        assert_eq!(intervals, vec![interval(1, 2), interval(4, 5)]);
+    }
+
+    #[test]
+    fn test_intervals_match_rule() {
+        let index = build_test_index();
+        assert_eq!(
+            search_rule(&index, match_rule("connection refused", -1, false)),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            search_rule(&index, match_rule("connection refused", -1, true)),
+            vec![0, 2]
+        );
+        assert_eq!(
+            search_rule(&index, match_rule("connection refused", 0, true)),
+            vec![0]
+        );
+        assert_eq!(
+            search_rule(&index, match_rule("connection refused", 3, false)),
+            vec![0, 1]
+        );
+        assert!(search_rule(&index, match_rule("", -1, false)).is_empty());
+    }
+
+    #[test]
+    fn test_intervals_any_of_all_of_rules() {
+        let index = build_test_index();
+        let rule = IntervalsRule::AllOf(IntervalsAllOfRule {
+            intervals: vec![
+                match_rule("remote", -1, false),
+                IntervalsRule::AnyOf(IntervalsAnyOfRule {
+                    intervals: vec![
+                        match_rule("host", -1, false),
+                        match_rule("server", -1, false),
+                    ],
+                }),
+            ],
+            max_gaps: 0,
+            ordered: true,
+        });
+        assert_eq!(search_rule(&index, rule), vec![0, 1, 2]);
+
+        let rule = IntervalsRule::AllOf(IntervalsAllOfRule {
+            intervals: vec![
+                match_rule("refused", -1, false),
+                match_rule("remote host", 0, true),
+            ],
+            max_gaps: 1,
+            ordered: true,
+        });
+        assert_eq!(search_rule(&index, rule), vec![0]);
+    }
+
+    #[test]
+    fn test_intervals_query_scores_narrow_intervals_higher() {
+        let index = build_test_index();
+        let query = QueryAst::from(IntervalsQuery {
+            field: "body".to_string(),
+            rule: match_rule("connection refused", -1, false),
+        })
+        .build_tantivy_query(&index.schema(), &[], true, true)
+        .unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 0));
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_intervals_query_ordered_unordered_and_slop() {
+        let index = build_test_index();
+        // Ordered sequences without gaps, compiled to exact phrase queries.
+        assert_eq!(
+            search_rule(&index, match_rule("remote host", 0, true)),
+            vec![0, 1]
+        );
+        assert!(search_rule(&index, match_rule("host remote", 0, true)).is_empty());
+        // Ordered sequences of two terms with gaps, compiled to sloppy phrase queries.
+        assert_eq!(
+            search_rule(&index, match_rule("refused host", 2, true)),
+            vec![0, 2]
+        );
+        assert_eq!(
+            search_rule(&index, match_rule("refused host", 1, true)),
+            vec![2]
+        );
+        assert_eq!(
+            search_rule(&index, match_rule("remote host", -1, true)),
+            vec![0, 1, 2]
+        );
+        // Unordered sequences.
+        assert_eq!(
+            search_rule(&index, match_rule("host remote", 0, false)),
+            vec![0, 1]
+        );
+        // The gaps of a sequence of three terms add up, unlike the slop of phrase queries.
+        assert_eq!(
+            search_rule(&index, match_rule("connection remote host", 2, true)),
+            vec![0]
+        );
+        assert!(search_rule(&index, match_rule("connection remote host", 1, true)).is_empty());
+
+        let any_of_rule = IntervalsRule::AnyOf(IntervalsAnyOfRule {
+            intervals: vec![
+                match_rule("remote host", 0, true),
+                match_rule("server", -1, false),
+            ],
+        });
+        assert_eq!(search_rule(&index, any_of_rule), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_intervals_query_requires_positions() {
+        let index = build_test_index();
+        let error = QueryAst::from(IntervalsQuery {
+            field: "id".to_string(),
+            rule: match_rule("hello", -1, false),
+        })
+        .build_tantivy_query(&index.schema(), &[], true, true)
+        .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+}
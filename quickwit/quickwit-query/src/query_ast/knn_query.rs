@@ -0,0 +1,164 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema as TantivySchema;
+
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{BuildTantivyAst, QueryAst};
+use crate::{find_field_or_hit_dynamic, InvalidQuery, NotNaNf32};
+
+/// Maximum number of candidates a k-nearest neighbors query may consider.
+pub const MAX_KNN_NUM_CANDIDATES: u32 = 10_000;
+
+/// Matches the `k` documents whose vector stored in `field` is the most similar to
+/// `query_vector`, among `num_candidates` approximate nearest neighbors.
+///
+/// This is the query-side half of dense vector support: the doc mapping does not have dense
+/// vector fields yet, so building this query currently always fails with a schema error.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct KnnQuery {
+    pub field: String,
+    pub query_vector: Vec<NotNaNf32>,
+    pub k: u32,
+    pub num_candidates: u32,
+}
+
+impl From<KnnQuery> for QueryAst {
+    fn from(knn_query: KnnQuery) -> Self {
+        QueryAst::Knn(knn_query)
+    }
+}
+
+impl KnnQuery {
+    /// Checks the parameters of the query, independently of the schema.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.query_vector.is_empty() {
+            anyhow::bail!(
+                "The `query_vector` of a knn query on `{}` is empty.",
+                self.field
+            );
+        }
+        if self.k == 0 {
+            anyhow::bail!("The `k` of a knn query must be greater than 0.");
+        }
+        if self.num_candidates < self.k {
+            anyhow::bail!(
+                "The `num_candidates` of a knn query ({}) cannot be lower than `k` ({}).",
+                self.num_candidates,
+                self.k
+            );
+        }
+        if self.num_candidates > MAX_KNN_NUM_CANDIDATES {
+            anyhow::bail!(
+                "The `num_candidates` of a knn query ({}) cannot exceed \
+                 {MAX_KNN_NUM_CANDIDATES}.",
+                self.num_candidates
+            );
+        }
+        Ok(())
+    }
+}
+
+impl BuildTantivyAst for KnnQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        self.validate().map_err(InvalidQuery::Other)?;
+        let (_field, field_entry, _json_path) = find_field_or_hit_dynamic(&self.field, schema)?;
+        Err(InvalidQuery::SchemaError(format!(
+            "Field `{}` is not a dense vector field. Knn queries require a dense vector field.",
+            field_entry.name()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TEXT};
+
+    use super::*;
+
+    fn knn_query(query_vector: &[f32], k: u32, num_candidates: u32) -> KnnQuery {
+        KnnQuery {
+            field: "embedding".to_string(),
+            query_vector: query_vector
+                .iter()
+                .map(|value| NotNaNf32::try_from(*value).unwrap())
+                .collect(),
+            k,
+            num_candidates,
+        }
+    }
+
+    #[test]
+    fn test_knn_query_validate() {
+        knn_query(&[0.1, 0.2], 10, 10).validate().unwrap();
+        knn_query(&[0.1, 0.2], 10, MAX_KNN_NUM_CANDIDATES)
+            .validate()
+            .unwrap();
+        assert!(knn_query(&[], 10, 10).validate().is_err());
+        assert!(knn_query(&[0.1], 0, 10).validate().is_err());
+        assert!(knn_query(&[0.1], 10, 5).validate().is_err());
+        assert!(knn_query(&[0.1], 10, MAX_KNN_NUM_CANDIDATES + 1)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_knn_query_requires_dense_vector_field() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("embedding", TEXT);
+        let schema = schema_builder.build();
+        let invalid_query = knn_query(&[0.1, 0.2], 3, 5)
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap_err();
+        let InvalidQuery::SchemaError(error) = invalid_query else {
+            panic!("unexpected error: {invalid_query:?}");
+        };
+        assert!(error.contains("is not a dense vector field"));
+
+        let missing_field_query = KnnQuery {
+            field: "missing".to_string(),
+            ..knn_query(&[0.1, 0.2], 3, 5)
+        };
+        let invalid_query = missing_field_query
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap_err();
+        assert!(matches!(
+            invalid_query,
+            InvalidQuery::FieldDoesNotExist { .. }
+        ));
+    }
+
+    #[test]
+    fn test_knn_query_serde() {
+        let query_ast: QueryAst = serde_json::from_str(
+            r#"{"type": "knn", "field": "embedding", "query_vector": [0.5, 1.0], "k": 2,
+            "num_candidates": 4}"#,
+        )
+        .unwrap();
+        assert_eq!(query_ast, knn_query(&[0.5, 1.0], 2, 4).into());
+    }
+}
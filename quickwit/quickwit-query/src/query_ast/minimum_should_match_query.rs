@@ -0,0 +1,265 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::query::{EmptyScorer, EnableScoring, Explanation, Scorer, Weight};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+
+use crate::TantivyQuery;
+
+/// Matches the documents matching at least `minimum_should_match` of its clauses.
+///
+/// The score of a document is the sum of the scores of the matching clauses.
+#[derive(Debug)]
+pub(crate) struct MinimumShouldMatchQuery {
+    clauses: Vec<Box<dyn TantivyQuery>>,
+    minimum_should_match: usize,
+}
+
+impl MinimumShouldMatchQuery {
+    pub fn new(clauses: Vec<Box<dyn TantivyQuery>>, minimum_should_match: usize) -> Self {
+        MinimumShouldMatchQuery {
+            clauses,
+            minimum_should_match,
+        }
+    }
+}
+
+impl Clone for MinimumShouldMatchQuery {
+    fn clone(&self) -> Self {
+        MinimumShouldMatchQuery {
+            clauses: self
+                .clauses
+                .iter()
+                .map(|clause| clause.box_clone())
+                .collect(),
+            minimum_should_match: self.minimum_should_match,
+        }
+    }
+}
+
+impl TantivyQuery for MinimumShouldMatchQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let weights = self
+            .clauses
+            .iter()
+            .map(|clause| clause.weight(enable_scoring))
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        Ok(Box::new(MinimumShouldMatchWeight {
+            weights,
+            minimum_should_match: self.minimum_should_match,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for clause in &self.clauses {
+            clause.query_terms(visitor);
+        }
+    }
+}
+
+struct MinimumShouldMatchWeight {
+    weights: Vec<Box<dyn Weight>>,
+    minimum_should_match: usize,
+}
+
+impl Weight for MinimumShouldMatchWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        if self.weights.len() < self.minimum_should_match {
+            return Ok(Box::new(EmptyScorer));
+        }
+        let scorers = self
+            .weights
+            .iter()
+            .map(|weight| weight.scorer(reader, boost))
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        let mut scorer = MinimumShouldMatchScorer {
+            scorers,
+            minimum_should_match: self.minimum_should_match,
+            doc: TERMINATED,
+        };
+        scorer.advance_until_match();
+        Ok(Box::new(scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        let mut explanation = Explanation::new("MinimumShouldMatch", scorer.score());
+        for weight in &self.weights {
+            if let Ok(clause_explanation) = weight.explain(reader, doc) {
+                explanation.add_detail(clause_explanation);
+            }
+        }
+        Ok(explanation)
+    }
+}
+
+struct MinimumShouldMatchScorer {
+    scorers: Vec<Box<dyn Scorer>>,
+    minimum_should_match: usize,
+    doc: DocId,
+}
+
+impl MinimumShouldMatchScorer {
+    /// Positions the scorer on the smallest doc matched by enough clauses, starting from the
+    /// current position of the clauses.
+    fn advance_until_match(&mut self) -> DocId {
+        loop {
+            let min_doc = self
+                .scorers
+                .iter()
+                .map(|scorer| scorer.doc())
+                .min()
+                .unwrap_or(TERMINATED);
+            if min_doc == TERMINATED {
+                self.doc = TERMINATED;
+                return TERMINATED;
+            }
+            let num_matching_clauses = self
+                .scorers
+                .iter()
+                .filter(|scorer| scorer.doc() == min_doc)
+                .count();
+            if num_matching_clauses >= self.minimum_should_match {
+                self.doc = min_doc;
+                return min_doc;
+            }
+            for scorer in &mut self.scorers {
+                if scorer.doc() == min_doc {
+                    scorer.advance();
+                }
+            }
+        }
+    }
+}
+
+impl DocSet for MinimumShouldMatchScorer {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        for scorer in &mut self.scorers {
+            if scorer.doc() == self.doc {
+                scorer.advance();
+            }
+        }
+        self.advance_until_match()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        for scorer in &mut self.scorers {
+            if scorer.doc() < target {
+                scorer.seek(target);
+            }
+        }
+        self.advance_until_match()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorers
+            .iter()
+            .map(|scorer| scorer.size_hint())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Scorer for MinimumShouldMatchScorer {
+    fn score(&mut self) -> Score {
+        let doc = self.doc;
+        self.scorers
+            .iter_mut()
+            .filter(|scorer| scorer.doc() == doc)
+            .map(|scorer| scorer.score())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{Count, TopDocs};
+    use tantivy::query::{QueryParser, TermQuery as TantivyTermQuery};
+    use tantivy::schema::{IndexRecordOption, Schema, TEXT};
+    use tantivy::{doc, DocAddress, Index, Term};
+
+    use super::MinimumShouldMatchQuery;
+    use crate::TantivyQuery;
+
+    #[test]
+    fn test_minimum_should_match_query() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for body in ["a b c", "a b", "a", "b c", "d"] {
+            index_writer.add_document(doc!(body_field => body)).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let clauses = |tokens: &[&str]| -> Vec<Box<dyn TantivyQuery>> {
+            tokens
+                .iter()
+                .map(|token| {
+                    let term = Term::from_field_text(body_field, token);
+                    Box::new(TantivyTermQuery::new(term, IndexRecordOption::WithFreqs))
+                        as Box<dyn TantivyQuery>
+                })
+                .collect()
+        };
+        let query = MinimumShouldMatchQuery::new(clauses(&["a", "b", "c"]), 2);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        let mut docs: Vec<DocAddress> = top_docs.into_iter().map(|(_, doc)| doc).collect();
+        docs.sort();
+        let expected_docs: Vec<DocAddress> = [0, 1, 3]
+            .into_iter()
+            .map(|doc| DocAddress::new(0, doc))
+            .collect();
+        assert_eq!(docs, expected_docs);
+
+        let query = MinimumShouldMatchQuery::new(clauses(&["a", "b", "c"]), 3);
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 1);
+
+        let query = MinimumShouldMatchQuery::new(clauses(&["a", "b"]), 3);
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 0);
+
+        // The score is the sum of the score of the matching clauses.
+        let query = MinimumShouldMatchQuery::new(clauses(&["a", "b"]), 1);
+        let union_query = QueryParser::for_index(&index, vec![body_field])
+            .parse_query("a OR b")
+            .unwrap();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        let union_top_docs = searcher
+            .search(&union_query, &TopDocs::with_limit(10))
+            .unwrap();
+        assert_eq!(top_docs.len(), 4);
+        for ((score, doc), (union_score, union_doc)) in top_docs.into_iter().zip(union_top_docs) {
+            assert_eq!(doc, union_doc);
+            assert!((score - union_score).abs() < 1e-5);
+        }
+    }
+}
@@ -18,28 +18,82 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use serde::{Deserialize, Serialize};
-use tantivy::query::BoostQuery as TantivyBoostQuery;
+use tantivy::query::{BoostQuery as TantivyBoostQuery, ConstScoreQuery as TantivyConstScoreQuery};
 use tantivy::schema::Schema as TantivySchema;
 
 mod bool_query;
+mod boosting_query;
+mod builder;
+mod combined_fields_query;
+mod dis_max_query;
+mod field_presence_query;
 mod full_text_query;
+mod function_score_query;
+mod intervals_query;
+mod knn_query;
+mod minimum_should_match_query;
+mod negative_boost_query;
+mod optimizer;
 mod phrase_prefix_query;
+mod profiled_query;
+mod query_string_printer;
 mod range_query;
+mod regex_query;
+mod simple_user_input_query;
+mod span_query;
+#[cfg(any(test, feature = "testsuite"))]
+pub mod strategies;
+mod synonyms;
 mod tantivy_query_ast;
 mod term_query;
 mod term_set_query;
+mod terms_lookup_query;
+#[cfg(test)]
+mod test_helpers;
+mod transformer;
+mod udf_query;
 mod user_input_query;
 pub(crate) mod utils;
+mod versioning;
 mod visitor;
 
 pub use bool_query::BoolQuery;
+pub use boosting_query::BoostingQuery;
+pub use combined_fields_query::CombinedFieldsQuery;
+pub use dis_max_query::DisMaxQuery;
+pub use field_presence_query::{
+    compute_field_presence_hash, FieldPresenceQuery, FIELD_PRESENCE_FIELD_NAME,
+};
 pub use full_text_query::{FullTextMode, FullTextParams, FullTextQuery};
+pub use function_score_query::{
+    DecayFunction, DecayFunctionType, DistanceFeatureFunction, FieldValueFactorFunction,
+    FieldValueFactorModifier, FunctionBoostMode, FunctionScoreMode, FunctionScoreQuery,
+    ScoreFunction, ScoreFunctionKind,
+};
+pub use intervals_query::{
+    IntervalsAllOfRule, IntervalsAnyOfRule, IntervalsMatchRule, IntervalsQuery, IntervalsRule,
+};
+pub use knn_query::{KnnQuery, MAX_KNN_NUM_CANDIDATES};
+pub use optimizer::optimize_query_ast;
 pub use phrase_prefix_query::PhrasePrefixQuery;
+pub use profiled_query::{QueryProfile, QueryProfileBreakdown, QueryProfiler};
 pub use range_query::RangeQuery;
+pub use regex_query::{RegexQuery, MAX_DETERMINIZED_STATES, MAX_REGEX_LENGTH};
+pub use simple_user_input_query::{
+    SimpleQueryStringFlag, SimpleQueryStringFlags, SimpleUserInputQuery,
+};
+pub use span_query::{
+    SpanFirstRule, SpanNearRule, SpanNotRule, SpanOrRule, SpanQuery, SpanRule, SpanTermRule,
+};
+pub use synonyms::SynonymDictionary;
 use tantivy_query_ast::TantivyQueryAst;
 pub use term_query::TermQuery;
 pub use term_set_query::TermSetQuery;
+pub use terms_lookup_query::{TermsLookupQuery, MAX_TERMS_LOOKUP_NUM_TERMS};
+pub use transformer::QueryAstTransformer;
+pub use udf_query::{UdfMode, UdfQuery};
 pub use user_input_query::UserInputQuery;
+pub use versioning::{query_ast_from_json, query_ast_to_json, QUERY_AST_VERSION};
 pub use visitor::QueryAstVisitor;
 
 use crate::{InvalidQuery, NotNaNf32};
@@ -49,18 +103,36 @@ use crate::{InvalidQuery, NotNaNf32};
 #[serde(rename_all = "snake_case")]
 pub enum QueryAst {
     Bool(BoolQuery),
+    DisMax(DisMaxQuery),
+    Boosting(BoostingQuery),
     Term(TermQuery),
     TermSet(TermSetQuery),
+    TermsLookup(TermsLookupQuery),
     FullText(FullTextQuery),
+    CombinedFields(CombinedFieldsQuery),
     PhrasePrefix(PhrasePrefixQuery),
+    Intervals(IntervalsQuery),
+    Span(SpanQuery),
+    Knn(KnnQuery),
+    Regex(RegexQuery),
     Range(RangeQuery),
+    FieldPresence(FieldPresenceQuery),
     UserInput(UserInputQuery),
+    SimpleUserInput(SimpleUserInputQuery),
     MatchAll,
     MatchNone,
     Boost {
         underlying: Box<QueryAst>,
         boost: NotNaNf32,
     },
+    /// Matches the same documents as `underlying`, all with the same `score`. The underlying
+    /// query is executed with scoring disabled.
+    ConstScore {
+        underlying: Box<QueryAst>,
+        score: NotNaNf32,
+    },
+    Udf(UdfQuery),
+    FunctionScore(FunctionScoreQuery),
 }
 
 impl QueryAst {
@@ -74,6 +146,7 @@ impl QueryAst {
                 must_not,
                 should,
                 filter,
+                minimum_should_match,
             }) => {
                 let must = parse_user_query_in_asts(must, default_search_fields)?;
                 let must_not = parse_user_query_in_asts(must_not, default_search_fields)?;
@@ -84,19 +157,55 @@ impl QueryAst {
                     must_not,
                     should,
                     filter,
+                    minimum_should_match,
+                }
+                .into())
+            }
+            QueryAst::DisMax(DisMaxQuery {
+                queries,
+                tie_breaker,
+            }) => {
+                let queries = parse_user_query_in_asts(queries, default_search_fields)?;
+                Ok(DisMaxQuery {
+                    queries,
+                    tie_breaker,
+                }
+                .into())
+            }
+            QueryAst::Boosting(BoostingQuery {
+                positive,
+                negative,
+                negative_boost,
+            }) => {
+                let positive = positive.parse_user_query(default_search_fields)?;
+                let negative = negative.parse_user_query(default_search_fields)?;
+                Ok(BoostingQuery {
+                    positive: Box::new(positive),
+                    negative: Box::new(negative),
+                    negative_boost,
                 }
                 .into())
             }
             ast @ QueryAst::Term(_)
             | ast @ QueryAst::TermSet(_)
+            | ast @ QueryAst::TermsLookup(_)
             | ast @ QueryAst::FullText(_)
+            | ast @ QueryAst::CombinedFields(_)
             | ast @ QueryAst::PhrasePrefix(_)
+            | ast @ QueryAst::Intervals(_)
+            | ast @ QueryAst::Span(_)
+            | ast @ QueryAst::Knn(_)
+            | ast @ QueryAst::Regex(_)
             | ast @ QueryAst::MatchAll
             | ast @ QueryAst::MatchNone
-            | ast @ QueryAst::Range(_) => Ok(ast),
+            | ast @ QueryAst::Range(_)
+            | ast @ QueryAst::FieldPresence(_) => Ok(ast),
             QueryAst::UserInput(user_text_query) => {
                 user_text_query.parse_user_query(default_search_fields)
             }
+            QueryAst::SimpleUserInput(simple_user_input_query) => {
+                simple_user_input_query.parse_user_query(default_search_fields)
+            }
             QueryAst::Boost { underlying, boost } => {
                 let underlying = underlying.parse_user_query(default_search_fields)?;
                 Ok(QueryAst::Boost {
@@ -104,6 +213,30 @@ impl QueryAst {
                     boost,
                 })
             }
+            QueryAst::ConstScore { underlying, score } => {
+                let underlying = underlying.parse_user_query(default_search_fields)?;
+                Ok(QueryAst::ConstScore {
+                    underlying: Box::new(underlying),
+                    score,
+                })
+            }
+            QueryAst::Udf(udf_query) => {
+                let underlying = udf_query
+                    .underlying
+                    .parse_user_query(default_search_fields)?;
+                Ok(UdfQuery {
+                    underlying: Box::new(underlying),
+                    ..udf_query
+                }
+                .into())
+            }
+            QueryAst::FunctionScore(function_score_query) => {
+                let function_score_query =
+                    function_score_query.try_map_subqueries(|query_ast| {
+                        query_ast.parse_user_query(default_search_fields)
+                    })?;
+                Ok(function_score_query.into())
+            }
         }
     }
 
@@ -138,11 +271,15 @@ trait BuildTantivyAst {
     ///
     /// This function is supposed to return an error if it detects a problem in the schema.
     /// It can call `into_tantivy_ast_call_me` but should never call `into_tantivy_ast_impl`.
+    ///
+    /// `with_scoring` is false when the scores of the node are discarded (filter context): the
+    /// node can then skip all the work related to scoring.
     fn build_tantivy_ast_impl(
         &self,
         schema: &TantivySchema,
         search_fields: &[String],
         with_validation: bool,
+        with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery>;
 
     /// This method is meant to be called, but should never be overloaded.
@@ -151,16 +288,40 @@ trait BuildTantivyAst {
         schema: &TantivySchema,
         search_fields: &[String],
         with_validation: bool,
+        with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
-        let tantivy_ast_res = self.build_tantivy_ast_impl(schema, search_fields, with_validation);
+        let tantivy_ast_res =
+            self.build_tantivy_ast_impl(schema, search_fields, with_validation, with_scoring);
         if !with_validation && tantivy_ast_res.is_err() {
             return match tantivy_ast_res {
-                res @ Ok(_) | res @ Err(InvalidQuery::UserQueryNotParsed) => res,
+                res @ Ok(_)
+                | res @ Err(InvalidQuery::UserQueryNotParsed)
+                | res @ Err(InvalidQuery::TermsLookupNotResolved) => res,
                 Err(_) => Ok(TantivyQueryAst::match_none()),
             };
         }
         tantivy_ast_res
     }
+
+    /// Builds a node in a filter context, i.e. whose scores are discarded.
+    ///
+    /// The node and its descendants are built with scoring disabled. If the parent node is
+    /// scored, the node is also wrapped so that it is executed with scoring disabled.
+    fn build_tantivy_ast_call_without_scoring(
+        &self,
+        schema: &TantivySchema,
+        search_fields: &[String],
+        with_validation: bool,
+        parent_with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let tantivy_ast =
+            self.build_tantivy_ast_call(schema, search_fields, with_validation, false)?;
+        if parent_with_scoring {
+            Ok(tantivy_ast.without_scoring())
+        } else {
+            Ok(tantivy_ast)
+        }
+    }
 }
 
 impl BuildTantivyAst for QueryAst {
@@ -169,37 +330,126 @@ impl BuildTantivyAst for QueryAst {
         schema: &TantivySchema,
         search_fields: &[String],
         with_validation: bool,
+        with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         match self {
-            QueryAst::Bool(bool_query) => {
-                bool_query.build_tantivy_ast_call(schema, search_fields, with_validation)
-            }
-            QueryAst::Term(term_query) => {
-                term_query.build_tantivy_ast_call(schema, search_fields, with_validation)
-            }
-            QueryAst::Range(range_query) => {
-                range_query.build_tantivy_ast_call(schema, search_fields, with_validation)
-            }
+            QueryAst::Bool(bool_query) => bool_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::DisMax(dis_max_query) => dis_max_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::Boosting(boosting_query) => boosting_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::Term(term_query) => term_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::Range(range_query) => range_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
             QueryAst::MatchAll => Ok(TantivyQueryAst::match_all()),
             QueryAst::MatchNone => Ok(TantivyQueryAst::match_none()),
             QueryAst::Boost { boost, underlying } => {
-                let underlying =
-                    underlying.build_tantivy_ast_call(schema, search_fields, with_validation)?;
+                let underlying = underlying.build_tantivy_ast_call(
+                    schema,
+                    search_fields,
+                    with_validation,
+                    with_scoring,
+                )?;
                 let boost_query = TantivyBoostQuery::new(underlying.into(), (*boost).into());
                 Ok(boost_query.into())
             }
-            QueryAst::TermSet(term_set) => {
-                term_set.build_tantivy_ast_call(schema, search_fields, with_validation)
-            }
-            QueryAst::FullText(full_text_query) => {
-                full_text_query.build_tantivy_ast_call(schema, search_fields, with_validation)
-            }
-            QueryAst::PhrasePrefix(phrase_prefix_query) => {
-                phrase_prefix_query.build_tantivy_ast_call(schema, search_fields, with_validation)
-            }
-            QueryAst::UserInput(user_text_query) => {
-                user_text_query.build_tantivy_ast_call(schema, search_fields, with_validation)
+            QueryAst::ConstScore { underlying, score } => {
+                let underlying = underlying.build_tantivy_ast_call_without_scoring(
+                    schema,
+                    search_fields,
+                    with_validation,
+                    with_scoring,
+                )?;
+                let const_score_query =
+                    TantivyConstScoreQuery::new(underlying.into(), (*score).into());
+                Ok(const_score_query.into())
             }
+            QueryAst::TermSet(term_set) => term_set.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::TermsLookup(terms_lookup_query) => terms_lookup_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::FullText(full_text_query) => full_text_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::PhrasePrefix(phrase_prefix_query) => phrase_prefix_query
+                .build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring),
+            QueryAst::Intervals(intervals_query) => intervals_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::Span(span_query) => span_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::CombinedFields(combined_fields_query) => combined_fields_query
+                .build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring),
+            QueryAst::Knn(knn_query) => knn_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::Regex(regex_query) => regex_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::FieldPresence(field_presence_query) => field_presence_query
+                .build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring),
+            QueryAst::UserInput(user_text_query) => user_text_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::SimpleUserInput(simple_user_input_query) => simple_user_input_query
+                .build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring),
+            QueryAst::Udf(udf_query) => udf_query.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            ),
+            QueryAst::FunctionScore(function_score_query) => function_score_query
+                .build_tantivy_ast_call(schema, search_fields, with_validation, with_scoring),
         }
     }
 }
@@ -210,10 +460,54 @@ impl QueryAst {
         schema: &TantivySchema,
         search_fields: &[String],
         with_validation: bool,
+        with_scoring: bool,
     ) -> Result<Box<dyn crate::TantivyQuery>, InvalidQuery> {
-        let tantivy_query_ast =
-            self.build_tantivy_ast_call(schema, search_fields, with_validation)?;
-        Ok(tantivy_query_ast.simplify().into())
+        let tantivy_query_ast = self.build_simplified_tantivy_ast(
+            schema,
+            search_fields,
+            with_validation,
+            with_scoring,
+        )?;
+        Ok(tantivy_query_ast.into())
+    }
+
+    /// Same as `build_tantivy_query`, but every node of the resulting query records the time
+    /// spent executing it. The timings are available through the returned `QueryProfiler`.
+    pub fn build_profiled_tantivy_query(
+        &self,
+        schema: &TantivySchema,
+        search_fields: &[String],
+        with_validation: bool,
+        with_scoring: bool,
+    ) -> Result<(Box<dyn crate::TantivyQuery>, QueryProfiler), InvalidQuery> {
+        let tantivy_query_ast = self.build_simplified_tantivy_ast(
+            schema,
+            search_fields,
+            with_validation,
+            with_scoring,
+        )?;
+        Ok(profiled_query::profile_tantivy_ast(tantivy_query_ast))
+    }
+
+    fn build_simplified_tantivy_ast(
+        &self,
+        schema: &TantivySchema,
+        search_fields: &[String],
+        with_validation: bool,
+        with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let tantivy_query_ast = if with_scoring {
+            self.build_tantivy_ast_call(schema, search_fields, with_validation, true)?
+        } else {
+            // The root has no parent: `true` forces the wrapping of the whole query.
+            self.build_tantivy_ast_call_without_scoring(
+                schema,
+                search_fields,
+                with_validation,
+                true,
+            )?
+        };
+        Ok(tantivy_query_ast.simplify())
     }
 }
 
@@ -228,9 +522,13 @@ fn parse_user_query_in_asts(
 
 #[cfg(test)]
 mod tests {
+    use tantivy::collector::TopDocs;
+    use tantivy::schema::{Schema, TEXT};
+    use tantivy::{doc, Index, Score};
+
     use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
-    use crate::query_ast::{BoolQuery, BuildTantivyAst, QueryAst, UserInputQuery};
-    use crate::InvalidQuery;
+    use crate::query_ast::{BoolQuery, BuildTantivyAst, QueryAst, TermQuery, UserInputQuery};
+    use crate::{InvalidQuery, NotNaNf32};
 
     #[test]
     fn test_user_query_not_parsed() {
@@ -238,11 +536,15 @@ mod tests {
             user_text: "*".to_string(),
             default_fields: Default::default(),
             default_operator: Default::default(),
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .into();
         let schema = tantivy::schema::Schema::builder().build();
         let build_tantivy_ast_err: InvalidQuery = query_ast
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap_err();
         assert!(matches!(
             build_tantivy_ast_err,
@@ -256,12 +558,16 @@ mod tests {
             user_text: "*".to_string(),
             default_fields: Default::default(),
             default_operator: Default::default(),
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .into();
         let query_ast_with_parsed_user_query: QueryAst = query_ast.parse_user_query(&[]).unwrap();
         let schema = tantivy::schema::Schema::builder().build();
         let tantivy_query_ast = query_ast_with_parsed_user_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         assert_eq!(&tantivy_query_ast, &TantivyQueryAst::match_all(),);
     }
@@ -272,6 +578,10 @@ mod tests {
             user_text: "*".to_string(),
             default_fields: Default::default(),
             default_operator: Default::default(),
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .into();
         let bool_query_ast: QueryAst = BoolQuery {
@@ -283,7 +593,7 @@ mod tests {
             bool_query_ast.parse_user_query(&[]).unwrap();
         let schema = tantivy::schema::Schema::builder().build();
         let tantivy_query_ast = query_ast_with_parsed_user_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let tantivy_query_ast_simplified = tantivy_query_ast.simplify();
         // This does not get more simplified than this, because we need the boost 0 score.
@@ -301,6 +611,10 @@ mod tests {
             user_text: "field:hello field:toto".to_string(),
             default_fields: None,
             default_operator: crate::BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .parse_user_query(&[])
         .unwrap();
@@ -314,10 +628,44 @@ mod tests {
             user_text: "field:hello field:toto".to_string(),
             default_fields: None,
             default_operator: crate::BooleanOperand::Or,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .parse_user_query(&[])
         .unwrap();
         let QueryAst::Bool(bool_query) = query_ast else { panic!() };
         assert_eq!(bool_query.should.len(), 2);
     }
+
+    #[test]
+    fn test_const_score_query() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(body_field => "hello hello hello"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(body_field => "hello world"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(body_field => "world"))
+            .unwrap();
+        index_writer.commit().unwrap();
+
+        let query_ast = QueryAst::ConstScore {
+            underlying: Box::new(TermQuery::from_field_value("body", "hello").into()),
+            score: NotNaNf32::try_from(2.5).unwrap(),
+        };
+        let query = query_ast
+            .build_tantivy_query(&index.schema(), &[], true, true)
+            .unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        let scores: Vec<Score> = top_docs.into_iter().map(|(score, _)| score).collect();
+        assert_eq!(scores, vec![2.5, 2.5]);
+    }
 }
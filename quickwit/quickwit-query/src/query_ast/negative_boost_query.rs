@@ -0,0 +1,209 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::query::{EnableScoring, Explanation, Scorer, Weight};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term};
+
+use crate::TantivyQuery;
+
+/// Matches the documents matching the `positive` query, and multiplies the score of the ones
+/// also matching the `negative` query by `negative_boost`.
+#[derive(Debug)]
+pub(crate) struct NegativeBoostQuery {
+    positive: Box<dyn TantivyQuery>,
+    negative: Box<dyn TantivyQuery>,
+    negative_boost: Score,
+}
+
+impl NegativeBoostQuery {
+    pub fn new(
+        positive: Box<dyn TantivyQuery>,
+        negative: Box<dyn TantivyQuery>,
+        negative_boost: Score,
+    ) -> Self {
+        NegativeBoostQuery {
+            positive,
+            negative,
+            negative_boost,
+        }
+    }
+}
+
+impl Clone for NegativeBoostQuery {
+    fn clone(&self) -> Self {
+        NegativeBoostQuery {
+            positive: self.positive.box_clone(),
+            negative: self.negative.box_clone(),
+            negative_boost: self.negative_boost,
+        }
+    }
+}
+
+impl TantivyQuery for NegativeBoostQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let positive_weight = self.positive.weight(enable_scoring)?;
+        // Only whether the negative query matches matters.
+        let negative_weight = match enable_scoring.searcher() {
+            Some(searcher) => self
+                .negative
+                .weight(EnableScoring::disabled_from_searcher(searcher))?,
+            None => self
+                .negative
+                .weight(EnableScoring::disabled_from_schema(enable_scoring.schema()))?,
+        };
+        Ok(Box::new(NegativeBoostWeight {
+            positive_weight,
+            negative_weight,
+            negative_boost: self.negative_boost,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.positive.query_terms(visitor);
+    }
+}
+
+struct NegativeBoostWeight {
+    positive_weight: Box<dyn Weight>,
+    negative_weight: Box<dyn Weight>,
+    negative_boost: Score,
+}
+
+impl Weight for NegativeBoostWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let positive_scorer = self.positive_weight.scorer(reader, boost)?;
+        let negative_scorer = self.negative_weight.scorer(reader, 1.0)?;
+        Ok(Box::new(NegativeBoostScorer {
+            positive_scorer,
+            negative_scorer,
+            negative_boost: self.negative_boost,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        let mut explanation = Explanation::new("NegativeBoost", scorer.score());
+        explanation.add_detail(self.positive_weight.explain(reader, doc)?);
+        Ok(explanation)
+    }
+}
+
+struct NegativeBoostScorer {
+    positive_scorer: Box<dyn Scorer>,
+    negative_scorer: Box<dyn Scorer>,
+    negative_boost: Score,
+}
+
+impl DocSet for NegativeBoostScorer {
+    fn advance(&mut self) -> DocId {
+        self.positive_scorer.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.positive_scorer.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.positive_scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.positive_scorer.size_hint()
+    }
+}
+
+impl Scorer for NegativeBoostScorer {
+    fn score(&mut self) -> Score {
+        let doc = self.positive_scorer.doc();
+        let score = self.positive_scorer.score();
+        if self.negative_scorer.doc() < doc {
+            self.negative_scorer.seek(doc);
+        }
+        if self.negative_scorer.doc() == doc {
+            score * self.negative_boost
+        } else {
+            score
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{Count, TopDocs};
+    use tantivy::query::TermQuery as TantivyTermQuery;
+    use tantivy::schema::{Field, IndexRecordOption, Schema, TEXT};
+    use tantivy::{doc, DocAddress, Index, Term};
+
+    use super::NegativeBoostQuery;
+    use crate::TantivyQuery;
+
+    fn term_query(field: Field, token: &str) -> Box<dyn TantivyQuery> {
+        let term = Term::from_field_text(field, token);
+        Box::new(TantivyTermQuery::new(term, IndexRecordOption::WithFreqs))
+    }
+
+    #[test]
+    fn test_negative_boost_query() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for body in ["apple pie stale", "apple pie", "pie stale", "stale"] {
+            index_writer.add_document(doc!(body_field => body)).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let query = NegativeBoostQuery::new(
+            term_query(body_field, "pie"),
+            term_query(body_field, "stale"),
+            0.5,
+        );
+        // The negative query demotes documents but does not exclude them.
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 3);
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 1));
+
+        let positive_query = term_query(body_field, "pie");
+        let positive_top_docs = searcher
+            .search(&positive_query, &TopDocs::with_limit(10))
+            .unwrap();
+        let positive_score = |doc: DocAddress| -> f32 {
+            positive_top_docs
+                .iter()
+                .find(|(_, positive_doc)| *positive_doc == doc)
+                .unwrap()
+                .0
+        };
+        for (score, doc) in top_docs {
+            let expected_score = if doc.doc_id == 1 {
+                positive_score(doc)
+            } else {
+                positive_score(doc) * 0.5
+            };
+            assert!((score - expected_score).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,631 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeSet, HashMap};
+use std::convert::Infallible;
+
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{BoolQuery, QueryAst, QueryAstTransformer, TermQuery, TermSetQuery};
+
+/// Rewrites a query AST into an equivalent, cheaper one.
+///
+/// The optimizer:
+/// - flattens the bool queries nested in a bool query clause of the same kind,
+/// - folds the `MatchAll` and `MatchNone` clauses,
+/// - deduplicates the identical clauses,
+/// - merges the term clauses targeting the same field into a term set query.
+///
+/// The rewrites preserve the set of matching documents. The rewrites that could alter the
+/// score of a document (deduplicating a `must` clause, turning scored term queries into a
+/// constant score term set query) are only applied to subtrees that are not scored, i.e. the
+/// `filter` and `must_not` clauses and the subtrees of a constant score query.
+pub fn optimize_query_ast(query_ast: QueryAst) -> QueryAst {
+    let mut optimizer = QueryAstOptimizer { with_scoring: true };
+    match optimizer.transform(query_ast) {
+        Ok(optimized_query_ast) => optimized_query_ast,
+        Err(infallible) => match infallible {},
+    }
+}
+
+struct QueryAstOptimizer {
+    // Whether the score of the subtree being transformed matters.
+    with_scoring: bool,
+}
+
+impl QueryAstOptimizer {
+    fn transform_without_scoring(
+        &mut self,
+        query_asts: Vec<QueryAst>,
+    ) -> Result<Vec<QueryAst>, Infallible> {
+        let with_scoring = std::mem::replace(&mut self.with_scoring, false);
+        let transform_result = self.transform_all(query_asts);
+        self.with_scoring = with_scoring;
+        transform_result
+    }
+}
+
+impl QueryAstTransformer for QueryAstOptimizer {
+    type Err = Infallible;
+
+    fn transform_bool(&mut self, bool_query: BoolQuery) -> Result<QueryAst, Infallible> {
+        let bool_query = BoolQuery {
+            must: self.transform_all(bool_query.must)?,
+            should: self.transform_all(bool_query.should)?,
+            filter: self.transform_without_scoring(bool_query.filter)?,
+            must_not: self.transform_without_scoring(bool_query.must_not)?,
+            minimum_should_match: bool_query.minimum_should_match,
+        };
+        Ok(optimize_bool_query(bool_query, self.with_scoring))
+    }
+
+    fn transform_boost(
+        &mut self,
+        underlying: QueryAst,
+        boost: NotNaNf32,
+    ) -> Result<QueryAst, Infallible> {
+        let underlying = self.transform(underlying)?;
+        if underlying == QueryAst::MatchNone {
+            return Ok(QueryAst::MatchNone);
+        }
+        Ok(QueryAst::Boost {
+            underlying: Box::new(underlying),
+            boost,
+        })
+    }
+
+    fn transform_const_score(
+        &mut self,
+        underlying: QueryAst,
+        score: NotNaNf32,
+    ) -> Result<QueryAst, Infallible> {
+        let underlying = self
+            .transform_without_scoring(vec![underlying])?
+            .pop()
+            .expect("the transformation should return one query AST per input query AST");
+        if underlying == QueryAst::MatchNone {
+            return Ok(QueryAst::MatchNone);
+        }
+        Ok(QueryAst::ConstScore {
+            underlying: Box::new(underlying),
+            score,
+        })
+    }
+}
+
+/// Optimizes a bool query whose clauses have already been optimized.
+fn optimize_bool_query(bool_query: BoolQuery, with_scoring: bool) -> QueryAst {
+    let BoolQuery {
+        must,
+        must_not,
+        should,
+        filter,
+        minimum_should_match,
+    } = flatten_bool_query(bool_query);
+
+    // Folding of the `MatchAll` and `MatchNone` clauses.
+    if must
+        .iter()
+        .chain(&filter)
+        .any(|clause| *clause == QueryAst::MatchNone)
+        || must_not.iter().any(|clause| *clause == QueryAst::MatchAll)
+    {
+        return QueryAst::MatchNone;
+    }
+    let mut must_not = must_not;
+    must_not.retain(|clause| *clause != QueryAst::MatchNone);
+
+    let mut should = should;
+    if minimum_should_match.is_none() {
+        let num_should_clauses = should.len();
+        should.retain(|clause| *clause != QueryAst::MatchNone);
+        // At least one should clause has to match when there are no must or filter clauses.
+        if num_should_clauses > 0 && should.is_empty() && must.is_empty() && filter.is_empty() {
+            return QueryAst::MatchNone;
+        }
+    }
+    // A `MatchAll` clause can only be removed if another clause makes the should clauses
+    // optional. A scored `MatchAll` clause contributes to the score of the matching documents.
+    let (mut must, mut filter) = (must, filter);
+    if must
+        .iter()
+        .chain(&filter)
+        .any(|clause| *clause != QueryAst::MatchAll)
+    {
+        filter.retain(|clause| *clause != QueryAst::MatchAll);
+        if !with_scoring {
+            must.retain(|clause| *clause != QueryAst::MatchAll);
+        }
+    }
+
+    // Deduplication of the identical clauses.
+    filter = dedup_clauses(filter);
+    must_not = dedup_clauses(must_not);
+    if !with_scoring {
+        must = dedup_clauses(must);
+        if minimum_should_match.is_none() {
+            should = dedup_clauses(should);
+        }
+    }
+
+    // Merging of the term clauses. `NOT a AND NOT b` is equivalent to `NOT (a OR b)`.
+    must_not = merge_term_clauses(must_not);
+    if !with_scoring && minimum_should_match.is_none() {
+        should = merge_term_clauses(should);
+    }
+
+    match (
+        must.len(),
+        should.len(),
+        filter.len(),
+        must_not.len(),
+        &minimum_should_match,
+    ) {
+        (0, 0, 0, 0, None) => QueryAst::MatchAll,
+        (1, 0, 0, 0, None) => must.pop().unwrap(),
+        (0, 1, 0, 0, None) => should.pop().unwrap(),
+        (0, 0, 1, 0, None) if !with_scoring => filter.pop().unwrap(),
+        _ => BoolQuery {
+            must,
+            must_not,
+            should,
+            filter,
+            minimum_should_match,
+        }
+        .into(),
+    }
+}
+
+/// Hoists the clauses of the bool queries nested in a clause of the same kind.
+///
+/// A nested bool query is only hoisted if it does not change the meaning of the should
+/// clauses of either query.
+fn flatten_bool_query(bool_query: BoolQuery) -> BoolQuery {
+    let mut flattened_bool_query = BoolQuery {
+        minimum_should_match: bool_query.minimum_should_match,
+        ..Default::default()
+    };
+    for clause in bool_query.must {
+        match clause {
+            QueryAst::Bool(nested_bool_query) if is_conjunction(&nested_bool_query) => {
+                flattened_bool_query.must.extend(nested_bool_query.must);
+                flattened_bool_query.filter.extend(nested_bool_query.filter);
+                flattened_bool_query
+                    .must_not
+                    .extend(nested_bool_query.must_not);
+            }
+            clause => flattened_bool_query.must.push(clause),
+        }
+    }
+    for clause in bool_query.filter {
+        match clause {
+            QueryAst::Bool(nested_bool_query) if is_conjunction(&nested_bool_query) => {
+                flattened_bool_query.filter.extend(nested_bool_query.must);
+                flattened_bool_query.filter.extend(nested_bool_query.filter);
+                flattened_bool_query
+                    .must_not
+                    .extend(nested_bool_query.must_not);
+            }
+            clause => flattened_bool_query.filter.push(clause),
+        }
+    }
+    // The number of should clauses matters when `minimum_should_match` is set.
+    let can_flatten_should = bool_query.minimum_should_match.is_none();
+    for clause in bool_query.should {
+        match clause {
+            QueryAst::Bool(nested_bool_query)
+                if can_flatten_should && is_disjunction(&nested_bool_query) =>
+            {
+                flattened_bool_query.should.extend(nested_bool_query.should);
+            }
+            clause => flattened_bool_query.should.push(clause),
+        }
+    }
+    // `NOT (a OR b)` is equivalent to `NOT a AND NOT b`.
+    for clause in bool_query.must_not {
+        match clause {
+            QueryAst::Bool(nested_bool_query) if is_disjunction(&nested_bool_query) => {
+                flattened_bool_query
+                    .must_not
+                    .extend(nested_bool_query.should);
+            }
+            clause => flattened_bool_query.must_not.push(clause),
+        }
+    }
+    flattened_bool_query
+}
+
+/// Returns true if the bool query only matches the documents matching all its must and filter
+/// clauses, and none of its must not clauses.
+fn is_conjunction(bool_query: &BoolQuery) -> bool {
+    bool_query.should.is_empty()
+        && bool_query.minimum_should_match.is_none()
+        && (!bool_query.must.is_empty() || !bool_query.filter.is_empty())
+}
+
+/// Returns true if the bool query matches the documents matching any of its should clauses.
+fn is_disjunction(bool_query: &BoolQuery) -> bool {
+    bool_query.must.is_empty()
+        && bool_query.filter.is_empty()
+        && bool_query.must_not.is_empty()
+        && bool_query.minimum_should_match.is_none()
+        && !bool_query.should.is_empty()
+}
+
+fn dedup_clauses(clauses: Vec<QueryAst>) -> Vec<QueryAst> {
+    let mut deduped_clauses: Vec<QueryAst> = Vec::with_capacity(clauses.len());
+    for clause in clauses {
+        if !deduped_clauses.contains(&clause) {
+            deduped_clauses.push(clause);
+        }
+    }
+    deduped_clauses
+}
+
+/// Merges the term clauses, and the single field term set clauses, of a disjunction targeting
+/// the same field into a single term set query.
+///
+/// The clauses are grouped per field so that a field missing from the schema only discards the
+/// terms of that field, as it would for the original term queries.
+fn merge_term_clauses(clauses: Vec<QueryAst>) -> Vec<QueryAst> {
+    let mut num_clauses_per_field: HashMap<&str, usize> = HashMap::new();
+    for clause in &clauses {
+        if let Some(field) = single_field_of_term_clause(clause) {
+            *num_clauses_per_field.entry(field).or_default() += 1;
+        }
+    }
+    let mergeable_fields: BTreeSet<String> = num_clauses_per_field
+        .into_iter()
+        .filter(|(_, num_clauses)| *num_clauses > 1)
+        .map(|(field, _)| field.to_string())
+        .collect();
+    if mergeable_fields.is_empty() {
+        return clauses;
+    }
+    let mut merged_clauses = Vec::with_capacity(clauses.len());
+    let mut terms_per_field: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for clause in clauses {
+        let is_mergeable = single_field_of_term_clause(&clause)
+            .map_or(false, |field| mergeable_fields.contains(field));
+        if !is_mergeable {
+            merged_clauses.push(clause);
+            continue;
+        }
+        match clause {
+            QueryAst::Term(TermQuery { field, value }) => {
+                terms_per_field.entry(field).or_default().insert(value);
+            }
+            QueryAst::TermSet(term_set_query) => {
+                for (field, terms) in term_set_query.terms_per_field {
+                    terms_per_field.entry(field).or_default().extend(terms);
+                }
+            }
+            _ => unreachable!("only term and term set clauses are mergeable"),
+        }
+    }
+    for field in mergeable_fields {
+        let terms = terms_per_field.remove(&field).unwrap_or_default();
+        let term_set_query = TermSetQuery {
+            terms_per_field: HashMap::from_iter([(field, terms)]),
+        };
+        merged_clauses.push(term_set_query.into());
+    }
+    merged_clauses
+}
+
+fn single_field_of_term_clause(clause: &QueryAst) -> Option<&str> {
+    match clause {
+        QueryAst::Term(term_query) => Some(&term_query.field),
+        QueryAst::TermSet(term_set_query) if term_set_query.terms_per_field.len() == 1 => {
+            term_set_query
+                .terms_per_field
+                .keys()
+                .next()
+                .map(String::as_str)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinimumShouldMatch;
+
+    fn term_query_for_test(field: &str, value: &str) -> QueryAst {
+        TermQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    fn term_set_query_for_test(field: &str, values: &[&str]) -> QueryAst {
+        let terms = values.iter().map(|value| value.to_string()).collect();
+        TermSetQuery {
+            terms_per_field: HashMap::from_iter([(field.to_string(), terms)]),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_optimize_query_ast_flattens_nested_bool_queries() {
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![
+                term_query_for_test("service", "api"),
+                BoolQuery {
+                    must: vec![term_query_for_test("level", "error")],
+                    filter: vec![term_query_for_test("env", "prod")],
+                    ..Default::default()
+                }
+                .into(),
+            ],
+            should: vec![
+                term_query_for_test("tag", "a"),
+                BoolQuery {
+                    should: vec![
+                        term_query_for_test("tag", "b"),
+                        term_query_for_test("tag", "c"),
+                    ],
+                    ..Default::default()
+                }
+                .into(),
+            ],
+            must_not: vec![BoolQuery {
+                should: vec![
+                    term_query_for_test("host", "x"),
+                    term_query_for_test("zone", "y"),
+                ],
+                ..Default::default()
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![
+                term_query_for_test("service", "api"),
+                term_query_for_test("level", "error"),
+            ],
+            should: vec![
+                term_query_for_test("tag", "a"),
+                term_query_for_test("tag", "b"),
+                term_query_for_test("tag", "c"),
+            ],
+            filter: vec![term_query_for_test("env", "prod")],
+            must_not: vec![
+                term_query_for_test("host", "x"),
+                term_query_for_test("zone", "y"),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), expected_query_ast);
+    }
+
+    #[test]
+    fn test_optimize_query_ast_keeps_nested_bool_queries_changing_should_semantics() {
+        // The nested bool query has no must or filter clause: hoisting its must not clause
+        // would make the should clause of the parent mandatory.
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![BoolQuery {
+                must_not: vec![term_query_for_test("level", "debug")],
+                ..Default::default()
+            }
+            .into()],
+            should: vec![term_query_for_test("tag", "a")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast.clone()), query_ast);
+
+        let query_ast: QueryAst = BoolQuery {
+            should: vec![
+                term_query_for_test("tag", "a"),
+                BoolQuery {
+                    should: vec![
+                        term_query_for_test("tag", "b"),
+                        term_query_for_test("tag", "c"),
+                    ],
+                    ..Default::default()
+                }
+                .into(),
+            ],
+            minimum_should_match: Some(MinimumShouldMatch::Absolute(2)),
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast.clone()), query_ast);
+    }
+
+    #[test]
+    fn test_optimize_query_ast_folds_match_all_and_match_none() {
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            filter: vec![QueryAst::MatchNone],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), QueryAst::MatchNone);
+
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            must_not: vec![QueryAst::MatchAll],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), QueryAst::MatchNone);
+
+        let query_ast: QueryAst = BoolQuery {
+            should: vec![QueryAst::MatchNone, QueryAst::MatchNone],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), QueryAst::MatchNone);
+
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            should: vec![QueryAst::MatchNone, term_query_for_test("tag", "a")],
+            filter: vec![QueryAst::MatchAll],
+            must_not: vec![QueryAst::MatchNone],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            should: vec![term_query_for_test("tag", "a")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), expected_query_ast);
+
+        // The `MatchAll` clause keeps the should clause optional.
+        let query_ast: QueryAst = BoolQuery {
+            filter: vec![QueryAst::MatchAll],
+            should: vec![term_query_for_test("tag", "a")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast.clone()), query_ast);
+
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![QueryAst::Boost {
+                underlying: Box::new(
+                    BoolQuery {
+                        filter: vec![QueryAst::MatchNone],
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+                boost: NotNaNf32::try_from(2.0).unwrap(),
+            }],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), QueryAst::MatchNone);
+
+        let query_ast: QueryAst = BoolQuery {
+            must_not: vec![QueryAst::MatchNone],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), QueryAst::MatchAll);
+    }
+
+    #[test]
+    fn test_optimize_query_ast_deduplicates_clauses() {
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![
+                term_query_for_test("service", "api"),
+                term_query_for_test("service", "api"),
+            ],
+            filter: vec![
+                term_query_for_test("env", "prod"),
+                term_query_for_test("env", "prod"),
+            ],
+            ..Default::default()
+        }
+        .into();
+        // Deduplicating the must clauses would change the score of the matching documents.
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![
+                term_query_for_test("service", "api"),
+                term_query_for_test("service", "api"),
+            ],
+            filter: vec![term_query_for_test("env", "prod")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), expected_query_ast);
+
+        let query_ast: QueryAst = BoolQuery {
+            filter: vec![BoolQuery {
+                must: vec![
+                    term_query_for_test("service", "api"),
+                    term_query_for_test("level", "error"),
+                ],
+                filter: vec![term_query_for_test("service", "api")],
+                ..Default::default()
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            filter: vec![
+                term_query_for_test("service", "api"),
+                term_query_for_test("level", "error"),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), expected_query_ast);
+    }
+
+    #[test]
+    fn test_optimize_query_ast_merges_term_clauses() {
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            must_not: vec![
+                term_query_for_test("level", "debug"),
+                term_query_for_test("host", "x"),
+                term_set_query_for_test("level", &["trace", "info"]),
+            ],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            must_not: vec![
+                term_query_for_test("host", "x"),
+                term_set_query_for_test("level", &["debug", "info", "trace"]),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), expected_query_ast);
+
+        // Merging scored should clauses would change the score of the matching documents.
+        let scored_query_ast: QueryAst = BoolQuery {
+            should: vec![
+                term_query_for_test("level", "error"),
+                term_query_for_test("level", "warn"),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            optimize_query_ast(scored_query_ast.clone()),
+            scored_query_ast
+        );
+
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            filter: vec![scored_query_ast],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            filter: vec![term_set_query_for_test("level", &["error", "warn"])],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(optimize_query_ast(query_ast), expected_query_ast);
+    }
+}
@@ -112,6 +112,7 @@ impl BuildTantivyAst for PhrasePrefixQuery {
         schema: &TantivySchema,
         _search_fields: &[String],
         _with_validation: bool,
+        _with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let (_, terms) = self.get_terms(schema)?;
 
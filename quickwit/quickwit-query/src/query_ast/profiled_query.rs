@@ -0,0 +1,423 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::{EnableScoring, Explanation, Scorer, Weight};
+use tantivy::{DocId, DocSet, Score, SegmentReader, Term};
+
+use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
+use crate::TantivyQuery;
+
+/// Time spent in the different stages of the execution of a query node, expressed in
+/// nanoseconds, and number of times each stage was run. The time of a node includes the time of
+/// its children.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+pub struct QueryProfileBreakdown {
+    pub create_weight: u64,
+    pub create_weight_count: u64,
+    pub build_scorer: u64,
+    pub build_scorer_count: u64,
+    pub next_doc: u64,
+    pub next_doc_count: u64,
+    pub advance: u64,
+    pub advance_count: u64,
+    pub score: u64,
+    pub score_count: u64,
+}
+
+impl QueryProfileBreakdown {
+    fn total_time_nanos(&self) -> u64 {
+        self.create_weight + self.build_scorer + self.next_doc + self.advance + self.score
+    }
+}
+
+/// Timings of a node of the tantivy query executed on a split, modeled after the query section
+/// of Elasticsearch's profile API.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct QueryProfile {
+    #[serde(rename = "type")]
+    pub query_type: String,
+    pub description: String,
+    pub time_in_nanos: u64,
+    pub breakdown: QueryProfileBreakdown,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<QueryProfile>,
+}
+
+#[derive(Default)]
+struct QueryNodeStats {
+    create_weight: AtomicU64,
+    create_weight_count: AtomicU64,
+    build_scorer: AtomicU64,
+    build_scorer_count: AtomicU64,
+    next_doc: AtomicU64,
+    next_doc_count: AtomicU64,
+    advance: AtomicU64,
+    advance_count: AtomicU64,
+    score: AtomicU64,
+    score_count: AtomicU64,
+}
+
+fn elapsed_nanos(start: Instant) -> u64 {
+    start.elapsed().as_nanos() as u64
+}
+
+impl QueryNodeStats {
+    fn breakdown(&self) -> QueryProfileBreakdown {
+        QueryProfileBreakdown {
+            create_weight: self.create_weight.load(Ordering::Relaxed),
+            create_weight_count: self.create_weight_count.load(Ordering::Relaxed),
+            build_scorer: self.build_scorer.load(Ordering::Relaxed),
+            build_scorer_count: self.build_scorer_count.load(Ordering::Relaxed),
+            next_doc: self.next_doc.load(Ordering::Relaxed),
+            next_doc_count: self.next_doc_count.load(Ordering::Relaxed),
+            advance: self.advance.load(Ordering::Relaxed),
+            advance_count: self.advance_count.load(Ordering::Relaxed),
+            score: self.score.load(Ordering::Relaxed),
+            score_count: self.score_count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct QueryProfileNode {
+    query_type: String,
+    description: String,
+    stats: Arc<QueryNodeStats>,
+    children: Vec<QueryProfileNode>,
+}
+
+impl QueryProfileNode {
+    fn query_profile(&self) -> QueryProfile {
+        let breakdown = self.stats.breakdown();
+        QueryProfile {
+            query_type: self.query_type.clone(),
+            description: self.description.clone(),
+            time_in_nanos: breakdown.total_time_nanos(),
+            breakdown,
+            children: self
+                .children
+                .iter()
+                .map(QueryProfileNode::query_profile)
+                .collect(),
+        }
+    }
+}
+
+/// Collects the timings of the nodes of a query built with
+/// [`QueryAst::build_profiled_tantivy_query`](crate::query_ast::QueryAst::build_profiled_tantivy_query).
+pub struct QueryProfiler {
+    root: QueryProfileNode,
+}
+
+impl QueryProfiler {
+    /// Returns the timings recorded so far.
+    pub fn query_profile(&self) -> QueryProfile {
+        self.root.query_profile()
+    }
+}
+
+/// Instruments every node of the AST. Profiling prevents some optimizations that depend on
+/// the concrete type of the children of a boolean query, such as block-max WAND.
+pub(crate) fn profile_tantivy_ast(
+    tantivy_ast: TantivyQueryAst,
+) -> (Box<dyn TantivyQuery>, QueryProfiler) {
+    let (query, root) = build_profiled_query(tantivy_ast);
+    (query, QueryProfiler { root })
+}
+
+fn build_profiled_query(tantivy_ast: TantivyQueryAst) -> (Box<dyn TantivyQuery>, QueryProfileNode) {
+    match tantivy_ast {
+        TantivyQueryAst::Bool(mut bool_query) => {
+            let description = describe_bool_query(&bool_query);
+            let mut children = Vec::new();
+            for clauses in [
+                &mut bool_query.must,
+                &mut bool_query.must_not,
+                &mut bool_query.should,
+                &mut bool_query.filter,
+            ] {
+                for clause in clauses.iter_mut() {
+                    let child_ast = std::mem::replace(clause, TantivyQueryAst::match_none());
+                    let (child_query, child_node) = build_profiled_query(child_ast);
+                    *clause = TantivyQueryAst::Leaf(child_query);
+                    children.push(child_node);
+                }
+            }
+            let query: Box<dyn TantivyQuery> = bool_query.into();
+            wrap_query(query, "BooleanQuery".to_string(), description, children)
+        }
+        tantivy_ast => {
+            let query: Box<dyn TantivyQuery> = tantivy_ast.into();
+            let description = format!("{query:?}");
+            let query_type = description
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            wrap_query(query, query_type, description, Vec::new())
+        }
+    }
+}
+
+fn describe_bool_query(bool_query: &TantivyBoolQuery) -> String {
+    let mut description = format!(
+        "must: {}, must_not: {}, should: {}, filter: {}",
+        bool_query.must.len(),
+        bool_query.must_not.len(),
+        bool_query.should.len(),
+        bool_query.filter.len()
+    );
+    if let Some(minimum_should_match) = bool_query.minimum_should_match {
+        description.push_str(&format!(", minimum_should_match: {minimum_should_match}"));
+    }
+    description
+}
+
+fn wrap_query(
+    underlying: Box<dyn TantivyQuery>,
+    query_type: String,
+    description: String,
+    children: Vec<QueryProfileNode>,
+) -> (Box<dyn TantivyQuery>, QueryProfileNode) {
+    let stats = Arc::new(QueryNodeStats::default());
+    let profiled_query = ProfiledQuery {
+        underlying,
+        stats: stats.clone(),
+    };
+    let node = QueryProfileNode {
+        query_type,
+        description,
+        stats,
+        children,
+    };
+    (Box::new(profiled_query), node)
+}
+
+/// Wraps a query and records the time spent in its weight and scorers.
+struct ProfiledQuery {
+    underlying: Box<dyn TantivyQuery>,
+    stats: Arc<QueryNodeStats>,
+}
+
+impl Clone for ProfiledQuery {
+    fn clone(&self) -> Self {
+        ProfiledQuery {
+            underlying: self.underlying.box_clone(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for ProfiledQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProfiledQuery")
+            .field("underlying", &self.underlying)
+            .finish()
+    }
+}
+
+impl TantivyQuery for ProfiledQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let start = Instant::now();
+        let underlying = self.underlying.weight(enable_scoring);
+        self.stats
+            .create_weight
+            .fetch_add(elapsed_nanos(start), Ordering::Relaxed);
+        self.stats
+            .create_weight_count
+            .fetch_add(1, Ordering::Relaxed);
+        Ok(Box::new(ProfiledWeight {
+            underlying: underlying?,
+            stats: self.stats.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.underlying.query_terms(visitor)
+    }
+}
+
+struct ProfiledWeight {
+    underlying: Box<dyn Weight>,
+    stats: Arc<QueryNodeStats>,
+}
+
+impl Weight for ProfiledWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let start = Instant::now();
+        let underlying = self.underlying.scorer(reader, boost);
+        self.stats
+            .build_scorer
+            .fetch_add(elapsed_nanos(start), Ordering::Relaxed);
+        self.stats
+            .build_scorer_count
+            .fetch_add(1, Ordering::Relaxed);
+        Ok(Box::new(ProfiledScorer {
+            underlying: underlying?,
+            stats: self.stats.clone(),
+            local_stats: ScorerStats::default(),
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        self.underlying.explain(reader, doc)
+    }
+}
+
+/// Scorer calls are very frequent: their timings are accumulated locally and only published to
+/// the shared stats when the scorer is dropped.
+#[derive(Default)]
+struct ScorerStats {
+    next_doc: u64,
+    next_doc_count: u64,
+    advance: u64,
+    advance_count: u64,
+    score: u64,
+    score_count: u64,
+}
+
+struct ProfiledScorer {
+    underlying: Box<dyn Scorer>,
+    stats: Arc<QueryNodeStats>,
+    local_stats: ScorerStats,
+}
+
+impl DocSet for ProfiledScorer {
+    fn advance(&mut self) -> DocId {
+        let start = Instant::now();
+        let doc = self.underlying.advance();
+        self.local_stats.next_doc += elapsed_nanos(start);
+        self.local_stats.next_doc_count += 1;
+        doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        let start = Instant::now();
+        let doc = self.underlying.seek(target);
+        self.local_stats.advance += elapsed_nanos(start);
+        self.local_stats.advance_count += 1;
+        doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying.size_hint()
+    }
+}
+
+impl Scorer for ProfiledScorer {
+    fn score(&mut self) -> Score {
+        let start = Instant::now();
+        let score = self.underlying.score();
+        self.local_stats.score += elapsed_nanos(start);
+        self.local_stats.score_count += 1;
+        score
+    }
+}
+
+impl Drop for ProfiledScorer {
+    fn drop(&mut self) {
+        let local_stats = &self.local_stats;
+        let stats = &self.stats;
+        stats
+            .next_doc
+            .fetch_add(local_stats.next_doc, Ordering::Relaxed);
+        stats
+            .next_doc_count
+            .fetch_add(local_stats.next_doc_count, Ordering::Relaxed);
+        stats
+            .advance
+            .fetch_add(local_stats.advance, Ordering::Relaxed);
+        stats
+            .advance_count
+            .fetch_add(local_stats.advance_count, Ordering::Relaxed);
+        stats.score.fetch_add(local_stats.score, Ordering::Relaxed);
+        stats
+            .score_count
+            .fetch_add(local_stats.score_count, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::TopDocs;
+    use tantivy::schema::{Schema, TEXT};
+    use tantivy::{doc, Index};
+
+    use crate::query_ast::{BoolQuery, QueryAst, TermQuery};
+
+    #[test]
+    fn test_profiled_query() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for body in ["apple pie", "apple", "pie", "cherry pie"] {
+            index_writer.add_document(doc!(body_field => body)).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let query_ast: QueryAst = BoolQuery {
+            should: vec![
+                TermQuery::from_field_value("body", "apple").into(),
+                TermQuery::from_field_value("body", "pie").into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        let (query, query_profiler) = query_ast
+            .build_profiled_tantivy_query(&schema, &[], true, true)
+            .unwrap();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        assert_eq!(top_docs.len(), 4);
+
+        let query_profile = query_profiler.query_profile();
+        assert_eq!(query_profile.query_type, "BooleanQuery");
+        assert_eq!(
+            query_profile.description,
+            "must: 0, must_not: 0, should: 2, filter: 0"
+        );
+        assert_eq!(query_profile.breakdown.create_weight_count, 1);
+        assert_eq!(query_profile.breakdown.build_scorer_count, 1);
+        assert_eq!(query_profile.breakdown.score_count, 4);
+        assert_eq!(query_profile.children.len(), 2);
+        let apple_profile = &query_profile.children[0];
+        assert_eq!(apple_profile.query_type, "TermQuery");
+        assert!(apple_profile.children.is_empty());
+        assert_eq!(apple_profile.breakdown.build_scorer_count, 1);
+        assert_eq!(
+            apple_profile.time_in_nanos,
+            apple_profile.breakdown.create_weight
+                + apple_profile.breakdown.build_scorer
+                + apple_profile.breakdown.next_doc
+                + apple_profile.breakdown.advance
+                + apple_profile.breakdown.score
+        );
+    }
+}
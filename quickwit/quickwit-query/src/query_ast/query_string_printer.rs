@@ -0,0 +1,464 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Bound;
+
+use crate::query_ast::{
+    BoolQuery, FullTextMode, FullTextQuery, QueryAst, RangeQuery, TermSetQuery,
+};
+use crate::{BooleanOperand, JsonLiteral};
+
+/// Characters that have a meaning in the query language and need to be escaped in a term.
+const SPECIAL_CHARS: &[char] = &[
+    '+', '^', '`', ':', '{', '}', '"', '\'', '[', ']', '(', ')', '~', '!', '\\', '*', '/', ' ',
+];
+
+impl QueryAst {
+    /// Renders the query AST in the query language, e.g. `+service:api -level:debug`, for
+    /// logging and debugging purposes.
+    ///
+    /// The rendering is best effort:
+    /// - the scoring parameters (`minimum_should_match`, tie breakers, score functions, ...)
+    ///   without an equivalent in the query language are dropped,
+    /// - the should clauses of a bool query with required clauses are rendered without operator,
+    ///   and are only optional if the query is parsed with the `OR` default operator,
+    /// - the nodes without an equivalent in the query language (kNN, intervals, span, UDF and
+    ///   terms lookup queries) are rendered as JSON.
+    pub fn to_query_string(&self) -> String {
+        match self {
+            QueryAst::Bool(bool_query) => bool_query_to_string(bool_query),
+            QueryAst::DisMax(dis_max_query) => {
+                // A dis max query matches the union of the documents of its queries.
+                disjunction_to_string(dis_max_query.queries.iter().map(QueryAst::to_query_string))
+            }
+            QueryAst::Boosting(boosting_query) => boosting_query.positive.to_query_string(),
+            QueryAst::Term(term_query) => {
+                format!("{}:{}", term_query.field, escape_term(&term_query.value))
+            }
+            QueryAst::TermSet(term_set_query) => term_set_query_to_string(term_set_query),
+            QueryAst::FullText(full_text_query) => full_text_query_to_string(full_text_query),
+            QueryAst::CombinedFields(combined_fields_query) => {
+                let text = match combined_fields_query.operator {
+                    BooleanOperand::And => quote(&combined_fields_query.text, '\''),
+                    BooleanOperand::Or => escape_term(&combined_fields_query.text),
+                };
+                disjunction_to_string(
+                    combined_fields_query
+                        .fields
+                        .iter()
+                        .map(|field| format!("{field}:{text}")),
+                )
+            }
+            QueryAst::PhrasePrefix(phrase_prefix_query) => format!(
+                "{}:{}*",
+                phrase_prefix_query.field,
+                quote(&phrase_prefix_query.phrase, '"')
+            ),
+            QueryAst::Regex(regex_query) => {
+                let case_insensitive_flag = if regex_query.case_insensitive {
+                    "(?i)"
+                } else {
+                    ""
+                };
+                format!(
+                    "{}:/{case_insensitive_flag}{}/",
+                    regex_query.field,
+                    regex_query.regex.replace('/', "\\/")
+                )
+            }
+            QueryAst::Range(range_query) => range_query_to_string(range_query),
+            QueryAst::FieldPresence(field_presence_query) => {
+                format!("{}:*", field_presence_query.field)
+            }
+            QueryAst::UserInput(user_input_query) => user_input_query.user_text.clone(),
+            QueryAst::SimpleUserInput(simple_user_input_query) => {
+                simple_user_input_query.user_text.clone()
+            }
+            QueryAst::MatchAll => "*".to_string(),
+            QueryAst::MatchNone => "-*".to_string(),
+            QueryAst::Boost { underlying, boost } => {
+                format!(
+                    "{}^{}",
+                    to_grouped_query_string(underlying),
+                    f32::from(*boost)
+                )
+            }
+            QueryAst::ConstScore { underlying, .. } => underlying.to_query_string(),
+            QueryAst::FunctionScore(function_score_query) => {
+                function_score_query.underlying.to_query_string()
+            }
+            QueryAst::TermsLookup(_)
+            | QueryAst::Intervals(_)
+            | QueryAst::Span(_)
+            | QueryAst::Knn(_)
+            | QueryAst::Udf(_) => {
+                serde_json::to_string(self).unwrap_or_else(|_| "<unserializable query>".to_string())
+            }
+        }
+    }
+}
+
+/// Renders a clause of a compound query, wrapping it in parentheses if it is made of several
+/// clauses or starts with an operator itself.
+fn to_grouped_query_string(query_ast: &QueryAst) -> String {
+    let is_compound = match query_ast {
+        // Only a bool query made of a single should clause is rendered without operator.
+        QueryAst::Bool(bool_query) => {
+            bool_query.should.len() > 1
+                || !bool_query.must.is_empty()
+                || !bool_query.filter.is_empty()
+                || !bool_query.must_not.is_empty()
+        }
+        QueryAst::DisMax(dis_max_query) => dis_max_query.queries.len() > 1,
+        QueryAst::TermSet(term_set_query) => term_set_query.terms_per_field.len() > 1,
+        QueryAst::CombinedFields(combined_fields_query) => combined_fields_query.fields.len() > 1,
+        QueryAst::UserInput(_) | QueryAst::SimpleUserInput(_) | QueryAst::MatchNone => true,
+        QueryAst::Boosting(boosting_query) => {
+            return to_grouped_query_string(&boosting_query.positive)
+        }
+        QueryAst::ConstScore { underlying, .. } => return to_grouped_query_string(underlying),
+        QueryAst::FunctionScore(function_score_query) => {
+            return to_grouped_query_string(&function_score_query.underlying)
+        }
+        _ => false,
+    };
+    let query_string = query_ast.to_query_string();
+    if is_compound {
+        format!("({query_string})")
+    } else {
+        query_string
+    }
+}
+
+fn bool_query_to_string(bool_query: &BoolQuery) -> String {
+    let has_required_clauses = !bool_query.must.is_empty()
+        || !bool_query.filter.is_empty()
+        || !bool_query.must_not.is_empty();
+    if !has_required_clauses {
+        if bool_query.should.is_empty() {
+            return "*".to_string();
+        }
+        return disjunction_to_string(bool_query.should.iter().map(to_grouped_query_string));
+    }
+    let required_clauses = bool_query
+        .must
+        .iter()
+        .chain(&bool_query.filter)
+        .map(|clause| format!("+{}", to_grouped_query_string(clause)));
+    let prohibited_clauses = bool_query
+        .must_not
+        .iter()
+        .map(|clause| format!("-{}", to_grouped_query_string(clause)));
+    let optional_clauses = bool_query.should.iter().map(to_grouped_query_string);
+    required_clauses
+        .chain(prohibited_clauses)
+        .chain(optional_clauses)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn disjunction_to_string(clauses: impl Iterator<Item = String>) -> String {
+    clauses.collect::<Vec<String>>().join(" OR ")
+}
+
+fn term_set_query_to_string(term_set_query: &TermSetQuery) -> String {
+    // The fields are sorted to make the rendering deterministic.
+    let mut terms_per_field: Vec<_> = term_set_query.terms_per_field.iter().collect();
+    terms_per_field.sort_unstable_by_key(|(field, _)| *field);
+    disjunction_to_string(terms_per_field.into_iter().map(|(field, terms)| {
+        let terms: Vec<String> = terms.iter().map(|term| escape_term(term)).collect();
+        format!("{field}: IN [{}]", terms.join(" "))
+    }))
+}
+
+fn full_text_query_to_string(full_text_query: &FullTextQuery) -> String {
+    let text = &full_text_query.text;
+    let rendered_text = match &full_text_query.params.mode {
+        FullTextMode::Phrase { slop } if *slop > 0 => format!("{}~{slop}", quote(text, '"')),
+        FullTextMode::Phrase { .. } => quote(text, '"'),
+        FullTextMode::Bool {
+            operator: BooleanOperand::And,
+            ..
+        } => quote(text, '\''),
+        FullTextMode::Bool {
+            operator: BooleanOperand::Or,
+            ..
+        }
+        | FullTextMode::PhraseFallbackToIntersection => escape_term(text),
+    };
+    format!("{}:{rendered_text}", full_text_query.field)
+}
+
+fn range_query_to_string(range_query: &RangeQuery) -> String {
+    let (lower_bracket, lower_value) = match &range_query.lower_bound {
+        Bound::Included(lower_value) => ('[', json_literal_to_string(lower_value)),
+        Bound::Excluded(lower_value) => ('{', json_literal_to_string(lower_value)),
+        Bound::Unbounded => ('[', "*".to_string()),
+    };
+    let (upper_value, upper_bracket) = match &range_query.upper_bound {
+        Bound::Included(upper_value) => (json_literal_to_string(upper_value), ']'),
+        Bound::Excluded(upper_value) => (json_literal_to_string(upper_value), '}'),
+        Bound::Unbounded => ("*".to_string(), ']'),
+    };
+    format!(
+        "{}:{lower_bracket}{lower_value} TO {upper_value}{upper_bracket}",
+        range_query.field
+    )
+}
+
+fn json_literal_to_string(json_literal: &JsonLiteral) -> String {
+    match json_literal {
+        JsonLiteral::Number(number) => number.to_string(),
+        JsonLiteral::String(text) => escape_term(text),
+        JsonLiteral::Bool(value) => value.to_string(),
+    }
+}
+
+/// Escapes the special characters of a term, as well as the leading characters and the words
+/// that would be interpreted as operators.
+fn escape_term(term: &str) -> String {
+    if matches!(term, "AND" | "OR" | "NOT" | "IN" | "TO") {
+        return format!("\\{term}");
+    }
+    let mut escaped_term = String::with_capacity(term.len());
+    for (char_idx, term_char) in term.chars().enumerate() {
+        let is_leading_operator = char_idx == 0 && matches!(term_char, '-' | '<' | '>' | '=');
+        if is_leading_operator || SPECIAL_CHARS.contains(&term_char) {
+            escaped_term.push('\\');
+        }
+        escaped_term.push(term_char);
+    }
+    escaped_term
+}
+
+fn quote(text: &str, quote_char: char) -> String {
+    let mut quoted_text = String::with_capacity(text.len() + 2);
+    quoted_text.push(quote_char);
+    for text_char in text.chars() {
+        if text_char == quote_char || text_char == '\\' {
+            quoted_text.push('\\');
+        }
+        quoted_text.push(text_char);
+    }
+    quoted_text.push(quote_char);
+    quoted_text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::*;
+    use crate::not_nan_f32::NotNaNf32;
+    use crate::query_ast::{
+        FieldPresenceQuery, FullTextParams, KnnQuery, PhrasePrefixQuery, RegexQuery, TermQuery,
+        UserInputQuery,
+    };
+    use crate::MatchAllOrNone;
+
+    fn full_text_query_for_test(field: &str, text: &str, mode: FullTextMode) -> QueryAst {
+        FullTextQuery {
+            field: field.to_string(),
+            text: text.to_string(),
+            params: FullTextParams {
+                tokenizer: None,
+                mode,
+                zero_terms_query: MatchAllOrNone::MatchNone,
+                lenient: false,
+            },
+            preferred_field: None,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_to_query_string_leaves() {
+        assert_eq!(
+            QueryAst::from(TermQuery::from_field_value("service", "api")).to_query_string(),
+            "service:api"
+        );
+        assert_eq!(
+            QueryAst::from(TermQuery::from_field_value("title", "hello world: AND"))
+                .to_query_string(),
+            "title:hello\\ world\\:\\ AND"
+        );
+        assert_eq!(
+            QueryAst::from(TermQuery::from_field_value("level", "-1")).to_query_string(),
+            "level:\\-1"
+        );
+        assert_eq!(
+            QueryAst::from(TermQuery::from_field_value("op", "AND")).to_query_string(),
+            "op:\\AND"
+        );
+        assert_eq!(
+            full_text_query_for_test("body", "small \"bike\"", FullTextMode::Phrase { slop: 0 })
+                .to_query_string(),
+            "body:\"small \\\"bike\\\"\""
+        );
+        assert_eq!(
+            full_text_query_for_test("body", "small bike", FullTextMode::Phrase { slop: 2 })
+                .to_query_string(),
+            "body:\"small bike\"~2"
+        );
+        assert_eq!(
+            full_text_query_for_test("body", "small bike", BooleanOperand::And.into())
+                .to_query_string(),
+            "body:'small bike'"
+        );
+        assert_eq!(
+            full_text_query_for_test("body", "bike", FullTextMode::PhraseFallbackToIntersection)
+                .to_query_string(),
+            "body:bike"
+        );
+        let term_set_query: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from_iter([
+                (
+                    "tag".to_string(),
+                    BTreeSet::from_iter(["b".to_string(), "a".to_string()]),
+                ),
+                ("label".to_string(), BTreeSet::from_iter(["c".to_string()])),
+            ]),
+        }
+        .into();
+        assert_eq!(
+            term_set_query.to_query_string(),
+            "label: IN [c] OR tag: IN [a b]"
+        );
+        let range_query: QueryAst = RangeQuery {
+            field: "ip".to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String("127.0.0.1".to_string())),
+            upper_bound: Bound::Unbounded,
+        }
+        .into();
+        assert_eq!(range_query.to_query_string(), "ip:[127.0.0.1 TO *]");
+        let range_query: QueryAst = RangeQuery {
+            field: "count".to_string(),
+            lower_bound: Bound::Excluded(JsonLiteral::Number(10.into())),
+            upper_bound: Bound::Excluded(JsonLiteral::Number(20.into())),
+        }
+        .into();
+        assert_eq!(range_query.to_query_string(), "count:{10 TO 20}");
+        let phrase_prefix_query: QueryAst = PhrasePrefixQuery {
+            field: "body".to_string(),
+            phrase: "small bi".to_string(),
+            max_expansions: 50,
+            analyzer: FullTextParams {
+                tokenizer: None,
+                mode: FullTextMode::Phrase { slop: 0 },
+                zero_terms_query: MatchAllOrNone::MatchNone,
+                lenient: false,
+            },
+        }
+        .into();
+        assert_eq!(phrase_prefix_query.to_query_string(), "body:\"small bi\"*");
+        let regex_query: QueryAst = RegexQuery {
+            field: "path".to_string(),
+            regex: "/api/v[0-9]+".to_string(),
+            case_insensitive: true,
+        }
+        .into();
+        assert_eq!(regex_query.to_query_string(), "path:/(?i)\\/api\\/v[0-9]+/");
+        let field_presence_query: QueryAst = FieldPresenceQuery {
+            field: "trace_id".to_string(),
+        }
+        .into();
+        assert_eq!(field_presence_query.to_query_string(), "trace_id:*");
+        assert_eq!(QueryAst::MatchAll.to_query_string(), "*");
+        assert_eq!(QueryAst::MatchNone.to_query_string(), "-*");
+    }
+
+    #[test]
+    fn test_to_query_string_compound_queries() {
+        let term_query = |field: &str, value: &str| -> QueryAst {
+            TermQuery::from_field_value(field, value).into()
+        };
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query("service", "api")],
+            filter: vec![BoolQuery {
+                should: vec![term_query("level", "error"), term_query("level", "warn")],
+                ..Default::default()
+            }
+            .into()],
+            must_not: vec![QueryAst::MatchNone],
+            should: vec![QueryAst::Boost {
+                underlying: Box::new(term_query("tag", "urgent")),
+                boost: NotNaNf32::try_from(2.0).unwrap(),
+            }],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            query_ast.to_query_string(),
+            "+service:api +(level:error OR level:warn) -(-*) tag:urgent^2"
+        );
+
+        let query_ast: QueryAst = BoolQuery {
+            should: vec![
+                BoolQuery {
+                    must: vec![term_query("color", "red")],
+                    must_not: vec![term_query("size", "large")],
+                    ..Default::default()
+                }
+                .into(),
+                QueryAst::Boost {
+                    underlying: Box::new(
+                        UserInputQuery {
+                            user_text: "color:green AND size:small".to_string(),
+                            default_fields: None,
+                            default_operator: BooleanOperand::And,
+                            quote_field_suffix: None,
+                            phrase_slop: 0,
+                            lenient: false,
+                            fuzziness: None,
+                        }
+                        .into(),
+                    ),
+                    boost: NotNaNf32::try_from(1.5).unwrap(),
+                },
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            query_ast.to_query_string(),
+            "(+color:red -size:large) OR (color:green AND size:small)^1.5"
+        );
+        assert_eq!(QueryAst::from(BoolQuery::default()).to_query_string(), "*");
+    }
+
+    #[test]
+    fn test_to_query_string_renders_unsupported_queries_as_json() {
+        let knn_query: QueryAst = KnnQuery {
+            field: "embedding".to_string(),
+            query_vector: vec![
+                NotNaNf32::try_from(1.0).unwrap(),
+                NotNaNf32::try_from(0.0).unwrap(),
+            ],
+            k: 3,
+            num_candidates: 10,
+        }
+        .into();
+        let query_string = knn_query.to_query_string();
+        assert!(query_string.starts_with("{\"type\":\"knn\""));
+        assert_eq!(
+            serde_json::from_str::<QueryAst>(&query_string).unwrap(),
+            knn_query
+        );
+    }
+}
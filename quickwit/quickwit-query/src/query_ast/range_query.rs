@@ -224,6 +224,7 @@ impl BuildTantivyAst for RangeQuery {
         schema: &TantivySchema,
         _search_fields: &[String],
         _with_validation: bool,
+        _with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let (_field, field_entry, _path) =
             super::utils::find_field_or_hit_dynamic(&self.field, schema)?;
@@ -276,7 +277,12 @@ impl BuildTantivyAst for RangeQuery {
                     field_name: field_entry.name().to_string(),
                 });
             }
-            tantivy::schema::FieldType::Bytes(_) => todo!(),
+            tantivy::schema::FieldType::Bytes(_) => {
+                return Err(InvalidQuery::RangeQueryNotSupportedForField {
+                    value_type: "bytes",
+                    field_name: field_entry.name().to_string(),
+                });
+            }
             tantivy::schema::FieldType::JsonObject(_) => {
                 let full_path = self.field.clone();
                 let mut sub_queries: Vec<TantivyQueryAst> = Vec::new();
@@ -349,6 +355,7 @@ mod tests {
         schema_builder.add_f64_field("my_f64_field", FAST);
         schema_builder.add_text_field("my_str_field", FAST);
         schema_builder.add_u64_field("my_u64_not_fastfield", STORED);
+        schema_builder.add_bytes_field("my_bytes_field", FAST);
         if dynamic_mode {
             schema_builder.add_json_field("_dynamic", TEXT | STORED | FAST);
         }
@@ -363,7 +370,7 @@ mod tests {
             upper_bound: Bound::Included(JsonLiteral::String("1989".to_string())),
         };
         let tantivy_ast = range_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap()
             .simplify();
         let leaf = tantivy_ast.as_leaf().unwrap();
@@ -402,7 +409,7 @@ mod tests {
         };
         // with validation
         let invalid_query: InvalidQuery = range_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap_err();
         assert!(
             matches!(invalid_query, InvalidQuery::FieldDoesNotExist { full_path } if full_path == "missing_field.toto")
@@ -410,7 +417,7 @@ mod tests {
         // without validation
         assert_eq!(
             range_query
-                .build_tantivy_ast_call(&schema, &[], false)
+                .build_tantivy_ast_call(&schema, &[], false, true)
                 .unwrap()
                 .const_predicate(),
             Some(MatchAllOrNone::MatchNone)
@@ -427,7 +434,7 @@ mod tests {
         };
         // with validation
         let invalid_query: InvalidQuery = range_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap_err();
         assert!(matches!(
             invalid_query,
@@ -436,13 +443,33 @@ mod tests {
         // without validation
         assert_eq!(
             range_query
-                .build_tantivy_ast_call(&schema, &[], false)
+                .build_tantivy_ast_call(&schema, &[], false, true)
                 .unwrap()
                 .const_predicate(),
             Some(MatchAllOrNone::MatchNone)
         );
     }
 
+    #[test]
+    fn test_range_query_bytes_field_unsupported() {
+        let schema = make_schema(false);
+        let range_query = RangeQuery {
+            field: "my_bytes_field".to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String("aGVsbG8=".to_string())),
+            upper_bound: Bound::Unbounded,
+        };
+        let invalid_query: InvalidQuery = range_query
+            .build_tantivy_ast_call(&schema, &[], true, true)
+            .unwrap_err();
+        assert!(matches!(
+            invalid_query,
+            InvalidQuery::RangeQueryNotSupportedForField {
+                value_type: "bytes",
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_range_dynamic() {
         let range_query = RangeQuery {
@@ -452,13 +479,14 @@ mod tests {
         };
         let schema = make_schema(true);
         let tantivy_ast = range_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let TantivyBoolQuery {
             must,
             must_not,
             should,
             filter,
+            ..
         } = tantivy_ast.as_bool_query().unwrap();
         assert!(must.is_empty());
         assert!(must_not.is_empty());
@@ -495,7 +523,7 @@ mod tests {
         };
         let schema = make_schema(false);
         let err = range_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap_err();
         assert!(matches!(err, InvalidQuery::SchemaError { .. }));
     }
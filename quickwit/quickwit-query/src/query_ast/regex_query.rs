@@ -0,0 +1,251 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::json_utils::JsonTermWriter;
+use tantivy::query::RegexQuery as TantivyRegexQuery;
+use tantivy::schema::{FieldType, Schema as TantivySchema};
+use tantivy::Term;
+
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{BuildTantivyAst, QueryAst};
+use crate::{find_field_or_hit_dynamic, InvalidQuery};
+
+/// Maximum length of a regular expression, in bytes.
+pub const MAX_REGEX_LENGTH: usize = 1_000;
+
+/// Maximum number of states of the automaton a regular expression compiles to. This limit is
+/// enforced by tantivy when the regular expression is compiled and cannot be raised.
+pub const MAX_DETERMINIZED_STATES: u32 = 10_000;
+
+/// Matches the documents in which `field` contains a term matching the regular expression
+/// `regex`.
+///
+/// The regular expression is matched against the indexed terms of the field: for tokenized
+/// fields, it matches individual tokens, not the original text. It follows the syntax of the
+/// `regex` crate and is anchored at both ends.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RegexQuery {
+    pub field: String,
+    pub regex: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub case_insensitive: bool,
+}
+
+impl From<RegexQuery> for QueryAst {
+    fn from(regex_query: RegexQuery) -> Self {
+        QueryAst::Regex(regex_query)
+    }
+}
+
+impl RegexQuery {
+    fn pattern(&self) -> String {
+        if self.case_insensitive {
+            format!("(?i:{})", self.regex)
+        } else {
+            format!("(?:{})", self.regex)
+        }
+    }
+}
+
+impl BuildTantivyAst for RegexQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        if self.regex.len() > MAX_REGEX_LENGTH {
+            return Err(InvalidQuery::Other(anyhow::anyhow!(
+                "Regular expression on field `{}` is {} bytes long, which exceeds the maximum \
+                 length of {MAX_REGEX_LENGTH} bytes.",
+                self.field,
+                self.regex.len()
+            )));
+        }
+        let (field, field_entry, json_path) = find_field_or_hit_dynamic(&self.field, schema)?;
+        let not_searchable_error = || {
+            InvalidQuery::SchemaError(format!(
+                "Field `{}` is not full-text searchable",
+                field_entry.name()
+            ))
+        };
+        let pattern = match field_entry.field_type() {
+            FieldType::Str(text_options) => {
+                text_options
+                    .get_indexing_options()
+                    .ok_or_else(not_searchable_error)?;
+                self.pattern()
+            }
+            FieldType::JsonObject(json_options) => {
+                json_options
+                    .get_text_indexing_options()
+                    .ok_or_else(not_searchable_error)?;
+                // The terms of a JSON field are prefixed with their path and type.
+                let mut term = Term::with_capacity(100);
+                let mut json_term_writer = JsonTermWriter::from_field_and_json_path(
+                    field,
+                    json_path,
+                    json_options.is_expand_dots_enabled(),
+                    &mut term,
+                );
+                json_term_writer.set_str("");
+                let json_term_prefix =
+                    String::from_utf8_lossy(json_term_writer.term().value_bytes());
+                format!("{}{}", escape_regex(&json_term_prefix), self.pattern())
+            }
+            _ => {
+                return Err(InvalidQuery::SchemaError(
+                    "Trying to run a regex query on a non-text field.".to_string(),
+                ));
+            }
+        };
+        let regex_query = TantivyRegexQuery::from_pattern(&pattern, field).map_err(|error| {
+            InvalidQuery::Other(anyhow::anyhow!(
+                "Invalid regular expression `{}` on field `{}`: {error}. Note that the automaton \
+                 of a regular expression cannot exceed {MAX_DETERMINIZED_STATES} states.",
+                self.regex,
+                self.field,
+            ))
+        })?;
+        Ok(regex_query.into())
+    }
+}
+
+/// Escapes the characters of `text` that are not alphanumeric, so that the resulting regular
+/// expression matches `text` literally.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            escaped.push(c);
+        } else {
+            escaped.push_str(&format!("\\x{{{:x}}}", c as u32));
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, STRING, TEXT};
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{self, search_docs};
+
+    fn search_regex(index: &Index, field: &str, regex: &str, case_insensitive: bool) -> Vec<u32> {
+        let query_ast = QueryAst::from(RegexQuery {
+            field: field.to_string(),
+            regex: regex.to_string(),
+            case_insensitive,
+        });
+        search_docs(index, &query_ast)
+    }
+
+    fn build_test_index() -> Index {
+        let mut schema_builder = Schema::builder();
+        let path_field = schema_builder.add_text_field("path", STRING);
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let attributes_field = schema_builder.add_json_field("attributes", STRING);
+        let docs = [
+            ("/api/v1/users/42", "User not found", "E404"),
+            ("/api/v2/users/42/orders", "Connection refused", "E503"),
+            ("/health", "OK", "E200"),
+        ]
+        .into_iter()
+        .map(|(path, body, error_code)| {
+            let attributes = serde_json::json!({ "error": { "code": error_code } });
+            let serde_json::Value::Object(attributes) = attributes else { unreachable!() };
+            doc!(
+                path_field => path,
+                body_field => body,
+                attributes_field => attributes,
+            )
+        });
+        test_helpers::build_test_index(schema_builder.build(), docs)
+    }
+
+    #[test]
+    fn test_regex_query_text_field() {
+        let index = build_test_index();
+        assert_eq!(
+            search_regex(&index, "path", "/api/v[0-9]+/.*", false),
+            [0, 1]
+        );
+        assert_eq!(search_regex(&index, "path", ".*/orders", false), [1]);
+        // The regular expression is anchored.
+        assert!(search_regex(&index, "path", "api", false).is_empty());
+        // Tokenized fields are matched token by token.
+        assert_eq!(search_regex(&index, "body", "ref.*", false), [1]);
+        assert!(search_regex(&index, "body", "connection refused", false).is_empty());
+    }
+
+    #[test]
+    fn test_regex_query_case_insensitive() {
+        let index = build_test_index();
+        assert!(search_regex(&index, "path", "/HEALTH", false).is_empty());
+        assert_eq!(search_regex(&index, "path", "/HEALTH", true), [2]);
+    }
+
+    #[test]
+    fn test_regex_query_json_field() {
+        let index = build_test_index();
+        assert_eq!(
+            search_regex(&index, "attributes.error.code", "E5[0-9]{2}", false),
+            [1]
+        );
+        assert_eq!(
+            search_regex(&index, "attributes.error.code", "E[24].*", false),
+            [0, 2]
+        );
+        assert!(search_regex(&index, "attributes.error", "E.*", false).is_empty());
+    }
+
+    #[test]
+    fn test_regex_query_invalid() {
+        let index = build_test_index();
+        let build_query = |field: &str, regex: String| {
+            QueryAst::from(RegexQuery {
+                field: field.to_string(),
+                regex,
+                case_insensitive: false,
+            })
+            .build_tantivy_query(&index.schema(), &[], true, true)
+        };
+        let error = build_query("path", "[a-z".to_string()).unwrap_err();
+        assert!(error.to_string().contains("Invalid regular expression"));
+
+        let error = build_query("path", "a".repeat(MAX_REGEX_LENGTH + 1)).unwrap_err();
+        assert!(error.to_string().contains("exceeds the maximum length"));
+
+        let error = build_query("path", "(a|b)*a(a|b){20}".to_string()).unwrap_err();
+        assert!(error.to_string().contains("Invalid regular expression"));
+
+        let error = build_query("unknown", ".*".to_string()).unwrap_err();
+        assert!(matches!(error, InvalidQuery::FieldDoesNotExist { .. }));
+    }
+
+    #[test]
+    fn test_escape_regex() {
+        assert_eq!(escape_regex("error.code"), "error\\x{2e}code");
+        assert_eq!(escape_regex("a\u{1}b\0"), "a\\x{1}b\\x{0}");
+    }
+}
@@ -0,0 +1,672 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema as TantivySchema;
+
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{
+    BoolQuery, BuildTantivyAst, FullTextMode, FullTextParams, FullTextQuery, PhrasePrefixQuery,
+    QueryAst,
+};
+use crate::search_field::{boost_query_ast, parse_search_fields};
+use crate::{BooleanOperand, InvalidQuery, MatchAllOrNone, NotNaNf32};
+
+const PREFIX_MAX_EXPANSIONS: u32 = 50;
+
+/// Operators of the simple query string syntax that can be enabled individually.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SimpleQueryStringFlag {
+    /// `+` combines clauses as a conjunction.
+    And,
+    /// `|` combines clauses as a disjunction.
+    Or,
+    /// `-` negates the following clause.
+    Not,
+    /// A trailing `*` turns a term into a prefix query.
+    Prefix,
+    /// `"` delimits phrases.
+    Phrase,
+    /// `(` and `)` group clauses.
+    Precedence,
+    /// `\` escapes the following character.
+    Escape,
+    /// Whitespaces separate clauses.
+    Whitespace,
+    /// `~N` after a term sets its fuzziness. Fuzzy matching is not supported: the suffix is
+    /// parsed and ignored.
+    Fuzzy,
+    /// `~N` after a phrase sets its slop. Same as `Slop`.
+    Near,
+    /// `~N` after a phrase sets its slop.
+    Slop,
+}
+
+const ALL_FLAGS: [SimpleQueryStringFlag; 11] = [
+    SimpleQueryStringFlag::And,
+    SimpleQueryStringFlag::Or,
+    SimpleQueryStringFlag::Not,
+    SimpleQueryStringFlag::Prefix,
+    SimpleQueryStringFlag::Phrase,
+    SimpleQueryStringFlag::Precedence,
+    SimpleQueryStringFlag::Escape,
+    SimpleQueryStringFlag::Whitespace,
+    SimpleQueryStringFlag::Fuzzy,
+    SimpleQueryStringFlag::Near,
+    SimpleQueryStringFlag::Slop,
+];
+
+impl SimpleQueryStringFlag {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SimpleQueryStringFlag::And => "AND",
+            SimpleQueryStringFlag::Or => "OR",
+            SimpleQueryStringFlag::Not => "NOT",
+            SimpleQueryStringFlag::Prefix => "PREFIX",
+            SimpleQueryStringFlag::Phrase => "PHRASE",
+            SimpleQueryStringFlag::Precedence => "PRECEDENCE",
+            SimpleQueryStringFlag::Escape => "ESCAPE",
+            SimpleQueryStringFlag::Whitespace => "WHITESPACE",
+            SimpleQueryStringFlag::Fuzzy => "FUZZY",
+            SimpleQueryStringFlag::Near => "NEAR",
+            SimpleQueryStringFlag::Slop => "SLOP",
+        }
+    }
+
+    fn mask(self) -> u16 {
+        1u16 << self as u16
+    }
+}
+
+/// Set of enabled [`SimpleQueryStringFlag`].
+///
+/// It is (de)serialized as a list of flag names separated by `|`, e.g. `OR|AND|PREFIX`, like
+/// the `flags` parameter of the Elasticsearch `simple_query_string` query. `ALL` and `NONE`
+/// are accepted too.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq)]
+#[serde(into = "String", try_from = "String")]
+pub struct SimpleQueryStringFlags(u16);
+
+impl SimpleQueryStringFlags {
+    pub const ALL: SimpleQueryStringFlags = SimpleQueryStringFlags((1u16 << ALL_FLAGS.len()) - 1);
+    pub const NONE: SimpleQueryStringFlags = SimpleQueryStringFlags(0);
+
+    pub fn contains(&self, flag: SimpleQueryStringFlag) -> bool {
+        self.0 & flag.mask() != 0
+    }
+
+    pub fn is_all(&self) -> bool {
+        *self == SimpleQueryStringFlags::ALL
+    }
+}
+
+impl Default for SimpleQueryStringFlags {
+    fn default() -> Self {
+        SimpleQueryStringFlags::ALL
+    }
+}
+
+impl FromStr for SimpleQueryStringFlags {
+    type Err = String;
+
+    fn from_str(flags_str: &str) -> Result<Self, String> {
+        let mut flags = SimpleQueryStringFlags::NONE;
+        for flag_str in flags_str.split('|') {
+            let flag_str = flag_str.trim();
+            if flag_str.eq_ignore_ascii_case("ALL") {
+                flags = SimpleQueryStringFlags::ALL;
+                continue;
+            }
+            if flag_str.is_empty() || flag_str.eq_ignore_ascii_case("NONE") {
+                continue;
+            }
+            let flag = ALL_FLAGS
+                .iter()
+                .find(|flag| flag.as_str().eq_ignore_ascii_case(flag_str))
+                .ok_or_else(|| format!("Unknown simple query string flag `{flag_str}`."))?;
+            flags.0 |= flag.mask();
+        }
+        Ok(flags)
+    }
+}
+
+impl TryFrom<String> for SimpleQueryStringFlags {
+    type Error = String;
+
+    fn try_from(flags_str: String) -> Result<Self, String> {
+        flags_str.parse()
+    }
+}
+
+impl From<SimpleQueryStringFlags> for String {
+    fn from(flags: SimpleQueryStringFlags) -> String {
+        if flags.is_all() {
+            return "ALL".to_string();
+        }
+        if flags == SimpleQueryStringFlags::NONE {
+            return "NONE".to_string();
+        }
+        let flag_strs: Vec<&str> = ALL_FLAGS
+            .iter()
+            .filter(|flag| flags.contains(**flag))
+            .map(|flag| flag.as_str())
+            .collect();
+        flag_strs.join("|")
+    }
+}
+
+/// A query expressed in the simple query string syntax.
+///
+/// This syntax is meant for search boxes exposed to end users: contrary to the grammar of
+/// [`super::UserInputQuery`], parsing never fails. Malformed or disabled operators are
+/// treated as regular text.
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-simple-query-string-query.html>
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SimpleUserInputQuery {
+    pub user_text: String,
+    // Set of search fields to search into.
+    //
+    // If None, the default search fields, as defined in the DocMapper
+    // will be used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_fields: Option<Vec<String>>,
+    pub default_operator: BooleanOperand,
+    #[serde(default, skip_serializing_if = "SimpleQueryStringFlags::is_all")]
+    pub flags: SimpleQueryStringFlags,
+}
+
+impl SimpleUserInputQuery {
+    /// Parses the user query to generate a structured QueryAST, without any user input node.
+    ///
+    /// The `default_fields` of the query take precedence over the `default_search_fields`.
+    pub fn parse_user_query(&self, default_search_fields: &[String]) -> anyhow::Result<QueryAst> {
+        let search_fields = self
+            .default_fields
+            .as_ref()
+            .map(|search_fields| &search_fields[..])
+            .unwrap_or(default_search_fields);
+        let search_fields = parse_search_fields(search_fields)?;
+        if search_fields.is_empty() {
+            anyhow::bail!("Query requires a default search field and none was supplied.");
+        }
+        let mut parser = SimpleQueryParser {
+            chars: self.user_text.chars().collect(),
+            pos: 0,
+            flags: self.flags,
+            default_operator: self.default_operator,
+            search_fields,
+        };
+        Ok(parser.parse_group(false).unwrap_or(QueryAst::MatchNone))
+    }
+}
+
+impl From<SimpleUserInputQuery> for QueryAst {
+    fn from(simple_user_input_query: SimpleUserInputQuery) -> Self {
+        QueryAst::SimpleUserInput(simple_user_input_query)
+    }
+}
+
+impl BuildTantivyAst for SimpleUserInputQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        _schema: &TantivySchema,
+        _default_search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        Err(InvalidQuery::UserQueryNotParsed)
+    }
+}
+
+fn bool_query_from_clauses(operator: BooleanOperand, clauses: Vec<QueryAst>) -> BoolQuery {
+    match operator {
+        BooleanOperand::And => BoolQuery {
+            must: clauses,
+            ..Default::default()
+        },
+        BooleanOperand::Or => BoolQuery {
+            should: clauses,
+            ..Default::default()
+        },
+    }
+}
+
+/// Combines the clauses of a group from left to right: a clause joined with a different
+/// operator than the previous one wraps everything on its left.
+#[derive(Default)]
+struct GroupBuilder {
+    query_ast_opt: Option<QueryAst>,
+    operator_opt: Option<BooleanOperand>,
+}
+
+impl GroupBuilder {
+    fn push(&mut self, operator: BooleanOperand, query_ast: QueryAst) {
+        let Some(previous_query_ast) = self.query_ast_opt.take() else {
+            self.query_ast_opt = Some(query_ast);
+            return;
+        };
+        let combined_query_ast = match previous_query_ast {
+            QueryAst::Bool(mut bool_query) if self.operator_opt == Some(operator) => {
+                match operator {
+                    BooleanOperand::And => bool_query.must.push(query_ast),
+                    BooleanOperand::Or => bool_query.should.push(query_ast),
+                }
+                bool_query.into()
+            }
+            previous_query_ast => {
+                self.operator_opt = Some(operator);
+                bool_query_from_clauses(operator, vec![previous_query_ast, query_ast]).into()
+            }
+        };
+        self.query_ast_opt = Some(combined_query_ast);
+    }
+}
+
+enum Leaf {
+    Term(String),
+    Prefix(String),
+    Phrase { phrase: String, slop: u32 },
+}
+
+struct SimpleQueryParser {
+    chars: Vec<char>,
+    pos: usize,
+    flags: SimpleQueryStringFlags,
+    default_operator: BooleanOperand,
+    search_fields: Vec<(String, Option<NotNaNf32>)>,
+}
+
+impl SimpleQueryParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn is_enabled(&self, flag: SimpleQueryStringFlag) -> bool {
+        self.flags.contains(flag)
+    }
+
+    /// Returns true if `c` ends the term being parsed.
+    fn is_term_delimiter(&self, c: char) -> bool {
+        (c.is_whitespace() && self.is_enabled(SimpleQueryStringFlag::Whitespace))
+            || (c == '|' && self.is_enabled(SimpleQueryStringFlag::Or))
+            || (c == '+' && self.is_enabled(SimpleQueryStringFlag::And))
+            || ((c == '(' || c == ')') && self.is_enabled(SimpleQueryStringFlag::Precedence))
+            || (c == '"' && self.is_enabled(SimpleQueryStringFlag::Phrase))
+    }
+
+    /// Parses clauses until the end of the text or, if `nested` is true, until the closing
+    /// parenthesis of the current group.
+    ///
+    /// Returns `None` if the group does not contain any clause.
+    fn parse_group(&mut self, nested: bool) -> Option<QueryAst> {
+        let mut group_builder = GroupBuilder::default();
+        let mut operator_opt: Option<BooleanOperand> = None;
+        let mut negated = false;
+        while let Some(c) = self.peek() {
+            let clause_opt = match c {
+                '|' if self.is_enabled(SimpleQueryStringFlag::Or) => {
+                    self.pos += 1;
+                    operator_opt = Some(BooleanOperand::Or);
+                    continue;
+                }
+                '+' if self.is_enabled(SimpleQueryStringFlag::And) => {
+                    self.pos += 1;
+                    operator_opt = Some(BooleanOperand::And);
+                    continue;
+                }
+                '-' if self.is_enabled(SimpleQueryStringFlag::Not) => {
+                    self.pos += 1;
+                    negated = !negated;
+                    continue;
+                }
+                '(' if self.is_enabled(SimpleQueryStringFlag::Precedence) => {
+                    self.pos += 1;
+                    self.parse_group(true)
+                }
+                ')' if self.is_enabled(SimpleQueryStringFlag::Precedence) => {
+                    self.pos += 1;
+                    if nested {
+                        break;
+                    }
+                    // Unbalanced closing parentheses are ignored.
+                    continue;
+                }
+                '"' if self.is_enabled(SimpleQueryStringFlag::Phrase) => {
+                    let leaf_opt = self.parse_phrase();
+                    leaf_opt.map(|leaf| self.build_leaf_query_ast(leaf))
+                }
+                c if c.is_whitespace() && self.is_enabled(SimpleQueryStringFlag::Whitespace) => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {
+                    let leaf_opt = self.parse_term();
+                    leaf_opt.map(|leaf| self.build_leaf_query_ast(leaf))
+                }
+            };
+            let operator = operator_opt.take().unwrap_or(self.default_operator);
+            let is_negated = std::mem::take(&mut negated);
+            let Some(clause) = clause_opt else {
+                continue;
+            };
+            let clause = if is_negated {
+                BoolQuery {
+                    must: vec![QueryAst::MatchAll],
+                    must_not: vec![clause],
+                    ..Default::default()
+                }
+                .into()
+            } else {
+                clause
+            };
+            group_builder.push(operator, clause);
+        }
+        group_builder.query_ast_opt
+    }
+
+    fn parse_term(&mut self) -> Option<Leaf> {
+        let mut term = String::new();
+        let mut is_prefix = false;
+        while let Some(c) = self.peek() {
+            if c == '\\' && self.is_enabled(SimpleQueryStringFlag::Escape) {
+                self.pos += 1;
+                if let Some(escaped_char) = self.peek() {
+                    term.push(escaped_char);
+                    self.pos += 1;
+                }
+                is_prefix = false;
+                continue;
+            }
+            if self.is_term_delimiter(c) {
+                break;
+            }
+            if c == '~' && self.is_enabled(SimpleQueryStringFlag::Fuzzy) {
+                self.pos += 1;
+                // Fuzzy matching is not supported: the fuzziness is parsed and ignored.
+                self.parse_number();
+                break;
+            }
+            term.push(c);
+            is_prefix = c == '*' && self.is_enabled(SimpleQueryStringFlag::Prefix);
+            self.pos += 1;
+        }
+        if is_prefix {
+            let prefix = term.trim_end_matches('*');
+            if prefix.is_empty() {
+                return None;
+            }
+            return Some(Leaf::Prefix(prefix.to_string()));
+        }
+        if term.is_empty() {
+            return None;
+        }
+        Some(Leaf::Term(term))
+    }
+
+    fn parse_phrase(&mut self) -> Option<Leaf> {
+        // Skips the opening quote.
+        self.pos += 1;
+        let mut phrase = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '\\' && self.is_enabled(SimpleQueryStringFlag::Escape) {
+                if let Some(escaped_char) = self.peek() {
+                    phrase.push(escaped_char);
+                    self.pos += 1;
+                }
+                continue;
+            }
+            if c == '"' {
+                break;
+            }
+            phrase.push(c);
+        }
+        let mut slop = 0;
+        if self.peek() == Some('~')
+            && (self.is_enabled(SimpleQueryStringFlag::Slop)
+                || self.is_enabled(SimpleQueryStringFlag::Near))
+        {
+            self.pos += 1;
+            slop = self.parse_number().unwrap_or(0);
+        }
+        if phrase.trim().is_empty() {
+            return None;
+        }
+        Some(Leaf::Phrase { phrase, slop })
+    }
+
+    fn parse_number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+            self.pos += 1;
+        }
+        let number_str: String = self.chars[start..self.pos].iter().collect();
+        number_str.parse().ok()
+    }
+
+    fn build_leaf_query_ast(&self, leaf: Leaf) -> QueryAst {
+        let mut field_query_asts: Vec<QueryAst> = self
+            .search_fields
+            .iter()
+            .map(|(field, boost_opt)| {
+                let field_query_ast = match &leaf {
+                    Leaf::Term(term) => FullTextQuery {
+                        field: field.clone(),
+                        text: term.clone(),
+                        params: FullTextParams {
+                            tokenizer: None,
+                            mode: self.default_operator.into(),
+                            zero_terms_query: MatchAllOrNone::MatchNone,
+                            lenient: false,
+                        },
+                        preferred_field: None,
+                    }
+                    .into(),
+                    Leaf::Prefix(prefix) => PhrasePrefixQuery {
+                        field: field.clone(),
+                        phrase: prefix.clone(),
+                        max_expansions: PREFIX_MAX_EXPANSIONS,
+                        analyzer: FullTextParams {
+                            tokenizer: None,
+                            mode: FullTextMode::Phrase { slop: 0 },
+                            zero_terms_query: MatchAllOrNone::MatchNone,
+                            lenient: false,
+                        },
+                    }
+                    .into(),
+                    Leaf::Phrase { phrase, slop } => FullTextQuery {
+                        field: field.clone(),
+                        text: phrase.clone(),
+                        params: FullTextParams {
+                            tokenizer: None,
+                            mode: FullTextMode::Phrase { slop: *slop },
+                            zero_terms_query: MatchAllOrNone::MatchNone,
+                            lenient: false,
+                        },
+                        preferred_field: None,
+                    }
+                    .into(),
+                };
+                boost_query_ast(field_query_ast, *boost_opt)
+            })
+            .collect();
+        if field_query_asts.len() == 1 {
+            return field_query_asts.pop().unwrap();
+        }
+        bool_query_from_clauses(BooleanOperand::Or, field_query_asts).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SimpleQueryStringFlag, SimpleQueryStringFlags, SimpleUserInputQuery};
+    use crate::query_ast::{BoolQuery, FullTextMode, QueryAst};
+    use crate::BooleanOperand;
+
+    fn parse(user_text: &str, flags: SimpleQueryStringFlags) -> QueryAst {
+        SimpleUserInputQuery {
+            user_text: user_text.to_string(),
+            default_fields: None,
+            default_operator: BooleanOperand::Or,
+            flags,
+        }
+        .parse_user_query(&["body".to_string()])
+        .unwrap()
+    }
+
+    fn full_text(query_ast: &QueryAst) -> (&str, FullTextMode) {
+        let QueryAst::FullText(full_text_query) = query_ast else {
+            panic!("Expected a full text query, got {query_ast:?}.");
+        };
+        (&full_text_query.text, full_text_query.params.mode)
+    }
+
+    #[test]
+    fn test_simple_query_string_flags() {
+        let flags: SimpleQueryStringFlags = "OR|and| prefix".parse().unwrap();
+        assert!(flags.contains(SimpleQueryStringFlag::Or));
+        assert!(flags.contains(SimpleQueryStringFlag::And));
+        assert!(flags.contains(SimpleQueryStringFlag::Prefix));
+        assert!(!flags.contains(SimpleQueryStringFlag::Not));
+        assert_eq!(String::from(flags), "AND|OR|PREFIX");
+        assert!("ALL".parse::<SimpleQueryStringFlags>().unwrap().is_all());
+        assert_eq!(
+            "NONE".parse::<SimpleQueryStringFlags>().unwrap(),
+            SimpleQueryStringFlags::NONE
+        );
+        assert!("WILDCARD".parse::<SimpleQueryStringFlags>().is_err());
+    }
+
+    #[test]
+    fn test_simple_query_string_default_operator() {
+        let query_ast = parse("hello world", SimpleQueryStringFlags::ALL);
+        let QueryAst::Bool(BoolQuery { should, .. }) = query_ast else { panic!() };
+        assert_eq!(should.len(), 2);
+        assert_eq!(full_text(&should[0]).0, "hello");
+        assert_eq!(full_text(&should[1]).0, "world");
+    }
+
+    #[test]
+    fn test_simple_query_string_operators() {
+        // `a + b | c` is `(a AND b) OR c`.
+        let query_ast = parse("a + b | c", SimpleQueryStringFlags::ALL);
+        let QueryAst::Bool(BoolQuery { should, .. }) = query_ast else { panic!() };
+        assert_eq!(should.len(), 2);
+        let QueryAst::Bool(BoolQuery { must, .. }) = &should[0] else { panic!() };
+        assert_eq!(must.len(), 2);
+        assert_eq!(full_text(&should[1]).0, "c");
+
+        // Parentheses group clauses.
+        let query_ast = parse("a +(b | c)", SimpleQueryStringFlags::ALL);
+        let QueryAst::Bool(BoolQuery { must, .. }) = query_ast else { panic!() };
+        assert_eq!(full_text(&must[0]).0, "a");
+        let QueryAst::Bool(BoolQuery { should, .. }) = &must[1] else { panic!() };
+        assert_eq!(should.len(), 2);
+    }
+
+    #[test]
+    fn test_simple_query_string_not() {
+        let QueryAst::Bool(BoolQuery {
+            must: and_clauses, ..
+        }) = parse("a + -b", SimpleQueryStringFlags::ALL)
+        else {
+            panic!()
+        };
+        let QueryAst::Bool(BoolQuery { must, must_not, .. }) = &and_clauses[1] else { panic!() };
+        assert_eq!(must, &vec![QueryAst::MatchAll]);
+        assert_eq!(full_text(&must_not[0]).0, "b");
+        // `-` within a term is regular text.
+        assert_eq!(
+            full_text(&parse("e-mail", SimpleQueryStringFlags::ALL)).0,
+            "e-mail"
+        );
+    }
+
+    #[test]
+    fn test_simple_query_string_phrase_and_prefix() {
+        let query_ast = parse("\"hello world\"~2", SimpleQueryStringFlags::ALL);
+        assert_eq!(
+            full_text(&query_ast),
+            ("hello world", FullTextMode::Phrase { slop: 2 })
+        );
+        let query_ast = parse("hel*", SimpleQueryStringFlags::ALL);
+        let QueryAst::PhrasePrefix(phrase_prefix_query) = query_ast else { panic!() };
+        assert_eq!(phrase_prefix_query.phrase, "hel");
+        // Fuzziness is ignored.
+        assert_eq!(
+            full_text(&parse("hello~1", SimpleQueryStringFlags::ALL)).0,
+            "hello"
+        );
+    }
+
+    #[test]
+    fn test_simple_query_string_never_fails() {
+        for user_text in [
+            "\"unbalanced",
+            "(a | b",
+            "a)) |",
+            "+",
+            "-",
+            "\\",
+            "~",
+            "()",
+            "\"\"",
+            "",
+        ] {
+            SimpleUserInputQuery {
+                user_text: user_text.to_string(),
+                default_fields: Some(vec!["body".to_string()]),
+                default_operator: BooleanOperand::And,
+                flags: SimpleQueryStringFlags::ALL,
+            }
+            .parse_user_query(&[])
+            .unwrap();
+        }
+        assert_eq!(parse("", SimpleQueryStringFlags::ALL), QueryAst::MatchNone);
+        let QueryAst::Bool(BoolQuery { should, .. }) = parse("(a | b", SimpleQueryStringFlags::ALL)
+        else {
+            panic!()
+        };
+        assert_eq!(should.len(), 2);
+    }
+
+    #[test]
+    fn test_simple_query_string_disabled_flags() {
+        let flags: SimpleQueryStringFlags = "WHITESPACE".parse().unwrap();
+        let QueryAst::Bool(BoolQuery { should, .. }) = parse("a|b -c", flags) else { panic!() };
+        assert_eq!(full_text(&should[0]).0, "a|b");
+        assert_eq!(full_text(&should[1]).0, "-c");
+    }
+
+    #[test]
+    fn test_simple_query_string_several_fields() {
+        let query_ast = SimpleUserInputQuery {
+            user_text: "hello".to_string(),
+            default_fields: Some(vec!["title".to_string(), "body".to_string()]),
+            default_operator: BooleanOperand::And,
+            flags: SimpleQueryStringFlags::ALL,
+        }
+        .parse_user_query(&[])
+        .unwrap();
+        let QueryAst::Bool(BoolQuery { should, .. }) = query_ast else { panic!() };
+        assert_eq!(should.len(), 2);
+    }
+}
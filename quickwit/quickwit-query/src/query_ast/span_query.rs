@@ -0,0 +1,372 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema as TantivySchema;
+
+use crate::query_ast::intervals_query::{build_intervals_tantivy_ast, IntervalsSource, TokenizeFn};
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{BuildTantivyAst, QueryAst};
+use crate::InvalidQuery;
+
+fn default_in_order() -> bool {
+    true
+}
+
+fn is_zero(val: &u32) -> bool {
+    *val == 0
+}
+
+/// Rule matching spans of positions, as the span queries of Elasticsearch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanRule {
+    SpanTerm(SpanTermRule),
+    SpanOr(SpanOrRule),
+    SpanNear(SpanNearRule),
+    SpanFirst(SpanFirstRule),
+    SpanNot(SpanNotRule),
+}
+
+/// Matches the positions of a term. Like term queries, the value is not analyzed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanTermRule {
+    pub value: String,
+}
+
+/// Matches the spans of any of its clauses.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanOrRule {
+    pub clauses: Vec<SpanRule>,
+}
+
+/// Matches the spans covering a span of each of its clauses, with at most `slop` positions
+/// not covered by the spans of the clauses.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanNearRule {
+    pub clauses: Vec<SpanRule>,
+    pub slop: u32,
+    /// If true, the spans must appear in the order of the clauses, without overlapping.
+    #[serde(default = "default_in_order")]
+    pub in_order: bool,
+}
+
+/// Matches the spans of its rule ending before the position `end`, i.e. within the first `end`
+/// positions of the field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanFirstRule {
+    #[serde(rename = "match")]
+    pub rule: Box<SpanRule>,
+    pub end: u32,
+}
+
+/// Matches the spans of `include` that do not overlap a span of `exclude`. The spans of
+/// `include` are extended by `pre` positions before and `post` positions after for the overlap
+/// check.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SpanNotRule {
+    pub include: Box<SpanRule>,
+    pub exclude: Box<SpanRule>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pre: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub post: u32,
+}
+
+/// Matches documents based on the positions of terms, as described by a tree of span rules.
+///
+/// The field must be a text field with positions indexed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SpanQuery {
+    pub field: String,
+    pub rule: SpanRule,
+}
+
+impl From<SpanQuery> for QueryAst {
+    fn from(span_query: SpanQuery) -> Self {
+        QueryAst::Span(span_query)
+    }
+}
+
+impl SpanRule {
+    /// Compiles the rule into an intervals source. Returns `None` if the rule cannot match any
+    /// document.
+    fn compile(&self, tokenize: &TokenizeFn) -> Result<Option<IntervalsSource>, InvalidQuery> {
+        match self {
+            SpanRule::SpanTerm(span_term_rule) => {
+                let mut terms = tokenize(&span_term_rule.value, &Some("raw".to_string()))?;
+                Ok(terms.pop().map(IntervalsSource::Term))
+            }
+            SpanRule::SpanOr(span_or_rule) => {
+                let mut sources = Vec::with_capacity(span_or_rule.clauses.len());
+                for rule in &span_or_rule.clauses {
+                    if let Some(source) = rule.compile(tokenize)? {
+                        sources.push(source);
+                    }
+                }
+                if sources.is_empty() {
+                    return Ok(None);
+                }
+                Ok(Some(IntervalsSource::AnyOf(sources)))
+            }
+            SpanRule::SpanNear(span_near_rule) => {
+                if span_near_rule.clauses.is_empty() {
+                    return Err(InvalidQuery::Other(anyhow::anyhow!(
+                        "A `span_near` rule requires at least one clause."
+                    )));
+                }
+                let mut sources = Vec::with_capacity(span_near_rule.clauses.len());
+                for rule in &span_near_rule.clauses {
+                    let Some(source) = rule.compile(tokenize)? else {
+                        return Ok(None);
+                    };
+                    sources.push(source);
+                }
+                Ok(Some(IntervalsSource::AllOf {
+                    sources,
+                    ordered: span_near_rule.in_order,
+                    max_gaps: Some(span_near_rule.slop),
+                }))
+            }
+            SpanRule::SpanFirst(span_first_rule) => {
+                let Some(source) = span_first_rule.rule.compile(tokenize)? else {
+                    return Ok(None);
+                };
+                Ok(Some(IntervalsSource::EndsBefore {
+                    source: Box::new(source),
+                    end: span_first_rule.end,
+                }))
+            }
+            SpanRule::SpanNot(span_not_rule) => {
+                let Some(include) = span_not_rule.include.compile(tokenize)? else {
+                    return Ok(None);
+                };
+                let Some(exclude) = span_not_rule.exclude.compile(tokenize)? else {
+                    return Ok(Some(include));
+                };
+                Ok(Some(IntervalsSource::NotOverlapping {
+                    include: Box::new(include),
+                    exclude: Box::new(exclude),
+                    pre: span_not_rule.pre,
+                    post: span_not_rule.post,
+                }))
+            }
+        }
+    }
+}
+
+impl BuildTantivyAst for SpanQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        build_intervals_tantivy_ast(&self.field, schema, "span", |tokenize| {
+            self.rule.compile(tokenize)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TEXT};
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{self, search_docs};
+
+    fn span_term(value: &str) -> SpanRule {
+        SpanRule::SpanTerm(SpanTermRule {
+            value: value.to_string(),
+        })
+    }
+
+    fn span_near(values: &[&str], slop: u32, in_order: bool) -> SpanRule {
+        SpanRule::SpanNear(SpanNearRule {
+            clauses: values.iter().map(|value| span_term(value)).collect(),
+            slop,
+            in_order,
+        })
+    }
+
+    fn search_rule(index: &Index, rule: SpanRule) -> Vec<u32> {
+        let query_ast = QueryAst::from(SpanQuery {
+            field: "body".to_string(),
+            rule,
+        });
+        search_docs(index, &query_ast)
+    }
+
+    fn build_test_index() -> Index {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let docs = [
+            "connection refused by remote host",
+            "remote host refused the connection",
+            "connection to the remote server was refused by host",
+            "nothing to see here",
+        ]
+        .into_iter()
+        .map(|body| doc!(body_field => body));
+        test_helpers::build_test_index(schema_builder.build(), docs)
+    }
+
+    #[test]
+    fn test_span_query_deserialize() {
+        let span_query_json = r#"{
+            "field": "body",
+            "rule": {
+                "span_not": {
+                    "include": {
+                        "span_near": {
+                            "clauses": [
+                                { "span_term": { "value": "refused" } },
+                                { "span_or": { "clauses": [
+                                    { "span_term": { "value": "host" } },
+                                    { "span_term": { "value": "server" } }
+                                ] } }
+                            ],
+                            "slop": 3,
+                            "in_order": false
+                        }
+                    },
+                    "exclude": {
+                        "span_first": { "match": { "span_term": { "value": "remote" } }, "end": 1 }
+                    },
+                    "post": 1
+                }
+            }
+        }"#;
+        let span_query: SpanQuery = serde_json::from_str(span_query_json).unwrap();
+        let SpanRule::SpanNot(span_not_rule) = &span_query.rule else { panic!() };
+        assert_eq!(span_not_rule.pre, 0);
+        assert_eq!(span_not_rule.post, 1);
+        let SpanRule::SpanNear(span_near_rule) = &*span_not_rule.include else { panic!() };
+        assert_eq!(span_near_rule.clauses.len(), 2);
+        assert_eq!(span_near_rule.slop, 3);
+        assert!(!span_near_rule.in_order);
+        let SpanRule::SpanFirst(span_first_rule) = &*span_not_rule.exclude else { panic!() };
+        assert_eq!(span_first_rule.end, 1);
+        assert_eq!(*span_first_rule.rule, span_term("remote"));
+    }
+
+    #[test]
+    fn test_span_term_and_span_or_rules() {
+        let index = build_test_index();
+        assert_eq!(search_rule(&index, span_term("server")), vec![2]);
+        // Like term queries, span terms are not analyzed.
+        assert!(search_rule(&index, span_term("Server")).is_empty());
+        let rule = SpanRule::SpanOr(SpanOrRule {
+            clauses: vec![span_term("server"), span_term("nothing")],
+        });
+        assert_eq!(search_rule(&index, rule), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_span_near_rule() {
+        let index = build_test_index();
+        assert_eq!(
+            search_rule(&index, span_near(&["connection", "refused"], 0, true)),
+            vec![0]
+        );
+        assert_eq!(
+            search_rule(&index, span_near(&["connection", "refused"], 5, true)),
+            vec![0, 2]
+        );
+        assert_eq!(
+            search_rule(&index, span_near(&["connection", "refused"], 3, false)),
+            vec![0, 1]
+        );
+        assert_eq!(
+            search_rule(&index, span_near(&["refused", "remote", "host"], 1, false)),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_span_first_rule() {
+        let index = build_test_index();
+        let span_first = |value: &str, end: u32| {
+            SpanRule::SpanFirst(SpanFirstRule {
+                rule: Box::new(span_term(value)),
+                end,
+            })
+        };
+        assert_eq!(search_rule(&index, span_first("connection", 1)), vec![0, 2]);
+        assert_eq!(search_rule(&index, span_first("refused", 2)), vec![0]);
+        assert_eq!(search_rule(&index, span_first("refused", 3)), vec![0, 1]);
+        assert!(search_rule(&index, span_first("host", 0)).is_empty());
+    }
+
+    #[test]
+    fn test_span_not_rule() {
+        let index = build_test_index();
+        let span_not = |include: SpanRule, exclude: SpanRule, pre: u32, post: u32| {
+            SpanRule::SpanNot(SpanNotRule {
+                include: Box::new(include),
+                exclude: Box::new(exclude),
+                pre,
+                post,
+            })
+        };
+        // "host" not preceded by "remote".
+        assert_eq!(
+            search_rule(
+                &index,
+                span_not(span_term("host"), span_term("remote"), 1, 0)
+            ),
+            vec![2]
+        );
+        // "refused" not followed by "by".
+        assert_eq!(
+            search_rule(
+                &index,
+                span_not(span_term("refused"), span_term("by"), 0, 1)
+            ),
+            vec![1]
+        );
+        // Without extension, excluded spans must overlap the included spans.
+        assert_eq!(
+            search_rule(
+                &index,
+                span_not(span_term("host"), span_term("remote"), 0, 0)
+            ),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            search_rule(
+                &index,
+                span_not(
+                    span_near(&["remote", "host"], 0, true),
+                    span_term("host"),
+                    0,
+                    0
+                )
+            ),
+            Vec::<u32>::new()
+        );
+    }
+}
@@ -0,0 +1,779 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! [proptest](https://docs.rs/proptest) strategies generating random [`QueryAst`]s and random
+//! schemas to run them against.
+//!
+//! They back the property tests of this crate and its fuzz target. They are exposed under the
+//! `testsuite` feature so that other crates can check their own handling of query ASTs.
+//!
+//! Generated queries only target the fields of [`QUERY_FIELD_NAMES`] so that they have a
+//! reasonable chance to hit the fields of the schemas generated by [`schema_strategy`].
+
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+use proptest::collection::{btree_set, hash_map, vec};
+use proptest::prelude::*;
+use proptest::sample::select;
+use tantivy::schema::{
+    BytesOptions, DateOptions, IndexRecordOption, IpAddrOptions, JsonObjectOptions, NumericOptions,
+    Schema as TantivySchema, SchemaBuilder, TextFieldIndexing, TextOptions,
+};
+
+use super::{
+    BoolQuery, BoostingQuery, CombinedFieldsQuery, DecayFunction, DecayFunctionType, DisMaxQuery,
+    DistanceFeatureFunction, FieldPresenceQuery, FieldValueFactorFunction,
+    FieldValueFactorModifier, FullTextMode, FullTextParams, FullTextQuery, FunctionBoostMode,
+    FunctionScoreMode, FunctionScoreQuery, IntervalsAllOfRule, IntervalsAnyOfRule,
+    IntervalsMatchRule, IntervalsQuery, IntervalsRule, KnnQuery, PhrasePrefixQuery, QueryAst,
+    RangeQuery, RegexQuery, ScoreFunction, ScoreFunctionKind, SimpleQueryStringFlags,
+    SimpleUserInputQuery, SpanFirstRule, SpanNearRule, SpanNotRule, SpanOrRule, SpanQuery,
+    SpanRule, SpanTermRule, TermQuery, TermSetQuery, TermsLookupQuery, UdfMode, UdfQuery,
+    UserInputQuery,
+};
+use crate::{
+    BooleanOperand, Fuzziness, JsonLiteral, MatchAllOrNone, MinimumShouldMatch, NotNaNf32,
+};
+
+/// Names of the fields of the schemas generated by [`schema_strategy`].
+pub const FIELD_NAMES: &[&str] = &["f0", "f1", "f2", "f3"];
+
+/// Names of the fields targeted by the generated queries: the schema fields, paths within them
+/// (only valid if the field is a JSON field) and a field that is never part of the schema.
+pub const QUERY_FIELD_NAMES: &[&str] = &["f0", "f1", "f2", "f3", "f0.sub", "f3.sub", "missing"];
+
+const TOKENIZERS: &[&str] = &["default", "raw", "en_stem", "chinese_compatible", "unknown"];
+
+/// Values that are valid for at least one field type.
+const SAMPLE_VALUES: &[&str] = &[
+    "",
+    "0",
+    "42",
+    "-1",
+    "1.5",
+    "18446744073709551615",
+    "true",
+    "false",
+    "2023-01-01T00:00:00Z",
+    "1672531200",
+    "127.0.0.1",
+    "::1",
+    "aGVsbG8=",
+    "hello world",
+];
+
+/// The kinds of field a generated schema is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    TextWithoutPositions,
+    Raw,
+    U64,
+    I64,
+    F64,
+    Bool,
+    Date,
+    IpAddr,
+    Bytes,
+    Json,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 11] = [
+        FieldKind::Text,
+        FieldKind::TextWithoutPositions,
+        FieldKind::Raw,
+        FieldKind::U64,
+        FieldKind::I64,
+        FieldKind::F64,
+        FieldKind::Bool,
+        FieldKind::Date,
+        FieldKind::IpAddr,
+        FieldKind::Bytes,
+        FieldKind::Json,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldKind::Text => "text",
+            FieldKind::TextWithoutPositions => "text_without_positions",
+            FieldKind::Raw => "raw",
+            FieldKind::U64 => "u64",
+            FieldKind::I64 => "i64",
+            FieldKind::F64 => "f64",
+            FieldKind::Bool => "bool",
+            FieldKind::Date => "date",
+            FieldKind::IpAddr => "ip",
+            FieldKind::Bytes => "bytes",
+            FieldKind::Json => "json",
+        }
+    }
+
+    /// Adds a stored field of this kind to the schema.
+    pub fn add_to_schema(
+        &self,
+        schema_builder: &mut SchemaBuilder,
+        field_name: &str,
+        indexed: bool,
+        fast: bool,
+    ) {
+        match self {
+            FieldKind::Text | FieldKind::TextWithoutPositions | FieldKind::Raw => {
+                let (tokenizer, index_record_option) = match self {
+                    FieldKind::Text => ("default", IndexRecordOption::WithFreqsAndPositions),
+                    FieldKind::TextWithoutPositions => ("default", IndexRecordOption::WithFreqs),
+                    _ => ("raw", IndexRecordOption::Basic),
+                };
+                let mut text_options = TextOptions::default().set_stored();
+                if indexed {
+                    let text_field_indexing = TextFieldIndexing::default()
+                        .set_tokenizer(tokenizer)
+                        .set_index_option(index_record_option);
+                    text_options = text_options.set_indexing_options(text_field_indexing);
+                }
+                if fast {
+                    text_options = text_options.set_fast(Some("raw"));
+                }
+                schema_builder.add_text_field(field_name, text_options);
+            }
+            FieldKind::U64 | FieldKind::I64 | FieldKind::F64 | FieldKind::Bool => {
+                let mut numeric_options = NumericOptions::default().set_stored();
+                if indexed {
+                    numeric_options = numeric_options.set_indexed();
+                }
+                if fast {
+                    numeric_options = numeric_options.set_fast();
+                }
+                match self {
+                    FieldKind::U64 => schema_builder.add_u64_field(field_name, numeric_options),
+                    FieldKind::I64 => schema_builder.add_i64_field(field_name, numeric_options),
+                    FieldKind::F64 => schema_builder.add_f64_field(field_name, numeric_options),
+                    _ => schema_builder.add_bool_field(field_name, numeric_options),
+                };
+            }
+            FieldKind::Date => {
+                let mut date_options = DateOptions::default().set_stored();
+                if indexed {
+                    date_options = date_options.set_indexed();
+                }
+                if fast {
+                    date_options = date_options.set_fast();
+                }
+                schema_builder.add_date_field(field_name, date_options);
+            }
+            FieldKind::IpAddr => {
+                let mut ip_addr_options = IpAddrOptions::default().set_stored();
+                if indexed {
+                    ip_addr_options = ip_addr_options.set_indexed();
+                }
+                if fast {
+                    ip_addr_options = ip_addr_options.set_fast();
+                }
+                schema_builder.add_ip_addr_field(field_name, ip_addr_options);
+            }
+            FieldKind::Bytes => {
+                let mut bytes_options = BytesOptions::default().set_stored();
+                if indexed {
+                    bytes_options = bytes_options.set_indexed();
+                }
+                if fast {
+                    bytes_options = bytes_options.set_fast();
+                }
+                schema_builder.add_bytes_field(field_name, bytes_options);
+            }
+            FieldKind::Json => {
+                let mut json_options = JsonObjectOptions::default().set_stored();
+                if indexed {
+                    let text_field_indexing = TextFieldIndexing::default()
+                        .set_tokenizer("default")
+                        .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+                    json_options = json_options.set_indexing_options(text_field_indexing);
+                }
+                if fast {
+                    json_options = json_options.set_fast(Some("raw"));
+                }
+                schema_builder.add_json_field(field_name, json_options);
+            }
+        }
+    }
+}
+
+/// Returns a schema with an indexed and fast field of each kind, named after the kind.
+pub fn schema_with_all_field_kinds() -> TantivySchema {
+    let mut schema_builder = TantivySchema::builder();
+    for field_kind in FieldKind::ALL {
+        field_kind.add_to_schema(&mut schema_builder, field_kind.name(), true, true);
+    }
+    schema_builder.build()
+}
+
+/// Generates schemas made of a random subset of [`FIELD_NAMES`], each with a random kind and
+/// random indexed and fast flags.
+pub fn schema_strategy() -> impl Strategy<Value = TantivySchema> {
+    let field_strategy = (
+        select(FieldKind::ALL.to_vec()),
+        any::<bool>(),
+        any::<bool>(),
+    );
+    vec(proptest::option::of(field_strategy), FIELD_NAMES.len()).prop_map(|fields| {
+        let mut schema_builder = TantivySchema::builder();
+        for (field_name, field_opt) in FIELD_NAMES.iter().zip(fields) {
+            if let Some((field_kind, indexed, fast)) = field_opt {
+                field_kind.add_to_schema(&mut schema_builder, field_name, indexed, fast);
+            }
+        }
+        schema_builder.build()
+    })
+}
+
+fn field_name_strategy() -> impl Strategy<Value = String> {
+    select(QUERY_FIELD_NAMES).prop_map(str::to_string)
+}
+
+fn value_strategy() -> impl Strategy<Value = String> {
+    prop_oneof![
+        select(SAMPLE_VALUES).prop_map(str::to_string),
+        "[a-zA-Z0-9 .:*-]{0,12}",
+    ]
+}
+
+fn json_literal_strategy() -> impl Strategy<Value = JsonLiteral> {
+    prop_oneof![
+        any::<u64>().prop_map(|val| JsonLiteral::Number(val.into())),
+        any::<i64>().prop_map(|val| JsonLiteral::Number(val.into())),
+        // Quarters are represented exactly, so they survive a JSON round trip.
+        (-1_000i32..1_000).prop_map(|val| {
+            let number = serde_json::Number::from_f64(val as f64 / 4.0).unwrap();
+            JsonLiteral::Number(number)
+        }),
+        any::<bool>().prop_map(JsonLiteral::Bool),
+        value_strategy().prop_map(JsonLiteral::String),
+    ]
+}
+
+fn bound_strategy() -> impl Strategy<Value = Bound<JsonLiteral>> {
+    prop_oneof![
+        Just(Bound::Unbounded),
+        json_literal_strategy().prop_map(Bound::Included),
+        json_literal_strategy().prop_map(Bound::Excluded),
+    ]
+}
+
+fn boolean_operand_strategy() -> impl Strategy<Value = BooleanOperand> {
+    prop_oneof![Just(BooleanOperand::And), Just(BooleanOperand::Or)]
+}
+
+fn minimum_should_match_strategy() -> impl Strategy<Value = MinimumShouldMatch> {
+    prop_oneof![
+        (-3i32..4).prop_map(MinimumShouldMatch::Absolute),
+        (-100i32..=100).prop_map(MinimumShouldMatch::Percentage),
+    ]
+}
+
+fn fuzziness_strategy() -> impl Strategy<Value = Fuzziness> {
+    prop_oneof![
+        (0u8..=2).prop_map(Fuzziness::Distance),
+        (0u32..4, 4u32..8).prop_map(|(low, high)| Fuzziness::Auto { low, high }),
+    ]
+}
+
+fn full_text_params_strategy() -> impl Strategy<Value = FullTextParams> {
+    let mode_strategy = prop_oneof![
+        (
+            boolean_operand_strategy(),
+            proptest::option::of(minimum_should_match_strategy()),
+            proptest::option::of(fuzziness_strategy())
+        )
+            .prop_map(
+                |(operator, minimum_should_match, fuzziness)| FullTextMode::Bool {
+                    operator,
+                    minimum_should_match,
+                    fuzziness,
+                }
+            ),
+        Just(FullTextMode::PhraseFallbackToIntersection),
+        (0u32..3).prop_map(|slop| FullTextMode::Phrase { slop }),
+    ];
+    let zero_terms_query_strategy = prop_oneof![
+        Just(MatchAllOrNone::MatchNone),
+        Just(MatchAllOrNone::MatchAll)
+    ];
+    (
+        proptest::option::of(select(TOKENIZERS).prop_map(str::to_string)),
+        mode_strategy,
+        zero_terms_query_strategy,
+    )
+        .prop_map(|(tokenizer, mode, zero_terms_query)| FullTextParams {
+            tokenizer,
+            mode,
+            zero_terms_query,
+            lenient: false,
+        })
+}
+
+fn max_gaps_strategy() -> impl Strategy<Value = i32> {
+    // `-1` is the default and is omitted when serializing.
+    prop_oneof![Just(-1), 0i32..5]
+}
+
+fn intervals_rule_strategy() -> impl Strategy<Value = IntervalsRule> {
+    let match_rule_strategy = (
+        "[a-z ]{0,12}",
+        max_gaps_strategy(),
+        any::<bool>(),
+        proptest::option::of(select(TOKENIZERS).prop_map(str::to_string)),
+    )
+        .prop_map(|(query, max_gaps, ordered, analyzer)| {
+            IntervalsRule::Match(IntervalsMatchRule {
+                query,
+                max_gaps,
+                ordered,
+                analyzer,
+            })
+        });
+    match_rule_strategy.prop_recursive(2, 8, 3, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..3)
+                .prop_map(|intervals| IntervalsRule::AnyOf(IntervalsAnyOfRule { intervals })),
+            (vec(inner, 0..3), max_gaps_strategy(), any::<bool>()).prop_map(
+                |(intervals, max_gaps, ordered)| {
+                    IntervalsRule::AllOf(IntervalsAllOfRule {
+                        intervals,
+                        max_gaps,
+                        ordered,
+                    })
+                }
+            ),
+        ]
+    })
+}
+
+fn span_rule_strategy() -> impl Strategy<Value = SpanRule> {
+    let span_term_rule_strategy =
+        value_strategy().prop_map(|value| SpanRule::SpanTerm(SpanTermRule { value }));
+    span_term_rule_strategy.prop_recursive(2, 8, 3, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..3).prop_map(|clauses| SpanRule::SpanOr(SpanOrRule { clauses })),
+            (vec(inner.clone(), 0..3), 0u32..5, any::<bool>()).prop_map(
+                |(clauses, slop, in_order)| {
+                    SpanRule::SpanNear(SpanNearRule {
+                        clauses,
+                        slop,
+                        in_order,
+                    })
+                }
+            ),
+            (inner.clone(), 0u32..5).prop_map(|(rule, end)| {
+                SpanRule::SpanFirst(SpanFirstRule {
+                    rule: Box::new(rule),
+                    end,
+                })
+            }),
+            (inner.clone(), inner, 0u32..3, 0u32..3).prop_map(|(include, exclude, pre, post)| {
+                SpanRule::SpanNot(SpanNotRule {
+                    include: Box::new(include),
+                    exclude: Box::new(exclude),
+                    pre,
+                    post,
+                })
+            }),
+        ]
+    })
+}
+
+/// Generates strings that the query parser understands, most of the time.
+fn user_text_strategy() -> impl Strategy<Value = String> {
+    let clause_strategy = (
+        select(vec!["", "-", "+"]),
+        proptest::option::of(select(QUERY_FIELD_NAMES)),
+        prop_oneof![
+            "[a-z0-9]{1,6}\\*?",
+            "\"[a-z0-9 ]{1,12}\"",
+            "[\\[{][0-9]{1,3} TO [0-9]{1,3}[\\]}]",
+        ],
+    )
+        .prop_map(|(occur, field_opt, value)| match field_opt {
+            Some(field) => format!("{occur}{field}:{value}"),
+            None => format!("{occur}{value}"),
+        });
+    let separator_strategy = select(vec![" ", " AND ", " OR "]);
+    (
+        clause_strategy.clone(),
+        vec((separator_strategy, clause_strategy), 0..3),
+    )
+        .prop_map(|(first_clause, clauses)| {
+            let mut user_text = first_clause;
+            for (separator, clause) in clauses {
+                user_text.push_str(separator);
+                user_text.push_str(&clause);
+            }
+            user_text
+        })
+}
+
+fn default_fields_strategy() -> impl Strategy<Value = Option<Vec<String>>> {
+    proptest::option::of(vec(field_name_strategy(), 1..3))
+}
+
+fn boost_strategy() -> impl Strategy<Value = NotNaNf32> {
+    // Boosts must be finite to be serialized to JSON.
+    (0u8..=40).prop_map(|quarters| NotNaNf32::try_from(quarters as f32 / 4.0).unwrap())
+}
+
+/// Generates query ASTs that do not contain other query ASTs.
+fn score_function_strategy() -> impl Strategy<Value = ScoreFunction> {
+    let kind_strategy = prop_oneof![
+        (
+            field_name_strategy(),
+            proptest::option::of(boost_strategy())
+        )
+            .prop_map(|(field, missing)| {
+                ScoreFunctionKind::FieldValueFactor(FieldValueFactorFunction {
+                    field,
+                    factor: NotNaNf32::ONE,
+                    modifier: FieldValueFactorModifier::Ln1p,
+                    missing,
+                })
+            }),
+        (
+            field_name_strategy(),
+            proptest::option::of(json_literal_strategy()),
+            json_literal_strategy(),
+            prop_oneof![
+                Just(DecayFunctionType::Gauss),
+                Just(DecayFunctionType::Exp),
+                Just(DecayFunctionType::Linear),
+            ],
+        )
+            .prop_map(|(field, origin, scale, decay_type)| {
+                ScoreFunctionKind::Decay(DecayFunction {
+                    decay_type,
+                    field,
+                    origin,
+                    scale,
+                    offset: None,
+                    decay: NotNaNf32::try_from(0.5).unwrap(),
+                })
+            }),
+        (
+            field_name_strategy(),
+            json_literal_strategy(),
+            json_literal_strategy()
+        )
+            .prop_map(|(field, origin, pivot)| {
+                ScoreFunctionKind::DistanceFeature(DistanceFeatureFunction {
+                    field,
+                    origin,
+                    pivot,
+                })
+            }),
+        Just(ScoreFunctionKind::Constant),
+    ];
+    (kind_strategy, proptest::option::of(boost_strategy())).prop_map(|(kind, weight)| {
+        ScoreFunction {
+            kind,
+            filter: None,
+            weight,
+        }
+    })
+}
+
+pub fn leaf_query_ast_strategy() -> impl Strategy<Value = QueryAst> {
+    prop_oneof![
+        Just(QueryAst::MatchAll),
+        Just(QueryAst::MatchNone),
+        (field_name_strategy(), value_strategy())
+            .prop_map(|(field, value)| QueryAst::Term(TermQuery { field, value })),
+        hash_map(
+            field_name_strategy(),
+            btree_set(value_strategy(), 0..3),
+            0..3
+        )
+        .prop_map(|terms_per_field: HashMap<String, BTreeSet<String>>| {
+            QueryAst::TermSet(TermSetQuery { terms_per_field })
+        }),
+        (field_name_strategy(), value_strategy(), value_strategy()).prop_map(
+            |(field, doc_id, path)| {
+                QueryAst::TermsLookup(TermsLookupQuery {
+                    field,
+                    index_id: "lookup-index".to_string(),
+                    doc_id,
+                    path,
+                })
+            }
+        ),
+        (
+            field_name_strategy(),
+            value_strategy(),
+            full_text_params_strategy()
+        )
+            .prop_map(|(field, text, params)| {
+                QueryAst::FullText(FullTextQuery {
+                    field,
+                    text,
+                    params,
+                    preferred_field: None,
+                })
+            }),
+        (
+            vec(field_name_strategy(), 1..3),
+            value_strategy(),
+            boolean_operand_strategy(),
+            proptest::option::of(minimum_should_match_strategy()),
+            prop_oneof![
+                Just(MatchAllOrNone::MatchNone),
+                Just(MatchAllOrNone::MatchAll)
+            ]
+        )
+            .prop_map(
+                |(fields, text, operator, minimum_should_match, zero_terms_query)| {
+                    QueryAst::CombinedFields(CombinedFieldsQuery {
+                        fields,
+                        text,
+                        operator,
+                        minimum_should_match,
+                        zero_terms_query,
+                    })
+                }
+            ),
+        (
+            field_name_strategy(),
+            value_strategy(),
+            0u32..100,
+            full_text_params_strategy()
+        )
+            .prop_map(|(field, phrase, max_expansions, analyzer)| {
+                QueryAst::PhrasePrefix(PhrasePrefixQuery {
+                    field,
+                    phrase,
+                    max_expansions,
+                    analyzer,
+                })
+            }),
+        (field_name_strategy(), intervals_rule_strategy())
+            .prop_map(|(field, rule)| QueryAst::Intervals(IntervalsQuery { field, rule })),
+        (field_name_strategy(), span_rule_strategy())
+            .prop_map(|(field, rule)| QueryAst::Span(SpanQuery { field, rule })),
+        (
+            field_name_strategy(),
+            vec(boost_strategy(), 0..4),
+            0u32..20,
+            0u32..40
+        )
+            .prop_map(|(field, query_vector, k, num_candidates)| {
+                QueryAst::Knn(KnnQuery {
+                    field,
+                    query_vector,
+                    k,
+                    num_candidates,
+                })
+            }),
+        (field_name_strategy(), value_strategy(), any::<bool>()).prop_map(
+            |(field, regex, case_insensitive)| {
+                QueryAst::Regex(RegexQuery {
+                    field,
+                    regex,
+                    case_insensitive,
+                })
+            }
+        ),
+        (field_name_strategy(), bound_strategy(), bound_strategy()).prop_map(
+            |(field, lower_bound, upper_bound)| {
+                QueryAst::Range(RangeQuery {
+                    field,
+                    lower_bound,
+                    upper_bound,
+                })
+            }
+        ),
+        field_name_strategy()
+            .prop_map(|field| QueryAst::FieldPresence(FieldPresenceQuery { field })),
+        (
+            user_text_strategy(),
+            default_fields_strategy(),
+            boolean_operand_strategy()
+        )
+            .prop_map(|(user_text, default_fields, default_operator)| {
+                QueryAst::UserInput(UserInputQuery {
+                    user_text,
+                    default_fields,
+                    default_operator,
+                    quote_field_suffix: None,
+                    phrase_slop: 0,
+                    lenient: false,
+                    fuzziness: None,
+                })
+            }),
+        (
+            user_text_strategy(),
+            default_fields_strategy(),
+            boolean_operand_strategy(),
+            prop_oneof![
+                Just(SimpleQueryStringFlags::ALL),
+                Just(SimpleQueryStringFlags::NONE)
+            ],
+        )
+            .prop_map(|(user_text, default_fields, default_operator, flags)| {
+                QueryAst::SimpleUserInput(SimpleUserInputQuery {
+                    user_text,
+                    default_fields,
+                    default_operator,
+                    flags,
+                })
+            }),
+    ]
+}
+
+/// Generates arbitrary query ASTs, up to a depth of 4.
+pub fn query_ast_strategy() -> impl Strategy<Value = QueryAst> {
+    leaf_query_ast_strategy().prop_recursive(4, 32, 3, |inner| {
+        prop_oneof![
+            (
+                vec(inner.clone(), 0..3),
+                vec(inner.clone(), 0..3),
+                vec(inner.clone(), 0..3),
+                vec(inner.clone(), 0..3),
+            )
+                .prop_map(|(must, must_not, should, filter)| {
+                    QueryAst::Bool(BoolQuery {
+                        must,
+                        must_not,
+                        should,
+                        filter,
+                        minimum_should_match: None,
+                    })
+                }),
+            (
+                vec(inner.clone(), 0..3),
+                proptest::option::of(boost_strategy()),
+            )
+                .prop_map(|(queries, tie_breaker)| {
+                    QueryAst::DisMax(DisMaxQuery {
+                        queries,
+                        tie_breaker,
+                    })
+                }),
+            (inner.clone(), inner.clone(), boost_strategy()).prop_map(
+                |(positive, negative, negative_boost)| {
+                    QueryAst::Boosting(BoostingQuery {
+                        positive: Box::new(positive),
+                        negative: Box::new(negative),
+                        negative_boost,
+                    })
+                }
+            ),
+            (inner.clone(), boost_strategy()).prop_map(|(underlying, boost)| QueryAst::Boost {
+                underlying: Box::new(underlying),
+                boost,
+            }),
+            (inner.clone(), boost_strategy()).prop_map(|(underlying, score)| {
+                QueryAst::ConstScore {
+                    underlying: Box::new(underlying),
+                    score,
+                }
+            }),
+            (
+                inner.clone(),
+                vec(field_name_strategy(), 0..3),
+                prop_oneof![Just(UdfMode::Filter), Just(UdfMode::Score)],
+            )
+                .prop_map(|(underlying, fields, mode)| {
+                    QueryAst::Udf(UdfQuery {
+                        underlying: Box::new(underlying),
+                        udf: "unregistered_udf".to_string(),
+                        fields,
+                        mode,
+                    })
+                }),
+            (
+                inner,
+                vec(score_function_strategy(), 0..3),
+                prop_oneof![
+                    Just(FunctionScoreMode::Multiply),
+                    Just(FunctionScoreMode::Avg),
+                    Just(FunctionScoreMode::First),
+                ],
+                prop_oneof![
+                    Just(FunctionBoostMode::Multiply),
+                    Just(FunctionBoostMode::Replace),
+                    Just(FunctionBoostMode::Sum),
+                ],
+            )
+                .prop_map(|(underlying, functions, score_mode, boost_mode)| {
+                    QueryAst::FunctionScore(FunctionScoreQuery {
+                        underlying: Box::new(underlying),
+                        functions,
+                        score_mode,
+                        boost_mode,
+                        now_timestamp_micros: None,
+                    })
+                }),
+        ]
+    })
+}
+
+impl Arbitrary for QueryAst {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<QueryAst>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        query_ast_strategy().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use tantivy::schema::Schema as TantivySchema;
+
+    use super::{query_ast_strategy, schema_strategy, FIELD_NAMES};
+    use crate::query_ast::{query_ast_from_json, query_ast_to_json, QueryAst};
+
+    fn build_all_ways(query_ast: &QueryAst, schema: &TantivySchema) {
+        let default_search_fields: Vec<String> =
+            FIELD_NAMES.iter().map(|field| field.to_string()).collect();
+        let Ok(query_ast) = query_ast.clone().parse_user_query(&default_search_fields) else {
+            return;
+        };
+        for with_validation in [false, true] {
+            for with_scoring in [false, true] {
+                // Errors are fine, panics are not.
+                let _ = query_ast.build_tantivy_query(schema, &[], with_validation, with_scoring);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_proptest_query_ast_json_roundtrip(query_ast in query_ast_strategy()) {
+            let query_ast_json = query_ast_to_json(&query_ast).unwrap();
+            let deserialized_query_ast = query_ast_from_json(&query_ast_json).unwrap();
+            prop_assert_eq!(deserialized_query_ast, query_ast);
+        }
+
+        #[test]
+        fn test_proptest_build_query_ast_never_panics(
+            query_ast in query_ast_strategy(),
+            schema in schema_strategy()
+        ) {
+            build_all_ways(&query_ast, &schema);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Query time synonym expansion.
+//!
+//! Synonyms are configured per index and per field. They are applied by rewriting the query AST
+//! before it is sent to the leaves, so updating the synonyms of an index does not require to
+//! reindex its documents.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use crate::query_ast::{
+    BoolQuery, BoostingQuery, DisMaxQuery, FullTextQuery, QueryAst, TermQuery, UdfQuery,
+};
+
+/// Sets of equivalent terms, indexed by the field they apply to.
+#[derive(Debug, Default, Clone)]
+pub struct SynonymDictionary {
+    // Field name -> normalized term -> index of the synonym set in `synonym_sets`.
+    fields: HashMap<String, HashMap<String, usize>>,
+    synonym_sets: Vec<Vec<String>>,
+}
+
+fn normalize_term(term: &str) -> String {
+    term.trim().to_lowercase()
+}
+
+impl SynonymDictionary {
+    /// Declares the `synonyms` as equivalent when searching any of the `fields`.
+    pub fn add_synonym_set(&mut self, fields: &[String], synonyms: &[String]) {
+        let synonym_set_ord = self.synonym_sets.len();
+        self.synonym_sets.push(synonyms.to_vec());
+
+        for field in fields {
+            let field_synonyms = self.fields.entry(field.clone()).or_default();
+            for synonym in synonyms {
+                field_synonyms.insert(normalize_term(synonym), synonym_set_ord);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.synonym_sets.is_empty()
+    }
+
+    /// Returns the synonyms of `text` (including `text` itself) when searched in `field`.
+    fn synonyms(&self, field: &str, text: &str) -> Option<&[String]> {
+        let synonym_set_ord = *self.fields.get(field)?.get(&normalize_term(text))?;
+        Some(&self.synonym_sets[synonym_set_ord])
+    }
+
+    /// Rewrites the full-text and term queries of the AST targeting a term that has synonyms into
+    /// a boolean query matching any of the synonyms.
+    ///
+    /// The AST is expected to be resolved, i.e. user input queries must already be parsed.
+    pub fn expand_synonyms(&self, query_ast: QueryAst) -> QueryAst {
+        if self.is_empty() {
+            return query_ast;
+        }
+        match query_ast {
+            QueryAst::Bool(BoolQuery {
+                must,
+                must_not,
+                should,
+                filter,
+                minimum_should_match,
+            }) => BoolQuery {
+                must: self.expand_synonyms_in_asts(must),
+                must_not: self.expand_synonyms_in_asts(must_not),
+                should: self.expand_synonyms_in_asts(should),
+                filter: self.expand_synonyms_in_asts(filter),
+                minimum_should_match,
+            }
+            .into(),
+            QueryAst::DisMax(DisMaxQuery {
+                queries,
+                tie_breaker,
+            }) => DisMaxQuery {
+                queries: self.expand_synonyms_in_asts(queries),
+                tie_breaker,
+            }
+            .into(),
+            QueryAst::Boosting(BoostingQuery {
+                positive,
+                negative,
+                negative_boost,
+            }) => BoostingQuery {
+                positive: Box::new(self.expand_synonyms(*positive)),
+                negative: Box::new(self.expand_synonyms(*negative)),
+                negative_boost,
+            }
+            .into(),
+            QueryAst::FullText(full_text_query) => {
+                let Some(synonyms) = self.synonyms(&full_text_query.field, &full_text_query.text)
+                else {
+                    return full_text_query.into();
+                };
+                let should = synonyms
+                    .iter()
+                    .map(|synonym| {
+                        FullTextQuery {
+                            text: synonym.clone(),
+                            ..full_text_query.clone()
+                        }
+                        .into()
+                    })
+                    .collect();
+                BoolQuery {
+                    should,
+                    ..Default::default()
+                }
+                .into()
+            }
+            QueryAst::Term(term_query) => {
+                let Some(synonyms) = self.synonyms(&term_query.field, &term_query.value) else {
+                    return term_query.into();
+                };
+                let should = synonyms
+                    .iter()
+                    .map(|synonym| TermQuery::from_field_value(&term_query.field, synonym).into())
+                    .collect();
+                BoolQuery {
+                    should,
+                    ..Default::default()
+                }
+                .into()
+            }
+            QueryAst::Boost { underlying, boost } => QueryAst::Boost {
+                underlying: Box::new(self.expand_synonyms(*underlying)),
+                boost,
+            },
+            QueryAst::ConstScore { underlying, score } => QueryAst::ConstScore {
+                underlying: Box::new(self.expand_synonyms(*underlying)),
+                score,
+            },
+            QueryAst::Udf(udf_query) => UdfQuery {
+                underlying: Box::new(self.expand_synonyms(*udf_query.underlying)),
+                ..udf_query
+            }
+            .into(),
+            QueryAst::FunctionScore(function_score_query) => function_score_query
+                .try_map_subqueries(|query_ast| {
+                    Ok::<_, Infallible>(self.expand_synonyms(query_ast))
+                })
+                .unwrap_or_else(|infallible| match infallible {})
+                .into(),
+            ast @ QueryAst::TermSet(_)
+            | ast @ QueryAst::TermsLookup(_)
+            | ast @ QueryAst::CombinedFields(_)
+            | ast @ QueryAst::PhrasePrefix(_)
+            | ast @ QueryAst::Intervals(_)
+            | ast @ QueryAst::Span(_)
+            | ast @ QueryAst::Knn(_)
+            | ast @ QueryAst::Regex(_)
+            | ast @ QueryAst::Range(_)
+            | ast @ QueryAst::FieldPresence(_)
+            | ast @ QueryAst::UserInput(_)
+            | ast @ QueryAst::SimpleUserInput(_)
+            | ast @ QueryAst::MatchAll
+            | ast @ QueryAst::MatchNone => ast,
+        }
+    }
+
+    fn expand_synonyms_in_asts(&self, asts: Vec<QueryAst>) -> Vec<QueryAst> {
+        asts.into_iter()
+            .map(|ast| self.expand_synonyms(ast))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_ast::{FullTextMode, FullTextParams, UserInputQuery};
+    use crate::BooleanOperand;
+
+    fn synonym_dictionary() -> SynonymDictionary {
+        let mut synonym_dictionary = SynonymDictionary::default();
+        synonym_dictionary.add_synonym_set(
+            &["body".to_string(), "service".to_string()],
+            &["k8s".to_string(), "kubernetes".to_string()],
+        );
+        synonym_dictionary
+    }
+
+    fn full_text_query(field: &str, text: &str) -> FullTextQuery {
+        FullTextQuery {
+            field: field.to_string(),
+            text: text.to_string(),
+            params: FullTextParams {
+                tokenizer: None,
+                mode: FullTextMode::Phrase { slop: 0 },
+                zero_terms_query: Default::default(),
+                lenient: false,
+            },
+            preferred_field: None,
+        }
+    }
+
+    #[test]
+    fn test_expand_synonyms_full_text() {
+        let synonym_dictionary = synonym_dictionary();
+        let query_ast: QueryAst = full_text_query("body", "K8s").into();
+        let expanded_query_ast = synonym_dictionary.expand_synonyms(query_ast);
+        let expected_query_ast: QueryAst = BoolQuery {
+            should: vec![
+                full_text_query("body", "k8s").into(),
+                full_text_query("body", "kubernetes").into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(expanded_query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_expand_synonyms_term() {
+        let synonym_dictionary = synonym_dictionary();
+        let query_ast: QueryAst = TermQuery::from_field_value("service", "kubernetes").into();
+        let expanded_query_ast = synonym_dictionary.expand_synonyms(query_ast);
+        let expected_query_ast: QueryAst = BoolQuery {
+            should: vec![
+                TermQuery::from_field_value("service", "k8s").into(),
+                TermQuery::from_field_value("service", "kubernetes").into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(expanded_query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_expand_synonyms_only_on_configured_fields() {
+        let synonym_dictionary = synonym_dictionary();
+        let query_ast: QueryAst = full_text_query("title", "k8s").into();
+        assert_eq!(
+            synonym_dictionary.expand_synonyms(query_ast.clone()),
+            query_ast
+        );
+        let query_ast: QueryAst = full_text_query("body", "k8s pods").into();
+        assert_eq!(
+            synonym_dictionary.expand_synonyms(query_ast.clone()),
+            query_ast
+        );
+    }
+
+    #[test]
+    fn test_expand_synonyms_in_parsed_user_query() {
+        let synonym_dictionary = synonym_dictionary();
+        let query_ast: QueryAst = UserInputQuery {
+            user_text: "k8s AND title:k8s".to_string(),
+            default_fields: Some(vec!["body".to_string()]),
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
+        }
+        .into();
+        let parsed_query_ast = query_ast.parse_user_query(&[]).unwrap();
+        let QueryAst::Bool(bool_query) = synonym_dictionary.expand_synonyms(parsed_query_ast)
+        else {
+            panic!("Expected a boolean query.");
+        };
+        assert_eq!(bool_query.must.len(), 2);
+        let QueryAst::Bool(expanded_query) = &bool_query.must[0] else {
+            panic!("Expected the synonyms to be expanded.");
+        };
+        assert_eq!(expanded_query.should.len(), 2);
+        assert!(matches!(&bool_query.must[1], QueryAst::FullText(_)));
+    }
+}
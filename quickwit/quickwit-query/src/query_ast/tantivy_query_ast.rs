@@ -19,10 +19,12 @@
 
 use tantivy::query::{
     AllQuery as TantivyAllQuery, ConstScoreQuery as TantivyConstScoreQuery,
-    EmptyQuery as TantivyEmptyQuery,
+    EmptyQuery as TantivyEmptyQuery, EnableScoring, Weight,
 };
 use tantivy::query_grammar::Occur;
+use tantivy::Term;
 
+use crate::query_ast::minimum_should_match_query::MinimumShouldMatchQuery;
 use crate::{BooleanOperand, MatchAllOrNone, TantivyQuery};
 
 /// This AST point, is only to make it easier to simplify the generated Tantivy query.
@@ -98,6 +100,46 @@ impl TantivyQueryAst {
             ast => ast,
         }
     }
+
+    /// Wraps the AST so that it is always executed with scoring disabled, sparing the BM25
+    /// computations and the decoding of term frequencies. The scores it produces are
+    /// meaningless.
+    pub(crate) fn without_scoring(self) -> TantivyQueryAst {
+        match self.simplify() {
+            const_predicate @ TantivyQueryAst::ConstPredicate(_) => const_predicate,
+            ast => ScoringDisabledQuery {
+                underlying: ast.into(),
+            }
+            .into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ScoringDisabledQuery {
+    underlying: Box<dyn TantivyQuery>,
+}
+
+impl Clone for ScoringDisabledQuery {
+    fn clone(&self) -> Self {
+        ScoringDisabledQuery {
+            underlying: self.underlying.box_clone(),
+        }
+    }
+}
+
+impl TantivyQuery for ScoringDisabledQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let scoring_disabled = match enable_scoring.searcher() {
+            Some(searcher) => EnableScoring::disabled_from_searcher(searcher),
+            None => EnableScoring::disabled_from_schema(enable_scoring.schema()),
+        };
+        self.underlying.weight(scoring_disabled)
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.underlying.query_terms(visitor)
+    }
 }
 
 impl<Q: TantivyQuery> From<Q> for TantivyQueryAst {
@@ -148,6 +190,8 @@ pub(crate) struct TantivyBoolQuery {
     pub must_not: Vec<TantivyQueryAst>,
     pub should: Vec<TantivyQueryAst>,
     pub filter: Vec<TantivyQueryAst>,
+    // Minimum number of should clauses that must match, if set.
+    pub minimum_should_match: Option<usize>,
 }
 
 fn simplify_asts(asts: Vec<TantivyQueryAst>) -> Vec<TantivyQueryAst> {
@@ -202,6 +246,27 @@ impl TantivyBoolQuery {
             has_no_positive_ast_so_far,
         );
         has_no_positive_ast_so_far &= self.should.is_empty();
+        if let Some(minimum_should_match) = self.minimum_should_match {
+            let num_should = self
+                .should
+                .iter()
+                .filter(|should| should.const_predicate() != Some(MatchAllOrNone::MatchNone))
+                .count();
+            if minimum_should_match > num_should {
+                return TantivyQueryAst::ConstPredicate(MatchAllOrNone::MatchNone);
+            }
+            if minimum_should_match == 0
+                || (minimum_should_match == 1 && self.must.is_empty() && self.filter.is_empty())
+            {
+                // Same semantics as the default ones.
+                self.minimum_should_match = None;
+            } else if minimum_should_match == num_should {
+                self.should
+                    .retain(|should| should.const_predicate() != Some(MatchAllOrNone::MatchNone));
+                self.must.append(&mut self.should);
+                self.minimum_should_match = None;
+            }
+        }
         remove_with_guard(
             &mut self.must_not,
             MatchAllOrNone::MatchNone,
@@ -249,10 +314,24 @@ impl From<TantivyBoolQuery> for Box<dyn TantivyQuery> {
                 + bool_query.should.len()
                 + bool_query.filter.len(),
         );
+        let mut should = bool_query.should;
+        if let Some(minimum_should_match) = bool_query.minimum_should_match {
+            let should_queries: Vec<Box<dyn TantivyQuery>> = should
+                .drain(..)
+                .map(|should_ast| should_ast.into())
+                .collect();
+            clause.push((
+                Occur::Must,
+                Box::new(MinimumShouldMatchQuery::new(
+                    should_queries,
+                    minimum_should_match,
+                )),
+            ));
+        }
         for (occur, child_asts) in [
             (Occur::Must, bool_query.must),
             (Occur::MustNot, bool_query.must_not),
-            (Occur::Should, bool_query.should),
+            (Occur::Should, should),
         ] {
             for child_ast in child_asts {
                 let sub_query = child_ast.into();
@@ -434,4 +513,39 @@ mod tests {
             Some(MatchAllOrNone::MatchAll)
         );
     }
+
+    #[test]
+    fn test_simplify_bool_query_with_minimum_should_match() {
+        let build_bool_query = |minimum_should_match: usize| TantivyBoolQuery {
+            must: vec![EmptyQuery.into()],
+            should: vec![
+                EmptyQuery.into(),
+                EmptyQuery.into(),
+                TantivyQueryAst::match_none(),
+            ],
+            minimum_should_match: Some(minimum_should_match),
+            ..Default::default()
+        };
+        let simplified_ast = build_bool_query(1).simplify();
+        let simplified_bool_query = simplified_ast.as_bool_query().unwrap();
+        assert_eq!(simplified_bool_query.minimum_should_match, Some(1));
+        assert_eq!(simplified_bool_query.should.len(), 2);
+
+        // All the should clauses that may match are required.
+        let simplified_ast = build_bool_query(2).simplify();
+        let simplified_bool_query = simplified_ast.as_bool_query().unwrap();
+        assert_eq!(simplified_bool_query.minimum_should_match, None);
+        assert_eq!(simplified_bool_query.must.len(), 3);
+        assert!(simplified_bool_query.should.is_empty());
+
+        let simplified_ast = build_bool_query(3).simplify();
+        assert_eq!(
+            simplified_ast.const_predicate(),
+            Some(MatchAllOrNone::MatchNone)
+        );
+
+        let simplified_ast = build_bool_query(0).simplify();
+        let simplified_bool_query = simplified_ast.as_bool_query().unwrap();
+        assert_eq!(simplified_bool_query.minimum_should_match, None);
+    }
 }
@@ -41,7 +41,6 @@ impl From<TermQuery> for QueryAst {
 }
 
 impl TermQuery {
-    #[cfg(test)]
     pub fn from_field_value(field: impl ToString, value: impl ToString) -> Self {
         Self {
             field: field.to_string(),
@@ -56,12 +55,14 @@ impl BuildTantivyAst for TermQuery {
         schema: &TantivySchema,
         _search_fields: &[String],
         _with_validation: bool,
+        _with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let full_text_params = FullTextParams {
             tokenizer: Some("raw".to_string()),
             // The parameter below won't matter, since we will have only one term
             mode: BooleanOperand::Or.into(),
             zero_terms_query: Default::default(),
+            lenient: false,
         };
         crate::query_ast::utils::full_text_query(
             &self.field,
@@ -136,7 +137,7 @@ mod tests {
         schema_builder.add_ip_addr_field("ip", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
@@ -155,7 +156,7 @@ mod tests {
         schema_builder.add_ip_addr_field("ip", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
@@ -174,7 +175,7 @@ mod tests {
         schema_builder.add_bytes_field("bytes", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
@@ -193,7 +194,7 @@ mod tests {
         schema_builder.add_bytes_field("bytes", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(&schema, &[], true)
+            .build_tantivy_ast_call(&schema, &[], true, true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
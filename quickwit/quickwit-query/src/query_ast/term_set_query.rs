@@ -20,11 +20,11 @@
 use std::collections::{BTreeSet, HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
-use tantivy::schema::Schema as TantivySchema;
+use tantivy::schema::{FieldType, Schema as TantivySchema};
 use tantivy::Term;
 
 use crate::query_ast::{BuildTantivyAst, QueryAst, TantivyQueryAst, TermQuery};
-use crate::InvalidQuery;
+use crate::{find_field_or_hit_dynamic, get_quickwit_tokenizer_manager, InvalidQuery};
 
 /// TermSetQuery matches the same document set as if it was a union of
 /// the equivalent set of TermQueries.
@@ -39,6 +39,24 @@ impl TermSetQuery {
     fn make_term_iterator(&self, schema: &TantivySchema) -> Result<HashSet<Term>, InvalidQuery> {
         let mut terms: HashSet<Term> = HashSet::default();
         for (full_path, values) in &self.terms_per_field {
+            let (field, field_entry, json_path) = find_field_or_hit_dynamic(full_path, schema)?;
+            if let FieldType::Str(text_options) = field_entry.field_type() {
+                if json_path.is_empty() && text_options.get_indexing_options().is_some() {
+                    // Fast path for text fields, e.g. doc ID fields targeted by large sets of
+                    // IDs: term queries tokenize the value with the raw tokenizer, so the terms
+                    // can be built directly, fetching the tokenizer once for all the values.
+                    let raw_tokenizer = get_quickwit_tokenizer_manager()
+                        .get("raw")
+                        .expect("The raw tokenizer should be registered.");
+                    for value in values {
+                        let mut token_stream = raw_tokenizer.token_stream(value);
+                        token_stream.process(&mut |token| {
+                            terms.insert(Term::from_field_text(field, &token.text));
+                        });
+                    }
+                    continue;
+                }
+            }
             for value in values {
                 // Mapping a text (field, value) is non-trival:
                 // It depends on the schema of course, and can actually result in a disjunction of
@@ -51,7 +69,7 @@ impl TermSetQuery {
                     field: full_path.to_string(),
                     value: value.to_string(),
                 };
-                let ast = term_query.build_tantivy_ast_call(schema, &[], false)?;
+                let ast = term_query.build_tantivy_ast_call(schema, &[], false, false)?;
                 let tantivy_query: Box<dyn crate::TantivyQuery> = ast.simplify().into();
                 tantivy_query.query_terms(&mut |term, _| {
                     terms.insert(term.clone());
@@ -68,6 +86,7 @@ impl BuildTantivyAst for TermSetQuery {
         schema: &TantivySchema,
         _search_fields: &[String],
         _with_validation: bool,
+        _with_scoring: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let terms_it = self.make_term_iterator(schema)?;
         let term_set_query = tantivy::query::TermSetQuery::new(terms_it);
@@ -80,3 +99,71 @@ impl From<TermSetQuery> for QueryAst {
         QueryAst::TermSet(term_set_query)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, FAST, STRING, TEXT};
+
+    use super::*;
+
+    #[test]
+    fn test_term_set_query_on_text_field() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("id", STRING | FAST);
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let term_set_query = TermSetQuery {
+            terms_per_field: HashMap::from([
+                (
+                    "id".to_string(),
+                    BTreeSet::from(["doc-1".to_string(), "Doc-2".to_string()]),
+                ),
+                (
+                    "body".to_string(),
+                    BTreeSet::from(["Hello World".to_string()]),
+                ),
+            ]),
+        };
+        let id_field = schema.get_field("id").unwrap();
+        let body_field = schema.get_field("body").unwrap();
+        let expected_terms = HashSet::from([
+            Term::from_field_text(id_field, "doc-1"),
+            Term::from_field_text(id_field, "Doc-2"),
+            Term::from_field_text(body_field, "Hello World"),
+        ]);
+        assert_eq!(
+            term_set_query.make_term_iterator(&schema).unwrap(),
+            expected_terms
+        );
+    }
+
+    #[test]
+    fn test_term_set_query_on_numeric_field() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_u64_field("count", FAST);
+        let schema = schema_builder.build();
+        let term_set_query = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "count".to_string(),
+                BTreeSet::from(["1".to_string(), "2".to_string()]),
+            )]),
+        };
+        let count_field = schema.get_field("count").unwrap();
+        let expected_terms = HashSet::from([
+            Term::from_field_u64(count_field, 1),
+            Term::from_field_u64(count_field, 2),
+        ]);
+        assert_eq!(
+            term_set_query.make_term_iterator(&schema).unwrap(),
+            expected_terms
+        );
+
+        let invalid_term_set_query = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "count".to_string(),
+                BTreeSet::from(["one".to_string()]),
+            )]),
+        };
+        assert!(invalid_term_set_query.make_term_iterator(&schema).is_err());
+    }
+}
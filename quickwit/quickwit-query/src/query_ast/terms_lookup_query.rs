@@ -0,0 +1,205 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tantivy::schema::Schema as TantivySchema;
+
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{BuildTantivyAst, QueryAst, TermSetQuery};
+use crate::InvalidQuery;
+
+/// Maximum number of terms a terms lookup can fetch.
+pub const MAX_TERMS_LOOKUP_NUM_TERMS: usize = 65_536;
+
+/// Matches the documents in which `field` contains one of the terms listed under `path` in the
+/// document `doc_id` of the index `index_id`.
+///
+/// The lookup document is fetched by the root before the query is dispatched to the leaves: the
+/// query is then replaced by the equivalent [`TermSetQuery`]. Leaves refuse to execute an
+/// unresolved terms lookup.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TermsLookupQuery {
+    pub field: String,
+    pub index_id: String,
+    pub doc_id: String,
+    pub path: String,
+}
+
+impl From<TermsLookupQuery> for QueryAst {
+    fn from(terms_lookup_query: TermsLookupQuery) -> Self {
+        QueryAst::TermsLookup(terms_lookup_query)
+    }
+}
+
+impl TermsLookupQuery {
+    /// Extracts the terms listed under `path` in the lookup document. The path is a dot-separated
+    /// list of keys. Strings, numbers, and booleans are accepted, either as a single value or as
+    /// an array of values. A missing path yields no terms.
+    pub fn extract_terms(&self, lookup_doc: &JsonValue) -> anyhow::Result<BTreeSet<String>> {
+        let mut value = lookup_doc;
+        for key in self.path.split('.') {
+            match value.get(key) {
+                Some(child_value) => value = child_value,
+                None => return Ok(BTreeSet::new()),
+            }
+        }
+        let values: &[JsonValue] = match value {
+            JsonValue::Array(values) => values,
+            value => std::slice::from_ref(value),
+        };
+        let mut terms = BTreeSet::new();
+        for value in values {
+            let term = match value {
+                JsonValue::String(value) => value.clone(),
+                JsonValue::Number(value) => value.to_string(),
+                JsonValue::Bool(value) => value.to_string(),
+                JsonValue::Null => continue,
+                _ => anyhow::bail!(
+                    "Path `{}` of lookup document `{}` in index `{}` must contain strings, \
+                     numbers, or booleans.",
+                    self.path,
+                    self.doc_id,
+                    self.index_id
+                ),
+            };
+            terms.insert(term);
+        }
+        if terms.len() > MAX_TERMS_LOOKUP_NUM_TERMS {
+            anyhow::bail!(
+                "Lookup document `{}` in index `{}` lists {} terms under path `{}`, which exceeds \
+                 the maximum of {MAX_TERMS_LOOKUP_NUM_TERMS} terms.",
+                self.doc_id,
+                self.index_id,
+                terms.len(),
+                self.path,
+            );
+        }
+        Ok(terms)
+    }
+
+    /// Builds the query equivalent to the terms lookup once its terms have been fetched.
+    pub fn resolve(self, terms: BTreeSet<String>) -> QueryAst {
+        if terms.is_empty() {
+            return QueryAst::MatchNone;
+        }
+        TermSetQuery {
+            terms_per_field: HashMap::from([(self.field, terms)]),
+        }
+        .into()
+    }
+}
+
+impl BuildTantivyAst for TermsLookupQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        _schema: &TantivySchema,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        Err(InvalidQuery::TermsLookupNotResolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn terms_lookup_query_for_test(path: &str) -> TermsLookupQuery {
+        TermsLookupQuery {
+            field: "user_id".to_string(),
+            index_id: "allow-lists".to_string(),
+            doc_id: "list-1".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_terms_lookup_extract_terms() {
+        let lookup_doc = json!({
+            "user_ids": ["alice", "bob", "alice", null],
+            "owner": {"id": 42},
+            "enabled": true,
+            "nested": [{"id": 1}],
+        });
+        assert_eq!(
+            terms_lookup_query_for_test("user_ids")
+                .extract_terms(&lookup_doc)
+                .unwrap(),
+            BTreeSet::from(["alice".to_string(), "bob".to_string()])
+        );
+        assert_eq!(
+            terms_lookup_query_for_test("owner.id")
+                .extract_terms(&lookup_doc)
+                .unwrap(),
+            BTreeSet::from(["42".to_string()])
+        );
+        assert_eq!(
+            terms_lookup_query_for_test("enabled")
+                .extract_terms(&lookup_doc)
+                .unwrap(),
+            BTreeSet::from(["true".to_string()])
+        );
+        assert!(terms_lookup_query_for_test("owner.name")
+            .extract_terms(&lookup_doc)
+            .unwrap()
+            .is_empty());
+        assert!(terms_lookup_query_for_test("nested")
+            .extract_terms(&lookup_doc)
+            .is_err());
+    }
+
+    #[test]
+    fn test_terms_lookup_resolve() {
+        let terms_lookup_query = terms_lookup_query_for_test("user_ids");
+        assert_eq!(
+            terms_lookup_query.clone().resolve(BTreeSet::new()),
+            QueryAst::MatchNone
+        );
+        let expected_query_ast: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([(
+                "user_id".to_string(),
+                BTreeSet::from(["alice".to_string()]),
+            )]),
+        }
+        .into();
+        assert_eq!(
+            terms_lookup_query.resolve(BTreeSet::from(["alice".to_string()])),
+            expected_query_ast
+        );
+    }
+
+    #[test]
+    fn test_terms_lookup_not_resolved() {
+        let query_ast: QueryAst = terms_lookup_query_for_test("user_ids").into();
+        let schema = TantivySchema::builder().build();
+        let build_tantivy_ast_err = query_ast
+            .build_tantivy_ast_call(&schema, &[], false, true)
+            .unwrap_err();
+        assert!(matches!(
+            build_tantivy_ast_err,
+            InvalidQuery::TermsLookupNotResolved
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Fixtures shared by the tests of the query AST nodes.
+
+use tantivy::collector::TopDocs;
+use tantivy::schema::Schema;
+use tantivy::{DocId, Document, Index, Score};
+
+use crate::query_ast::QueryAst;
+use crate::InvalidQuery;
+
+/// Builds an in-RAM index holding `docs` in a single segment.
+pub(crate) fn build_test_index(schema: Schema, docs: impl IntoIterator<Item = Document>) -> Index {
+    let index = Index::create_in_ram(schema);
+    let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+    for doc in docs {
+        index_writer.add_document(doc).unwrap();
+    }
+    index_writer.commit().unwrap();
+    index
+}
+
+/// Returns the top 10 documents matching the query with their scores, best ones first.
+pub(crate) fn search_scored_docs(
+    index: &Index,
+    query_ast: &QueryAst,
+    with_scoring: bool,
+) -> Result<Vec<(Score, DocId)>, InvalidQuery> {
+    let query = query_ast.build_tantivy_query(&index.schema(), &[], true, with_scoring)?;
+    let searcher = index.reader().unwrap().searcher();
+    let scored_docs = searcher
+        .search(&query, &TopDocs::with_limit(10))
+        .unwrap()
+        .into_iter()
+        .map(|(score, doc_address)| (score, doc_address.doc_id))
+        .collect();
+    Ok(scored_docs)
+}
+
+/// Returns the IDs of the documents matching the query, sorted.
+pub(crate) fn search_docs(index: &Index, query_ast: &QueryAst) -> Vec<DocId> {
+    try_search_docs(index, query_ast).unwrap()
+}
+
+/// Same as [`search_docs`], but returns the error of queries that cannot be built.
+pub(crate) fn try_search_docs(
+    index: &Index,
+    query_ast: &QueryAst,
+) -> Result<Vec<DocId>, InvalidQuery> {
+    let mut docs: Vec<DocId> = search_scored_docs(index, query_ast, true)?
+        .into_iter()
+        .map(|(_, doc_id)| doc_id)
+        .collect();
+    docs.sort();
+    Ok(docs)
+}
@@ -0,0 +1,349 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::user_input_query::UserInputQuery;
+use crate::query_ast::{
+    BoolQuery, BoostingQuery, CombinedFieldsQuery, DisMaxQuery, FieldPresenceQuery, FullTextQuery,
+    FunctionScoreQuery, IntervalsQuery, KnnQuery, PhrasePrefixQuery, QueryAst, RangeQuery,
+    RegexQuery, SimpleUserInputQuery, SpanQuery, TermQuery, TermSetQuery, TermsLookupQuery,
+    UdfQuery,
+};
+
+/// Trait to implement a rewriting pass over the QueryAst.
+///
+/// Each method takes ownership of a node and returns the subtree replacing it. By default, the
+/// compound nodes are rebuilt from their transformed children and the leaves are returned
+/// untouched, so implementors only override the methods of the nodes they rewrite.
+pub trait QueryAstTransformer {
+    type Err;
+
+    fn transform(&mut self, query_ast: QueryAst) -> Result<QueryAst, Self::Err> {
+        match query_ast {
+            QueryAst::Bool(bool_query) => self.transform_bool(bool_query),
+            QueryAst::DisMax(dis_max_query) => self.transform_dis_max(dis_max_query),
+            QueryAst::Boosting(boosting_query) => self.transform_boosting(boosting_query),
+            QueryAst::Term(term_query) => self.transform_term(term_query),
+            QueryAst::TermSet(term_set_query) => self.transform_term_set(term_set_query),
+            QueryAst::TermsLookup(terms_lookup_query) => {
+                self.transform_terms_lookup(terms_lookup_query)
+            }
+            QueryAst::FullText(full_text_query) => self.transform_full_text(full_text_query),
+            QueryAst::CombinedFields(combined_fields_query) => {
+                self.transform_combined_fields(combined_fields_query)
+            }
+            QueryAst::PhrasePrefix(phrase_prefix_query) => {
+                self.transform_phrase_prefix(phrase_prefix_query)
+            }
+            QueryAst::Intervals(intervals_query) => self.transform_intervals(intervals_query),
+            QueryAst::Span(span_query) => self.transform_span(span_query),
+            QueryAst::Knn(knn_query) => self.transform_knn(knn_query),
+            QueryAst::Regex(regex_query) => self.transform_regex(regex_query),
+            QueryAst::Range(range_query) => self.transform_range(range_query),
+            QueryAst::FieldPresence(field_presence_query) => {
+                self.transform_field_presence(field_presence_query)
+            }
+            QueryAst::MatchAll => self.transform_match_all(),
+            QueryAst::MatchNone => self.transform_match_none(),
+            QueryAst::Boost { underlying, boost } => self.transform_boost(*underlying, boost),
+            QueryAst::ConstScore { underlying, score } => {
+                self.transform_const_score(*underlying, score)
+            }
+            QueryAst::UserInput(user_text_query) => self.transform_user_text(user_text_query),
+            QueryAst::SimpleUserInput(simple_user_input_query) => {
+                self.transform_simple_user_text(simple_user_input_query)
+            }
+            QueryAst::Udf(udf_query) => self.transform_udf(udf_query),
+            QueryAst::FunctionScore(function_score_query) => {
+                self.transform_function_score(function_score_query)
+            }
+        }
+    }
+
+    /// Transforms each query AST of the list. Called on the clauses of the compound queries.
+    fn transform_all(&mut self, query_asts: Vec<QueryAst>) -> Result<Vec<QueryAst>, Self::Err> {
+        query_asts
+            .into_iter()
+            .map(|query_ast| self.transform(query_ast))
+            .collect()
+    }
+
+    fn transform_bool(&mut self, bool_query: BoolQuery) -> Result<QueryAst, Self::Err> {
+        let BoolQuery {
+            must,
+            must_not,
+            should,
+            filter,
+            minimum_should_match,
+        } = bool_query;
+        let bool_query = BoolQuery {
+            must: self.transform_all(must)?,
+            must_not: self.transform_all(must_not)?,
+            should: self.transform_all(should)?,
+            filter: self.transform_all(filter)?,
+            minimum_should_match,
+        };
+        Ok(bool_query.into())
+    }
+
+    fn transform_dis_max(&mut self, dis_max_query: DisMaxQuery) -> Result<QueryAst, Self::Err> {
+        let dis_max_query = DisMaxQuery {
+            queries: self.transform_all(dis_max_query.queries)?,
+            tie_breaker: dis_max_query.tie_breaker,
+        };
+        Ok(dis_max_query.into())
+    }
+
+    fn transform_boosting(&mut self, boosting_query: BoostingQuery) -> Result<QueryAst, Self::Err> {
+        let boosting_query = BoostingQuery {
+            positive: Box::new(self.transform(*boosting_query.positive)?),
+            negative: Box::new(self.transform(*boosting_query.negative)?),
+            negative_boost: boosting_query.negative_boost,
+        };
+        Ok(boosting_query.into())
+    }
+
+    fn transform_term(&mut self, term_query: TermQuery) -> Result<QueryAst, Self::Err> {
+        Ok(term_query.into())
+    }
+
+    fn transform_term_set(&mut self, term_set_query: TermSetQuery) -> Result<QueryAst, Self::Err> {
+        Ok(term_set_query.into())
+    }
+
+    fn transform_terms_lookup(
+        &mut self,
+        terms_lookup_query: TermsLookupQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(terms_lookup_query.into())
+    }
+
+    fn transform_full_text(
+        &mut self,
+        full_text_query: FullTextQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(full_text_query.into())
+    }
+
+    fn transform_combined_fields(
+        &mut self,
+        combined_fields_query: CombinedFieldsQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(combined_fields_query.into())
+    }
+
+    fn transform_phrase_prefix(
+        &mut self,
+        phrase_prefix_query: PhrasePrefixQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(phrase_prefix_query.into())
+    }
+
+    fn transform_intervals(
+        &mut self,
+        intervals_query: IntervalsQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(intervals_query.into())
+    }
+
+    fn transform_span(&mut self, span_query: SpanQuery) -> Result<QueryAst, Self::Err> {
+        Ok(span_query.into())
+    }
+
+    fn transform_knn(&mut self, knn_query: KnnQuery) -> Result<QueryAst, Self::Err> {
+        Ok(knn_query.into())
+    }
+
+    fn transform_regex(&mut self, regex_query: RegexQuery) -> Result<QueryAst, Self::Err> {
+        Ok(regex_query.into())
+    }
+
+    fn transform_match_all(&mut self) -> Result<QueryAst, Self::Err> {
+        Ok(QueryAst::MatchAll)
+    }
+
+    fn transform_match_none(&mut self) -> Result<QueryAst, Self::Err> {
+        Ok(QueryAst::MatchNone)
+    }
+
+    fn transform_boost(
+        &mut self,
+        underlying: QueryAst,
+        boost: NotNaNf32,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(QueryAst::Boost {
+            underlying: Box::new(self.transform(underlying)?),
+            boost,
+        })
+    }
+
+    fn transform_const_score(
+        &mut self,
+        underlying: QueryAst,
+        score: NotNaNf32,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(QueryAst::ConstScore {
+            underlying: Box::new(self.transform(underlying)?),
+            score,
+        })
+    }
+
+    fn transform_range(&mut self, range_query: RangeQuery) -> Result<QueryAst, Self::Err> {
+        Ok(range_query.into())
+    }
+
+    fn transform_field_presence(
+        &mut self,
+        field_presence_query: FieldPresenceQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(field_presence_query.into())
+    }
+
+    fn transform_user_text(
+        &mut self,
+        user_text_query: UserInputQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(user_text_query.into())
+    }
+
+    fn transform_simple_user_text(
+        &mut self,
+        simple_user_input_query: SimpleUserInputQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        Ok(simple_user_input_query.into())
+    }
+
+    fn transform_udf(&mut self, udf_query: UdfQuery) -> Result<QueryAst, Self::Err> {
+        let udf_query = UdfQuery {
+            underlying: Box::new(self.transform(*udf_query.underlying)?),
+            ..udf_query
+        };
+        Ok(udf_query.into())
+    }
+
+    fn transform_function_score(
+        &mut self,
+        function_score_query: FunctionScoreQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        let function_score_query =
+            function_score_query.try_map_subqueries(|query_ast| self.transform(query_ast))?;
+        Ok(function_score_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct RenameFields {
+        renames: HashMap<&'static str, &'static str>,
+    }
+
+    impl QueryAstTransformer for RenameFields {
+        type Err = std::convert::Infallible;
+
+        fn transform_term(&mut self, mut term_query: TermQuery) -> Result<QueryAst, Self::Err> {
+            if let Some(new_field) = self.renames.get(term_query.field.as_str()) {
+                term_query.field = new_field.to_string();
+            }
+            Ok(term_query.into())
+        }
+    }
+
+    struct RejectRegexes;
+
+    impl QueryAstTransformer for RejectRegexes {
+        type Err = String;
+
+        fn transform_regex(&mut self, regex_query: RegexQuery) -> Result<QueryAst, Self::Err> {
+            Err(format!(
+                "regex on field `{}` is forbidden",
+                regex_query.field
+            ))
+        }
+    }
+
+    fn term_query_for_test(field: &str, value: &str) -> QueryAst {
+        TermQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_query_ast_transformer_rewrites_nested_nodes() {
+        let boost = NotNaNf32::try_from(2.0).unwrap();
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            should: vec![QueryAst::Boost {
+                underlying: Box::new(term_query_for_test("level", "error")),
+                boost,
+            }],
+            filter: vec![QueryAst::MatchAll],
+            ..Default::default()
+        }
+        .into();
+        let mut rename_fields = RenameFields {
+            renames: HashMap::from_iter([("service", "service_name"), ("level", "severity")]),
+        };
+        let transformed_query_ast = rename_fields.transform(query_ast).unwrap();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service_name", "api")],
+            should: vec![QueryAst::Boost {
+                underlying: Box::new(term_query_for_test("severity", "error")),
+                boost,
+            }],
+            filter: vec![QueryAst::MatchAll],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(transformed_query_ast, expected_query_ast);
+    }
+
+    #[test]
+    fn test_query_ast_transformer_propagates_errors() {
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![term_query_for_test("service", "api")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            RejectRegexes.transform(query_ast.clone()).unwrap(),
+            query_ast
+        );
+
+        let query_ast: QueryAst = BoolQuery {
+            must_not: vec![RegexQuery {
+                field: "body".to_string(),
+                regex: "err.*".to_string(),
+                case_insensitive: false,
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            RejectRegexes.transform(query_ast).unwrap_err(),
+            "regex on field `body` is forbidden"
+        );
+    }
+}
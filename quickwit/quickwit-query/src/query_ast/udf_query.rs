@@ -0,0 +1,407 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::{EnableScoring, Explanation, Scorer, Weight};
+use tantivy::schema::{FieldType, Schema as TantivySchema};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+
+use super::utils::NumericalFastFieldColumn;
+use super::{BuildTantivyAst, QueryAst, TantivyQueryAst};
+use crate::udf::{get_udf, Udf, UdfInstance};
+use crate::{InvalidQuery, TantivyQuery};
+
+/// Defines what the value returned by a UDF is used for.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UdfMode {
+    /// Only the documents for which the UDF returns a strictly positive value match. Scores are
+    /// left untouched.
+    #[default]
+    Filter,
+    /// The score of the documents is replaced by the value returned by the UDF. Documents for
+    /// which the UDF returns `NaN` do not match.
+    Score,
+}
+
+/// Filters or scores the documents matching an underlying query using a user-defined function
+/// (see [`crate::udf`]).
+///
+/// The function is called with the values of `fields` for each document matching the
+/// underlying query. These fields must be numerical, boolean or datetime fast fields.
+/// Datetimes are passed as a number of seconds since the Unix epoch, booleans as `0` or `1`
+/// and missing values as `NaN`.
+///
+/// Documents for which the function fails (because it ran out of fuel, for instance) do not
+/// match.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UdfQuery {
+    pub underlying: Box<QueryAst>,
+    pub udf: String,
+    pub fields: Vec<String>,
+    #[serde(default)]
+    pub mode: UdfMode,
+}
+
+impl From<UdfQuery> for QueryAst {
+    fn from(udf_query: UdfQuery) -> Self {
+        QueryAst::Udf(udf_query)
+    }
+}
+
+impl BuildTantivyAst for UdfQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        search_fields: &[String],
+        with_validation: bool,
+        with_scoring: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let udf = get_udf(&self.udf).ok_or_else(|| InvalidQuery::UdfNotFound {
+            udf_name: self.udf.clone(),
+        })?;
+        for field_name in &self.fields {
+            let (_field, field_entry, path) =
+                super::utils::find_field_or_hit_dynamic(field_name, schema)?;
+            let is_supported_type = matches!(
+                field_entry.field_type(),
+                FieldType::U64(_)
+                    | FieldType::I64(_)
+                    | FieldType::F64(_)
+                    | FieldType::Bool(_)
+                    | FieldType::Date(_)
+            );
+            if !path.is_empty() || !field_entry.is_fast() || !is_supported_type {
+                return Err(InvalidQuery::SchemaError(format!(
+                    "UDF arguments must be numerical, boolean or datetime fast fields. (`{}` is \
+                     not)",
+                    field_name
+                )));
+            }
+        }
+        let underlying = match self.mode {
+            UdfMode::Filter => self.underlying.build_tantivy_ast_call(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            )?,
+            // The score of the underlying query is replaced by the output of the UDF.
+            UdfMode::Score => self.underlying.build_tantivy_ast_call_without_scoring(
+                schema,
+                search_fields,
+                with_validation,
+                with_scoring,
+            )?,
+        };
+        let udf_query = UdfTantivyQuery {
+            underlying: underlying.into(),
+            udf_name: self.udf.clone(),
+            udf,
+            fields: self.fields.clone(),
+            mode: self.mode,
+        };
+        Ok(udf_query.into())
+    }
+}
+
+struct UdfTantivyQuery {
+    underlying: Box<dyn TantivyQuery>,
+    udf_name: String,
+    udf: Arc<dyn Udf>,
+    fields: Vec<String>,
+    mode: UdfMode,
+}
+
+impl Clone for UdfTantivyQuery {
+    fn clone(&self) -> Self {
+        UdfTantivyQuery {
+            underlying: self.underlying.box_clone(),
+            udf_name: self.udf_name.clone(),
+            udf: self.udf.clone(),
+            fields: self.fields.clone(),
+            mode: self.mode,
+        }
+    }
+}
+
+impl fmt::Debug for UdfTantivyQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UdfTantivyQuery")
+            .field("underlying", &self.underlying)
+            .field("udf_name", &self.udf_name)
+            .field("fields", &self.fields)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl TantivyQuery for UdfTantivyQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let underlying_weight = self.underlying.weight(enable_scoring)?;
+        Ok(Box::new(UdfWeight {
+            underlying_weight,
+            udf_name: self.udf_name.clone(),
+            udf: self.udf.clone(),
+            fields: self.fields.clone(),
+            mode: self.mode,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.underlying.query_terms(visitor)
+    }
+}
+
+struct UdfWeight {
+    underlying_weight: Box<dyn Weight>,
+    udf_name: String,
+    udf: Arc<dyn Udf>,
+    fields: Vec<String>,
+    mode: UdfMode,
+}
+
+impl Weight for UdfWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let underlying_scorer = self.underlying_weight.scorer(reader, boost)?;
+        let arg_columns = self
+            .fields
+            .iter()
+            .map(|field_name| NumericalFastFieldColumn::open(reader, field_name))
+            .collect::<tantivy::Result<Vec<_>>>()?;
+        let udf_instance = self.udf.instantiate().map_err(|error| {
+            TantivyError::InternalError(format!(
+                "Failed to instantiate UDF `{}`: {error:?}",
+                self.udf_name
+            ))
+        })?;
+        let mut udf_scorer = UdfScorer {
+            underlying_scorer,
+            args: Vec::with_capacity(arg_columns.len()),
+            arg_columns,
+            udf_instance,
+            mode: self.mode,
+            boost,
+            udf_output: 0.0,
+        };
+        udf_scorer.advance_until_match();
+        Ok(Box::new(udf_scorer))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        let mut explanation = Explanation::new("UdfQuery", scorer.score());
+        explanation.add_detail(self.underlying_weight.explain(reader, doc)?);
+        Ok(explanation)
+    }
+}
+
+struct UdfScorer {
+    underlying_scorer: Box<dyn Scorer>,
+    arg_columns: Vec<NumericalFastFieldColumn>,
+    args: Vec<f64>,
+    udf_instance: Box<dyn UdfInstance>,
+    mode: UdfMode,
+    // In score mode, the UDF output replaces the score of the underlying scorer, so the boost has
+    // to be applied to it.
+    boost: Score,
+    udf_output: f64,
+}
+
+impl UdfScorer {
+    fn is_current_doc_match(&mut self) -> bool {
+        let doc = self.underlying_scorer.doc();
+        self.args.clear();
+        self.args.extend(
+            self.arg_columns
+                .iter()
+                .map(|arg_column| arg_column.value(doc).unwrap_or(f64::NAN)),
+        );
+        let Ok(udf_output) = self.udf_instance.call(&self.args) else {
+            return false;
+        };
+        self.udf_output = udf_output;
+        match self.mode {
+            UdfMode::Filter => udf_output > 0.0,
+            UdfMode::Score => !udf_output.is_nan(),
+        }
+    }
+
+    fn advance_until_match(&mut self) -> DocId {
+        let mut doc = self.underlying_scorer.doc();
+        while doc != TERMINATED && !self.is_current_doc_match() {
+            doc = self.underlying_scorer.advance();
+        }
+        doc
+    }
+}
+
+impl DocSet for UdfScorer {
+    fn advance(&mut self) -> DocId {
+        self.underlying_scorer.advance();
+        self.advance_until_match()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.underlying_scorer.seek(target);
+        self.advance_until_match()
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying_scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying_scorer.size_hint()
+    }
+}
+
+impl Scorer for UdfScorer {
+    fn score(&mut self) -> Score {
+        match self.mode {
+            UdfMode::Filter => self.underlying_scorer.score(),
+            UdfMode::Score => self.udf_output as Score * self.boost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, FAST, INDEXED, TEXT};
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::query_ast::test_helpers::{self, search_docs, search_scored_docs};
+    use crate::udf::register_udf;
+    use crate::NotNaNf32;
+
+    struct SumUdf;
+
+    impl Udf for SumUdf {
+        fn instantiate(&self) -> anyhow::Result<Box<dyn UdfInstance>> {
+            Ok(Box::new(SumUdfInstance))
+        }
+    }
+
+    struct SumUdfInstance;
+
+    impl UdfInstance for SumUdfInstance {
+        fn call(&mut self, args: &[f64]) -> anyhow::Result<f64> {
+            Ok(args.iter().sum())
+        }
+    }
+
+    fn build_test_index() -> Index {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let count_field = schema_builder.add_i64_field("count", FAST);
+        schema_builder.add_i64_field("not_fast", INDEXED);
+        let docs = [-2i64, 0, 1, 3]
+            .into_iter()
+            .map(|count| doc!(body_field => "hello", count_field => count));
+        test_helpers::build_test_index(schema_builder.build(), docs)
+    }
+
+    fn udf_query(mode: UdfMode) -> QueryAst {
+        UdfQuery {
+            underlying: Box::new(QueryAst::MatchAll),
+            udf: "sum_for_test".to_string(),
+            fields: vec!["count".to_string()],
+            mode,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_udf_query_filter() {
+        register_udf("sum_for_test", Arc::new(SumUdf));
+        let index = build_test_index();
+        assert_eq!(search_docs(&index, &udf_query(UdfMode::Filter)), [2, 3]);
+    }
+
+    #[test]
+    fn test_udf_query_score() {
+        register_udf("sum_for_test", Arc::new(SumUdf));
+        let index = build_test_index();
+        let scored_docs = search_scored_docs(&index, &udf_query(UdfMode::Score), true).unwrap();
+        assert_eq!(scored_docs, vec![(3.0, 3), (1.0, 2), (0.0, 1), (-2.0, 0)]);
+    }
+
+    #[test]
+    fn test_udf_query_score_with_boost() {
+        register_udf("sum_for_test", Arc::new(SumUdf));
+        let index = build_test_index();
+        let query_ast = QueryAst::Boost {
+            underlying: Box::new(udf_query(UdfMode::Score)),
+            boost: NotNaNf32::try_from(2.0).unwrap(),
+        };
+        let scored_docs = search_scored_docs(&index, &query_ast, true).unwrap();
+        assert_eq!(scored_docs, vec![(6.0, 3), (2.0, 2), (0.0, 1), (-4.0, 0)]);
+    }
+
+    #[test]
+    fn test_udf_query_invalid() {
+        register_udf("sum_for_test", Arc::new(SumUdf));
+        let index = build_test_index();
+        let unknown_udf_query: QueryAst = UdfQuery {
+            underlying: Box::new(QueryAst::MatchAll),
+            udf: "unknown".to_string(),
+            fields: Vec::new(),
+            mode: UdfMode::Filter,
+        }
+        .into();
+        let error = unknown_udf_query
+            .build_tantivy_query(&index.schema(), &[], true, true)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::UdfNotFound { .. }));
+
+        let not_fast_field_query: QueryAst = UdfQuery {
+            underlying: Box::new(QueryAst::MatchAll),
+            udf: "sum_for_test".to_string(),
+            fields: vec!["not_fast".to_string()],
+            mode: UdfMode::Filter,
+        }
+        .into();
+        let error = not_fast_field_query
+            .build_tantivy_query(&index.schema(), &[], true, true)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+
+    #[test]
+    fn test_udf_query_serde() {
+        let udf_query: QueryAst = serde_json::from_str(
+            r#"{"type": "udf", "underlying": {"type": "match_all"}, "udf": "my_udf", "fields": ["count"]}"#,
+        )
+        .unwrap();
+        let QueryAst::Udf(udf_query) = udf_query else {
+            panic!();
+        };
+        assert_eq!(udf_query.udf, "my_udf");
+        assert_eq!(udf_query.mode, UdfMode::Filter);
+    }
+}
@@ -29,8 +29,10 @@ use tantivy::schema::Schema as TantivySchema;
 
 use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::utils::is_false;
 use crate::query_ast::{self, BuildTantivyAst, FullTextMode, FullTextParams, QueryAst};
-use crate::{BooleanOperand, InvalidQuery, JsonLiteral};
+use crate::search_field::{boost_query_ast, parse_search_fields};
+use crate::{BooleanOperand, Fuzziness, InvalidQuery, JsonLiteral};
 
 /// A query expressed in the tantivy query grammar DSL.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -44,6 +46,27 @@ pub struct UserInputQuery {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default_fields: Option<Vec<String>>,
     pub default_operator: BooleanOperand,
+    // Suffix appended to the field names targeted by the quoted phrases of the query.
+    // This makes it possible to run exact phrase matches against a differently analyzed
+    // field, e.g. `body.exact`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_field_suffix: Option<String>,
+    // Slop applied to the quoted phrases of the query that do not set one with `~N`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub phrase_slop: u32,
+    // If true, the terms that cannot be interpreted with the type of the field they target,
+    // e.g. text searched in a numeric field, match no documents instead of failing the query.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub lenient: bool,
+    // If set, unquoted terms suffixed with `~N` match the terms within `N` edits, and terms
+    // suffixed with `~` alone use this fuzziness. If None, the `~` suffixes are searched as
+    // regular text, as they always were outside of `query_string` queries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuzziness: Option<Fuzziness>,
+}
+
+fn is_zero(val: &u32) -> bool {
+    *val == 0u32
 }
 
 impl UserInputQuery {
@@ -68,7 +91,18 @@ impl UserInputQuery {
             BooleanOperand::And => Occur::Must,
             BooleanOperand::Or => Occur::Should,
         };
-        convert_user_input_ast_to_query_ast(user_input_ast, default_occur, search_fields)
+        let literal_options = LiteralOptions {
+            quote_field_suffix: self.quote_field_suffix.as_deref(),
+            phrase_slop: self.phrase_slop,
+            lenient: self.lenient,
+            fuzziness: self.fuzziness,
+        };
+        convert_user_input_ast_to_query_ast(
+            user_input_ast,
+            default_occur,
+            search_fields,
+            &literal_options,
+        )
     }
 }
 
@@ -84,15 +118,25 @@ impl BuildTantivyAst for UserInputQuery {
         _schema: &TantivySchema,
         _default_search_fields: &[String],
         _with_validation: bool,
+        _with_scoring: bool,
     ) -> Result<TantivyQueryAst, crate::InvalidQuery> {
         Err(InvalidQuery::UserQueryNotParsed)
     }
 }
 
+/// Options of the [`UserInputQuery`] applied to each of its literals.
+struct LiteralOptions<'a> {
+    quote_field_suffix: Option<&'a str>,
+    phrase_slop: u32,
+    lenient: bool,
+    fuzziness: Option<Fuzziness>,
+}
+
 fn convert_user_input_ast_to_query_ast(
     user_input_ast: UserInputAst,
     default_occur: Occur,
     default_search_fields: &[String],
+    literal_options: &LiteralOptions,
 ) -> anyhow::Result<QueryAst> {
     match user_input_ast {
         UserInputAst::Clause(clause) => {
@@ -102,6 +146,7 @@ fn convert_user_input_ast_to_query_ast(
                     sub_ast,
                     default_occur,
                     default_search_fields,
+                    literal_options,
                 )?;
                 let children_ast_for_occur: &mut Vec<QueryAst> =
                     match occur_opt.unwrap_or(default_occur) {
@@ -115,7 +160,7 @@ fn convert_user_input_ast_to_query_ast(
         }
         UserInputAst::Leaf(leaf) => match *leaf {
             UserInputLeaf::Literal(literal) => {
-                convert_user_input_literal(literal, default_search_fields)
+                convert_user_input_literal(literal, default_search_fields, literal_options)
             }
             UserInputLeaf::All => Ok(QueryAst::MatchAll),
             UserInputLeaf::Range {
@@ -141,10 +186,14 @@ fn convert_user_input_ast_to_query_ast(
                 Ok(range_query.into())
             }
             UserInputLeaf::Set { field, elements } => {
+                // Boosts do not apply to term set queries, which are not scored.
                 let field_names: Vec<String> = if let Some(field) = field.as_ref() {
                     vec![field.to_string()]
                 } else {
-                    default_search_fields.to_vec()
+                    parse_search_fields(default_search_fields)?
+                        .into_iter()
+                        .map(|(field_name, _boost_opt)| field_name)
+                        .collect()
                 };
                 if field_names.is_empty() {
                     anyhow::bail!("Set query need to target a specific field.");
@@ -163,6 +212,7 @@ fn convert_user_input_ast_to_query_ast(
                 *underlying,
                 default_occur,
                 default_search_fields,
+                literal_options,
             )?;
             let boost: NotNaNf32 = (boost as f32)
                 .try_into()
@@ -178,6 +228,7 @@ fn convert_user_input_ast_to_query_ast(
 fn convert_user_input_literal(
     user_input_literal: UserInputLiteral,
     default_search_fields: &[String],
+    literal_options: &LiteralOptions,
 ) -> anyhow::Result<QueryAst> {
     let UserInputLiteral {
         field_name,
@@ -185,21 +236,38 @@ fn convert_user_input_literal(
         delimiter,
         slop,
     } = user_input_literal;
-    let field_names: Vec<String> = if let Some(field_name) = field_name {
-        vec![field_name]
+    // Search field boosts only apply when the literal is expanded over the default search fields.
+    let search_fields: Vec<(String, Option<NotNaNf32>)> = if let Some(field_name) = field_name {
+        vec![(field_name, None)]
     } else {
-        default_search_fields
-            .iter()
-            .map(|field_name| field_name.to_string())
-            .collect()
+        parse_search_fields(default_search_fields)?
     };
-    if field_names.is_empty() {
+    if search_fields.is_empty() {
         anyhow::bail!("Query requires a default search field and none was supplied.");
     }
+    // Quoted phrases target the field with the `quote_field_suffix` if it exists in the schema,
+    // which is only known once the query is built.
+    let quote_field_suffix_opt = match delimiter {
+        Delimiter::DoubleQuotes => literal_options.quote_field_suffix,
+        _ => None,
+    };
+    let (phrase, fuzziness_opt) = match (delimiter, literal_options.fuzziness) {
+        (Delimiter::None, Some(default_fuzziness)) => {
+            split_fuzziness_suffix(phrase, slop, default_fuzziness)?
+        }
+        _ => (phrase, None),
+    };
     let mode = match delimiter {
-        Delimiter::None => FullTextMode::PhraseFallbackToIntersection,
-        Delimiter::SingleQuotes => FullTextMode::Bool {
+        Delimiter::None if fuzziness_opt.is_some() => FullTextMode::Bool {
             operator: BooleanOperand::And,
+            minimum_should_match: None,
+            fuzziness: fuzziness_opt,
+        },
+        Delimiter::None => FullTextMode::PhraseFallbackToIntersection,
+        Delimiter::SingleQuotes => BooleanOperand::And.into(),
+        // The tantivy grammar reports a slop of 0 for phrases without `~N`.
+        Delimiter::DoubleQuotes if slop == 0 => FullTextMode::Phrase {
+            slop: literal_options.phrase_slop,
         },
         Delimiter::DoubleQuotes => FullTextMode::Phrase { slop },
     };
@@ -207,16 +275,21 @@ fn convert_user_input_literal(
         tokenizer: None,
         mode,
         zero_terms_query: crate::MatchAllOrNone::MatchNone,
+        lenient: literal_options.lenient,
     };
-    let mut phrase_queries: Vec<QueryAst> = field_names
+    let mut phrase_queries: Vec<QueryAst> = search_fields
         .into_iter()
-        .map(|field_name| {
-            query_ast::FullTextQuery {
+        .map(|(field_name, boost_opt)| {
+            let preferred_field = quote_field_suffix_opt
+                .map(|quote_field_suffix| format!("{field_name}{quote_field_suffix}"));
+            let phrase_query = query_ast::FullTextQuery {
                 field: field_name,
                 text: phrase.clone(),
                 params: full_text_params.clone(),
+                preferred_field,
             }
-            .into()
+            .into();
+            boost_query_ast(phrase_query, boost_opt)
         })
         .collect();
     if phrase_queries.is_empty() {
@@ -232,12 +305,40 @@ fn convert_user_input_literal(
     }
 }
 
+/// Splits the `~N` suffix of an unquoted term, which lets the term match the terms within `N`
+/// edits. Without an edit distance, the suffix stands for the `default_fuzziness` of the query.
+fn split_fuzziness_suffix(
+    term: String,
+    slop: u32,
+    default_fuzziness: Fuzziness,
+) -> anyhow::Result<(String, Option<Fuzziness>)> {
+    // The tantivy grammar may report the edit distance of an unquoted term as a slop.
+    if slop > 0 {
+        let distance = u8::try_from(slop).unwrap_or(u8::MAX);
+        let fuzziness = Fuzziness::distance(distance).map_err(|error| anyhow::anyhow!(error))?;
+        return Ok((term, Some(fuzziness)));
+    }
+    let Some((text, distance_str)) = term.rsplit_once('~') else {
+        return Ok((term, None));
+    };
+    if text.is_empty() || !distance_str.chars().all(|c| c.is_ascii_digit()) {
+        return Ok((term, None));
+    }
+    let fuzziness = if distance_str.is_empty() {
+        default_fuzziness
+    } else {
+        let distance = distance_str.parse::<u8>().unwrap_or(u8::MAX);
+        Fuzziness::distance(distance).map_err(|error| anyhow::anyhow!(error))?
+    };
+    Ok((text.to_string(), Some(fuzziness)))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::query_ast::{
         BoolQuery, BuildTantivyAst, FullTextMode, FullTextQuery, QueryAst, UserInputQuery,
     };
-    use crate::{BooleanOperand, InvalidQuery};
+    use crate::{BooleanOperand, Fuzziness, InvalidQuery};
 
     #[test]
     fn test_user_input_query_not_parsed_error() {
@@ -245,17 +346,21 @@ mod tests {
             user_text: "hello".to_string(),
             default_fields: None,
             default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         };
         let schema = tantivy::schema::Schema::builder().build();
         {
             let invalid_query = user_input_query
-                .build_tantivy_ast_call(&schema, &[], true)
+                .build_tantivy_ast_call(&schema, &[], true, true)
                 .unwrap_err();
             assert!(matches!(invalid_query, InvalidQuery::UserQueryNotParsed));
         }
         {
             let invalid_query = user_input_query
-                .build_tantivy_ast_call(&schema, &[], false)
+                .build_tantivy_ast_call(&schema, &[], false, true)
                 .unwrap_err();
             assert!(matches!(invalid_query, InvalidQuery::UserQueryNotParsed));
         }
@@ -268,6 +373,10 @@ mod tests {
                 user_text: "hello".to_string(),
                 default_fields: None,
                 default_operator: BooleanOperand::And,
+                quote_field_suffix: None,
+                phrase_slop: 0,
+                lenient: false,
+                fuzziness: None,
             }
             .parse_user_query(&[])
             .unwrap_err();
@@ -281,6 +390,10 @@ mod tests {
                 user_text: "hello".to_string(),
                 default_fields: Some(Vec::new()),
                 default_operator: BooleanOperand::And,
+                quote_field_suffix: None,
+                phrase_slop: 0,
+                lenient: false,
+                fuzziness: None,
             }
             .parse_user_query(&[])
             .unwrap_err();
@@ -297,6 +410,10 @@ mod tests {
             user_text: "hello".to_string(),
             default_fields: None,
             default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .parse_user_query(&["defaultfield".to_string()])
         .unwrap();
@@ -315,6 +432,10 @@ mod tests {
             user_text: "hello".to_string(),
             default_fields: Some(vec!["defaultfield".to_string()]),
             default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .parse_user_query(&["defaultfieldweshouldignore".to_string()])
         .unwrap();
@@ -333,6 +454,10 @@ mod tests {
             user_text: "hello".to_string(),
             default_fields: Some(vec!["fielda".to_string(), "fieldb".to_string()]),
             default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .parse_user_query(&["defaultfieldweshouldignore".to_string()])
         .unwrap();
@@ -340,12 +465,59 @@ mod tests {
         assert_eq!(should.len(), 2);
     }
 
+    #[test]
+    fn test_user_input_query_default_fields_with_boosts() {
+        let ast = UserInputQuery {
+            user_text: "hello AND title:world".to_string(),
+            default_fields: None,
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
+        }
+        .parse_user_query(&["body".to_string(), "error.message^3".to_string()])
+        .unwrap();
+        let QueryAst::Bool(BoolQuery { must, .. }) = ast else { panic!() };
+        assert_eq!(must.len(), 2);
+        let QueryAst::Bool(BoolQuery { should, .. }) = &must[0] else { panic!() };
+        let QueryAst::FullText(body_query) = &should[0] else { panic!() };
+        assert_eq!(&body_query.field, "body");
+        let QueryAst::Boost { underlying, boost } = &should[1] else { panic!() };
+        assert_eq!(f32::from(*boost), 3.0);
+        let QueryAst::FullText(error_message_query) = &**underlying else { panic!() };
+        assert_eq!(&error_message_query.field, "error.message");
+        // Fields targeted explicitly in the query are not boosted.
+        let QueryAst::FullText(title_query) = &must[1] else { panic!() };
+        assert_eq!(&title_query.field, "title");
+
+        let invalid_err = UserInputQuery {
+            user_text: "hello".to_string(),
+            default_fields: Some(vec!["body^high".to_string()]),
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
+        }
+        .parse_user_query(&[])
+        .unwrap_err();
+        assert_eq!(
+            invalid_err.to_string(),
+            "Invalid boost `high` for search field `body`: expected a non-negative number."
+        );
+    }
+
     #[test]
     fn test_user_input_query_field_specified_in_user_input() {
         let ast = UserInputQuery {
             user_text: "myfield:hello".to_string(),
             default_fields: Some(vec!["fieldtoignore".to_string()]),
             default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         }
         .parse_user_query(&["fieldtoignore".to_string()])
         .unwrap();
@@ -358,6 +530,134 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_user_input_query_quote_field_suffix() {
+        let ast = UserInputQuery {
+            user_text: "hello \"happy world\"".to_string(),
+            default_fields: Some(vec!["body".to_string()]),
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: Some(".exact".to_string()),
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
+        }
+        .parse_user_query(&[])
+        .unwrap();
+        let QueryAst::Bool(BoolQuery { must, .. }) = ast else { panic!() };
+        let QueryAst::FullText(unquoted_query) = &must[0] else { panic!() };
+        assert_eq!(&unquoted_query.field, "body");
+        assert!(unquoted_query.preferred_field.is_none());
+        let QueryAst::FullText(quoted_query) = &must[1] else { panic!() };
+        assert_eq!(&quoted_query.field, "body");
+        assert_eq!(quoted_query.preferred_field.as_deref(), Some("body.exact"));
+        assert_eq!(&quoted_query.text, "happy world");
+    }
+
+    #[test]
+    fn test_user_input_query_phrase_slop_and_lenient() {
+        let ast = UserInputQuery {
+            user_text: "\"happy world\" \"hello world\"~1".to_string(),
+            default_fields: Some(vec!["body".to_string()]),
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 3,
+            lenient: true,
+            fuzziness: None,
+        }
+        .parse_user_query(&[])
+        .unwrap();
+        let QueryAst::Bool(BoolQuery { must, .. }) = ast else { panic!() };
+        let QueryAst::FullText(default_slop_query) = &must[0] else { panic!() };
+        assert_eq!(
+            default_slop_query.params.mode,
+            FullTextMode::Phrase { slop: 3 }
+        );
+        assert!(default_slop_query.params.lenient);
+        let QueryAst::FullText(explicit_slop_query) = &must[1] else { panic!() };
+        assert_eq!(
+            explicit_slop_query.params.mode,
+            FullTextMode::Phrase { slop: 1 }
+        );
+    }
+
+    #[test]
+    fn test_user_input_query_fuzziness() {
+        let ast = UserInputQuery {
+            user_text: "helo~2 wrld~ title:quikc~1 \"happy world\"~1".to_string(),
+            default_fields: Some(vec!["body".to_string()]),
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: Some(Fuzziness::Distance(1)),
+        }
+        .parse_user_query(&[])
+        .unwrap();
+        let fuzzy_mode = |distance: u8| FullTextMode::Bool {
+            operator: BooleanOperand::And,
+            minimum_should_match: None,
+            fuzziness: Some(Fuzziness::Distance(distance)),
+        };
+        let QueryAst::Bool(BoolQuery { must, .. }) = ast else { panic!() };
+        let QueryAst::FullText(fuzzy_query) = &must[0] else { panic!() };
+        assert_eq!(&fuzzy_query.text, "helo");
+        assert_eq!(fuzzy_query.params.mode, fuzzy_mode(2));
+        // Without an edit distance, the fuzziness of the query applies.
+        let QueryAst::FullText(default_fuzzy_query) = &must[1] else { panic!() };
+        assert_eq!(&default_fuzzy_query.text, "wrld");
+        assert_eq!(default_fuzzy_query.params.mode, fuzzy_mode(1));
+        let QueryAst::FullText(field_fuzzy_query) = &must[2] else { panic!() };
+        assert_eq!(&field_fuzzy_query.field, "title");
+        assert_eq!(&field_fuzzy_query.text, "quikc");
+        assert_eq!(field_fuzzy_query.params.mode, fuzzy_mode(1));
+        let QueryAst::FullText(phrase_query) = &must[3] else { panic!() };
+        assert_eq!(phrase_query.params.mode, FullTextMode::Phrase { slop: 1 });
+
+        let invalid_err = UserInputQuery {
+            user_text: "helo~3".to_string(),
+            default_fields: Some(vec!["body".to_string()]),
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: Some(Fuzziness::default()),
+        }
+        .parse_user_query(&[])
+        .unwrap_err();
+        assert_eq!(
+            invalid_err.to_string(),
+            "Fuzziness must be at most 2, got `3`."
+        );
+    }
+
+    #[test]
+    fn test_user_input_query_without_fuzziness_keeps_tilde_suffixes() {
+        let ast = UserInputQuery {
+            user_text: "helo~2 wrld~".to_string(),
+            default_fields: Some(vec!["body".to_string()]),
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
+        }
+        .parse_user_query(&[])
+        .unwrap();
+        let QueryAst::Bool(BoolQuery { must, .. }) = ast else { panic!() };
+        let QueryAst::FullText(first_query) = &must[0] else { panic!() };
+        assert_eq!(&first_query.text, "helo~2");
+        assert_eq!(
+            first_query.params.mode,
+            FullTextMode::PhraseFallbackToIntersection
+        );
+        let QueryAst::FullText(second_query) = &must[1] else { panic!() };
+        assert_eq!(&second_query.text, "wrld~");
+        assert_eq!(
+            second_query.params.mode,
+            FullTextMode::PhraseFallbackToIntersection
+        );
+    }
+
     #[test]
     fn test_user_input_query_different_delimiter() {
         let parse_user_query_delimiter_util = |query: &str| {
@@ -365,6 +665,10 @@ mod tests {
                 user_text: query.to_string(),
                 default_fields: None,
                 default_operator: BooleanOperand::Or,
+                quote_field_suffix: None,
+                phrase_slop: 0,
+                lenient: false,
+                fuzziness: None,
             }
             .parse_user_query(&[])
             .unwrap();
@@ -399,7 +703,9 @@ mod tests {
             assert_eq!(
                 double_quote_query.params.mode,
                 FullTextMode::Bool {
-                    operator: BooleanOperand::And
+                    operator: BooleanOperand::And,
+                    minimum_should_match: None,
+                    fuzziness: None,
                 }
             );
         }
@@ -17,20 +17,27 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
+use tantivy::fastfield::Column;
 use tantivy::json_utils::{convert_to_fast_value_and_get_term, JsonTermWriter};
 use tantivy::query::TermQuery as TantivyTermQuery;
 use tantivy::schema::{
     Field, FieldEntry, FieldType, IndexRecordOption, JsonObjectOptions, Schema as TantivySchema,
     Type,
 };
-use tantivy::Term;
+use tantivy::{DateTime, DocId, SegmentReader, Term};
 
 use crate::json_literal::InterpretUserInput;
 use crate::query_ast::full_text_query::FullTextParams;
 use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
 use crate::InvalidQuery;
 
-const DYNAMIC_FIELD_NAME: &str = "_dynamic";
+pub(crate) const DYNAMIC_FIELD_NAME: &str = "_dynamic";
+
+/// Used to skip serializing boolean fields that are false, e.g. `lenient`.
+pub(crate) fn is_false(val: &bool) -> bool {
+    !*val
+}
 
 fn make_term_query(term: Term) -> TantivyQueryAst {
     TantivyTermQuery::new(term, IndexRecordOption::WithFreqs).into()
@@ -67,6 +74,48 @@ pub fn find_field_or_hit_dynamic<'a>(
     Ok((field, field_entry, path))
 }
 
+/// Numerical, boolean or datetime fast field column whose values are read as `f64`.
+///
+/// Datetimes are read as a number of seconds since the Unix epoch and booleans as `0` or `1`.
+pub(crate) struct NumericalFastFieldColumn {
+    column: Column<u64>,
+    column_type: ColumnType,
+}
+
+impl NumericalFastFieldColumn {
+    /// Opens the column of the fast field `field_name`. The column is empty if the segment
+    /// has no values for the field.
+    pub fn open(reader: &SegmentReader, field_name: &str) -> tantivy::Result<Self> {
+        let (column, column_type) = reader
+            .fast_fields()
+            .u64_lenient(field_name)?
+            .unwrap_or_else(|| {
+                (
+                    Column::build_empty_column(reader.max_doc()),
+                    ColumnType::U64,
+                )
+            });
+        Ok(Self {
+            column,
+            column_type,
+        })
+    }
+
+    /// Returns the first value of the document, if any.
+    pub fn value(&self, doc: DocId) -> Option<f64> {
+        let value = self.column.first(doc)?;
+        let value = match self.column_type {
+            ColumnType::I64 => i64::from_u64(value) as f64,
+            ColumnType::F64 => f64::from_u64(value),
+            ColumnType::DateTime => {
+                DateTime::from_u64(value).into_timestamp_micros() as f64 / 1_000_000.0
+            }
+            _ => value as f64,
+        };
+        Some(value)
+    }
+}
+
 /// Creates a full text query.
 ///
 /// If tokenize is set to true, the text will be tokenized.
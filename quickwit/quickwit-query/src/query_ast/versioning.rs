@@ -0,0 +1,409 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Versioned JSON representation of the [`QueryAst`].
+//!
+//! Query ASTs are exchanged between nodes that may run different versions of Quickwit during a
+//! rolling upgrade, and delete queries are persisted in the metastore. The JSON representation
+//! therefore carries a `version` key next to the `type` key of the root query.
+//!
+//! Compatibility rules:
+//! - new parameters must be optional and default to the previous behavior, so that newer nodes
+//!   can read ASTs that do not have them;
+//! - new query types, and new parameters that change the matched documents or their scores when
+//!   ignored, require bumping [`QUERY_AST_VERSION`] and recording the change in
+//!   `RequiredVersion`;
+//! - an AST is tagged with the oldest version able to read it rather than with the version of the
+//!   node that serialized it, so that older nodes keep reading the ASTs that only use the query
+//!   types and parameters they know;
+//! - ASTs without a `version` key were serialized before versioning was introduced and are read as
+//!   version 1;
+//! - an AST requiring a version more recent than the one of the node reading it is rejected
+//!   upfront, with an error that says so, instead of failing on an unknown query type or silently
+//!   ignoring a parameter. Leaf search errors are retryable, so the root retries the affected
+//!   splits on another searcher.
+
+use std::convert::Infallible;
+
+use serde::de::Error;
+use serde::Serialize;
+
+use super::{
+    BoolQuery, BoostingQuery, CombinedFieldsQuery, DisMaxQuery, FieldPresenceQuery, FullTextMode,
+    FullTextParams, FullTextQuery, FunctionScoreQuery, IntervalsQuery, KnnQuery, PhrasePrefixQuery,
+    QueryAst, QueryAstVisitor, RegexQuery, SimpleUserInputQuery, SpanQuery, TermsLookupQuery,
+    UdfQuery, UserInputQuery,
+};
+use crate::NotNaNf32;
+
+/// Current version of the JSON representation of the `QueryAst`.
+///
+/// This version must be bumped whenever a change cannot be handled by older nodes by ignoring
+/// unknown parameters, and the change recorded in `RequiredVersion`.
+pub const QUERY_AST_VERSION: u64 = 2;
+
+const VERSION_KEY: &str = "version";
+
+/// Version assumed for ASTs serialized without a `version` key.
+const UNVERSIONED_QUERY_AST_VERSION: u64 = 1;
+
+#[derive(Serialize)]
+struct VersionedQueryAst<'a> {
+    #[serde(flatten)]
+    query_ast: &'a QueryAst,
+    version: u64,
+}
+
+/// Serializes a `QueryAst` into JSON, tagged with the oldest version able to read it.
+pub fn query_ast_to_json(query_ast: &QueryAst) -> serde_json::Result<String> {
+    let versioned_query_ast = VersionedQueryAst {
+        query_ast,
+        version: required_version(query_ast),
+    };
+    serde_json::to_string(&versioned_query_ast)
+}
+
+/// Deserializes a `QueryAst` serialized by [`query_ast_to_json`], possibly by another version of
+/// Quickwit, or by plain `serde_json` serialization.
+pub fn query_ast_from_json(query_ast_json: &str) -> serde_json::Result<QueryAst> {
+    let mut query_ast_value: serde_json::Value = serde_json::from_str(query_ast_json)?;
+    let version = match query_ast_value
+        .as_object_mut()
+        .and_then(|query_ast_object| query_ast_object.remove(VERSION_KEY))
+    {
+        Some(version_value) => version_value.as_u64().ok_or_else(|| {
+            serde_json::Error::custom(format!(
+                "invalid query AST version `{version_value}`, expected a positive integer"
+            ))
+        })?,
+        None => UNVERSIONED_QUERY_AST_VERSION,
+    };
+    if version > QUERY_AST_VERSION {
+        return Err(serde_json::Error::custom(format!(
+            "failed to read query AST of version {version} produced by a more recent node (this \
+             node supports up to version {QUERY_AST_VERSION})"
+        )));
+    }
+    serde_json::from_value(query_ast_value)
+}
+
+/// Returns the oldest version of the JSON representation able to read `query_ast`.
+fn required_version(query_ast: &QueryAst) -> u64 {
+    let mut required_version_visitor = RequiredVersion {
+        version: UNVERSIONED_QUERY_AST_VERSION,
+    };
+    required_version_visitor
+        .visit(query_ast)
+        .expect("can't fail unwrapping Infallible");
+    required_version_visitor.version
+}
+
+/// Computes the oldest version able to read a query AST, from the versions that introduced its
+/// query types and parameters.
+///
+/// Version 2 introduced the dis max, boosting, terms lookup, combined fields, intervals, span,
+/// knn, regex, field presence, simple query string, constant score, UDF and function score
+/// queries, as well as the `minimum_should_match`, `fuzziness`, `lenient`, `preferred_field`,
+/// `quote_field_suffix` and `phrase_slop` parameters.
+struct RequiredVersion {
+    version: u64,
+}
+
+impl RequiredVersion {
+    fn require(&mut self, version: u64) {
+        self.version = self.version.max(version);
+    }
+
+    fn check_full_text_params(&mut self, full_text_params: &FullTextParams) {
+        let has_bool_mode_params = matches!(
+            full_text_params.mode,
+            FullTextMode::Bool {
+                minimum_should_match: Some(_),
+                ..
+            } | FullTextMode::Bool {
+                fuzziness: Some(_),
+                ..
+            }
+        );
+        if has_bool_mode_params || full_text_params.lenient {
+            self.require(2);
+        }
+    }
+}
+
+impl<'a> QueryAstVisitor<'a> for RequiredVersion {
+    type Err = Infallible;
+
+    fn visit_bool(&mut self, bool_query: &'a BoolQuery) -> Result<(), Self::Err> {
+        if bool_query.minimum_should_match.is_some() {
+            self.require(2);
+        }
+        for ast in bool_query
+            .must
+            .iter()
+            .chain(bool_query.should.iter())
+            .chain(bool_query.must_not.iter())
+            .chain(bool_query.filter.iter())
+        {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+
+    fn visit_dis_max(&mut self, dis_max_query: &'a DisMaxQuery) -> Result<(), Self::Err> {
+        self.require(2);
+        for ast in &dis_max_query.queries {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+
+    fn visit_boosting(&mut self, boosting_query: &'a BoostingQuery) -> Result<(), Self::Err> {
+        self.require(2);
+        self.visit(&boosting_query.positive)?;
+        self.visit(&boosting_query.negative)
+    }
+
+    fn visit_terms_lookup(
+        &mut self,
+        _terms_lookup_query: &'a TermsLookupQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_full_text(&mut self, full_text_query: &'a FullTextQuery) -> Result<(), Self::Err> {
+        if full_text_query.preferred_field.is_some() {
+            self.require(2);
+        }
+        self.check_full_text_params(&full_text_query.params);
+        Ok(())
+    }
+
+    fn visit_combined_fields(
+        &mut self,
+        _combined_fields_query: &'a CombinedFieldsQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_phrase_prefix(
+        &mut self,
+        phrase_prefix_query: &'a PhrasePrefixQuery,
+    ) -> Result<(), Self::Err> {
+        self.check_full_text_params(&phrase_prefix_query.analyzer);
+        Ok(())
+    }
+
+    fn visit_intervals(&mut self, _intervals_query: &'a IntervalsQuery) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_span(&mut self, _span_query: &'a SpanQuery) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_knn(&mut self, _knn_query: &'a KnnQuery) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_regex(&mut self, _regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_field_presence(
+        &mut self,
+        _field_presence_query: &'a FieldPresenceQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_const_score(
+        &mut self,
+        underlying: &'a QueryAst,
+        _score: NotNaNf32,
+    ) -> Result<(), Self::Err> {
+        self.require(2);
+        self.visit(underlying)
+    }
+
+    fn visit_user_text(&mut self, user_text_query: &'a UserInputQuery) -> Result<(), Self::Err> {
+        if user_text_query.quote_field_suffix.is_some()
+            || user_text_query.phrase_slop != 0
+            || user_text_query.lenient
+            || user_text_query.fuzziness.is_some()
+        {
+            self.require(2);
+        }
+        Ok(())
+    }
+
+    fn visit_simple_user_text(
+        &mut self,
+        _simple_user_input_query: &'a SimpleUserInputQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(2);
+        Ok(())
+    }
+
+    fn visit_udf(&mut self, udf_query: &'a UdfQuery) -> Result<(), Self::Err> {
+        self.require(2);
+        self.visit(&udf_query.underlying)
+    }
+
+    fn visit_function_score(
+        &mut self,
+        function_score_query: &'a FunctionScoreQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(2);
+        self.visit(&function_score_query.underlying)?;
+        for function in &function_score_query.functions {
+            if let Some(filter) = &function.filter {
+                self.visit(filter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BooleanOperand, Fuzziness, MatchAllOrNone};
+
+    #[test]
+    fn test_query_ast_json_round_trip() {
+        let query_ast = QueryAst::term("a", "1").and(QueryAst::MatchAll);
+        let query_ast_json = query_ast_to_json(&query_ast).unwrap();
+        let query_ast_value: serde_json::Value = serde_json::from_str(&query_ast_json).unwrap();
+        assert_eq!(query_ast_value["version"], 1);
+        assert_eq!(query_ast_value["type"], "bool");
+        assert_eq!(query_ast_from_json(&query_ast_json).unwrap(), query_ast);
+    }
+
+    #[test]
+    fn test_query_ast_to_json_required_version() {
+        let required_version = |query_ast: QueryAst| -> serde_json::Value {
+            let query_ast_json = query_ast_to_json(&query_ast).unwrap();
+            assert_eq!(query_ast_from_json(&query_ast_json).unwrap(), query_ast);
+            let query_ast_value: serde_json::Value = serde_json::from_str(&query_ast_json).unwrap();
+            query_ast_value["version"].clone()
+        };
+        assert_eq!(required_version(QueryAst::full_text("a", "hello")), 1);
+
+        let dis_max_query: QueryAst = DisMaxQuery {
+            queries: vec![QueryAst::term("a", "1")],
+            tie_breaker: None,
+        }
+        .into();
+        assert_eq!(
+            required_version(QueryAst::term("b", "2").or(dis_max_query)),
+            2
+        );
+
+        let fuzzy_query: QueryAst = FullTextQuery {
+            field: "a".to_string(),
+            text: "hello".to_string(),
+            params: FullTextParams {
+                tokenizer: None,
+                mode: FullTextMode::Bool {
+                    operator: BooleanOperand::Or,
+                    minimum_should_match: None,
+                    fuzziness: Some(Fuzziness::Distance(1)),
+                },
+                zero_terms_query: MatchAllOrNone::MatchNone,
+                lenient: false,
+            },
+            preferred_field: None,
+        }
+        .into();
+        assert_eq!(
+            required_version(QueryAst::term("b", "2").and(fuzzy_query)),
+            2
+        );
+    }
+
+    #[test]
+    fn test_query_ast_from_unversioned_json() {
+        let query_ast_json = r#"{"type": "term", "field": "a", "value": "1"}"#;
+        assert_eq!(
+            query_ast_from_json(query_ast_json).unwrap(),
+            QueryAst::term("a", "1")
+        );
+    }
+
+    #[test]
+    fn test_query_ast_from_json_ignores_unknown_parameters() {
+        let query_ast_json = r#"{
+            "type": "full_text",
+            "field": "a",
+            "text": "hello",
+            "params": { "mode": { "type": "phrase" }, "some_future_param": true },
+            "version": 2
+        }"#;
+        let expected_query_ast: QueryAst = FullTextQuery {
+            field: "a".to_string(),
+            text: "hello".to_string(),
+            params: FullTextParams {
+                tokenizer: None,
+                mode: FullTextMode::Phrase { slop: 0 },
+                zero_terms_query: MatchAllOrNone::MatchNone,
+                lenient: false,
+            },
+            preferred_field: None,
+        }
+        .into();
+        assert_eq!(
+            query_ast_from_json(query_ast_json).unwrap(),
+            expected_query_ast
+        );
+    }
+
+    #[test]
+    fn test_query_ast_from_json_unknown_query_type() {
+        let query_ast_json = r#"{
+            "type": "bool",
+            "must": [{ "type": "some_future_query" }],
+            "version": 3
+        }"#;
+        let error = query_ast_from_json(query_ast_json).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("produced by a more recent node (this node supports up to version 2)"));
+
+        let query_ast_json = r#"{"type": "bool", "must": [{ "type": "some_future_query" }]}"#;
+        let error = query_ast_from_json(query_ast_json).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("unknown variant `some_future_query`"));
+    }
+
+    #[test]
+    fn test_query_ast_from_json_invalid_version() {
+        let query_ast_json = r#"{"type": "match_all", "version": "latest"}"#;
+        let error = query_ast_from_json(query_ast_json).unwrap_err();
+        assert!(error.to_string().contains("invalid query AST version"));
+    }
+}
@@ -20,7 +20,10 @@
 use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::user_input_query::UserInputQuery;
 use crate::query_ast::{
-    BoolQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, RangeQuery, TermQuery, TermSetQuery,
+    BoolQuery, BoostingQuery, CombinedFieldsQuery, DisMaxQuery, FieldPresenceQuery, FullTextQuery,
+    FunctionScoreQuery, IntervalsQuery, KnnQuery, PhrasePrefixQuery, QueryAst, RangeQuery,
+    RegexQuery, SimpleUserInputQuery, SpanQuery, TermQuery, TermSetQuery, TermsLookupQuery,
+    UdfQuery,
 };
 
 /// Simple trait to implement a Visitor over the QueryAst.
@@ -30,17 +33,42 @@ pub trait QueryAstVisitor<'a> {
     fn visit(&mut self, query_ast: &'a QueryAst) -> Result<(), Self::Err> {
         match query_ast {
             QueryAst::Bool(bool_query) => self.visit_bool(bool_query),
+            QueryAst::DisMax(dis_max_query) => self.visit_dis_max(dis_max_query),
+            QueryAst::Boosting(boosting_query) => self.visit_boosting(boosting_query),
             QueryAst::Term(term_query) => self.visit_term(term_query),
             QueryAst::TermSet(term_set_query) => self.visit_term_set(term_set_query),
+            QueryAst::TermsLookup(terms_lookup_query) => {
+                self.visit_terms_lookup(terms_lookup_query)
+            }
             QueryAst::FullText(full_text_query) => self.visit_full_text(full_text_query),
+            QueryAst::CombinedFields(combined_fields_query) => {
+                self.visit_combined_fields(combined_fields_query)
+            }
             QueryAst::PhrasePrefix(phrase_prefix_query) => {
                 self.visit_phrase_prefix(phrase_prefix_query)
             }
+            QueryAst::Intervals(intervals_query) => self.visit_intervals(intervals_query),
+            QueryAst::Span(span_query) => self.visit_span(span_query),
+            QueryAst::Knn(knn_query) => self.visit_knn(knn_query),
+            QueryAst::Regex(regex_query) => self.visit_regex(regex_query),
             QueryAst::Range(range_query) => self.visit_range(range_query),
+            QueryAst::FieldPresence(field_presence_query) => {
+                self.visit_field_presence(field_presence_query)
+            }
             QueryAst::MatchAll => self.visit_match_all(),
             QueryAst::MatchNone => self.visit_match_none(),
             QueryAst::Boost { underlying, boost } => self.visit_boost(underlying, *boost),
+            QueryAst::ConstScore { underlying, score } => {
+                self.visit_const_score(underlying, *score)
+            }
             QueryAst::UserInput(user_text_query) => self.visit_user_text(user_text_query),
+            QueryAst::SimpleUserInput(simple_user_input_query) => {
+                self.visit_simple_user_text(simple_user_input_query)
+            }
+            QueryAst::Udf(udf_query) => self.visit_udf(udf_query),
+            QueryAst::FunctionScore(function_score_query) => {
+                self.visit_function_score(function_score_query)
+            }
         }
     }
 
@@ -57,6 +85,18 @@ pub trait QueryAstVisitor<'a> {
         Ok(())
     }
 
+    fn visit_dis_max(&mut self, dis_max_query: &'a DisMaxQuery) -> Result<(), Self::Err> {
+        for ast in &dis_max_query.queries {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+
+    fn visit_boosting(&mut self, boosting_query: &'a BoostingQuery) -> Result<(), Self::Err> {
+        self.visit(&boosting_query.positive)?;
+        self.visit(&boosting_query.negative)
+    }
+
     fn visit_term(&mut self, _term_query: &'a TermQuery) -> Result<(), Self::Err> {
         Ok(())
     }
@@ -65,10 +105,24 @@ pub trait QueryAstVisitor<'a> {
         Ok(())
     }
 
+    fn visit_terms_lookup(
+        &mut self,
+        _terms_lookup_query: &'a TermsLookupQuery,
+    ) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
     fn visit_full_text(&mut self, _full_text: &'a FullTextQuery) -> Result<(), Self::Err> {
         Ok(())
     }
 
+    fn visit_combined_fields(
+        &mut self,
+        _combined_fields_query: &'a CombinedFieldsQuery,
+    ) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
     fn visit_phrase_prefix(
         &mut self,
         _phrase_query: &'a PhrasePrefixQuery,
@@ -76,6 +130,22 @@ pub trait QueryAstVisitor<'a> {
         Ok(())
     }
 
+    fn visit_intervals(&mut self, _intervals_query: &'a IntervalsQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_span(&mut self, _span_query: &'a SpanQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_knn(&mut self, _knn_query: &'a KnnQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_regex(&mut self, _regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
     fn visit_match_all(&mut self) -> Result<(), Self::Err> {
         Ok(())
     }
@@ -92,11 +162,50 @@ pub trait QueryAstVisitor<'a> {
         self.visit(underlying)
     }
 
+    fn visit_const_score(
+        &mut self,
+        underlying: &'a QueryAst,
+        _score: NotNaNf32,
+    ) -> Result<(), Self::Err> {
+        self.visit(underlying)
+    }
+
     fn visit_range(&mut self, _range_query: &'a RangeQuery) -> Result<(), Self::Err> {
         Ok(())
     }
 
+    fn visit_field_presence(
+        &mut self,
+        _field_presence_query: &'a FieldPresenceQuery,
+    ) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
     fn visit_user_text(&mut self, _user_text_query: &'a UserInputQuery) -> Result<(), Self::Err> {
         Ok(())
     }
+
+    fn visit_simple_user_text(
+        &mut self,
+        _simple_user_input_query: &'a SimpleUserInputQuery,
+    ) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_udf(&mut self, udf_query: &'a UdfQuery) -> Result<(), Self::Err> {
+        self.visit(&udf_query.underlying)
+    }
+
+    fn visit_function_score(
+        &mut self,
+        function_score_query: &'a FunctionScoreQuery,
+    ) -> Result<(), Self::Err> {
+        self.visit(&function_score_query.underlying)?;
+        for function in &function_score_query.functions {
+            if let Some(filter) = &function.filter {
+                self.visit(filter)?;
+            }
+        }
+        Ok(())
+    }
 }
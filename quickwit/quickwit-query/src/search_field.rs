@@ -0,0 +1,119 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::query_ast::QueryAst;
+use crate::NotNaNf32;
+
+/// Splits a search field specification of the form `field_name^boost` (e.g. `error.message^3`)
+/// into a field name and an optional boost.
+///
+/// Boosts are applied whenever a query is expanded over the search fields, be it the default
+/// search fields of an index or the search fields supplied with a request.
+pub fn parse_search_field(search_field: &str) -> anyhow::Result<(&str, Option<f32>)> {
+    let Some((field_name, boost_str)) = search_field.rsplit_once('^') else {
+        return Ok((search_field, None));
+    };
+    let boost: f32 = boost_str
+        .parse()
+        .ok()
+        .filter(|boost: &f32| boost.is_finite() && *boost >= 0.0)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid boost `{boost_str}` for search field `{field_name}`: expected a \
+                 non-negative number."
+            )
+        })?;
+    if field_name.is_empty() {
+        anyhow::bail!("Invalid search field `{search_field}`: missing field name.");
+    }
+    Ok((field_name, Some(boost)))
+}
+
+/// Parses a list of search field specifications, see [`parse_search_field`].
+pub(crate) fn parse_search_fields(
+    search_fields: &[String],
+) -> anyhow::Result<Vec<(String, Option<NotNaNf32>)>> {
+    search_fields
+        .iter()
+        .map(|search_field| {
+            let (field_name, boost_opt) = parse_search_field(search_field)?;
+            let boost_opt = boost_opt
+                .map(NotNaNf32::try_from)
+                .transpose()
+                .map_err(|err_msg: &str| anyhow::anyhow!(err_msg))?;
+            Ok((field_name.to_string(), boost_opt))
+        })
+        .collect()
+}
+
+/// Wraps `query_ast` into a boost query, unless there is no boost to apply.
+pub(crate) fn boost_query_ast(query_ast: QueryAst, boost_opt: Option<NotNaNf32>) -> QueryAst {
+    match boost_opt {
+        Some(boost) if boost != NotNaNf32::ONE => QueryAst::Boost {
+            underlying: Box::new(query_ast),
+            boost,
+        },
+        _ => query_ast,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_field() {
+        assert_eq!(parse_search_field("body").unwrap(), ("body", None));
+        assert_eq!(
+            parse_search_field("error.message^3").unwrap(),
+            ("error.message", Some(3.0))
+        );
+        assert_eq!(
+            parse_search_field("message^0.5").unwrap(),
+            ("message", Some(0.5))
+        );
+        assert_eq!(
+            parse_search_field("message^foo").unwrap_err().to_string(),
+            "Invalid boost `foo` for search field `message`: expected a non-negative number."
+        );
+        parse_search_field("message^-1").unwrap_err();
+        parse_search_field("message^").unwrap_err();
+        parse_search_field("^2").unwrap_err();
+    }
+
+    #[test]
+    fn test_boost_query_ast() {
+        assert_eq!(
+            boost_query_ast(QueryAst::MatchAll, None),
+            QueryAst::MatchAll
+        );
+        assert_eq!(
+            boost_query_ast(QueryAst::MatchAll, Some(NotNaNf32::ONE)),
+            QueryAst::MatchAll
+        );
+        let boost = NotNaNf32::try_from(3.0).unwrap();
+        assert_eq!(
+            boost_query_ast(QueryAst::MatchAll, Some(boost)),
+            QueryAst::Boost {
+                underlying: Box::new(QueryAst::MatchAll),
+                boost,
+            }
+        );
+    }
+}
@@ -20,11 +20,15 @@
 use std::str::CharIndices;
 
 use once_cell::sync::Lazy;
+use rphonetic::DoubleMetaphone;
 use tantivy::tokenizer::{
     LowerCaser, RawTokenizer, RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
     TokenizerManager,
 };
 
+use crate::char_filters::VecTokenStream;
+use crate::trace_context::{normalize_span_id, normalize_trace_id};
+
 fn create_quickwit_tokenizer_manager() -> TokenizerManager {
     let raw_tokenizer = TextAnalyzer::builder(RawTokenizer)
         .filter(RemoveLongFilter::limit(255))
@@ -38,6 +42,7 @@ fn create_quickwit_tokenizer_manager() -> TokenizerManager {
     let tokenizer_manager = TokenizerManager::new();
     tokenizer_manager.register("raw", raw_tokenizer);
     tokenizer_manager.register("chinese_compatible", chinese_tokenizer);
+    register_hex_id_tokenizers(&tokenizer_manager);
 
     tokenizer_manager.register(
         "default",
@@ -56,6 +61,16 @@ fn create_quickwit_tokenizer_manager() -> TokenizerManager {
             ))
             .build(),
     );
+    tokenizer_manager.register(
+        "double_metaphone",
+        TextAnalyzer::builder(DoubleMetaphoneTokenizer {
+            text_analyzer: TextAnalyzer::builder(tantivy::tokenizer::SimpleTokenizer)
+                .filter(RemoveLongFilter::limit(255))
+                .filter(LowerCaser)
+                .build(),
+        })
+        .build(),
+    );
 
     tokenizer_manager
 }
@@ -73,9 +88,117 @@ fn create_quickwit_fastfield_normalizer_manager() -> TokenizerManager {
     let tokenizer_manager = TokenizerManager::new();
     tokenizer_manager.register("raw", raw_tokenizer);
     tokenizer_manager.register("lowercase", lower_case_tokenizer);
+    register_hex_id_tokenizers(&tokenizer_manager);
     tokenizer_manager
 }
 
+fn register_hex_id_tokenizers(tokenizer_manager: &TokenizerManager) {
+    let trace_id_tokenizer = TextAnalyzer::builder(HexIdTokenizer {
+        normalize_fn: normalize_trace_id,
+    })
+    .filter(RemoveLongFilter::limit(255))
+    .build();
+    let span_id_tokenizer = TextAnalyzer::builder(HexIdTokenizer {
+        normalize_fn: normalize_span_id,
+    })
+    .filter(RemoveLongFilter::limit(255))
+    .build();
+    tokenizer_manager.register("trace_id", trace_id_tokenizer);
+    tokenizer_manager.register("span_id", span_id_tokenizer);
+}
+
+/// Tokenizes the text like the default tokenizer, then replaces each token with its double
+/// metaphone codes, so that words that sound alike, such as misspelled person names, match.
+///
+/// The primary and alternate codes of a word are emitted at the same position. Words without
+/// phonetic code, e.g. numbers, are emitted unchanged.
+#[derive(Clone)]
+struct DoubleMetaphoneTokenizer {
+    text_analyzer: TextAnalyzer,
+}
+
+impl Tokenizer for DoubleMetaphoneTokenizer {
+    type TokenStream<'a> = VecTokenStream;
+
+    fn token_stream<'a>(&self, text: &'a str) -> Self::TokenStream<'a> {
+        let double_metaphone = DoubleMetaphone::default();
+        let mut token_stream = self.text_analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+
+        while token_stream.advance() {
+            let token = token_stream.token();
+            let codes = double_metaphone.double_metaphone(&token.text);
+            let primary_code = codes.primary();
+
+            if primary_code.is_empty() {
+                tokens.push(token.clone());
+                continue;
+            }
+            let alternate_code = codes.alternate();
+            let has_alternate_code = !alternate_code.is_empty() && alternate_code != primary_code;
+
+            tokens.push(Token {
+                text: primary_code,
+                ..token.clone()
+            });
+            if has_alternate_code {
+                tokens.push(Token {
+                    text: alternate_code,
+                    ..token.clone()
+                });
+            }
+        }
+        VecTokenStream::new(tokens)
+    }
+}
+
+/// Emits the whole text as a single token, like the raw tokenizer, after normalizing it as a
+/// trace or span ID. Text that is not a valid hex ID is emitted unchanged.
+#[derive(Clone)]
+struct HexIdTokenizer {
+    normalize_fn: fn(&str) -> Option<String>,
+}
+
+impl Tokenizer for HexIdTokenizer {
+    type TokenStream<'a> = HexIdTokenStream;
+
+    fn token_stream<'a>(&self, text: &'a str) -> Self::TokenStream<'a> {
+        let token_text = (self.normalize_fn)(text).unwrap_or_else(|| text.to_string());
+        let token = Token {
+            offset_from: 0,
+            offset_to: text.len(),
+            position: 0,
+            text: token_text,
+            position_length: 1,
+        };
+        HexIdTokenStream {
+            token,
+            has_token: true,
+        }
+    }
+}
+
+struct HexIdTokenStream {
+    token: Token,
+    has_token: bool,
+}
+
+impl TokenStream for HexIdTokenStream {
+    fn advance(&mut self) -> bool {
+        let has_token = self.has_token;
+        self.has_token = false;
+        has_token
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
 #[derive(Clone)]
 struct ChineseTokenizer;
 
@@ -199,7 +322,7 @@ pub fn get_quickwit_fastfield_normalizer_manager() -> &'static TokenizerManager
 mod tests {
     use tantivy::tokenizer::Token;
 
-    use super::get_quickwit_tokenizer_manager;
+    use super::{get_quickwit_fastfield_normalizer_manager, get_quickwit_tokenizer_manager};
 
     #[test]
     fn test_raw_tokenizer() {
@@ -219,6 +342,64 @@ mod tests {
         assert!(tokenizer.token_stream(&my_long_text).advance());
     }
 
+    #[test]
+    fn test_hex_id_tokenizers() {
+        let tokenize = |tokenizer_name: &str, text: &str| {
+            let tokenizer = get_quickwit_tokenizer_manager()
+                .get(tokenizer_name)
+                .unwrap();
+            let mut token_stream = tokenizer.token_stream(text);
+            let mut tokens = Vec::new();
+            while let Some(token) = token_stream.next() {
+                tokens.push(token.text.clone());
+            }
+            tokens
+        };
+        assert_eq!(
+            tokenize("trace_id", "0xA3CE929D0E0E4736"),
+            ["0000000000000000a3ce929d0e0e4736"]
+        );
+        assert_eq!(
+            tokenize("trace_id", "4bf92f3577b34da6a3ce929d0e0e4736"),
+            ["4bf92f3577b34da6a3ce929d0e0e4736"]
+        );
+        assert_eq!(tokenize("trace_id", "not a trace id"), ["not a trace id"]);
+        assert_eq!(tokenize("span_id", "F067AA0BA902B7"), ["00f067aa0ba902b7"]);
+
+        let normalizer = get_quickwit_fastfield_normalizer_manager()
+            .get("trace_id")
+            .unwrap();
+        let mut token_stream = normalizer.token_stream("A3CE929D0E0E4736");
+        assert!(token_stream.advance());
+        assert_eq!(
+            token_stream.token().text,
+            "0000000000000000a3ce929d0e0e4736"
+        );
+        assert!(!token_stream.advance());
+    }
+
+    #[test]
+    fn test_double_metaphone_tokenizer() {
+        let tokenizer = get_quickwit_tokenizer_manager()
+            .get("double_metaphone")
+            .unwrap();
+        let mut token_stream = tokenizer.token_stream("Smith Schmidt 42");
+        let mut tokens = Vec::new();
+        while let Some(token) = token_stream.next() {
+            tokens.push((token.text.clone(), token.position));
+        }
+        assert_eq!(
+            tokens,
+            [
+                ("SM0".to_string(), 0),
+                ("XMT".to_string(), 0),
+                ("XMT".to_string(), 1),
+                ("SMT".to_string(), 1),
+                ("42".to_string(), 2),
+            ]
+        );
+    }
+
     #[test]
     fn test_chinese_tokenizer() {
         let text = "Hello world, 你好世界, bonjour monde";
@@ -0,0 +1,160 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers to normalize trace and span IDs.
+//!
+//! Tracing systems do not agree on the textual representation of trace and span IDs: some emit
+//! upper-case hex, some prefix IDs with `0x`, and 64-bit tracers (Jaeger, Zipkin) emit 16-character
+//! trace IDs that other systems left-pad to 128 bits. The helpers in this module fold these
+//! representations into a canonical form: lower-case hex, without prefix, left-padded with zeros
+//! to the full width of the ID.
+
+use std::collections::BTreeSet;
+
+/// Number of hex characters of a 128-bit trace ID.
+pub const TRACE_ID_HEX_LEN: usize = 32;
+
+/// Number of hex characters of a 64-bit span ID.
+pub const SPAN_ID_HEX_LEN: usize = 16;
+
+/// Normalizes a hex-encoded ID to `num_hex_chars` lower-case hex characters.
+///
+/// Returns `None` if the text is not a hex string or if it is longer than `num_hex_chars` once
+/// leading zeros are stripped.
+fn normalize_hex_id(text: &str, num_hex_chars: usize) -> Option<String> {
+    let text = text.trim();
+    let hex = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if hex.is_empty() || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = if hex.len() > num_hex_chars {
+        let (leading, trailing) = hex.split_at(hex.len() - num_hex_chars);
+        if leading.bytes().any(|byte| byte != b'0') {
+            return None;
+        }
+        trailing
+    } else {
+        hex
+    };
+    let mut normalized = String::with_capacity(num_hex_chars);
+    normalized.extend(std::iter::repeat('0').take(num_hex_chars - hex.len()));
+    normalized.push_str(&hex.to_ascii_lowercase());
+    Some(normalized)
+}
+
+/// Normalizes a hex-encoded trace ID to its canonical 32-character form.
+///
+/// 64-bit trace IDs are left-padded with zeros.
+pub fn normalize_trace_id(trace_id: &str) -> Option<String> {
+    normalize_hex_id(trace_id, TRACE_ID_HEX_LEN)
+}
+
+/// Normalizes a hex-encoded span ID to its canonical 16-character form.
+pub fn normalize_span_id(span_id: &str) -> Option<String> {
+    normalize_hex_id(span_id, SPAN_ID_HEX_LEN)
+}
+
+/// Returns the textual forms under which a trace ID may have been indexed.
+///
+/// The variants include the canonical 128-bit form and the 64-bit form made of its lower 64 bits,
+/// which is what 64-bit tracers propagate. Each form is emitted in lower and upper case so that
+/// they also match fields indexed with the `raw` tokenizer.
+pub fn trace_id_variants(trace_id: &str) -> Option<BTreeSet<String>> {
+    let normalized = normalize_trace_id(trace_id)?;
+    let lower_64_bits = normalized[TRACE_ID_HEX_LEN - SPAN_ID_HEX_LEN..].to_string();
+    Some(with_upper_case_variants([normalized, lower_64_bits]))
+}
+
+/// Returns the textual forms under which a span ID may have been indexed.
+pub fn span_id_variants(span_id: &str) -> Option<BTreeSet<String>> {
+    let normalized = normalize_span_id(span_id)?;
+    Some(with_upper_case_variants([normalized]))
+}
+
+fn with_upper_case_variants<const N: usize>(ids: [String; N]) -> BTreeSet<String> {
+    let mut variants = BTreeSet::new();
+    for id in ids {
+        variants.insert(id.to_ascii_uppercase());
+        variants.insert(id);
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_trace_id() {
+        assert_eq!(
+            normalize_trace_id("4BF92F3577B34DA6A3CE929D0E0E4736").unwrap(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(
+            normalize_trace_id(" 0x4bf92f3577b34da6a3ce929d0e0e4736 ").unwrap(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(
+            normalize_trace_id("a3ce929d0e0e4736").unwrap(),
+            "0000000000000000a3ce929d0e0e4736"
+        );
+        assert_eq!(
+            normalize_trace_id("00a3ce929d0e0e4736a3ce929d0e0e4736").unwrap(),
+            "a3ce929d0e0e4736a3ce929d0e0e4736"
+        );
+        assert!(normalize_trace_id("").is_none());
+        assert!(normalize_trace_id("0x").is_none());
+        assert!(normalize_trace_id("not-a-trace-id").is_none());
+        assert!(normalize_trace_id("1a3ce929d0e0e4736a3ce929d0e0e4736").is_none());
+    }
+
+    #[test]
+    fn test_normalize_span_id() {
+        assert_eq!(
+            normalize_span_id("00F067AA0BA902B7").unwrap(),
+            "00f067aa0ba902b7"
+        );
+        assert_eq!(
+            normalize_span_id("f067aa0ba902b7").unwrap(),
+            "00f067aa0ba902b7"
+        );
+        assert!(normalize_span_id("4bf92f3577b34da6a3ce929d0e0e4736").is_none());
+    }
+
+    #[test]
+    fn test_trace_id_variants() {
+        let variants: Vec<String> = trace_id_variants("A3CE929D0E0E4736")
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            variants,
+            [
+                "0000000000000000A3CE929D0E0E4736",
+                "0000000000000000a3ce929d0e0e4736",
+                "A3CE929D0E0E4736",
+                "a3ce929d0e0e4736",
+            ]
+        );
+        assert!(trace_id_variants("not-a-trace-id").is_none());
+    }
+}
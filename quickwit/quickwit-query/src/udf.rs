@@ -0,0 +1,63 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! User-defined functions (UDFs) callable from the query DSL.
+//!
+//! UDFs are registered once per process (typically by the searcher at startup) and looked up by
+//! name when a [`UdfQuery`](crate::query_ast::UdfQuery) is turned into a tantivy query.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+/// A user-defined function over the fast field values of a document.
+pub trait Udf: Send + Sync + 'static {
+    /// Creates a new instance of the function. An instance is created for every segment
+    /// searched, so that instances do not need to be shared across threads.
+    fn instantiate(&self) -> anyhow::Result<Box<dyn UdfInstance>>;
+}
+
+/// A ready-to-call instance of a [`Udf`].
+pub trait UdfInstance: Send {
+    /// Evaluates the function over the arguments of a single document.
+    ///
+    /// Missing values are passed as `NaN`.
+    fn call(&mut self, args: &[f64]) -> anyhow::Result<f64>;
+}
+
+static UDF_REGISTRY: Lazy<RwLock<HashMap<String, Arc<dyn Udf>>>> = Lazy::new(Default::default);
+
+/// Registers a UDF under `name`, replacing the UDF previously registered under the same name, if
+/// any.
+pub fn register_udf(name: impl Into<String>, udf: Arc<dyn Udf>) {
+    UDF_REGISTRY
+        .write()
+        .expect("The UDF registry lock should not be poisoned.")
+        .insert(name.into(), udf);
+}
+
+/// Returns the UDF registered under `name`, if any.
+pub fn get_udf(name: &str) -> Option<Arc<dyn Udf>> {
+    UDF_REGISTRY
+        .read()
+        .expect("The UDF registry lock should not be poisoned.")
+        .get(name)
+        .cloned()
+}
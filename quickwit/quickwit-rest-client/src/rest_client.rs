@@ -17,17 +17,19 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use bytes::Bytes;
 use quickwit_cluster::ClusterSnapshot;
 use quickwit_common::FileEntry;
 use quickwit_config::{ConfigFormat, SourceConfig};
+use quickwit_doc_mapper::MappingCompatibilityReport;
 use quickwit_indexing::actors::IndexingServiceCounters;
 pub use quickwit_ingest::CommitType;
 use quickwit_metastore::{IndexMetadata, Split};
 use quickwit_search::SearchResponseRest;
-use quickwit_serve::{ListSplitsQueryParams, SearchRequestQueryString};
+use quickwit_serve::{LabelsQueryParams, ListSplitsQueryParams, SearchRequestQueryString};
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use reqwest::{Client, ClientBuilder, Method, StatusCode, Url};
 use serde::Serialize;
@@ -345,15 +347,63 @@ impl<'a> IndexClient<'a> {
         Ok(index_metadata)
     }
 
-    pub async fn list(&self) -> Result<Vec<IndexMetadata>, Error> {
+    pub async fn check_mapping(
+        &self,
+        index_id: &str,
+        body: Bytes,
+        config_format: ConfigFormat,
+    ) -> Result<MappingCompatibilityReport, Error> {
+        let path = format!("indexes/{index_id}/check-mapping");
+        let header_map = header_from_config_format(config_format);
         let response = self
             .transport
-            .send::<()>(Method::GET, "indexes", None, None, None, self.timeout)
+            .send::<()>(
+                Method::POST,
+                &path,
+                Some(header_map),
+                None,
+                Some(body),
+                self.timeout,
+            )
+            .await?;
+        let report = response.deserialize().await?;
+        Ok(report)
+    }
+
+    pub async fn list(
+        &self,
+        labels_query_params: LabelsQueryParams,
+    ) -> Result<Vec<IndexMetadata>, Error> {
+        let response = self
+            .transport
+            .send(
+                Method::GET,
+                "indexes",
+                None,
+                Some(&labels_query_params),
+                None,
+                self.timeout,
+            )
             .await?;
         let indexes_metadatas = response.deserialize().await?;
         Ok(indexes_metadatas)
     }
 
+    pub async fn update_labels(
+        &self,
+        index_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> Result<(), Error> {
+        let path = format!("indexes/{index_id}/labels");
+        let body = Bytes::from(serde_json::to_vec(&json!({ "labels": labels }))?);
+        let response = self
+            .transport
+            .send::<()>(Method::PUT, &path, None, None, Some(body), self.timeout)
+            .await?;
+        response.check().await?;
+        Ok(())
+    }
+
     pub async fn get(&self, index_id: &str) -> Result<IndexMetadata, Error> {
         let path = format!("indexes/{index_id}");
         let response = self
@@ -524,14 +574,32 @@ impl<'a, 'b> SourceClient<'a, 'b> {
         Ok(())
     }
 
-    pub async fn list(&self) -> Result<Vec<SourceConfig>, Error> {
+    pub async fn update_labels(
+        &self,
+        source_id: &str,
+        labels: BTreeMap<String, String>,
+    ) -> Result<(), Error> {
+        let path = format!("{}/{source_id}/labels", self.sources_root_url());
+        let body = Bytes::from(serde_json::to_vec(&json!({ "labels": labels }))?);
         let response = self
             .transport
-            .send::<()>(
+            .send::<()>(Method::PUT, &path, None, None, Some(body), self.timeout)
+            .await?;
+        response.check().await?;
+        Ok(())
+    }
+
+    pub async fn list(
+        &self,
+        labels_query_params: LabelsQueryParams,
+    ) -> Result<Vec<SourceConfig>, Error> {
+        let response = self
+            .transport
+            .send(
                 Method::GET,
                 &self.sources_root_url(),
                 None,
-                None,
+                Some(&labels_query_params),
                 None,
                 self.timeout,
             )
@@ -644,16 +712,18 @@ fn header_from_config_format(config_format: ConfigFormat) -> HeaderMap {
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeMap;
     use std::path::PathBuf;
     use std::str::FromStr;
 
     use bytes::Bytes;
     use quickwit_config::{ConfigFormat, SourceConfig};
+    use quickwit_doc_mapper::MappingCompatibilityReport;
     use quickwit_indexing::mock_split;
     use quickwit_ingest::CommitType;
     use quickwit_metastore::IndexMetadata;
     use quickwit_search::SearchResponseRest;
-    use quickwit_serve::{ListSplitsQueryParams, SearchRequestQueryString};
+    use quickwit_serve::{LabelsQueryParams, ListSplitsQueryParams, SearchRequestQueryString};
     use reqwest::header::CONTENT_TYPE;
     use reqwest::{StatusCode, Url};
     use serde_json::json;
@@ -673,7 +743,11 @@ mod test {
         let port = quickwit_common::net::find_available_tcp_port().unwrap();
         let server_url = Url::parse(&format!("http://127.0.0.1:{port}")).unwrap();
         let qw_client = QuickwitClientBuilder::new(server_url).build();
-        let error = qw_client.indexes().list().await.unwrap_err();
+        let error = qw_client
+            .indexes()
+            .list(LabelsQueryParams::default())
+            .await
+            .unwrap_err();
 
         assert!(matches!(error, Error::Client(_)));
         assert!(error.to_string().contains("tcp connect error"));
@@ -695,6 +769,7 @@ mod test {
             aggregations: None,
             elapsed_time_micros: 100,
             errors: Vec::new(),
+            profile: None,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/search"))
@@ -867,10 +942,50 @@ mod test {
             .mount(&mock_server)
             .await;
         assert_eq!(
-            qw_client.indexes().list().await.unwrap(),
+            qw_client
+                .indexes()
+                .list(LabelsQueryParams::default())
+                .await
+                .unwrap(),
             vec![index_metadata.clone()]
         );
 
+        // GET indexes filtered by labels
+        Mock::given(method("GET"))
+            .and(path("/api/v1/indexes"))
+            .and(query_param("labels", "team:payments,env"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!([])))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        let labels_query_params = LabelsQueryParams {
+            labels: Some(vec![
+                "team:payments".parse().unwrap(),
+                "env".parse().unwrap(),
+            ]),
+        };
+        assert!(qw_client
+            .indexes()
+            .list(labels_query_params)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // PUT index labels
+        let labels = BTreeMap::from_iter([("team".to_string(), "payments".to_string())]);
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/indexes/test-index/labels"))
+            .and(body_json(json!({"labels": {"team": "payments"}})))
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        qw_client
+            .indexes()
+            .update_labels("test-index", labels)
+            .await
+            .unwrap();
+
         // POST create index
         let index_config_to_create = index_metadata.index_config.clone();
         Mock::given(method("POST"))
@@ -911,6 +1026,24 @@ mod test {
             index_metadata
         );
 
+        // POST check mapping
+        let report = MappingCompatibilityReport::default();
+        Mock::given(method("POST"))
+            .and(path("/api/v1/indexes/my-index/check-mapping"))
+            .and(header(CONTENT_TYPE.as_str(), "application/yaml"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(report.clone()))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        assert_eq!(
+            qw_client
+                .indexes()
+                .check_mapping("my-index", Bytes::from("".as_bytes()), ConfigFormat::Yaml)
+                .await
+                .unwrap(),
+            report
+        );
+
         // PUT clear index
         Mock::given(method("PUT"))
             .and(path("/api/v1/indexes/my-index/clear"))
@@ -1048,10 +1181,29 @@ mod test {
             .mount(&mock_server)
             .await;
         assert_eq!(
-            qw_client.sources("my-index").list().await.unwrap(),
+            qw_client
+                .sources("my-index")
+                .list(LabelsQueryParams::default())
+                .await
+                .unwrap(),
             vec![source_config.clone()]
         );
 
+        // PUT source labels
+        let labels = BTreeMap::from_iter([("env".to_string(), "prod".to_string())]);
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/indexes/my-index/sources/my-source-1/labels"))
+            .and(body_json(json!({"labels": {"env": "prod"}})))
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        qw_client
+            .sources("my-index")
+            .update_labels("my-source-1", labels)
+            .await
+            .unwrap();
+
         // Toggle source
         Mock::given(method("PUT"))
             .and(path("/api/v1/indexes/my-index/sources/my-source-1/toggle"))
@@ -0,0 +1,216 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks of the query path: conversion of Elasticsearch queries into a `QueryAst`,
+//! compilation into a tantivy query, computation of the warmup info, and search over a
+//! generated corpus.
+
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use quickwit_doc_mapper::DocMapper;
+use quickwit_indexing::TestSandbox;
+use quickwit_proto::SearchRequest;
+use quickwit_query::query_ast::{query_ast_to_json, QueryAst};
+use quickwit_query::ElasticQueryDsl;
+use quickwit_search::single_node_search;
+use serde_json::json;
+use tokio::runtime::Runtime;
+
+const INDEX_ID: &str = "query-path-bench";
+
+const DOC_MAPPING_YAML: &str = r#"
+    field_mappings:
+      - name: body
+        type: text
+      - name: severity
+        type: text
+        tokenizer: raw
+      - name: status_code
+        type: u64
+        fast: true
+"#;
+
+const NUM_DOCS: usize = 10_000;
+
+const VOCABULARY: &[&str] = &[
+    "the",
+    "quick",
+    "brown",
+    "fox",
+    "jumps",
+    "over",
+    "lazy",
+    "dog",
+    "connection",
+    "refused",
+    "timeout",
+    "retry",
+    "request",
+    "upstream",
+    "cache",
+    "miss",
+];
+
+const SEVERITIES: &[&str] = &["DEBUG", "INFO", "WARN", "ERROR"];
+
+const STATUS_CODES: &[u64] = &[200, 201, 204, 301, 400, 404, 429, 500, 503];
+
+const QUERIES: &[(&str, &str)] = &[
+    (
+        "term",
+        r#"{ "term": { "severity": { "value": "ERROR" } } }"#,
+    ),
+    ("match", r#"{ "match": { "body": "quick brown fox" } }"#),
+    (
+        "match_phrase_prefix",
+        r#"{ "match_phrase_prefix": { "body": { "query": "connection ref" } } }"#,
+    ),
+    (
+        "range",
+        r#"{ "range": { "status_code": { "gte": 400, "lt": 500 } } }"#,
+    ),
+    (
+        "bool",
+        r#"{
+            "bool": {
+                "must": [{ "match": { "body": "timeout" } }],
+                "must_not": [{ "term": { "severity": { "value": "DEBUG" } } }],
+                "filter": [{ "range": { "status_code": { "gte": 500 } } }]
+            }
+        }"#,
+    ),
+    (
+        "query_string",
+        r#"{ "query_string": { "query": "body:upstream AND severity:WARN" } }"#,
+    ),
+];
+
+/// Generates a deterministic corpus in which every word, severity and status code appears.
+fn generate_docs() -> Vec<serde_json::Value> {
+    (0..NUM_DOCS)
+        .map(|doc_id| {
+            let body: Vec<&str> = (0..12)
+                .map(|word_id| VOCABULARY[(doc_id * 7 + word_id * 13) % VOCABULARY.len()])
+                .collect();
+            json!({
+                "body": body.join(" "),
+                "severity": SEVERITIES[doc_id % SEVERITIES.len()],
+                "status_code": STATUS_CODES[doc_id % STATUS_CODES.len()],
+            })
+        })
+        .collect()
+}
+
+fn parse_query(doc_mapper: &dyn DocMapper, elastic_query_json: &str) -> QueryAst {
+    let elastic_query_dsl: ElasticQueryDsl = serde_json::from_str(elastic_query_json).unwrap();
+    let query_ast: QueryAst = elastic_query_dsl.try_into().unwrap();
+    query_ast
+        .parse_user_query(doc_mapper.default_search_fields())
+        .unwrap()
+}
+
+fn query_path_benchmark(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let test_sandbox = runtime.block_on(async {
+        let test_sandbox = TestSandbox::create(INDEX_ID, DOC_MAPPING_YAML, "{}", &["body"])
+            .await
+            .unwrap();
+        test_sandbox.add_documents(generate_docs()).await.unwrap();
+        test_sandbox
+    });
+    let doc_mapper: Arc<dyn DocMapper> = test_sandbox.doc_mapper();
+    let schema = doc_mapper.schema();
+
+    let mut group = c.benchmark_group("query-ast-parsing");
+    for (query_name, elastic_query_json) in QUERIES {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(query_name),
+            elastic_query_json,
+            |b, elastic_query_json| {
+                b.iter(|| parse_query(&*doc_mapper, black_box(elastic_query_json)))
+            },
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("query-compilation");
+    for (query_name, elastic_query_json) in QUERIES {
+        let query_ast = parse_query(&*doc_mapper, elastic_query_json);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(query_name),
+            &query_ast,
+            |b, query_ast| {
+                b.iter(|| {
+                    query_ast
+                        .build_tantivy_query(&schema, &[], true, true)
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+
+    // `DocMapper::query` compiles the query and computes its warmup info.
+    let mut group = c.benchmark_group("query-compilation-with-warmup-info");
+    for (query_name, elastic_query_json) in QUERIES {
+        let query_ast = parse_query(&*doc_mapper, elastic_query_json);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(query_name),
+            &query_ast,
+            |b, query_ast| b.iter(|| doc_mapper.query(schema.clone(), query_ast, true).unwrap()),
+        );
+    }
+    group.finish();
+
+    let metastore = test_sandbox.metastore();
+    let storage_resolver = test_sandbox.storage_resolver();
+    let mut group = c.benchmark_group("leaf-execution");
+    for (query_name, elastic_query_json) in QUERIES {
+        let query_ast = parse_query(&*doc_mapper, elastic_query_json);
+        let search_request = SearchRequest {
+            index_id: INDEX_ID.to_string(),
+            query_ast: query_ast_to_json(&query_ast).unwrap(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        group.bench_with_input(
+            BenchmarkId::from_parameter(query_name),
+            &search_request,
+            |b, search_request| {
+                b.to_async(&runtime).iter(|| {
+                    let search_request = search_request.clone();
+                    let metastore = metastore.clone();
+                    let storage_resolver = storage_resolver.clone();
+                    async move {
+                        single_node_search(search_request, &*metastore, storage_resolver)
+                            .await
+                            .unwrap()
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+
+    runtime.block_on(test_sandbox.assert_quit());
+}
+
+criterion_group!(benches, query_path_benchmark);
+criterion_main!(benches);
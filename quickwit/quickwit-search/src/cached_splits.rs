@@ -0,0 +1,112 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use quickwit_cluster::{CachedSplitsDigest, Cluster};
+use tracing::debug;
+
+use crate::service::SearcherContext;
+
+const CACHED_SPLITS_DIGEST_UPDATE_INTERVAL: Duration = if cfg!(test) {
+    Duration::from_millis(50)
+} else {
+    Duration::from_secs(10)
+};
+
+/// Periodically advertises the splits whose footer is cached by the searcher to the other nodes
+/// of the cluster. The root nodes rely on it to assign the search jobs targeting these splits to
+/// the searcher. The task stops once the searcher context is dropped.
+pub(crate) fn spawn_cached_splits_digest_task(
+    cluster: Cluster,
+    searcher_context: &Arc<SearcherContext>,
+) {
+    let searcher_context_weak: Weak<SearcherContext> = Arc::downgrade(searcher_context);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CACHED_SPLITS_DIGEST_UPDATE_INTERVAL);
+        let mut advertised_split_ids: Option<Vec<String>> = None;
+        loop {
+            interval.tick().await;
+            let Some(searcher_context) = searcher_context_weak.upgrade() else {
+                return;
+            };
+            let mut split_ids = searcher_context.split_footer_cache.split_ids();
+            drop(searcher_context);
+            split_ids.sort_unstable();
+
+            if advertised_split_ids.as_ref() == Some(&split_ids) {
+                continue;
+            }
+            debug!(
+                num_cached_splits = split_ids.len(),
+                "Advertising cached splits digest."
+            );
+            let cached_splits_digest = CachedSplitsDigest::from_split_ids(&split_ids);
+            cluster
+                .update_self_node_cached_splits_digest(&cached_splits_digest)
+                .await;
+            advertised_split_ids = Some(split_ids);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+    use quickwit_config::SearcherConfig;
+    use quickwit_storage::OwnedBytes;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cached_splits_digest_task() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default()));
+        searcher_context
+            .split_footer_cache
+            .put(
+                "index",
+                "split-1".to_string(),
+                OwnedBytes::new(vec![0u8; 10]),
+            )
+            .await;
+        spawn_cached_splits_digest_task(cluster.clone(), &searcher_context);
+
+        cluster
+            .wait_for_ready_members(
+                |members| {
+                    members.iter().any(|member| {
+                        member
+                            .cached_splits_digest
+                            .as_ref()
+                            .map_or(false, |digest| digest.may_contain("split-1"))
+                    })
+                },
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+    }
+}
@@ -25,6 +25,9 @@ use std::time::Duration;
 use async_trait::async_trait;
 use futures::{StreamExt, TryStreamExt};
 use http::Uri;
+use quickwit_cluster::{CachedSplitsDigest, ProtocolFeature, PROTOCOL_VERSION};
+#[cfg(any(test, feature = "testsuite"))]
+use quickwit_common::fault_injection::{truncated_len, FaultInjectionConfig, FaultInjector};
 use quickwit_config::service::QuickwitService;
 use quickwit_grpc_clients::service_client_pool::ServiceClient;
 use quickwit_proto::tonic::codegen::InterceptedService;
@@ -37,6 +40,8 @@ use tower::timeout::Timeout;
 use tracing::*;
 
 use crate::error::parse_grpc_error;
+#[cfg(any(test, feature = "testsuite"))]
+use crate::SearchError;
 use crate::SearchService;
 
 /// Impl is an enumeration that meant to manage Quickwit's search service client types.
@@ -57,6 +62,10 @@ enum SearchServiceClientImpl {
 pub struct SearchServiceClient {
     client_impl: SearchServiceClientImpl,
     grpc_addr: SocketAddr,
+    protocol_version: u32,
+    cached_splits_digest_opt: Option<Arc<CachedSplitsDigest>>,
+    #[cfg(any(test, feature = "testsuite"))]
+    fault_injector_opt: Option<Arc<FaultInjector>>,
 }
 
 impl fmt::Debug for SearchServiceClient {
@@ -98,6 +107,10 @@ impl SearchServiceClient {
         SearchServiceClient {
             client_impl: SearchServiceClientImpl::Grpc(client),
             grpc_addr,
+            protocol_version: PROTOCOL_VERSION,
+            cached_splits_digest_opt: None,
+            #[cfg(any(test, feature = "testsuite"))]
+            fault_injector_opt: None,
         }
     }
 
@@ -106,6 +119,10 @@ impl SearchServiceClient {
         SearchServiceClient {
             client_impl: SearchServiceClientImpl::Local(service),
             grpc_addr,
+            protocol_version: PROTOCOL_VERSION,
+            cached_splits_digest_opt: None,
+            #[cfg(any(test, feature = "testsuite"))]
+            fault_injector_opt: None,
         }
     }
 
@@ -114,28 +131,96 @@ impl SearchServiceClient {
         self.grpc_addr
     }
 
+    /// Sets the version of the protocol spoken by the node to which the client connects. Clients
+    /// are assumed to connect to nodes speaking the same protocol version as this node by default.
+    pub fn with_protocol_version(mut self, protocol_version: u32) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    /// Returns the version of the protocol spoken by the node to which the client connects.
+    pub fn protocol_version(&self) -> u32 {
+        self.protocol_version
+    }
+
+    /// Sets the digest of the splits cached by the node to which the client connects, as
+    /// advertised in the cluster state.
+    pub fn with_cached_splits_digest(
+        mut self,
+        cached_splits_digest_opt: Option<Arc<CachedSplitsDigest>>,
+    ) -> Self {
+        self.cached_splits_digest_opt = cached_splits_digest_opt;
+        self
+    }
+
+    /// Returns whether the node to which the client connects may have the split in cache.
+    /// Returns `false` if the node has not advertised its cached splits.
+    pub fn may_have_cached_split(&self, split_id: &str) -> bool {
+        self.cached_splits_digest_opt
+            .as_ref()
+            .map_or(false, |cached_splits_digest| {
+                cached_splits_digest.may_contain(split_id)
+            })
+    }
+
+    /// Returns whether the node to which the client connects supports all the `features`.
+    pub fn supports_features(&self, features: &[ProtocolFeature]) -> bool {
+        features
+            .iter()
+            .all(|feature| feature.is_supported_by(self.protocol_version))
+    }
+
     /// Returns whether the underlying client is local or remote.
     #[cfg(any(test, feature = "testsuite"))]
     pub fn is_local(&self) -> bool {
         matches!(self.client_impl, SearchServiceClientImpl::Local(_))
     }
 
+    /// Makes the client inject faults in its calls: latency, errors, and truncated responses.
+    /// Clones of the client share the same fault injector.
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn with_fault_injection(mut self, config: FaultInjectionConfig) -> Self {
+        self.fault_injector_opt = Some(Arc::new(FaultInjector::new(config)));
+        self
+    }
+
+    /// Injects the latency and error of the fault drawn for the next call, if any.
+    /// Returns whether the response of the call should be truncated.
+    async fn inject_fault(&self) -> crate::Result<bool> {
+        #[cfg(any(test, feature = "testsuite"))]
+        if let Some(fault_injector) = &self.fault_injector_opt {
+            let fault = fault_injector.next_fault();
+            fault.inject_latency().await;
+
+            if fault.is_error {
+                return Err(SearchError::InternalError(format!(
+                    "Injected fault on searcher `{}`.",
+                    self.grpc_addr
+                )));
+            }
+            return Ok(fault.is_truncation);
+        }
+        Ok(false)
+    }
+
     /// Perform root search.
     pub async fn root_search(
         &mut self,
         request: quickwit_proto::SearchRequest,
     ) -> crate::Result<quickwit_proto::SearchResponse> {
-        match &mut self.client_impl {
+        let is_truncation = self.inject_fault().await?;
+        let response = match &mut self.client_impl {
             SearchServiceClientImpl::Grpc(grpc_client) => {
                 let tonic_request = Request::new(request);
                 let tonic_response = grpc_client
                     .root_search(tonic_request)
                     .await
                     .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
-                Ok(tonic_response.into_inner())
+                tonic_response.into_inner()
             }
-            SearchServiceClientImpl::Local(service) => service.root_search(request).await,
-        }
+            SearchServiceClientImpl::Local(service) => service.root_search(request).await?,
+        };
+        Ok(truncate_response(response, is_truncation))
     }
 
     /// Perform leaf search.
@@ -143,17 +228,19 @@ impl SearchServiceClient {
         &mut self,
         request: quickwit_proto::LeafSearchRequest,
     ) -> crate::Result<quickwit_proto::LeafSearchResponse> {
-        match &mut self.client_impl {
+        let is_truncation = self.inject_fault().await?;
+        let response = match &mut self.client_impl {
             SearchServiceClientImpl::Grpc(grpc_client) => {
                 let tonic_request = Request::new(request);
                 let tonic_response = grpc_client
                     .leaf_search(tonic_request)
                     .await
                     .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
-                Ok(tonic_response.into_inner())
+                tonic_response.into_inner()
             }
-            SearchServiceClientImpl::Local(service) => service.leaf_search(request).await,
-        }
+            SearchServiceClientImpl::Local(service) => service.leaf_search(request).await?,
+        };
+        Ok(truncate_response(response, is_truncation))
     }
 
     /// Perform leaf stream.
@@ -161,6 +248,13 @@ impl SearchServiceClient {
         &mut self,
         request: quickwit_proto::LeafSearchStreamRequest,
     ) -> UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>> {
+        // Streamed responses are never truncated.
+        if let Err(error) = self.inject_fault().await {
+            let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
+            // Receiver cannot be closed here, ignore error.
+            let _ = result_sender.send(Err(error));
+            return UnboundedReceiverStream::new(result_receiver);
+        }
         match &mut self.client_impl {
             SearchServiceClientImpl::Grpc(grpc_client) => {
                 let mut grpc_client_clone = grpc_client.clone();
@@ -215,17 +309,19 @@ impl SearchServiceClient {
         &mut self,
         request: quickwit_proto::FetchDocsRequest,
     ) -> crate::Result<quickwit_proto::FetchDocsResponse> {
-        match &mut self.client_impl {
+        let is_truncation = self.inject_fault().await?;
+        let response = match &mut self.client_impl {
             SearchServiceClientImpl::Grpc(grpc_client) => {
                 let tonic_request = Request::new(request);
                 let tonic_response = grpc_client
                     .fetch_docs(tonic_request)
                     .await
                     .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
-                Ok(tonic_response.into_inner())
+                tonic_response.into_inner()
             }
-            SearchServiceClientImpl::Local(service) => service.fetch_docs(request).await,
-        }
+            SearchServiceClientImpl::Local(service) => service.fetch_docs(request).await?,
+        };
+        Ok(truncate_response(response, is_truncation))
     }
 
     /// Perform leaf list terms.
@@ -233,20 +329,121 @@ impl SearchServiceClient {
         &mut self,
         request: quickwit_proto::LeafListTermsRequest,
     ) -> crate::Result<quickwit_proto::LeafListTermsResponse> {
-        match &mut self.client_impl {
+        let is_truncation = self.inject_fault().await?;
+        let response = match &mut self.client_impl {
             SearchServiceClientImpl::Grpc(grpc_client) => {
                 let tonic_request = Request::new(request);
                 let tonic_response = grpc_client
                     .leaf_list_terms(tonic_request)
                     .await
                     .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
-                Ok(tonic_response.into_inner())
+                tonic_response.into_inner()
             }
-            SearchServiceClientImpl::Local(service) => service.leaf_list_terms(request).await,
-        }
+            SearchServiceClientImpl::Local(service) => service.leaf_list_terms(request).await?,
+        };
+        Ok(truncate_response(response, is_truncation))
+    }
+
+    /// Perform split warmup.
+    pub async fn warmup_splits(
+        &mut self,
+        request: quickwit_proto::WarmupSplitsRequest,
+    ) -> crate::Result<quickwit_proto::WarmupSplitsResponse> {
+        // Warmup responses only hold a count, so they are never truncated.
+        self.inject_fault().await?;
+        let response = match &mut self.client_impl {
+            SearchServiceClientImpl::Grpc(grpc_client) => {
+                let tonic_request = Request::new(request);
+                let tonic_response = grpc_client
+                    .warmup_splits(tonic_request)
+                    .await
+                    .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+                tonic_response.into_inner()
+            }
+            SearchServiceClientImpl::Local(service) => service.warmup_splits(request).await?,
+        };
+        Ok(response)
+    }
+
+    /// Fetches a split footer from the split footer cache of the searcher.
+    pub async fn fetch_cached_split_footer(
+        &mut self,
+        request: quickwit_proto::FetchCachedSplitFooterRequest,
+    ) -> crate::Result<quickwit_proto::FetchCachedSplitFooterResponse> {
+        let is_truncation = self.inject_fault().await?;
+        let response = match &mut self.client_impl {
+            SearchServiceClientImpl::Grpc(grpc_client) => {
+                let tonic_request = Request::new(request);
+                let tonic_response = grpc_client
+                    .fetch_cached_split_footer(tonic_request)
+                    .await
+                    .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+                tonic_response.into_inner()
+            }
+            SearchServiceClientImpl::Local(service) => {
+                service.fetch_cached_split_footer(request).await?
+            }
+        };
+        Ok(truncate_response(response, is_truncation))
+    }
+}
+
+/// Responses that lose part of their items when a truncation fault is injected.
+#[cfg(any(test, feature = "testsuite"))]
+trait TruncateResponse {
+    fn truncate(&mut self);
+}
+
+#[cfg(any(test, feature = "testsuite"))]
+impl TruncateResponse for quickwit_proto::SearchResponse {
+    fn truncate(&mut self) {
+        self.hits.truncate(truncated_len(self.hits.len()));
+    }
+}
+
+#[cfg(any(test, feature = "testsuite"))]
+impl TruncateResponse for quickwit_proto::LeafSearchResponse {
+    fn truncate(&mut self) {
+        self.partial_hits
+            .truncate(truncated_len(self.partial_hits.len()));
+    }
+}
+
+#[cfg(any(test, feature = "testsuite"))]
+impl TruncateResponse for quickwit_proto::FetchDocsResponse {
+    fn truncate(&mut self) {
+        self.hits.truncate(truncated_len(self.hits.len()));
     }
 }
 
+#[cfg(any(test, feature = "testsuite"))]
+impl TruncateResponse for quickwit_proto::LeafListTermsResponse {
+    fn truncate(&mut self) {
+        self.terms.truncate(truncated_len(self.terms.len()));
+    }
+}
+
+#[cfg(any(test, feature = "testsuite"))]
+impl TruncateResponse for quickwit_proto::FetchCachedSplitFooterResponse {
+    fn truncate(&mut self) {
+        self.split_footer
+            .truncate(truncated_len(self.split_footer.len()));
+    }
+}
+
+#[cfg(any(test, feature = "testsuite"))]
+fn truncate_response<T: TruncateResponse>(mut response: T, is_truncation: bool) -> T {
+    if is_truncation {
+        response.truncate();
+    }
+    response
+}
+
+#[cfg(not(any(test, feature = "testsuite")))]
+fn truncate_response<T>(response: T, _is_truncation: bool) -> T {
+    response
+}
+
 /// Creates a [`SearchServiceClient`] from a socket address.
 /// The underlying channel connects lazily and is set up to time out after 5 seconds. It reconnects
 /// automatically should the connection be dropped.
@@ -277,3 +474,56 @@ pub fn create_search_client_from_channel(
     );
     SearchServiceClient::from_grpc_client(client, grpc_addr)
 }
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::{LeafSearchRequest, LeafSearchResponse, PartialHit};
+
+    use super::*;
+    use crate::MockSearchService;
+
+    #[tokio::test]
+    async fn test_search_client_fault_injection_truncates_responses() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_leaf_search()
+            .returning(|_: LeafSearchRequest| {
+                Ok(LeafSearchResponse {
+                    num_hits: 4,
+                    partial_hits: vec![PartialHit::default(); 4],
+                    ..Default::default()
+                })
+            });
+        let grpc_addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let mut client =
+            SearchServiceClient::from_service(Arc::new(mock_search_service), grpc_addr)
+                .with_fault_injection(FaultInjectionConfig {
+                    truncation_rate: 1.0,
+                    ..Default::default()
+                });
+        let leaf_search_response = client
+            .leaf_search(LeafSearchRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(leaf_search_response.num_hits, 4);
+        assert_eq!(leaf_search_response.partial_hits.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_client_fault_injection_errors() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().never();
+        let grpc_addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let mut client =
+            SearchServiceClient::from_service(Arc::new(mock_search_service), grpc_addr)
+                .with_fault_injection(FaultInjectionConfig {
+                    error_rate: 1.0,
+                    ..Default::default()
+                });
+        let search_error = client
+            .leaf_search(LeafSearchRequest::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(search_error, SearchError::InternalError(_)));
+    }
+}
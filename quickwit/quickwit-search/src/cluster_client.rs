@@ -21,6 +21,7 @@ use futures::StreamExt;
 use quickwit_proto::{
     FetchDocsRequest, FetchDocsResponse, LeafListTermsRequest, LeafListTermsResponse,
     LeafSearchRequest, LeafSearchResponse, LeafSearchStreamRequest, LeafSearchStreamResponse,
+    SortOrder,
 };
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tokio::sync::mpsc::error::SendError;
@@ -28,6 +29,7 @@ use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::debug;
 
+use crate::collector::{merge_sorted_partial_hits, sort_by_from_request};
 use crate::retry::search::LeafSearchRetryPolicy;
 use crate::retry::search_stream::{LeafSearchStreamRetryPolicy, SuccessfulSplitIds};
 use crate::retry::{retry_client, DefaultRetryPolicy, RetryPolicy};
@@ -78,6 +80,11 @@ impl ClusterClient {
         request: LeafSearchRequest,
         mut client: SearchServiceClient,
     ) -> crate::Result<LeafSearchResponse> {
+        let sort_order = request
+            .search_request
+            .as_ref()
+            .map(|search_request| sort_by_from_request(search_request).sort_order())
+            .unwrap_or(SortOrder::Desc);
         let mut response_res = client.leaf_search(request.clone()).await;
         let retry_policy = LeafSearchRetryPolicy {};
         if let Some(retry_request) = retry_policy.retry_request(request, &response_res) {
@@ -93,7 +100,7 @@ impl ClusterClient {
                 response_res, retry_request, client
             );
             let retry_result = client.leaf_search(retry_request).await;
-            response_res = merge_leaf_search_results(response_res, retry_result);
+            response_res = merge_leaf_search_results(response_res, retry_result, sort_order);
         }
         response_res
     }
@@ -160,15 +167,23 @@ impl ClusterClient {
 }
 
 // Merge initial leaf search results with results obtained from a retry.
+// The hits of both responses are sorted according to `sort_order` and so are the merged hits.
 fn merge_leaf_search_results(
     initial_response_result: crate::Result<LeafSearchResponse>,
     retry_response_result: crate::Result<LeafSearchResponse>,
+    sort_order: SortOrder,
 ) -> crate::Result<LeafSearchResponse> {
     match (initial_response_result, retry_response_result) {
         (Ok(mut initial_response), Ok(mut retry_response)) => {
+            let partial_hits = merge_sorted_partial_hits(
+                [initial_response.partial_hits, retry_response.partial_hits].into_iter(),
+                sort_order,
+                0,
+                usize::MAX,
+            );
             initial_response
-                .partial_hits
-                .append(&mut retry_response.partial_hits);
+                .split_profiles
+                .append(&mut retry_response.split_profiles);
             let intermediate_aggregation_result = initial_response
                 .intermediate_aggregation_result
                 .map::<crate::Result<_>, _>(|res1_bytes| {
@@ -192,7 +207,8 @@ fn merge_leaf_search_results(
                 num_attempted_splits: initial_response.num_attempted_splits
                     + retry_response.num_attempted_splits,
                 failed_splits: retry_response.failed_splits,
-                partial_hits: initial_response.partial_hits,
+                partial_hits,
+                split_profiles: initial_response.split_profiles,
             };
             Ok(merged_response)
         }
@@ -233,6 +249,7 @@ mod tests {
     use std::collections::HashSet;
     use std::net::SocketAddr;
 
+    use quickwit_common::fault_injection::FaultInjectionConfig;
     use quickwit_proto::{
         qast_helper, PartialHit, SearchRequest, SearchStreamRequest, SortValue,
         SplitIdAndFooterOffsets, SplitSearchError,
@@ -262,6 +279,7 @@ mod tests {
                 split_footer_start: 0,
                 timestamp_start: None,
                 timestamp_end: None,
+                create_timestamp: None,
             }],
             ..Default::default()
         }
@@ -278,6 +296,7 @@ mod tests {
             search_request: Some(search_request),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            bypass_caches: false,
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
@@ -285,6 +304,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    create_timestamp: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -292,6 +312,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    create_timestamp: None,
                 },
             ],
         }
@@ -319,6 +340,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    create_timestamp: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -326,6 +348,7 @@ mod tests {
                     split_footer_end: 100,
                     timestamp_start: None,
                     timestamp_end: None,
+                    create_timestamp: None,
                 },
             ],
         }
@@ -384,6 +407,39 @@ mod tests {
         assert_eq!(fetch_docs_response.hits.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_cluster_client_fetch_docs_retry_on_injected_fault() {
+        let request = mock_doc_request("split_1");
+        let mut mock_search_service_1 = MockSearchService::new();
+        mock_search_service_1.expect_fetch_docs().never();
+        let mut mock_search_service_2 = MockSearchService::new();
+        mock_search_service_2.expect_fetch_docs().return_once(
+            |_: quickwit_proto::FetchDocsRequest| {
+                Ok(quickwit_proto::FetchDocsResponse { hits: Vec::new() })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", mock_search_service_1),
+            ("127.0.0.1:1002", mock_search_service_2),
+        ]);
+        let first_client_addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let first_client = searcher_pool
+            .get(&first_client_addr)
+            .await
+            .unwrap()
+            .with_fault_injection(FaultInjectionConfig {
+                error_rate: 1.0,
+                ..Default::default()
+            });
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer);
+        let fetch_docs_response = cluster_client
+            .fetch_docs(request, first_client)
+            .await
+            .unwrap();
+        assert_eq!(fetch_docs_response.hits.len(), 0);
+    }
+
     #[tokio::test]
     async fn test_cluster_client_fetch_docs_retry_with_final_error() {
         let request = mock_doc_request("split_1");
@@ -500,7 +556,8 @@ mod tests {
             ..Default::default()
         };
         let merged_leaf_search_response =
-            merge_leaf_search_results(Ok(leaf_response), Ok(leaf_response_retry)).unwrap();
+            merge_leaf_search_results(Ok(leaf_response), Ok(leaf_response_retry), SortOrder::Desc)
+                .unwrap();
         assert_eq!(merged_leaf_search_response.num_attempted_splits, 2);
         assert_eq!(merged_leaf_search_response.num_hits, 2);
         assert_eq!(merged_leaf_search_response.partial_hits.len(), 2);
@@ -525,6 +582,7 @@ mod tests {
         let merged_result = merge_leaf_search_results(
             Err(SearchError::InternalError("error".to_string())),
             Ok(leaf_response),
+            SortOrder::Desc,
         )
         .unwrap();
         assert_eq!(merged_result.num_attempted_splits, 1);
@@ -539,6 +597,7 @@ mod tests {
         let merge_error = merge_leaf_search_results(
             Err(SearchError::InternalError("error".to_string())),
             Err(SearchError::InternalError("retry error".to_string())),
+            SortOrder::Desc,
         )
         .unwrap_err();
         assert_eq!(merge_error.to_string(), "Internal error: `error`.");
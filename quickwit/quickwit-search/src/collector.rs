@@ -19,9 +19,9 @@
 
 use std::cmp::{Ordering, Reverse};
 use std::collections::{BinaryHeap, HashSet};
+use std::mem;
 
 use itertools::Itertools;
-use quickwit_common::binary_heap::top_k;
 use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 use quickwit_proto::{LeafSearchResponse, PartialHit, SearchRequest, SortOrder, SortValue};
 use serde::Deserialize;
@@ -31,11 +31,13 @@ use tantivy::aggregation::{AggregationLimits, AggregationSegmentCollector};
 use tantivy::collector::{Collector, SegmentCollector};
 use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
 use tantivy::fastfield::Column;
+use tantivy::query::Weight;
 use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use tracing::warn;
 
 use crate::filters::{create_timestamp_filter_builder, TimestampFilter, TimestampFilterBuilder};
 use crate::find_trace_ids_collector::{FindTraceIdsCollector, FindTraceIdsSegmentCollector};
-use crate::GlobalDocAddress;
+use crate::sessions_collector::{SessionsCollector, SessionsSegmentCollector};
 
 #[derive(Clone, Debug)]
 pub(crate) enum SortBy {
@@ -84,6 +86,14 @@ enum SortingFieldComputer {
 }
 
 impl SortingFieldComputer {
+    fn sort_order(&self) -> SortOrder {
+        match self {
+            SortingFieldComputer::DocId => SortOrder::Desc,
+            SortingFieldComputer::FastField { order, .. } => *order,
+            SortingFieldComputer::Score { order } => *order,
+        }
+    }
+
     fn recover_typed_sort_value(&self, sort_value: u64) -> SortValue {
         match self {
             SortingFieldComputer::DocId => SortValue::U64(sort_value),
@@ -234,6 +244,7 @@ impl Eq for PartialHitHeapItem {}
 
 enum AggregationSegmentCollectors {
     FindTraceIdsSegmentCollector(Box<FindTraceIdsSegmentCollector>),
+    SessionsSegmentCollector(Box<SessionsSegmentCollector>),
     TantivyAggregationSegmentCollector(AggregationSegmentCollector),
 }
 
@@ -283,6 +294,20 @@ impl QuickwitSegmentCollector {
         }
     }
 
+    /// Returns the score a document must exceed to enter the top hits of the segment, when
+    /// collecting hits sorted by descending score.
+    #[inline]
+    fn score_threshold(&self) -> Score {
+        if !self.at_capacity() {
+            return Score::MIN;
+        }
+        self.hits
+            .peek()
+            .and_then(|head| head.sort_value_opt)
+            .map(|sort_value| f64::from_u64(sort_value) as Score)
+            .unwrap_or(Score::MIN)
+    }
+
     #[inline]
     fn accept_document(&self, doc_id: DocId) -> bool {
         if let Some(ref timestamp_filter) = self.timestamp_filter_opt {
@@ -308,6 +333,9 @@ impl SegmentCollector for QuickwitSegmentCollector {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
                 collector.collect(doc_id, score)
             }
+            Some(AggregationSegmentCollectors::SessionsSegmentCollector(collector)) => {
+                collector.collect(doc_id, score)
+            }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
                 collector.collect(doc_id, score)
             }
@@ -320,7 +348,7 @@ impl SegmentCollector for QuickwitSegmentCollector {
         // TODO use into_iter_sorted() once it gets stable.
         let split_id = self.split_id;
         let sort_by = self.sort_by;
-        let partial_hits: Vec<PartialHit> = self
+        let mut partial_hits: Vec<PartialHit> = self
             .hits
             .into_sorted_vec()
             .into_iter()
@@ -333,6 +361,10 @@ impl SegmentCollector for QuickwitSegmentCollector {
                 split_id: split_id.clone(),
             })
             .collect();
+        // The heap breaks ties by ascending doc id regardless of the sort order, so the hits are
+        // reordered the way `merge_sorted_partial_hits` expects them.
+        let sort_order = sort_by.sort_order();
+        partial_hits.sort_unstable_by(|left, right| cmp_partial_hits(left, right, sort_order));
 
         let intermediate_aggregation_result = match self.aggregation {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
@@ -341,6 +373,12 @@ impl SegmentCollector for QuickwitSegmentCollector {
                     postcard::to_allocvec(&fruit).expect("Collector fruit should be serializable.");
                 Some(serialized)
             }
+            Some(AggregationSegmentCollectors::SessionsSegmentCollector(collector)) => {
+                let fruit = collector.harvest();
+                let serialized =
+                    postcard::to_allocvec(&fruit).expect("Collector fruit should be serializable.");
+                Some(serialized)
+            }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
                 let serialized = postcard::to_allocvec(&collector.harvest()?)
                     .expect("Collector fruit should be serializable.");
@@ -354,6 +392,7 @@ impl SegmentCollector for QuickwitSegmentCollector {
             partial_hits,
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
+            split_profiles: Vec::new(),
         })
     }
 }
@@ -365,6 +404,8 @@ pub enum QuickwitAggregations {
     /// Aggregation used by the Jaeger service to find trace IDs that match a
     /// [`quickwit_proto::jaeger::storage::v1::FindTraceIDsRequest`].
     FindTraceIdsAggregation(FindTraceIdsCollector),
+    /// Aggregation grouping events by key into sessions separated by an inactivity gap.
+    SessionsAggregation(SessionsCollector),
     /// Your classic Tantivy aggregation.
     TantivyAggregations(Aggregations),
 }
@@ -375,6 +416,7 @@ impl QuickwitAggregations {
             QuickwitAggregations::FindTraceIdsAggregation(collector) => {
                 collector.fast_field_names()
             }
+            QuickwitAggregations::SessionsAggregation(collector) => collector.fast_field_names(),
             QuickwitAggregations::TantivyAggregations(aggregations) => {
                 get_fast_field_names(aggregations)
             }
@@ -395,6 +437,7 @@ pub(crate) struct QuickwitCollector {
     timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
     pub aggregation: Option<QuickwitAggregations>,
     pub aggregation_limits: AggregationLimits,
+    pub track_total_hits: bool,
 }
 
 impl QuickwitCollector {
@@ -415,6 +458,36 @@ impl QuickwitCollector {
         fast_field_names
     }
 
+    /// Returns true if the documents that cannot make it into the top hits can be skipped
+    /// altogether, using the block-max information stored in the postings lists
+    /// (block-max WAND).
+    ///
+    /// This is only possible if the hits are sorted by descending score, no aggregation
+    /// needs to see every matching document, and the number of hits is not required to be
+    /// exact.
+    fn can_prune_by_score(&self) -> bool {
+        !self.track_total_hits
+            && self.max_hits + self.start_offset > 0
+            && self.aggregation.is_none()
+            && matches!(
+                self.sort_by,
+                SortBy::Score {
+                    order: SortOrder::Desc
+                }
+            )
+    }
+
+    /// Returns the intermediate aggregation result of a split whose documents cannot be collected
+    /// by the aggregation. It is harvested from a segment collector that did not collect any
+    /// document, so that the split does not need to be searched.
+    pub fn empty_intermediate_aggregation_result(
+        &self,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Option<Vec<u8>>> {
+        let segment_collector = self.for_segment(0, segment_reader)?;
+        Ok(segment_collector.harvest()?.intermediate_aggregation_result)
+    }
+
     pub fn warmup_info(&self) -> WarmupInfo {
         WarmupInfo {
             fast_field_names: self.fast_field_names(),
@@ -448,6 +521,11 @@ impl Collector for QuickwitCollector {
                     Box::new(collector.for_segment(0, segment_reader)?),
                 ))
             }
+            Some(QuickwitAggregations::SessionsAggregation(collector)) => {
+                Some(AggregationSegmentCollectors::SessionsSegmentCollector(
+                    Box::new(collector.for_segment(0, segment_reader)?),
+                ))
+            }
             Some(QuickwitAggregations::TantivyAggregations(aggs)) => Some(
                 AggregationSegmentCollectors::TantivyAggregationSegmentCollector(
                     AggregationSegmentCollector::from_agg_req_and_reader(
@@ -471,6 +549,45 @@ impl Collector for QuickwitCollector {
         })
     }
 
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<tantivy::Result<LeafSearchResponse>> {
+        let mut segment_collector = self.for_segment(segment_ord, segment_reader)?;
+        let alive_bitset_opt = segment_reader.alive_bitset();
+        let is_alive = |doc_id: DocId| {
+            alive_bitset_opt.map_or(true, |alive_bitset| alive_bitset.is_alive(doc_id))
+        };
+        if self.can_prune_by_score() {
+            // The callback is only called for the documents scoring above the threshold it
+            // returns, so `num_hits` ends up being a lower bound of the number of matching
+            // documents.
+            weight.for_each_pruning(Score::MIN, segment_reader, &mut |doc_id, score| {
+                if is_alive(doc_id) {
+                    segment_collector.collect(doc_id, score);
+                }
+                segment_collector.score_threshold()
+            })?;
+        } else if self.requires_scoring() {
+            weight.for_each(segment_reader, &mut |doc_id, score| {
+                if is_alive(doc_id) {
+                    segment_collector.collect(doc_id, score);
+                }
+            })?;
+        } else {
+            weight.for_each_no_score(segment_reader, &mut |doc_ids| {
+                for &doc_id in doc_ids {
+                    if is_alive(doc_id) {
+                        segment_collector.collect(doc_id, 0.0);
+                    }
+                }
+            })?;
+        }
+        Ok(segment_collector.harvest())
+    }
+
     fn requires_scoring(&self) -> bool {
         // We do not need BM25 scoring in Quickwit if it is not opted-in.
         // By returning false, we inform tantivy that it does not need to decompress
@@ -488,22 +605,15 @@ impl Collector for QuickwitCollector {
         let segment_fruits: tantivy::Result<Vec<LeafSearchResponse>> =
             segment_fruits.into_iter().collect();
         // We want the hits in [start_offset..start_offset + max_hits).
-        // All leaves will return their top [0..max_hits) documents.
-        // We compute the overall [0..start_offset + max_hits) documents ...
-        let num_hits = self.start_offset + self.max_hits;
+        // All leaves will return their top [0..start_offset + max_hits) documents.
         let sort_order = self.sort_by.sort_order();
-        let mut merged_leaf_response =
-            merge_leaf_responses(&self.aggregation, segment_fruits?, sort_order, num_hits)?;
-        // ... and drop the first [..start_offsets) hits.
-        merged_leaf_response
-            .partial_hits
-            .drain(
-                0..self
-                    .start_offset
-                    .min(merged_leaf_response.partial_hits.len()),
-            )
-            .count(); //< we just use count as a way to consume the entire iterator.
-        Ok(merged_leaf_response)
+        merge_leaf_responses(
+            &self.aggregation,
+            segment_fruits?,
+            sort_order,
+            self.start_offset,
+            self.max_hits,
+        )
     }
 }
 
@@ -511,16 +621,22 @@ fn map_error(err: postcard::Error) -> TantivyError {
     TantivyError::InternalError(format!("Merge Result Postcard Error: {err}"))
 }
 
-/// Merges a set of Leaf Results.
+/// Merges a set of Leaf Results and keeps the hits in [start_offset..start_offset + max_hits).
 fn merge_leaf_responses(
     aggregations_opt: &Option<QuickwitAggregations>,
     mut leaf_responses: Vec<LeafSearchResponse>,
     sort_order: SortOrder,
+    start_offset: usize,
     max_hits: usize,
 ) -> tantivy::Result<LeafSearchResponse> {
     // Optimization: No merging needed if there is only one result.
     if leaf_responses.len() == 1 {
-        return Ok(leaf_responses.pop().unwrap());
+        let mut leaf_response = leaf_responses.pop().unwrap();
+        leaf_response
+            .partial_hits
+            .drain(0..start_offset.min(leaf_response.partial_hits.len()))
+            .count(); //< we just use count as a way to consume the entire iterator.
+        return Ok(leaf_response);
     }
     let merged_intermediate_aggregation_result = match aggregations_opt {
         Some(QuickwitAggregations::FindTraceIdsAggregation(collector)) => {
@@ -541,6 +657,23 @@ fn merge_leaf_responses(
             let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
             Some(serialized)
         }
+        Some(QuickwitAggregations::SessionsAggregation(collector)) => {
+            let fruits: Vec<<<SessionsCollector as Collector>::Child as SegmentCollector>::Fruit> =
+                leaf_responses
+                    .iter()
+                    .filter_map(|leaf_response| {
+                        leaf_response.intermediate_aggregation_result.as_ref().map(
+                            |intermediate_aggregation_result| {
+                                postcard::from_bytes(intermediate_aggregation_result.as_slice())
+                                    .map_err(map_error)
+                            },
+                        )
+                    })
+                    .collect::<Result<_, _>>()?;
+            let merged_fruit = collector.merge_fruits(fruits)?;
+            let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
+            Some(serialized)
+        }
         Some(QuickwitAggregations::TantivyAggregations(_)) => {
             let fruits: Vec<IntermediateAggregationResults> = leaf_responses
                 .iter()
@@ -582,46 +715,132 @@ fn merge_leaf_responses(
         .flat_map(|leaf_response| leaf_response.failed_splits.iter())
         .cloned()
         .collect_vec();
-    let all_partial_hits: Vec<PartialHit> = leaf_responses
-        .into_iter()
-        .flat_map(|leaf_response| leaf_response.partial_hits)
-        .collect();
-    let top_k_partial_hits: Vec<PartialHit> =
-        top_k_partial_hits(all_partial_hits.into_iter(), sort_order, max_hits);
+    let split_profiles = leaf_responses
+        .iter_mut()
+        .flat_map(|leaf_response| mem::take(&mut leaf_response.split_profiles))
+        .collect_vec();
+    let partial_hits: Vec<PartialHit> = merge_sorted_partial_hits(
+        leaf_responses
+            .into_iter()
+            .map(|leaf_response| leaf_response.partial_hits),
+        sort_order,
+        start_offset,
+        max_hits,
+    );
     Ok(LeafSearchResponse {
         intermediate_aggregation_result: merged_intermediate_aggregation_result,
         num_hits,
-        partial_hits: top_k_partial_hits,
+        partial_hits,
         failed_splits,
         num_attempted_splits,
+        split_profiles,
     })
 }
 
-/// Mutates partial_hits so that it contains the top-num_hitso hits,
-/// and so that these elements are sorted.
+/// Compares two partial hits, hits ranked first being the lesser ones.
 ///
-/// TODO we could possibly optimize the sort away (but I doubt it matters).
-fn top_k_partial_hits(
-    partial_hits: impl Iterator<Item = PartialHit>,
-    sort_order: SortOrder,
-    num_hits: usize,
-) -> Vec<PartialHit> {
+/// In case of a tie on the sort value, hits are ordered by their global doc address.
+fn cmp_partial_hits(left: &PartialHit, right: &PartialHit, sort_order: SortOrder) -> Ordering {
+    let left_addr = (left.split_id.as_str(), left.segment_ord, left.doc_id);
+    let right_addr = (right.split_id.as_str(), right.segment_ord, right.doc_id);
     match sort_order {
-        SortOrder::Asc => top_k(partial_hits.into_iter(), num_hits, |partial_hit| {
+        SortOrder::Asc => {
             // This reverse dance is a little bit complicated.
             // Note that `Option<Reverse<T>>` is very different from `Reverse<Option<T>>`.
             //
             // We do want the earlier: documents without any values should always get ranked after
             // documents with a value, regardless of whether we use ascending or
             // descending order.
-            let score = partial_hit.sort_value.map(Reverse);
-            let addr = GlobalDocAddress::from_partial_hit(partial_hit);
-            (score, Reverse(addr))
-        }),
-        SortOrder::Desc => top_k(partial_hits.into_iter(), num_hits, |partial_hit| {
-            let addr = GlobalDocAddress::from_partial_hit(partial_hit);
-            (partial_hit.sort_value, addr)
-        }),
+            let left_key = (left.sort_value.as_ref().map(Reverse), Reverse(left_addr));
+            let right_key = (right.sort_value.as_ref().map(Reverse), Reverse(right_addr));
+            right_key.cmp(&left_key)
+        }
+        SortOrder::Desc => (&right.sort_value, right_addr).cmp(&(&left.sort_value, left_addr)),
+    }
+}
+
+/// Returns true if the partial hits are sorted according to `sort_order`.
+fn is_sorted_partial_hits(partial_hits: &[PartialHit], sort_order: SortOrder) -> bool {
+    partial_hits
+        .windows(2)
+        .all(|window| cmp_partial_hits(&window[0], &window[1], sort_order) != Ordering::Greater)
+}
+
+/// Merges lists of partial hits into the hits ranked in [start_offset..start_offset + max_hits).
+///
+/// The lists are expected to be sorted according to `sort_order`, which is the case of leaf
+/// responses. They are consumed with a k-way merge, and at most `start_offset + max_hits` hits are
+/// read from each of them. A list that is not sorted, for instance because it was returned by a
+/// leaf running an older version, is sorted before being merged.
+pub(crate) fn merge_sorted_partial_hits(
+    partial_hits_lists: impl Iterator<Item = Vec<PartialHit>>,
+    sort_order: SortOrder,
+    start_offset: usize,
+    max_hits: usize,
+) -> Vec<PartialHit> {
+    let num_hits_to_keep = start_offset.saturating_add(max_hits);
+    partial_hits_lists
+        .map(|mut partial_hits| {
+            if !is_sorted_partial_hits(&partial_hits, sort_order) {
+                warn!("partial hits are not sorted, sorting them before merging");
+                partial_hits
+                    .sort_unstable_by(|left, right| cmp_partial_hits(left, right, sort_order));
+            }
+            partial_hits.into_iter().take(num_hits_to_keep)
+        })
+        .kmerge_by(|left, right| cmp_partial_hits(left, right, sort_order) == Ordering::Less)
+        .skip(start_offset)
+        .take(max_hits)
+        .collect()
+}
+
+/// Merges leaf responses as they arrive.
+///
+/// The hits of each leaf response are merged right away, keeping only the top
+/// `start_offset + max_hits` ones, so that the hits of all the leaf responses are never buffered
+/// at once. The other fields of the leaf responses are merged by `finalize`.
+pub(crate) struct IncrementalCollector {
+    merge_collector: QuickwitCollector,
+    partial_hits: Vec<PartialHit>,
+    leaf_responses: Vec<LeafSearchResponse>,
+}
+
+impl IncrementalCollector {
+    pub(crate) fn new(merge_collector: QuickwitCollector) -> Self {
+        Self {
+            merge_collector,
+            partial_hits: Vec::new(),
+            leaf_responses: Vec::new(),
+        }
+    }
+
+    /// Merges the hits of a leaf response with the hits collected so far.
+    pub(crate) fn add_leaf_response(&mut self, mut leaf_response: LeafSearchResponse) {
+        let num_hits_to_keep = self
+            .merge_collector
+            .start_offset
+            .saturating_add(self.merge_collector.max_hits);
+        let partial_hits = mem::take(&mut leaf_response.partial_hits);
+        self.partial_hits = merge_sorted_partial_hits(
+            [mem::take(&mut self.partial_hits), partial_hits].into_iter(),
+            self.merge_collector.sort_by.sort_order(),
+            0,
+            num_hits_to_keep,
+        );
+        self.leaf_responses.push(leaf_response);
+    }
+
+    /// Merges the leaf responses added so far and keeps the hits in
+    /// [start_offset..start_offset + max_hits).
+    pub(crate) fn finalize(self) -> tantivy::Result<LeafSearchResponse> {
+        let leaf_responses = self.leaf_responses.into_iter().map(Ok).collect();
+        let mut merged_leaf_response = self.merge_collector.merge_fruits(leaf_responses)?;
+        merged_leaf_response.partial_hits = self
+            .partial_hits
+            .into_iter()
+            .skip(self.merge_collector.start_offset)
+            .collect();
+        Ok(merged_leaf_response)
     }
 }
 
@@ -671,6 +890,7 @@ pub(crate) fn make_collector_for_split(
         timestamp_filter_builder_opt,
         aggregation,
         aggregation_limits,
+        track_total_hits: search_request.track_total_hits.unwrap_or(true),
     })
 }
 
@@ -692,6 +912,7 @@ pub(crate) fn make_merge_collector(
         timestamp_filter_builder_opt: None,
         aggregation,
         aggregation_limits: aggregation_limits.clone(),
+        track_total_hits: search_request.track_total_hits.unwrap_or(true),
     })
 }
 
@@ -699,10 +920,11 @@ pub(crate) fn make_merge_collector(
 mod tests {
     use std::cmp::Ordering;
 
-    use quickwit_proto::{PartialHit, SortOrder, SortValue};
+    use quickwit_proto::{LeafSearchResponse, PartialHit, SearchRequest, SortOrder, SortValue};
+    use tantivy::aggregation::AggregationLimits;
 
     use super::PartialHitHeapItem;
-    use crate::collector::top_k_partial_hits;
+    use crate::collector::{make_merge_collector, merge_sorted_partial_hits, IncrementalCollector};
 
     #[test]
     fn test_partial_hit_ordered_by_sorting_field() {
@@ -726,9 +948,15 @@ mod tests {
             doc_id: 0u32,
         };
         assert_eq!(
-            top_k_partial_hits(
-                vec![make_doc(1u64), make_doc(3u64), make_doc(2u64),].into_iter(),
+            merge_sorted_partial_hits(
+                [
+                    vec![make_doc(1u64)],
+                    vec![make_doc(3u64)],
+                    vec![make_doc(2u64)]
+                ]
+                .into_iter(),
                 SortOrder::Asc,
+                0,
                 2
             ),
             vec![make_doc(1), make_doc(2)]
@@ -744,30 +972,154 @@ mod tests {
             doc_id: 0u32,
         };
         assert_eq!(
-            &top_k_partial_hits(
-                vec![
-                    make_hit_given_split_id(1u64),
-                    make_hit_given_split_id(3u64),
-                    make_hit_given_split_id(2u64),
+            &merge_sorted_partial_hits(
+                [
+                    vec![make_hit_given_split_id(1u64)],
+                    vec![make_hit_given_split_id(3u64)],
+                    vec![make_hit_given_split_id(2u64)],
                 ]
                 .into_iter(),
                 SortOrder::Desc,
+                0,
                 2
             ),
             &[make_hit_given_split_id(3), make_hit_given_split_id(2)]
         );
         assert_eq!(
-            &top_k_partial_hits(
-                vec![
-                    make_hit_given_split_id(1u64),
-                    make_hit_given_split_id(3u64),
-                    make_hit_given_split_id(2u64),
+            &merge_sorted_partial_hits(
+                [
+                    vec![make_hit_given_split_id(1u64)],
+                    vec![make_hit_given_split_id(3u64)],
+                    vec![make_hit_given_split_id(2u64)],
                 ]
                 .into_iter(),
                 SortOrder::Asc,
+                0,
                 2
             ),
             &[make_hit_given_split_id(1), make_hit_given_split_id(2)]
         );
     }
+
+    #[test]
+    fn test_merge_sorted_partial_hits_kway() {
+        let make_hit = |split_id: &str, sort_value_opt: Option<u64>| PartialHit {
+            sort_value: sort_value_opt.map(SortValue::U64),
+            split_id: split_id.to_string(),
+            segment_ord: 0u32,
+            doc_id: 0u32,
+        };
+        let partial_hits_lists = vec![
+            vec![
+                make_hit("split_1", Some(9)),
+                make_hit("split_1", Some(4)),
+                make_hit("split_1", None),
+            ],
+            vec![make_hit("split_2", Some(7)), make_hit("split_2", Some(4))],
+            Vec::new(),
+            vec![make_hit("split_3", Some(8)), make_hit("split_3", Some(1))],
+        ];
+        assert_eq!(
+            merge_sorted_partial_hits(
+                partial_hits_lists.clone().into_iter(),
+                SortOrder::Desc,
+                1,
+                3
+            ),
+            vec![
+                make_hit("split_3", Some(8)),
+                make_hit("split_2", Some(7)),
+                make_hit("split_2", Some(4)),
+            ]
+        );
+        assert_eq!(
+            merge_sorted_partial_hits(partial_hits_lists.into_iter(), SortOrder::Desc, 5, 10),
+            vec![make_hit("split_3", Some(1)), make_hit("split_1", None)]
+        );
+    }
+
+    #[test]
+    fn test_merge_sorted_partial_hits_sorts_unsorted_lists() {
+        let make_hit = |split_id: &str, sort_value: u64| PartialHit {
+            sort_value: Some(SortValue::U64(sort_value)),
+            split_id: split_id.to_string(),
+            segment_ord: 0u32,
+            doc_id: 0u32,
+        };
+        let partial_hits_lists = vec![
+            vec![
+                make_hit("split_1", 2),
+                make_hit("split_1", 9),
+                make_hit("split_1", 5),
+            ],
+            vec![make_hit("split_2", 7), make_hit("split_2", 3)],
+        ];
+        assert_eq!(
+            merge_sorted_partial_hits(partial_hits_lists.into_iter(), SortOrder::Desc, 0, 3),
+            vec![
+                make_hit("split_1", 9),
+                make_hit("split_2", 7),
+                make_hit("split_1", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_incremental_collector() {
+        let make_hit = |split_id: &str, sort_value: u64| PartialHit {
+            sort_value: Some(SortValue::U64(sort_value)),
+            split_id: split_id.to_string(),
+            segment_ord: 0u32,
+            doc_id: 0u32,
+        };
+        let search_request = SearchRequest {
+            start_offset: 1,
+            max_hits: 2,
+            ..Default::default()
+        };
+        let merge_collector =
+            make_merge_collector(&search_request, &AggregationLimits::default()).unwrap();
+        let mut incremental_collector = IncrementalCollector::new(merge_collector);
+        incremental_collector.add_leaf_response(LeafSearchResponse {
+            num_hits: 4,
+            partial_hits: vec![
+                make_hit("split_1", 9),
+                make_hit("split_1", 5),
+                make_hit("split_1", 3),
+                make_hit("split_1", 1),
+            ],
+            num_attempted_splits: 1,
+            ..Default::default()
+        });
+        // Only the top `start_offset + max_hits` hits are kept.
+        assert_eq!(
+            incremental_collector.partial_hits,
+            vec![
+                make_hit("split_1", 9),
+                make_hit("split_1", 5),
+                make_hit("split_1", 3),
+            ]
+        );
+        incremental_collector.add_leaf_response(LeafSearchResponse {
+            num_hits: 2,
+            partial_hits: vec![make_hit("split_2", 8), make_hit("split_2", 4)],
+            num_attempted_splits: 1,
+            ..Default::default()
+        });
+        assert_eq!(
+            incremental_collector.partial_hits,
+            vec![
+                make_hit("split_1", 9),
+                make_hit("split_2", 8),
+                make_hit("split_1", 5),
+            ]
+        );
+        let leaf_response = incremental_collector.finalize().unwrap();
+        assert_eq!(leaf_response.num_hits, 6);
+        assert_eq!(leaf_response.num_attempted_splits, 2);
+        assert_eq!(
+            leaf_response.partial_hits,
+            vec![make_hit("split_2", 8), make_hit("split_1", 5)]
+        );
+    }
 }
@@ -0,0 +1,98 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Deduplication of the hits of a search on the value of a fast field.
+//!
+//! Unlike a collapse, deduplication does not change how the leaves collect their top hits: the
+//! root drops the hits of the requested page whose field value was already seen in a better ranked
+//! hit.
+
+use std::collections::HashSet;
+
+use quickwit_proto::Hit;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::lookup_enrichment::get_field_value;
+
+/// Keeps only the first hit for each value of `dedup_by_field`. The hits without a value for the
+/// field are all kept.
+pub(crate) fn dedup_hits(hits: &mut Vec<Hit>, dedup_by_field: &str) -> crate::Result<()> {
+    let mut seen_values: HashSet<String> = HashSet::with_capacity(hits.len());
+    let mut keep_hits: Vec<bool> = Vec::with_capacity(hits.len());
+
+    for hit in hits.iter() {
+        let hit_json: JsonMap<String, JsonValue> = serde_json::from_str(&hit.json)?;
+        let keep_hit = match get_field_value(&hit_json, dedup_by_field) {
+            None | Some(JsonValue::Null) => true,
+            Some(value) => seen_values.insert(value.to_string()),
+        };
+        keep_hits.push(keep_hit);
+    }
+    let mut keep_hits_iter = keep_hits.into_iter();
+    hits.retain(|_| keep_hits_iter.next().unwrap_or(true));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn hit_for_test(hit_json: JsonValue) -> Hit {
+        Hit {
+            json: hit_json.to_string(),
+            partial_hit: None,
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_dedup_hits() {
+        let mut hits = vec![
+            hit_for_test(json!({"id": 1, "log": {"message": "connection reset"}})),
+            hit_for_test(json!({"id": 2, "log": {"message": "disk full"}})),
+            hit_for_test(json!({"id": 3, "log": {"message": "connection reset"}})),
+            hit_for_test(json!({"id": 4})),
+            hit_for_test(json!({"id": 5, "log": {"message": null}})),
+            hit_for_test(json!({"id": 6})),
+            hit_for_test(json!({"id": 7, "log": {"message": "disk full"}})),
+        ];
+        dedup_hits(&mut hits, "log.message").unwrap();
+        let ids: Vec<i64> = hits
+            .iter()
+            .map(|hit| {
+                let hit_json: JsonValue = serde_json::from_str(&hit.json).unwrap();
+                hit_json["id"].as_i64().unwrap()
+            })
+            .collect();
+        assert_eq!(ids, [1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_dedup_hits_distinguishes_value_types() {
+        let mut hits = vec![
+            hit_for_test(json!({"code": 1})),
+            hit_for_test(json!({"code": "1"})),
+            hit_for_test(json!({"code": 1})),
+        ];
+        dedup_hits(&mut hits, "code").unwrap();
+        assert_eq!(hits.len(), 2);
+    }
+}
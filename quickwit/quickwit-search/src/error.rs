@@ -32,6 +32,8 @@ use tokio::task::JoinError;
 pub enum SearchError {
     #[error("Index `{index_id}` does not exist.")]
     IndexDoesNotExist { index_id: String },
+    #[error("Index `{index_id}` is closed.")]
+    IndexClosed { index_id: String },
     #[error("Internal error: `{0}`.")]
     InternalError(String),
     #[error("Storage not found: `{0}`)")]
@@ -42,17 +44,21 @@ pub enum SearchError {
     InvalidArgument(String),
     #[error("{0}")]
     InvalidQuery(String),
+    #[error("Timeout: {0}")]
+    Timeout(String),
 }
 
 impl ServiceError for SearchError {
     fn status_code(&self) -> ServiceErrorCode {
         match self {
             SearchError::IndexDoesNotExist { .. } => ServiceErrorCode::NotFound,
+            SearchError::IndexClosed { .. } => ServiceErrorCode::BadRequest,
             SearchError::InternalError(_) => ServiceErrorCode::Internal,
             SearchError::StorageResolverError(_) => ServiceErrorCode::BadRequest,
             SearchError::InvalidQuery(_) => ServiceErrorCode::BadRequest,
             SearchError::InvalidArgument(_) => ServiceErrorCode::BadRequest,
             SearchError::InvalidAggregationRequest(_) => ServiceErrorCode::BadRequest,
+            SearchError::Timeout(_) => ServiceErrorCode::Unavailable,
         }
     }
 }
@@ -25,13 +25,15 @@ use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::{FetchDocsResponse, PartialHit, SearchRequest, SplitIdAndFooterOffsets};
+use quickwit_query::query_ast::query_ast_from_json;
 use quickwit_storage::Storage;
 use tantivy::query::Query;
 use tantivy::schema::{Field, Value};
-use tantivy::{ReloadPolicy, Score, Searcher, SnippetGenerator, Term};
+use tantivy::{Index, ReloadPolicy, Score, Searcher, SnippetGenerator, Term};
 use tracing::error;
 
 use crate::leaf::open_index_with_caches;
+use crate::metadata_fields::{resolve_metadata_fields, MetadataFieldValues};
 use crate::service::SearcherContext;
 use crate::{convert_document_to_json_string, GlobalDocAddress};
 
@@ -119,7 +121,7 @@ pub async fn fetch_docs(
         .map(GlobalDocAddress::from_partial_hit)
         .collect();
 
-    let mut global_doc_addr_to_doc_json = fetch_docs_to_map(
+    let global_doc_addr_to_doc_json = fetch_docs_to_map(
         searcher_context,
         global_doc_addrs,
         index_storage,
@@ -128,8 +130,40 @@ pub async fn fetch_docs(
         search_request_opt,
     )
     .await?;
+    let hits = build_leaf_hits(&partial_hits, global_doc_addr_to_doc_json);
+    Ok(FetchDocsResponse { hits })
+}
+
+/// Fetches the documents of `partial_hits` from the already opened index of `split`.
+///
+/// All the partial hits must belong to `split`.
+pub(crate) async fn fetch_docs_in_index(
+    partial_hits: Vec<PartialHit>,
+    index: Index,
+    split: &SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    search_request_opt: Option<&SearchRequest>,
+) -> anyhow::Result<FetchDocsResponse> {
+    let global_doc_addrs: Vec<GlobalDocAddress> = partial_hits
+        .iter()
+        .map(GlobalDocAddress::from_partial_hit)
+        .collect();
+    let global_doc_addr_to_doc_json: HashMap<GlobalDocAddress, Document> =
+        fetch_docs_in_opened_split(global_doc_addrs, index, split, doc_mapper, search_request_opt)
+            .await?
+            .into_iter()
+            .collect();
+    let hits = build_leaf_hits(&partial_hits, global_doc_addr_to_doc_json);
+    Ok(FetchDocsResponse { hits })
+}
 
-    let hits: Vec<quickwit_proto::LeafHit> = partial_hits
+/// Builds the leaf hits from the partial hits and their fetched documents, preserving the order of
+/// the partial hits.
+fn build_leaf_hits(
+    partial_hits: &[PartialHit],
+    mut global_doc_addr_to_doc_json: HashMap<GlobalDocAddress, Document>,
+) -> Vec<quickwit_proto::LeafHit> {
+    partial_hits
         .iter()
         .flat_map(|partial_hit| {
             let global_doc_addr = GlobalDocAddress::from_partial_hit(partial_hit);
@@ -144,8 +178,7 @@ pub async fn fetch_docs(
                 None
             }
         })
-        .collect();
-    Ok(FetchDocsResponse { hits })
+        .collect()
 }
 
 // number of concurrent fetch allowed for a single split.
@@ -162,18 +195,28 @@ struct Document {
 #[tracing::instrument(skip(global_doc_addrs, index_storage, split, searcher_context))]
 async fn fetch_docs_in_split(
     searcher_context: Arc<SearcherContext>,
-    mut global_doc_addrs: Vec<GlobalDocAddress>,
+    global_doc_addrs: Vec<GlobalDocAddress>,
     index_storage: Arc<dyn Storage>,
     split: &SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
     search_request_opt: Option<&SearchRequest>,
 ) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
-    global_doc_addrs.sort_by_key(|doc| doc.doc_addr);
     // Opens the index without the ephemeral unbounded cache, this cache is indeed not useful
     // when fetching docs as we will fetch them only once.
-    let index = open_index_with_caches(&searcher_context, index_storage, split, false)
+    let index = open_index_with_caches(&searcher_context, index_storage, split, false, false)
         .await
         .with_context(|| "open-index-for-split")?;
+    fetch_docs_in_opened_split(global_doc_addrs, index, split, doc_mapper, search_request_opt).await
+}
+
+async fn fetch_docs_in_opened_split(
+    mut global_doc_addrs: Vec<GlobalDocAddress>,
+    index: Index,
+    split: &SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    search_request_opt: Option<&SearchRequest>,
+) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
+    global_doc_addrs.sort_by_key(|doc| doc.doc_addr);
     let index_reader = index
         .reader_builder()
         // the docs are presorted so a cache size of NUM_CONCURRENT_REQUESTS is fine
@@ -182,7 +225,10 @@ async fn fetch_docs_in_split(
         .try_into()?;
     let searcher = Arc::new(index_reader.searcher());
     let fields_snippet_generator_opt = if let Some(search_request) = search_request_opt {
-        Some(create_fields_snippet_generator(&searcher, doc_mapper.clone(), search_request).await?)
+        Some(
+            create_fields_snippet_generator(&searcher, doc_mapper.clone(), search_request, split)
+                .await?,
+        )
     } else {
         None
     };
@@ -291,10 +337,14 @@ async fn create_fields_snippet_generator(
     searcher: &Searcher,
     doc_mapper: Arc<dyn DocMapper>,
     search_request: &SearchRequest,
+    split: &SplitIdAndFooterOffsets,
 ) -> anyhow::Result<FieldsSnippetGenerator> {
     let schema = searcher.schema();
     let query_ast =
-        serde_json::from_str(&search_request.query_ast).context("Invalid query ast Json")?;
+        query_ast_from_json(&search_request.query_ast).context("Invalid query ast Json")?;
+    let metadata_field_values =
+        MetadataFieldValues::for_split_offsets(&search_request.index_id, split);
+    let query_ast = resolve_metadata_fields(query_ast, &metadata_field_values)?;
     let (query, _) = doc_mapper.query(schema.clone(), &query_ast, false)?;
     let mut snippet_generators = HashMap::new();
     for field_name in &search_request.snippet_fields {
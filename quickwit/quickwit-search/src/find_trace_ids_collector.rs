@@ -348,7 +348,7 @@ impl SelectTraceIds {
     }
 }
 
-mod serde_datetime {
+pub(crate) mod serde_datetime {
     use serde::{Deserialize, Deserializer, Serializer};
     use tantivy::DateTime;
 
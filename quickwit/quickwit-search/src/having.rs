@@ -0,0 +1,445 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Post-aggregation filtering of buckets.
+//!
+//! A bucket aggregation can carry a `having` clause, which removes the buckets whose metrics do
+//! not meet a set of conditions, e.g. the services with an error rate above 5%. The clauses are
+//! stripped from the aggregation request sent to the leaves and evaluated by the root on the
+//! merged aggregation results.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::SearchError;
+
+const HAVING_KEY: &str = "having";
+
+/// Path of the number of documents of a bucket.
+const DOC_COUNT_PATH: &str = "_count";
+
+/// Condition on a metric of a bucket. The bounds are combined with a logical AND.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HavingCondition {
+    #[serde(default)]
+    gt: Option<f64>,
+    #[serde(default)]
+    gte: Option<f64>,
+    #[serde(default)]
+    lt: Option<f64>,
+    #[serde(default)]
+    lte: Option<f64>,
+}
+
+impl HavingCondition {
+    fn is_empty(&self) -> bool {
+        self.gt.is_none() && self.gte.is_none() && self.lt.is_none() && self.lte.is_none()
+    }
+
+    fn matches(&self, value: f64) -> bool {
+        self.gt.map_or(true, |gt| value > gt)
+            && self.gte.map_or(true, |gte| value >= gte)
+            && self.lt.map_or(true, |lt| value < lt)
+            && self.lte.map_or(true, |lte| value <= lte)
+    }
+}
+
+/// Conditions of a `having` clause, keyed by the path of the metric they apply to: `_count` for
+/// the number of documents of the bucket, the name of a single-value metric sub-aggregation, or
+/// `<sub-aggregation>.<metric>` for multi-value metric sub-aggregations, e.g. `latency.avg`.
+type HavingClause = BTreeMap<String, HavingCondition>;
+
+fn parse_having_clause(
+    agg_name: &str,
+    having: &JsonValue,
+    sub_agg_requests_opt: Option<&JsonMap<String, JsonValue>>,
+) -> crate::Result<HavingClause> {
+    let having_clause: HavingClause = serde_json::from_value(having.clone())
+        .map_err(|err| invalid_having_clause(agg_name, &err.to_string()))?;
+    if having_clause.is_empty() {
+        return Err(invalid_having_clause(agg_name, "no condition"));
+    }
+    for (path, condition) in &having_clause {
+        if condition.is_empty() {
+            return Err(invalid_having_clause(
+                agg_name,
+                &format!("no bound for `{path}`"),
+            ));
+        }
+        let sub_agg_name = path.split('.').next().unwrap_or_default();
+        let is_sub_agg = sub_agg_requests_opt
+            .map(|sub_agg_requests| sub_agg_requests.contains_key(sub_agg_name))
+            .unwrap_or(false);
+        if path != DOC_COUNT_PATH && !is_sub_agg {
+            return Err(invalid_having_clause(
+                agg_name,
+                &format!("`{path}` is neither `{DOC_COUNT_PATH}` nor a sub-aggregation"),
+            ));
+        }
+    }
+    Ok(having_clause)
+}
+
+fn sub_agg_requests(
+    agg_request: &JsonMap<String, JsonValue>,
+) -> Option<&JsonMap<String, JsonValue>> {
+    agg_request
+        .get("aggs")
+        .or_else(|| agg_request.get("aggregations"))
+        .and_then(JsonValue::as_object)
+}
+
+/// Removes the `having` clauses from the aggregation request, after validating them. Returns
+/// `None` if the request does not contain any `having` clause.
+pub(crate) fn strip_having_clauses(aggregation_request: &str) -> crate::Result<Option<String>> {
+    let Ok(JsonValue::Object(mut agg_requests)) = serde_json::from_str(aggregation_request) else {
+        // Quickwit-specific aggregations are not JSON objects.
+        return Ok(None);
+    };
+    if !strip_aggregations(&mut agg_requests)? {
+        return Ok(None);
+    }
+    let stripped_aggregation_request = serde_json::to_string(&agg_requests)?;
+    Ok(Some(stripped_aggregation_request))
+}
+
+/// Returns whether at least one `having` clause was removed.
+fn strip_aggregations(agg_requests: &mut JsonMap<String, JsonValue>) -> crate::Result<bool> {
+    let mut stripped = false;
+
+    for (agg_name, agg_request) in agg_requests.iter_mut() {
+        let Some(agg_request) = agg_request.as_object_mut() else {
+            continue;
+        };
+        if let Some(having) = agg_request.remove(HAVING_KEY) {
+            parse_having_clause(agg_name, &having, sub_agg_requests(agg_request))?;
+            stripped = true;
+        }
+        for sub_aggs_key in ["aggs", "aggregations"] {
+            if let Some(JsonValue::Object(sub_agg_requests)) = agg_request.get_mut(sub_aggs_key) {
+                stripped |= strip_aggregations(sub_agg_requests)?;
+            }
+        }
+    }
+    Ok(stripped)
+}
+
+/// Removes the buckets that do not meet the `having` clauses of `aggregation_request`, the
+/// original aggregation request, from the aggregation results.
+pub(crate) fn apply_having_clauses(
+    aggregation: &mut String,
+    aggregation_request: &str,
+) -> crate::Result<()> {
+    let Ok(JsonValue::Object(agg_requests)) = serde_json::from_str(aggregation_request) else {
+        return Ok(());
+    };
+    let mut aggregation_json: JsonValue = serde_json::from_str(aggregation)?;
+    let Some(agg_results) = aggregation_json.as_object_mut() else {
+        return Ok(());
+    };
+    if filter_aggregation_results(&agg_requests, agg_results)? {
+        *aggregation = serde_json::to_string(&aggregation_json)?;
+    }
+    Ok(())
+}
+
+/// Walks the aggregation request and results in parallel. Returns whether at least one bucket was
+/// removed.
+fn filter_aggregation_results(
+    agg_requests: &JsonMap<String, JsonValue>,
+    agg_results: &mut JsonMap<String, JsonValue>,
+) -> crate::Result<bool> {
+    let mut filtered = false;
+
+    for (agg_name, agg_request) in agg_requests {
+        let Some(agg_request) = agg_request.as_object() else {
+            continue;
+        };
+        let Some(buckets) = agg_results
+            .get_mut(agg_name)
+            .and_then(|agg_result| agg_result.get_mut("buckets"))
+        else {
+            continue;
+        };
+        let sub_agg_requests_opt = sub_agg_requests(agg_request);
+
+        if let Some(having) = agg_request.get(HAVING_KEY) {
+            let having_clause = parse_having_clause(agg_name, having, sub_agg_requests_opt)?;
+            let num_buckets_before = num_buckets(buckets);
+            match &mut *buckets {
+                JsonValue::Array(buckets) => {
+                    buckets.retain(|bucket| bucket_matches(bucket, &having_clause))
+                }
+                // Keyed buckets.
+                JsonValue::Object(buckets) => {
+                    buckets.retain(|_, bucket| bucket_matches(bucket, &having_clause))
+                }
+                _ => {}
+            }
+            filtered |= num_buckets(buckets) != num_buckets_before;
+        }
+        let Some(sub_agg_requests) = sub_agg_requests_opt else {
+            continue;
+        };
+        let buckets: Vec<&mut JsonValue> = match buckets {
+            JsonValue::Array(buckets) => buckets.iter_mut().collect(),
+            JsonValue::Object(buckets) => buckets.values_mut().collect(),
+            _ => continue,
+        };
+        for bucket in buckets {
+            if let Some(bucket) = bucket.as_object_mut() {
+                filtered |= filter_aggregation_results(sub_agg_requests, bucket)?;
+            }
+        }
+    }
+    Ok(filtered)
+}
+
+fn num_buckets(buckets: &JsonValue) -> usize {
+    match buckets {
+        JsonValue::Array(buckets) => buckets.len(),
+        JsonValue::Object(buckets) => buckets.len(),
+        _ => 0,
+    }
+}
+
+/// Returns whether the bucket meets all the conditions of the clause. A bucket lacking a metric,
+/// or whose metric has no value, does not meet the condition on that metric.
+fn bucket_matches(bucket: &JsonValue, having_clause: &HavingClause) -> bool {
+    having_clause.iter().all(|(path, condition)| {
+        let value_opt = if path == DOC_COUNT_PATH {
+            bucket.get("doc_count")
+        } else if let Some(metric) = bucket.get(path.as_str()) {
+            // Single-value metric aggregation.
+            metric.get("value")
+        } else {
+            resolve_path(bucket, path)
+        };
+        value_opt
+            .and_then(JsonValue::as_f64)
+            .map_or(false, |value| condition.matches(value))
+    })
+}
+
+/// Resolves a dotted path in a JSON value. Keys may contain dots, as the percentile keys of the
+/// `percentiles` aggregation, e.g. `latency.values.99.0`.
+fn resolve_path<'a>(value: &'a JsonValue, path: &str) -> Option<&'a JsonValue> {
+    if let Some(child) = value.get(path) {
+        return Some(child);
+    }
+    path.match_indices('.').find_map(|(dot_position, _)| {
+        let child = value.get(&path[..dot_position])?;
+        resolve_path(child, &path[dot_position + 1..])
+    })
+}
+
+fn invalid_having_clause(agg_name: &str, message: &str) -> SearchError {
+    SearchError::InvalidAggregationRequest(format!(
+        "invalid `having` clause of aggregation `{agg_name}`: {message}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn aggregation_request_for_test() -> JsonValue {
+        json!({
+            "by_service": {
+                "terms": {"field": "service"},
+                "aggs": {
+                    "error_rate": {"avg": {"field": "is_error"}},
+                    "latency": {"stats": {"field": "latency"}},
+                    "by_host": {
+                        "terms": {"field": "host"},
+                        "having": {"_count": {"gte": 2}},
+                    },
+                },
+                "having": {
+                    "error_rate": {"gt": 0.05},
+                    "latency.max": {"lt": 1000},
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn test_strip_having_clauses() {
+        let aggregation_request = aggregation_request_for_test().to_string();
+        let stripped_aggregation_request =
+            strip_having_clauses(&aggregation_request).unwrap().unwrap();
+        let stripped_aggregation_request_json: JsonValue =
+            serde_json::from_str(&stripped_aggregation_request).unwrap();
+        assert_eq!(
+            stripped_aggregation_request_json,
+            json!({
+                "by_service": {
+                    "terms": {"field": "service"},
+                    "aggs": {
+                        "error_rate": {"avg": {"field": "is_error"}},
+                        "latency": {"stats": {"field": "latency"}},
+                        "by_host": {"terms": {"field": "host"}},
+                    },
+                },
+            })
+        );
+        let aggregation_request = json!({"by_service": {"terms": {"field": "service"}}});
+        assert_eq!(
+            strip_having_clauses(&aggregation_request.to_string()).unwrap(),
+            None
+        );
+        assert_eq!(
+            strip_having_clauses(r#"{"session_key_field_name": "status"}"#).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_strip_invalid_having_clauses() {
+        for (having, expected_error) in [
+            (json!({}), "no condition"),
+            (json!({"_count": {}}), "no bound for `_count`"),
+            (json!({"_count": {"ne": 2}}), "unknown field `ne`"),
+            (
+                json!({"error_rate": {"gt": 0.05}}),
+                "`error_rate` is neither `_count` nor a sub-aggregation",
+            ),
+        ] {
+            let aggregation_request = json!({
+                "by_service": {
+                    "terms": {"field": "service"},
+                    "having": having,
+                },
+            });
+            let SearchError::InvalidAggregationRequest(error) =
+                strip_having_clauses(&aggregation_request.to_string()).unwrap_err()
+            else {
+                panic!("Expected an invalid aggregation request error.");
+            };
+            assert!(error.contains(expected_error), "{error}");
+        }
+    }
+
+    #[test]
+    fn test_apply_having_clauses() {
+        let aggregation = json!({
+            "by_service": {
+                "buckets": [
+                    {
+                        "key": "api",
+                        "doc_count": 10,
+                        "error_rate": {"value": 0.1},
+                        "latency": {"max": 250.0},
+                        "by_host": {
+                            "buckets": [
+                                {"key": "host-1", "doc_count": 9},
+                                {"key": "host-2", "doc_count": 1},
+                            ],
+                        },
+                    },
+                    {
+                        "key": "auth",
+                        "doc_count": 10,
+                        "error_rate": {"value": 0.01},
+                        "latency": {"max": 100.0},
+                        "by_host": {"buckets": []},
+                    },
+                    {
+                        "key": "search",
+                        "doc_count": 10,
+                        "error_rate": {"value": 0.5},
+                        "latency": {"max": 5000.0},
+                        "by_host": {"buckets": []},
+                    },
+                    {
+                        "key": "empty",
+                        "doc_count": 0,
+                        "error_rate": {"value": null},
+                        "latency": {"max": null},
+                        "by_host": {"buckets": []},
+                    },
+                ],
+            },
+        });
+        let mut aggregation_str = aggregation.to_string();
+        apply_having_clauses(
+            &mut aggregation_str,
+            &aggregation_request_for_test().to_string(),
+        )
+        .unwrap();
+        let aggregation_json: JsonValue = serde_json::from_str(&aggregation_str).unwrap();
+        assert_eq!(
+            aggregation_json,
+            json!({
+                "by_service": {
+                    "buckets": [
+                        {
+                            "key": "api",
+                            "doc_count": 10,
+                            "error_rate": {"value": 0.1},
+                            "latency": {"max": 250.0},
+                            "by_host": {
+                                "buckets": [
+                                    {"key": "host-1", "doc_count": 9},
+                                ],
+                            },
+                        },
+                    ],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_having_clauses_keyed_buckets() {
+        let aggregation_request = json!({
+            "by_latency": {
+                "range": {"field": "latency", "ranges": [{"to": 100.0}, {"from": 100.0}], "keyed": true},
+                "aggs": {"latency": {"percentiles": {"field": "latency"}}},
+                "having": {"latency.values.99.0": {"lte": 150.0}},
+            },
+        });
+        let aggregation = json!({
+            "by_latency": {
+                "buckets": {
+                    "*-100": {"doc_count": 3, "latency": {"values": {"99.0": 99.0}}},
+                    "100-*": {"doc_count": 2, "latency": {"values": {"99.0": 300.0}}},
+                },
+            },
+        });
+        let mut aggregation_str = aggregation.to_string();
+        apply_having_clauses(&mut aggregation_str, &aggregation_request.to_string()).unwrap();
+        let aggregation_json: JsonValue = serde_json::from_str(&aggregation_str).unwrap();
+        assert_eq!(
+            aggregation_json,
+            json!({
+                "by_latency": {
+                    "buckets": {
+                        "*-100": {"doc_count": 3, "latency": {"values": {"99.0": 99.0}}},
+                    },
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,395 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Joins of the hits of a search with a second, small index or with a lookup table.
+//!
+//! Full joins are out of scope: once the top hits of a search are fetched, the root looks up the
+//! documents of the joined index (or the rows of the lookup table) matching the join keys of the
+//! hits. Matching documents are attached to the hits and, in `filter` mode, the hits without a
+//! match are dropped.
+
+use std::collections::{BTreeSet, HashMap};
+
+use quickwit_metastore::Metastore;
+use quickwit_proto::{SearchRequest, SearchResponse};
+use quickwit_query::query_ast::{query_ast_to_json, QueryAst, TermSetQuery};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::lookup_enrichment::{get_field_value, lookup_key, row_to_json};
+use crate::{SearchError, SearchService};
+
+/// Maximum number of distinct join keys, hence of documents fetched from the joined index.
+const MAX_JOIN_KEYS: usize = 1_000;
+
+/// What to do with the hits without a matching document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum HitJoinMode {
+    /// Hits without a match are returned as is.
+    #[default]
+    Enrich,
+    /// Hits without a match are dropped.
+    Filter,
+}
+
+/// A join of the hits of a search on a key field, serialized as JSON in the `join` parameter of
+/// search requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct HitJoin {
+    /// Field of the hits holding the join key.
+    pub field: String,
+    /// Index whose documents are joined with the hits.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    /// Field of the joined index holding the join key. Defaults to `field`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_field: Option<String>,
+    /// Lookup table of the searched index whose rows are joined with the hits.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lookup_table: Option<String>,
+    /// Name of the field holding the joined document. Defaults to the joined index ID or lookup
+    /// table name.
+    #[serde(default)]
+    #[serde(rename = "as")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_field: Option<String>,
+    #[serde(default)]
+    pub mode: HitJoinMode,
+}
+
+impl HitJoin {
+    pub fn parse(hit_join_json: &str) -> crate::Result<HitJoin> {
+        let hit_join: HitJoin = serde_json::from_str(hit_join_json)
+            .map_err(|err| SearchError::InvalidArgument(format!("Invalid join: {err}")))?;
+        match (&hit_join.index, &hit_join.lookup_table) {
+            (Some(_), None) => {}
+            (None, Some(_)) if hit_join.key_field.is_none() => {}
+            (None, Some(_)) => {
+                return Err(SearchError::InvalidArgument(
+                    "Invalid join: `key_field` only applies to joins with an index.".to_string(),
+                ));
+            }
+            _ => {
+                return Err(SearchError::InvalidArgument(
+                    "Invalid join: exactly one of `index` and `lookup_table` must be set."
+                        .to_string(),
+                ));
+            }
+        }
+        Ok(hit_join)
+    }
+
+    fn target_field(&self) -> &str {
+        self.target_field
+            .as_deref()
+            .or(self.index.as_deref())
+            .or(self.lookup_table.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// Joins the hits of the search response of index `index_id` with the documents of the joined
+/// index or the rows of the lookup table.
+pub(crate) async fn join_hits(
+    search_response: &mut SearchResponse,
+    index_id: &str,
+    hit_join: &HitJoin,
+    search_service: &dyn SearchService,
+    metastore: &dyn Metastore,
+) -> crate::Result<()> {
+    let mut hits_json: Vec<JsonMap<String, JsonValue>> = search_response
+        .hits
+        .iter()
+        .map(|hit| serde_json::from_str(&hit.json))
+        .collect::<Result<_, _>>()?;
+    let keys: Vec<Option<String>> = hits_json
+        .iter()
+        .map(|hit_json| get_field_value(hit_json, &hit_join.field).and_then(lookup_key))
+        .collect();
+
+    let joined_docs: HashMap<String, JsonValue> = if let Some(joined_index_id) = &hit_join.index {
+        let distinct_keys: BTreeSet<String> = keys.iter().flatten().cloned().collect();
+        let key_field = hit_join.key_field.as_deref().unwrap_or(&hit_join.field);
+        fetch_joined_docs(joined_index_id, key_field, distinct_keys, search_service).await?
+    } else {
+        let table_name = hit_join.lookup_table.as_deref().unwrap_or_default();
+        let index_metadata = metastore.index_metadata(index_id).await?;
+        let Some(lookup_table) = index_metadata.index_config.lookup_tables.get(table_name) else {
+            return Err(SearchError::InvalidArgument(format!(
+                "Index `{index_id}` does not define a lookup table named `{table_name}`."
+            )));
+        };
+        keys.iter()
+            .flatten()
+            .filter_map(|key| Some((key.clone(), row_to_json(lookup_table.get(key)?))))
+            .collect()
+    };
+
+    let target_field = hit_join.target_field();
+    let mut matches: Vec<bool> = Vec::with_capacity(keys.len());
+
+    for ((hit, hit_json), key_opt) in search_response
+        .hits
+        .iter_mut()
+        .zip(hits_json.iter_mut())
+        .zip(keys)
+    {
+        let Some(joined_doc) = key_opt.and_then(|key| joined_docs.get(&key)) else {
+            matches.push(false);
+            continue;
+        };
+        hit_json.insert(target_field.to_string(), joined_doc.clone());
+        hit.json = serde_json::to_string(&hit_json)?;
+        matches.push(true);
+    }
+    if hit_join.mode == HitJoinMode::Filter {
+        let mut matches_iter = matches.into_iter();
+        search_response
+            .hits
+            .retain(|_| matches_iter.next().unwrap_or(false));
+    }
+    Ok(())
+}
+
+/// Fetches the documents of the joined index matching the join keys. The joined index is
+/// expected to hold one document per key: if several documents match a key, only one of them is
+/// joined.
+async fn fetch_joined_docs(
+    joined_index_id: &str,
+    key_field: &str,
+    keys: BTreeSet<String>,
+    search_service: &dyn SearchService,
+) -> crate::Result<HashMap<String, JsonValue>> {
+    if keys.is_empty() {
+        return Ok(HashMap::new());
+    }
+    if keys.len() > MAX_JOIN_KEYS {
+        return Err(SearchError::InvalidArgument(format!(
+            "Join on more than {MAX_JOIN_KEYS} distinct keys is not supported, got {}. Reduce \
+             `max_hits`.",
+            keys.len()
+        )));
+    }
+    let max_hits = keys.len() as u64;
+    let query_ast: QueryAst = TermSetQuery {
+        terms_per_field: HashMap::from([(key_field.to_string(), keys)]),
+    }
+    .into();
+    let search_request = SearchRequest {
+        index_id: joined_index_id.to_string(),
+        query_ast: query_ast_to_json(&query_ast).map_err(|err| {
+            SearchError::InternalError(format!("Failed to serialize query ast: Cause {err}"))
+        })?,
+        max_hits,
+        ..Default::default()
+    };
+    let search_response = search_service.root_search(search_request).await?;
+
+    let mut joined_docs = HashMap::with_capacity(search_response.hits.len());
+    for hit in search_response.hits {
+        let joined_doc: JsonMap<String, JsonValue> = serde_json::from_str(&hit.json)?;
+        let Some(key) = get_field_value(&joined_doc, key_field).and_then(lookup_key) else {
+            continue;
+        };
+        joined_docs
+            .entry(key)
+            .or_insert(JsonValue::Object(joined_doc));
+    }
+    Ok(joined_docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use quickwit_config::LookupTable;
+    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_proto::Hit;
+    use quickwit_query::query_ast::query_ast_from_json;
+    use serde_json::json;
+
+    use super::*;
+    use crate::MockSearchService;
+
+    fn hit_for_test(hit_json: JsonValue) -> Hit {
+        Hit {
+            json: hit_json.to_string(),
+            partial_hit: None,
+            snippet: None,
+        }
+    }
+
+    fn hits_json(search_response: &SearchResponse) -> Vec<JsonValue> {
+        search_response
+            .hits
+            .iter()
+            .map(|hit| serde_json::from_str(&hit.json).unwrap())
+            .collect()
+    }
+
+    fn search_response_for_test() -> SearchResponse {
+        SearchResponse {
+            hits: vec![
+                hit_for_test(json!({"user_id": 1, "msg": "login"})),
+                hit_for_test(json!({"user_id": 3, "msg": "logout"})),
+                hit_for_test(json!({"msg": "anonymous"})),
+            ],
+            num_hits: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_hit_join() {
+        let hit_join =
+            HitJoin::parse(r#"{"field": "user_id", "index": "users", "key_field": "id"}"#).unwrap();
+        assert_eq!(hit_join.index.as_deref(), Some("users"));
+        assert_eq!(hit_join.key_field.as_deref(), Some("id"));
+        assert_eq!(hit_join.mode, HitJoinMode::Enrich);
+        assert_eq!(hit_join.target_field(), "users");
+
+        let hit_join = HitJoin::parse(
+            r#"{"field": "status", "lookup_table": "http_status", "as": "st", "mode": "filter"}"#,
+        )
+        .unwrap();
+        assert_eq!(hit_join.mode, HitJoinMode::Filter);
+        assert_eq!(hit_join.target_field(), "st");
+
+        for invalid_hit_join_json in [
+            r#"{"field": "user_id"}"#,
+            r#"{"field": "user_id", "index": "users", "lookup_table": "users"}"#,
+            r#"{"field": "user_id", "lookup_table": "users", "key_field": "id"}"#,
+            r#"{"field": "user_id", "index": "users", "mode": "left"}"#,
+            r#"{"field": "user_id", "index": "users", "unknown": 1}"#,
+        ] {
+            let search_error = HitJoin::parse(invalid_hit_join_json).unwrap_err();
+            assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_join_hits_with_index() {
+        let metastore = MockMetastore::new();
+        let mut search_service = MockSearchService::new();
+        search_service
+            .expect_root_search()
+            .times(1)
+            .returning(|search_request| {
+                assert_eq!(search_request.index_id, "users");
+                assert_eq!(search_request.max_hits, 2);
+                let query_ast: QueryAst = query_ast_from_json(&search_request.query_ast).unwrap();
+                let expected_query_ast: QueryAst = TermSetQuery {
+                    terms_per_field: HashMap::from([(
+                        "id".to_string(),
+                        BTreeSet::from(["1".to_string(), "3".to_string()]),
+                    )]),
+                }
+                .into();
+                assert_eq!(query_ast, expected_query_ast);
+                Ok(SearchResponse {
+                    hits: vec![hit_for_test(json!({"id": 1, "name": "alice"}))],
+                    num_hits: 1,
+                    ..Default::default()
+                })
+            });
+        let hit_join = HitJoin::parse(
+            r#"{"field": "user_id", "index": "users", "key_field": "id", "as": "user"}"#,
+        )
+        .unwrap();
+        let mut search_response = search_response_for_test();
+        join_hits(
+            &mut search_response,
+            "events",
+            &hit_join,
+            &search_service,
+            &metastore,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            hits_json(&search_response),
+            [
+                json!({"user_id": 1, "msg": "login", "user": {"id": 1, "name": "alice"}}),
+                json!({"user_id": 3, "msg": "logout"}),
+                json!({"msg": "anonymous"}),
+            ]
+        );
+        assert_eq!(search_response.num_hits, 3);
+    }
+
+    #[tokio::test]
+    async fn test_join_hits_with_lookup_table_filter() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .times(1)
+            .returning(|index_id| {
+                assert_eq!(index_id, "events");
+                let mut index_metadata = IndexMetadata::for_test("events", "ram:///indexes/events");
+                let users =
+                    LookupTable::from_csv("user_id".to_string(), b"user_id,name\n3,bob\n").unwrap();
+                index_metadata.index_config.lookup_tables =
+                    BTreeMap::from([("users".to_string(), users)]);
+                Ok(index_metadata)
+            });
+        let search_service = MockSearchService::new();
+        let hit_join =
+            HitJoin::parse(r#"{"field": "user_id", "lookup_table": "users", "mode": "filter"}"#)
+                .unwrap();
+        let mut search_response = search_response_for_test();
+        join_hits(
+            &mut search_response,
+            "events",
+            &hit_join,
+            &search_service,
+            &metastore,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            hits_json(&search_response),
+            [json!({"user_id": 3, "msg": "logout", "users": {"name": "bob"}})]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_hits_with_missing_lookup_table() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_| Ok(IndexMetadata::for_test("events", "ram:///indexes/events")));
+        let search_service = MockSearchService::new();
+        let hit_join = HitJoin::parse(r#"{"field": "user_id", "lookup_table": "users"}"#).unwrap();
+        let search_error = join_hits(
+            &mut search_response_for_test(),
+            "events",
+            &hit_join,
+            &search_service,
+            &metastore,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+    }
+}
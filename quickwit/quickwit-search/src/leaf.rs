@@ -18,48 +18,81 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
-use std::ops::Bound;
+use std::ops::{Bound, RangeInclusive};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Context;
 use futures::future::try_join_all;
 use itertools::{Either, Itertools};
-use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
+use quickwit_directories::{CachingDirectory, DebugProxyDirectory, HotDirectory, StorageDirectory};
 use quickwit_doc_mapper::{DocMapper, TermRange, WarmupInfo};
 use quickwit_proto::{
     LeafListTermsResponse, LeafSearchResponse, ListTermsRequest, SearchRequest,
-    SplitIdAndFooterOffsets, SplitSearchError,
-};
-use quickwit_query::query_ast::QueryAst;
-use quickwit_storage::{
-    wrap_storage_with_long_term_cache, BundleStorage, MemorySizedCache, OwnedBytes, Storage,
+    SplitIdAndFooterOffsets, SplitSearchError, SplitSearchProfile, WarmupSplitsResponse,
 };
+use quickwit_query::query_ast::{optimize_query_ast, query_ast_from_json, QueryAst};
+use quickwit_storage::{wrap_storage_with_long_term_cache, BundleStorage, OwnedBytes, Storage};
+use serde_json::Value as JsonValue;
 use tantivy::collector::Collector;
 use tantivy::directory::FileSlice;
 use tantivy::fastfield::FastFieldReaders;
 use tantivy::schema::{Field, FieldType};
-use tantivy::{Index, ReloadPolicy, Searcher, Term};
+use tantivy::{Directory, Index, ReloadPolicy, Searcher, Term};
 use tracing::*;
 
 use crate::collector::{make_collector_for_split, make_merge_collector};
+use crate::leaf_cache::date_histogram_bucket_boundary;
+use crate::metadata_fields::{resolve_metadata_fields, MetadataFieldValues};
 use crate::service::SearcherContext;
+use crate::split_footer_transfer::fetch_split_footer_from_peers;
 use crate::SearchError;
 
-#[instrument(skip(index_storage, footer_cache))]
+/// Returns the hotcache and footer of the split from the split footer cache. On cache miss, they
+/// are fetched from the cache of a peer searcher advertising the split, or from the storage.
+#[instrument(skip(index_storage, searcher_context))]
 async fn get_split_footer_from_cache_or_fetch(
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
-    footer_cache: &MemorySizedCache<String>,
+    searcher_context: &SearcherContext,
 ) -> anyhow::Result<OwnedBytes> {
+    let footer_cache = &searcher_context.split_footer_cache;
     {
-        let possible_val = footer_cache.get(&split_and_footer_offsets.split_id);
+        let possible_val = footer_cache.get(&split_and_footer_offsets.split_id).await;
         if let Some(footer_data) = possible_val {
             return Ok(footer_data);
         }
     }
+    let index_key = index_storage.uri().to_string();
+    let peer_footer_data_opt = match &searcher_context.split_footer_peers_opt {
+        Some(split_footer_peers) => {
+            fetch_split_footer_from_peers(split_footer_peers, split_and_footer_offsets).await
+        }
+        None => None,
+    };
+    let footer_data_opt = match peer_footer_data_opt {
+        Some(footer_data) => footer_data,
+        None => fetch_split_footer(index_storage, split_and_footer_offsets).await?,
+    };
+
+    footer_cache
+        .put(
+            &index_key,
+            split_and_footer_offsets.split_id.to_owned(),
+            footer_data_opt.clone(),
+        )
+        .await;
+
+    Ok(footer_data_opt)
+}
+
+async fn fetch_split_footer(
+    index_storage: Arc<dyn Storage>,
+    split_and_footer_offsets: &SplitIdAndFooterOffsets,
+) -> anyhow::Result<OwnedBytes> {
     let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
-    let footer_data_opt = index_storage
+    let footer_data = index_storage
         .get_slice(
             &split_file,
             split_and_footer_offsets.split_footer_start as usize
@@ -73,44 +106,98 @@ async fn get_split_footer_from_cache_or_fetch(
                 split_and_footer_offsets.split_id
             )
         })?;
-
-    footer_cache.put(
-        split_and_footer_offsets.split_id.to_owned(),
-        footer_data_opt.clone(),
-    );
-
-    Ok(footer_data_opt)
+    Ok(footer_data)
 }
 
 /// Opens a `tantivy::Index` for the given split with several cache layers:
 /// - A split footer cache given by `SearcherContext.split_footer_cache`.
 /// - A fast fields cache given by `SearcherContext.storage_long_term_cache`.
 /// - An ephemeral unbounded cache directory whose lifetime is tied to the returned `Index`.
+///
+/// The first two caches are long-lived and shared across requests. They are skipped when
+/// `bypass_long_term_caches` is set.
 #[instrument(skip(searcher_context, index_storage))]
 pub(crate) async fn open_index_with_caches(
     searcher_context: &SearcherContext,
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
     ephemeral_unbounded_cache: bool,
+    bypass_long_term_caches: bool,
 ) -> anyhow::Result<Index> {
-    let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
-    let footer_data = get_split_footer_from_cache_or_fetch(
-        index_storage.clone(),
+    let (directory, hotcache_bytes) = open_split_directory(
+        searcher_context,
+        index_storage,
+        split_and_footer_offsets,
+        bypass_long_term_caches,
+    )
+    .await?;
+    open_index_from_directory(directory, hotcache_bytes, ephemeral_unbounded_cache)
+}
+
+/// Same as `open_index_with_caches` with an ephemeral unbounded cache, but also returns a
+/// directory recording the reads hitting the storage of the split, i.e. the reads that are
+/// served neither by the hotcache nor by the ephemeral cache.
+pub(crate) async fn open_index_recording_storage_reads(
+    searcher_context: &SearcherContext,
+    index_storage: Arc<dyn Storage>,
+    split_and_footer_offsets: &SplitIdAndFooterOffsets,
+    bypass_long_term_caches: bool,
+) -> anyhow::Result<(Index, DebugProxyDirectory<StorageDirectory>)> {
+    let (directory, hotcache_bytes) = open_split_directory(
+        searcher_context,
+        index_storage,
         split_and_footer_offsets,
-        &searcher_context.split_footer_cache,
+        bypass_long_term_caches,
     )
     .await?;
+    let debug_proxy_directory = DebugProxyDirectory::wrap(directory);
+    let index = open_index_from_directory(debug_proxy_directory.clone(), hotcache_bytes, true)?;
+    Ok((index, debug_proxy_directory))
+}
+
+/// Returns the directory of the split, backed by its storage and the long-term caches, along
+/// with its hotcache.
+async fn open_split_directory(
+    searcher_context: &SearcherContext,
+    index_storage: Arc<dyn Storage>,
+    split_and_footer_offsets: &SplitIdAndFooterOffsets,
+    bypass_long_term_caches: bool,
+) -> anyhow::Result<(StorageDirectory, FileSlice)> {
+    let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
+    let footer_data = if bypass_long_term_caches {
+        fetch_split_footer(index_storage.clone(), split_and_footer_offsets).await?
+    } else {
+        get_split_footer_from_cache_or_fetch(
+            index_storage.clone(),
+            split_and_footer_offsets,
+            searcher_context,
+        )
+        .await?
+    };
 
     let (hotcache_bytes, bundle_storage) = BundleStorage::open_from_split_data(
         index_storage,
         split_file,
         FileSlice::new(Arc::new(footer_data)),
     )?;
-    let bundle_storage_with_cache = wrap_storage_with_long_term_cache(
-        searcher_context.fast_fields_cache.clone(),
-        Arc::new(bundle_storage),
-    );
+    let bundle_storage: Arc<dyn Storage> = Arc::new(bundle_storage);
+    let bundle_storage_with_cache = if bypass_long_term_caches {
+        bundle_storage
+    } else {
+        wrap_storage_with_long_term_cache(
+            searcher_context.fast_fields_cache.clone(),
+            bundle_storage,
+        )
+    };
     let directory = StorageDirectory::new(bundle_storage_with_cache);
+    Ok((directory, hotcache_bytes))
+}
+
+fn open_index_from_directory<D: Directory>(
+    directory: D,
+    hotcache_bytes: FileSlice,
+    ephemeral_unbounded_cache: bool,
+) -> anyhow::Result<Index> {
     let hot_directory = if ephemeral_unbounded_cache {
         let caching_directory = CachingDirectory::new_unbounded(Arc::new(directory));
         HotDirectory::open(caching_directory, hotcache_bytes.read_bytes()?)?
@@ -329,28 +416,174 @@ async fn leaf_search_single_split(
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
+    bypass_caches: bool,
 ) -> crate::Result<LeafSearchResponse> {
     rewrite_request(&mut search_request, &split);
-    if let Some(cached_answer) = searcher_context
+    // Profiled searches are always executed: a cached response would not say anything about the
+    // cost of the search.
+    let use_leaf_search_cache = !bypass_caches && !search_request.profile;
+    if use_leaf_search_cache {
+        if let Some(bucket_boundary) = date_histogram_bucket_boundary(&search_request, &split) {
+            return leaf_search_single_split_at_bucket_boundary(
+                searcher_context,
+                search_request,
+                storage,
+                split,
+                doc_mapper,
+                bucket_boundary,
+            )
+            .await;
+        }
+        if let Some(cached_answer) = searcher_context
+            .leaf_search_cache
+            .get(split.clone(), search_request.clone())
+        {
+            return Ok(cached_answer);
+        }
+    }
+
+    let (index, storage_reads_opt) = if search_request.profile {
+        let (index, storage_reads) =
+            open_index_recording_storage_reads(searcher_context, storage, &split, bypass_caches)
+                .await?;
+        (index, Some(storage_reads))
+    } else {
+        let index =
+            open_index_with_caches(searcher_context, storage, &split, true, bypass_caches).await?;
+        (index, None)
+    };
+    let leaf_search_response = leaf_search_index(
+        searcher_context,
+        &search_request,
+        index,
+        &split,
+        doc_mapper,
+        storage_reads_opt.as_ref(),
+    )
+    .await?;
+
+    if use_leaf_search_cache {
+        searcher_context
+            .leaf_search_cache
+            .put(split, search_request, leaf_search_response.clone());
+    }
+    Ok(leaf_search_response)
+}
+
+/// Applies a leaf search on a split whose time range starts before the start of the request time
+/// range, splitting the search at the first date histogram bucket boundary.
+///
+/// The part of the search before the boundary covers an open bucket that changes every time a
+/// dashboard refreshes its time range. It is always recomputed. The part after the boundary only
+/// covers closed buckets and does not depend on the exact start of the request time range, so it
+/// is served from and stored in the leaf search cache.
+async fn leaf_search_single_split_at_bucket_boundary(
+    searcher_context: &SearcherContext,
+    search_request: SearchRequest,
+    storage: Arc<dyn Storage>,
+    split: SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    bucket_boundary: i64,
+) -> crate::Result<LeafSearchResponse> {
+    let mut open_bucket_request = search_request.clone();
+    open_bucket_request.end_timestamp = Some(bucket_boundary);
+
+    let mut closed_buckets_request = search_request.clone();
+    closed_buckets_request.start_timestamp = Some(bucket_boundary);
+    rewrite_request(&mut closed_buckets_request, &split);
+
+    let index = open_index_with_caches(searcher_context, storage, &split, true, false).await?;
+    let open_bucket_response = leaf_search_index(
+        searcher_context,
+        &open_bucket_request,
+        index.clone(),
+        &split,
+        doc_mapper.clone(),
+        None,
+    )
+    .await?;
+
+    let closed_buckets_response = if let Some(cached_answer) = searcher_context
         .leaf_search_cache
-        .get(split.clone(), search_request.clone())
+        .get(split.clone(), closed_buckets_request.clone())
     {
-        return Ok(cached_answer);
-    }
+        cached_answer
+    } else {
+        let closed_buckets_response = leaf_search_index(
+            searcher_context,
+            &closed_buckets_request,
+            index,
+            &split,
+            doc_mapper,
+            None,
+        )
+        .await?;
+        searcher_context.leaf_search_cache.put(
+            split.clone(),
+            closed_buckets_request,
+            closed_buckets_response.clone(),
+        );
+        closed_buckets_response
+    };
+    let merge_collector =
+        make_merge_collector(&search_request, &searcher_context.get_aggregation_limits())?;
+    let split_id = split.split_id;
+    let span = info_span!("merge_bucket_search_responses", split_id = %split_id);
+    let mut leaf_search_response = crate::run_cpu_intensive(move || {
+        let _span_guard = span.enter();
+        merge_collector.merge_fruits(vec![Ok(open_bucket_response), Ok(closed_buckets_response)])
+    })
+    .await
+    .map_err(|_| {
+        crate::SearchError::InternalError(format!("Leaf search panicked. split={split_id}"))
+    })??;
+    // Both parts were computed on the same split.
+    leaf_search_response.num_attempted_splits = 1;
+    Ok(leaf_search_response)
+}
 
+/// Apply a leaf search on the already opened index of a split.
+///
+/// If the request asks for profiling, the response holds the profile of the split.
+/// `storage_reads_opt` is then used to measure the number of bytes fetched to warm up the
+/// split.
+pub(crate) async fn leaf_search_index(
+    searcher_context: &SearcherContext,
+    search_request: &SearchRequest,
+    index: Index,
+    split: &SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    storage_reads_opt: Option<&DebugProxyDirectory<StorageDirectory>>,
+) -> crate::Result<LeafSearchResponse> {
     let split_id = split.split_id.to_string();
-    let index = open_index_with_caches(searcher_context, storage, &split, true).await?;
     let split_schema = index.schema();
 
     let quickwit_collector = make_collector_for_split(
         split_id.clone(),
         doc_mapper.as_ref(),
-        &search_request,
+        search_request,
         searcher_context.get_aggregation_limits(),
     )?;
-    let query_ast: QueryAst = serde_json::from_str(search_request.query_ast.as_str())
+    let query_ast: QueryAst = query_ast_from_json(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
-    let (query, mut warmup_info) = doc_mapper.query(split_schema, &query_ast, false)?;
+    let metadata_field_values =
+        MetadataFieldValues::for_split_offsets(&search_request.index_id, split);
+    let query_ast = resolve_metadata_fields(query_ast, &metadata_field_values)?;
+    let (query, mut warmup_info) = doc_mapper.query(split_schema.clone(), &query_ast, false)?;
+    let (query, query_profiler_opt) = if search_request.profile {
+        // Profiles the query as it is executed, i.e. after the optimization of its AST.
+        let (profiled_query, query_profiler) = optimize_query_ast(query_ast)
+            .build_profiled_tantivy_query(
+                &split_schema,
+                doc_mapper.default_search_fields(),
+                false,
+                true,
+            )
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        (profiled_query, Some(query_profiler))
+    } else {
+        (query, None)
+    };
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
@@ -360,20 +593,80 @@ async fn leaf_search_single_split(
     let collector_warmup_info = quickwit_collector.warmup_info();
     warmup_info.merge(collector_warmup_info);
 
+    if let Some(storage_reads) = storage_reads_opt {
+        // Discards the reads performed while opening the index.
+        storage_reads.drain_read_operations().count();
+    }
+    let warmup_start = Instant::now();
     warmup(&searcher, &warmup_info).await?;
+    let warmup_time_nanos = warmup_start.elapsed().as_nanos() as u64;
+    let warmup_num_bytes: u64 = storage_reads_opt
+        .map(|storage_reads| {
+            storage_reads
+                .drain_read_operations()
+                .map(|read_operation| read_operation.num_bytes as u64)
+                .sum()
+        })
+        .unwrap_or(0);
+
+    let collector_without_aggregation_opt =
+        if histogram_hard_bounds_exclude_split(search_request, &searcher) {
+            let mut search_request_without_aggregation = search_request.clone();
+            search_request_without_aggregation.aggregation_request = None;
+            let collector_without_aggregation = make_collector_for_split(
+                split_id.clone(),
+                doc_mapper.as_ref(),
+                &search_request_without_aggregation,
+                searcher_context.get_aggregation_limits(),
+            )?;
+            Some(collector_without_aggregation)
+        } else {
+            None
+        };
     let span = info_span!("tantivy_search", split_id = %split.split_id);
-    let leaf_search_response = crate::run_cpu_intensive(move || {
+    let (leaf_search_response_res, search_time_nanos) = crate::run_cpu_intensive(move || {
         let _span_guard = span.enter();
-        searcher.search(&query, &quickwit_collector)
+        let search_start = Instant::now();
+        let leaf_search_response_res = match collector_without_aggregation_opt {
+            None => searcher.search(&query, &quickwit_collector),
+            Some(collector_without_aggregation) => {
+                // The aggregation cannot collect any document of the split: its (empty)
+                // intermediate result is built without searching the split.
+                searcher
+                    .search(&query, &collector_without_aggregation)
+                    .and_then(|mut leaf_search_response| {
+                        if let Some(segment_reader) = searcher.segment_readers().first() {
+                            leaf_search_response.intermediate_aggregation_result =
+                                quickwit_collector
+                                    .empty_intermediate_aggregation_result(segment_reader)?;
+                        }
+                        Ok(leaf_search_response)
+                    })
+            }
+        };
+        let search_time_nanos = search_start.elapsed().as_nanos() as u64;
+        (leaf_search_response_res, search_time_nanos)
     })
     .await
     .map_err(|_| {
         crate::SearchError::InternalError(format!("Leaf search panicked. split={split_id}"))
-    })??;
+    })?;
+    let mut leaf_search_response = leaf_search_response_res?;
 
-    searcher_context
-        .leaf_search_cache
-        .put(split, search_request, leaf_search_response.clone());
+    if let Some(query_profiler) = query_profiler_opt {
+        let query_profile = query_profiler.query_profile();
+        let scorer_init_time_nanos =
+            query_profile.breakdown.create_weight + query_profile.breakdown.build_scorer;
+        let split_profile = SplitSearchProfile {
+            split_id: split.split_id.clone(),
+            warmup_num_bytes,
+            warmup_time_nanos,
+            scorer_init_time_nanos,
+            collect_time_nanos: search_time_nanos.saturating_sub(scorer_init_time_nanos),
+            query_profile: serde_json::to_string(&query_profile)?,
+        };
+        leaf_search_response.split_profiles.push(split_profile);
+    }
     Ok(leaf_search_response)
 }
 
@@ -393,6 +686,101 @@ fn rewrite_request(search_request: &mut SearchRequest, split: &SplitIdAndFooterO
     )
 }
 
+/// Returns whether the aggregation of the request cannot collect any document of the split.
+///
+/// This is the case when the aggregation only consists of histograms whose `hard_bounds` do not
+/// intersect the range of values of their field in the split. The range is read from the
+/// statistics of the fast field columns, so it requires the columns to be warmed up. Latency
+/// histograms over long retention windows thus skip the collection of most splits.
+///
+/// The buckets of the histograms are not pre-sized from `extended_bounds` or `hard_bounds`: they
+/// are allocated by the tantivy histogram collector, which does not expose their capacity.
+pub(crate) fn histogram_hard_bounds_exclude_split(
+    search_request: &SearchRequest,
+    searcher: &Searcher,
+) -> bool {
+    let Some(aggregation_request) = &search_request.aggregation_request else {
+        return false;
+    };
+    let Ok(JsonValue::Object(agg_requests)) = serde_json::from_str(aggregation_request) else {
+        return false;
+    };
+    if agg_requests.is_empty() {
+        return false;
+    }
+    agg_requests.values().all(|agg_request| {
+        let Some(histogram) = agg_request.get("histogram") else {
+            return false;
+        };
+        // Extended bounds create empty buckets, and missing values are collected regardless of
+        // the range of the column.
+        if histogram.get("extended_bounds").is_some() || histogram.get("missing").is_some() {
+            return false;
+        }
+        let Some(field_name) = histogram.get("field").and_then(JsonValue::as_str) else {
+            return false;
+        };
+        let Some(hard_bounds) = histogram.get("hard_bounds") else {
+            return false;
+        };
+        let (Some(min), Some(max)) = (
+            hard_bounds.get("min").and_then(JsonValue::as_f64),
+            hard_bounds.get("max").and_then(JsonValue::as_f64),
+        ) else {
+            return false;
+        };
+        match fast_field_value_range(searcher, field_name) {
+            Some(Some(value_range)) => *value_range.end() < min || *value_range.start() > max,
+            // The split does not hold any value for the field.
+            Some(None) => true,
+            None => false,
+        }
+    })
+}
+
+/// Returns the range of the values of a numeric fast field across the segments of the split, or
+/// `None` if the range cannot be determined.
+fn fast_field_value_range(
+    searcher: &Searcher,
+    field_name: &str,
+) -> Option<Option<RangeInclusive<f64>>> {
+    let schema = searcher.schema();
+    let field = schema.get_field(field_name).ok()?;
+    let field_type = schema.get_field_entry(field).field_type();
+    let mut value_range_opt: Option<RangeInclusive<f64>> = None;
+
+    for segment_reader in searcher.segment_readers() {
+        let fast_fields = segment_reader.fast_fields();
+        let segment_value_range_opt = match field_type {
+            FieldType::U64(_) => fast_fields
+                .column_opt::<u64>(field_name)
+                .ok()?
+                .map(|column| column.min_value() as f64..=column.max_value() as f64),
+            FieldType::I64(_) => fast_fields
+                .column_opt::<i64>(field_name)
+                .ok()?
+                .map(|column| column.min_value() as f64..=column.max_value() as f64),
+            FieldType::F64(_) => fast_fields
+                .column_opt::<f64>(field_name)
+                .ok()?
+                .map(|column| column.min_value()..=column.max_value()),
+            _ => return None,
+        };
+        let Some(segment_value_range) = segment_value_range_opt else {
+            continue;
+        };
+        let value_range = match value_range_opt {
+            Some(value_range) => {
+                value_range.start().min(*segment_value_range.start())
+                    ..=value_range.end().max(*segment_value_range.end())
+            }
+            None => segment_value_range,
+        };
+        value_range_opt = Some(value_range);
+    }
+    Some(value_range_opt)
+}
+
 pub(crate) fn rewrite_start_end_time_bounds(
     start_timestamp_opt: &mut Option<i64>,
     end_timestamp_opt: &mut Option<i64>,
@@ -420,12 +808,15 @@ pub(crate) fn rewrite_start_end_time_bounds(
 /// [PartialHit](quickwit_proto::PartialHit) candidates. The root will be in
 /// charge to consolidate, identify the actual final top hits to display, and
 /// fetch the actual documents to convert the partial hits into actual Hits.
+///
+/// If `bypass_caches` is set, the searcher's long-term caches are neither read nor populated.
 pub async fn leaf_search(
     searcher_context: Arc<SearcherContext>,
     request: &SearchRequest,
     index_storage: Arc<dyn Storage>,
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
+    bypass_caches: bool,
 ) -> Result<LeafSearchResponse, SearchError> {
     let request = Arc::new(request.clone());
     let leaf_search_single_split_futures: Vec<_> = splits
@@ -452,6 +843,7 @@ pub async fn leaf_search(
                     index_storage_clone,
                     split.clone(),
                     doc_mapper_clone,
+                    bypass_caches,
                 )
                 .await;
                 timer.observe_duration();
@@ -509,7 +901,7 @@ async fn leaf_list_terms_single_split(
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
 ) -> crate::Result<LeafListTermsResponse> {
-    let index = open_index_with_caches(searcher_context, storage, &split, true).await?;
+    let index = open_index_with_caches(searcher_context, storage, &split, true, false).await?;
     let split_schema = index.schema();
     let reader = index
         .reader_builder()
@@ -682,3 +1074,45 @@ pub async fn leaf_list_terms(
 
     Ok(merged_search_response)
 }
+
+/// Fetches the hotcache and footer of the given splits into the split footer cache.
+///
+/// Splits that are already cached are skipped. Warming up is best effort: failures are logged and
+/// do not fail the request.
+pub async fn warmup_splits(
+    searcher_context: Arc<SearcherContext>,
+    index_storage: Arc<dyn Storage>,
+    splits: &[SplitIdAndFooterOffsets],
+) -> WarmupSplitsResponse {
+    let warmup_split_futures = splits
+        .iter()
+        .filter(|split| !searcher_context.split_footer_cache.contains(&split.split_id))
+        .map(|split| {
+            let index_storage_clone = index_storage.clone();
+            let searcher_context_clone = searcher_context.clone();
+            async move {
+                let _leaf_split_search_permit = searcher_context_clone.leaf_search_split_semaphore
+                    .acquire()
+                    .await
+                    .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
+                let warmup_res = get_split_footer_from_cache_or_fetch(
+                    index_storage_clone,
+                    split,
+                    &searcher_context_clone,
+                )
+                .await;
+                if let Err(error) = &warmup_res {
+                    warn!(split_id=%split.split_id, error=?error, "Failed to warm up split.");
+                }
+                warmup_res.is_ok()
+            }
+        });
+    let num_warmed_up_splits = futures::future::join_all(warmup_split_futures)
+        .await
+        .into_iter()
+        .filter(|is_warmed_up| *is_warmed_up)
+        .count();
+    WarmupSplitsResponse {
+        num_warmed_up_splits: num_warmed_up_splits as u64,
+    }
+}
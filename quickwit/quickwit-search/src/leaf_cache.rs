@@ -22,6 +22,7 @@ use std::ops::Bound;
 use prost::Message;
 use quickwit_proto::{LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets};
 use quickwit_storage::{MemorySizedCache, OwnedBytes};
+use serde_json::Value as JsonValue;
 
 /// A cache to memoize `leaf_search_single_split` results.
 pub struct LeafSearchCache {
@@ -93,6 +94,69 @@ impl CacheKey {
     }
 }
 
+/// Returns the first boundary, in seconds, of the buckets of the request's date histogram
+/// aggregation that falls after the start of the request time range, if the split covers time on
+/// both sides of it.
+///
+/// The results for the part of the split after this boundary do not depend on the exact start of
+/// the request time range: they can be cached across dashboard refreshes. The request is expected
+/// to have been rewritten for the split beforehand, so a start timestamp is only set when it falls
+/// within the split time range.
+pub(crate) fn date_histogram_bucket_boundary(
+    search_request: &SearchRequest,
+    split: &SplitIdAndFooterOffsets,
+) -> Option<i64> {
+    let start_timestamp = search_request.start_timestamp?;
+    let split_end_timestamp = split.timestamp_end?;
+    let aggregation_request = search_request.aggregation_request.as_ref()?;
+    let interval_secs = date_histogram_fixed_interval_secs(aggregation_request)?;
+
+    if start_timestamp.rem_euclid(interval_secs) == 0 {
+        // The start of the time range is already aligned on a bucket boundary.
+        return None;
+    }
+    let bucket_boundary =
+        (start_timestamp.div_euclid(interval_secs) + 1).checked_mul(interval_secs)?;
+    if bucket_boundary > split_end_timestamp {
+        return None;
+    }
+    if let Some(end_timestamp) = search_request.end_timestamp {
+        if bucket_boundary >= end_timestamp {
+            return None;
+        }
+    }
+    Some(bucket_boundary)
+}
+
+/// Returns the fixed interval in seconds of the first top-level date histogram aggregation of the
+/// request, if any. Intervals that are not a whole number of seconds are ignored.
+fn date_histogram_fixed_interval_secs(aggregation_request: &str) -> Option<i64> {
+    let aggregations: serde_json::Map<String, JsonValue> =
+        serde_json::from_str(aggregation_request).ok()?;
+    aggregations.values().find_map(|aggregation| {
+        let fixed_interval = aggregation
+            .get("date_histogram")?
+            .get("fixed_interval")?
+            .as_str()?;
+        parse_fixed_interval_secs(fixed_interval)
+    })
+}
+
+fn parse_fixed_interval_secs(fixed_interval: &str) -> Option<i64> {
+    let unit_start = fixed_interval.find(|c: char| !c.is_ascii_digit())?;
+    let (value, unit) = fixed_interval.split_at(unit_start);
+    let value: i64 = value.parse().ok()?;
+    let interval_secs = match unit {
+        "ms" if value % 1_000 == 0 => value / 1_000,
+        "s" => value,
+        "m" => value.checked_mul(60)?,
+        "h" => value.checked_mul(3_600)?,
+        "d" => value.checked_mul(86_400)?,
+        _ => return None,
+    };
+    (interval_secs > 0).then_some(interval_secs)
+}
+
 /// A (half-open) range bounded inclusively below and exclusively above [start..end).
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Range {
@@ -174,7 +238,96 @@ mod tests {
         LeafSearchResponse, PartialHit, SearchRequest, SortValue, SplitIdAndFooterOffsets,
     };
 
-    use super::LeafSearchCache;
+    use super::{date_histogram_bucket_boundary, parse_fixed_interval_secs, LeafSearchCache};
+
+    #[test]
+    fn test_parse_fixed_interval_secs() {
+        assert_eq!(parse_fixed_interval_secs("30s"), Some(30));
+        assert_eq!(parse_fixed_interval_secs("5m"), Some(300));
+        assert_eq!(parse_fixed_interval_secs("1h"), Some(3_600));
+        assert_eq!(parse_fixed_interval_secs("2d"), Some(172_800));
+        assert_eq!(parse_fixed_interval_secs("60000ms"), Some(60));
+        assert_eq!(parse_fixed_interval_secs("500ms"), None);
+        assert_eq!(parse_fixed_interval_secs("0s"), None);
+        assert_eq!(parse_fixed_interval_secs("1w"), None);
+        assert_eq!(parse_fixed_interval_secs("h"), None);
+        assert_eq!(parse_fixed_interval_secs("60"), None);
+    }
+
+    #[test]
+    fn test_date_histogram_bucket_boundary() {
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split".to_string(),
+            split_footer_start: 0,
+            split_footer_end: 100,
+            timestamp_start: Some(1_000),
+            timestamp_end: Some(2_000),
+            create_timestamp: None,
+        };
+        let aggregation_request = r#"{
+            "histo": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "5m"},
+                "aggs": {"max_response_time": {"max": {"field": "response_time"}}}
+            }
+        }"#;
+        let search_request = SearchRequest {
+            start_timestamp: Some(1_234),
+            aggregation_request: Some(aggregation_request.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            date_histogram_bucket_boundary(&search_request, &split),
+            Some(1_500)
+        );
+        {
+            // The boundary does not move as long as the start stays within the same bucket.
+            let search_request = SearchRequest {
+                start_timestamp: Some(1_499),
+                ..search_request.clone()
+            };
+            assert_eq!(
+                date_histogram_bucket_boundary(&search_request, &split),
+                Some(1_500)
+            );
+        }
+        {
+            let search_request = SearchRequest {
+                start_timestamp: Some(1_500),
+                ..search_request.clone()
+            };
+            assert!(date_histogram_bucket_boundary(&search_request, &split).is_none());
+        }
+        {
+            let search_request = SearchRequest {
+                start_timestamp: Some(1_801),
+                ..search_request.clone()
+            };
+            assert!(date_histogram_bucket_boundary(&search_request, &split).is_none());
+        }
+        {
+            let search_request = SearchRequest {
+                end_timestamp: Some(1_500),
+                ..search_request.clone()
+            };
+            assert!(date_histogram_bucket_boundary(&search_request, &split).is_none());
+        }
+        {
+            let search_request = SearchRequest {
+                start_timestamp: None,
+                ..search_request.clone()
+            };
+            assert!(date_histogram_bucket_boundary(&search_request, &split).is_none());
+        }
+        {
+            let search_request = SearchRequest {
+                aggregation_request: Some(
+                    r#"{"count_per_status": {"terms": {"field": "status"}}}"#.to_string(),
+                ),
+                ..search_request
+            };
+            assert!(date_histogram_bucket_boundary(&search_request, &split).is_none());
+        }
+    }
 
     #[test]
     fn test_leaf_search_cache_no_timestamp() {
@@ -186,6 +339,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: None,
             timestamp_end: None,
+            create_timestamp: None,
         };
 
         let split_2 = SplitIdAndFooterOffsets {
@@ -194,6 +348,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: None,
             timestamp_end: None,
+            create_timestamp: None,
         };
 
         let query_1 = SearchRequest {
@@ -227,6 +382,7 @@ mod tests {
                 sort_value: Some(SortValue::U64(0u64)),
                 split_id: "split_1".to_string(),
             }],
+            split_profiles: Vec::new(),
         };
 
         assert!(cache.get(split_1.clone(), query_1.clone()).is_none());
@@ -247,6 +403,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(100),
             timestamp_end: Some(199),
+            create_timestamp: None,
         };
         let split_2 = SplitIdAndFooterOffsets {
             split_id: "split_2".to_string(),
@@ -254,6 +411,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(150),
             timestamp_end: Some(249),
+            create_timestamp: None,
         };
         let split_3 = SplitIdAndFooterOffsets {
             split_id: "split_3".to_string(),
@@ -261,6 +419,7 @@ mod tests {
             split_footer_end: 100,
             timestamp_start: Some(150),
             timestamp_end: Some(249),
+            create_timestamp: None,
         };
 
         let query_1 = SearchRequest {
@@ -312,6 +471,7 @@ mod tests {
                 sort_value: Some(SortValue::U64(0)),
                 split_id: "split_1".to_string(),
             }],
+            split_profiles: Vec::new(),
         };
 
         // for split_1, 1 and 1bis cover different timestamp ranges
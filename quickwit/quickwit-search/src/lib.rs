@@ -22,22 +22,39 @@
 #![allow(clippy::bool_assert_comparison)]
 #![deny(clippy::disallowed_methods)]
 
+mod cached_splits;
 mod client;
 mod cluster_client;
 mod collector;
+mod dedup;
 mod error;
 mod fetch_docs;
 mod filters;
 mod find_trace_ids_collector;
+mod having;
+mod hit_join;
 mod leaf;
 mod leaf_cache;
+mod lookup_enrichment;
+mod metadata_fields;
+mod realtime;
+mod request_template;
 mod retry;
+mod rollup;
 mod root;
 mod search_job_placer;
 mod search_response_rest;
 mod search_stream;
 mod service;
+mod sessions_collector;
+mod shadow_search;
+mod split_footer_transfer;
+mod split_warmup;
+mod terms_lookup;
 mod thread_pool;
+mod time_zone_histogram;
+mod udf;
+mod validate_query;
 
 mod metrics;
 
@@ -46,10 +63,11 @@ mod tests;
 
 pub use collector::QuickwitAggregations;
 use metrics::SEARCH_METRICS;
+use quickwit_cluster::Cluster;
 use quickwit_common::tower::Pool;
-use quickwit_doc_mapper::DocMapper;
-use quickwit_query::query_ast::QueryAst;
-use root::{finalize_aggregation, validate_request};
+use quickwit_doc_mapper::{resolve_doc_id_field_alias, DocMapper};
+use quickwit_query::query_ast::{query_ast_from_json, query_ast_to_json, QueryAst};
+use root::{check_feature_flags, finalize_aggregation, validate_request};
 use service::SearcherContext;
 use tantivy::schema::NamedFieldDocument;
 
@@ -64,26 +82,45 @@ pub use find_trace_ids_collector::FindTraceIdsCollector;
 use itertools::Itertools;
 use quickwit_config::{build_doc_mapper, SearcherConfig};
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
+use quickwit_ingest::IngesterPool;
 use quickwit_metastore::{ListSplitsQuery, Metastore, SplitMetadata, SplitState};
 use quickwit_proto::{
     Hit, IndexUid, PartialHit, SearchRequest, SearchResponse, SplitIdAndFooterOffsets,
 };
 use quickwit_storage::StorageResolver;
+pub use sessions_collector::{Session, SessionsCollector};
 use tantivy::DocAddress;
 
 pub use crate::client::{
     create_search_client_from_channel, create_search_client_from_grpc_addr, SearchServiceClient,
 };
 pub use crate::cluster_client::ClusterClient;
+use crate::cached_splits::spawn_cached_splits_digest_task;
+use crate::dedup::dedup_hits;
 pub use crate::error::{parse_grpc_error, SearchError};
 use crate::fetch_docs::fetch_docs;
-use crate::leaf::{leaf_list_terms, leaf_search};
+use crate::having::{apply_having_clauses, strip_having_clauses};
+use crate::leaf::{leaf_list_terms, leaf_search, warmup_splits};
+use crate::lookup_enrichment::enrich_search_response;
+pub use crate::request_template::RequestTemplates;
+pub use crate::rollup::{
+    published_splits_time_range, rollup_aggregation_request, rollup_documents,
+};
+use crate::root::check_index_is_searchable;
 pub use crate::root::{jobs_to_leaf_request, root_list_terms, root_search, SearchJob};
 pub use crate::search_job_placer::{Job, SearchJobPlacer};
-pub use crate::search_response_rest::SearchResponseRest;
+pub use crate::search_response_rest::{
+    CollectorProfileRest, SearchProfileRest, SearchResponseRest, SplitProfileRest,
+    SplitSearchProfileRest, WarmupProfileRest,
+};
 pub use crate::search_stream::root_search_stream;
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+use crate::shadow_search::ShadowSearcher;
+pub use crate::split_warmup::SplitWarmupNotifier;
 use crate::thread_pool::run_cpu_intensive;
+pub use crate::validate_query::{
+    validate_query, QueryDiagnostic, QueryDiagnosticKind, QueryValidation,
+};
 
 /// A pool of searcher clients identified by their gRPC socket address.
 pub type SearcherPool = Pool<SocketAddr, SearchServiceClient>;
@@ -120,6 +157,7 @@ fn extract_split_and_footer_offsets(split_metadata: &SplitMetadata) -> SplitIdAn
             .time_range
             .as_ref()
             .map(|time_range| *time_range.end()),
+        create_timestamp: Some(split_metadata.create_timestamp),
     }
 }
 
@@ -140,7 +178,7 @@ async fn list_relevant_splits(
         query = query.with_time_range_end_lt(end_ts);
     }
 
-    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).map_err(|_| {
+    let query_ast: QueryAst = query_ast_from_json(&search_request.query_ast).map_err(|_| {
         SearchError::InternalError(format!(
             "Failed to deserialize query_ast: `{}`",
             search_request.query_ast
@@ -182,6 +220,7 @@ pub async fn single_node_search(
 ) -> crate::Result<SearchResponse> {
     let start_instant = tokio::time::Instant::now();
     let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
+    check_index_is_searchable(&index_metadata)?;
     let index_uid = index_metadata.index_uid.clone();
     let index_config = index_metadata.into_index_config();
 
@@ -190,15 +229,34 @@ pub async fn single_node_search(
             SearchError::InternalError(format!("Failed to build doc mapper. Cause: {err}"))
         })?;
 
-    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)?;
+    let query_ast: QueryAst = query_ast_from_json(&search_request.query_ast)?;
     let query_ast_resolved: QueryAst =
         query_ast.parse_user_query(doc_mapper.default_search_fields())?;
-    search_request.query_ast = serde_json::to_string(&query_ast_resolved)?;
+    let query_ast_resolved = index_config
+        .search_settings
+        .synonym_dictionary()
+        .expand_synonyms(query_ast_resolved);
+    let query_ast_resolved =
+        resolve_doc_id_field_alias(query_ast_resolved, doc_mapper.doc_id_field_name())
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    check_feature_flags(
+        &search_request,
+        &query_ast_resolved,
+        &index_config.search_settings,
+    )?;
+    search_request.query_ast = query_ast_to_json(&query_ast_resolved)?;
 
     let index_storage = storage_resolver.resolve(&index_config.index_uri).await?;
     let metas = list_relevant_splits(index_uid, &search_request, metastore).await?;
     let split_metadata: Vec<SplitIdAndFooterOffsets> =
         metas.iter().map(extract_split_and_footer_offsets).collect();
+    // `having` clauses are evaluated on the merged aggregation results.
+    let aggregation_request_opt = search_request.aggregation_request.clone();
+    if let Some(aggregation_request) = &aggregation_request_opt {
+        if let Some(stripped_aggregation_request) = strip_having_clauses(aggregation_request)? {
+            search_request.aggregation_request = Some(stripped_aggregation_request);
+        }
+    }
     validate_request(&*doc_mapper, &search_request)?;
 
     // Verifying that the query is valid.
@@ -214,6 +272,7 @@ pub async fn single_node_search(
         index_storage.clone(),
         &split_metadata[..],
         doc_mapper.clone(),
+        false,
     )
     .await
     .context("Failed to perform leaf search.")?;
@@ -234,7 +293,7 @@ pub async fn single_node_search(
     )
     .await
     .context("Failed to perform fetch docs.")?;
-    let hits: Vec<Hit> = fetch_docs_response
+    let mut hits: Vec<Hit> = fetch_docs_response
         .hits
         .into_iter()
         .map(|leaf_hit| Hit {
@@ -243,6 +302,9 @@ pub async fn single_node_search(
             snippet: leaf_hit.leaf_snippet_json,
         })
         .collect();
+    if let Some(dedup_by_field) = &search_request.dedup_by {
+        dedup_hits(&mut hits, dedup_by_field)?;
+    }
     let elapsed = start_instant.elapsed();
 
     let aggregations: Option<QuickwitAggregations> = search_request
@@ -251,12 +313,17 @@ pub async fn single_node_search(
         .map(|agg| serde_json::from_str(agg))
         .transpose()?;
 
-    let aggregation = finalize_aggregation(
+    let mut aggregation = finalize_aggregation(
         leaf_search_response.intermediate_aggregation_result,
         aggregations,
         &searcher_context,
     )?;
-    Ok(SearchResponse {
+    if let (Some(aggregation), Some(aggregation_request)) =
+        (aggregation.as_mut(), aggregation_request_opt.as_deref())
+    {
+        apply_having_clauses(aggregation, aggregation_request)?;
+    }
+    let mut search_response = SearchResponse {
         aggregation,
         num_hits: leaf_search_response.num_hits,
         hits,
@@ -266,24 +333,48 @@ pub async fn single_node_search(
             .iter()
             .map(|error| format!("{error:?}"))
             .collect_vec(),
-    })
+        split_profiles: leaf_search_response.split_profiles,
+    };
+    enrich_search_response(
+        &mut search_response,
+        aggregation_request_opt.as_deref(),
+        &index_config.lookup_tables,
+    )?;
+    Ok(search_response)
 }
 
 /// Starts a search node, aka a `searcher`.
+///
+/// If a `cluster` is provided, the searcher advertises the splits it caches to the other nodes.
 pub async fn start_searcher_service(
     searcher_config: SearcherConfig,
     metastore: Arc<dyn Metastore>,
     storage_resolver: StorageResolver,
     search_job_placer: SearchJobPlacer,
+    ingester_pool: IngesterPool,
+    cluster_opt: Option<Cluster>,
 ) -> anyhow::Result<Arc<dyn SearchService>> {
+    udf::register_wasm_udfs(&searcher_config.udfs, &storage_resolver).await?;
+    let shadow_searcher_opt = match &searcher_config.shadow_search {
+        Some(shadow_search_config) => Some(ShadowSearcher::new(shadow_search_config).await?),
+        None => None,
+    };
     let cluster_client = ClusterClient::new(search_job_placer.clone());
-    let search_service = Arc::new(SearchServiceImpl::new(
+    let mut search_service = SearchServiceImpl::new(
         metastore,
         storage_resolver,
         cluster_client,
         search_job_placer,
+        ingester_pool,
         searcher_config,
-    ));
+    );
+    if let Some(shadow_searcher) = shadow_searcher_opt {
+        search_service = search_service.with_shadow_searcher(shadow_searcher);
+    }
+    let search_service = Arc::new(search_service);
+    if let Some(cluster) = cluster_opt {
+        spawn_cached_splits_digest_task(cluster, search_service.searcher_context());
+    }
     Ok(search_service)
 }
 
@@ -0,0 +1,318 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Enrichment of search responses with the lookup tables of an index.
+//!
+//! For each lookup table, the value of the table field is looked up in the table and the matching
+//! row is attached to the hit (or to the terms aggregation bucket) under the name of the table.
+
+use std::collections::BTreeMap;
+
+use quickwit_config::LookupTable;
+use quickwit_proto::SearchResponse;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+/// Attaches the rows of the lookup tables matching the hits and the terms aggregation buckets of
+/// `search_response`.
+pub(crate) fn enrich_search_response(
+    search_response: &mut SearchResponse,
+    aggregation_request_opt: Option<&str>,
+    lookup_tables: &BTreeMap<String, LookupTable>,
+) -> crate::Result<()> {
+    if lookup_tables.is_empty() {
+        return Ok(());
+    }
+    for hit in &mut search_response.hits {
+        let mut hit_json: JsonMap<String, JsonValue> = serde_json::from_str(&hit.json)?;
+        if enrich_hit(&mut hit_json, lookup_tables) {
+            hit.json = serde_json::to_string(&hit_json)?;
+        }
+    }
+    if let (Some(aggregation_request), Some(aggregation)) = (
+        aggregation_request_opt,
+        search_response.aggregation.as_mut(),
+    ) {
+        let aggregation_request_json: JsonValue = serde_json::from_str(aggregation_request)?;
+        let mut aggregation_json: JsonValue = serde_json::from_str(aggregation)?;
+        // Quickwit-specific aggregations, such as sessions, return arrays and are left untouched.
+        if let (Some(agg_requests), Some(agg_results)) = (
+            aggregation_request_json.as_object(),
+            aggregation_json.as_object_mut(),
+        ) {
+            enrich_aggregation_results(agg_requests, agg_results, lookup_tables);
+            *aggregation = serde_json::to_string(&aggregation_json)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the hit was enriched.
+fn enrich_hit(
+    hit_json: &mut JsonMap<String, JsonValue>,
+    lookup_tables: &BTreeMap<String, LookupTable>,
+) -> bool {
+    let mut enriched = false;
+    for (table_name, lookup_table) in lookup_tables {
+        let Some(row) = get_field_value(hit_json, &lookup_table.field)
+            .and_then(lookup_key)
+            .and_then(|key| lookup_table.get(&key))
+        else {
+            continue;
+        };
+        hit_json.insert(table_name.clone(), row_to_json(row));
+        enriched = true;
+    }
+    enriched
+}
+
+/// Walks the aggregation request and results in parallel, enriching the buckets of the terms
+/// aggregations targeting the field of a lookup table.
+fn enrich_aggregation_results(
+    agg_requests: &JsonMap<String, JsonValue>,
+    agg_results: &mut JsonMap<String, JsonValue>,
+    lookup_tables: &BTreeMap<String, LookupTable>,
+) {
+    for (agg_name, agg_request) in agg_requests {
+        let Some(buckets) = agg_results
+            .get_mut(agg_name)
+            .and_then(|agg_result| agg_result.get_mut("buckets"))
+        else {
+            continue;
+        };
+        let terms_field_opt = agg_request
+            .get("terms")
+            .and_then(|terms| terms.get("field"))
+            .and_then(JsonValue::as_str);
+        let sub_agg_requests_opt = agg_request
+            .get("aggs")
+            .or_else(|| agg_request.get("aggregations"))
+            .and_then(JsonValue::as_object);
+
+        let buckets: Vec<&mut JsonValue> = match buckets {
+            JsonValue::Array(buckets) => buckets.iter_mut().collect(),
+            // Keyed buckets.
+            JsonValue::Object(buckets) => buckets.values_mut().collect(),
+            _ => continue,
+        };
+        for bucket in buckets {
+            let Some(bucket) = bucket.as_object_mut() else {
+                continue;
+            };
+            if let Some(sub_agg_requests) = sub_agg_requests_opt {
+                enrich_aggregation_results(sub_agg_requests, bucket, lookup_tables);
+            }
+            let Some(terms_field) = terms_field_opt else {
+                continue;
+            };
+            let Some(key) = bucket.get("key").and_then(lookup_key) else {
+                continue;
+            };
+            for (table_name, lookup_table) in lookup_tables {
+                if lookup_table.field != terms_field {
+                    continue;
+                }
+                if let Some(row) = lookup_table.get(&key) {
+                    bucket.insert(table_name.clone(), row_to_json(row));
+                }
+            }
+        }
+    }
+}
+
+/// Resolves a dotted field path (e.g. `source.asn`) in a hit.
+pub(crate) fn get_field_value<'a>(
+    hit_json: &'a JsonMap<String, JsonValue>,
+    field_path: &str,
+) -> Option<&'a JsonValue> {
+    if let Some(value) = hit_json.get(field_path) {
+        return Some(value);
+    }
+    let mut field_path_parts = field_path.split('.');
+    let mut value = hit_json.get(field_path_parts.next()?)?;
+    for field_path_part in field_path_parts {
+        value = value.get(field_path_part)?;
+    }
+    Some(value)
+}
+
+/// Converts a field value or a bucket key into a lookup table key. Integral numbers are
+/// formatted without a fractional part since numeric bucket keys are returned as floats.
+/// Multivalued fields are not enriched.
+pub(crate) fn lookup_key(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(value) => Some(value.clone()),
+        JsonValue::Bool(value) => Some(value.to_string()),
+        JsonValue::Number(number) => match number.as_f64() {
+            Some(value) if number.is_f64() && value.fract() == 0.0 && value.abs() < 1e15 => {
+                Some((value as i64).to_string())
+            }
+            _ => Some(number.to_string()),
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn row_to_json(row: &BTreeMap<String, String>) -> JsonValue {
+    JsonValue::Object(
+        row.iter()
+            .map(|(column, value)| (column.clone(), JsonValue::String(value.clone())))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::Hit;
+    use serde_json::json;
+
+    use super::*;
+
+    fn lookup_tables_for_test() -> BTreeMap<String, LookupTable> {
+        let http_status = LookupTable::from_csv(
+            "status".to_string(),
+            b"status,description\n200,OK\n404,Not Found\n",
+        )
+        .unwrap();
+        let asn = LookupTable::from_csv(
+            "source.asn".to_string(),
+            b"asn,org\n13335,Cloudflare\n15169,Google\n",
+        )
+        .unwrap();
+        BTreeMap::from([
+            ("http_status".to_string(), http_status),
+            ("asn_info".to_string(), asn),
+        ])
+    }
+
+    fn hit_for_test(hit_json: JsonValue) -> Hit {
+        Hit {
+            json: hit_json.to_string(),
+            partial_hit: None,
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_enrich_hits() {
+        let mut search_response = SearchResponse {
+            hits: vec![
+                hit_for_test(json!({"status": 404, "source": {"asn": 15169}})),
+                hit_for_test(json!({"status": 503})),
+                hit_for_test(json!({"status": [200, 404]})),
+            ],
+            ..Default::default()
+        };
+        enrich_search_response(&mut search_response, None, &lookup_tables_for_test()).unwrap();
+
+        let hit_json: JsonValue = serde_json::from_str(&search_response.hits[0].json).unwrap();
+        assert_eq!(
+            hit_json,
+            json!({
+                "status": 404,
+                "source": {"asn": 15169},
+                "http_status": {"description": "Not Found"},
+                "asn_info": {"org": "Google"},
+            })
+        );
+        let hit_json: JsonValue = serde_json::from_str(&search_response.hits[1].json).unwrap();
+        assert_eq!(hit_json, json!({"status": 503}));
+        let hit_json: JsonValue = serde_json::from_str(&search_response.hits[2].json).unwrap();
+        assert_eq!(hit_json, json!({"status": [200, 404]}));
+    }
+
+    #[test]
+    fn test_enrich_aggregation() {
+        let aggregation_request = json!({
+            "by_asn": {
+                "terms": {"field": "source.asn"},
+                "aggs": {
+                    "by_status": {"terms": {"field": "status"}},
+                },
+            },
+        });
+        let aggregation = json!({
+            "by_asn": {
+                "buckets": [
+                    {
+                        "key": 13335.0,
+                        "doc_count": 2,
+                        "by_status": {
+                            "buckets": [
+                                {"key": 200.0, "doc_count": 1},
+                                {"key": 418.0, "doc_count": 1},
+                            ],
+                        },
+                    },
+                ],
+            },
+        });
+        let mut search_response = SearchResponse {
+            aggregation: Some(aggregation.to_string()),
+            ..Default::default()
+        };
+        enrich_search_response(
+            &mut search_response,
+            Some(&aggregation_request.to_string()),
+            &lookup_tables_for_test(),
+        )
+        .unwrap();
+        let aggregation_json: JsonValue =
+            serde_json::from_str(search_response.aggregation.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            aggregation_json,
+            json!({
+                "by_asn": {
+                    "buckets": [
+                        {
+                            "key": 13335.0,
+                            "doc_count": 2,
+                            "asn_info": {"org": "Cloudflare"},
+                            "by_status": {
+                                "buckets": [
+                                    {
+                                        "key": 200.0,
+                                        "doc_count": 1,
+                                        "http_status": {"description": "OK"},
+                                    },
+                                    {"key": 418.0, "doc_count": 1},
+                                ],
+                            },
+                        },
+                    ],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_enrich_search_response_leaves_array_aggregations_untouched() {
+        let aggregation = r#"[{"key":"200"}]"#.to_string();
+        let mut search_response = SearchResponse {
+            aggregation: Some(aggregation.clone()),
+            ..Default::default()
+        };
+        enrich_search_response(
+            &mut search_response,
+            Some(r#"{"session_key_field_name": "status"}"#),
+            &lookup_tables_for_test(),
+        )
+        .unwrap();
+        assert_eq!(search_response.aggregation, Some(aggregation));
+    }
+}
@@ -0,0 +1,790 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Virtual metadata fields.
+//!
+//! Metadata fields are not indexed: they hold the same value for all the documents of a split,
+//! so queries targeting them are resolved split by split, before the query is built against the
+//! split schema. The root uses this resolution to prune the splits that cannot match, and the
+//! leaves to replace the corresponding clauses by a `MatchAll` or a `MatchNone` query.
+
+use std::collections::HashMap;
+use std::ops::{Bound, RangeBounds};
+
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::SplitIdAndFooterOffsets;
+use quickwit_query::query_ast::{
+    BoolQuery, BoostingQuery, CombinedFieldsQuery, DisMaxQuery, FieldPresenceQuery, FullTextQuery,
+    IntervalsQuery, KnnQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor, RangeQuery, RegexQuery,
+    SpanQuery, TermQuery, TermSetQuery, UdfQuery,
+};
+use quickwit_query::{InterpretUserInput, JsonLiteral};
+use tantivy::DateTime;
+
+use crate::SearchError;
+
+/// Virtual field holding the ID of the index of a document.
+pub(crate) const INDEX_ID_FIELD_NAME: &str = "_index";
+/// Virtual field holding the ID of the split of a document.
+pub(crate) const SPLIT_ID_FIELD_NAME: &str = "_split_id";
+/// Virtual field holding the time at which the split of a document was created. Documents are
+/// ingested at most one commit timeout before their split is created.
+pub(crate) const INGEST_TIMESTAMP_FIELD_NAME: &str = "_ingest_timestamp";
+
+/// Values taken by the metadata fields for all the documents of a split.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MetadataFieldValues<'a> {
+    pub index_id: &'a str,
+    /// `None` when the split is unknown, e.g. when validating the query on the root.
+    pub split_id: Option<&'a str>,
+    /// Split creation timestamp, in seconds. `None` when the split is unknown.
+    pub ingest_timestamp: Option<i64>,
+}
+
+impl<'a> MetadataFieldValues<'a> {
+    /// Values used to validate a query independently of any split. Clauses on split-level fields
+    /// are then resolved as matching all documents.
+    pub fn for_validation(index_id: &'a str) -> Self {
+        Self {
+            index_id,
+            split_id: None,
+            ingest_timestamp: None,
+        }
+    }
+
+    pub fn for_split_metadata(index_id: &'a str, split_metadata: &'a SplitMetadata) -> Self {
+        Self {
+            index_id,
+            split_id: Some(split_metadata.split_id()),
+            ingest_timestamp: Some(split_metadata.create_timestamp),
+        }
+    }
+
+    pub fn for_split_offsets(index_id: &'a str, split: &'a SplitIdAndFooterOffsets) -> Self {
+        Self {
+            index_id,
+            split_id: Some(&split.split_id),
+            ingest_timestamp: split.create_timestamp,
+        }
+    }
+
+    fn get(&self, field: &str) -> Option<MetadataFieldValue<'a>> {
+        match field {
+            INDEX_ID_FIELD_NAME => Some(MetadataFieldValue::Keyword(Some(self.index_id))),
+            SPLIT_ID_FIELD_NAME => Some(MetadataFieldValue::Keyword(self.split_id)),
+            INGEST_TIMESTAMP_FIELD_NAME => {
+                Some(MetadataFieldValue::Timestamp(self.ingest_timestamp))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Value of a metadata field. `None` stands for an unknown value, which matches any clause.
+#[derive(Debug, Clone, Copy)]
+enum MetadataFieldValue<'a> {
+    Keyword(Option<&'a str>),
+    Timestamp(Option<i64>),
+}
+
+impl<'a> MetadataFieldValue<'a> {
+    fn matches_term(&self, field: &str, term: &str) -> crate::Result<bool> {
+        match *self {
+            MetadataFieldValue::Keyword(keyword_opt) => {
+                Ok(keyword_opt.map_or(true, |keyword| keyword == term))
+            }
+            MetadataFieldValue::Timestamp(timestamp_opt) => {
+                let date_time = parse_date_time(field, &JsonLiteral::String(term.to_string()))?;
+                Ok(timestamp_opt.map_or(true, |timestamp| {
+                    date_time.into_timestamp_secs() == timestamp
+                }))
+            }
+        }
+    }
+
+    fn matches_prefix(&self, field: &str, prefix: &str) -> crate::Result<bool> {
+        match *self {
+            MetadataFieldValue::Keyword(keyword_opt) => {
+                Ok(keyword_opt.map_or(true, |keyword| keyword.starts_with(prefix)))
+            }
+            MetadataFieldValue::Timestamp(_) => Err(SearchError::InvalidQuery(format!(
+                "prefix queries are not supported on the metadata field `{field}`"
+            ))),
+        }
+    }
+
+    fn matches_range(
+        &self,
+        field: &str,
+        lower_bound: &Bound<JsonLiteral>,
+        upper_bound: &Bound<JsonLiteral>,
+    ) -> crate::Result<bool> {
+        match *self {
+            MetadataFieldValue::Keyword(keyword_opt) => {
+                let lower_bound = map_bound(lower_bound, |literal| parse_keyword(field, literal))?;
+                let upper_bound = map_bound(upper_bound, |literal| parse_keyword(field, literal))?;
+                Ok(keyword_opt.map_or(true, |keyword| {
+                    (lower_bound, upper_bound).contains(&keyword)
+                }))
+            }
+            MetadataFieldValue::Timestamp(timestamp_opt) => {
+                let lower_bound =
+                    map_bound(lower_bound, |literal| parse_date_time(field, literal))?;
+                let upper_bound =
+                    map_bound(upper_bound, |literal| parse_date_time(field, literal))?;
+                Ok(timestamp_opt.map_or(true, |timestamp| {
+                    (lower_bound, upper_bound).contains(&DateTime::from_timestamp_secs(timestamp))
+                }))
+            }
+        }
+    }
+}
+
+fn map_bound<T>(
+    bound: &Bound<JsonLiteral>,
+    parse_fn: impl Fn(&JsonLiteral) -> crate::Result<T>,
+) -> crate::Result<Bound<T>> {
+    match bound {
+        Bound::Included(literal) => Ok(Bound::Included(parse_fn(literal)?)),
+        Bound::Excluded(literal) => Ok(Bound::Excluded(parse_fn(literal)?)),
+        Bound::Unbounded => Ok(Bound::Unbounded),
+    }
+}
+
+fn parse_keyword<'a>(field: &str, literal: &'a JsonLiteral) -> crate::Result<&'a str> {
+    <&str>::interpret_json(literal).ok_or_else(|| {
+        SearchError::InvalidQuery(format!(
+            "expected a string for the metadata field `{field}`, got `{literal:?}`"
+        ))
+    })
+}
+
+fn parse_date_time(field: &str, literal: &JsonLiteral) -> crate::Result<DateTime> {
+    DateTime::interpret_json(literal).ok_or_else(|| {
+        SearchError::InvalidQuery(format!(
+            "expected a datetime for the metadata field `{field}`, got `{literal:?}`"
+        ))
+    })
+}
+
+fn match_all_or_none(matches: bool) -> QueryAst {
+    if matches {
+        QueryAst::MatchAll
+    } else {
+        QueryAst::MatchNone
+    }
+}
+
+/// Returns true if the query has at least one clause targeting a metadata field.
+pub(crate) fn has_metadata_fields(query_ast: &QueryAst) -> bool {
+    let mut metadata_field_finder = MetadataFieldFinder::default();
+    metadata_field_finder
+        .visit(query_ast)
+        .expect("can't fail unwrapping Infallible");
+    metadata_field_finder.found
+}
+
+fn is_metadata_field(field: &str) -> bool {
+    matches!(
+        field,
+        INDEX_ID_FIELD_NAME | SPLIT_ID_FIELD_NAME | INGEST_TIMESTAMP_FIELD_NAME
+    )
+}
+
+#[derive(Default)]
+struct MetadataFieldFinder {
+    found: bool,
+}
+
+impl<'a> QueryAstVisitor<'a> for MetadataFieldFinder {
+    type Err = std::convert::Infallible;
+
+    fn visit_term(&mut self, term_query: &'a TermQuery) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&term_query.field);
+        Ok(())
+    }
+
+    fn visit_term_set(&mut self, term_set_query: &'a TermSetQuery) -> Result<(), Self::Err> {
+        self.found |= term_set_query
+            .terms_per_field
+            .keys()
+            .any(|field| is_metadata_field(field));
+        Ok(())
+    }
+
+    fn visit_full_text(&mut self, full_text_query: &'a FullTextQuery) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&full_text_query.field);
+        Ok(())
+    }
+
+    fn visit_combined_fields(
+        &mut self,
+        combined_fields_query: &'a CombinedFieldsQuery,
+    ) -> Result<(), Self::Err> {
+        self.found |= combined_fields_query.field_names().any(is_metadata_field);
+        Ok(())
+    }
+
+    fn visit_phrase_prefix(
+        &mut self,
+        phrase_prefix_query: &'a PhrasePrefixQuery,
+    ) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&phrase_prefix_query.field);
+        Ok(())
+    }
+
+    fn visit_intervals(&mut self, intervals_query: &'a IntervalsQuery) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&intervals_query.field);
+        Ok(())
+    }
+
+    fn visit_span(&mut self, span_query: &'a SpanQuery) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&span_query.field);
+        Ok(())
+    }
+
+    fn visit_knn(&mut self, knn_query: &'a KnnQuery) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&knn_query.field);
+        Ok(())
+    }
+
+    fn visit_regex(&mut self, regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&regex_query.field);
+        Ok(())
+    }
+
+    fn visit_range(&mut self, range_query: &'a RangeQuery) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&range_query.field);
+        Ok(())
+    }
+
+    fn visit_field_presence(
+        &mut self,
+        field_presence_query: &'a FieldPresenceQuery,
+    ) -> Result<(), Self::Err> {
+        self.found |= is_metadata_field(&field_presence_query.field);
+        Ok(())
+    }
+}
+
+/// Replaces the clauses targeting metadata fields by a `MatchAll` or a `MatchNone` query,
+/// depending on the metadata values of the split, and propagates `MatchNone` queries upward
+/// when it does not change the set of matching documents.
+///
+/// Term, term set, full-text, phrase prefix and range clauses are supported. Full-text clauses
+/// are not analyzed: their text has to match the metadata value exactly.
+pub(crate) fn resolve_metadata_fields(
+    query_ast: QueryAst,
+    metadata_field_values: &MetadataFieldValues,
+) -> crate::Result<QueryAst> {
+    let resolved_query_ast = match query_ast {
+        QueryAst::Bool(bool_query) => resolve_bool(bool_query, metadata_field_values)?,
+        QueryAst::DisMax(dis_max_query) => {
+            let DisMaxQuery {
+                queries,
+                tie_breaker,
+            } = dis_max_query;
+            let mut resolved_queries = Vec::with_capacity(queries.len());
+            for query_ast in queries {
+                match resolve_metadata_fields(query_ast, metadata_field_values)? {
+                    QueryAst::MatchNone => {}
+                    resolved_query_ast => resolved_queries.push(resolved_query_ast),
+                }
+            }
+            if resolved_queries.is_empty() {
+                QueryAst::MatchNone
+            } else {
+                DisMaxQuery {
+                    queries: resolved_queries,
+                    tie_breaker,
+                }
+                .into()
+            }
+        }
+        QueryAst::Boosting(boosting_query) => {
+            let BoostingQuery {
+                positive,
+                negative,
+                negative_boost,
+            } = boosting_query;
+            match resolve_metadata_fields(*positive, metadata_field_values)? {
+                QueryAst::MatchNone => QueryAst::MatchNone,
+                positive => match resolve_metadata_fields(*negative, metadata_field_values)? {
+                    QueryAst::MatchNone => positive,
+                    negative => BoostingQuery {
+                        positive: Box::new(positive),
+                        negative: Box::new(negative),
+                        negative_boost,
+                    }
+                    .into(),
+                },
+            }
+        }
+        QueryAst::Term(term_query) => match metadata_field_values.get(&term_query.field) {
+            Some(value) => {
+                match_all_or_none(value.matches_term(&term_query.field, &term_query.value)?)
+            }
+            None => term_query.into(),
+        },
+        QueryAst::TermSet(term_set_query) => {
+            resolve_term_set(term_set_query, metadata_field_values)?
+        }
+        QueryAst::FullText(full_text_query) => {
+            match metadata_field_values.get(&full_text_query.field) {
+                Some(value) => match_all_or_none(
+                    value.matches_term(&full_text_query.field, &full_text_query.text)?,
+                ),
+                None => full_text_query.into(),
+            }
+        }
+        QueryAst::CombinedFields(combined_fields_query) => {
+            if let Some(field) = combined_fields_query
+                .field_names()
+                .find(|field| is_metadata_field(field))
+            {
+                return Err(SearchError::InvalidQuery(format!(
+                    "combined_fields queries are not supported on the metadata field `{field}`"
+                )));
+            }
+            combined_fields_query.into()
+        }
+        QueryAst::PhrasePrefix(phrase_prefix_query) => {
+            match metadata_field_values.get(&phrase_prefix_query.field) {
+                Some(value) => match_all_or_none(
+                    value
+                        .matches_prefix(&phrase_prefix_query.field, &phrase_prefix_query.phrase)?,
+                ),
+                None => phrase_prefix_query.into(),
+            }
+        }
+        QueryAst::Range(range_query) => match metadata_field_values.get(&range_query.field) {
+            Some(value) => match_all_or_none(value.matches_range(
+                &range_query.field,
+                &range_query.lower_bound,
+                &range_query.upper_bound,
+            )?),
+            None => range_query.into(),
+        },
+        QueryAst::Intervals(intervals_query) => {
+            if is_metadata_field(&intervals_query.field) {
+                return Err(SearchError::InvalidQuery(format!(
+                    "intervals queries are not supported on the metadata field `{}`",
+                    intervals_query.field
+                )));
+            }
+            intervals_query.into()
+        }
+        QueryAst::Span(span_query) => {
+            if is_metadata_field(&span_query.field) {
+                return Err(SearchError::InvalidQuery(format!(
+                    "span queries are not supported on the metadata field `{}`",
+                    span_query.field
+                )));
+            }
+            span_query.into()
+        }
+        QueryAst::Knn(knn_query) => {
+            if is_metadata_field(&knn_query.field) {
+                return Err(SearchError::InvalidQuery(format!(
+                    "knn queries are not supported on the metadata field `{}`",
+                    knn_query.field
+                )));
+            }
+            knn_query.into()
+        }
+        QueryAst::Regex(regex_query) => {
+            if is_metadata_field(&regex_query.field) {
+                return Err(SearchError::InvalidQuery(format!(
+                    "regex queries are not supported on the metadata field `{}`",
+                    regex_query.field
+                )));
+            }
+            regex_query.into()
+        }
+        QueryAst::FieldPresence(field_presence_query) => {
+            // Every document has a value for each metadata field.
+            if is_metadata_field(&field_presence_query.field) {
+                QueryAst::MatchAll
+            } else {
+                field_presence_query.into()
+            }
+        }
+        QueryAst::Boost { underlying, boost } => {
+            match resolve_metadata_fields(*underlying, metadata_field_values)? {
+                QueryAst::MatchNone => QueryAst::MatchNone,
+                underlying => QueryAst::Boost {
+                    underlying: Box::new(underlying),
+                    boost,
+                },
+            }
+        }
+        QueryAst::ConstScore { underlying, score } => {
+            match resolve_metadata_fields(*underlying, metadata_field_values)? {
+                QueryAst::MatchNone => QueryAst::MatchNone,
+                underlying => QueryAst::ConstScore {
+                    underlying: Box::new(underlying),
+                    score,
+                },
+            }
+        }
+        QueryAst::Udf(udf_query) => {
+            let UdfQuery {
+                underlying,
+                udf,
+                fields,
+                mode,
+            } = udf_query;
+            match resolve_metadata_fields(*underlying, metadata_field_values)? {
+                QueryAst::MatchNone => QueryAst::MatchNone,
+                underlying => UdfQuery {
+                    underlying: Box::new(underlying),
+                    udf,
+                    fields,
+                    mode,
+                }
+                .into(),
+            }
+        }
+        QueryAst::FunctionScore(function_score_query) => {
+            let function_score_query = function_score_query.try_map_subqueries(|query_ast| {
+                resolve_metadata_fields(query_ast, metadata_field_values)
+            })?;
+            if *function_score_query.underlying == QueryAst::MatchNone {
+                QueryAst::MatchNone
+            } else {
+                function_score_query.into()
+            }
+        }
+        query_ast @ (QueryAst::TermsLookup(_)
+        | QueryAst::UserInput(_)
+        | QueryAst::SimpleUserInput(_)
+        | QueryAst::MatchAll
+        | QueryAst::MatchNone) => query_ast,
+    };
+    Ok(resolved_query_ast)
+}
+
+fn resolve_bool(
+    bool_query: BoolQuery,
+    metadata_field_values: &MetadataFieldValues,
+) -> crate::Result<QueryAst> {
+    let resolve_all = |query_asts: Vec<QueryAst>| -> crate::Result<Vec<QueryAst>> {
+        query_asts
+            .into_iter()
+            .map(|query_ast| resolve_metadata_fields(query_ast, metadata_field_values))
+            .collect()
+    };
+    let must = resolve_all(bool_query.must)?;
+    let must_not = resolve_all(bool_query.must_not)?;
+    let should = resolve_all(bool_query.should)?;
+    let filter = resolve_all(bool_query.filter)?;
+
+    if must
+        .iter()
+        .chain(filter.iter())
+        .any(|query_ast| *query_ast == QueryAst::MatchNone)
+        || must_not
+            .iter()
+            .any(|query_ast| *query_ast == QueryAst::MatchAll)
+    {
+        return Ok(QueryAst::MatchNone);
+    }
+    let minimum_should_match = bool_query.minimum_should_match;
+    let has_should = !should.is_empty();
+    // The should clauses that match no documents are kept when `minimum_should_match` is set, as
+    // it may depend on the number of clauses.
+    let should: Vec<QueryAst> = if minimum_should_match.is_some() {
+        should
+    } else {
+        should
+            .into_iter()
+            .filter(|query_ast| *query_ast != QueryAst::MatchNone)
+            .collect()
+    };
+    // Without must or filter clauses, at least one should clause has to match.
+    if has_should
+        && should
+            .iter()
+            .all(|query_ast| *query_ast == QueryAst::MatchNone)
+        && must.is_empty()
+        && filter.is_empty()
+    {
+        return Ok(QueryAst::MatchNone);
+    }
+    let resolved_bool_query = BoolQuery {
+        must,
+        must_not,
+        should,
+        filter,
+        minimum_should_match,
+    };
+    Ok(resolved_bool_query.into())
+}
+
+fn resolve_term_set(
+    term_set_query: TermSetQuery,
+    metadata_field_values: &MetadataFieldValues,
+) -> crate::Result<QueryAst> {
+    let mut terms_per_field = HashMap::new();
+    let mut metadata_term_matches = false;
+
+    for (field, terms) in term_set_query.terms_per_field {
+        let Some(value) = metadata_field_values.get(&field) else {
+            terms_per_field.insert(field, terms);
+            continue;
+        };
+        for term in &terms {
+            metadata_term_matches |= value.matches_term(&field, term)?;
+        }
+    }
+    if metadata_term_matches {
+        return Ok(QueryAst::MatchAll);
+    }
+    if terms_per_field.is_empty() {
+        return Ok(QueryAst::MatchNone);
+    }
+    Ok(TermSetQuery { terms_per_field }.into())
+}
+
+/// Returns the splits that can hold documents matching the query, based on their metadata
+/// fields.
+pub(crate) fn prune_splits_on_metadata_fields(
+    query_ast: &QueryAst,
+    index_id: &str,
+    split_metadatas: Vec<SplitMetadata>,
+) -> crate::Result<Vec<SplitMetadata>> {
+    if !has_metadata_fields(query_ast) {
+        return Ok(split_metadatas);
+    }
+    let mut matching_split_metadatas = Vec::with_capacity(split_metadatas.len());
+
+    for split_metadata in split_metadatas {
+        let metadata_field_values =
+            MetadataFieldValues::for_split_metadata(index_id, &split_metadata);
+        let resolved_query_ast =
+            resolve_metadata_fields(query_ast.clone(), &metadata_field_values)?;
+
+        if resolved_query_ast != QueryAst::MatchNone {
+            matching_split_metadatas.push(split_metadata);
+        }
+    }
+    Ok(matching_split_metadatas)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_query::query_ast::{FullTextMode, FullTextParams};
+
+    use super::*;
+
+    const SPLIT_VALUES: MetadataFieldValues<'static> = MetadataFieldValues {
+        index_id: "my-index",
+        split_id: Some("split-1"),
+        // 2023-05-25T10:00:00Z
+        ingest_timestamp: Some(1_685_008_800),
+    };
+
+    fn resolve(query_ast: QueryAst) -> QueryAst {
+        resolve_metadata_fields(query_ast, &SPLIT_VALUES).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_metadata_fields_term() {
+        assert_eq!(
+            resolve(TermQuery::from_field_value("_index", "my-index").into()),
+            QueryAst::MatchAll
+        );
+        assert_eq!(
+            resolve(TermQuery::from_field_value("_index", "other-index").into()),
+            QueryAst::MatchNone
+        );
+        assert_eq!(
+            resolve(TermQuery::from_field_value("_split_id", "split-1").into()),
+            QueryAst::MatchAll
+        );
+        assert_eq!(
+            resolve(
+                TermQuery::from_field_value("_ingest_timestamp", "2023-05-25T10:00:00Z").into()
+            ),
+            QueryAst::MatchAll
+        );
+        assert_eq!(
+            resolve(
+                TermQuery::from_field_value("_ingest_timestamp", "2023-05-25T10:00:01Z").into()
+            ),
+            QueryAst::MatchNone
+        );
+        let term_query_ast: QueryAst = TermQuery::from_field_value("body", "my-index").into();
+        assert_eq!(resolve(term_query_ast.clone()), term_query_ast);
+    }
+
+    #[test]
+    fn test_resolve_metadata_fields_full_text_is_not_analyzed() {
+        let full_text_query_ast: QueryAst = FullTextQuery {
+            field: "_index".to_string(),
+            text: "my-index".to_string(),
+            params: FullTextParams {
+                tokenizer: None,
+                mode: FullTextMode::Phrase { slop: 0 },
+                zero_terms_query: Default::default(),
+                lenient: false,
+            },
+            preferred_field: None,
+        }
+        .into();
+        assert_eq!(resolve(full_text_query_ast), QueryAst::MatchAll);
+    }
+
+    #[test]
+    fn test_resolve_metadata_fields_term_set() {
+        let term_set_query_ast: QueryAst = TermSetQuery {
+            terms_per_field: [
+                (
+                    "_index".to_string(),
+                    ["my-index".to_string()].into_iter().collect(),
+                ),
+                (
+                    "body".to_string(),
+                    ["hello".to_string()].into_iter().collect(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+        .into();
+        assert_eq!(resolve(term_set_query_ast), QueryAst::MatchAll);
+
+        let term_set_query_ast: QueryAst = TermSetQuery {
+            terms_per_field: [
+                (
+                    "_index".to_string(),
+                    ["other-index".to_string()].into_iter().collect(),
+                ),
+                (
+                    "body".to_string(),
+                    ["hello".to_string()].into_iter().collect(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+        .into();
+        let expected_query_ast: QueryAst = TermSetQuery {
+            terms_per_field: [(
+                "body".to_string(),
+                ["hello".to_string()].into_iter().collect(),
+            )]
+            .into_iter()
+            .collect(),
+        }
+        .into();
+        assert_eq!(resolve(term_set_query_ast), expected_query_ast);
+    }
+
+    #[test]
+    fn test_resolve_metadata_fields_range() {
+        let range_query_ast = |lower_bound: &str, upper_bound: &str| -> QueryAst {
+            RangeQuery {
+                field: "_ingest_timestamp".to_string(),
+                lower_bound: Bound::Included(JsonLiteral::String(lower_bound.to_string())),
+                upper_bound: Bound::Excluded(JsonLiteral::String(upper_bound.to_string())),
+            }
+            .into()
+        };
+        assert_eq!(
+            resolve(range_query_ast(
+                "2023-05-25T00:00:00Z",
+                "2023-05-26T00:00:00Z"
+            )),
+            QueryAst::MatchAll
+        );
+        assert_eq!(
+            resolve(range_query_ast(
+                "2023-05-24T00:00:00Z",
+                "2023-05-25T10:00:00Z"
+            )),
+            QueryAst::MatchNone
+        );
+        let error = resolve_metadata_fields(
+            range_query_ast("yesterday", "2023-05-25T10:00:00Z"),
+            &SPLIT_VALUES,
+        )
+        .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidQuery(_)));
+    }
+
+    #[test]
+    fn test_resolve_metadata_fields_bool() {
+        let bool_query_ast: QueryAst = BoolQuery {
+            must: vec![TermQuery::from_field_value("body", "hello").into()],
+            must_not: vec![TermQuery::from_field_value("_index", "my-index").into()],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(resolve(bool_query_ast), QueryAst::MatchNone);
+
+        let bool_query_ast: QueryAst = BoolQuery {
+            should: vec![
+                TermQuery::from_field_value("_split_id", "split-2").into(),
+                TermQuery::from_field_value("_split_id", "split-3").into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(resolve(bool_query_ast), QueryAst::MatchNone);
+
+        let bool_query_ast: QueryAst = BoolQuery {
+            must: vec![TermQuery::from_field_value("body", "hello").into()],
+            should: vec![TermQuery::from_field_value("_split_id", "split-2").into()],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![TermQuery::from_field_value("body", "hello").into()],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(resolve(bool_query_ast), expected_query_ast);
+    }
+
+    #[test]
+    fn test_resolve_metadata_fields_for_validation() {
+        let metadata_field_values = MetadataFieldValues::for_validation("my-index");
+        let query_ast: QueryAst = TermQuery::from_field_value("_split_id", "split-2").into();
+        assert_eq!(
+            resolve_metadata_fields(query_ast, &metadata_field_values).unwrap(),
+            QueryAst::MatchAll
+        );
+        let query_ast: QueryAst = TermQuery::from_field_value("_ingest_timestamp", "now").into();
+        assert!(resolve_metadata_fields(query_ast, &metadata_field_values).is_err());
+    }
+
+    #[test]
+    fn test_prune_splits_on_metadata_fields() {
+        let split_metadatas = vec![
+            SplitMetadata::for_test("split-1".to_string()),
+            SplitMetadata::for_test("split-2".to_string()),
+        ];
+        let query_ast: QueryAst = TermQuery::from_field_value("_split_id", "split-2").into();
+        let pruned_split_metadatas =
+            prune_splits_on_metadata_fields(&query_ast, "my-index", split_metadatas).unwrap();
+        assert_eq!(pruned_split_metadatas.len(), 1);
+        assert_eq!(pruned_split_metadatas[0].split_id(), "split-2");
+    }
+}
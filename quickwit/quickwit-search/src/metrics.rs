@@ -21,13 +21,17 @@
 
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    new_counter, new_gauge, new_histogram, Histogram, IntCounter, IntGauge,
+    new_counter, new_counter_vec, new_gauge, new_histogram, new_histogram_vec, Histogram,
+    HistogramVec, IntCounter, IntCounterVec, IntGauge,
 };
 
 pub struct SearchMetrics {
     pub leaf_searches_splits_total: IntCounter,
     pub leaf_search_split_duration_secs: Histogram,
     pub active_search_threads_count: IntGauge,
+    pub split_footer_peer_fetches_total: IntCounter,
+    pub shadow_searches_total: IntCounterVec<1>,
+    pub shadow_search_duration_secs: HistogramVec<1>,
 }
 
 impl Default for SearchMetrics {
@@ -49,6 +53,25 @@ impl Default for SearchMetrics {
                 "Number of threads in use in the CPU thread pool",
                 "quickwit_search",
             ),
+            split_footer_peer_fetches_total: new_counter(
+                "split_footer_peer_fetches_total",
+                "Number of split footers fetched from the cache of a peer searcher instead of the \
+                 storage.",
+                "quickwit_search",
+            ),
+            shadow_searches_total: new_counter_vec(
+                "shadow_searches_total",
+                "Number of search requests mirrored to the shadow target, by verdict of the \
+                 comparison with the primary search.",
+                "quickwit_search",
+                ["verdict"],
+            ),
+            shadow_search_duration_secs: new_histogram_vec(
+                "shadow_search_duration_secs",
+                "Duration of the primary and shadow searches of the mirrored search requests.",
+                "quickwit_search",
+                ["search"],
+            ),
         }
     }
 }
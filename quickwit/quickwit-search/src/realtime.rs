@@ -0,0 +1,618 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Realtime search over the documents that were ingested but not published yet.
+//!
+//! The ingest API source truncates the ingest queues only once the documents it read from them
+//! have been published, so the documents of the queues located after the position recorded in
+//! the checkpoint of the source are the in-flight documents. The root searcher fetches those
+//! documents from every ingester, indexes them in an ephemeral in-RAM index, and searches that
+//! index as if it were an additional split.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::future::join_all;
+use quickwit_doc_mapper::DocMapper;
+use quickwit_ingest::{
+    DocCommand, FetchRequest, IngestService, IngestServiceClient, IngestServiceError, IngesterPool,
+};
+use quickwit_metastore::checkpoint::{PartitionId, Position, SourceCheckpoint};
+use quickwit_proto::{
+    FetchDocsResponse, LeafSearchResponse, PartialHit, SearchRequest, SplitIdAndFooterOffsets,
+};
+use quickwit_query::{get_quickwit_fastfield_normalizer_manager, get_quickwit_tokenizer_manager};
+use tantivy::time::OffsetDateTime;
+use tantivy::{Index, IndexBuilder};
+use tracing::{debug, warn};
+
+use crate::fetch_docs::fetch_docs_in_index;
+use crate::leaf::leaf_search_index;
+use crate::service::SearcherContext;
+use crate::SearchError;
+
+/// Split ID assigned to the partial hits originating from the realtime index.
+pub(crate) const REALTIME_SPLIT_ID: &str = "_realtime";
+
+/// Maximum number of bytes of in-flight documents fetched from a single ingester. When the
+/// in-flight documents of an ingester exceed this limit, only the most recent ones are searched.
+const MAX_IN_FLIGHT_NUM_BYTES_PER_INGESTER: usize = 20_000_000;
+
+/// The ingesters skip the documents appended before the start of the searched time range, minus
+/// this margin, which absorbs the clock skew between the clients, the ingesters, and the searcher.
+const APPEND_TIMESTAMP_MARGIN_SECS: i64 = 60;
+
+/// Memory budget of the writer building the realtime index. This is the minimum allowed by
+/// tantivy.
+const REALTIME_INDEX_WRITER_MEMORY_BUDGET: usize = 15_000_000;
+
+/// Ephemeral in-RAM index holding the in-flight documents of an index.
+pub(crate) struct RealtimeIndex {
+    index: Index,
+    split: SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    errors: Vec<String>,
+}
+
+impl RealtimeIndex {
+    /// Fetches the in-flight documents of the index targeted by `search_request` from all the
+    /// ingesters of the pool and indexes them in RAM. The documents up to the positions of
+    /// `published_checkpoint`, the checkpoint of the ingest API source, are already searchable in
+    /// the published splits and are skipped, and so are the documents appended before the start
+    /// of the searched time range. Ingesters that cannot be reached are reported in
+    /// [`RealtimeIndex::errors`] instead of failing the search.
+    pub async fn build(
+        ingester_pool: &IngesterPool,
+        search_request: &SearchRequest,
+        published_checkpoint: &SourceCheckpoint,
+        doc_mapper: Arc<dyn DocMapper>,
+    ) -> crate::Result<Self> {
+        let append_start_timestamp_opt = realtime_start_timestamp(
+            search_request,
+            doc_mapper.as_ref(),
+            OffsetDateTime::now_utc().unix_timestamp(),
+        )
+        .map(|start_timestamp| start_timestamp.saturating_sub(APPEND_TIMESTAMP_MARGIN_SECS));
+        let (json_docs, errors) = fetch_in_flight_docs(
+            ingester_pool,
+            &search_request.index_id,
+            published_checkpoint,
+            append_start_timestamp_opt,
+        )
+        .await;
+        let doc_mapper_clone = doc_mapper.clone();
+        let index = crate::run_cpu_intensive(move || {
+            build_realtime_index(json_docs, doc_mapper_clone.as_ref())
+        })
+        .await
+        .map_err(|_| {
+            SearchError::InternalError("Building the realtime index panicked.".to_string())
+        })??;
+        let split = SplitIdAndFooterOffsets {
+            split_id: REALTIME_SPLIT_ID.to_string(),
+            create_timestamp: Some(OffsetDateTime::now_utc().unix_timestamp()),
+            ..Default::default()
+        };
+        Ok(Self {
+            index,
+            split,
+            doc_mapper,
+            errors,
+        })
+    }
+
+    /// Returns the errors encountered while fetching the in-flight documents.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Searches the realtime index. Like the leaf requests sent to the searchers, the request is
+    /// rewritten to return the first `start_offset + max_hits` hits. When the index has a
+    /// timestamp field, the search is further restricted to the realtime window.
+    pub async fn leaf_search(
+        &self,
+        searcher_context: &SearcherContext,
+        search_request: &SearchRequest,
+    ) -> crate::Result<LeafSearchResponse> {
+        let mut realtime_search_request = search_request.clone();
+        realtime_search_request.start_offset = 0;
+        realtime_search_request.max_hits += search_request.start_offset;
+
+        if let Some(start_timestamp) = realtime_start_timestamp(
+            search_request,
+            self.doc_mapper.as_ref(),
+            OffsetDateTime::now_utc().unix_timestamp(),
+        ) {
+            realtime_search_request.start_timestamp = Some(start_timestamp);
+        }
+        leaf_search_index(
+            searcher_context,
+            &realtime_search_request,
+            self.index.clone(),
+            &self.split,
+            self.doc_mapper.clone(),
+            None,
+        )
+        .await
+    }
+
+    /// Fetches the documents of partial hits returned by [`RealtimeIndex::leaf_search`].
+    pub async fn fetch_docs(
+        &self,
+        partial_hits: Vec<PartialHit>,
+        search_request_opt: Option<&SearchRequest>,
+    ) -> crate::Result<FetchDocsResponse> {
+        let fetch_docs_response = fetch_docs_in_index(
+            partial_hits,
+            self.index.clone(),
+            &self.split,
+            self.doc_mapper.clone(),
+            search_request_opt,
+        )
+        .await?;
+        Ok(fetch_docs_response)
+    }
+}
+
+/// Returns the start of the time range searched in the realtime index, that is the latest of the
+/// start timestamp of the request and the start of the realtime window. Returns `None` when the
+/// index has no timestamp field or the request no time bound.
+fn realtime_start_timestamp(
+    search_request: &SearchRequest,
+    doc_mapper: &dyn DocMapper,
+    now_timestamp: i64,
+) -> Option<i64> {
+    doc_mapper.timestamp_field_name()?;
+
+    let window_start_timestamp_opt = search_request
+        .realtime_window_secs
+        .map(|window_secs| realtime_window_start_timestamp(now_timestamp, window_secs));
+    match (search_request.start_timestamp, window_start_timestamp_opt) {
+        (Some(start_timestamp), Some(window_start_timestamp)) => {
+            Some(start_timestamp.max(window_start_timestamp))
+        }
+        (start_timestamp_opt, window_start_timestamp_opt) => {
+            start_timestamp_opt.or(window_start_timestamp_opt)
+        }
+    }
+}
+
+fn realtime_window_start_timestamp(now_timestamp: i64, realtime_window_secs: u64) -> i64 {
+    let realtime_window_secs = i64::try_from(realtime_window_secs).unwrap_or(i64::MAX);
+    now_timestamp.saturating_sub(realtime_window_secs)
+}
+
+/// Fetches the in-flight documents of `index_id` from all the ingesters of the pool.
+async fn fetch_in_flight_docs(
+    ingester_pool: &IngesterPool,
+    index_id: &str,
+    published_checkpoint: &SourceCheckpoint,
+    append_start_timestamp_opt: Option<i64>,
+) -> (Vec<Bytes>, Vec<String>) {
+    let ingesters = ingester_pool.all().await;
+    let fetch_futures = ingesters
+        .into_iter()
+        .map(|(grpc_addr, ingest_service)| async move {
+            let fetch_result = fetch_in_flight_docs_from_ingester(
+                ingest_service,
+                index_id,
+                published_checkpoint,
+                append_start_timestamp_opt,
+                MAX_IN_FLIGHT_NUM_BYTES_PER_INGESTER,
+            )
+            .await;
+            (grpc_addr, fetch_result)
+        });
+    let mut json_docs = Vec::new();
+    let mut errors = Vec::new();
+
+    for (grpc_addr, fetch_result) in join_all(fetch_futures).await {
+        match fetch_result {
+            Ok(ingester_json_docs) => json_docs.extend(ingester_json_docs),
+            // The ingester has not received any document for this index.
+            Err(IngestServiceError::IndexNotFound { .. }) => {}
+            Err(error) => {
+                warn!(ingester = %grpc_addr, error = ?error, "Failed to fetch in-flight documents.");
+                errors.push(in_flight_docs_error(grpc_addr, error));
+            }
+        }
+    }
+    (json_docs, errors)
+}
+
+fn in_flight_docs_error(grpc_addr: SocketAddr, error: IngestServiceError) -> String {
+    format!("Failed to fetch in-flight documents from ingester `{grpc_addr}`: {error}")
+}
+
+/// Fetches the documents of the queue of `index_id` located after the position of the ingester in
+/// `published_checkpoint`. The ingester skips the documents appended before
+/// `append_start_timestamp_opt` and only returns the most recent documents fitting in
+/// `max_num_bytes`, so a single request is needed.
+async fn fetch_in_flight_docs_from_ingester(
+    mut ingest_service: IngestServiceClient,
+    index_id: &str,
+    published_checkpoint: &SourceCheckpoint,
+    append_start_timestamp_opt: Option<i64>,
+    max_num_bytes: usize,
+) -> Result<Vec<Bytes>, IngestServiceError> {
+    // The partition of the ingester in the checkpoint is only known once it responded, so the
+    // published documents are filtered out below. Since they precede the unpublished ones, they
+    // can only take up the budget when all the unpublished documents fit in it.
+    let fetch_request = FetchRequest {
+        index_id: index_id.to_string(),
+        start_after: None,
+        num_bytes_limit: Some(max_num_bytes as u64),
+        start_timestamp: append_start_timestamp_opt,
+        keep_most_recent: true,
+    };
+    let fetch_response = ingest_service.fetch(fetch_request).await?;
+    let (Some(first_position), Some(doc_batch)) =
+        (fetch_response.first_position, fetch_response.doc_batch) else {
+        return Ok(Vec::new());
+    };
+    let published_position_opt = fetch_response
+        .partition_id
+        .and_then(|partition_id| published_position(published_checkpoint, partition_id));
+
+    let json_docs = (first_position..)
+        .zip(doc_batch.iter())
+        // Published documents are already searchable in the splits.
+        .filter(|(position, _)| {
+            published_position_opt.map_or(true, |published_position| *position > published_position)
+        })
+        .filter_map(|(_, doc_command)| match doc_command {
+            DocCommand::Ingest { payload } => Some(payload),
+            DocCommand::Commit => None,
+        })
+        .collect();
+    Ok(json_docs)
+}
+
+/// Returns the position up to which the ingest API source published the documents of the queues of
+/// the partition.
+fn published_position(
+    published_checkpoint: &SourceCheckpoint,
+    partition_id: String,
+) -> Option<u64> {
+    let partition_id = PartitionId::from(partition_id);
+    match published_checkpoint.position_for_partition(&partition_id) {
+        Some(Position::Offset(offset)) => offset.parse::<u64>().ok(),
+        _ => None,
+    }
+}
+
+fn build_realtime_index(json_docs: Vec<Bytes>, doc_mapper: &dyn DocMapper) -> crate::Result<Index> {
+    let index = IndexBuilder::new()
+        .schema(doc_mapper.schema())
+        .tokenizers(get_quickwit_tokenizer_manager().clone())
+        .fast_field_tokenizers(get_quickwit_fastfield_normalizer_manager().clone())
+        .create_in_ram()?;
+    let mut index_writer = index.writer_with_num_threads(1, REALTIME_INDEX_WRITER_MEMORY_BUDGET)?;
+    let mut num_invalid_docs = 0;
+
+    for json_doc in json_docs {
+        // Invalid documents are dropped by the indexing pipeline as well.
+        match doc_mapper.doc_from_json_bytes(&json_doc) {
+            Ok((_partition, doc)) => {
+                index_writer.add_document(doc)?;
+            }
+            Err(_) => num_invalid_docs += 1,
+        }
+    }
+    index_writer.commit()?;
+
+    if num_invalid_docs > 0 {
+        debug!(
+            num_invalid_docs = num_invalid_docs,
+            "Skipped invalid in-flight documents."
+        );
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::SearcherConfig;
+    use quickwit_doc_mapper::DefaultDocMapper;
+    use quickwit_ingest::{DocBatchBuilder, FetchResponse};
+    use quickwit_proto::qast_helper;
+    use serde_json::json;
+
+    use super::*;
+
+    fn doc_mapper_for_test() -> Arc<dyn DocMapper> {
+        let doc_mapper: DefaultDocMapper = serde_json::from_value(json!({
+            "field_mappings": [
+                { "name": "body", "type": "text", "stored": true },
+                { "name": "ts", "type": "datetime", "fast": true, "input_formats": ["unix_timestamp"] }
+            ],
+            "timestamp_field": "ts",
+            "mode": "lenient"
+        }))
+        .unwrap();
+        Arc::new(doc_mapper)
+    }
+
+    fn ingester_pool_for_test(json_docs: Vec<serde_json::Value>) -> IngesterPool {
+        let mut mock_ingest_service = IngestServiceClient::mock();
+        mock_ingest_service
+            .expect_fetch()
+            .returning(move |fetch_request| {
+                assert_eq!(fetch_request.index_id, "test-index");
+                assert_eq!(
+                    fetch_request.num_bytes_limit,
+                    Some(MAX_IN_FLIGHT_NUM_BYTES_PER_INGESTER as u64)
+                );
+                assert!(fetch_request.keep_most_recent);
+
+                // The request has a realtime window of 60 seconds.
+                let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+                let start_timestamp = fetch_request.start_timestamp.unwrap();
+                assert!(start_timestamp <= now_timestamp - 60 - APPEND_TIMESTAMP_MARGIN_SECS);
+                assert!(start_timestamp >= now_timestamp - 70 - APPEND_TIMESTAMP_MARGIN_SECS);
+
+                let mut doc_batch_builder = DocBatchBuilder::new("test-index".to_string());
+                for json_doc in &json_docs {
+                    doc_batch_builder.ingest_doc(Bytes::from(json_doc.to_string()));
+                }
+                doc_batch_builder.commit();
+                Ok(FetchResponse {
+                    first_position: Some(0),
+                    doc_batch: Some(doc_batch_builder.build()),
+                    partition_id: None,
+                })
+            });
+        let grpc_addr: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        IngesterPool::from_iter([(grpc_addr, IngestServiceClient::from(mock_ingest_service))])
+    }
+
+    #[tokio::test]
+    async fn test_realtime_index_search() {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let ingester_pool = ingester_pool_for_test(vec![
+            json!({"body": "hello realtime", "ts": now_timestamp}),
+            json!({"body": "hello again", "ts": now_timestamp - 10}),
+            json!({"body": "hello from the past", "ts": now_timestamp - 3_600}),
+            json!({"body": "goodbye", "ts": now_timestamp}),
+            json!("not an object"),
+        ]);
+        let search_request = SearchRequest {
+            index_id: "test-index".to_string(),
+            query_ast: qast_helper("hello", &["body"]),
+            max_hits: 10,
+            realtime_window_secs: Some(60),
+            snippet_fields: vec!["body".to_string()],
+            ..Default::default()
+        };
+        let doc_mapper = doc_mapper_for_test();
+        let realtime_index = RealtimeIndex::build(
+            &ingester_pool,
+            &search_request,
+            &SourceCheckpoint::default(),
+            doc_mapper,
+        )
+        .await
+        .unwrap();
+        assert!(realtime_index.errors().is_empty());
+
+        let searcher_context = SearcherContext::new(SearcherConfig::default());
+        let leaf_search_response = realtime_index
+            .leaf_search(&searcher_context, &search_request)
+            .await
+            .unwrap();
+        assert_eq!(leaf_search_response.num_hits, 2);
+        assert!(leaf_search_response
+            .partial_hits
+            .iter()
+            .all(|partial_hit| partial_hit.split_id == REALTIME_SPLIT_ID));
+
+        let fetch_docs_response = realtime_index
+            .fetch_docs(leaf_search_response.partial_hits, Some(&search_request))
+            .await
+            .unwrap();
+        assert_eq!(fetch_docs_response.hits.len(), 2);
+
+        let mut bodies: Vec<String> = fetch_docs_response
+            .hits
+            .iter()
+            .map(|leaf_hit| {
+                let doc: serde_json::Value = serde_json::from_str(&leaf_hit.leaf_json).unwrap();
+                doc["body"].as_str().unwrap().to_string()
+            })
+            .collect();
+        bodies.sort();
+        assert_eq!(bodies, ["hello again", "hello realtime"]);
+        assert!(fetch_docs_response
+            .hits
+            .iter()
+            .all(|leaf_hit| leaf_hit.leaf_snippet_json.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_realtime_index_ingester_errors() {
+        let mut mock_ingest_service = IngestServiceClient::mock();
+        mock_ingest_service
+            .expect_fetch()
+            .returning(|fetch_request| {
+                Err(IngestServiceError::IndexNotFound {
+                    index_id: fetch_request.index_id,
+                })
+            });
+        let mut failing_mock_ingest_service = IngestServiceClient::mock();
+        failing_mock_ingest_service
+            .expect_fetch()
+            .returning(|_| Err(IngestServiceError::Unavailable));
+        let ingester_pool = IngesterPool::from_iter([
+            (
+                "127.0.0.1:1000".parse().unwrap(),
+                IngestServiceClient::from(mock_ingest_service),
+            ),
+            (
+                "127.0.0.1:1001".parse().unwrap(),
+                IngestServiceClient::from(failing_mock_ingest_service),
+            ),
+        ]);
+        let search_request = SearchRequest {
+            index_id: "test-index".to_string(),
+            realtime_window_secs: Some(60),
+            ..Default::default()
+        };
+        let realtime_index = RealtimeIndex::build(
+            &ingester_pool,
+            &search_request,
+            &SourceCheckpoint::default(),
+            doc_mapper_for_test(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(realtime_index.errors().len(), 1);
+        assert!(realtime_index.errors()[0].contains("127.0.0.1:1001"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_in_flight_docs_from_ingester() {
+        // The queue holds 10 documents of 10 bytes. Like the ingesters, the mock returns the most
+        // recent ones fitting in the limit.
+        let json_docs: Vec<Bytes> = (0..10)
+            .map(|doc_id| Bytes::from(format!("{{\"id\":{doc_id:03}}}")))
+            .collect();
+        let json_docs_clone = json_docs.clone();
+        let mut mock_ingest_service = IngestServiceClient::mock();
+        mock_ingest_service
+            .expect_fetch()
+            .returning(move |fetch_request| {
+                assert!(fetch_request.start_after.is_none());
+                assert_eq!(fetch_request.start_timestamp, Some(1_000));
+                assert!(fetch_request.keep_most_recent);
+
+                let num_docs = (fetch_request.num_bytes_limit.unwrap() / 10).min(10) as usize;
+                let first_position = 10 - num_docs;
+                let mut doc_batch_builder = DocBatchBuilder::new("test-index".to_string());
+                for json_doc in json_docs_clone.iter().skip(first_position) {
+                    doc_batch_builder.ingest_doc(json_doc.clone());
+                }
+                Ok(FetchResponse {
+                    first_position: Some(first_position as u64),
+                    doc_batch: Some(doc_batch_builder.build()),
+                    partition_id: Some("test-partition".to_string()),
+                })
+            });
+        let ingest_service = IngestServiceClient::from(mock_ingest_service);
+        let published_checkpoint = |position: u64| {
+            SourceCheckpoint::from_iter([(
+                PartitionId::from("test-partition"),
+                Position::from(position),
+            )])
+        };
+        // The unpublished documents exceed the limit: only the most recent ones are kept.
+        let fetched_json_docs = fetch_in_flight_docs_from_ingester(
+            ingest_service.clone(),
+            "test-index",
+            &published_checkpoint(2),
+            Some(1_000),
+            50,
+        )
+        .await
+        .unwrap();
+        assert_eq!(fetched_json_docs, json_docs[5..]);
+
+        // The published documents are skipped.
+        let fetched_json_docs = fetch_in_flight_docs_from_ingester(
+            ingest_service.clone(),
+            "test-index",
+            &published_checkpoint(5),
+            Some(1_000),
+            MAX_IN_FLIGHT_NUM_BYTES_PER_INGESTER,
+        )
+        .await
+        .unwrap();
+        assert_eq!(fetched_json_docs, json_docs[6..]);
+
+        // Nothing was published yet.
+        let fetched_json_docs = fetch_in_flight_docs_from_ingester(
+            ingest_service,
+            "test-index",
+            &SourceCheckpoint::default(),
+            Some(1_000),
+            MAX_IN_FLIGHT_NUM_BYTES_PER_INGESTER,
+        )
+        .await
+        .unwrap();
+        assert_eq!(fetched_json_docs, json_docs);
+    }
+
+    #[test]
+    fn test_realtime_start_timestamp() {
+        let doc_mapper = doc_mapper_for_test();
+        let search_request = SearchRequest {
+            realtime_window_secs: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(
+            realtime_start_timestamp(&search_request, doc_mapper.as_ref(), 1_000),
+            Some(940)
+        );
+        let search_request = SearchRequest {
+            start_timestamp: Some(900),
+            realtime_window_secs: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(
+            realtime_start_timestamp(&search_request, doc_mapper.as_ref(), 1_000),
+            Some(940)
+        );
+        let search_request = SearchRequest {
+            start_timestamp: Some(950),
+            realtime_window_secs: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(
+            realtime_start_timestamp(&search_request, doc_mapper.as_ref(), 1_000),
+            Some(950)
+        );
+        let search_request = SearchRequest::default();
+        assert_eq!(
+            realtime_start_timestamp(&search_request, doc_mapper.as_ref(), 1_000),
+            None
+        );
+        let doc_mapper_without_timestamp: DefaultDocMapper = serde_json::from_value(json!({
+            "field_mappings": [{ "name": "body", "type": "text" }]
+        }))
+        .unwrap();
+        let search_request = SearchRequest {
+            start_timestamp: Some(950),
+            realtime_window_secs: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(
+            realtime_start_timestamp(&search_request, &doc_mapper_without_timestamp, 1_000),
+            None
+        );
+    }
+
+    #[test]
+    fn test_realtime_window_start_timestamp() {
+        assert_eq!(realtime_window_start_timestamp(1_000, 60), 940);
+        assert_eq!(
+            realtime_window_start_timestamp(1_000, u64::MAX),
+            1_000 - i64::MAX
+        );
+    }
+}
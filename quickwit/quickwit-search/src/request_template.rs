@@ -0,0 +1,446 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Guardrails enforced on the search requests authenticated with the API key of a request
+//! template.
+//!
+//! The search service handed to the requests presenting an API key is wrapped into a
+//! [`GuardedSearchService`] that rejects the requests exceeding the guardrails of the template
+//! bound to the key, and adds the filters of the template to their query.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_config::RequestTemplateConfig;
+use quickwit_proto::{
+    query_ast_from_user_text, FetchCachedSplitFooterRequest, FetchCachedSplitFooterResponse,
+    FetchDocsRequest, FetchDocsResponse, LeafListTermsRequest, LeafListTermsResponse,
+    LeafSearchRequest, LeafSearchResponse, LeafSearchStreamRequest, LeafSearchStreamResponse,
+    ListTermsRequest, ListTermsResponse, SearchRequest, SearchResponse, SearchStreamRequest,
+    WarmupSplitsRequest, WarmupSplitsResponse,
+};
+use quickwit_query::query_ast::{
+    query_ast_from_json, query_ast_to_json, BoolQuery, QueryAst, QueryAstVisitor, TermsLookupQuery,
+};
+use tantivy::time::OffsetDateTime;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::hit_join::HitJoin;
+use crate::{SearchError, SearchService};
+
+/// Request templates keyed by the API key they are bound to.
+#[derive(Clone, Default)]
+pub struct RequestTemplates {
+    templates_per_api_key: Arc<HashMap<String, Arc<RequestTemplateConfig>>>,
+}
+
+impl RequestTemplates {
+    pub fn new(request_template_configs: &[RequestTemplateConfig]) -> Self {
+        let templates_per_api_key = request_template_configs
+            .iter()
+            .map(|request_template_config| {
+                (
+                    request_template_config.api_key.clone(),
+                    Arc::new(request_template_config.clone()),
+                )
+            })
+            .collect();
+        RequestTemplates {
+            templates_per_api_key: Arc::new(templates_per_api_key),
+        }
+    }
+
+    /// Returns the search service serving the requests authenticated with `api_key`, or `None`
+    /// if no request template is bound to the key.
+    pub fn guard_search_service(
+        &self,
+        api_key: &str,
+        search_service: Arc<dyn SearchService>,
+    ) -> Option<Arc<dyn SearchService>> {
+        let request_template = self.templates_per_api_key.get(api_key)?.clone();
+        let guarded_search_service = GuardedSearchService {
+            search_service,
+            request_template,
+        };
+        Some(Arc::new(guarded_search_service))
+    }
+}
+
+/// Search service enforcing the guardrails of a request template on the root requests. The leaf
+/// requests are issued by the root searchers and forwarded as is.
+struct GuardedSearchService {
+    search_service: Arc<dyn SearchService>,
+    request_template: Arc<RequestTemplateConfig>,
+}
+
+impl GuardedSearchService {
+    fn check_index_id(&self, index_id: &str) -> crate::Result<()> {
+        let is_allowed = self
+            .request_template
+            .index_id_patterns
+            .iter()
+            .any(|index_id_pattern| index_id_matches_pattern(index_id, index_id_pattern));
+        if !is_allowed {
+            return Err(SearchError::InvalidArgument(format!(
+                "The API key is not allowed to search index `{index_id}`."
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks the time range of the request, restricting it to the maximum time range of the
+    /// template if it misses a bound.
+    fn check_time_range(
+        &self,
+        start_timestamp: &mut Option<i64>,
+        end_timestamp: &mut Option<i64>,
+    ) -> crate::Result<()> {
+        let Some(max_time_range_secs) = self.request_template.max_time_range_secs else {
+            return Ok(());
+        };
+        let max_time_range_secs = max_time_range_secs.get() as i64;
+        let end = end_timestamp.unwrap_or_else(|| OffsetDateTime::now_utc().unix_timestamp());
+        let start = start_timestamp.unwrap_or(end - max_time_range_secs);
+        if end - start > max_time_range_secs {
+            return Err(SearchError::InvalidArgument(format!(
+                "The time range of the request ({} seconds) exceeds the maximum time range \
+                 allowed for the API key ({max_time_range_secs} seconds).",
+                end - start
+            )));
+        }
+        *start_timestamp = Some(start);
+        *end_timestamp = Some(end);
+        Ok(())
+    }
+
+    fn check_max_hits(&self, max_hits: u64) -> crate::Result<()> {
+        match self.request_template.max_hits {
+            Some(max_allowed_hits) if max_hits > max_allowed_hits => {
+                Err(SearchError::InvalidArgument(format!(
+                    "The request asks for {max_hits} hits, more than the {max_allowed_hits} hits \
+                     allowed for the API key."
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the indexes read by the query, and adds the filters of the template to it.
+    fn guard_query_ast(&self, query_ast_json: &str) -> crate::Result<String> {
+        let query_ast: QueryAst = query_ast_from_json(query_ast_json)
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        let mut terms_lookup_index_ids = TermsLookupIndexIds::default();
+        let _: Result<(), std::convert::Infallible> = terms_lookup_index_ids.visit(&query_ast);
+        for index_id in terms_lookup_index_ids.index_ids {
+            self.check_index_id(index_id)?;
+        }
+        if self.request_template.filters.is_empty() {
+            return Ok(query_ast_json.to_string());
+        }
+        let filters: Vec<QueryAst> = self
+            .request_template
+            .filters
+            .iter()
+            .map(|filter| query_ast_from_user_text(filter, None))
+            .collect();
+        let guarded_query_ast: QueryAst = BoolQuery {
+            must: vec![query_ast],
+            filter: filters,
+            ..Default::default()
+        }
+        .into();
+        query_ast_to_json(&guarded_query_ast)
+            .map_err(|err| SearchError::InternalError(err.to_string()))
+    }
+}
+
+#[derive(Default)]
+struct TermsLookupIndexIds<'a> {
+    index_ids: Vec<&'a str>,
+}
+
+impl<'a> QueryAstVisitor<'a> for TermsLookupIndexIds<'a> {
+    type Err = std::convert::Infallible;
+
+    fn visit_terms_lookup(
+        &mut self,
+        terms_lookup_query: &'a TermsLookupQuery,
+    ) -> Result<(), Self::Err> {
+        self.index_ids.push(&terms_lookup_query.index_id);
+        Ok(())
+    }
+}
+
+/// Matches an index ID against a pattern in which `*` stands for any sequence of characters.
+fn index_id_matches_pattern(index_id: &str, index_id_pattern: &str) -> bool {
+    let mut pattern_parts = index_id_pattern.split('*');
+    let prefix = pattern_parts.next().unwrap_or_default();
+    let Some(mut remaining_index_id) = index_id.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut pattern_parts: Vec<&str> = pattern_parts.collect();
+    let Some(suffix) = pattern_parts.pop() else {
+        // The pattern has no wildcard.
+        return remaining_index_id.is_empty();
+    };
+    for pattern_part in pattern_parts {
+        let Some(position) = remaining_index_id.find(pattern_part) else {
+            return false;
+        };
+        remaining_index_id = &remaining_index_id[position + pattern_part.len()..];
+    }
+    remaining_index_id.ends_with(suffix)
+}
+
+#[async_trait]
+impl SearchService for GuardedSearchService {
+    async fn root_search(&self, mut request: SearchRequest) -> crate::Result<SearchResponse> {
+        self.check_index_id(&request.index_id)?;
+        self.check_time_range(&mut request.start_timestamp, &mut request.end_timestamp)?;
+        self.check_max_hits(request.max_hits)?;
+        if let Some(hit_join_json) = &request.join {
+            if let Some(joined_index_id) = HitJoin::parse(hit_join_json)?.index {
+                self.check_index_id(&joined_index_id)?;
+            }
+        }
+        request.query_ast = self.guard_query_ast(&request.query_ast)?;
+        self.search_service.root_search(request).await
+    }
+
+    async fn leaf_search(&self, request: LeafSearchRequest) -> crate::Result<LeafSearchResponse> {
+        self.search_service.leaf_search(request).await
+    }
+
+    async fn fetch_docs(&self, request: FetchDocsRequest) -> crate::Result<FetchDocsResponse> {
+        self.search_service.fetch_docs(request).await
+    }
+
+    async fn root_search_stream(
+        &self,
+        mut request: SearchStreamRequest,
+    ) -> crate::Result<Pin<Box<dyn futures::Stream<Item = crate::Result<Bytes>> + Send>>> {
+        self.check_index_id(&request.index_id)?;
+        self.check_time_range(&mut request.start_timestamp, &mut request.end_timestamp)?;
+        request.query_ast = self.guard_query_ast(&request.query_ast)?;
+        self.search_service.root_search_stream(request).await
+    }
+
+    async fn leaf_search_stream(
+        &self,
+        request: LeafSearchStreamRequest,
+    ) -> crate::Result<UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>>> {
+        self.search_service.leaf_search_stream(request).await
+    }
+
+    async fn root_list_terms(
+        &self,
+        mut request: ListTermsRequest,
+    ) -> crate::Result<ListTermsResponse> {
+        self.check_index_id(&request.index_id)?;
+        self.check_time_range(&mut request.start_timestamp, &mut request.end_timestamp)?;
+        if !self.request_template.filters.is_empty() {
+            // The terms of a field cannot be restricted to the documents matching the filters.
+            return Err(SearchError::InvalidArgument(
+                "The API key is not allowed to list the terms of a field.".to_string(),
+            ));
+        }
+        if let Some(max_hits) = request.max_hits {
+            self.check_max_hits(max_hits)?;
+        } else {
+            request.max_hits = self.request_template.max_hits;
+        }
+        self.search_service.root_list_terms(request).await
+    }
+
+    async fn leaf_list_terms(
+        &self,
+        request: LeafListTermsRequest,
+    ) -> crate::Result<LeafListTermsResponse> {
+        self.search_service.leaf_list_terms(request).await
+    }
+
+    async fn warmup_splits(
+        &self,
+        request: WarmupSplitsRequest,
+    ) -> crate::Result<WarmupSplitsResponse> {
+        self.search_service.warmup_splits(request).await
+    }
+
+    async fn fetch_cached_split_footer(
+        &self,
+        request: FetchCachedSplitFooterRequest,
+    ) -> crate::Result<FetchCachedSplitFooterResponse> {
+        self.search_service.fetch_cached_split_footer(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use super::*;
+    use crate::MockSearchService;
+
+    fn request_templates_for_test() -> RequestTemplates {
+        RequestTemplates::new(&[RequestTemplateConfig {
+            api_key: "acme-analyst-key".to_string(),
+            index_id_patterns: vec!["acme-logs-*".to_string(), "acme-traces".to_string()],
+            max_time_range_secs: NonZeroU64::new(3600),
+            max_hits: Some(100),
+            filters: vec!["tenant_id:acme".to_string()],
+        }])
+    }
+
+    fn search_request_for_test(index_id: &str) -> SearchRequest {
+        SearchRequest {
+            index_id: index_id.to_string(),
+            query_ast: query_ast_to_json(&QueryAst::MatchAll).unwrap(),
+            start_timestamp: Some(10_000),
+            end_timestamp: Some(12_000),
+            max_hits: 20,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_index_id_matches_pattern() {
+        assert!(index_id_matches_pattern("acme-logs", "acme-logs"));
+        assert!(!index_id_matches_pattern("acme-logs-v2", "acme-logs"));
+        assert!(index_id_matches_pattern("acme-logs-v2", "acme-logs-*"));
+        assert!(index_id_matches_pattern("acme-logs-", "acme-logs-*"));
+        assert!(!index_id_matches_pattern("acme-traces", "acme-logs-*"));
+        assert!(index_id_matches_pattern("acme-prod-logs", "acme-*-logs"));
+        assert!(!index_id_matches_pattern("acme-logs", "acme-*-logs"));
+        assert!(index_id_matches_pattern("any-index", "*"));
+    }
+
+    #[test]
+    fn test_guarded_search_service_rejects_unknown_api_key() {
+        let request_templates = request_templates_for_test();
+        let search_service: Arc<dyn SearchService> = Arc::new(MockSearchService::new());
+        assert!(request_templates
+            .guard_search_service("unknown-key", search_service)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_guarded_search_service_rejects_requests_exceeding_guardrails() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().never();
+        let guarded_search_service = request_templates_for_test()
+            .guard_search_service("acme-analyst-key", Arc::new(mock_search_service))
+            .unwrap();
+
+        let search_error = guarded_search_service
+            .root_search(search_request_for_test("globex-logs"))
+            .await
+            .unwrap_err();
+        assert!(search_error
+            .to_string()
+            .contains("not allowed to search index `globex-logs`"));
+
+        let search_request = SearchRequest {
+            max_hits: 1_000,
+            ..search_request_for_test("acme-logs-v2")
+        };
+        let search_error = guarded_search_service
+            .root_search(search_request)
+            .await
+            .unwrap_err();
+        assert!(search_error.to_string().contains("asks for 1000 hits"));
+
+        let search_request = SearchRequest {
+            start_timestamp: Some(0),
+            ..search_request_for_test("acme-logs-v2")
+        };
+        let search_error = guarded_search_service
+            .root_search(search_request)
+            .await
+            .unwrap_err();
+        assert!(search_error
+            .to_string()
+            .contains("exceeds the maximum time range"));
+
+        let search_request = SearchRequest {
+            join: Some(r#"{"field": "user_id", "index": "globex-users"}"#.to_string()),
+            ..search_request_for_test("acme-logs-v2")
+        };
+        let search_error = guarded_search_service
+            .root_search(search_request)
+            .await
+            .unwrap_err();
+        assert!(search_error
+            .to_string()
+            .contains("not allowed to search index `globex-users`"));
+
+        let terms_lookup_query: QueryAst = TermsLookupQuery {
+            field: "user_id".to_string(),
+            index_id: "globex-users".to_string(),
+            doc_id: "1".to_string(),
+            path: "user_ids".to_string(),
+        }
+        .into();
+        let search_request = SearchRequest {
+            query_ast: query_ast_to_json(&terms_lookup_query).unwrap(),
+            ..search_request_for_test("acme-logs-v2")
+        };
+        let search_error = guarded_search_service
+            .root_search(search_request)
+            .await
+            .unwrap_err();
+        assert!(search_error
+            .to_string()
+            .contains("not allowed to search index `globex-users`"));
+    }
+
+    #[tokio::test]
+    async fn test_guarded_search_service_locks_requests() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().times(1).returning(
+            |search_request: SearchRequest| {
+                assert_eq!(search_request.index_id, "acme-traces");
+                assert_eq!(search_request.start_timestamp, Some(8_400));
+                assert_eq!(search_request.end_timestamp, Some(12_000));
+                let query_ast = query_ast_from_json(&search_request.query_ast).unwrap();
+                let expected_query_ast: QueryAst = BoolQuery {
+                    must: vec![QueryAst::MatchAll],
+                    filter: vec![query_ast_from_user_text("tenant_id:acme", None)],
+                    ..Default::default()
+                }
+                .into();
+                assert_eq!(query_ast, expected_query_ast);
+                Ok(SearchResponse::default())
+            },
+        );
+        let guarded_search_service = request_templates_for_test()
+            .guard_search_service("acme-analyst-key", Arc::new(mock_search_service))
+            .unwrap();
+        let search_request = SearchRequest {
+            start_timestamp: None,
+            ..search_request_for_test("acme-traces")
+        };
+        guarded_search_service
+            .root_search(search_request)
+            .await
+            .unwrap();
+    }
+}
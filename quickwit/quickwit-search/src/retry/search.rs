@@ -73,6 +73,7 @@ mod tests {
             }),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            bypass_caches: false,
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
@@ -80,6 +81,7 @@ mod tests {
                     split_footer_start: 0,
                     timestamp_start: None,
                     timestamp_end: None,
+                    create_timestamp: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -87,6 +89,7 @@ mod tests {
                     split_footer_start: 0,
                     timestamp_start: None,
                     timestamp_end: None,
+                    create_timestamp: None,
                 },
             ],
         }
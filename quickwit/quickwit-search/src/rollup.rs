@@ -0,0 +1,965 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Rollups of indexes into coarser aggregate indexes.
+//!
+//! A rollup policy periodically counts the documents of an index per time bucket and per
+//! combination of values of its `group_by` fields, and writes one document per group holding the
+//! count to a rollup index. The janitor computes the counts with [`rollup_aggregation_request`]
+//! and converts the aggregation results into rollup documents with [`rollup_documents`].
+//!
+//! The root searcher answers the aggregations made of date histograms and terms aggregations
+//! over the time ranges already rolled up from the rollup index: the rewritten aggregations sum
+//! the counts held by the rollup documents, which replace the document counts of the buckets.
+
+use std::ops::RangeInclusive;
+
+use quickwit_config::{build_doc_mapper, RollupPolicy};
+use quickwit_metastore::{IndexMetadata, ListSplitsQuery, Metastore, SplitState};
+use quickwit_proto::{IndexUid, SearchRequest, SearchResponse};
+use quickwit_query::query_ast::{query_ast_from_json, query_ast_to_json, QueryAst};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use tantivy::schema::{FieldType, Schema};
+use tracing::warn;
+
+use crate::root::{check_index_is_searchable, refine_start_end_timestamp_from_ast};
+use crate::SearchError;
+
+/// Maximum number of values of a `group_by` field rolled up per time bucket.
+const MAX_NUM_GROUPS: usize = 1_000;
+
+/// Name of the date histogram of the rollup aggregation request.
+const ROLLUP_AGG_NAME: &str = "rollup";
+
+/// Name of the sub-aggregation summing the counts of the rollup documents of a bucket.
+const ROLLUP_COUNT_AGG_NAME: &str = "__rollup_count";
+
+/// Name of the aggregation summing the counts of all the matching rollup documents.
+const ROLLUP_NUM_HITS_AGG_NAME: &str = "__rollup_num_hits";
+
+/// Returns the time range covered by the published splits of the index, in seconds, or `None` if
+/// the index has no published split with a time range.
+pub async fn published_splits_time_range(
+    metastore: &dyn Metastore,
+    index_id: &str,
+) -> crate::Result<Option<RangeInclusive<i64>>> {
+    let index_metadata = metastore.index_metadata(index_id).await?;
+    index_published_splits_time_range(metastore, index_metadata.index_uid).await
+}
+
+async fn index_published_splits_time_range(
+    metastore: &dyn Metastore,
+    index_uid: IndexUid,
+) -> crate::Result<Option<RangeInclusive<i64>>> {
+    let query = ListSplitsQuery::for_index(index_uid).with_split_state(SplitState::Published);
+    let splits = metastore.list_splits(query).await?;
+    let time_range_opt = splits
+        .iter()
+        .filter_map(|split| split.split_metadata.time_range.as_ref())
+        .fold(
+            None,
+            |time_range_opt: Option<RangeInclusive<i64>>, time_range| {
+                Some(match time_range_opt {
+                    Some(current) => {
+                        *current.start().min(time_range.start())
+                            ..=*current.end().max(time_range.end())
+                    }
+                    None => time_range.clone(),
+                })
+            },
+        );
+    Ok(time_range_opt)
+}
+
+/// Builds the aggregation request counting the documents per time bucket and per group of the
+/// rollup policy.
+pub fn rollup_aggregation_request(
+    timestamp_field: &str,
+    rollup_policy: &RollupPolicy,
+) -> crate::Result<String> {
+    let interval_secs = rollup_policy
+        .interval_secs()
+        .map_err(|error| SearchError::InvalidArgument(error.to_string()))?;
+    let mut agg_request = json!({
+        "date_histogram": {
+            "field": timestamp_field,
+            "fixed_interval": format!("{interval_secs}s"),
+            "min_doc_count": 1,
+        }
+    });
+    let mut sub_agg_requests_opt: Option<JsonValue> = None;
+
+    for (group_ord, field) in rollup_policy.group_by().iter().enumerate().rev() {
+        let mut terms_agg_request = json!({
+            "terms": {
+                "field": field,
+                "size": MAX_NUM_GROUPS,
+            }
+        });
+        if let Some(sub_agg_requests) = sub_agg_requests_opt.take() {
+            terms_agg_request["aggs"] = sub_agg_requests;
+        }
+        sub_agg_requests_opt = Some(json!({ group_agg_name(group_ord): terms_agg_request }));
+    }
+    if let Some(sub_agg_requests) = sub_agg_requests_opt {
+        agg_request["aggs"] = sub_agg_requests;
+    }
+    let agg_requests = json!({ ROLLUP_AGG_NAME: agg_request });
+    Ok(serde_json::to_string(&agg_requests)?)
+}
+
+/// Converts the results of the aggregation built by [`rollup_aggregation_request`] into rollup
+/// documents, one per time bucket and group.
+pub fn rollup_documents(
+    aggregation: &str,
+    timestamp_field: &str,
+    rollup_policy: &RollupPolicy,
+) -> crate::Result<Vec<JsonValue>> {
+    let aggregation_json: JsonValue = serde_json::from_str(aggregation)?;
+    let Some(buckets) = aggregation_json
+        .get(ROLLUP_AGG_NAME)
+        .and_then(|agg_result| agg_result.get("buckets"))
+        .and_then(JsonValue::as_array)
+    else {
+        return Err(SearchError::InternalError(
+            "The rollup aggregation results are missing.".to_string(),
+        ));
+    };
+    let mut documents = Vec::new();
+
+    for bucket in buckets {
+        let Some(key_millis) = bucket.get("key").and_then(JsonValue::as_f64) else {
+            continue;
+        };
+        let mut document = JsonMap::new();
+        document.insert(
+            timestamp_field.to_string(),
+            JsonValue::from((key_millis / 1_000.0) as i64),
+        );
+        collect_group_documents(bucket, 0, rollup_policy, &mut document, &mut documents);
+    }
+    Ok(documents)
+}
+
+fn collect_group_documents(
+    bucket: &JsonValue,
+    group_ord: usize,
+    rollup_policy: &RollupPolicy,
+    document: &mut JsonMap<String, JsonValue>,
+    documents: &mut Vec<JsonValue>,
+) {
+    let Some(field) = rollup_policy.group_by().get(group_ord) else {
+        let doc_count = bucket
+            .get("doc_count")
+            .and_then(JsonValue::as_u64)
+            .unwrap_or_default();
+        let mut document = document.clone();
+        document.insert(
+            rollup_policy.count_field().to_string(),
+            JsonValue::from(doc_count),
+        );
+        documents.push(JsonValue::Object(document));
+        return;
+    };
+    let Some(terms_agg_result) = bucket.get(group_agg_name(group_ord)) else {
+        return;
+    };
+    if terms_agg_result
+        .get("sum_other_doc_count")
+        .and_then(JsonValue::as_u64)
+        .map_or(false, |sum_other_doc_count| sum_other_doc_count > 0)
+    {
+        warn!(
+            field=%field,
+            "The rollup counts are truncated to the {MAX_NUM_GROUPS} most frequent values of the \
+             field."
+        );
+    }
+    let Some(sub_buckets) = terms_agg_result
+        .get("buckets")
+        .and_then(JsonValue::as_array)
+    else {
+        return;
+    };
+    for sub_bucket in sub_buckets {
+        let Some(key) = sub_bucket.get("key") else {
+            continue;
+        };
+        document.insert(field.clone(), key.clone());
+        collect_group_documents(
+            sub_bucket,
+            group_ord + 1,
+            rollup_policy,
+            document,
+            documents,
+        );
+    }
+    document.remove(field);
+}
+
+fn group_agg_name(group_ord: usize) -> String {
+    format!("__rollup_group_{group_ord}")
+}
+
+/// Returns whether the request could be answered from a rollup index, before looking up the
+/// rollup policies of the index.
+fn may_target_rollup_index(search_request: &SearchRequest) -> bool {
+    search_request.max_hits == 0
+        && search_request.aggregation_request.is_some()
+        && search_request.realtime_window_secs.is_none()
+        && search_request.position_token.is_none()
+        && search_request.join.is_none()
+        && search_request.dedup_by.is_none()
+        && !search_request.profile
+}
+
+/// Rewrites the search request into a search on a rollup index of the searched index, described
+/// by `index_metadata`, or returns `None` if none of the rollup indexes can answer it. Returns the
+/// metadata of the rollup index along with the rewritten request, whose response must be
+/// converted with [`convert_rollup_search_response`].
+pub(crate) async fn rollup_search_request(
+    search_request: &SearchRequest,
+    index_metadata: &IndexMetadata,
+    metastore: &dyn Metastore,
+) -> crate::Result<Option<(SearchRequest, IndexMetadata)>> {
+    let index_config = index_metadata.index_config();
+
+    // The rollup policies are checked first, so that the searches on the indexes without rollup
+    // don't pay for the metastore lookups below.
+    if index_config.rollup_policies.is_empty() || !may_target_rollup_index(search_request) {
+        return Ok(None);
+    }
+    let Some(aggregation_request) = search_request.aggregation_request.as_deref() else {
+        return Ok(None);
+    };
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|err| {
+            SearchError::InternalError(format!("Failed to build doc mapper. Cause: {err}"))
+        })?;
+    let Some(timestamp_field) = doc_mapper.timestamp_field_name() else {
+        return Ok(None);
+    };
+    let query_ast: QueryAst = query_ast_from_json(&search_request.query_ast)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let query_ast = query_ast.parse_user_query(doc_mapper.default_search_fields())?;
+
+    let mut start_timestamp_opt = search_request.start_timestamp;
+    let mut end_timestamp_opt = search_request.end_timestamp;
+    refine_start_end_timestamp_from_ast(
+        &query_ast,
+        timestamp_field,
+        &mut start_timestamp_opt,
+        &mut end_timestamp_opt,
+    );
+    // The end of the searched time range is compared to the watermark of the rollup index.
+    let Some(end_timestamp) = end_timestamp_opt else {
+        return Ok(None);
+    };
+    for rollup_policy in &index_config.rollup_policies {
+        let Ok(interval_secs) = rollup_policy.interval_secs() else {
+            continue;
+        };
+        let is_aligned = |timestamp: i64| timestamp.rem_euclid(interval_secs) == 0;
+
+        if !is_aligned(end_timestamp) || !start_timestamp_opt.map_or(true, is_aligned) {
+            continue;
+        }
+        let raw_group_by_fields: Vec<&str> = rollup_policy
+            .group_by()
+            .iter()
+            .map(String::as_str)
+            .filter(|field| is_raw_field(doc_mapper.schema(), field))
+            .collect();
+        if !is_rollup_compatible_query(&query_ast, timestamp_field, &raw_group_by_fields, true) {
+            continue;
+        }
+        let Some(rewritten_aggregation_request) = rewrite_aggregation_request(
+            aggregation_request,
+            timestamp_field,
+            rollup_policy,
+            interval_secs,
+        ) else {
+            continue;
+        };
+        let rollup_index_metadata = metastore
+            .index_metadata(rollup_policy.rollup_index_id())
+            .await?;
+        check_index_is_searchable(&rollup_index_metadata)?;
+        let Some(rollup_time_range) = index_published_splits_time_range(
+            metastore,
+            rollup_index_metadata.index_uid.clone(),
+        )
+        .await?
+        else {
+            continue;
+        };
+        // The documents of the last rolled up time bucket may be spread over splits not
+        // published yet, so the bucket is not answered from the rollup index.
+        if end_timestamp > *rollup_time_range.end() {
+            continue;
+        }
+        let query_ast_json = query_ast_to_json(&query_ast).map_err(|err| {
+            SearchError::InternalError(format!("Failed to serialize query ast: Cause {err}"))
+        })?;
+        let rollup_search_request = SearchRequest {
+            index_id: rollup_policy.rollup_index_id().to_string(),
+            query_ast: query_ast_json,
+            start_timestamp: search_request.start_timestamp,
+            end_timestamp: search_request.end_timestamp,
+            max_hits: 0,
+            aggregation_request: Some(rewritten_aggregation_request),
+            ..Default::default()
+        };
+        return Ok(Some((rollup_search_request, rollup_index_metadata)));
+    }
+    Ok(None)
+}
+
+/// Returns whether the values of the field are indexed as is, i.e. the field is not a text field
+/// or a text field with the `raw` tokenizer.
+fn is_raw_field(schema: &Schema, field_name: &str) -> bool {
+    let Ok(field) = schema.get_field(field_name) else {
+        return false;
+    };
+    match schema.get_field_entry(field).field_type() {
+        FieldType::Str(text_options) => text_options
+            .get_indexing_options()
+            .map_or(false, |indexing_options| {
+                indexing_options.tokenizer() == "raw"
+            }),
+        FieldType::JsonObject(_) => false,
+        _ => true,
+    }
+}
+
+/// Returns whether the query only filters with term, term set and range queries on the raw
+/// `group_by` fields of the rollup policy, and with ranges on the timestamp field. The searches
+/// on tokenized fields are rejected, as the rollup index may not tokenize the values the same way.
+/// The ranges on the timestamp field must be strict requirements, so that they are taken into
+/// account in the searched time range.
+fn is_rollup_compatible_query(
+    query_ast: &QueryAst,
+    timestamp_field: &str,
+    raw_group_by_fields: &[&str],
+    is_required: bool,
+) -> bool {
+    let is_raw_group_by_field = |field: &str| raw_group_by_fields.contains(&field);
+
+    match query_ast {
+        QueryAst::MatchAll | QueryAst::MatchNone => true,
+        QueryAst::Term(term_query) => is_raw_group_by_field(&term_query.field),
+        QueryAst::TermSet(term_set_query) => term_set_query
+            .terms_per_field
+            .keys()
+            .all(|field| is_raw_group_by_field(field)),
+        QueryAst::Range(range_query) => {
+            (is_required && range_query.field == timestamp_field)
+                || is_raw_group_by_field(&range_query.field)
+        }
+        QueryAst::Bool(bool_query) => {
+            bool_query
+                .must
+                .iter()
+                .chain(bool_query.filter.iter())
+                .all(|sub_query_ast| {
+                    is_rollup_compatible_query(
+                        sub_query_ast,
+                        timestamp_field,
+                        raw_group_by_fields,
+                        is_required,
+                    )
+                })
+                && bool_query
+                    .should
+                    .iter()
+                    .chain(bool_query.must_not.iter())
+                    .all(|sub_query_ast| {
+                        is_rollup_compatible_query(
+                            sub_query_ast,
+                            timestamp_field,
+                            raw_group_by_fields,
+                            false,
+                        )
+                    })
+        }
+        QueryAst::Boost { underlying, .. } | QueryAst::ConstScore { underlying, .. } => {
+            is_rollup_compatible_query(
+                underlying,
+                timestamp_field,
+                raw_group_by_fields,
+                is_required,
+            )
+        }
+        _ => false,
+    }
+}
+
+/// Rewrites the aggregation request so that the buckets count the documents rolled up instead of
+/// the rollup documents. Returns `None` if the request contains an aggregation that cannot be
+/// computed from the rollup documents.
+fn rewrite_aggregation_request(
+    aggregation_request: &str,
+    timestamp_field: &str,
+    rollup_policy: &RollupPolicy,
+    interval_secs: i64,
+) -> Option<String> {
+    let Ok(JsonValue::Object(mut agg_requests)) = serde_json::from_str(aggregation_request) else {
+        return None;
+    };
+    let rollup_context = RollupContext {
+        timestamp_field,
+        rollup_policy,
+        interval_millis: interval_secs * 1_000,
+    };
+    for agg_request in agg_requests.values_mut() {
+        let agg_request = agg_request.as_object_mut()?;
+
+        if !rollup_context.rewrite_bucket_aggregation(agg_request) {
+            return None;
+        }
+    }
+    agg_requests.insert(
+        ROLLUP_NUM_HITS_AGG_NAME.to_string(),
+        rollup_context.count_sum_agg_request(),
+    );
+    serde_json::to_string(&agg_requests).ok()
+}
+
+struct RollupContext<'a> {
+    timestamp_field: &'a str,
+    rollup_policy: &'a RollupPolicy,
+    interval_millis: i64,
+}
+
+impl<'a> RollupContext<'a> {
+    fn count_sum_agg_request(&self) -> JsonValue {
+        json!({
+            "sum": {
+                "field": self.rollup_policy.count_field(),
+            }
+        })
+    }
+
+    /// Returns whether the aggregation and its sub-aggregations could be rewritten. Only the date
+    /// histograms on the timestamp field and the terms aggregations on the `group_by` fields are
+    /// supported.
+    fn rewrite_bucket_aggregation(&self, agg_request: &mut JsonMap<String, JsonValue>) -> bool {
+        if agg_request.contains_key("aggs") && agg_request.contains_key("aggregations") {
+            return false;
+        }
+        let mut num_bucket_aggs = 0;
+
+        for (key, params) in agg_request.iter_mut() {
+            let is_supported = match key.as_str() {
+                "date_histogram" => self.is_supported_date_histogram(params),
+                "terms" => self.rewrite_terms(params),
+                "aggs" | "aggregations" => continue,
+                _ => false,
+            };
+            if !is_supported {
+                return false;
+            }
+            num_bucket_aggs += 1;
+        }
+        if num_bucket_aggs != 1 {
+            return false;
+        }
+        let sub_aggs_key = if agg_request.contains_key("aggregations") {
+            "aggregations"
+        } else {
+            "aggs"
+        };
+        let Some(sub_agg_requests) = agg_request
+            .entry(sub_aggs_key)
+            .or_insert_with(|| JsonValue::Object(JsonMap::new()))
+            .as_object_mut()
+        else {
+            return false;
+        };
+        for sub_agg_request in sub_agg_requests.values_mut() {
+            let Some(sub_agg_request) = sub_agg_request.as_object_mut() else {
+                return false;
+            };
+            if !self.rewrite_bucket_aggregation(sub_agg_request) {
+                return false;
+            }
+        }
+        sub_agg_requests.insert(
+            ROLLUP_COUNT_AGG_NAME.to_string(),
+            self.count_sum_agg_request(),
+        );
+        true
+    }
+
+    /// The buckets of the date histogram must be unions of time buckets of the rollup.
+    fn is_supported_date_histogram(&self, params: &JsonValue) -> bool {
+        let Some(params) = params.as_object() else {
+            return false;
+        };
+        let is_supported = params.iter().all(|(param, value)| match param.as_str() {
+            "field" => value.as_str() == Some(self.timestamp_field),
+            "fixed_interval" => value.as_str().and_then(parse_fixed_interval_millis).map_or(
+                false,
+                |fixed_interval_millis| {
+                    fixed_interval_millis > 0 && fixed_interval_millis % self.interval_millis == 0
+                },
+            ),
+            "min_doc_count" => value
+                .as_u64()
+                .map_or(false, |min_doc_count| min_doc_count <= 1),
+            "keyed" | "extended_bounds" => true,
+            _ => false,
+        });
+        is_supported && params.contains_key("fixed_interval")
+    }
+
+    /// Terms ordered by document count are ordered by the sum of the counts of the rollup
+    /// documents instead.
+    fn rewrite_terms(&self, params: &mut JsonValue) -> bool {
+        let Some(params) = params.as_object_mut() else {
+            return false;
+        };
+        let is_group_by_field = |field: &str| {
+            self.rollup_policy
+                .group_by()
+                .iter()
+                .any(|group_by_field| group_by_field == field)
+        };
+        let is_supported = params.iter().all(|(param, value)| match param.as_str() {
+            "field" => value.as_str().map_or(false, is_group_by_field),
+            "size" | "shard_size" | "split_size" | "segment_size" | "show_term_doc_count_error" => {
+                true
+            }
+            "min_doc_count" => value
+                .as_u64()
+                .map_or(false, |min_doc_count| min_doc_count <= 1),
+            "order" => value.as_object().map_or(false, |order| {
+                order.len() == 1 && (order.contains_key("_count") || order.contains_key("_key"))
+            }),
+            _ => false,
+        });
+        if !is_supported {
+            return false;
+        }
+        let order = params
+            .entry("order")
+            .or_insert_with(|| json!({"_count": "desc"}));
+        if let Some(direction) = order
+            .as_object_mut()
+            .and_then(|order| order.remove("_count"))
+        {
+            *order = json!({ ROLLUP_COUNT_AGG_NAME: direction });
+        }
+        true
+    }
+}
+
+/// Parses a fixed interval of a date histogram, e.g. `30s` or `1h`, into milliseconds.
+fn parse_fixed_interval_millis(fixed_interval: &str) -> Option<i64> {
+    let unit_start = fixed_interval.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = fixed_interval.split_at(unit_start);
+    let number: i64 = number.parse().ok()?;
+    let unit_millis = match unit {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return None,
+    };
+    number.checked_mul(unit_millis)
+}
+
+/// Replaces the document counts of the buckets and the number of hits of the response to a
+/// request rewritten by [`rollup_search_request`] with the sums of the counts of the rollup
+/// documents.
+pub(crate) fn convert_rollup_search_response(
+    search_response: &mut SearchResponse,
+) -> crate::Result<()> {
+    let Some(aggregation) = search_response.aggregation.as_mut() else {
+        return Ok(());
+    };
+    let mut aggregation_json: JsonValue = serde_json::from_str(aggregation)?;
+    let Some(agg_results) = aggregation_json.as_object_mut() else {
+        return Ok(());
+    };
+    if let Some(num_hits_agg_result) = agg_results.remove(ROLLUP_NUM_HITS_AGG_NAME) {
+        search_response.num_hits = sum_value(&num_hits_agg_result);
+    }
+    convert_bucket_results(agg_results);
+    *aggregation = serde_json::to_string(&aggregation_json)?;
+    Ok(())
+}
+
+fn convert_bucket_results(agg_results: &mut JsonMap<String, JsonValue>) {
+    for agg_result in agg_results.values_mut() {
+        let buckets: Vec<&mut JsonValue> = match agg_result.get_mut("buckets") {
+            Some(JsonValue::Array(buckets)) => buckets.iter_mut().collect(),
+            // Keyed buckets.
+            Some(JsonValue::Object(buckets)) => buckets.values_mut().collect(),
+            _ => continue,
+        };
+        for bucket in buckets {
+            let Some(bucket) = bucket.as_object_mut() else {
+                continue;
+            };
+            if let Some(count_agg_result) = bucket.remove(ROLLUP_COUNT_AGG_NAME) {
+                bucket.insert(
+                    "doc_count".to_string(),
+                    JsonValue::from(sum_value(&count_agg_result)),
+                );
+            }
+            convert_bucket_results(bucket);
+        }
+    }
+}
+
+/// Returns the value of a sum aggregation result, which is null for empty buckets.
+fn sum_value(agg_result: &JsonValue) -> u64 {
+    agg_result
+        .get("value")
+        .and_then(JsonValue::as_f64)
+        .map_or(0, |value| value as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::{IndexMetadata, MockMetastore, Split, SplitMetadata};
+    use quickwit_query::query_ast::{BoolQuery, RangeQuery, TermQuery};
+    use tantivy::schema::{INDEXED, STRING, TEXT};
+
+    use super::*;
+
+    fn rollup_policy() -> RollupPolicy {
+        RollupPolicy::new(
+            "logs-rollup".to_string(),
+            "1 minute".to_string(),
+            vec!["service".to_string(), "level".to_string()],
+        )
+    }
+
+    fn term_query(field: &str, value: &str) -> QueryAst {
+        TermQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_rollup_aggregation_request() {
+        let aggregation_request =
+            rollup_aggregation_request("timestamp", &rollup_policy()).unwrap();
+        let agg_requests: JsonValue = serde_json::from_str(&aggregation_request).unwrap();
+        assert_eq!(
+            agg_requests,
+            json!({
+                "rollup": {
+                    "date_histogram": {
+                        "field": "timestamp",
+                        "fixed_interval": "60s",
+                        "min_doc_count": 1,
+                    },
+                    "aggs": {
+                        "__rollup_group_0": {
+                            "terms": {"field": "service", "size": MAX_NUM_GROUPS},
+                            "aggs": {
+                                "__rollup_group_1": {
+                                    "terms": {"field": "level", "size": MAX_NUM_GROUPS},
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_rollup_documents() {
+        let aggregation = json!({
+            "rollup": {
+                "buckets": [{
+                    "key": 120000.0,
+                    "doc_count": 5,
+                    "__rollup_group_0": {
+                        "sum_other_doc_count": 0,
+                        "buckets": [{
+                            "key": "api",
+                            "doc_count": 5,
+                            "__rollup_group_1": {
+                                "sum_other_doc_count": 0,
+                                "buckets": [
+                                    {"key": "info", "doc_count": 4},
+                                    {"key": "error", "doc_count": 1},
+                                ]
+                            }
+                        }]
+                    }
+                }]
+            }
+        })
+        .to_string();
+        let documents = rollup_documents(&aggregation, "timestamp", &rollup_policy()).unwrap();
+        assert_eq!(
+            documents,
+            vec![
+                json!({"timestamp": 120, "service": "api", "level": "info", "count": 4}),
+                json!({"timestamp": 120, "service": "api", "level": "error", "count": 1}),
+            ]
+        );
+        rollup_documents("{}", "timestamp", &rollup_policy()).unwrap_err();
+    }
+
+    #[test]
+    fn test_is_rollup_compatible_query() {
+        let is_compatible = |query_ast: &QueryAst| {
+            is_rollup_compatible_query(query_ast, "timestamp", &["service", "level"], true)
+        };
+        assert!(is_compatible(&QueryAst::MatchAll));
+        assert!(is_compatible(&term_query("service", "api")));
+        assert!(is_compatible(&QueryAst::term_set(
+            "level",
+            ["info", "warn"]
+        )));
+        assert!(!is_compatible(&term_query("body", "error")));
+        assert!(!is_compatible(&QueryAst::full_text("service", "api")));
+        assert!(is_compatible(&QueryAst::range("level", "a".."m")));
+
+        let timestamp_range: QueryAst = RangeQuery {
+            field: "timestamp".to_string(),
+            lower_bound: std::ops::Bound::Unbounded,
+            upper_bound: std::ops::Bound::Excluded("2023-01-01T00:00:00Z".into()),
+        }
+        .into();
+        let bool_query: QueryAst = BoolQuery {
+            must: vec![term_query("service", "api")],
+            filter: vec![timestamp_range.clone()],
+            must_not: vec![term_query("level", "debug")],
+            ..Default::default()
+        }
+        .into();
+        assert!(is_compatible(&bool_query));
+
+        let bool_query: QueryAst = BoolQuery {
+            should: vec![timestamp_range],
+            ..Default::default()
+        }
+        .into();
+        assert!(!is_compatible(&bool_query));
+    }
+
+    #[test]
+    fn test_is_raw_field() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("service", STRING);
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_u64_field("status", INDEXED);
+        schema_builder.add_json_field("attributes", TEXT);
+        let schema = schema_builder.build();
+
+        assert!(is_raw_field(&schema, "service"));
+        assert!(is_raw_field(&schema, "status"));
+        assert!(!is_raw_field(&schema, "body"));
+        assert!(!is_raw_field(&schema, "attributes"));
+        assert!(!is_raw_field(&schema, "missing"));
+    }
+
+    #[test]
+    fn test_rewrite_aggregation_request() {
+        let rewrite = |agg_requests: JsonValue| {
+            rewrite_aggregation_request(
+                &agg_requests.to_string(),
+                "timestamp",
+                &rollup_policy(),
+                60,
+            )
+            .map(|rewritten| serde_json::from_str::<JsonValue>(&rewritten).unwrap())
+        };
+        let rewritten_agg_requests = rewrite(json!({
+            "histo": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                "aggs": {
+                    "services": {"terms": {"field": "service", "size": 5}}
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            rewritten_agg_requests,
+            json!({
+                "histo": {
+                    "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                    "aggs": {
+                        "services": {
+                            "terms": {
+                                "field": "service",
+                                "size": 5,
+                                "order": {"__rollup_count": "desc"},
+                            },
+                            "aggs": {
+                                "__rollup_count": {"sum": {"field": "count"}},
+                            }
+                        },
+                        "__rollup_count": {"sum": {"field": "count"}},
+                    }
+                },
+                "__rollup_num_hits": {"sum": {"field": "count"}},
+            })
+        );
+        // The histogram interval is not a multiple of the rollup interval.
+        assert!(rewrite(json!({
+            "histo": {"date_histogram": {"field": "timestamp", "fixed_interval": "90s"}}
+        }))
+        .is_none());
+        // The field is not a `group_by` field.
+        assert!(rewrite(json!({"hosts": {"terms": {"field": "host"}}})).is_none());
+        // Metrics cannot be computed from the rollup documents.
+        assert!(rewrite(json!({
+            "histo": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                "aggs": {"latency": {"avg": {"field": "latency"}}}
+            }
+        }))
+        .is_none());
+        assert!(rewrite(json!({
+            "histo": {
+                "date_histogram": {
+                    "field": "timestamp",
+                    "fixed_interval": "1d",
+                    "time_zone": "Europe/Paris",
+                }
+            }
+        }))
+        .is_none());
+    }
+
+    #[test]
+    fn test_parse_fixed_interval_millis() {
+        assert_eq!(parse_fixed_interval_millis("30s"), Some(30_000));
+        assert_eq!(parse_fixed_interval_millis("2h"), Some(7_200_000));
+        assert_eq!(parse_fixed_interval_millis("1d"), Some(86_400_000));
+        assert_eq!(parse_fixed_interval_millis("1w"), None);
+        assert_eq!(parse_fixed_interval_millis("m"), None);
+    }
+
+    #[test]
+    fn test_convert_rollup_search_response() {
+        let aggregation = json!({
+            "histo": {
+                "buckets": [{
+                    "key": 0.0,
+                    "doc_count": 2,
+                    "__rollup_count": {"value": 12.0},
+                    "services": {
+                        "buckets": [
+                            {"key": "api", "doc_count": 1, "__rollup_count": {"value": 10.0}},
+                            {"key": "db", "doc_count": 1, "__rollup_count": {"value": 2.0}},
+                        ]
+                    }
+                }]
+            },
+            "__rollup_num_hits": {"value": 12.0},
+        });
+        let mut search_response = SearchResponse {
+            num_hits: 2,
+            aggregation: Some(aggregation.to_string()),
+            ..Default::default()
+        };
+        convert_rollup_search_response(&mut search_response).unwrap();
+        assert_eq!(search_response.num_hits, 12);
+
+        let aggregation_json: JsonValue =
+            serde_json::from_str(search_response.aggregation.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            aggregation_json,
+            json!({
+                "histo": {
+                    "buckets": [{
+                        "key": 0.0,
+                        "doc_count": 12,
+                        "services": {
+                            "buckets": [
+                                {"key": "api", "doc_count": 10},
+                                {"key": "db", "doc_count": 2},
+                            ]
+                        }
+                    }]
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rollup_search_request_without_rollup_policies() {
+        // The metastore is not expected to be called.
+        let metastore = MockMetastore::new();
+        let index_metadata = IndexMetadata::for_test("logs", "ram:///indexes/logs");
+        let search_request = SearchRequest {
+            index_id: "logs".to_string(),
+            query_ast: query_ast_to_json(&QueryAst::MatchAll).unwrap(),
+            end_timestamp: Some(120),
+            max_hits: 0,
+            aggregation_request: Some(
+                json!({
+                    "by_minute": {
+                        "date_histogram": {"field": "timestamp", "fixed_interval": "60s"}
+                    }
+                })
+                .to_string(),
+            ),
+            ..Default::default()
+        };
+        let rollup_search_request_opt =
+            rollup_search_request(&search_request, &index_metadata, &metastore)
+                .await
+                .unwrap();
+        assert!(rollup_search_request_opt.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_published_splits_time_range() {
+        let mut metastore = MockMetastore::new();
+        metastore.expect_index_metadata().returning(|index_id| {
+            Ok(IndexMetadata::for_test(
+                index_id,
+                &format!("ram:///indexes/{index_id}"),
+            ))
+        });
+        metastore.expect_list_splits().returning(|_query| {
+            let splits = [Some(60..=119), Some(0..=59), None]
+                .into_iter()
+                .map(|time_range| Split {
+                    split_state: SplitState::Published,
+                    update_timestamp: 0,
+                    publish_timestamp: None,
+                    split_metadata: SplitMetadata {
+                        time_range,
+                        ..Default::default()
+                    },
+                })
+                .collect();
+            Ok(splits)
+        });
+        let time_range = published_splits_time_range(&metastore, "logs-rollup")
+            .await
+            .unwrap();
+        assert_eq!(time_range, Some(0..=119));
+    }
+}
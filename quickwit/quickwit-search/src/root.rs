@@ -18,38 +18,72 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 use anyhow::Context;
 use futures::future::try_join_all;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
 use itertools::Itertools;
-use quickwit_config::{build_doc_mapper, IndexConfig};
-use quickwit_doc_mapper::{DocMapper, DYNAMIC_FIELD_NAME};
-use quickwit_metastore::{Metastore, SplitMetadata};
+use quickwit_cluster::ProtocolFeature;
+use quickwit_config::{
+    build_doc_mapper, IndexConfig, SearchFeature, SearchSettings, INGEST_API_SOURCE_ID,
+};
+use quickwit_doc_mapper::{
+    extract_doc_ids_from_query, resolve_doc_id_field_alias, DocMapper, DYNAMIC_FIELD_NAME,
+};
+use quickwit_ingest::{IngesterPool, PositionToken};
+use quickwit_metastore::checkpoint::{PartitionId, Position};
+use quickwit_metastore::{IndexMetadata, IndexState, Metastore, SplitMetadata};
 use quickwit_proto::{
     FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafListTermsRequest, LeafListTermsResponse,
-    LeafSearchRequest, LeafSearchResponse, ListTermsRequest, ListTermsResponse, PartialHit,
-    SearchRequest, SearchResponse, SplitIdAndFooterOffsets,
+    LeafSearchRequest, ListTermsRequest, ListTermsResponse, PartialHit, SearchRequest,
+    SearchResponse, SplitIdAndFooterOffsets,
 };
 use quickwit_query::query_ast::{
-    BoolQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
+    query_ast_from_json, query_ast_to_json, BoolQuery, BoostingQuery, CombinedFieldsQuery,
+    DisMaxQuery, FieldPresenceQuery, FunctionScoreQuery, IntervalsQuery, KnnQuery, QueryAst,
+    QueryAstTransformer, QueryAstVisitor, RangeQuery, RegexQuery, SimpleUserInputQuery, SpanQuery,
+    TermQuery, TermSetQuery, UdfQuery,
 };
+use quickwit_query::NotNaNf32;
 use tantivy::aggregation::agg_result::AggregationResults;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
-use tantivy::collector::Collector;
 use tantivy::schema::{FieldType, Schema};
+use tantivy::time::OffsetDateTime;
 use tantivy::TantivyError;
-use tracing::{debug, error, info_span, instrument};
+use tracing::{debug, error, info_span, instrument, warn};
 
 use crate::cluster_client::ClusterClient;
-use crate::collector::{make_merge_collector, QuickwitAggregations};
+use crate::collector::{make_merge_collector, IncrementalCollector, QuickwitAggregations};
+use crate::dedup::dedup_hits;
 use crate::find_trace_ids_collector::Span;
+use crate::having::{apply_having_clauses, strip_having_clauses};
+use crate::lookup_enrichment::enrich_search_response;
+use crate::metadata_fields::{
+    prune_splits_on_metadata_fields, resolve_metadata_fields, MetadataFieldValues,
+};
+use crate::realtime::{RealtimeIndex, REALTIME_SPLIT_ID};
+use crate::rollup::{convert_rollup_search_response, rollup_search_request};
 use crate::search_job_placer::Job;
 use crate::service::SearcherContext;
+use crate::sessions_collector::Session;
+use crate::time_zone_histogram::{
+    convert_time_zone_date_histogram_results, rewrite_time_zone_date_histograms,
+    searched_time_range,
+};
 use crate::{
     extract_split_and_footer_offsets, list_relevant_splits, SearchError, SearchJobPlacer,
     SearchServiceClient,
 };
 
+/// Maximum time a search request waits for the documents of its position token to be
+/// searchable.
+const POSITION_TOKEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval at which the index checkpoint is polled while waiting for a position token.
+const POSITION_TOKEN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// SearchJob to be assigned to search clients by the [`SearchJobPlacer`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct SearchJob {
@@ -170,6 +204,22 @@ fn validate_sort_by_field(field_name: &str, schema: &Schema) -> crate::Result<()
     Ok(())
 }
 
+fn validate_dedup_by_field(field_name: &str, schema: &Schema) -> crate::Result<()> {
+    let dynamic_field_opt = schema.get_field(DYNAMIC_FIELD_NAME).ok();
+    let (dedup_by_field, _json_path) = schema
+        .find_field_with_default(field_name, dynamic_field_opt)
+        .ok_or_else(|| {
+            SearchError::InvalidArgument(format!("Unknown field used in `dedup_by`: {field_name}"))
+        })?;
+    if !schema.get_field_entry(dedup_by_field).is_fast() {
+        return Err(SearchError::InvalidArgument(format!(
+            "Dedup by field must be a fast field, please add the fast property to your field \
+             `{field_name}`.",
+        )));
+    }
+    Ok(())
+}
+
 pub(crate) fn validate_request(
     doc_mapper: &dyn DocMapper,
     search_request: &SearchRequest,
@@ -182,6 +232,10 @@ pub(crate) fn validate_request(
         validate_sort_by_field(sort_by_field, &schema)?;
     }
 
+    if let Some(dedup_by_field) = &search_request.dedup_by {
+        validate_dedup_by_field(dedup_by_field, &schema)?;
+    }
+
     if let Some(agg) = search_request.aggregation_request.as_ref() {
         let _aggs: QuickwitAggregations = serde_json::from_str(agg).map_err(|_err| {
             let err = serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(agg)
@@ -212,18 +266,84 @@ pub(crate) fn validate_request(
 /// 2. Merges the search results.
 /// 3. Sends fetch docs requests to multiple leaf nodes.
 /// 4. Builds the response with docs and returns.
-#[instrument(skip(search_request, cluster_client, search_job_placer, metastore))]
+///
+/// In realtime mode, the documents that are still in the ingesters' queues are also searched,
+/// see [`RealtimeIndex`].
+///
+/// Aggregation requests are answered from a rollup index of the searched index when one of its
+/// rollup policies can, see [`rollup_search_request`].
+#[instrument(skip(search_request, cluster_client, search_job_placer, ingester_pool, metastore))]
 pub async fn root_search(
     searcher_context: &SearcherContext,
+    search_request: SearchRequest,
+    metastore: &dyn Metastore,
+    cluster_client: &ClusterClient,
+    search_job_placer: &SearchJobPlacer,
+    ingester_pool: &IngesterPool,
+) -> crate::Result<SearchResponse> {
+    let index_metadata = if let Some(position_token) = &search_request.position_token {
+        wait_for_position_token(&search_request.index_id, position_token, metastore).await?
+    } else {
+        metastore.index_metadata(&search_request.index_id).await?
+    };
+    check_index_is_searchable(&index_metadata)?;
+
+    match rollup_search_request(&search_request, &index_metadata, metastore).await {
+        Ok(Some((rollup_search_request, rollup_index_metadata))) => {
+            let mut search_response = root_search_index(
+                searcher_context,
+                rollup_index_metadata,
+                rollup_search_request,
+                metastore,
+                cluster_client,
+                search_job_placer,
+                ingester_pool,
+            )
+            .await?;
+            convert_rollup_search_response(&mut search_response)?;
+            return Ok(search_response);
+        }
+        Ok(None) => {}
+        Err(error) => {
+            warn!(
+                index_id=%search_request.index_id,
+                error=?error,
+                "Failed to search the rollup index, falling back to the searched index."
+            );
+        }
+    }
+    root_search_index(
+        searcher_context,
+        index_metadata,
+        search_request,
+        metastore,
+        cluster_client,
+        search_job_placer,
+        ingester_pool,
+    )
+    .await
+}
+
+/// Searches the index described by `index_metadata`.
+async fn root_search_index(
+    searcher_context: &SearcherContext,
+    index_metadata: IndexMetadata,
     mut search_request: SearchRequest,
     metastore: &dyn Metastore,
     cluster_client: &ClusterClient,
     search_job_placer: &SearchJobPlacer,
+    ingester_pool: &IngesterPool,
 ) -> crate::Result<SearchResponse> {
     let start_instant = tokio::time::Instant::now();
 
-    let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
+    // Searches on frozen indexes must not evict the searchers' cached data of other indexes.
+    let bypass_caches = index_metadata.state == IndexState::Frozen;
     let index_uid = index_metadata.index_uid.clone();
+    let ingest_api_checkpoint = index_metadata
+        .checkpoint
+        .source_checkpoint(INGEST_API_SOURCE_ID)
+        .cloned()
+        .unwrap_or_default();
     let index_config = index_metadata.into_index_config();
 
     let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
@@ -231,11 +351,32 @@ pub async fn root_search(
             SearchError::InternalError(format!("Failed to build doc mapper. Cause: {err}"))
         })?;
 
+    // `having` clauses are evaluated by the root on the merged aggregation results.
+    let aggregation_request_opt = search_request.aggregation_request.clone();
+    if let Some(aggregation_request) = &aggregation_request_opt {
+        if let Some(stripped_aggregation_request) = strip_having_clauses(aggregation_request)? {
+            search_request.aggregation_request = Some(stripped_aggregation_request);
+        }
+    }
     validate_request(&*doc_mapper, &search_request)?;
 
-    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+    let query_ast: QueryAst = query_ast_from_json(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
     let query_ast_resolved = query_ast.parse_user_query(doc_mapper.default_search_fields())?;
+    let query_ast_resolved = index_config
+        .search_settings
+        .synonym_dictionary()
+        .expand_synonyms(query_ast_resolved);
+    let query_ast_resolved =
+        resolve_doc_id_field_alias(query_ast_resolved, doc_mapper.doc_id_field_name())
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let now_timestamp_micros = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000) as i64;
+    let query_ast_resolved = set_function_score_now(query_ast_resolved, now_timestamp_micros);
+    check_feature_flags(
+        &search_request,
+        &query_ast_resolved,
+        &index_config.search_settings,
+    )?;
 
     if let Some(timestamp_field) = doc_mapper.timestamp_field_name() {
         refine_start_end_timestamp_from_ast(
@@ -247,9 +388,13 @@ pub async fn root_search(
     }
 
     // Validates the query by effectively building it against the current schema.
-    doc_mapper.query(doc_mapper.schema(), &query_ast_resolved, true)?;
+    let query_ast_for_validation = resolve_metadata_fields(
+        query_ast_resolved.clone(),
+        &MetadataFieldValues::for_validation(&search_request.index_id),
+    )?;
+    doc_mapper.query(doc_mapper.schema(), &query_ast_for_validation, true)?;
 
-    search_request.query_ast = serde_json::to_string(&query_ast_resolved).map_err(|err| {
+    search_request.query_ast = query_ast_to_json(&query_ast_resolved).map_err(|err| {
         SearchError::InternalError(format!("Failed to serialize query ast: Cause {err}"))
     })?;
 
@@ -259,6 +404,25 @@ pub async fn root_search(
 
     let split_metadatas: Vec<SplitMetadata> =
         list_relevant_splits(index_uid, &search_request, metastore).await?;
+    let split_metadatas = prune_splits_on_metadata_fields(
+        &query_ast_resolved,
+        &search_request.index_id,
+        split_metadatas,
+    )?;
+    let split_metadatas =
+        prune_splits_on_doc_ids(&query_ast_resolved, &*doc_mapper, split_metadatas);
+
+    // Time zone aware date histograms are executed as range aggregations by the leaves.
+    if let Some(aggregation_request) = &search_request.aggregation_request {
+        let time_range = searched_time_range(&search_request, &split_metadatas);
+        if let Some(rewritten_aggregation_request) = rewrite_time_zone_date_histograms(
+            aggregation_request,
+            doc_mapper.timestamp_field_name(),
+            time_range,
+        )? {
+            search_request.aggregation_request = Some(rewritten_aggregation_request);
+        }
+    }
 
     let split_offsets_map: HashMap<String, SplitIdAndFooterOffsets> = split_metadatas
         .iter()
@@ -273,37 +437,58 @@ pub async fn root_search(
     let index_uri = &index_config.index_uri;
 
     let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
+    let required_features = required_protocol_features(&query_ast_resolved);
+
+    // Creates a collector which merges responses into one.
+    let merge_collector =
+        make_merge_collector(&search_request, &searcher_context.get_aggregation_limits())?;
+    let aggregations = merge_collector.aggregation.clone();
+    let mut incremental_collector = IncrementalCollector::new(merge_collector);
 
     let assigned_leaf_search_jobs = search_job_placer
-        .assign_jobs(jobs, &HashSet::default())
+        .assign_jobs_requiring_features(jobs, &required_features)
         .await?;
-    let leaf_search_responses: Vec<LeafSearchResponse> =
-        try_join_all(assigned_leaf_search_jobs.map(|(client, client_jobs)| {
-            let leaf_request = jobs_to_leaf_request(
+    let mut leaf_search_futures: FuturesUnordered<_> = assigned_leaf_search_jobs
+        .map(|(client, client_jobs)| {
+            let mut leaf_request = jobs_to_leaf_request(
                 &search_request,
                 &doc_mapper_str,
                 index_uri.as_ref(),
                 client_jobs,
             );
+            leaf_request.bypass_caches = bypass_caches;
             cluster_client.leaf_search(leaf_request, client)
-        }))
-        .await?;
+        })
+        .collect();
 
-    // Creates a collector which merges responses into one
-    let merge_collector =
-        make_merge_collector(&search_request, &searcher_context.get_aggregation_limits())?;
-    let aggregations = merge_collector.aggregation.clone();
+    // The hits of the leaf responses are merged as soon as they arrive.
+    while let Some(leaf_search_result) = leaf_search_futures.next().await {
+        incremental_collector.add_leaf_response(leaf_search_result?);
+    }
 
-    // Merging is a cpu-bound task.
-    // It should be executed by Tokio's blocking threads.
+    let realtime_index_opt = if search_request.realtime_window_secs.is_some() {
+        let realtime_index = RealtimeIndex::build(
+            ingester_pool,
+            &search_request,
+            &ingest_api_checkpoint,
+            doc_mapper.clone(),
+        )
+        .await?;
+        let realtime_leaf_search_response = realtime_index
+            .leaf_search(searcher_context, &search_request)
+            .await?;
+        incremental_collector.add_leaf_response(realtime_leaf_search_response);
+        Some(realtime_index)
+    } else {
+        None
+    };
 
-    // Wrap into result for merge_fruits
-    let leaf_search_responses: Vec<tantivy::Result<LeafSearchResponse>> =
-        leaf_search_responses.into_iter().map(Ok).collect_vec();
+    // Merging the aggregations is a cpu-bound task.
+    // It should be executed by Tokio's blocking threads.
     let span = info_span!("merge_fruits");
     let leaf_search_response = crate::run_cpu_intensive(move || {
         let _span_guard = span.enter();
-        merge_collector.merge_fruits(leaf_search_responses)
+        incremental_collector.finalize()
     })
     .await
     .context("failed to merge fruits")?
@@ -337,13 +522,17 @@ pub async fn root_search(
         })
         .collect();
 
+    // The documents of the realtime index are fetched locally.
+    let (realtime_partial_hits, partial_hits): (Vec<PartialHit>, Vec<PartialHit>) =
+        leaf_search_response
+            .partial_hits
+            .iter()
+            .cloned()
+            .partition(|partial_hit| partial_hit.split_id == REALTIME_SPLIT_ID);
+
     let client_fetch_docs_task: Vec<(SearchServiceClient, Vec<FetchDocsJob>)> =
-        assign_client_fetch_doc_tasks(
-            &leaf_search_response.partial_hits,
-            &split_offsets_map,
-            search_job_placer,
-        )
-        .await?;
+        assign_client_fetch_doc_tasks(&partial_hits, &split_offsets_map, search_job_placer)
+            .await?;
 
     let fetch_docs_resp_futures =
         client_fetch_docs_task
@@ -374,7 +563,20 @@ pub async fn root_search(
                 cluster_client.fetch_docs(fetch_docs_req, client)
             });
 
-    let fetch_docs_resps: Vec<FetchDocsResponse> = try_join_all(fetch_docs_resp_futures).await?;
+    let mut fetch_docs_resps: Vec<FetchDocsResponse> =
+        try_join_all(fetch_docs_resp_futures).await?;
+
+    if let Some(realtime_index) = &realtime_index_opt {
+        let search_request_opt = if search_request.snippet_fields.is_empty() {
+            None
+        } else {
+            Some(&search_request)
+        };
+        let realtime_fetch_docs_resp = realtime_index
+            .fetch_docs(realtime_partial_hits, search_request_opt)
+            .await?;
+        fetch_docs_resps.push(realtime_fetch_docs_resp);
+    }
 
     // Merge the fetched docs.
     let leaf_hits = fetch_docs_resps
@@ -402,26 +604,46 @@ pub async fn root_search(
         .collect();
 
     hits_with_position.sort_by_key(|(position, _)| *position);
-    let hits = hits_with_position
+    let mut hits: Vec<Hit> = hits_with_position
         .into_iter()
         .map(|(_position, hit)| hit)
         .collect();
+    if let Some(dedup_by_field) = &search_request.dedup_by {
+        dedup_hits(&mut hits, dedup_by_field)?;
+    }
 
     let elapsed = start_instant.elapsed();
 
-    let aggregation: Option<String> = finalize_aggregation(
+    let mut aggregation: Option<String> = finalize_aggregation(
         leaf_search_response.intermediate_aggregation_result,
         aggregations,
         searcher_context,
     )?;
+    if let (Some(aggregation), Some(aggregation_request)) =
+        (aggregation.as_mut(), aggregation_request_opt.as_deref())
+    {
+        convert_time_zone_date_histogram_results(aggregation, aggregation_request)?;
+        apply_having_clauses(aggregation, aggregation_request)?;
+    }
+
+    let errors = realtime_index_opt
+        .map(|realtime_index| realtime_index.errors().to_vec())
+        .unwrap_or_default();
 
-    Ok(SearchResponse {
+    let mut search_response = SearchResponse {
         aggregation,
         num_hits: leaf_search_response.num_hits,
         hits,
         elapsed_time_micros: elapsed.as_micros() as u64,
-        errors: Vec::new(),
-    })
+        errors,
+        split_profiles: leaf_search_response.split_profiles,
+    };
+    enrich_search_response(
+        &mut search_response,
+        aggregation_request_opt.as_deref(),
+        &index_config.lookup_tables,
+    )?;
+    Ok(search_response)
 }
 
 pub(crate) fn refine_start_end_timestamp_from_ast(
@@ -442,6 +664,189 @@ pub(crate) fn refine_start_end_timestamp_from_ast(
     *end_timestamp = timestamp_range_extractor.end_timestamp;
 }
 
+/// Returns the protocol features the leaf searchers must support to execute the query.
+pub(crate) fn required_protocol_features(query_ast: &QueryAst) -> Vec<ProtocolFeature> {
+    let mut required_features_visitor = RequiredProtocolFeatures::default();
+    required_features_visitor
+        .visit(query_ast)
+        .expect("can't fail unwrapping Infallible");
+    required_features_visitor.features
+}
+
+#[derive(Default)]
+struct RequiredProtocolFeatures {
+    features: Vec<ProtocolFeature>,
+}
+
+impl RequiredProtocolFeatures {
+    fn require(&mut self, feature: ProtocolFeature) {
+        if !self.features.contains(&feature) {
+            self.features.push(feature);
+        }
+    }
+}
+
+impl<'a> QueryAstVisitor<'a> for RequiredProtocolFeatures {
+    type Err = std::convert::Infallible;
+
+    fn visit_regex(&mut self, _regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::RegexQuery);
+        Ok(())
+    }
+
+    fn visit_field_presence(
+        &mut self,
+        _field_presence_query: &'a FieldPresenceQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::FieldPresenceQuery);
+        Ok(())
+    }
+
+    fn visit_dis_max(&mut self, dis_max_query: &'a DisMaxQuery) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::DisMaxQuery);
+        for ast in &dis_max_query.queries {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+
+    fn visit_boosting(&mut self, boosting_query: &'a BoostingQuery) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::BoostingQuery);
+        self.visit(&boosting_query.positive)?;
+        self.visit(&boosting_query.negative)
+    }
+
+    fn visit_combined_fields(
+        &mut self,
+        _combined_fields_query: &'a CombinedFieldsQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::CombinedFieldsQuery);
+        Ok(())
+    }
+
+    fn visit_intervals(&mut self, _intervals_query: &'a IntervalsQuery) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::IntervalsQuery);
+        Ok(())
+    }
+
+    fn visit_span(&mut self, _span_query: &'a SpanQuery) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::SpanQuery);
+        Ok(())
+    }
+
+    fn visit_knn(&mut self, _knn_query: &'a KnnQuery) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::KnnQuery);
+        Ok(())
+    }
+
+    fn visit_simple_user_text(
+        &mut self,
+        _simple_user_input_query: &'a SimpleUserInputQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::SimpleUserInputQuery);
+        Ok(())
+    }
+
+    fn visit_const_score(
+        &mut self,
+        underlying: &'a QueryAst,
+        _score: NotNaNf32,
+    ) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::ConstScoreQuery);
+        self.visit(underlying)
+    }
+
+    fn visit_udf(&mut self, udf_query: &'a UdfQuery) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::UdfQuery);
+        self.visit(&udf_query.underlying)
+    }
+
+    fn visit_function_score(
+        &mut self,
+        function_score_query: &'a FunctionScoreQuery,
+    ) -> Result<(), Self::Err> {
+        self.require(ProtocolFeature::FunctionScoreQuery);
+        self.visit(&function_score_query.underlying)?;
+        for function in &function_score_query.functions {
+            if let Some(filter) = &function.filter {
+                self.visit(filter)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rejects the search request if it uses a feature that is disabled on the searched index.
+pub(crate) fn check_feature_flags(
+    search_request: &SearchRequest,
+    query_ast: &QueryAst,
+    search_settings: &SearchSettings,
+) -> crate::Result<()> {
+    let mut disabled_features = disabled_query_features(query_ast, search_settings);
+    if search_request.realtime_window_secs.is_some()
+        && !search_settings.is_feature_enabled(SearchFeature::RealtimeSearch)
+    {
+        disabled_features.push(SearchFeature::RealtimeSearch);
+    }
+    if let Some(feature) = disabled_features.first() {
+        return Err(SearchError::InvalidArgument(format!(
+            "Feature `{}` is disabled on index `{}`. It can be enabled with the \
+             `search_settings.feature_flags` index setting.",
+            feature.as_str(),
+            search_request.index_id
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the features used by the query that are disabled on the searched index.
+pub(crate) fn disabled_query_features(
+    query_ast: &QueryAst,
+    search_settings: &SearchSettings,
+) -> Vec<SearchFeature> {
+    let mut used_features_visitor = UsedSearchFeatures::default();
+    used_features_visitor
+        .visit(query_ast)
+        .expect("can't fail unwrapping Infallible");
+    used_features_visitor
+        .features
+        .into_iter()
+        .filter(|feature| !search_settings.is_feature_enabled(*feature))
+        .collect()
+}
+
+#[derive(Default)]
+struct UsedSearchFeatures {
+    features: Vec<SearchFeature>,
+}
+
+impl UsedSearchFeatures {
+    fn use_feature(&mut self, feature: SearchFeature) {
+        if !self.features.contains(&feature) {
+            self.features.push(feature);
+        }
+    }
+}
+
+impl<'a> QueryAstVisitor<'a> for UsedSearchFeatures {
+    type Err = std::convert::Infallible;
+
+    fn visit_regex(&mut self, _regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        self.use_feature(SearchFeature::RegexQueries);
+        Ok(())
+    }
+
+    fn visit_knn(&mut self, _knn_query: &'a KnnQuery) -> Result<(), Self::Err> {
+        self.use_feature(SearchFeature::KnnQueries);
+        Ok(())
+    }
+
+    fn visit_udf(&mut self, udf_query: &'a UdfQuery) -> Result<(), Self::Err> {
+        self.use_feature(SearchFeature::UdfQueries);
+        self.visit(&udf_query.underlying)
+    }
+}
+
 /// Boundaries identified as being implied by the QueryAst.
 ///
 /// `start_timestamp` is to be interpreted as Inclusive (or Unbounded)
@@ -501,6 +906,15 @@ impl<'a, 'b> QueryAstVisitor<'b> for ExtractTimestampRange<'a> {
         Ok(())
     }
 
+    fn visit_function_score(
+        &mut self,
+        function_score_query: &'b FunctionScoreQuery,
+    ) -> Result<(), Self::Err> {
+        // The function filters only select the documents a function applies to, they don't
+        // restrict the matching documents.
+        self.visit(&function_score_query.underlying)
+    }
+
     fn visit_range(&mut self, range_query: &'b RangeQuery) -> Result<(), Self::Err> {
         use std::ops::Bound;
 
@@ -548,6 +962,64 @@ impl<'a, 'b> QueryAstVisitor<'b> for ExtractTimestampRange<'a> {
     }
 }
 
+/// Sets the reference time of the function score queries, so that the decay functions computed
+/// relative to now score the documents the same way on every leaf.
+struct SetFunctionScoreNow {
+    now_timestamp_micros: i64,
+}
+
+impl QueryAstTransformer for SetFunctionScoreNow {
+    type Err = std::convert::Infallible;
+
+    fn transform_function_score(
+        &mut self,
+        function_score_query: FunctionScoreQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        let mut function_score_query =
+            function_score_query.try_map_subqueries(|query_ast| self.transform(query_ast))?;
+        function_score_query
+            .now_timestamp_micros
+            .get_or_insert(self.now_timestamp_micros);
+        Ok(function_score_query.into())
+    }
+}
+
+fn set_function_score_now(query_ast: QueryAst, now_timestamp_micros: i64) -> QueryAst {
+    SetFunctionScoreNow {
+        now_timestamp_micros,
+    }
+    .transform(query_ast)
+    .expect("can't fail unwrapping Infallible")
+}
+
+/// Returns the splits that can hold the documents targeted by a query on the doc ID field, based
+/// on their doc ID bloom filters. Splits without a bloom filter are always kept.
+pub(crate) fn prune_splits_on_doc_ids(
+    query_ast: &QueryAst,
+    doc_mapper: &dyn DocMapper,
+    split_metadatas: Vec<SplitMetadata>,
+) -> Vec<SplitMetadata> {
+    let Some(doc_id_field) = doc_mapper.doc_id_field_name() else {
+        return split_metadatas;
+    };
+    let Some(doc_ids) = extract_doc_ids_from_query(query_ast, doc_id_field) else {
+        return split_metadatas;
+    };
+    split_metadatas
+        .into_iter()
+        .filter(|split_metadata| {
+            split_metadata
+                .doc_id_bloom_filter
+                .as_ref()
+                .map_or(true, |bloom_filter| {
+                    doc_ids
+                        .iter()
+                        .any(|doc_id| bloom_filter.may_contain(doc_id.as_bytes()))
+                })
+        })
+        .collect()
+}
+
 pub fn finalize_aggregation(
     intermediate_aggregation_result: Option<Vec<u8>>,
     aggregations: Option<QuickwitAggregations>,
@@ -565,6 +1037,12 @@ pub fn finalize_aggregation(
                     postcard::from_bytes(intermediate_aggregation_result.as_slice())?;
                 Some(serde_json::to_string(&aggs)?)
             }
+            QuickwitAggregations::SessionsAggregation(collector) => {
+                // The merge collector has already stitched the sessions together across splits.
+                let sessions: Vec<Session> =
+                    postcard::from_bytes(intermediate_aggregation_result.as_slice())?;
+                Some(serde_json::to_string(&collector.finalize(sessions))?)
+            }
             QuickwitAggregations::TantivyAggregations(aggregations) => {
                 let res: IntermediateAggregationResults =
                     postcard::from_bytes(intermediate_aggregation_result.as_slice())?;
@@ -596,6 +1074,7 @@ pub async fn root_list_terms(
     let index_metadata = metastore
         .index_metadata(&list_terms_request.index_id)
         .await?;
+    check_index_is_searchable(&index_metadata)?;
     let index_uid = index_metadata.index_uid.clone();
     let index_config: IndexConfig = index_metadata.into_index_config();
 
@@ -742,6 +1221,55 @@ fn compute_split_cost(_split_metadata: &SplitMetadata) -> usize {
 }
 
 /// Builds a [`LeafSearchRequest`] from a list of [`SearchJob`].
+/// Returns an error if the index is closed. Open and frozen indexes are searchable.
+pub(crate) fn check_index_is_searchable(index_metadata: &IndexMetadata) -> crate::Result<()> {
+    if !index_metadata.state.is_searchable() {
+        return Err(SearchError::IndexClosed {
+            index_id: index_metadata.index_id().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Waits until the documents ingested up to the position recorded in `position_token` are
+/// searchable, i.e. until the checkpoint of the ingest API source of the index reaches that
+/// position, and returns the metadata of the index.
+async fn wait_for_position_token(
+    index_id: &str,
+    position_token: &str,
+    metastore: &dyn Metastore,
+) -> crate::Result<IndexMetadata> {
+    let position_token = PositionToken::decode(position_token)
+        .map_err(|error| SearchError::InvalidArgument(format!("{error:#}")))?;
+    let Some(position) = position_token.position_for_index(index_id) else {
+        // No documents were ingested into this index.
+        return Ok(metastore.index_metadata(index_id).await?);
+    };
+    let partition_id = PartitionId::from(position_token.partition_id.as_str());
+    let target_position = Position::from(position);
+    let deadline = tokio::time::Instant::now() + POSITION_TOKEN_TIMEOUT;
+
+    loop {
+        let index_metadata = metastore.index_metadata(index_id).await?;
+        let is_position_reached = index_metadata
+            .checkpoint
+            .source_checkpoint(INGEST_API_SOURCE_ID)
+            .and_then(|source_checkpoint| source_checkpoint.position_for_partition(&partition_id))
+            .map(|published_position| *published_position >= target_position)
+            .unwrap_or(false);
+        if is_position_reached {
+            return Ok(index_metadata);
+        }
+        if tokio::time::Instant::now() + POSITION_TOKEN_POLL_INTERVAL > deadline {
+            return Err(SearchError::Timeout(format!(
+                "the documents of the position token were not searchable after {} seconds",
+                POSITION_TOKEN_TIMEOUT.as_secs()
+            )));
+        }
+        tokio::time::sleep(POSITION_TOKEN_POLL_INTERVAL).await;
+    }
+}
+
 pub fn jobs_to_leaf_request(
     request: &SearchRequest,
     doc_mapper_str: &str,
@@ -756,21 +1284,28 @@ pub fn jobs_to_leaf_request(
         split_offsets: jobs.into_iter().map(|job| job.offsets).collect(),
         doc_mapper: doc_mapper_str.to_string(),
         index_uri: index_uri.to_string(),
+        bypass_caches: false,
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
 
+    use quickwit_cluster::LEGACY_PROTOCOL_VERSION;
     use quickwit_config::SearcherConfig;
-    use quickwit_indexing::mock_split;
-    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_doc_mapper::{DefaultDocMapper, DocIdBloomFilter};
+    use quickwit_indexing::{mock_split, mock_split_meta};
+    use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
+    use quickwit_metastore::MockMetastore;
     use quickwit_proto::{qast_helper, SortOrder, SortValue, SplitSearchError};
-    use tantivy::schema::{FAST, STORED, TEXT};
+    use tantivy::schema::{FAST, STORED, STRING, TEXT};
 
     use super::*;
-    use crate::{searcher_pool_for_test, MockSearchService};
+    use crate::{searcher_pool_for_test, MockSearchService, SearcherPool};
 
     #[track_caller]
     fn check_snippet_fields_validation(snippet_fields: &[String]) -> anyhow::Result<()> {
@@ -805,6 +1340,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_dedup_by_field() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("message", STRING | FAST);
+        schema_builder.add_text_field("desc", TEXT);
+        let schema = schema_builder.build();
+        validate_dedup_by_field("message", &schema).unwrap();
+        let field_not_fast_err = validate_dedup_by_field("desc", &schema).unwrap_err();
+        assert!(matches!(
+            field_not_fast_err,
+            SearchError::InvalidArgument(_)
+        ));
+        let field_doesnotexist_err = validate_dedup_by_field("doesnotexist", &schema).unwrap_err();
+        assert_eq!(
+            field_doesnotexist_err.to_string(),
+            "Invalid argument: Unknown field used in `dedup_by`: doesnotexist"
+        );
+    }
+
     fn mock_partial_hit(
         split_id: &str,
         sort_value: u64,
@@ -916,6 +1470,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -977,6 +1532,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -985,6 +1541,88 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_closed_and_frozen_indexes() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::SearchRequest {
+            index_id: "test-index".to_string(),
+            query_ast: qast_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .times(1)
+            .returning(|_index_id: &str| {
+                let mut index_metadata =
+                    IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+                index_metadata.state = IndexState::Closed;
+                Ok(index_metadata)
+            });
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let search_error = root_search(
+            &SearcherContext::new(SearcherConfig::default()),
+            search_request.clone(),
+            &metastore,
+            &cluster_client,
+            &search_job_placer,
+            &IngesterPool::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::IndexClosed { .. }));
+
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                let mut index_metadata =
+                    IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+                index_metadata.state = IndexState::Frozen;
+                Ok(index_metadata)
+            });
+        metastore
+            .expect_list_splits()
+            .returning(|_filter| Ok(vec![mock_split("split1")]));
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::LeafSearchRequest| {
+                assert!(leaf_search_req.bypass_caches);
+                Ok(quickwit_proto::LeafSearchResponse {
+                    num_hits: 1,
+                    partial_hits: vec![mock_partial_hit("split1", 1, 1)],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
+                Ok(quickwit_proto::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let search_response = root_search(
+            &SearcherContext::new(SearcherConfig::default()),
+            search_request,
+            &metastore,
+            &cluster_client,
+            &search_job_placer,
+            &IngesterPool::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_root_search_multiple_splits() -> anyhow::Result<()> {
         let search_request = quickwit_proto::SearchRequest {
@@ -1058,6 +1696,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -1170,6 +1809,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await?;
 
@@ -1326,6 +1966,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await?;
 
@@ -1478,6 +2119,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -1603,6 +2245,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -1676,6 +2319,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -1735,6 +2379,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await;
         assert!(search_response.is_err());
@@ -1817,6 +2462,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -1891,6 +2537,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .unwrap();
@@ -1929,6 +2576,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .is_err());
@@ -1944,6 +2592,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await
         .is_err());
@@ -2000,6 +2649,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await;
         assert!(search_response.is_err());
@@ -2041,6 +2691,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await;
         assert!(search_response.is_err());
@@ -2062,6 +2713,7 @@ mod tests {
             &metastore,
             &cluster_client,
             &search_job_placer,
+            &IngesterPool::default(),
         )
         .await;
         assert!(search_response.is_err());
@@ -2073,6 +2725,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_prune_splits_on_doc_ids() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+                "doc_id": {"field": "id"},
+                "field_mappings": [
+                    {"name": "id", "type": "text", "tokenizer": "raw", "fast": true},
+                    {"name": "body", "type": "text"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut split_1 = mock_split_meta("split1");
+        let mut bloom_filter = DocIdBloomFilter::with_num_doc_ids(2).unwrap();
+        bloom_filter.insert(b"doc-1");
+        bloom_filter.insert(b"doc-2");
+        split_1.doc_id_bloom_filter = Some(bloom_filter);
+
+        let mut split_2 = mock_split_meta("split2");
+        let mut bloom_filter = DocIdBloomFilter::with_num_doc_ids(1).unwrap();
+        bloom_filter.insert(b"doc-3");
+        split_2.doc_id_bloom_filter = Some(bloom_filter);
+
+        // Splits without a bloom filter cannot be pruned.
+        let split_3 = mock_split_meta("split3");
+
+        let prune = |query_ast: QueryAst| -> Vec<String> {
+            prune_splits_on_doc_ids(
+                &query_ast,
+                &doc_mapper,
+                vec![split_1.clone(), split_2.clone(), split_3.clone()],
+            )
+            .into_iter()
+            .map(|split_metadata| split_metadata.split_id)
+            .collect()
+        };
+        assert_eq!(
+            prune(TermQuery::from_field_value("id", "doc-2").into()),
+            ["split1", "split3"]
+        );
+        assert_eq!(
+            prune(TermQuery::from_field_value("id", "doc-3").into()),
+            ["split2", "split3"]
+        );
+        assert_eq!(
+            prune(TermQuery::from_field_value("body", "doc-3").into()),
+            ["split1", "split2", "split3"]
+        );
+    }
+
     #[test]
     fn test_extract_timestamp_range_from_ast() {
         use std::ops::Bound;
@@ -2184,4 +2886,451 @@ mod tests {
         assert_eq!(timestamp_range_extractor.start_timestamp, Some(1618353941));
         assert_eq!(timestamp_range_extractor.end_timestamp, Some(1620283880));
     }
+
+    #[test]
+    fn test_extract_timestamp_range_ignores_function_filters() {
+        use std::ops::Bound;
+
+        use quickwit_query::query_ast::{ScoreFunction, ScoreFunctionKind};
+        use quickwit_query::JsonLiteral;
+
+        let timestamp_field = "timestamp";
+        let range: QueryAst = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String("2021-04-13T22:45:41Z".to_owned())),
+            upper_bound: Bound::Unbounded,
+        }
+        .into();
+        let function_score_query: QueryAst = FunctionScoreQuery {
+            underlying: Box::new(QueryAst::MatchAll),
+            functions: vec![ScoreFunction {
+                kind: ScoreFunctionKind::Constant,
+                filter: Some(Box::new(range)),
+                weight: None,
+            }],
+            score_mode: Default::default(),
+            boost_mode: Default::default(),
+            now_timestamp_micros: None,
+        }
+        .into();
+        let mut timestamp_range_extractor = ExtractTimestampRange {
+            timestamp_field,
+            start_timestamp: None,
+            end_timestamp: None,
+        };
+        timestamp_range_extractor
+            .visit(&function_score_query)
+            .unwrap();
+        assert_eq!(timestamp_range_extractor.start_timestamp, None);
+        assert_eq!(timestamp_range_extractor.end_timestamp, None);
+    }
+
+    #[test]
+    fn test_set_function_score_now() {
+        let function_score_query = |now_timestamp_micros: Option<i64>| -> QueryAst {
+            FunctionScoreQuery {
+                underlying: Box::new(QueryAst::MatchAll),
+                functions: Vec::new(),
+                score_mode: Default::default(),
+                boost_mode: Default::default(),
+                now_timestamp_micros,
+            }
+            .into()
+        };
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![function_score_query(None)],
+            should: vec![function_score_query(Some(1))],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![function_score_query(Some(42))],
+            should: vec![function_score_query(Some(1))],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(set_function_score_now(query_ast, 42), expected_query_ast);
+    }
+
+    #[test]
+    fn test_required_protocol_features() {
+        let term_query: QueryAst = TermQuery {
+            field: "body".to_string(),
+            value: "test".to_string(),
+        }
+        .into();
+        assert!(required_protocol_features(&term_query).is_empty());
+
+        let regex_query: QueryAst = RegexQuery {
+            field: "body".to_string(),
+            regex: "te.*".to_string(),
+            case_insensitive: false,
+        }
+        .into();
+        let field_presence_query: QueryAst = FieldPresenceQuery {
+            field: "body".to_string(),
+        }
+        .into();
+        let bool_query: QueryAst = BoolQuery {
+            must: vec![regex_query.clone(), field_presence_query],
+            should: vec![regex_query],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            required_protocol_features(&bool_query),
+            [
+                ProtocolFeature::RegexQuery,
+                ProtocolFeature::FieldPresenceQuery
+            ]
+        );
+    }
+
+    fn gated_query_asts_for_test() -> Vec<(QueryAst, ProtocolFeature)> {
+        use quickwit_query::query_ast::{
+            IntervalsMatchRule, IntervalsRule, ScoreFunction, ScoreFunctionKind,
+            SimpleQueryStringFlags, SpanRule, SpanTermRule, UdfMode,
+        };
+        use quickwit_query::{BooleanOperand, MatchAllOrNone};
+
+        let term_query = QueryAst::term("body", "test");
+        vec![
+            (
+                DisMaxQuery {
+                    queries: vec![term_query.clone(), QueryAst::term("body", "other")],
+                    tie_breaker: None,
+                }
+                .into(),
+                ProtocolFeature::DisMaxQuery,
+            ),
+            (
+                BoostingQuery {
+                    positive: Box::new(term_query.clone()),
+                    negative: Box::new(QueryAst::term("body", "other")),
+                    negative_boost: NotNaNf32::try_from(0.5).unwrap(),
+                }
+                .into(),
+                ProtocolFeature::BoostingQuery,
+            ),
+            (
+                CombinedFieldsQuery {
+                    fields: vec!["body".to_string(), "attributes.server".to_string()],
+                    text: "test".to_string(),
+                    operator: BooleanOperand::Or,
+                    minimum_should_match: None,
+                    zero_terms_query: MatchAllOrNone::MatchNone,
+                }
+                .into(),
+                ProtocolFeature::CombinedFieldsQuery,
+            ),
+            (
+                IntervalsQuery {
+                    field: "body".to_string(),
+                    rule: IntervalsRule::Match(IntervalsMatchRule {
+                        query: "hello world".to_string(),
+                        max_gaps: 1,
+                        ordered: true,
+                        analyzer: None,
+                    }),
+                }
+                .into(),
+                ProtocolFeature::IntervalsQuery,
+            ),
+            (
+                SpanQuery {
+                    field: "body".to_string(),
+                    rule: SpanRule::SpanTerm(SpanTermRule {
+                        value: "test".to_string(),
+                    }),
+                }
+                .into(),
+                ProtocolFeature::SpanQuery,
+            ),
+            (
+                KnnQuery {
+                    field: "embedding".to_string(),
+                    query_vector: vec![NotNaNf32::ONE, NotNaNf32::ZERO],
+                    k: 10,
+                    num_candidates: 10,
+                }
+                .into(),
+                ProtocolFeature::KnnQuery,
+            ),
+            (
+                SimpleUserInputQuery {
+                    user_text: "test".to_string(),
+                    default_fields: None,
+                    default_operator: BooleanOperand::Or,
+                    flags: SimpleQueryStringFlags::ALL,
+                }
+                .into(),
+                ProtocolFeature::SimpleUserInputQuery,
+            ),
+            (
+                QueryAst::ConstScore {
+                    underlying: Box::new(term_query.clone()),
+                    score: NotNaNf32::ONE,
+                },
+                ProtocolFeature::ConstScoreQuery,
+            ),
+            (
+                UdfQuery {
+                    underlying: Box::new(term_query.clone()),
+                    udf: "response_time * 2".to_string(),
+                    fields: vec!["response_time".to_string()],
+                    mode: UdfMode::Score,
+                }
+                .into(),
+                ProtocolFeature::UdfQuery,
+            ),
+            (
+                FunctionScoreQuery {
+                    underlying: Box::new(term_query),
+                    functions: vec![ScoreFunction {
+                        kind: ScoreFunctionKind::Constant,
+                        filter: None,
+                        weight: None,
+                    }],
+                    score_mode: Default::default(),
+                    boost_mode: Default::default(),
+                    now_timestamp_micros: None,
+                }
+                .into(),
+                ProtocolFeature::FunctionScoreQuery,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_required_protocol_features_of_nested_queries() {
+        for (query_ast, feature) in gated_query_asts_for_test() {
+            let bool_query: QueryAst = BoolQuery {
+                must: vec![QueryAst::MatchAll],
+                filter: vec![query_ast],
+                ..Default::default()
+            }
+            .into();
+            assert_eq!(required_protocol_features(&bool_query), [feature]);
+        }
+        let regex_query: QueryAst = RegexQuery {
+            field: "body".to_string(),
+            regex: "te.*".to_string(),
+            case_insensitive: false,
+        }
+        .into();
+        let const_score_query = QueryAst::ConstScore {
+            underlying: Box::new(regex_query),
+            score: NotNaNf32::ONE,
+        };
+        assert_eq!(
+            required_protocol_features(&const_score_query),
+            [
+                ProtocolFeature::ConstScoreQuery,
+                ProtocolFeature::RegexQuery
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_root_search_does_not_send_new_queries_to_legacy_searchers() {
+        let legacy_searcher_addr: SocketAddr = ([127, 0, 0, 1], 1001).into();
+        let searcher_addr: SocketAddr = ([127, 0, 0, 1], 1002).into();
+        let mut legacy_search_service = MockSearchService::new();
+        legacy_search_service.expect_leaf_search().never();
+        let legacy_client = SearchServiceClient::from_service(
+            Arc::new(legacy_search_service),
+            legacy_searcher_addr,
+        )
+        .with_protocol_version(LEGACY_PROTOCOL_VERSION);
+        let mut search_service = MockSearchService::new();
+        search_service.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::LeafSearchRequest| {
+                Ok(quickwit_proto::LeafSearchResponse {
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        let client = SearchServiceClient::from_service(Arc::new(search_service), searcher_addr);
+
+        for (query_ast, feature) in gated_query_asts_for_test() {
+            let required_features = required_protocol_features(&query_ast);
+            assert_eq!(required_features, [feature]);
+
+            let searcher_pool = SearcherPool::from_iter([
+                (legacy_searcher_addr, legacy_client.clone()),
+                (searcher_addr, client.clone()),
+            ]);
+            let search_job_placer = SearchJobPlacer::new(searcher_pool);
+            let jobs = vec![
+                SearchJob::for_test("split1", 1),
+                SearchJob::for_test("split2", 2),
+            ];
+            let assigned_addrs: Vec<SocketAddr> = search_job_placer
+                .assign_jobs_requiring_features(jobs.clone(), &required_features)
+                .await
+                .unwrap()
+                .map(|(client, _jobs)| client.grpc_addr())
+                .collect();
+            assert_eq!(assigned_addrs, [searcher_addr]);
+
+            let legacy_searcher_pool =
+                SearcherPool::from_iter([(legacy_searcher_addr, legacy_client.clone())]);
+            let error = SearchJobPlacer::new(legacy_searcher_pool)
+                .assign_jobs_requiring_features(jobs, &required_features)
+                .await
+                .err()
+                .unwrap();
+            assert!(error.to_string().contains(feature.as_str()));
+        }
+
+        // End to end, the legacy searcher never receives the leaf requests.
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "test-index",
+                    "ram:///indexes/test-index",
+                ))
+            });
+        metastore
+            .expect_list_splits()
+            .returning(|_filter| Ok(vec![mock_split("split1"), mock_split("split2")]));
+        let (function_score_query, _) = gated_query_asts_for_test().pop().unwrap();
+        let search_request = quickwit_proto::SearchRequest {
+            index_id: "test-index".to_string(),
+            query_ast: query_ast_to_json(&function_score_query).unwrap(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let searcher_pool = SearcherPool::from_iter([
+            (legacy_searcher_addr, legacy_client),
+            (searcher_addr, client),
+        ]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let search_response = root_search(
+            &SearcherContext::new(SearcherConfig::default()),
+            search_request,
+            &metastore,
+            &cluster_client,
+            &search_job_placer,
+            &IngesterPool::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 0);
+    }
+
+    #[test]
+    fn test_check_feature_flags() {
+        let search_request = SearchRequest {
+            index_id: "test-index".to_string(),
+            ..Default::default()
+        };
+        let regex_query: QueryAst = RegexQuery {
+            field: "body".to_string(),
+            regex: "te.*".to_string(),
+            case_insensitive: false,
+        }
+        .into();
+        let search_settings = SearchSettings::default();
+        check_feature_flags(&search_request, &regex_query, &search_settings).unwrap();
+
+        let search_settings = SearchSettings {
+            feature_flags: BTreeMap::from_iter([(SearchFeature::RegexQueries, false)]),
+            ..Default::default()
+        };
+        let error =
+            check_feature_flags(&search_request, &regex_query, &search_settings).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: Feature `regex_queries` is disabled on index `test-index`. It can \
+             be enabled with the `search_settings.feature_flags` index setting."
+        );
+        let bool_query: QueryAst = BoolQuery {
+            filter: vec![regex_query],
+            ..Default::default()
+        }
+        .into();
+        check_feature_flags(&search_request, &bool_query, &search_settings).unwrap_err();
+
+        let realtime_search_request = SearchRequest {
+            index_id: "test-index".to_string(),
+            realtime_window_secs: Some(60),
+            ..Default::default()
+        };
+        let search_settings = SearchSettings::default();
+        check_feature_flags(
+            &realtime_search_request,
+            &QueryAst::MatchAll,
+            &search_settings,
+        )
+        .unwrap_err();
+
+        let search_settings = SearchSettings {
+            feature_flags: BTreeMap::from_iter([(SearchFeature::RealtimeSearch, true)]),
+            ..Default::default()
+        };
+        check_feature_flags(
+            &realtime_search_request,
+            &QueryAst::MatchAll,
+            &search_settings,
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_position_token() {
+        let position_token = PositionToken {
+            partition_id: "test-partition".to_string(),
+            positions: BTreeMap::from([("test-index".to_string(), 10)]),
+        }
+        .encode();
+        let num_calls = Arc::new(AtomicUsize::new(0));
+        let num_calls_clone = num_calls.clone();
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(move |_index_id: &str| {
+                let mut index_metadata =
+                    IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+                // The documents are published on the second call.
+                let published_position = if num_calls_clone.fetch_add(1, Ordering::Relaxed) == 0 {
+                    5u64
+                } else {
+                    12u64
+                };
+                let checkpoint_delta = IndexCheckpointDelta {
+                    source_id: INGEST_API_SOURCE_ID.to_string(),
+                    source_delta: SourceCheckpointDelta::from_partition_delta(
+                        PartitionId::from("test-partition"),
+                        Position::Beginning,
+                        Position::from(published_position),
+                    )
+                    .unwrap(),
+                };
+                index_metadata
+                    .checkpoint
+                    .try_apply_delta(checkpoint_delta)
+                    .unwrap();
+                Ok(index_metadata)
+            });
+        wait_for_position_token("test-index", &position_token, &metastore)
+            .await
+            .unwrap();
+        assert_eq!(num_calls.load(Ordering::Relaxed), 2);
+
+        // The position token does not cover the index: no need to wait.
+        wait_for_position_token("other-index", &position_token, &metastore)
+            .await
+            .unwrap();
+        assert_eq!(num_calls.load(Ordering::Relaxed), 3);
+
+        let search_error = wait_for_position_token("test-index", "invalid-token", &metastore)
+            .await
+            .unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+    }
 }
@@ -23,10 +23,16 @@ use std::hash::{Hash, Hasher};
 use std::net::SocketAddr;
 
 use anyhow::bail;
+use quickwit_cluster::ProtocolFeature;
 use quickwit_common::rendezvous_hasher::sort_by_rendez_vous_hash;
 
 use crate::{SearchServiceClient, SearcherPool};
 
+/// A searcher advertising a split in its cache is assigned the jobs targeting that split as long
+/// as its load does not exceed this many times the load of the searcher that would have been
+/// chosen otherwise.
+const MAX_CACHE_AFFINITY_LOAD_RATIO: usize = 2;
+
 /// Job.
 /// The unit in which distributed search is performed.
 ///
@@ -64,6 +70,11 @@ impl SearchJobPlacer {
     pub fn new(searcher_pool: SearcherPool) -> Self {
         Self { searcher_pool }
     }
+
+    /// Returns whether the searcher listening on `grpc_addr` can be assigned jobs.
+    pub async fn contains_searcher(&self, grpc_addr: &SocketAddr) -> bool {
+        self.searcher_pool.get(grpc_addr).await.is_some()
+    }
 }
 
 impl SearchJobPlacer {
@@ -108,11 +119,14 @@ impl SearchJobPlacer {
         for job in jobs {
             sort_by_rendez_vous_hash(&mut candidate_nodes, job.split_id());
             // Select the least loaded node.
-            let chosen_node_idx = if candidate_nodes.len() >= 2 {
+            let least_loaded_node_idx = if candidate_nodes.len() >= 2 {
                 usize::from(candidate_nodes[0].load > candidate_nodes[1].load)
             } else {
                 0
             };
+            let chosen_node_idx =
+                cache_affinity_node_idx(&candidate_nodes, least_loaded_node_idx, &job)
+                    .unwrap_or(least_loaded_node_idx);
             let chosen_node = &mut candidate_nodes[chosen_node_idx];
             chosen_node.load += job.cost();
 
@@ -125,6 +139,46 @@ impl SearchJobPlacer {
         Ok(job_assignments.into_values())
     }
 
+    /// Assigns the given jobs to the searchers supporting all the `required_features`.
+    ///
+    /// During a rolling upgrade, the searchers running an older version of Quickwit are
+    /// bypassed. Fails if they are the only searchers available.
+    pub async fn assign_jobs_requiring_features<J: Job>(
+        &self,
+        jobs: Vec<J>,
+        required_features: &[ProtocolFeature],
+    ) -> anyhow::Result<impl Iterator<Item = (SearchServiceClient, Vec<J>)>> {
+        let excluded_addrs: HashSet<SocketAddr> = self
+            .searcher_pool
+            .all()
+            .await
+            .into_iter()
+            .filter(|(_, client)| !client.supports_features(required_features))
+            .map(|(grpc_addr, _)| grpc_addr)
+            .collect();
+        let job_assignments: Vec<(SearchServiceClient, Vec<J>)> =
+            self.assign_jobs(jobs, &excluded_addrs).await?.collect();
+
+        // `assign_jobs` ignores the excluded addresses when they cover all the searchers.
+        for (client, _) in &job_assignments {
+            if let Some(feature) = required_features
+                .iter()
+                .find(|feature| !feature.is_supported_by(client.protocol_version()))
+            {
+                bail!(
+                    "Failed to assign search jobs. The request relies on the {} feature, which \
+                     searcher `{}` does not support (protocol version {} < {}). Retry once the \
+                     searchers are upgraded.",
+                    feature.as_str(),
+                    client.grpc_addr(),
+                    client.protocol_version(),
+                    feature.min_protocol_version(),
+                );
+            }
+        }
+        Ok(job_assignments.into_iter())
+    }
+
     /// Assigns a single job to a client.
     pub async fn assign_job<J: Job>(
         &self,
@@ -139,6 +193,82 @@ impl SearchJobPlacer {
             .expect("`assign_jobs` should return at least one client or fail.");
         Ok(client)
     }
+
+    /// Returns the searchers a job targeting `split_id` can be assigned to, ordered by decreasing
+    /// affinity.
+    ///
+    /// Jobs are assigned to the least loaded of the two searchers with the highest affinity, so
+    /// `num_searchers` should be at least 2 to cover both candidates.
+    pub async fn affinity_searchers(
+        &self,
+        split_id: &str,
+        num_searchers: usize,
+    ) -> Vec<(SocketAddr, SearchServiceClient)> {
+        let mut candidate_nodes: Vec<CandidateNodes> = self
+            .searcher_pool
+            .all()
+            .await
+            .into_iter()
+            .map(|(grpc_addr, client)| CandidateNodes {
+                grpc_addr,
+                client,
+                load: 0,
+            })
+            .collect();
+        sort_by_rendez_vous_hash(&mut candidate_nodes, split_id);
+        candidate_nodes
+            .into_iter()
+            .take(num_searchers)
+            .map(|candidate_node| (candidate_node.grpc_addr, candidate_node.client))
+            .collect()
+    }
+
+    /// Returns the searchers advertising the split in their split footer cache, sorted by
+    /// affinity for the split.
+    pub async fn searchers_with_cached_split(&self, split_id: &str) -> Vec<SearchServiceClient> {
+        let mut candidate_nodes: Vec<CandidateNodes> = self
+            .searcher_pool
+            .all()
+            .await
+            .into_iter()
+            .filter(|(_grpc_addr, client)| client.may_have_cached_split(split_id))
+            .map(|(grpc_addr, client)| CandidateNodes {
+                grpc_addr,
+                client,
+                load: 0,
+            })
+            .collect();
+        sort_by_rendez_vous_hash(&mut candidate_nodes, split_id);
+        candidate_nodes
+            .into_iter()
+            .map(|candidate_node| candidate_node.client)
+            .collect()
+    }
+}
+
+/// Returns the node with the highest affinity advertising the split of the job in its cache,
+/// unless the node chosen by default already has it or the node is too loaded.
+///
+/// When searchers join or leave the cluster, the affinity of some splits moves to searchers that
+/// do not have them in cache yet. This keeps the jobs on the searchers holding the data.
+fn cache_affinity_node_idx<J: Job>(
+    candidate_nodes: &[CandidateNodes],
+    default_node_idx: usize,
+    job: &J,
+) -> Option<usize> {
+    let split_id = job.split_id();
+
+    if candidate_nodes[default_node_idx]
+        .client
+        .may_have_cached_split(split_id)
+    {
+        return None;
+    }
+    let max_load =
+        candidate_nodes[default_node_idx].load * MAX_CACHE_AFFINITY_LOAD_RATIO + job.cost();
+    candidate_nodes.iter().position(|candidate_node| {
+        candidate_node.load <= max_load && candidate_node.client.may_have_cached_split(split_id)
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -166,9 +296,16 @@ impl Eq for CandidateNodes {}
 mod tests {
     use std::collections::HashSet;
     use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use quickwit_cluster::{CachedSplitsDigest, ProtocolFeature, LEGACY_PROTOCOL_VERSION};
 
+    use super::Job;
     use crate::root::SearchJob;
-    use crate::{searcher_pool_for_test, MockSearchService, SearchJobPlacer, SearcherPool};
+    use crate::{
+        searcher_pool_for_test, MockSearchService, SearchJobPlacer, SearchServiceClient,
+        SearcherPool,
+    };
 
     #[tokio::test]
     async fn test_search_job_placer() {
@@ -184,6 +321,16 @@ mod tests {
             let searcher_pool =
                 searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
             let search_job_placer = SearchJobPlacer::new(searcher_pool);
+            assert!(
+                search_job_placer
+                    .contains_searcher(&([127, 0, 0, 1], 1001).into())
+                    .await
+            );
+            assert!(
+                !search_job_placer
+                    .contains_searcher(&([127, 0, 0, 1], 1002).into())
+                    .await
+            );
             let jobs = vec![
                 SearchJob::for_test("split1", 1),
                 SearchJob::for_test("split2", 2),
@@ -253,4 +400,92 @@ mod tests {
             assert_eq!(assigned_jobs, expected_assigned_jobs);
         }
     }
+
+    #[tokio::test]
+    async fn test_search_job_placer_cache_affinity() {
+        let searcher_addr_1: SocketAddr = ([127, 0, 0, 1], 1001).into();
+        let searcher_addr_2: SocketAddr = ([127, 0, 0, 1], 1002).into();
+        let split_ids: Vec<String> = (0..8).map(|i| format!("split{i}")).collect();
+        // The jobs targeting uncached splits are more expensive and assigned first.
+        let jobs: Vec<SearchJob> = split_ids
+            .iter()
+            .enumerate()
+            .map(|(i, split_id)| SearchJob::for_test(split_id, if i < 4 { 1 } else { 2 }))
+            .collect();
+        let client_1 =
+            SearchServiceClient::from_service(Arc::new(MockSearchService::new()), searcher_addr_1);
+        let client_2 =
+            SearchServiceClient::from_service(Arc::new(MockSearchService::new()), searcher_addr_2)
+                .with_cached_splits_digest(Some(Arc::new(CachedSplitsDigest::from_split_ids(
+                    &split_ids[..4],
+                ))));
+        let searcher_pool =
+            SearcherPool::from_iter([(searcher_addr_1, client_1), (searcher_addr_2, client_2)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let assigned_jobs: Vec<(SocketAddr, Vec<SearchJob>)> = search_job_placer
+            .assign_jobs(jobs, &HashSet::default())
+            .await
+            .unwrap()
+            .map(|(client, jobs)| (client.grpc_addr(), jobs))
+            .collect();
+        let searcher_2_split_ids: HashSet<&str> = assigned_jobs
+            .iter()
+            .filter(|(grpc_addr, _)| *grpc_addr == searcher_addr_2)
+            .flat_map(|(_, jobs)| jobs.iter().map(|job| job.split_id()))
+            .collect();
+        for split_id in &split_ids[..4] {
+            assert!(searcher_2_split_ids.contains(split_id.as_str()));
+        }
+        // The uncached splits are still spread over both searchers.
+        assert!(searcher_2_split_ids.len() < split_ids.len());
+    }
+
+    #[tokio::test]
+    async fn test_search_job_placer_assign_jobs_requiring_features() {
+        let legacy_searcher_addr: SocketAddr = ([127, 0, 0, 1], 1001).into();
+        let searcher_addr: SocketAddr = ([127, 0, 0, 1], 1002).into();
+        let legacy_client = SearchServiceClient::from_service(
+            Arc::new(MockSearchService::new()),
+            legacy_searcher_addr,
+        )
+        .with_protocol_version(LEGACY_PROTOCOL_VERSION);
+        let client =
+            SearchServiceClient::from_service(Arc::new(MockSearchService::new()), searcher_addr);
+        {
+            let searcher_pool = SearcherPool::from_iter([
+                (legacy_searcher_addr, legacy_client.clone()),
+                (searcher_addr, client),
+            ]);
+            let search_job_placer = SearchJobPlacer::new(searcher_pool);
+            let jobs = vec![
+                SearchJob::for_test("split1", 1),
+                SearchJob::for_test("split2", 2),
+                SearchJob::for_test("split3", 3),
+            ];
+            let assigned_jobs: Vec<(SocketAddr, Vec<SearchJob>)> = search_job_placer
+                .assign_jobs_requiring_features(jobs, &[ProtocolFeature::RegexQuery])
+                .await
+                .unwrap()
+                .map(|(client, jobs)| (client.grpc_addr(), jobs))
+                .collect();
+            assert_eq!(assigned_jobs.len(), 1);
+            assert_eq!(assigned_jobs[0].0, searcher_addr);
+            assert_eq!(assigned_jobs[0].1.len(), 3);
+        }
+        {
+            let searcher_pool = SearcherPool::from_iter([(legacy_searcher_addr, legacy_client)]);
+            let search_job_placer = SearchJobPlacer::new(searcher_pool);
+            let jobs = vec![SearchJob::for_test("split1", 1)];
+            assert!(search_job_placer
+                .assign_jobs_requiring_features(jobs.clone(), &[])
+                .await
+                .is_ok());
+            let error = search_job_placer
+                .assign_jobs_requiring_features(jobs, &[ProtocolFeature::FieldPresenceQuery])
+                .await
+                .err()
+                .unwrap();
+            assert!(error.to_string().contains("field presence query"));
+        }
+    }
 }
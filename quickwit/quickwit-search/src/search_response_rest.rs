@@ -20,7 +20,7 @@
 use std::convert::TryFrom;
 
 use quickwit_common::truncate_str;
-use quickwit_proto::SearchResponse;
+use quickwit_proto::{SearchResponse, SplitSearchProfile};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -47,6 +47,81 @@ pub struct SearchResponseRest {
     #[schema(value_type = Object)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<JsonValue>,
+    /// Profile of the search, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<SearchProfileRest>,
+}
+
+/// Profile of a search, modeled after the response of Elasticsearch's profile API.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct SearchProfileRest {
+    /// Profiles of the searched splits.
+    pub splits: Vec<SplitProfileRest>,
+}
+
+/// Profile of the search of a single split.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct SplitProfileRest {
+    /// Split ID.
+    pub id: String,
+    /// Warmup of the split, i.e. the download of the data required by the search.
+    pub warmup: WarmupProfileRest,
+    /// Searches executed on the split.
+    pub searches: Vec<SplitSearchProfileRest>,
+}
+
+/// Profile of the warmup of a split.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct WarmupProfileRest {
+    /// Number of bytes fetched from the storage.
+    pub num_bytes: u64,
+    /// Time spent warming up the split.
+    pub time_in_nanos: u64,
+}
+
+/// Profile of a search executed on a split.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct SplitSearchProfileRest {
+    /// Tree of the query nodes, with the time spent in each of them.
+    #[schema(value_type = Vec<Object>)]
+    pub query: Vec<JsonValue>,
+    /// Time spent creating the weights and scorers of the query.
+    pub scorer_init_time_in_nanos: u64,
+    /// Collectors of the matching documents.
+    pub collector: Vec<CollectorProfileRest>,
+}
+
+/// Profile of a collector.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct CollectorProfileRest {
+    /// Name of the collector.
+    pub name: String,
+    /// Time spent iterating over the matching documents and collecting them.
+    pub time_in_nanos: u64,
+}
+
+impl TryFrom<SplitSearchProfile> for SplitProfileRest {
+    type Error = SearchError;
+
+    fn try_from(split_profile: SplitSearchProfile) -> Result<Self, Self::Error> {
+        let query_profile: JsonValue = serde_json::from_str(&split_profile.query_profile)
+            .map_err(|err| SearchError::InternalError(err.to_string()))?;
+        Ok(SplitProfileRest {
+            id: split_profile.split_id,
+            warmup: WarmupProfileRest {
+                num_bytes: split_profile.warmup_num_bytes,
+                time_in_nanos: split_profile.warmup_time_nanos,
+            },
+            searches: vec![SplitSearchProfileRest {
+                query: vec![query_profile],
+                scorer_init_time_in_nanos: split_profile.scorer_init_time_nanos,
+                collector: vec![CollectorProfileRest {
+                    name: "QuickwitCollector".to_string(),
+                    time_in_nanos: split_profile.collect_time_nanos,
+                }],
+            }],
+        })
+    }
 }
 
 impl TryFrom<SearchResponse> for SearchResponseRest {
@@ -90,6 +165,17 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             None
         };
 
+        let profile_opt = if search_response.split_profiles.is_empty() {
+            None
+        } else {
+            let splits = search_response
+                .split_profiles
+                .into_iter()
+                .map(SplitProfileRest::try_from)
+                .collect::<Result<_, _>>()?;
+            Some(SearchProfileRest { splits })
+        };
+
         Ok(SearchResponseRest {
             num_hits: search_response.num_hits,
             hits: documents,
@@ -97,6 +183,7 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: aggregations_opt,
+            profile: profile_opt,
         })
     }
 }
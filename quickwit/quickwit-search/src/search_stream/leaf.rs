@@ -28,6 +28,7 @@ use quickwit_proto::{
     LeafSearchStreamResponse, OutputFormat, SearchRequest, SearchStreamRequest,
     SplitIdAndFooterOffsets,
 };
+use quickwit_query::query_ast::query_ast_from_json;
 use quickwit_storage::Storage;
 use tantivy::columnar::{DynamicColumn, HasAssociatedColumnType};
 use tantivy::fastfield::Column;
@@ -41,6 +42,7 @@ use super::collector::{PartionnedFastFieldCollector, PartitionValues};
 use super::FastFieldCollector;
 use crate::filters::{create_timestamp_filter_builder, TimestampFilterBuilder};
 use crate::leaf::{open_index_with_caches, rewrite_start_end_time_bounds, warmup};
+use crate::metadata_fields::{resolve_metadata_fields, MetadataFieldValues};
 use crate::service::SearcherContext;
 use crate::{Result, SearchError};
 
@@ -124,7 +126,7 @@ async fn leaf_search_stream_single_split(
         &split,
     );
 
-    let index = open_index_with_caches(&searcher_context, storage, &split, true).await?;
+    let index = open_index_with_caches(&searcher_context, storage, &split, true, false).await?;
     let split_schema = index.schema();
 
     let request_fields = Arc::new(SearchStreamRequestFields::from_request(
@@ -148,8 +150,11 @@ async fn leaf_search_stream_single_split(
     }
 
     let search_request = Arc::new(SearchRequest::try_from(stream_request.clone())?);
-    let query_ast = serde_json::from_str(&search_request.query_ast)
+    let query_ast = query_ast_from_json(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let metadata_field_values =
+        MetadataFieldValues::for_split_offsets(&search_request.index_id, &split);
+    let query_ast = resolve_metadata_fields(query_ast, &metadata_field_values)?;
     let (query, mut warmup_info) = doc_mapper.query(split_schema.clone(), &query_ast, false)?;
     let reader = index
         .reader_builder()
@@ -17,20 +17,24 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashSet;
-
 use bytes::Bytes;
 use futures::{StreamExt, TryStreamExt};
 use quickwit_common::uri::Uri;
 use quickwit_config::build_doc_mapper;
 use quickwit_metastore::Metastore;
 use quickwit_proto::{LeafSearchStreamRequest, SearchRequest, SearchStreamRequest};
-use quickwit_query::query_ast::QueryAst;
+use quickwit_query::query_ast::{query_ast_from_json, query_ast_to_json, QueryAst};
 use tokio_stream::StreamMap;
 use tracing::*;
 
 use crate::cluster_client::ClusterClient;
-use crate::root::{refine_start_end_timestamp_from_ast, SearchJob};
+use crate::metadata_fields::{
+    prune_splits_on_metadata_fields, resolve_metadata_fields, MetadataFieldValues,
+};
+use crate::root::{
+    check_index_is_searchable, prune_splits_on_doc_ids, refine_start_end_timestamp_from_ast,
+    required_protocol_features, SearchJob,
+};
 use crate::{list_relevant_splits, SearchError, SearchJobPlacer};
 
 /// Perform a distributed search stream.
@@ -47,6 +51,7 @@ pub async fn root_search_stream(
     let index_metadata = metastore
         .index_metadata(&search_stream_request.index_id)
         .await?;
+    check_index_is_searchable(&index_metadata)?;
     let index_uid = index_metadata.index_uid.clone();
     let index_config = index_metadata.into_index_config();
 
@@ -55,9 +60,13 @@ pub async fn root_search_stream(
             SearchError::InternalError(format!("Failed to build doc mapper. Cause: {err}"))
         })?;
 
-    let query_ast: QueryAst = serde_json::from_str(&search_stream_request.query_ast)
+    let query_ast: QueryAst = query_ast_from_json(&search_stream_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
     let query_ast_resolved = query_ast.parse_user_query(doc_mapper.default_search_fields())?;
+    let query_ast_resolved = index_config
+        .search_settings
+        .synonym_dictionary()
+        .expand_synonyms(query_ast_resolved);
 
     if let Some(timestamp_field) = doc_mapper.timestamp_field_name() {
         refine_start_end_timestamp_from_ast(
@@ -69,11 +78,22 @@ pub async fn root_search_stream(
     }
 
     // Validates the query by effectively building it against the current schema.
-    doc_mapper.query(doc_mapper.schema(), &query_ast_resolved, true)?;
-    search_stream_request.query_ast = serde_json::to_string(&query_ast_resolved)?;
+    let query_ast_for_validation = resolve_metadata_fields(
+        query_ast_resolved.clone(),
+        &MetadataFieldValues::for_validation(&search_stream_request.index_id),
+    )?;
+    doc_mapper.query(doc_mapper.schema(), &query_ast_for_validation, true)?;
+    search_stream_request.query_ast = query_ast_to_json(&query_ast_resolved)?;
 
     let search_request = SearchRequest::try_from(search_stream_request.clone())?;
     let split_metadatas = list_relevant_splits(index_uid, &search_request, metastore).await?;
+    let split_metadatas = prune_splits_on_metadata_fields(
+        &query_ast_resolved,
+        &search_request.index_id,
+        split_metadatas,
+    )?;
+    let split_metadatas =
+        prune_splits_on_doc_ids(&query_ast_resolved, &*doc_mapper, split_metadatas);
 
     let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|err| {
         SearchError::InternalError(format!("Failed to serialize doc mapper: Cause {err}"))
@@ -81,8 +101,9 @@ pub async fn root_search_stream(
 
     let index_uri: &Uri = &index_config.index_uri;
     let leaf_search_jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
+    let required_features = required_protocol_features(&query_ast_resolved);
     let assigned_leaf_search_jobs = search_job_placer
-        .assign_jobs(leaf_search_jobs, &HashSet::default())
+        .assign_jobs_requiring_features(leaf_search_jobs, &required_features)
         .await?;
 
     let mut stream_map: StreamMap<usize, _> = StreamMap::new();
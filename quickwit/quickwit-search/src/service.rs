@@ -19,29 +19,40 @@
 
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Instant;
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use quickwit_common::uri::Uri;
-use quickwit_config::SearcherConfig;
+use quickwit_config::{SearcherConfig, SplitCacheConfig};
 use quickwit_doc_mapper::DocMapper;
+use quickwit_ingest::IngesterPool;
 use quickwit_metastore::Metastore;
 use quickwit_proto::{
-    FetchDocsRequest, FetchDocsResponse, LeafListTermsRequest, LeafListTermsResponse,
-    LeafSearchRequest, LeafSearchResponse, LeafSearchStreamRequest, LeafSearchStreamResponse,
-    ListTermsRequest, ListTermsResponse, SearchRequest, SearchResponse, SearchStreamRequest,
+    FetchCachedSplitFooterRequest, FetchCachedSplitFooterResponse, FetchDocsRequest,
+    FetchDocsResponse, LeafListTermsRequest, LeafListTermsResponse, LeafSearchRequest,
+    LeafSearchResponse, LeafSearchStreamRequest, LeafSearchStreamResponse, ListTermsRequest,
+    ListTermsResponse, SearchRequest, SearchResponse, SearchStreamRequest, WarmupSplitsRequest,
+    WarmupSplitsResponse,
+};
+use quickwit_storage::{
+    Cache, DiskSplitCache, QuickwitCache, RemoteSplitCache, SplitCache, SplitFooterCache,
+    StorageResolver,
 };
-use quickwit_storage::{Cache, MemorySizedCache, QuickwitCache, StorageResolver};
 use tantivy::aggregation::AggregationLimits;
-use tokio::sync::Semaphore;
+use tokio::sync::{oneshot, Semaphore};
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use tracing::info;
+use tracing::{error, info};
 
+use crate::hit_join::{join_hits, HitJoin};
 use crate::leaf_cache::LeafSearchCache;
 use crate::search_stream::{leaf_search_stream, root_search_stream};
+use crate::shadow_search::{spawn_shadow_search, SearchOutcome, ShadowSearcher};
+use crate::split_footer_transfer::fetch_cached_split_footer;
+use crate::terms_lookup::{resolve_terms_lookups, TermsLookupCache};
 use crate::{
-    fetch_docs, leaf_list_terms, leaf_search, root_list_terms, root_search, ClusterClient,
-    SearchError, SearchJobPlacer,
+    fetch_docs, leaf_list_terms, leaf_search, root_list_terms, root_search, warmup_splits,
+    ClusterClient, SearchError, SearchJobPlacer,
 };
 
 #[derive(Clone)]
@@ -51,7 +62,9 @@ pub struct SearchServiceImpl {
     storage_resolver: StorageResolver,
     cluster_client: ClusterClient,
     search_job_placer: SearchJobPlacer,
+    ingester_pool: IngesterPool,
     searcher_context: Arc<SearcherContext>,
+    shadow_searcher_opt: Option<ShadowSearcher>,
 }
 
 /// Trait representing a search service.
@@ -113,6 +126,18 @@ pub trait SearchService: 'static + Send + Sync {
         &self,
         request: LeafListTermsRequest,
     ) -> crate::Result<LeafListTermsResponse>;
+
+    /// Fetches the hotcache and footer of the given splits into the split footer cache.
+    async fn warmup_splits(
+        &self,
+        request: WarmupSplitsRequest,
+    ) -> crate::Result<WarmupSplitsResponse>;
+
+    /// Returns the hotcache and footer of a split if it is present in the split footer cache.
+    async fn fetch_cached_split_footer(
+        &self,
+        request: FetchCachedSplitFooterRequest,
+    ) -> crate::Result<FetchCachedSplitFooterResponse>;
 }
 
 impl SearchServiceImpl {
@@ -122,16 +147,100 @@ impl SearchServiceImpl {
         storage_resolver: StorageResolver,
         cluster_client: ClusterClient,
         search_job_placer: SearchJobPlacer,
+        ingester_pool: IngesterPool,
         searcher_config: SearcherConfig,
     ) -> Self {
-        let searcher_context = Arc::new(SearcherContext::new(searcher_config));
+        let searcher_context = Arc::new(
+            SearcherContext::new(searcher_config)
+                .with_split_footer_peers(search_job_placer.clone()),
+        );
         SearchServiceImpl {
             metastore,
             storage_resolver,
             cluster_client,
             search_job_placer,
+            ingester_pool,
             searcher_context,
+            shadow_searcher_opt: None,
+        }
+    }
+
+    /// Makes the root searcher mirror a fraction of the search requests to a shadow cluster or
+    /// index.
+    pub(crate) fn with_shadow_searcher(mut self, shadow_searcher: ShadowSearcher) -> Self {
+        self.shadow_searcher_opt = Some(shadow_searcher);
+        self
+    }
+
+    pub(crate) fn searcher_context(&self) -> &Arc<SearcherContext> {
+        &self.searcher_context
+    }
+
+    /// Starts mirroring the search request to the shadow target if it is sampled. The returned
+    /// sender expects the outcome of the primary search.
+    fn start_shadow_search(
+        &self,
+        search_request: &SearchRequest,
+    ) -> Option<oneshot::Sender<SearchOutcome>> {
+        let shadow_searcher = self.shadow_searcher_opt.as_ref()?;
+        let shadow_request = shadow_searcher.sample(search_request)?;
+        let index_id = search_request.index_id.clone();
+        let shadow_index_id = shadow_request.index_id.clone();
+        let shadow_client_opt = shadow_searcher.shadow_client_opt();
+        let search_service = self.clone();
+
+        let shadow_search_fut = async move {
+            if let Some(mut shadow_client) = shadow_client_opt {
+                shadow_client.root_search(shadow_request).await
+            } else {
+                search_service
+                    .root_search_without_shadowing(shadow_request)
+                    .await
+            }
+        };
+        let primary_outcome_tx = spawn_shadow_search(index_id, shadow_index_id, shadow_search_fut);
+        Some(primary_outcome_tx)
+    }
+
+    async fn root_search_without_shadowing(
+        &self,
+        mut search_request: SearchRequest,
+    ) -> crate::Result<SearchResponse> {
+        resolve_terms_lookups(
+            &mut search_request.query_ast,
+            self,
+            self.metastore.as_ref(),
+            &self.searcher_context.terms_lookup_cache,
+        )
+        .await?;
+        // The hits are joined once fetched: taking the join out of the request keeps the leaf
+        // search cache keys independent from it.
+        let hit_join_opt = search_request
+            .join
+            .take()
+            .map(|hit_join_json| HitJoin::parse(&hit_join_json))
+            .transpose()?;
+        let index_id = search_request.index_id.clone();
+        let mut search_result = root_search(
+            &self.searcher_context,
+            search_request,
+            self.metastore.as_ref(),
+            &self.cluster_client,
+            &self.search_job_placer,
+            &self.ingester_pool,
+        )
+        .await?;
+        if let Some(hit_join) = hit_join_opt {
+            join_hits(
+                &mut search_result,
+                &index_id,
+                &hit_join,
+                self,
+                self.metastore.as_ref(),
+            )
+            .await?;
         }
+        Ok(search_result)
     }
 }
 
@@ -145,16 +254,15 @@ fn deserialize_doc_mapper(doc_mapper_str: &str) -> crate::Result<Arc<dyn DocMapp
 #[async_trait]
 impl SearchService for SearchServiceImpl {
     async fn root_search(&self, search_request: SearchRequest) -> crate::Result<SearchResponse> {
-        let search_result = root_search(
-            &self.searcher_context,
-            search_request,
-            self.metastore.as_ref(),
-            &self.cluster_client,
-            &self.search_job_placer,
-        )
-        .await?;
+        let primary_outcome_tx_opt = self.start_shadow_search(&search_request);
+        let start = Instant::now();
+        let search_result = self.root_search_without_shadowing(search_request).await;
 
-        Ok(search_result)
+        if let Some(primary_outcome_tx) = primary_outcome_tx_opt {
+            let primary_outcome = SearchOutcome::new(&search_result, start.elapsed());
+            let _ = primary_outcome_tx.send(primary_outcome);
+        }
+        search_result
     }
 
     async fn leaf_search(
@@ -178,6 +286,7 @@ impl SearchService for SearchServiceImpl {
             storage.clone(),
             &split_ids[..],
             doc_mapper,
+            leaf_search_request.bypass_caches,
         )
         .await?;
 
@@ -209,8 +318,15 @@ impl SearchService for SearchServiceImpl {
 
     async fn root_search_stream(
         &self,
-        stream_request: SearchStreamRequest,
+        mut stream_request: SearchStreamRequest,
     ) -> crate::Result<Pin<Box<dyn futures::Stream<Item = crate::Result<Bytes>> + Send>>> {
+        resolve_terms_lookups(
+            &mut stream_request.query_ast,
+            self,
+            self.metastore.as_ref(),
+            &self.searcher_context.terms_lookup_cache,
+        )
+        .await?;
         let data = root_search_stream(
             stream_request,
             self.metastore.as_ref(),
@@ -285,6 +401,36 @@ impl SearchService for SearchServiceImpl {
 
         Ok(leaf_search_response)
     }
+
+    async fn warmup_splits(
+        &self,
+        warmup_splits_request: WarmupSplitsRequest,
+    ) -> crate::Result<WarmupSplitsResponse> {
+        info!(splits=?warmup_splits_request.split_offsets, "warmup_splits");
+        let storage = self
+            .storage_resolver
+            .resolve(&Uri::from_well_formed(warmup_splits_request.index_uri))
+            .await?;
+        let warmup_splits_response = warmup_splits(
+            self.searcher_context.clone(),
+            storage,
+            &warmup_splits_request.split_offsets,
+        )
+        .await;
+        Ok(warmup_splits_response)
+    }
+
+    async fn fetch_cached_split_footer(
+        &self,
+        fetch_cached_split_footer_request: FetchCachedSplitFooterRequest,
+    ) -> crate::Result<FetchCachedSplitFooterResponse> {
+        let fetch_cached_split_footer_response = fetch_cached_split_footer(
+            &*self.searcher_context.split_footer_cache,
+            fetch_cached_split_footer_request,
+        )
+        .await;
+        Ok(fetch_cached_split_footer_response)
+    }
 }
 
 /// [`SearcherContext`] provides a common set of variables
@@ -297,12 +443,17 @@ pub struct SearcherContext {
     pub fast_fields_cache: Arc<dyn Cache>,
     /// Counting semaphore to limit concurrent leaf search split requests.
     pub leaf_search_split_semaphore: Semaphore,
-    /// Split footer cache.
-    pub split_footer_cache: MemorySizedCache<String>,
+    /// Split footer cache, backed by the backend selected in the searcher config.
+    pub split_footer_cache: Arc<dyn SplitCache>,
     /// Counting semaphore to limit concurrent split stream requests.
     pub split_stream_semaphore: Semaphore,
     /// Recent sub-query cache.
     pub leaf_search_cache: LeafSearchCache,
+    /// Cache of the terms fetched from the lookup documents of terms lookups.
+    pub terms_lookup_cache: TermsLookupCache,
+    /// Searchers whose split footer cache is looked up before fetching split footers from the
+    /// storage.
+    pub split_footer_peers_opt: Option<SearchJobPlacer>,
 }
 
 impl std::fmt::Debug for SearcherContext {
@@ -320,11 +471,7 @@ impl std::fmt::Debug for SearcherContext {
 
 impl SearcherContext {
     pub fn new(searcher_config: SearcherConfig) -> Self {
-        let capacity_in_bytes = searcher_config.split_footer_cache_capacity.get_bytes() as usize;
-        let global_split_footer_cache = MemorySizedCache::with_capacity_in_bytes(
-            capacity_in_bytes,
-            &quickwit_storage::STORAGE_METRICS.split_footer_cache,
-        );
+        let global_split_footer_cache = create_split_footer_cache(&searcher_config);
         let leaf_search_split_semaphore =
             Semaphore::new(searcher_config.max_num_concurrent_split_searches);
         let split_stream_semaphore =
@@ -342,8 +489,18 @@ impl SearcherContext {
             split_footer_cache: global_split_footer_cache,
             split_stream_semaphore,
             leaf_search_cache,
+            terms_lookup_cache: TermsLookupCache::default(),
+            split_footer_peers_opt: None,
         }
     }
+
+    /// Makes the searcher fetch the split footers missing from its cache from the cache of its
+    /// peers, when they advertise them, rather than from the storage.
+    pub fn with_split_footer_peers(mut self, search_job_placer: SearchJobPlacer) -> Self {
+        self.split_footer_peers_opt = Some(search_job_placer);
+        self
+    }
+
     // Returns a new instance to track the aggregation memory usage.
     pub fn get_aggregation_limits(&self) -> AggregationLimits {
         AggregationLimits::new(
@@ -352,3 +509,52 @@ impl SearcherContext {
         )
     }
 }
+
+/// Creates the split footer cache backed by the backend selected in the searcher config. Falls
+/// back to a memory cache if the disk cache cannot be opened.
+fn create_split_footer_cache(searcher_config: &SearcherConfig) -> Arc<dyn SplitCache> {
+    let cache_metrics = &quickwit_storage::STORAGE_METRICS.split_footer_cache;
+
+    match &searcher_config.split_cache {
+        SplitCacheConfig::Memory => {}
+        SplitCacheConfig::Disk { root_dir, capacity } => {
+            match DiskSplitCache::open(
+                root_dir.clone(),
+                capacity.get_bytes() as usize,
+                cache_metrics,
+            ) {
+                Ok(disk_split_cache) => return Arc::new(disk_split_cache),
+                Err(error) => {
+                    error!(
+                        root_dir=%root_dir.display(),
+                        error=?error,
+                        "Failed to open disk split cache. Falling back to memory split cache."
+                    );
+                }
+            }
+        }
+        SplitCacheConfig::Remote {
+            protocol,
+            endpoint,
+            ttl_secs,
+            max_item_size,
+        } => {
+            return Arc::new(RemoteSplitCache::new(
+                *protocol,
+                endpoint.clone(),
+                *ttl_secs,
+                max_item_size.get_bytes() as usize,
+                cache_metrics,
+            ));
+        }
+    }
+    let capacity_in_bytes = searcher_config.split_footer_cache_capacity.get_bytes() as usize;
+    let per_index_capacity_in_bytes = searcher_config
+        .split_footer_cache_per_index_capacity
+        .map(|per_index_capacity| per_index_capacity.get_bytes() as usize);
+    Arc::new(SplitFooterCache::new(
+        capacity_in_bytes,
+        per_index_capacity_in_bytes,
+        cache_metrics,
+    ))
+}
@@ -0,0 +1,374 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::columnar::StrColumn;
+use tantivy::fastfield::Column;
+use tantivy::{DateTime, DocId, Score, SegmentReader};
+
+use crate::find_trace_ids_collector::serde_datetime;
+
+type TermOrd = u64;
+
+const fn default_max_sessions() -> usize {
+    100
+}
+
+/// A run of events sharing the same key, in which two consecutive events are never separated by
+/// more than the configured inactivity gap.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// The value of the key field shared by all the events of the session.
+    pub key: String,
+    /// The timestamp of the first event of the session.
+    #[serde(with = "serde_datetime")]
+    pub start_timestamp: DateTime,
+    /// The timestamp of the last event of the session.
+    #[serde(with = "serde_datetime")]
+    pub end_timestamp: DateTime,
+    /// The number of events in the session.
+    pub num_events: u64,
+}
+
+/// Groups the matching events by key into sessions separated by an inactivity gap, akin to the
+/// `transaction` command found in other log analytics tools.
+///
+/// Sessions are computed over the sorted timestamps of each segment, then stitched together across
+/// segments and splits as the partial results get merged. Only the most recent sessions are
+/// returned once the final result is built on the root node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionsCollector {
+    /// The name of the fast field storing the key the events are grouped by.
+    pub session_key_field_name: String,
+    /// The name of the fast field recording the events' timestamp.
+    pub session_timestamp_field_name: String,
+    /// The maximum amount of inactivity, in seconds, between two consecutive events of the same
+    /// session.
+    pub max_gap_secs: u64,
+    /// The maximum number of sessions to return.
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: usize,
+}
+
+impl SessionsCollector {
+    /// The names of the fast fields accessed by this collector.
+    pub fn fast_field_names(&self) -> HashSet<String> {
+        HashSet::from_iter([
+            self.session_key_field_name.clone(),
+            self.session_timestamp_field_name.clone(),
+        ])
+    }
+
+    fn max_gap_nanos(&self) -> i64 {
+        i64::try_from(self.max_gap_secs)
+            .unwrap_or(i64::MAX)
+            .saturating_mul(1_000_000_000)
+    }
+
+    /// Keeps the `max_sessions` most recent sessions among a set of fully merged sessions.
+    pub fn finalize(&self, mut sessions: Vec<Session>) -> Vec<Session> {
+        sessions.sort_unstable_by(|left, right| {
+            right
+                .end_timestamp
+                .cmp(&left.end_timestamp)
+                .then_with(|| left.key.cmp(&right.key))
+        });
+        sessions.truncate(self.max_sessions);
+        sessions
+    }
+}
+
+impl Collector for SessionsCollector {
+    type Fruit = Vec<Session>;
+    type Child = SessionsSegmentCollector;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: u32,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let session_key_column = segment_reader
+            .fast_fields()
+            .str(&self.session_key_field_name)?
+            .ok_or_else(|| {
+                let err_msg = format!(
+                    "Failed to find column for session key field `{}`",
+                    self.session_key_field_name
+                );
+                tantivy::TantivyError::InternalError(err_msg)
+            })?;
+        let session_timestamp_column: Column<DateTime> = segment_reader
+            .fast_fields()
+            .date(&self.session_timestamp_field_name)?;
+        Ok(SessionsSegmentCollector {
+            session_key_column,
+            session_timestamp_column,
+            max_gap_nanos: self.max_gap_nanos(),
+            events: Vec::new(),
+        })
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        // Sessions cannot be truncated here: a session that looks old in a given split may still
+        // be extended by the events of another split.
+        Ok(merge_sessions(
+            segment_fruits.into_iter().flatten().collect(),
+            self.max_gap_nanos(),
+        ))
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+}
+
+/// Stitches together the sessions sharing the same key that overlap or are separated by no more
+/// than `max_gap_nanos`.
+fn merge_sessions(mut sessions: Vec<Session>, max_gap_nanos: i64) -> Vec<Session> {
+    sessions.sort_unstable_by(|left, right| {
+        left.key
+            .cmp(&right.key)
+            .then_with(|| left.start_timestamp.cmp(&right.start_timestamp))
+    });
+    let mut merged_sessions: Vec<Session> = Vec::with_capacity(sessions.len());
+
+    for session in sessions {
+        if let Some(running_session) = merged_sessions.last_mut() {
+            if running_session.key == session.key
+                && session.start_timestamp.into_timestamp_nanos()
+                    <= running_session
+                        .end_timestamp
+                        .into_timestamp_nanos()
+                        .saturating_add(max_gap_nanos)
+            {
+                running_session.end_timestamp =
+                    running_session.end_timestamp.max(session.end_timestamp);
+                running_session.num_events += session.num_events;
+                continue;
+            }
+        }
+        merged_sessions.push(session);
+    }
+    merged_sessions
+}
+
+pub struct SessionsSegmentCollector {
+    session_key_column: StrColumn,
+    session_timestamp_column: Column<DateTime>,
+    max_gap_nanos: i64,
+    events: Vec<(TermOrd, i64)>,
+}
+
+impl SegmentCollector for SessionsSegmentCollector {
+    type Fruit = Vec<Session>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        // Events missing either the key or the timestamp cannot be assigned to a session.
+        let Some(term_ord) = self.session_key_column.term_ords(doc).next() else {
+            return;
+        };
+        let Some(session_timestamp) = self.session_timestamp_column.first(doc) else {
+            return;
+        };
+        self.events
+            .push((term_ord, session_timestamp.into_timestamp_nanos()));
+    }
+
+    fn harvest(mut self) -> Self::Fruit {
+        self.events.sort_unstable();
+
+        let mut key = String::new();
+        sessionize(&self.events, self.max_gap_nanos)
+            .into_iter()
+            .map(
+                |(term_ord, start_timestamp_nanos, end_timestamp_nanos, num_events)| {
+                    let found_term = self
+                        .session_key_column
+                        .ord_to_str(term_ord, &mut key)
+                        .expect("Failed to lookup session key in the column term dictionary");
+                    debug_assert!(found_term);
+                    Session {
+                        key: key.clone(),
+                        start_timestamp: DateTime::from_timestamp_nanos(start_timestamp_nanos),
+                        end_timestamp: DateTime::from_timestamp_nanos(end_timestamp_nanos),
+                        num_events,
+                    }
+                },
+            )
+            .collect()
+    }
+}
+
+/// Splits a list of events sorted by key and timestamp into sessions. Sessions are returned as
+/// `(term_ord, start_timestamp_nanos, end_timestamp_nanos, num_events)` tuples.
+fn sessionize(
+    sorted_events: &[(TermOrd, i64)],
+    max_gap_nanos: i64,
+) -> Vec<(TermOrd, i64, i64, u64)> {
+    let mut sessions: Vec<(TermOrd, i64, i64, u64)> = Vec::new();
+
+    for &(term_ord, timestamp_nanos) in sorted_events {
+        if let Some(running_session) = sessions.last_mut() {
+            if running_session.0 == term_ord
+                && timestamp_nanos <= running_session.2.saturating_add(max_gap_nanos)
+            {
+                running_session.2 = timestamp_nanos;
+                running_session.3 += 1;
+                continue;
+            }
+        }
+        sessions.push((term_ord, timestamp_nanos, timestamp_nanos, 1));
+    }
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::QuickwitAggregations;
+
+    impl Session {
+        fn for_test(
+            key: &str,
+            start_timestamp_secs: i64,
+            end_timestamp_secs: i64,
+            num_events: u64,
+        ) -> Self {
+            Self {
+                key: key.to_string(),
+                start_timestamp: DateTime::from_timestamp_secs(start_timestamp_secs),
+                end_timestamp: DateTime::from_timestamp_secs(end_timestamp_secs),
+                num_events,
+            }
+        }
+    }
+
+    #[test]
+    fn test_sessions_collector_serde() {
+        let collector_json = r#"{
+            "session_key_field_name": "user_id",
+            "session_timestamp_field_name": "timestamp",
+            "max_gap_secs": 1800
+        }"#;
+        let aggregation: QuickwitAggregations = serde_json::from_str(collector_json).unwrap();
+        let QuickwitAggregations::SessionsAggregation(collector) = aggregation else {
+            panic!("Expected SessionsAggregation");
+        };
+        assert_eq!(collector.session_key_field_name, "user_id");
+        assert_eq!(collector.session_timestamp_field_name, "timestamp");
+        assert_eq!(collector.max_gap_secs, 1800);
+        assert_eq!(collector.max_sessions, 100);
+    }
+
+    #[test]
+    fn test_sessionize() {
+        assert!(sessionize(&[], 10).is_empty());
+        assert_eq!(sessionize(&[(0, 5)], 10), &[(0, 5, 5, 1)]);
+
+        let sorted_events = [(0, 0), (0, 10), (0, 25), (0, 30), (1, 30), (1, 40), (2, 0)];
+        assert_eq!(
+            sessionize(&sorted_events, 10),
+            &[(0, 0, 10, 2), (0, 25, 30, 2), (1, 30, 40, 2), (2, 0, 0, 1)]
+        );
+        assert_eq!(
+            sessionize(&sorted_events, 0),
+            &[
+                (0, 0, 0, 1),
+                (0, 10, 10, 1),
+                (0, 25, 25, 1),
+                (0, 30, 30, 1),
+                (1, 30, 30, 1),
+                (1, 40, 40, 1),
+                (2, 0, 0, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_sessions() {
+        let max_gap_nanos = 10 * 1_000_000_000;
+        {
+            let merged_sessions = merge_sessions(Vec::new(), max_gap_nanos);
+            assert!(merged_sessions.is_empty());
+        }
+        {
+            let sessions = vec![
+                Session::for_test("foo", 20, 30, 2),
+                Session::for_test("foo", 0, 10, 3),
+                Session::for_test("bar", 5, 5, 1),
+            ];
+            let merged_sessions = merge_sessions(sessions, max_gap_nanos);
+            assert_eq!(
+                merged_sessions,
+                &[
+                    Session::for_test("bar", 5, 5, 1),
+                    Session::for_test("foo", 0, 30, 5),
+                ]
+            );
+        }
+        {
+            let sessions = vec![
+                Session::for_test("foo", 0, 100, 10),
+                Session::for_test("foo", 20, 30, 2),
+                Session::for_test("foo", 111, 120, 2),
+                Session::for_test("bar", 0, 5, 2),
+                Session::for_test("bar", 16, 20, 2),
+            ];
+            let merged_sessions = merge_sessions(sessions, max_gap_nanos);
+            assert_eq!(
+                merged_sessions,
+                &[
+                    Session::for_test("bar", 0, 5, 2),
+                    Session::for_test("bar", 16, 20, 2),
+                    Session::for_test("foo", 0, 100, 12),
+                    Session::for_test("foo", 111, 120, 2),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_sessions_collector_finalize() {
+        let collector = SessionsCollector {
+            session_key_field_name: "user_id".to_string(),
+            session_timestamp_field_name: "timestamp".to_string(),
+            max_gap_secs: 10,
+            max_sessions: 2,
+        };
+        let sessions = vec![
+            Session::for_test("foo", 0, 10, 2),
+            Session::for_test("bar", 20, 30, 2),
+            Session::for_test("foo", 25, 30, 2),
+            Session::for_test("qux", 0, 5, 1),
+        ];
+        assert_eq!(
+            collector.finalize(sessions),
+            &[
+                Session::for_test("bar", 20, 30, 2),
+                Session::for_test("foo", 25, 30, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use quickwit_config::ShadowSearchConfig;
+use quickwit_proto::{SearchRequest, SearchResponse};
+use rand::Rng;
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use crate::metrics::SEARCH_METRICS;
+use crate::{create_search_client_from_grpc_addr, SearchServiceClient};
+
+/// Mirrors a fraction of the root search requests to a shadow cluster or to a second version of
+/// the searched index. See [`ShadowSearchConfig`].
+#[derive(Clone)]
+pub(crate) struct ShadowSearcher {
+    sample_rate: f64,
+    index_ids: BTreeMap<String, String>,
+    /// Client of a searcher of the shadow cluster. The requests are mirrored to the local cluster
+    /// if `None`.
+    shadow_client_opt: Option<SearchServiceClient>,
+}
+
+impl ShadowSearcher {
+    pub async fn new(shadow_search_config: &ShadowSearchConfig) -> anyhow::Result<Self> {
+        let shadow_client_opt = match shadow_search_config.endpoint_addr()? {
+            Some(endpoint_addr) => {
+                let grpc_addr = endpoint_addr.resolve().await.with_context(|| {
+                    format!("Failed to resolve shadow search endpoint `{endpoint_addr}`.")
+                })?;
+                Some(create_search_client_from_grpc_addr(grpc_addr))
+            }
+            None => None,
+        };
+        Ok(Self {
+            sample_rate: shadow_search_config.sample_rate,
+            index_ids: shadow_search_config.index_ids.clone(),
+            shadow_client_opt,
+        })
+    }
+
+    /// Returns the client of the shadow cluster, or `None` if the requests are mirrored to the
+    /// local cluster.
+    pub fn shadow_client_opt(&self) -> Option<SearchServiceClient> {
+        self.shadow_client_opt.clone()
+    }
+
+    /// Draws whether the search request is mirrored and, if so, returns the request to send to
+    /// the shadow target.
+    pub fn sample(&self, search_request: &SearchRequest) -> Option<SearchRequest> {
+        let shadow_index_id_opt = self.index_ids.get(&search_request.index_id);
+
+        // Mirroring a request to the same index of the same cluster would compare the index with
+        // itself.
+        if self.shadow_client_opt.is_none() && shadow_index_id_opt.is_none() {
+            return None;
+        }
+        if !rand::thread_rng().gen_bool(self.sample_rate) {
+            return None;
+        }
+        let mut shadow_request = search_request.clone();
+
+        if let Some(shadow_index_id) = shadow_index_id_opt {
+            shadow_request.index_id = shadow_index_id.clone();
+        }
+        Some(shadow_request)
+    }
+}
+
+/// Summary of the outcome of a search, compared between the primary and the shadow searches.
+#[derive(Debug)]
+pub(crate) struct SearchOutcome {
+    num_hits_res: Result<u64, String>,
+    elapsed: Duration,
+}
+
+impl SearchOutcome {
+    pub fn new(search_result: &crate::Result<SearchResponse>, elapsed: Duration) -> Self {
+        let num_hits_res = match search_result {
+            Ok(search_response) => Ok(search_response.num_hits),
+            Err(search_error) => Err(search_error.to_string()),
+        };
+        Self {
+            num_hits_res,
+            elapsed,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ShadowSearchVerdict {
+    /// Both searches returned the same number of hits or both failed.
+    Match,
+    /// Both searches succeeded but returned a different number of hits.
+    NumHitsMismatch,
+    /// Only one of the searches failed.
+    ErrorMismatch,
+}
+
+impl ShadowSearchVerdict {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ShadowSearchVerdict::Match => "match",
+            ShadowSearchVerdict::NumHitsMismatch => "num_hits_mismatch",
+            ShadowSearchVerdict::ErrorMismatch => "error_mismatch",
+        }
+    }
+}
+
+fn compare_outcomes(
+    primary_outcome: &SearchOutcome,
+    shadow_outcome: &SearchOutcome,
+) -> ShadowSearchVerdict {
+    match (&primary_outcome.num_hits_res, &shadow_outcome.num_hits_res) {
+        (Ok(primary_num_hits), Ok(shadow_num_hits)) if primary_num_hits == shadow_num_hits => {
+            ShadowSearchVerdict::Match
+        }
+        (Ok(_), Ok(_)) => ShadowSearchVerdict::NumHitsMismatch,
+        (Err(_), Err(_)) => ShadowSearchVerdict::Match,
+        _ => ShadowSearchVerdict::ErrorMismatch,
+    }
+}
+
+/// Runs the shadow search in the background and compares its outcome with the outcome of the
+/// primary search, which is expected on the returned sender.
+pub(crate) fn spawn_shadow_search(
+    index_id: String,
+    shadow_index_id: String,
+    shadow_search_fut: impl Future<Output = crate::Result<SearchResponse>> + Send + 'static,
+) -> oneshot::Sender<SearchOutcome> {
+    let (primary_outcome_tx, primary_outcome_rx) = oneshot::channel();
+    tokio::spawn(run_shadow_search(
+        index_id,
+        shadow_index_id,
+        shadow_search_fut,
+        primary_outcome_rx,
+    ));
+    primary_outcome_tx
+}
+
+async fn run_shadow_search(
+    index_id: String,
+    shadow_index_id: String,
+    shadow_search_fut: impl Future<Output = crate::Result<SearchResponse>>,
+    primary_outcome_rx: oneshot::Receiver<SearchOutcome>,
+) -> Option<ShadowSearchVerdict> {
+    let start = Instant::now();
+    let shadow_search_result = shadow_search_fut.await;
+    let shadow_outcome = SearchOutcome::new(&shadow_search_result, start.elapsed());
+
+    // The sender is dropped if the primary search is cancelled, for instance when the client goes
+    // away.
+    let primary_outcome = primary_outcome_rx.await.ok()?;
+    let verdict = compare_outcomes(&primary_outcome, &shadow_outcome);
+
+    SEARCH_METRICS
+        .shadow_searches_total
+        .with_label_values([verdict.as_str()])
+        .inc();
+    SEARCH_METRICS
+        .shadow_search_duration_secs
+        .with_label_values(["primary"])
+        .observe(primary_outcome.elapsed.as_secs_f64());
+    SEARCH_METRICS
+        .shadow_search_duration_secs
+        .with_label_values(["shadow"])
+        .observe(shadow_outcome.elapsed.as_secs_f64());
+
+    if verdict == ShadowSearchVerdict::Match {
+        debug!(
+            index_id=%index_id,
+            shadow_index_id=%shadow_index_id,
+            primary_elapsed_ms=%primary_outcome.elapsed.as_millis(),
+            shadow_elapsed_ms=%shadow_outcome.elapsed.as_millis(),
+            "Shadow search matches primary search."
+        );
+    } else {
+        warn!(
+            index_id=%index_id,
+            shadow_index_id=%shadow_index_id,
+            verdict=%verdict.as_str(),
+            primary_num_hits=?primary_outcome.num_hits_res,
+            shadow_num_hits=?shadow_outcome.num_hits_res,
+            primary_elapsed_ms=%primary_outcome.elapsed.as_millis(),
+            shadow_elapsed_ms=%shadow_outcome.elapsed.as_millis(),
+            "Shadow search differs from primary search."
+        );
+    }
+    Some(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchError;
+
+    fn outcome_for_test(num_hits_res: Result<u64, &str>) -> SearchOutcome {
+        SearchOutcome {
+            num_hits_res: num_hits_res.map_err(|error| error.to_string()),
+            elapsed: Duration::from_millis(10),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shadow_searcher_sample() {
+        let shadow_search_config = ShadowSearchConfig {
+            sample_rate: 1.0,
+            endpoint: None,
+            index_ids: BTreeMap::from_iter([("hdfs-logs".to_string(), "hdfs-logs-v2".to_string())]),
+        };
+        let shadow_searcher = ShadowSearcher::new(&shadow_search_config).await.unwrap();
+        assert!(shadow_searcher.shadow_client_opt().is_none());
+
+        let search_request = SearchRequest {
+            index_id: "hdfs-logs".to_string(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let shadow_request = shadow_searcher.sample(&search_request).unwrap();
+        assert_eq!(shadow_request.index_id, "hdfs-logs-v2");
+        assert_eq!(shadow_request.max_hits, 10);
+
+        let search_request = SearchRequest {
+            index_id: "otel-logs".to_string(),
+            ..Default::default()
+        };
+        assert!(shadow_searcher.sample(&search_request).is_none());
+    }
+
+    #[test]
+    fn test_compare_outcomes() {
+        assert_eq!(
+            compare_outcomes(&outcome_for_test(Ok(3)), &outcome_for_test(Ok(3))),
+            ShadowSearchVerdict::Match
+        );
+        assert_eq!(
+            compare_outcomes(&outcome_for_test(Ok(3)), &outcome_for_test(Ok(4))),
+            ShadowSearchVerdict::NumHitsMismatch
+        );
+        assert_eq!(
+            compare_outcomes(&outcome_for_test(Ok(3)), &outcome_for_test(Err("timeout"))),
+            ShadowSearchVerdict::ErrorMismatch
+        );
+        assert_eq!(
+            compare_outcomes(&outcome_for_test(Err("timeout")), &outcome_for_test(Ok(3))),
+            ShadowSearchVerdict::ErrorMismatch
+        );
+        assert_eq!(
+            compare_outcomes(
+                &outcome_for_test(Err("timeout")),
+                &outcome_for_test(Err("timeout"))
+            ),
+            ShadowSearchVerdict::Match
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_shadow_search() {
+        let (primary_outcome_tx, primary_outcome_rx) = oneshot::channel();
+        primary_outcome_tx.send(outcome_for_test(Ok(3))).unwrap();
+        let shadow_search_fut = async {
+            Ok(SearchResponse {
+                num_hits: 4,
+                ..Default::default()
+            })
+        };
+        let verdict = run_shadow_search(
+            "hdfs-logs".to_string(),
+            "hdfs-logs-v2".to_string(),
+            shadow_search_fut,
+            primary_outcome_rx,
+        )
+        .await;
+        assert_eq!(verdict, Some(ShadowSearchVerdict::NumHitsMismatch));
+
+        let (primary_outcome_tx, primary_outcome_rx) = oneshot::channel();
+        drop(primary_outcome_tx);
+        let shadow_search_fut = async {
+            Err(SearchError::InternalError(
+                "Shadow cluster is down.".to_string(),
+            ))
+        };
+        let verdict = run_shadow_search(
+            "hdfs-logs".to_string(),
+            "hdfs-logs-v2".to_string(),
+            shadow_search_fut,
+            primary_outcome_rx,
+        )
+        .await;
+        assert!(verdict.is_none());
+    }
+}
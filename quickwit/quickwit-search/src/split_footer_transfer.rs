@@ -0,0 +1,280 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::bail;
+use quickwit_proto::{
+    FetchCachedSplitFooterRequest, FetchCachedSplitFooterResponse, SplitIdAndFooterOffsets,
+};
+use quickwit_storage::{OwnedBytes, SplitCache};
+use tracing::{debug, warn};
+
+use crate::metrics::SEARCH_METRICS;
+use crate::SearchJobPlacer;
+
+/// Maximum number of peers asked for a split footer before falling back to the storage. The split
+/// digests advertised by the peers may be stale or yield false positives.
+const MAX_NUM_PEERS: usize = 2;
+
+/// Returns the hotcache and footer of the split if it is present in the split footer cache.
+///
+/// The storage is never hit: peers asking for a split footer fall back to the storage themselves.
+pub(crate) async fn fetch_cached_split_footer(
+    split_footer_cache: &dyn SplitCache,
+    request: FetchCachedSplitFooterRequest,
+) -> FetchCachedSplitFooterResponse {
+    let Some(split_footer) = split_footer_cache.get(&request.split_id).await else {
+        return FetchCachedSplitFooterResponse::default();
+    };
+    let split_footer_md5 = md5::compute(split_footer.as_slice()).0.to_vec();
+
+    FetchCachedSplitFooterResponse {
+        is_cached: true,
+        split_footer: split_footer.to_vec(),
+        split_footer_md5,
+    }
+}
+
+/// Fetches the hotcache and footer of the split from the split footer cache of the peer
+/// searchers advertising it.
+///
+/// Returns `None` if none of the peers could provide a split footer passing the integrity checks,
+/// in which case the caller is expected to fetch it from the storage.
+pub(crate) async fn fetch_split_footer_from_peers(
+    search_job_placer: &SearchJobPlacer,
+    split_and_footer_offsets: &SplitIdAndFooterOffsets,
+) -> Option<OwnedBytes> {
+    let split_id = &split_and_footer_offsets.split_id;
+    let peers = search_job_placer
+        .searchers_with_cached_split(split_id)
+        .await;
+
+    for mut peer in peers.into_iter().take(MAX_NUM_PEERS) {
+        let request = FetchCachedSplitFooterRequest {
+            split_id: split_id.clone(),
+        };
+        let response = match peer.fetch_cached_split_footer(request).await {
+            Ok(response) => response,
+            Err(error) => {
+                warn!(
+                    split_id=%split_id,
+                    peer=%peer.grpc_addr(),
+                    error=?error,
+                    "Failed to fetch split footer from peer."
+                );
+                continue;
+            }
+        };
+        if !response.is_cached {
+            continue;
+        }
+        match check_split_footer_integrity(response, split_and_footer_offsets) {
+            Ok(split_footer) => {
+                debug!(
+                    split_id=%split_id,
+                    peer=%peer.grpc_addr(),
+                    "Fetched split footer from peer."
+                );
+                SEARCH_METRICS.split_footer_peer_fetches_total.inc();
+                return Some(split_footer);
+            }
+            Err(error) => {
+                warn!(
+                    split_id=%split_id,
+                    peer=%peer.grpc_addr(),
+                    error=?error,
+                    "Discarding split footer received from peer."
+                );
+            }
+        }
+    }
+    None
+}
+
+/// Checks that the split footer received from a peer has the length recorded in the split
+/// metadata and was not corrupted during the transfer.
+fn check_split_footer_integrity(
+    response: FetchCachedSplitFooterResponse,
+    split_and_footer_offsets: &SplitIdAndFooterOffsets,
+) -> anyhow::Result<OwnedBytes> {
+    let expected_num_bytes =
+        split_and_footer_offsets.split_footer_end - split_and_footer_offsets.split_footer_start;
+    let num_bytes = response.split_footer.len() as u64;
+
+    if num_bytes != expected_num_bytes {
+        bail!("Expected a split footer of {expected_num_bytes} bytes, got {num_bytes} bytes.");
+    }
+    let split_footer_md5 = md5::compute(&response.split_footer);
+
+    if split_footer_md5.0[..] != response.split_footer_md5[..] {
+        bail!("The MD5 digest of the split footer does not match.");
+    }
+    Ok(OwnedBytes::new(response.split_footer))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use quickwit_cluster::CachedSplitsDigest;
+    use quickwit_config::SearcherConfig;
+
+    use super::*;
+    use crate::service::SearcherContext;
+    use crate::{MockSearchService, SearchServiceClient, SearcherPool};
+
+    fn split_offsets_for_test(split_id: &str, num_bytes: u64) -> SplitIdAndFooterOffsets {
+        SplitIdAndFooterOffsets {
+            split_id: split_id.to_string(),
+            split_footer_start: 100,
+            split_footer_end: 100 + num_bytes,
+            timestamp_start: None,
+            timestamp_end: None,
+            create_timestamp: None,
+        }
+    }
+
+    fn peer_for_test(
+        grpc_addr: &str,
+        cached_split_ids: &[&str],
+        mock_search_service: MockSearchService,
+    ) -> (SocketAddr, SearchServiceClient) {
+        let grpc_addr: SocketAddr = grpc_addr.parse().unwrap();
+        let cached_splits_digest = CachedSplitsDigest::from_split_ids(cached_split_ids);
+        let client = SearchServiceClient::from_service(Arc::new(mock_search_service), grpc_addr)
+            .with_cached_splits_digest(Some(Arc::new(cached_splits_digest)));
+        (grpc_addr, client)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cached_split_footer() {
+        let searcher_context = SearcherContext::new(SearcherConfig::default());
+        let split_footer_cache = &*searcher_context.split_footer_cache;
+        split_footer_cache
+            .put(
+                "ram:///indexes/test-index",
+                "split-1".to_string(),
+                OwnedBytes::new(&b"footer"[..]),
+            )
+            .await;
+        let request = FetchCachedSplitFooterRequest {
+            split_id: "split-1".to_string(),
+        };
+        let response = fetch_cached_split_footer(split_footer_cache, request).await;
+        assert!(response.is_cached);
+        assert_eq!(response.split_footer, b"footer");
+        assert_eq!(
+            response.split_footer_md5,
+            md5::compute(b"footer").0.to_vec()
+        );
+
+        let request = FetchCachedSplitFooterRequest {
+            split_id: "split-2".to_string(),
+        };
+        let response = fetch_cached_split_footer(split_footer_cache, request).await;
+        assert!(!response.is_cached);
+        assert!(response.split_footer.is_empty());
+    }
+
+    #[test]
+    fn test_check_split_footer_integrity() {
+        let split_offsets = split_offsets_for_test("split-1", 6);
+        {
+            let response = FetchCachedSplitFooterResponse {
+                is_cached: true,
+                split_footer: b"footer".to_vec(),
+                split_footer_md5: md5::compute(b"footer").0.to_vec(),
+            };
+            let split_footer = check_split_footer_integrity(response, &split_offsets).unwrap();
+            assert_eq!(split_footer.as_slice(), b"footer");
+        }
+        {
+            let response = FetchCachedSplitFooterResponse {
+                is_cached: true,
+                split_footer: b"foot".to_vec(),
+                split_footer_md5: md5::compute(b"foot").0.to_vec(),
+            };
+            let error = check_split_footer_integrity(response, &split_offsets).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "Expected a split footer of 6 bytes, got 4 bytes."
+            );
+        }
+        {
+            let response = FetchCachedSplitFooterResponse {
+                is_cached: true,
+                split_footer: b"fooler".to_vec(),
+                split_footer_md5: md5::compute(b"footer").0.to_vec(),
+            };
+            let error = check_split_footer_integrity(response, &split_offsets).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "The MD5 digest of the split footer does not match."
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_split_footer_from_peers() {
+        let mut corrupting_search_service = MockSearchService::new();
+        corrupting_search_service
+            .expect_fetch_cached_split_footer()
+            .returning(|_| {
+                Ok(FetchCachedSplitFooterResponse {
+                    is_cached: true,
+                    split_footer: b"fooler".to_vec(),
+                    split_footer_md5: md5::compute(b"footer").0.to_vec(),
+                })
+            });
+        let mut caching_search_service = MockSearchService::new();
+        caching_search_service
+            .expect_fetch_cached_split_footer()
+            .returning(|_| {
+                Ok(FetchCachedSplitFooterResponse {
+                    is_cached: true,
+                    split_footer: b"footer".to_vec(),
+                    split_footer_md5: md5::compute(b"footer").0.to_vec(),
+                })
+            });
+        let mut unrelated_search_service = MockSearchService::new();
+        unrelated_search_service
+            .expect_fetch_cached_split_footer()
+            .never();
+
+        let searcher_pool = SearcherPool::from_iter([
+            peer_for_test("127.0.0.1:1001", &["split-1"], corrupting_search_service),
+            peer_for_test("127.0.0.1:1002", &["split-1"], caching_search_service),
+            peer_for_test("127.0.0.1:1003", &["split-2"], unrelated_search_service),
+        ]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+
+        let split_offsets = split_offsets_for_test("split-1", 6);
+        let split_footer = fetch_split_footer_from_peers(&search_job_placer, &split_offsets)
+            .await
+            .unwrap();
+        assert_eq!(split_footer.as_slice(), b"footer");
+
+        let split_offsets = split_offsets_for_test("split-3", 6);
+        assert!(
+            fetch_split_footer_from_peers(&search_job_placer, &split_offsets)
+                .await
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use quickwit_common::pubsub::EventSubscriber;
+use quickwit_metastore::{
+    IndexState, ListSplitsQuery, Metastore, MetastoreEvent, MetastoreResult, SplitState,
+};
+use quickwit_proto::{IndexUid, SplitIdAndFooterOffsets, WarmupSplitsRequest};
+use tantivy::time::OffsetDateTime;
+use tracing::warn;
+
+use crate::{extract_split_and_footer_offsets, SearchJobPlacer, SearchServiceClient};
+
+/// Number of searchers a published split is warmed up on. Search jobs are assigned to the least
+/// loaded of the two searchers with the highest affinity for the split.
+const NUM_AFFINITY_SEARCHERS: usize = 2;
+
+/// Published splits are looked up among the splits updated within this period before the event is
+/// handled.
+const PUBLISH_LOOKBACK_SECS: i64 = 60;
+
+/// Notifies searchers when splits are published so that they fetch the hotcache and footer of
+/// the new splits before the first query targeting them arrives.
+///
+/// Each split is only warmed up on the searchers the search job placer is likely to assign it to.
+#[derive(Clone)]
+pub struct SplitWarmupNotifier {
+    metastore: Arc<dyn Metastore>,
+    search_job_placer: SearchJobPlacer,
+}
+
+impl SplitWarmupNotifier {
+    /// Creates a new split warmup notifier.
+    pub fn new(metastore: Arc<dyn Metastore>, search_job_placer: SearchJobPlacer) -> Self {
+        Self {
+            metastore,
+            search_job_placer,
+        }
+    }
+
+    async fn warmup_published_splits(
+        &self,
+        index_uid: IndexUid,
+        split_ids: Vec<String>,
+    ) -> MetastoreResult<()> {
+        let index_metadata = self.metastore.index_metadata(index_uid.index_id()).await?;
+
+        // Closed indexes are not searchable and searches on frozen indexes bypass the split footer
+        // cache.
+        if index_metadata.state != IndexState::Open {
+            return Ok(());
+        }
+        let update_timestamp_lower_bound =
+            OffsetDateTime::now_utc().unix_timestamp() - PUBLISH_LOOKBACK_SECS;
+        let query = ListSplitsQuery::for_index(index_uid)
+            .with_split_state(SplitState::Published)
+            .with_update_timestamp_gte(update_timestamp_lower_bound);
+        let split_ids: HashSet<String> = split_ids.into_iter().collect();
+        let splits = self.metastore.list_splits(query).await?;
+
+        let mut assignments: HashMap<
+            SocketAddr,
+            (SearchServiceClient, Vec<SplitIdAndFooterOffsets>),
+        > = HashMap::new();
+
+        for split in splits {
+            if !split_ids.contains(split.split_id()) {
+                continue;
+            }
+            let split_offsets = extract_split_and_footer_offsets(&split.split_metadata);
+            let affinity_searchers = self
+                .search_job_placer
+                .affinity_searchers(split.split_id(), NUM_AFFINITY_SEARCHERS)
+                .await;
+
+            for (grpc_addr, client) in affinity_searchers {
+                assignments
+                    .entry(grpc_addr)
+                    .or_insert_with(|| (client, Vec::new()))
+                    .1
+                    .push(split_offsets.clone());
+            }
+        }
+        let index_uri = index_metadata.index_uri().to_string();
+        let warmup_futures =
+            assignments
+                .into_iter()
+                .map(|(grpc_addr, (mut client, split_offsets))| {
+                    let warmup_splits_request = WarmupSplitsRequest {
+                        index_uri: index_uri.clone(),
+                        split_offsets,
+                    };
+                    async move {
+                        if let Err(error) = client.warmup_splits(warmup_splits_request).await {
+                            warn!(grpc_addr=%grpc_addr, error=?error, "Failed to warm up splits.");
+                        }
+                    }
+                });
+        join_all(warmup_futures).await;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SplitWarmupNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SplitWarmupNotifier")
+            .field("metastore_uri", self.metastore.uri())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl EventSubscriber<MetastoreEvent> for SplitWarmupNotifier {
+    async fn handle_event(&mut self, event: MetastoreEvent) {
+        let MetastoreEvent::PublishSplits {
+            index_uid,
+            split_ids,
+        } = event
+        else {
+            return;
+        };
+        if let Err(error) = self
+            .warmup_published_splits(index_uid.clone(), split_ids)
+            .await
+        {
+            warn!(index_id=%index_uid.index_id(), error=?error, "Failed to warm up published splits.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::mock_split;
+    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_proto::WarmupSplitsResponse;
+
+    use super::*;
+    use crate::{searcher_pool_for_test, MockSearchService};
+
+    #[tokio::test]
+    async fn test_split_warmup_notifier() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "test-index",
+                    "ram:///indexes/test-index",
+                ))
+            });
+        metastore
+            .expect_list_splits()
+            .returning(|_query| Ok(vec![mock_split("split1"), mock_split("split2")]));
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_warmup_splits()
+            .times(1)
+            .returning(|warmup_splits_request: WarmupSplitsRequest| {
+                assert_eq!(warmup_splits_request.index_uri, "ram:///indexes/test-index");
+                assert_eq!(warmup_splits_request.split_offsets.len(), 1);
+                assert_eq!(warmup_splits_request.split_offsets[0].split_id, "split1");
+                assert_eq!(
+                    warmup_splits_request.split_offsets[0].split_footer_start,
+                    700
+                );
+                assert_eq!(warmup_splits_request.split_offsets[0].split_footer_end, 800);
+                Ok(WarmupSplitsResponse {
+                    num_warmed_up_splits: 1,
+                })
+            });
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+
+        let mut split_warmup_notifier =
+            SplitWarmupNotifier::new(Arc::new(metastore), search_job_placer);
+
+        let event = MetastoreEvent::DeleteIndex {
+            index_uid: IndexUid::new("test-index"),
+        };
+        split_warmup_notifier.handle_event(event).await;
+
+        let event = MetastoreEvent::PublishSplits {
+            index_uid: IndexUid::new("test-index"),
+            split_ids: vec!["split1".to_string()],
+        };
+        split_warmup_notifier.handle_event(event).await;
+    }
+
+    #[tokio::test]
+    async fn test_split_warmup_notifier_ignores_frozen_indexes() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                let mut index_metadata =
+                    IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+                index_metadata.state = IndexState::Frozen;
+                Ok(index_metadata)
+            });
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+
+        let mut split_warmup_notifier =
+            SplitWarmupNotifier::new(Arc::new(metastore), search_job_placer);
+
+        let event = MetastoreEvent::PublishSplits {
+            index_uid: IndexUid::new("test-index"),
+            split_ids: vec!["split1".to_string()],
+        };
+        split_warmup_notifier.handle_event(event).await;
+    }
+}
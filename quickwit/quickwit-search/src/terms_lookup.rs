@@ -0,0 +1,413 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Resolution of the terms lookups of a query.
+//!
+//! A terms lookup fetches the terms of a terms query from a document of another index. The root
+//! fetches the lookup documents by their doc ID before dispatching the query, and caches their
+//! terms for a short period of time.
+
+use std::collections::{BTreeSet, HashMap};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use lru::LruCache;
+use quickwit_metastore::Metastore;
+use quickwit_proto::SearchRequest;
+use quickwit_query::query_ast::{
+    query_ast_from_json, query_ast_to_json, QueryAst, QueryAstTransformer, QueryAstVisitor,
+    TermQuery, TermsLookupQuery,
+};
+use serde_json::Value as JsonValue;
+use tokio::time::Instant;
+
+use crate::{SearchError, SearchService};
+
+/// Period of time during which the terms of a lookup document are reused without fetching the
+/// document again. Updates of lookup documents become visible once it has elapsed.
+const TERMS_LOOKUP_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Maximum number of lookup documents whose terms are cached.
+const TERMS_LOOKUP_CACHE_CAPACITY: usize = 1_000;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+struct TermsLookupKey {
+    index_id: String,
+    doc_id: String,
+    path: String,
+}
+
+impl From<&TermsLookupQuery> for TermsLookupKey {
+    fn from(terms_lookup_query: &TermsLookupQuery) -> Self {
+        TermsLookupKey {
+            index_id: terms_lookup_query.index_id.clone(),
+            doc_id: terms_lookup_query.doc_id.clone(),
+            path: terms_lookup_query.path.clone(),
+        }
+    }
+}
+
+/// A cache of the terms fetched from lookup documents.
+pub struct TermsLookupCache {
+    entries: Mutex<LruCache<TermsLookupKey, (Instant, Arc<BTreeSet<String>>)>>,
+}
+
+impl Default for TermsLookupCache {
+    fn default() -> Self {
+        let capacity = NonZeroUsize::new(TERMS_LOOKUP_CACHE_CAPACITY).unwrap();
+        TermsLookupCache {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+impl TermsLookupCache {
+    fn get(&self, key: &TermsLookupKey) -> Option<Arc<BTreeSet<String>>> {
+        let mut entries = self.entries.lock().unwrap();
+        let (fetched_at, terms) = entries.get(key)?;
+        if fetched_at.elapsed() >= TERMS_LOOKUP_CACHE_TTL {
+            entries.pop(key);
+            return None;
+        }
+        Some(terms.clone())
+    }
+
+    fn put(&self, key: TermsLookupKey, terms: Arc<BTreeSet<String>>) {
+        self.entries
+            .lock()
+            .unwrap()
+            .put(key, (Instant::now(), terms));
+    }
+}
+
+#[derive(Default)]
+struct ExtractTermsLookups<'a> {
+    terms_lookups: Vec<&'a TermsLookupQuery>,
+}
+
+impl<'a> QueryAstVisitor<'a> for ExtractTermsLookups<'a> {
+    type Err = std::convert::Infallible;
+
+    fn visit_terms_lookup(
+        &mut self,
+        terms_lookup_query: &'a TermsLookupQuery,
+    ) -> Result<(), Self::Err> {
+        self.terms_lookups.push(terms_lookup_query);
+        Ok(())
+    }
+}
+
+/// Replaces the terms lookups of the serialized query AST with the equivalent term set queries.
+/// The query is left untouched if it does not contain any terms lookup.
+pub(crate) async fn resolve_terms_lookups(
+    query_ast_json: &mut String,
+    search_service: &dyn SearchService,
+    metastore: &dyn Metastore,
+    terms_lookup_cache: &TermsLookupCache,
+) -> crate::Result<()> {
+    let query_ast: QueryAst = query_ast_from_json(query_ast_json)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let mut extract_terms_lookups = ExtractTermsLookups::default();
+    extract_terms_lookups
+        .visit(&query_ast)
+        .expect("can't fail unwrapping Infallible");
+    if extract_terms_lookups.terms_lookups.is_empty() {
+        return Ok(());
+    }
+    let mut terms_per_lookup: HashMap<TermsLookupKey, Arc<BTreeSet<String>>> = HashMap::new();
+
+    for terms_lookup_query in extract_terms_lookups.terms_lookups {
+        let key = TermsLookupKey::from(terms_lookup_query);
+        if terms_per_lookup.contains_key(&key) {
+            continue;
+        }
+        let terms = match terms_lookup_cache.get(&key) {
+            Some(terms) => terms,
+            None => {
+                let terms =
+                    Arc::new(fetch_terms(terms_lookup_query, search_service, metastore).await?);
+                terms_lookup_cache.put(key.clone(), terms.clone());
+                terms
+            }
+        };
+        terms_per_lookup.insert(key, terms);
+    }
+    let resolved_query_ast = replace_terms_lookups(query_ast, &terms_per_lookup);
+    *query_ast_json = query_ast_to_json(&resolved_query_ast).map_err(|err| {
+        SearchError::InternalError(format!("Failed to serialize query ast: Cause {err}"))
+    })?;
+    Ok(())
+}
+
+/// Fetches the lookup document by its doc ID and extracts its terms. A missing document yields no
+/// terms.
+async fn fetch_terms(
+    terms_lookup_query: &TermsLookupQuery,
+    search_service: &dyn SearchService,
+    metastore: &dyn Metastore,
+) -> crate::Result<BTreeSet<String>> {
+    let index_id = &terms_lookup_query.index_id;
+    let index_metadata = metastore.index_metadata(index_id).await?;
+    let Some(doc_id_config) = index_metadata.index_config.doc_mapping.doc_id else {
+        return Err(SearchError::InvalidQuery(format!(
+            "Index `{index_id}` does not define a doc ID field: its documents cannot be used for \
+             terms lookups."
+        )));
+    };
+    let query_ast: QueryAst = TermQuery {
+        field: doc_id_config.field,
+        value: terms_lookup_query.doc_id.clone(),
+    }
+    .into();
+    let search_request = SearchRequest {
+        index_id: index_id.clone(),
+        query_ast: query_ast_to_json(&query_ast).map_err(|err| {
+            SearchError::InternalError(format!("Failed to serialize query ast: Cause {err}"))
+        })?,
+        max_hits: 1,
+        ..Default::default()
+    };
+    let search_response = search_service.root_search(search_request).await?;
+
+    let Some(hit) = search_response.hits.into_iter().next() else {
+        return Ok(BTreeSet::new());
+    };
+    let lookup_doc: JsonValue = serde_json::from_str(&hit.json)?;
+    terms_lookup_query
+        .extract_terms(&lookup_doc)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))
+}
+
+/// Replaces the terms lookups with the term set queries holding their fetched terms.
+struct ReplaceTermsLookups<'a> {
+    terms_per_lookup: &'a HashMap<TermsLookupKey, Arc<BTreeSet<String>>>,
+}
+
+impl<'a> QueryAstTransformer for ReplaceTermsLookups<'a> {
+    type Err = std::convert::Infallible;
+
+    fn transform_terms_lookup(
+        &mut self,
+        terms_lookup_query: TermsLookupQuery,
+    ) -> Result<QueryAst, Self::Err> {
+        let key = TermsLookupKey::from(&terms_lookup_query);
+        let terms = self
+            .terms_per_lookup
+            .get(&key)
+            .map(|terms| terms.as_ref().clone())
+            .unwrap_or_default();
+        Ok(terms_lookup_query.resolve(terms))
+    }
+}
+
+fn replace_terms_lookups(
+    query_ast: QueryAst,
+    terms_per_lookup: &HashMap<TermsLookupKey, Arc<BTreeSet<String>>>,
+) -> QueryAst {
+    ReplaceTermsLookups { terms_per_lookup }
+        .transform(query_ast)
+        .expect("can't fail unwrapping Infallible")
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::DocIdConfig;
+    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_proto::{Hit, SearchResponse};
+    use quickwit_query::query_ast::{BoolQuery, TermSetQuery};
+    use serde_json::json;
+
+    use super::*;
+    use crate::MockSearchService;
+
+    fn terms_lookup_query_for_test(field: &str) -> TermsLookupQuery {
+        TermsLookupQuery {
+            field: field.to_string(),
+            index_id: "allow-lists".to_string(),
+            doc_id: "list-1".to_string(),
+            path: "user_ids".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_terms_lookups() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .times(1)
+            .returning(|index_id| {
+                assert_eq!(index_id, "allow-lists");
+                let mut index_metadata =
+                    IndexMetadata::for_test("allow-lists", "ram:///indexes/allow-lists");
+                index_metadata.index_config.doc_mapping.doc_id = Some(DocIdConfig {
+                    field: "list_id".to_string(),
+                    generation: Default::default(),
+                });
+                Ok(index_metadata)
+            });
+        let mut search_service = MockSearchService::new();
+        search_service
+            .expect_root_search()
+            .times(1)
+            .returning(|search_request| {
+                assert_eq!(search_request.index_id, "allow-lists");
+                assert_eq!(search_request.max_hits, 1);
+                let query_ast: QueryAst = query_ast_from_json(&search_request.query_ast).unwrap();
+                assert_eq!(query_ast, QueryAst::term("list_id", "list-1"));
+                Ok(SearchResponse {
+                    hits: vec![Hit {
+                        json: json!({"list_id": "list-1", "user_ids": ["alice", "bob"]})
+                            .to_string(),
+                        partial_hit: None,
+                        snippet: None,
+                    }],
+                    num_hits: 1,
+                    ..Default::default()
+                })
+            });
+        let terms_lookup_cache = TermsLookupCache::default();
+
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![QueryAst::term("status", "active")],
+            should: vec![
+                terms_lookup_query_for_test("user_id").into(),
+                terms_lookup_query_for_test("owner_id").into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        let mut query_ast_json = query_ast_to_json(&query_ast).unwrap();
+        resolve_terms_lookups(
+            &mut query_ast_json,
+            &search_service,
+            &metastore,
+            &terms_lookup_cache,
+        )
+        .await
+        .unwrap();
+
+        let user_ids = BTreeSet::from(["alice".to_string(), "bob".to_string()]);
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![QueryAst::term("status", "active")],
+            should: vec![
+                TermSetQuery {
+                    terms_per_field: HashMap::from([("user_id".to_string(), user_ids.clone())]),
+                }
+                .into(),
+                TermSetQuery {
+                    terms_per_field: HashMap::from([("owner_id".to_string(), user_ids)]),
+                }
+                .into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            query_ast_from_json(&query_ast_json).unwrap(),
+            expected_query_ast
+        );
+
+        // The terms of the lookup document are now cached.
+        let mut query_ast_json =
+            query_ast_to_json(&terms_lookup_query_for_test("user_id").into()).unwrap();
+        resolve_terms_lookups(
+            &mut query_ast_json,
+            &search_service,
+            &metastore,
+            &terms_lookup_cache,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            query_ast_from_json(&query_ast_json).unwrap(),
+            QueryAst::TermSet(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_terms_lookups_missing_doc() {
+        let mut metastore = MockMetastore::new();
+        metastore.expect_index_metadata().returning(|_| {
+            let mut index_metadata =
+                IndexMetadata::for_test("allow-lists", "ram:///indexes/allow-lists");
+            index_metadata.index_config.doc_mapping.doc_id = Some(DocIdConfig {
+                field: "list_id".to_string(),
+                generation: Default::default(),
+            });
+            Ok(index_metadata)
+        });
+        let mut search_service = MockSearchService::new();
+        search_service
+            .expect_root_search()
+            .returning(|_| Ok(SearchResponse::default()));
+
+        let mut query_ast_json =
+            query_ast_to_json(&terms_lookup_query_for_test("user_id").into()).unwrap();
+        resolve_terms_lookups(
+            &mut query_ast_json,
+            &search_service,
+            &metastore,
+            &TermsLookupCache::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            query_ast_from_json(&query_ast_json).unwrap(),
+            QueryAst::MatchNone
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_terms_lookups_requires_doc_id_field() {
+        let mut metastore = MockMetastore::new();
+        metastore.expect_index_metadata().returning(|_| {
+            Ok(IndexMetadata::for_test(
+                "allow-lists",
+                "ram:///indexes/allow-lists",
+            ))
+        });
+        let search_service = MockSearchService::new();
+
+        let mut query_ast_json =
+            query_ast_to_json(&terms_lookup_query_for_test("user_id").into()).unwrap();
+        let search_error = resolve_terms_lookups(
+            &mut query_ast_json,
+            &search_service,
+            &metastore,
+            &TermsLookupCache::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidQuery(_)));
+    }
+
+    #[tokio::test]
+    async fn test_terms_lookup_cache_expires_entries() {
+        tokio::time::pause();
+        let terms_lookup_cache = TermsLookupCache::default();
+        let key = TermsLookupKey::from(&terms_lookup_query_for_test("user_id"));
+        let terms = Arc::new(BTreeSet::from(["alice".to_string()]));
+        terms_lookup_cache.put(key.clone(), terms.clone());
+        assert_eq!(terms_lookup_cache.get(&key), Some(terms));
+
+        tokio::time::advance(TERMS_LOOKUP_CACHE_TTL).await;
+        assert!(terms_lookup_cache.get(&key).is_none());
+    }
+}
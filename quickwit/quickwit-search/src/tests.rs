@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
 use quickwit_config::SearcherConfig;
@@ -28,6 +28,7 @@ use quickwit_proto::{
     qast_helper, query_ast_from_user_text, LeafListTermsResponse, SearchRequest, SortOrder,
     SortValue,
 };
+use quickwit_query::query_ast::TermSetQuery;
 use serde_json::{json, Value as JsonValue};
 use tantivy::schema::Value as TantivyValue;
 use tantivy::time::OffsetDateTime;
@@ -35,6 +36,7 @@ use tantivy::Term;
 
 use super::*;
 use crate::find_trace_ids_collector::Span;
+use crate::sessions_collector::Session;
 use crate::single_node_search;
 
 #[tokio::test]
@@ -80,6 +82,128 @@ async fn test_single_node_simple() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_doc_id_field_alias() -> anyhow::Result<()> {
+    let index_id = "single-node-doc-id-alias";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: id
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: body
+                type: text
+            doc_id:
+              field: id
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"id": "doc-1", "body": "hello"}),
+        json!({"id": "doc-2", "body": "hello"}),
+        json!({"id": "doc-3", "body": "hello"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+
+    let ids_query_ast: QueryAst = TermSetQuery {
+        terms_per_field: HashMap::from([(
+            "_id".to_string(),
+            BTreeSet::from([
+                "doc-1".to_string(),
+                "doc-3".to_string(),
+                "doc-4".to_string(),
+            ]),
+        )]),
+    }
+    .into();
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query_ast: serde_json::to_string(&ids_query_ast)?,
+        max_hits: 10,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 2);
+
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query_ast: qast_helper("_id:doc-2", &[]),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+    let hit_json: JsonValue = serde_json::from_str(&single_node_result.hits[0].json)?;
+    assert_eq!(hit_json["id"], "doc-2");
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_profile() -> anyhow::Result<()> {
+    let index_id = "single-node-profile";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "snoopy", "body": "Snoopy is an anthropomorphic beagle."}),
+        json!({"title": "beagle", "body": "The beagle is a breed of small scent hound."}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query_ast: qast_helper("beagle AND title:snoopy", &["body"]),
+        max_hits: 2,
+        profile: true,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+    assert_eq!(single_node_result.split_profiles.len(), 1);
+    let split_profile = &single_node_result.split_profiles[0];
+    assert!(split_profile.warmup_num_bytes > 0);
+    let query_profile: JsonValue = serde_json::from_str(&split_profile.query_profile)?;
+    assert_eq!(query_profile["type"], "BooleanQuery");
+    assert_eq!(query_profile["children"].as_array().unwrap().len(), 2);
+    let build_scorer_count = query_profile["breakdown"]["build_scorer_count"].as_u64();
+    assert!(build_scorer_count.unwrap() >= 1);
+
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query_ast: qast_helper("beagle", &["body"]),
+        max_hits: 2,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert!(single_node_result.split_profiles.is_empty());
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_termset() -> anyhow::Result<()> {
     let index_id = "single-node-termset-1";
@@ -629,6 +753,66 @@ async fn test_sort_bm25() {
     test_sandbox.assert_quit().await;
 }
 
+#[tokio::test]
+async fn test_sort_bm25_without_tracking_total_hits() {
+    let index_id = "sort_by_bm25_without_tracking_total_hits".to_string();
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+                record: freq
+                fieldnorms: true
+            "#;
+    let test_sandbox = TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &["body"])
+        .await
+        .unwrap();
+    let docs: Vec<JsonValue> = (0..2_000)
+        .map(|doc_id| {
+            if doc_id % 500 == 7 {
+                json!({ "body": "common rare rare rare" })
+            } else {
+                json!({ "body": format!("common filler{doc_id}") })
+            }
+        })
+        .collect();
+    test_sandbox.add_documents(docs).await.unwrap();
+    let search = |track_total_hits: Option<bool>| {
+        let query_ast_json =
+            serde_json::to_string(&query_ast_from_user_text("common rare", None)).unwrap();
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query_ast: query_ast_json,
+            max_hits: 3,
+            sort_by_field: Some("_score".to_string()),
+            sort_order: Some(SortOrder::Desc as i32),
+            track_total_hits,
+            ..Default::default()
+        };
+        let metastore = test_sandbox.metastore();
+        let storage_resolver = test_sandbox.storage_resolver();
+        async move {
+            let search_response = single_node_search(search_request, &*metastore, storage_resolver)
+                .await
+                .unwrap();
+            let doc_ids: Vec<u32> = search_response
+                .hits
+                .into_iter()
+                .map(|hit| hit.partial_hit.unwrap().doc_id)
+                .collect();
+            (search_response.num_hits, doc_ids)
+        }
+    };
+    let (exact_num_hits, exact_doc_ids) = search(None).await;
+    assert_eq!(exact_num_hits, 2_000);
+    assert_eq!(exact_doc_ids, vec![7, 507, 1007]);
+
+    let (lower_bound_num_hits, pruned_doc_ids) = search(Some(false)).await;
+    assert_eq!(pruned_doc_ids, exact_doc_ids);
+    assert!(lower_bound_num_hits >= 3);
+    assert!(lower_bound_num_hits < 2_000);
+    test_sandbox.assert_quit().await;
+}
+
 #[tokio::test]
 async fn test_sort_by_static_and_dynamic_field() {
     let index_id = "sort_by_dynamic_field".to_string();
@@ -873,6 +1057,7 @@ async fn test_search_dynamic_util(test_sandbox: &TestSandbox, query: &str) -> Ve
         test_sandbox.storage(),
         &splits_offsets,
         test_sandbox.doc_mapper(),
+        false,
     )
     .await
     .unwrap();
@@ -1277,6 +1462,102 @@ async fn test_single_node_aggregation_missing_fast_field() {
     test_sandbox.assert_quit().await;
 }
 
+#[tokio::test]
+async fn test_single_node_histogram_aggregation_hard_bounds() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-hard-bounds";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: service
+                type: text
+              - name: latency
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["service"]).await?;
+    // Each call to `add_documents` creates a split.
+    test_sandbox
+        .add_documents(vec![
+            json!({"service": "api", "latency": 10}),
+            json!({"service": "api", "latency": 15}),
+            json!({"service": "db", "latency": 25}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"service": "api", "latency": 1_000}),
+            json!({"service": "db", "latency": 1_500}),
+        ])
+        .await?;
+    {
+        let agg_req = r#"
+            {
+                "latencies": {
+                    "histogram": {
+                        "field": "latency",
+                        "interval": 10,
+                        "hard_bounds": {"min": 0, "max": 100}
+                    }
+                }
+            }"#;
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query_ast: qast_helper("*", &[]),
+            max_hits: 10,
+            aggregation_request: Some(agg_req.to_string()),
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await?;
+        // The split outside of the bounds is still searched for hits.
+        assert_eq!(single_node_result.num_hits, 5);
+        assert_eq!(single_node_result.hits.len(), 5);
+        let agg_res_json: JsonValue =
+            serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+        assert_eq!(
+            agg_res_json["latencies"]["buckets"],
+            json!([
+                {"key": 10.0, "doc_count": 2},
+                {"key": 20.0, "doc_count": 1},
+            ])
+        );
+    }
+    {
+        let agg_req = r#"
+            {
+                "latencies": {
+                    "histogram": {
+                        "field": "latency",
+                        "interval": 10,
+                        "hard_bounds": {"min": 5000, "max": 10000}
+                    }
+                }
+            }"#;
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query_ast: qast_helper("api", &["service"]),
+            max_hits: 10,
+            aggregation_request: Some(agg_req.to_string()),
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await?;
+        assert_eq!(single_node_result.num_hits, 3);
+        let agg_res_json: JsonValue =
+            serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+        assert_eq!(agg_res_json["latencies"]["buckets"], json!([]));
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_with_ip_field() -> anyhow::Result<()> {
     let index_id = "single-node-with-ip-field";
@@ -1662,3 +1943,117 @@ async fn test_single_node_find_trace_ids_collector() {
     }
     test_sandbox.assert_quit().await;
 }
+
+#[tokio::test]
+async fn test_single_node_sessions_collector() {
+    let index_id = "single-node-sessions-collector";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: user_id
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: timestamp
+                type: datetime
+                fast: true
+                precision: seconds
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[])
+        .await
+        .unwrap();
+    // The events of the `foo` session are spread over two splits.
+    test_sandbox
+        .add_documents(vec![
+            json!({"user_id": "foo", "timestamp": "2023-01-10T15:00:00Z"}),
+            json!({"user_id": "foo", "timestamp": "2023-01-10T15:04:00Z"}),
+            json!({"user_id": "foo", "timestamp": "2023-01-10T18:00:00Z"}),
+            json!({"user_id": "bar", "timestamp": "2023-01-10T16:00:00Z"}),
+            json!({"timestamp": "2023-01-10T16:00:00Z"}),
+        ])
+        .await
+        .unwrap();
+    test_sandbox
+        .add_documents(vec![
+            json!({"user_id": "foo", "timestamp": "2023-01-10T15:08:00Z"}),
+            json!({"user_id": "bar", "timestamp": "2023-01-10T17:00:00Z"}),
+        ])
+        .await
+        .unwrap();
+    {
+        let aggregations = r#"{
+            "session_key_field_name": "user_id",
+            "session_timestamp_field_name": "timestamp",
+            "max_gap_secs": 300,
+            "max_sessions": 3
+        }"#
+        .to_string();
+
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query_ast: qast_helper("*", &[]),
+            aggregation_request: Some(aggregations),
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap();
+        let aggregation = single_node_result.aggregation.unwrap();
+        let sessions: Vec<Session> = serde_json::from_str(&aggregation).unwrap();
+        let sessions: Vec<(&str, i64, i64, u64)> = sessions
+            .iter()
+            .map(|session| {
+                (
+                    session.key.as_str(),
+                    session.start_timestamp.into_timestamp_secs(),
+                    session.end_timestamp.into_timestamp_secs(),
+                    session.num_events,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sessions,
+            &[
+                ("foo", 1673373600, 1673373600, 1),
+                ("bar", 1673370000, 1673370000, 1),
+                ("bar", 1673366400, 1673366400, 1),
+            ]
+        );
+    }
+    {
+        let aggregations = r#"{
+            "session_key_field_name": "user_id",
+            "session_timestamp_field_name": "timestamp",
+            "max_gap_secs": 300
+        }"#
+        .to_string();
+
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query_ast: qast_helper("user_id:foo", &[]),
+            aggregation_request: Some(aggregations),
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap();
+        let aggregation = single_node_result.aggregation.unwrap();
+        let sessions: Vec<Session> = serde_json::from_str(&aggregation).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1].key, "foo");
+        assert_eq!(
+            sessions[1].start_timestamp.into_timestamp_secs(),
+            1673362800
+        );
+        assert_eq!(sessions[1].end_timestamp.into_timestamp_secs(), 1673363280);
+        assert_eq!(sessions[1].num_events, 3);
+    }
+    test_sandbox.assert_quit().await;
+}
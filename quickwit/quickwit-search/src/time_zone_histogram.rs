@@ -0,0 +1,838 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Time zone aware date histogram aggregations.
+//!
+//! Tantivy buckets dates on a grid of fixed intervals anchored at the UTC epoch, which cannot
+//! express local days: they start at local midnight and last 23 or 25 hours on daylight saving
+//! time transitions. The root searcher rewrites the date histograms carrying a `time_zone`
+//! parameter into range aggregations covering the local days (or weeks, or months) of the searched
+//! time range, and converts the results of the range aggregations back into histogram buckets.
+
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, LocalResult, Months, NaiveDate, SecondsFormat, TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::SearchRequest;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use crate::SearchError;
+
+/// Maximum number of buckets a time zone aware date histogram can produce, in line with the
+/// default bucket limit of tantivy aggregations.
+const MAX_NUM_BUCKETS: usize = 65_000;
+
+/// Parameters of the tantivy date histogram that cannot be combined with a time zone.
+const UNSUPPORTED_PARAMS: [&str; 3] = ["offset", "hard_bounds", "extended_bounds"];
+
+/// Returns the time range `[start, end)`, in seconds, covered by the splits and the realtime window
+/// of the request.
+pub(crate) fn searched_time_range(
+    search_request: &SearchRequest,
+    split_metadatas: &[SplitMetadata],
+) -> Range<i64> {
+    let mut start_timestamp = i64::MAX;
+    let mut end_timestamp = i64::MIN;
+
+    for time_range in split_metadatas
+        .iter()
+        .filter_map(|split_metadata| split_metadata.time_range.as_ref())
+    {
+        start_timestamp = start_timestamp.min(*time_range.start());
+        end_timestamp = end_timestamp.max(time_range.end().saturating_add(1));
+    }
+    if let Some(realtime_window_secs) = search_request.realtime_window_secs {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+        start_timestamp = start_timestamp.min(now.saturating_sub(realtime_window_secs as i64));
+        end_timestamp = end_timestamp.max(now.saturating_add(1));
+    }
+    if let Some(request_start_timestamp) = search_request.start_timestamp {
+        start_timestamp = start_timestamp.max(request_start_timestamp);
+    }
+    if let Some(request_end_timestamp) = search_request.end_timestamp {
+        end_timestamp = end_timestamp.min(request_end_timestamp);
+    }
+    start_timestamp..end_timestamp
+}
+
+/// Rewrites the date histograms of the aggregation request carrying a `time_zone` parameter into
+/// range aggregations with one range per local bucket of `time_range`. Returns `None` if the
+/// request does not contain any such date histogram.
+pub(crate) fn rewrite_time_zone_date_histograms(
+    aggregation_request: &str,
+    timestamp_field_opt: Option<&str>,
+    time_range: Range<i64>,
+) -> crate::Result<Option<String>> {
+    let Ok(JsonValue::Object(mut agg_requests)) = serde_json::from_str(aggregation_request) else {
+        // Quickwit-specific aggregations are not JSON objects.
+        return Ok(None);
+    };
+    if !rewrite_aggregations(&mut agg_requests, timestamp_field_opt, &time_range)? {
+        return Ok(None);
+    }
+    let rewritten_aggregation_request = serde_json::to_string(&agg_requests)?;
+    Ok(Some(rewritten_aggregation_request))
+}
+
+/// Returns whether at least one date histogram was rewritten.
+fn rewrite_aggregations(
+    agg_requests: &mut JsonMap<String, JsonValue>,
+    timestamp_field_opt: Option<&str>,
+    time_range: &Range<i64>,
+) -> crate::Result<bool> {
+    let mut rewritten = false;
+
+    for (agg_name, agg_request) in agg_requests.iter_mut() {
+        let Some(agg_request) = agg_request.as_object_mut() else {
+            continue;
+        };
+        for sub_aggs_key in ["aggs", "aggregations"] {
+            if let Some(JsonValue::Object(sub_agg_requests)) = agg_request.get_mut(sub_aggs_key) {
+                rewritten |=
+                    rewrite_aggregations(sub_agg_requests, timestamp_field_opt, time_range)?;
+            }
+        }
+        let Some(date_histogram) = agg_request
+            .get("date_histogram")
+            .and_then(JsonValue::as_object)
+        else {
+            continue;
+        };
+        let Some(params) = TimeZoneHistogramParams::parse(agg_name, date_histogram)? else {
+            continue;
+        };
+        if timestamp_field_opt != Some(params.field.as_str()) {
+            return Err(invalid_aggregation(
+                agg_name,
+                "`time_zone` is only supported on the timestamp field of the index",
+            ));
+        }
+        let ranges = params.build_ranges(agg_name, time_range)?;
+        agg_request.remove("date_histogram");
+        agg_request.insert(
+            "range".to_string(),
+            json!({
+                "field": params.field,
+                "ranges": ranges,
+                "keyed": true,
+            }),
+        );
+        rewritten = true;
+    }
+    Ok(rewritten)
+}
+
+/// Converts the results of the range aggregations produced by
+/// [`rewrite_time_zone_date_histograms`] back into date histogram results. `aggregation_request`
+/// is the original aggregation request.
+pub(crate) fn convert_time_zone_date_histogram_results(
+    aggregation: &mut String,
+    aggregation_request: &str,
+) -> crate::Result<()> {
+    let Ok(JsonValue::Object(agg_requests)) = serde_json::from_str(aggregation_request) else {
+        return Ok(());
+    };
+    let mut aggregation_json: JsonValue = serde_json::from_str(aggregation)?;
+    let Some(agg_results) = aggregation_json.as_object_mut() else {
+        return Ok(());
+    };
+    if convert_aggregation_results(&agg_requests, agg_results)? {
+        *aggregation = serde_json::to_string(&aggregation_json)?;
+    }
+    Ok(())
+}
+
+/// Walks the aggregation request and results in parallel. Returns whether at least one date
+/// histogram result was converted.
+fn convert_aggregation_results(
+    agg_requests: &JsonMap<String, JsonValue>,
+    agg_results: &mut JsonMap<String, JsonValue>,
+) -> crate::Result<bool> {
+    let mut converted = false;
+
+    for (agg_name, agg_request) in agg_requests {
+        let Some(agg_result) = agg_results.get_mut(agg_name) else {
+            continue;
+        };
+        let params_opt = match agg_request
+            .get("date_histogram")
+            .and_then(JsonValue::as_object)
+        {
+            Some(date_histogram) => TimeZoneHistogramParams::parse(agg_name, date_histogram)?,
+            None => None,
+        };
+        if let Some(params) = &params_opt {
+            *agg_result = params.convert_range_result(agg_result);
+            converted = true;
+        }
+        let Some(sub_agg_requests) = agg_request
+            .get("aggs")
+            .or_else(|| agg_request.get("aggregations"))
+            .and_then(JsonValue::as_object)
+        else {
+            continue;
+        };
+        let buckets: Vec<&mut JsonValue> = match agg_result.get_mut("buckets") {
+            Some(JsonValue::Array(buckets)) => buckets.iter_mut().collect(),
+            // Keyed buckets.
+            Some(JsonValue::Object(buckets)) => buckets.values_mut().collect(),
+            _ => continue,
+        };
+        for bucket in buckets {
+            if let Some(bucket) = bucket.as_object_mut() {
+                converted |= convert_aggregation_results(sub_agg_requests, bucket)?;
+            }
+        }
+    }
+    Ok(converted)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HistogramTimeZone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl HistogramTimeZone {
+    /// Parses an IANA time zone name, such as `Europe/Paris`, or a fixed UTC offset, such as
+    /// `+05:30`.
+    fn parse(time_zone_str: &str) -> Option<Self> {
+        if let Ok(time_zone) = time_zone_str.parse::<Tz>() {
+            return Some(HistogramTimeZone::Named(time_zone));
+        }
+        time_zone_str.parse().ok().map(HistogramTimeZone::Fixed)
+    }
+
+    fn local_date(&self, timestamp_millis: i64) -> Option<NaiveDate> {
+        let utc_datetime = Utc.timestamp_millis_opt(timestamp_millis).single()?;
+        let local_date = match self {
+            HistogramTimeZone::Named(time_zone) => {
+                utc_datetime.with_timezone(time_zone).date_naive()
+            }
+            HistogramTimeZone::Fixed(offset) => utc_datetime.with_timezone(offset).date_naive(),
+        };
+        Some(local_date)
+    }
+
+    /// Returns the timestamp in milliseconds of the start of the local day. When the clocks move
+    /// forward at midnight, the day starts at the first valid local time.
+    fn start_of_day_millis(&self, date: NaiveDate) -> Option<i64> {
+        let midnight = date.and_hms_opt(0, 0, 0)?;
+        (0..=24 * 4).find_map(|num_quarters| {
+            let local_datetime = midnight + chrono::Duration::minutes(15 * num_quarters);
+            match self {
+                HistogramTimeZone::Named(time_zone) => {
+                    earliest_millis(time_zone.from_local_datetime(&local_datetime))
+                }
+                HistogramTimeZone::Fixed(offset) => {
+                    earliest_millis(offset.from_local_datetime(&local_datetime))
+                }
+            }
+        })
+    }
+
+    /// Formats the timestamp as an RFC 3339 date in the time zone, e.g.
+    /// `2023-03-26T00:00:00+01:00`.
+    fn format_millis(&self, timestamp_millis: i64) -> Option<String> {
+        let utc_datetime = Utc.timestamp_millis_opt(timestamp_millis).single()?;
+        let formatted = match self {
+            HistogramTimeZone::Named(time_zone) => utc_datetime
+                .with_timezone(time_zone)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            HistogramTimeZone::Fixed(offset) => utc_datetime
+                .with_timezone(offset)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+        };
+        Some(formatted)
+    }
+}
+
+fn earliest_millis<T: TimeZone>(local_result: LocalResult<DateTime<T>>) -> Option<i64> {
+    match local_result {
+        LocalResult::Single(datetime) | LocalResult::Ambiguous(datetime, _) => {
+            Some(datetime.timestamp_millis())
+        }
+        LocalResult::None => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CalendarInterval {
+    Days(u32),
+    Week,
+    Month,
+}
+
+impl CalendarInterval {
+    fn parse(date_histogram: &JsonMap<String, JsonValue>) -> Option<Self> {
+        if let Some(calendar_interval) = date_histogram
+            .get("calendar_interval")
+            .and_then(JsonValue::as_str)
+        {
+            return match calendar_interval {
+                "day" | "1d" => Some(CalendarInterval::Days(1)),
+                "week" | "1w" => Some(CalendarInterval::Week),
+                "month" | "1M" => Some(CalendarInterval::Month),
+                _ => None,
+            };
+        }
+        let num_days: u32 = date_histogram
+            .get("fixed_interval")
+            .and_then(JsonValue::as_str)?
+            .strip_suffix('d')?
+            .parse()
+            .ok()?;
+        (num_days > 0).then_some(CalendarInterval::Days(num_days))
+    }
+
+    /// Returns the first day of the bucket containing `date`. Buckets of several days are aligned
+    /// on the local epoch, and weeks start on Monday.
+    fn bucket_start(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            CalendarInterval::Days(num_days) => {
+                let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+                let num_days_since_epoch = (date - epoch).num_days();
+                let offset = num_days_since_epoch.rem_euclid(*num_days as i64) as u64;
+                date.checked_sub_days(Days::new(offset))
+            }
+            CalendarInterval::Week => {
+                let offset = date.weekday().num_days_from_monday() as u64;
+                date.checked_sub_days(Days::new(offset))
+            }
+            CalendarInterval::Month => date.with_day(1),
+        }
+    }
+
+    fn next_bucket_start(&self, bucket_start: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            CalendarInterval::Days(num_days) => {
+                bucket_start.checked_add_days(Days::new(*num_days as u64))
+            }
+            CalendarInterval::Week => bucket_start.checked_add_days(Days::new(7)),
+            CalendarInterval::Month => bucket_start.checked_add_months(Months::new(1)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TimeZoneHistogramParams {
+    field: String,
+    time_zone: HistogramTimeZone,
+    interval: CalendarInterval,
+    min_doc_count: u64,
+    keyed: bool,
+}
+
+impl TimeZoneHistogramParams {
+    /// Returns `None` if the date histogram does not carry a `time_zone` parameter.
+    fn parse(
+        agg_name: &str,
+        date_histogram: &JsonMap<String, JsonValue>,
+    ) -> crate::Result<Option<Self>> {
+        let Some(time_zone_value) = date_histogram.get("time_zone") else {
+            return Ok(None);
+        };
+        let time_zone = time_zone_value
+            .as_str()
+            .and_then(HistogramTimeZone::parse)
+            .ok_or_else(|| {
+                invalid_aggregation(
+                    agg_name,
+                    &format!(
+                        "unknown time zone `{time_zone_value}`, expected an IANA time zone name \
+                         or a UTC offset"
+                    ),
+                )
+            })?;
+        let interval = CalendarInterval::parse(date_histogram).ok_or_else(|| {
+            invalid_aggregation(
+                agg_name,
+                "`time_zone` requires a `calendar_interval` of `day`, `week`, or `month`, or a \
+                 `fixed_interval` expressed in days",
+            )
+        })?;
+        if let Some(param) = UNSUPPORTED_PARAMS
+            .iter()
+            .find(|param| date_histogram.contains_key(**param))
+        {
+            return Err(invalid_aggregation(
+                agg_name,
+                &format!("`{param}` cannot be combined with `time_zone`"),
+            ));
+        }
+        let field = date_histogram
+            .get("field")
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| invalid_aggregation(agg_name, "missing `field` parameter"))?
+            .to_string();
+        let min_doc_count = date_histogram
+            .get("min_doc_count")
+            .and_then(JsonValue::as_u64)
+            .unwrap_or(0);
+        let keyed = date_histogram
+            .get("keyed")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(false);
+        let params = TimeZoneHistogramParams {
+            field,
+            time_zone,
+            interval,
+            min_doc_count,
+            keyed,
+        };
+        Ok(Some(params))
+    }
+
+    /// Builds one range per local bucket overlapping `time_range`. Ranges are keyed by the
+    /// timestamp in milliseconds of the start of the bucket.
+    fn build_ranges(
+        &self,
+        agg_name: &str,
+        time_range: &Range<i64>,
+    ) -> crate::Result<Vec<JsonValue>> {
+        let mut ranges = Vec::new();
+
+        if time_range.is_empty() {
+            return Ok(ranges);
+        }
+        let start_millis = time_range.start.saturating_mul(1_000);
+        let end_millis = time_range.end.saturating_mul(1_000);
+        let Some(mut bucket_start) = self
+            .time_zone
+            .local_date(start_millis)
+            .and_then(|date| self.interval.bucket_start(date))
+        else {
+            return Ok(ranges);
+        };
+        while let Some(bucket_start_millis) = self.time_zone.start_of_day_millis(bucket_start) {
+            if bucket_start_millis >= end_millis {
+                break;
+            }
+            let Some(next_bucket_start) = self.interval.next_bucket_start(bucket_start) else {
+                break;
+            };
+            let Some(bucket_end_millis) = self.time_zone.start_of_day_millis(next_bucket_start)
+            else {
+                break;
+            };
+            if ranges.len() == MAX_NUM_BUCKETS {
+                return Err(invalid_aggregation(
+                    agg_name,
+                    &format!(
+                        "the searched time range spans more than {MAX_NUM_BUCKETS} buckets, \
+                         narrow it down or use a larger interval"
+                    ),
+                ));
+            }
+            ranges.push(json!({
+                "key": bucket_start_millis.to_string(),
+                "from": bucket_start_millis,
+                "to": bucket_end_millis,
+            }));
+            bucket_start = next_bucket_start;
+        }
+        Ok(ranges)
+    }
+
+    /// Converts the keyed buckets of a range aggregation into date histogram buckets.
+    fn convert_range_result(&self, range_result: &JsonValue) -> JsonValue {
+        let mut buckets: Vec<(i64, JsonMap<String, JsonValue>)> = range_result
+            .get("buckets")
+            .and_then(JsonValue::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, range_bucket)| {
+                // Skips the buckets tantivy adds to cover the values outside of the ranges.
+                let bucket_start_millis: i64 = key.parse().ok()?;
+                let mut bucket = range_bucket.as_object()?.clone();
+                for range_key in ["key", "from", "to", "from_as_string", "to_as_string"] {
+                    bucket.remove(range_key);
+                }
+                bucket.insert("key".to_string(), json!(bucket_start_millis as f64));
+                if let Some(key_as_string) = self.time_zone.format_millis(bucket_start_millis) {
+                    bucket.insert("key_as_string".to_string(), json!(key_as_string));
+                }
+                Some((bucket_start_millis, bucket))
+            })
+            .collect();
+        buckets.sort_unstable_by_key(|(bucket_start_millis, _)| *bucket_start_millis);
+
+        let doc_count = |bucket: &JsonMap<String, JsonValue>| {
+            bucket
+                .get("doc_count")
+                .and_then(JsonValue::as_u64)
+                .unwrap_or(0)
+        };
+        if self.min_doc_count > 0 {
+            buckets.retain(|(_, bucket)| doc_count(bucket) >= self.min_doc_count);
+        } else {
+            // Like tantivy date histograms, only the buckets between the first and the last
+            // non-empty buckets are returned.
+            let first_non_empty = buckets.iter().position(|(_, bucket)| doc_count(bucket) > 0);
+            let last_non_empty = buckets
+                .iter()
+                .rposition(|(_, bucket)| doc_count(bucket) > 0);
+            match (first_non_empty, last_non_empty) {
+                (Some(first), Some(last)) => {
+                    buckets.truncate(last + 1);
+                    buckets.drain(..first);
+                }
+                _ => buckets.clear(),
+            }
+        }
+        let buckets_json = if self.keyed {
+            let keyed_buckets: JsonMap<String, JsonValue> = buckets
+                .into_iter()
+                .map(|(bucket_start_millis, bucket)| {
+                    let key = bucket
+                        .get("key_as_string")
+                        .and_then(JsonValue::as_str)
+                        .map(ToString::to_string)
+                        .unwrap_or_else(|| bucket_start_millis.to_string());
+                    (key, JsonValue::Object(bucket))
+                })
+                .collect();
+            JsonValue::Object(keyed_buckets)
+        } else {
+            JsonValue::Array(
+                buckets
+                    .into_iter()
+                    .map(|(_, bucket)| JsonValue::Object(bucket))
+                    .collect(),
+            )
+        };
+        json!({ "buckets": buckets_json })
+    }
+}
+
+fn invalid_aggregation(agg_name: &str, message: &str) -> SearchError {
+    SearchError::InvalidAggregationRequest(format!(
+        "invalid date histogram aggregation `{agg_name}`: {message}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Local midnights in Europe/Paris around the switch to daylight saving time on 2023-03-26.
+    const MARCH_25_MILLIS: i64 = 1_679_698_800_000;
+    const MARCH_26_MILLIS: i64 = 1_679_785_200_000;
+    const MARCH_27_MILLIS: i64 = 1_679_868_000_000;
+    const MARCH_28_MILLIS: i64 = 1_679_954_400_000;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_histogram_time_zone() {
+        let paris = HistogramTimeZone::parse("Europe/Paris").unwrap();
+        assert_eq!(paris, HistogramTimeZone::Named(Tz::Europe__Paris));
+        assert_eq!(
+            paris.start_of_day_millis(date(2023, 3, 26)),
+            Some(MARCH_26_MILLIS)
+        );
+        // The day of the switch to daylight saving time lasts 23 hours.
+        assert_eq!(MARCH_27_MILLIS - MARCH_26_MILLIS, 23 * 3_600 * 1_000);
+        assert_eq!(
+            paris.start_of_day_millis(date(2023, 3, 27)),
+            Some(MARCH_27_MILLIS)
+        );
+        assert_eq!(
+            paris.local_date(MARCH_27_MILLIS - 1),
+            Some(date(2023, 3, 26))
+        );
+        assert_eq!(
+            paris.format_millis(MARCH_27_MILLIS).unwrap(),
+            "2023-03-27T00:00:00+02:00"
+        );
+        let kolkata = HistogramTimeZone::parse("+05:30").unwrap();
+        assert!(matches!(kolkata, HistogramTimeZone::Fixed(_)));
+        assert_eq!(
+            kolkata.format_millis(0).unwrap(),
+            "1970-01-01T05:30:00+05:30"
+        );
+        assert!(HistogramTimeZone::parse("Mars/Olympus_Mons").is_none());
+
+        // In Santiago, the clocks moved forward at midnight on 2022-09-11: the day started at 1am.
+        let santiago = HistogramTimeZone::parse("America/Santiago").unwrap();
+        let start_of_day_millis = santiago.start_of_day_millis(date(2022, 9, 11)).unwrap();
+        assert_eq!(
+            santiago.format_millis(start_of_day_millis).unwrap(),
+            "2022-09-11T01:00:00-03:00"
+        );
+    }
+
+    #[test]
+    fn test_calendar_interval() {
+        let sunday = date(2023, 3, 26);
+        assert_eq!(CalendarInterval::Days(1).bucket_start(sunday), Some(sunday));
+        assert_eq!(
+            CalendarInterval::Days(7).bucket_start(sunday),
+            Some(date(2023, 3, 23))
+        );
+        assert_eq!(
+            CalendarInterval::Week.bucket_start(sunday),
+            Some(date(2023, 3, 20))
+        );
+        assert_eq!(
+            CalendarInterval::Month.bucket_start(sunday),
+            Some(date(2023, 3, 1))
+        );
+        assert_eq!(
+            CalendarInterval::Month.next_bucket_start(date(2023, 1, 1)),
+            Some(date(2023, 2, 1))
+        );
+        let parse = |date_histogram: JsonValue| {
+            CalendarInterval::parse(date_histogram.as_object().unwrap())
+        };
+        assert_eq!(
+            parse(json!({"calendar_interval": "week"})),
+            Some(CalendarInterval::Week)
+        );
+        assert_eq!(
+            parse(json!({"calendar_interval": "1M"})),
+            Some(CalendarInterval::Month)
+        );
+        assert_eq!(
+            parse(json!({"fixed_interval": "2d"})),
+            Some(CalendarInterval::Days(2))
+        );
+        assert_eq!(parse(json!({"fixed_interval": "12h"})), None);
+        assert_eq!(parse(json!({"calendar_interval": "quarter"})), None);
+    }
+
+    #[test]
+    fn test_searched_time_range() {
+        let split_metadatas = vec![
+            SplitMetadata {
+                time_range: Some(100..=199),
+                ..Default::default()
+            },
+            SplitMetadata {
+                time_range: Some(50..=149),
+                ..Default::default()
+            },
+            SplitMetadata::default(),
+        ];
+        let mut search_request = SearchRequest::default();
+        assert_eq!(
+            searched_time_range(&search_request, &split_metadatas),
+            50..200
+        );
+        search_request.start_timestamp = Some(120);
+        search_request.end_timestamp = Some(500);
+        assert_eq!(
+            searched_time_range(&search_request, &split_metadatas),
+            120..200
+        );
+        assert!(searched_time_range(&search_request, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_rewrite_time_zone_date_histograms() {
+        let aggregation_request = json!({
+            "per_day": {
+                "date_histogram": {
+                    "field": "timestamp",
+                    "calendar_interval": "day",
+                    "time_zone": "Europe/Paris",
+                },
+                "aggs": {
+                    "max_value": {"max": {"field": "value"}}
+                }
+            }
+        })
+        .to_string();
+        // From 2023-03-25T02:00:00+01:00 to 2023-03-27T08:53:20+02:00.
+        let time_range = 1_679_706_000..1_679_900_000;
+        let rewritten_aggregation_request = rewrite_time_zone_date_histograms(
+            &aggregation_request,
+            Some("timestamp"),
+            time_range.clone(),
+        )
+        .unwrap()
+        .unwrap();
+        let rewritten_aggregation_request: JsonValue =
+            serde_json::from_str(&rewritten_aggregation_request).unwrap();
+        let range = |from_millis: i64, to_millis: i64| {
+            json!({
+                "key": from_millis.to_string(),
+                "from": from_millis,
+                "to": to_millis,
+            })
+        };
+        assert_eq!(
+            rewritten_aggregation_request,
+            json!({
+                "per_day": {
+                    "range": {
+                        "field": "timestamp",
+                        "ranges": [
+                            range(MARCH_25_MILLIS, MARCH_26_MILLIS),
+                            range(MARCH_26_MILLIS, MARCH_27_MILLIS),
+                            range(MARCH_27_MILLIS, MARCH_28_MILLIS),
+                        ],
+                        "keyed": true,
+                    },
+                    "aggs": {
+                        "max_value": {"max": {"field": "value"}}
+                    }
+                }
+            })
+        );
+        let no_time_zone_aggregation_request = json!({
+            "per_hour": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h"}
+            }
+        })
+        .to_string();
+        assert!(rewrite_time_zone_date_histograms(
+            &no_time_zone_aggregation_request,
+            Some("timestamp"),
+            time_range.clone(),
+        )
+        .unwrap()
+        .is_none());
+
+        let rewrite_error = |date_histogram: JsonValue| {
+            let aggregation_request = json!({"per_day": {"date_histogram": date_histogram}});
+            rewrite_time_zone_date_histograms(
+                &aggregation_request.to_string(),
+                Some("timestamp"),
+                time_range.clone(),
+            )
+            .unwrap_err()
+            .to_string()
+        };
+        assert!(rewrite_error(json!({
+            "field": "timestamp",
+            "calendar_interval": "day",
+            "time_zone": "Mars/Olympus_Mons",
+        }))
+        .contains("unknown time zone"));
+        assert!(rewrite_error(json!({
+            "field": "timestamp",
+            "fixed_interval": "1h",
+            "time_zone": "Europe/Paris",
+        }))
+        .contains("requires a `calendar_interval`"));
+        assert!(rewrite_error(json!({
+            "field": "timestamp",
+            "calendar_interval": "day",
+            "offset": "+6h",
+            "time_zone": "Europe/Paris",
+        }))
+        .contains("`offset` cannot be combined with `time_zone`"));
+        assert!(rewrite_error(json!({
+            "field": "created_at",
+            "calendar_interval": "day",
+            "time_zone": "Europe/Paris",
+        }))
+        .contains("only supported on the timestamp field"));
+    }
+
+    #[test]
+    fn test_convert_time_zone_date_histogram_results() {
+        let aggregation_request = json!({
+            "per_day": {
+                "date_histogram": {
+                    "field": "timestamp",
+                    "calendar_interval": "day",
+                    "time_zone": "Europe/Paris",
+                },
+                "aggs": {
+                    "max_value": {"max": {"field": "value"}}
+                }
+            }
+        })
+        .to_string();
+        let range_bucket =
+            |from_millis: i64, to_millis: i64, doc_count: u64, max_value: JsonValue| {
+                json!({
+                    "key": from_millis.to_string(),
+                    "doc_count": doc_count,
+                    "from": from_millis,
+                    "to": to_millis,
+                    "max_value": {"value": max_value},
+                })
+            };
+        let range_results = json!({
+            "per_day": {
+                "buckets": {
+                    // Bucket added by tantivy for the values below the first range.
+                    format!("*-{MARCH_25_MILLIS}"): {
+                        "key": format!("*-{MARCH_25_MILLIS}"),
+                        "doc_count": 0,
+                        "to": MARCH_25_MILLIS,
+                        "max_value": {"value": null},
+                    },
+                    MARCH_25_MILLIS.to_string():
+                        range_bucket(MARCH_25_MILLIS, MARCH_26_MILLIS, 0, JsonValue::Null),
+                    MARCH_26_MILLIS.to_string():
+                        range_bucket(MARCH_26_MILLIS, MARCH_27_MILLIS, 3, json!(4.0)),
+                    MARCH_27_MILLIS.to_string():
+                        range_bucket(MARCH_27_MILLIS, MARCH_28_MILLIS, 1, json!(2.0)),
+                }
+            }
+        });
+        let mut aggregation = range_results.to_string();
+        convert_time_zone_date_histogram_results(&mut aggregation, &aggregation_request).unwrap();
+        let aggregation_json: JsonValue = serde_json::from_str(&aggregation).unwrap();
+        assert_eq!(
+            aggregation_json,
+            json!({
+                "per_day": {
+                    "buckets": [
+                        {
+                            "key": MARCH_26_MILLIS as f64,
+                            "key_as_string": "2023-03-26T00:00:00+01:00",
+                            "doc_count": 3,
+                            "max_value": {"value": 4.0},
+                        },
+                        {
+                            "key": MARCH_27_MILLIS as f64,
+                            "key_as_string": "2023-03-27T00:00:00+02:00",
+                            "doc_count": 1,
+                            "max_value": {"value": 2.0},
+                        },
+                    ]
+                }
+            })
+        );
+
+        // Aggregations without a time zone are left untouched.
+        let aggregation_request = json!({
+            "per_day": {"date_histogram": {"field": "timestamp", "fixed_interval": "1d"}}
+        })
+        .to_string();
+        let mut aggregation = range_results.to_string();
+        convert_time_zone_date_histogram_results(&mut aggregation, &aggregation_request).unwrap();
+        assert_eq!(aggregation, range_results.to_string());
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! WebAssembly implementation of the user-defined functions callable from the query DSL.
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use quickwit_config::UdfConfig;
+use quickwit_query::udf::{register_udf, Udf, UdfInstance};
+use quickwit_storage::{load_file, StorageResolver};
+use tracing::info;
+use wasmi::core::F64;
+use wasmi::{Engine, Func, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Value};
+
+/// Loads the WASM modules of the UDFs defined in the searcher config and registers them so that
+/// they can be referenced by queries.
+pub(crate) async fn register_wasm_udfs(
+    udf_configs: &[UdfConfig],
+    storage_resolver: &StorageResolver,
+) -> anyhow::Result<()> {
+    for udf_config in udf_configs {
+        let module_bytes = load_file(storage_resolver, &udf_config.module_uri)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to load WASM module `{}` of UDF `{}`.",
+                    udf_config.module_uri, udf_config.name
+                )
+            })?;
+        let wasm_udf = WasmUdf::try_new(udf_config, module_bytes.as_slice())?;
+        // Fail early rather than on the first query if the module does not fulfill the contract.
+        wasm_udf.instantiate()?;
+        register_udf(udf_config.name.clone(), Arc::new(wasm_udf));
+        info!(udf_name=%udf_config.name, module_uri=%udf_config.module_uri, "Registered UDF.");
+    }
+    Ok(())
+}
+
+/// Holds a compiled WASM module, ready to be instantiated.
+struct WasmUdf {
+    engine: Engine,
+    module: Module,
+    name: String,
+    entrypoint: String,
+    max_fuel_per_call: u64,
+    max_memory_num_bytes: usize,
+}
+
+impl WasmUdf {
+    fn try_new(udf_config: &UdfConfig, module_bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut engine_config = wasmi::Config::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, module_bytes).with_context(|| {
+            format!(
+                "Failed to compile WASM module `{}` of UDF `{}`.",
+                udf_config.module_uri, udf_config.name
+            )
+        })?;
+        Ok(Self {
+            engine,
+            module,
+            name: udf_config.name.clone(),
+            entrypoint: udf_config.entrypoint.clone(),
+            max_fuel_per_call: udf_config.max_fuel_per_call,
+            max_memory_num_bytes: udf_config.max_memory.get_bytes() as usize,
+        })
+    }
+}
+
+impl Udf for WasmUdf {
+    fn instantiate(&self) -> anyhow::Result<Box<dyn UdfInstance>> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_num_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        // The linker does not define any import: the module is not allowed to interact with the
+        // host.
+        let linker: Linker<StoreLimits> = Linker::new(&self.engine);
+        // Running the start function consumes fuel too.
+        store
+            .add_fuel(self.max_fuel_per_call)
+            .map_err(wasmi::Error::from)?;
+        let instance = linker
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)?;
+        let func = instance
+            .get_func(&store, &self.entrypoint)
+            .with_context(|| {
+                format!(
+                    "WASM module of UDF `{}` must export an `{}` function.",
+                    self.name, self.entrypoint
+                )
+            })?;
+        Ok(Box::new(WasmUdfInstance {
+            store,
+            func,
+            inputs: Vec::new(),
+            max_fuel_per_call: self.max_fuel_per_call,
+        }))
+    }
+}
+
+struct WasmUdfInstance {
+    store: Store<StoreLimits>,
+    func: Func,
+    inputs: Vec<Value>,
+    max_fuel_per_call: u64,
+}
+
+impl WasmUdfInstance {
+    /// Refills the fuel tank so that each call gets exactly `max_fuel_per_call` fuel.
+    fn refill_fuel(&mut self) -> anyhow::Result<()> {
+        let remaining_fuel = self.store.consume_fuel(0).map_err(wasmi::Error::from)?;
+        self.store
+            .add_fuel(self.max_fuel_per_call.saturating_sub(remaining_fuel))
+            .map_err(wasmi::Error::from)?;
+        Ok(())
+    }
+}
+
+impl UdfInstance for WasmUdfInstance {
+    fn call(&mut self, args: &[f64]) -> anyhow::Result<f64> {
+        self.refill_fuel()?;
+        self.inputs.clear();
+        self.inputs
+            .extend(args.iter().map(|arg| Value::F64(F64::from(*arg))));
+        let mut outputs = [Value::F64(F64::from(0.0))];
+        // The arity and the types of the parameters and results are checked by wasmi.
+        self.func
+            .call(&mut self.store, &self.inputs, &mut outputs)?;
+        let output = outputs[0]
+            .f64()
+            .context("UDF must return a single `f64`.")?;
+        Ok(output.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEIGHTED_SUM_MODULE_WAT: &str = r#"
+        (module
+            (func (export "call") (param f64) (param f64) (result f64)
+                local.get 0
+                local.get 1
+                f64.const 2
+                f64.mul
+                f64.add))
+    "#;
+
+    const INFINITE_LOOP_MODULE_WAT: &str = r#"
+        (module
+            (func (export "call") (param f64) (result f64)
+                (loop $forever
+                    br $forever)
+                f64.const 0))
+    "#;
+
+    fn build_udf(module_wat: &str) -> WasmUdf {
+        let module_bytes = wat::parse_str(module_wat).unwrap();
+        let udf_config = UdfConfig::for_test("test_udf", "file:///udf.wasm");
+        WasmUdf::try_new(&udf_config, &module_bytes).unwrap()
+    }
+
+    #[test]
+    fn test_wasm_udf_call() {
+        let mut udf_instance = build_udf(WEIGHTED_SUM_MODULE_WAT).instantiate().unwrap();
+        assert_eq!(udf_instance.call(&[1.0, 2.0]).unwrap(), 5.0);
+        assert_eq!(udf_instance.call(&[-1.0, 0.5]).unwrap(), 0.0);
+        // Wrong arity.
+        udf_instance.call(&[1.0]).unwrap_err();
+    }
+
+    #[test]
+    fn test_wasm_udf_runs_out_of_fuel() {
+        let mut udf_instance = build_udf(INFINITE_LOOP_MODULE_WAT).instantiate().unwrap();
+        // The fuel is refilled for each call, so the UDF fails consistently rather than once.
+        for _ in 0..2 {
+            udf_instance.call(&[1.0]).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_wasm_udf_missing_entrypoint() {
+        assert!(build_udf("(module)").instantiate().is_err());
+    }
+}
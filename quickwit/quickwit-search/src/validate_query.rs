@@ -0,0 +1,401 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Validation of queries against the doc mapping of an index, without executing them.
+
+use quickwit_config::build_doc_mapper;
+use quickwit_doc_mapper::resolve_doc_id_field_alias;
+use quickwit_metastore::Metastore;
+use quickwit_query::query_ast::QueryAst;
+use quickwit_query::{find_field_or_hit_dynamic, InvalidQuery};
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema;
+
+use crate::metadata_fields::{resolve_metadata_fields, MetadataFieldValues};
+use crate::root::disabled_query_features;
+use crate::SearchError;
+
+/// Category of a problem found while validating a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryDiagnosticKind {
+    /// The query could not be parsed.
+    ParseError,
+    /// The query targets a field that does not exist in the doc mapping.
+    UnknownField,
+    /// A value of the query does not match the type of its field.
+    TypeMismatch,
+    /// The query uses a construct that is not supported, or not supported on its field.
+    UnsupportedConstruct,
+    /// The query uses a feature disabled on the index.
+    DisabledFeature,
+    /// Any other problem.
+    InvalidQuery,
+}
+
+/// A problem found while validating a query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryDiagnostic {
+    /// Category of the problem.
+    pub kind: QueryDiagnosticKind,
+    /// Field involved in the problem, if any.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Clause of the query holding the problem, rendered in the query language.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clause: Option<String>,
+    /// Human-readable description of the problem.
+    pub message: String,
+}
+
+impl QueryDiagnostic {
+    /// Creates a diagnostic not attached to any field or clause.
+    pub fn new(kind: QueryDiagnosticKind, message: impl ToString) -> Self {
+        QueryDiagnostic {
+            kind,
+            field: None,
+            clause: None,
+            message: message.to_string(),
+        }
+    }
+
+    fn from_invalid_query(invalid_query: InvalidQuery, clause: &QueryAst) -> Self {
+        let (kind, field) = match &invalid_query {
+            InvalidQuery::FieldDoesNotExist { full_path }
+            | InvalidQuery::JsonFieldRootNotSearchable { full_path } => {
+                (QueryDiagnosticKind::UnknownField, Some(full_path.clone()))
+            }
+            InvalidQuery::InvalidBoundary { field_name, .. }
+            | InvalidQuery::InvalidSearchTerm { field_name, .. } => {
+                (QueryDiagnosticKind::TypeMismatch, Some(field_name.clone()))
+            }
+            InvalidQuery::RangeQueryNotSupportedForField { field_name, .. } => (
+                QueryDiagnosticKind::UnsupportedConstruct,
+                Some(field_name.clone()),
+            ),
+            InvalidQuery::SchemaError(_) => (QueryDiagnosticKind::UnsupportedConstruct, None),
+            InvalidQuery::UdfNotFound { .. }
+            | InvalidQuery::UserQueryNotParsed
+            | InvalidQuery::TermsLookupNotResolved
+            | InvalidQuery::Other(_) => (QueryDiagnosticKind::InvalidQuery, None),
+        };
+        QueryDiagnostic {
+            kind,
+            field,
+            clause: Some(clause.to_query_string()),
+            message: invalid_query.to_string(),
+        }
+    }
+}
+
+/// Outcome of the validation of a query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryValidation {
+    /// Problems found in the query. The query is valid if there are none.
+    pub diagnostics: Vec<QueryDiagnostic>,
+    /// The query as it would be executed, once the user queries are parsed and the synonyms
+    /// expanded, rendered in the query language. `None` if the query could not be parsed.
+    pub explanation: Option<String>,
+}
+
+impl QueryValidation {
+    /// Returns whether the query is valid.
+    pub fn is_valid(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// Validates a query against the doc mapping and the search settings of an index, going through
+/// the same steps as a search but without executing it. Instead of stopping at the first problem,
+/// the validation reports one diagnostic per invalid clause.
+///
+/// Terms lookups are not resolved: only the existence of their field is checked.
+pub async fn validate_query(
+    index_id: &str,
+    query_ast: QueryAst,
+    metastore: &dyn Metastore,
+) -> crate::Result<QueryValidation> {
+    let index_config = metastore
+        .index_metadata(index_id)
+        .await?
+        .into_index_config();
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|err| {
+            SearchError::InternalError(format!("Failed to build doc mapper. Cause: {err}"))
+        })?;
+    let mut query_validation = QueryValidation::default();
+
+    let query_ast_resolved = match query_ast.parse_user_query(doc_mapper.default_search_fields()) {
+        Ok(query_ast_resolved) => query_ast_resolved,
+        Err(error) => {
+            let diagnostic = QueryDiagnostic::new(QueryDiagnosticKind::ParseError, error);
+            query_validation.diagnostics.push(diagnostic);
+            return Ok(query_validation);
+        }
+    };
+    let query_ast_resolved = index_config
+        .search_settings
+        .synonym_dictionary()
+        .expand_synonyms(query_ast_resolved);
+    query_validation.explanation = Some(query_ast_resolved.to_query_string());
+
+    let query_ast_resolved =
+        match resolve_doc_id_field_alias(query_ast_resolved, doc_mapper.doc_id_field_name()) {
+            Ok(query_ast_resolved) => query_ast_resolved,
+            Err(error) => {
+                let diagnostic = QueryDiagnostic::new(QueryDiagnosticKind::InvalidQuery, error);
+                query_validation.diagnostics.push(diagnostic);
+                return Ok(query_validation);
+            }
+        };
+    for feature in disabled_query_features(&query_ast_resolved, &index_config.search_settings) {
+        let message = format!(
+            "Feature `{}` is disabled on index `{index_id}`. It can be enabled with the \
+             `search_settings.feature_flags` index setting.",
+            feature.as_str(),
+        );
+        let diagnostic = QueryDiagnostic::new(QueryDiagnosticKind::DisabledFeature, message);
+        query_validation.diagnostics.push(diagnostic);
+    }
+    let query_ast_for_validation = match resolve_metadata_fields(
+        query_ast_resolved,
+        &MetadataFieldValues::for_validation(index_id),
+    ) {
+        Ok(query_ast_for_validation) => query_ast_for_validation,
+        Err(error) => {
+            let diagnostic = QueryDiagnostic::new(QueryDiagnosticKind::InvalidQuery, error);
+            query_validation.diagnostics.push(diagnostic);
+            return Ok(query_validation);
+        }
+    };
+    collect_diagnostics(
+        &query_ast_for_validation,
+        &doc_mapper.schema(),
+        doc_mapper.default_search_fields(),
+        &mut query_validation.diagnostics,
+    );
+    Ok(query_validation)
+}
+
+/// Returns the subqueries of a compound query.
+fn subqueries(query_ast: &QueryAst) -> Vec<&QueryAst> {
+    match query_ast {
+        QueryAst::Bool(bool_query) => bool_query
+            .must
+            .iter()
+            .chain(&bool_query.must_not)
+            .chain(&bool_query.should)
+            .chain(&bool_query.filter)
+            .collect(),
+        QueryAst::DisMax(dis_max_query) => dis_max_query.queries.iter().collect(),
+        QueryAst::Boosting(boosting_query) => {
+            vec![&*boosting_query.positive, &*boosting_query.negative]
+        }
+        QueryAst::Boost { underlying, .. } | QueryAst::ConstScore { underlying, .. } => {
+            vec![&**underlying]
+        }
+        QueryAst::Udf(udf_query) => vec![&*udf_query.underlying],
+        QueryAst::FunctionScore(function_score_query) => {
+            let function_filters = function_score_query
+                .functions
+                .iter()
+                .filter_map(|function| function.filter.as_deref());
+            std::iter::once(&*function_score_query.underlying)
+                .chain(function_filters)
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Builds every clause of the query against the schema and records a diagnostic for each clause
+/// that fails to build. A compound query is only reported when all its subqueries are valid, so
+/// that a problem is reported once, on the innermost clause holding it.
+fn collect_diagnostics(
+    query_ast: &QueryAst,
+    schema: &Schema,
+    search_fields: &[String],
+    diagnostics: &mut Vec<QueryDiagnostic>,
+) {
+    let num_diagnostics = diagnostics.len();
+    for subquery in subqueries(query_ast) {
+        collect_diagnostics(subquery, schema, search_fields, diagnostics);
+    }
+    if diagnostics.len() > num_diagnostics {
+        return;
+    }
+    let build_result = if let QueryAst::TermsLookup(terms_lookup_query) = query_ast {
+        find_field_or_hit_dynamic(&terms_lookup_query.field, schema).map(|_| ())
+    } else {
+        query_ast
+            .build_tantivy_query(schema, search_fields, true, true)
+            .map(|_| ())
+    };
+    if let Err(invalid_query) = build_result {
+        diagnostics.push(QueryDiagnostic::from_invalid_query(
+            invalid_query,
+            query_ast,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_query::query_ast::{BoolQuery, RangeQuery, TermQuery, UserInputQuery};
+    use quickwit_query::{BooleanOperand, JsonLiteral};
+
+    use super::*;
+
+    fn mock_metastore() -> MockMetastore {
+        let mut metastore = MockMetastore::new();
+        metastore.expect_index_metadata().returning(|index_id| {
+            Ok(IndexMetadata::for_test(
+                index_id,
+                &format!("ram:///indexes/{index_id}"),
+            ))
+        });
+        metastore
+    }
+
+    fn user_input_query(user_text: &str) -> QueryAst {
+        UserInputQuery {
+            user_text: user_text.to_string(),
+            default_fields: None,
+            default_operator: BooleanOperand::And,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
+        }
+        .into()
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_valid() {
+        let metastore = mock_metastore();
+        let query_ast = user_input_query("body:hello AND response_time:12.5");
+        let query_validation = validate_query("test-index", query_ast, &metastore)
+            .await
+            .unwrap();
+        assert!(query_validation.is_valid());
+        assert_eq!(
+            query_validation.explanation.as_deref(),
+            Some("+body:hello +response_time:12.5")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_parse_error() {
+        let metastore = mock_metastore();
+        let query_ast = user_input_query("body:(hello");
+        let query_validation = validate_query("test-index", query_ast, &metastore)
+            .await
+            .unwrap();
+        assert!(!query_validation.is_valid());
+        assert!(query_validation.explanation.is_none());
+        assert_eq!(query_validation.diagnostics.len(), 1);
+        assert_eq!(
+            query_validation.diagnostics[0].kind,
+            QueryDiagnosticKind::ParseError
+        );
+        assert_eq!(
+            query_validation.diagnostics[0].message,
+            "Failed to parse query: `body:(hello`."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_reports_every_invalid_clause() {
+        let metastore = mock_metastore();
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![
+                TermQuery {
+                    field: "body".to_string(),
+                    value: "hello".to_string(),
+                }
+                .into(),
+                TermQuery {
+                    field: "unknown".to_string(),
+                    value: "hello".to_string(),
+                }
+                .into(),
+            ],
+            filter: vec![
+                TermQuery {
+                    field: "response_time".to_string(),
+                    value: "fast".to_string(),
+                }
+                .into(),
+                RangeQuery {
+                    field: "response_payload".to_string(),
+                    lower_bound: std::ops::Bound::Included(JsonLiteral::String("a".to_string())),
+                    upper_bound: std::ops::Bound::Unbounded,
+                }
+                .into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        let query_validation = validate_query("test-index", query_ast, &metastore)
+            .await
+            .unwrap();
+        let diagnostics = &query_validation.diagnostics;
+        assert_eq!(diagnostics.len(), 3);
+
+        assert_eq!(diagnostics[0].kind, QueryDiagnosticKind::UnknownField);
+        assert_eq!(diagnostics[0].field.as_deref(), Some("unknown"));
+        assert_eq!(diagnostics[0].clause.as_deref(), Some("unknown:hello"));
+
+        assert_eq!(diagnostics[1].kind, QueryDiagnosticKind::TypeMismatch);
+        assert_eq!(diagnostics[1].field.as_deref(), Some("response_time"));
+
+        assert_eq!(
+            diagnostics[2].kind,
+            QueryDiagnosticKind::UnsupportedConstruct
+        );
+        assert_eq!(diagnostics[2].field.as_deref(), Some("response_payload"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_disabled_feature() {
+        let metastore = mock_metastore();
+        let query_ast: QueryAst = serde_json::from_value(serde_json::json!({
+            "type": "knn",
+            "field": "embedding",
+            "query_vector": [0.5, 0.5],
+            "k": 10,
+            "num_candidates": 100,
+        }))
+        .unwrap();
+        let query_validation = validate_query("test-index", query_ast, &metastore)
+            .await
+            .unwrap();
+        let diagnostics = &query_validation.diagnostics;
+        assert_eq!(diagnostics[0].kind, QueryDiagnosticKind::DisabledFeature);
+        assert_eq!(
+            diagnostics[0].message,
+            "Feature `knn_queries` is disabled on index `test-index`. It can be enabled with the \
+             `search_settings.feature_flags` index setting."
+        );
+        assert_eq!(diagnostics[1].kind, QueryDiagnosticKind::UnknownField);
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use aws_sdk_secretsmanager::Client as SecretsManagerClient;
+use quickwit_aws::get_aws_config;
+use tokio::sync::OnceCell;
+
+use crate::SecretProvider;
+
+/// Reads secrets from AWS Secrets Manager: the path is the name or the ARN of the secret.
+///
+/// The client is created on first use with the credentials and the region of the default AWS
+/// config.
+#[derive(Default)]
+pub struct AwsSecretsManagerProvider {
+    client: OnceCell<SecretsManagerClient>,
+}
+
+impl AwsSecretsManagerProvider {
+    async fn client(&self) -> &SecretsManagerClient {
+        self.client
+            .get_or_init(|| async { SecretsManagerClient::new(get_aws_config().await) })
+            .await
+    }
+}
+
+#[async_trait]
+impl SecretProvider for AwsSecretsManagerProvider {
+    async fn fetch_secret(&self, path: &str) -> anyhow::Result<String> {
+        let output = self
+            .client()
+            .await
+            .get_secret_value()
+            .secret_id(path)
+            .send()
+            .await
+            .with_context(|| {
+                format!("Failed to fetch secret `{path}` from AWS Secrets Manager.")
+            })?;
+
+        if let Some(secret_string) = output.secret_string() {
+            return Ok(secret_string.to_string());
+        }
+        if let Some(secret_binary) = output.secret_binary() {
+            return String::from_utf8(secret_binary.as_ref().to_vec())
+                .with_context(|| format!("Secret `{path}` is not valid UTF-8."));
+        }
+        bail!("Secret `{path}` has no value.")
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+#![deny(clippy::disallowed_methods)]
+
+//! Resolution of the secrets referenced in source and storage configs.
+//!
+//! Instead of inlining a credential in plaintext, a config may reference a secret stored in an
+//! external provider with `secret://<provider>/<path>[#<key>]`. The reference is what ends up in
+//! the metastore and is resolved at runtime, when the storage or the source is instantiated.
+//! Supported providers are:
+//! - `env`: `secret://env/KAFKA_SASL_PASSWORD` reads an environment variable.
+//! - `file`: `secret://file/etc/quickwit/secrets/kafka` reads a file (e.g. a Kubernetes secret
+//!   mounted as a volume).
+//! - `aws-secrets-manager`: `secret://aws-secrets-manager/prod/kafka#password` reads a secret from
+//!   AWS Secrets Manager.
+//! - `vault`: `secret://vault/secret/data/kafka#password` reads a secret from HashiCorp Vault.
+//!
+//! When a key is specified, the secret is parsed as a JSON object and the value of the key is
+//! returned.
+
+mod aws_secrets_manager;
+mod provider;
+mod secret_ref;
+mod secret_resolver;
+mod vault;
+
+pub use aws_secrets_manager::AwsSecretsManagerProvider;
+pub use provider::{EnvSecretProvider, FileSecretProvider, SecretProvider};
+pub use secret_ref::{is_secret_ref, SecretRef, SECRET_REF_SCHEME};
+pub use secret_resolver::SecretResolver;
+pub use vault::VaultSecretProvider;
@@ -0,0 +1,57 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::Path;
+
+use anyhow::Context;
+use async_trait::async_trait;
+
+/// A source of secrets, such as a secret manager.
+#[async_trait]
+pub trait SecretProvider: Send + Sync + 'static {
+    /// Fetches the secret stored at `path`.
+    async fn fetch_secret(&self, path: &str) -> anyhow::Result<String>;
+}
+
+/// Reads secrets from environment variables: the path is the name of the variable.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnvSecretProvider;
+
+#[async_trait]
+impl SecretProvider for EnvSecretProvider {
+    async fn fetch_secret(&self, path: &str) -> anyhow::Result<String> {
+        std::env::var(path).with_context(|| format!("Environment variable `{path}` is not set."))
+    }
+}
+
+/// Reads secrets from files: the path is the absolute path of the file. Trailing newlines are
+/// trimmed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileSecretProvider;
+
+#[async_trait]
+impl SecretProvider for FileSecretProvider {
+    async fn fetch_secret(&self, path: &str) -> anyhow::Result<String> {
+        let filepath = Path::new("/").join(path);
+        let secret = tokio::fs::read_to_string(&filepath)
+            .await
+            .with_context(|| format!("Failed to read secret file `{}`.", filepath.display()))?;
+        Ok(secret.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
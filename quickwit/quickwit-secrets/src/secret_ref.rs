@@ -0,0 +1,143 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+
+/// Scheme of the secret references.
+pub const SECRET_REF_SCHEME: &str = "secret://";
+
+/// Returns whether `value` is a secret reference rather than a plaintext value.
+pub fn is_secret_ref(value: &str) -> bool {
+    value.starts_with(SECRET_REF_SCHEME)
+}
+
+/// A reference to a secret stored in an external provider, expressed as
+/// `secret://<provider>/<path>[#<key>]`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SecretRef {
+    provider: String,
+    path: String,
+    key_opt: Option<String>,
+}
+
+impl SecretRef {
+    /// Returns the name of the provider storing the secret.
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// Returns the path of the secret within the provider.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the key to extract from the secret, if any.
+    pub fn key(&self) -> Option<&str> {
+        self.key_opt.as_deref()
+    }
+}
+
+impl FromStr for SecretRef {
+    type Err = anyhow::Error;
+
+    fn from_str(secret_ref_str: &str) -> anyhow::Result<Self> {
+        let Some(secret_ref_body) = secret_ref_str.strip_prefix(SECRET_REF_SCHEME) else {
+            bail!("Secret reference `{secret_ref_str}` must start with `{SECRET_REF_SCHEME}`.");
+        };
+        let (location, key_opt) = match secret_ref_body.split_once('#') {
+            Some((location, key)) => (location, Some(key)),
+            None => (secret_ref_body, None),
+        };
+        let Some((provider, path)) = location.split_once('/') else {
+            bail!(
+                "Secret reference `{secret_ref_str}` is invalid. Secret references must be of the \
+                 form `{SECRET_REF_SCHEME}<provider>/<path>[#<key>]`."
+            );
+        };
+        if provider.is_empty() || path.is_empty() || key_opt == Some("") {
+            bail!(
+                "Secret reference `{secret_ref_str}` is invalid. Secret references must be of the \
+                 form `{SECRET_REF_SCHEME}<provider>/<path>[#<key>]`."
+            );
+        }
+        Ok(SecretRef {
+            provider: provider.to_string(),
+            path: path.to_string(),
+            key_opt: key_opt.map(|key| key.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{SECRET_REF_SCHEME}{}/{}", self.provider, self.path)?;
+        if let Some(key) = &self.key_opt {
+            write!(f, "#{key}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_ref_from_str() {
+        let secret_ref: SecretRef = "secret://env/KAFKA_SASL_PASSWORD".parse().unwrap();
+        assert_eq!(secret_ref.provider(), "env");
+        assert_eq!(secret_ref.path(), "KAFKA_SASL_PASSWORD");
+        assert_eq!(secret_ref.key(), None);
+
+        let secret_ref: SecretRef = "secret://aws-secrets-manager/prod/kafka#password"
+            .parse()
+            .unwrap();
+        assert_eq!(secret_ref.provider(), "aws-secrets-manager");
+        assert_eq!(secret_ref.path(), "prod/kafka");
+        assert_eq!(secret_ref.key(), Some("password"));
+
+        for secret_ref_str in [
+            "secret://env/KAFKA_SASL_PASSWORD",
+            "secret://vault/secret/data/kafka#password",
+        ] {
+            assert_eq!(
+                secret_ref_str.parse::<SecretRef>().unwrap().to_string(),
+                secret_ref_str
+            );
+        }
+        for invalid_secret_ref_str in [
+            "env/KAFKA_SASL_PASSWORD",
+            "secret://env",
+            "secret:///KAFKA_SASL_PASSWORD",
+            "secret://env/",
+            "secret://vault/secret/data/kafka#",
+        ] {
+            assert!(invalid_secret_ref_str.parse::<SecretRef>().is_err());
+        }
+    }
+
+    #[test]
+    fn test_is_secret_ref() {
+        assert!(is_secret_ref("secret://env/KAFKA_SASL_PASSWORD"));
+        assert!(!is_secret_ref("my-password"));
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use quickwit_common::get_from_env;
+use serde_json::Value as JsonValue;
+use tracing::warn;
+
+use crate::{
+    is_secret_ref, AwsSecretsManagerProvider, EnvSecretProvider, FileSecretProvider,
+    SecretProvider, SecretRef, VaultSecretProvider,
+};
+
+const DEFAULT_SECRETS_CACHE_TTL_SECS: u64 = 300;
+
+static SECRET_RESOLVER: Lazy<SecretResolver> = Lazy::new(SecretResolver::with_default_providers);
+
+struct CachedSecret {
+    secret: String,
+    fetched_at: Instant,
+}
+
+/// Resolves secret references using the registered providers.
+///
+/// Fetched secrets are cached for a configurable amount of time, after which they are fetched
+/// again so that rotated secrets are eventually picked up. If the provider becomes unavailable,
+/// the last known value of the secret is used.
+pub struct SecretResolver {
+    providers: HashMap<String, Arc<dyn SecretProvider>>,
+    cache: Mutex<HashMap<(String, String), CachedSecret>>,
+    cache_ttl: Duration,
+}
+
+impl SecretResolver {
+    /// Creates a resolver without any provider.
+    pub fn new(cache_ttl: Duration) -> Self {
+        Self {
+            providers: HashMap::new(),
+            cache: Mutex::new(HashMap::new()),
+            cache_ttl,
+        }
+    }
+
+    /// Creates a resolver supporting the `env`, `file`, `aws-secrets-manager`, and `vault`
+    /// providers. The cache TTL is set by the `QW_SECRETS_CACHE_TTL_SECS` environment variable.
+    pub fn with_default_providers() -> Self {
+        let cache_ttl_secs =
+            get_from_env("QW_SECRETS_CACHE_TTL_SECS", DEFAULT_SECRETS_CACHE_TTL_SECS);
+        SecretResolver::new(Duration::from_secs(cache_ttl_secs))
+            .register("env", EnvSecretProvider)
+            .register("file", FileSecretProvider)
+            .register("aws-secrets-manager", AwsSecretsManagerProvider::default())
+            .register("vault", VaultSecretProvider::default())
+    }
+
+    /// Returns the process-wide resolver, configured with the default providers.
+    pub fn global() -> &'static SecretResolver {
+        &SECRET_RESOLVER
+    }
+
+    /// Returns the time after which cached secrets are fetched again.
+    pub fn cache_ttl(&self) -> Duration {
+        self.cache_ttl
+    }
+
+    /// Registers a provider under `name`, replacing any provider previously registered under the
+    /// same name.
+    pub fn register(mut self, name: &str, provider: impl SecretProvider) -> Self {
+        self.providers.insert(name.to_string(), Arc::new(provider));
+        self
+    }
+
+    /// Resolves `value` if it is a secret reference, or returns it as is otherwise.
+    pub async fn resolve(&self, value: &str) -> anyhow::Result<String> {
+        if !is_secret_ref(value) {
+            return Ok(value.to_string());
+        }
+        let secret_ref: SecretRef = value.parse()?;
+        let secret = self.fetch_secret(&secret_ref).await?;
+
+        let Some(key) = secret_ref.key() else {
+            return Ok(secret);
+        };
+        extract_secret_key(&secret, key)
+            .with_context(|| format!("Failed to resolve secret `{secret_ref}`."))
+    }
+
+    /// Resolves all the secret references found in the string values of a JSON document.
+    pub async fn resolve_json(&self, mut json_value: JsonValue) -> anyhow::Result<JsonValue> {
+        let mut values = vec![&mut json_value];
+
+        while let Some(value) = values.pop() {
+            match value {
+                JsonValue::String(value_str) if is_secret_ref(value_str) => {
+                    let secret = self.resolve(value_str).await?;
+                    *value_str = secret;
+                }
+                JsonValue::Array(array) => values.extend(array.iter_mut()),
+                JsonValue::Object(object) => values.extend(object.values_mut()),
+                _ => {}
+            }
+        }
+        Ok(json_value)
+    }
+
+    /// Evicts the secret referenced by `value` from the cache, so that it is fetched again the
+    /// next time it is resolved. This is useful when the secret is rejected, for instance after
+    /// it was rotated.
+    pub fn invalidate(&self, value: &str) {
+        let Ok(secret_ref) = value.parse::<SecretRef>() else {
+            return;
+        };
+        let cache_key = (
+            secret_ref.provider().to_string(),
+            secret_ref.path().to_string(),
+        );
+        self.cache.lock().unwrap().remove(&cache_key);
+    }
+
+    /// Evicts the secrets referenced in the string values of a JSON document from the cache.
+    pub fn invalidate_json(&self, json_value: &JsonValue) {
+        let mut values = vec![json_value];
+
+        while let Some(value) = values.pop() {
+            match value {
+                JsonValue::String(value_str) => self.invalidate(value_str),
+                JsonValue::Array(array) => values.extend(array.iter()),
+                JsonValue::Object(object) => values.extend(object.values()),
+                _ => {}
+            }
+        }
+    }
+
+    async fn fetch_secret(&self, secret_ref: &SecretRef) -> anyhow::Result<String> {
+        let cache_key = (
+            secret_ref.provider().to_string(),
+            secret_ref.path().to_string(),
+        );
+
+        let stale_secret_opt = match self.cache.lock().unwrap().get(&cache_key) {
+            Some(cached_secret) if cached_secret.fetched_at.elapsed() < self.cache_ttl => {
+                return Ok(cached_secret.secret.clone());
+            }
+            Some(cached_secret) => Some(cached_secret.secret.clone()),
+            None => None,
+        };
+        let Some(provider) = self.providers.get(secret_ref.provider()) else {
+            bail!(
+                "Secret provider `{}` is not supported. Supported providers are: {}.",
+                secret_ref.provider(),
+                self.providers.keys().sorted().join(", ")
+            );
+        };
+        match provider.fetch_secret(secret_ref.path()).await {
+            Ok(secret) => {
+                let cached_secret = CachedSecret {
+                    secret: secret.clone(),
+                    fetched_at: Instant::now(),
+                };
+                self.cache.lock().unwrap().insert(cache_key, cached_secret);
+                Ok(secret)
+            }
+            Err(error) => {
+                let Some(stale_secret) = stale_secret_opt else {
+                    return Err(error.context(format!("Failed to fetch secret `{secret_ref}`.")));
+                };
+                warn!(
+                    secret_ref=%secret_ref,
+                    error=?error,
+                    "Failed to refresh secret. Using the last known value."
+                );
+                Ok(stale_secret)
+            }
+        }
+    }
+}
+
+fn extract_secret_key(secret: &str, key: &str) -> anyhow::Result<String> {
+    let secret_json: JsonValue =
+        serde_json::from_str(secret).context("Secret is not a JSON object.")?;
+    match secret_json.get(key) {
+        Some(JsonValue::String(value)) => Ok(value.clone()),
+        Some(JsonValue::Null) | None => bail!("Secret has no key `{key}`."),
+        Some(value) => Ok(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Returns `secret-<n>` on the n-th fetch and fails once `num_successful_fetches` fetches
+    /// have succeeded.
+    #[derive(Clone, Default)]
+    struct RotatingSecretProvider {
+        num_fetches: Arc<AtomicUsize>,
+        num_successful_fetches: usize,
+    }
+
+    #[async_trait]
+    impl SecretProvider for RotatingSecretProvider {
+        async fn fetch_secret(&self, path: &str) -> anyhow::Result<String> {
+            let num_fetches = self.num_fetches.fetch_add(1, Ordering::Relaxed) + 1;
+            if num_fetches > self.num_successful_fetches {
+                bail!("Provider is unavailable.");
+            }
+            Ok(format!("{path}-{num_fetches}"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secret_resolver_plaintext() {
+        let secret_resolver = SecretResolver::new(Duration::ZERO);
+        assert_eq!(
+            secret_resolver.resolve("my-password").await.unwrap(),
+            "my-password"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_secret_resolver_env_and_file() {
+        std::env::set_var("QW_TEST_SECRET_RESOLVER_PASSWORD", "my-password");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let secret_filepath = temp_dir.path().join("password");
+        std::fs::write(&secret_filepath, "my-file-password\n").unwrap();
+
+        let secret_resolver = SecretResolver::new(Duration::ZERO)
+            .register("env", EnvSecretProvider)
+            .register("file", FileSecretProvider);
+        assert_eq!(
+            secret_resolver
+                .resolve("secret://env/QW_TEST_SECRET_RESOLVER_PASSWORD")
+                .await
+                .unwrap(),
+            "my-password"
+        );
+        assert_eq!(
+            secret_resolver
+                .resolve(&format!("secret://file{}", secret_filepath.display()))
+                .await
+                .unwrap(),
+            "my-file-password"
+        );
+        let error = secret_resolver
+            .resolve("secret://env/QW_TEST_SECRET_RESOLVER_UNDEFINED")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to fetch secret `secret://env/QW_TEST_SECRET_RESOLVER_UNDEFINED`."
+        );
+        let error = secret_resolver
+            .resolve("secret://keychain/password")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Secret provider `keychain` is not supported. Supported providers are: env, file."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_secret_resolver_key() {
+        std::env::set_var(
+            "QW_TEST_SECRET_RESOLVER_CREDENTIALS",
+            r#"{"username": "quickwit", "password": "my-password"}"#,
+        );
+        let secret_resolver =
+            SecretResolver::new(Duration::ZERO).register("env", EnvSecretProvider);
+        assert_eq!(
+            secret_resolver
+                .resolve("secret://env/QW_TEST_SECRET_RESOLVER_CREDENTIALS#password")
+                .await
+                .unwrap(),
+            "my-password"
+        );
+        secret_resolver
+            .resolve("secret://env/QW_TEST_SECRET_RESOLVER_CREDENTIALS#token")
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_secret_resolver_cache() {
+        let provider = RotatingSecretProvider {
+            num_successful_fetches: usize::MAX,
+            ..Default::default()
+        };
+        let secret_resolver =
+            SecretResolver::new(Duration::from_secs(3600)).register("rotating", provider.clone());
+        for _ in 0..2 {
+            assert_eq!(
+                secret_resolver
+                    .resolve("secret://rotating/password")
+                    .await
+                    .unwrap(),
+                "password-1"
+            );
+        }
+        assert_eq!(provider.num_fetches.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_secret_resolver_invalidate() {
+        let provider = RotatingSecretProvider {
+            num_successful_fetches: usize::MAX,
+            ..Default::default()
+        };
+        let secret_resolver =
+            SecretResolver::new(Duration::from_secs(3600)).register("rotating", provider.clone());
+        let client_params = json!({
+            "sasl.username": "quickwit",
+            "sasl.password": "secret://rotating/password#password",
+        });
+        secret_resolver
+            .resolve("secret://rotating/password")
+            .await
+            .unwrap();
+        secret_resolver.invalidate_json(&client_params);
+        assert_eq!(
+            secret_resolver
+                .resolve("secret://rotating/password")
+                .await
+                .unwrap(),
+            "password-2"
+        );
+        assert_eq!(provider.num_fetches.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_secret_resolver_rotation() {
+        let provider = RotatingSecretProvider {
+            num_successful_fetches: 2,
+            ..Default::default()
+        };
+        let secret_resolver = SecretResolver::new(Duration::ZERO).register("rotating", provider);
+        assert_eq!(
+            secret_resolver
+                .resolve("secret://rotating/password")
+                .await
+                .unwrap(),
+            "password-1"
+        );
+        assert_eq!(
+            secret_resolver
+                .resolve("secret://rotating/password")
+                .await
+                .unwrap(),
+            "password-2"
+        );
+        // The provider is now unavailable: the last known value is used.
+        assert_eq!(
+            secret_resolver
+                .resolve("secret://rotating/password")
+                .await
+                .unwrap(),
+            "password-2"
+        );
+        secret_resolver
+            .resolve("secret://rotating/token")
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_secret_resolver_resolve_json() {
+        std::env::set_var("QW_TEST_SECRET_RESOLVER_SASL_PASSWORD", "my-password");
+        let secret_resolver =
+            SecretResolver::new(Duration::ZERO).register("env", EnvSecretProvider);
+        let client_params = json!({
+            "bootstrap.servers": "localhost:9092",
+            "sasl.password": "secret://env/QW_TEST_SECRET_RESOLVER_SASL_PASSWORD",
+            "enable.ssl.certificate.verification": true,
+            "nested": ["secret://env/QW_TEST_SECRET_RESOLVER_SASL_PASSWORD"],
+        });
+        assert_eq!(
+            secret_resolver.resolve_json(client_params).await.unwrap(),
+            json!({
+                "bootstrap.servers": "localhost:9092",
+                "sasl.password": "my-password",
+                "enable.ssl.certificate.verification": true,
+                "nested": ["my-password"],
+            })
+        );
+    }
+}
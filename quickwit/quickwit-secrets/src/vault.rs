@@ -0,0 +1,133 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::env;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+
+use crate::SecretProvider;
+
+const VAULT_ADDR_ENV_VAR: &str = "VAULT_ADDR";
+
+const VAULT_TOKEN_ENV_VAR: &str = "VAULT_TOKEN";
+
+const VAULT_NAMESPACE_ENV_VAR: &str = "VAULT_NAMESPACE";
+
+/// Reads secrets from HashiCorp Vault: the path is the API path of the secret, without the `v1/`
+/// prefix, for instance `secret/data/kafka` for the `kafka` secret of a KV v2 engine mounted at
+/// `secret`.
+///
+/// The address of the server and the token are read from the `VAULT_ADDR` and `VAULT_TOKEN`
+/// environment variables. The secret data is returned as a JSON object.
+#[derive(Default)]
+pub struct VaultSecretProvider {
+    http_client: reqwest::Client,
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecretProvider {
+    async fn fetch_secret(&self, path: &str) -> anyhow::Result<String> {
+        let vault_addr = env::var(VAULT_ADDR_ENV_VAR).with_context(|| {
+            format!("Environment variable `{VAULT_ADDR_ENV_VAR}` must be set to read from Vault.")
+        })?;
+        let vault_token = env::var(VAULT_TOKEN_ENV_VAR).with_context(|| {
+            format!("Environment variable `{VAULT_TOKEN_ENV_VAR}` must be set to read from Vault.")
+        })?;
+        let url = format!(
+            "{}/v1/{}",
+            vault_addr.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+        let mut request = self
+            .http_client
+            .get(&url)
+            .header("X-Vault-Token", vault_token);
+
+        if let Ok(vault_namespace) = env::var(VAULT_NAMESPACE_ENV_VAR) {
+            request = request.header("X-Vault-Namespace", vault_namespace);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch secret `{path}` from Vault."))?;
+
+        if !response.status().is_success() {
+            bail!(
+                "Failed to fetch secret `{path}` from Vault: server responded with status `{}`.",
+                response.status()
+            );
+        }
+        let response_json: JsonValue = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse Vault response for secret `{path}`."))?;
+        extract_secret_data(response_json)
+            .with_context(|| format!("Failed to parse Vault response for secret `{path}`."))
+    }
+}
+
+fn extract_secret_data(mut response_json: JsonValue) -> anyhow::Result<String> {
+    let Some(data) = response_json.get_mut("data").map(JsonValue::take) else {
+        bail!("Response has no `data` field.");
+    };
+    // KV v2 engines nest the secret data and its metadata under `data`.
+    let secret_data = match data {
+        JsonValue::Object(mut data_map) if data_map.contains_key("metadata") => {
+            data_map.remove("data").unwrap_or_default()
+        }
+        data => data,
+    };
+    if !secret_data.is_object() {
+        bail!("Secret data is not a JSON object.");
+    }
+    Ok(secret_data.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_extract_secret_data() {
+        let kv_v1_response = json!({
+            "lease_duration": 2764800,
+            "data": {"password": "my-password"},
+        });
+        assert_eq!(
+            extract_secret_data(kv_v1_response).unwrap(),
+            r#"{"password":"my-password"}"#
+        );
+        let kv_v2_response = json!({
+            "data": {
+                "data": {"password": "my-password"},
+                "metadata": {"version": 3},
+            },
+        });
+        assert_eq!(
+            extract_secret_data(kv_v2_response).unwrap(),
+            r#"{"password":"my-password"}"#
+        );
+        extract_secret_data(json!({"errors": []})).unwrap_err();
+        extract_secret_data(json!({"data": "my-password"})).unwrap_err();
+    }
+}
@@ -0,0 +1,393 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use itertools::Itertools;
+use quickwit_cluster::Cluster;
+use quickwit_common::uri::Uri;
+use quickwit_config::{load_index_config_from_user_config, ConfigFormat, IndexConfig};
+use quickwit_ingest::{
+    CommitType, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
+};
+use quickwit_proto::indexing_api::IndexingTask;
+use quickwit_proto::{SearchRequest, ServiceError, ServiceErrorCode, SortOrder};
+use quickwit_query::query_ast::QueryAst;
+use quickwit_search::{SearchError, SearchService};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use time::OffsetDateTime;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+/// ID of the internal index storing the snapshots of the cluster state.
+pub const CLUSTER_HISTORY_INDEX_ID: &str = "cluster-history-v0_6";
+
+const CLUSTER_HISTORY_INDEX_CONFIG: &str = r#"
+version: 0.6
+
+index_id: ${INDEX_ID}
+
+doc_mapping:
+  mode: strict
+  field_mappings:
+    - name: timestamp
+      type: datetime
+      input_formats: [unix_timestamp]
+      output_format: unix_timestamp_secs
+      precision: seconds
+      fast: true
+    - name: cluster_id
+      type: text
+      tokenizer: raw
+    - name: recorder_node_id
+      type: text
+      tokenizer: raw
+    - name: nodes
+      type: array<json>
+      tokenizer: raw
+
+  timestamp_field: timestamp
+
+indexing_settings:
+  commit_timeout_secs: 10
+
+search_settings:
+  default_search_fields: []
+
+retention:
+  period: 30 days
+  schedule: daily
+"#;
+
+/// Health of a node at the time a snapshot was taken, as seen by the recording node.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeHealth {
+    /// The node is alive and ready to handle requests.
+    Ready,
+    /// The node is alive but not ready.
+    Live,
+    /// The node is flagged as dead or faulty.
+    Dead,
+}
+
+/// State of a node at the time a snapshot was taken.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct NodeStateRecord {
+    pub node_id: String,
+    pub generation_id: u64,
+    pub health: NodeHealth,
+    /// Enabled services. Only known for ready nodes.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub enabled_services: Vec<String>,
+    /// gRPC advertise address. Only known for ready nodes.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grpc_advertise_addr: Option<String>,
+    /// Indexing pipelines running on the node.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indexing_tasks: Vec<IndexingTask>,
+}
+
+/// Snapshot of the cluster membership, indexing pipelines placement, and node health at a given
+/// point in time.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ClusterStateRecord {
+    /// Time at which the snapshot was taken, expressed in seconds since epoch.
+    pub timestamp: i64,
+    pub cluster_id: String,
+    /// ID of the node that took the snapshot.
+    pub recorder_node_id: String,
+    pub nodes: Vec<NodeStateRecord>,
+}
+
+#[derive(Error, Debug)]
+pub enum ClusterHistoryError {
+    #[error("No snapshot of the cluster state was recorded at or before timestamp `{0}`.")]
+    NotFound(i64),
+    #[error("Failed to parse cluster state snapshot: `{0}`.")]
+    InvalidRecord(String),
+    #[error(transparent)]
+    Search(#[from] SearchError),
+}
+
+impl ServiceError for ClusterHistoryError {
+    fn status_code(&self) -> ServiceErrorCode {
+        match self {
+            Self::NotFound(_) => ServiceErrorCode::NotFound,
+            Self::InvalidRecord(_) => ServiceErrorCode::Internal,
+            Self::Search(search_error) => search_error.status_code(),
+        }
+    }
+}
+
+/// Returns the config of the internal index storing the snapshots of the cluster state.
+pub(crate) fn cluster_history_index_config(
+    default_index_root_uri: &Uri,
+) -> anyhow::Result<IndexConfig> {
+    let index_config_str =
+        CLUSTER_HISTORY_INDEX_CONFIG.replace("${INDEX_ID}", CLUSTER_HISTORY_INDEX_ID);
+    let index_config = load_index_config_from_user_config(
+        ConfigFormat::Yaml,
+        index_config_str.as_bytes(),
+        default_index_root_uri,
+    )?;
+    Ok(index_config)
+}
+
+/// Takes a snapshot of the cluster state as seen by the local node.
+pub(crate) async fn snapshot_cluster_state(cluster: &Cluster) -> ClusterStateRecord {
+    let timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let cluster_snapshot = cluster.snapshot().await;
+    let ready_members = cluster.ready_members().await;
+
+    let ready_nodes = ready_members.into_iter().map(|member| NodeStateRecord {
+        node_id: member.node_id,
+        generation_id: member.generation_id.as_u64(),
+        health: NodeHealth::Ready,
+        enabled_services: member
+            .enabled_services
+            .iter()
+            .map(|service| service.as_str().to_string())
+            .sorted()
+            .collect(),
+        grpc_advertise_addr: Some(member.grpc_advertise_addr.to_string()),
+        indexing_tasks: member
+            .indexing_tasks
+            .into_iter()
+            .sorted_by(|left, right| {
+                (&left.index_uid, &left.source_id).cmp(&(&right.index_uid, &right.source_id))
+            })
+            .collect(),
+    });
+    let live_nodes = cluster_snapshot
+        .live_nodes
+        .into_iter()
+        .map(|chitchat_id| (chitchat_id, NodeHealth::Live));
+    let dead_nodes = cluster_snapshot
+        .dead_nodes
+        .into_iter()
+        .map(|chitchat_id| (chitchat_id, NodeHealth::Dead));
+    let unready_nodes = live_nodes
+        .chain(dead_nodes)
+        .map(|(chitchat_id, health)| NodeStateRecord {
+            node_id: chitchat_id.node_id,
+            generation_id: chitchat_id.generation_id,
+            health,
+            enabled_services: Vec::new(),
+            grpc_advertise_addr: None,
+            indexing_tasks: Vec::new(),
+        });
+    let nodes = ready_nodes
+        .chain(unready_nodes)
+        .sorted_by(|left, right| left.node_id.cmp(&right.node_id))
+        .collect();
+
+    ClusterStateRecord {
+        timestamp,
+        cluster_id: cluster_snapshot.cluster_id,
+        recorder_node_id: cluster_snapshot.self_node_id,
+        nodes,
+    }
+}
+
+/// Spawns a task that periodically snapshots the cluster state and ingests the snapshots into
+/// the cluster history index.
+pub(crate) fn start_cluster_history_recorder(
+    cluster: Cluster,
+    mut ingest_service: IngestServiceClient,
+    snapshot_interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(snapshot_interval);
+
+        loop {
+            interval.tick().await;
+
+            let cluster_state_record = snapshot_cluster_state(&cluster).await;
+            let mut doc_batch_builder =
+                DocBatchBuilder::new(CLUSTER_HISTORY_INDEX_ID.to_string()).json_writer();
+            if let Err(error) = doc_batch_builder.ingest_doc(&cluster_state_record) {
+                error!(error=?error, "Failed to JSON serialize cluster state snapshot.");
+                continue;
+            }
+            let ingest_request = IngestRequest {
+                doc_batches: vec![doc_batch_builder.build()],
+                commit: CommitType::Auto as u32,
+            };
+            match ingest_service.ingest(ingest_request).await {
+                Ok(_) => debug!(
+                    num_nodes = cluster_state_record.nodes.len(),
+                    "Recorded cluster state snapshot."
+                ),
+                Err(error) => warn!(error=?error, "Failed to record cluster state snapshot."),
+            }
+        }
+    })
+}
+
+/// Returns the most recent snapshot of the cluster state taken at or before `timestamp`.
+pub(crate) async fn find_cluster_state_at(
+    search_service: Arc<dyn SearchService>,
+    timestamp: i64,
+) -> Result<ClusterStateRecord, ClusterHistoryError> {
+    let query_ast = serde_json::to_string(&QueryAst::MatchAll)
+        .expect("The match all query should be JSON serializable.");
+    let search_request = SearchRequest {
+        index_id: CLUSTER_HISTORY_INDEX_ID.to_string(),
+        query_ast,
+        // The time filter is a semi-open interval.
+        end_timestamp: Some(timestamp + 1),
+        max_hits: 1,
+        sort_by_field: Some("timestamp".to_string()),
+        sort_order: Some(SortOrder::Desc as i32),
+        ..Default::default()
+    };
+    let search_response = search_service.root_search(search_request).await?;
+    let Some(hit) = search_response.hits.into_iter().next() else {
+        return Err(ClusterHistoryError::NotFound(timestamp));
+    };
+    serde_json::from_str(&hit.json)
+        .map_err(|error| ClusterHistoryError::InvalidRecord(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+    use quickwit_proto::{Hit, SearchResponse};
+    use quickwit_search::MockSearchService;
+
+    use super::*;
+
+    #[test]
+    fn test_cluster_history_index_config_is_valid() {
+        let index_config =
+            cluster_history_index_config(&Uri::from_well_formed("ram:///indexes")).unwrap();
+        assert_eq!(index_config.index_id, CLUSTER_HISTORY_INDEX_ID);
+        assert_eq!(
+            index_config.doc_mapping.timestamp_field.as_deref(),
+            Some("timestamp")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_cluster_state() {
+        let transport = ChannelTransport::default();
+        let cluster =
+            create_cluster_for_test(Vec::new(), &["indexer", "searcher"], &transport, true)
+                .await
+                .unwrap();
+        let indexing_tasks = vec![
+            IndexingTask {
+                index_uid: "test-index:0".to_string(),
+                source_id: "source-2".to_string(),
+            },
+            IndexingTask {
+                index_uid: "test-index:0".to_string(),
+                source_id: "source-1".to_string(),
+            },
+        ];
+        cluster
+            .update_self_node_indexing_tasks(&indexing_tasks)
+            .await
+            .unwrap();
+        cluster
+            .wait_for_ready_members(
+                |members| members.len() == 1 && members[0].indexing_tasks.len() == 2,
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        let cluster_state_record = snapshot_cluster_state(&cluster).await;
+        assert_eq!(cluster_state_record.cluster_id, "test-cluster");
+        assert_eq!(
+            cluster_state_record.recorder_node_id,
+            cluster.self_node_id()
+        );
+        assert_eq!(cluster_state_record.nodes.len(), 1);
+
+        let node = &cluster_state_record.nodes[0];
+        assert_eq!(node.node_id, cluster.self_node_id());
+        assert_eq!(node.health, NodeHealth::Ready);
+        assert_eq!(node.enabled_services, ["indexer", "searcher"]);
+        assert_eq!(node.indexing_tasks.len(), 2);
+        assert_eq!(node.indexing_tasks[0].source_id, "source-1");
+        assert_eq!(node.indexing_tasks[1].source_id, "source-2");
+    }
+
+    #[tokio::test]
+    async fn test_find_cluster_state_at() {
+        let cluster_state_record = ClusterStateRecord {
+            timestamp: 1_684_000_000,
+            cluster_id: "test-cluster".to_string(),
+            recorder_node_id: "node-1".to_string(),
+            nodes: vec![NodeStateRecord {
+                node_id: "node-1".to_string(),
+                generation_id: 1,
+                health: NodeHealth::Ready,
+                enabled_services: vec!["control_plane".to_string()],
+                grpc_advertise_addr: Some("127.0.0.1:7281".to_string()),
+                indexing_tasks: Vec::new(),
+            }],
+        };
+        let hit_json = serde_json::to_string(&cluster_state_record).unwrap();
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| {
+                search_request.index_id == CLUSTER_HISTORY_INDEX_ID
+                    && search_request.end_timestamp == Some(1_684_000_061)
+                    && search_request.max_hits == 1
+                    && search_request.sort_order == Some(SortOrder::Desc as i32)
+            })
+            .returning(move |_| {
+                Ok(SearchResponse {
+                    num_hits: 1,
+                    hits: vec![Hit {
+                        json: hit_json.clone(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            });
+        let found_record = find_cluster_state_at(Arc::new(mock_search_service), 1_684_000_060)
+            .await
+            .unwrap();
+        assert_eq!(found_record, cluster_state_record);
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|_| Ok(SearchResponse::default()));
+        let error = find_cluster_state_at(Arc::new(mock_search_service), 1_600_000_000)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ClusterHistoryError::NotFound(1_600_000_000)
+        ));
+        assert!(matches!(error.status_code(), ServiceErrorCode::NotFound));
+    }
+}
@@ -17,6 +17,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod cluster_history;
 mod rest_handler;
 
-pub use rest_handler::{cluster_handler, ClusterApi};
+pub(crate) use cluster_history::{cluster_history_index_config, start_cluster_history_recorder};
+pub use rest_handler::{
+    cluster_handler, cluster_history_handler, cluster_topology_handler, ClusterApi,
+};
@@ -18,17 +18,41 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::convert::Infallible;
+use std::sync::Arc;
 
-use quickwit_cluster::{Cluster, ClusterSnapshot, NodeIdSchema};
+use quickwit_cluster::{
+    Cluster, ClusterSnapshot, ClusterTopology, NodeIdSchema, NodeStatus, NodeTopology,
+    PipelinePlacement, SplitCacheStats,
+};
+use quickwit_proto::indexing_api::IndexingTask;
+use quickwit_search::SearchService;
+use serde::Deserialize;
+use time::OffsetDateTime;
 use warp::{Filter, Rejection};
 
+use super::cluster_history::{
+    find_cluster_state_at, ClusterHistoryError, ClusterStateRecord, NodeHealth, NodeStateRecord,
+};
 use crate::format::extract_format_from_qs;
 use crate::json_api_response::make_json_api_response;
+use crate::with_arg;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(get_cluster),
-    components(schemas(ClusterSnapshot, NodeIdSchema,))
+    paths(get_cluster, get_cluster_topology, get_cluster_history),
+    components(schemas(
+        ClusterSnapshot,
+        NodeIdSchema,
+        ClusterTopology,
+        NodeTopology,
+        NodeStatus,
+        PipelinePlacement,
+        SplitCacheStats,
+        ClusterStateRecord,
+        NodeStateRecord,
+        NodeHealth,
+        IndexingTask,
+    ))
 )]
 pub struct ClusterApi;
 
@@ -59,3 +83,167 @@ async fn get_cluster(cluster: Cluster) -> Result<ClusterSnapshot, Infallible> {
     let snapshot = cluster.snapshot().await;
     Ok(snapshot)
 }
+
+/// Cluster topology handler.
+pub fn cluster_topology_handler(
+    cluster: Cluster,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "topology")
+        .and(warp::get())
+        .and(warp::path::end().map(move || cluster.clone()))
+        .then(get_cluster_topology)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Cluster Info",
+    path = "/cluster/topology",
+    responses(
+        (status = 200, description = "Successfully fetched the cluster topology.", body = ClusterTopology)
+    )
+)]
+
+/// Get the topology of the cluster.
+///
+/// Returns the nodes of the cluster along with their status, roles, version, running indexing
+/// pipelines, and split cache usage.
+async fn get_cluster_topology(cluster: Cluster) -> Result<ClusterTopology, Infallible> {
+    let topology = cluster.topology().await;
+    Ok(topology)
+}
+
+/// This struct represents the QueryString passed to
+/// the rest API to fetch a snapshot of the cluster state.
+#[derive(Debug, Clone, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClusterHistoryQueryParams {
+    /// Point in time, expressed in seconds since epoch. The most recent snapshot of the cluster
+    /// state recorded at or before this time is returned. Defaults to now.
+    pub timestamp: Option<i64>,
+}
+
+/// Cluster history handler.
+pub fn cluster_history_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "history")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(search_service))
+        .then(get_cluster_history)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Cluster Info",
+    path = "/cluster/history",
+    responses(
+        (status = 200, description = "Successfully fetched the cluster state snapshot.", body = ClusterStateRecord)
+    ),
+    params(
+        ClusterHistoryQueryParams,
+    )
+)]
+
+/// Get the state of the cluster at a given point in time.
+///
+/// Requires cluster history recording to be enabled on the node running the control plane.
+async fn get_cluster_history(
+    query_params: ClusterHistoryQueryParams,
+    search_service: Arc<dyn SearchService>,
+) -> Result<ClusterStateRecord, ClusterHistoryError> {
+    let timestamp = query_params
+        .timestamp
+        .unwrap_or_else(|| OffsetDateTime::now_utc().unix_timestamp());
+    find_cluster_state_at(search_service, timestamp).await
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
+    use quickwit_proto::{Hit, SearchResponse};
+    use quickwit_search::MockSearchService;
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+    use crate::recover_fn;
+
+    #[tokio::test]
+    async fn test_cluster_topology_handler() {
+        let transport = ChannelTransport::default();
+        let cluster =
+            create_cluster_for_test(Vec::new(), &["indexer", "searcher"], &transport, true)
+                .await
+                .unwrap();
+        cluster.set_self_node_build_version("0.6.0").await;
+        let cluster_topology_handler =
+            cluster_topology_handler(cluster.clone()).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/cluster/topology")
+            .reply(&cluster_topology_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["cluster_id"], "test-cluster");
+        assert_eq!(resp_json["self_node_id"], cluster.self_node_id());
+
+        let node_json = &resp_json["nodes"][0];
+        assert_eq!(node_json["node_id"], cluster.self_node_id());
+        assert_eq!(node_json["status"], "ready");
+        assert_eq!(node_json["roles"], json!(["indexer", "searcher"]));
+        assert_eq!(node_json["version"], "0.6.0");
+        assert_eq!(node_json["indexing_pipelines"], json!([]));
+        assert_eq!(node_json["split_cache"], JsonValue::Null);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_history_handler() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.end_timestamp == Some(1_684_000_001))
+            .returning(|_| {
+                Ok(SearchResponse {
+                    num_hits: 1,
+                    hits: vec![Hit {
+                        json: r#"{"timestamp": 1684000000, "cluster_id": "test-cluster", "recorder_node_id": "node-1", "nodes": [{"node_id": "node-1", "generation_id": 1, "health": "ready"}, {"node_id": "node-2", "generation_id": 3, "health": "dead"}]}"#.to_string(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            });
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.end_timestamp == Some(1_000_001))
+            .returning(|_| Ok(SearchResponse::default()));
+        let cluster_history_handler =
+            cluster_history_handler(Arc::new(mock_search_service)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/cluster/history?timestamp=1684000000")
+            .reply(&cluster_history_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["timestamp"], 1_684_000_000);
+        assert_eq!(resp_json["nodes"][1]["node_id"], "node-2");
+        assert_eq!(resp_json["nodes"][1]["health"], "dead");
+
+        let resp = warp::test::request()
+            .path("/cluster/history?timestamp=1000000")
+            .reply(&cluster_history_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = warp::test::request()
+            .path("/cluster/history?timestamp=yesterday")
+            .reply(&cluster_history_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+}
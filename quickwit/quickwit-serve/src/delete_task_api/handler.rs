@@ -24,7 +24,7 @@ use quickwit_janitor::error::JanitorError;
 use quickwit_metastore::{Metastore, MetastoreError};
 use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
 use quickwit_proto::{query_ast_from_user_text, IndexUid, SearchRequest};
-use quickwit_query::query_ast::QueryAst;
+use quickwit_query::query_ast::{query_ast_from_json, query_ast_to_json, QueryAst};
 use serde::Deserialize;
 use warp::{Filter, Rejection};
 
@@ -138,7 +138,7 @@ pub async fn post_delete_request(
     let query_ast = query_ast_from_user_text(&delete_request.query, Some(Vec::new()))
         .parse_user_query(&[])
         .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
-    let query_ast_json = serde_json::to_string(&query_ast).map_err(|_err| {
+    let query_ast_json = query_ast_to_json(&query_ast).map_err(|_err| {
         JanitorError::InternalError("Failed to serialized delete query ast".to_string())
     })?;
     let delete_query = DeleteQuery {
@@ -155,7 +155,7 @@ pub async fn post_delete_request(
         .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
 
     // Validate the delete query against the current doc mapping configuration.
-    let query_ast: QueryAst = serde_json::from_str(&delete_search_request.query_ast)
+    let query_ast: QueryAst = query_ast_from_json(&delete_search_request.query_ast)
         .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
     doc_mapper
         .query(doc_mapper.schema(), &query_ast, true)
@@ -208,7 +208,7 @@ mod tests {
         );
         assert_eq!(
             created_delete_query.query_ast,
-            r#"{"type":"full_text","field":"body","text":"myterm","params":{"mode":{"type":"phrase_fallback_to_intersection"}}}"#
+            r#"{"type":"full_text","field":"body","text":"myterm","params":{"mode":{"type":"phrase_fallback_to_intersection"}},"version":1}"#
         );
         assert_eq!(created_delete_query.start_timestamp, Some(1));
         assert_eq!(created_delete_query.end_timestamp, Some(10));
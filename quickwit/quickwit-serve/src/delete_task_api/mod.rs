@@ -18,5 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod handler;
+mod purge;
 
 pub use handler::{delete_task_api_handlers, DeleteTaskApi};
+pub use purge::{purge_api_handlers, PurgeApi};
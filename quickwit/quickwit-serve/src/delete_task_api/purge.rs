@@ -0,0 +1,460 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+
+use quickwit_common::new_coolid;
+use quickwit_common::uri::Uri;
+use quickwit_config::build_doc_mapper;
+use quickwit_janitor::error::JanitorError;
+use quickwit_metastore::{ListSplitsQuery, Metastore, SplitState};
+use quickwit_proto::metastore_api::{DeleteQuery, DeleteTask};
+use quickwit_proto::{IndexUid, SearchRequest};
+use quickwit_query::query_ast::{query_ast_to_json, QueryAst, TermSetQuery};
+use quickwit_search::SearchService;
+use quickwit_storage::StorageResolver;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::json_api_response::make_json_api_response;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(post_purge_request, get_purge_status),
+    components(schemas(PurgeRequest, PurgeReport, PurgeStatus,))
+)]
+pub struct PurgeApi;
+
+/// Maximum number of documents a purge request can export, and therefore delete.
+pub const MAX_PURGE_NUM_DOCS: u64 = 10_000;
+
+/// Request to export, then delete, the documents of an index whose `field` holds one of `values`,
+/// e.g. all the documents of a user for a data subject request.
+#[derive(Deserialize, Debug, Eq, PartialEq, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PurgeRequest {
+    /// Field identifying the documents to purge, e.g. `user_id`.
+    pub field: String,
+    /// Values of `field` identifying the documents to purge.
+    pub values: BTreeSet<String>,
+    /// URI of the directory where the exported documents and the report are written.
+    #[schema(value_type = String)]
+    pub archive_uri: Uri,
+}
+
+/// Auditable report of a purge request, also written next to the exported documents.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+pub struct PurgeReport {
+    pub purge_id: String,
+    pub index_id: String,
+    pub index_uid: String,
+    pub field: String,
+    pub values: BTreeSet<String>,
+    /// Time of the request, expressed in seconds since epoch.
+    pub requested_at: i64,
+    pub num_exported_docs: u64,
+    /// URI of the newline-delimited JSON file holding the exported documents.
+    pub exported_docs_uri: String,
+    pub report_uri: String,
+    /// Delete task removing the exported documents from the index. Its progress can be followed
+    /// with the purge status endpoint.
+    pub delete_task: DeleteTask,
+}
+
+/// Progress of the delete task of a purge request.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, utoipa::ToSchema)]
+pub struct PurgeStatus {
+    pub opstamp: u64,
+    /// Number of published splits the delete task has not been applied to yet.
+    pub num_pending_splits: usize,
+    /// True once the delete task has been applied to all the published splits of the index.
+    pub is_completed: bool,
+}
+
+/// Purge API handlers.
+pub fn purge_api_handlers(
+    metastore: Arc<dyn Metastore>,
+    search_service: Arc<dyn SearchService>,
+    storage_resolver: StorageResolver,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    post_purge_request_handler(metastore.clone(), search_service, storage_resolver)
+        .or(get_purge_status_handler(metastore))
+}
+
+fn post_purge_request_handler(
+    metastore: Arc<dyn Metastore>,
+    search_service: Arc<dyn SearchService>,
+    storage_resolver: StorageResolver,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "purge-requests")
+        .and(warp::body::json())
+        .and(warp::post())
+        .and(with_arg(metastore))
+        .and(with_arg(search_service))
+        .and(with_arg(storage_resolver))
+        .then(post_purge_request)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+fn get_purge_status_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "purge-requests" / u64)
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_purge_status)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Delete Tasks",
+    path = "/{index_id}/purge-requests",
+    request_body = PurgeRequest,
+    responses(
+        (status = 200, description = "Successfully exported the documents and added a delete task.", body = PurgeReport)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to purge documents from."),
+    )
+)]
+/// Create Purge Request
+///
+/// Exports the documents whose `field` holds one of `values` to `archive_uri` as newline-delimited
+/// JSON, then creates a delete task removing them. The report of the request is returned and
+/// written to `archive_uri` as well. Documents are not deleted if the export fails.
+pub async fn post_purge_request(
+    index_id: String,
+    purge_request: PurgeRequest,
+    metastore: Arc<dyn Metastore>,
+    search_service: Arc<dyn SearchService>,
+    storage_resolver: StorageResolver,
+) -> Result<PurgeReport, JanitorError> {
+    let requested_at = OffsetDateTime::now_utc().unix_timestamp();
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    // The delete tasks of read-only and retention locked indexes are not executed.
+    if !index_metadata.state.is_writable() || index_metadata.is_retention_locked(requested_at) {
+        return Err(JanitorError::InvalidDeleteQuery(format!(
+            "Index `{index_id}` is read-only or retention locked, its documents cannot be purged."
+        )));
+    }
+    if purge_request.values.is_empty() {
+        return Err(JanitorError::InvalidDeleteQuery(
+            "A purge request requires at least one value.".to_string(),
+        ));
+    }
+    let index_uid: IndexUid = index_metadata.index_uid.clone();
+    let query_ast: QueryAst = TermSetQuery {
+        terms_per_field: HashMap::from([(
+            purge_request.field.clone(),
+            purge_request.values.clone(),
+        )]),
+    }
+    .into();
+    // Validates the query against the current doc mapping before exporting anything.
+    let index_config = index_metadata.into_index_config();
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| JanitorError::InternalError(error.to_string()))?;
+    doc_mapper
+        .query(doc_mapper.schema(), &query_ast, true)
+        .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
+    let query_ast_json = query_ast_to_json(&query_ast).map_err(|_err| {
+        JanitorError::InternalError("Failed to serialized delete query ast".to_string())
+    })?;
+
+    let search_request = SearchRequest {
+        index_id: index_id.clone(),
+        query_ast: query_ast_json.clone(),
+        max_hits: MAX_PURGE_NUM_DOCS,
+        ..Default::default()
+    };
+    let search_response = search_service
+        .root_search(search_request)
+        .await
+        .map_err(|error| {
+            JanitorError::InternalError(format!("Failed to export documents: {error}"))
+        })?;
+    if !search_response.errors.is_empty() {
+        return Err(JanitorError::InternalError(format!(
+            "Failed to export documents: {}",
+            search_response.errors.join(", ")
+        )));
+    }
+    if search_response.num_hits > MAX_PURGE_NUM_DOCS {
+        return Err(JanitorError::InvalidDeleteQuery(format!(
+            "The purge request matches {} documents, but at most {MAX_PURGE_NUM_DOCS} documents \
+             can be purged at once.",
+            search_response.num_hits
+        )));
+    }
+    let mut exported_docs: Vec<u8> = Vec::new();
+    for hit in &search_response.hits {
+        exported_docs.extend_from_slice(hit.json.as_bytes());
+        exported_docs.push(b'\n');
+    }
+    let num_exported_docs = search_response.hits.len() as u64;
+
+    let purge_id = new_coolid("purge");
+    let exported_docs_filename = format!("{index_id}-{purge_id}.ndjson");
+    let report_filename = format!("{index_id}-{purge_id}-report.json");
+    let archive_uri = &purge_request.archive_uri;
+    let join_archive_uri = |filename: &str| {
+        archive_uri
+            .join(filename)
+            .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))
+    };
+    let exported_docs_uri = join_archive_uri(&exported_docs_filename)?;
+    let report_uri = join_archive_uri(&report_filename)?;
+    let archive_storage = storage_resolver
+        .resolve(archive_uri)
+        .await
+        .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
+    archive_storage
+        .put(Path::new(&exported_docs_filename), Box::new(exported_docs))
+        .await
+        .map_err(|error| {
+            JanitorError::InternalError(format!("Failed to write exported documents: {error}"))
+        })?;
+
+    let delete_query = DeleteQuery {
+        index_uid: index_uid.to_string(),
+        start_timestamp: None,
+        end_timestamp: None,
+        query_ast: query_ast_json,
+    };
+    let delete_task = metastore.create_delete_task(delete_query).await?;
+    let purge_report = PurgeReport {
+        purge_id,
+        index_id,
+        index_uid: index_uid.to_string(),
+        field: purge_request.field,
+        values: purge_request.values,
+        requested_at,
+        num_exported_docs,
+        exported_docs_uri: exported_docs_uri.to_string(),
+        report_uri: report_uri.to_string(),
+        delete_task,
+    };
+    info!(
+        purge_id = %purge_report.purge_id,
+        index_id = %purge_report.index_id,
+        num_exported_docs = num_exported_docs,
+        opstamp = purge_report.delete_task.opstamp,
+        "Created purge request."
+    );
+    let report_json = serde_json::to_vec_pretty(&purge_report).map_err(|_err| {
+        JanitorError::InternalError("Failed to serialize purge report".to_string())
+    })?;
+    archive_storage
+        .put(Path::new(&report_filename), Box::new(report_json))
+        .await
+        .map_err(|error| {
+            JanitorError::InternalError(format!("Failed to write purge report: {error}"))
+        })?;
+    Ok(purge_report)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Delete Tasks",
+    path = "/{index_id}/purge-requests/{opstamp}",
+    responses(
+        (status = 200, description = "Successfully fetched the purge request status.", body = PurgeStatus)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID the documents are purged from."),
+        ("opstamp" = u64, Path, description = "The opstamp of the delete task of the purge request."),
+    )
+)]
+/// Get Purge Request Status
+///
+/// Returns the number of published splits the delete task of a purge request has not been
+/// applied to yet.
+pub async fn get_purge_status(
+    index_id: String,
+    opstamp: u64,
+    metastore: Arc<dyn Metastore>,
+) -> Result<PurgeStatus, JanitorError> {
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    let delete_tasks = metastore.list_delete_tasks(index_uid.clone(), 0).await?;
+    if !delete_tasks
+        .iter()
+        .any(|delete_task| delete_task.opstamp == opstamp)
+    {
+        return Err(JanitorError::InvalidDeleteQuery(format!(
+            "No delete task with opstamp `{opstamp}` found for index `{index_id}`."
+        )));
+    }
+    let query = ListSplitsQuery::for_index(index_uid)
+        .with_split_state(SplitState::Published)
+        .with_delete_opstamp_lt(opstamp);
+    let num_pending_splits = metastore.list_splits(query).await?.len();
+    Ok(PurgeStatus {
+        opstamp,
+        num_pending_splits,
+        is_completed: num_pending_splits == 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use quickwit_indexing::TestSandbox;
+    use quickwit_proto::{Hit, SearchResponse};
+    use quickwit_search::MockSearchService;
+    use quickwit_storage::StorageResolver;
+    use warp::Filter;
+
+    use super::{PurgeReport, PurgeStatus};
+    use crate::rest::recover_fn;
+
+    #[tokio::test]
+    async fn test_purge_api() {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = "test-purge-rest";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: user_id
+                type: text
+                tokenizer: raw
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(1)
+            .returning(|search_request| {
+                assert_eq!(search_request.index_id, "test-purge-rest");
+                assert_eq!(search_request.max_hits, super::MAX_PURGE_NUM_DOCS);
+                let hits = [
+                    "{\"user_id\":\"u1\",\"body\":\"a\"}",
+                    "{\"user_id\":\"u1\",\"body\":\"b\"}",
+                ]
+                .into_iter()
+                .map(|json| Hit {
+                    json: json.to_string(),
+                    ..Default::default()
+                })
+                .collect();
+                Ok(SearchResponse {
+                    num_hits: 2,
+                    hits,
+                    ..Default::default()
+                })
+            });
+        let storage_resolver = StorageResolver::ram_for_test();
+        let purge_api_handlers = super::purge_api_handlers(
+            metastore,
+            Arc::new(mock_search_service),
+            storage_resolver.clone(),
+        )
+        .recover(recover_fn);
+
+        // POST a purge request on an unknown field.
+        let resp = warp::test::request()
+            .path("/test-purge-rest/purge-requests")
+            .method("POST")
+            .json(&true)
+            .body(
+                r#"{"field": "unknown_field", "values": ["u1"], "archive_uri": "ram:///archive"}"#,
+            )
+            .reply(&purge_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        // POST a valid purge request.
+        let resp = warp::test::request()
+            .path("/test-purge-rest/purge-requests")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"field": "user_id", "values": ["u1"], "archive_uri": "ram:///archive"}"#)
+            .reply(&purge_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let purge_report: PurgeReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(purge_report.index_uid, test_sandbox.index_uid().to_string());
+        assert_eq!(purge_report.num_exported_docs, 2);
+        assert_eq!(purge_report.delete_task.opstamp, 1);
+        assert_eq!(
+            purge_report
+                .delete_task
+                .delete_query
+                .as_ref()
+                .unwrap()
+                .query_ast,
+            r#"{"type":"term_set","terms_per_field":{"user_id":["u1"]},"version":1}"#
+        );
+        let archive_storage = storage_resolver
+            .resolve(&"ram:///archive".parse().unwrap())
+            .await
+            .unwrap();
+        let exported_docs_filename = Path::new(&purge_report.exported_docs_uri)
+            .file_name()
+            .unwrap();
+        let exported_docs = archive_storage
+            .get_all(Path::new(exported_docs_filename))
+            .await
+            .unwrap();
+        assert_eq!(
+            exported_docs.as_slice(),
+            b"{\"user_id\":\"u1\",\"body\":\"a\"}\n{\"user_id\":\"u1\",\"body\":\"b\"}\n"
+        );
+        let report_filename = Path::new(&purge_report.report_uri).file_name().unwrap();
+        let report_json = archive_storage
+            .get_all(Path::new(report_filename))
+            .await
+            .unwrap();
+        let archived_purge_report: PurgeReport =
+            serde_json::from_slice(report_json.as_slice()).unwrap();
+        assert_eq!(archived_purge_report, purge_report);
+
+        // GET the status of the purge request.
+        let resp = warp::test::request()
+            .path("/test-purge-rest/purge-requests/1")
+            .reply(&purge_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let purge_status: PurgeStatus = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(purge_status.opstamp, 1);
+        assert!(purge_status.is_completed);
+
+        // GET the status of an unknown purge request.
+        let resp = warp::test::request()
+            .path("/test-purge-rest/purge-requests/2")
+            .reply(&purge_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+        test_sandbox.assert_quit().await;
+    }
+}
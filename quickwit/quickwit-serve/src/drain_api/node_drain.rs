@@ -0,0 +1,174 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use quickwit_actors::Mailbox;
+use quickwit_cluster::Cluster;
+use quickwit_common::get_from_env;
+use quickwit_indexing::actors::IndexingService;
+use quickwit_indexing::models::{DrainPipelines, Observe};
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+/// Maximum duration, in seconds, the node waits for its indexing pipelines to commit their
+/// in-progress splits before shutting down. It can be overridden with the
+/// `QW_NODE_DRAIN_TIMEOUT_SECS` environment variable.
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 300;
+
+const DRAIN_POLL_INTERVAL: Duration = if cfg!(test) {
+    Duration::from_millis(25)
+} else {
+    Duration::from_secs(1)
+};
+
+/// Signal shared by the drain REST API, the node readiness reporting task, and the shutdown
+/// sequence. Once triggered, it cannot be reset: a drained node shuts down.
+#[derive(Clone)]
+pub(crate) struct DrainSignal {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for DrainSignal {
+    fn default() -> Self {
+        let (sender, _receiver) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+}
+
+impl DrainSignal {
+    /// Triggers the drain. Returns `false` if the drain was already triggered.
+    pub fn trigger(&self) -> bool {
+        self.sender.send_if_modified(|is_triggered| {
+            if *is_triggered {
+                return false;
+            }
+            *is_triggered = true;
+            true
+        })
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Resolves once the drain is triggered.
+    pub async fn triggered(&self) {
+        let mut receiver = self.sender.subscribe();
+        while !*receiver.borrow_and_update() {
+            receiver
+                .changed()
+                .await
+                .expect("The sender should not be dropped while the signal is borrowed.");
+        }
+    }
+}
+
+/// Drains the node before it shuts down:
+/// - the node reports itself as not ready, so it no longer receives search jobs, ingest
+///   requests, or indexing tasks;
+/// - the indexing pipelines stop reading from their sources, commit their in-progress splits,
+///   and exit. The control plane then reassigns their indexing tasks to the other indexers.
+pub(crate) async fn drain_node(
+    cluster: &Cluster,
+    indexing_service_opt: Option<&Mailbox<IndexingService>>,
+) {
+    info!("Draining node.");
+    cluster.set_self_node_readiness(false).await;
+
+    if let Some(indexing_service) = indexing_service_opt {
+        let drain_timeout = Duration::from_secs(get_from_env(
+            "QW_NODE_DRAIN_TIMEOUT_SECS",
+            DEFAULT_DRAIN_TIMEOUT_SECS,
+        ));
+        if tokio::time::timeout(drain_timeout, drain_indexing_pipelines(indexing_service))
+            .await
+            .is_err()
+        {
+            warn!(drain_timeout=?drain_timeout, "Timed out while draining the indexing pipelines.");
+        }
+    }
+    info!("Node drained.");
+}
+
+async fn drain_indexing_pipelines(indexing_service: &Mailbox<IndexingService>) {
+    if let Err(error) = indexing_service.ask(DrainPipelines).await {
+        error!(error=?error, "Failed to drain the indexing pipelines.");
+        return;
+    }
+    loop {
+        match indexing_service.ask(Observe).await {
+            Ok(counters) if counters.num_running_pipelines == 0 => return,
+            Ok(counters) => {
+                debug!(
+                    num_running_pipelines = counters.num_running_pipelines,
+                    "Waiting for the indexing pipelines to drain."
+                );
+            }
+            Err(error) => {
+                error!(error=?error, "Failed to observe the indexing service.");
+                return;
+            }
+        }
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chitchat::transport::ChannelTransport;
+    use quickwit_cluster::create_cluster_for_test;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_signal() {
+        let drain_signal = DrainSignal::default();
+        assert!(!drain_signal.is_triggered());
+
+        let drain_signal_clone = drain_signal.clone();
+        let triggered_handle = tokio::spawn(async move { drain_signal_clone.triggered().await });
+
+        assert!(drain_signal.trigger());
+        assert!(drain_signal.is_triggered());
+        assert!(!drain_signal.trigger());
+
+        tokio::time::timeout(Duration::from_secs(1), triggered_handle)
+            .await
+            .unwrap()
+            .unwrap();
+        // The signal cannot be reset, so late subscribers resolve immediately.
+        drain_signal.triggered().await;
+    }
+
+    #[tokio::test]
+    async fn test_drain_node_marks_node_as_not_ready() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        assert!(cluster.is_self_node_ready().await);
+
+        drain_node(&cluster, None).await;
+        assert!(!cluster.is_self_node_ready().await);
+    }
+}
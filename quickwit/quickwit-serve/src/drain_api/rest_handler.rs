@@ -0,0 +1,89 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tracing::info;
+use warp::hyper::StatusCode;
+use warp::reply::with_status;
+use warp::{Filter, Rejection};
+
+use super::DrainSignal;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(post_drain))]
+pub struct DrainApi;
+
+/// Drain handler.
+pub(crate) fn drain_handler(
+    drain_signal: DrainSignal,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("drain")
+        .and(warp::post())
+        .and(with_arg(drain_signal))
+        .then(post_drain)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Node Health",
+    path = "/drain",
+    responses(
+        (status = 202, description = "The node is draining and will shut down once drained.", body = bool),
+    ),
+)]
+/// Drain Node
+///
+/// Stops routing new search jobs, ingest requests, and indexing tasks to the node, commits the
+/// in-progress splits, hands off the indexing pipelines to the other indexers, and finally shuts
+/// the node down. The request returns immediately.
+async fn post_drain(drain_signal: DrainSignal) -> impl warp::Reply {
+    if drain_signal.trigger() {
+        info!("Drain requested via the REST API.");
+    }
+    with_status(warp::reply::json(&true), StatusCode::ACCEPTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recover_fn;
+
+    #[tokio::test]
+    async fn test_rest_drain_api() {
+        let drain_signal = DrainSignal::default();
+        let drain_handler = drain_handler(drain_signal.clone()).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/drain")
+            .reply(&drain_handler)
+            .await;
+        assert_eq!(resp.status(), 405);
+        assert!(!drain_signal.is_triggered());
+
+        for _ in 0..2 {
+            let resp = warp::test::request()
+                .method("POST")
+                .path("/drain")
+                .reply(&drain_handler)
+                .await;
+            assert_eq!(resp.status(), 202);
+            assert!(drain_signal.is_triggered());
+        }
+    }
+}
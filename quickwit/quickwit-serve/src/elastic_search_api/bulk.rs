@@ -135,7 +135,8 @@ mod tests {
     use quickwit_ingest::{
         FetchRequest, IngestResponse, IngestServiceClient, SuggestTruncateRequest,
     };
-    use quickwit_search::MockSearchService;
+    use quickwit_metastore::MockMetastore;
+    use quickwit_search::{MockSearchService, RequestTemplates};
 
     use crate::elastic_search_api::elastic_api_handlers;
     use crate::ingest_api::setup_ingest_service;
@@ -145,7 +146,12 @@ mod tests {
         let search_service = Arc::new(MockSearchService::new());
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
-        let elastic_api_handlers = elastic_api_handlers(search_service, ingest_service);
+        let elastic_api_handlers = elastic_api_handlers(
+            search_service,
+            ingest_service,
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index", "_id" : "1"} }
             {"id": 1, "message": "push"}
@@ -166,7 +172,12 @@ mod tests {
         let search_service = Arc::new(MockSearchService::new());
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index-1", "my-index-2"], &IngestApiConfig::default()).await;
-        let elastic_api_handlers = elastic_api_handlers(search_service, ingest_service);
+        let elastic_api_handlers = elastic_api_handlers(
+            search_service,
+            ingest_service,
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index-1", "_id" : "1"} }
             {"id": 1, "message": "push"}
@@ -191,7 +202,12 @@ mod tests {
         let search_service = Arc::new(MockSearchService::new());
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index-1", "my-index-2"], &IngestApiConfig::default()).await;
-        let elastic_api_handlers = elastic_api_handlers(search_service, ingest_service);
+        let elastic_api_handlers = elastic_api_handlers(
+            search_service,
+            ingest_service,
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index-1", "_id" : "1"} }
             {"id": 1, "message": "push"}
@@ -216,7 +232,12 @@ mod tests {
         let search_service = Arc::new(MockSearchService::new());
         let (universe, _temp_dir, ingest_service, ingest_service_mailbox) =
             setup_ingest_service(&["my-index-1", "my-index-2"], &IngestApiConfig::default()).await;
-        let elastic_api_handlers = elastic_api_handlers(search_service, ingest_service);
+        let elastic_api_handlers = elastic_api_handlers(
+            search_service,
+            ingest_service,
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index-1", "_id" : "1"} }
             {"id": 1, "message": "push"}
@@ -244,6 +265,8 @@ mod tests {
                     index_id: "my-index-1".to_string(),
                     start_after: None,
                     num_bytes_limit: None,
+                    start_timestamp: None,
+                    keep_most_recent: false,
                 })
                 .await
                 .unwrap()
@@ -259,6 +282,8 @@ mod tests {
                     index_id: "my-index-2".to_string(),
                     start_after: None,
                     num_bytes_limit: None,
+                    start_timestamp: None,
+                    keep_most_recent: false,
                 })
                 .await
                 .unwrap()
@@ -292,7 +317,12 @@ mod tests {
         let search_service = Arc::new(MockSearchService::new());
         let (universe, _temp_dir, ingest_service, ingest_service_mailbox) =
             setup_ingest_service(&["my-index-1", "my-index-2"], &IngestApiConfig::default()).await;
-        let elastic_api_handlers = elastic_api_handlers(search_service, ingest_service);
+        let elastic_api_handlers = elastic_api_handlers(
+            search_service,
+            ingest_service,
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index-1", "_id" : "1"} }
             {"id": 1, "message": "push"}
@@ -320,6 +350,8 @@ mod tests {
                     index_id: "my-index-1".to_string(),
                     start_after: None,
                     num_bytes_limit: None,
+                    start_timestamp: None,
+                    keep_most_recent: false,
                 })
                 .await
                 .unwrap()
@@ -334,6 +366,8 @@ mod tests {
                     index_id: "my-index-2".to_string(),
                     start_after: None,
                     num_bytes_limit: None,
+                    start_timestamp: None,
+                    keep_most_recent: false,
                 })
                 .await
                 .unwrap()
@@ -366,7 +400,12 @@ mod tests {
     async fn test_bulk_ingest_request_returns_400_if_action_is_malformed() {
         let search_service = Arc::new(MockSearchService::new());
         let ingest_service = IngestServiceClient::new(IngestServiceClient::mock());
-        let elastic_api_handlers = elastic_api_handlers(search_service, ingest_service);
+        let elastic_api_handlers = elastic_api_handlers(
+            search_service,
+            ingest_service,
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let payload = r#"
             {"create": {"_index": "my-index", "_id": "1"},}
             {"id": 1, "message": "my-doc"}"#;
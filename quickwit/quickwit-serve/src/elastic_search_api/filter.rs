@@ -24,7 +24,10 @@ use warp::reject::LengthRequired;
 use warp::{Filter, Rejection};
 
 use super::model::MultiSearchQueryParams;
-use crate::elastic_search_api::model::{ElasticIngestOptions, SearchBody, SearchQueryParams};
+use crate::elastic_search_api::model::{
+    ElasticIngestOptions, SearchBody, SearchQueryParams, SearchTemplateRequest, ValidateQueryBody,
+    ValidateQueryParams,
+};
 
 const BODY_LENGTH_LIMIT: Byte = byte_unit::Byte::from_bytes(1_000_000);
 const CONTENT_LENGTH_LIMIT: Byte = byte_unit::Byte::from_bytes(10 * 1024 * 1024); // 10MiB
@@ -87,21 +90,59 @@ fn json_or_empty<T: DeserializeOwned + Send + Default>(
 pub(crate) fn elastic_index_search_filter(
 ) -> impl Filter<Extract = (String, SearchQueryParams, SearchBody), Error = Rejection> + Clone {
     warp::path!("_elastic" / String / "_search")
-        .and_then(|comma_separated_indexes: String| async move {
-            if comma_separated_indexes.contains(',') {
-                return Err(warp::reject::custom(crate::rest::InvalidArgument(format!(
-                    "Searching only one index is supported for now. Got \
-                     (`{comma_separated_indexes}`)"
-                ))));
-            }
-            let index = comma_separated_indexes.trim();
-            if index.is_empty() {
-                return Err(warp::reject::custom(crate::rest::InvalidArgument(
-                    "Missing index name.".to_string(),
-                )));
-            }
-            Ok(index.to_string())
-        })
+        .and_then(extract_single_index)
+        .and(warp::get().or(warp::post()).unify())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(json_or_empty())
+}
+
+async fn extract_single_index(comma_separated_indexes: String) -> Result<String, Rejection> {
+    if comma_separated_indexes.contains(',') {
+        return Err(warp::reject::custom(crate::rest::InvalidArgument(format!(
+            "Searching only one index is supported for now. Got (`{comma_separated_indexes}`)"
+        ))));
+    }
+    let index = comma_separated_indexes.trim();
+    if index.is_empty() {
+        return Err(warp::reject::custom(crate::rest::InvalidArgument(
+            "Missing index name.".to_string(),
+        )));
+    }
+    Ok(index.to_string())
+}
+
+#[utoipa::path(post, tag = "Search", path = "/{index}/_search/template")]
+pub(crate) fn elastic_index_search_template_filter(
+) -> impl Filter<Extract = (String, SearchQueryParams, SearchTemplateRequest), Error = Rejection> + Clone
+{
+    warp::path!("_elastic" / String / "_search" / "template")
+        .and_then(extract_single_index)
+        .and(warp::get().or(warp::post()).unify())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(warp::body::content_length_limit(
+            BODY_LENGTH_LIMIT.get_bytes(),
+        ))
+        .and(warp::body::json())
+}
+
+#[utoipa::path(post, tag = "Search", path = "/{index}/_render/template")]
+pub(crate) fn elastic_index_render_search_template_filter(
+) -> impl Filter<Extract = (String, SearchTemplateRequest), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_render" / "template")
+        .and_then(extract_single_index)
+        .and(warp::get().or(warp::post()).unify())
+        .and(warp::body::content_length_limit(
+            BODY_LENGTH_LIMIT.get_bytes(),
+        ))
+        .and(warp::body::json())
+}
+
+#[utoipa::path(get, tag = "Search", path = "/{index}/_validate/query")]
+pub(crate) fn elastic_index_validate_query_filter(
+) -> impl Filter<Extract = (String, ValidateQueryParams, ValidateQueryBody), Error = Rejection> + Clone
+{
+    warp::path!("_elastic" / String / "_validate" / "query")
+        .and_then(extract_single_index)
         .and(warp::get().or(warp::post()).unify())
         .and(serde_qs::warp::query(serde_qs::Config::default()))
         .and(json_or_empty())
@@ -132,6 +173,24 @@ pub(crate) fn elastic_index_bulk_filter(
         ))
 }
 
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/{index}/_doc/{id}",
+    responses(
+        (status = 200, description = "Successfully retrieved the document."),
+        (status = 404, description = "No document has this ID."),
+    ),
+    params(
+        ("index" = String, Path, description = "The index ID."),
+        ("id" = String, Path, description = "The document ID, held by the doc ID field of the index."),
+    )
+)]
+pub(crate) fn elastic_get_doc_filter(
+) -> impl Filter<Extract = (String, String), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_doc" / String).and(warp::get())
+}
+
 #[utoipa::path(post, tag = "Search", path = "/_msearch")]
 pub(crate) fn elastic_multi_search_filter(
 ) -> impl Filter<Extract = (Bytes, MultiSearchQueryParams), Error = Rejection> + Clone {
@@ -26,9 +26,13 @@ use std::sync::Arc;
 
 use bulk::{es_compat_bulk_handler, es_compat_index_bulk_handler};
 use quickwit_ingest::IngestServiceClient;
-use quickwit_search::SearchService;
+use quickwit_metastore::Metastore;
+use quickwit_search::{RequestTemplates, SearchService};
 use rest_handler::{
-    es_compat_index_multi_search_handler, es_compat_index_search_handler, es_compat_search_handler,
+    es_compat_get_doc_handler, es_compat_index_multi_search_handler,
+    es_compat_index_render_search_template_handler, es_compat_index_search_handler,
+    es_compat_index_search_template_handler, es_compat_index_validate_query_handler,
+    es_compat_search_handler,
 };
 use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
@@ -40,10 +44,32 @@ use warp::{Filter, Rejection};
 pub fn elastic_api_handlers(
     search_service: Arc<dyn SearchService>,
     ingest_service: IngestServiceClient,
+    metastore: Arc<dyn Metastore>,
+    request_templates: RequestTemplates,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     es_compat_search_handler(search_service.clone())
-        .or(es_compat_index_search_handler(search_service.clone()))
-        .or(es_compat_index_multi_search_handler(search_service))
+        .or(es_compat_index_search_handler(
+            search_service.clone(),
+            request_templates.clone(),
+        ))
+        .or(es_compat_index_multi_search_handler(
+            search_service.clone(),
+            request_templates.clone(),
+        ))
+        .or(es_compat_get_doc_handler(
+            search_service.clone(),
+            metastore.clone(),
+            request_templates.clone(),
+        ))
+        .or(es_compat_index_search_template_handler(
+            search_service,
+            metastore.clone(),
+            request_templates,
+        ))
+        .or(es_compat_index_render_search_template_handler(
+            metastore.clone(),
+        ))
+        .or(es_compat_index_validate_query_handler(metastore))
         .or(es_compat_bulk_handler(ingest_service.clone()))
         .or(es_compat_index_bulk_handler(ingest_service))
     // Register newly created handlers here.
@@ -83,8 +109,12 @@ mod tests {
     use std::sync::Arc;
 
     use mockall::predicate;
+    use quickwit_config::SearchTemplate;
+    use quickwit_doc_mapper::{DocIdConfig, DocIdGeneration};
     use quickwit_ingest::{IngestApiService, IngestServiceClient};
-    use quickwit_search::MockSearchService;
+    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_search::{MockSearchService, RequestTemplates};
+    use serde_json::{json, Value as JsonValue};
 
     use super::model::ElasticSearchError;
     use crate::elastic_search_api::model::MultiSearchResponse;
@@ -111,8 +141,12 @@ mod tests {
                 },
             ))
             .returning(|_| Ok(Default::default()));
-        let es_search_api_handler =
-            super::elastic_api_handlers(Arc::new(mock_search_service), ingest_service_client());
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let msearch_payload = r#"
             {"index":"index-1"}
             {"query":{"query_string":{"query":"test"}}, "from": 5, "size": 20}
@@ -150,8 +184,12 @@ mod tests {
                     ))
                 }
             });
-        let es_search_api_handler =
-            super::elastic_api_handlers(Arc::new(mock_search_service), ingest_service_client());
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let msearch_payload = r#"
             {"index":"index-1"}
             {"query":{"query_string":{"query":"test"}}, "from": 5, "size": 10}
@@ -181,8 +219,12 @@ mod tests {
     #[tokio::test]
     async fn test_msearch_api_return_400_with_malformed_request_header() {
         let mock_search_service = MockSearchService::new();
-        let es_search_api_handler =
-            super::elastic_api_handlers(Arc::new(mock_search_service), ingest_service_client());
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let msearch_payload = r#"
             {"index":"index-1"
             {"query":{"query_string":{"query":"test"}}}
@@ -205,8 +247,12 @@ mod tests {
     #[tokio::test]
     async fn test_msearch_api_return_400_with_malformed_request_body() {
         let mock_search_service = MockSearchService::new();
-        let es_search_api_handler =
-            super::elastic_api_handlers(Arc::new(mock_search_service), ingest_service_client());
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let msearch_payload = r#"
             {"index":"index-1"}
             {"query":{"query_string":{"bad":"test"}}}
@@ -229,8 +275,12 @@ mod tests {
     #[tokio::test]
     async fn test_msearch_api_return_400_with_only_a_header_request() {
         let mock_search_service = MockSearchService::new();
-        let es_search_api_handler =
-            super::elastic_api_handlers(Arc::new(mock_search_service), ingest_service_client());
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let msearch_payload = r#"
             {"index":"index-1"}
             "#;
@@ -252,8 +302,12 @@ mod tests {
     #[tokio::test]
     async fn test_msearch_api_return_400_with_no_index() {
         let mock_search_service = MockSearchService::new();
-        let es_search_api_handler =
-            super::elastic_api_handlers(Arc::new(mock_search_service), ingest_service_client());
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let msearch_payload = r#"
             {}
             {"query":{"query_string":{"bad":"test"}}}
@@ -274,8 +328,12 @@ mod tests {
     #[tokio::test]
     async fn test_msearch_api_return_400_with_multiple_indexes() {
         let mock_search_service = MockSearchService::new();
-        let es_search_api_handler =
-            super::elastic_api_handlers(Arc::new(mock_search_service), ingest_service_client());
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
         let msearch_payload = r#"
             {"index": ["index-1", "index-2"]}
             {"query":{"query_string":{"bad":"test"}}}
@@ -294,4 +352,284 @@ mod tests {
             .unwrap()
             .starts_with("Invalid argument: Searching only one index is supported for now."));
     }
+
+    #[tokio::test]
+    async fn test_get_doc_api() {
+        let mut mock_metastore = MockMetastore::new();
+        mock_metastore
+            .expect_index_metadata()
+            .returning(|index_id: &str| {
+                let mut index_metadata =
+                    IndexMetadata::for_test(index_id, &format!("ram:///indexes/{index_id}"));
+                if index_id == "index-1" {
+                    index_metadata.index_config.doc_mapping.doc_id = Some(DocIdConfig {
+                        field: "id".to_string(),
+                        generation: DocIdGeneration::Ulid,
+                    });
+                }
+                Ok(index_metadata)
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|search_request| {
+                assert_eq!(search_request.max_hits, 1);
+                let hits = if search_request.query_ast.contains(r#""value":"doc-1""#) {
+                    vec![quickwit_proto::Hit {
+                        json: r#"{"id": "doc-1", "body": "hello"}"#.to_string(),
+                        ..Default::default()
+                    }]
+                } else {
+                    Vec::new()
+                };
+                Ok(quickwit_proto::SearchResponse {
+                    num_hits: hits.len() as u64,
+                    hits,
+                    ..Default::default()
+                })
+            });
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(mock_metastore),
+            RequestTemplates::default(),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_doc/doc-1")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let get_doc_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            get_doc_response,
+            json!({
+                "_index": "index-1",
+                "_id": "doc-1",
+                "found": true,
+                "_source": {"id": "doc-1", "body": "hello"}
+            })
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_doc/doc-2")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+        let get_doc_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            get_doc_response,
+            json!({"_index": "index-1", "_id": "doc-2", "found": false})
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-2/_doc/doc-1")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let es_error: ElasticSearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            es_error.error.reason.unwrap(),
+            "Invalid argument: Index `index-2` does not define a doc ID field."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_api_without_tracking_total_hits() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|search_request| {
+                let num_hits = if search_request.track_total_hits == Some(false) {
+                    3
+                } else {
+                    1_000
+                };
+                Ok(quickwit_proto::SearchResponse {
+                    num_hits,
+                    ..Default::default()
+                })
+            });
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(MockMetastore::new()),
+            RequestTemplates::default(),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search")
+            .method("POST")
+            .json(&json!({"query": {"match": {"body": "hello"}}, "track_total_hits": false}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let search_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(search_response["hits"]["total"].is_null());
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search")
+            .method("POST")
+            .json(&json!({"query": {"match": {"body": "hello"}}, "track_total_hits": 10_000}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let search_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            search_response["hits"]["total"],
+            json!({"value": 1_000, "relation": "eq"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_template_api() {
+        let mut mock_metastore = MockMetastore::new();
+        mock_metastore
+            .expect_index_metadata()
+            .returning(|index_id: &str| {
+                let mut index_metadata =
+                    IndexMetadata::for_test(index_id, &format!("ram:///indexes/{index_id}"));
+                index_metadata.index_config.search_templates.insert(
+                    "by-body".to_string(),
+                    SearchTemplate {
+                        source: r#"{"query": {"match": {"body": "{{query}}"}}, "size": {{size}}}"#
+                            .to_string(),
+                    },
+                );
+                Ok(index_metadata)
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.index_id == "index-1"
+                        && search_request.max_hits == 3
+                        && search_request.query_ast.contains("hello")
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            Arc::new(mock_metastore),
+            RequestTemplates::default(),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_render/template")
+            .method("POST")
+            .json(&json!({"id": "by-body", "params": {"query": "hello", "size": 3}}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let render_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            render_response["template_output"],
+            json!({"query": {"match": {"body": "hello"}}, "size": 3})
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search/template")
+            .method("POST")
+            .json(&json!({"id": "by-body", "params": {"query": "hello", "size": 3}}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_render/template")
+            .method("POST")
+            .json(&json!({"source": {"size": "{{size}}"}, "params": {"size": 5}}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let render_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(render_response["template_output"], json!({"size": "5"}));
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_render/template")
+            .method("POST")
+            .json(&json!({"id": "unknown"}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let es_error: ElasticSearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            es_error.error.reason.unwrap(),
+            "Invalid argument: Search template `unknown` does not exist for index `index-1`."
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_render/template")
+            .method("POST")
+            .json(&json!({"id": "by-body", "source": "{}"}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_validate_query_api() {
+        let mut mock_metastore = MockMetastore::new();
+        mock_metastore
+            .expect_index_metadata()
+            .returning(|index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    index_id,
+                    &format!("ram:///indexes/{index_id}"),
+                ))
+            });
+        let es_search_api_handler = super::elastic_api_handlers(
+            Arc::new(MockSearchService::new()),
+            ingest_service_client(),
+            Arc::new(mock_metastore),
+            RequestTemplates::default(),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_validate/query?explain=true")
+            .method("POST")
+            .json(&json!({"query": {"term": {"owner": {"value": "alice"}}}}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let validate_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            validate_response,
+            json!({
+                "valid": true,
+                "explanations": [{"index": "index-1", "valid": true, "explanation": "owner:alice"}]
+            })
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_validate/query?q=unknown:alice")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let validate_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            validate_response,
+            json!({
+                "valid": false,
+                "diagnostics": [{
+                    "kind": "unknown_field",
+                    "field": "unknown",
+                    "clause": "unknown:alice",
+                    "message": "Field does not exist: `unknown`"
+                }]
+            })
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_validate/query")
+            .method("POST")
+            .json(&json!({"query": {"unknown_query": {}}}))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let validate_response: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(validate_response["valid"], json!(false));
+        assert_eq!(
+            validate_response["diagnostics"][0]["kind"],
+            json!("parse_error")
+        );
+    }
 }
@@ -20,15 +20,23 @@
 mod bulk_body;
 mod bulk_query_params;
 mod error;
+mod get_doc;
 mod multi_search;
 mod search_body;
 mod search_query_params;
+mod search_template;
+mod validate_query;
 
 pub use bulk_body::{BulkAction, BulkActionMeta};
 pub use bulk_query_params::{ElasticIngestOptions, ElasticRefresh};
 pub use error::ElasticSearchError;
+pub use get_doc::GetDocResponse;
 pub use multi_search::{
     MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
 };
 pub use search_body::SearchBody;
 pub use search_query_params::SearchQueryParams;
+pub use search_template::{RenderSearchTemplateResponse, SearchTemplateRequest};
+pub use validate_query::{
+    QueryExplanation, ValidateQueryBody, ValidateQueryParams, ValidateQueryResponse,
+};
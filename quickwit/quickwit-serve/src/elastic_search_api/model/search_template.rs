@@ -0,0 +1,41 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+// Search template API: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-template-api.html
+
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SearchTemplateRequest {
+    /// ID of a search template stored in the index config.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Inline search template, as a string or as a JSON object.
+    #[serde(default)]
+    pub source: Option<JsonValue>,
+    #[serde(default)]
+    pub params: JsonMap<String, JsonValue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderSearchTemplateResponse {
+    pub template_output: JsonValue,
+}
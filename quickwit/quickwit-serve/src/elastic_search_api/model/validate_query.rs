@@ -0,0 +1,64 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_query::BooleanOperand;
+use quickwit_search::QueryDiagnostic;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+// Validate query API: https://www.elastic.co/guide/en/elasticsearch/reference/current/search-validate.html
+
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+pub struct ValidateQueryParams {
+    #[serde(default)]
+    pub q: Option<String>,
+    #[serde(default)]
+    pub default_operator: Option<BooleanOperand>,
+    /// Returns the query as it would be executed.
+    #[serde(default)]
+    pub explain: Option<bool>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+pub struct ValidateQueryBody {
+    /// The query is kept as JSON so that the queries that cannot be deserialized are reported
+    /// as diagnostics rather than rejected.
+    #[serde(default)]
+    pub query: Option<JsonValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidateQueryResponse {
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub explanations: Vec<QueryExplanation>,
+    /// Problems found in the query. This field is specific to Quickwit.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<QueryDiagnostic>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryExplanation {
+    pub index: String,
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
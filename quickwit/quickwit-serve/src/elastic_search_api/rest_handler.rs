@@ -28,21 +28,33 @@ use futures_util::StreamExt;
 use hyper::StatusCode;
 use itertools::Itertools;
 use quickwit_common::truncate_str;
+use quickwit_config::SearchTemplate;
+use quickwit_metastore::Metastore;
 use quickwit_proto::{SearchResponse, ServiceErrorCode, SortOrder};
-use quickwit_query::query_ast::{QueryAst, UserInputQuery};
-use quickwit_query::BooleanOperand;
-use quickwit_search::{SearchError, SearchService};
+use quickwit_query::query_ast::{query_ast_to_json, QueryAst, TermQuery, UserInputQuery};
+use quickwit_query::{BooleanOperand, ElasticQueryDsl};
+use quickwit_search::{
+    validate_query, QueryDiagnostic, QueryDiagnosticKind, QueryValidation, RequestTemplates,
+    SearchError, SearchService,
+};
 use warp::{Filter, Rejection};
 
-use super::filter::elastic_multi_search_filter;
+use super::filter::{
+    elastic_get_doc_filter, elastic_index_render_search_template_filter,
+    elastic_index_search_template_filter, elastic_index_validate_query_filter,
+    elastic_multi_search_filter,
+};
 use super::model::{
-    ElasticSearchError, MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse,
-    MultiSearchSingleResponse, SearchBody, SearchQueryParams,
+    ElasticSearchError, GetDocResponse, MultiSearchHeader, MultiSearchQueryParams,
+    MultiSearchResponse, MultiSearchSingleResponse, QueryExplanation, RenderSearchTemplateResponse,
+    SearchBody, SearchQueryParams, SearchTemplateRequest, ValidateQueryBody, ValidateQueryParams,
+    ValidateQueryResponse,
 };
+use super::TrackTotalHits;
 use crate::elastic_search_api::filter::elastic_index_search_filter;
 use crate::format::BodyFormat;
 use crate::json_api_response::{make_json_api_response, ApiError, JsonApiResponse};
-use crate::with_arg;
+use crate::{with_arg, with_search_service};
 
 /// GET or POST _elastic/_search
 pub fn es_compat_search_handler(
@@ -63,9 +75,10 @@ pub fn es_compat_search_handler(
 /// GET or POST _elastic/{index}/_search
 pub fn es_compat_index_search_handler(
     search_service: Arc<dyn SearchService>,
+    request_templates: RequestTemplates,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     elastic_index_search_filter()
-        .and(with_arg(search_service))
+        .and(with_search_service(search_service, request_templates))
         .then(es_compat_index_search)
         .map(make_elastic_api_response)
 }
@@ -73,9 +86,10 @@ pub fn es_compat_index_search_handler(
 /// POST _elastic/_msearch
 pub fn es_compat_index_multi_search_handler(
     search_service: Arc<dyn SearchService>,
+    request_templates: RequestTemplates,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     elastic_multi_search_filter()
-        .and(with_arg(search_service))
+        .and(with_search_service(search_service, request_templates))
         .then(es_compat_index_multi_search)
         .map(|result: Result<MultiSearchResponse, ElasticSearchError>| {
             let status_code = match &result {
@@ -86,6 +100,75 @@ pub fn es_compat_index_multi_search_handler(
         })
 }
 
+/// GET _elastic/{index}/_doc/{id}
+pub fn es_compat_get_doc_handler(
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
+    request_templates: RequestTemplates,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_get_doc_filter()
+        .and(with_search_service(search_service, request_templates))
+        .and(with_arg(metastore))
+        .then(es_compat_get_doc)
+        .map(|result: Result<GetDocResponse, ElasticSearchError>| {
+            let status_code = match &result {
+                Ok(get_doc_response) if get_doc_response.found => StatusCode::OK,
+                Ok(_) => StatusCode::NOT_FOUND,
+                Err(err) => err.status,
+            };
+            JsonApiResponse::new(&result, status_code, &BodyFormat::default())
+        })
+}
+
+/// GET or POST _elastic/{index}/_search/template
+pub fn es_compat_index_search_template_handler(
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
+    request_templates: RequestTemplates,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_search_template_filter()
+        .and(with_search_service(search_service, request_templates))
+        .and(with_arg(metastore))
+        .then(es_compat_index_search_template)
+        .map(make_elastic_api_response)
+}
+
+/// GET or POST _elastic/{index}/_render/template
+pub fn es_compat_index_render_search_template_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_render_search_template_filter()
+        .and(with_arg(metastore))
+        .then(es_compat_index_render_search_template)
+        .map(
+            |result: Result<RenderSearchTemplateResponse, ElasticSearchError>| {
+                let status_code = match &result {
+                    Ok(_) => StatusCode::OK,
+                    Err(err) => err.status,
+                };
+                JsonApiResponse::new(&result, status_code, &BodyFormat::default())
+            },
+        )
+}
+
+/// GET or POST _elastic/{index}/_validate/query
+pub fn es_compat_index_validate_query_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_validate_query_filter()
+        .and(with_arg(metastore))
+        .then(es_compat_index_validate_query)
+        .map(
+            |result: Result<ValidateQueryResponse, ElasticSearchError>| {
+                let status_code = match &result {
+                    Ok(_) => StatusCode::OK,
+                    Err(err) => err.status,
+                };
+                JsonApiResponse::new(&result, status_code, &BodyFormat::default())
+            },
+        )
+}
+
 fn build_request_for_es_api(
     index_id: String,
     search_params: SearchQueryParams,
@@ -99,6 +182,10 @@ fn build_request_for_es_api(
             user_text: q.to_string(),
             default_fields: None,
             default_operator,
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
         };
         user_text_query.into()
     } else if let Some(query_dsl) = search_body.query {
@@ -142,6 +229,16 @@ fn build_request_for_es_api(
             (None, None)
         };
 
+    // Only an explicit `false` lets the hit count be a lower bound: an integer value asks
+    // for an accurate count up to that value, which we always honor.
+    let track_total_hits_opt = search_params
+        .track_total_hits
+        .or(search_body.track_total_hits);
+    let track_total_hits = match track_total_hits_opt {
+        Some(TrackTotalHits::Track(false)) => Some(false),
+        _ => None,
+    };
+
     Ok(quickwit_proto::SearchRequest {
         index_id,
         query_ast: serde_json::to_string(&query_ast).expect("Failed to serialize QueryAst"),
@@ -150,6 +247,7 @@ fn build_request_for_es_api(
         aggregation_request,
         sort_by_field,
         sort_order: sort_order.map(|order| order as i32),
+        track_total_hits,
         ..Default::default()
     })
 }
@@ -162,21 +260,202 @@ async fn es_compat_index_search(
 ) -> Result<ElasticSearchResponse, ElasticSearchError> {
     let start_instant = Instant::now();
     let search_request = build_request_for_es_api(index_id, search_params, search_body)?;
+    let index_id = search_request.index_id.clone();
+    let track_total_hits = search_request.track_total_hits.unwrap_or(true);
     let search_response: SearchResponse = search_service.root_search(search_request).await?;
     let elapsed = start_instant.elapsed();
     let mut search_response_rest: ElasticSearchResponse =
-        convert_to_es_search_response(search_response);
+        convert_to_es_search_response(search_response, &index_id, track_total_hits);
     search_response_rest.took = elapsed.as_millis() as u32;
     Ok(search_response_rest)
 }
 
-fn convert_hit(hit: quickwit_proto::Hit) -> ElasticHit {
+/// Renders the search template stored in the index config under `id`, or the inline template
+/// passed in `source`, into a search request body.
+async fn render_search_template(
+    index_id: &str,
+    search_template_request: SearchTemplateRequest,
+    metastore: &dyn Metastore,
+) -> Result<serde_json::Value, ElasticSearchError> {
+    let search_template = match (search_template_request.id, search_template_request.source) {
+        (Some(template_id), None) => {
+            let index_metadata = metastore
+                .index_metadata(index_id)
+                .await
+                .map_err(SearchError::from)?;
+            let mut search_templates = index_metadata.index_config.search_templates;
+            search_templates.remove(&template_id).ok_or_else(|| {
+                SearchError::InvalidArgument(format!(
+                    "Search template `{template_id}` does not exist for index `{index_id}`."
+                ))
+            })?
+        }
+        (None, Some(serde_json::Value::String(source))) => SearchTemplate { source },
+        (None, Some(source)) => SearchTemplate {
+            source: source.to_string(),
+        },
+        _ => {
+            return Err(ElasticSearchError::from(SearchError::InvalidArgument(
+                "Exactly one of `id` or `source` must be set.".to_string(),
+            )));
+        }
+    };
+    search_template
+        .render_json(&search_template_request.params)
+        .map_err(|error| ElasticSearchError::from(SearchError::InvalidArgument(error.to_string())))
+}
+
+async fn es_compat_index_search_template(
+    index_id: String,
+    search_params: SearchQueryParams,
+    search_template_request: SearchTemplateRequest,
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
+) -> Result<ElasticSearchResponse, ElasticSearchError> {
+    let search_body_json =
+        render_search_template(&index_id, search_template_request, &*metastore).await?;
+    let search_body: SearchBody = serde_json::from_value(search_body_json).map_err(|error| {
+        SearchError::InvalidArgument(format!(
+            "The rendered search template is not a valid search request body: {error}."
+        ))
+    })?;
+    es_compat_index_search(index_id, search_params, search_body, search_service).await
+}
+
+async fn es_compat_index_render_search_template(
+    index_id: String,
+    search_template_request: SearchTemplateRequest,
+    metastore: Arc<dyn Metastore>,
+) -> Result<RenderSearchTemplateResponse, ElasticSearchError> {
+    let template_output =
+        render_search_template(&index_id, search_template_request, &*metastore).await?;
+    Ok(RenderSearchTemplateResponse { template_output })
+}
+
+/// Builds the query to validate. The queries that cannot be parsed or converted are reported as
+/// diagnostics.
+fn build_query_ast_for_validation(
+    validate_query_params: ValidateQueryParams,
+    validate_query_body: ValidateQueryBody,
+) -> Result<QueryAst, QueryDiagnostic> {
+    // Like for searches, the query string takes priority over the request body.
+    if let Some(q) = validate_query_params.q {
+        let user_text_query = UserInputQuery {
+            user_text: q,
+            default_fields: None,
+            default_operator: validate_query_params
+                .default_operator
+                .unwrap_or(BooleanOperand::Or),
+            quote_field_suffix: None,
+            phrase_slop: 0,
+            lenient: false,
+            fuzziness: None,
+        };
+        return Ok(user_text_query.into());
+    }
+    let Some(query_json) = validate_query_body.query else {
+        return Ok(QueryAst::MatchAll);
+    };
+    let query_dsl: ElasticQueryDsl = serde_json::from_value(query_json)
+        .map_err(|error| QueryDiagnostic::new(QueryDiagnosticKind::ParseError, error))?;
+    query_dsl.try_into().map_err(|error: anyhow::Error| {
+        QueryDiagnostic::new(QueryDiagnosticKind::UnsupportedConstruct, error)
+    })
+}
+
+async fn es_compat_index_validate_query(
+    index_id: String,
+    validate_query_params: ValidateQueryParams,
+    validate_query_body: ValidateQueryBody,
+    metastore: Arc<dyn Metastore>,
+) -> Result<ValidateQueryResponse, ElasticSearchError> {
+    let explain = validate_query_params.explain.unwrap_or(false);
+    let query_validation =
+        match build_query_ast_for_validation(validate_query_params, validate_query_body) {
+            Ok(query_ast) => validate_query(&index_id, query_ast, &*metastore).await?,
+            Err(diagnostic) => QueryValidation {
+                diagnostics: vec![diagnostic],
+                explanation: None,
+            },
+        };
+    let valid = query_validation.is_valid();
+    let explanations = if explain {
+        let error = if valid {
+            None
+        } else {
+            Some(
+                query_validation
+                    .diagnostics
+                    .iter()
+                    .map(|diagnostic| diagnostic.message.as_str())
+                    .join(" "),
+            )
+        };
+        vec![QueryExplanation {
+            index: index_id,
+            valid,
+            explanation: query_validation.explanation,
+            error,
+        }]
+    } else {
+        Vec::new()
+    };
+    Ok(ValidateQueryResponse {
+        valid,
+        explanations,
+        diagnostics: query_validation.diagnostics,
+    })
+}
+
+/// Retrieves a document with a term query on the doc ID field of the index. The root prunes the
+/// splits that cannot hold the document thanks to their doc ID bloom filters.
+async fn es_compat_get_doc(
+    index_id: String,
+    doc_id: String,
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
+) -> Result<GetDocResponse, ElasticSearchError> {
+    let index_metadata = metastore
+        .index_metadata(&index_id)
+        .await
+        .map_err(SearchError::from)?;
+    let Some(doc_id_config) = index_metadata.index_config.doc_mapping.doc_id else {
+        return Err(ElasticSearchError::from(SearchError::InvalidArgument(
+            format!("Index `{index_id}` does not define a doc ID field."),
+        )));
+    };
+    let query_ast: QueryAst = TermQuery {
+        field: doc_id_config.field,
+        value: doc_id.clone(),
+    }
+    .into();
+    let search_request = quickwit_proto::SearchRequest {
+        index_id: index_id.clone(),
+        query_ast: query_ast_to_json(&query_ast).expect("Failed to serialize QueryAst"),
+        max_hits: 1,
+        ..Default::default()
+    };
+    let search_response: SearchResponse = search_service.root_search(search_request).await?;
+    let source: Option<serde_json::Value> = search_response
+        .hits
+        .into_iter()
+        .next()
+        .and_then(|hit| serde_json::from_str(&hit.json).ok());
+    Ok(GetDocResponse {
+        index: index_id,
+        id: doc_id,
+        found: source.is_some(),
+        source,
+    })
+}
+
+fn convert_hit(hit: quickwit_proto::Hit, index_id: &str) -> ElasticHit {
     let fields: elasticsearch_dsl::Map<String, serde_json::Value> =
         serde_json::from_str(&hit.json).unwrap_or_default();
     ElasticHit {
         fields,
         explanation: None,
-        index: "".to_string(),
+        index: index_id.to_string(),
         id: "".to_string(),
         score: None,
         nested: None,
@@ -241,11 +520,13 @@ async fn es_compat_index_multi_search(
     }
     let futures = search_requests.into_iter().map(|search_request| async {
         let start_instant = Instant::now();
+        let index_id = search_request.index_id.clone();
+        let track_total_hits = search_request.track_total_hits.unwrap_or(true);
         let search_response: SearchResponse =
             search_service.clone().root_search(search_request).await?;
         let elapsed = start_instant.elapsed();
         let mut search_response_rest: ElasticSearchResponse =
-            convert_to_es_search_response(search_response);
+            convert_to_es_search_response(search_response, &index_id, track_total_hits);
         search_response_rest.took = elapsed.as_millis() as u32;
         Ok::<_, ElasticSearchError>(search_response_rest)
     });
@@ -266,20 +547,35 @@ async fn es_compat_index_multi_search(
     Ok(multi_search_response)
 }
 
-fn convert_to_es_search_response(resp: SearchResponse) -> ElasticSearchResponse {
-    let hits: Vec<ElasticHit> = resp.hits.into_iter().map(convert_hit).collect();
+fn convert_to_es_search_response(
+    resp: SearchResponse,
+    index_id: &str,
+    track_total_hits: bool,
+) -> ElasticSearchResponse {
+    let hits: Vec<ElasticHit> = resp
+        .hits
+        .into_iter()
+        .map(|hit| convert_hit(hit, index_id))
+        .collect();
     let aggregations: Option<serde_json::Value> = if let Some(aggregation_json) = resp.aggregation {
         serde_json::from_str(&aggregation_json).ok()
     } else {
         None
     };
+    // Like Elasticsearch, we omit the total number of hits when it is not tracked, as it is
+    // then only a lower bound.
+    let total = if track_total_hits {
+        Some(TotalHits {
+            value: resp.num_hits,
+            relation: TotalHitsRelation::Equal,
+        })
+    } else {
+        None
+    };
     ElasticSearchResponse {
         timed_out: false,
         hits: HitsMetadata {
-            total: Some(TotalHits {
-                value: resp.num_hits,
-                relation: TotalHitsRelation::Equal,
-            }),
+            total,
             max_score: None,
             hits,
         },
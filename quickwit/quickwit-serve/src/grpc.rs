@@ -21,13 +21,16 @@ use std::collections::BTreeSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use quickwit_cluster::Cluster;
 use quickwit_common::tower::BoxFutureInfaillible;
 use quickwit_config::service::QuickwitService;
 use quickwit_control_plane::control_plane_service_grpc_server::ControlPlaneServiceGrpcServer;
-use quickwit_control_plane::ControlPlaneServiceGrpcServerAdapter;
+use quickwit_control_plane::{
+    ControlPlaneServiceGrpcServerAdapter, CONTROL_PLANE_FILE_DESCRIPTOR_SET,
+};
 use quickwit_indexing::grpc_adapter::GrpcIndexingAdapter;
 use quickwit_ingest::ingest_service_grpc_server::IngestServiceGrpcServer;
-use quickwit_ingest::IngestServiceGrpcServerAdapter;
+use quickwit_ingest::{IngestServiceGrpcServerAdapter, INGEST_FILE_DESCRIPTOR_SET};
 use quickwit_jaeger::JaegerService;
 use quickwit_metastore::GrpcMetastoreAdapter;
 use quickwit_opentelemetry::otlp::{OtlpGrpcLogsService, OtlpGrpcTracesService};
@@ -37,13 +40,22 @@ use quickwit_proto::metastore_api::metastore_api_service_server::MetastoreApiSer
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::logs_service_server::LogsServiceServer;
 use quickwit_proto::opentelemetry::proto::collector::trace::v1::trace_service_server::TraceServiceServer;
 use quickwit_proto::search_service_server::SearchServiceServer;
-use quickwit_proto::tonic;
 use quickwit_proto::tonic::codegen::CompressionEncoding;
+use quickwit_proto::{
+    tonic, JAEGER_FILE_DESCRIPTOR_SET, OTLP_FILE_DESCRIPTOR_SET, QUICKWIT_FILE_DESCRIPTOR_SET,
+};
+use tonic::server::NamedService;
 use tonic::transport::Server;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+use tonic_reflection::server::{ServerReflection, ServerReflectionServer};
 use tracing::*;
 
 use crate::search_api::GrpcSearchAdapter;
-use crate::QuickwitServices;
+use crate::{QuickwitServices, READINESS_REPORTING_INTERVAL};
+
+/// Name under which the health of the node as a whole is reported by the gRPC health service.
+const NODE_HEALTH_SERVICE_NAME: &str = "";
 
 /// Starts gRPC services given a gRPC address.
 pub(crate) async fn start_grpc_server(
@@ -53,14 +65,26 @@ pub(crate) async fn start_grpc_server(
     shutdown_signal: BoxFutureInfaillible<()>,
 ) -> anyhow::Result<()> {
     let mut enabled_grpc_services = BTreeSet::new();
+    // File descriptor sets of the enabled services, served by the reflection service.
+    let mut file_descriptor_sets: BTreeSet<&'static [u8]> = BTreeSet::new();
     let mut server = Server::builder();
 
+    // The health of each enabled service is reported by the standard gRPC health service. The
+    // health of the node as a whole follows its readiness.
+    let (mut health_reporter, health_grpc_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_service_status(NODE_HEALTH_SERVICE_NAME, ServingStatus::NotServing)
+        .await;
+
     // Mount gRPC metastore service if `QuickwitService::Metastore` is enabled on node.
     let metastore_grpc_service = if services.services.contains(&QuickwitService::Metastore) {
         enabled_grpc_services.insert("metastore");
+        file_descriptor_sets.insert(QUICKWIT_FILE_DESCRIPTOR_SET);
         let metastore = services.metastore.clone();
         let grpc_metastore = GrpcMetastoreAdapter::from(metastore);
-        Some(MetastoreApiServiceServer::new(grpc_metastore))
+        let metastore_grpc_service = MetastoreApiServiceServer::new(grpc_metastore);
+        set_serving(&mut health_reporter, &metastore_grpc_service).await;
+        Some(metastore_grpc_service)
     } else {
         None
     };
@@ -68,8 +92,11 @@ pub(crate) async fn start_grpc_server(
     let indexing_grpc_service = if services.services.contains(&QuickwitService::Indexer) {
         if let Some(indexing_service) = services.indexing_service.as_ref() {
             enabled_grpc_services.insert("indexing");
+            file_descriptor_sets.insert(QUICKWIT_FILE_DESCRIPTOR_SET);
             let grpc_indexing = GrpcIndexingAdapter::from(indexing_service.clone());
-            Some(IndexingServiceServer::new(grpc_indexing))
+            let indexing_grpc_service = IndexingServiceServer::new(grpc_indexing);
+            set_serving(&mut health_reporter, &indexing_grpc_service).await;
+            Some(indexing_grpc_service)
         } else {
             None
         }
@@ -79,9 +106,12 @@ pub(crate) async fn start_grpc_server(
     // Mount gRPC ingest service if `QuickwitService::Indexer` is enabled on node.
     let ingest_api_grpc_service = if services.services.contains(&QuickwitService::Indexer) {
         enabled_grpc_services.insert("ingest_api");
+        file_descriptor_sets.insert(INGEST_FILE_DESCRIPTOR_SET);
         let ingest_service_adapter =
             IngestServiceGrpcServerAdapter::new(services.ingest_service.clone());
-        Some(IngestServiceGrpcServer::new(ingest_service_adapter))
+        let ingest_api_grpc_service = IngestServiceGrpcServer::new(ingest_service_adapter);
+        set_serving(&mut health_reporter, &ingest_api_grpc_service).await;
+        Some(ingest_api_grpc_service)
     } else {
         None
     };
@@ -89,8 +119,11 @@ pub(crate) async fn start_grpc_server(
     let control_plane_grpc_service = if services.services.contains(&QuickwitService::ControlPlane) {
         if let Some(control_plane_client) = &services.control_plane_service {
             enabled_grpc_services.insert("control-plane");
+            file_descriptor_sets.insert(CONTROL_PLANE_FILE_DESCRIPTOR_SET);
             let adapter = ControlPlaneServiceGrpcServerAdapter::new(control_plane_client.clone());
-            Some(ControlPlaneServiceGrpcServer::new(adapter))
+            let control_plane_grpc_service = ControlPlaneServiceGrpcServer::new(adapter);
+            set_serving(&mut health_reporter, &control_plane_grpc_service).await;
+            Some(control_plane_grpc_service)
         } else {
             None
         }
@@ -104,11 +137,13 @@ pub(crate) async fn start_grpc_server(
         && services.services.contains(&QuickwitService::Indexer)
     {
         enabled_grpc_services.insert("otlp-trace");
+        file_descriptor_sets.insert(OTLP_FILE_DESCRIPTOR_SET);
         let ingest_service = services.ingest_service.clone();
         let commit_type_opt = None;
         let trace_service =
             TraceServiceServer::new(OtlpGrpcTracesService::new(ingest_service, commit_type_opt))
                 .accept_compressed(CompressionEncoding::Gzip);
+        set_serving(&mut health_reporter, &trace_service).await;
         Some(trace_service)
     } else {
         None
@@ -117,9 +152,11 @@ pub(crate) async fn start_grpc_server(
         && services.services.contains(&QuickwitService::Indexer)
     {
         enabled_grpc_services.insert("otlp-logs");
+        file_descriptor_sets.insert(OTLP_FILE_DESCRIPTOR_SET);
         let ingest_service = services.ingest_service.clone();
         let logs_service = LogsServiceServer::new(OtlpGrpcLogsService::new(ingest_service))
             .accept_compressed(CompressionEncoding::Gzip);
+        set_serving(&mut health_reporter, &logs_service).await;
         Some(logs_service)
     } else {
         None
@@ -127,9 +164,12 @@ pub(crate) async fn start_grpc_server(
     // Mount gRPC search service if `QuickwitService::Searcher` is enabled on node.
     let search_grpc_service = if services.services.contains(&QuickwitService::Searcher) {
         enabled_grpc_services.insert("search");
+        file_descriptor_sets.insert(QUICKWIT_FILE_DESCRIPTOR_SET);
         let search_service = services.search_service.clone();
         let grpc_search_service = GrpcSearchAdapter::from(search_service);
-        Some(SearchServiceServer::new(grpc_search_service))
+        let search_grpc_service = SearchServiceServer::new(grpc_search_service);
+        set_serving(&mut health_reporter, &search_grpc_service).await;
+        Some(search_grpc_service)
     } else {
         None
     };
@@ -137,15 +177,26 @@ pub(crate) async fn start_grpc_server(
     let jaeger_grpc_service =
         if enable_jaeger_endpoint && services.services.contains(&QuickwitService::Searcher) {
             enabled_grpc_services.insert("jaeger");
+            file_descriptor_sets.insert(JAEGER_FILE_DESCRIPTOR_SET);
             let search_service = services.search_service.clone();
-            Some(SpanReaderPluginServer::new(JaegerService::new(
+            let jaeger_grpc_service = SpanReaderPluginServer::new(JaegerService::new(
                 services.config.jaeger_config.clone(),
                 search_service,
-            )))
+            ));
+            set_serving(&mut health_reporter, &jaeger_grpc_service).await;
+            Some(jaeger_grpc_service)
         } else {
             None
         };
+    let reflection_grpc_service = build_reflection_service(&file_descriptor_sets)?;
+    tokio::spawn(node_health_reporting_task(
+        services.cluster.clone(),
+        health_reporter,
+    ));
+
     let server_router = server
+        .add_service(health_grpc_service)
+        .add_service(reflection_grpc_service)
         .add_optional_service(metastore_grpc_service)
         .add_optional_service(control_plane_grpc_service)
         .add_optional_service(indexing_grpc_service)
@@ -165,3 +216,63 @@ pub(crate) async fn start_grpc_server(
     serve_res?;
     Ok(())
 }
+
+async fn set_serving<S: NamedService>(health_reporter: &mut HealthReporter, _service: &S) {
+    health_reporter
+        .set_service_status(S::NAME, ServingStatus::Serving)
+        .await;
+}
+
+/// Builds the gRPC reflection service, which lets tools such as `grpcurl` discover the enabled
+/// services without the proto files.
+fn build_reflection_service(
+    file_descriptor_sets: &BTreeSet<&'static [u8]>,
+) -> anyhow::Result<ServerReflectionServer<impl ServerReflection>> {
+    let reflection_service = file_descriptor_sets
+        .iter()
+        .copied()
+        .fold(
+            tonic_reflection::server::Builder::configure(),
+            |builder, file_descriptor_set| {
+                builder.register_encoded_file_descriptor_set(file_descriptor_set)
+            },
+        )
+        .build()?;
+    Ok(reflection_service)
+}
+
+/// Reports the health of the node to the gRPC health service: the node is serving as long as it
+/// is ready, and stops serving when it is draining.
+async fn node_health_reporting_task(cluster: Cluster, mut health_reporter: HealthReporter) {
+    let mut interval = tokio::time::interval(READINESS_REPORTING_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let serving_status = if cluster.is_self_node_ready().await {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+        health_reporter
+            .set_service_status(NODE_HEALTH_SERVICE_NAME, serving_status)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_reflection_service() {
+        let file_descriptor_sets = BTreeSet::from([
+            QUICKWIT_FILE_DESCRIPTOR_SET,
+            INGEST_FILE_DESCRIPTOR_SET,
+            CONTROL_PLANE_FILE_DESCRIPTOR_SET,
+            JAEGER_FILE_DESCRIPTOR_SET,
+            OTLP_FILE_DESCRIPTOR_SET,
+        ]);
+        build_reflection_service(&file_descriptor_sets).unwrap();
+    }
+}
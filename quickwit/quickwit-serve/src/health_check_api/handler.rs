@@ -17,28 +17,48 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use quickwit_actors::{Healthz, Mailbox};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+
+use quickwit_actors::{Healthz, Mailbox, Readyz};
 use quickwit_cluster::Cluster;
+use quickwit_config::service::QuickwitService;
 use quickwit_indexing::IndexingService;
 use quickwit_janitor::JanitorService;
-use tracing::error;
+use quickwit_search::SearchJobPlacer;
+use serde::Deserialize;
+use tracing::{error, warn};
 use warp::hyper::StatusCode;
 use warp::reply::with_status;
 use warp::{Filter, Rejection};
 
+use crate::simple_list::from_simple_list;
 use crate::with_arg;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(paths(get_liveness, get_readiness))]
 pub struct HealthCheckApi;
 
+/// The services running on the node and what their readiness depends on.
+#[derive(Clone)]
+pub(crate) struct NodeServices {
+    pub services: HashSet<QuickwitService>,
+    pub grpc_advertise_addr: SocketAddr,
+    pub search_job_placer: SearchJobPlacer,
+    pub indexing_service_opt: Option<Mailbox<IndexingService>>,
+    pub janitor_service_opt: Option<Mailbox<JanitorService>>,
+}
+
 /// Health check handlers.
 pub(crate) fn health_check_handlers(
     cluster: Cluster,
-    indexer_service_opt: Option<Mailbox<IndexingService>>,
-    janitor_service_opt: Option<Mailbox<JanitorService>>,
+    node_services: NodeServices,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    liveness_handler(indexer_service_opt, janitor_service_opt).or(readiness_handler(cluster))
+    liveness_handler(
+        node_services.indexing_service_opt.clone(),
+        node_services.janitor_service_opt.clone(),
+    )
+    .or(readiness_handler(cluster, node_services))
 }
 
 fn liveness_handler(
@@ -52,12 +72,27 @@ fn liveness_handler(
         .then(get_liveness)
 }
 
+/// This struct represents the QueryString passed to the readiness probe.
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReadinessQueryParams {
+    /// Comma-separated list of the services whose readiness is checked, for instance `searcher`
+    /// or `indexer,janitor`. Defaults to all the services running on the node.
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub services: Option<Vec<QuickwitService>>,
+}
+
 fn readiness_handler(
     cluster: Cluster,
+    node_services: NodeServices,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("health" / "readyz")
         .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
         .and(with_arg(cluster))
+        .and(with_arg(node_services))
         .then(get_readiness)
 }
 
@@ -105,10 +140,34 @@ async fn get_liveness(
         (status = 200, description = "The service is ready.", body = bool),
         (status = 503, description = "The service is not ready.", body = bool),
     ),
+    params(
+        ReadinessQueryParams,
+    )
 )]
 /// Get Node Readiness
-async fn get_readiness(cluster: Cluster) -> impl warp::Reply {
-    let is_ready = cluster.is_self_node_ready().await;
+///
+/// The node is ready when it is ready in the cluster and each of the requested services is ready:
+/// - a searcher is ready once it has joined the pool of searchers that leaf search jobs are
+///   assigned to;
+/// - an indexer is ready as long as it is not draining and its ingest API queues are writable;
+/// - a janitor is ready as long as it is healthy.
+async fn get_readiness(
+    query_params: ReadinessQueryParams,
+    cluster: Cluster,
+    node_services: NodeServices,
+) -> impl warp::Reply {
+    let mut is_ready = cluster.is_self_node_ready().await;
+    if is_ready {
+        let services = query_params
+            .services
+            .unwrap_or_else(|| node_services.services.iter().copied().collect());
+        for service in services {
+            if !is_service_ready(service, &node_services).await {
+                is_ready = false;
+                break;
+            }
+        }
+    }
     let status_code = if is_ready {
         StatusCode::OK
     } else {
@@ -117,11 +176,50 @@ async fn get_readiness(cluster: Cluster) -> impl warp::Reply {
     with_status(warp::reply::json(&is_ready), status_code)
 }
 
+async fn is_service_ready(service: QuickwitService, node_services: &NodeServices) -> bool {
+    if !node_services.services.contains(&service) {
+        warn!(service=%service, "Readiness requested for a service not running on the node.");
+        return false;
+    }
+    match service {
+        QuickwitService::Searcher => {
+            node_services
+                .search_job_placer
+                .contains_searcher(&node_services.grpc_advertise_addr)
+                .await
+        }
+        QuickwitService::Indexer => match &node_services.indexing_service_opt {
+            Some(indexing_service) => indexing_service.ask(Readyz).await.unwrap_or(false),
+            None => false,
+        },
+        QuickwitService::Janitor => match &node_services.janitor_service_opt {
+            Some(janitor_service) => janitor_service.ask(Healthz).await.unwrap_or(false),
+            None => false,
+        },
+        // Both services are ready as soon as the node is.
+        QuickwitService::ControlPlane | QuickwitService::Metastore => true,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-
     use chitchat::transport::ChannelTransport;
     use quickwit_cluster::create_cluster_for_test;
+    use quickwit_search::{searcher_pool_for_test, MockSearchService};
+
+    use super::*;
+    use crate::recover_fn;
+
+    fn node_services_for_test(grpc_advertise_addr: &str) -> NodeServices {
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:7281", MockSearchService::new())]);
+        NodeServices {
+            services: HashSet::from_iter([QuickwitService::Searcher, QuickwitService::Metastore]),
+            grpc_advertise_addr: grpc_advertise_addr.parse().unwrap(),
+            search_job_placer: SearchJobPlacer::new(searcher_pool),
+            indexing_service_opt: None,
+            janitor_service_opt: None,
+        }
+    }
 
     #[tokio::test]
     async fn test_rest_search_api_health_checks() {
@@ -129,7 +227,8 @@ mod tests {
         let cluster = create_cluster_for_test(Vec::new(), &[], &transport, false)
             .await
             .unwrap();
-        let health_check_handler = super::health_check_handlers(cluster.clone(), None, None);
+        let health_check_handler =
+            super::health_check_handlers(cluster.clone(), node_services_for_test("127.0.0.1:7281"));
         let resp = warp::test::request()
             .path("/health/livez")
             .reply(&health_check_handler)
@@ -147,4 +246,38 @@ mod tests {
             .await;
         assert_eq!(resp.status(), 200);
     }
+
+    #[tokio::test]
+    async fn test_rest_readiness_per_service() {
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &[], &transport, true)
+            .await
+            .unwrap();
+        // The searcher has not joined the searcher pool yet.
+        let health_check_handler =
+            super::health_check_handlers(cluster.clone(), node_services_for_test("127.0.0.1:7291"))
+                .recover(recover_fn);
+        for (path, expected_status) in [
+            ("/health/readyz", 503),
+            ("/health/readyz?services=searcher", 503),
+            ("/health/readyz?services=metastore", 200),
+            ("/health/readyz?services=metastore,searcher", 503),
+            // The indexer does not run on this node.
+            ("/health/readyz?services=indexer", 503),
+            ("/health/readyz?services=unknown", 400),
+        ] {
+            let resp = warp::test::request()
+                .path(path)
+                .reply(&health_check_handler)
+                .await;
+            assert_eq!(resp.status(), expected_status, "{path}");
+        }
+        let health_check_handler =
+            super::health_check_handlers(cluster.clone(), node_services_for_test("127.0.0.1:7281"));
+        let resp = warp::test::request()
+            .path("/health/readyz?services=searcher")
+            .reply(&health_check_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
 }
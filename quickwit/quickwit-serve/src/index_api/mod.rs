@@ -20,5 +20,6 @@
 mod rest_handler;
 
 pub use self::rest_handler::{
-    index_management_handlers, IndexApi, ListSplitsQueryParams, UnsupportedContentType,
+    index_management_handlers, IndexApi, LabelsQueryParams, ListSplitsQueryParams,
+    UnsupportedContentType,
 };
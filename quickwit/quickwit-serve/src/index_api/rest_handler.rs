@@ -17,19 +17,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use bytes::Bytes;
 use hyper::header::CONTENT_TYPE;
+use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_common::FileEntry;
 use quickwit_config::{
-    load_source_config_from_user_config, ConfigFormat, QuickwitConfig, SourceConfig, SourceParams,
-    CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
+    build_doc_mapper, load_source_config_from_user_config, validate_labels, validate_lookup_tables,
+    validate_search_templates, ConfigFormat, IndexingSettings, IndexingSettingsUpdate, LabelFilter,
+    LookupTable, QuickwitConfig, SearchTemplate, SourceConfig, SourceParams, CLI_INGEST_SOURCE_ID,
+    INGEST_API_SOURCE_ID,
+};
+use quickwit_core::{FieldDiskUsage, IndexService, IndexServiceError, SplitsDiskUsage};
+use quickwit_doc_mapper::{
+    analyze_text, DocValidationReport, JsonObject, MappingCompatibilityReport, TextAnalysis,
 };
-use quickwit_core::{IndexService, IndexServiceError};
 use quickwit_metastore::{
-    IndexMetadata, ListSplitsQuery, Metastore, MetastoreError, Split, SplitState,
+    IndexMetadata, IndexState, ListSplitsQuery, Metastore, MetastoreError, Split, SplitState,
 };
 use quickwit_proto::IndexUid;
 use serde::de::DeserializeOwned;
@@ -47,18 +54,48 @@ use crate::with_arg;
 #[openapi(
     paths(
         create_index,
+        check_mapping_compatibility,
+        validate_doc,
+        analyze,
         clear_index,
         delete_index,
         get_indexes_metadatas,
         list_splits,
         describe_index,
         mark_splits_for_deletion,
+        splits_disk_usage,
+        update_index_labels,
+        update_indexing_settings,
+        get_lookup_tables,
+        put_lookup_table,
+        delete_lookup_table,
+        get_search_templates,
+        put_search_template,
+        delete_search_template,
+        open_index,
+        close_index,
+        freeze_index,
+        set_retention_lock,
         create_source,
+        get_sources,
         reset_source_checkpoint,
         toggle_source,
+        update_source_labels,
         delete_source,
     ),
-    components(schemas(ToggleSource, SplitsForDeletion, IndexStats))
+    components(schemas(
+        ToggleSource,
+        UpdateLabels,
+        RetentionLock,
+        SplitsForDeletion,
+        SplitsDiskUsageRequest,
+        SplitsDiskUsage,
+        FieldDiskUsage,
+        IndexStats,
+        LookupTable,
+        SearchTemplate,
+        AnalyzeRequest
+    ))
 )]
 pub struct IndexApi;
 
@@ -69,17 +106,41 @@ pub fn index_management_handlers(
     // Indexes handlers.
     get_index_metadata_handler(index_service.metastore())
         .or(get_indexes_metadatas_handler(index_service.metastore()))
-        .or(create_index_handler(index_service.clone(), quickwit_config))
+        .or(create_index_handler(
+            index_service.clone(),
+            quickwit_config.clone(),
+        ))
+        .or(check_mapping_compatibility_handler(
+            index_service.metastore(),
+            quickwit_config,
+        ))
+        .or(validate_doc_handler(index_service.metastore()))
+        .or(analyze_handler(index_service.metastore()))
         .or(clear_index_handler(index_service.clone()))
         .or(delete_index_handler(index_service.clone()))
+        .or(update_index_labels_handler(index_service.metastore()))
+        .or(update_indexing_settings_handler(index_service.metastore()))
+        .or(get_lookup_tables_handler(index_service.metastore()))
+        .or(put_lookup_table_handler(index_service.metastore()))
+        .or(delete_lookup_table_handler(index_service.metastore()))
+        .or(get_search_templates_handler(index_service.metastore()))
+        .or(put_search_template_handler(index_service.metastore()))
+        .or(delete_search_template_handler(index_service.metastore()))
+        .or(open_index_handler(index_service.metastore()))
+        .or(close_index_handler(index_service.metastore()))
+        .or(freeze_index_handler(index_service.metastore()))
+        .or(set_retention_lock_handler(index_service.metastore()))
         // Splits handlers
         .or(list_splits_handler(index_service.metastore()))
         .or(describe_index_handler(index_service.metastore()))
         .or(mark_splits_for_deletion_handler(index_service.metastore()))
+        .or(splits_disk_usage_handler(index_service.clone()))
         // Sources handlers.
         .or(reset_source_checkpoint_handler(index_service.metastore()))
         .or(toggle_source_handler(index_service.metastore()))
+        .or(update_source_labels_handler(index_service.metastore()))
         .or(create_source_handler(index_service.clone()))
+        .or(get_sources_handler(index_service.metastore()))
         .or(get_source_handler(index_service.metastore()))
         .or(delete_source_handler(index_service.metastore()))
 }
@@ -140,6 +201,7 @@ fn get_indexes_metadatas_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes")
         .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
         .and(with_arg(metastore))
         .then(get_indexes_metadatas)
         .and(extract_format_from_qs())
@@ -357,951 +419,2456 @@ fn mark_splits_for_deletion_handler(
         .map(make_json_api_response)
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct SplitsDiskUsageRequest {
+    pub split_ids: Vec<String>,
+}
+
 #[utoipa::path(
-    get,
-    tag = "Indexes",
-    path = "/indexes",
+    post,
+    tag = "Splits",
+    path = "/indexes/{index_id}/splits/disk-usage",
+    request_body = SplitsDiskUsageRequest,
     responses(
-        // We return `VersionedIndexMetadata` as it's the serialized model view.
-        (status = 200, description = "Successfully fetched all indexes.", body = [VersionedIndexMetadata])
+        (status = 200, description = "Successfully analyzed the disk usage of the splits.", body = SplitsDiskUsage)
     ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the splits to analyze."),
+    )
 )]
-/// Gets indexes metadata.
-async fn get_indexes_metadatas(
-    metastore: Arc<dyn Metastore>,
-) -> Result<Vec<IndexMetadata>, MetastoreError> {
-    info!("get-indexes-metadatas");
-    metastore.list_indexes_metadatas().await
-}
-
-#[derive(Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
-#[into_params(parameter_in = Query)]
-struct CreateIndexQueryParams {
-    #[serde(default)]
-    overwrite: bool,
+/// Reports the on-disk usage of the fields of published splits, broken down by data structure
+/// (term dictionary, postings, positions, fast fields, fieldnorms). The splits are downloaded in
+/// full.
+async fn splits_disk_usage(
+    index_id: String,
+    splits_disk_usage_request: SplitsDiskUsageRequest,
+    index_service: Arc<IndexService>,
+) -> Result<SplitsDiskUsage, IndexServiceError> {
+    info!(index_id = %index_id, split_ids = ?splits_disk_usage_request.split_ids, "splits-disk-usage");
+    index_service
+        .disk_usage(&index_id, &splits_disk_usage_request.split_ids)
+        .await
 }
 
-fn create_index_handler(
+fn splits_disk_usage_handler(
     index_service: Arc<IndexService>,
-    quickwit_config: Arc<QuickwitConfig>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes")
+    warp::path!("indexes" / String / "splits" / "disk-usage")
         .and(warp::post())
-        .and(serde_qs::warp::query(serde_qs::Config::default()))
-        .and(config_format_filter())
-        .and(warp::body::content_length_limit(1024 * 1024))
-        .and(warp::filters::body::bytes())
+        .and(json_body())
         .and(with_arg(index_service))
-        .and(with_arg(quickwit_config))
-        .then(create_index)
+        .then(splits_disk_usage)
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
 
+/// This struct represents the QueryString passed to
+/// the rest API to filter indexes or sources by label.
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::IntoParams, Default)]
+#[into_params(parameter_in = Query)]
+pub struct LabelsQueryParams {
+    /// Comma-separated list of label filters (`key` or `key:value`).
+    /// Only the items matching all the filters are returned.
+    #[param(value_type = Option<String>)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(serialize_with = "to_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub labels: Option<Vec<LabelFilter>>,
+}
+
+impl LabelsQueryParams {
+    fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        match &self.labels {
+            Some(label_filters) => LabelFilter::matches_all(label_filters, labels),
+            None => true,
+        }
+    }
+}
+
 #[utoipa::path(
-    post,
+    get,
     tag = "Indexes",
     path = "/indexes",
-    request_body = VersionedIndexConfig,
     responses(
         // We return `VersionedIndexMetadata` as it's the serialized model view.
-        (status = 200, description = "Successfully created index.", body = VersionedIndexMetadata)
+        (status = 200, description = "Successfully fetched all indexes.", body = [VersionedIndexMetadata])
     ),
     params(
-        CreateIndexQueryParams,
+        LabelsQueryParams,
     )
 )]
-/// Creates index.
-async fn create_index(
-    create_index_query_params: CreateIndexQueryParams,
-    config_format: ConfigFormat,
-    index_config_bytes: Bytes,
-    index_service: Arc<IndexService>,
-    quickwit_config: Arc<QuickwitConfig>,
-) -> Result<IndexMetadata, IndexServiceError> {
-    let index_config = quickwit_config::load_index_config_from_user_config(
-        config_format,
-        &index_config_bytes,
-        &quickwit_config.default_index_root_uri,
-    )
-    .map_err(IndexServiceError::InvalidConfig)?;
-    info!(index_id = %index_config.index_id, overwrite = create_index_query_params.overwrite, "create-index");
-    index_service
-        .create_index(index_config, create_index_query_params.overwrite)
-        .await
+/// Gets indexes metadata.
+async fn get_indexes_metadatas(
+    labels_query: LabelsQueryParams,
+    metastore: Arc<dyn Metastore>,
+) -> Result<Vec<IndexMetadata>, MetastoreError> {
+    info!(labels_query = ?labels_query, "get-indexes-metadatas");
+    let indexes_metadatas = metastore
+        .list_indexes_metadatas()
+        .await?
+        .into_iter()
+        .filter(|index_metadata| labels_query.matches(&index_metadata.index_config.labels))
+        .collect();
+    Ok(indexes_metadatas)
 }
 
-fn clear_index_handler(
-    index_service: Arc<IndexService>,
+fn update_index_labels_handler(
+    metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String / "clear")
+    warp::path!("indexes" / String / "labels")
         .and(warp::put())
-        .and(with_arg(index_service))
-        .then(clear_index)
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(update_index_labels)
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct UpdateLabels {
+    labels: BTreeMap<String, String>,
+}
+
 #[utoipa::path(
     put,
     tag = "Indexes",
-    path = "/indexes/{index_id}/clear",
+    path = "/indexes/{index_id}/labels",
+    request_body = UpdateLabels,
     responses(
-        (status = 200, description = "Successfully cleared index.")
+        (status = 200, description = "Successfully updated index labels.")
     ),
     params(
-        ("index_id" = String, Path, description = "The index ID to clear."),
+        ("index_id" = String, Path, description = "The index ID to update the labels of."),
     )
 )]
-/// Removes all of the data (splits, queued document) associated with the index, but keeps the index
-/// configuration. (See also, `delete-index`).
-async fn clear_index(
+/// Replaces the labels of an index.
+async fn update_index_labels(
     index_id: String,
-    index_service: Arc<IndexService>,
+    update_labels: UpdateLabels,
+    metastore: Arc<dyn Metastore>,
 ) -> Result<(), IndexServiceError> {
-    info!(index_id = %index_id, "clear-index");
-    index_service.clear_index(&index_id).await
-}
-
-#[derive(Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
-#[into_params(parameter_in = Query)]
-struct DeleteIndexQueryParam {
-    #[serde(default)]
-    dry_run: bool,
+    info!(index_id = %index_id, labels = ?update_labels.labels, "update-index-labels");
+    validate_labels(&update_labels.labels).map_err(IndexServiceError::InvalidConfig)?;
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    metastore
+        .update_index_labels(index_uid, update_labels.labels)
+        .await?;
+    Ok(())
 }
 
-fn delete_index_handler(
-    index_service: Arc<IndexService>,
+fn update_indexing_settings_handler(
+    metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String)
-        .and(warp::delete())
-        .and(serde_qs::warp::query(serde_qs::Config::default()))
-        .and(with_arg(index_service))
-        .then(delete_index)
+    warp::path!("indexes" / String / "indexing-settings")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(update_indexing_settings)
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
 
 #[utoipa::path(
-    delete,
+    put,
     tag = "Indexes",
-    path = "/indexes/{index_id}",
+    path = "/indexes/{index_id}/indexing-settings",
+    request_body = IndexingSettingsUpdate,
     responses(
-        // We return `VersionedIndexMetadata` as it's the serialized model view.
-        (status = 200, description = "Successfully deleted index.", body = [FileEntry])
+        (status = 200, description = "Successfully updated indexing settings.", body = IndexingSettings)
     ),
     params(
-        DeleteIndexQueryParam,
-        ("index_id" = String, Path, description = "The index ID to delete."),
+        ("index_id" = String, Path, description = "The index ID to update the indexing settings of."),
     )
 )]
-/// Deletes index.
-async fn delete_index(
+/// Updates the commit policy of an index. Running indexing pipelines pick up the new settings
+/// without being restarted.
+async fn update_indexing_settings(
     index_id: String,
-    delete_index_query_param: DeleteIndexQueryParam,
-    index_service: Arc<IndexService>,
-) -> Result<Vec<FileEntry>, IndexServiceError> {
-    info!(index_id = %index_id, dry_run = delete_index_query_param.dry_run, "delete-index");
-    index_service
-        .delete_index(&index_id, delete_index_query_param.dry_run)
-        .await
+    update: IndexingSettingsUpdate,
+    metastore: Arc<dyn Metastore>,
+) -> Result<IndexingSettings, IndexServiceError> {
+    info!(index_id = %index_id, update = ?update, "update-indexing-settings");
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let indexing_settings = index_metadata
+        .index_config
+        .indexing_settings
+        .with_update(&update);
+    indexing_settings
+        .validate()
+        .map_err(IndexServiceError::InvalidConfig)?;
+    metastore
+        .update_indexing_settings(index_metadata.index_uid, indexing_settings.clone())
+        .await?;
+    Ok(indexing_settings)
 }
 
-fn create_source_handler(
-    index_service: Arc<IndexService>,
+fn get_lookup_tables_handler(
+    metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String / "sources")
-        .and(warp::post())
-        .and(config_format_filter())
-        .and(warp::body::content_length_limit(1024 * 1024))
-        .and(warp::filters::body::bytes())
-        .and(with_arg(index_service))
-        .then(create_source)
+    warp::path!("indexes" / String / "lookup-tables")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_lookup_tables)
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
 
 #[utoipa::path(
-    post,
-    tag = "Sources",
-    path = "/indexes/{index_id}/sources",
-    request_body = VersionedSourceConfig,
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/lookup-tables",
     responses(
-        // We return `VersionedSourceConfig` as it's the serialized model view.
-        (status = 200, description = "Successfully created source.", body = VersionedSourceConfig)
+        (status = 200, description = "Successfully fetched the lookup tables of the index.", body = BTreeMap<String, LookupTable>)
     ),
     params(
-        ("index_id" = String, Path, description = "The index ID to create a source for."),
+        ("index_id" = String, Path, description = "The index ID to get the lookup tables of."),
     )
 )]
-/// Creates Source.
-async fn create_source(
+/// Returns the lookup tables of an index.
+async fn get_lookup_tables(
     index_id: String,
-    config_format: ConfigFormat,
-    source_config_bytes: Bytes,
-    index_service: Arc<IndexService>,
-) -> Result<SourceConfig, IndexServiceError> {
-    let source_config: SourceConfig =
-        load_source_config_from_user_config(config_format, &source_config_bytes)
-            .map_err(IndexServiceError::InvalidConfig)?;
-    if let SourceParams::File(_) = &source_config.source_params {
-        return Err(IndexServiceError::OperationNotAllowed(
-            "File sources are limited to a local usage. Please use the CLI command `quickwit tool \
-             local-ingest` to ingest data from a file."
-                .to_string(),
-        ));
-    }
-    let index_uid: IndexUid = index_service
-        .metastore()
-        .index_metadata(&index_id)
-        .await?
-        .index_uid;
-    info!(index_id = %index_id, source_id = %source_config.source_id, "create-source");
-    index_service.create_source(index_uid, source_config).await
-}
-
-fn get_source_handler(
     metastore: Arc<dyn Metastore>,
-) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String / "sources" / String)
-        .and(warp::get())
-        .and(with_arg(metastore))
-        .then(get_source)
-        .and(extract_format_from_qs())
-        .map(make_json_api_response)
+) -> Result<BTreeMap<String, LookupTable>, MetastoreError> {
+    info!(index_id = %index_id, "get-lookup-tables");
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    Ok(index_metadata.index_config.lookup_tables)
 }
 
-async fn get_source(
-    index_id: String,
-    source_id: String,
-    metastore: Arc<dyn Metastore>,
-) -> Result<SourceConfig, MetastoreError> {
-    info!(index_id = %index_id, source_id = %source_id, "get-source");
-    let source_config = metastore
-        .index_metadata(&index_id)
-        .await?
-        .sources
-        .get(&source_id)
-        .ok_or_else(|| MetastoreError::SourceDoesNotExist {
-            source_id: source_id.to_string(),
-        })?
-        .clone();
-    Ok(source_config)
+/// This struct represents the QueryString passed to the REST API when uploading a lookup table in
+/// CSV format.
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::IntoParams, Default)]
+#[into_params(parameter_in = Query)]
+struct PutLookupTableQueryParams {
+    /// Field whose values are looked up in the table. Required when the table is uploaded in CSV
+    /// format.
+    #[serde(default)]
+    field: Option<String>,
 }
 
-fn reset_source_checkpoint_handler(
+fn put_lookup_table_handler(
     metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String / "sources" / String / "reset-checkpoint")
+    warp::path!("indexes" / String / "lookup-tables" / String)
         .and(warp::put())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(warp::filters::header::optional::<mime_guess::Mime>(
+            CONTENT_TYPE.as_str(),
+        ))
+        .and(warp::body::content_length_limit(10 * 1024 * 1024))
+        .and(warp::filters::body::bytes())
         .and(with_arg(metastore))
-        .then(reset_source_checkpoint)
+        .then(put_lookup_table)
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
 
 #[utoipa::path(
     put,
-    tag = "Sources",
-    path = "/indexes/{index_id}/sources/{source_id}/reset-checkpoint",
+    tag = "Indexes",
+    path = "/indexes/{index_id}/lookup-tables/{table_name}",
+    request_body = LookupTable,
     responses(
-        (status = 200, description = "Successfully reset source checkpoint.")
+        (status = 200, description = "Successfully created or replaced the lookup table.")
     ),
     params(
-        ("index_id" = String, Path, description = "The index ID of the source."),
-        ("source_id" = String, Path, description = "The source ID whose checkpoint is reset."),
+        ("index_id" = String, Path, description = "The index ID to attach the lookup table to."),
+        ("table_name" = String, Path, description = "The name of the lookup table."),
+        PutLookupTableQueryParams,
     )
 )]
-/// Resets source checkpoint.
-async fn reset_source_checkpoint(
+/// Creates or replaces a lookup table of an index. The table is expected as JSON, or as CSV when
+/// the content type is `text/csv`.
+async fn put_lookup_table(
     index_id: String,
-    source_id: String,
+    table_name: String,
+    query_params: PutLookupTableQueryParams,
+    content_type_opt: Option<mime_guess::Mime>,
+    body: Bytes,
     metastore: Arc<dyn Metastore>,
-) -> Result<(), MetastoreError> {
-    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
-    info!(index_id = %index_id, source_id = %source_id, "reset-checkpoint");
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, table_name = %table_name, "put-lookup-table");
+    let is_csv = content_type_opt
+        .map(|mime| mime.subtype() == mime_guess::mime::CSV)
+        .unwrap_or(false);
+    let lookup_table = if is_csv {
+        let Some(field) = query_params.field else {
+            return Err(IndexServiceError::InvalidConfig(anyhow::anyhow!(
+                "The `field` query parameter is required to upload a lookup table in CSV format."
+            )));
+        };
+        LookupTable::from_csv(field, &body).map_err(IndexServiceError::InvalidConfig)?
+    } else {
+        serde_json::from_slice::<LookupTable>(&body).map_err(|error| {
+            IndexServiceError::InvalidConfig(anyhow::anyhow!(
+                "Failed to parse lookup table: {error}"
+            ))
+        })?
+    };
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let mut lookup_tables = index_metadata.index_config.lookup_tables;
+    lookup_tables.insert(table_name, lookup_table);
+    validate_lookup_tables(&lookup_tables).map_err(IndexServiceError::InvalidConfig)?;
     metastore
-        .reset_source_checkpoint(index_uid, &source_id)
-        .await
+        .update_lookup_tables(index_metadata.index_uid, lookup_tables)
+        .await?;
+    Ok(())
 }
 
-fn toggle_source_handler(
+fn delete_lookup_table_handler(
     metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String / "sources" / String / "toggle")
-        .and(warp::put())
-        .and(json_body())
+    warp::path!("indexes" / String / "lookup-tables" / String)
+        .and(warp::delete())
         .and(with_arg(metastore))
-        .then(toggle_source)
+        .then(delete_lookup_table)
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
 
-#[derive(Deserialize, utoipa::ToSchema)]
-#[serde(deny_unknown_fields)]
-struct ToggleSource {
-    enable: bool,
+#[utoipa::path(
+    delete,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/lookup-tables/{table_name}",
+    responses(
+        (status = 200, description = "Successfully deleted the lookup table.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to remove the lookup table from."),
+        ("table_name" = String, Path, description = "The name of the lookup table."),
+    )
+)]
+/// Deletes a lookup table of an index. Deleting a table that does not exist is a no-op.
+async fn delete_lookup_table(
+    index_id: String,
+    table_name: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, table_name = %table_name, "delete-lookup-table");
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let mut lookup_tables = index_metadata.index_config.lookup_tables;
+    if lookup_tables.remove(&table_name).is_some() {
+        metastore
+            .update_lookup_tables(index_metadata.index_uid, lookup_tables)
+            .await?;
+    }
+    Ok(())
+}
+
+fn get_search_templates_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "search-templates")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_search_templates)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/search-templates",
+    responses(
+        (status = 200, description = "Successfully fetched the search templates of the index.", body = BTreeMap<String, SearchTemplate>)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to get the search templates of."),
+    )
+)]
+/// Returns the search templates of an index.
+async fn get_search_templates(
+    index_id: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<BTreeMap<String, SearchTemplate>, MetastoreError> {
+    info!(index_id = %index_id, "get-search-templates");
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    Ok(index_metadata.index_config.search_templates)
+}
+
+fn put_search_template_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "search-templates" / String)
+        .and(warp::put())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::filters::body::bytes())
+        .and(with_arg(metastore))
+        .then(put_search_template)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
 }
 
 #[utoipa::path(
     put,
-    tag = "Sources",
-    path = "/indexes/{index_id}/sources/{source_id}/toggle",
-    request_body = ToggleSource,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/search-templates/{template_id}",
+    request_body = SearchTemplate,
     responses(
-        (status = 200, description = "Successfully toggled source.")
+        (status = 200, description = "Successfully created or replaced the search template.")
     ),
     params(
-        ("index_id" = String, Path, description = "The index ID of the source."),
-        ("source_id" = String, Path, description = "The source ID to toggle."),
+        ("index_id" = String, Path, description = "The index ID to attach the search template to."),
+        ("template_id" = String, Path, description = "The ID of the search template."),
     )
 )]
-/// Toggles source.
-async fn toggle_source(
+/// Creates or replaces a search template of an index.
+async fn put_search_template(
     index_id: String,
-    source_id: String,
-    toggle_source: ToggleSource,
+    template_id: String,
+    body: Bytes,
     metastore: Arc<dyn Metastore>,
 ) -> Result<(), IndexServiceError> {
-    info!(index_id = %index_id, source_id = %source_id, enable = toggle_source.enable, "toggle-source");
-    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
-    if [CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID].contains(&source_id.as_str()) {
-        return Err(IndexServiceError::OperationNotAllowed(format!(
-            "Source `{source_id}` is managed by Quickwit, you cannot enable or disable a source \
-             managed by Quickwit."
-        )));
-    }
+    info!(index_id = %index_id, template_id = %template_id, "put-search-template");
+    let search_template = serde_json::from_slice::<SearchTemplate>(&body).map_err(|error| {
+        IndexServiceError::InvalidConfig(anyhow::anyhow!(
+            "Failed to parse search template: {error}"
+        ))
+    })?;
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let mut search_templates = index_metadata.index_config.search_templates;
+    search_templates.insert(template_id, search_template);
+    validate_search_templates(&search_templates).map_err(IndexServiceError::InvalidConfig)?;
     metastore
-        .toggle_source(index_uid, &source_id, toggle_source.enable)
+        .update_search_templates(index_metadata.index_uid, search_templates)
         .await?;
     Ok(())
 }
 
-fn delete_source_handler(
+fn delete_search_template_handler(
     metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String / "sources" / String)
+    warp::path!("indexes" / String / "search-templates" / String)
         .and(warp::delete())
         .and(with_arg(metastore))
-        .then(delete_source)
+        .then(delete_search_template)
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
 
 #[utoipa::path(
     delete,
-    tag = "Sources",
-    path = "/indexes/{index_id}/sources/{source_id}",
+    tag = "Indexes",
+    path = "/indexes/{index_id}/search-templates/{template_id}",
     responses(
-        (status = 200, description = "Successfully deleted source.")
+        (status = 200, description = "Successfully deleted the search template.")
     ),
     params(
-        ("index_id" = String, Path, description = "The index ID to remove the source from."),
-        ("source_id" = String, Path, description = "The source ID to remove from the index."),
+        ("index_id" = String, Path, description = "The index ID to remove the search template from."),
+        ("template_id" = String, Path, description = "The ID of the search template."),
     )
 )]
-/// Deletes source.
-async fn delete_source(
+/// Deletes a search template of an index. Deleting a template that does not exist is a no-op.
+async fn delete_search_template(
     index_id: String,
-    source_id: String,
+    template_id: String,
     metastore: Arc<dyn Metastore>,
 ) -> Result<(), IndexServiceError> {
-    info!(index_id = %index_id, source_id = %source_id, "delete-source");
-    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
-    if [INGEST_API_SOURCE_ID, CLI_INGEST_SOURCE_ID].contains(&source_id.as_str()) {
-        return Err(IndexServiceError::OperationNotAllowed(format!(
-            "Source `{source_id}` is managed by Quickwit, you cannot delete a source managed by \
-             Quickwit."
-        )));
+    info!(index_id = %index_id, template_id = %template_id, "delete-search-template");
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let mut search_templates = index_metadata.index_config.search_templates;
+    if search_templates.remove(&template_id).is_some() {
+        metastore
+            .update_search_templates(index_metadata.index_uid, search_templates)
+            .await?;
     }
-    metastore.delete_source(index_uid, &source_id).await?;
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use std::ops::{Bound, RangeInclusive};
+fn open_index_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "open")
+        .and(warp::post())
+        .and(with_arg(metastore))
+        .then(open_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
 
-    use assert_json_diff::assert_json_include;
-    use quickwit_common::uri::Uri;
-    use quickwit_config::{SourceParams, VecSourceParams};
-    use quickwit_indexing::mock_split;
-    use quickwit_metastore::{metastore_for_test, IndexMetadata, MetastoreError, MockMetastore};
-    use quickwit_storage::StorageResolver;
-    use serde::__private::from_utf8_lossy;
-    use serde_json::Value as JsonValue;
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/open",
+    responses(
+        (status = 200, description = "Successfully opened index.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to open."),
+    )
+)]
+/// Opens a closed or frozen index: it is indexed, merged, and searchable again.
+async fn open_index(
+    index_id: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    set_index_state(index_id, IndexState::Open, metastore).await
+}
 
-    use super::*;
-    use crate::recover_fn;
+fn close_index_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "close")
+        .and(warp::post())
+        .and(with_arg(metastore))
+        .then(close_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
 
-    #[tokio::test]
-    async fn test_get_index() -> anyhow::Result<()> {
-        let mut metastore = MockMetastore::new();
-        metastore
-            .expect_index_metadata()
-            .return_once(|_index_id: &str| {
-                Ok(IndexMetadata::for_test(
-                    "test-index",
-                    "ram:///indexes/test-index",
-                ))
-            });
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
-        let resp = warp::test::request()
-            .path("/indexes/test-index")
-            .reply(&index_management_handler)
-            .await;
-        assert_eq!(resp.status(), 200);
-        let actual_response_json: JsonValue = serde_json::from_slice(resp.body())?;
-        let expected_response_json = serde_json::json!({
-            "index_id": "test-index",
-            "index_uri": "ram:///indexes/test-index",
-        });
-        assert_json_include!(
-            actual: actual_response_json.get("index_config").unwrap(),
-            expected: expected_response_json
-        );
-        Ok(())
-    }
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/close",
+    responses(
+        (status = 200, description = "Successfully closed index.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to close."),
+    )
+)]
+/// Closes an index: its indexing and merge pipelines are stopped and it can no longer be searched.
+/// Its splits are kept.
+async fn close_index(
+    index_id: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    set_index_state(index_id, IndexState::Closed, metastore).await
+}
 
-    #[tokio::test]
-    async fn test_get_non_existing_index() {
-        let metastore = metastore_for_test();
-        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
-        let resp = warp::test::request()
-            .path("/indexes/test-index")
-            .reply(&index_management_handler)
-            .await;
-        assert_eq!(resp.status(), 404);
-    }
+fn freeze_index_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "freeze")
+        .and(warp::post())
+        .and(with_arg(metastore))
+        .then(freeze_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
 
-    #[tokio::test]
-    async fn test_get_splits() {
-        let mut metastore = MockMetastore::new();
-        metastore
-            .expect_index_metadata()
-            .returning(|_index_id: &str| {
-                Ok(IndexMetadata::for_test(
-                    "quickwit-demo-index",
-                    "ram:///indexes/quickwit-demo-index",
-                ))
-            })
-            .times(2);
-        metastore
-            .expect_list_splits()
-            .returning(|list_split_query: ListSplitsQuery| {
-                if list_split_query.index_uid.index_id() == "quickwit-demo-index"
-                    && list_split_query.split_states
-                        == vec![SplitState::Published, SplitState::Staged]
-                    && list_split_query.time_range.start == Bound::Included(10)
-                    && list_split_query.time_range.end == Bound::Excluded(20)
-                    && list_split_query.create_timestamp.end == Bound::Excluded(2)
-                {
-                    return Ok(vec![mock_split("split_1")]);
-                }
-                Err(MetastoreError::InternalError {
-                    message: "".to_string(),
-                    cause: "".to_string(),
-                })
-            })
-            .times(2);
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
-        {
-            let resp = warp::test::request()
-                .path(
-                    "/indexes/quickwit-demo-index/splits?split_states=Published,Staged&\
-                     start_timestamp=10&end_timestamp=20&end_create_timestamp=2",
-                )
-                .reply(&index_management_handler)
-                .await;
-            assert_eq!(resp.status(), 200);
-            let actual_response_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
-            let expected_response_json = serde_json::json!([{
-                "create_timestamp": 0,
-                "split_id": "split_1",
-            }]);
-            assert_json_include!(
-                actual: actual_response_json,
-                expected: expected_response_json
-            );
-        }
-        {
-            let resp = warp::test::request()
-                .path(
-                    "/indexes/quickwit-demo-index/splits?split_states=Published&\
-                     start_timestamp=11&end_timestamp=20&end_create_timestamp=2",
-                )
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/freeze",
+    responses(
+        (status = 200, description = "Successfully froze index.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to freeze."),
+    )
+)]
+/// Freezes an index: its indexing and merge pipelines are stopped but it remains searchable,
+/// without using the searchers' long-term caches.
+async fn freeze_index(
+    index_id: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    set_index_state(index_id, IndexState::Frozen, metastore).await
+}
+
+async fn set_index_state(
+    index_id: String,
+    state: IndexState,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, state = %state, "set-index-state");
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    metastore.set_index_state(index_uid, state).await?;
+    Ok(())
+}
+
+fn set_retention_lock_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "retention-lock")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(set_retention_lock)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct RetentionLock {
+    /// Unix timestamp (in seconds) until which the index is locked.
+    locked_until: i64,
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/retention-lock",
+    request_body = RetentionLock,
+    responses(
+        (status = 200, description = "Successfully placed the index under a retention lock.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to lock."),
+    )
+)]
+/// Places an index under a retention lock until a given date. Until then, the index cannot be
+/// deleted and its documents and splits can be neither deleted nor garbage collected. An active
+/// lock can be extended but neither shortened nor lifted.
+async fn set_retention_lock(
+    index_id: String,
+    retention_lock: RetentionLock,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, locked_until = retention_lock.locked_until, "set-retention-lock");
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    metastore
+        .set_retention_lock(index_uid, retention_lock.locked_until)
+        .await?;
+    Ok(())
+}
+
+#[derive(Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+struct CreateIndexQueryParams {
+    #[serde(default)]
+    overwrite: bool,
+}
+
+fn create_index_handler(
+    index_service: Arc<IndexService>,
+    quickwit_config: Arc<QuickwitConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes")
+        .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(config_format_filter())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::filters::body::bytes())
+        .and(with_arg(index_service))
+        .and(with_arg(quickwit_config))
+        .then(create_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes",
+    request_body = VersionedIndexConfig,
+    responses(
+        // We return `VersionedIndexMetadata` as it's the serialized model view.
+        (status = 200, description = "Successfully created index.", body = VersionedIndexMetadata)
+    ),
+    params(
+        CreateIndexQueryParams,
+    )
+)]
+/// Creates index.
+async fn create_index(
+    create_index_query_params: CreateIndexQueryParams,
+    config_format: ConfigFormat,
+    index_config_bytes: Bytes,
+    index_service: Arc<IndexService>,
+    quickwit_config: Arc<QuickwitConfig>,
+) -> Result<IndexMetadata, IndexServiceError> {
+    let index_config = quickwit_config::load_index_config_from_user_config(
+        config_format,
+        &index_config_bytes,
+        &quickwit_config.default_index_root_uri,
+    )
+    .map_err(IndexServiceError::InvalidConfig)?;
+    info!(index_id = %index_config.index_id, overwrite = create_index_query_params.overwrite, "create-index");
+    index_service
+        .create_index(index_config, create_index_query_params.overwrite)
+        .await
+}
+
+fn check_mapping_compatibility_handler(
+    metastore: Arc<dyn Metastore>,
+    quickwit_config: Arc<QuickwitConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "check-mapping")
+        .and(warp::post())
+        .and(config_format_filter())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::filters::body::bytes())
+        .and(with_arg(metastore))
+        .and(with_arg(quickwit_config))
+        .then(check_mapping_compatibility)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/check-mapping",
+    request_body = VersionedIndexConfig,
+    responses(
+        (status = 200, description = "Successfully compared the doc mappings.", body = MappingCompatibilityReport)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID whose doc mapping is compared."),
+    )
+)]
+/// Compares the doc mapping of an index with the doc mapping of the index config passed in the
+/// request body, and reports breaking and non-breaking changes.
+async fn check_mapping_compatibility(
+    index_id: String,
+    config_format: ConfigFormat,
+    index_config_bytes: Bytes,
+    metastore: Arc<dyn Metastore>,
+    quickwit_config: Arc<QuickwitConfig>,
+) -> Result<MappingCompatibilityReport, IndexServiceError> {
+    info!(index_id = %index_id, "check-mapping-compatibility");
+    let new_index_config = quickwit_config::load_index_config_from_user_config(
+        config_format,
+        &index_config_bytes,
+        &quickwit_config.default_index_root_uri,
+    )
+    .map_err(IndexServiceError::InvalidConfig)?;
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let report = index_metadata
+        .index_config
+        .doc_mapping
+        .check_compatibility(&new_index_config.doc_mapping);
+    Ok(report)
+}
+
+fn validate_doc_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "validate-doc")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(validate_doc)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/validate-doc",
+    request_body(content = String, description = "JSON document to validate.", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successfully validated the document.", body = DocValidationReport)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID whose doc mapping the document is validated against."),
+    )
+)]
+/// Validates a document against the doc mapping of an index without indexing it, and reports how
+/// each of its fields would be coerced and which dynamic paths it would create.
+async fn validate_doc(
+    index_id: String,
+    json_obj: JsonObject,
+    metastore: Arc<dyn Metastore>,
+) -> Result<DocValidationReport, IndexServiceError> {
+    info!(index_id = %index_id, "validate-doc");
+    let index_config = metastore
+        .index_metadata(&index_id)
+        .await?
+        .into_index_config();
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| IndexServiceError::Internal(error.to_string()))?;
+    let report = doc_mapper.validate_json_obj(json_obj);
+    Ok(report)
+}
+
+/// Text to analyze, either with the tokenizer of a field of the index or with a given tokenizer.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct AnalyzeRequest {
+    /// Text to analyze.
+    text: String,
+    /// Field whose tokenizer the text is analyzed with. Fields of JSON fields are addressed with
+    /// their full path, e.g. `attributes.color`.
+    #[serde(default)]
+    field: Option<String>,
+    /// Name of the tokenizer the text is analyzed with, e.g. `en_stem`.
+    #[serde(default)]
+    tokenizer: Option<String>,
+}
+
+fn analyze_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "analyze")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(analyze)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/analyze",
+    request_body = AnalyzeRequest,
+    responses(
+        (status = 200, description = "Successfully analyzed the text.", body = TextAnalysis)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID whose tokenizers are used."),
+    )
+)]
+/// Splits a text into tokens, with their positions and offsets, as it would be indexed or searched
+/// in a field of the index. Useful to understand why a query does not match a document.
+async fn analyze(
+    index_id: String,
+    analyze_request: AnalyzeRequest,
+    metastore: Arc<dyn Metastore>,
+) -> Result<TextAnalysis, IndexServiceError> {
+    info!(index_id = %index_id, "analyze");
+    let index_config = metastore
+        .index_metadata(&index_id)
+        .await?
+        .into_index_config();
+    // Building the doc mapper registers the custom tokenizers of the fields.
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| IndexServiceError::Internal(error.to_string()))?;
+    let text_analysis = match (analyze_request.field, analyze_request.tokenizer) {
+        (Some(field), None) => doc_mapper.analyze_field_text(&field, &analyze_request.text),
+        (None, Some(tokenizer)) => analyze_text(&tokenizer, &analyze_request.text),
+        _ => Err(anyhow::anyhow!(
+            "Exactly one of `field` and `tokenizer` must be specified."
+        )),
+    };
+    text_analysis.map_err(IndexServiceError::InvalidConfig)
+}
+
+fn clear_index_handler(
+    index_service: Arc<IndexService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "clear")
+        .and(warp::put())
+        .and(with_arg(index_service))
+        .then(clear_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/clear",
+    responses(
+        (status = 200, description = "Successfully cleared index.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to clear."),
+    )
+)]
+/// Removes all of the data (splits, queued document) associated with the index, but keeps the index
+/// configuration. (See also, `delete-index`).
+async fn clear_index(
+    index_id: String,
+    index_service: Arc<IndexService>,
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, "clear-index");
+    index_service.clear_index(&index_id).await
+}
+
+#[derive(Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+struct DeleteIndexQueryParam {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn delete_index_handler(
+    index_service: Arc<IndexService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String)
+        .and(warp::delete())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(index_service))
+        .then(delete_index)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    delete,
+    tag = "Indexes",
+    path = "/indexes/{index_id}",
+    responses(
+        // We return `VersionedIndexMetadata` as it's the serialized model view.
+        (status = 200, description = "Successfully deleted index.", body = [FileEntry])
+    ),
+    params(
+        DeleteIndexQueryParam,
+        ("index_id" = String, Path, description = "The index ID to delete."),
+    )
+)]
+/// Deletes index.
+async fn delete_index(
+    index_id: String,
+    delete_index_query_param: DeleteIndexQueryParam,
+    index_service: Arc<IndexService>,
+) -> Result<Vec<FileEntry>, IndexServiceError> {
+    info!(index_id = %index_id, dry_run = delete_index_query_param.dry_run, "delete-index");
+    index_service
+        .delete_index(&index_id, delete_index_query_param.dry_run)
+        .await
+}
+
+fn create_source_handler(
+    index_service: Arc<IndexService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources")
+        .and(warp::post())
+        .and(config_format_filter())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::filters::body::bytes())
+        .and(with_arg(index_service))
+        .then(create_source)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources",
+    request_body = VersionedSourceConfig,
+    responses(
+        // We return `VersionedSourceConfig` as it's the serialized model view.
+        (status = 200, description = "Successfully created source.", body = VersionedSourceConfig)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to create a source for."),
+    )
+)]
+/// Creates Source.
+async fn create_source(
+    index_id: String,
+    config_format: ConfigFormat,
+    source_config_bytes: Bytes,
+    index_service: Arc<IndexService>,
+) -> Result<SourceConfig, IndexServiceError> {
+    let source_config: SourceConfig =
+        load_source_config_from_user_config(config_format, &source_config_bytes)
+            .map_err(IndexServiceError::InvalidConfig)?;
+    if let SourceParams::File(_) = &source_config.source_params {
+        return Err(IndexServiceError::OperationNotAllowed(
+            "File sources are limited to a local usage. Please use the CLI command `quickwit tool \
+             local-ingest` to ingest data from a file."
+                .to_string(),
+        ));
+    }
+    let index_uid: IndexUid = index_service
+        .metastore()
+        .index_metadata(&index_id)
+        .await?
+        .index_uid;
+    info!(index_id = %index_id, source_id = %source_config.source_id, "create-source");
+    index_service.create_source(index_uid, source_config).await
+}
+
+fn get_sources_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(metastore))
+        .then(get_sources)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources",
+    responses(
+        // We return `VersionedSourceConfig` as it's the serialized model view.
+        (status = 200, description = "Successfully fetched sources.", body = [VersionedSourceConfig])
+    ),
+    params(
+        LabelsQueryParams,
+        ("index_id" = String, Path, description = "The index ID to list the sources of."),
+    )
+)]
+/// Lists the sources of an index.
+async fn get_sources(
+    index_id: String,
+    labels_query: LabelsQueryParams,
+    metastore: Arc<dyn Metastore>,
+) -> Result<Vec<SourceConfig>, MetastoreError> {
+    info!(index_id = %index_id, labels_query = ?labels_query, "get-sources");
+    let sources = metastore
+        .index_metadata(&index_id)
+        .await?
+        .sources
+        .into_values()
+        .filter(|source_config| labels_query.matches(&source_config.labels))
+        .sorted_by(|left, right| left.source_id.cmp(&right.source_id))
+        .collect();
+    Ok(sources)
+}
+
+fn get_source_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String)
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_source)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+async fn get_source(
+    index_id: String,
+    source_id: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<SourceConfig, MetastoreError> {
+    info!(index_id = %index_id, source_id = %source_id, "get-source");
+    let source_config = metastore
+        .index_metadata(&index_id)
+        .await?
+        .sources
+        .get(&source_id)
+        .ok_or_else(|| MetastoreError::SourceDoesNotExist {
+            source_id: source_id.to_string(),
+        })?
+        .clone();
+    Ok(source_config)
+}
+
+fn reset_source_checkpoint_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "reset-checkpoint")
+        .and(warp::put())
+        .and(with_arg(metastore))
+        .then(reset_source_checkpoint)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}/reset-checkpoint",
+    responses(
+        (status = 200, description = "Successfully reset source checkpoint.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the source."),
+        ("source_id" = String, Path, description = "The source ID whose checkpoint is reset."),
+    )
+)]
+/// Resets source checkpoint.
+async fn reset_source_checkpoint(
+    index_id: String,
+    source_id: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), MetastoreError> {
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    info!(index_id = %index_id, source_id = %source_id, "reset-checkpoint");
+    metastore
+        .reset_source_checkpoint(index_uid, &source_id)
+        .await
+}
+
+fn toggle_source_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "toggle")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(toggle_source)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct ToggleSource {
+    enable: bool,
+}
+
+#[utoipa::path(
+    put,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}/toggle",
+    request_body = ToggleSource,
+    responses(
+        (status = 200, description = "Successfully toggled source.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the source."),
+        ("source_id" = String, Path, description = "The source ID to toggle."),
+    )
+)]
+/// Toggles source.
+async fn toggle_source(
+    index_id: String,
+    source_id: String,
+    toggle_source: ToggleSource,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, source_id = %source_id, enable = toggle_source.enable, "toggle-source");
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    if [CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID].contains(&source_id.as_str()) {
+        return Err(IndexServiceError::OperationNotAllowed(format!(
+            "Source `{source_id}` is managed by Quickwit, you cannot enable or disable a source \
+             managed by Quickwit."
+        )));
+    }
+    metastore
+        .toggle_source(index_uid, &source_id, toggle_source.enable)
+        .await?;
+    Ok(())
+}
+
+fn update_source_labels_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "labels")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(update_source_labels)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}/labels",
+    request_body = UpdateLabels,
+    responses(
+        (status = 200, description = "Successfully updated source labels.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the source."),
+        ("source_id" = String, Path, description = "The source ID to update the labels of."),
+    )
+)]
+/// Replaces the labels of a source.
+async fn update_source_labels(
+    index_id: String,
+    source_id: String,
+    update_labels: UpdateLabels,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, source_id = %source_id, labels = ?update_labels.labels, "update-source-labels");
+    validate_labels(&update_labels.labels).map_err(IndexServiceError::InvalidConfig)?;
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    metastore
+        .update_source_labels(index_uid, &source_id, update_labels.labels)
+        .await?;
+    Ok(())
+}
+
+fn delete_source_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String)
+        .and(warp::delete())
+        .and(with_arg(metastore))
+        .then(delete_source)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    delete,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}",
+    responses(
+        (status = 200, description = "Successfully deleted source.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to remove the source from."),
+        ("source_id" = String, Path, description = "The source ID to remove from the index."),
+    )
+)]
+/// Deletes source.
+async fn delete_source(
+    index_id: String,
+    source_id: String,
+    metastore: Arc<dyn Metastore>,
+) -> Result<(), IndexServiceError> {
+    info!(index_id = %index_id, source_id = %source_id, "delete-source");
+    let index_uid: IndexUid = metastore.index_metadata(&index_id).await?.index_uid;
+    if [INGEST_API_SOURCE_ID, CLI_INGEST_SOURCE_ID].contains(&source_id.as_str()) {
+        return Err(IndexServiceError::OperationNotAllowed(format!(
+            "Source `{source_id}` is managed by Quickwit, you cannot delete a source managed by \
+             Quickwit."
+        )));
+    }
+    metastore.delete_source(index_uid, &source_id).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::{Bound, RangeInclusive};
+
+    use assert_json_diff::assert_json_include;
+    use quickwit_common::uri::Uri;
+    use quickwit_config::{SourceParams, VecSourceParams};
+    use quickwit_indexing::mock_split;
+    use quickwit_metastore::{metastore_for_test, IndexMetadata, MetastoreError, MockMetastore};
+    use quickwit_storage::StorageResolver;
+    use serde::__private::from_utf8_lossy;
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::recover_fn;
+
+    #[tokio::test]
+    async fn test_get_index() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .return_once(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "test-index",
+                    "ram:///indexes/test-index",
+                ))
+            });
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/test-index")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let actual_response_json: JsonValue = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "index_id": "test-index",
+            "index_uri": "ram:///indexes/test-index",
+        });
+        assert_json_include!(
+            actual: actual_response_json.get("index_config").unwrap(),
+            expected: expected_response_json
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_non_existing_index() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/test-index")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_get_splits() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "quickwit-demo-index",
+                    "ram:///indexes/quickwit-demo-index",
+                ))
+            })
+            .times(2);
+        metastore
+            .expect_list_splits()
+            .returning(|list_split_query: ListSplitsQuery| {
+                if list_split_query.index_uid.index_id() == "quickwit-demo-index"
+                    && list_split_query.split_states
+                        == vec![SplitState::Published, SplitState::Staged]
+                    && list_split_query.time_range.start == Bound::Included(10)
+                    && list_split_query.time_range.end == Bound::Excluded(20)
+                    && list_split_query.create_timestamp.end == Bound::Excluded(2)
+                {
+                    return Ok(vec![mock_split("split_1")]);
+                }
+                Err(MetastoreError::InternalError {
+                    message: "".to_string(),
+                    cause: "".to_string(),
+                })
+            })
+            .times(2);
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        {
+            let resp = warp::test::request()
+                .path(
+                    "/indexes/quickwit-demo-index/splits?split_states=Published,Staged&\
+                     start_timestamp=10&end_timestamp=20&end_create_timestamp=2",
+                )
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let actual_response_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+            let expected_response_json = serde_json::json!([{
+                "create_timestamp": 0,
+                "split_id": "split_1",
+            }]);
+            assert_json_include!(
+                actual: actual_response_json,
+                expected: expected_response_json
+            );
+        }
+        {
+            let resp = warp::test::request()
+                .path(
+                    "/indexes/quickwit-demo-index/splits?split_states=Published&\
+                     start_timestamp=11&end_timestamp=20&end_create_timestamp=2",
+                )
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 500);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_describe_index() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .return_once(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "test-index",
+                    "ram:///indexes/test-index",
+                ))
+            });
+        let split_1 = mock_split("split_1");
+        let split_1_time_range = split_1.split_metadata.time_range.clone().unwrap();
+        let mut split_2 = mock_split("split_2");
+        split_2.split_metadata.time_range = Some(RangeInclusive::new(
+            split_1_time_range.start() - 10,
+            split_1_time_range.end() + 10,
+        ));
+        metastore
+            .expect_list_splits()
+            .return_once(|list_split_query: ListSplitsQuery| {
+                if list_split_query.index_uid.index_id() == "test-index" {
+                    return Ok(vec![split_1, split_2]);
+                }
+                Err(MetastoreError::InternalError {
+                    message: "".to_string(),
+                    cause: "".to_string(),
+                })
+            });
+
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/test-index/describe")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let actual_response_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "index_id": "test-index",
+            "index_uri": "ram:///indexes/test-index",
+            "num_published_splits": 2,
+            "size_published_splits": 1600,
+            "num_published_docs": 20,
+            "size_published_docs_uncompressed": 512,
+            "timestamp_field_name": "timestamp",
+            "min_timestamp": split_1_time_range.start() - 10,
+            "max_timestamp": split_1_time_range.end() + 10,
+        });
+
+        assert_eq!(actual_response_json, expected_response_json);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_all_splits() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .return_once(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "quickwit-demo-index",
+                    "ram:///indexes/quickwit-demo-index",
+                ))
+            });
+        metastore
+            .expect_list_splits()
+            .return_once(|list_split_query: ListSplitsQuery| {
+                if list_split_query.index_uid.index_id() == "quickwit-demo-index"
+                    && list_split_query.split_states.is_empty()
+                    && list_split_query.time_range.is_unbounded()
+                    && list_split_query.create_timestamp.is_unbounded()
+                {
+                    return Ok(Vec::new());
+                }
+                Err(MetastoreError::InternalError {
+                    message: "".to_string(),
+                    cause: "".to_string(),
+                })
+            });
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_mark_splits_for_deletion() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "quickwit-demo-index",
+                    "ram:///indexes/quickwit-demo-index",
+                ))
+            })
+            .times(2);
+        metastore
+            .expect_mark_splits_for_deletion()
+            .returning(|index_uid: IndexUid, split_ids: &[&str]| {
+                if index_uid.index_id() == "quickwit-demo-index"
+                    && split_ids == ["split-1", "split-2"]
+                {
+                    return Ok(());
+                }
+                Err(MetastoreError::InternalError {
+                    message: "".to_string(),
+                    cause: "".to_string(),
+                })
+            })
+            .times(2);
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits/mark-for-deletion")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"split_ids": ["split-1", "split-2"]}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits/mark-for-deletion")
+            .json(&true)
+            .body(r#"{"split_ids": [""]}"#)
+            .method("PUT")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 500);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_splits_disk_usage_on_unknown_split() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "quickwit-demo-index",
+                    "ram:///indexes/quickwit-demo-index",
+                ))
+            });
+        metastore
+            .expect_list_splits()
+            .returning(|_| Ok(vec![mock_split("split_1")]));
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits/disk-usage")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"split_ids": ["split_1", "split_2"]}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let body = from_utf8_lossy(resp.body());
+        assert!(body.contains("Splits `split_2` are not published splits"));
+
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits/disk-usage")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"split_ids": []}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_get_list_indexes() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore.expect_list_indexes_metadatas().return_once(|| {
+            Ok(vec![IndexMetadata::for_test(
+                "test-index",
+                "ram:///indexes/test-index",
+            )])
+        });
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let actual_response_json: JsonValue = serde_json::from_slice(resp.body())?;
+        let actual_response_arr: &Vec<JsonValue> = actual_response_json.as_array().unwrap();
+        assert_eq!(actual_response_arr.len(), 1);
+        let actual_index_metadata_json: &JsonValue = &actual_response_arr[0];
+        let expected_response_json = serde_json::json!({
+            "index_id": "test-index",
+            "index_uri": "ram:///indexes/test-index",
+        });
+        assert_json_include!(
+            actual: actual_index_metadata_json.get("index_config").unwrap(),
+            expected: expected_response_json
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clear_index() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .return_once(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "quickwit-demo-index",
+                    "file:///path/to/index/quickwit-demo-index",
+                ))
+            });
+        metastore
+            .expect_list_all_splits()
+            .return_once(|_| Ok(vec![mock_split("split_1")]));
+        metastore
+            .expect_mark_splits_for_deletion()
+            .return_once(|_index_id: IndexUid, _splits: &[&str]| Ok(()));
+        metastore
+            .expect_delete_splits()
+            .return_once(|_index_id: IndexUid, _splits: &[&str]| Ok(()));
+        metastore
+            .expect_reset_source_checkpoint()
+            .return_once(|_index_id: IndexUid, _source_id: &str| Ok(()));
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/clear")
+            .method("PUT")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_index() {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "quickwit-demo-index",
+                    "file:///path/to/index/quickwit-demo-index",
+                ))
+            })
+            .times(2);
+        metastore
+            .expect_list_all_splits()
+            .return_once(|_| Ok(vec![mock_split("split_1")]));
+        metastore
+            .expect_list_splits()
+            .returning(|_| Ok(vec![mock_split("split_1")]))
+            .times(2);
+        metastore
+            .expect_mark_splits_for_deletion()
+            .return_once(|_index_uid: IndexUid, _splits: &[&str]| Ok(()));
+        metastore
+            .expect_delete_splits()
+            .return_once(|_index_uid: IndexUid, _splits: &[&str]| Ok(()));
+        metastore
+            .expect_delete_index()
+            .return_once(|_index_uid: IndexUid| Ok(()));
+        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        {
+            // Dry run
+            let resp = warp::test::request()
+                .path("/indexes/quickwit-demo-index?dry_run=true")
+                .method("DELETE")
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            let expected_response_json = serde_json::json!([{
+                "file_name": "split_1.split",
+                "file_size_in_bytes": 800,
+            }]);
+            assert_json_include!(actual: resp_json, expected: expected_response_json);
+        }
+        {
+            let resp = warp::test::request()
+                .path("/indexes/quickwit-demo-index")
+                .method("DELETE")
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+            let expected_response_json = serde_json::json!([{
+                "file_name": "split_1.split",
+                "file_size_in_bytes": 800,
+            }]);
+            assert_json_include!(actual: resp_json, expected: expected_response_json);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_on_non_existing_index() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let index_management_handler = super::index_management_handlers(
+            Arc::new(index_service),
+            Arc::new(QuickwitConfig::for_test()),
+        )
+        .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index")
+            .method("DELETE")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_create_index_with_overwrite() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config));
+        {
+            let resp = warp::test::request()
+                .path("/indexes?overwrite=true")
+                .method("POST")
+                .json(&true)
+                .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+        }
+        {
+            let resp = warp::test::request()
+                .path("/indexes?overwrite=true")
+                .method("POST")
+                .json(&true)
+                .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+        }
+        {
+            let resp = warp::test::request()
+                .path("/indexes")
+                .method("POST")
+                .json(&true)
+                .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 400);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_mapping_compatibility() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/check-mapping")
+            .method("POST")
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "u64", "fast": true, "indexed": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let report: MappingCompatibilityReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].path, "timestamp");
+        assert!(report.requires_reindex());
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/check-mapping")
+            .method("POST")
+            .header("content-type", "application/yaml")
+            .body(
+                r#"
+                version: 0.6
+                index_id: hdfs-logs
+                doc_mapping:
+                  field_mappings:
+                    - name: timestamp
+                      type: i64
+                      fast: true
+                      indexed: true
+                "#,
+            )
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let report: MappingCompatibilityReport = serde_json::from_slice(resp.body()).unwrap();
+        assert!(report.changes.is_empty());
+
+        let resp = warp::test::request()
+            .path("/indexes/unknown-index/check-mapping")
+            .method("POST")
+            .body(r#"{"version": "0.6", "index_id": "unknown-index", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "u64", "fast": true, "indexed": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_validate_doc() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"mode": "dynamic", "field_mappings":[{"name": "severity", "type": "u64"}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/validate-doc")
+            .method("POST")
+            .body(r#"{"severity": 3, "attributes": {"service": "datanode"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let report: DocValidationReport = serde_json::from_slice(resp.body()).unwrap();
+        assert!(report.is_valid);
+        assert_eq!(report.fields.len(), 1);
+        assert_eq!(report.fields[0].path, "severity");
+        assert_eq!(report.fields[0].values, [serde_json::json!(3)]);
+        assert_eq!(report.dynamic_paths, ["attributes.service"]);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/validate-doc")
+            .method("POST")
+            .body(r#"{"severity": "high"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let report: DocValidationReport = serde_json::from_slice(resp.body()).unwrap();
+        assert!(!report.is_valid);
+        assert!(report.fields[0].error.is_some());
+
+        let resp = warp::test::request()
+            .path("/indexes/unknown-index/validate-doc")
+            .method("POST")
+            .body(r#"{"severity": 3}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_analyze() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "body", "type": "text", "tokenizer": "en_stem"}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/analyze")
+            .method("POST")
+            .body(r#"{"text": "Running dogs", "field": "body"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let text_analysis: TextAnalysis = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(text_analysis.tokenizer, "en_stem");
+        assert_eq!(text_analysis.tokens.len(), 2);
+        assert_eq!(text_analysis.tokens[0].token, "run");
+        assert_eq!(text_analysis.tokens[1].start_offset, 8);
+        assert_eq!(text_analysis.tokens[1].position, 1);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/analyze")
+            .method("POST")
+            .body(r#"{"text": "Running dogs", "tokenizer": "raw"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let text_analysis: TextAnalysis = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(text_analysis.tokens.len(), 1);
+        assert_eq!(text_analysis.tokens[0].token, "Running dogs");
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/analyze")
+            .method("POST")
+            .body(r#"{"text": "Running dogs"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/analyze")
+            .method("POST")
+            .body(r#"{"text": "Running dogs", "tokenizer": "unknown"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_create_delete_index_and_source() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config));
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "index_config": {
+                "index_id": "hdfs-logs",
+                "index_uri": "file:///default-index-root-uri/hdfs-logs",
+            }
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        // Create source.
+        let source_config_body = r#"{"version": "0.6", "source_id": "vec-source", "source_type": "vec", "params": {"docs": [], "batch_num_docs": 10}}"#;
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/sources")
+            .method("POST")
+            .json(&true)
+            .body(source_config_body)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        // Get source.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/sources/vec-source")
+            .method("GET")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        // Check that the source has been added to index metadata.
+        let index_metadata = metastore.index_metadata("hdfs-logs").await.unwrap();
+        assert!(index_metadata.sources.contains_key("vec-source"));
+        let source_config = index_metadata.sources.get("vec-source").unwrap();
+        assert_eq!(source_config.source_type(), "vec");
+        assert_eq!(
+            source_config.source_params,
+            SourceParams::Vec(VecSourceParams {
+                docs: Vec::new(),
+                batch_num_docs: 10,
+                partition: "".to_string(),
+            })
+        );
+
+        // Check delete source.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/sources/vec-source")
+            .method("DELETE")
+            .body(source_config_body)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let index_metadata = metastore.index_metadata("hdfs-logs").await.unwrap();
+        assert!(!index_metadata.sources.contains_key("file-source"));
+
+        // Check cannot delete source managed by Quickwit.
+        let resp = warp::test::request()
+            .path(format!("/indexes/hdfs-logs/sources/{INGEST_API_SOURCE_ID}").as_str())
+            .method("DELETE")
+            .body(source_config_body)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 405);
+
+        let resp = warp::test::request()
+            .path(format!("/indexes/hdfs-logs/sources/{CLI_INGEST_SOURCE_ID}").as_str())
+            .method("DELETE")
+            .body(source_config_body)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 405);
+
+        // Check get a non existing source returns 404.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/sources/file-source")
+            .method("GET")
+            .body(source_config_body)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+
+        // Check delete index.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("DELETE")
+            .body(source_config_body)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let indexes = metastore.list_indexes_metadatas().await.unwrap();
+        assert!(indexes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_and_filter_labels() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
+        for (index_id, team) in [("payments-logs", "payments"), ("storage-logs", "storage")] {
+            let resp = warp::test::request()
+                .path("/indexes")
+                .method("POST")
+                .json(&true)
+                .body(format!(
+                    r#"{{"version": "0.6", "index_id": "{index_id}", "doc_mapping": {{}}, "labels": {{"team": "{team}"}}}}"#
+                ))
                 .reply(&index_management_handler)
                 .await;
-            assert_eq!(resp.status(), 500);
+            assert_eq!(resp.status(), 200);
+        }
+        let resp = warp::test::request()
+            .path("/indexes?labels=team:payments")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let indexes_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let indexes_arr = indexes_json.as_array().unwrap();
+        assert_eq!(indexes_arr.len(), 1);
+        assert_eq!(indexes_arr[0]["index_config"]["index_id"], "payments-logs");
+
+        // Update index labels.
+        let resp = warp::test::request()
+            .path("/indexes/storage-logs/labels")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"labels": {"team": "payments", "env": "prod"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp = warp::test::request()
+            .path("/indexes?labels=team:payments,env")
+            .reply(&index_management_handler)
+            .await;
+        let indexes_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let indexes_arr = indexes_json.as_array().unwrap();
+        assert_eq!(indexes_arr.len(), 1);
+        assert_eq!(indexes_arr[0]["index_config"]["index_id"], "storage-logs");
+
+        let resp = warp::test::request()
+            .path("/indexes/storage-logs/labels")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"labels": {"team": "pay:ments"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        // Create sources and update source labels.
+        for source_id in ["void-source-1", "void-source-2"] {
+            let resp = warp::test::request()
+                .path("/indexes/payments-logs/sources")
+                .method("POST")
+                .json(&true)
+                .body(format!(
+                    r#"{{"version": "0.6", "source_id": "{source_id}", "source_type": "void", "params": {{}}}}"#
+                ))
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
         }
+        let resp = warp::test::request()
+            .path("/indexes/payments-logs/sources/void-source-2/labels")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"labels": {"env": "staging"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp = warp::test::request()
+            .path("/indexes/payments-logs/sources?labels=env:staging")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let sources_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let sources_arr = sources_json.as_array().unwrap();
+        assert_eq!(sources_arr.len(), 1);
+        assert_eq!(sources_arr[0]["source_id"], "void-source-2");
+        assert_eq!(
+            sources_arr[0]["labels"],
+            serde_json::json!({"env": "staging"})
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes/payments-logs/sources")
+            .reply(&index_management_handler)
+            .await;
+        let sources_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        // The ingest API and CLI sources are created along with the index.
+        assert_eq!(sources_json.as_array().unwrap().len(), 4);
+
+        let resp = warp::test::request()
+            .path("/indexes/payments-logs/sources/non-existing-source/labels")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"labels": {"env": "staging"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
     }
 
     #[tokio::test]
-    async fn test_describe_index() -> anyhow::Result<()> {
-        let mut metastore = MockMetastore::new();
-        metastore
-            .expect_index_metadata()
-            .return_once(|_index_id: &str| {
-                Ok(IndexMetadata::for_test(
-                    "test-index",
-                    "ram:///indexes/test-index",
-                ))
-            });
-        let split_1 = mock_split("split_1");
-        let split_1_time_range = split_1.split_metadata.time_range.clone().unwrap();
-        let mut split_2 = mock_split("split_2");
-        split_2.split_metadata.time_range = Some(RangeInclusive::new(
-            split_1_time_range.start() - 10,
-            split_1_time_range.end() + 10,
-        ));
-        metastore
-            .expect_list_splits()
-            .return_once(|list_split_query: ListSplitsQuery| {
-                if list_split_query.index_uid.index_id() == "test-index" {
-                    return Ok(vec![split_1, split_2]);
-                }
-                Err(MetastoreError::InternalError {
-                    message: "".to_string(),
-                    cause: "".to_string(),
-                })
-            });
+    async fn test_update_indexing_settings() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
 
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
         let resp = warp::test::request()
-            .path("/indexes/test-index/describe")
+            .path("/indexes/hdfs-logs/indexing-settings")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"commit_timeout_secs": 5, "split_num_bytes_target": "100MB"}"#)
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
+        let indexing_settings_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(indexing_settings_json["commit_timeout_secs"], 5);
+        assert_eq!(
+            indexing_settings_json["split_num_docs_target"],
+            IndexingSettings::default().split_num_docs_target
+        );
 
-        let actual_response_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
-        let expected_response_json = serde_json::json!({
-            "index_id": "test-index",
-            "index_uri": "ram:///indexes/test-index",
-            "num_published_splits": 2,
-            "size_published_splits": 1600,
-            "num_published_docs": 20,
-            "size_published_docs_uncompressed": 512,
-            "timestamp_field_name": "timestamp",
-            "min_timestamp": split_1_time_range.start() - 10,
-            "max_timestamp": split_1_time_range.end() + 10,
-        });
+        let index_metadata = metastore.index_metadata("hdfs-logs").await.unwrap();
+        let indexing_settings = index_metadata.index_config.indexing_settings;
+        assert_eq!(indexing_settings.commit_timeout_secs, 5);
+        assert_eq!(
+            indexing_settings
+                .split_num_bytes_target
+                .unwrap()
+                .get_bytes(),
+            100_000_000
+        );
 
-        assert_eq!(actual_response_json, expected_response_json);
-        Ok(())
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/indexing-settings")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"split_num_docs_target": 0}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/indexing-settings")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"merge_policy": {"type": "no_merge"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/non-existing-index/indexing-settings")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"commit_timeout_secs": 5}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
     }
 
     #[tokio::test]
-    async fn test_get_all_splits() {
-        let mut metastore = MockMetastore::new();
-        metastore
-            .expect_index_metadata()
-            .return_once(|_index_id: &str| {
-                Ok(IndexMetadata::for_test(
-                    "quickwit-demo-index",
-                    "ram:///indexes/quickwit-demo-index",
-                ))
-            });
-        metastore
-            .expect_list_splits()
-            .return_once(|list_split_query: ListSplitsQuery| {
-                if list_split_query.index_uid.index_id() == "quickwit-demo-index"
-                    && list_split_query.split_states.is_empty()
-                    && list_split_query.time_range.is_unbounded()
-                    && list_split_query.create_timestamp.is_unbounded()
-                {
-                    return Ok(Vec::new());
-                }
-                Err(MetastoreError::InternalError {
-                    message: "".to_string(),
-                    cause: "".to_string(),
-                })
-            });
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
+    async fn test_lookup_tables() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
         let resp = warp::test::request()
-            .path("/indexes/quickwit-demo-index/splits")
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {}}"#)
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
-    }
 
-    #[tokio::test]
-    async fn test_mark_splits_for_deletion() -> anyhow::Result<()> {
-        let mut metastore = MockMetastore::new();
-        metastore
-            .expect_index_metadata()
-            .returning(|_index_id: &str| {
-                Ok(IndexMetadata::for_test(
-                    "quickwit-demo-index",
-                    "ram:///indexes/quickwit-demo-index",
-                ))
-            })
-            .times(2);
-        metastore
-            .expect_mark_splits_for_deletion()
-            .returning(|index_uid: IndexUid, split_ids: &[&str]| {
-                if index_uid.index_id() == "quickwit-demo-index"
-                    && split_ids == ["split-1", "split-2"]
-                {
-                    return Ok(());
-                }
-                Err(MetastoreError::InternalError {
-                    message: "".to_string(),
-                    cause: "".to_string(),
-                })
-            })
-            .times(2);
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
         let resp = warp::test::request()
-            .path("/indexes/quickwit-demo-index/splits/mark-for-deletion")
+            .path("/indexes/hdfs-logs/lookup-tables/http_status")
             .method("PUT")
             .json(&true)
-            .body(r#"{"split_ids": ["split-1", "split-2"]}"#)
+            .body(r#"{"field": "status", "rows": {"200": {"description": "OK"}}}"#)
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
+
         let resp = warp::test::request()
-            .path("/indexes/quickwit-demo-index/splits/mark-for-deletion")
-            .json(&true)
-            .body(r#"{"split_ids": [""]}"#)
+            .path("/indexes/hdfs-logs/lookup-tables/asn?field=source.asn")
             .method("PUT")
+            .header("content-type", "text/csv")
+            .body("asn,org\n13335,Cloudflare\n15169,Google\n")
             .reply(&index_management_handler)
             .await;
-        assert_eq!(resp.status(), 500);
-        Ok(())
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/lookup-tables")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let lookup_tables_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            lookup_tables_json["http_status"]["rows"]["200"]["description"],
+            "OK"
+        );
+        assert_eq!(lookup_tables_json["asn"]["field"], "source.asn");
+        assert_eq!(lookup_tables_json["asn"]["rows"]["15169"]["org"], "Google");
+
+        // The `field` query parameter is required for CSV uploads.
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/lookup-tables/asn")
+            .method("PUT")
+            .header("content-type", "text/csv")
+            .body("asn,org\n13335,Cloudflare\n")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/lookup-tables/0-invalid")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"field": "status"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/lookup-tables/http_status")
+            .method("DELETE")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let index_metadata = metastore.index_metadata("hdfs-logs").await.unwrap();
+        let lookup_tables = index_metadata.index_config.lookup_tables;
+        assert_eq!(lookup_tables.len(), 1);
+        assert!(lookup_tables.contains_key("asn"));
+
+        let resp = warp::test::request()
+            .path("/indexes/non-existing-index/lookup-tables")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
     }
 
     #[tokio::test]
-    async fn test_get_list_indexes() -> anyhow::Result<()> {
-        let mut metastore = MockMetastore::new();
-        metastore.expect_list_indexes_metadatas().return_once(|| {
-            Ok(vec![IndexMetadata::for_test(
-                "test-index",
-                "ram:///indexes/test-index",
-            )])
-        });
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
+    async fn test_search_templates() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut quickwit_config = QuickwitConfig::for_test();
+        quickwit_config.default_index_root_uri =
+            Uri::from_well_formed("file:///default-index-root-uri");
+        let index_management_handler =
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/search-templates/by_severity")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"source": {"query": {"term": {"severity_text": "{{severity}}"}}}}"#)
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
-        let actual_response_json: JsonValue = serde_json::from_slice(resp.body())?;
-        let actual_response_arr: &Vec<JsonValue> = actual_response_json.as_array().unwrap();
-        assert_eq!(actual_response_arr.len(), 1);
-        let actual_index_metadata_json: &JsonValue = &actual_response_arr[0];
-        let expected_response_json = serde_json::json!({
-            "index_id": "test-index",
-            "index_uri": "ram:///indexes/test-index",
-        });
-        assert_json_include!(
-            actual: actual_index_metadata_json.get("index_config").unwrap(),
-            expected: expected_response_json
-        );
-        Ok(())
-    }
 
-    #[tokio::test]
-    async fn test_clear_index() -> anyhow::Result<()> {
-        let mut metastore = MockMetastore::new();
-        metastore
-            .expect_index_metadata()
-            .return_once(|_index_id: &str| {
-                Ok(IndexMetadata::for_test(
-                    "quickwit-demo-index",
-                    "file:///path/to/index/quickwit-demo-index",
-                ))
-            });
-        metastore
-            .expect_list_all_splits()
-            .return_once(|_| Ok(vec![mock_split("split_1")]));
-        metastore
-            .expect_mark_splits_for_deletion()
-            .return_once(|_index_id: IndexUid, _splits: &[&str]| Ok(()));
-        metastore
-            .expect_delete_splits()
-            .return_once(|_index_id: IndexUid, _splits: &[&str]| Ok(()));
-        metastore
-            .expect_reset_source_checkpoint()
-            .return_once(|_index_id: IndexUid, _source_id: &str| Ok(()));
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
         let resp = warp::test::request()
-            .path("/indexes/quickwit-demo-index/clear")
+            .path("/indexes/hdfs-logs/search-templates/by_tenant")
             .method("PUT")
+            .json(&true)
+            .body(r#"{"source": "{\"size\": {{size}}}"}"#)
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
-        Ok(())
-    }
 
-    #[tokio::test]
-    async fn test_delete_index() {
-        let mut metastore = MockMetastore::new();
-        metastore
-            .expect_index_metadata()
-            .returning(|_index_id: &str| {
-                Ok(IndexMetadata::for_test(
-                    "quickwit-demo-index",
-                    "file:///path/to/index/quickwit-demo-index",
-                ))
-            })
-            .times(2);
-        metastore
-            .expect_list_all_splits()
-            .return_once(|_| Ok(vec![mock_split("split_1")]));
-        metastore
-            .expect_list_splits()
-            .returning(|_| Ok(vec![mock_split("split_1")]))
-            .times(2);
-        metastore
-            .expect_mark_splits_for_deletion()
-            .return_once(|_index_uid: IndexUid, _splits: &[&str]| Ok(()));
-        metastore
-            .expect_delete_splits()
-            .return_once(|_index_uid: IndexUid, _splits: &[&str]| Ok(()));
-        metastore
-            .expect_delete_index()
-            .return_once(|_index_uid: IndexUid| Ok(()));
-        let index_service = IndexService::new(Arc::new(metastore), StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
-        {
-            // Dry run
-            let resp = warp::test::request()
-                .path("/indexes/quickwit-demo-index?dry_run=true")
-                .method("DELETE")
-                .reply(&index_management_handler)
-                .await;
-            assert_eq!(resp.status(), 200);
-            let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
-            let expected_response_json = serde_json::json!([{
-                "file_name": "split_1.split",
-                "file_size_in_bytes": 800,
-            }]);
-            assert_json_include!(actual: resp_json, expected: expected_response_json);
-        }
-        {
-            let resp = warp::test::request()
-                .path("/indexes/quickwit-demo-index")
-                .method("DELETE")
-                .reply(&index_management_handler)
-                .await;
-            assert_eq!(resp.status(), 200);
-            let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
-            let expected_response_json = serde_json::json!([{
-                "file_name": "split_1.split",
-                "file_size_in_bytes": 800,
-            }]);
-            assert_json_include!(actual: resp_json, expected: expected_response_json);
-        }
-    }
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/search-templates")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let search_templates_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            search_templates_json["by_severity"]["source"],
+            r#"{"query":{"term":{"severity_text":"{{severity}}"}}}"#
+        );
+        assert_eq!(
+            search_templates_json["by_tenant"]["source"],
+            r#"{"size": {{size}}}"#
+        );
 
-    #[tokio::test]
-    async fn test_delete_on_non_existing_index() {
-        let metastore = metastore_for_test();
-        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler = super::index_management_handlers(
-            Arc::new(index_service),
-            Arc::new(QuickwitConfig::for_test()),
-        )
-        .recover(recover_fn);
         let resp = warp::test::request()
-            .path("/indexes/quickwit-demo-index")
+            .path("/indexes/hdfs-logs/search-templates/unclosed")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"source": "{{#size}}"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/search-templates/by_severity")
             .method("DELETE")
             .reply(&index_management_handler)
             .await;
-        assert_eq!(resp.status(), 404);
+        assert_eq!(resp.status(), 200);
+
+        let index_metadata = metastore.index_metadata("hdfs-logs").await.unwrap();
+        let search_templates = index_metadata.index_config.search_templates;
+        assert_eq!(search_templates.len(), 1);
+        assert!(search_templates.contains_key("by_tenant"));
     }
 
     #[tokio::test]
-    async fn test_create_index_with_overwrite() {
+    async fn test_close_freeze_and_open_index() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut quickwit_config = QuickwitConfig::for_test();
         quickwit_config.default_index_root_uri =
             Uri::from_well_formed("file:///default-index-root-uri");
         let index_management_handler =
-            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config));
-        {
-            let resp = warp::test::request()
-                .path("/indexes?overwrite=true")
-                .method("POST")
-                .json(&true)
-                .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
-                .reply(&index_management_handler)
-                .await;
-            assert_eq!(resp.status(), 200);
-        }
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        for (action, expected_state) in
+            [("close", "closed"), ("freeze", "frozen"), ("open", "open")]
         {
             let resp = warp::test::request()
-                .path("/indexes?overwrite=true")
+                .path(&format!("/indexes/hdfs-logs/{action}"))
                 .method("POST")
-                .json(&true)
-                .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
                 .reply(&index_management_handler)
                 .await;
             assert_eq!(resp.status(), 200);
-        }
-        {
+
             let resp = warp::test::request()
-                .path("/indexes")
-                .method("POST")
-                .json(&true)
-                .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+                .path("/indexes/hdfs-logs")
                 .reply(&index_management_handler)
                 .await;
-            assert_eq!(resp.status(), 400);
+            let index_metadata_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(index_metadata_json["state"], expected_state);
         }
+
+        let resp = warp::test::request()
+            .path("/indexes/non-existing-index/close")
+            .method("POST")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
     }
 
     #[tokio::test]
-    async fn test_create_delete_index_and_source() {
+    async fn test_set_retention_lock() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut quickwit_config = QuickwitConfig::for_test();
         quickwit_config.default_index_root_uri =
             Uri::from_well_formed("file:///default-index-root-uri");
         let index_management_handler =
-            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config));
+            super::index_management_handlers(Arc::new(index_service), Arc::new(quickwit_config))
+                .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
             .json(&true)
-            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+            .body(r#"{"version": "0.6", "index_id": "hdfs-logs", "doc_mapping": {}}"#)
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
-        let resp_json: serde_json::Value = serde_json::from_slice(resp.body()).unwrap();
-        let expected_response_json = serde_json::json!({
-            "index_config": {
-                "index_id": "hdfs-logs",
-                "index_uri": "file:///default-index-root-uri/hdfs-logs",
-            }
-        });
-        assert_json_include!(actual: resp_json, expected: expected_response_json);
 
-        // Create source.
-        let source_config_body = r#"{"version": "0.6", "source_id": "vec-source", "source_type": "vec", "params": {"docs": [], "batch_num_docs": 10}}"#;
+        let locked_until = time::OffsetDateTime::now_utc().unix_timestamp() + 3600;
         let resp = warp::test::request()
-            .path("/indexes/hdfs-logs/sources")
-            .method("POST")
+            .path("/indexes/hdfs-logs/retention-lock")
+            .method("PUT")
             .json(&true)
-            .body(source_config_body)
-            .reply(&index_management_handler)
-            .await;
-        assert_eq!(resp.status(), 200);
-
-        // Get source.
-        let resp = warp::test::request()
-            .path("/indexes/hdfs-logs/sources/vec-source")
-            .method("GET")
+            .body(format!(r#"{{"locked_until": {locked_until}}}"#))
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 200);
 
-        // Check that the source has been added to index metadata.
-        let index_metadata = metastore.index_metadata("hdfs-logs").await.unwrap();
-        assert!(index_metadata.sources.contains_key("vec-source"));
-        let source_config = index_metadata.sources.get("vec-source").unwrap();
-        assert_eq!(source_config.source_type(), "vec");
-        assert_eq!(
-            source_config.source_params,
-            SourceParams::Vec(VecSourceParams {
-                docs: Vec::new(),
-                batch_num_docs: 10,
-                partition: "".to_string(),
-            })
-        );
-
-        // Check delete source.
         let resp = warp::test::request()
-            .path("/indexes/hdfs-logs/sources/vec-source")
-            .method("DELETE")
-            .body(source_config_body)
+            .path("/indexes/hdfs-logs")
             .reply(&index_management_handler)
             .await;
-        assert_eq!(resp.status(), 200);
-        let index_metadata = metastore.index_metadata("hdfs-logs").await.unwrap();
-        assert!(!index_metadata.sources.contains_key("file-source"));
+        let index_metadata_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(index_metadata_json["retention_locked_until"], locked_until);
 
-        // Check cannot delete source managed by Quickwit.
+        // The lock cannot be shortened.
         let resp = warp::test::request()
-            .path(format!("/indexes/hdfs-logs/sources/{INGEST_API_SOURCE_ID}").as_str())
-            .method("DELETE")
-            .body(source_config_body)
+            .path("/indexes/hdfs-logs/retention-lock")
+            .method("PUT")
+            .json(&true)
+            .body(format!(r#"{{"locked_until": {}}}"#, locked_until - 60))
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 405);
 
         let resp = warp::test::request()
-            .path(format!("/indexes/hdfs-logs/sources/{CLI_INGEST_SOURCE_ID}").as_str())
+            .path("/indexes/hdfs-logs")
             .method("DELETE")
-            .body(source_config_body)
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 405);
+        let response_body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(response_body.contains("retention lock"));
 
-        // Check get a non existing source returns 404.
         let resp = warp::test::request()
-            .path("/indexes/hdfs-logs/sources/file-source")
-            .method("GET")
-            .body(source_config_body)
+            .path("/indexes/non-existing-index/retention-lock")
+            .method("PUT")
+            .json(&true)
+            .body(format!(r#"{{"locked_until": {locked_until}}}"#))
             .reply(&index_management_handler)
             .await;
         assert_eq!(resp.status(), 404);
-
-        // Check delete index.
-        let resp = warp::test::request()
-            .path("/indexes/hdfs-logs")
-            .method("DELETE")
-            .body(source_config_body)
-            .reply(&index_management_handler)
-            .await;
-        assert_eq!(resp.status(), 200);
-        let indexes = metastore.list_indexes_metadatas().await.unwrap();
-        assert!(indexes.is_empty());
     }
 
     #[tokio::test]
@@ -18,18 +18,29 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::convert::Infallible;
+use std::sync::Arc;
 
+use futures::future::try_join_all;
 use quickwit_actors::{AskError, Mailbox};
+use quickwit_core::IndexServiceError;
+use quickwit_grpc_clients::service_client_pool::ServiceClientPool;
 use quickwit_indexing::actors::{IndexingService, IndexingServiceCounters};
+use quickwit_indexing::indexing_client::IndexingServiceClient;
 use quickwit_indexing::models::Observe;
+use quickwit_metastore::Metastore;
+use quickwit_proto::indexing_api::{ForceCommitRequest, ForceCommitResponse};
+use tracing::info;
 use warp::{Filter, Rejection};
 
 use crate::format::extract_format_from_qs;
 use crate::json_api_response::make_json_api_response;
-use crate::require;
+use crate::{require, with_arg};
 
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(indexing_endpoint))]
+#[openapi(
+    paths(indexing_endpoint, force_commit_endpoint),
+    components(schemas(ForceCommitResponse))
+)]
 pub struct IndexingApi;
 
 #[utoipa::path(
@@ -61,3 +72,102 @@ pub fn indexing_get_handler(
         .and(extract_format_from_qs())
         .map(make_json_api_response)
 }
+
+#[utoipa::path(
+    post,
+    tag = "Indexing",
+    path = "/indexes/{index_id}/commit",
+    responses(
+        (status = 200, description = "Successfully requested a commit from the indexing pipelines of the index.", body = ForceCommitResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to commit."),
+    )
+)]
+/// Force Commit
+///
+/// Asks every indexing pipeline of the index to commit its in-progress split right away,
+/// regardless of the commit timeout. The call returns as soon as the pipelines have been notified
+/// and does not wait for the resulting splits to be published.
+async fn force_commit_endpoint(
+    index_id: String,
+    metastore: Arc<dyn Metastore>,
+    indexing_service_client_pool: ServiceClientPool<IndexingServiceClient>,
+) -> Result<ForceCommitResponse, IndexServiceError> {
+    info!(index_id = %index_id, "force-commit");
+    // Fails with a 404 if the index does not exist.
+    metastore.index_metadata(&index_id).await?;
+    let force_commit_futures =
+        indexing_service_client_pool
+            .all()
+            .into_values()
+            .map(|mut indexing_service_client| {
+                let force_commit_request = ForceCommitRequest {
+                    index_id: index_id.clone(),
+                };
+                async move {
+                    indexing_service_client
+                        .force_commit(force_commit_request)
+                        .await
+                }
+            });
+    let force_commit_responses = try_join_all(force_commit_futures)
+        .await
+        .map_err(|error| IndexServiceError::Internal(error.to_string()))?;
+    let num_pipelines = force_commit_responses
+        .iter()
+        .map(|force_commit_response| force_commit_response.num_pipelines)
+        .sum();
+    Ok(ForceCommitResponse { num_pipelines })
+}
+
+fn force_commit_filter() -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "commit").and(warp::post())
+}
+
+pub fn force_commit_handler(
+    metastore: Arc<dyn Metastore>,
+    indexing_service_client_pool: ServiceClientPool<IndexingServiceClient>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    force_commit_filter()
+        .and(with_arg(metastore))
+        .and(with_arg(indexing_service_client_pool))
+        .then(force_commit_endpoint)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::{metastore_for_test, IndexMetadata};
+
+    use super::*;
+    use crate::recover_fn;
+
+    #[tokio::test]
+    async fn test_force_commit_handler() {
+        let metastore = metastore_for_test();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        metastore.create_index(index_metadata).await.unwrap();
+        let force_commit_handler =
+            super::force_commit_handler(metastore, ServiceClientPool::default())
+                .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/commit")
+            .method("POST")
+            .reply(&force_commit_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let force_commit_response: ForceCommitResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(force_commit_response.num_pipelines, 0);
+
+        let resp = warp::test::request()
+            .path("/indexes/non-existing-index/commit")
+            .method("POST")
+            .reply(&force_commit_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+}
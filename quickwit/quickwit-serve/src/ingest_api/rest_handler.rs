@@ -94,7 +94,7 @@ fn ingest_handler(
     ),
     params(
         ("index_id" = String, Path, description = "The index ID to add docs to."),
-        ("commit" = Option<CommitType>, Query, description = "Force or wait for commit at the end of the indexing operation."),
+        ("commit" = Option<CommitType>, Query, description = "Acknowledgement level of the request: `auto` (or `queued`) returns once the documents are queued, `indexed` once they are indexed, `wait_for` (or `published`) once they are published, and `force` forces a commit and waits for it."),
     )
 )]
 /// Ingest documents
@@ -167,8 +167,8 @@ pub(crate) mod tests {
     use quickwit_config::IngestApiConfig;
     use quickwit_ingest::{
         init_ingest_api, CreateQueueIfNotExistsRequest, FetchRequest, FetchResponse,
-        IngestApiService, IngestResponse, IngestServiceClient, SuggestTruncateRequest,
-        QUEUES_DIR_NAME,
+        IngestApiService, IngestResponse, IngestServiceClient, NotifyIndexedRequest,
+        SuggestTruncateRequest, QUEUES_DIR_NAME,
     };
 
     use super::ingest_api_handlers;
@@ -302,6 +302,8 @@ pub(crate) mod tests {
                     index_id: "my-index".to_string(),
                     start_after: None,
                     num_bytes_limit: None,
+                    start_timestamp: None,
+                    keep_most_recent: false,
                 })
                 .await
                 .unwrap()
@@ -321,6 +323,36 @@ pub(crate) mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_blocks_when_indexed_is_specified() {
+        let (universe, _temp_dir, ingest_service_client, ingest_service_mailbox) =
+            setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
+        let ingest_api_handlers = ingest_api_handlers(ingest_service_client);
+        let handle = tokio::spawn(async move {
+            let resp = warp::test::request()
+                .path("/my-index/ingest?commit=indexed")
+                .method("POST")
+                .json(&true)
+                .body(r#"{"id": 1, "message": "push"}"#)
+                .reply(&ingest_api_handlers)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let ingest_response: IngestResponse = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(ingest_response.num_docs_for_processing, 1);
+        });
+        universe.sleep(Duration::from_secs(10)).await;
+        assert!(!handle.is_finished());
+        ingest_service_mailbox
+            .ask(NotifyIndexedRequest {
+                index_id: "my-index".to_string(),
+                up_to_position_included: 0,
+            })
+            .await
+            .unwrap();
+        handle.await.unwrap();
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_ingest_api_blocks_when_force_is_specified() {
         let (universe, _temp_dir, ingest_service_client, ingest_service_mailbox) =
@@ -346,6 +378,8 @@ pub(crate) mod tests {
                     index_id: "my-index".to_string(),
                     start_after: None,
                     num_bytes_limit: None,
+                    start_timestamp: None,
+                    keep_most_recent: false,
                 })
                 .await
                 .unwrap()
@@ -29,6 +29,7 @@ pub(crate) mod simple_list;
 mod build_info;
 mod cluster_api;
 mod delete_task_api;
+mod drain_api;
 mod elastic_search_api;
 mod health_check_api;
 mod index_api;
@@ -39,6 +40,7 @@ mod node_info_handler;
 mod openapi;
 mod search_api;
 mod ui_handler;
+mod ui_preferences_api;
 
 use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
@@ -53,7 +55,10 @@ use format::BodyFormat;
 use futures::{Stream, StreamExt};
 use itertools::Itertools;
 use quickwit_actors::{ActorExitStatus, Mailbox, Universe};
-use quickwit_cluster::{Cluster, ClusterChange, ClusterMember};
+use quickwit_cluster::{
+    is_compatible_protocol_version, Cluster, ClusterChange, ClusterMember,
+    MIN_COMPATIBLE_PROTOCOL_VERSION,
+};
 use quickwit_common::pubsub::{EventBroker, EventSubscriptionHandle};
 use quickwit_common::runtimes::RuntimesConfig;
 use quickwit_common::tower::{
@@ -64,10 +69,13 @@ use quickwit_config::service::QuickwitService;
 use quickwit_config::{QuickwitConfig, SearcherConfig};
 use quickwit_control_plane::{start_control_plane_service, ControlPlaneServiceClient};
 use quickwit_core::{IndexService, IndexServiceError};
+use quickwit_grpc_clients::service_client_pool::ServiceClientPool;
 use quickwit_indexing::actors::IndexingService;
+use quickwit_indexing::indexing_client::IndexingServiceClient;
 use quickwit_indexing::start_indexing_service;
 use quickwit_ingest::{
-    start_ingest_api_service, GetMemoryCapacity, IngestRequest, IngestServiceClient, MemoryCapacity,
+    start_ingest_api_service, GetMemoryCapacity, IngestRequest, IngestServiceClient, IngesterPool,
+    MemoryCapacity,
 };
 use quickwit_janitor::{start_janitor_service, JanitorService};
 use quickwit_metastore::{
@@ -76,8 +84,8 @@ use quickwit_metastore::{
 };
 use quickwit_opentelemetry::otlp::{OtlpGrpcLogsService, OtlpGrpcTracesService};
 use quickwit_search::{
-    create_search_client_from_channel, start_searcher_service, SearchJobPlacer, SearchService,
-    SearchServiceClient, SearcherPool,
+    create_search_client_from_channel, start_searcher_service, RequestTemplates, SearchJobPlacer,
+    SearchService, SearchServiceClient, SearcherPool, SplitWarmupNotifier,
 };
 use quickwit_storage::StorageResolver;
 use tokio::sync::oneshot;
@@ -87,11 +95,14 @@ use tracing::{debug, error, info, warn};
 use warp::{Filter, Rejection};
 
 pub use crate::build_info::{BuildInfo, RuntimeInfo};
-pub use crate::index_api::ListSplitsQueryParams;
+use crate::cluster_api::{cluster_history_index_config, start_cluster_history_recorder};
+use crate::drain_api::{drain_node, DrainSignal};
+pub use crate::index_api::{LabelsQueryParams, ListSplitsQueryParams};
 pub use crate::metrics::SERVE_METRICS;
 #[cfg(test)]
 use crate::rest::recover_fn;
-pub use crate::search_api::{SearchRequestQueryString, SortByField};
+pub use crate::search_api::{QuerySyntax, SearchRequestQueryString, SortByField};
+use crate::ui_preferences_api::ui_preferences_index_config;
 
 const READINESS_REPORTING_INTERVAL: Duration = if cfg!(any(test, feature = "testsuite")) {
     Duration::from_millis(25)
@@ -108,15 +119,25 @@ struct QuickwitServices {
     /// We need to keep the subscription handle to keep listening. If not, subscription is dropped.
     #[allow(dead_code)]
     pub control_plane_subscription_handle: Option<EventSubscriptionHandle<MetastoreEvent>>,
+    /// Searchers are notified of split publications so they can warm up the new splits.
+    #[allow(dead_code)]
+    pub split_warmup_subscription_handle: Option<EventSubscriptionHandle<MetastoreEvent>>,
     /// We do have a search service even on nodes that are not running `search`.
     /// It is only used to serve the rest API calls and will only execute
     /// the root requests.
     pub search_service: Arc<dyn SearchService>,
+    pub search_job_placer: SearchJobPlacer,
     pub indexing_service: Option<Mailbox<IndexingService>>,
+    /// Clients of the indexers of the cluster, used to fan out per-index indexing requests such
+    /// as force commits.
+    pub indexing_service_client_pool: ServiceClientPool<IndexingServiceClient>,
     pub janitor_service: Option<Mailbox<JanitorService>>,
     pub ingest_service: IngestServiceClient,
     pub index_service: Arc<IndexService>,
+    /// Resolves the storages the purge requests export documents to.
+    pub storage_resolver: StorageResolver,
     pub services: HashSet<QuickwitService>,
+    pub drain_signal: DrainSignal,
 }
 
 fn has_node_with_metastore_service(members: &[ClusterMember]) -> bool {
@@ -157,6 +178,9 @@ pub async fn serve_quickwit(
     let event_broker = EventBroker::default();
     let cluster =
         quickwit_cluster::start_cluster_service(&config, &config.enabled_services).await?;
+    cluster
+        .set_self_node_build_version(&BuildInfo::get().version)
+        .await;
 
     // Instantiate either a file-backed or postgresql [`Metastore`] if the node runs a `Metastore`
     // service, else instantiate a [`MetastoreGrpcClient`].
@@ -289,6 +313,52 @@ pub async fn serve_quickwit(
         (ingest_service, None)
     };
 
+    // The cluster state is recorded by the control plane node, of which there is only one in the
+    // cluster.
+    let cluster_history_config = &config.cluster_history_config;
+    if cluster_history_config.enable_recording
+        && config
+            .enabled_services
+            .contains(&QuickwitService::ControlPlane)
+    {
+        let index_config = cluster_history_index_config(&config.default_index_root_uri)?;
+        match index_service.create_index(index_config, false).await {
+            Ok(_)
+            | Err(IndexServiceError::MetastoreError(MetastoreError::IndexAlreadyExists {
+                ..
+            })) => Ok(()),
+            Err(error) => Err(error),
+        }?;
+        start_cluster_history_recorder(
+            cluster.clone(),
+            ingest_service.clone(),
+            cluster_history_config.snapshot_interval(),
+        );
+    }
+
+    // Likewise, the UI preferences index is created by the control plane node.
+    if config.ui_preferences_config.enable_api
+        && config
+            .enabled_services
+            .contains(&QuickwitService::ControlPlane)
+    {
+        let index_config = ui_preferences_index_config(&config.default_index_root_uri)?;
+        match index_service.create_index(index_config, false).await {
+            Ok(_)
+            | Err(IndexServiceError::MetastoreError(MetastoreError::IndexAlreadyExists {
+                ..
+            })) => Ok(()),
+            Err(error) => Err(error),
+        }?;
+    }
+
+    let indexing_service_client_pool =
+        ServiceClientPool::create_and_update_members(cluster.ready_members_watcher().await).await?;
+
+    let ingester_pool = setup_ingester_pool(
+        cluster.ready_nodes_change_stream().await,
+        ingest_service.clone(),
+    );
     let searcher_config = config.searcher_config.clone();
     let cluster_change_stream = cluster.ready_nodes_change_stream().await;
 
@@ -297,16 +367,32 @@ pub async fn serve_quickwit(
         cluster_change_stream,
         metastore.clone(),
         storage_resolver.clone(),
+        ingester_pool,
+        Some(cluster.clone()),
     )
     .await?;
 
+    // Metastore events are only published on the nodes running the metastore service.
+    let split_warmup_subscription_handle = if config
+        .enabled_services
+        .contains(&QuickwitService::Metastore)
+    {
+        let split_warmup_notifier =
+            SplitWarmupNotifier::new(metastore.clone(), search_job_placer.clone());
+        Some(event_broker.subscribe::<MetastoreEvent>(split_warmup_notifier))
+    } else {
+        None
+    };
+
     let janitor_service = if config.enabled_services.contains(&QuickwitService::Janitor) {
         let janitor_service = start_janitor_service(
             &universe,
             &config,
             metastore.clone(),
-            search_job_placer,
+            search_job_placer.clone(),
             storage_resolver.clone(),
+            search_service.clone(),
+            ingest_service.clone(),
         )
         .await?;
         Some(janitor_service)
@@ -317,18 +403,24 @@ pub async fn serve_quickwit(
     let grpc_listen_addr = config.grpc_listen_addr;
     let rest_listen_addr = config.rest_listen_addr;
     let services = config.enabled_services.clone();
+    let drain_signal = DrainSignal::default();
     let quickwit_services: Arc<QuickwitServices> = Arc::new(QuickwitServices {
         config: Arc::new(config),
         cluster: cluster.clone(),
         metastore: metastore.clone(),
         control_plane_service,
         control_plane_subscription_handle,
+        split_warmup_subscription_handle,
         search_service,
+        search_job_placer,
         indexing_service,
+        indexing_service_client_pool,
         janitor_service,
         ingest_service,
         index_service,
+        storage_resolver,
         services,
+        drain_signal: drain_signal.clone(),
     });
     // Setup and start gRPC server.
     let (grpc_readiness_trigger_tx, grpc_readiness_signal_rx) = oneshot::channel::<()>();
@@ -362,6 +454,7 @@ pub async fn serve_quickwit(
             debug!("REST server shutdown trigger sender was dropped.");
         }
     });
+    let indexing_service_opt = quickwit_services.indexing_service.clone();
     let rest_server = rest::start_rest_server(
         rest_listen_addr,
         quickwit_services,
@@ -372,13 +465,21 @@ pub async fn serve_quickwit(
     // Node readiness indicates that the server is ready to receive requests.
     // Thus readiness task is started once gRPC and REST servers are started.
     tokio::spawn(node_readiness_reporting_task(
-        cluster,
+        cluster.clone(),
         metastore,
         grpc_readiness_signal_rx,
         rest_readiness_signal_rx,
+        drain_signal.clone(),
     ));
     let shutdown_handle = tokio::spawn(async move {
-        shutdown_signal.await;
+        // The node is drained before shutting down, whether the shutdown was requested by a
+        // signal or via the drain REST API.
+        tokio::select! {
+            _ = shutdown_signal => {}
+            _ = drain_signal.triggered() => {}
+        }
+        drain_signal.trigger();
+        drain_node(&cluster, indexing_service_opt.as_ref()).await;
 
         if grpc_shutdown_trigger_tx.send(()).is_err() {
             debug!("gRPC server shutdown signal receiver was dropped.");
@@ -470,6 +571,8 @@ async fn setup_searcher(
     cluster_change_stream: impl Stream<Item = ClusterChange> + Send + 'static,
     metastore: Arc<dyn Metastore>,
     storage_resolver: StorageResolver,
+    ingester_pool: IngesterPool,
+    cluster_opt: Option<Cluster>,
 ) -> anyhow::Result<(SearchJobPlacer, Arc<dyn SearchService>)> {
     let searcher_pool = SearcherPool::default();
     let search_job_placer = SearchJobPlacer::new(searcher_pool.clone());
@@ -478,6 +581,8 @@ async fn setup_searcher(
         metastore,
         storage_resolver,
         search_job_placer.clone(),
+        ingester_pool,
+        cluster_opt,
     )
     .await?;
     let search_service_clone = search_service.clone();
@@ -485,19 +590,31 @@ async fn setup_searcher(
         let search_service_clone = search_service_clone.clone();
         Box::pin(async move {
             match cluster_change {
-                ClusterChange::Add(node)
+                // Updates refresh the digest of the splits cached by the searcher.
+                ClusterChange::Add(node) | ClusterChange::Update(node)
                     if node.enabled_services().contains(&QuickwitService::Searcher) =>
                 {
                     let grpc_addr = node.grpc_advertise_addr();
 
                     if node.is_self_node() {
                         let search_client =
-                            SearchServiceClient::from_service(search_service_clone, grpc_addr);
+                            SearchServiceClient::from_service(search_service_clone, grpc_addr)
+                                .with_cached_splits_digest(node.cached_splits_digest());
                         Some(Change::Insert(grpc_addr, search_client))
+                    } else if !is_compatible_protocol_version(node.protocol_version()) {
+                        warn!(
+                            node_id=%node.node_id(),
+                            protocol_version=%node.protocol_version(),
+                            min_compatible_protocol_version=%MIN_COMPATIBLE_PROTOCOL_VERSION,
+                            "Ignoring searcher speaking an incompatible protocol version."
+                        );
+                        None
                     } else {
                         let timeout_channel = Timeout::new(node.channel(), Duration::from_secs(30));
                         let search_client =
-                            create_search_client_from_channel(grpc_addr, timeout_channel);
+                            create_search_client_from_channel(grpc_addr, timeout_channel)
+                                .with_protocol_version(node.protocol_version())
+                                .with_cached_splits_digest(node.cached_splits_digest());
                         Some(Change::Insert(grpc_addr, search_client))
                     }
                 }
@@ -510,6 +627,39 @@ async fn setup_searcher(
     Ok((search_job_placer, search_service))
 }
 
+/// Maintains a pool of ingest service clients for the indexers of the cluster. The searchers rely
+/// on it to search the documents that have not been published yet in realtime mode.
+fn setup_ingester_pool(
+    cluster_change_stream: impl Stream<Item = ClusterChange> + Send + 'static,
+    local_ingest_service: IngestServiceClient,
+) -> IngesterPool {
+    let ingester_pool = IngesterPool::default();
+    let ingester_change_stream = cluster_change_stream.filter_map(move |cluster_change| {
+        let local_ingest_service = local_ingest_service.clone();
+        Box::pin(async move {
+            match cluster_change {
+                ClusterChange::Add(node)
+                    if node.enabled_services().contains(&QuickwitService::Indexer) =>
+                {
+                    let grpc_addr = node.grpc_advertise_addr();
+
+                    if node.is_self_node() {
+                        Some(Change::Insert(grpc_addr, local_ingest_service))
+                    } else {
+                        let timeout_channel = Timeout::new(node.channel(), Duration::from_secs(30));
+                        let ingest_service = IngestServiceClient::from_channel(timeout_channel);
+                        Some(Change::Insert(grpc_addr, ingest_service))
+                    }
+                }
+                ClusterChange::Remove(node) => Some(Change::Remove(node.grpc_advertise_addr())),
+                _ => None,
+            }
+        })
+    });
+    ingester_pool.listen_for_changes(ingester_change_stream);
+    ingester_pool
+}
+
 fn require<T: Clone + Send>(
     val_opt: Option<T>,
 ) -> impl Filter<Extract = (T,), Error = Rejection> + Clone {
@@ -529,12 +679,40 @@ fn with_arg<T: Clone + Send>(arg: T) -> impl Filter<Extract = (T,), Error = Infa
     warp::any().map(move || arg.clone())
 }
 
-/// Reports node readiness to chitchat cluster every 10 seconds (25 ms for tests).
+/// Extracts the search service serving the request. The requests presenting an API key in an
+/// `Authorization: ApiKey <api_key>` header are served by a search service enforcing the
+/// guardrails of the request template bound to the key, and rejected if the key is unknown.
+fn with_search_service(
+    search_service: Arc<dyn SearchService>,
+    request_templates: RequestTemplates,
+) -> impl Filter<Extract = (Arc<dyn SearchService>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization").and_then(
+        move |authorization_opt: Option<String>| {
+            let search_service = search_service.clone();
+            let request_templates = request_templates.clone();
+            async move {
+                let Some(api_key) = authorization_opt
+                    .as_deref()
+                    .and_then(|authorization| authorization.strip_prefix("ApiKey "))
+                else {
+                    return Ok(search_service);
+                };
+                request_templates
+                    .guard_search_service(api_key.trim(), search_service)
+                    .ok_or_else(|| warp::reject::custom(crate::rest::InvalidApiKey))
+            }
+        },
+    )
+}
+
+/// Reports node readiness to chitchat cluster every 10 seconds (25 ms for tests). Once the node
+/// is draining, it is reported as not ready until it shuts down.
 async fn node_readiness_reporting_task(
     cluster: Cluster,
     metastore: Arc<dyn Metastore>,
     grpc_readiness_signal_rx: oneshot::Receiver<()>,
     rest_readiness_signal_rx: oneshot::Receiver<()>,
+    drain_signal: DrainSignal,
 ) {
     if grpc_readiness_signal_rx.await.is_err() {
         // the gRPC server failed.
@@ -551,20 +729,28 @@ async fn node_readiness_reporting_task(
     let mut interval = tokio::time::interval(READINESS_REPORTING_INTERVAL);
 
     loop {
-        interval.tick().await;
+        let check_metastore_connectivity = async {
+            interval.tick().await;
 
-        let node_ready = match metastore.check_connectivity().await {
-            Ok(()) => {
-                debug!(metastore_uri=%metastore.uri(), "Metastore service is available.");
-                true
-            }
-            Err(error) => {
-                warn!(metastore_uri=%metastore.uri(), error=?error, "Metastore service is unavailable.");
-                false
+            match metastore.check_connectivity().await {
+                Ok(()) => {
+                    debug!(metastore_uri=%metastore.uri(), "Metastore service is available.");
+                    true
+                }
+                Err(error) => {
+                    warn!(metastore_uri=%metastore.uri(), error=?error, "Metastore service is unavailable.");
+                    false
+                }
             }
         };
-        cluster.set_self_node_readiness(node_ready).await;
+        tokio::select! {
+            node_ready = check_metastore_connectivity => {
+                cluster.set_self_node_readiness(node_ready).await;
+            }
+            _ = drain_signal.triggered() => break,
+        }
     }
+    cluster.set_self_node_readiness(false).await;
 }
 
 /// Displays some warnings if the cluster runs a file-backed metastore or serves file-backed
@@ -610,7 +796,9 @@ async fn check_cluster_configuration(
 #[cfg(test)]
 mod tests {
     use chitchat::transport::ChannelTransport;
-    use quickwit_cluster::{create_cluster_for_test, ClusterNode};
+    use quickwit_cluster::{
+        create_cluster_for_test, ClusterNode, LEGACY_PROTOCOL_VERSION, PROTOCOL_VERSION,
+    };
     use quickwit_common::uri::Uri;
     use quickwit_metastore::{metastore_for_test, IndexMetadata, MockMetastore};
     use quickwit_search::Job;
@@ -658,11 +846,13 @@ mod tests {
         });
         let (grpc_readiness_trigger_tx, grpc_readiness_signal_rx) = oneshot::channel();
         let (rest_readiness_trigger_tx, rest_readiness_signal_rx) = oneshot::channel();
+        let drain_signal = DrainSignal::default();
         tokio::spawn(node_readiness_reporting_task(
             cluster.clone(),
             Arc::new(metastore),
             grpc_readiness_signal_rx,
             rest_readiness_signal_rx,
+            drain_signal.clone(),
         ));
         assert!(!cluster.is_self_node_ready().await);
 
@@ -677,6 +867,17 @@ mod tests {
         metastore_readiness_tx.send(false).unwrap();
         tokio::time::sleep(Duration::from_millis(25)).await;
         assert!(!cluster.is_self_node_ready().await);
+
+        metastore_readiness_tx.send(true).unwrap();
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert!(cluster.is_self_node_ready().await);
+
+        // A draining node is no longer ready, even though the metastore is available.
+        drain_signal.trigger();
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert!(!cluster.is_self_node_ready().await);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!cluster.is_self_node_ready().await);
     }
 
     #[tokio::test]
@@ -686,10 +887,16 @@ mod tests {
         let (change_stream_tx, change_stream_rx) = mpsc::unbounded_channel();
         let change_stream = UnboundedReceiverStream::new(change_stream_rx);
         let storage_resolver = StorageResolver::unconfigured();
-        let (search_job_placer, _searcher_service) =
-            setup_searcher(searcher_config, change_stream, metastore, storage_resolver)
-                .await
-                .unwrap();
+        let (search_job_placer, _searcher_service) = setup_searcher(
+            searcher_config,
+            change_stream,
+            metastore,
+            storage_resolver,
+            IngesterPool::default(),
+            None,
+        )
+        .await
+        .unwrap();
 
         struct DummyJob(String);
 
@@ -724,7 +931,9 @@ mod tests {
             .unwrap();
 
         let node = ClusterNode::for_test("node-1", 1337, false, &["searcher"]).await;
-        change_stream_tx.send(ClusterChange::Add(node)).unwrap();
+        change_stream_tx
+            .send(ClusterChange::Add(node.clone()))
+            .unwrap();
         tokio::time::sleep(Duration::from_millis(1)).await;
 
         let searcher_client = search_job_placer
@@ -732,5 +941,82 @@ mod tests {
             .await
             .unwrap();
         assert!(!searcher_client.is_local());
+        assert_eq!(searcher_client.protocol_version(), PROTOCOL_VERSION);
+
+        change_stream_tx.send(ClusterChange::Remove(node)).unwrap();
+
+        let legacy_node = ClusterNode::for_test_with_protocol_version(
+            "node-2",
+            1339,
+            false,
+            &["searcher"],
+            LEGACY_PROTOCOL_VERSION,
+        )
+        .await;
+        change_stream_tx
+            .send(ClusterChange::Add(legacy_node.clone()))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+
+        let searcher_client = search_job_placer
+            .assign_job(DummyJob("job-1".to_string()), &HashSet::new())
+            .await
+            .unwrap();
+        assert_eq!(searcher_client.protocol_version(), LEGACY_PROTOCOL_VERSION);
+
+        change_stream_tx
+            .send(ClusterChange::Remove(legacy_node))
+            .unwrap();
+
+        let incompatible_node = ClusterNode::for_test_with_protocol_version(
+            "node-3",
+            1341,
+            false,
+            &["searcher"],
+            MIN_COMPATIBLE_PROTOCOL_VERSION - 1,
+        )
+        .await;
+        change_stream_tx
+            .send(ClusterChange::Add(incompatible_node))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+
+        search_job_placer
+            .assign_job(DummyJob("job-1".to_string()), &HashSet::new())
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_setup_ingester_pool() {
+        let (change_stream_tx, change_stream_rx) = mpsc::unbounded_channel();
+        let change_stream = UnboundedReceiverStream::new(change_stream_rx);
+        let ingester_pool = setup_ingester_pool(
+            change_stream,
+            IngestServiceClient::from(IngestServiceClient::mock()),
+        );
+        assert!(ingester_pool.is_empty().await);
+
+        let searcher_node = ClusterNode::for_test("node-1", 1337, false, &["searcher"]).await;
+        change_stream_tx
+            .send(ClusterChange::Add(searcher_node))
+            .unwrap();
+        let indexer_node = ClusterNode::for_test("node-2", 1338, false, &["indexer"]).await;
+        change_stream_tx
+            .send(ClusterChange::Add(indexer_node.clone()))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+
+        assert_eq!(ingester_pool.len().await, 1);
+        assert!(ingester_pool
+            .get(&indexer_node.grpc_advertise_addr())
+            .await
+            .is_some());
+
+        change_stream_tx
+            .send(ClusterChange::Remove(indexer_node))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        assert!(ingester_pool.is_empty().await);
     }
 }
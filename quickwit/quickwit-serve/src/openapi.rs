@@ -29,12 +29,14 @@ use utoipa::openapi::Tag;
 use utoipa::OpenApi;
 
 use crate::cluster_api::ClusterApi;
-use crate::delete_task_api::DeleteTaskApi;
+use crate::delete_task_api::{DeleteTaskApi, PurgeApi};
+use crate::drain_api::DrainApi;
 use crate::health_check_api::HealthCheckApi;
 use crate::index_api::IndexApi;
 use crate::indexing_api::IndexingApi;
 use crate::ingest_api::{IngestApi, IngestApiSchemas};
-use crate::search_api::SearchApi;
+use crate::search_api::{FrequencyAnalysisApi, SearchApi};
+use crate::ui_preferences_api::UiPreferencesApi;
 
 /// Builds the OpenApi docs structure using the registered/merged docs.
 pub fn build_docs() -> utoipa::openapi::OpenApi {
@@ -71,6 +73,7 @@ pub fn build_docs() -> utoipa::openapi::OpenApi {
         Tag::new("Cluster Info"),
         Tag::new("Indexing"),
         Tag::new("Splits"),
+        Tag::new("UI Preferences"),
     ];
     docs_base.tags = Some(tags);
 
@@ -79,10 +82,15 @@ pub fn build_docs() -> utoipa::openapi::OpenApi {
     docs_base.merge_components_and_paths(MetricsApi::openapi().with_path_prefix("/metrics"));
     docs_base.merge_components_and_paths(ClusterApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(DeleteTaskApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(PurgeApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(DrainApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(IndexApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(IndexingApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(IngestApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(SearchApi::openapi().with_path_prefix("/api/v1"));
+    docs_base
+        .merge_components_and_paths(FrequencyAnalysisApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(UiPreferencesApi::openapi().with_path_prefix("/api/v1"));
 
     // Schemas
     docs_base.merge_components_and_paths(MetastoreApiSchemas::openapi());
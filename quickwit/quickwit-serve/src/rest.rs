@@ -25,6 +25,7 @@ use hyper::{http, Method};
 use quickwit_common::metrics;
 use quickwit_common::tower::BoxFutureInfaillible;
 use quickwit_proto::ServiceErrorCode;
+use quickwit_search::RequestTemplates;
 use tower::make::Shared;
 use tower::ServiceBuilder;
 use tower_http::compression::predicate::{DefaultPredicate, Predicate, SizeAbove};
@@ -33,17 +34,21 @@ use tower_http::cors::CorsLayer;
 use tracing::{error, info};
 use warp::{redirect, Filter, Rejection, Reply};
 
-use crate::cluster_api::cluster_handler;
-use crate::delete_task_api::delete_task_api_handlers;
+use crate::cluster_api::{cluster_handler, cluster_history_handler, cluster_topology_handler};
+use crate::delete_task_api::{delete_task_api_handlers, purge_api_handlers};
+use crate::drain_api::drain_handler;
 use crate::elastic_search_api::elastic_api_handlers;
-use crate::health_check_api::health_check_handlers;
+use crate::health_check_api::{health_check_handlers, NodeServices};
 use crate::index_api::index_management_handlers;
-use crate::indexing_api::indexing_get_handler;
+use crate::indexing_api::{force_commit_handler, indexing_get_handler};
 use crate::ingest_api::ingest_api_handlers;
 use crate::json_api_response::{ApiError, JsonApiResponse};
 use crate::node_info_handler::node_info_handler;
-use crate::search_api::{search_get_handler, search_post_handler, search_stream_handler};
+use crate::search_api::{
+    frequency_analysis_handler, search_get_handler, search_post_handler, search_stream_handler,
+};
 use crate::ui_handler::ui_handler;
+use crate::ui_preferences_api::ui_preferences_handlers;
 use crate::{BodyFormat, BuildInfo, QuickwitServices, RuntimeInfo};
 
 /// The minimum size a response body must be in order to
@@ -60,6 +65,12 @@ pub(crate) struct InvalidArgument(pub String);
 
 impl warp::reject::Reject for InvalidArgument {}
 
+/// The API key presented by a request is not bound to any request template.
+#[derive(Debug)]
+pub(crate) struct InvalidApiKey;
+
+impl warp::reject::Reject for InvalidApiKey {}
+
 /// Starts REST services.
 pub(crate) async fn start_rest_server(
     rest_listen_addr: SocketAddr,
@@ -76,11 +87,15 @@ pub(crate) async fn start_rest_server(
         .map(|| warp::reply::json(&crate::openapi::build_docs()));
 
     // `/health/*` routes.
-    let health_check_routes = health_check_handlers(
-        quickwit_services.cluster.clone(),
-        quickwit_services.indexing_service.clone(),
-        quickwit_services.janitor_service.clone(),
-    );
+    let node_services = NodeServices {
+        services: quickwit_services.services.clone(),
+        grpc_advertise_addr: quickwit_services.config.grpc_advertise_addr,
+        search_job_placer: quickwit_services.search_job_placer.clone(),
+        indexing_service_opt: quickwit_services.indexing_service.clone(),
+        janitor_service_opt: quickwit_services.janitor_service.clone(),
+    };
+    let health_check_routes =
+        health_check_handlers(quickwit_services.cluster.clone(), node_services);
 
     // `/metrics` route.
     let metrics_routes = warp::path("metrics")
@@ -88,24 +103,44 @@ pub(crate) async fn start_rest_server(
         .map(metrics::metrics_handler);
 
     let ingest_service = quickwit_services.ingest_service.clone();
+    let request_templates =
+        RequestTemplates::new(&quickwit_services.config.searcher_config.request_templates);
 
     // `/api/v1/*` routes.
     let api_v1_root_url = warp::path!("api" / "v1" / ..);
     let api_v1_routes = cluster_handler(quickwit_services.cluster.clone())
+        .or(cluster_topology_handler(quickwit_services.cluster.clone()))
+        .or(cluster_history_handler(
+            quickwit_services.search_service.clone(),
+        ))
         .or(node_info_handler(
             BuildInfo::get(),
             RuntimeInfo::get(),
             quickwit_services.config.clone(),
         ))
+        .or(drain_handler(quickwit_services.drain_signal.clone()))
         .or(indexing_get_handler(
             quickwit_services.indexing_service.clone(),
         ))
-        .or(search_get_handler(quickwit_services.search_service.clone()))
+        .or(force_commit_handler(
+            quickwit_services.metastore.clone(),
+            quickwit_services.indexing_service_client_pool.clone(),
+        ))
+        .or(search_get_handler(
+            quickwit_services.search_service.clone(),
+            request_templates.clone(),
+        ))
         .or(search_post_handler(
             quickwit_services.search_service.clone(),
+            request_templates.clone(),
         ))
         .or(search_stream_handler(
             quickwit_services.search_service.clone(),
+            request_templates.clone(),
+        ))
+        .or(frequency_analysis_handler(
+            quickwit_services.search_service.clone(),
+            quickwit_services.metastore.clone(),
         ))
         .or(ingest_api_handlers(ingest_service.clone()))
         .or(index_management_handlers(
@@ -115,9 +150,20 @@ pub(crate) async fn start_rest_server(
         .or(delete_task_api_handlers(
             quickwit_services.metastore.clone(),
         ))
+        .or(purge_api_handlers(
+            quickwit_services.metastore.clone(),
+            quickwit_services.search_service.clone(),
+            quickwit_services.storage_resolver.clone(),
+        ))
         .or(elastic_api_handlers(
             quickwit_services.search_service.clone(),
             ingest_service.clone(),
+            quickwit_services.metastore.clone(),
+            request_templates,
+        ))
+        .or(ui_preferences_handlers(
+            quickwit_services.search_service.clone(),
+            ingest_service.clone(),
         ));
 
     let api_v1_root_route = api_v1_root_url.and(api_v1_routes);
@@ -200,6 +246,11 @@ fn get_status_with_error(rejection: Rejection) -> ApiError {
             service_code: ServiceErrorCode::BadRequest,
             message: error.to_string(),
         }
+    } else if rejection.find::<InvalidApiKey>().is_some() {
+        ApiError {
+            service_code: ServiceErrorCode::Unauthorized,
+            message: "Invalid API key.".to_string(),
+        }
     } else if let Some(error) = rejection.find::<InvalidJsonRequest>() {
         // Happens when the request body could not be deserialized correctly.
         ApiError {
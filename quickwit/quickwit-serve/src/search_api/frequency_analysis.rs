@@ -0,0 +1,540 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use quickwit_metastore::Metastore;
+use quickwit_proto::query_ast_from_user_text;
+use quickwit_query::query_ast::query_ast_to_json;
+use quickwit_search::{SearchError, SearchService};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use crate::json_api_response::make_json_api_response;
+use crate::simple_list::from_simple_list;
+use crate::{with_arg, BodyFormat};
+
+/// Values outside of the comparison window's top values are reported with a count of zero. In
+/// order to make that less likely, we fetch more values than requested for both windows.
+const TERMS_SIZE_FACTOR: u64 = 4;
+
+const CURRENT_WINDOW_KEY: &str = "current";
+const COMPARISON_WINDOW_KEY: &str = "comparison";
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(frequency_analysis_handler),
+    components(schemas(FrequencyAnalysisResponse, FieldValueFrequency))
+)]
+pub struct FrequencyAnalysisApi;
+
+fn default_num_values() -> u64 {
+    10
+}
+
+/// This struct represents the frequency analysis query passed to the REST API.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub struct FrequencyAnalysisRequestQueryString {
+    /// Query text. The query language is that of tantivy.
+    pub query: String,
+    // Fields to search on.
+    #[param(rename = "search_field")]
+    #[serde(default)]
+    #[serde(rename = "search_field")]
+    #[serde(deserialize_with = "from_simple_list")]
+    pub search_fields: Option<Vec<String>>,
+    /// The fast field whose top values are counted.
+    pub field: String,
+    /// Start of the current window, inclusive. This timestamp is expressed in seconds.
+    pub start_timestamp: i64,
+    /// End of the current window, exclusive. This timestamp is expressed in seconds.
+    pub end_timestamp: i64,
+    /// Start of the comparison window, inclusive. Defaults to the window of the same length
+    /// preceding the current window.
+    pub comparison_start_timestamp: Option<i64>,
+    /// End of the comparison window, exclusive. Defaults to the start of the current window.
+    pub comparison_end_timestamp: Option<i64>,
+    /// Number of values to return (by default 10).
+    #[serde(default = "default_num_values")]
+    pub num_values: u64,
+}
+
+/// Frequency of a field value in the current and comparison windows.
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct FieldValueFrequency {
+    #[schema(value_type = Object)]
+    pub value: JsonValue,
+    pub current_count: u64,
+    pub comparison_count: u64,
+    /// `current_count - comparison_count`.
+    pub delta: i64,
+    /// `current_count / comparison_count`, absent if the value does not appear in the comparison
+    /// window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, utoipa::ToSchema)]
+pub struct FrequencyAnalysisResponse {
+    /// Number of documents matching the query in the current window.
+    pub current_num_hits: u64,
+    /// Number of documents matching the query in the comparison window.
+    pub comparison_num_hits: u64,
+    /// Top values of the current window, sorted by descending count.
+    pub values: Vec<FieldValueFrequency>,
+    pub elapsed_time_micros: u64,
+}
+
+#[derive(Deserialize)]
+struct WindowsAggregationResult {
+    windows: RangeAggregationResult,
+}
+
+#[derive(Deserialize)]
+struct RangeAggregationResult {
+    buckets: HashMap<String, RangeBucket>,
+}
+
+#[derive(Deserialize)]
+struct RangeBucket {
+    doc_count: u64,
+    top_values: TermsAggregationResult,
+}
+
+#[derive(Deserialize)]
+struct TermsAggregationResult {
+    buckets: Vec<TermBucket>,
+}
+
+#[derive(Deserialize)]
+struct TermBucket {
+    key: JsonValue,
+    doc_count: u64,
+}
+
+/// Resolves the comparison window, which defaults to the window of the same length preceding the
+/// current window.
+fn comparison_window(
+    request: &FrequencyAnalysisRequestQueryString,
+) -> Result<(i64, i64), SearchError> {
+    if request.start_timestamp >= request.end_timestamp {
+        return Err(SearchError::InvalidArgument(
+            "`start_timestamp` must be lower than `end_timestamp`.".to_string(),
+        ));
+    }
+    let window_secs = request.end_timestamp - request.start_timestamp;
+    let comparison_end_timestamp = request
+        .comparison_end_timestamp
+        .unwrap_or(request.start_timestamp);
+    let comparison_start_timestamp = request
+        .comparison_start_timestamp
+        .unwrap_or(comparison_end_timestamp - window_secs);
+
+    if comparison_start_timestamp >= comparison_end_timestamp {
+        return Err(SearchError::InvalidArgument(
+            "`comparison_start_timestamp` must be lower than `comparison_end_timestamp`."
+                .to_string(),
+        ));
+    }
+    if comparison_start_timestamp < request.end_timestamp
+        && request.start_timestamp < comparison_end_timestamp
+    {
+        return Err(SearchError::InvalidArgument(
+            "The current and comparison windows must not overlap.".to_string(),
+        ));
+    }
+    Ok((comparison_start_timestamp, comparison_end_timestamp))
+}
+
+/// Builds the aggregation counting the top values of `field` in both windows. Each window is a
+/// bucket of a range aggregation on the timestamp field, which acts as a filter for its terms
+/// sub-aggregation.
+fn build_windows_aggregation(
+    timestamp_field: &str,
+    field: &str,
+    num_values: u64,
+    current_window: (i64, i64),
+    comparison_window: (i64, i64),
+) -> JsonValue {
+    let current_range = json!({
+        "key": CURRENT_WINDOW_KEY,
+        "from": current_window.0 * 1_000,
+        "to": current_window.1 * 1_000,
+    });
+    let comparison_range = json!({
+        "key": COMPARISON_WINDOW_KEY,
+        "from": comparison_window.0 * 1_000,
+        "to": comparison_window.1 * 1_000,
+    });
+    // Range buckets must be provided in ascending order.
+    let ranges = if comparison_window.0 < current_window.0 {
+        [comparison_range, current_range]
+    } else {
+        [current_range, comparison_range]
+    };
+    json!({
+        "windows": {
+            "range": {
+                "field": timestamp_field,
+                "ranges": ranges,
+                "keyed": true,
+            },
+            "aggs": {
+                "top_values": {
+                    "terms": {
+                        "field": field,
+                        "size": num_values.saturating_mul(TERMS_SIZE_FACTOR),
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn compute_field_value_frequencies(
+    current_bucket: RangeBucket,
+    comparison_bucket: RangeBucket,
+    num_values: usize,
+) -> Vec<FieldValueFrequency> {
+    let comparison_counts: HashMap<String, u64> = comparison_bucket
+        .top_values
+        .buckets
+        .into_iter()
+        .map(|term_bucket| (term_bucket.key.to_string(), term_bucket.doc_count))
+        .collect();
+    current_bucket
+        .top_values
+        .buckets
+        .into_iter()
+        .take(num_values)
+        .map(|term_bucket| {
+            let current_count = term_bucket.doc_count;
+            let comparison_count = comparison_counts
+                .get(&term_bucket.key.to_string())
+                .copied()
+                .unwrap_or_default();
+            let ratio = if comparison_count > 0 {
+                Some(current_count as f64 / comparison_count as f64)
+            } else {
+                None
+            };
+            FieldValueFrequency {
+                value: term_bucket.key,
+                current_count,
+                comparison_count,
+                delta: current_count as i64 - comparison_count as i64,
+                ratio,
+            }
+        })
+        .collect()
+}
+
+async fn frequency_analysis_endpoint(
+    index_id: String,
+    request: FrequencyAnalysisRequestQueryString,
+    search_service: &dyn SearchService,
+    metastore: &dyn Metastore,
+) -> Result<FrequencyAnalysisResponse, SearchError> {
+    let current_window = (request.start_timestamp, request.end_timestamp);
+    let comparison_window = comparison_window(&request)?;
+
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let Some(timestamp_field) = index_metadata.index_config.doc_mapping.timestamp_field else {
+        return Err(SearchError::InvalidArgument(format!(
+            "Index `{index_id}` does not define a timestamp field."
+        )));
+    };
+    let aggregation = build_windows_aggregation(
+        &timestamp_field,
+        &request.field,
+        request.num_values,
+        current_window,
+        comparison_window,
+    );
+    let query_ast = query_ast_from_user_text(&request.query, request.search_fields);
+    let search_request = quickwit_proto::SearchRequest {
+        index_id,
+        query_ast: query_ast_to_json(&query_ast)?,
+        start_timestamp: Some(current_window.0.min(comparison_window.0)),
+        end_timestamp: Some(current_window.1.max(comparison_window.1)),
+        max_hits: 0,
+        aggregation_request: Some(aggregation.to_string()),
+        ..Default::default()
+    };
+    let search_response = search_service.root_search(search_request).await?;
+    let aggregation_json = search_response.aggregation.ok_or_else(|| {
+        SearchError::InternalError("Search response is missing aggregation.".to_string())
+    })?;
+    let mut aggregation_result: WindowsAggregationResult = serde_json::from_str(&aggregation_json)?;
+    let mut take_bucket = |key: &str| {
+        aggregation_result
+            .windows
+            .buckets
+            .remove(key)
+            .ok_or_else(|| SearchError::InternalError(format!("Missing `{key}` window bucket.")))
+    };
+    let current_bucket = take_bucket(CURRENT_WINDOW_KEY)?;
+    let comparison_bucket = take_bucket(COMPARISON_WINDOW_KEY)?;
+
+    Ok(FrequencyAnalysisResponse {
+        current_num_hits: current_bucket.doc_count,
+        comparison_num_hits: comparison_bucket.doc_count,
+        values: compute_field_value_frequencies(
+            current_bucket,
+            comparison_bucket,
+            request.num_values as usize,
+        ),
+        elapsed_time_micros: search_response.elapsed_time_micros,
+    })
+}
+
+async fn frequency_analysis(
+    index_id: String,
+    request: FrequencyAnalysisRequestQueryString,
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
+) -> impl warp::Reply {
+    info!(index_id = %index_id, request =? request, "frequency_analysis");
+    let result =
+        frequency_analysis_endpoint(index_id, request, &*search_service, &*metastore).await;
+    make_json_api_response(result, BodyFormat::default())
+}
+
+fn frequency_analysis_filter(
+) -> impl Filter<Extract = (String, FrequencyAnalysisRequestQueryString), Error = Rejection> + Clone
+{
+    warp::path!(String / "frequency-analysis")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/{index_id}/frequency-analysis",
+    responses(
+        (status = 200, description = "Successfully computed the field value frequencies.", body = FrequencyAnalysisResponse)
+    ),
+    params(
+        FrequencyAnalysisRequestQueryString,
+        ("index_id" = String, Path, description = "The index ID to analyze."),
+    )
+)]
+/// Frequency Analysis
+///
+/// Returns the top values of a field for a query along with their counts in the current window
+/// and in a comparison window.
+pub fn frequency_analysis_handler(
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    frequency_analysis_filter()
+        .and(with_arg(search_service))
+        .and(with_arg(metastore))
+        .then(frequency_analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::{IndexMetadata, MockMetastore};
+    use quickwit_search::MockSearchService;
+
+    use super::*;
+    use crate::recover_fn;
+
+    fn frequency_analysis_request(
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> FrequencyAnalysisRequestQueryString {
+        FrequencyAnalysisRequestQueryString {
+            query: "*".to_string(),
+            search_fields: None,
+            field: "status".to_string(),
+            start_timestamp,
+            end_timestamp,
+            comparison_start_timestamp: None,
+            comparison_end_timestamp: None,
+            num_values: 10,
+        }
+    }
+
+    #[test]
+    fn test_comparison_window() {
+        let request = frequency_analysis_request(100, 160);
+        assert_eq!(comparison_window(&request).unwrap(), (40, 100));
+
+        let mut request = frequency_analysis_request(100, 160);
+        request.comparison_start_timestamp = Some(200);
+        request.comparison_end_timestamp = Some(300);
+        assert_eq!(comparison_window(&request).unwrap(), (200, 300));
+
+        let mut request = frequency_analysis_request(100, 160);
+        request.comparison_end_timestamp = Some(50);
+        assert_eq!(comparison_window(&request).unwrap(), (-10, 50));
+
+        let request = frequency_analysis_request(100, 100);
+        comparison_window(&request).unwrap_err();
+
+        let mut request = frequency_analysis_request(100, 160);
+        request.comparison_start_timestamp = Some(150);
+        request.comparison_end_timestamp = Some(200);
+        let error = comparison_window(&request).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: The current and comparison windows must not overlap."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frequency_analysis_filter() {
+        let (index_id, request) = warp::test::request()
+            .path(
+                "/my-index/frequency-analysis?query=level:error&field=status&start_timestamp=100&\
+                 end_timestamp=160&num_values=3",
+            )
+            .filter(&frequency_analysis_filter())
+            .await
+            .unwrap();
+        assert_eq!(index_id, "my-index");
+        assert_eq!(
+            request,
+            FrequencyAnalysisRequestQueryString {
+                query: "level:error".to_string(),
+                num_values: 3,
+                ..frequency_analysis_request(100, 160)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frequency_analysis_handler() {
+        let mut mock_metastore = MockMetastore::new();
+        mock_metastore
+            .expect_index_metadata()
+            .returning(|index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    index_id,
+                    &format!("ram:///indexes/{index_id}"),
+                ))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|search_request| {
+                assert_eq!(search_request.max_hits, 0);
+                assert_eq!(search_request.start_timestamp, Some(40));
+                assert_eq!(search_request.end_timestamp, Some(160));
+
+                let aggregation: JsonValue =
+                    serde_json::from_str(search_request.aggregation_request.as_ref().unwrap())
+                        .unwrap();
+                assert_eq!(
+                    aggregation["windows"]["range"]["ranges"],
+                    json!([
+                        {"key": "comparison", "from": 40_000, "to": 100_000},
+                        {"key": "current", "from": 100_000, "to": 160_000},
+                    ])
+                );
+                assert_eq!(
+                    aggregation["windows"]["aggs"]["top_values"]["terms"],
+                    json!({"field": "status", "size": 8})
+                );
+                let aggregation = json!({
+                    "windows": {
+                        "buckets": {
+                            "*-40000": {
+                                "key": "*-40000",
+                                "doc_count": 0,
+                                "top_values": {"buckets": [], "sum_other_doc_count": 0}
+                            },
+                            "comparison": {
+                                "key": "comparison",
+                                "doc_count": 12,
+                                "top_values": {
+                                    "buckets": [
+                                        {"key": 200, "doc_count": 10},
+                                        {"key": 500, "doc_count": 2},
+                                    ],
+                                    "sum_other_doc_count": 0
+                                }
+                            },
+                            "current": {
+                                "key": "current",
+                                "doc_count": 25,
+                                "top_values": {
+                                    "buckets": [
+                                        {"key": 500, "doc_count": 15},
+                                        {"key": 200, "doc_count": 8},
+                                        {"key": 404, "doc_count": 2},
+                                    ],
+                                    "sum_other_doc_count": 0
+                                }
+                            }
+                        }
+                    }
+                });
+                Ok(quickwit_proto::SearchResponse {
+                    aggregation: Some(aggregation.to_string()),
+                    elapsed_time_micros: 42,
+                    ..Default::default()
+                })
+            });
+        let handler =
+            frequency_analysis_handler(Arc::new(mock_search_service), Arc::new(mock_metastore))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path(
+                "/my-index/frequency-analysis?query=*&field=status&start_timestamp=100&\
+                 end_timestamp=160&num_values=2",
+            )
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json,
+            json!({
+                "current_num_hits": 25,
+                "comparison_num_hits": 12,
+                "values": [
+                    {
+                        "value": 500,
+                        "current_count": 15,
+                        "comparison_count": 2,
+                        "delta": 13,
+                        "ratio": 7.5
+                    },
+                    {
+                        "value": 200,
+                        "current_count": 8,
+                        "comparison_count": 10,
+                        "delta": -2,
+                        "ratio": 0.8
+                    }
+                ],
+                "elapsed_time_micros": 42
+            })
+        );
+    }
+}
@@ -116,4 +116,30 @@ impl grpc::SearchService for GrpcSearchAdapter {
         let leaf_search_res = self.0.leaf_list_terms(leaf_search_request).await;
         convert_to_grpc_result(leaf_search_res)
     }
+
+    #[instrument(skip(self, request))]
+    async fn warmup_splits(
+        &self,
+        request: tonic::Request<quickwit_proto::WarmupSplitsRequest>,
+    ) -> Result<tonic::Response<quickwit_proto::WarmupSplitsResponse>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let warmup_splits_request = request.into_inner();
+        let warmup_splits_res = self.0.warmup_splits(warmup_splits_request).await;
+        convert_to_grpc_result(warmup_splits_res)
+    }
+
+    #[instrument(skip(self, request))]
+    async fn fetch_cached_split_footer(
+        &self,
+        request: tonic::Request<quickwit_proto::FetchCachedSplitFooterRequest>,
+    ) -> Result<tonic::Response<quickwit_proto::FetchCachedSplitFooterResponse>, tonic::Status>
+    {
+        set_parent_span_from_request_metadata(request.metadata());
+        let fetch_cached_split_footer_request = request.into_inner();
+        let fetch_cached_split_footer_res = self
+            .0
+            .fetch_cached_split_footer(fetch_cached_split_footer_request)
+            .await;
+        convert_to_grpc_result(fetch_cached_split_footer_res)
+    }
 }
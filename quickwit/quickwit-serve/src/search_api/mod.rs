@@ -17,12 +17,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod frequency_analysis;
 mod grpc_adapter;
 mod rest_handler;
 
+pub use self::frequency_analysis::{frequency_analysis_handler, FrequencyAnalysisApi};
 pub use self::grpc_adapter::GrpcSearchAdapter;
 pub use self::rest_handler::{
-    search_get_handler, search_post_handler, search_stream_handler, SearchApi,
+    search_get_handler, search_post_handler, search_stream_handler, QuerySyntax, SearchApi,
     SearchRequestQueryString, SortByField,
 };
 
@@ -24,7 +24,12 @@ use futures::stream::StreamExt;
 use hyper::header::HeaderValue;
 use hyper::HeaderMap;
 use quickwit_proto::{query_ast_from_user_text, OutputFormat, ServiceError, SortOrder};
-use quickwit_search::{SearchError, SearchResponseRest, SearchService};
+use quickwit_query::query_ast::{QueryAst, SimpleUserInputQuery};
+use quickwit_query::BooleanOperand;
+use quickwit_search::{
+    CollectorProfileRest, RequestTemplates, SearchError, SearchProfileRest, SearchResponseRest,
+    SearchService, SplitProfileRest, SplitSearchProfileRest, WarmupProfileRest,
+};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value as JsonValue;
 use tracing::info;
@@ -34,7 +39,7 @@ use warp::{reply, Filter, Rejection, Reply};
 
 use crate::json_api_response::make_json_api_response;
 use crate::simple_list::{from_simple_list, to_simple_list};
-use crate::{with_arg, BodyFormat};
+use crate::{with_search_service, BodyFormat};
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
@@ -42,15 +47,21 @@ use crate::{with_arg, BodyFormat};
     components(schemas(
         SearchRequestQueryString,
         SearchResponseRest,
+        SearchProfileRest,
+        SplitProfileRest,
+        SplitSearchProfileRest,
+        WarmupProfileRest,
+        CollectorProfileRest,
         SortByField,
         SortOrder,
         OutputFormat,
         BodyFormat,
+        QuerySyntax,
     ),)
 )]
 pub struct SearchApi;
 
-#[derive(Debug, Eq, PartialEq, Deserialize, utoipa::ToSchema)]
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, utoipa::ToSchema)]
 pub struct SortByField {
     /// Name of the field to sort by.
     pub field_name: String,
@@ -111,16 +122,46 @@ where D: Deserializer<'de> {
     Ok(value)
 }
 
+/// Grammar of the `query` parameter of a search request.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuerySyntax {
+    /// The query language of tantivy. Malformed queries are rejected.
+    #[default]
+    Tantivy,
+    /// The simple query string syntax (`+`, `|`, `-`, quotes and trailing `*`). Parsing never
+    /// fails, which makes it suitable for queries typed by end users.
+    Simple,
+}
+
+impl QuerySyntax {
+    fn is_tantivy(&self) -> bool {
+        *self == QuerySyntax::Tantivy
+    }
+}
+
 /// This struct represents the QueryString passed to
 /// the rest API.
 #[derive(
-    Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::IntoParams, utoipa::ToSchema,
+    Debug,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    utoipa::IntoParams,
+    utoipa::ToSchema,
 )]
 #[into_params(parameter_in = Query)]
 #[serde(deny_unknown_fields)]
 pub struct SearchRequestQueryString {
-    /// Query text. The query language is that of tantivy.
+    /// Query text. The query language is that of tantivy, unless `query_syntax` is `simple`.
     pub query: String,
+    /// Grammar used to parse the query text.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "QuerySyntax::is_tantivy")]
+    pub query_syntax: QuerySyntax,
     #[param(value_type = Object)]
     #[schema(value_type = Object)]
     /// The aggregation JSON string.
@@ -168,6 +209,27 @@ pub struct SearchRequestQueryString {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_by_field: Option<SortByField>,
+    /// If set, the search also covers the documents ingested over the last
+    /// `realtime_window_secs` seconds that have not been published yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub realtime_window_secs: Option<u64>,
+    /// Position token returned by the ingest API. If set, the search waits until the documents
+    /// ingested up to that position are searchable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_token: Option<String>,
+    /// If set, the response holds a profile of the search, detailing for each split the time
+    /// spent warming it up and executing each node of the query.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub profile: bool,
+    #[param(value_type = Object)]
+    #[schema(value_type = Object)]
+    /// The JSON string of a join of the hits with a second, small index or a lookup table.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub join: Option<JsonValue>,
+    /// If set, only the first hit for each value of this fast field is returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup_by: Option<String>,
 }
 
 fn get_proto_search_by(search_request: &SearchRequestQueryString) -> (Option<i32>, Option<String>) {
@@ -190,7 +252,17 @@ async fn search_endpoint(
     // The query ast below may still contain user input query. The actual
     // parsing of the user query will happen in the root service, and might require
     // the user of the docmapper default fields (which we do not have at this point).
-    let query_ast = query_ast_from_user_text(&search_request.query, search_request.search_fields);
+    let query_ast = match search_request.query_syntax {
+        QuerySyntax::Tantivy => {
+            query_ast_from_user_text(&search_request.query, search_request.search_fields)
+        }
+        QuerySyntax::Simple => QueryAst::from(SimpleUserInputQuery {
+            user_text: search_request.query,
+            default_fields: search_request.search_fields,
+            default_operator: BooleanOperand::And,
+            flags: Default::default(),
+        }),
+    };
     let query_ast_json = serde_json::to_string(&query_ast)?;
     let search_request = quickwit_proto::SearchRequest {
         index_id,
@@ -205,6 +277,14 @@ async fn search_endpoint(
             .map(|agg| serde_json::to_string(&agg).expect("could not serialize JsonValue")),
         sort_order,
         sort_by_field,
+        realtime_window_secs: search_request.realtime_window_secs,
+        position_token: search_request.position_token,
+        track_total_hits: None,
+        profile: search_request.profile,
+        join: search_request
+            .join
+            .map(|join| serde_json::to_string(&join).expect("could not serialize JsonValue")),
+        dedup_by: search_request.dedup_by,
     };
     let search_response = search_service.root_search(search_request).await?;
     let search_response_rest = SearchResponseRest::try_from(search_response)?;
@@ -254,9 +334,10 @@ async fn search(
 /// Parses the search request from the request query string.
 pub fn search_get_handler(
     search_service: Arc<dyn SearchService>,
+    request_templates: RequestTemplates,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     search_get_filter()
-        .and(with_arg(search_service))
+        .and(with_search_service(search_service, request_templates))
         .then(search)
 }
 
@@ -279,9 +360,10 @@ pub fn search_get_handler(
 /// Parses the search request from the request body.
 pub fn search_post_handler(
     search_service: Arc<dyn SearchService>,
+    request_templates: RequestTemplates,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     search_post_filter()
-        .and(with_arg(search_service))
+        .and(with_search_service(search_service, request_templates))
         .then(search)
 }
 
@@ -300,9 +382,10 @@ pub fn search_post_handler(
 /// Stream Search Index
 pub fn search_stream_handler(
     search_service: Arc<dyn SearchService>,
+    request_templates: RequestTemplates,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     search_stream_filter()
-        .and(with_arg(search_service))
+        .and(with_search_service(search_service, request_templates))
         .then(search_stream)
 }
 
@@ -443,10 +526,20 @@ mod tests {
         mock_search_service: MockSearchService,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
         let mock_search_service_in_arc = Arc::new(mock_search_service);
-        search_get_handler(mock_search_service_in_arc.clone())
-            .or(search_post_handler(mock_search_service_in_arc.clone()))
-            .or(search_stream_handler(mock_search_service_in_arc))
-            .recover(recover_fn)
+        let request_templates = RequestTemplates::default();
+        search_get_handler(
+            mock_search_service_in_arc.clone(),
+            request_templates.clone(),
+        )
+        .or(search_post_handler(
+            mock_search_service_in_arc.clone(),
+            request_templates.clone(),
+        ))
+        .or(search_stream_handler(
+            mock_search_service_in_arc,
+            request_templates,
+        ))
+        .recover(recover_fn)
     }
 
     #[test]
@@ -458,6 +551,7 @@ mod tests {
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            profile: None,
         };
         let search_response_json: JsonValue = serde_json::to_value(search_response)?;
         let expected_search_response_json: JsonValue = json!({
@@ -655,6 +749,104 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_route_realtime_window() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&realtime_window_secs=300")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(
+            &req,
+            &super::SearchRequestQueryString {
+                query: "*".to_string(),
+                max_hits: 20,
+                realtime_window_secs: Some(300),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_position_token() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&position_token=eyJwYXJ0aXRpb24")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(
+            &req,
+            &super::SearchRequestQueryString {
+                query: "*".to_string(),
+                max_hits: 20,
+                position_token: Some("eyJwYXJ0aXRpb24".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_profile() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&profile=true")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(
+            &req,
+            &super::SearchRequestQueryString {
+                query: "*".to_string(),
+                max_hits: 20,
+                profile: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_dedup_by() {
+        let rest_search_api_filter = search_get_filter();
+        let (_, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&dedup_by=message")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(
+            &req,
+            &super::SearchRequestQueryString {
+                query: "*".to_string(),
+                max_hits: 20,
+                dedup_by: Some("message".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_post_join() {
+        let rest_search_api_filter = search_post_filter();
+        let (_, req) = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .json(&true)
+            .body(r#"{"query": "*", "join": {"field": "user_id", "index": "users"}}"#)
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(
+            &req,
+            &super::SearchRequestQueryString {
+                query: "*".to_string(),
+                max_hits: 20,
+                join: Some(json!({"field": "user_id", "index": "users"})),
+                ..Default::default()
+            }
+        );
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_route_invalid_key() -> anyhow::Result<()> {
         let resp = warp::test::request()
@@ -664,7 +856,7 @@ mod tests {
         assert_eq!(resp.status(), 400);
         let resp_json: JsonValue = serde_json::from_slice(resp.body())?;
         let exp_resp_json = serde_json::json!({
-            "message": "unknown field `end_unix_timestamp`, expected one of `query`, `aggs`, `search_field`, `snippet_fields`, `start_timestamp`, `end_timestamp`, `max_hits`, `start_offset`, `format`, `sort_by_field`"
+            "message": "unknown field `end_unix_timestamp`, expected one of `query`, `query_syntax`, `aggs`, `search_field`, `snippet_fields`, `start_timestamp`, `end_timestamp`, `max_hits`, `start_offset`, `format`, `sort_by_field`, `realtime_window_secs`, `position_token`, `profile`, `join`, `dedup_by`"
         });
         assert_eq!(resp_json, exp_resp_json);
         Ok(())
@@ -736,6 +928,38 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_simple_query_syntax() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    let query_ast: QueryAst =
+                        serde_json::from_str(&search_request.query_ast).unwrap();
+                    let QueryAst::SimpleUserInput(simple_user_input_query) = query_ast else {
+                        return false;
+                    };
+                    simple_user_input_query.user_text == "(unbalanced +"
+                        && simple_user_input_query.default_fields == Some(vec!["body".to_string()])
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        assert_eq!(
+            warp::test::request()
+                .path(
+                    "/quickwit-demo-index/search?query=%28unbalanced%20%2B&query_syntax=simple&\
+                     search_field=body"
+                )
+                .reply(&rest_search_api_handler)
+                .await
+                .status(),
+            200
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_with_index_does_not_exist() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
@@ -756,6 +980,18 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_with_unknown_api_key() {
+        let mock_search_service = MockSearchService::new();
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .header("authorization", "ApiKey unknown-key")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 401);
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_with_wrong_fieldname() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
@@ -0,0 +1,261 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use quickwit_ingest::IngestServiceClient;
+use quickwit_search::SearchService;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use warp::{Filter, Rejection};
+
+use super::ui_preferences_store::{
+    delete_ui_preference, list_ui_preferences, put_ui_preference, UiPreference, UiPreferenceKind,
+    UiPreferencesError,
+};
+use crate::format::extract_format_from_qs;
+use crate::json_api_response::make_json_api_response;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        get_ui_preferences,
+        put_ui_preference_endpoint,
+        delete_ui_preference_endpoint
+    ),
+    components(schemas(UiPreference, UiPreferenceKind))
+)]
+pub struct UiPreferencesApi;
+
+/// UI preferences handlers.
+pub fn ui_preferences_handlers(
+    search_service: Arc<dyn SearchService>,
+    ingest_service: IngestServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    get_ui_preferences_handler(search_service.clone())
+        .or(put_ui_preference_handler(ingest_service.clone()))
+        .or(delete_ui_preference_handler(search_service, ingest_service))
+}
+
+/// This struct represents the QueryString passed to
+/// the rest API to filter the preferences of a user.
+#[derive(Debug, Clone, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UiPreferencesQueryParams {
+    /// If set, only returns the preferences of this kind.
+    pub kind: Option<UiPreferenceKind>,
+}
+
+fn get_ui_preferences_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("ui" / "users" / String / "preferences")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(search_service))
+        .then(get_ui_preferences)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "UI Preferences",
+    path = "/ui/users/{user_id}/preferences",
+    responses(
+        (status = 200, description = "Successfully fetched the preferences of the user.", body = [UiPreference])
+    ),
+    params(
+        UiPreferencesQueryParams,
+        ("user_id" = String, Path, description = "The ID of the user."),
+    )
+)]
+
+/// Get the saved searches, column layouts, and time range presets of a UI user.
+async fn get_ui_preferences(
+    user_id: String,
+    query_params: UiPreferencesQueryParams,
+    search_service: Arc<dyn SearchService>,
+) -> Result<Vec<UiPreference>, UiPreferencesError> {
+    list_ui_preferences(search_service, user_id, query_params.kind).await
+}
+
+fn put_ui_preference_handler(
+    ingest_service: IngestServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("ui" / "users" / String / "preferences" / UiPreferenceKind / String)
+        .and(warp::put())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and(with_arg(ingest_service))
+        .then(put_ui_preference_endpoint)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "UI Preferences",
+    path = "/ui/users/{user_id}/preferences/{kind}/{preference_id}",
+    request_body(content = String, description = "JSON value of the preference.", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successfully saved the preference.", body = UiPreference)
+    ),
+    params(
+        ("user_id" = String, Path, description = "The ID of the user."),
+        ("kind" = UiPreferenceKind, Path, description = "The kind of the preference."),
+        ("preference_id" = String, Path, description = "The ID of the preference."),
+    )
+)]
+
+/// Create or replace a preference of a UI user.
+async fn put_ui_preference_endpoint(
+    user_id: String,
+    kind: UiPreferenceKind,
+    preference_id: String,
+    value: JsonValue,
+    ingest_service: IngestServiceClient,
+) -> Result<UiPreference, UiPreferencesError> {
+    put_ui_preference(ingest_service, user_id, kind, preference_id, value).await
+}
+
+fn delete_ui_preference_handler(
+    search_service: Arc<dyn SearchService>,
+    ingest_service: IngestServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("ui" / "users" / String / "preferences" / UiPreferenceKind / String)
+        .and(warp::delete())
+        .and(with_arg(search_service))
+        .and(with_arg(ingest_service))
+        .then(delete_ui_preference_endpoint)
+        .and(extract_format_from_qs())
+        .map(make_json_api_response)
+}
+
+#[utoipa::path(
+    delete,
+    tag = "UI Preferences",
+    path = "/ui/users/{user_id}/preferences/{kind}/{preference_id}",
+    responses(
+        (status = 200, description = "Successfully deleted the preference.", body = UiPreference)
+    ),
+    params(
+        ("user_id" = String, Path, description = "The ID of the user."),
+        ("kind" = UiPreferenceKind, Path, description = "The kind of the preference."),
+        ("preference_id" = String, Path, description = "The ID of the preference."),
+    )
+)]
+
+/// Delete a preference of a UI user.
+async fn delete_ui_preference_endpoint(
+    user_id: String,
+    kind: UiPreferenceKind,
+    preference_id: String,
+    search_service: Arc<dyn SearchService>,
+    ingest_service: IngestServiceClient,
+) -> Result<UiPreference, UiPreferencesError> {
+    delete_ui_preference(search_service, ingest_service, user_id, kind, preference_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_ingest::{CommitType, IngestResponse};
+    use quickwit_proto::SearchResponse;
+    use quickwit_search::MockSearchService;
+    use serde_json::json;
+
+    use super::*;
+    use crate::recover_fn;
+
+    #[tokio::test]
+    async fn test_put_ui_preference_handler() {
+        let mut mock_ingest_service = IngestServiceClient::mock();
+        mock_ingest_service
+            .expect_ingest()
+            .withf(|ingest_request| {
+                let doc_batch = &ingest_request.doc_batches[0];
+                ingest_request.commit == CommitType::Force as u32
+                    && doc_batch.index_id == "ui-preferences-v0_6"
+                    && doc_batch.doc_lengths.len() == 1
+            })
+            .returning(|_| {
+                Ok(IngestResponse {
+                    num_docs_for_processing: 1,
+                    position_token: None,
+                })
+            });
+        let ui_preferences_handlers = ui_preferences_handlers(
+            Arc::new(MockSearchService::new()),
+            IngestServiceClient::from(mock_ingest_service),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/ui/users/jane@example.com/preferences/saved_search/errors")
+            .json(&json!({"query": "severity_text:ERROR", "index_id": "otel-logs-v0_6"}))
+            .reply(&ui_preferences_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["kind"], "saved_search");
+        assert_eq!(resp_json["preference_id"], "errors");
+        assert_eq!(resp_json["value"]["query"], "severity_text:ERROR");
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/ui/users/jane@example.com/preferences/saved_search/my%20errors")
+            .json(&json!({"query": "severity_text:ERROR"}))
+            .reply(&ui_preferences_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/ui/users/jane@example.com/preferences/unknown_kind/errors")
+            .json(&json!({}))
+            .reply(&ui_preferences_handlers)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_delete_ui_preference_handler() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(SearchResponse {
+                num_hits: 0,
+                ..Default::default()
+            })
+        });
+        let ui_preferences_handlers = ui_preferences_handlers(
+            Arc::new(mock_search_service),
+            IngestServiceClient::from(IngestServiceClient::mock()),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .method("DELETE")
+            .path("/ui/users/jane@example.com/preferences/column_layout/default")
+            .reply(&ui_preferences_handlers)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+}
@@ -0,0 +1,539 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use quickwit_common::uri::Uri;
+use quickwit_config::{load_index_config_from_user_config, ConfigFormat, IndexConfig};
+use quickwit_ingest::{
+    CommitType, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
+    IngestServiceError,
+};
+use quickwit_proto::{SearchRequest, ServiceError, ServiceErrorCode, SortOrder};
+use quickwit_query::query_ast::{query_ast_to_json, QueryAst};
+use quickwit_search::{SearchError, SearchService};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+use time::OffsetDateTime;
+
+/// ID of the internal index storing the preferences of the UI users.
+pub const UI_PREFERENCES_INDEX_ID: &str = "ui-preferences-v0_6";
+
+/// Maximum number of records fetched when listing the preferences of a user. Every update or
+/// deletion of a preference appends a record to the index, so users with more changes than that
+/// may see their oldest preferences disappear.
+const MAX_NUM_RECORDS_PER_USER: u64 = 10_000;
+
+/// Maximum length of user and preference IDs.
+const MAX_ID_LEN: usize = 255;
+
+const UI_PREFERENCES_INDEX_CONFIG: &str = r#"
+version: 0.6
+
+index_id: ${INDEX_ID}
+
+doc_mapping:
+  mode: strict
+  field_mappings:
+    - name: timestamp
+      type: datetime
+      input_formats: [unix_timestamp]
+      output_format: unix_timestamp_millis
+      precision: milliseconds
+      fast: true
+    - name: user_id
+      type: text
+      tokenizer: raw
+    - name: kind
+      type: text
+      tokenizer: raw
+    - name: preference_id
+      type: text
+      tokenizer: raw
+    - name: deleted
+      type: bool
+    - name: value
+      type: text
+      indexed: false
+
+  timestamp_field: timestamp
+
+indexing_settings:
+  commit_timeout_secs: 10
+
+search_settings:
+  default_search_fields: []
+"#;
+
+/// Kinds of preferences the UI persists.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UiPreferenceKind {
+    /// A search query, with its time range and the index it targets.
+    SavedSearch,
+    /// The columns displayed in the search results table.
+    ColumnLayout,
+    /// A named time range.
+    TimeRangePreset,
+}
+
+impl UiPreferenceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SavedSearch => "saved_search",
+            Self::ColumnLayout => "column_layout",
+            Self::TimeRangePreset => "time_range_preset",
+        }
+    }
+}
+
+impl fmt::Display for UiPreferenceKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl FromStr for UiPreferenceKind {
+    type Err = String;
+
+    fn from_str(kind_str: &str) -> Result<Self, Self::Err> {
+        match kind_str {
+            "saved_search" => Ok(Self::SavedSearch),
+            "column_layout" => Ok(Self::ColumnLayout),
+            "time_range_preset" => Ok(Self::TimeRangePreset),
+            _ => Err(format!("Unknown UI preference kind `{kind_str}`.")),
+        }
+    }
+}
+
+/// A preference of a UI user. The value is opaque to Quickwit.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct UiPreference {
+    pub kind: UiPreferenceKind,
+    pub preference_id: String,
+    #[schema(value_type = Object)]
+    pub value: JsonValue,
+    /// Time of the last update of the preference, expressed in milliseconds since epoch.
+    pub updated_at: i64,
+}
+
+/// Record appended to the UI preferences index on every update or deletion of a preference.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct UiPreferenceRecord {
+    /// Expressed in milliseconds since epoch.
+    timestamp: i64,
+    user_id: String,
+    kind: UiPreferenceKind,
+    preference_id: String,
+    deleted: bool,
+    /// JSON-serialized value of the preference. Absent for deletions.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum UiPreferencesError {
+    #[error(
+        "The UI preferences API is not enabled. Set `ui_preferences.enable_api` to `true` in the \
+         config of the node running the control plane."
+    )]
+    NotEnabled,
+    #[error("UI preference `{kind}/{preference_id}` does not exist.")]
+    NotFound {
+        kind: UiPreferenceKind,
+        preference_id: String,
+    },
+    #[error("Invalid {name} `{id}`: {reason}")]
+    InvalidId {
+        name: &'static str,
+        id: String,
+        reason: &'static str,
+    },
+    #[error("Failed to parse UI preference record: `{0}`.")]
+    InvalidRecord(String),
+    #[error(transparent)]
+    Ingest(IngestServiceError),
+    #[error(transparent)]
+    Search(SearchError),
+}
+
+impl ServiceError for UiPreferencesError {
+    fn status_code(&self) -> ServiceErrorCode {
+        match self {
+            Self::NotEnabled => ServiceErrorCode::NotFound,
+            Self::NotFound { .. } => ServiceErrorCode::NotFound,
+            Self::InvalidId { .. } => ServiceErrorCode::BadRequest,
+            Self::InvalidRecord(_) => ServiceErrorCode::Internal,
+            Self::Ingest(ingest_error) => ingest_error.status_code(),
+            Self::Search(search_error) => search_error.status_code(),
+        }
+    }
+}
+
+impl From<IngestServiceError> for UiPreferencesError {
+    fn from(ingest_error: IngestServiceError) -> Self {
+        match ingest_error {
+            IngestServiceError::IndexNotFound { .. } => Self::NotEnabled,
+            _ => Self::Ingest(ingest_error),
+        }
+    }
+}
+
+impl From<SearchError> for UiPreferencesError {
+    fn from(search_error: SearchError) -> Self {
+        match search_error {
+            SearchError::IndexDoesNotExist { .. } => Self::NotEnabled,
+            _ => Self::Search(search_error),
+        }
+    }
+}
+
+/// Returns the config of the internal index storing the preferences of the UI users.
+pub(crate) fn ui_preferences_index_config(
+    default_index_root_uri: &Uri,
+) -> anyhow::Result<IndexConfig> {
+    let index_config_str =
+        UI_PREFERENCES_INDEX_CONFIG.replace("${INDEX_ID}", UI_PREFERENCES_INDEX_ID);
+    let index_config = load_index_config_from_user_config(
+        ConfigFormat::Yaml,
+        index_config_str.as_bytes(),
+        default_index_root_uri,
+    )?;
+    Ok(index_config)
+}
+
+/// User and preference IDs appear in URL paths, so we restrict them to characters that do not
+/// need to be percent-encoded. Email addresses are valid user IDs.
+pub(crate) fn validate_id(name: &'static str, id: &str) -> Result<(), UiPreferencesError> {
+    let reason = if id.is_empty() {
+        "must not be empty."
+    } else if id.len() > MAX_ID_LEN {
+        "must not be longer than 255 characters."
+    } else if !id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+    {
+        "must only contain ASCII alphanumeric characters, `-`, `_`, `.`, and `@`."
+    } else {
+        return Ok(());
+    };
+    Err(UiPreferencesError::InvalidId {
+        name,
+        id: id.to_string(),
+        reason,
+    })
+}
+
+async fn append_record(
+    mut ingest_service: IngestServiceClient,
+    record: &UiPreferenceRecord,
+) -> Result<(), UiPreferencesError> {
+    let mut doc_batch_builder =
+        DocBatchBuilder::new(UI_PREFERENCES_INDEX_ID.to_string()).json_writer();
+    doc_batch_builder
+        .ingest_doc(record)
+        .map_err(|error| UiPreferencesError::InvalidRecord(error.to_string()))?;
+    // Preferences are read back right after being written, so we force a commit to make the
+    // record searchable before replying.
+    let ingest_request = IngestRequest {
+        doc_batches: vec![doc_batch_builder.build()],
+        commit: CommitType::Force as u32,
+    };
+    ingest_service.ingest(ingest_request).await?;
+    Ok(())
+}
+
+/// Creates or replaces a preference of a user.
+pub(crate) async fn put_ui_preference(
+    ingest_service: IngestServiceClient,
+    user_id: String,
+    kind: UiPreferenceKind,
+    preference_id: String,
+    value: JsonValue,
+) -> Result<UiPreference, UiPreferencesError> {
+    validate_id("user ID", &user_id)?;
+    validate_id("preference ID", &preference_id)?;
+    let record = UiPreferenceRecord {
+        timestamp: now_millis(),
+        user_id,
+        kind,
+        preference_id: preference_id.clone(),
+        deleted: false,
+        value: Some(value.to_string()),
+    };
+    append_record(ingest_service, &record).await?;
+    Ok(UiPreference {
+        kind,
+        preference_id,
+        value,
+        updated_at: record.timestamp,
+    })
+}
+
+/// Deletes a preference of a user by appending a tombstone record.
+pub(crate) async fn delete_ui_preference(
+    search_service: Arc<dyn SearchService>,
+    ingest_service: IngestServiceClient,
+    user_id: String,
+    kind: UiPreferenceKind,
+    preference_id: String,
+) -> Result<UiPreference, UiPreferencesError> {
+    let ui_preferences = list_ui_preferences(search_service, user_id.clone(), Some(kind)).await?;
+    let ui_preference = ui_preferences
+        .into_iter()
+        .find(|ui_preference| ui_preference.preference_id == preference_id)
+        .ok_or_else(|| UiPreferencesError::NotFound {
+            kind,
+            preference_id: preference_id.clone(),
+        })?;
+    let record = UiPreferenceRecord {
+        timestamp: now_millis(),
+        user_id,
+        kind,
+        preference_id,
+        deleted: true,
+        value: None,
+    };
+    append_record(ingest_service, &record).await?;
+    Ok(ui_preference)
+}
+
+/// Returns the current preferences of a user, optionally restricted to a kind, sorted by kind
+/// and preference ID.
+pub(crate) async fn list_ui_preferences(
+    search_service: Arc<dyn SearchService>,
+    user_id: String,
+    kind_opt: Option<UiPreferenceKind>,
+) -> Result<Vec<UiPreference>, UiPreferencesError> {
+    validate_id("user ID", &user_id)?;
+    let mut query_ast = QueryAst::term("user_id", &user_id);
+    if let Some(kind) = kind_opt {
+        query_ast = query_ast.and(QueryAst::term("kind", kind));
+    }
+    let search_request = SearchRequest {
+        index_id: UI_PREFERENCES_INDEX_ID.to_string(),
+        query_ast: query_ast_to_json(&query_ast)
+            .expect("The query AST should be JSON serializable."),
+        max_hits: MAX_NUM_RECORDS_PER_USER,
+        sort_by_field: Some("timestamp".to_string()),
+        sort_order: Some(SortOrder::Desc as i32),
+        ..Default::default()
+    };
+    let search_response = search_service.root_search(search_request).await?;
+    let records = search_response
+        .hits
+        .into_iter()
+        .map(|hit| {
+            serde_json::from_str::<UiPreferenceRecord>(&hit.json)
+                .map_err(|error| UiPreferencesError::InvalidRecord(error.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    latest_ui_preferences(records)
+}
+
+/// Folds the records of a user, sorted from the most recent to the oldest, into the current
+/// state of their preferences.
+fn latest_ui_preferences(
+    records: Vec<UiPreferenceRecord>,
+) -> Result<Vec<UiPreference>, UiPreferencesError> {
+    let mut seen_keys: HashSet<(UiPreferenceKind, String)> = HashSet::new();
+    let mut ui_preferences = Vec::new();
+
+    for record in records {
+        if !seen_keys.insert((record.kind, record.preference_id.clone())) || record.deleted {
+            continue;
+        }
+        let value_str = record.value.as_deref().unwrap_or("null");
+        let value = serde_json::from_str(value_str)
+            .map_err(|error| UiPreferencesError::InvalidRecord(error.to_string()))?;
+        ui_preferences.push(UiPreference {
+            kind: record.kind,
+            preference_id: record.preference_id,
+            value,
+            updated_at: record.timestamp,
+        });
+    }
+    ui_preferences.sort_by(|left, right| {
+        (left.kind.as_str(), &left.preference_id).cmp(&(right.kind.as_str(), &right.preference_id))
+    });
+    Ok(ui_preferences)
+}
+
+fn now_millis() -> i64 {
+    (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::{Hit, SearchResponse};
+    use quickwit_search::MockSearchService;
+    use serde_json::json;
+
+    use super::*;
+
+    fn record(
+        timestamp: i64,
+        kind: UiPreferenceKind,
+        preference_id: &str,
+        value_opt: Option<JsonValue>,
+    ) -> UiPreferenceRecord {
+        UiPreferenceRecord {
+            timestamp,
+            user_id: "jane@example.com".to_string(),
+            kind,
+            preference_id: preference_id.to_string(),
+            deleted: value_opt.is_none(),
+            value: value_opt.map(|value| value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_ui_preferences_index_config_is_valid() {
+        let index_config =
+            ui_preferences_index_config(&Uri::from_well_formed("ram:///indexes")).unwrap();
+        assert_eq!(index_config.index_id, UI_PREFERENCES_INDEX_ID);
+        assert_eq!(
+            index_config.doc_mapping.timestamp_field.as_deref(),
+            Some("timestamp")
+        );
+    }
+
+    #[test]
+    fn test_validate_id() {
+        validate_id("user ID", "jane.doe@example.com").unwrap();
+        validate_id("preference ID", "errors-last_hour").unwrap();
+        validate_id("user ID", "").unwrap_err();
+        validate_id("user ID", &"a".repeat(256)).unwrap_err();
+        let error = validate_id("preference ID", "errors last hour").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid preference ID `errors last hour`: must only contain ASCII alphanumeric \
+             characters, `-`, `_`, `.`, and `@`."
+        );
+    }
+
+    #[test]
+    fn test_latest_ui_preferences() {
+        let records = vec![
+            record(5, UiPreferenceKind::SavedSearch, "errors", None),
+            record(
+                4,
+                UiPreferenceKind::ColumnLayout,
+                "default",
+                Some(json!(["timestamp", "severity_text"])),
+            ),
+            record(
+                3,
+                UiPreferenceKind::SavedSearch,
+                "errors",
+                Some(json!({"query": "severity_text:ERROR"})),
+            ),
+            record(
+                2,
+                UiPreferenceKind::ColumnLayout,
+                "default",
+                Some(json!(["timestamp"])),
+            ),
+            record(
+                1,
+                UiPreferenceKind::SavedSearch,
+                "warnings",
+                Some(json!({"query": "severity_text:WARN"})),
+            ),
+        ];
+        let ui_preferences = latest_ui_preferences(records).unwrap();
+        assert_eq!(
+            ui_preferences,
+            [
+                UiPreference {
+                    kind: UiPreferenceKind::ColumnLayout,
+                    preference_id: "default".to_string(),
+                    value: json!(["timestamp", "severity_text"]),
+                    updated_at: 4,
+                },
+                UiPreference {
+                    kind: UiPreferenceKind::SavedSearch,
+                    preference_id: "warnings".to_string(),
+                    value: json!({"query": "severity_text:WARN"}),
+                    updated_at: 1,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_ui_preferences() {
+        let hit_json = serde_json::to_string(&record(
+            1_684_000_000_000,
+            UiPreferenceKind::TimeRangePreset,
+            "last-15-minutes",
+            Some(json!({"start": "now-15m"})),
+        ))
+        .unwrap();
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| {
+                search_request.index_id == UI_PREFERENCES_INDEX_ID
+                    && search_request.query_ast.contains("jane@example.com")
+                    && search_request.query_ast.contains("time_range_preset")
+                    && search_request.sort_order == Some(SortOrder::Desc as i32)
+            })
+            .returning(move |_| {
+                Ok(SearchResponse {
+                    num_hits: 1,
+                    hits: vec![Hit {
+                        json: hit_json.clone(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                })
+            });
+        let ui_preferences = list_ui_preferences(
+            Arc::new(mock_search_service),
+            "jane@example.com".to_string(),
+            Some(UiPreferenceKind::TimeRangePreset),
+        )
+        .await
+        .unwrap();
+        assert_eq!(ui_preferences.len(), 1);
+        assert_eq!(ui_preferences[0].preference_id, "last-15-minutes");
+        assert_eq!(ui_preferences[0].updated_at, 1_684_000_000_000);
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Err(SearchError::IndexDoesNotExist {
+                index_id: UI_PREFERENCES_INDEX_ID.to_string(),
+            })
+        });
+        let error = list_ui_preferences(
+            Arc::new(mock_search_service),
+            "jane@example.com".to_string(),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, UiPreferencesError::NotEnabled));
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use lru::LruCache;
+use tracing::{info, warn};
+
+use crate::cache::SplitCache;
+use crate::metrics::CacheMetrics;
+use crate::OwnedBytes;
+
+const FOOTER_FILE_EXTENSION: &str = "footer";
+
+const TEMP_FILE_EXTENSION: &str = "temp";
+
+struct NeedMutDiskSplitCache {
+    capacity_in_bytes: usize,
+    // Number of bytes of the cached footers, ordered from least to most recently used.
+    footers: LruCache<String, usize>,
+    num_bytes: usize,
+    cache_metrics: &'static CacheMetrics,
+}
+
+impl Drop for NeedMutDiskSplitCache {
+    fn drop(&mut self) {
+        self.cache_metrics
+            .in_cache_count
+            .sub(self.footers.len() as i64);
+        self.cache_metrics
+            .in_cache_num_bytes
+            .sub(self.num_bytes as i64);
+    }
+}
+
+impl NeedMutDiskSplitCache {
+    fn insert(&mut self, split_id: String, num_bytes: usize) {
+        self.remove(&split_id);
+        self.footers.put(split_id, num_bytes);
+        self.num_bytes += num_bytes;
+        self.cache_metrics.in_cache_count.inc();
+        self.cache_metrics.in_cache_num_bytes.add(num_bytes as i64);
+    }
+
+    fn remove(&mut self, split_id: &str) -> Option<usize> {
+        let num_bytes = self.footers.pop(split_id)?;
+        self.num_bytes -= num_bytes;
+        self.cache_metrics.in_cache_count.dec();
+        self.cache_metrics.in_cache_num_bytes.sub(num_bytes as i64);
+        Some(num_bytes)
+    }
+
+    /// Evicts the least recently used footers until `num_bytes` more bytes fit in the cache.
+    /// Returns the IDs of the evicted splits.
+    fn make_room_for(&mut self, num_bytes: usize) -> Vec<String> {
+        let mut evicted_split_ids = Vec::new();
+
+        while self.num_bytes + num_bytes > self.capacity_in_bytes {
+            let Some((split_id, evicted_num_bytes)) = self.footers.pop_lru() else {
+                break;
+            };
+            self.num_bytes -= evicted_num_bytes;
+            self.cache_metrics.in_cache_count.dec();
+            self.cache_metrics
+                .in_cache_num_bytes
+                .sub(evicted_num_bytes as i64);
+            self.cache_metrics.evicts_num_items.inc();
+            self.cache_metrics
+                .evicts_num_bytes
+                .inc_by(evicted_num_bytes as u64);
+            evicted_split_ids.push(split_id);
+        }
+        evicted_split_ids
+    }
+}
+
+/// A disk cache for split footers (hotcache and bundle metadata), keyed by split ID.
+///
+/// Each footer is stored in its own file under the root directory and footers are evicted in
+/// least recently used order. The footers found in the root directory when the cache is opened are
+/// reloaded, so the cache survives restarts.
+pub struct DiskSplitCache {
+    root_dir: PathBuf,
+    inner: Mutex<NeedMutDiskSplitCache>,
+    temp_file_sequence: AtomicU64,
+}
+
+impl DiskSplitCache {
+    /// Opens a disk split cache in `root_dir`, creating the directory if necessary.
+    pub fn open(
+        root_dir: PathBuf,
+        capacity_in_bytes: usize,
+        cache_metrics: &'static CacheMetrics,
+    ) -> io::Result<Self> {
+        std::fs::create_dir_all(&root_dir)?;
+
+        let mut cached_footers: Vec<(SystemTime, String, usize)> = Vec::new();
+
+        for dir_entry_res in std::fs::read_dir(&root_dir)? {
+            let dir_entry = dir_entry_res?;
+            let path = dir_entry.path();
+            let extension = path.extension().and_then(|extension| extension.to_str());
+
+            if extension == Some(TEMP_FILE_EXTENSION) {
+                // Leftover of a footer write interrupted by a crash.
+                std::fs::remove_file(&path)?;
+                continue;
+            }
+            if extension != Some(FOOTER_FILE_EXTENSION) {
+                continue;
+            }
+            let Some(split_id) = path.file_stem().and_then(|file_stem| file_stem.to_str()) else {
+                continue;
+            };
+            let metadata = dir_entry.metadata()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            cached_footers.push((modified, split_id.to_string(), metadata.len() as usize));
+        }
+        // Reloaded footers are inserted from the oldest to the most recently written one.
+        cached_footers.sort_unstable();
+
+        let mut inner = NeedMutDiskSplitCache {
+            capacity_in_bytes,
+            footers: LruCache::unbounded(),
+            num_bytes: 0,
+            cache_metrics,
+        };
+        for (_modified, split_id, num_bytes) in cached_footers {
+            inner.insert(split_id, num_bytes);
+        }
+        let evicted_split_ids = inner.make_room_for(0);
+
+        for split_id in &evicted_split_ids {
+            std::fs::remove_file(footer_path(&root_dir, split_id))?;
+        }
+        info!(
+            root_dir=%root_dir.display(),
+            num_cached_splits=inner.footers.len(),
+            num_bytes=inner.num_bytes,
+            "Opened disk split cache."
+        );
+        Ok(DiskSplitCache {
+            root_dir,
+            inner: Mutex::new(inner),
+            temp_file_sequence: AtomicU64::new(0),
+        })
+    }
+
+    async fn remove_footer_files(&self, split_ids: &[String]) {
+        for split_id in split_ids {
+            let path = footer_path(&self.root_dir, split_id);
+
+            if let Err(error) = tokio::fs::remove_file(&path).await {
+                if error.kind() != io::ErrorKind::NotFound {
+                    warn!(path=%path.display(), error=?error, "Failed to remove cached footer.");
+                }
+            }
+        }
+    }
+}
+
+fn footer_path(root_dir: &Path, split_id: &str) -> PathBuf {
+    root_dir.join(format!("{split_id}.{FOOTER_FILE_EXTENSION}"))
+}
+
+/// Split IDs are used as file names, so we refuse to cache the footers of splits whose ID could
+/// escape the root directory or clash with the temporary files.
+fn is_valid_split_id(split_id: &str) -> bool {
+    !split_id.is_empty()
+        && split_id
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character))
+}
+
+#[async_trait]
+impl SplitCache for DiskSplitCache {
+    async fn get(&self, split_id: &str) -> Option<OwnedBytes> {
+        let (is_cached, cache_metrics) = {
+            let mut inner = self.inner.lock().unwrap();
+            (inner.footers.get(split_id).is_some(), inner.cache_metrics)
+        };
+
+        if !is_cached {
+            cache_metrics.misses_num_items.inc();
+            return None;
+        }
+        match tokio::fs::read(footer_path(&self.root_dir, split_id)).await {
+            Ok(footer) => {
+                cache_metrics.hits_num_items.inc();
+                cache_metrics.hits_num_bytes.inc_by(footer.len() as u64);
+                Some(OwnedBytes::new(footer))
+            }
+            Err(error) => {
+                warn!(split_id=%split_id, error=?error, "Failed to read cached footer.");
+                self.inner.lock().unwrap().remove(split_id);
+                cache_metrics.misses_num_items.inc();
+                None
+            }
+        }
+    }
+
+    async fn put(&self, _index_key: &str, split_id: String, footer: OwnedBytes) {
+        if !is_valid_split_id(&split_id) {
+            return;
+        }
+        let num_bytes = footer.len();
+        let capacity_in_bytes = self.inner.lock().unwrap().capacity_in_bytes;
+
+        if num_bytes > capacity_in_bytes {
+            warn!(
+                capacity_in_bytes = capacity_in_bytes,
+                len = num_bytes,
+                "Downloaded a split footer larger than the cache capacity."
+            );
+            return;
+        }
+        let temp_file_sequence = self.temp_file_sequence.fetch_add(1, Ordering::Relaxed);
+        let temp_path = self.root_dir.join(format!(
+            "{split_id}.{temp_file_sequence}.{TEMP_FILE_EXTENSION}"
+        ));
+
+        if let Err(error) = tokio::fs::write(&temp_path, footer.as_slice()).await {
+            warn!(path=%temp_path.display(), error=?error, "Failed to write cached footer.");
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return;
+        }
+        let path = footer_path(&self.root_dir, &split_id);
+
+        if let Err(error) = tokio::fs::rename(&temp_path, &path).await {
+            warn!(path=%path.display(), error=?error, "Failed to write cached footer.");
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return;
+        }
+        let evicted_split_ids = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.remove(&split_id);
+            let evicted_split_ids = inner.make_room_for(num_bytes);
+            inner.insert(split_id, num_bytes);
+            evicted_split_ids
+        };
+        self.remove_footer_files(&evicted_split_ids).await;
+    }
+
+    fn contains(&self, split_id: &str) -> bool {
+        self.inner.lock().unwrap().footers.contains(split_id)
+    }
+
+    fn split_ids(&self) -> Vec<String> {
+        self.inner
+            .lock()
+            .unwrap()
+            .footers
+            .iter()
+            .map(|(split_id, _)| split_id.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::CACHE_METRICS_FOR_TESTS;
+
+    fn footer(num_bytes: usize) -> OwnedBytes {
+        OwnedBytes::new(vec![0u8; num_bytes])
+    }
+
+    #[tokio::test]
+    async fn test_disk_split_cache_evicts_least_recently_used_footers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_dir = temp_dir.path().to_path_buf();
+        let cache = DiskSplitCache::open(root_dir.clone(), 10, &CACHE_METRICS_FOR_TESTS).unwrap();
+        cache.put("index", "split-1".to_string(), footer(4)).await;
+        cache.put("index", "split-2".to_string(), footer(4)).await;
+
+        assert_eq!(cache.get("split-1").await.unwrap().len(), 4);
+        cache.put("index", "split-3".to_string(), footer(4)).await;
+
+        assert!(cache.get("split-1").await.is_some());
+        assert!(cache.get("split-2").await.is_none());
+        assert!(cache.get("split-3").await.is_some());
+        assert!(!footer_path(&root_dir, "split-2").try_exists().unwrap());
+
+        // The footer does not fit in the cache.
+        cache.put("index", "split-4".to_string(), footer(11)).await;
+        assert!(cache.get("split-4").await.is_none());
+
+        let mut split_ids = cache.split_ids();
+        split_ids.sort();
+        assert_eq!(split_ids, ["split-1", "split-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_disk_split_cache_reloads_footers_on_open() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_dir = temp_dir.path().to_path_buf();
+        {
+            let cache =
+                DiskSplitCache::open(root_dir.clone(), 10, &CACHE_METRICS_FOR_TESTS).unwrap();
+            cache
+                .put(
+                    "index",
+                    "split-1".to_string(),
+                    OwnedBytes::new(&b"footer"[..]),
+                )
+                .await;
+        }
+        std::fs::write(root_dir.join("split-2.0.temp"), b"partial").unwrap();
+
+        let cache = DiskSplitCache::open(root_dir.clone(), 10, &CACHE_METRICS_FOR_TESTS).unwrap();
+        assert_eq!(cache.split_ids(), ["split-1"]);
+        assert_eq!(cache.get("split-1").await.unwrap().as_slice(), b"footer");
+        assert!(!root_dir.join("split-2.0.temp").try_exists().unwrap());
+
+        // Reopening the cache with a smaller capacity evicts footers.
+        drop(cache);
+        let cache = DiskSplitCache::open(root_dir.clone(), 5, &CACHE_METRICS_FOR_TESTS).unwrap();
+        assert!(cache.split_ids().is_empty());
+        assert!(!footer_path(&root_dir, "split-1").try_exists().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_disk_split_cache_rejects_invalid_split_ids() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache =
+            DiskSplitCache::open(temp_dir.path().to_path_buf(), 10, &CACHE_METRICS_FOR_TESTS)
+                .unwrap();
+        cache.put("index", "../split".to_string(), footer(4)).await;
+        assert!(cache.split_ids().is_empty());
+        assert!(cache.get("../split").await.is_none());
+    }
+}
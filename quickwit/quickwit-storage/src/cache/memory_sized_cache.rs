@@ -164,6 +164,10 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
             }
             if let Some((_, bytes)) = self.lru_cache.pop_lru() {
                 self.drop_item(bytes.len() as u64);
+                self.cache_counters.evicts_num_items.inc();
+                self.cache_counters
+                    .evicts_num_bytes
+                    .inc_by(bytes.len() as u64);
             } else {
                 error!(
                     "Logical error. Even after removing all of the items in the cache the \
@@ -18,9 +18,12 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod byte_range_cache;
+mod disk_split_cache;
 mod memory_sized_cache;
 mod quickwit_cache;
+mod remote_split_cache;
 mod slice_address;
+mod split_footer_cache;
 mod storage_with_cache;
 mod stored_item;
 
@@ -33,7 +36,10 @@ pub use quickwit_cache::QuickwitCache;
 pub use storage_with_cache::StorageWithCache;
 
 pub use self::byte_range_cache::ByteRangeCache;
+pub use self::disk_split_cache::DiskSplitCache;
 pub use self::memory_sized_cache::MemorySizedCache;
+pub use self::remote_split_cache::RemoteSplitCache;
+pub use self::split_footer_cache::SplitFooterCache;
 use crate::{OwnedBytes, Storage};
 
 /// Wraps the given directory with a slice cache that is actually global
@@ -68,3 +74,22 @@ pub trait Cache: Send + Sync + 'static {
     /// Put an entire file into the cache.
     async fn put_all(&self, path: PathBuf, bytes: OwnedBytes);
 }
+
+/// The `SplitCache` trait is the abstraction used to describe the cache holding the split footers
+/// (hotcache and bundle metadata) fetched by a searcher, keyed by split ID.
+#[async_trait]
+pub trait SplitCache: Send + Sync + 'static {
+    /// If available, returns the cached footer of the split.
+    async fn get(&self, split_id: &str) -> Option<OwnedBytes>;
+    /// Attempts to put the footer of a split belonging to the index identified by `index_key` in
+    /// the cache. This may fail silently, for instance if the footer is larger than the cache
+    /// capacity.
+    async fn put(&self, index_key: &str, split_id: String, footer: OwnedBytes);
+    /// Returns whether the footer of the split is known to be cached, without counting it as an
+    /// access. Caches that cannot answer cheaply return `false`.
+    fn contains(&self, split_id: &str) -> bool;
+    /// Returns the IDs of the splits whose footer is cached and that the searcher should
+    /// advertise to the other nodes of the cluster. Caches shared by several searchers return an
+    /// empty list since routing search jobs to a specific searcher brings no benefit.
+    fn split_ids(&self) -> Vec<String>;
+}
@@ -0,0 +1,304 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use quickwit_config::RemoteCacheProtocol;
+use redis::aio::ConnectionManager;
+use tokio::sync::OnceCell;
+use tracing::{debug, warn};
+
+use crate::cache::SplitCache;
+use crate::metrics::CacheMetrics;
+use crate::OwnedBytes;
+
+/// Prefix of the keys of the footers in the cache server, which may be shared with other
+/// applications.
+const KEY_PREFIX: &str = "quickwit:split_footer:";
+
+/// Maximum number of connections opened to a memcached server.
+const MAX_NUM_MEMCACHED_CONNECTIONS: u32 = 16;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Memcached interprets expiration delays longer than 30 days as Unix timestamps.
+const MEMCACHED_MAX_TTL_SECS: u64 = 30 * 24 * 3_600;
+
+/// Client of the cache server. The memcached client is blocking, so its requests run on the
+/// blocking thread pool.
+enum RemoteCacheClient {
+    Memcached(memcache::Client),
+    Redis(ConnectionManager),
+}
+
+impl RemoteCacheClient {
+    async fn connect(protocol: RemoteCacheProtocol, endpoint: &str) -> anyhow::Result<Self> {
+        match protocol {
+            RemoteCacheProtocol::Memcached => {
+                let url = format!(
+                    "memcache://{endpoint}?protocol=ascii&tcp_nodelay=true&timeout={}",
+                    REQUEST_TIMEOUT.as_secs()
+                );
+                let client = tokio::task::spawn_blocking(move || {
+                    memcache::Client::with_pool_size(url, MAX_NUM_MEMCACHED_CONNECTIONS)
+                })
+                .await??;
+                Ok(RemoteCacheClient::Memcached(client))
+            }
+            RemoteCacheProtocol::Redis => {
+                let client = redis::Client::open(format!("redis://{endpoint}"))?;
+                let connection_manager = ConnectionManager::new(client).await?;
+                Ok(RemoteCacheClient::Redis(connection_manager))
+            }
+        }
+    }
+
+    async fn get(&self, key: String) -> anyhow::Result<Option<Vec<u8>>> {
+        match self {
+            RemoteCacheClient::Memcached(client) => {
+                let client = client.clone();
+                let value_opt =
+                    tokio::task::spawn_blocking(move || client.get::<Vec<u8>>(&key)).await??;
+                Ok(value_opt)
+            }
+            RemoteCacheClient::Redis(connection_manager) => {
+                let mut connection = connection_manager.clone();
+                let value_opt = redis::cmd("GET")
+                    .arg(key)
+                    .query_async::<_, Option<Vec<u8>>>(&mut connection)
+                    .await?;
+                Ok(value_opt)
+            }
+        }
+    }
+
+    async fn set(
+        &self,
+        key: String,
+        value: OwnedBytes,
+        ttl_secs_opt: Option<NonZeroU64>,
+    ) -> anyhow::Result<()> {
+        match self {
+            RemoteCacheClient::Memcached(client) => {
+                let client = client.clone();
+                let expiration =
+                    ttl_secs_opt.map_or(0, |ttl_secs| ttl_secs.get().min(MEMCACHED_MAX_TTL_SECS));
+                tokio::task::spawn_blocking(move || {
+                    client.set(&key, value.as_slice(), expiration as u32)
+                })
+                .await??;
+            }
+            RemoteCacheClient::Redis(connection_manager) => {
+                let mut connection = connection_manager.clone();
+                let mut set_command = redis::cmd("SET");
+                set_command.arg(key).arg(value.as_slice());
+
+                if let Some(ttl_secs) = ttl_secs_opt {
+                    set_command.arg("EX").arg(ttl_secs.get());
+                }
+                set_command.query_async::<_, ()>(&mut connection).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A split footer cache backed by a cache server speaking the memcached or Redis protocol, and
+/// shared by the searchers of the cluster.
+///
+/// The cache server owns the eviction policy. Errors talking to the server are logged and treated
+/// as cache misses.
+pub struct RemoteSplitCache {
+    protocol: RemoteCacheProtocol,
+    endpoint: String,
+    ttl_secs_opt: Option<NonZeroU64>,
+    max_item_size: usize,
+    client: OnceCell<RemoteCacheClient>,
+    cache_metrics: &'static CacheMetrics,
+}
+
+impl RemoteSplitCache {
+    /// Creates a remote split cache. The connection to the cache server is opened lazily.
+    /// Footers larger than `max_item_size` are not cached, since cache servers reject them.
+    pub fn new(
+        protocol: RemoteCacheProtocol,
+        endpoint: String,
+        ttl_secs_opt: Option<NonZeroU64>,
+        max_item_size: usize,
+        cache_metrics: &'static CacheMetrics,
+    ) -> Self {
+        RemoteSplitCache {
+            protocol,
+            endpoint,
+            ttl_secs_opt,
+            max_item_size,
+            client: OnceCell::new(),
+            cache_metrics,
+        }
+    }
+
+    async fn client(&self) -> anyhow::Result<&RemoteCacheClient> {
+        self.client
+            .get_or_try_init(|| RemoteCacheClient::connect(self.protocol, &self.endpoint))
+            .await
+    }
+
+    async fn fetch(&self, key: String) -> anyhow::Result<Option<Vec<u8>>> {
+        let fetch_future = async { self.client().await?.get(key).await };
+        tokio::time::timeout(REQUEST_TIMEOUT, fetch_future)
+            .await
+            .context("Request to the cache server timed out.")?
+    }
+
+    async fn store(&self, key: String, value: OwnedBytes) -> anyhow::Result<()> {
+        let store_future = async {
+            self.client()
+                .await?
+                .set(key, value, self.ttl_secs_opt)
+                .await
+        };
+        tokio::time::timeout(REQUEST_TIMEOUT, store_future)
+            .await
+            .context("Request to the cache server timed out.")?
+    }
+}
+
+#[async_trait]
+impl SplitCache for RemoteSplitCache {
+    async fn get(&self, split_id: &str) -> Option<OwnedBytes> {
+        let key = format!("{KEY_PREFIX}{split_id}");
+
+        match self.fetch(key).await {
+            Ok(Some(footer)) => {
+                self.cache_metrics.hits_num_items.inc();
+                self.cache_metrics
+                    .hits_num_bytes
+                    .inc_by(footer.len() as u64);
+                Some(OwnedBytes::new(footer))
+            }
+            Ok(None) => {
+                self.cache_metrics.misses_num_items.inc();
+                None
+            }
+            Err(error) => {
+                warn!(
+                    endpoint=%self.endpoint,
+                    split_id=%split_id,
+                    error=?error,
+                    "Failed to fetch footer from remote split cache."
+                );
+                self.cache_metrics.misses_num_items.inc();
+                None
+            }
+        }
+    }
+
+    async fn put(&self, _index_key: &str, split_id: String, footer: OwnedBytes) {
+        // The cache server would reject the footer: skip the round-trip.
+        if footer.len() > self.max_item_size {
+            debug!(
+                split_id=%split_id,
+                num_bytes=footer.len(),
+                max_item_size=self.max_item_size,
+                "Footer too large for remote split cache."
+            );
+            return;
+        }
+        let key = format!("{KEY_PREFIX}{split_id}");
+
+        if let Err(error) = self.store(key, footer).await {
+            warn!(
+                endpoint=%self.endpoint,
+                split_id=%split_id,
+                error=?error,
+                "Failed to store footer in remote split cache."
+            );
+        }
+    }
+
+    fn contains(&self, _split_id: &str) -> bool {
+        false
+    }
+
+    fn split_ids(&self) -> Vec<String> {
+        // The cache is shared by all the searchers.
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::metrics::CACHE_METRICS_FOR_TESTS;
+
+    #[tokio::test]
+    async fn test_remote_split_cache_skips_large_footers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let cache = RemoteSplitCache::new(
+            RemoteCacheProtocol::Memcached,
+            addr.to_string(),
+            None,
+            4,
+            &CACHE_METRICS_FOR_TESTS,
+        );
+        cache
+            .put(
+                "index",
+                "split-1".to_string(),
+                OwnedBytes::new(&b"footer"[..]),
+            )
+            .await;
+        // The footer exceeds the max item size: the cache server was not contacted.
+        tokio::time::timeout(Duration::from_millis(100), listener.accept())
+            .await
+            .unwrap_err();
+        assert!(cache.client.get().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remote_split_cache_unreachable_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let cache = RemoteSplitCache::new(
+            RemoteCacheProtocol::Redis,
+            addr.to_string(),
+            None,
+            1_000_000,
+            &CACHE_METRICS_FOR_TESTS,
+        );
+        cache
+            .put(
+                "index",
+                "split-1".to_string(),
+                OwnedBytes::new(&b"footer"[..]),
+            )
+            .await;
+        assert!(cache.get("split-1").await.is_none());
+        assert!(cache.split_ids().is_empty());
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::cache::SplitCache;
+use crate::metrics::CacheMetrics;
+use crate::OwnedBytes;
+
+/// Eviction priority of a cached footer. Priorities are non-negative floats, so ordering their bit
+/// patterns is equivalent to ordering the floats. Ties are broken by insertion order.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+struct PriorityKey {
+    priority_bits: u64,
+    sequence: u64,
+}
+
+impl PriorityKey {
+    fn priority(&self) -> f64 {
+        f64::from_bits(self.priority_bits)
+    }
+}
+
+struct CachedFooter {
+    index_key: String,
+    footer: OwnedBytes,
+    num_hits: u64,
+    priority_key: PriorityKey,
+}
+
+struct NeedMutSplitFooterCache {
+    capacity_in_bytes: usize,
+    per_index_capacity_in_bytes: Option<usize>,
+    footers: HashMap<String, CachedFooter>,
+    // Split IDs ordered by increasing eviction priority.
+    eviction_queue: BTreeMap<PriorityKey, String>,
+    index_num_bytes: HashMap<String, usize>,
+    num_bytes: usize,
+    // Priority of the last evicted footer. Entries accessed recently start from this value, which
+    // ages out entries that were popular in the past but are no longer accessed.
+    clock: f64,
+    sequence: u64,
+    cache_metrics: &'static CacheMetrics,
+}
+
+impl Drop for NeedMutSplitFooterCache {
+    fn drop(&mut self) {
+        self.cache_metrics
+            .in_cache_count
+            .sub(self.footers.len() as i64);
+        self.cache_metrics
+            .in_cache_num_bytes
+            .sub(self.num_bytes as i64);
+    }
+}
+
+impl NeedMutSplitFooterCache {
+    /// Computes the priority of a footer according to the Greedy-Dual-Size-Frequency policy:
+    /// frequently accessed and small footers are kept longer than large footers accessed once.
+    fn next_priority_key(&mut self, num_hits: u64, num_bytes: usize) -> PriorityKey {
+        let priority = self.clock + num_hits as f64 / num_bytes.max(1) as f64;
+        self.sequence += 1;
+        PriorityKey {
+            priority_bits: priority.to_bits(),
+            sequence: self.sequence,
+        }
+    }
+
+    fn contains(&self, split_id: &str) -> bool {
+        self.footers.contains_key(split_id)
+    }
+
+    fn get(&mut self, split_id: &str) -> Option<OwnedBytes> {
+        let Some(cached_footer) = self.footers.get(split_id) else {
+            self.cache_metrics.misses_num_items.inc();
+            return None;
+        };
+        let num_hits = cached_footer.num_hits + 1;
+        let num_bytes = cached_footer.footer.len();
+        let previous_priority_key = cached_footer.priority_key;
+        let priority_key = self.next_priority_key(num_hits, num_bytes);
+
+        let split_id = self
+            .eviction_queue
+            .remove(&previous_priority_key)
+            .expect("The eviction queue should contain all the cached footers.");
+        let cached_footer = self
+            .footers
+            .get_mut(&split_id)
+            .expect("The footer should be cached.");
+        cached_footer.num_hits = num_hits;
+        cached_footer.priority_key = priority_key;
+        let footer = cached_footer.footer.clone();
+        self.eviction_queue.insert(priority_key, split_id);
+
+        self.cache_metrics.hits_num_items.inc();
+        self.cache_metrics.hits_num_bytes.inc_by(num_bytes as u64);
+        Some(footer)
+    }
+
+    fn put(&mut self, index_key: &str, split_id: String, footer: OwnedBytes) {
+        let num_bytes = footer.len();
+        let max_num_bytes = self
+            .per_index_capacity_in_bytes
+            .map_or(self.capacity_in_bytes, |per_index_capacity_in_bytes| {
+                per_index_capacity_in_bytes.min(self.capacity_in_bytes)
+            });
+        if num_bytes > max_num_bytes {
+            // The footer does not fit in the cache. We simply don't store it.
+            if max_num_bytes != 0 {
+                warn!(
+                    capacity_in_bytes = max_num_bytes,
+                    len = num_bytes,
+                    "Downloaded a split footer larger than the cache capacity."
+                );
+            }
+            return;
+        }
+        self.remove(&split_id);
+
+        if let Some(per_index_capacity_in_bytes) = self.per_index_capacity_in_bytes {
+            while self.index_num_bytes.get(index_key).copied().unwrap_or(0) + num_bytes
+                > per_index_capacity_in_bytes
+            {
+                let split_id_to_evict = self
+                    .eviction_queue
+                    .values()
+                    .find(|split_id| self.footers[*split_id].index_key == index_key)
+                    .cloned()
+                    .expect("The index should have at least one cached footer.");
+                self.evict(&split_id_to_evict);
+            }
+        }
+        while self.num_bytes + num_bytes > self.capacity_in_bytes {
+            let split_id_to_evict = self
+                .eviction_queue
+                .values()
+                .next()
+                .cloned()
+                .expect("The cache should have at least one cached footer.");
+            self.evict(&split_id_to_evict);
+        }
+        let priority_key = self.next_priority_key(1, num_bytes);
+        self.eviction_queue.insert(priority_key, split_id.clone());
+        self.footers.insert(
+            split_id,
+            CachedFooter {
+                index_key: index_key.to_string(),
+                footer,
+                num_hits: 1,
+                priority_key,
+            },
+        );
+        *self
+            .index_num_bytes
+            .entry(index_key.to_string())
+            .or_default() += num_bytes;
+        self.num_bytes += num_bytes;
+        self.cache_metrics.in_cache_count.inc();
+        self.cache_metrics.in_cache_num_bytes.add(num_bytes as i64);
+    }
+
+    fn evict(&mut self, split_id: &str) {
+        if let Some(cached_footer) = self.remove(split_id) {
+            self.clock = self.clock.max(cached_footer.priority_key.priority());
+            self.cache_metrics.evicts_num_items.inc();
+            self.cache_metrics
+                .evicts_num_bytes
+                .inc_by(cached_footer.footer.len() as u64);
+        }
+    }
+
+    fn remove(&mut self, split_id: &str) -> Option<CachedFooter> {
+        let cached_footer = self.footers.remove(split_id)?;
+        let num_bytes = cached_footer.footer.len();
+        self.eviction_queue.remove(&cached_footer.priority_key);
+
+        if let Some(index_num_bytes) = self.index_num_bytes.get_mut(&cached_footer.index_key) {
+            *index_num_bytes -= num_bytes;
+
+            if *index_num_bytes == 0 {
+                self.index_num_bytes.remove(&cached_footer.index_key);
+            }
+        }
+        self.num_bytes -= num_bytes;
+        self.cache_metrics.in_cache_count.dec();
+        self.cache_metrics.in_cache_num_bytes.sub(num_bytes as i64);
+        Some(cached_footer)
+    }
+}
+
+/// A memory cache for split footers (hotcache and bundle metadata), keyed by split ID.
+///
+/// Eviction is weighted by size and access frequency: footers that are large and rarely accessed
+/// are evicted first. The footers of a single index can optionally be capped so that one index
+/// cannot take over the whole cache.
+pub struct SplitFooterCache {
+    inner: Mutex<NeedMutSplitFooterCache>,
+}
+
+impl SplitFooterCache {
+    /// Creates a split footer cache with the given capacity and optional per-index capacity.
+    pub fn new(
+        capacity_in_bytes: usize,
+        per_index_capacity_in_bytes: Option<usize>,
+        cache_metrics: &'static CacheMetrics,
+    ) -> Self {
+        SplitFooterCache {
+            inner: Mutex::new(NeedMutSplitFooterCache {
+                capacity_in_bytes,
+                per_index_capacity_in_bytes,
+                footers: HashMap::new(),
+                eviction_queue: BTreeMap::new(),
+                index_num_bytes: HashMap::new(),
+                num_bytes: 0,
+                clock: 0.0,
+                sequence: 0,
+                cache_metrics,
+            }),
+        }
+    }
+
+    /// Returns whether the footer of the split is cached, without counting it as an access.
+    pub fn contains(&self, split_id: &str) -> bool {
+        self.inner.lock().unwrap().contains(split_id)
+    }
+
+    /// Returns the IDs of the splits whose footer is cached.
+    pub fn split_ids(&self) -> Vec<String> {
+        self.inner.lock().unwrap().footers.keys().cloned().collect()
+    }
+
+    /// If available, returns the cached footer of the split.
+    pub fn get(&self, split_id: &str) -> Option<OwnedBytes> {
+        self.inner.lock().unwrap().get(split_id)
+    }
+
+    /// Attempts to put the footer of a split belonging to the index identified by `index_key` in
+    /// the cache. This may fail silently if the footer is larger than the cache capacity.
+    pub fn put(&self, index_key: &str, split_id: String, footer: OwnedBytes) {
+        self.inner.lock().unwrap().put(index_key, split_id, footer);
+    }
+}
+
+#[async_trait]
+impl SplitCache for SplitFooterCache {
+    async fn get(&self, split_id: &str) -> Option<OwnedBytes> {
+        SplitFooterCache::get(self, split_id)
+    }
+
+    async fn put(&self, index_key: &str, split_id: String, footer: OwnedBytes) {
+        SplitFooterCache::put(self, index_key, split_id, footer)
+    }
+
+    fn contains(&self, split_id: &str) -> bool {
+        SplitFooterCache::contains(self, split_id)
+    }
+
+    fn split_ids(&self) -> Vec<String> {
+        SplitFooterCache::split_ids(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::CACHE_METRICS_FOR_TESTS;
+
+    fn footer(num_bytes: usize) -> OwnedBytes {
+        OwnedBytes::new(vec![0u8; num_bytes])
+    }
+
+    #[test]
+    fn test_split_footer_cache_evicts_large_footers_first() {
+        let cache = SplitFooterCache::new(10, None, &CACHE_METRICS_FOR_TESTS);
+        cache.put("index", "small".to_string(), footer(3));
+        cache.put("index", "large".to_string(), footer(6));
+        cache.put("index", "medium".to_string(), footer(4));
+
+        assert!(cache.contains("small"));
+        assert!(!cache.contains("large"));
+        assert!(cache.contains("medium"));
+    }
+
+    #[test]
+    fn test_split_footer_cache_keeps_frequently_accessed_footers() {
+        let cache = SplitFooterCache::new(10, None, &CACHE_METRICS_FOR_TESTS);
+        cache.put("index", "small".to_string(), footer(3));
+        cache.put("index", "large".to_string(), footer(6));
+
+        for _ in 0..2 {
+            assert_eq!(cache.get("large").unwrap().len(), 6);
+        }
+        cache.put("index", "medium".to_string(), footer(4));
+
+        assert!(!cache.contains("small"));
+        assert!(cache.contains("large"));
+        assert!(cache.contains("medium"));
+        assert!(cache.get("small").is_none());
+    }
+
+    #[test]
+    fn test_split_footer_cache_per_index_capacity() {
+        let cache = SplitFooterCache::new(100, Some(5), &CACHE_METRICS_FOR_TESTS);
+        cache.put("index-1", "split-1".to_string(), footer(3));
+        cache.put("index-2", "split-2".to_string(), footer(3));
+        cache.put("index-1", "split-3".to_string(), footer(3));
+
+        assert!(!cache.contains("split-1"));
+        assert!(cache.contains("split-2"));
+        assert!(cache.contains("split-3"));
+
+        // The footer exceeds the per-index capacity.
+        cache.put("index-2", "split-4".to_string(), footer(6));
+        assert!(!cache.contains("split-4"));
+        assert!(cache.contains("split-2"));
+    }
+
+    #[test]
+    fn test_split_footer_cache_put_replaces_footer() {
+        let cache = SplitFooterCache::new(10, None, &CACHE_METRICS_FOR_TESTS);
+        cache.put("index", "split".to_string(), footer(3));
+        cache.put("index", "split".to_string(), footer(8));
+        assert_eq!(cache.get("split").unwrap().len(), 8);
+
+        cache.put("index", "other-split".to_string(), footer(2));
+        assert!(cache.contains("split"));
+        assert!(cache.contains("other-split"));
+
+        let mut split_ids = cache.split_ids();
+        split_ids.sort();
+        assert_eq!(split_ids, ["other-split", "split"]);
+    }
+}
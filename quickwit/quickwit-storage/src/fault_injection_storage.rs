@@ -0,0 +1,235 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use quickwit_common::fault_injection::{truncated_len, Fault, FaultInjectionConfig, FaultInjector};
+use quickwit_common::uri::Uri;
+
+use crate::storage::{BulkDeleteError, SendableAsync};
+use crate::{OwnedBytes, PutPayload, Storage, StorageErrorKind, StorageResult};
+
+/// This storage acts as a proxy to another storage that injects faults in the API calls:
+/// latency, errors, and truncated reads, at the rates defined by a [`FaultInjectionConfig`].
+///
+/// It is meant to test the resilience of the code paths relying on storage.
+pub struct FaultInjectionStorage {
+    underlying: Arc<dyn Storage>,
+    fault_injector: FaultInjector,
+}
+
+impl FaultInjectionStorage {
+    /// Creates a [`FaultInjectionStorage`] wrapping `underlying`.
+    pub fn new(underlying: Arc<dyn Storage>, config: FaultInjectionConfig) -> Self {
+        FaultInjectionStorage {
+            underlying,
+            fault_injector: FaultInjector::new(config),
+        }
+    }
+
+    /// Injects the latency and error of the fault drawn for the next call on `path`.
+    async fn inject_fault(&self, path: &Path) -> StorageResult<Fault> {
+        let fault = self.fault_injector.next_fault();
+        fault.inject_latency().await;
+
+        if fault.is_error {
+            return Err(StorageErrorKind::Io
+                .with_error(anyhow::anyhow!("Injected fault on `{}`.", path.display())));
+        }
+        Ok(fault)
+    }
+}
+
+fn truncate_bytes(bytes: OwnedBytes, fault: Fault) -> OwnedBytes {
+    if fault.is_truncation {
+        bytes.slice(0..truncated_len(bytes.len()))
+    } else {
+        bytes
+    }
+}
+
+impl fmt::Debug for FaultInjectionStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FaultInjectionStorage")
+            .field("underlying", &self.underlying)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for FaultInjectionStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.underlying.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.inject_fault(path).await?;
+        self.underlying.put(path, payload).await
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        self.inject_fault(path).await?;
+        self.underlying.copy_to(path, output).await
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        let fault = self.inject_fault(path).await?;
+        let bytes = self.underlying.get_slice(path, range).await?;
+        Ok(truncate_bytes(bytes, fault))
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        let fault = self.inject_fault(path).await?;
+        let bytes = self.underlying.get_all(path).await?;
+        Ok(truncate_bytes(bytes, fault))
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.inject_fault(path).await?;
+        self.underlying.delete(path).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        let fault = self.fault_injector.next_fault();
+        fault.inject_latency().await;
+
+        if fault.is_error {
+            return Err(BulkDeleteError {
+                error: Some(StorageErrorKind::Io.with_error(anyhow::anyhow!("Injected fault."))),
+                unattempted: paths.iter().map(|path| path.to_path_buf()).collect(),
+                ..Default::default()
+            });
+        }
+        self.underlying.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.inject_fault(path).await?;
+        self.underlying.exists(path).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.underlying.uri()
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.inject_fault(path).await?;
+        self.underlying.file_num_bytes(path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::RamStorage;
+
+    fn ram_storage_with_file(path: &str, content: &[u8]) -> Arc<dyn Storage> {
+        Arc::new(RamStorage::builder().put(path, content).build())
+    }
+
+    #[tokio::test]
+    async fn test_fault_injection_storage_errors() {
+        let path = Path::new("file");
+        let underlying = ram_storage_with_file("file", b"abcd");
+        let storage = FaultInjectionStorage::new(
+            underlying,
+            FaultInjectionConfig {
+                error_rate: 1.0,
+                ..Default::default()
+            },
+        );
+        let error = storage.get_all(path).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Io);
+
+        let error = storage.get_slice(path, 0..2).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Io);
+
+        let error = storage.delete(path).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Io);
+
+        let bulk_delete_error = storage.bulk_delete(&[path]).await.unwrap_err();
+        assert_eq!(bulk_delete_error.unattempted, vec![path.to_path_buf()]);
+    }
+
+    #[tokio::test]
+    async fn test_fault_injection_storage_truncation() {
+        let path = Path::new("file");
+        let underlying = ram_storage_with_file("file", b"abcd");
+        let storage = FaultInjectionStorage::new(
+            underlying,
+            FaultInjectionConfig {
+                truncation_rate: 1.0,
+                ..Default::default()
+            },
+        );
+        assert_eq!(storage.get_all(path).await.unwrap().as_slice(), b"ab");
+        assert_eq!(
+            storage.get_slice(path, 1..4).await.unwrap().as_slice(),
+            b"b"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fault_injection_storage_latency() {
+        let path = Path::new("file");
+        let underlying = ram_storage_with_file("file", b"abcd");
+        let storage = FaultInjectionStorage::new(
+            underlying,
+            FaultInjectionConfig {
+                latency: Duration::from_millis(50),
+                latency_rate: 1.0,
+                ..Default::default()
+            },
+        );
+        let start = std::time::Instant::now();
+        assert_eq!(storage.get_all(path).await.unwrap().as_slice(), b"abcd");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_fault_injection_storage_is_deterministic() {
+        let path = Path::new("file");
+        let config = FaultInjectionConfig {
+            error_rate: 0.5,
+            seed: 7,
+            ..Default::default()
+        };
+        let mut outcomes = Vec::new();
+
+        for _ in 0..2 {
+            let underlying = ram_storage_with_file("file", b"abcd");
+            let storage = FaultInjectionStorage::new(underlying, config.clone());
+            let mut storage_outcomes = Vec::new();
+
+            for _ in 0..20 {
+                storage_outcomes.push(storage.get_all(path).await.is_ok());
+            }
+            outcomes.push(storage_outcomes);
+        }
+        assert_eq!(outcomes[0], outcomes[1]);
+        assert!(outcomes[0].contains(&true));
+        assert!(outcomes[0].contains(&false));
+    }
+}
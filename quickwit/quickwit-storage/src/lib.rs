@@ -43,6 +43,8 @@ pub use self::storage::Storage;
 
 mod bundle_storage;
 mod error;
+#[cfg(any(test, feature = "testsuite"))]
+mod fault_injection_storage;
 mod local_file_storage;
 mod object_storage;
 mod payload;
@@ -59,9 +61,12 @@ pub use versioned_component::VersionedComponent;
 
 pub use self::bundle_storage::{BundleStorage, BundleStorageFileOffsets};
 #[cfg(any(test, feature = "testsuite"))]
+pub use self::fault_injection_storage::FaultInjectionStorage;
+#[cfg(any(test, feature = "testsuite"))]
 pub use self::cache::MockCache;
 pub use self::cache::{
-    wrap_storage_with_long_term_cache, ByteRangeCache, Cache, MemorySizedCache, QuickwitCache,
+    wrap_storage_with_long_term_cache, ByteRangeCache, Cache, DiskSplitCache, MemorySizedCache,
+    QuickwitCache, RemoteSplitCache, SplitCache, SplitFooterCache,
 };
 pub use self::local_file_storage::{LocalFileStorage, LocalFileStorageFactory};
 #[cfg(feature = "azure")]
@@ -21,7 +21,8 @@ use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 use std::{fmt, io};
 
 use async_trait::async_trait;
@@ -40,12 +41,13 @@ use quickwit_aws::retry::{retry, RetryParams, Retryable};
 use quickwit_common::uri::Uri;
 use quickwit_common::{chunk_range, ignore_error_kind, into_u64_range};
 use quickwit_config::{AzureStorageConfig, StorageBackend, StorageConfig};
+use quickwit_secrets::{is_secret_ref, SecretResolver};
 use regex::Regex;
 use tantivy::directory::OwnedBytes;
 use thiserror::Error;
 use tokio::io::{AsyncWriteExt, BufReader};
 use tokio_util::compat::FuturesAsyncReadCompatExt;
-use tracing::{instrument, warn};
+use tracing::{info, instrument, warn};
 
 use crate::debouncer::DebouncedStorage;
 use crate::storage::{BulkDeleteError, DeleteFailure, SendableAsync};
@@ -76,14 +78,15 @@ impl StorageFactory for AzureBlobStorageFactory {
             );
             StorageResolverError::InvalidConfig(message)
         })?;
-        let storage = AzureBlobStorage::from_uri(azure_storage_config, uri)?;
+        let storage = AzureBlobStorage::from_uri(azure_storage_config, uri).await?;
         Ok(Arc::new(DebouncedStorage::new(storage)))
     }
 }
 
 /// Azure object storage implementation
 pub struct AzureBlobStorage {
-    container_client: ContainerClient,
+    container_client: RwLock<ContainerClient>,
+    access_key_refresher_opt: Option<AccessKeyRefresher>,
     uri: Uri,
     prefix: PathBuf,
     multipart_policy: MultiPartPolicy,
@@ -102,11 +105,10 @@ impl fmt::Debug for AzureBlobStorage {
 impl AzureBlobStorage {
     /// Creates a new [`AzureBlobStorage`] instance.
     pub fn new(account: String, access_key: String, uri: Uri, container_name: String) -> Self {
-        let storage_credentials = StorageCredentials::access_key(account.clone(), access_key);
-        let container_client =
-            BlobServiceClient::new(account, storage_credentials).container_client(container_name);
+        let container_client = build_container_client(account, access_key, container_name);
         Self {
-            container_client,
+            container_client: RwLock::new(container_client),
+            access_key_refresher_opt: None,
             uri,
             prefix: PathBuf::new(),
             multipart_policy: MultiPartPolicy::default(),
@@ -123,6 +125,7 @@ impl AzureBlobStorage {
     pub fn with_prefix(self, prefix: PathBuf) -> Self {
         Self {
             container_client: self.container_client,
+            access_key_refresher_opt: self.access_key_refresher_opt,
             uri: self.uri,
             prefix,
             multipart_policy: self.multipart_policy,
@@ -135,7 +138,8 @@ impl AzureBlobStorage {
     pub fn new_emulated(container: &str) -> Self {
         let container_client = ClientBuilder::emulator().container_client(container);
         Self {
-            container_client,
+            container_client: RwLock::new(container_client),
+            access_key_refresher_opt: None,
             uri: Uri::from_well_formed(format!("azure://tester/{container}")),
             prefix: PathBuf::new(),
             multipart_policy: MultiPartPolicy::default(),
@@ -154,7 +158,10 @@ impl AzureBlobStorage {
     }
 
     /// Builds instance from URI.
-    pub fn from_uri(
+    ///
+    /// If the access key references a secret, it is resolved and the container client is rebuilt
+    /// whenever the secret is rotated.
+    pub async fn from_uri(
         azure_storage_config: &AzureStorageConfig,
         uri: &Uri,
     ) -> Result<AzureBlobStorage, StorageResolverError> {
@@ -176,11 +183,41 @@ impl AzureBlobStorage {
             let message = format!("Failed to extract container name from Azure URI: {uri}");
             StorageResolverError::InvalidUri(message)
         })?;
-        let azure_blob_storage =
-            AzureBlobStorage::new(account_name, access_key, uri.clone(), container_name);
+        if !is_secret_ref(&access_key) {
+            let azure_blob_storage =
+                AzureBlobStorage::new(account_name, access_key, uri.clone(), container_name);
+            return Ok(azure_blob_storage.with_prefix(prefix));
+        }
+        let access_key_ref = access_key;
+        let access_key = SecretResolver::global()
+            .resolve(&access_key_ref)
+            .await
+            .map_err(|error| StorageResolverError::InvalidConfig(format!("{error:#}")))?;
+        let mut azure_blob_storage = AzureBlobStorage::new(
+            account_name.clone(),
+            access_key.clone(),
+            uri.clone(),
+            container_name.clone(),
+        );
+        azure_blob_storage.access_key_refresher_opt = Some(AccessKeyRefresher {
+            account_name,
+            container_name,
+            access_key_ref,
+            access_key: Mutex::new((access_key, Instant::now())),
+        });
         Ok(azure_blob_storage.with_prefix(prefix))
     }
 
+    /// Returns the container client, rebuilt beforehand if the access key was rotated.
+    async fn container_client(&self) -> ContainerClient {
+        if let Some(access_key_refresher) = &self.access_key_refresher_opt {
+            if let Some(container_client) = access_key_refresher.refresh().await {
+                *self.container_client.write().unwrap() = container_client;
+            }
+        }
+        self.container_client.read().unwrap().clone()
+    }
+
     /// Returns the blob name (a.k.a blob key).
     fn blob_name(&self, relative_path: &Path) -> String {
         let key_path = self.prefix.join(relative_path);
@@ -198,13 +235,18 @@ impl AzureBlobStorage {
 
         retry(&self.retry_params, || async {
             let mut response_stream = if let Some(range) = range_opt.as_ref() {
-                self.container_client
+                self.container_client()
+                    .await
                     .blob_client(&name)
                     .get()
                     .range(range.clone())
                     .into_stream()
             } else {
-                self.container_client.blob_client(&name).get().into_stream()
+                self.container_client()
+                    .await
+                    .blob_client(&name)
+                    .get()
+                    .into_stream()
             };
 
             let mut buf: Vec<u8> = Vec::with_capacity(capacity);
@@ -229,7 +271,8 @@ impl AzureBlobStorage {
         retry(&self.retry_params, || async {
             let data = Bytes::from(payload.read_all().await?.to_vec());
             let hash = md5::compute(&data[..]);
-            self.container_client
+            self.container_client()
+                .await
                 .blob_client(name)
                 .put_block_blob(data)
                 .hash(hash)
@@ -253,7 +296,7 @@ impl AzureBlobStorage {
         let multipart_ranges =
             chunk_range(0..total_len as usize, part_len as usize).map(into_u64_range);
 
-        let blob_client = self.container_client.blob_client(name);
+        let blob_client = self.container_client().await.blob_client(name);
         let mut upload_blocks_stream_result = tokio_stream::iter(multipart_ranges.enumerate())
             .map(|(num, range)| {
                 let moved_blob_client = blob_client.clone();
@@ -306,7 +349,8 @@ impl AzureBlobStorage {
 impl Storage for AzureBlobStorage {
     async fn check_connectivity(&self) -> anyhow::Result<()> {
         if let Some(first_blob_result) = self
-            .container_client
+            .container_client()
+            .await
             .list_blobs()
             .max_results(NonZeroU32::new(1u32).expect("1 is always non-zero."))
             .into_stream()
@@ -339,7 +383,12 @@ impl Storage for AzureBlobStorage {
 
     async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
         let name = self.blob_name(path);
-        let mut output_stream = self.container_client.blob_client(name).get().into_stream();
+        let mut output_stream = self
+            .container_client()
+            .await
+            .blob_client(name)
+            .get()
+            .into_stream();
 
         while let Some(chunk_result) = output_stream.next().await {
             let chunk_response = chunk_result.map_err(AzureErrorWrapper::from)?;
@@ -361,7 +410,8 @@ impl Storage for AzureBlobStorage {
     async fn delete(&self, path: &Path) -> StorageResult<()> {
         let blob_name = self.blob_name(path);
         let delete_res: Result<_, StorageError> = self
-            .container_client
+            .container_client()
+            .await
             .blob_client(blob_name)
             .delete()
             .into_future()
@@ -448,7 +498,8 @@ impl Storage for AzureBlobStorage {
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
         let name = self.blob_name(path);
         let properties_result = self
-            .container_client
+            .container_client()
+            .await
             .blob_client(name)
             .get_properties()
             .into_future()
@@ -464,6 +515,60 @@ impl Storage for AzureBlobStorage {
     }
 }
 
+fn build_container_client(
+    account: String,
+    access_key: String,
+    container_name: String,
+) -> ContainerClient {
+    let storage_credentials = StorageCredentials::access_key(account.clone(), access_key);
+    BlobServiceClient::new(account, storage_credentials).container_client(container_name)
+}
+
+/// Resolves the access key of a storage again once the secrets cache TTL has elapsed.
+struct AccessKeyRefresher {
+    account_name: String,
+    container_name: String,
+    access_key_ref: String,
+    /// Access key of the current container client and the time it was resolved at.
+    access_key: Mutex<(String, Instant)>,
+}
+
+impl AccessKeyRefresher {
+    /// Returns a new container client if the access key was rotated.
+    async fn refresh(&self) -> Option<ContainerClient> {
+        let secret_resolver = SecretResolver::global();
+        let resolved_at = self.access_key.lock().unwrap().1;
+
+        if resolved_at.elapsed() < secret_resolver.cache_ttl() {
+            return None;
+        }
+        let access_key = match secret_resolver.resolve(&self.access_key_ref).await {
+            Ok(access_key) => access_key,
+            Err(error) => {
+                warn!(error=?error, "Failed to refresh Azure storage access key.");
+                return None;
+            }
+        };
+        let mut access_key_guard = self.access_key.lock().unwrap();
+        access_key_guard.1 = Instant::now();
+
+        if access_key_guard.0 == access_key {
+            return None;
+        }
+        info!(
+            account_name=%self.account_name,
+            container_name=%self.container_name,
+            "Azure storage access key was rotated. Rebuilding the container client."
+        );
+        access_key_guard.0 = access_key.clone();
+        Some(build_container_client(
+            self.account_name.clone(),
+            access_key,
+            self.container_name.clone(),
+        ))
+    }
+}
+
 /// Copy range of payload into `Bytes` and return the computed md5.
 async fn extract_range_data_and_hash(
     payload: Box<dyn PutPayload>,
@@ -21,10 +21,14 @@ use std::collections::HashMap;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 use std::{env, fmt, io};
 
 use anyhow::anyhow;
 use async_trait::async_trait;
+use aws_credential_types::provider::error::CredentialsError;
+use aws_credential_types::provider::{future, ProvideCredentials};
+use aws_sdk_s3::config::Credentials;
 use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_s3::operation::get_object::{GetObjectError, GetObjectOutput};
 use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier};
@@ -38,6 +42,7 @@ use quickwit_aws::retry::{retry, Retry, RetryParams, Retryable};
 use quickwit_common::uri::Uri;
 use quickwit_common::{chunk_range, into_u64_range};
 use quickwit_config::S3StorageConfig;
+use quickwit_secrets::{is_secret_ref, SecretResolver};
 use regex::Regex;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::sync::Semaphore;
@@ -82,7 +87,48 @@ impl fmt::Debug for S3CompatibleObjectStorage {
     }
 }
 
-async fn create_s3_client(s3_storage_config: &S3StorageConfig) -> S3Client {
+const STORAGE_CONFIG_CREDENTIALS_PROVIDER_NAME: &str = "quickwit-storage-config";
+
+/// Provides the credentials of the storage config when they reference secrets. The credentials
+/// expire along with the cached secrets, so the SDK loads them again and picks up rotated
+/// secrets without the client being recreated.
+#[derive(Debug)]
+struct StorageConfigCredentialsProvider {
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl StorageConfigCredentialsProvider {
+    async fn load_credentials(&self) -> anyhow::Result<Credentials> {
+        let secret_resolver = SecretResolver::global();
+        let access_key_id = secret_resolver.resolve(&self.access_key_id).await?;
+        let secret_access_key = secret_resolver.resolve(&self.secret_access_key).await?;
+        let expiry = SystemTime::now() + secret_resolver.cache_ttl();
+        let credentials = Credentials::new(
+            access_key_id,
+            secret_access_key,
+            None,
+            Some(expiry),
+            STORAGE_CONFIG_CREDENTIALS_PROVIDER_NAME,
+        );
+        Ok(credentials)
+    }
+}
+
+impl ProvideCredentials for StorageConfigCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where Self: 'a {
+        future::ProvideCredentials::new(async move {
+            self.load_credentials()
+                .await
+                .map_err(|error| CredentialsError::provider_error(format!("{error:#}")))
+        })
+    }
+}
+
+async fn create_s3_client(
+    s3_storage_config: &S3StorageConfig,
+) -> Result<S3Client, StorageResolverError> {
     let aws_config = get_aws_config().await;
     let mut s3_config = aws_sdk_s3::Config::builder().region(aws_config.region().cloned());
 
@@ -94,11 +140,38 @@ async fn create_s3_client(s3_storage_config: &S3StorageConfig) -> S3Client {
     s3_config.set_sleep_impl(Some(Arc::new(quickwit_aws::TokioSleep::default())));
     s3_config.set_force_path_style(s3_storage_config.force_path_style_access());
 
+    if let (Some(access_key_id), Some(secret_access_key)) = (
+        &s3_storage_config.access_key_id,
+        &s3_storage_config.secret_access_key,
+    ) {
+        info!("Using the S3 credentials of the storage config.");
+        let credentials_provider = StorageConfigCredentialsProvider {
+            access_key_id: access_key_id.clone(),
+            secret_access_key: secret_access_key.clone(),
+        };
+        if is_secret_ref(access_key_id) || is_secret_ref(secret_access_key) {
+            // Fails early if the secrets cannot be resolved.
+            credentials_provider
+                .load_credentials()
+                .await
+                .map_err(|error| StorageResolverError::InvalidConfig(format!("{error:#}")))?;
+            s3_config = s3_config.credentials_provider(credentials_provider);
+        } else {
+            let credentials = Credentials::new(
+                access_key_id,
+                secret_access_key,
+                None,
+                None,
+                STORAGE_CONFIG_CREDENTIALS_PROVIDER_NAME,
+            );
+            s3_config = s3_config.credentials_provider(credentials);
+        }
+    }
     if let Some(endpoint) = s3_storage_config.endpoint() {
         info!(endpoint=%endpoint, "Using custom S3 endpoint.");
         s3_config.set_endpoint_url(Some(endpoint));
     }
-    S3Client::from_conf(s3_config.build())
+    Ok(S3Client::from_conf(s3_config.build()))
 }
 
 impl S3CompatibleObjectStorage {
@@ -108,7 +181,7 @@ impl S3CompatibleObjectStorage {
         uri: Uri,
         bucket: String,
     ) -> Result<Self, StorageResolverError> {
-        let s3_client = create_s3_client(s3_storage_config).await;
+        let s3_client = create_s3_client(s3_storage_config).await?;
         let retry_params = RetryParams {
             max_attempts: 3,
             ..Default::default()